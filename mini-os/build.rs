@@ -10,8 +10,8 @@ fn main() {
     // Configuration pour le support du noyau
     println!("cargo:rustc-cfg=kernel");
     
-    // Configuration du linker
-    println!("cargo:rustc-link-arg=-Tlinker.ld");
+    // Le script d'édition de liens vient de la cible (pre-link-args) : le
+    // passer une seconde fois duplique ses sections
     println!("cargo:rerun-if-changed=linker.ld");
     
     // Active les optimisations pour le code du noyau
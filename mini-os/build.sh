@@ -13,6 +13,11 @@ mkdir -p isodir/boot/grub
 cp target/x86_64-unknown-none/release/mini-os isodir/boot/kernel.elf
 cp grub.cfg isodir/boot/grub/
 
+# Initramfs (cpio newc) : contenu du répertoire initramfs/ s'il existe
+if [ -d initramfs ]; then
+    (cd initramfs && find . | cpio -o -H newc --quiet) > isodir/boot/initramfs.cpio
+fi
+
 # Créer l'ISO bootable
 grub-mkrescue -o mini-os.iso isodir
 
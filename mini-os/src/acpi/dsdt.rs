@@ -0,0 +1,99 @@
+/// Lecture minimale de la DSDT
+///
+/// Pas d'interpréteur AML : on cherche l'objet `\_S5` (paquet
+/// `Name(_S5, Package() { SLP_TYPa, SLP_TYPb, ... })`) dans le bytecode
+/// brut pour obtenir les valeurs SLP_TYP de l'arrêt logiciel (S5).
+
+use core::ptr::read_volatile;
+use super::fadt::Fadt;
+use super::tables::SdtHeader;
+
+const NAME_OP: u8 = 0x08;
+const PACKAGE_OP: u8 = 0x12;
+const BYTE_PREFIX: u8 = 0x0A;
+const ZERO_OP: u8 = 0x00;
+const ONE_OP: u8 = 0x01;
+
+/// Bytecode AML de la DSDT (table sans son en-tête)
+pub fn aml(fadt: &Fadt) -> Option<&'static [u8]> {
+    let address = fadt.dsdt_address();
+    if address == 0 {
+        return None;
+    }
+    let header = unsafe { read_volatile(address as *const SdtHeader) };
+    let length = header.length as usize;
+    let header_size = core::mem::size_of::<SdtHeader>();
+    if &header.signature != b"DSDT" || length <= header_size {
+        return None;
+    }
+    Some(unsafe { core::slice::from_raw_parts((address as usize + header_size) as *const u8, length - header_size) })
+}
+
+/// Entier AML court (ZeroOp, OneOp ou BytePrefix)
+fn integer(aml: &[u8], pos: &mut usize) -> Option<u8> {
+    match *aml.get(*pos)? {
+        ZERO_OP => {
+            *pos += 1;
+            Some(0)
+        }
+        ONE_OP => {
+            *pos += 1;
+            Some(1)
+        }
+        BYTE_PREFIX => {
+            let value = *aml.get(*pos + 1)?;
+            *pos += 2;
+            Some(value)
+        }
+        _ => None,
+    }
+}
+
+/// Valeurs (SLP_TYPa, SLP_TYPb) du paquet `\_S5`
+pub fn parse_s5(aml: &[u8]) -> Option<(u8, u8)> {
+    let mut start = 0;
+    while let Some(found) = aml[start..].windows(4).position(|w| w == b"_S5_") {
+        let name = start + found;
+        start = name + 1;
+        // NameOp devant le nom, éventuellement suivi de '\' (racine)
+        let declared = (name >= 1 && aml[name - 1] == NAME_OP)
+            || (name >= 2 && aml[name - 2] == NAME_OP && aml[name - 1] == b'\\');
+        if !declared || aml.get(name + 4) != Some(&PACKAGE_OP) {
+            continue;
+        }
+        // PkgLength : les bits 6-7 du premier octet comptent les octets suivants
+        let pkg_length = match aml.get(name + 5) {
+            Some(&byte) => 1 + (byte >> 6) as usize,
+            None => return None,
+        };
+        // Puis NumElements
+        let mut pos = name + 5 + pkg_length + 1;
+        let slp_typ_a = integer(aml, &mut pos)?;
+        let slp_typ_b = integer(aml, &mut pos)?;
+        return Some((slp_typ_a, slp_typ_b));
+    }
+    None
+}
+
+/// SLP_TYP de l'état S5 lus dans la DSDT
+pub fn find_s5(fadt: &Fadt) -> Option<(u8, u8)> {
+    aml(fadt).and_then(parse_s5)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_parse_s5_package() {
+        // Name(\_S5, Package(4) { 0x05, Zero, Zero, Zero }) précédé de bruit
+        let aml = [
+            0x10, b'_', b'S', b'5', b'_', 0x00,
+            NAME_OP, b'\\', b'_', b'S', b'5', b'_', PACKAGE_OP, 0x08, 0x04,
+            BYTE_PREFIX, 0x05, ZERO_OP, ZERO_OP, ZERO_OP,
+        ];
+        assert_eq!(parse_s5(&aml), Some((5, 0)));
+        // Sans paquet : ignoré
+        assert_eq!(parse_s5(&[NAME_OP, b'_', b'S', b'5', b'_', 0x0A, 0x05]), None);
+    }
+}
@@ -1,4 +1,4 @@
-use super::tables::SdtHeader;
+use super::tables::{GenericAddress, SdtHeader};
 
 #[repr(C, packed)]
 #[derive(Debug, Clone, Copy)]
@@ -42,11 +42,45 @@ pub struct Fadt {
     pub iapc_boot_arch: u16,
     pub reserved2: u8,
     pub flags: u32,
-    // Further fields omitted for now (ResetReg, etc.) as we target basic 32-bit PM1a control
+    // ACPI 2.0+ : présents seulement si `header.length` les couvre
+    pub reset_reg: GenericAddress,
+    pub reset_value: u8,
+    pub arm_boot_arch: u16,
+    pub fadt_minor_version: u8,
+    pub x_firmware_ctrl: u64,
+    pub x_dsdt: u64,
 }
 
+/// Drapeau RESET_REG_SUP : le registre de reset est utilisable
+const RESET_REG_SUP: u32 = 1 << 10;
+
 impl Fadt {
     pub fn validate(&self) -> bool {
         &self.header.signature == b"FACP"
     }
+
+    /// Le champ se terminant à `end` est-il couvert par la table ?
+    fn covers(&self, end: usize) -> bool {
+        self.header.length as usize >= end
+    }
+
+    /// Registre et valeur de reset, si la FADT en déclare un
+    pub fn reset_register(&self) -> Option<(GenericAddress, u8)> {
+        let flags = self.flags;
+        if self.covers(129) && flags & RESET_REG_SUP != 0 {
+            Some((self.reset_reg, self.reset_value))
+        } else {
+            None
+        }
+    }
+
+    /// Adresse physique de la DSDT (X_DSDT prioritaire)
+    pub fn dsdt_address(&self) -> u64 {
+        let x_dsdt = self.x_dsdt;
+        if self.covers(148) && x_dsdt != 0 {
+            x_dsdt
+        } else {
+            self.dsdt as u64
+        }
+    }
 }
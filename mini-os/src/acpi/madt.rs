@@ -1,4 +1,3 @@
-
 use super::tables::SdtHeader;
 use alloc::vec::Vec;
 
@@ -10,6 +9,11 @@ pub struct Madt {
     pub flags: u32,
 }
 
+/// Types d'entrées de la MADT
+const ENTRY_LOCAL_APIC: u8 = 0;
+const ENTRY_IO_APIC: u8 = 1;
+const ENTRY_INTERRUPT_OVERRIDE: u8 = 2;
+
 #[derive(Debug)]
 pub struct ProcessorInfo {
     pub processor_id: u8,
@@ -17,41 +21,150 @@ pub struct ProcessorInfo {
     pub flags: u32,
 }
 
-pub fn parse_madt(madt_ptr: *const Madt) -> Vec<ProcessorInfo> {
-    let mut processors = Vec::new();
-    
-    let madt = unsafe { *madt_ptr };
+/// Contrôleur d'E/S (entrée de type 1)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IoApicInfo {
+    pub id: u8,
+    /// Adresse physique des registres IOREGSEL/IOWIN
+    pub address: u32,
+    /// Première GSI servie par ce contrôleur
+    pub gsi_base: u32,
+}
+
+/// Redirection d'une IRQ ISA vers une autre GSI (entrée de type 2)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InterruptOverride {
+    pub bus: u8,
+    /// IRQ ISA d'origine
+    pub source: u8,
+    pub gsi: u32,
+    /// Drapeaux MPS INTI : polarité (bits 0-1) et déclenchement (bits 2-3)
+    pub flags: u16,
+}
+
+impl InterruptOverride {
+    /// Polarité active à l'état bas (sinon celle du bus : haute pour l'ISA)
+    pub fn active_low(&self) -> bool {
+        self.flags & 0x3 == 0x3
+    }
+
+    /// Déclenchement sur niveau (sinon celui du bus : front pour l'ISA)
+    pub fn level_triggered(&self) -> bool {
+        (self.flags >> 2) & 0x3 == 0x3
+    }
+}
+
+/// Parcourt les entrées de la MADT : `f(type, octets de l'entrée)`
+fn for_each_entry(madt_ptr: *const Madt, mut f: impl FnMut(u8, &[u8])) {
+    let madt = unsafe { madt_ptr.read_unaligned() };
     let header_len = core::mem::size_of::<Madt>();
     let total_len = madt.header.length as usize;
-    
+
     let mut offset = header_len;
     let start_ptr = madt_ptr as *const u8;
-    
-    while offset < total_len {
+
+    while offset + 2 <= total_len {
         let entry_ptr = unsafe { start_ptr.add(offset) };
         let entry_type = unsafe { *entry_ptr };
-        let entry_len = unsafe { *entry_ptr.add(1) };
-        
-        if entry_type == 0 { // Processor Local APIC
-            let processor_id = unsafe { *entry_ptr.add(2) };
-            let apic_id = unsafe { *entry_ptr.add(3) };
-            let flags = unsafe { 
-                let ptr = entry_ptr.add(4) as *const u32;
-                ptr.read_unaligned()
-            };
-            
+        let entry_len = unsafe { *entry_ptr.add(1) } as usize;
+        // Entrée tronquée ou corrompue : on s'arrête
+        if entry_len < 2 || offset + entry_len > total_len {
+            break;
+        }
+
+        let entry = unsafe { core::slice::from_raw_parts(entry_ptr, entry_len) };
+        f(entry_type, entry);
+
+        offset += entry_len;
+    }
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]])
+}
+
+pub fn parse_madt(madt_ptr: *const Madt) -> Vec<ProcessorInfo> {
+    let mut processors = Vec::new();
+
+    for_each_entry(madt_ptr, |entry_type, entry| {
+        if entry_type == ENTRY_LOCAL_APIC && entry.len() >= 8 {
+            let flags = read_u32(entry, 4);
             // Check if processor is enabled (Bit 0 of flags)
             if flags & 1 == 1 {
                 processors.push(ProcessorInfo {
-                    processor_id,
-                    apic_id,
+                    processor_id: entry[2],
+                    apic_id: entry[3],
                     flags,
                 });
             }
         }
-        
-        offset += entry_len as usize;
-    }
-    
+    });
+
     processors
 }
+
+/// IOAPIC déclarés par la MADT
+pub fn parse_io_apics(madt_ptr: *const Madt) -> Vec<IoApicInfo> {
+    let mut io_apics = Vec::new();
+
+    for_each_entry(madt_ptr, |entry_type, entry| {
+        if entry_type == ENTRY_IO_APIC && entry.len() >= 12 {
+            io_apics.push(IoApicInfo {
+                id: entry[2],
+                address: read_u32(entry, 4),
+                gsi_base: read_u32(entry, 8),
+            });
+        }
+    });
+
+    io_apics
+}
+
+/// Redirections des IRQ ISA (IRQ 0 vers la GSI 2 sous QEMU, par exemple)
+pub fn parse_overrides(madt_ptr: *const Madt) -> Vec<InterruptOverride> {
+    let mut overrides = Vec::new();
+
+    for_each_entry(madt_ptr, |entry_type, entry| {
+        if entry_type == ENTRY_INTERRUPT_OVERRIDE && entry.len() >= 10 {
+            overrides.push(InterruptOverride {
+                bus: entry[2],
+                source: entry[3],
+                gsi: read_u32(entry, 4),
+                flags: read_u16(entry, 8),
+            });
+        }
+    });
+
+    overrides
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_parse_madt_entries() {
+        let mut table = alloc::vec![0u8; core::mem::size_of::<Madt>()];
+        table[..4].copy_from_slice(b"APIC");
+        // LAPIC 0 actif, IOAPIC 0 à 0xFEC00000, IRQ 0 -> GSI 2, IRQ 9 -> GSI 9 niveau/bas
+        table.extend_from_slice(&[ENTRY_LOCAL_APIC, 8, 0, 0, 1, 0, 0, 0]);
+        table.extend_from_slice(&[ENTRY_IO_APIC, 12, 0, 0, 0x00, 0x00, 0xC0, 0xFE, 0, 0, 0, 0]);
+        table.extend_from_slice(&[ENTRY_INTERRUPT_OVERRIDE, 10, 0, 0, 2, 0, 0, 0, 0, 0]);
+        table.extend_from_slice(&[ENTRY_INTERRUPT_OVERRIDE, 10, 0, 9, 9, 0, 0, 0, 0x0F, 0]);
+        let length = table.len() as u32;
+        table[4..8].copy_from_slice(&length.to_le_bytes());
+        let madt = table.as_ptr() as *const Madt;
+
+        assert_eq!(parse_madt(madt).len(), 1);
+        assert_eq!(parse_io_apics(madt), alloc::vec![IoApicInfo { id: 0, address: 0xFEC0_0000, gsi_base: 0 }]);
+        let overrides = parse_overrides(madt);
+        assert_eq!(overrides.len(), 2);
+        assert_eq!((overrides[0].source, overrides[0].gsi), (0, 2));
+        assert!(!overrides[0].active_low() && !overrides[0].level_triggered());
+        assert!(overrides[1].active_low() && overrides[1].level_triggered());
+    }
+}
@@ -2,6 +2,7 @@
 pub mod tables;
 pub mod madt;
 pub mod fadt;
+pub mod dsdt;
 
 use core::ptr::read_volatile;
 use self::tables::{RsdpDescriptor, SdtHeader};
@@ -42,8 +43,8 @@ pub fn find_rsdp() -> Option<tables::RsdpDescriptor> {
     None
 }
 
-/// Trouve la table MADT via le RSDP
-pub fn find_madt(rsdp: &RsdpDescriptor) -> Option<Madt> {
+/// Adresse physique d'une table listée par la RSDT
+pub fn find_table(rsdp: &RsdpDescriptor, signature: &[u8; 4]) -> Option<u64> {
     let rsdt_addr = rsdp.rsdt_address as *const SdtHeader;
     let rsdt = unsafe { read_volatile(rsdt_addr) };
     
@@ -56,45 +57,29 @@ pub fn find_madt(rsdp: &RsdpDescriptor) -> Option<Madt> {
     let entries_ptr = unsafe { (rsdt_addr as *const u8).add(core::mem::size_of::<SdtHeader>()) as *const u32 };
     
     for i in 0..entry_count {
-        let entry_addr = unsafe { *entries_ptr.add(i) };
-        let header_ptr = entry_addr as *const SdtHeader;
-        let header = unsafe { read_volatile(header_ptr) };
+        let entry_addr = unsafe { entries_ptr.add(i).read_unaligned() };
+        let header = unsafe { read_volatile(entry_addr as *const SdtHeader) };
         
-        if &header.signature == b"APIC" {
-            // Found MADT
-            let madt_ptr = entry_addr as *const Madt;
-            return Some(unsafe { read_volatile(madt_ptr) });
+        if &header.signature == signature {
+            return Some(entry_addr as u64);
         }
     }
     
     None
 }
 
+/// Trouve la table MADT via le RSDP
+pub fn find_madt(rsdp: &RsdpDescriptor) -> Option<Madt> {
+    // Signature "APIC"
+    let address = find_table(rsdp, b"APIC")?;
+    Some(unsafe { read_volatile(address as *const Madt) })
+}
+
 /// Trouve la table FADT via le RSDP
 pub fn find_fadt(rsdp: &RsdpDescriptor) -> Option<Fadt> {
-    let rsdt_addr = rsdp.rsdt_address as *const SdtHeader;
-    let rsdt = unsafe { read_volatile(rsdt_addr) };
-    
-    if &rsdt.signature != b"RSDT" {
-        return None;
-    }
-    
-    let entry_count = (rsdt.length as usize - core::mem::size_of::<SdtHeader>()) / 4;
-    let entries_ptr = unsafe { (rsdt_addr as *const u8).add(core::mem::size_of::<SdtHeader>()) as *const u32 };
-    
-    for i in 0..entry_count {
-        let entry_addr = unsafe { *entries_ptr.add(i) };
-        let header_ptr = entry_addr as *const SdtHeader;
-        let header = unsafe { read_volatile(header_ptr) };
-        
-        if &header.signature == b"FACP" {
-            // Found FADT (Signature is "FACP")
-            let fadt_ptr = entry_addr as *const Fadt;
-            return Some(unsafe { read_volatile(fadt_ptr) });
-        }
-    }
-    
-    None
+    // Signature "FACP"
+    let address = find_table(rsdp, b"FACP")?;
+    Some(unsafe { read_volatile(address as *const Fadt) })
 }
 
 unsafe fn check_signature(ptr: *const u8) -> bool {
@@ -40,3 +40,23 @@ pub struct SdtHeader {
     pub creator_id: u32,
     pub creator_revision: u32,
 }
+
+/// Generic Address Structure (registres décrits par la FADT)
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy)]
+pub struct GenericAddress {
+    pub address_space: u8,
+    pub bit_width: u8,
+    pub bit_offset: u8,
+    pub access_size: u8,
+    pub address: u64,
+}
+
+impl GenericAddress {
+    /// Espace mémoire
+    pub const SYSTEM_MEMORY: u8 = 0;
+    /// Espace des ports d'E/S
+    pub const SYSTEM_IO: u8 = 1;
+    /// Espace de configuration PCI
+    pub const PCI_CONFIG: u8 = 2;
+}
@@ -26,7 +26,7 @@ fn test_dns_compilation() {
     // Vérifier juste que les types et fonctions sont accessibles
     // On ne lance pas vraiment la résolution car pas de réseau
     let _packet = dns::DnsPacket::new("example.com");
-    // let _ = dns::resolve("example.com", Ipv4Address::new(8,8,8,8));
+    // let _ = dns::resolve("example.com");
 }
 
 fn test_dhcp_compilation() {
@@ -0,0 +1,309 @@
+/// Ligne de commande du noyau
+///
+/// La ligne transmise par le chargeur (tag Multiboot2 n° 1) est enregistrée
+/// par `init` dès l'entrée du noyau, avant le tas : elle reste dans la
+/// structure d'informations du chargeur, que l'allocateur de cadres
+/// épargne, et son analyse n'alloue rien. Les sous-systèmes interrogent
+/// `options()` pendant leur initialisation.
+///
+/// Syntaxe (celle de Linux) : paramètres séparés par des espaces, `clé`
+/// ou `clé=valeur`, valeurs entre guillemets pour les espaces ; ce qui
+/// suit `--` est transmis tel quel au programme init. Options reconnues :
+/// - `root=/dev/<bloc>` : périphérique de la racine (ext4) ;
+/// - `init=<chemin>` : premier programme utilisateur (/bin/init) ;
+/// - `loglevel=<0-7>`, `quiet`, `debug` : niveau du journal ;
+/// - `nosmp` : les AP ne sont pas démarrés ;
+/// - `heap=<taille>[K|M|G]` : taille du tas noyau ;
+/// - `sched_slice=<ticks>` : tranche de temps du planificateur ;
+/// - `netconsole=<cible>` : console réseau (cf. `net::netconsole`) ;
+/// - `fsck.mode=auto|force|skip` : vérification de la racine au montage
+///   (`auto` : quand `max_mnt_count` est atteint, cf. `fsck`) ;
+/// - `kdump[=<répertoire>]` : vidage sur panique, enregistré au démarrage
+///   suivant dans /var/crash par défaut (cf. `kdump`) ;
+/// - `watchdog_thresh=<s>`, `nowatchdog` : seuil du détecteur de blocages
+///   (0 ou `nowatchdog` : inactif) ;
+/// - `watchdog=auto|i6300esb|ib700` : chien de garde matériel (cf. `watchdog`) ;
+/// - `fault_inject=<point>:<probabilité>[:<intervalle>[:<fois>]],...` :
+///   injection de fautes (cf. `fault_inject`) ;
+/// - `trace_event=<événement>,...|none` : événements tracés dès le
+///   démarrage (cf. `trace`, tous par défaut) ;
+/// - `norandmaps` : pas d'ASLR, tas, pile et mmap à adresses fixes
+///   (cf. `process::aslr`) ;
+/// - `nosmep`, `nosmap` : SMEP / SMAP laissés éteints (cf.
+///   `memory::uaccess`).
+
+use alloc::vec::Vec;
+use log::LevelFilter;
+use spin::Mutex;
+use crate::drivers::watchdog::Kind as WatchdogKind;
+use crate::net::netconsole::NetconsoleTarget;
+
+/// Programme init par défaut
+pub const DEFAULT_INIT: &str = "/bin/init";
+/// Taille du tas noyau par défaut
+pub const DEFAULT_HEAP_SIZE: usize = 100 * 1024;
+/// Bornes acceptées pour `heap=`
+pub const MIN_HEAP_SIZE: usize = 64 * 1024;
+pub const MAX_HEAP_SIZE: usize = 64 * 1024 * 1024;
+
+/// Ligne de commande brute
+static RAW: Mutex<&'static str> = Mutex::new("");
+
+/// Enregistre la ligne de commande (appelé une fois au démarrage)
+pub fn init(line: &'static str) {
+    *RAW.lock() = line.trim();
+}
+
+/// Ligne de commande brute (/proc/cmdline)
+pub fn raw() -> &'static str {
+    *RAW.lock()
+}
+
+/// Paramètres d'une ligne : `(clé, valeur)`, jusqu'à `--`
+pub struct Params<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Params<'a> {
+    pub fn new(line: &'a str) -> Self {
+        Self { rest: line }
+    }
+
+    /// Arguments suivant `--`
+    pub fn init_args(line: &'a str) -> impl Iterator<Item = &'a str> {
+        let mut params = Self::new(line);
+        while params.next().is_some() {}
+        let tail = params.rest.trim_start();
+        let tail = tail.strip_prefix("--").unwrap_or("");
+        tail.split_whitespace()
+    }
+
+    /// Prochain mot (les espaces entre guillemets n'y mettent pas fin)
+    fn next_word(&mut self) -> Option<&'a str> {
+        let line = self.rest.trim_start();
+        if line.is_empty() {
+            return None;
+        }
+        let mut quoted = false;
+        let end = line
+            .char_indices()
+            .find(|&(_, c)| {
+                if c == '"' {
+                    quoted = !quoted;
+                }
+                c.is_whitespace() && !quoted
+            })
+            .map_or(line.len(), |(i, _)| i);
+        self.rest = &line[end..];
+        Some(&line[..end])
+    }
+}
+
+impl<'a> Iterator for Params<'a> {
+    type Item = (&'a str, Option<&'a str>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let before = self.rest;
+        let word = self.next_word()?;
+        if word == "--" {
+            self.rest = before;
+            return None;
+        }
+        Some(match word.split_once('=') {
+            Some((key, value)) => (key, Some(value.trim_matches('"'))),
+            None => (word, None),
+        })
+    }
+}
+
+/// Valeur du dernier `key=` de `line`
+pub fn lookup<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    Params::new(line).filter(|(k, _)| *k == key).filter_map(|(_, v)| v).last()
+}
+
+/// `line` contient-elle le paramètre `flag` (avec ou sans valeur) ?
+pub fn contains(line: &str, flag: &str) -> bool {
+    Params::new(line).any(|(k, _)| k == flag)
+}
+
+/// Valeur de `key=` sur la ligne de démarrage
+pub fn get(key: &str) -> Option<&'static str> {
+    lookup(raw(), key)
+}
+
+/// Paramètre `flag` présent sur la ligne de démarrage ?
+pub fn has(flag: &str) -> bool {
+    contains(raw(), flag)
+}
+
+/// Taille avec suffixe facultatif K, M ou G (puissances de 1024)
+pub fn parse_size(value: &str) -> Option<usize> {
+    let (digits, shift) = match value.as_bytes().last()? {
+        b'k' | b'K' => (&value[..value.len() - 1], 10),
+        b'm' | b'M' => (&value[..value.len() - 1], 20),
+        b'g' | b'G' => (&value[..value.len() - 1], 30),
+        _ => (value, 0),
+    };
+    digits.parse::<usize>().ok()?.checked_mul(1 << shift)
+}
+
+/// Options reconnues, valeurs par défaut comprises
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BootOptions<'a> {
+    /// Périphérique bloc de la racine, sans `/dev/`
+    pub root: Option<&'a str>,
+    pub init: &'a str,
+    /// `init=` explicite : son absence mérite un avertissement
+    pub init_given: bool,
+    pub log_level: LevelFilter,
+    pub nosmp: bool,
+    pub heap_size: usize,
+    /// None : tranche par défaut du planificateur
+    pub sched_slice: Option<u64>,
+    pub netconsole: Option<NetconsoleTarget>,
+    /// Répertoire des vidages sur panique ; None : kdump inactif
+    pub kdump: Option<&'a str>,
+    /// Seuil du détecteur de blocages en secondes (0 : inactif)
+    pub watchdog_thresh: u64,
+    /// Chien de garde matériel à armer
+    pub watchdog: Option<WatchdogKind>,
+    /// Points d'injection de fautes, validés par `fault_inject::configure`
+    pub fault_inject: Option<&'a str>,
+    /// Masque des événements tracés ; None : tous
+    pub trace_events: Option<u32>,
+    /// ASLR des processus (faux avec `norandmaps`)
+    pub randomize_va: bool,
+    /// SMEP si le CPU l'offre (faux avec `nosmep`)
+    pub smep: bool,
+    /// SMAP si le CPU l'offre (faux avec `nosmap`)
+    pub smap: bool,
+}
+
+impl<'a> BootOptions<'a> {
+    /// Analyse `line` ; une valeur invalide laisse l'option par défaut
+    pub fn parse(line: &'a str) -> Self {
+        let mut options = BootOptions {
+            root: None,
+            init: DEFAULT_INIT,
+            init_given: false,
+            log_level: LevelFilter::Info,
+            nosmp: false,
+            heap_size: DEFAULT_HEAP_SIZE,
+            sched_slice: None,
+            netconsole: None,
+            kdump: None,
+            watchdog_thresh: crate::watchdog::DEFAULT_THRESH,
+            watchdog: None,
+            fault_inject: None,
+            trace_events: None,
+            randomize_va: true,
+            smep: true,
+            smap: true,
+        };
+        for (key, value) in Params::new(line) {
+            match (key, value) {
+                ("root", Some(dev)) if !dev.is_empty() => {
+                    options.root = Some(dev.strip_prefix("/dev/").unwrap_or(dev));
+                }
+                ("init", Some(path)) if path.starts_with('/') => {
+                    options.init = path;
+                    options.init_given = true;
+                }
+                ("loglevel", Some(level)) => {
+                    if let Ok(level) = level.parse::<u8>() {
+                        options.log_level = log_level(level);
+                    }
+                }
+                ("quiet", None) => options.log_level = LevelFilter::Warn,
+                ("debug", None) => options.log_level = LevelFilter::Debug,
+                ("nosmp", None) => options.nosmp = true,
+                ("heap", Some(size)) => {
+                    if let Some(size) = parse_size(size) {
+                        options.heap_size = size.clamp(MIN_HEAP_SIZE, MAX_HEAP_SIZE);
+                    }
+                }
+                ("sched_slice", Some(ticks)) => {
+                    if let Ok(ticks) = ticks.parse::<u64>() {
+                        options.sched_slice = Some(ticks.max(1));
+                    }
+                }
+                ("netconsole", Some(spec)) => options.netconsole = NetconsoleTarget::parse(spec).ok(),
+                ("kdump", None) => options.kdump = Some(crate::kdump::DEFAULT_CRASH_DIR),
+                ("kdump", Some(dir)) if dir.starts_with('/') => options.kdump = Some(dir),
+                ("watchdog_thresh", Some(secs)) => {
+                    if let Ok(secs) = secs.parse::<u64>() {
+                        options.watchdog_thresh = secs;
+                    }
+                }
+                ("nowatchdog", None) => options.watchdog_thresh = 0,
+                ("watchdog", Some(kind)) => options.watchdog = WatchdogKind::parse(kind),
+                ("fault_inject", Some(spec)) if !spec.is_empty() => options.fault_inject = Some(spec),
+                ("norandmaps", None) => options.randomize_va = false,
+                ("nosmep", None) => options.smep = false,
+                ("nosmap", None) => options.smap = false,
+                ("trace_event", Some(list)) => options.trace_events = crate::trace::parse_events(list).ok(),
+                _ => {}
+            }
+        }
+        options
+    }
+
+    /// Arguments transmis à init (après `--`)
+    pub fn init_args(line: &'a str) -> Vec<&'a str> {
+        Params::init_args(line).collect()
+    }
+}
+
+/// Niveau `loglevel=` de Linux (0 urgence ... 7 débogage)
+fn log_level(level: u8) -> LevelFilter {
+    match level {
+        0..=3 => LevelFilter::Error,
+        4 => LevelFilter::Warn,
+        5 | 6 => LevelFilter::Info,
+        _ => LevelFilter::Debug,
+    }
+}
+
+/// Options de la ligne de démarrage
+pub fn options() -> BootOptions<'static> {
+    BootOptions::parse(raw())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_parse_boot_options() {
+        let line = "root=/dev/sda1 init=\"/bin/sh\" loglevel=4 nosmp heap=2M sched_slice=8 kdump watchdog=ib700 nowatchdog fault_inject=frame:10,disk_read:100:4:1 trace_event=sched_switch,irq_entry norandmaps nosmap console=ttyS0 -- -l single";
+        let options = BootOptions::parse(line);
+        assert_eq!(options.root, Some("sda1"));
+        assert_eq!(options.init, "/bin/sh");
+        assert!(options.init_given);
+        assert_eq!(options.log_level, LevelFilter::Warn);
+        assert!(options.nosmp);
+        assert_eq!(options.heap_size, 2 * 1024 * 1024);
+        assert_eq!(options.sched_slice, Some(8));
+        assert_eq!(options.kdump, Some("/var/crash"));
+        assert_eq!((options.watchdog, options.watchdog_thresh), (Some(WatchdogKind::Ib700), 0));
+        assert_eq!(options.fault_inject, Some("frame:10,disk_read:100:4:1"));
+        assert_eq!(options.trace_events, Some(0b10001));
+        assert!(!options.randomize_va);
+        assert!(options.smep && !options.smap);
+        assert_eq!(lookup(line, "console"), Some("ttyS0"));
+        assert!(!contains(line, "single"));
+        assert_eq!(BootOptions::init_args(line), ["-l", "single"]);
+
+        // Valeurs par défaut, valeurs invalides ignorées
+        let options = BootOptions::parse("heap=beaucoup loglevel=x kdump=crash trace_event=softirq");
+        assert_eq!(options.init, DEFAULT_INIT);
+        assert_eq!(options.kdump, None);
+        assert_eq!(options.trace_events, None);
+        assert!(options.randomize_va);
+        assert!(options.smep && options.smap);
+        assert_eq!(options.watchdog_thresh, crate::watchdog::DEFAULT_THRESH);
+        assert_eq!(options.heap_size, DEFAULT_HEAP_SIZE);
+        assert_eq!(options.log_level, LevelFilter::Info);
+        assert_eq!(parse_size("16K"), Some(16 * 1024));
+    }
+}
@@ -0,0 +1,305 @@
+/// Détection des fonctionnalités du processeur (CPUID)
+///
+/// Les fonctionnalités sont relevées une fois, au premier accès, sur le
+/// CPU de démarrage ; les AP sont supposés identiques. `cpu_has(Feature)`
+/// sert à choisir un chemin de code (NX, TSC invariant, x2APIC...).
+/// `init_cpu()` active sur le CPU courant ce que le noyau exige :
+/// SSE (CR0/CR4, pour l'espace utilisateur), XSAVE et l'état AVX (XCR0),
+/// pages globales et EFER.NXE. Rien n'est alloué : la détection peut
+/// précéder l'initialisation du tas.
+
+use alloc::string::String;
+use core::fmt::Write;
+use lazy_static::lazy_static;
+use raw_cpuid::CpuId;
+use x86_64::registers::control::{Cr0, Cr0Flags, Cr4, Cr4Flags};
+use x86_64::registers::model_specific::{Efer, EferFlags};
+use x86_64::registers::xcontrol::{XCr0, XCr0Flags};
+use crate::time::ClockSource;
+
+/// Fonctionnalité détectable ; l'ordre est celui de la ligne `flags`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feature {
+    Fpu,
+    Tsc,
+    Msr,
+    Pae,
+    Apic,
+    Pge,
+    Fxsr,
+    Sse,
+    Sse2,
+    Sse3,
+    Ssse3,
+    Sse41,
+    Sse42,
+    X2apic,
+    Popcnt,
+    Xsave,
+    Avx,
+    Rdrand,
+    Hypervisor,
+    Nx,
+    Pages1G,
+    Rdtscp,
+    LongMode,
+    InvariantTsc,
+    FsGsBase,
+    Avx2,
+    Smep,
+    Smap,
+    Pcid,
+    Rdseed,
+}
+
+impl Feature {
+    pub const ALL: [Feature; 30] = [
+        Feature::Fpu, Feature::Tsc, Feature::Msr, Feature::Pae, Feature::Apic,
+        Feature::Pge, Feature::Fxsr, Feature::Sse, Feature::Sse2, Feature::Sse3,
+        Feature::Ssse3, Feature::Sse41, Feature::Sse42, Feature::X2apic, Feature::Popcnt,
+        Feature::Xsave, Feature::Avx, Feature::Rdrand, Feature::Hypervisor, Feature::Nx,
+        Feature::Pages1G, Feature::Rdtscp, Feature::LongMode, Feature::InvariantTsc,
+        Feature::FsGsBase, Feature::Avx2, Feature::Smep, Feature::Smap, Feature::Pcid,
+        Feature::Rdseed,
+    ];
+
+    /// Nom dans /proc/cpuinfo (conventions de Linux)
+    pub fn name(self) -> &'static str {
+        match self {
+            Feature::Fpu => "fpu",
+            Feature::Tsc => "tsc",
+            Feature::Msr => "msr",
+            Feature::Pae => "pae",
+            Feature::Apic => "apic",
+            Feature::Pge => "pge",
+            Feature::Fxsr => "fxsr",
+            Feature::Sse => "sse",
+            Feature::Sse2 => "sse2",
+            Feature::Sse3 => "pni",
+            Feature::Ssse3 => "ssse3",
+            Feature::Sse41 => "sse4_1",
+            Feature::Sse42 => "sse4_2",
+            Feature::X2apic => "x2apic",
+            Feature::Popcnt => "popcnt",
+            Feature::Xsave => "xsave",
+            Feature::Avx => "avx",
+            Feature::Rdrand => "rdrand",
+            Feature::Hypervisor => "hypervisor",
+            Feature::Nx => "nx",
+            Feature::Pages1G => "pdpe1gb",
+            Feature::Rdtscp => "rdtscp",
+            Feature::LongMode => "lm",
+            Feature::InvariantTsc => "constant_tsc",
+            Feature::FsGsBase => "fsgsbase",
+            Feature::Avx2 => "avx2",
+            Feature::Smep => "smep",
+            Feature::Smap => "smap",
+            Feature::Pcid => "pcid",
+            Feature::Rdseed => "rdseed",
+        }
+    }
+
+    fn bit(self) -> u64 {
+        1 << self as u64
+    }
+}
+
+/// Identification du processeur
+pub struct CpuInfo {
+    vendor: [u8; 12],
+    brand: [u8; 48],
+    pub family: u8,
+    pub model: u8,
+    pub stepping: u8,
+    /// Masque des `Feature` présentes
+    features: u64,
+}
+
+impl CpuInfo {
+    fn detect() -> Self {
+        let cpuid = CpuId::new();
+        let mut info = CpuInfo { vendor: [0; 12], brand: [0; 48], family: 0, model: 0, stepping: 0, features: 0 };
+
+        if let Some(vendor) = cpuid.get_vendor_info() {
+            copy_str(&mut info.vendor, vendor.as_str());
+        }
+        if let Some(brand) = cpuid.get_processor_brand_string() {
+            copy_str(&mut info.brand, brand.as_str().trim());
+        }
+
+        let mut set = |feature: Feature, present: bool| {
+            if present {
+                info.features |= feature.bit();
+            }
+        };
+        if let Some(f) = cpuid.get_feature_info() {
+            set(Feature::Fpu, f.has_fpu());
+            set(Feature::Tsc, f.has_tsc());
+            set(Feature::Msr, f.has_msr());
+            set(Feature::Pae, f.has_pae());
+            set(Feature::Apic, f.has_apic());
+            set(Feature::Pge, f.has_pge());
+            set(Feature::Fxsr, f.has_fxsave_fxstor());
+            set(Feature::Sse, f.has_sse());
+            set(Feature::Sse2, f.has_sse2());
+            set(Feature::Sse3, f.has_sse3());
+            set(Feature::Ssse3, f.has_ssse3());
+            set(Feature::Sse41, f.has_sse41());
+            set(Feature::Sse42, f.has_sse42());
+            set(Feature::X2apic, f.has_x2apic());
+            set(Feature::Popcnt, f.has_popcnt());
+            set(Feature::Xsave, f.has_xsave());
+            set(Feature::Avx, f.has_avx());
+            set(Feature::Rdrand, f.has_rdrand());
+            set(Feature::Hypervisor, f.has_hypervisor());
+            set(Feature::Pcid, f.has_pcid());
+        }
+        if let Some(ext) = cpuid.get_extended_processor_and_feature_identifiers() {
+            set(Feature::Nx, ext.has_execute_disable());
+            set(Feature::Pages1G, ext.has_1gib_pages());
+            set(Feature::Rdtscp, ext.has_rdtscp());
+            set(Feature::LongMode, ext.has_64bit_mode());
+        }
+        if let Some(ext) = cpuid.get_extended_feature_info() {
+            set(Feature::FsGsBase, ext.has_fsgsbase());
+            set(Feature::Avx2, ext.has_avx2());
+            set(Feature::Smep, ext.has_smep());
+            set(Feature::Smap, ext.has_smap());
+            set(Feature::Rdseed, ext.has_rdseed());
+        }
+        if let Some(apm) = cpuid.get_advanced_power_mgmt_info() {
+            set(Feature::InvariantTsc, apm.has_invariant_tsc());
+        }
+        if let Some(f) = cpuid.get_feature_info() {
+            info.family = f.family_id();
+            info.model = f.model_id();
+            info.stepping = f.stepping_id();
+        }
+        info
+    }
+
+    pub fn vendor(&self) -> &str {
+        as_str(&self.vendor)
+    }
+
+    pub fn brand(&self) -> &str {
+        as_str(&self.brand)
+    }
+
+    pub fn has(&self, feature: Feature) -> bool {
+        self.features & feature.bit() != 0
+    }
+
+    /// Noms des fonctionnalités présentes, séparés par des espaces
+    pub fn flags(&self) -> String {
+        let mut out = String::new();
+        for feature in Feature::ALL.iter().filter(|f| self.has(**f)) {
+            if !out.is_empty() {
+                out.push(' ');
+            }
+            out.push_str(feature.name());
+        }
+        out
+    }
+}
+
+fn copy_str(dst: &mut [u8], src: &str) {
+    let len = src.len().min(dst.len());
+    dst[..len].copy_from_slice(&src.as_bytes()[..len]);
+}
+
+fn as_str(bytes: &[u8]) -> &str {
+    let len = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    core::str::from_utf8(&bytes[..len]).unwrap_or("?")
+}
+
+lazy_static! {
+    /// Fonctionnalités du CPU de démarrage
+    pub static ref CPU_INFO: CpuInfo = CpuInfo::detect();
+}
+
+/// Le processeur offre-t-il `feature` ?
+pub fn cpu_has(feature: Feature) -> bool {
+    CPU_INFO.has(feature)
+}
+
+/// Active sur le CPU courant les fonctionnalités exigées par le noyau ;
+/// appelé par le BSP au démarrage puis par chaque AP
+pub fn init_cpu() {
+    unsafe {
+        // SSE : plus d'émulation x87, FXSAVE et exceptions SIMD gérées
+        if cpu_has(Feature::Sse) && cpu_has(Feature::Fxsr) {
+            Cr0::update(|cr0| {
+                cr0.remove(Cr0Flags::EMULATE_COPROCESSOR);
+                cr0.insert(Cr0Flags::MONITOR_COPROCESSOR);
+            });
+            Cr4::update(|cr4| cr4.insert(Cr4Flags::OSFXSR | Cr4Flags::OSXMMEXCPT_ENABLE));
+        }
+        // XSAVE, puis l'état AVX dans XCR0
+        if cpu_has(Feature::Xsave) {
+            Cr4::update(|cr4| cr4.insert(Cr4Flags::OSXSAVE));
+            let mut xcr0 = XCr0Flags::X87 | XCr0Flags::SSE;
+            if cpu_has(Feature::Avx) {
+                xcr0 |= XCr0Flags::AVX;
+            }
+            XCr0::write(xcr0);
+        }
+        // Entrées de TLB globales pour les pages noyau
+        if cpu_has(Feature::Pge) {
+            Cr4::update(|cr4| cr4.insert(Cr4Flags::PAGE_GLOBAL));
+        }
+        if cpu_has(Feature::Nx) {
+            Efer::update(|efer| efer.insert(EferFlags::NO_EXECUTE_ENABLE));
+        }
+        // CR4.FSGSBASE reste éteint : il ouvrirait WRGSBASE au ring 3, qui
+        // pourrait alors choisir le GS du noyau. FS passe par le MSR.
+    }
+}
+
+/// Résumé d'une ligne pour la console de démarrage
+pub fn summary() -> String {
+    let info = &*CPU_INFO;
+    let mut out = String::new();
+    let _ = write!(out, "CPU: {} ({}) famille {} modèle {} stepping {}",
+        info.brand(), info.vendor(), info.family, info.model, info.stepping);
+    out
+}
+
+/// Contenu de /proc/cpuinfo : un bloc par CPU enregistré
+pub fn cpuinfo() -> String {
+    let info = &*CPU_INFO;
+    let flags = info.flags();
+    let mhz = crate::time::tsc::TSC.frequency() / 1_000_000;
+    let mut out = String::new();
+    for cpu in 0..crate::percpu::cpu_count() {
+        let _ = writeln!(out, "processor\t: {}", cpu);
+        let _ = writeln!(out, "vendor_id\t: {}", info.vendor());
+        let _ = writeln!(out, "cpu family\t: {}", info.family);
+        let _ = writeln!(out, "model\t\t: {}", info.model);
+        let _ = writeln!(out, "model name\t: {}", info.brand());
+        let _ = writeln!(out, "stepping\t: {}", info.stepping);
+        let _ = writeln!(out, "cpu MHz\t\t: {}", mhz);
+        let _ = writeln!(out, "flags\t\t: {}", flags);
+        let _ = writeln!(out);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_cpu_features() {
+        // Le noyau tourne en mode long : lm, fpu et tsc sont acquis
+        assert!(cpu_has(Feature::LongMode));
+        assert!(cpu_has(Feature::Fpu));
+        let flags = CPU_INFO.flags();
+        assert!(flags.split(' ').any(|f| f == "lm"));
+        assert!(cpuinfo().contains("processor\t: 0"));
+        // Un bit par fonctionnalité
+        for (i, feature) in Feature::ALL.iter().enumerate() {
+            assert_eq!(*feature as usize, i);
+        }
+    }
+}
@@ -16,6 +16,7 @@ pub mod audio;
 pub mod video;
 pub mod hotplug;
 pub mod events;
+pub mod virtio;
 
 pub use pci::*;
 pub use ethernet::*;
@@ -29,7 +30,12 @@ pub use video::*;
 pub use hotplug::*;
 pub use events::*;
 
+use alloc::sync::Arc;
+use mini_os::fs::devfs::{self, DeviceOps, DeviceKind};
+use mini_os::fs::{VfsError, VfsResult};
 use crate::vga_buffer::WRITER;
+#[cfg(feature = "usb")]
+use mini_os::drivers::usb_hid::HidHotplugEvent;
 
 /// Types de périphériques
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -37,6 +43,8 @@ pub enum DeviceType {
     Ethernet,
     Wifi,
     UsbDisk,
+    Storage,
+    Input,
     Bluetooth,
     Audio,
     Video,
@@ -61,6 +69,46 @@ pub trait Device: Send + Sync {
     fn device_type(&self) -> DeviceType;
     fn init(&mut self) -> Result<(), DeviceError>;
     fn shutdown(&mut self) -> Result<(), DeviceError>;
+
+    /// Lecture via le nœud /dev (non supportée par défaut)
+    fn read(&mut self, _offset: u64, _buf: &mut [u8]) -> Result<usize, DeviceError> {
+        Err(DeviceError::NotSupported)
+    }
+
+    /// Écriture via le nœud /dev (non supportée par défaut)
+    fn write(&mut self, _offset: u64, _buf: &[u8]) -> Result<usize, DeviceError> {
+        Err(DeviceError::NotSupported)
+    }
+}
+
+/// Nœud /dev routant les E/S vers un périphérique du DEVICE_MANAGER
+struct ManagedDeviceNode {
+    name: String,
+}
+
+impl DeviceOps for ManagedDeviceNode {
+    fn read(&mut self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        let mut manager = DEVICE_MANAGER.lock();
+        let device = manager.get_device_mut(&self.name).ok_or(VfsError::NotFound)?;
+        device.read(offset, buf).map_err(VfsError::from)
+    }
+
+    fn write(&mut self, offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        let mut manager = DEVICE_MANAGER.lock();
+        let device = manager.get_device_mut(&self.name).ok_or(VfsError::NotFound)?;
+        device.write(offset, buf).map_err(VfsError::from)
+    }
+}
+
+impl From<DeviceError> for VfsError {
+    fn from(err: DeviceError) -> Self {
+        match err {
+            DeviceError::NotFound => VfsError::NotFound,
+            DeviceError::InvalidArgument => VfsError::InvalidArgument,
+            DeviceError::NotSupported => VfsError::NotSupported,
+            _ => VfsError::IoError,
+        }
+    }
 }
 
 /// Trait pour les énumérateurs de bus
@@ -81,6 +129,8 @@ pub struct DeviceManager {
     buses: BTreeMap<String, Box<dyn BusEnumerator>>,
     hotplug_handlers: Vec<Box<dyn HotplugHandler>>,
     initialized: BTreeMap<String, bool>,
+    /// Métadonnées (clé -> valeur) par nom de périphérique
+    metadata: BTreeMap<String, BTreeMap<String, String>>,
 }
 
 impl DeviceManager {
@@ -91,6 +141,7 @@ impl DeviceManager {
             buses: BTreeMap::new(),
             hotplug_handlers: Vec::new(),
             initialized: BTreeMap::new(),
+            metadata: BTreeMap::new(),
         }
     }
 
@@ -102,6 +153,22 @@ impl DeviceManager {
 
         self.devices.insert(name.into(), device);
         self.initialized.insert(name.into(), false);
+
+        // Créer le nœud /dev correspondant
+        let _ = devfs::register_device(
+            name,
+            DeviceKind::Char,
+            Arc::new(Mutex::new(ManagedDeviceNode { name: name.into() })),
+        );
+        Ok(())
+    }
+
+    /// Retire un périphérique (après un débranchement)
+    pub fn unregister_device(&mut self, name: &str) -> Result<(), DeviceError> {
+        let mut device = self.devices.remove(name).ok_or(DeviceError::NotFound)?;
+        if self.initialized.remove(name).unwrap_or(false) {
+            let _ = device.shutdown();
+        }
         Ok(())
     }
 
@@ -178,6 +245,16 @@ impl DeviceManager {
             }
         }
 
+        // Claviers et souris USB déjà attachés par les contrôleurs
+        #[cfg(feature = "usb")]
+        self.poll_hotplug();
+
+        // Drivers choisis d'après les identifiants PCI
+        let virtio = virtio::detect(self);
+        if virtio > 0 {
+            WRITER.lock().write_string(&format!("virtio: {} périphériques\n", virtio));
+        }
+
         Ok(())
     }
 
@@ -195,6 +272,35 @@ impl DeviceManager {
         }
     }
 
+    /// Attache une métadonnée à un périphérique
+    pub fn set_metadata(&mut self, name: &str, key: &str, value: String) {
+        self.metadata.entry(name.into()).or_default().insert(key.into(), value);
+    }
+
+    /// Métadonnées d'un périphérique
+    pub fn metadata(&self, name: &str) -> Option<&BTreeMap<String, String>> {
+        self.metadata.get(name)
+    }
+
+    /// Reprend l'identité ATA et les attributs SMART publiés par le driver
+    /// du disque `name`
+    pub fn import_disk_info(&mut self, name: &str) -> bool {
+        let Some(info) = mini_os::drivers::ata_info::get(name) else {
+            return false;
+        };
+        let id = &info.identity;
+        self.set_metadata(name, "model", id.model.clone());
+        self.set_metadata(name, "serial", id.serial.clone());
+        self.set_metadata(name, "firmware", id.firmware.clone());
+        self.set_metadata(name, "sectors", format!("{}", id.sectors));
+        self.set_metadata(name, "dma", format!("{}", id.dma));
+        self.set_metadata(name, "smart", format!("{}", id.smart_enabled));
+        for attr in &info.smart {
+            self.set_metadata(name, &format!("smart.{}", attr.name()), format!("{}", attr.raw));
+        }
+        true
+    }
+
     /// Vérifie si un périphérique est initialisé
     pub fn is_initialized(&self, name: &str) -> bool {
         self.initialized.get(name).copied().unwrap_or(false)
@@ -227,6 +333,8 @@ impl DeviceManager {
 
     /// Gère un événement de retrait de hotplug
     pub fn handle_hotplug_remove(&mut self, device_name: &str) -> Result<(), DeviceError> {
+        let _ = devfs::unregister_device(device_name);
+
         WRITER.lock().write_string(&format!(
             "Périphérique retiré: {}\n",
             device_name
@@ -239,6 +347,27 @@ impl DeviceManager {
         Ok(())
     }
 
+    /// Reporte les branchements et retraits signalés par les drivers USB HID
+    #[cfg(feature = "usb")]
+    pub fn poll_hotplug(&mut self) -> usize {
+        let events = mini_os::drivers::usb_hid::take_hid_hotplug_events();
+        for event in &events {
+            match event {
+                HidHotplugEvent::Attached { name, class } => {
+                    if self.register_device(name, Box::new(UsbHidDevice::new(name, *class))).is_ok() {
+                        let _ = self.init_device(name);
+                        let _ = self.handle_hotplug_add(name);
+                    }
+                }
+                HidHotplugEvent::Detached { name } => {
+                    let _ = self.handle_hotplug_remove(name);
+                    let _ = self.unregister_device(name);
+                }
+            }
+        }
+        events.len()
+    }
+
     /// Arrête un périphérique
     pub fn shutdown_device(&mut self, name: &str) -> Result<(), DeviceError> {
         if let Some(device) = self.devices.get_mut(name) {
@@ -266,6 +395,17 @@ lazy_static! {
     pub static ref DEVICE_MANAGER: Mutex<DeviceManager> = Mutex::new(DeviceManager::new());
 }
 
+/// Détecte puis initialise les périphériques de tous les bus
+fn init() -> Result<(), DeviceError> {
+    let mut manager = DEVICE_MANAGER.lock();
+    manager.detect_all_devices()?;
+    manager.init_all_devices()?;
+    WRITER.lock().write_string(&format!("Périphériques détectés: {}\n", manager.list_devices().len()));
+    Ok(())
+}
+
+mini_os::initcall!(late, "Gestionnaire de périphériques", init);
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2,6 +2,7 @@ use super::{Device, DeviceType, DeviceError};
 use alloc::vec::Vec;
 use alloc::string::String;
 use crate::vga_buffer::WRITER;
+use mini_os::drivers::usb_hid::HidClass;
 
 /// Vitesses USB
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -125,6 +126,39 @@ impl Device for UsbDevice {
     }
 }
 
+/// Clavier ou souris USB attaché par le driver HID
+pub struct UsbHidDevice {
+    name: String,
+    class: HidClass,
+}
+
+impl UsbHidDevice {
+    pub fn new(name: &str, class: HidClass) -> Self {
+        Self { name: name.into(), class }
+    }
+}
+
+impl Device for UsbHidDevice {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn device_type(&self) -> DeviceType {
+        DeviceType::Input
+    }
+
+    fn init(&mut self) -> Result<(), DeviceError> {
+        // Les rapports arrivent déjà : le driver HID est actif dès l'attachement
+        WRITER.lock().write_string(&format!("USB HID: {} ({:?})\n", self.name, self.class));
+        Ok(())
+    }
+
+    fn shutdown(&mut self) -> Result<(), DeviceError> {
+        WRITER.lock().write_string(&format!("Arrêt USB HID: {}\n", self.name));
+        Ok(())
+    }
+}
+
 /// Disque USB
 #[derive(Debug, Clone)]
 pub struct UsbDisk {
@@ -235,6 +269,12 @@ mod tests {
         assert_eq!(disk.get_size_gb(), 32);
     }
 
+    #[test_case]
+    fn test_usb_hid_device_type() {
+        let device = UsbHidDevice::new("usbkbd0", HidClass::Keyboard);
+        assert_eq!(device.device_type(), DeviceType::Input);
+    }
+
     #[test_case]
     fn test_usb_enumerator() {
         let devices = UsbEnumerator::enumerate().unwrap();
@@ -0,0 +1,128 @@
+use super::{Device, DeviceType, DeviceError, DeviceManager, PciEnumerator};
+use crate::vga_buffer::WRITER;
+use alloc::boxed::Box;
+use alloc::string::String;
+use mini_os::drivers::virtio::{self, VIRTIO_VENDOR};
+use mini_os::net::{interface, Ipv4Address};
+
+/// Adresse par défaut (réseau « user » de QEMU) si aucune n'est configurée
+const DEFAULT_IP: [u8; 4] = [10, 0, 2, 15];
+const DEFAULT_GATEWAY: [u8; 4] = [10, 0, 2, 2];
+
+/// Driver virtio choisi d'après l'identifiant PCI
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VirtioKind {
+    Block,
+    Net,
+}
+
+impl VirtioKind {
+    /// Identifiants transitionnels (0x1000+) et modernes (0x1040 + type)
+    pub fn from_device_id(device_id: u16) -> Option<Self> {
+        match device_id {
+            virtio::DEVICE_BLK | 0x1042 => Some(VirtioKind::Block),
+            virtio::DEVICE_NET | 0x1041 => Some(VirtioKind::Net),
+            _ => None,
+        }
+    }
+}
+
+/// Périphérique virtio détecté sur le bus PCI
+pub struct VirtioDevice {
+    name: String,
+    kind: VirtioKind,
+    initialized: bool,
+}
+
+impl VirtioDevice {
+    pub fn new(name: &str, kind: VirtioKind) -> Self {
+        Self { name: name.into(), kind, initialized: false }
+    }
+}
+
+impl Device for VirtioDevice {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn device_type(&self) -> DeviceType {
+        match self.kind {
+            VirtioKind::Block => DeviceType::Storage,
+            VirtioKind::Net => DeviceType::Ethernet,
+        }
+    }
+
+    fn init(&mut self) -> Result<(), DeviceError> {
+        if self.initialized {
+            return Ok(());
+        }
+        // Le probe couvre toutes les fonctions du même type
+        let found = match self.kind {
+            VirtioKind::Block => virtio::blk::probe(),
+            VirtioKind::Net => {
+                let nics = virtio::net::probe();
+                if let Some((name, mac)) = nics.first() {
+                    if interface::NETWORK_INTERFACE.lock().is_none() {
+                        interface::init(*mac, Ipv4Address(DEFAULT_IP));
+                        interface::set_gateway(Ipv4Address(DEFAULT_GATEWAY));
+                        // Configuration enregistrée par ifconfig/ip
+                        interface::load_config();
+                        mini_os::net::netconsole::load_config();
+                    }
+                    WRITER.lock().write_string(&format!("virtio-net: {} {}\n", name, mac));
+                }
+                nics.len()
+            }
+        };
+        if found == 0 {
+            return Err(DeviceError::InitializationFailed);
+        }
+        self.initialized = true;
+        Ok(())
+    }
+
+    fn shutdown(&mut self) -> Result<(), DeviceError> {
+        self.initialized = false;
+        Ok(())
+    }
+}
+
+/// Enregistre un périphérique par type virtio présent sur le bus PCI
+pub fn detect(manager: &mut DeviceManager) -> usize {
+    let mut registered = 0;
+    for device in PciEnumerator::enumerate() {
+        if device.vendor_id != VIRTIO_VENDOR {
+            continue;
+        }
+        let kind = match VirtioKind::from_device_id(device.device_id) {
+            Some(kind) => kind,
+            None => continue,
+        };
+        let name = match kind {
+            VirtioKind::Block => "virtio-blk",
+            VirtioKind::Net => "virtio-net",
+        };
+        if manager.register_device(name, Box::new(VirtioDevice::new(name, kind))).is_ok() {
+            registered += 1;
+        }
+    }
+    registered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_virtio_kind_from_id() {
+        assert_eq!(VirtioKind::from_device_id(0x1001), Some(VirtioKind::Block));
+        assert_eq!(VirtioKind::from_device_id(0x1041), Some(VirtioKind::Net));
+        assert_eq!(VirtioKind::from_device_id(0x1050), None);
+    }
+
+    #[test_case]
+    fn test_virtio_device_type() {
+        let dev = VirtioDevice::new("virtio-net", VirtioKind::Net);
+        assert_eq!(dev.device_type(), DeviceType::Ethernet);
+    }
+}
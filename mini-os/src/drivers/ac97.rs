@@ -0,0 +1,374 @@
+/// Contrôleur audio AC'97 (Intel ICH, QEMU `-device AC97`)
+///
+/// Deux espaces d'E/S : NAM (BAR 0, registres du codec) et NABM
+/// (BAR 1, moteurs DMA). Seule la sortie PCM est gérée : une liste de
+/// 32 descripteurs pointe vers autant de tampons DMA remplis à tour de
+/// rôle. Le moteur joue jusqu'au dernier descripteur valide (LVI) ; le
+/// driver avance LVI à chaque tampon rempli et récupère les tampons déjà
+/// joués en suivant l'index courant (CIV).
+///
+/// Format : PCM signé 16 bits stéréo entrelacé.
+
+use spin::Mutex;
+use x86_64::instructions::interrupts::without_interrupts;
+use x86_64::instructions::port::Port;
+use crate::drivers::dma::DmaBuffer;
+use crate::drivers::pci::{self, PciFunction};
+use super::{Driver, DriverError};
+
+/// Registres du codec (NAM)
+mod nam {
+    pub const RESET: u16 = 0x00;
+    pub const MASTER_VOLUME: u16 = 0x02;
+    pub const PCM_OUT_VOLUME: u16 = 0x18;
+    pub const EXT_AUDIO_ID: u16 = 0x28;
+    pub const EXT_AUDIO_CTRL: u16 = 0x2A;
+    pub const PCM_FRONT_RATE: u16 = 0x2C;
+}
+
+/// Registres du bus master (NABM)
+mod nabm {
+    /// Boîte « PCM out »
+    pub const PO_BDBAR: u16 = 0x10;
+    pub const PO_CIV: u16 = 0x14;
+    pub const PO_LVI: u16 = 0x15;
+    pub const PO_SR: u16 = 0x16;
+    pub const PO_CR: u16 = 0x1B;
+    pub const GLOBAL_CONTROL: u16 = 0x2C;
+    pub const GLOBAL_STATUS: u16 = 0x30;
+}
+
+/// Extended Audio : taux variable (VRA)
+const EXT_VRA: u16 = 1 << 0;
+
+/// Registre d'état d'une boîte DMA
+const SR_DCH: u16 = 1 << 0;
+const SR_LVBCI: u16 = 1 << 2;
+const SR_BCIS: u16 = 1 << 3;
+const SR_FIFOE: u16 = 1 << 4;
+
+/// Registre de contrôle d'une boîte DMA
+const CR_RUN: u8 = 1 << 0;
+const CR_RESET: u8 = 1 << 1;
+const CR_IOCE: u8 = 1 << 4;
+
+/// Contrôle global : sortie du reset à froid
+const GC_COLD_RESET: u32 = 1 << 1;
+
+/// Descripteur : interruption en fin de tampon
+const BD_IOC: u16 = 1 << 15;
+
+/// Descripteurs de la liste (fixé par le matériel)
+const BD_COUNT: usize = 32;
+/// Octets par tampon (1024 trames stéréo 16 bits)
+pub const BUFFER_BYTES: usize = 4096;
+/// Échantillons 16 bits par tampon
+pub const BUFFER_SAMPLES: usize = BUFFER_BYTES / 2;
+/// Fréquence fixe du codec sans VRA
+pub const DEFAULT_RATE: u32 = 48_000;
+
+/// Entrée de la liste de descripteurs
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+struct BufferDescriptor {
+    addr: u32,
+    /// Nombre d'échantillons 16 bits (toutes voies confondues)
+    samples: u16,
+    flags: u16,
+}
+
+/// Contrôleur AC'97
+pub struct Ac97 {
+    pub function: PciFunction,
+    nam: u16,
+    nabm: u16,
+    bdl: DmaBuffer,
+    buffers: DmaBuffer,
+    /// Plus ancien tampon en attente de lecture
+    head: usize,
+    /// Nombre de tampons en attente
+    queued: usize,
+    /// Fréquence d'échantillonnage programmée
+    rate: u32,
+    variable_rate: bool,
+    pub underruns: u64,
+}
+
+fn inb(port: u16) -> u8 {
+    unsafe { Port::<u8>::new(port).read() }
+}
+
+fn outb(port: u16, value: u8) {
+    unsafe { Port::<u8>::new(port).write(value) }
+}
+
+fn inw(port: u16) -> u16 {
+    unsafe { Port::<u16>::new(port).read() }
+}
+
+fn outw(port: u16, value: u16) {
+    unsafe { Port::<u16>::new(port).write(value) }
+}
+
+fn outl(port: u16, value: u32) {
+    unsafe { Port::<u32>::new(port).write(value) }
+}
+
+fn inl(port: u16) -> u32 {
+    unsafe { Port::<u32>::new(port).read() }
+}
+
+/// Tampons joués entre l'index `head` et l'index courant `civ`
+pub fn completed_between(head: usize, civ: usize) -> usize {
+    (civ + BD_COUNT - head) % BD_COUNT
+}
+
+impl Ac97 {
+    pub fn probe(function: PciFunction) -> Result<Self, DriverError> {
+        let nam = function.io_bar(0).ok_or(DriverError::NotSupported)?;
+        let nabm = function.io_bar(1).ok_or(DriverError::NotSupported)?;
+        function.enable_bus_master();
+
+        // Sortie du reset à froid puis reset du codec
+        outl(nabm + nabm::GLOBAL_CONTROL, GC_COLD_RESET);
+        for _ in 0..100_000 {
+            core::hint::spin_loop();
+        }
+        outw(nam + nam::RESET, 0);
+
+        // Volumes : 0 = atténuation nulle, bit 15 = muet
+        outw(nam + nam::MASTER_VOLUME, 0x0000);
+        outw(nam + nam::PCM_OUT_VOLUME, 0x0808);
+
+        let variable_rate = inw(nam + nam::EXT_AUDIO_ID) & EXT_VRA != 0;
+        if variable_rate {
+            outw(nam + nam::EXT_AUDIO_CTRL, inw(nam + nam::EXT_AUDIO_CTRL) | EXT_VRA);
+        }
+
+        let bdl = DmaBuffer::new(BD_COUNT * 8).ok_or(DriverError::InitializationFailed)?;
+        let buffers = DmaBuffer::new(BD_COUNT * BUFFER_BYTES).ok_or(DriverError::InitializationFailed)?;
+
+        let mut dev = Self {
+            function,
+            nam,
+            nabm,
+            bdl,
+            buffers,
+            head: 0,
+            queued: 0,
+            rate: DEFAULT_RATE,
+            variable_rate,
+            underruns: 0,
+        };
+        dev.reset_engine();
+        dev.set_rate(DEFAULT_RATE)?;
+        Ok(dev)
+    }
+
+    /// Réinitialise le moteur de sortie et vide la file
+    fn reset_engine(&mut self) {
+        outb(self.nabm + nabm::PO_CR, 0);
+        outb(self.nabm + nabm::PO_CR, CR_RESET);
+        for _ in 0..1000 {
+            if inb(self.nabm + nabm::PO_CR) & CR_RESET == 0 {
+                break;
+            }
+            core::hint::spin_loop();
+        }
+        outl(self.nabm + nabm::PO_BDBAR, self.bdl.phys() as u32);
+        self.head = 0;
+        self.queued = 0;
+    }
+
+    /// Programme la fréquence ; retourne la fréquence réellement obtenue
+    pub fn set_rate(&mut self, rate: u32) -> Result<u32, DriverError> {
+        if !self.variable_rate {
+            return if rate == DEFAULT_RATE { Ok(rate) } else { Err(DriverError::NotSupported) };
+        }
+        if !(8_000..=48_000).contains(&rate) {
+            return Err(DriverError::InvalidArgument);
+        }
+        outw(self.nam + nam::PCM_FRONT_RATE, rate as u16);
+        self.rate = inw(self.nam + nam::PCM_FRONT_RATE) as u32;
+        Ok(self.rate)
+    }
+
+    pub fn rate(&self) -> u32 {
+        self.rate
+    }
+
+    pub fn variable_rate(&self) -> bool {
+        self.variable_rate
+    }
+
+    /// Volume principal, 0 (muet) à 100
+    pub fn set_volume(&mut self, percent: u8) {
+        let value = if percent == 0 {
+            0x8000
+        } else {
+            // 6 bits d'atténuation par voie (1,5 dB par pas)
+            let attenuation = (63 * (100 - percent.min(100) as u16)) / 100;
+            attenuation << 8 | attenuation
+        };
+        outw(self.nam + nam::MASTER_VOLUME, value);
+    }
+
+    fn halted(&self) -> bool {
+        inw(self.nabm + nabm::PO_SR) & SR_DCH != 0
+    }
+
+    /// Libère les tampons déjà joués
+    fn reclaim(&mut self) {
+        if self.queued == 0 {
+            return;
+        }
+        let status = inw(self.nabm + nabm::PO_SR);
+        // Acquittement des bits d'état (écrire 1 pour effacer)
+        outw(self.nabm + nabm::PO_SR, status & (SR_LVBCI | SR_BCIS | SR_FIFOE));
+
+        if status & SR_DCH != 0 {
+            // Dernier descripteur valide joué : la file est vide
+            self.head = (self.head + self.queued) % BD_COUNT;
+            self.queued = 0;
+            self.underruns += 1;
+            return;
+        }
+        let civ = inb(self.nabm + nabm::PO_CIV) as usize % BD_COUNT;
+        let done = completed_between(self.head, civ);
+        if done <= self.queued {
+            self.head = civ;
+            self.queued -= done;
+        }
+    }
+
+    /// Tampons libres (un descripteur reste toujours inutilisé pour que
+    /// CIV et LVI ne se rejoignent pas)
+    pub fn free_buffers(&mut self) -> usize {
+        self.reclaim();
+        BD_COUNT - 1 - self.queued
+    }
+
+    /// Tampons encore à jouer
+    pub fn queued_buffers(&mut self) -> usize {
+        self.reclaim();
+        self.queued
+    }
+
+    /// Copie des échantillons stéréo dans les tampons libres et relance le
+    /// moteur ; retourne le nombre d'échantillons pris (sans bloquer)
+    pub fn write(&mut self, samples: &[i16]) -> usize {
+        self.reclaim();
+        let mut written = 0;
+        while written < samples.len() && self.queued < BD_COUNT - 1 {
+            // Un tampon contient toujours des trames entières
+            let count = (samples.len() - written).min(BUFFER_SAMPLES) & !1;
+            if count == 0 {
+                break;
+            }
+            let index = (self.head + self.queued) % BD_COUNT;
+            let offset = index * BUFFER_BYTES;
+            let dst = &mut self.buffers.as_mut_slice()[offset..offset + count * 2];
+            for (chunk, sample) in dst.chunks_exact_mut(2).zip(&samples[written..written + count]) {
+                chunk.copy_from_slice(&sample.to_le_bytes());
+            }
+
+            let descriptor = BufferDescriptor {
+                addr: (self.buffers.phys() + offset as u64) as u32,
+                samples: count as u16,
+                flags: BD_IOC,
+            };
+            unsafe { core::ptr::write_volatile(self.bdl.as_ptr::<BufferDescriptor>(index * 8), descriptor) }
+
+            self.queued += 1;
+            written += count;
+            outb(self.nabm + nabm::PO_LVI, index as u8);
+        }
+
+        if written > 0 && (self.halted() || inb(self.nabm + nabm::PO_CR) & CR_RUN == 0) {
+            outb(self.nabm + nabm::PO_CR, CR_RUN | CR_IOCE);
+        }
+        written
+    }
+
+    /// Arrête la lecture et abandonne les tampons en attente
+    pub fn stop(&mut self) {
+        self.reset_engine();
+    }
+
+    /// Registre d'état global (diagnostic)
+    pub fn global_status(&self) -> u32 {
+        inl(self.nabm + nabm::GLOBAL_STATUS)
+    }
+}
+
+/// Contrôleur détecté (None avant `init` ou sans carte)
+pub static AC97: Mutex<Option<Ac97>> = Mutex::new(None);
+
+/// Détecte la carte (classe 04/01 : multimédia audio)
+pub fn init() -> Result<(), DriverError> {
+    let function = pci::find_by_class(0x04, 0x01, None)
+        .into_iter()
+        .next()
+        .ok_or(DriverError::NotFound)?;
+    let dev = Ac97::probe(function)?;
+    log::info!(
+        "AC'97: {:04x}:{:04x}, {} Hz{}",
+        function.vendor_id,
+        function.device_id,
+        dev.rate(),
+        if dev.variable_rate() { " (VRA)" } else { "" }
+    );
+    without_interrupts(|| *AC97.lock() = Some(dev));
+    if let Err(e) = crate::interrupts::request_pci_irq(function.interrupt_line(), handle_interrupt) {
+        log::warn!("AC'97: IRQ {} {:?}", function.interrupt_line(), e);
+    }
+    Ok(())
+}
+
+/// Fin de tampon signalée par le contrôleur
+pub fn handle_interrupt() {
+    if let Some(mut guard) = AC97.try_lock() {
+        if let Some(dev) = guard.as_mut() {
+            dev.reclaim();
+        }
+    }
+}
+
+/// Enveloppe pour le gestionnaire de drivers
+pub struct Ac97Driver;
+
+impl Driver for Ac97Driver {
+    fn name(&self) -> &str {
+        "ac97"
+    }
+
+    fn init(&mut self) -> Result<(), DriverError> {
+        init()
+    }
+
+    fn handle_interrupt(&mut self, _irq: u8) {
+        handle_interrupt();
+    }
+
+    fn shutdown(&mut self) -> Result<(), DriverError> {
+        without_interrupts(|| {
+            if let Some(dev) = AC97.lock().as_mut() {
+                dev.stop();
+            }
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_completed_between_wraps() {
+        assert_eq!(completed_between(0, 0), 0);
+        assert_eq!(completed_between(3, 7), 4);
+        assert_eq!(completed_between(30, 2), 4);
+        assert_eq!(core::mem::size_of::<BufferDescriptor>(), 8);
+    }
+}
@@ -0,0 +1,687 @@
+/// Driver AHCI (contrôleurs SATA)
+///
+/// Le HBA est trouvé sur le bus PCI (classe 01h, sous-classe 06h,
+/// interface 01h) et ses registres lus via ABAR (BAR 5). Chaque port
+/// occupé par un disque reçoit :
+///
+/// - une liste de commandes (32 en-têtes) et une zone de FIS reçus ;
+/// - une table de commande par emplacement (FIS H2D + PRDT) ;
+/// - un tampon de rebond de 4 Kio par emplacement pour le DMA.
+///
+/// Les requêtes READ/WRITE DMA EXT passent par une file : elles sont
+/// placées dans les emplacements libres, et l'interruption du port
+/// (ou, à défaut, le scrutateur de l'appelant) relève celles que le HBA
+/// a terminées. Chaque disque est exposé dans la couche bloc (`sata0`...).
+
+use alloc::collections::VecDeque;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::ptr::{read_volatile, write_volatile};
+use spin::Mutex;
+use x86_64::instructions::interrupts::without_interrupts;
+use super::block::{self, BlockDevice};
+use super::disk::DiskError;
+use super::dma::DmaBuffer;
+use super::pci::{self, PciFunction};
+use super::{Driver, DriverError};
+
+/// Taille d'un secteur SATA
+pub const SECTOR_SIZE: usize = 512;
+/// Emplacements de commande utilisés par port (sur 32 possibles)
+const SLOTS: usize = 8;
+/// Secteurs par commande : un tampon de rebond de 4 Kio par emplacement
+const SECTORS_PER_SLOT: usize = 8;
+/// Taille réservée à chaque table de commande (FIS + une entrée PRDT)
+const TABLE_STRIDE: usize = 256;
+/// Itérations de scrutation avant d'abandonner une commande
+const TIMEOUT_SPINS: usize = 10_000_000;
+
+/// Registres globaux du HBA
+mod hba {
+    pub const CAP: usize = 0x00;
+    pub const GHC: usize = 0x04;
+    pub const IS: usize = 0x08;
+    pub const PI: usize = 0x0C;
+
+    pub const GHC_IE: u32 = 1 << 1;
+    pub const GHC_AE: u32 = 1 << 31;
+    pub const CAP_S64A: u32 = 1 << 31;
+}
+
+/// Registres d'un port (à 0x100 + n * 0x80)
+mod port {
+    pub const BASE: usize = 0x100;
+    pub const STRIDE: usize = 0x80;
+
+    pub const CLB: usize = 0x00;
+    pub const CLBU: usize = 0x04;
+    pub const FB: usize = 0x08;
+    pub const FBU: usize = 0x0C;
+    pub const IS: usize = 0x10;
+    pub const IE: usize = 0x14;
+    pub const CMD: usize = 0x18;
+    pub const TFD: usize = 0x20;
+    pub const SIG: usize = 0x24;
+    pub const SSTS: usize = 0x28;
+    pub const SERR: usize = 0x30;
+    pub const CI: usize = 0x38;
+
+    pub const CMD_ST: u32 = 1 << 0;
+    pub const CMD_FRE: u32 = 1 << 4;
+    pub const CMD_FR: u32 = 1 << 14;
+    pub const CMD_CR: u32 = 1 << 15;
+
+    /// D2H, PIO Setup, DMA Setup, Set Device Bits, erreurs de tâche/bus
+    pub const IE_DEFAULT: u32 = 0x0F | (1 << 27) | (1 << 29) | (1 << 30);
+    pub const IS_TFES: u32 = 1 << 30;
+
+    pub const TFD_ERR: u32 = 0x01;
+    pub const TFD_DRQ: u32 = 0x08;
+    pub const TFD_BSY: u32 = 0x80;
+}
+
+/// Commandes ATA utilisées
+pub mod ata {
+    pub const READ_DMA_EXT: u8 = 0x25;
+    pub const WRITE_DMA_EXT: u8 = 0x35;
+    pub const FLUSH_CACHE_EXT: u8 = 0xEA;
+    pub const IDENTIFY: u8 = 0xEC;
+}
+
+/// Signature d'un disque SATA (ni ATAPI ni multiplicateur)
+const SATA_SIG_ATA: u32 = 0x0000_0101;
+
+const FIS_TYPE_REG_H2D: u8 = 0x27;
+
+/// FIS Register Host to Device
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FisRegH2D {
+    pub fis_type: u8,
+    /// Bit 7 : commande (et non contrôle)
+    pub pm_c: u8,
+    pub command: u8,
+    pub feature_low: u8,
+    pub lba0: u8,
+    pub lba1: u8,
+    pub lba2: u8,
+    pub device: u8,
+    pub lba3: u8,
+    pub lba4: u8,
+    pub lba5: u8,
+    pub feature_high: u8,
+    pub count_low: u8,
+    pub count_high: u8,
+    pub icc: u8,
+    pub control: u8,
+    _reserved: [u8; 4],
+}
+
+impl FisRegH2D {
+    /// Commande ATA adressée en LBA48
+    pub fn command(command: u8, lba: u64, count: u16) -> Self {
+        let lba_mode = command != ata::IDENTIFY;
+        Self {
+            fis_type: FIS_TYPE_REG_H2D,
+            pm_c: 0x80,
+            command,
+            lba0: lba as u8,
+            lba1: (lba >> 8) as u8,
+            lba2: (lba >> 16) as u8,
+            device: if lba_mode { 1 << 6 } else { 0 },
+            lba3: (lba >> 24) as u8,
+            lba4: (lba >> 32) as u8,
+            lba5: (lba >> 40) as u8,
+            count_low: count as u8,
+            count_high: (count >> 8) as u8,
+            ..Self::default()
+        }
+    }
+}
+
+/// En-tête de la liste de commandes
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CommandHeader {
+    /// CFL (longueur du FIS en dwords), bit 6 : écriture, bit 10 : clear busy
+    pub flags: u16,
+    /// Nombre d'entrées PRDT
+    pub prdtl: u16,
+    /// Octets transférés (mis à jour par le HBA)
+    pub prdbc: u32,
+    pub ctba: u32,
+    pub ctbau: u32,
+    _reserved: [u32; 4],
+}
+
+impl CommandHeader {
+    pub fn new(table: u64, write: bool, prdt_entries: u16) -> Self {
+        let cfl = (core::mem::size_of::<FisRegH2D>() / 4) as u16;
+        Self {
+            flags: cfl | if write { 1 << 6 } else { 0 },
+            prdtl: prdt_entries,
+            ctba: table as u32,
+            ctbau: (table >> 32) as u32,
+            ..Self::default()
+        }
+    }
+}
+
+/// Entrée de la table de descripteurs physiques
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PrdEntry {
+    pub dba: u32,
+    pub dbau: u32,
+    _reserved: u32,
+    /// Octets - 1, bit 31 : interruption à la fin
+    pub dbc: u32,
+}
+
+impl PrdEntry {
+    pub fn new(addr: u64, bytes: usize) -> Self {
+        Self {
+            dba: addr as u32,
+            dbau: (addr >> 32) as u32,
+            dbc: (bytes as u32 - 1) | (1 << 31),
+            ..Self::default()
+        }
+    }
+}
+
+/// Offset de la PRDT dans une table de commande
+const PRDT_OFFSET: usize = 0x80;
+/// Offset de la zone de FIS reçus dans le tampon de la liste de commandes
+const RECEIVED_FIS_OFFSET: usize = 0x400;
+
+/// Requête en attente d'un emplacement
+struct Request {
+    id: u64,
+    command: u8,
+    lba: u64,
+    count: u16,
+    /// Données à écrire (None pour une lecture)
+    data: Option<Vec<u8>>,
+}
+
+/// Emplacement occupé
+struct Inflight {
+    id: u64,
+    write: bool,
+    bytes: usize,
+    result: Option<Result<(), DiskError>>,
+}
+
+/// Port SATA occupé par un disque
+pub struct AhciPort {
+    pub index: usize,
+    /// Adresse des registres du port
+    regs: usize,
+    pub sectors: u64,
+    pub model: String,
+    cmd_list: DmaBuffer,
+    tables: DmaBuffer,
+    bounce: DmaBuffer,
+    queue: VecDeque<Request>,
+    slots: [Option<Inflight>; SLOTS],
+    next_id: u64,
+    pub completed: u64,
+    pub errors: u64,
+}
+
+impl AhciPort {
+    fn read(&self, reg: usize) -> u32 {
+        unsafe { read_volatile((self.regs + reg) as *const u32) }
+    }
+
+    fn write(&self, reg: usize, value: u32) {
+        unsafe { write_volatile((self.regs + reg) as *mut u32, value) }
+    }
+
+    fn wait_clear(&self, reg: usize, mask: u32) -> Result<(), DiskError> {
+        for _ in 0..TIMEOUT_SPINS {
+            if self.read(reg) & mask == 0 {
+                return Ok(());
+            }
+            core::hint::spin_loop();
+        }
+        Err(DiskError::Timeout)
+    }
+
+    /// Arrête le moteur de commandes et la réception de FIS
+    fn stop(&self) -> Result<(), DiskError> {
+        self.write(port::CMD, self.read(port::CMD) & !(port::CMD_ST | port::CMD_FRE));
+        self.wait_clear(port::CMD, port::CMD_CR | port::CMD_FR)
+    }
+
+    fn start(&self) -> Result<(), DiskError> {
+        self.wait_clear(port::CMD, port::CMD_CR)?;
+        self.write(port::CMD, self.read(port::CMD) | port::CMD_FRE);
+        self.write(port::CMD, self.read(port::CMD) | port::CMD_ST);
+        Ok(())
+    }
+
+    /// Programme les structures DMA du port et redémarre son moteur
+    fn init(index: usize, regs: usize) -> Result<Self, DiskError> {
+        let alloc = || DmaBuffer::new(4096).ok_or(DiskError::NotReady);
+        let mut p = Self {
+            index,
+            regs,
+            sectors: 0,
+            model: String::new(),
+            cmd_list: alloc()?,
+            tables: alloc()?,
+            bounce: DmaBuffer::new(SLOTS * SECTORS_PER_SLOT * SECTOR_SIZE).ok_or(DiskError::NotReady)?,
+            queue: VecDeque::new(),
+            slots: Default::default(),
+            next_id: 1,
+            completed: 0,
+            errors: 0,
+        };
+
+        p.stop()?;
+        let clb = p.cmd_list.phys();
+        let fb = clb + RECEIVED_FIS_OFFSET as u64;
+        p.write(port::CLB, clb as u32);
+        p.write(port::CLBU, (clb >> 32) as u32);
+        p.write(port::FB, fb as u32);
+        p.write(port::FBU, (fb >> 32) as u32);
+        p.write(port::SERR, 0xFFFF_FFFF);
+        p.write(port::IS, 0xFFFF_FFFF);
+        p.write(port::IE, port::IE_DEFAULT);
+        p.start()?;
+
+        p.identify()?;
+        Ok(p)
+    }
+
+    /// IDENTIFY DEVICE : capacité LBA48 et modèle
+    fn identify(&mut self) -> Result<(), DiskError> {
+        let id = self.submit(ata::IDENTIFY, 0, 1, None);
+        let mut words = [0u8; SECTOR_SIZE];
+        wait_on(self, id, Some(&mut words[..]))?;
+
+        let word = |i: usize| u16::from_le_bytes([words[i * 2], words[i * 2 + 1]]);
+        self.sectors = (0..4).fold(0u64, |acc, i| acc | (word(100 + i) as u64) << (16 * i));
+        if self.sectors == 0 {
+            // Pas de LBA48 : capacité LBA28
+            self.sectors = word(60) as u64 | (word(61) as u64) << 16;
+        }
+        // Chaîne ATA : octets permutés deux à deux
+        let model: Vec<u8> = (27..47).flat_map(|i| word(i).to_be_bytes()).collect();
+        self.model = String::from_utf8_lossy(&model).trim().into();
+        Ok(())
+    }
+
+    /// Met une commande en file ; retourne son identifiant
+    fn submit(&mut self, command: u8, lba: u64, count: u16, data: Option<Vec<u8>>) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.queue.push_back(Request { id, command, lba, count, data });
+        self.dispatch();
+        id
+    }
+
+    /// Place les requêtes en attente dans les emplacements libres
+    fn dispatch(&mut self) {
+        while let Some(slot) = self.slots.iter().position(|s| s.is_none()) {
+            let req = match self.queue.pop_front() {
+                Some(req) => req,
+                None => return,
+            };
+            self.issue(slot, req);
+        }
+    }
+
+    fn issue(&mut self, slot: usize, req: Request) {
+        let bytes = req.count as usize * SECTOR_SIZE;
+        let bounce_offset = slot * SECTORS_PER_SLOT * SECTOR_SIZE;
+        let write = req.data.is_some();
+        if let Some(data) = &req.data {
+            self.bounce.as_mut_slice()[bounce_offset..bounce_offset + bytes].copy_from_slice(data);
+        }
+
+        let table = self.tables.phys() + (slot * TABLE_STRIDE) as u64;
+        let prdt_entries = if bytes == 0 { 0 } else { 1 };
+        unsafe {
+            let fis = FisRegH2D::command(req.command, req.lba, req.count);
+            write_volatile(self.tables.as_ptr::<FisRegH2D>(slot * TABLE_STRIDE), fis);
+            if bytes != 0 {
+                let prd = PrdEntry::new(self.bounce.phys() + bounce_offset as u64, bytes);
+                write_volatile(self.tables.as_ptr::<PrdEntry>(slot * TABLE_STRIDE + PRDT_OFFSET), prd);
+            }
+            let header = CommandHeader::new(table, write, prdt_entries);
+            write_volatile(self.cmd_list.as_ptr::<CommandHeader>(slot * core::mem::size_of::<CommandHeader>()), header);
+        }
+
+        self.slots[slot] = Some(Inflight { id: req.id, write, bytes, result: None });
+        self.write(port::CI, 1 << slot);
+    }
+
+    /// Relève les commandes terminées par le HBA
+    fn reap(&mut self) {
+        let status = self.read(port::IS);
+        self.write(port::IS, status);
+
+        if status & port::IS_TFES != 0 || self.read(port::TFD) & port::TFD_ERR != 0 {
+            self.fail_all(DiskError::ReadFailed);
+            return;
+        }
+
+        let issued = self.read(port::CI);
+        for (slot, inflight) in self.slots.iter_mut().enumerate() {
+            if let Some(inflight) = inflight {
+                if inflight.result.is_none() && issued & (1 << slot) == 0 {
+                    inflight.result = Some(Ok(()));
+                    self.completed += 1;
+                }
+            }
+        }
+    }
+
+    /// Erreur de tâche : toutes les commandes en vol échouent, le port repart
+    fn fail_all(&mut self, err: DiskError) {
+        for inflight in self.slots.iter_mut().flatten() {
+            if inflight.result.is_none() {
+                let err = if inflight.write && err == DiskError::ReadFailed { DiskError::WriteFailed } else { err };
+                inflight.result = Some(Err(err));
+                self.errors += 1;
+            }
+        }
+        let _ = self.stop();
+        self.write(port::SERR, 0xFFFF_FFFF);
+        self.write(port::IS, 0xFFFF_FFFF);
+        let _ = self.start();
+    }
+
+    /// Récupère le résultat d'une requête terminée et libère son emplacement
+    fn take(&mut self, id: u64, out: Option<&mut [u8]>) -> Option<Result<(), DiskError>> {
+        let slot = self.slots.iter().position(|s| matches!(s, Some(f) if f.id == id))?;
+        let inflight = self.slots[slot].as_ref()?;
+        let result = inflight.result?;
+        if let (Ok(()), Some(out)) = (result, out) {
+            let offset = slot * SECTORS_PER_SLOT * SECTOR_SIZE;
+            let len = inflight.bytes.min(out.len());
+            out[..len].copy_from_slice(&self.bounce.as_slice()[offset..offset + len]);
+        }
+        self.slots[slot] = None;
+        self.dispatch();
+        Some(result)
+    }
+}
+
+/// Attend la fin d'une requête en scrutant le port
+///
+/// Utilisé quand le contrôleur est déjà verrouillé (initialisation) ;
+/// les disques enregistrés attendent via `wait_request`, sans garder
+/// le verrou, pour laisser l'interruption relever les commandes.
+fn wait_on(p: &mut AhciPort, id: u64, mut out: Option<&mut [u8]>) -> Result<(), DiskError> {
+    for _ in 0..TIMEOUT_SPINS {
+        p.reap();
+        if let Some(result) = p.take(id, out.as_deref_mut()) {
+            return result;
+        }
+        core::hint::spin_loop();
+    }
+    p.fail_all(DiskError::Timeout);
+    p.take(id, None).unwrap_or(Err(DiskError::Timeout))
+}
+
+/// Contrôleur AHCI
+pub struct AhciController {
+    pub function: PciFunction,
+    abar: usize,
+    pub ports: Vec<AhciPort>,
+}
+
+impl AhciController {
+    fn read(&self, reg: usize) -> u32 {
+        unsafe { read_volatile((self.abar + reg) as *const u32) }
+    }
+
+    fn write(&self, reg: usize, value: u32) {
+        unsafe { write_volatile((self.abar + reg) as *mut u32, value) }
+    }
+
+    /// Active le mode AHCI et initialise les ports occupés par un disque
+    pub fn probe(function: PciFunction) -> Result<Self, DriverError> {
+        let abar = function.bar(5).ok_or(DriverError::NotSupported)? as usize;
+        function.enable_bus_master();
+
+        let mut controller = Self { function, abar, ports: Vec::new() };
+        controller.write(hba::GHC, controller.read(hba::GHC) | hba::GHC_AE);
+        if controller.read(hba::CAP) & hba::CAP_S64A == 0 {
+            log::info!("AHCI: adressage 32 bits seulement");
+        }
+
+        let implemented = controller.read(hba::PI);
+        for index in 0..32 {
+            if implemented & (1 << index) == 0 {
+                continue;
+            }
+            let regs = abar + port::BASE + index * port::STRIDE;
+            let ssts = unsafe { read_volatile((regs + port::SSTS) as *const u32) };
+            let sig = unsafe { read_volatile((regs + port::SIG) as *const u32) };
+            // DET = 3 (présent, lien établi), IPM = 1 (actif)
+            if ssts & 0xF != 3 || (ssts >> 8) & 0xF != 1 || sig != SATA_SIG_ATA {
+                continue;
+            }
+            match AhciPort::init(index, regs) {
+                Ok(p) => controller.ports.push(p),
+                Err(e) => log::error!("AHCI: port {} inutilisable: {:?}", index, e),
+            }
+        }
+
+        controller.write(hba::IS, 0xFFFF_FFFF);
+        controller.write(hba::GHC, controller.read(hba::GHC) | hba::GHC_IE);
+        Ok(controller)
+    }
+
+    /// Interruption du HBA : relève les ports signalés
+    pub fn handle_interrupt(&mut self) {
+        let pending = self.read(hba::IS);
+        for p in self.ports.iter_mut() {
+            if pending & (1 << p.index) != 0 {
+                p.reap();
+            }
+        }
+        self.write(hba::IS, pending);
+    }
+}
+
+/// Contrôleur AHCI détecté (None avant `init` ou sans HBA)
+pub static AHCI_CONTROLLER: Mutex<Option<AhciController>> = Mutex::new(None);
+
+/// Exécute `f` sur un port, verrou pris interruptions masquées
+fn with_port<T>(index: usize, f: impl FnOnce(&mut AhciPort) -> T) -> Result<T, DiskError> {
+    without_interrupts(|| {
+        let mut guard = AHCI_CONTROLLER.lock();
+        let controller = guard.as_mut().ok_or(DiskError::NotReady)?;
+        let p = controller.ports.iter_mut().find(|p| p.index == index).ok_or(DiskError::NotReady)?;
+        Ok(f(p))
+    })
+}
+
+/// Attend une requête sans monopoliser le contrôleur
+fn wait_request(index: usize, id: u64, mut out: Option<&mut [u8]>) -> Result<(), DiskError> {
+    for _ in 0..TIMEOUT_SPINS {
+        let done = with_port(index, |p| {
+            // Relève aussi si l'interruption n'est pas routée
+            p.reap();
+            p.take(id, out.as_deref_mut())
+        })?;
+        if let Some(result) = done {
+            return result;
+        }
+        core::hint::spin_loop();
+    }
+    with_port(index, |p| {
+        p.fail_all(DiskError::Timeout);
+        p.take(id, None).unwrap_or(Err(DiskError::Timeout))
+    })?
+}
+
+/// Disque SATA vu par la couche bloc
+pub struct AhciDisk {
+    port: usize,
+    sectors: u64,
+}
+
+impl AhciDisk {
+    /// Commandes d'au plus un tampon de rebond, toutes en file avant d'attendre
+    fn transfer(&mut self, lba: u64, read: Option<&mut [u8]>, write: Option<&[u8]>) -> Result<(), DiskError> {
+        let len = read.as_ref().map(|b| b.len()).or(write.map(|b| b.len())).unwrap_or(0);
+        block::check_request(&*self, lba, len)?;
+        let chunk = SECTORS_PER_SLOT * SECTOR_SIZE;
+
+        let ids = with_port(self.port, |p| {
+            (0..len)
+                .step_by(chunk)
+                .map(|offset| {
+                    let bytes = chunk.min(len - offset);
+                    let lba = lba + (offset / SECTOR_SIZE) as u64;
+                    let count = (bytes / SECTOR_SIZE) as u16;
+                    match write {
+                        Some(data) => p.submit(ata::WRITE_DMA_EXT, lba, count, Some(data[offset..offset + bytes].to_vec())),
+                        None => p.submit(ata::READ_DMA_EXT, lba, count, None),
+                    }
+                })
+                .collect::<Vec<u64>>()
+        })?;
+
+        let mut read = read;
+        let mut result = Ok(());
+        for (i, id) in ids.into_iter().enumerate() {
+            let offset = i * chunk;
+            let out = read.as_deref_mut().map(|b| &mut b[offset..(offset + chunk).min(len)]);
+            if let Err(e) = wait_request(self.port, id, out) {
+                result = Err(e);
+            }
+        }
+        result
+    }
+}
+
+impl BlockDevice for AhciDisk {
+    fn block_size(&self) -> usize {
+        SECTOR_SIZE
+    }
+
+    fn block_count(&self) -> u64 {
+        self.sectors
+    }
+
+    fn read_blocks(&mut self, lba: u64, buf: &mut [u8]) -> Result<(), DiskError> {
+        self.transfer(lba, Some(buf), None)
+    }
+
+    fn write_blocks(&mut self, lba: u64, buf: &[u8]) -> Result<(), DiskError> {
+        self.transfer(lba, None, Some(buf))
+    }
+
+    fn flush(&mut self) -> Result<(), DiskError> {
+        let id = with_port(self.port, |p| p.submit(ata::FLUSH_CACHE_EXT, 0, 0, None))?;
+        wait_request(self.port, id, None)
+    }
+}
+
+/// Détecte le premier HBA AHCI et enregistre ses disques (`sata0`, `sata1`...)
+///
+/// Retourne le nombre de disques trouvés.
+pub fn init() -> Result<usize, DriverError> {
+    let function = pci::find_by_class(0x01, 0x06, Some(0x01))
+        .into_iter()
+        .next()
+        .ok_or(DriverError::NotFound)?;
+    let controller = AhciController::probe(function)?;
+
+    let disks: Vec<(usize, u64)> = controller.ports.iter().map(|p| (p.index, p.sectors)).collect();
+    for p in &controller.ports {
+        log::info!("AHCI: port {} {} ({} secteurs)", p.index, p.model, p.sectors);
+    }
+    without_interrupts(|| *AHCI_CONTROLLER.lock() = Some(controller));
+    // MSI-X ou MSI si la fonction les propose, sinon la ligne INTx
+    match crate::interrupts::request_pci_interrupt(function, handle_interrupt) {
+        Ok(irq) => log::info!("AHCI: IRQ {}", irq),
+        Err(e) => log::warn!("AHCI: pas d'interruption ({:?})", e),
+    }
+
+    for (n, &(port, sectors)) in disks.iter().enumerate() {
+        let name = alloc::format!("sata{}", n);
+        let _ = block::register(&name, Arc::new(Mutex::new(AhciDisk { port, sectors })));
+    }
+    Ok(disks.len())
+}
+
+crate::initcall!(driver, "AHCI", init);
+
+/// Appelé depuis le vecteur d'interruption du HBA
+///
+/// Si un appelant tient déjà le contrôleur, il relève lui-même ses commandes.
+pub fn handle_interrupt() {
+    if let Some(mut guard) = AHCI_CONTROLLER.try_lock() {
+        if let Some(controller) = guard.as_mut() {
+            controller.handle_interrupt();
+        }
+    }
+}
+
+/// Enveloppe pour le gestionnaire de drivers
+pub struct AhciDriver;
+
+impl Driver for AhciDriver {
+    fn name(&self) -> &str {
+        "ahci"
+    }
+
+    fn init(&mut self) -> Result<(), DriverError> {
+        init().map(|_| ())
+    }
+
+    fn handle_interrupt(&mut self, _irq: u8) {
+        handle_interrupt();
+    }
+
+    fn shutdown(&mut self) -> Result<(), DriverError> {
+        without_interrupts(|| {
+            if let Some(controller) = AHCI_CONTROLLER.lock().as_ref() {
+                for p in &controller.ports {
+                    let _ = p.stop();
+                }
+            }
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_fis_read_dma_ext() {
+        let fis = FisRegH2D::command(ata::READ_DMA_EXT, 0x0102_0304_0506, 8);
+        assert_eq!(core::mem::size_of::<FisRegH2D>(), 20);
+        assert_eq!(fis.fis_type, 0x27);
+        assert_eq!(fis.pm_c, 0x80);
+        assert_eq!(fis.device, 1 << 6);
+        assert_eq!([fis.lba0, fis.lba1, fis.lba2, fis.lba3, fis.lba4, fis.lba5], [6, 5, 4, 3, 2, 1]);
+        assert_eq!((fis.count_low, fis.count_high), (8, 0));
+    }
+
+    #[test_case]
+    fn test_command_structures_layout() {
+        assert_eq!(core::mem::size_of::<CommandHeader>(), 32);
+        assert_eq!(core::mem::size_of::<PrdEntry>(), 16);
+
+        let header = CommandHeader::new(0x1_2345_6780, true, 1);
+        assert_eq!(header.flags, 5 | (1 << 6));
+        assert_eq!((header.ctba, header.ctbau), (0x2345_6780, 1));
+
+        let prd = PrdEntry::new(0x8000, 4096);
+        assert_eq!(prd.dbc, 4095 | (1 << 31));
+        // FIS + PRDT tiennent dans le pas d'une table
+        assert!(PRDT_OFFSET + core::mem::size_of::<PrdEntry>() <= TABLE_STRIDE);
+    }
+}
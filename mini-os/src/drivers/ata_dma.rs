@@ -0,0 +1,245 @@
+/// DMA Bus Master IDE (PCI classe 01h, sous-classe 01h)
+///
+/// Le contrôleur IDE annonce le bus mastering par le bit 7 de son
+/// prog-if ; ses registres sont dans le BAR4 (8 ports par canal :
+/// commande, statut, adresse de la table PRD). Un transfert décrit par
+/// la table PRD (une seule entrée ici, sur un tampon de rebond sous
+/// 4 Gio ne franchissant pas de frontière de 64 Kio) est lancé par
+/// READ DMA / WRITE DMA puis le bit Start ; la fin est signalée par
+/// l'IRQ 14 (canal primaire) ou 15 (secondaire) en mode compatible.
+///
+/// L'attente se fait en `hlt` quand l'IRQ est routée, avec scrutation du
+/// statut Bus Master en secours : le CPU n'est plus occupé à recopier
+/// les mots de données comme en PIO.
+
+use core::sync::atomic::{AtomicBool, AtomicU16, AtomicU8, Ordering};
+use x86_64::instructions::port::Port;
+use crate::drivers::disk::DiskError;
+use crate::drivers::dma::DmaBuffer;
+use crate::drivers::pci;
+
+pub const SECTOR_SIZE: usize = 512;
+/// Secteurs par commande DMA (tampon de rebond de 32 Kio)
+pub const MAX_SECTORS: usize = 64;
+/// Délai maximal d'une commande DMA
+const TIMEOUT_NS: u64 = 5_000_000_000;
+
+/// Registres Bus Master (décalages depuis la base du canal)
+const BM_COMMAND: u16 = 0;
+const BM_STATUS: u16 = 2;
+const BM_PRDT: u16 = 4;
+
+/// Commande : démarrage, sens périphérique -> mémoire
+const BM_CMD_START: u8 = 0x01;
+const BM_CMD_READ: u8 = 0x08;
+
+/// Statut : transfert actif, erreur, interruption, disques 0/1 capables
+const BM_STATUS_ACTIVE: u8 = 0x01;
+const BM_STATUS_ERROR: u8 = 0x02;
+const BM_STATUS_IRQ: u8 = 0x04;
+const BM_STATUS_DRIVE0_DMA: u8 = 0x20;
+const BM_STATUS_DRIVE1_DMA: u8 = 0x40;
+
+/// Fin de table PRD
+const PRD_EOT: u16 = 0x8000;
+
+/// Commandes ATA DMA (LBA 28 bits)
+pub const READ_DMA: u8 = 0xC8;
+pub const WRITE_DMA: u8 = 0xCA;
+
+/// Prog-if : canal primaire / secondaire en mode natif PCI, bus master
+const PROG_IF_PRIMARY_NATIVE: u8 = 0x01;
+const PROG_IF_SECONDARY_NATIVE: u8 = 0x04;
+const PROG_IF_BUS_MASTER: u8 = 0x80;
+
+/// Ports de commande ATA et IRQ des canaux en mode compatible
+const LEGACY_STATUS: [u16; 2] = [0x1F7, 0x177];
+const LEGACY_IRQ: [u8; 2] = [14, 15];
+
+/// Base Bus Master de chaque canal (0 : pas de DMA), lue par l'IRQ
+static BM_BASE: [AtomicU16; 2] = [AtomicU16::new(0), AtomicU16::new(0)];
+/// Statut Bus Master relevé par l'IRQ de fin de transfert
+static COMPLETED: [AtomicU8; 2] = [AtomicU8::new(0), AtomicU8::new(0)];
+/// IRQ de fin de transfert enregistrée
+static IRQ_ROUTED: [AtomicBool; 2] = [AtomicBool::new(false), AtomicBool::new(false)];
+
+/// Entrée de la table PRD (Physical Region Descriptor)
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrdEntry {
+    pub addr: u32,
+    /// Octets à transférer (0 = 64 Kio)
+    pub count: u16,
+    pub flags: u16,
+}
+
+impl PrdEntry {
+    /// Dernière (unique) entrée couvrant `bytes` octets depuis `addr`
+    pub fn last(addr: u32, bytes: usize) -> Self {
+        Self { addr, count: (bytes & 0xFFFF) as u16, flags: PRD_EOT }
+    }
+}
+
+/// Canal Bus Master d'un contrôleur IDE
+pub struct BusMaster {
+    channel: usize,
+    base: u16,
+    prdt: DmaBuffer,
+    buffer: DmaBuffer,
+}
+
+impl BusMaster {
+    /// Cherche un contrôleur IDE bus master pour `channel` (0 primaire,
+    /// 1 secondaire) et réserve la table PRD et le tampon de rebond.
+    /// None si le contrôleur n'annonce pas le DMA : le driver reste en PIO.
+    pub fn probe(channel: usize, drive: usize) -> Option<Self> {
+        let function = pci::find_by_class(0x01, 0x01, None)
+            .into_iter()
+            .find(|f| f.prog_if & PROG_IF_BUS_MASTER != 0)?;
+        let native = if channel == 0 { PROG_IF_PRIMARY_NATIVE } else { PROG_IF_SECONDARY_NATIVE };
+        if function.prog_if & native != 0 {
+            // Les ports ATA du driver sont ceux du mode compatible
+            log::info!("ATA DMA: canal {} en mode natif PCI, PIO conservé", channel);
+            return None;
+        }
+        let base = function.io_bar(4)? + 8 * channel as u16;
+
+        let prdt = DmaBuffer::new(core::mem::size_of::<PrdEntry>())?;
+        let buffer = DmaBuffer::new(MAX_SECTORS * SECTOR_SIZE)?;
+        if prdt.phys() + prdt.len() as u64 > u32::MAX as u64 || buffer.phys() + buffer.len() as u64 > u32::MAX as u64 {
+            log::warn!("ATA DMA: tampons au-delà de 4 Gio, PIO conservé");
+            return None;
+        }
+        function.enable_bus_master();
+
+        let mut bm = Self { channel, base, prdt, buffer };
+        // Bit posé par le firmware quand le disque a été configuré en DMA
+        let capable = if drive == 0 { BM_STATUS_DRIVE0_DMA } else { BM_STATUS_DRIVE1_DMA };
+        if bm.read(BM_STATUS) & capable == 0 {
+            log::info!("ATA DMA: disque {} non signalé capable par le firmware", drive);
+        }
+        bm.write(BM_COMMAND, 0);
+        bm.write(BM_STATUS, BM_STATUS_ERROR | BM_STATUS_IRQ);
+
+        BM_BASE[channel].store(base, Ordering::SeqCst);
+        // Plusieurs handles peuvent partager le canal : IRQ demandée une fois
+        if !IRQ_ROUTED[channel].load(Ordering::SeqCst) {
+            let handler: crate::interrupts::IrqHandler = if channel == 0 { primary_interrupt } else { secondary_interrupt };
+            match crate::interrupts::request_irq(LEGACY_IRQ[channel], handler) {
+                Ok(()) => IRQ_ROUTED[channel].store(true, Ordering::SeqCst),
+                Err(e) => log::warn!("ATA DMA: IRQ {} {:?}, scrutation", LEGACY_IRQ[channel], e),
+            }
+        }
+        Some(bm)
+    }
+
+    fn read(&self, reg: u16) -> u8 {
+        unsafe { Port::<u8>::new(self.base + reg).read() }
+    }
+
+    fn write(&mut self, reg: u16, value: u8) {
+        unsafe { Port::<u8>::new(self.base + reg).write(value) }
+    }
+
+    /// Tampon de rebond (données à écrire avant `start`, lues après `finish`)
+    pub fn buffer(&mut self) -> &mut [u8] {
+        &mut self.buffer.as_mut_slice()[..MAX_SECTORS * SECTOR_SIZE]
+    }
+
+    /// Arme le canal pour `sectors` secteurs ; la commande ATA est envoyée
+    /// ensuite par l'appelant, puis `start` lance le transfert
+    pub fn prepare(&mut self, sectors: usize, read: bool) {
+        let bytes = sectors.min(MAX_SECTORS) * SECTOR_SIZE;
+        let entry = PrdEntry::last(self.buffer.phys() as u32, bytes);
+        unsafe { core::ptr::write_volatile(self.prdt.as_ptr::<PrdEntry>(0), entry) };
+        unsafe { Port::<u32>::new(self.base + BM_PRDT).write(self.prdt.phys() as u32) };
+        self.write(BM_COMMAND, if read { BM_CMD_READ } else { 0 });
+        // Bits d'erreur et d'interruption effacés par écriture de 1
+        self.write(BM_STATUS, BM_STATUS_ERROR | BM_STATUS_IRQ);
+        COMPLETED[self.channel].store(0, Ordering::SeqCst);
+    }
+
+    pub fn start(&mut self) {
+        let command = self.read(BM_COMMAND);
+        self.write(BM_COMMAND, command | BM_CMD_START);
+    }
+
+    /// Attend la fin du transfert lancé par `start` et arrête le canal
+    pub fn finish(&mut self) -> Result<(), DiskError> {
+        let deadline = crate::time::now_ns() + TIMEOUT_NS;
+        let status = loop {
+            let status = COMPLETED[self.channel].swap(0, Ordering::SeqCst);
+            if status & BM_STATUS_IRQ != 0 {
+                break status;
+            }
+            // IRQ non routée ou pas encore servie : statut lu directement
+            let status = self.read(BM_STATUS);
+            if status & BM_STATUS_IRQ != 0 && status & BM_STATUS_ACTIVE == 0 {
+                break status;
+            }
+            if status & BM_STATUS_ERROR != 0 || crate::time::now_ns() > deadline {
+                self.stop();
+                return Err(if status & BM_STATUS_ERROR != 0 { DiskError::ReadFailed } else { DiskError::Timeout });
+            }
+            if IRQ_ROUTED[self.channel].load(Ordering::SeqCst) && x86_64::instructions::interrupts::are_enabled() {
+                x86_64::instructions::hlt();
+            } else {
+                core::hint::spin_loop();
+            }
+        };
+        self.stop();
+        // Lecture du statut ATA : acquitte l'interruption du disque
+        let ata = unsafe { Port::<u8>::new(LEGACY_STATUS[self.channel]).read() };
+        if status & BM_STATUS_ERROR != 0 || ata & 0x21 != 0 {
+            return Err(DiskError::ReadFailed);
+        }
+        Ok(())
+    }
+
+    fn stop(&mut self) {
+        let command = self.read(BM_COMMAND);
+        self.write(BM_COMMAND, command & !BM_CMD_START);
+        self.write(BM_STATUS, BM_STATUS_ERROR | BM_STATUS_IRQ);
+    }
+}
+
+/// Relève le statut Bus Master d'un canal et acquitte le disque
+fn channel_interrupt(channel: usize) {
+    let base = BM_BASE[channel].load(Ordering::SeqCst);
+    if base == 0 {
+        return;
+    }
+    let status = unsafe { Port::<u8>::new(base + BM_STATUS).read() };
+    if status & BM_STATUS_IRQ == 0 {
+        return;
+    }
+    COMPLETED[channel].store(status, Ordering::SeqCst);
+    unsafe {
+        Port::<u8>::new(LEGACY_STATUS[channel]).read();
+        Port::<u8>::new(base + BM_STATUS).write(BM_STATUS_IRQ);
+    }
+}
+
+/// IRQ 14
+fn primary_interrupt() {
+    channel_interrupt(0);
+}
+
+/// IRQ 15
+fn secondary_interrupt() {
+    channel_interrupt(1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_prd_entry_layout() {
+        assert_eq!(core::mem::size_of::<PrdEntry>(), 8);
+        let entry = PrdEntry::last(0x10_0000, MAX_SECTORS * SECTOR_SIZE);
+        assert_eq!((entry.addr, entry.count, entry.flags), (0x10_0000, 0x8000, PRD_EOT));
+        // 64 Kio s'encodent par un compte nul
+        assert_eq!(PrdEntry::last(0, 0x1_0000).count, 0);
+    }
+}
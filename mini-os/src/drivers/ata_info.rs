@@ -0,0 +1,227 @@
+/// Identification des disques ATA et attributs S.M.A.R.T.
+///
+/// `parse_identify` décode les 256 mots d'IDENTIFY DEVICE (modèle, numéro
+/// de série et firmware en ASCII aux octets permutés, capacité LBA 28/48
+/// bits, fonctionnalités) ; `parse_smart` décode la page SMART READ DATA
+/// (30 attributs de 12 octets à partir de l'octet 2).
+///
+/// Les drivers enregistrent ce qu'ils ont lu sous le nom du disque ;
+/// /proc/block/<dev>/info, `lsblk`, `hdinfo` et le gestionnaire de
+/// périphériques le relisent ici.
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use lazy_static::lazy_static;
+use spin::Mutex;
+
+/// Identité d'un disque (IDENTIFY DEVICE)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiskIdentity {
+    pub model: String,
+    pub serial: String,
+    pub firmware: String,
+    /// Secteurs adressables (LBA 48 bits si supporté)
+    pub sectors: u64,
+    pub sector_size: u32,
+    pub lba48: bool,
+    pub dma: bool,
+    pub smart_supported: bool,
+    pub smart_enabled: bool,
+    pub write_cache: bool,
+    pub trim: bool,
+}
+
+/// Attribut S.M.A.R.T.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SmartAttribute {
+    pub id: u8,
+    pub flags: u16,
+    /// Valeur normalisée courante et pire valeur observée
+    pub current: u8,
+    pub worst: u8,
+    /// Valeur brute (48 bits)
+    pub raw: u64,
+}
+
+impl SmartAttribute {
+    /// Nom usuel des attributs courants
+    pub fn name(&self) -> &'static str {
+        match self.id {
+            1 => "Raw_Read_Error_Rate",
+            3 => "Spin_Up_Time",
+            4 => "Start_Stop_Count",
+            5 => "Reallocated_Sector_Ct",
+            7 => "Seek_Error_Rate",
+            9 => "Power_On_Hours",
+            10 => "Spin_Retry_Count",
+            12 => "Power_Cycle_Count",
+            187 => "Reported_Uncorrect",
+            190 => "Airflow_Temperature",
+            194 => "Temperature_Celsius",
+            196 => "Reallocated_Event_Count",
+            197 => "Current_Pending_Sector",
+            198 => "Offline_Uncorrectable",
+            199 => "UDMA_CRC_Error_Count",
+            _ => "Unknown_Attribute",
+        }
+    }
+}
+
+/// Informations connues d'un disque
+#[derive(Debug, Clone, Default)]
+pub struct DiskInfo {
+    pub identity: DiskIdentity,
+    pub smart: Vec<SmartAttribute>,
+}
+
+/// Chaîne ATA : deux caractères par mot, octet de poids fort en premier
+fn ata_string(words: &[u16]) -> String {
+    let mut out = String::new();
+    for word in words {
+        for byte in [(word >> 8) as u8, *word as u8] {
+            if byte.is_ascii_graphic() || byte == b' ' {
+                out.push(byte as char);
+            }
+        }
+    }
+    String::from(out.trim())
+}
+
+/// Décode les données d'IDENTIFY DEVICE
+pub fn parse_identify(words: &[u16; 256]) -> DiskIdentity {
+    let lba48 = words[83] & (1 << 10) != 0;
+    let sectors = if lba48 {
+        words[100] as u64 | (words[101] as u64) << 16 | (words[102] as u64) << 32 | (words[103] as u64) << 48
+    } else {
+        words[60] as u64 | (words[61] as u64) << 16
+    };
+    // Mot 106 valide (bit 14 seul posé) et secteurs logiques > 256 mots
+    let sector_size = if words[106] & 0xC000 == 0x4000 && words[106] & (1 << 12) != 0 {
+        (words[117] as u32 | (words[118] as u32) << 16) * 2
+    } else {
+        512
+    };
+    DiskIdentity {
+        model: ata_string(&words[27..47]),
+        serial: ata_string(&words[10..20]),
+        firmware: ata_string(&words[23..27]),
+        sectors,
+        sector_size,
+        lba48,
+        dma: words[49] & (1 << 8) != 0,
+        smart_supported: words[82] & 1 != 0,
+        smart_enabled: words[85] & 1 != 0,
+        write_cache: words[85] & (1 << 5) != 0,
+        trim: words[169] & 1 != 0,
+    }
+}
+
+/// Décode la page SMART READ DATA (entrées d'identifiant nul ignorées)
+pub fn parse_smart(data: &[u8; 512]) -> Vec<SmartAttribute> {
+    data[2..2 + 30 * 12]
+        .chunks_exact(12)
+        .filter(|e| e[0] != 0)
+        .map(|e| SmartAttribute {
+            id: e[0],
+            flags: u16::from_le_bytes([e[1], e[2]]),
+            current: e[3],
+            worst: e[4],
+            raw: e[5..11].iter().rev().fold(0u64, |acc, &b| (acc << 8) | b as u64),
+        })
+        .collect()
+}
+
+lazy_static! {
+    static ref DISK_INFO: Mutex<BTreeMap<String, DiskInfo>> = Mutex::new(BTreeMap::new());
+}
+
+/// Enregistre (ou remplace) les informations d'un disque
+pub fn register(name: &str, info: DiskInfo) {
+    DISK_INFO.lock().insert(name.into(), info);
+}
+
+pub fn get(name: &str) -> Option<DiskInfo> {
+    DISK_INFO.lock().get(name).cloned()
+}
+
+/// Noms des disques identifiés, triés
+pub fn list() -> Vec<String> {
+    DISK_INFO.lock().keys().cloned().collect()
+}
+
+/// Texte de /proc/block/<dev>/info (et de `hdinfo`)
+pub fn format_info(name: &str) -> Option<String> {
+    let info = get(name)?;
+    let id = &info.identity;
+    let yes_no = |b: bool| if b { "yes" } else { "no" };
+    let mut out = String::new();
+    let _ = writeln!(out, "Device:       {}", name);
+    let _ = writeln!(out, "Model:        {}", id.model);
+    let _ = writeln!(out, "Serial:       {}", id.serial);
+    let _ = writeln!(out, "Firmware:     {}", id.firmware);
+    let _ = writeln!(out, "Sectors:      {}", id.sectors);
+    let _ = writeln!(out, "SectorSize:   {}", id.sector_size);
+    let _ = writeln!(out, "Capacity:     {} MiB", id.sectors * id.sector_size as u64 / (1024 * 1024));
+    let _ = writeln!(out, "LBA48:        {}", yes_no(id.lba48));
+    let _ = writeln!(out, "DMA:          {}", yes_no(id.dma));
+    let _ = writeln!(out, "WriteCache:   {}", yes_no(id.write_cache));
+    let _ = writeln!(out, "TRIM:         {}", yes_no(id.trim));
+    let smart = match (id.smart_supported, id.smart_enabled) {
+        (false, _) => "unsupported",
+        (true, false) => "disabled",
+        (true, true) => "enabled",
+    };
+    let _ = writeln!(out, "SMART:        {}", smart);
+    if !info.smart.is_empty() {
+        let _ = writeln!(out, "ID# {:<24} {:>5} {:>5} {:>12}", "ATTRIBUTE_NAME", "VALUE", "WORST", "RAW_VALUE");
+        for attr in &info.smart {
+            let _ = writeln!(out, "{:>3} {:<24} {:>5} {:>5} {:>12}", attr.id, attr.name(), attr.current, attr.worst, attr.raw);
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn put_string(words: &mut [u16], text: &str) {
+        for (word, pair) in words.iter_mut().zip(text.as_bytes().chunks(2)) {
+            *word = (pair[0] as u16) << 8 | *pair.get(1).unwrap_or(&b' ') as u16;
+        }
+    }
+
+    #[test_case]
+    fn test_parse_identify() {
+        let mut words = [0u16; 256];
+        put_string(&mut words[27..47], "QEMU HARDDISK");
+        put_string(&mut words[10..20], "QM00001");
+        words[49] = 1 << 8;
+        words[60] = 0x0000;
+        words[61] = 0x0004;
+        words[82] = 1;
+        words[85] = 1 | (1 << 5);
+        let id = parse_identify(&words);
+        assert_eq!((id.model.as_str(), id.serial.as_str()), ("QEMU HARDDISK", "QM00001"));
+        assert_eq!((id.sectors, id.sector_size), (0x40000, 512));
+        assert!(id.dma && id.smart_supported && id.smart_enabled && id.write_cache && !id.lba48);
+
+        words[83] = 1 << 10;
+        words[100] = 0x1234;
+        words[102] = 1;
+        assert_eq!(parse_identify(&words).sectors, 0x1_0000_1234);
+    }
+
+    #[test_case]
+    fn test_parse_smart() {
+        let mut data = [0u8; 512];
+        data[2..14].copy_from_slice(&[9, 0x32, 0, 98, 97, 0x10, 0x27, 0, 0, 0, 0, 0]);
+        data[14..26].copy_from_slice(&[194, 0x22, 0, 64, 50, 36, 0, 0, 0, 0, 0, 0]);
+        let attrs = parse_smart(&data);
+        assert_eq!(attrs.len(), 2);
+        assert_eq!((attrs[0].name(), attrs[0].current, attrs[0].worst, attrs[0].raw), ("Power_On_Hours", 98, 97, 10000));
+        assert_eq!((attrs[1].id, attrs[1].raw), (194, 36));
+    }
+}
@@ -0,0 +1,230 @@
+/// Couche bloc : interface commune des disques et registre global
+///
+/// Les drivers de stockage (AHCI, NVMe...) enregistrent ici leurs
+/// disques sous un nom. Un `BlockHandle` implémente `Disk` et peut donc
+/// être passé tel quel aux systèmes de fichiers ; `CachedStorage` lit
+/// et écrit le disque racine à travers ce registre. Toutes les E/S passent
+/// par la file de requêtes du disque (`elevator`).
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use spin::Mutex;
+use lazy_static::lazy_static;
+use super::disk::{Disk, DiskError};
+use super::elevator::{BlockQueue, IoStats};
+
+/// Périphérique adressé par blocs (secteurs)
+pub trait BlockDevice: Send {
+    /// Taille d'un bloc en octets
+    fn block_size(&self) -> usize;
+    /// Nombre de blocs du périphérique
+    fn block_count(&self) -> u64;
+    /// Lit `buf.len() / block_size` blocs à partir de `lba`
+    fn read_blocks(&mut self, lba: u64, buf: &mut [u8]) -> Result<(), DiskError>;
+    /// Écrit `buf.len() / block_size` blocs à partir de `lba`
+    fn write_blocks(&mut self, lba: u64, buf: &[u8]) -> Result<(), DiskError>;
+    /// Vide le cache d'écriture du périphérique
+    fn flush(&mut self) -> Result<(), DiskError> {
+        Ok(())
+    }
+}
+
+/// Vérifie qu'un transfert tient dans le périphérique
+pub fn check_request(dev: &dyn BlockDevice, lba: u64, len: usize) -> Result<u64, DiskError> {
+    let block_size = dev.block_size();
+    if len == 0 || len % block_size != 0 {
+        return Err(DiskError::InvalidSize);
+    }
+    let count = (len / block_size) as u64;
+    match lba.checked_add(count) {
+        Some(end) if end <= dev.block_count() => Ok(count),
+        _ => Err(DiskError::InvalidSector),
+    }
+}
+
+/// Référence partagée sur un périphérique bloc enregistré
+#[derive(Clone)]
+pub struct BlockHandle {
+    pub name: String,
+    queue: Arc<BlockQueue>,
+}
+
+impl BlockHandle {
+    /// Référence sur un périphérique hors registre (image en mémoire...)
+    pub fn new(name: &str, device: Arc<Mutex<dyn BlockDevice>>) -> Self {
+        Self { name: name.into(), queue: Arc::new(BlockQueue::new(device)) }
+    }
+
+    pub fn block_size(&self) -> usize {
+        self.queue.block_size()
+    }
+
+    pub fn block_count(&self) -> u64 {
+        self.queue.block_count()
+    }
+
+    pub fn read_blocks(&self, lba: u64, buf: &mut [u8]) -> Result<(), DiskError> {
+        self.queue.read_blocks(lba, buf)
+    }
+
+    pub fn write_blocks(&self, lba: u64, buf: &[u8]) -> Result<(), DiskError> {
+        self.queue.write_blocks(lba, buf)
+    }
+
+    pub fn flush(&self) -> Result<(), DiskError> {
+        self.queue.flush()
+    }
+
+    /// Retient les écritures pour les fusionner (écritures en masse)
+    pub fn plug(&self) {
+        self.queue.plug()
+    }
+
+    /// Fin d'une écriture en masse : transmet la file au dernier bouchon
+    pub fn unplug(&self) -> Result<(), DiskError> {
+        self.queue.unplug()
+    }
+
+    pub fn stats(&self) -> IoStats {
+        self.queue.stats()
+    }
+}
+
+// Façade `Disk` pour les systèmes de fichiers
+impl Disk for BlockHandle {
+    fn read(&self, sector: u64, buffer: &mut [u8]) -> Result<(), DiskError> {
+        self.read_blocks(sector, buffer)
+    }
+
+    fn write(&mut self, sector: u64, buffer: &[u8]) -> Result<(), DiskError> {
+        self.write_blocks(sector, buffer)
+    }
+}
+
+/// Registre des périphériques bloc
+pub struct BlockRegistry {
+    devices: BTreeMap<String, Arc<BlockQueue>>,
+    /// Disque portant la racine (par défaut le premier enregistré)
+    root: Option<String>,
+}
+
+impl BlockRegistry {
+    pub const fn new() -> Self {
+        Self { devices: BTreeMap::new(), root: None }
+    }
+
+    /// Enregistre un périphérique sous `name`
+    pub fn register(&mut self, name: &str, device: Arc<Mutex<dyn BlockDevice>>) -> Result<(), DiskError> {
+        if self.devices.contains_key(name) {
+            return Err(DiskError::NotReady);
+        }
+        self.devices.insert(name.into(), Arc::new(BlockQueue::new(device)));
+        if self.root.is_none() {
+            self.root = Some(name.into());
+        }
+        Ok(())
+    }
+
+    /// Retire un périphérique (la racine n'est plus désignée si c'était elle)
+    pub fn unregister(&mut self, name: &str) {
+        self.devices.remove(name);
+        if self.root.as_deref() == Some(name) {
+            self.root = self.devices.keys().next().cloned();
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<BlockHandle> {
+        self.devices.get(name).map(|queue| BlockHandle { name: name.into(), queue: queue.clone() })
+    }
+
+    /// Désigne le disque racine
+    pub fn set_root(&mut self, name: &str) -> Result<(), DiskError> {
+        if !self.devices.contains_key(name) {
+            return Err(DiskError::NotReady);
+        }
+        self.root = Some(name.into());
+        Ok(())
+    }
+
+    pub fn root(&self) -> Option<BlockHandle> {
+        self.root.as_deref().and_then(|name| self.get(name))
+    }
+
+    pub fn list(&self) -> Vec<String> {
+        self.devices.keys().cloned().collect()
+    }
+}
+
+lazy_static! {
+    pub static ref BLOCK_DEVICES: Mutex<BlockRegistry> = Mutex::new(BlockRegistry::new());
+}
+
+/// Enregistre un périphérique dans le registre global
+pub fn register(name: &str, device: Arc<Mutex<dyn BlockDevice>>) -> Result<(), DiskError> {
+    BLOCK_DEVICES.lock().register(name, device)
+}
+
+/// Périphérique bloc par nom
+pub fn get(name: &str) -> Option<BlockHandle> {
+    BLOCK_DEVICES.lock().get(name)
+}
+
+/// Disque racine, s'il y en a un
+pub fn root() -> Option<BlockHandle> {
+    BLOCK_DEVICES.lock().root()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    /// Disque en mémoire de quelques secteurs
+    struct MemDisk {
+        data: Vec<u8>,
+    }
+
+    impl BlockDevice for MemDisk {
+        fn block_size(&self) -> usize {
+            512
+        }
+
+        fn block_count(&self) -> u64 {
+            (self.data.len() / 512) as u64
+        }
+
+        fn read_blocks(&mut self, lba: u64, buf: &mut [u8]) -> Result<(), DiskError> {
+            check_request(&*self, lba, buf.len())?;
+            let start = lba as usize * 512;
+            buf.copy_from_slice(&self.data[start..start + buf.len()]);
+            Ok(())
+        }
+
+        fn write_blocks(&mut self, lba: u64, buf: &[u8]) -> Result<(), DiskError> {
+            check_request(&*self, lba, buf.len())?;
+            let start = lba as usize * 512;
+            self.data[start..start + buf.len()].copy_from_slice(buf);
+            Ok(())
+        }
+    }
+
+    #[test_case]
+    fn test_registry_and_disk_facade() {
+        let mut registry = BlockRegistry::new();
+        registry.register("mem0", Arc::new(Mutex::new(MemDisk { data: vec![0; 4 * 512] }))).unwrap();
+        assert!(registry.register("mem0", Arc::new(Mutex::new(MemDisk { data: vec![] }))).is_err());
+
+        let mut disk = registry.root().unwrap();
+        assert_eq!(disk.name, "mem0");
+        assert_eq!(disk.block_count(), 4);
+        disk.write(2, &[0xAA; 1024]).unwrap();
+        let mut buf = [0u8; 512];
+        disk.read(3, &mut buf).unwrap();
+        assert_eq!(buf[511], 0xAA);
+
+        assert!(disk.read(4, &mut buf).is_err());
+        assert!(disk.read(0, &mut buf[..100]).is_err());
+    }
+}
@@ -6,9 +6,11 @@ use alloc::vec::Vec;
 use crate::vga_buffer::WRITER;
 use x86_64::instructions::port::{Port, PortReadOnly, PortWriteOnly};
 use spin::Mutex;
+use super::ata_dma::{self, BusMaster};
+use super::ata_info::{self, DiskIdentity, DiskInfo, SmartAttribute};
 
 /// Erreurs spécifiques au driver disque
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DiskError {
     InvalidSector,
     BufferTooSmall,
@@ -37,6 +39,15 @@ pub mod ata_commands {
     pub const READ_SECTORS: u8 = 0x20;
     pub const WRITE_SECTORS: u8 = 0x30;
     pub const IDENTIFY: u8 = 0xEC;
+    pub const SMART: u8 = 0xB0;
+}
+
+/// Sous-commandes SMART (registre features) et signature LBA requise
+pub mod smart_features {
+    pub const READ_DATA: u8 = 0xD0;
+    pub const ENABLE: u8 = 0xD8;
+    pub const LBA_MID: u8 = 0x4F;
+    pub const LBA_HIGH: u8 = 0xC2;
 }
 
 /// Bits de statut ATA
@@ -54,6 +65,7 @@ pub mod ata_status {
 struct AtaPorts {
     data: Port<u16>,
     error: PortReadOnly<u8>,
+    features: PortWriteOnly<u8>,
     sector_count: Port<u8>,
     lba_low: Port<u8>,
     lba_mid: Port<u8>,
@@ -69,6 +81,7 @@ impl AtaPorts {
         Self {
             data: Port::new(base),
             error: PortReadOnly::new(base + 1),
+            features: PortWriteOnly::new(base + 1),
             sector_count: Port::new(base + 2),
             lba_low: Port::new(base + 3),
             lba_mid: Port::new(base + 4),
@@ -90,6 +103,8 @@ pub struct DiskDriver {
     
     // Ports wrapped in Mutex for interior mutability
     ports: Mutex<AtaPorts>,
+    /// Canal Bus Master (None : transferts en PIO)
+    dma: Mutex<Option<BusMaster>>,
 }
 
 pub trait Disk {
@@ -107,6 +122,7 @@ impl DiskDriver {
             initialized: false,
             primary_master,
             ports: Mutex::new(AtaPorts::new(ata_ports::PRIMARY_DATA)),
+            dma: Mutex::new(None),
         }
     }
     
@@ -198,6 +214,177 @@ impl DiskDriver {
         Ok(())
     }
 
+    /// Lit les 256 mots d'IDENTIFY DEVICE
+    fn identify_words(&self) -> Option<[u16; 256]> {
+        let mut ports = self.ports.lock();
+        unsafe {
+            ports.device.write(if self.primary_master { 0xA0 } else { 0xB0 });
+            ports.sector_count.write(0);
+            ports.lba_low.write(0);
+            ports.lba_mid.write(0);
+            ports.lba_high.write(0);
+            ports.command.write(ata_commands::IDENTIFY);
+        }
+        if unsafe { ports.status.read() } == 0 {
+            return None;
+        }
+        Self::wait_ready(&mut ports).ok()?;
+        Self::wait_drq(&mut ports).ok()?;
+        let mut words = [0u16; 256];
+        for word in words.iter_mut() {
+            *word = unsafe { ports.data.read() };
+        }
+        Some(words)
+    }
+
+    /// Identité du disque (modèle, série, capacité, fonctionnalités)
+    pub fn identity(&self) -> Option<DiskIdentity> {
+        self.identify_words().map(|words| ata_info::parse_identify(&words))
+    }
+
+    /// Envoie une sous-commande SMART ; `data` reçoit le secteur renvoyé
+    fn smart_command(&self, feature: u8, data: Option<&mut [u8; 512]>) -> Result<(), DiskError> {
+        let mut ports = self.ports.lock();
+        Self::wait_ready(&mut ports)?;
+        unsafe {
+            ports.device.write(if self.primary_master { 0xA0 } else { 0xB0 });
+            ports.features.write(feature);
+            ports.sector_count.write(1);
+            ports.lba_low.write(1);
+            ports.lba_mid.write(smart_features::LBA_MID);
+            ports.lba_high.write(smart_features::LBA_HIGH);
+            ports.command.write(ata_commands::SMART);
+        }
+        let Some(data) = data else {
+            Self::wait_ready(&mut ports)?;
+            return if unsafe { ports.status.read() } & ata_status::ERROR != 0 { Err(DiskError::ReadFailed) } else { Ok(()) };
+        };
+        Self::wait_ready(&mut ports)?;
+        Self::wait_drq(&mut ports)?;
+        for i in 0..256 {
+            let word = unsafe { ports.data.read() };
+            data[i * 2] = word as u8;
+            data[i * 2 + 1] = (word >> 8) as u8;
+        }
+        Ok(())
+    }
+
+    /// Attributs S.M.A.R.T. (SMART activé au besoin)
+    pub fn smart_attributes(&self, identity: &DiskIdentity) -> Result<Vec<SmartAttribute>, DiskError> {
+        if !identity.smart_supported {
+            return Err(DiskError::NotReady);
+        }
+        if !identity.smart_enabled {
+            self.smart_command(smart_features::ENABLE, None)?;
+        }
+        let mut data = [0u8; 512];
+        self.smart_command(smart_features::READ_DATA, Some(&mut data))?;
+        Ok(ata_info::parse_smart(&data))
+    }
+
+    /// Lit l'identité et les attributs SMART et les publie sous le nom du
+    /// disque ; adopte la capacité annoncée par le disque
+    pub fn publish_info(&mut self) -> Option<DiskIdentity> {
+        let identity = self.identity()?;
+        if identity.sectors != 0 {
+            self.sectors = identity.sectors;
+        }
+        let smart = self.smart_attributes(&identity).unwrap_or_default();
+        ata_info::register(&self.name, DiskInfo { identity: identity.clone(), smart });
+        Some(identity)
+    }
+
+    /// Passe en DMA Bus Master si le disque (IDENTIFY mot 49, bit 8) et
+    /// le contrôleur le permettent ; sinon les transferts restent en PIO
+    pub fn enable_dma(&mut self) -> bool {
+        let capable = self.identity().map_or(false, |identity| identity.dma);
+        if !capable {
+            log::info!("{}: DMA non supporté par le disque, mode PIO", self.name);
+            return false;
+        }
+        let bm = BusMaster::probe(0, if self.primary_master { 0 } else { 1 });
+        let enabled = bm.is_some();
+        *self.dma.lock() = bm;
+        log::info!("{}: mode {}", self.name, if enabled { "DMA Bus Master" } else { "PIO" });
+        enabled
+    }
+
+    /// Transferts en DMA actifs
+    pub fn dma_enabled(&self) -> bool {
+        self.dma.lock().is_some()
+    }
+
+    /// Un transfert DMA d'au plus `ata_dma::MAX_SECTORS` secteurs
+    fn dma_transfer(&self, bm: &mut BusMaster, lba: u64, count: usize, read: bool) -> Result<(), DiskError> {
+        let mut ports = self.ports.lock();
+        Self::wait_ready(&mut ports)?;
+        bm.prepare(count, read);
+        unsafe {
+            let drive_select = if self.primary_master { 0xE0 } else { 0xF0 };
+            ports.device.write(drive_select | ((lba >> 24) & 0x0F) as u8);
+            ports.sector_count.write(count as u8);
+            ports.lba_low.write(lba as u8);
+            ports.lba_mid.write((lba >> 8) as u8);
+            ports.lba_high.write((lba >> 16) as u8);
+            ports.command.write(if read { ata_dma::READ_DMA } else { ata_dma::WRITE_DMA });
+        }
+        bm.start();
+        bm.finish()
+    }
+
+    /// Lit `buffer.len() / 512` secteurs : DMA par lots, PIO en secours.
+    /// Une erreur DMA désactive le DMA et le transfert reprend en PIO.
+    pub fn read_sectors(&self, lba: u64, buffer: &mut [u8]) -> Result<(), DiskError> {
+        let sector_size = self.sector_size as usize;
+        let chunk = ata_dma::MAX_SECTORS * sector_size;
+        let mut dma = self.dma.lock();
+        for (i, part) in buffer.chunks_mut(chunk).enumerate() {
+            let start = lba + (i * ata_dma::MAX_SECTORS) as u64;
+            let count = part.len() / sector_size;
+            if let Some(bm) = dma.as_mut() {
+                match self.dma_transfer(bm, start, count, true) {
+                    Ok(()) => {
+                        part[..count * sector_size].copy_from_slice(&bm.buffer()[..count * sector_size]);
+                        continue;
+                    }
+                    Err(e) => {
+                        log::warn!("{}: lecture DMA LBA {} {:?}, retour au PIO", self.name, start, e);
+                        *dma = None;
+                    }
+                }
+            }
+            for (j, sector) in part.chunks_exact_mut(sector_size).enumerate() {
+                self.read_sector(start + j as u64, sector)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Écrit `data.len() / 512` secteurs : DMA par lots, PIO en secours
+    pub fn write_sectors(&self, lba: u64, data: &[u8]) -> Result<(), DiskError> {
+        let sector_size = self.sector_size as usize;
+        let chunk = ata_dma::MAX_SECTORS * sector_size;
+        let mut dma = self.dma.lock();
+        for (i, part) in data.chunks(chunk).enumerate() {
+            let start = lba + (i * ata_dma::MAX_SECTORS) as u64;
+            let count = part.len() / sector_size;
+            if let Some(bm) = dma.as_mut() {
+                bm.buffer()[..count * sector_size].copy_from_slice(&part[..count * sector_size]);
+                match self.dma_transfer(bm, start, count, false) {
+                    Ok(()) => continue,
+                    Err(e) => {
+                        log::warn!("{}: écriture DMA LBA {} {:?}, retour au PIO", self.name, start, e);
+                        *dma = None;
+                    }
+                }
+            }
+            for (j, sector) in part.chunks_exact(sector_size).enumerate() {
+                self.write_sector(start + j as u64, sector)?;
+            }
+        }
+        Ok(())
+    }
+
     /// Identifie le disque
     pub fn identify(&mut self) -> Result<(), DiskError> {
         let mut ports = self.ports.lock();
@@ -248,6 +435,8 @@ impl Driver for DiskDriver {
     fn init(&mut self) -> Result<(), DriverError> {
         self.initialized = true;
         self.sectors = 204800; // 100MB
+        self.publish_info();
+        self.enable_dma();
         Ok(())
     }
 
@@ -262,11 +451,16 @@ impl Driver for DiskDriver {
 // Implémentation du trait Disk pour l'abstraction FS
 impl Disk for DiskDriver {
     fn read(&self, sector: u64, buffer: &mut [u8]) -> Result<(), DiskError> {
-        self.read_sector(sector, buffer)
+        if buffer.len() < self.sector_size as usize {
+            return Err(DiskError::BufferTooSmall);
+        }
+        self.read_sectors(sector, buffer)
     }
     
     fn write(&mut self, sector: u64, buffer: &[u8]) -> Result<(), DiskError> {
-        // Now calling write_sector which takes &self
-        self.write_sector(sector, buffer)
+        if buffer.len() < self.sector_size as usize {
+            return Err(DiskError::InvalidSize);
+        }
+        self.write_sectors(sector, buffer)
     }
 }
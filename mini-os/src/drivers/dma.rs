@@ -0,0 +1,55 @@
+/// Tampons DMA physiquement contigus
+///
+/// Alloués directement dans l'allocateur buddy : la mémoire physique
+/// étant projetée à l'identique, l'adresse physique donnée au
+/// périphérique est aussi l'adresse utilisable par le noyau.
+
+use x86_64::PhysAddr;
+use crate::memory::frame::FRAME_ALLOCATOR;
+
+const FRAME_SIZE: usize = 4096;
+
+/// Zone contiguë, mise à zéro, rendue à l'allocateur au drop
+pub struct DmaBuffer {
+    phys: u64,
+    order: usize,
+}
+
+impl DmaBuffer {
+    /// Alloue au moins `size` octets (arrondi à une puissance de deux de pages)
+    pub fn new(size: usize) -> Option<Self> {
+        let frames = (size.max(1) + FRAME_SIZE - 1) / FRAME_SIZE;
+        let order = frames.next_power_of_two().trailing_zeros() as usize;
+        let phys = FRAME_ALLOCATOR.lock().allocate(order)?.as_u64();
+        unsafe { core::ptr::write_bytes(phys as *mut u8, 0, FRAME_SIZE << order) };
+        Some(Self { phys, order })
+    }
+
+    /// Adresse physique à programmer dans le périphérique
+    pub fn phys(&self) -> u64 {
+        self.phys
+    }
+
+    pub fn len(&self) -> usize {
+        FRAME_SIZE << self.order
+    }
+
+    pub fn as_ptr<T>(&self, offset: usize) -> *mut T {
+        debug_assert!(offset < self.len());
+        (self.phys as usize + offset) as *mut T
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        unsafe { core::slice::from_raw_parts(self.phys as *const u8, self.len()) }
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        unsafe { core::slice::from_raw_parts_mut(self.phys as *mut u8, self.len()) }
+    }
+}
+
+impl Drop for DmaBuffer {
+    fn drop(&mut self) {
+        FRAME_ALLOCATOR.lock().free(PhysAddr::new(self.phys), self.order);
+    }
+}
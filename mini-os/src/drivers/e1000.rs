@@ -0,0 +1,435 @@
+/// Driver Intel e1000 / e1000e (82540EM, 82545EM, 82574L)
+///
+/// Les registres sont dans la BAR 0 (MMIO). Réception et émission
+/// utilisent chacune un anneau de descripteurs legacy de 16 octets ;
+/// chaque descripteur pointe vers un tampon de 2 Kio pris dans une
+/// zone DMA commune. L'interruption (RXT0) confie la remontée des
+/// trames reçues à un kworker via `interface::schedule_rx`.
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ptr::{read_volatile, write_volatile};
+use core::sync::atomic::{fence, AtomicU64, Ordering};
+use crate::drivers::dma::DmaBuffer;
+use crate::drivers::pci::{self, PciFunction};
+use crate::net::ethernet::MacAddress;
+use crate::net::interface::{self, NetDevice, NetDeviceError};
+use super::{Driver, DriverError};
+
+/// Identifiants PCI pris en charge (vendeur Intel)
+pub const INTEL_VENDOR: u16 = 0x8086;
+pub const SUPPORTED_DEVICES: [u16; 4] = [
+    0x100E, // 82540EM (QEMU `-device e1000`)
+    0x100F, // 82545EM
+    0x10D3, // 82574L (QEMU `-device e1000e`)
+    0x153A, // I217-LM
+];
+
+/// Registres (offsets dans la BAR 0)
+mod reg {
+    pub const CTRL: usize = 0x0000;
+    pub const STATUS: usize = 0x0008;
+    pub const EERD: usize = 0x0014;
+    pub const ICR: usize = 0x00C0;
+    pub const IMS: usize = 0x00D0;
+    pub const IMC: usize = 0x00D8;
+    pub const RCTL: usize = 0x0100;
+    pub const TCTL: usize = 0x0400;
+    pub const TIPG: usize = 0x0410;
+    pub const RDBAL: usize = 0x2800;
+    pub const RDBAH: usize = 0x2804;
+    pub const RDLEN: usize = 0x2808;
+    pub const RDH: usize = 0x2810;
+    pub const RDT: usize = 0x2818;
+    pub const TDBAL: usize = 0x3800;
+    pub const TDBAH: usize = 0x3804;
+    pub const TDLEN: usize = 0x3808;
+    pub const TDH: usize = 0x3810;
+    pub const TDT: usize = 0x3818;
+    /// Table multicast (128 mots)
+    pub const MTA: usize = 0x5200;
+    pub const RAL0: usize = 0x5400;
+    pub const RAH0: usize = 0x5404;
+}
+
+const CTRL_SLU: u32 = 1 << 6;
+const CTRL_RST: u32 = 1 << 26;
+const STATUS_LU: u32 = 1 << 1;
+
+const RCTL_EN: u32 = 1 << 1;
+/// Accepte tout le multicast (groupes solicited-node d'IPv6)
+const RCTL_MPE: u32 = 1 << 4;
+const RCTL_BAM: u32 = 1 << 15;
+/// Retire le CRC des trames reçues
+const RCTL_SECRC: u32 = 1 << 26;
+
+const TCTL_EN: u32 = 1 << 1;
+const TCTL_PSP: u32 = 1 << 3;
+const TCTL_CT: u32 = 0x0F << 4;
+const TCTL_COLD: u32 = 0x40 << 12;
+
+/// Causes d'interruption
+pub const ICR_TXDW: u32 = 1 << 0;
+pub const ICR_LSC: u32 = 1 << 2;
+pub const ICR_RXDMT0: u32 = 1 << 4;
+pub const ICR_RXO: u32 = 1 << 6;
+pub const ICR_RXT0: u32 = 1 << 7;
+
+const RAH_AV: u32 = 1 << 31;
+
+/// Bits d'état et de commande des descripteurs
+const DESC_DD: u8 = 1 << 0;
+const DESC_EOP: u8 = 1 << 1;
+const CMD_EOP: u8 = 1 << 0;
+const CMD_IFCS: u8 = 1 << 1;
+const CMD_RS: u8 = 1 << 3;
+
+const RX_DESCS: usize = 32;
+const TX_DESCS: usize = 32;
+const BUFFER_SIZE: usize = 2048;
+/// Trame Ethernet maximale (sans FCS)
+const MAX_FRAME: usize = 1514;
+
+/// Descripteur de réception legacy
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RxDesc {
+    pub addr: u64,
+    pub length: u16,
+    pub checksum: u16,
+    pub status: u8,
+    pub errors: u8,
+    pub special: u16,
+}
+
+/// Descripteur d'émission legacy
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TxDesc {
+    pub addr: u64,
+    pub length: u16,
+    pub cso: u8,
+    pub cmd: u8,
+    pub status: u8,
+    pub css: u8,
+    pub special: u16,
+}
+
+/// Base MMIO de la carte servie par l'interruption (0 : aucune)
+static IRQ_MMIO: AtomicU64 = AtomicU64::new(0);
+
+/// Carte e1000
+pub struct E1000 {
+    mmio: u64,
+    rx_ring: DmaBuffer,
+    tx_ring: DmaBuffer,
+    rx_buffers: DmaBuffer,
+    tx_buffers: DmaBuffer,
+    rx_next: usize,
+    tx_next: usize,
+    mac: MacAddress,
+    pub rx_packets: u64,
+    pub tx_packets: u64,
+}
+
+impl E1000 {
+    /// Réinitialise la carte et installe les anneaux
+    pub fn new(function: PciFunction) -> Result<Self, DriverError> {
+        let mmio = function.bar(0).ok_or(DriverError::NotSupported)?;
+        function.enable_bus_master();
+
+        let mut nic = Self {
+            mmio,
+            rx_ring: DmaBuffer::new(RX_DESCS * 16).ok_or(DriverError::InitializationFailed)?,
+            tx_ring: DmaBuffer::new(TX_DESCS * 16).ok_or(DriverError::InitializationFailed)?,
+            rx_buffers: DmaBuffer::new(RX_DESCS * BUFFER_SIZE).ok_or(DriverError::InitializationFailed)?,
+            tx_buffers: DmaBuffer::new(TX_DESCS * BUFFER_SIZE).ok_or(DriverError::InitializationFailed)?,
+            rx_next: 0,
+            tx_next: 0,
+            mac: MacAddress::new([0; 6]),
+            rx_packets: 0,
+            tx_packets: 0,
+        };
+
+        nic.write(reg::IMC, u32::MAX);
+        nic.write(reg::CTRL, nic.read(reg::CTRL) | CTRL_RST);
+        for _ in 0..100_000 {
+            if nic.read(reg::CTRL) & CTRL_RST == 0 {
+                break;
+            }
+            core::hint::spin_loop();
+        }
+        nic.write(reg::IMC, u32::MAX);
+        let _ = nic.read(reg::ICR);
+
+        nic.mac = nic.read_mac();
+        nic.write(reg::CTRL, nic.read(reg::CTRL) | CTRL_SLU);
+        for i in 0..128 {
+            nic.write(reg::MTA + 4 * i, 0);
+        }
+        nic.setup_rx();
+        nic.setup_tx();
+        nic.write(reg::IMS, ICR_RXT0 | ICR_RXO | ICR_RXDMT0 | ICR_LSC);
+        Ok(nic)
+    }
+
+    fn read(&self, offset: usize) -> u32 {
+        unsafe { read_volatile((self.mmio as usize + offset) as *const u32) }
+    }
+
+    fn write(&self, offset: usize, value: u32) {
+        unsafe { write_volatile((self.mmio as usize + offset) as *mut u32, value) }
+    }
+
+    /// Adresse MAC : registres RAL/RAH, sinon EEPROM
+    fn read_mac(&self) -> MacAddress {
+        let high = self.read(reg::RAH0);
+        if high & RAH_AV != 0 {
+            let low = self.read(reg::RAL0);
+            return MacAddress::new(mac_from_ra(low, high));
+        }
+        let mut mac = [0u8; 6];
+        for word in 0..3 {
+            let value = self.read_eeprom(word as u8);
+            mac[2 * word] = value as u8;
+            mac[2 * word + 1] = (value >> 8) as u8;
+        }
+        // Programmer RAL/RAH pour le filtrage unicast
+        let low = u32::from_le_bytes([mac[0], mac[1], mac[2], mac[3]]);
+        let high = u16::from_le_bytes([mac[4], mac[5]]) as u32 | RAH_AV;
+        self.write(reg::RAL0, low);
+        self.write(reg::RAH0, high);
+        MacAddress::new(mac)
+    }
+
+    /// Lit un mot de l'EEPROM via EERD
+    fn read_eeprom(&self, word: u8) -> u16 {
+        self.write(reg::EERD, 1 | (word as u32) << 8);
+        for _ in 0..100_000 {
+            let value = self.read(reg::EERD);
+            if value & (1 << 4) != 0 {
+                return (value >> 16) as u16;
+            }
+            core::hint::spin_loop();
+        }
+        0
+    }
+
+    fn rx_desc(&self, i: usize) -> *mut RxDesc {
+        self.rx_ring.as_ptr::<RxDesc>(16 * i)
+    }
+
+    fn tx_desc(&self, i: usize) -> *mut TxDesc {
+        self.tx_ring.as_ptr::<TxDesc>(16 * i)
+    }
+
+    fn setup_rx(&mut self) {
+        for i in 0..RX_DESCS {
+            let addr = self.rx_buffers.phys() + (i * BUFFER_SIZE) as u64;
+            unsafe { write_volatile(self.rx_desc(i), RxDesc { addr, ..RxDesc::default() }) }
+        }
+        let base = self.rx_ring.phys();
+        self.write(reg::RDBAL, base as u32);
+        self.write(reg::RDBAH, (base >> 32) as u32);
+        self.write(reg::RDLEN, (RX_DESCS * 16) as u32);
+        self.write(reg::RDH, 0);
+        self.write(reg::RDT, (RX_DESCS - 1) as u32);
+        // BSIZE = 00 : tampons de 2048 octets
+        self.write(reg::RCTL, RCTL_EN | RCTL_MPE | RCTL_BAM | RCTL_SECRC);
+    }
+
+    fn setup_tx(&mut self) {
+        for i in 0..TX_DESCS {
+            let addr = self.tx_buffers.phys() + (i * BUFFER_SIZE) as u64;
+            // DD levé : descripteur libre
+            unsafe { write_volatile(self.tx_desc(i), TxDesc { addr, status: DESC_DD, ..TxDesc::default() }) }
+        }
+        let base = self.tx_ring.phys();
+        self.write(reg::TDBAL, base as u32);
+        self.write(reg::TDBAH, (base >> 32) as u32);
+        self.write(reg::TDLEN, (TX_DESCS * 16) as u32);
+        self.write(reg::TDH, 0);
+        self.write(reg::TDT, 0);
+        self.write(reg::TCTL, TCTL_EN | TCTL_PSP | TCTL_CT | TCTL_COLD);
+        self.write(reg::TIPG, 0x0060_200A);
+    }
+
+    pub fn link_up(&self) -> bool {
+        self.read(reg::STATUS) & STATUS_LU != 0
+    }
+}
+
+impl NetDevice for E1000 {
+    fn mac_address(&self) -> MacAddress {
+        self.mac
+    }
+
+    fn transmit(&mut self, frame: &[u8]) -> Result<(), NetDeviceError> {
+        if frame.len() > MAX_FRAME {
+            return Err(NetDeviceError::FrameTooLarge);
+        }
+        let i = self.tx_next;
+        let mut desc = unsafe { read_volatile(self.tx_desc(i)) };
+        if desc.status & DESC_DD == 0 {
+            return Err(NetDeviceError::QueueFull);
+        }
+
+        let offset = i * BUFFER_SIZE;
+        self.tx_buffers.as_mut_slice()[offset..offset + frame.len()].copy_from_slice(frame);
+        desc.length = frame.len() as u16;
+        desc.cmd = CMD_EOP | CMD_IFCS | CMD_RS;
+        desc.status = 0;
+        unsafe { write_volatile(self.tx_desc(i), desc) }
+        fence(Ordering::SeqCst);
+
+        self.tx_next = (i + 1) % TX_DESCS;
+        self.write(reg::TDT, self.tx_next as u32);
+        self.tx_packets += 1;
+        Ok(())
+    }
+
+    fn receive(&mut self) -> Option<Vec<u8>> {
+        loop {
+            let i = self.rx_next;
+            let mut desc = unsafe { read_volatile(self.rx_desc(i)) };
+            if desc.status & DESC_DD == 0 {
+                return None;
+            }
+
+            // Trames réparties sur plusieurs tampons : ignorées (pas de jumbo)
+            let complete = desc.status & DESC_EOP != 0 && desc.errors == 0;
+            let frame = if complete {
+                let offset = i * BUFFER_SIZE;
+                let len = (desc.length as usize).min(BUFFER_SIZE);
+                Some(self.rx_buffers.as_slice()[offset..offset + len].to_vec())
+            } else {
+                None
+            };
+
+            desc.status = 0;
+            unsafe { write_volatile(self.rx_desc(i), desc) }
+            fence(Ordering::SeqCst);
+            self.write(reg::RDT, i as u32);
+            self.rx_next = (i + 1) % RX_DESCS;
+
+            if frame.is_some() {
+                self.rx_packets += 1;
+                return frame;
+            }
+        }
+    }
+}
+
+/// Octets de la MAC depuis RAL0/RAH0 (petit-boutiste)
+pub fn mac_from_ra(low: u32, high: u32) -> [u8; 6] {
+    let l = low.to_le_bytes();
+    let h = high.to_le_bytes();
+    [l[0], l[1], l[2], l[3], h[0], h[1]]
+}
+
+/// Fonctions PCI e1000 présentes
+pub fn find() -> Vec<PciFunction> {
+    pci::scan()
+        .into_iter()
+        .filter(|f| f.vendor_id == INTEL_VENDOR && SUPPORTED_DEVICES.contains(&f.device_id))
+        .collect()
+}
+
+/// Initialise les cartes e1000 et les attache à la pile réseau
+///
+/// Retourne le nom et l'adresse MAC des cartes enregistrées.
+pub fn probe() -> Vec<(String, MacAddress)> {
+    let mut attached = Vec::new();
+    for function in find() {
+        match E1000::new(function) {
+            Ok(nic) => {
+                let mac = nic.mac_address();
+                log::info!("e1000: {:04x} lien {}", function.device_id, if nic.link_up() { "actif" } else { "inactif" });
+                if IRQ_MMIO.compare_exchange(0, nic.mmio, Ordering::SeqCst, Ordering::SeqCst).is_ok() {
+                    if let Err(e) = crate::interrupts::request_pci_irq(function.interrupt_line(), handle_interrupt) {
+                        log::warn!("e1000: IRQ {} {:?}", function.interrupt_line(), e);
+                    }
+                }
+                let name = interface::register_device(Box::new(nic));
+                log::info!("e1000: {} {}", name, mac);
+                attached.push((name, mac));
+            }
+            Err(e) => log::error!("e1000: {:?}", e),
+        }
+    }
+    attached
+}
+
+/// Gestionnaire d'interruption : lit ICR (acquittement) et remonte les trames
+pub fn handle_interrupt() {
+    let mmio = IRQ_MMIO.load(Ordering::SeqCst);
+    if mmio == 0 {
+        return;
+    }
+    let cause = unsafe { read_volatile((mmio as usize + reg::ICR) as *const u32) };
+    if cause & (ICR_RXT0 | ICR_RXO | ICR_RXDMT0) != 0 {
+        interface::schedule_rx();
+    }
+}
+
+/// Enveloppe pour le gestionnaire de drivers
+pub struct E1000Driver;
+
+/// QEMU `-device e1000` / `e1000e`
+fn register() -> Result<(), DriverError> {
+    super::register_and_init("e1000", Box::new(E1000Driver))
+}
+
+crate::initcall!(driver, "e1000", register);
+
+impl Driver for E1000Driver {
+    fn name(&self) -> &str {
+        "e1000"
+    }
+
+    fn init(&mut self) -> Result<(), DriverError> {
+        let attached = probe();
+        let (_, mac) = attached.first().ok_or(DriverError::NotFound)?;
+        if interface::NETWORK_INTERFACE.lock().is_none() {
+            // Adresse par défaut du réseau « user » de QEMU
+            interface::init(*mac, crate::net::Ipv4Address::new(10, 0, 2, 15));
+            interface::set_gateway(crate::net::Ipv4Address::new(10, 0, 2, 2));
+            // Configuration enregistrée par ifconfig/ip
+            interface::load_config();
+            // Journal vers l'hôte configuré, dès que la carte est prête
+            crate::net::netconsole::load_config();
+        }
+        Ok(())
+    }
+
+    fn handle_interrupt(&mut self, _irq: u8) {
+        handle_interrupt();
+    }
+
+    fn shutdown(&mut self) -> Result<(), DriverError> {
+        let mmio = IRQ_MMIO.swap(0, Ordering::SeqCst);
+        if mmio != 0 {
+            unsafe { write_volatile((mmio as usize + reg::IMC) as *mut u32, u32::MAX) }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_descriptor_layout() {
+        assert_eq!(core::mem::size_of::<RxDesc>(), 16);
+        assert_eq!(core::mem::size_of::<TxDesc>(), 16);
+    }
+
+    #[test_case]
+    fn test_mac_from_receive_address() {
+        // 52:54:00:12:34:56, adresse par défaut de QEMU
+        let mac = mac_from_ra(0x1200_5452, RAH_AV | 0x5634);
+        assert_eq!(mac, [0x52, 0x54, 0x00, 0x12, 0x34, 0x56]);
+    }
+}
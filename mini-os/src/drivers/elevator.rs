@@ -0,0 +1,304 @@
+/// File de requêtes de la couche bloc : fusion des écritures et ascenseur
+///
+/// Chaque périphérique du registre passe par une `BlockQueue`. Les écritures
+/// y sont mises en attente tant que la file est « bouchée » (`plug`) :
+/// une écriture qui chevauche ou prolonge une requête en attente s'y fond,
+/// si bien que le vidage du cache en blocs de 4 Kio devient quelques grands
+/// transferts. Au débouchage (`unplug`), les requêtes partent dans l'ordre
+/// de l'ascenseur C-LOOK (LBA croissants depuis la position de la tête,
+/// puis retour au début), sauf celles qui attendent depuis plus de
+/// `WRITE_EXPIRE_NS`, servies d'abord (politique deadline).
+///
+/// Sans bouchon, une écriture part immédiatement. Une lecture qui recouvre
+/// une écriture en attente vide d'abord la file : les données lues sont
+/// toujours les dernières écrites. Le bouchon est celui de la file, pas
+/// d'une tâche : deux vidages concurrents partagent la même fenêtre.
+
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use spin::Mutex;
+use super::block::{check_request, BlockDevice};
+use super::disk::DiskError;
+
+/// Taille maximale d'une requête obtenue par fusion de voisines
+pub const MAX_REQUEST_BYTES: usize = 128 * 1024;
+/// Au-delà, la file est vidée même bouchée
+pub const MAX_QUEUED_BYTES: usize = 1024 * 1024;
+/// Âge au-delà duquel une écriture passe devant l'ascenseur
+pub const WRITE_EXPIRE_NS: u64 = 500_000_000;
+
+/// Statistiques d'E/S d'un disque (/proc/diskstats)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IoStats {
+    /// Lectures servies
+    pub reads: u64,
+    pub sectors_read: u64,
+    /// Écritures soumises par les systèmes de fichiers
+    pub writes: u64,
+    /// Écritures fondues dans une requête en attente
+    pub write_merges: u64,
+    pub sectors_written: u64,
+    /// Requêtes transmises au pilote
+    pub dispatches: u64,
+    pub errors: u64,
+    /// Requêtes actuellement en attente
+    pub queued: u64,
+}
+
+/// Écriture en attente
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    pub lba: u64,
+    pub data: Vec<u8>,
+    /// Horodatage (ns) de la plus ancienne écriture fondue
+    queued_at: u64,
+}
+
+/// File d'attente et ordonnancement, indépendants du matériel
+pub struct RequestQueue {
+    block_size: usize,
+    /// Requêtes par LBA de début, sans chevauchement
+    pending: BTreeMap<u64, Request>,
+    queued_bytes: usize,
+    plug_depth: u32,
+    /// Fin de la dernière requête transmise
+    head: u64,
+    stats: IoStats,
+}
+
+impl RequestQueue {
+    pub fn new(block_size: usize) -> Self {
+        Self {
+            block_size,
+            pending: BTreeMap::new(),
+            queued_bytes: 0,
+            plug_depth: 0,
+            head: 0,
+            stats: IoStats::default(),
+        }
+    }
+
+    fn end_of(&self, request: &Request) -> u64 {
+        request.lba + (request.data.len() / self.block_size) as u64
+    }
+
+    pub fn is_plugged(&self) -> bool {
+        self.plug_depth > 0
+    }
+
+    /// Faut-il transmettre maintenant ?
+    pub fn should_dispatch(&self) -> bool {
+        !self.is_plugged() || self.queued_bytes > MAX_QUEUED_BYTES
+    }
+
+    pub fn stats(&self) -> IoStats {
+        IoStats { queued: self.pending.len() as u64, ..self.stats }
+    }
+
+    /// Une écriture en attente recouvre-t-elle `[lba, lba + count)` ?
+    pub fn overlaps(&self, lba: u64, count: u64) -> bool {
+        let end = lba + count;
+        self.pending.range(..end).any(|(_, r)| self.end_of(r) > lba)
+    }
+
+    /// Met une écriture en attente, fondue avec les requêtes qu'elle
+    /// chevauche ou prolonge ; les données les plus récentes l'emportent
+    pub fn add_write(&mut self, lba: u64, data: Vec<u8>, now: u64) {
+        let end = lba + (data.len() / self.block_size) as u64;
+        self.stats.writes += 1;
+        self.stats.sectors_written += (data.len() / self.block_size) as u64;
+
+        let mut start = lba;
+        let mut stop = end;
+        let mut absorbed = Vec::new();
+        for (&key, request) in self.pending.range(..=end) {
+            let request_end = self.end_of(request);
+            let overlapping = key < end && request_end > lba;
+            let adjacent = request_end == lba || key == end;
+            let span = (stop.max(request_end) - start.min(key)) as usize * self.block_size;
+            // Un chevauchement doit fusionner ; un voisin seulement si la requête reste raisonnable
+            if overlapping || (adjacent && span <= MAX_REQUEST_BYTES) {
+                start = start.min(key);
+                stop = stop.max(request_end);
+                absorbed.push(key);
+            }
+        }
+
+        let mut merged = vec![0u8; (stop - start) as usize * self.block_size];
+        let mut queued_at = now;
+        for key in absorbed {
+            let old = self.pending.remove(&key).expect("requête en attente");
+            let offset = (old.lba - start) as usize * self.block_size;
+            merged[offset..offset + old.data.len()].copy_from_slice(&old.data);
+            queued_at = queued_at.min(old.queued_at);
+            self.queued_bytes -= old.data.len();
+            self.stats.write_merges += 1;
+        }
+        let offset = (lba - start) as usize * self.block_size;
+        merged[offset..offset + data.len()].copy_from_slice(&data);
+        self.queued_bytes += merged.len();
+        self.pending.insert(start, Request { lba: start, data: merged, queued_at });
+    }
+
+    /// Vide la file dans l'ordre de transmission : écritures expirées (les
+    /// plus anciennes d'abord), puis C-LOOK depuis la tête
+    pub fn drain(&mut self, now: u64) -> Vec<Request> {
+        let pending = core::mem::take(&mut self.pending);
+        self.queued_bytes = 0;
+        let (mut expired, rest): (Vec<Request>, Vec<Request>) = pending
+            .into_values()
+            .partition(|r| now.saturating_sub(r.queued_at) >= WRITE_EXPIRE_NS);
+        expired.sort_by_key(|r| r.queued_at);
+
+        let (ahead, behind): (Vec<Request>, Vec<Request>) = rest.into_iter().partition(|r| r.lba >= self.head);
+        let mut order = expired;
+        order.extend(ahead);
+        order.extend(behind);
+        if let Some(last) = order.last() {
+            self.head = self.end_of(last);
+        }
+        self.stats.dispatches += order.len() as u64;
+        order
+    }
+}
+
+/// File d'un périphérique enregistré
+pub struct BlockQueue {
+    device: Arc<Mutex<dyn BlockDevice>>,
+    queue: Mutex<RequestQueue>,
+}
+
+impl BlockQueue {
+    pub fn new(device: Arc<Mutex<dyn BlockDevice>>) -> Self {
+        let block_size = device.lock().block_size();
+        Self { device, queue: Mutex::new(RequestQueue::new(block_size)) }
+    }
+
+    pub fn block_size(&self) -> usize {
+        self.device.lock().block_size()
+    }
+
+    pub fn block_count(&self) -> u64 {
+        self.device.lock().block_count()
+    }
+
+    /// Transmet toutes les requêtes en attente ; la première erreur est
+    /// rendue, les requêtes suivantes partent quand même
+    fn dispatch(&self, queue: &mut RequestQueue) -> Result<(), DiskError> {
+        let requests = queue.drain(crate::time::now_ns());
+        let mut device = self.device.lock();
+        let mut result = Ok(());
+        for request in requests {
+            let written = if crate::fault_inject::should_fail(crate::fault_inject::Point::DiskWrite) {
+                Err(DiskError::WriteFailed)
+            } else {
+                device.write_blocks(request.lba, &request.data)
+            };
+            if let Err(e) = written {
+                queue.stats.errors += 1;
+                result = result.and(Err(e));
+            }
+        }
+        result
+    }
+
+    pub fn read_blocks(&self, lba: u64, buf: &mut [u8]) -> Result<(), DiskError> {
+        let mut queue = self.queue.lock();
+        let count = check_request(&*self.device.lock(), lba, buf.len())?;
+        if queue.overlaps(lba, count) {
+            self.dispatch(&mut queue)?;
+        }
+        let result = if crate::fault_inject::should_fail(crate::fault_inject::Point::DiskRead) {
+            Err(DiskError::ReadFailed)
+        } else {
+            self.device.lock().read_blocks(lba, buf)
+        };
+        match result {
+            Ok(()) => {
+                queue.stats.reads += 1;
+                queue.stats.sectors_read += count;
+            }
+            Err(_) => queue.stats.errors += 1,
+        }
+        result
+    }
+
+    pub fn write_blocks(&self, lba: u64, buf: &[u8]) -> Result<(), DiskError> {
+        let mut queue = self.queue.lock();
+        check_request(&*self.device.lock(), lba, buf.len())?;
+        queue.add_write(lba, buf.to_vec(), crate::time::now_ns());
+        if queue.should_dispatch() {
+            self.dispatch(&mut queue)?;
+        }
+        Ok(())
+    }
+
+    /// Retient les écritures jusqu'au `unplug` correspondant
+    pub fn plug(&self) {
+        self.queue.lock().plug_depth += 1;
+    }
+
+    /// Retire un bouchon ; au dernier, la file est transmise
+    pub fn unplug(&self) -> Result<(), DiskError> {
+        let mut queue = self.queue.lock();
+        queue.plug_depth = queue.plug_depth.saturating_sub(1);
+        if queue.is_plugged() {
+            return Ok(());
+        }
+        self.dispatch(&mut queue)
+    }
+
+    /// Transmet les écritures en attente puis vide le cache du périphérique
+    pub fn flush(&self) -> Result<(), DiskError> {
+        let mut queue = self.queue.lock();
+        self.dispatch(&mut queue)?;
+        self.device.lock().flush()
+    }
+
+    pub fn stats(&self) -> IoStats {
+        self.queue.lock().stats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_merge_and_elevator_order() {
+        let mut queue = RequestQueue::new(512);
+        queue.plug_depth = 1;
+        queue.head = 50;
+
+        // Trois blocs contigus de 4 Kio : une seule requête
+        for block in 0..3u64 {
+            queue.add_write(100 + block * 8, vec![block as u8; 4096], 0);
+        }
+        // Réécriture d'une partie : les dernières données l'emportent
+        queue.add_write(108, vec![0xFF; 512], 0);
+        queue.add_write(10, vec![1; 512], 0);
+        queue.add_write(60, vec![2; 512], 0);
+        assert!(!queue.should_dispatch());
+        assert!(queue.overlaps(120, 8));
+        assert!(!queue.overlaps(124, 1));
+
+        let stats = queue.stats();
+        assert_eq!((stats.writes, stats.write_merges, stats.queued), (6, 3, 3));
+
+        let order = queue.drain(1_000);
+        let lbas: Vec<u64> = order.iter().map(|r| r.lba).collect();
+        assert_eq!(lbas, [60, 100, 10]); // C-LOOK depuis la tête (50)
+        assert_eq!(order[1].data.len(), 3 * 4096);
+        assert_eq!(order[1].data[8 * 512], 0xFF);
+        assert_eq!(order[1].data[8 * 512 + 512], 1);
+
+        // Deadline : une requête expirée passe devant
+        queue.head = 100;
+        queue.add_write(5, vec![0; 512], 0);
+        queue.add_write(200, vec![0; 512], WRITE_EXPIRE_NS);
+        let lbas: Vec<u64> = queue.drain(WRITE_EXPIRE_NS).iter().map(|r| r.lba).collect();
+        assert_eq!(lbas, [5, 200]);
+    }
+}
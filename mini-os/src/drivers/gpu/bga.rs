@@ -0,0 +1,93 @@
+/// Extensions VBE de Bochs/QEMU (interface DISPI)
+///
+/// Permet de changer de mode graphique depuis le mode long, sans
+/// appeler le BIOS VBE en mode réel : les registres DISPI sont lus et
+/// écrits via les ports 0x1CE (index) et 0x1CF (données). Le
+/// framebuffer linéaire est la BAR 0 de l'adaptateur PCI 1234:1111
+/// (QEMU `-vga std`).
+
+use x86_64::instructions::port::Port;
+use crate::drivers::pci;
+use super::vesa::VesaModeInfo;
+
+const INDEX_PORT: u16 = 0x01CE;
+const DATA_PORT: u16 = 0x01CF;
+
+/// Registres DISPI
+mod index {
+    pub const ID: u16 = 0;
+    pub const XRES: u16 = 1;
+    pub const YRES: u16 = 2;
+    pub const BPP: u16 = 3;
+    pub const ENABLE: u16 = 4;
+    pub const VIRT_WIDTH: u16 = 6;
+    pub const X_OFFSET: u16 = 8;
+    pub const Y_OFFSET: u16 = 9;
+}
+
+const ENABLED: u16 = 0x01;
+const LFB_ENABLED: u16 = 0x40;
+
+/// Identifiants gérés (0xB0C0 à 0xB0C5)
+const ID_MIN: u16 = 0xB0C0;
+const ID_MAX: u16 = 0xB0C5;
+
+/// Adaptateur graphique de Bochs/QEMU
+const BGA_VENDOR: u16 = 0x1234;
+const BGA_DEVICE: u16 = 0x1111;
+
+fn write(register: u16, value: u16) {
+    unsafe {
+        Port::<u16>::new(INDEX_PORT).write(register);
+        Port::<u16>::new(DATA_PORT).write(value);
+    }
+}
+
+fn read(register: u16) -> u16 {
+    unsafe {
+        Port::<u16>::new(INDEX_PORT).write(register);
+        Port::<u16>::new(DATA_PORT).read()
+    }
+}
+
+/// Vrai si l'interface DISPI répond
+pub fn is_available() -> bool {
+    (ID_MIN..=ID_MAX).contains(&read(index::ID))
+}
+
+/// Programme un mode linéaire ; retourne sa description
+pub fn set_mode(width: u16, height: u16, bpp: u8) -> Option<VesaModeInfo> {
+    if !is_available() {
+        return None;
+    }
+    let function = pci::find_by_id(BGA_VENDOR, BGA_DEVICE).into_iter().next()?;
+    let framebuffer = function.bar(0)?;
+
+    write(index::ENABLE, 0);
+    write(index::XRES, width);
+    write(index::YRES, height);
+    write(index::BPP, bpp as u16);
+    write(index::VIRT_WIDTH, width);
+    write(index::X_OFFSET, 0);
+    write(index::Y_OFFSET, 0);
+    write(index::ENABLE, ENABLED | LFB_ENABLED);
+
+    // Le mode demandé peut être refusé (mémoire vidéo insuffisante)
+    if read(index::XRES) != width || read(index::YRES) != height {
+        return None;
+    }
+    Some(VesaModeInfo {
+        width,
+        height,
+        pitch: width * (bpp as u16 / 8),
+        bpp,
+        framebuffer,
+    })
+}
+
+/// Revient au mode texte VGA
+pub fn disable() {
+    if is_available() {
+        write(index::ENABLE, 0);
+    }
+}
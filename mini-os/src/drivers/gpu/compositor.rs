@@ -0,0 +1,524 @@
+/// Compositeur 2D minimal
+///
+/// Chaque fenêtre dessine dans sa propre surface hors écran (ARGB 32
+/// bits, via `Canvas`). Les zones modifiées sont accumulées comme
+/// rectangles de dommage ; à chaque image (`FRAME_TICKS` ticks du timer)
+/// seules ces zones sont recomposées dans le tampon arrière — fond,
+/// fenêtres de bas en haut avec mélange alpha, puis curseur — et
+/// recopiées vers le framebuffer. Le framebuffer est celui de la
+/// console, suspendue tant que le compositeur tourne.
+
+use alloc::vec::Vec;
+use spin::Mutex;
+use x86_64::instructions::interrupts::without_interrupts;
+use super::primitives::{Canvas, GraphicsContext};
+use super::vesa::{Color, VesaModeInfo};
+use crate::vga_buffer;
+
+/// Ticks du timer entre deux images (50 images/s à 100 Hz)
+pub const FRAME_TICKS: u64 = 2;
+/// Hauteur de la barre de titre (zone de déplacement)
+pub const TITLE_HEIGHT: u16 = 20;
+/// Rectangles de dommage conservés avant fusion en un seul
+const MAX_DAMAGE: usize = 16;
+
+/// Curseur (flèche 8×12, 1 = bord, 2 = intérieur)
+const CURSOR: [[u8; 8]; 12] = [
+    [1, 0, 0, 0, 0, 0, 0, 0],
+    [1, 1, 0, 0, 0, 0, 0, 0],
+    [1, 2, 1, 0, 0, 0, 0, 0],
+    [1, 2, 2, 1, 0, 0, 0, 0],
+    [1, 2, 2, 2, 1, 0, 0, 0],
+    [1, 2, 2, 2, 2, 1, 0, 0],
+    [1, 2, 2, 2, 2, 2, 1, 0],
+    [1, 2, 2, 2, 2, 2, 2, 1],
+    [1, 2, 2, 2, 1, 1, 1, 1],
+    [1, 2, 1, 2, 1, 0, 0, 0],
+    [1, 1, 0, 1, 2, 1, 0, 0],
+    [0, 0, 0, 0, 1, 1, 0, 0],
+];
+
+/// Identifiant de fenêtre
+pub type WindowId = u32;
+
+/// Rectangle en coordonnées écran
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub w: i32,
+    pub h: i32,
+}
+
+impl Rect {
+    pub const fn new(x: i32, y: i32, w: i32, h: i32) -> Self {
+        Self { x, y, w, h }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.w <= 0 || self.h <= 0
+    }
+
+    pub fn intersect(&self, other: &Rect) -> Rect {
+        let x0 = self.x.max(other.x);
+        let y0 = self.y.max(other.y);
+        let x1 = (self.x + self.w).min(other.x + other.w);
+        let y1 = (self.y + self.h).min(other.y + other.h);
+        Rect::new(x0, y0, (x1 - x0).max(0), (y1 - y0).max(0))
+    }
+
+    pub fn union(&self, other: &Rect) -> Rect {
+        let x0 = self.x.min(other.x);
+        let y0 = self.y.min(other.y);
+        let x1 = (self.x + self.w).max(other.x + other.w);
+        let y1 = (self.y + self.h).max(other.y + other.h);
+        Rect::new(x0, y0, x1 - x0, y1 - y0)
+    }
+
+    pub fn overlaps(&self, other: &Rect) -> bool {
+        !self.intersect(other).is_empty()
+    }
+
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x && y >= self.y && x < self.x + self.w && y < self.y + self.h
+    }
+}
+
+/// Mélange « source sur destination » de deux pixels ARGB
+pub fn blend(src: u32, dst: u32) -> u32 {
+    let alpha = src >> 24;
+    match alpha {
+        0xFF => src,
+        0 => dst,
+        _ => {
+            let mix = |shift: u32| {
+                let s = (src >> shift) & 0xFF;
+                let d = (dst >> shift) & 0xFF;
+                ((s * alpha + d * (255 - alpha)) / 255) << shift
+            };
+            0xFF00_0000 | mix(16) | mix(8) | mix(0)
+        }
+    }
+}
+
+fn argb(color: Color) -> u32 {
+    (color.a as u32) << 24 | (color.r as u32) << 16 | (color.g as u32) << 8 | color.b as u32
+}
+
+/// Surface hors écran d'une fenêtre
+pub struct Surface {
+    pub width: u16,
+    pub height: u16,
+    pixels: Vec<u32>,
+}
+
+impl Surface {
+    pub fn new(width: u16, height: u16) -> Self {
+        Self { width, height, pixels: alloc::vec![0; width as usize * height as usize] }
+    }
+
+    pub fn pixel(&self, x: u16, y: u16) -> u32 {
+        self.pixels[y as usize * self.width as usize + x as usize]
+    }
+
+    pub fn fill(&mut self, color: Color) {
+        self.pixels.fill(argb(color));
+    }
+
+    /// Canvas de dessin sur la surface
+    pub fn canvas(&mut self) -> Canvas<'_, Surface> {
+        Canvas::new(self)
+    }
+}
+
+impl GraphicsContext for Surface {
+    fn draw_pixel(&mut self, x: u16, y: u16, color: Color) {
+        if x < self.width && y < self.height {
+            self.pixels[y as usize * self.width as usize + x as usize] = argb(color);
+        }
+    }
+
+    fn width(&self) -> u16 {
+        self.width
+    }
+
+    fn height(&self) -> u16 {
+        self.height
+    }
+}
+
+/// Fenêtre : position et surface
+pub struct Window {
+    pub id: WindowId,
+    pub x: i32,
+    pub y: i32,
+    pub surface: Surface,
+}
+
+impl Window {
+    pub fn rect(&self) -> Rect {
+        Rect::new(self.x, self.y, self.surface.width as i32, self.surface.height as i32)
+    }
+}
+
+/// Déplacement en cours d'une fenêtre à la souris
+#[derive(Debug, Clone, Copy)]
+struct Drag {
+    id: WindowId,
+    dx: i32,
+    dy: i32,
+}
+
+/// Compositeur
+pub struct Compositor {
+    mode: VesaModeInfo,
+    framebuffer: *mut u8,
+    back: Vec<u32>,
+    /// Fenêtres de la plus basse à la plus haute
+    windows: Vec<Window>,
+    damage: Vec<Rect>,
+    background: u32,
+    next_id: WindowId,
+    cursor: (i32, i32),
+    buttons: u8,
+    drag: Option<Drag>,
+    next_frame: u64,
+    pub frames: u64,
+}
+
+// Le framebuffer n'est touché que sous le verrou du compositeur
+unsafe impl Send for Compositor {}
+
+impl Compositor {
+    pub fn new(mode: VesaModeInfo) -> Self {
+        let size = mode.width as usize * mode.height as usize;
+        let mut compositor = Self {
+            mode,
+            framebuffer: mode.framebuffer as *mut u8,
+            back: alloc::vec![0; size],
+            windows: Vec::new(),
+            damage: Vec::new(),
+            background: 0xFF20_4060,
+            next_id: 1,
+            cursor: (mode.width as i32 / 2, mode.height as i32 / 2),
+            buttons: 0,
+            drag: None,
+            next_frame: 0,
+            frames: 0,
+        };
+        compositor.damage(compositor.screen());
+        compositor
+    }
+
+    pub fn screen(&self) -> Rect {
+        Rect::new(0, 0, self.mode.width as i32, self.mode.height as i32)
+    }
+
+    /// Marque une zone à recomposer
+    pub fn damage(&mut self, rect: Rect) {
+        let mut rect = rect.intersect(&self.screen());
+        if rect.is_empty() {
+            return;
+        }
+        // Fusion avec les zones qui se chevauchent
+        let mut i = 0;
+        while i < self.damage.len() {
+            if self.damage[i].overlaps(&rect) {
+                rect = rect.union(&self.damage.swap_remove(i));
+                i = 0;
+            } else {
+                i += 1;
+            }
+        }
+        self.damage.push(rect);
+        if self.damage.len() > MAX_DAMAGE {
+            let all = self.damage.drain(..).reduce(|a, b| a.union(&b));
+            self.damage.extend(all);
+        }
+    }
+
+    pub fn damage_count(&self) -> usize {
+        self.damage.len()
+    }
+
+    pub fn create_window(&mut self, x: i32, y: i32, width: u16, height: u16) -> WindowId {
+        let id = self.next_id;
+        self.next_id += 1;
+        let window = Window { id, x, y, surface: Surface::new(width, height) };
+        self.damage(window.rect());
+        self.windows.push(window);
+        id
+    }
+
+    fn index_of(&self, id: WindowId) -> Option<usize> {
+        self.windows.iter().position(|w| w.id == id)
+    }
+
+    pub fn destroy_window(&mut self, id: WindowId) -> bool {
+        match self.index_of(id) {
+            Some(index) => {
+                let window = self.windows.remove(index);
+                self.damage(window.rect());
+                if self.drag.map(|d| d.id) == Some(id) {
+                    self.drag = None;
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn move_window(&mut self, id: WindowId, x: i32, y: i32) -> bool {
+        let old = match self.index_of(id) {
+            Some(index) => {
+                let window = &mut self.windows[index];
+                let old = window.rect();
+                window.x = x;
+                window.y = y;
+                old
+            }
+            None => return false,
+        };
+        self.damage(old);
+        let new = Rect::new(x, y, old.w, old.h);
+        self.damage(new);
+        true
+    }
+
+    /// Passe une fenêtre au premier plan
+    pub fn raise(&mut self, id: WindowId) {
+        if let Some(index) = self.index_of(id) {
+            let window = self.windows.remove(index);
+            self.damage(window.rect());
+            self.windows.push(window);
+        }
+    }
+
+    /// Dessine dans une fenêtre ; toute la fenêtre est marquée modifiée
+    pub fn draw<F: FnOnce(&mut Surface)>(&mut self, id: WindowId, f: F) -> bool {
+        let rect = match self.index_of(id) {
+            Some(index) => {
+                f(&mut self.windows[index].surface);
+                self.windows[index].rect()
+            }
+            None => return false,
+        };
+        self.damage(rect);
+        true
+    }
+
+    /// Fenêtre visible sous un point (la plus haute)
+    pub fn window_at(&self, x: i32, y: i32) -> Option<WindowId> {
+        self.windows.iter().rev().find(|w| w.rect().contains(x, y)).map(|w| w.id)
+    }
+
+    fn cursor_rect(&self) -> Rect {
+        Rect::new(self.cursor.0, self.cursor.1, 8, 12)
+    }
+
+    /// Déplacement relatif de la souris (y positif vers le haut)
+    pub fn pointer_event(&mut self, dx: i32, dy: i32, buttons: u8) {
+        let screen = self.screen();
+        let old = self.cursor_rect();
+        self.cursor.0 = (self.cursor.0 + dx).clamp(0, screen.w - 1);
+        self.cursor.1 = (self.cursor.1 - dy).clamp(0, screen.h - 1);
+        self.damage(old);
+        self.damage(self.cursor_rect());
+
+        let (x, y) = self.cursor;
+        let pressed = buttons & 1 != 0;
+        let was_pressed = self.buttons & 1 != 0;
+        self.buttons = buttons;
+
+        if pressed && !was_pressed {
+            if let Some(id) = self.window_at(x, y) {
+                self.raise(id);
+                let window = &self.windows[self.windows.len() - 1];
+                // Seule la barre de titre permet de déplacer la fenêtre
+                if y < window.y + TITLE_HEIGHT as i32 {
+                    self.drag = Some(Drag { id, dx: x - window.x, dy: y - window.y });
+                }
+            }
+        } else if !pressed {
+            self.drag = None;
+        }
+
+        if let Some(drag) = self.drag {
+            self.move_window(drag.id, x - drag.dx, y - drag.dy);
+        }
+    }
+
+    /// Recompose une zone dans le tampon arrière
+    fn compose_rect(&mut self, rect: Rect) {
+        let width = self.mode.width as usize;
+        for y in rect.y..rect.y + rect.h {
+            let row = y as usize * width;
+            self.back[row + rect.x as usize..row + (rect.x + rect.w) as usize].fill(self.background);
+        }
+        for window in &self.windows {
+            let area = window.rect().intersect(&rect);
+            for y in area.y..area.y + area.h {
+                let row = y as usize * width;
+                let sy = (y - window.y) as u16;
+                for x in area.x..area.x + area.w {
+                    let src = window.surface.pixel((x - window.x) as u16, sy);
+                    let dst = &mut self.back[row + x as usize];
+                    *dst = blend(src, *dst);
+                }
+            }
+        }
+        let cursor = self.cursor_rect().intersect(&rect);
+        for y in cursor.y..cursor.y + cursor.h {
+            for x in cursor.x..cursor.x + cursor.w {
+                let color = match CURSOR[(y - self.cursor.1) as usize][(x - self.cursor.0) as usize] {
+                    1 => 0xFF00_0000,
+                    2 => 0xFFFF_FFFF,
+                    _ => continue,
+                };
+                self.back[y as usize * width + x as usize] = color;
+            }
+        }
+    }
+
+    /// Recopie une zone du tampon arrière vers le framebuffer
+    fn present_rect(&self, rect: Rect) {
+        let width = self.mode.width as usize;
+        for y in rect.y..rect.y + rect.h {
+            let src = &self.back[y as usize * width + rect.x as usize..][..rect.w as usize];
+            unsafe {
+                let dst = self.framebuffer.add(y as usize * self.mode.pitch as usize + rect.x as usize * 4) as *mut u32;
+                core::ptr::copy_nonoverlapping(src.as_ptr(), dst, src.len());
+            }
+        }
+    }
+
+    /// Compose et affiche les zones endommagées ; retourne leur nombre
+    pub fn render(&mut self) -> usize {
+        let damage: Vec<Rect> = self.damage.drain(..).collect();
+        for rect in &damage {
+            self.compose_rect(*rect);
+        }
+        for rect in &damage {
+            self.present_rect(*rect);
+        }
+        if !damage.is_empty() {
+            self.frames += 1;
+        }
+        damage.len()
+    }
+}
+
+/// Compositeur actif (None : l'écran appartient à la console)
+pub static COMPOSITOR: Mutex<Option<Compositor>> = Mutex::new(None);
+
+/// Prend l'écran de la console framebuffer
+pub fn start() -> Result<(), &'static str> {
+    let mode = vga_buffer::framebuffer_info().ok_or("console en mode texte")?;
+    if mode.bpp != 32 {
+        return Err("format de pixel non géré");
+    }
+    let compositor = Compositor::new(mode);
+    vga_buffer::suspend_console(true);
+    without_interrupts(|| *COMPOSITOR.lock() = Some(compositor));
+    Ok(())
+}
+
+/// Rend l'écran à la console
+pub fn stop() {
+    let running = without_interrupts(|| COMPOSITOR.lock().take().is_some());
+    if running {
+        vga_buffer::suspend_console(false);
+    }
+}
+
+pub fn is_running() -> bool {
+    without_interrupts(|| COMPOSITOR.lock().is_some())
+}
+
+/// Exécute `f` sur le compositeur actif
+pub fn with<R, F: FnOnce(&mut Compositor) -> R>(f: F) -> Option<R> {
+    without_interrupts(|| COMPOSITOR.lock().as_mut().map(f))
+}
+
+pub fn create_window(x: i32, y: i32, width: u16, height: u16) -> Option<WindowId> {
+    with(|c| c.create_window(x, y, width, height))
+}
+
+pub fn move_window(id: WindowId, x: i32, y: i32) -> bool {
+    with(|c| c.move_window(id, x, y)).unwrap_or(false)
+}
+
+pub fn destroy_window(id: WindowId) -> bool {
+    with(|c| c.destroy_window(id)).unwrap_or(false)
+}
+
+/// Image suivante si l'échéance est atteinte : souris puis composition
+pub fn poll() {
+    let now = crate::scheduler::ticks();
+    with(|c| {
+        if now < c.next_frame {
+            return;
+        }
+        c.next_frame = now + FRAME_TICKS;
+        while let Some(event) = crate::mouse::poll_event() {
+            c.pointer_event(event.dx as i32, event.dy as i32, event.buttons);
+        }
+        c.render();
+    });
+}
+
+/// Fenêtre de démonstration : barre de titre, fond et motif
+fn decorate(surface: &mut Surface, title_color: Color, body: Color) {
+    let (w, h) = (surface.width, surface.height);
+    surface.fill(body);
+    let mut canvas = surface.canvas();
+    canvas.fill_rect(0, 0, w, TITLE_HEIGHT, title_color);
+    canvas.draw_rect(0, 0, w - 1, h - 1, Color::WHITE);
+    canvas.draw_circle(w / 2, (h + TITLE_HEIGHT) / 2, h / 4, Color::WHITE);
+}
+
+/// Démarre le compositeur avec trois fenêtres déplaçables à la souris
+pub fn demo() -> Result<(), &'static str> {
+    start()?;
+    with(|c| {
+        let a = c.create_window(80, 80, 320, 220);
+        c.draw(a, |s| decorate(s, Color::new(200, 40, 40), Color::new(240, 240, 240)));
+        let b = c.create_window(260, 180, 300, 200);
+        c.draw(b, |s| decorate(s, Color::new(40, 120, 200), Color::new(200, 220, 240)));
+        // Fenêtre semi-transparente
+        let glass = c.create_window(460, 320, 260, 180);
+        c.draw(glass, |s| decorate(s, Color::new(40, 160, 80), Color::with_alpha(255, 255, 255, 128)));
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_blend_and_rect() {
+        assert_eq!(blend(0xFFFF_0000, 0xFF00_00FF), 0xFFFF_0000);
+        assert_eq!(blend(0x0012_3456, 0xFF00_00FF), 0xFF00_00FF);
+        assert_eq!(blend(0x80FF_FFFF, 0xFF00_0000), 0xFF80_8080);
+        let a = Rect::new(0, 0, 10, 10);
+        let b = Rect::new(5, 5, 10, 10);
+        assert_eq!(a.intersect(&b), Rect::new(5, 5, 5, 5));
+        assert_eq!(a.union(&b), Rect::new(0, 0, 15, 15));
+        assert!(a.intersect(&Rect::new(20, 20, 1, 1)).is_empty());
+    }
+
+    #[test_case]
+    fn test_damage_merges_overlapping_rects() {
+        let mode = VesaModeInfo { width: 64, height: 64, pitch: 256, bpp: 32, framebuffer: 0 };
+        let mut compositor = Compositor::new(mode);
+        compositor.damage.clear();
+        compositor.damage(Rect::new(0, 0, 8, 8));
+        compositor.damage(Rect::new(4, 4, 8, 8));
+        compositor.damage(Rect::new(40, 40, 4, 4));
+        assert_eq!(compositor.damage_count(), 2);
+        // Hors écran : ignoré
+        compositor.damage(Rect::new(100, 100, 4, 4));
+        assert_eq!(compositor.damage_count(), 2);
+    }
+}
@@ -0,0 +1,108 @@
+/// Console texte sur framebuffer linéaire
+///
+/// Grille de cellules 8×16 (glyphes 8×8 de `font` aux lignes doublées),
+/// couleurs de la palette VGA 16 couleurs. Le défilement recopie le
+/// framebuffer d'une ligne de cellules vers le haut. Seuls les modes
+/// 32 bits par pixel (XRGB) sont gérés.
+
+use super::font::{self, GLYPH_WIDTH, GLYPH_HEIGHT};
+use super::vesa::VesaModeInfo;
+
+/// Hauteur d'une cellule (lignes du glyphe doublées)
+pub const CELL_HEIGHT: usize = GLYPH_HEIGHT * 2;
+pub const CELL_WIDTH: usize = GLYPH_WIDTH;
+
+/// Palette VGA (index 0 à 15, même ordre que `vga_buffer::Color`)
+pub const PALETTE: [u32; 16] = [
+    0x000000, 0x0000AA, 0x00AA00, 0x00AAAA, 0xAA0000, 0xAA00AA, 0xAA5500, 0xAAAAAA,
+    0x555555, 0x5555FF, 0x55FF55, 0x55FFFF, 0xFF5555, 0xFF55FF, 0xFFFF55, 0xFFFFFF,
+];
+
+/// Console framebuffer
+pub struct FbConsole {
+    info: VesaModeInfo,
+    base: *mut u8,
+    cols: usize,
+    rows: usize,
+}
+
+// Le framebuffer n'est accédé que sous le verrou du WRITER
+unsafe impl Send for FbConsole {}
+
+impl FbConsole {
+    /// Console sur un mode déjà programmé ; None si le format n'est pas géré
+    pub fn new(info: VesaModeInfo) -> Option<Self> {
+        if info.bpp != 32 || info.framebuffer == 0 {
+            return None;
+        }
+        let console = Self {
+            info,
+            base: info.framebuffer as *mut u8,
+            cols: info.width as usize / CELL_WIDTH,
+            rows: info.height as usize / CELL_HEIGHT,
+        };
+        Some(console)
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn mode_info(&self) -> VesaModeInfo {
+        self.info
+    }
+
+    #[inline]
+    fn pixel_row(&self, y: usize) -> *mut u32 {
+        unsafe { self.base.add(y * self.info.pitch as usize) as *mut u32 }
+    }
+
+    /// Dessine un caractère dans la cellule (col, row)
+    pub fn draw_char(&mut self, col: usize, row: usize, byte: u8, fg: u8, bg: u8) {
+        if col >= self.cols || row >= self.rows {
+            return;
+        }
+        let fg = PALETTE[(fg & 0x0F) as usize];
+        let bg = PALETTE[(bg & 0x0F) as usize];
+        let glyph = font::glyph(byte);
+        let x0 = col * CELL_WIDTH;
+        for (y, bits) in glyph.iter().enumerate() {
+            for half in 0..2 {
+                let line = self.pixel_row(row * CELL_HEIGHT + y * 2 + half);
+                for x in 0..CELL_WIDTH {
+                    let color = if bits & (1 << x) != 0 { fg } else { bg };
+                    unsafe { core::ptr::write_volatile(line.add(x0 + x), color) }
+                }
+            }
+        }
+    }
+
+    /// Efface une ligne de cellules
+    pub fn clear_row(&mut self, row: usize, bg: u8) {
+        if row >= self.rows {
+            return;
+        }
+        let color = PALETTE[(bg & 0x0F) as usize];
+        let width = self.cols * CELL_WIDTH;
+        for y in row * CELL_HEIGHT..(row + 1) * CELL_HEIGHT {
+            let line = self.pixel_row(y);
+            for x in 0..width {
+                unsafe { core::ptr::write_volatile(line.add(x), color) }
+            }
+        }
+    }
+
+    /// Remonte le contenu d'une ligne de cellules et efface la dernière
+    pub fn scroll_up(&mut self, bg: u8) {
+        let pitch = self.info.pitch as usize;
+        let moved = (self.rows - 1) * CELL_HEIGHT * pitch;
+        unsafe {
+            core::ptr::copy(self.base.add(CELL_HEIGHT * pitch), self.base, moved);
+        }
+        self.clear_row(self.rows - 1, bg);
+    }
+}
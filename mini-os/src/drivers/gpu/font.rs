@@ -0,0 +1,189 @@
+/// Police bitmap 8×8 pour la console framebuffer
+///
+/// ASCII imprimable (0x20 à 0x7E) plus les octets CP437 utilisés par la
+/// console (accents, traits de cadre), une ligne par octet, bit 0 = pixel
+/// le plus à gauche. La console double chaque ligne pour obtenir des
+/// cellules 8×16 proches de celles du mode texte.
+
+/// Largeur et hauteur d'un glyphe
+pub const GLYPH_WIDTH: usize = 8;
+pub const GLYPH_HEIGHT: usize = 8;
+
+/// Premier caractère de la table
+const FIRST: u8 = 0x20;
+
+/// Glyphe de remplacement (carré plein, comme 0xFE en CP437)
+const REPLACEMENT: [u8; GLYPH_HEIGHT] = [0x00, 0x00, 0x3C, 0x3C, 0x3C, 0x3C, 0x00, 0x00];
+
+static GLYPHS: [[u8; GLYPH_HEIGHT]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // espace
+    [0x18, 0x3C, 0x3C, 0x18, 0x18, 0x00, 0x18, 0x00], // !
+    [0x36, 0x36, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // "
+    [0x36, 0x36, 0x7F, 0x36, 0x7F, 0x36, 0x36, 0x00], // #
+    [0x0C, 0x3E, 0x03, 0x1E, 0x30, 0x1F, 0x0C, 0x00], // $
+    [0x00, 0x63, 0x33, 0x18, 0x0C, 0x66, 0x63, 0x00], // %
+    [0x1C, 0x36, 0x1C, 0x6E, 0x3B, 0x33, 0x6E, 0x00], // &
+    [0x06, 0x06, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00], // '
+    [0x18, 0x0C, 0x06, 0x06, 0x06, 0x0C, 0x18, 0x00], // (
+    [0x06, 0x0C, 0x18, 0x18, 0x18, 0x0C, 0x06, 0x00], // )
+    [0x00, 0x66, 0x3C, 0xFF, 0x3C, 0x66, 0x00, 0x00], // *
+    [0x00, 0x0C, 0x0C, 0x3F, 0x0C, 0x0C, 0x00, 0x00], // +
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C, 0x06], // ,
+    [0x00, 0x00, 0x00, 0x3F, 0x00, 0x00, 0x00, 0x00], // -
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C, 0x00], // .
+    [0x60, 0x30, 0x18, 0x0C, 0x06, 0x03, 0x01, 0x00], // /
+    [0x3E, 0x63, 0x73, 0x7B, 0x6F, 0x67, 0x3E, 0x00], // 0
+    [0x0C, 0x0E, 0x0C, 0x0C, 0x0C, 0x0C, 0x3F, 0x00], // 1
+    [0x1E, 0x33, 0x30, 0x1C, 0x06, 0x33, 0x3F, 0x00], // 2
+    [0x1E, 0x33, 0x30, 0x1C, 0x30, 0x33, 0x1E, 0x00], // 3
+    [0x38, 0x3C, 0x36, 0x33, 0x7F, 0x30, 0x78, 0x00], // 4
+    [0x3F, 0x03, 0x1F, 0x30, 0x30, 0x33, 0x1E, 0x00], // 5
+    [0x1C, 0x06, 0x03, 0x1F, 0x33, 0x33, 0x1E, 0x00], // 6
+    [0x3F, 0x33, 0x30, 0x18, 0x0C, 0x0C, 0x0C, 0x00], // 7
+    [0x1E, 0x33, 0x33, 0x1E, 0x33, 0x33, 0x1E, 0x00], // 8
+    [0x1E, 0x33, 0x33, 0x3E, 0x30, 0x18, 0x0E, 0x00], // 9
+    [0x00, 0x0C, 0x0C, 0x00, 0x00, 0x0C, 0x0C, 0x00], // :
+    [0x00, 0x0C, 0x0C, 0x00, 0x00, 0x0C, 0x0C, 0x06], // ;
+    [0x18, 0x0C, 0x06, 0x03, 0x06, 0x0C, 0x18, 0x00], // <
+    [0x00, 0x00, 0x3F, 0x00, 0x00, 0x3F, 0x00, 0x00], // =
+    [0x06, 0x0C, 0x18, 0x30, 0x18, 0x0C, 0x06, 0x00], // >
+    [0x1E, 0x33, 0x30, 0x18, 0x0C, 0x00, 0x0C, 0x00], // ?
+    [0x3E, 0x63, 0x7B, 0x7B, 0x7B, 0x03, 0x1E, 0x00], // @
+    [0x0C, 0x1E, 0x33, 0x33, 0x3F, 0x33, 0x33, 0x00], // A
+    [0x3F, 0x66, 0x66, 0x3E, 0x66, 0x66, 0x3F, 0x00], // B
+    [0x3C, 0x66, 0x03, 0x03, 0x03, 0x66, 0x3C, 0x00], // C
+    [0x1F, 0x36, 0x66, 0x66, 0x66, 0x36, 0x1F, 0x00], // D
+    [0x7F, 0x46, 0x16, 0x1E, 0x16, 0x46, 0x7F, 0x00], // E
+    [0x7F, 0x46, 0x16, 0x1E, 0x16, 0x06, 0x0F, 0x00], // F
+    [0x3C, 0x66, 0x03, 0x03, 0x73, 0x66, 0x7C, 0x00], // G
+    [0x33, 0x33, 0x33, 0x3F, 0x33, 0x33, 0x33, 0x00], // H
+    [0x1E, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // I
+    [0x78, 0x30, 0x30, 0x30, 0x33, 0x33, 0x1E, 0x00], // J
+    [0x67, 0x66, 0x36, 0x1E, 0x36, 0x66, 0x67, 0x00], // K
+    [0x0F, 0x06, 0x06, 0x06, 0x46, 0x66, 0x7F, 0x00], // L
+    [0x63, 0x77, 0x7F, 0x7F, 0x6B, 0x63, 0x63, 0x00], // M
+    [0x63, 0x67, 0x6F, 0x7B, 0x73, 0x63, 0x63, 0x00], // N
+    [0x1C, 0x36, 0x63, 0x63, 0x63, 0x36, 0x1C, 0x00], // O
+    [0x3F, 0x66, 0x66, 0x3E, 0x06, 0x06, 0x0F, 0x00], // P
+    [0x1E, 0x33, 0x33, 0x33, 0x3B, 0x1E, 0x38, 0x00], // Q
+    [0x3F, 0x66, 0x66, 0x3E, 0x36, 0x66, 0x67, 0x00], // R
+    [0x1E, 0x33, 0x07, 0x0E, 0x38, 0x33, 0x1E, 0x00], // S
+    [0x3F, 0x2D, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // T
+    [0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x3F, 0x00], // U
+    [0x33, 0x33, 0x33, 0x33, 0x33, 0x1E, 0x0C, 0x00], // V
+    [0x63, 0x63, 0x63, 0x6B, 0x7F, 0x77, 0x63, 0x00], // W
+    [0x63, 0x63, 0x36, 0x1C, 0x1C, 0x36, 0x63, 0x00], // X
+    [0x33, 0x33, 0x33, 0x1E, 0x0C, 0x0C, 0x1E, 0x00], // Y
+    [0x7F, 0x63, 0x31, 0x18, 0x4C, 0x66, 0x7F, 0x00], // Z
+    [0x1E, 0x06, 0x06, 0x06, 0x06, 0x06, 0x1E, 0x00], // [
+    [0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x40, 0x00], // \
+    [0x1E, 0x18, 0x18, 0x18, 0x18, 0x18, 0x1E, 0x00], // ]
+    [0x08, 0x1C, 0x36, 0x63, 0x00, 0x00, 0x00, 0x00], // ^
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF], // _
+    [0x0C, 0x0C, 0x18, 0x00, 0x00, 0x00, 0x00, 0x00], // `
+    [0x00, 0x00, 0x1E, 0x30, 0x3E, 0x33, 0x6E, 0x00], // a
+    [0x07, 0x06, 0x06, 0x3E, 0x66, 0x66, 0x3B, 0x00], // b
+    [0x00, 0x00, 0x1E, 0x33, 0x03, 0x33, 0x1E, 0x00], // c
+    [0x38, 0x30, 0x30, 0x3E, 0x33, 0x33, 0x6E, 0x00], // d
+    [0x00, 0x00, 0x1E, 0x33, 0x3F, 0x03, 0x1E, 0x00], // e
+    [0x1C, 0x36, 0x06, 0x0F, 0x06, 0x06, 0x0F, 0x00], // f
+    [0x00, 0x00, 0x6E, 0x33, 0x33, 0x3E, 0x30, 0x1F], // g
+    [0x07, 0x06, 0x36, 0x6E, 0x66, 0x66, 0x67, 0x00], // h
+    [0x0C, 0x00, 0x0E, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // i
+    [0x30, 0x00, 0x30, 0x30, 0x30, 0x33, 0x33, 0x1E], // j
+    [0x07, 0x06, 0x66, 0x36, 0x1E, 0x36, 0x67, 0x00], // k
+    [0x0E, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // l
+    [0x00, 0x00, 0x33, 0x7F, 0x7F, 0x6B, 0x63, 0x00], // m
+    [0x00, 0x00, 0x1F, 0x33, 0x33, 0x33, 0x33, 0x00], // n
+    [0x00, 0x00, 0x1E, 0x33, 0x33, 0x33, 0x1E, 0x00], // o
+    [0x00, 0x00, 0x3B, 0x66, 0x66, 0x3E, 0x06, 0x0F], // p
+    [0x00, 0x00, 0x6E, 0x33, 0x33, 0x3E, 0x30, 0x78], // q
+    [0x00, 0x00, 0x3B, 0x6E, 0x66, 0x06, 0x0F, 0x00], // r
+    [0x00, 0x00, 0x3E, 0x03, 0x1E, 0x30, 0x1F, 0x00], // s
+    [0x08, 0x0C, 0x3E, 0x0C, 0x0C, 0x2C, 0x18, 0x00], // t
+    [0x00, 0x00, 0x33, 0x33, 0x33, 0x33, 0x6E, 0x00], // u
+    [0x00, 0x00, 0x33, 0x33, 0x33, 0x1E, 0x0C, 0x00], // v
+    [0x00, 0x00, 0x63, 0x6B, 0x7F, 0x7F, 0x36, 0x00], // w
+    [0x00, 0x00, 0x63, 0x36, 0x1C, 0x36, 0x63, 0x00], // x
+    [0x00, 0x00, 0x33, 0x33, 0x33, 0x3E, 0x30, 0x1F], // y
+    [0x00, 0x00, 0x3F, 0x19, 0x0C, 0x26, 0x3F, 0x00], // z
+    [0x38, 0x0C, 0x0C, 0x07, 0x0C, 0x0C, 0x38, 0x00], // {
+    [0x18, 0x18, 0x18, 0x00, 0x18, 0x18, 0x18, 0x00], // |
+    [0x07, 0x0C, 0x0C, 0x38, 0x0C, 0x0C, 0x07, 0x00], // }
+    [0x6E, 0x3B, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // ~
+];
+
+/// Glyphes des octets CP437 au-delà de 0x7F que produit `to_cp437`
+/// (lettres accentuées, guillemets, traits de cadre), triés par octet
+static EXTENDED: [(u8, [u8; GLYPH_HEIGHT]); 58] = [
+    (0x80, [0x3C, 0x66, 0x03, 0x03, 0x66, 0x3C, 0x18, 0x0C]), // Ç
+    (0x81, [0x36, 0x00, 0x33, 0x33, 0x33, 0x33, 0x6E, 0x00]), // ü
+    (0x82, [0x30, 0x18, 0x1E, 0x33, 0x3F, 0x03, 0x1E, 0x00]), // é
+    (0x83, [0x1C, 0x36, 0x1E, 0x30, 0x3E, 0x33, 0x6E, 0x00]), // â
+    (0x84, [0x36, 0x00, 0x1E, 0x30, 0x3E, 0x33, 0x6E, 0x00]), // ä
+    (0x85, [0x06, 0x0C, 0x1E, 0x30, 0x3E, 0x33, 0x6E, 0x00]), // à
+    (0x87, [0x00, 0x00, 0x1E, 0x33, 0x03, 0x33, 0x1E, 0x18]), // ç
+    (0x88, [0x1C, 0x36, 0x1E, 0x33, 0x3F, 0x03, 0x1E, 0x00]), // ê
+    (0x89, [0x36, 0x00, 0x1E, 0x33, 0x3F, 0x03, 0x1E, 0x00]), // ë
+    (0x8A, [0x06, 0x0C, 0x1E, 0x33, 0x3F, 0x03, 0x1E, 0x00]), // è
+    (0x8B, [0x36, 0x00, 0x0E, 0x0C, 0x0C, 0x0C, 0x1E, 0x00]), // ï
+    (0x8C, [0x1C, 0x36, 0x0E, 0x0C, 0x0C, 0x0C, 0x1E, 0x00]), // î
+    (0x8D, [0x06, 0x0C, 0x0E, 0x0C, 0x0C, 0x0C, 0x1E, 0x00]), // ì
+    (0x8E, [0x33, 0x0C, 0x1E, 0x33, 0x3F, 0x33, 0x33, 0x00]), // Ä
+    (0x90, [0x30, 0x7F, 0x06, 0x1E, 0x06, 0x06, 0x7F, 0x00]), // É
+    (0x93, [0x1C, 0x36, 0x1E, 0x33, 0x33, 0x33, 0x1E, 0x00]), // ô
+    (0x94, [0x36, 0x00, 0x1E, 0x33, 0x33, 0x33, 0x1E, 0x00]), // ö
+    (0x95, [0x06, 0x0C, 0x1E, 0x33, 0x33, 0x33, 0x1E, 0x00]), // ò
+    (0x96, [0x1C, 0x36, 0x33, 0x33, 0x33, 0x33, 0x6E, 0x00]), // û
+    (0x97, [0x06, 0x0C, 0x33, 0x33, 0x33, 0x33, 0x6E, 0x00]), // ù
+    (0x99, [0x33, 0x00, 0x1E, 0x33, 0x33, 0x33, 0x1E, 0x00]), // Ö
+    (0x9A, [0x33, 0x00, 0x33, 0x33, 0x33, 0x33, 0x1E, 0x00]), // Ü
+    (0x9C, [0x1C, 0x36, 0x26, 0x0F, 0x06, 0x67, 0x3F, 0x00]), // £
+    (0xA0, [0x30, 0x18, 0x1E, 0x30, 0x3E, 0x33, 0x6E, 0x00]), // á
+    (0xA1, [0x30, 0x18, 0x0E, 0x0C, 0x0C, 0x0C, 0x1E, 0x00]), // í
+    (0xA2, [0x30, 0x18, 0x1E, 0x33, 0x33, 0x33, 0x1E, 0x00]), // ó
+    (0xA3, [0x30, 0x18, 0x33, 0x33, 0x33, 0x33, 0x6E, 0x00]), // ú
+    (0xA4, [0x6E, 0x3B, 0x1F, 0x33, 0x33, 0x33, 0x33, 0x00]), // ñ
+    (0xA5, [0x6E, 0x3B, 0x33, 0x37, 0x3F, 0x3B, 0x33, 0x00]), // Ñ
+    (0xA8, [0x0C, 0x00, 0x0C, 0x06, 0x03, 0x33, 0x1E, 0x00]), // ¿
+    (0xAD, [0x18, 0x00, 0x18, 0x18, 0x3C, 0x3C, 0x18, 0x00]), // ¡
+    (0xAE, [0x00, 0xCC, 0x66, 0x33, 0x66, 0xCC, 0x00, 0x00]), // «
+    (0xAF, [0x00, 0x33, 0x66, 0xCC, 0x66, 0x33, 0x00, 0x00]), // »
+    (0xB0, [0x11, 0x44, 0x11, 0x44, 0x11, 0x44, 0x11, 0x44]), // ░
+    (0xB1, [0x55, 0xAA, 0x55, 0xAA, 0x55, 0xAA, 0x55, 0xAA]), // ▒
+    (0xB2, [0xDD, 0x77, 0xDD, 0x77, 0xDD, 0x77, 0xDD, 0x77]), // ▓
+    (0xB3, [0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18]), // │
+    (0xB4, [0x18, 0x18, 0x18, 0x1F, 0x18, 0x18, 0x18, 0x18]), // ┤
+    (0xBA, [0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66]), // ║
+    (0xBF, [0x00, 0x00, 0x00, 0x1F, 0x18, 0x18, 0x18, 0x18]), // ┐
+    (0xC0, [0x18, 0x18, 0x18, 0xF8, 0x00, 0x00, 0x00, 0x00]), // └
+    (0xC1, [0x18, 0x18, 0x18, 0xFF, 0x00, 0x00, 0x00, 0x00]), // ┴
+    (0xC2, [0x00, 0x00, 0x00, 0xFF, 0x18, 0x18, 0x18, 0x18]), // ┬
+    (0xC3, [0x18, 0x18, 0x18, 0xF8, 0x18, 0x18, 0x18, 0x18]), // ├
+    (0xC4, [0x00, 0x00, 0x00, 0xFF, 0x00, 0x00, 0x00, 0x00]), // ─
+    (0xC5, [0x18, 0x18, 0x18, 0xFF, 0x18, 0x18, 0x18, 0x18]), // ┼
+    (0xCD, [0x00, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0x00, 0x00]), // ═
+    (0xD9, [0x18, 0x18, 0x18, 0x1F, 0x00, 0x00, 0x00, 0x00]), // ┘
+    (0xDA, [0x00, 0x00, 0x00, 0xF8, 0x18, 0x18, 0x18, 0x18]), // ┌
+    (0xDB, [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]), // █
+    (0xE1, [0x00, 0x1E, 0x33, 0x1F, 0x33, 0x1F, 0x03, 0x03]), // ß
+    (0xE6, [0x00, 0x00, 0x66, 0x66, 0x66, 0x3E, 0x06, 0x03]), // µ
+    (0xF1, [0x0C, 0x0C, 0x3F, 0x0C, 0x0C, 0x00, 0x3F, 0x00]), // ±
+    (0xF6, [0x00, 0x0C, 0x00, 0x3F, 0x00, 0x0C, 0x00, 0x00]), // ÷
+    (0xF8, [0x1C, 0x36, 0x36, 0x1C, 0x00, 0x00, 0x00, 0x00]), // °
+    (0xFA, [0x00, 0x00, 0x00, 0x18, 0x18, 0x00, 0x00, 0x00]), // ·
+    (0xFB, [0x00, 0x40, 0x20, 0x11, 0x0A, 0x04, 0x00, 0x00]), // √
+    (0xFD, [0x0E, 0x18, 0x0C, 0x06, 0x1E, 0x00, 0x00, 0x00]), // ²
+];
+
+/// Glyphe d'un octet CP437 ; hors table, le carré de remplacement
+pub fn glyph(byte: u8) -> &'static [u8; GLYPH_HEIGHT] {
+    match byte {
+        0x20..=0x7E => &GLYPHS[(byte - FIRST) as usize],
+        0x80..=0xFF => match EXTENDED.binary_search_by_key(&byte, |&(code, _)| code) {
+            Ok(index) => &EXTENDED[index].1,
+            Err(_) => &REPLACEMENT,
+        },
+        _ => &REPLACEMENT,
+    }
+}
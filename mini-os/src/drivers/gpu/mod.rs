@@ -5,7 +5,12 @@
 pub mod vga;
 pub mod vesa;
 pub mod primitives;
+pub mod font;
+pub mod fbconsole;
+pub mod bga;
+pub mod compositor;
 
 pub use vga::{VGA_WRITER, VgaWriter, Color as VgaColor};
 pub use vesa::{VESA_DRIVER, VesaDriver, VesaModeInfo, Color as GRAPHICS_COLOR};
 pub use primitives::{Canvas, GraphicsContext};
+pub use fbconsole::FbConsole;
@@ -110,12 +110,13 @@ impl VgaWriter {
 
     /// Écrit une chaîne
     pub fn write_string(&mut self, s: &str) {
-        for byte in s.bytes() {
-            match byte {
-                // Caractères imprimables ASCII ou newline
-                0x20..=0x7e | b'\n' => self.write_byte(byte),
-                // Autres caractères -> carré blanc
-                _ => self.write_byte(0xfe),
+        for c in s.chars() {
+            match c {
+                '\n' => self.write_byte(b'\n'),
+                // Caractères de contrôle -> carré blanc
+                c if c.is_control() => self.write_byte(0xfe),
+                // Le reste en CP437 (accents, cadres), carré blanc à défaut
+                c => self.write_byte(crate::vga_buffer::to_cp437(c)),
             }
         }
     }
@@ -13,21 +13,42 @@ pub mod usb_protocol;
 pub mod usb_mass_storage;
 #[cfg(feature = "usb")]
 pub mod usb_hid;
+#[cfg(feature = "usb")]
+pub mod xhci;
 
 pub mod serial_trait;
 pub mod mock_serial;
 pub mod disk;
+pub mod ata_dma;
+pub mod ata_info;
+pub mod pci;
+pub mod dma;
+pub mod block;
+pub mod elevator;
+pub mod ahci;
 pub mod nvme;
+pub mod nvme_hw;
 pub mod nvme_cache;
 pub mod nvme_queue;
+pub mod virtio;
+pub mod e1000;
+pub mod ac97;
+pub mod pcm;
 pub mod gpu;
+pub mod watchdog;
 
 // Ré-exports
 pub use serial_trait::SerialPort;
 pub use mock_serial::MockSerial;
-pub use nvme::{NVMeController, NVMeNamespace, NVMeError, NVMeStats, NVME_CONTROLLER, NVME_BLOCK_SIZE};
+pub use block::{BlockDevice, BlockHandle, BLOCK_DEVICES};
+pub use ahci::{AhciController, AhciDisk, AHCI_CONTROLLER};
+pub use nvme::{NVMeController, NVMeNamespace, NVMeError, NVMeStats, NVMeDisk, NVMeDriver, NVME_CONTROLLER, NVME_BLOCK_SIZE};
 pub use nvme_cache::{CachedStorage, CACHED_STORAGE, CachedStorageStats, init_storage};
 pub use nvme_queue::{IoQueueManager, IO_QUEUE_MANAGER, IoQueueStats, NUM_IO_QUEUES};
+pub use virtio::{VirtioPci, VirtioError};
+pub use e1000::{E1000, E1000Driver};
+pub use ac97::{Ac97, Ac97Driver, AC97};
+pub use pcm::{PcmStream, PcmError, Waveform};
 
 #[cfg(feature = "bluetooth")]
 pub mod bluetooth_hci;
@@ -40,6 +61,8 @@ pub use usb_controller::*;
 pub use usb_mass_storage::*;
 #[cfg(feature = "usb")]
 pub use usb_hid::*;
+#[cfg(feature = "usb")]
+pub use xhci::{XhciController, XhciDriver, XHCI_CONTROLLER};
 #[cfg(feature = "bluetooth")]
 pub use bluetooth_hci::*;
 #[cfg(feature = "bluetooth")]
@@ -185,6 +208,13 @@ lazy_static! {
     pub static ref DRIVER_MANAGER: Mutex<DriverManager> = Mutex::new(DriverManager::new());
 }
 
+/// Enregistre `driver` sous `name` puis l'initialise (initcalls des drivers)
+pub fn register_and_init(name: &str, driver: Box<dyn Driver>) -> Result<(), DriverError> {
+    let mut manager = DRIVER_MANAGER.lock();
+    manager.register_driver(name, driver)?;
+    manager.init_driver(name)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,9 +1,19 @@
 /// Module NVMe Driver
 /// 
 /// Implémente le support pour les disques NVMe (Non-Volatile Memory Express)
+///
+/// Avec un contrôleur PCI présent (`-device nvme` sous QEMU), les
+/// commandes passent par `nvme_hw` ; sinon un namespace simulé est créé.
+/// Chaque namespace actif est exposé dans la couche bloc (`nvme0n1`...).
 
+use alloc::format;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
 use spin::Mutex;
+use super::block::{self, BlockDevice};
+use super::disk::DiskError;
+use super::nvme_hw::{NVMeHardware, MAX_TRANSFER};
+use super::{pci, Driver, DriverError};
 
 /// Taille d'un bloc NVMe (512 bytes standard)
 pub const NVME_BLOCK_SIZE: usize = 512;
@@ -165,6 +175,8 @@ pub struct NVMeController {
     commands_completed: usize,
     /// Initialisé
     initialized: bool,
+    /// Contrôleur PCI programmé (None : namespace simulé)
+    hw: Option<NVMeHardware>,
 }
 
 impl NVMeController {
@@ -175,18 +187,28 @@ impl NVMeController {
             commands_sent: 0,
             commands_completed: 0,
             initialized: false,
+            hw: None,
         }
     }
     
     /// Initialise le contrôleur
+    ///
+    /// Cherche un contrôleur NVMe sur le bus PCI ; à défaut, un namespace
+    /// simulé de 512 Mio est créé.
     pub fn init(&mut self) -> Result<(), NVMeError> {
-        // TODO: Initialisation réelle du hardware
-        // 1. Mapper les registres BAR
-        // 2. Reset du contrôleur
-        // 3. Configurer Admin Queue
-        // 4. Identifier les namespaces
+        if self.initialized {
+            return Ok(());
+        }
+        
+        if let Some(function) = pci::find_by_class(0x01, 0x08, Some(0x02)).into_iter().next() {
+            let mut hw = NVMeHardware::init(function)?;
+            self.namespaces = hw.namespaces()?;
+            self.hw = Some(hw);
+            self.initialized = true;
+            return Ok(());
+        }
         
-        // Pour l'instant, créer un namespace de test
+        // Pas de contrôleur : namespace de test
         let mut ns = NVMeNamespace::new(1);
         ns.size_blocks = 1024 * 1024; // 512 MB
         ns.block_size = NVME_BLOCK_SIZE;
@@ -215,8 +237,18 @@ impl NVMeController {
             return Err(NVMeError::BufferTooSmall);
         }
         
-        // TODO: Créer et soumettre la commande NVMe
-        // Pour l'instant, simuler la lecture
+        let block_size = ns.block_size;
+        if let Some(hw) = self.hw.as_mut() {
+            for (i, chunk) in buffer[..required_size].chunks_mut(MAX_TRANSFER).enumerate() {
+                let chunk_lba = lba + (i * MAX_TRANSFER / block_size) as u64;
+                self.commands_sent += 1;
+                hw.transfer(nsid, chunk_lba, block_size, Some(chunk), None)?;
+                self.commands_completed += 1;
+            }
+            return Ok(required_size);
+        }
+        
+        // Namespace simulé
         self.commands_sent += 1;
         self.commands_completed += 1;
         
@@ -238,13 +270,36 @@ impl NVMeController {
             return Err(NVMeError::BufferTooSmall);
         }
         
-        // TODO: Créer et soumettre la commande NVMe
+        let block_size = ns.block_size;
+        if let Some(hw) = self.hw.as_mut() {
+            for (i, chunk) in buffer[..required_size].chunks(MAX_TRANSFER).enumerate() {
+                let chunk_lba = lba + (i * MAX_TRANSFER / block_size) as u64;
+                self.commands_sent += 1;
+                hw.transfer(nsid, chunk_lba, block_size, None, Some(chunk))?;
+                self.commands_completed += 1;
+            }
+            return Ok(required_size);
+        }
+        
         self.commands_sent += 1;
         self.commands_completed += 1;
         
         Ok(required_size)
     }
     
+    /// Vide le cache d'écriture d'un namespace
+    pub fn flush(&mut self, nsid: u32) -> Result<(), NVMeError> {
+        match self.hw.as_mut() {
+            Some(hw) => hw.flush(nsid),
+            None => Ok(()),
+        }
+    }
+    
+    /// Vrai si un contrôleur PCI est utilisé
+    pub fn has_hardware(&self) -> bool {
+        self.hw.is_some()
+    }
+    
     /// Retourne les namespaces
     pub fn get_namespaces(&self) -> &[NVMeNamespace] {
         &self.namespaces
@@ -287,6 +342,96 @@ lazy_static! {
     pub static ref NVME_CONTROLLER: Mutex<NVMeController> = Mutex::new(NVMeController::new());
 }
 
+/// Namespace vu par la couche bloc
+pub struct NVMeDisk {
+    nsid: u32,
+    block_size: usize,
+    blocks: u64,
+}
+
+impl BlockDevice for NVMeDisk {
+    fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    fn block_count(&self) -> u64 {
+        self.blocks
+    }
+
+    fn read_blocks(&mut self, lba: u64, buf: &mut [u8]) -> Result<(), DiskError> {
+        let count = block::check_request(&*self, lba, buf.len())?;
+        let count = u16::try_from(count).map_err(|_| DiskError::InvalidSize)?;
+        NVME_CONTROLLER.lock()
+            .read_blocks(self.nsid, lba, count, buf)
+            .map(|_| ())
+            .map_err(|_| DiskError::ReadFailed)
+    }
+
+    fn write_blocks(&mut self, lba: u64, buf: &[u8]) -> Result<(), DiskError> {
+        let count = block::check_request(&*self, lba, buf.len())?;
+        let count = u16::try_from(count).map_err(|_| DiskError::InvalidSize)?;
+        NVME_CONTROLLER.lock()
+            .write_blocks(self.nsid, lba, count, buf)
+            .map(|_| ())
+            .map_err(|_| DiskError::WriteFailed)
+    }
+
+    fn flush(&mut self) -> Result<(), DiskError> {
+        NVME_CONTROLLER.lock().flush(self.nsid).map_err(|_| DiskError::WriteFailed)
+    }
+}
+
+/// Driver NVMe pour le gestionnaire de drivers
+///
+/// `init` programme le contrôleur et enregistre ses namespaces réels dans
+/// la couche bloc ; le namespace simulé n'y est pas exposé.
+pub struct NVMeDriver;
+
+fn register() -> Result<(), DriverError> {
+    super::register_and_init("nvme", alloc::boxed::Box::new(NVMeDriver))
+}
+
+crate::initcall!(driver, "NVMe", register);
+
+impl Driver for NVMeDriver {
+    fn name(&self) -> &str {
+        "nvme"
+    }
+
+    fn init(&mut self) -> Result<(), DriverError> {
+        let mut controller = NVME_CONTROLLER.lock();
+        controller.init().map_err(|_| DriverError::InitializationFailed)?;
+        if !controller.has_hardware() {
+            return Err(DriverError::NotFound);
+        }
+
+        let disks: Vec<NVMeDisk> = controller.get_namespaces().iter()
+            .filter(|ns| ns.active)
+            .map(|ns| NVMeDisk { nsid: ns.id, block_size: ns.block_size, blocks: ns.size_blocks })
+            .collect();
+        drop(controller);
+
+        for disk in disks {
+            let name = format!("nvme0n{}", disk.nsid);
+            log::info!("NVMe: {} ({} blocs de {} octets)", name, disk.blocks, disk.block_size);
+            let _ = block::register(&name, Arc::new(Mutex::new(disk)));
+        }
+        Ok(())
+    }
+
+    // Complétions relevées par scrutation
+    fn handle_interrupt(&mut self, _irq: u8) {}
+
+    fn shutdown(&mut self) -> Result<(), DriverError> {
+        let mut controller = NVME_CONTROLLER.lock();
+        let ids: Vec<u32> = controller.get_namespaces().iter().map(|ns| ns.id).collect();
+        for id in ids {
+            let _ = controller.flush(id);
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
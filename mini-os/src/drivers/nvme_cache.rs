@@ -1,11 +1,13 @@
 /// Module d'intégration NVMe avec Buffer Cache
 /// 
-/// Fournit une couche d'abstraction qui combine NVMe et cache
+/// Fournit une couche d'abstraction qui combine le disque racine de la
+/// couche bloc (NVMe par défaut) et le cache
 
 use alloc::vec;
 use alloc::vec::Vec;
 use spin::Mutex;
 use super::nvme::{NVME_CONTROLLER, NVMeError};
+use super::block;
 use crate::fs::cache::{BUFFER_CACHE, READAHEAD_MANAGER, WRITEBACK_DAEMON};
 
 /// Taille d'un bloc (aligné sur NVMe et cache)
@@ -56,8 +58,8 @@ impl CachedStorage {
         self.cache_misses += 1;
         drop(cache);
         
-        // 2. Lire depuis NVMe
-        let data = self.read_from_nvme(block_num)?;
+        // 2. Lire depuis le disque
+        let data = self.read_from_disk(block_num)?;
         
         // 3. Stocker dans le cache
         BUFFER_CACHE.lock().write_block(block_num, data.clone());
@@ -84,10 +86,16 @@ impl CachedStorage {
         Ok(())
     }
     
-    /// Lit depuis NVMe (sans cache)
-    fn read_from_nvme(&self, block_num: u64) -> Result<Vec<u8>, StorageError> {
+    /// Lit depuis le disque (sans cache) : disque racine de la couche bloc, NVMe sinon
+    fn read_from_disk(&self, block_num: u64) -> Result<Vec<u8>, StorageError> {
         let mut buffer = vec![0u8; BLOCK_SIZE];
         
+        if let Some(disk) = block::root() {
+            let lba = block_num * (BLOCK_SIZE / disk.block_size()) as u64;
+            disk.read_blocks(lba, &mut buffer).map_err(|_| StorageError::ReadError)?;
+            return Ok(buffer);
+        }
+        
         // Convertir numéro de bloc cache en LBA NVMe
         let lba = block_num * NVME_BLOCKS_PER_CACHE_BLOCK as u64;
         
@@ -98,12 +106,17 @@ impl CachedStorage {
         Ok(buffer)
     }
     
-    /// Écrit vers NVMe (sans cache)
-    fn write_to_nvme(&self, block_num: u64, data: &[u8]) -> Result<(), StorageError> {
+    /// Écrit vers le disque (sans cache)
+    fn write_to_disk(&self, block_num: u64, data: &[u8]) -> Result<(), StorageError> {
         if data.len() != BLOCK_SIZE {
             return Err(StorageError::InvalidBlockSize);
         }
         
+        if let Some(disk) = block::root() {
+            let lba = block_num * (BLOCK_SIZE / disk.block_size()) as u64;
+            return disk.write_blocks(lba, data).map_err(|_| StorageError::WriteError);
+        }
+        
         let lba = block_num * NVME_BLOCKS_PER_CACHE_BLOCK as u64;
         
         NVME_CONTROLLER.lock()
@@ -116,20 +129,33 @@ impl CachedStorage {
     /// Flush un bloc spécifique vers le disque
     pub fn flush_block(&mut self, block_num: u64) -> Result<(), StorageError> {
         if let Some(data) = BUFFER_CACHE.lock().flush_block(block_num) {
-            self.write_to_nvme(block_num, &data)?;
+            self.write_to_disk(block_num, &data)?;
         }
         Ok(())
     }
     
-    /// Flush tous les blocs dirty
+    /// Flush tous les blocs dirty, dans l'ordre des numéros de bloc et file
+    /// bouchée : les blocs voisins partent en une seule requête
     pub fn flush_all(&mut self) -> Result<(), StorageError> {
-        let blocks = BUFFER_CACHE.lock().flush_all();
+        let mut blocks = BUFFER_CACHE.lock().flush_all();
+        blocks.sort_unstable_by_key(|(block_num, _)| *block_num);
         
+        let disk = block::root();
+        if let Some(disk) = &disk {
+            disk.plug();
+        }
+        let mut result = Ok(());
         for (block_num, data) in blocks {
-            self.write_to_nvme(block_num, &data)?;
+            if let Err(e) = self.write_to_disk(block_num, &data) {
+                result = Err(e);
+                break;
+            }
+        }
+        if let Some(disk) = disk {
+            disk.unplug().map_err(|_| StorageError::WriteError)?;
         }
         
-        Ok(())
+        result
     }
     
     /// Retourne les statistiques
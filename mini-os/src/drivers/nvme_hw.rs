@@ -0,0 +1,325 @@
+/// Accès matériel aux contrôleurs NVMe (PCI classe 01h/08h/02h)
+///
+/// Une paire de files d'administration et une paire de files d'E/S
+/// sont allouées en mémoire DMA. Les complétions sont relevées par
+/// scrutation du bit de phase ; le contrôleur est programmé sans
+/// interruptions (IEN = 0 sur la file de complétion).
+///
+/// Les transferts passent par un tampon de rebond de deux pages,
+/// décrit par PRP1/PRP2 : une commande déplace au plus 8 Kio.
+
+use alloc::vec::Vec;
+use core::ptr::{read_volatile, write_volatile};
+use super::dma::DmaBuffer;
+use super::nvme::{NVMeCommand, NVMeCompletion, NVMeError, NVMeNamespace};
+use super::pci::PciFunction;
+
+/// Entrées par file (admin et E/S)
+const QUEUE_DEPTH: u16 = 64;
+/// Octets au plus par commande d'E/S
+pub const MAX_TRANSFER: usize = 2 * PAGE_SIZE;
+const PAGE_SIZE: usize = 4096;
+/// Itérations de scrutation avant d'abandonner une commande
+const TIMEOUT_SPINS: usize = 10_000_000;
+
+/// Registres du contrôleur (offsets dans la BAR 0)
+mod reg {
+    pub const CAP: usize = 0x00;
+    pub const CC: usize = 0x14;
+    pub const CSTS: usize = 0x1C;
+    pub const AQA: usize = 0x24;
+    pub const ASQ: usize = 0x28;
+    pub const ACQ: usize = 0x30;
+    pub const DOORBELLS: usize = 0x1000;
+
+    pub const CC_EN: u32 = 1 << 0;
+    /// Entrées SQ de 64 octets (2^6), CQ de 16 octets (2^4)
+    pub const CC_QUEUE_SIZES: u32 = (6 << 16) | (4 << 20);
+    pub const CSTS_RDY: u32 = 1 << 0;
+    pub const CSTS_CFS: u32 = 1 << 1;
+}
+
+/// Opcodes d'administration
+mod admin {
+    pub const CREATE_IO_SQ: u8 = 0x01;
+    pub const CREATE_IO_CQ: u8 = 0x05;
+    pub const IDENTIFY: u8 = 0x06;
+
+    pub const CNS_NAMESPACE: u32 = 0x00;
+    pub const CNS_CONTROLLER: u32 = 0x01;
+    pub const CNS_ACTIVE_NAMESPACES: u32 = 0x02;
+}
+
+/// Opcode NVM Flush
+const NVM_FLUSH: u8 = 0x00;
+
+/// Paire file de soumission / file de complétion
+struct QueuePair {
+    id: u16,
+    sq: DmaBuffer,
+    cq: DmaBuffer,
+    sq_tail: u16,
+    cq_head: u16,
+    /// Bit de phase attendu pour la prochaine complétion
+    phase: bool,
+}
+
+impl QueuePair {
+    fn new(id: u16) -> Result<Self, NVMeError> {
+        let depth = QUEUE_DEPTH as usize;
+        Ok(Self {
+            id,
+            sq: DmaBuffer::new(depth * core::mem::size_of::<NVMeCommand>()).ok_or(NVMeError::NotInitialized)?,
+            cq: DmaBuffer::new(depth * core::mem::size_of::<NVMeCompletion>()).ok_or(NVMeError::NotInitialized)?,
+            sq_tail: 0,
+            cq_head: 0,
+            phase: true,
+        })
+    }
+}
+
+/// Contrôleur NVMe programmé
+pub struct NVMeHardware {
+    pub function: PciFunction,
+    regs: usize,
+    /// Écart entre doorbells (4 << CAP.DSTRD)
+    doorbell_stride: usize,
+    admin: QueuePair,
+    io: QueuePair,
+    next_cid: u16,
+    bounce: DmaBuffer,
+}
+
+impl NVMeHardware {
+    fn read32(&self, offset: usize) -> u32 {
+        unsafe { read_volatile((self.regs + offset) as *const u32) }
+    }
+
+    fn write32(&self, offset: usize, value: u32) {
+        unsafe { write_volatile((self.regs + offset) as *mut u32, value) }
+    }
+
+    fn write64(&self, offset: usize, value: u64) {
+        self.write32(offset, value as u32);
+        self.write32(offset + 4, (value >> 32) as u32);
+    }
+
+    fn wait_ready(&self, ready: bool) -> Result<(), NVMeError> {
+        for _ in 0..TIMEOUT_SPINS {
+            let csts = self.read32(reg::CSTS);
+            if csts & reg::CSTS_CFS != 0 {
+                return Err(NVMeError::CommandFailed);
+            }
+            if (csts & reg::CSTS_RDY != 0) == ready {
+                return Ok(());
+            }
+            core::hint::spin_loop();
+        }
+        Err(NVMeError::Timeout)
+    }
+
+    /// Réinitialise le contrôleur, installe les files et crée la paire d'E/S
+    pub fn init(function: PciFunction) -> Result<Self, NVMeError> {
+        let regs = function.bar(0).ok_or(NVMeError::NotInitialized)? as usize;
+        function.enable_bus_master();
+
+        let cap = unsafe { read_volatile((regs + reg::CAP) as *const u64) };
+        let max_entries = (cap & 0xFFFF) as u16 + 1;
+        if max_entries < QUEUE_DEPTH {
+            return Err(NVMeError::NotInitialized);
+        }
+
+        let mut hw = Self {
+            function,
+            regs,
+            doorbell_stride: 4 << ((cap >> 32) & 0xF),
+            admin: QueuePair::new(0)?,
+            io: QueuePair::new(1)?,
+            next_cid: 0,
+            bounce: DmaBuffer::new(MAX_TRANSFER).ok_or(NVMeError::NotInitialized)?,
+        };
+
+        // Désactiver avant de reprogrammer les files d'administration
+        hw.write32(reg::CC, hw.read32(reg::CC) & !reg::CC_EN);
+        hw.wait_ready(false)?;
+
+        let depth = (QUEUE_DEPTH - 1) as u32;
+        hw.write32(reg::AQA, (depth << 16) | depth);
+        hw.write64(reg::ASQ, hw.admin.sq.phys());
+        hw.write64(reg::ACQ, hw.admin.cq.phys());
+        hw.write32(reg::CC, reg::CC_QUEUE_SIZES | reg::CC_EN);
+        hw.wait_ready(true)?;
+
+        // File de complétion d'E/S (contiguë, sans interruption) puis de soumission
+        let mut cmd = NVMeCommand::new();
+        cmd.opcode = admin::CREATE_IO_CQ;
+        cmd.prp1 = hw.io.cq.phys();
+        cmd.cdw10 = (depth << 16) | hw.io.id as u32;
+        cmd.cdw11 = 1;
+        hw.admin_command(cmd)?;
+
+        let mut cmd = NVMeCommand::new();
+        cmd.opcode = admin::CREATE_IO_SQ;
+        cmd.prp1 = hw.io.sq.phys();
+        cmd.cdw10 = (depth << 16) | hw.io.id as u32;
+        cmd.cdw11 = ((hw.io.id as u32) << 16) | 1;
+        hw.admin_command(cmd)?;
+
+        Ok(hw)
+    }
+
+    fn doorbell(&self, queue: u16, completion: bool) -> usize {
+        reg::DOORBELLS + (2 * queue as usize + completion as usize) * self.doorbell_stride
+    }
+
+    /// Soumet une commande et attend sa complétion
+    fn execute(&mut self, io: bool, mut cmd: NVMeCommand) -> Result<u32, NVMeError> {
+        cmd.cid = self.next_cid;
+        self.next_cid = self.next_cid.wrapping_add(1);
+
+        let (sq_doorbell, cq_doorbell) = {
+            let q = if io { &self.io } else { &self.admin };
+            (self.doorbell(q.id, false), self.doorbell(q.id, true))
+        };
+        let q = if io { &mut self.io } else { &mut self.admin };
+
+        unsafe { write_volatile(q.sq.as_ptr::<NVMeCommand>(q.sq_tail as usize * 64), cmd) };
+        q.sq_tail = (q.sq_tail + 1) % QUEUE_DEPTH;
+        let tail = q.sq_tail as u32;
+        let regs = self.regs;
+        unsafe { write_volatile((regs + sq_doorbell) as *mut u32, tail) };
+
+        let q = if io { &mut self.io } else { &mut self.admin };
+        for _ in 0..TIMEOUT_SPINS {
+            let entry = unsafe { read_volatile(q.cq.as_ptr::<NVMeCompletion>(q.cq_head as usize * 16)) };
+            if (entry.status & 1 != 0) != q.phase {
+                core::hint::spin_loop();
+                continue;
+            }
+
+            q.cq_head += 1;
+            if q.cq_head == QUEUE_DEPTH {
+                q.cq_head = 0;
+                q.phase = !q.phase;
+            }
+            unsafe { write_volatile((regs + cq_doorbell) as *mut u32, q.cq_head as u32) };
+
+            // Code de statut (bits 15:1), 0 = succès
+            return if entry.cid == cmd.cid && entry.status >> 1 == 0 {
+                Ok(entry.result)
+            } else {
+                Err(NVMeError::CommandFailed)
+            };
+        }
+        Err(NVMeError::Timeout)
+    }
+
+    fn admin_command(&mut self, cmd: NVMeCommand) -> Result<u32, NVMeError> {
+        self.execute(false, cmd)
+    }
+
+    /// IDENTIFY dans le tampon de rebond
+    fn identify(&mut self, cns: u32, nsid: u32) -> Result<&[u8], NVMeError> {
+        let mut cmd = NVMeCommand::new();
+        cmd.opcode = admin::IDENTIFY;
+        cmd.nsid = nsid;
+        cmd.prp1 = self.bounce.phys();
+        cmd.cdw10 = cns;
+        self.admin_command(cmd)?;
+        Ok(&self.bounce.as_slice()[..PAGE_SIZE])
+    }
+
+    /// Découvre les namespaces actifs (taille et format de bloc)
+    pub fn namespaces(&mut self) -> Result<Vec<NVMeNamespace>, NVMeError> {
+        let count = {
+            let data = self.identify(admin::CNS_CONTROLLER, 0)?;
+            u32::from_le_bytes([data[516], data[517], data[518], data[519]])
+        };
+
+        let ids: Vec<u32> = self
+            .identify(admin::CNS_ACTIVE_NAMESPACES, 0)?
+            .chunks_exact(4)
+            .map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+            .take_while(|&id| id != 0)
+            .take(count as usize)
+            .collect();
+
+        let mut namespaces = Vec::new();
+        for id in ids {
+            let data = self.identify(admin::CNS_NAMESPACE, id)?;
+            let mut ns = NVMeNamespace::new(id);
+            ns.size_blocks = u64::from_le_bytes(data[0..8].try_into().unwrap());
+            ns.block_size = parse_block_size(data);
+            ns.active = ns.size_blocks != 0;
+            namespaces.push(ns);
+        }
+        Ok(namespaces)
+    }
+
+    /// Lit ou écrit au plus `MAX_TRANSFER` octets
+    pub fn transfer(&mut self, nsid: u32, lba: u64, block_size: usize, read: Option<&mut [u8]>, write: Option<&[u8]>) -> Result<(), NVMeError> {
+        let bytes = read.as_ref().map(|b| b.len()).or(write.map(|b| b.len())).unwrap_or(0);
+        if bytes == 0 || bytes > MAX_TRANSFER || bytes % block_size != 0 {
+            return Err(NVMeError::BufferTooSmall);
+        }
+        let count = (bytes / block_size) as u16;
+        let prp1 = self.bounce.phys();
+
+        let mut cmd = match write {
+            Some(data) => {
+                self.bounce.as_mut_slice()[..bytes].copy_from_slice(data);
+                NVMeCommand::write(nsid, lba, count - 1, prp1)
+            }
+            None => NVMeCommand::read(nsid, lba, count - 1, prp1),
+        };
+        if bytes > PAGE_SIZE {
+            cmd.prp2 = prp1 + PAGE_SIZE as u64;
+        }
+        self.execute(true, cmd)?;
+
+        if let Some(out) = read {
+            out.copy_from_slice(&self.bounce.as_slice()[..bytes]);
+        }
+        Ok(())
+    }
+
+    /// Vide le cache d'écriture du namespace
+    pub fn flush(&mut self, nsid: u32) -> Result<(), NVMeError> {
+        let mut cmd = NVMeCommand::new();
+        cmd.opcode = NVM_FLUSH;
+        cmd.nsid = nsid;
+        self.execute(true, cmd).map(|_| ())
+    }
+}
+
+/// Taille de bloc du format LBA courant (FLBAS, puis LBAF[n].LBADS)
+pub fn parse_block_size(identify_ns: &[u8]) -> usize {
+    let format = (identify_ns[26] & 0xF) as usize;
+    let offset = 128 + format * 4;
+    let lbads = identify_ns[offset + 2];
+    1usize << lbads
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_parse_block_size() {
+        let mut data = [0u8; 4096];
+        // Format 1 sélectionné, LBADS = 12 (4 Kio)
+        data[26] = 1;
+        data[128 + 4 + 2] = 12;
+        data[128 + 2] = 9;
+        assert_eq!(parse_block_size(&data), 4096);
+        data[26] = 0;
+        assert_eq!(parse_block_size(&data), 512);
+    }
+
+    #[test_case]
+    fn test_queue_entry_sizes() {
+        // Tailles imposées par CC.IOSQES / CC.IOCQES
+        assert_eq!(core::mem::size_of::<NVMeCommand>(), 64);
+        assert_eq!(core::mem::size_of::<NVMeCompletion>(), 16);
+    }
+}
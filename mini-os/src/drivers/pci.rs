@@ -0,0 +1,336 @@
+/// Accès à l'espace de configuration PCI pour les drivers
+///
+/// Version réduite de l'énumérateur du gestionnaire de périphériques,
+/// utilisable depuis la bibliothèque : recherche par classe ou par
+/// identifiants, lecture des BAR et activation du bus mastering (DMA).
+/// Les capacités MSI et MSI-X sont décodées ici ; le choix des vecteurs
+/// revient à `interrupts::irq::request_msi`.
+
+use alloc::vec::Vec;
+use x86_64::instructions::port::Port;
+
+const CONFIG_ADDRESS: u16 = 0xCF8;
+const CONFIG_DATA: u16 = 0xCFC;
+
+/// Registre de commande : décodage mémoire et bus master
+const COMMAND_IO: u16 = 1 << 0;
+const COMMAND_MEMORY: u16 = 1 << 1;
+const COMMAND_BUS_MASTER: u16 = 1 << 2;
+/// Registre de commande : INTx désactivée
+const COMMAND_INTX_DISABLE: u32 = 1 << 10;
+/// Registre d'état : liste de capacités présente
+const STATUS_CAP_LIST: u32 = 1 << 20;
+
+/// Identifiants de capacités
+pub const CAP_MSI: u8 = 0x05;
+pub const CAP_MSIX: u8 = 0x11;
+
+/// Fonction PCI détectée
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PciFunction {
+    pub bus: u8,
+    pub slot: u8,
+    pub function: u8,
+    pub vendor_id: u16,
+    pub device_id: u16,
+    pub class: u8,
+    pub subclass: u8,
+    pub prog_if: u8,
+}
+
+fn config_address(bus: u8, slot: u8, function: u8, offset: u8) -> u32 {
+    0x8000_0000
+        | ((bus as u32) << 16)
+        | ((slot as u32 & 0x1F) << 11)
+        | ((function as u32 & 0x07) << 8)
+        | (offset as u32 & 0xFC)
+}
+
+/// Lit un mot de 32 bits de l'espace de configuration
+pub fn read_config(bus: u8, slot: u8, function: u8, offset: u8) -> u32 {
+    unsafe {
+        Port::<u32>::new(CONFIG_ADDRESS).write(config_address(bus, slot, function, offset));
+        Port::<u32>::new(CONFIG_DATA).read()
+    }
+}
+
+/// Écrit un mot de 32 bits de l'espace de configuration
+pub fn write_config(bus: u8, slot: u8, function: u8, offset: u8, value: u32) {
+    unsafe {
+        Port::<u32>::new(CONFIG_ADDRESS).write(config_address(bus, slot, function, offset));
+        Port::<u32>::new(CONFIG_DATA).write(value);
+    }
+}
+
+impl PciFunction {
+    /// Lit l'en-tête d'une fonction (None si absente)
+    pub fn probe(bus: u8, slot: u8, function: u8) -> Option<Self> {
+        let id = read_config(bus, slot, function, 0x00);
+        if id & 0xFFFF == 0xFFFF {
+            return None;
+        }
+        let class = read_config(bus, slot, function, 0x08);
+        Some(Self {
+            bus,
+            slot,
+            function,
+            vendor_id: id as u16,
+            device_id: (id >> 16) as u16,
+            class: (class >> 24) as u8,
+            subclass: (class >> 16) as u8,
+            prog_if: (class >> 8) as u8,
+        })
+    }
+
+    pub fn read(&self, offset: u8) -> u32 {
+        read_config(self.bus, self.slot, self.function, offset)
+    }
+
+    pub fn write(&self, offset: u8, value: u32) {
+        write_config(self.bus, self.slot, self.function, offset, value)
+    }
+
+    /// Adresse physique d'une BAR mémoire (BAR 64 bits gérées), None pour une BAR d'E/S
+    pub fn bar(&self, index: u8) -> Option<u64> {
+        let offset = 0x10 + index * 4;
+        let low = self.read(offset);
+        if low & 1 != 0 {
+            return None;
+        }
+        let mut addr = (low & !0xF) as u64;
+        if (low >> 1) & 0b11 == 0b10 {
+            addr |= (self.read(offset + 4) as u64) << 32;
+        }
+        Some(addr)
+    }
+
+    /// Port de base d'une BAR d'E/S, None pour une BAR mémoire
+    pub fn io_bar(&self, index: u8) -> Option<u16> {
+        let low = self.read(0x10 + index * 4);
+        if low & 1 == 0 {
+            return None;
+        }
+        Some((low & !0x3) as u16)
+    }
+
+    /// Ligne d'interruption legacy programmée par le firmware
+    pub fn interrupt_line(&self) -> u8 {
+        self.read(0x3C) as u8
+    }
+
+    /// Active le décodage E/S et mémoire et le DMA (bus master)
+    pub fn enable_bus_master(&self) {
+        let reg = self.read(0x04);
+        let command = reg as u16 | COMMAND_IO | COMMAND_MEMORY | COMMAND_BUS_MASTER;
+        self.write(0x04, (reg & 0xFFFF_0000) | command as u32);
+    }
+
+    /// Autorise ou coupe l'interruption legacy (INTx)
+    pub fn set_intx(&self, enabled: bool) {
+        // Les bits d'état sont effacés en y écrivant 1 : on ne réécrit que la commande
+        let command = self.read(0x04) & 0xFFFF;
+        self.write(0x04, if enabled { command & !COMMAND_INTX_DISABLE } else { command | COMMAND_INTX_DISABLE });
+    }
+
+    /// Position d'une capacité dans l'espace de configuration
+    pub fn capability(&self, id: u8) -> Option<u8> {
+        if self.read(0x04) & STATUS_CAP_LIST == 0 {
+            return None;
+        }
+        let mut offset = (self.read(0x34) & 0xFC) as u8;
+        // Borne : 48 capacités au plus dans 256 octets
+        for _ in 0..48 {
+            if offset < 0x40 {
+                return None;
+            }
+            let header = self.read(offset);
+            if header as u8 == id {
+                return Some(offset);
+            }
+            offset = ((header >> 8) & 0xFC) as u8;
+        }
+        None
+    }
+}
+
+/// Capacité MSI : un message (adresse, donnée) pour toute la fonction
+#[derive(Debug, Clone, Copy)]
+pub struct Msi {
+    function: PciFunction,
+    offset: u8,
+}
+
+impl Msi {
+    const CONTROL_ENABLE: u32 = 1 << 16;
+    const CONTROL_64BIT: u32 = 1 << 23;
+    /// Multiple Message Enable (bits 4-6 du contrôle)
+    const CONTROL_MME: u32 = 0x7 << 20;
+
+    pub fn find(function: PciFunction) -> Option<Self> {
+        function.capability(CAP_MSI).map(|offset| Self { function, offset })
+    }
+
+    fn control(&self) -> u32 {
+        self.function.read(self.offset)
+    }
+
+    /// Adresse sur 64 bits
+    pub fn is_64bit(&self) -> bool {
+        self.control() & Self::CONTROL_64BIT != 0
+    }
+
+    /// Nombre de messages demandés par la fonction (Multiple Message Capable)
+    pub fn vectors(&self) -> usize {
+        1 << ((self.control() >> 17) & 0x7)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.control() & Self::CONTROL_ENABLE != 0
+    }
+
+    /// Programme le message et active MSI (un seul vecteur)
+    pub fn enable(&self, address: u64, data: u16) {
+        let offset = self.offset;
+        self.function.write(offset + 4, address as u32);
+        if self.is_64bit() {
+            self.function.write(offset + 8, (address >> 32) as u32);
+            self.function.write(offset + 12, data as u32);
+        } else {
+            self.function.write(offset + 8, data as u32);
+        }
+        let control = self.control() & !Self::CONTROL_MME;
+        self.function.write(offset, control | Self::CONTROL_ENABLE);
+    }
+
+    pub fn disable(&self) {
+        self.function.write(self.offset, self.control() & !Self::CONTROL_ENABLE);
+    }
+}
+
+/// Capacité MSI-X : une table de messages dans une BAR mémoire
+#[derive(Debug, Clone, Copy)]
+pub struct MsiX {
+    function: PciFunction,
+    offset: u8,
+    /// Adresse de la table (BAR + décalage)
+    table: u64,
+    size: u16,
+}
+
+impl MsiX {
+    const CONTROL_ENABLE: u32 = 1 << 31;
+    const CONTROL_FUNCTION_MASK: u32 = 1 << 30;
+    const ENTRY_SIZE: u64 = 16;
+    const VECTOR_MASKED: u32 = 1 << 0;
+
+    pub fn find(function: PciFunction) -> Option<Self> {
+        let offset = function.capability(CAP_MSIX)?;
+        let control = function.read(offset);
+        let table = function.read(offset + 4);
+        let bar = function.bar((table & 0x7) as u8)?;
+        Some(Self {
+            function,
+            offset,
+            table: bar + (table & !0x7) as u64,
+            size: ((control >> 16) & 0x7FF) as u16 + 1,
+        })
+    }
+
+    /// Nombre d'entrées de la table
+    pub fn table_size(&self) -> u16 {
+        self.size
+    }
+
+    fn entry(&self, index: u16) -> *mut u32 {
+        (self.table + index as u64 * Self::ENTRY_SIZE) as *mut u32
+    }
+
+    /// Programme une entrée et la démasque
+    pub fn set_entry(&self, index: u16, address: u64, data: u32) {
+        if index >= self.size {
+            return;
+        }
+        let entry = self.entry(index);
+        unsafe {
+            core::ptr::write_volatile(entry.add(3), Self::VECTOR_MASKED);
+            core::ptr::write_volatile(entry, address as u32);
+            core::ptr::write_volatile(entry.add(1), (address >> 32) as u32);
+            core::ptr::write_volatile(entry.add(2), data);
+            core::ptr::write_volatile(entry.add(3), 0);
+        }
+    }
+
+    /// Donnée programmée dans une entrée
+    pub fn entry_data(&self, index: u16) -> Option<u32> {
+        if index >= self.size {
+            return None;
+        }
+        Some(unsafe { core::ptr::read_volatile(self.entry(index).add(2)) })
+    }
+
+    pub fn mask(&self, index: u16, masked: bool) {
+        if index < self.size {
+            let value = if masked { Self::VECTOR_MASKED } else { 0 };
+            unsafe { core::ptr::write_volatile(self.entry(index).add(3), value) };
+        }
+    }
+
+    /// Active MSI-X ; les entrées non programmées restent masquées
+    pub fn enable(&self) {
+        let control = self.function.read(self.offset);
+        self.function.write(self.offset, (control | Self::CONTROL_ENABLE) & !Self::CONTROL_FUNCTION_MASK);
+    }
+
+    pub fn disable(&self) {
+        let control = self.function.read(self.offset);
+        self.function.write(self.offset, control & !Self::CONTROL_ENABLE);
+    }
+}
+
+/// Énumère toutes les fonctions présentes
+pub fn scan() -> Vec<PciFunction> {
+    let mut found = Vec::new();
+    for bus in 0..=255u8 {
+        for slot in 0..32u8 {
+            let first = if let Some(f) = PciFunction::probe(bus, slot, 0) { f } else { continue };
+            found.push(first);
+            let header_type = (read_config(bus, slot, 0, 0x0C) >> 16) as u8;
+            if header_type & 0x80 == 0 {
+                continue;
+            }
+            for function in 1..8u8 {
+                if let Some(f) = PciFunction::probe(bus, slot, function) {
+                    found.push(f);
+                }
+            }
+        }
+    }
+    found
+}
+
+/// Fonctions d'une classe donnée (prog_if ignoré si None)
+pub fn find_by_class(class: u8, subclass: u8, prog_if: Option<u8>) -> Vec<PciFunction> {
+    scan()
+        .into_iter()
+        .filter(|f| f.class == class && f.subclass == subclass && prog_if.map_or(true, |p| f.prog_if == p))
+        .collect()
+}
+
+/// Fonctions correspondant à un couple vendeur/périphérique
+pub fn find_by_id(vendor_id: u16, device_id: u16) -> Vec<PciFunction> {
+    scan()
+        .into_iter()
+        .filter(|f| f.vendor_id == vendor_id && f.device_id == device_id)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_config_address_layout() {
+        assert_eq!(config_address(0, 0, 0, 0), 0x8000_0000);
+        assert_eq!(config_address(1, 2, 3, 0x11), 0x8001_1310);
+    }
+}
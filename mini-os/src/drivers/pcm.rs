@@ -0,0 +1,261 @@
+/// API PCM du noyau
+///
+/// Un seul flux de lecture à la fois. Le flux accepte des échantillons
+/// signés 16 bits, mono ou stéréo, à sa propre fréquence : ils sont
+/// convertis en stéréo à la fréquence du codec (maintien de
+/// l'échantillon, sans filtrage) puis passés au driver AC'97. `write`
+/// bloque jusqu'à ce que tout soit en file ; `drain` attend la fin de la
+/// lecture.
+///
+/// /dev/dsp expose le même flux : PCM 16 bits stéréo petit-boutiste à
+/// 48 kHz.
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, Ordering};
+use spin::Mutex;
+use x86_64::instructions::interrupts::{self, without_interrupts};
+use crate::fs::devfs::{self, DeviceKind, DeviceOps};
+use crate::fs::{VfsError, VfsResult};
+use super::ac97::{self, AC97};
+
+/// Fréquence de /dev/dsp
+pub const DSP_RATE: u32 = 48_000;
+
+/// Erreurs de l'API PCM
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PcmError {
+    /// Aucune carte son détectée
+    NoDevice,
+    /// Un flux est déjà ouvert
+    Busy,
+    /// Fréquence ou nombre de voies non géré
+    InvalidFormat,
+}
+
+/// Flux ouvert (un seul à la fois)
+static STREAM_OPEN: AtomicBool = AtomicBool::new(false);
+
+/// Formes d'onde du générateur
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Waveform {
+    Square,
+    Triangle,
+    Sawtooth,
+}
+
+impl Waveform {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "square" | "carre" => Some(Waveform::Square),
+            "triangle" => Some(Waveform::Triangle),
+            "saw" | "sawtooth" => Some(Waveform::Sawtooth),
+            _ => None,
+        }
+    }
+
+    /// Valeur pour une phase dans [0, 65536), entre -amplitude et +amplitude
+    fn sample(&self, phase: u32, amplitude: i16) -> i16 {
+        let amplitude = amplitude as i32;
+        let value = match self {
+            Waveform::Square => if phase < 0x8000 { amplitude } else { -amplitude },
+            Waveform::Sawtooth => (phase as i32 - 0x8000) * amplitude / 0x8000,
+            Waveform::Triangle => {
+                let ramp = if phase < 0x8000 { phase as i32 } else { 0xFFFF - phase as i32 };
+                (ramp - 0x4000) * amplitude / 0x4000
+            }
+        };
+        value as i16
+    }
+}
+
+/// Génère un signal mono de `duration_ms` millisecondes
+pub fn tone(waveform: Waveform, frequency: u32, rate: u32, duration_ms: u32, amplitude: i16) -> Vec<i16> {
+    let count = (rate as u64 * duration_ms as u64 / 1000) as usize;
+    let step = ((frequency as u64) << 16) / rate.max(1) as u64;
+    let mut phase: u64 = 0;
+    let mut samples = Vec::with_capacity(count);
+    for _ in 0..count {
+        samples.push(waveform.sample((phase & 0xFFFF) as u32, amplitude));
+        phase += step;
+    }
+    samples
+}
+
+/// Flux de lecture
+pub struct PcmStream {
+    rate: u32,
+    channels: u8,
+    hw_rate: u32,
+    /// Accumulateur de la conversion de fréquence
+    acc: u32,
+}
+
+/// Ouvre le flux de lecture
+pub fn open(rate: u32, channels: u8) -> Result<PcmStream, PcmError> {
+    if !(1..=2).contains(&channels) || !(4_000..=96_000).contains(&rate) {
+        return Err(PcmError::InvalidFormat);
+    }
+    if without_interrupts(|| AC97.lock().is_none()) {
+        return Err(PcmError::NoDevice);
+    }
+    if STREAM_OPEN.swap(true, Ordering::SeqCst) {
+        return Err(PcmError::Busy);
+    }
+    let mut stream = PcmStream { rate, channels, hw_rate: ac97::DEFAULT_RATE, acc: 0 };
+    stream.set_rate(rate)?;
+    Ok(stream)
+}
+
+/// Attend un peu que le contrôleur consomme des tampons
+fn wait() {
+    if interrupts::are_enabled() {
+        x86_64::instructions::hlt();
+    } else {
+        core::hint::spin_loop();
+    }
+}
+
+impl PcmStream {
+    /// Change la fréquence du flux ; le codec suit s'il gère le taux variable
+    pub fn set_rate(&mut self, rate: u32) -> Result<(), PcmError> {
+        if !(4_000..=96_000).contains(&rate) {
+            return Err(PcmError::InvalidFormat);
+        }
+        self.hw_rate = without_interrupts(|| {
+            let mut guard = AC97.lock();
+            let dev = guard.as_mut().ok_or(PcmError::NoDevice)?;
+            Ok(dev.set_rate(rate).unwrap_or_else(|_| dev.rate()))
+        })?;
+        self.rate = rate;
+        self.acc = 0;
+        Ok(())
+    }
+
+    pub fn rate(&self) -> u32 {
+        self.rate
+    }
+
+    pub fn channels(&self) -> u8 {
+        self.channels
+    }
+
+    /// Convertit des trames du flux en stéréo à la fréquence du codec
+    fn convert(&mut self, samples: &[i16]) -> Vec<i16> {
+        let channels = self.channels as usize;
+        let mut out = Vec::with_capacity(samples.len() * 2);
+        for frame in samples.chunks_exact(channels) {
+            let (left, right) = (frame[0], frame[channels - 1]);
+            self.acc += self.hw_rate;
+            while self.acc >= self.rate {
+                out.push(left);
+                out.push(right);
+                self.acc -= self.rate;
+            }
+        }
+        out
+    }
+
+    /// Met en file des échantillons entrelacés ; bloque tant que les tampons
+    /// du contrôleur sont pleins. Retourne le nombre d'échantillons consommés.
+    pub fn write(&mut self, samples: &[i16]) -> Result<usize, PcmError> {
+        let frames = samples.len() / self.channels as usize * self.channels as usize;
+        let converted = self.convert(&samples[..frames]);
+        let mut offset = 0;
+        while offset < converted.len() {
+            let written = without_interrupts(|| {
+                AC97.lock().as_mut().map(|dev| dev.write(&converted[offset..])).ok_or(PcmError::NoDevice)
+            })?;
+            offset += written;
+            if written == 0 {
+                wait();
+            }
+        }
+        Ok(frames)
+    }
+
+    /// Attend que tous les tampons en file soient joués
+    pub fn drain(&mut self) {
+        loop {
+            let queued = without_interrupts(|| AC97.lock().as_mut().map(|dev| dev.queued_buffers()).unwrap_or(0));
+            if queued == 0 {
+                return;
+            }
+            wait();
+        }
+    }
+}
+
+impl Drop for PcmStream {
+    fn drop(&mut self) {
+        STREAM_OPEN.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Joue une note et attend sa fin
+pub fn play_tone(waveform: Waveform, frequency: u32, duration_ms: u32) -> Result<(), PcmError> {
+    let rate = 22_050;
+    let mut stream = open(rate, 1)?;
+    stream.write(&tone(waveform, frequency, rate, duration_ms, 8000))?;
+    stream.drain();
+    Ok(())
+}
+
+/// Bip court (carré, 880 Hz)
+pub fn beep() -> Result<(), PcmError> {
+    play_tone(Waveform::Square, 880, 150)
+}
+
+/// /dev/dsp : écriture de PCM 16 bits stéréo à 48 kHz
+///
+/// Le flux n'est tenu que le temps d'une écriture (devfs n'a pas de
+/// fermeture), ce qui laisse l'API PCM disponible entre deux `write`.
+pub struct DspDevice;
+
+impl DeviceOps for DspDevice {
+    fn read(&mut self, _offset: u64, _buf: &mut [u8]) -> VfsResult<usize> {
+        // Pas de capture
+        Ok(0)
+    }
+
+    fn write(&mut self, _offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        let mut stream = open(DSP_RATE, 2).map_err(|e| match e {
+            PcmError::Busy => VfsError::PermissionDenied,
+            _ => VfsError::IoError,
+        })?;
+        let samples: Vec<i16> = buf.chunks_exact(2).map(|b| i16::from_le_bytes([b[0], b[1]])).collect();
+        let consumed = stream.write(&samples).map_err(|_| VfsError::IoError)?;
+        Ok(consumed * 2)
+    }
+}
+
+/// Détecte la carte son et crée /dev/dsp
+pub fn init() -> Result<(), PcmError> {
+    ac97::init().map_err(|_| PcmError::NoDevice)?;
+    let _ = devfs::register_device("dsp", DeviceKind::Char, Arc::new(Mutex::new(DspDevice)));
+    Ok(())
+}
+
+// Carte son AC'97 (QEMU `-device AC97`)
+crate::initcall!(driver, "Audio AC'97 (/dev/dsp)", init);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_tone_length_and_shape() {
+        let samples = tone(Waveform::Square, 1000, 8000, 10, 100);
+        assert_eq!(samples.len(), 80);
+        // 8 échantillons par période : 4 hauts puis 4 bas
+        assert_eq!(&samples[..8], &[100, 100, 100, 100, -100, -100, -100, -100]);
+        assert_eq!(Waveform::Triangle.sample(0, 100), -100);
+        assert_eq!(Waveform::Triangle.sample(0x8000, 100), 99);
+    }
+
+    #[test_case]
+    fn test_convert_mono_upsamples_to_stereo() {
+        let mut stream = PcmStream { rate: 24_000, channels: 1, hw_rate: 48_000, acc: 0 };
+        assert_eq!(stream.convert(&[1, 2]), alloc::vec![1, 1, 1, 1, 2, 2, 2, 2]);
+    }
+}
@@ -7,6 +7,7 @@ use alloc::vec::Vec;
 use alloc::string::String;
 use alloc::format;
 use crate::vga_buffer::WRITER;
+use super::usb_protocol::{EndpointDescriptor, SetupPacket};
 
 /// Types de contrôleurs USB
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -225,6 +226,20 @@ pub enum UsbError {
     NotSupported,
 }
 
+/// Transferts offerts par un contrôleur hôte aux drivers de classe
+///
+/// `device` est l'adresse (ou le slot xHCI) attribuée à l'énumération.
+pub trait UsbHostController: Send {
+    /// Transfert de contrôle sur l'endpoint 0 ; retourne les octets transférés
+    fn control_transfer(&mut self, device: u8, setup: &SetupPacket, data: &mut [u8]) -> Result<usize, UsbError>;
+
+    /// Prépare un endpoint d'interruption avant les premiers transferts
+    fn configure_endpoint(&mut self, device: u8, endpoint: &EndpointDescriptor) -> Result<(), UsbError>;
+
+    /// Transfert d'interruption IN non bloquant : `None` si rien n'est arrivé
+    fn poll_interrupt(&mut self, device: u8, endpoint: u8, buf: &mut [u8]) -> Result<Option<usize>, UsbError>;
+}
+
 /// Gestionnaire de contrôleurs USB
 pub struct UsbControllerManager {
     controllers: Vec<UsbController>,
@@ -242,23 +257,33 @@ impl UsbControllerManager {
     pub fn detect_controllers(&mut self) -> Result<usize, UsbError> {
         WRITER.lock().write_string("Détection des contrôleurs USB...\\n");
 
-        // TODO: Scanner le bus PCI pour trouver les contrôleurs USB
-        // Class Code: 0x0C (Serial Bus Controller)
-        // Subclass: 0x03 (USB Controller)
-        // Programming Interface:
-        //   0x00 = UHCI
-        //   0x10 = OHCI
-        //   0x20 = EHCI
-        //   0x30 = XHCI
-
-        // Exemple de contrôleurs détectés (simulation)
-        let ehci = UsbController::new(
-            UsbControllerType::EHCI,
-            (0, 29, 7),
-            0xFEBFF000,
-            23,
-        );
-        self.controllers.push(ehci);
+        // Classe 0x0C (bus série), sous-classe 0x03 (USB) ; l'interface
+        // de programmation donne le type de contrôleur
+        for function in super::pci::find_by_class(0x0C, 0x03, None) {
+            let controller_type = match function.prog_if {
+                0x00 => UsbControllerType::UHCI,
+                0x10 => UsbControllerType::OHCI,
+                0x20 => UsbControllerType::EHCI,
+                0x30 => UsbControllerType::XHCI,
+                _ => continue,
+            };
+            // UHCI utilise un BAR d'E/S (BAR 4), les autres un BAR mémoire
+            let base_address = if controller_type == UsbControllerType::UHCI {
+                function.io_bar(4).map(|port| port as u64)
+            } else {
+                function.bar(0)
+            };
+            let base_address = match base_address {
+                Some(base) => base,
+                None => continue,
+            };
+            self.controllers.push(UsbController::new(
+                controller_type,
+                (function.bus, function.slot, function.function),
+                base_address,
+                function.interrupt_line(),
+            ));
+        }
 
         WRITER.lock().write_string(&format!(
             "{} contrôleur(s) USB détecté(s)\\n",
@@ -1,12 +1,22 @@
 /// USB HID (Human Interface Device) Driver
 /// 
 /// Gère les périphériques HID comme les claviers, souris, etc.
+///
+/// Les interfaces Boot (clavier, souris) sont lues par transferts
+/// d'interruption. Les rapports clavier sont traduits en scancodes du
+/// jeu 1 et passent par le même décodeur que le clavier PS/2 ; les
+/// rapports souris rejoignent la file d'événements de la souris PS/2.
 
 extern crate alloc;
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::string::String;
 use alloc::vec::Vec;
 use alloc::format;
+use spin::Mutex;
+use lazy_static::lazy_static;
+use x86_64::instructions::interrupts::without_interrupts;
 use super::usb_protocol::*;
-use super::usb_controller::UsbError;
+use super::usb_controller::{UsbError, UsbHostController};
 use crate::vga_buffer::WRITER;
 
 /// Classe HID
@@ -188,6 +198,95 @@ impl MouseReport {
     }
 }
 
+/// Sous-classe d'interface « Boot Interface »
+pub const SUBCLASS_BOOT: u8 = 1;
+/// Valeur de SET_PROTOCOL sélectionnant le protocole Boot
+const BOOT_PROTOCOL: u16 = 0;
+/// Usage signalant trop de touches enfoncées (rapport à ignorer)
+const KEY_ERROR_ROLLOVER: u8 = 0x01;
+
+/// Usages HID (page clavier) vers scancodes du jeu 1 ; 0xE0xx : préfixe étendu
+const USAGE_TO_SET1: [u16; 0x53] = [
+    0, 0, 0, 0,
+    // a-z
+    0x1E, 0x30, 0x2E, 0x20, 0x12, 0x21, 0x22, 0x23, 0x17, 0x24, 0x25, 0x26, 0x32,
+    0x31, 0x18, 0x19, 0x10, 0x13, 0x1F, 0x14, 0x16, 0x2F, 0x11, 0x2D, 0x15, 0x2C,
+    // 1-9, 0
+    0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A, 0x0B,
+    // Entrée, Échap, Retour arrière, Tab, Espace, - = [ ] \ # ; ' ` , . /
+    0x1C, 0x01, 0x0E, 0x0F, 0x39, 0x0C, 0x0D, 0x1A, 0x1B, 0x2B, 0x2B, 0x27, 0x28,
+    0x29, 0x33, 0x34, 0x35,
+    // Verr. Maj, F1-F12
+    0x3A, 0x3B, 0x3C, 0x3D, 0x3E, 0x3F, 0x40, 0x41, 0x42, 0x43, 0x44, 0x57, 0x58,
+    // Impr. écran (non traduit), Arrêt défil., Pause (non traduit)
+    0, 0x46, 0,
+    // Inser, Début, Page préc., Suppr, Fin, Page suiv., flèches → ← ↓ ↑
+    0xE052, 0xE047, 0xE049, 0xE053, 0xE04F, 0xE051, 0xE04D, 0xE04B, 0xE050, 0xE048,
+];
+
+/// Modificateurs (bits 0 à 7 du rapport) vers scancodes du jeu 1
+const MODIFIER_TO_SET1: [u16; 8] = [0x1D, 0x2A, 0x38, 0xE05B, 0xE01D, 0x36, 0xE038, 0xE05C];
+
+/// Scancode jeu 1 d'un usage HID clavier
+pub fn usage_to_set1(usage: u8) -> Option<u16> {
+    match USAGE_TO_SET1.get(usage as usize) {
+        Some(&code) if code != 0 => Some(code),
+        _ => None,
+    }
+}
+
+fn push_set1(out: &mut Vec<u8>, code: u16, released: bool) {
+    if code >> 8 == 0xE0 {
+        out.push(0xE0);
+    }
+    let byte = code as u8;
+    out.push(if released { byte | 0x80 } else { byte });
+}
+
+impl KeyboardReport {
+    /// Scancodes jeu 1 (relâchements puis appuis) depuis le rapport précédent
+    ///
+    /// Le clavier USB alimente ainsi le même décodeur que le clavier PS/2.
+    pub fn scancodes_since(&self, previous: &KeyboardReport) -> Vec<u8> {
+        let mut out = Vec::new();
+        let (old_keys, new_keys) = (previous.keycodes, self.keycodes);
+        if new_keys.contains(&KEY_ERROR_ROLLOVER) {
+            return out;
+        }
+        let changed = previous.modifiers ^ self.modifiers;
+
+        for bit in 0..8 {
+            if changed & (1 << bit) != 0 && self.modifiers & (1 << bit) == 0 {
+                push_set1(&mut out, MODIFIER_TO_SET1[bit], true);
+            }
+        }
+        for &usage in old_keys.iter().filter(|&&k| k != 0 && !new_keys.contains(&k)) {
+            if let Some(code) = usage_to_set1(usage) {
+                push_set1(&mut out, code, true);
+            }
+        }
+        for bit in 0..8 {
+            if changed & (1 << bit) != 0 && self.modifiers & (1 << bit) != 0 {
+                push_set1(&mut out, MODIFIER_TO_SET1[bit], false);
+            }
+        }
+        for &usage in new_keys.iter().filter(|&&k| k != 0 && !old_keys.contains(&k)) {
+            if let Some(code) = usage_to_set1(usage) {
+                push_set1(&mut out, code, false);
+            }
+        }
+        out
+    }
+}
+
+impl MouseReport {
+    /// Événement équivalent à celui de la souris PS/2 (y positif vers le haut)
+    pub fn to_event(&self) -> crate::mouse::MouseEvent {
+        let (buttons, x, y, wheel) = (self.buttons, self.x, self.y, self.wheel);
+        crate::mouse::MouseEvent { dx: x as i16, dy: -(y as i16), buttons: buttons & 0x07, wheel }
+    }
+}
+
 /// Driver HID
 pub struct UsbHidDriver {
     /// Type de périphérique HID
@@ -204,6 +303,15 @@ pub struct UsbHidDriver {
     
     /// Taille maximale de paquet
     pub max_packet_size: u16,
+
+    /// Adresse (ou slot) du périphérique sur le contrôleur
+    pub address: u8,
+
+    /// Numéro de l'interface HID
+    pub interface: u8,
+
+    /// Dernier rapport clavier, pour détecter appuis et relâchements
+    last_keyboard: KeyboardReport,
 }
 
 impl UsbHidDriver {
@@ -221,87 +329,122 @@ impl UsbHidDriver {
             endpoint_in,
             poll_interval,
             max_packet_size,
+            address: 0,
+            interface: 0,
+            last_keyboard: KeyboardReport::new(),
         }
     }
 
-    /// Définit le protocole (Boot ou Report)
-    pub fn set_protocol(&mut self, protocol: HidProtocol) -> Result<(), UsbError> {
-        let setup = SetupPacket {
-            request_type: 0x21,  // Host to Device, Class, Interface
-            request: HidRequest::SetProtocol as u8,
-            value: protocol as u16,
-            index: 0,
-            length: 0,
+    /// Driver pour une interface Boot clavier/souris, `None` sinon
+    pub fn probe(address: u8, interface: &InterfaceDescriptor, endpoint: &EndpointDescriptor) -> Option<Self> {
+        if interface.interface_class != 0x03 || interface.interface_subclass != SUBCLASS_BOOT {
+            return None;
+        }
+        if !endpoint.is_in() || endpoint.transfer_type() != TransferType::Interrupt {
+            return None;
+        }
+        let class = match interface.interface_protocol {
+            1 => HidClass::Keyboard,
+            2 => HidClass::Mouse,
+            _ => return None,
         };
-
-        // TODO: Envoyer le Setup Packet
-        WRITER.lock().write_string(&format!(
-            "Définition protocole HID: {:?}\n",
-            protocol
-        ));
-
-        self.protocol = protocol;
-        Ok(())
+        let max_packet_size = endpoint.max_packet_size;
+        let mut driver = Self::new(class, endpoint.endpoint_address, endpoint.interval, max_packet_size);
+        driver.address = address;
+        driver.interface = interface.interface_number;
+        Some(driver)
     }
 
-    /// Définit l'idle rate
-    pub fn set_idle(&mut self, duration: u8, report_id: u8) -> Result<(), UsbError> {
+    fn class_request(&self, host: &mut dyn UsbHostController, request: HidRequest, value: u16, data: &mut [u8]) -> Result<usize, UsbError> {
         let setup = SetupPacket {
-            request_type: 0x21,  // Host to Device, Class, Interface
-            request: HidRequest::SetIdle as u8,
-            value: ((duration as u16) << 8) | (report_id as u16),
-            index: 0,
-            length: 0,
+            request_type: if data.is_empty() { 0x21 } else { 0xA1 },  // Class, Interface
+            request: request as u8,
+            value,
+            index: self.interface as u16,
+            length: data.len() as u16,
         };
+        host.control_transfer(self.address, &setup, data)
+    }
 
-        // TODO: Envoyer le Setup Packet
-        WRITER.lock().write_string("Définition idle rate HID\n");
-
+    /// Passe l'interface en protocole Boot (rapports de format fixe)
+    pub fn set_boot_protocol(&mut self, host: &mut dyn UsbHostController) -> Result<(), UsbError> {
+        self.class_request(host, HidRequest::SetProtocol, BOOT_PROTOCOL, &mut [])?;
         Ok(())
     }
 
-    /// Lit un rapport
-    pub fn get_report(&self, report_type: ReportType, report_id: u8) -> Result<Vec<u8>, UsbError> {
-        let setup = SetupPacket {
-            request_type: 0xA1,  // Device to Host, Class, Interface
-            request: HidRequest::GetReport as u8,
-            value: ((report_type as u16) << 8) | (report_id as u16),
-            index: 0,
-            length: self.max_packet_size,
-        };
+    /// Définit l'idle rate
+    pub fn set_idle(&mut self, host: &mut dyn UsbHostController, duration: u8, report_id: u8) -> Result<(), UsbError> {
+        self.class_request(host, HidRequest::SetIdle, ((duration as u16) << 8) | (report_id as u16), &mut [])?;
+        Ok(())
+    }
 
-        // TODO: Envoyer le Setup Packet et recevoir les données
-        Ok(Vec::new())
+    /// Lit un rapport par l'endpoint de contrôle
+    pub fn get_report(&self, host: &mut dyn UsbHostController, report_type: ReportType, report_id: u8) -> Result<Vec<u8>, UsbError> {
+        let mut data = alloc::vec![0u8; self.max_packet_size as usize];
+        let value = ((report_type as u16) << 8) | (report_id as u16);
+        let len = self.class_request(host, HidRequest::GetReport, value, &mut data)?;
+        data.truncate(len);
+        Ok(data)
     }
 
-    /// Lit un rapport de clavier
-    pub fn read_keyboard(&self) -> Result<KeyboardReport, UsbError> {
-        // TODO: Lire via transfert d'interruption IN
-        let data = [0u8; 8];
-        
-        KeyboardReport::from_bytes(&data).ok_or(UsbError::TransferFailed)
+    /// Traite un rapport reçu sur l'endpoint d'interruption
+    pub fn handle_report(&mut self, data: &[u8]) {
+        match self.device_type {
+            HidClass::Keyboard => {
+                if let Some(report) = KeyboardReport::from_bytes(data) {
+                    for scancode in report.scancodes_since(&self.last_keyboard) {
+                        crate::keyboard::handle_scancode(scancode);
+                    }
+                    if !report.keycodes.contains(&KEY_ERROR_ROLLOVER) {
+                        self.last_keyboard = report;
+                    }
+                }
+            }
+            HidClass::Mouse => {
+                if let Some(report) = MouseReport::from_bytes(data) {
+                    crate::mouse::push_event(report.to_event());
+                }
+            }
+            HidClass::None => {}
+        }
     }
 
-    /// Lit un rapport de souris
-    pub fn read_mouse(&self) -> Result<MouseReport, UsbError> {
-        // TODO: Lire via transfert d'interruption IN
-        let data = [0u8; 4];
-        
-        MouseReport::from_bytes(&data).ok_or(UsbError::TransferFailed)
+    /// Interroge l'endpoint d'interruption ; retourne vrai si un rapport a été traité
+    pub fn poll(&mut self, host: &mut dyn UsbHostController) -> Result<bool, UsbError> {
+        let mut buf = [0u8; 8];
+        let len = (self.max_packet_size as usize).clamp(1, buf.len());
+        match host.poll_interrupt(self.address, self.endpoint_in, &mut buf[..len])? {
+            Some(n) => {
+                self.handle_report(&buf[..n]);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
     }
 
     /// Initialise le driver HID
-    pub fn init(&mut self) -> Result<(), UsbError> {
+    pub fn init(&mut self, host: &mut dyn UsbHostController) -> Result<(), UsbError> {
         WRITER.lock().write_string(&format!(
             "Initialisation HID {:?}...\n",
             self.device_type
         ));
 
         // Définir le protocole Boot
-        self.set_protocol(self.protocol)?;
-
-        // Définir l'idle rate (0 = infini)
-        self.set_idle(0, 0)?;
+        self.set_boot_protocol(host)?;
+
+        // Définir l'idle rate (0 = rapport uniquement sur changement)
+        // Certaines souris refusent SET_IDLE : sans conséquence
+        let _ = self.set_idle(host, 0, 0);
+
+        let endpoint = EndpointDescriptor {
+            length: 7,
+            descriptor_type: DescriptorType::Endpoint as u8,
+            endpoint_address: self.endpoint_in,
+            attributes: 0x03,
+            max_packet_size: self.max_packet_size,
+            interval: self.poll_interval,
+        };
+        host.configure_endpoint(self.address, &endpoint)?;
 
         WRITER.lock().write_string("HID initialisé\n");
 
@@ -309,6 +452,127 @@ impl UsbHidDriver {
     }
 }
 
+/// Branchement ou retrait d'un périphérique HID
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HidHotplugEvent {
+    Attached { name: String, class: HidClass },
+    Detached { name: String },
+}
+
+/// Claviers et souris USB actifs
+pub struct HidRegistry {
+    devices: BTreeMap<String, UsbHidDriver>,
+    events: VecDeque<HidHotplugEvent>,
+    next_keyboard: usize,
+    next_mouse: usize,
+}
+
+impl HidRegistry {
+    pub fn new() -> Self {
+        Self {
+            devices: BTreeMap::new(),
+            events: VecDeque::new(),
+            next_keyboard: 0,
+            next_mouse: 0,
+        }
+    }
+
+    /// Ajoute un driver déjà initialisé ; retourne son nom (usbkbd0, usbmouse0...)
+    pub fn insert(&mut self, driver: UsbHidDriver) -> String {
+        let name = match driver.device_type {
+            HidClass::Keyboard => {
+                self.next_keyboard += 1;
+                format!("usbkbd{}", self.next_keyboard - 1)
+            }
+            _ => {
+                self.next_mouse += 1;
+                format!("usbmouse{}", self.next_mouse - 1)
+            }
+        };
+        self.events.push_back(HidHotplugEvent::Attached { name: name.clone(), class: driver.device_type });
+        self.devices.insert(name.clone(), driver);
+        name
+    }
+
+    /// Retire toutes les interfaces d'un périphérique débranché
+    pub fn remove_address(&mut self, address: u8) -> usize {
+        let names: Vec<String> = self.devices
+            .iter()
+            .filter(|(_, d)| d.address == address)
+            .map(|(name, _)| name.clone())
+            .collect();
+        for name in &names {
+            self.devices.remove(name);
+            self.events.push_back(HidHotplugEvent::Detached { name: name.clone() });
+        }
+        names.len()
+    }
+
+    /// Remet un rapport reçu au driver de l'endpoint concerné
+    pub fn dispatch(&mut self, address: u8, endpoint: u8, data: &[u8]) -> bool {
+        match self.devices.values_mut().find(|d| d.address == address && d.endpoint_in == endpoint) {
+            Some(driver) => {
+                driver.handle_report(data);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.devices.len()
+    }
+}
+
+lazy_static! {
+    /// Périphériques HID attachés, tous contrôleurs confondus
+    pub static ref HID_DEVICES: Mutex<HidRegistry> = Mutex::new(HidRegistry::new());
+}
+
+/// Initialise un driver HID et l'ajoute aux périphériques actifs
+pub fn attach_hid(host: &mut dyn UsbHostController, mut driver: UsbHidDriver) -> Result<String, UsbError> {
+    driver.init(host)?;
+    Ok(without_interrupts(|| HID_DEVICES.lock().insert(driver)))
+}
+
+/// Détache les interfaces HID d'un périphérique débranché
+pub fn detach_hid_address(address: u8) -> usize {
+    without_interrupts(|| HID_DEVICES.lock().remove_address(address))
+}
+
+/// Interroge tous les endpoints HID d'un contrôleur sans interruptions
+///
+/// Appelée aussi depuis l'interruption du contrôleur : le registre déjà
+/// verrouillé ailleurs est laissé pour le prochain passage.
+pub fn poll_hid_devices(host: &mut dyn UsbHostController) -> usize {
+    without_interrupts(|| {
+        let mut registry = match HID_DEVICES.try_lock() {
+            Some(registry) => registry,
+            None => return 0,
+        };
+        let mut reports = 0;
+        for driver in registry.devices.values_mut() {
+            if let Ok(true) = driver.poll(host) {
+                reports += 1;
+            }
+        }
+        reports
+    })
+}
+
+/// Rapport signalé par l'interruption du contrôleur (fin de transfert)
+pub fn dispatch_hid_report(address: u8, endpoint: u8, data: &[u8]) -> bool {
+    match HID_DEVICES.try_lock() {
+        Some(mut registry) => registry.dispatch(address, endpoint, data),
+        None => false,
+    }
+}
+
+/// Événements de branchement en attente pour le gestionnaire de périphériques
+pub fn take_hid_hotplug_events() -> Vec<HidHotplugEvent> {
+    without_interrupts(|| HID_DEVICES.lock().events.drain(..).collect())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -360,4 +624,24 @@ mod tests {
         assert_eq!(driver.protocol, HidProtocol::Keyboard);
         assert_eq!(driver.endpoint_in, 0x81);
     }
+
+    #[test_case]
+    fn test_keyboard_report_to_scancodes() {
+        let empty = KeyboardReport::new();
+        // Maj gauche + 'a' puis relâchement du seul 'a'
+        let pressed = KeyboardReport::from_bytes(&[0x02, 0, 0x04, 0, 0, 0, 0, 0]).unwrap();
+        assert_eq!(pressed.scancodes_since(&empty), alloc::vec![0x2A, 0x1E]);
+        let released = KeyboardReport::from_bytes(&[0x02, 0, 0, 0, 0, 0, 0, 0]).unwrap();
+        assert_eq!(released.scancodes_since(&pressed), alloc::vec![0x9E]);
+        // Flèche haut : préfixe étendu
+        let up = KeyboardReport::from_bytes(&[0, 0, 0x52, 0, 0, 0, 0, 0]).unwrap();
+        assert_eq!(up.scancodes_since(&empty), alloc::vec![0xE0, 0x48]);
+    }
+
+    #[test_case]
+    fn test_mouse_report_to_event() {
+        let report = MouseReport::from_bytes(&[0x02, 5, 3, 0xFF]).unwrap();
+        let event = report.to_event();
+        assert_eq!((event.dx, event.dy, event.buttons, event.wheel), (5, -3, 0x02, -1));
+    }
 }
@@ -7,6 +7,7 @@ use alloc::format;
 
 use alloc::vec::Vec;
 use alloc::string::String;
+use super::usb_controller::UsbHostController;
 
 /// Erreurs USB
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -309,51 +310,130 @@ impl UsbTransfer {
     }
 }
 
+impl From<super::usb_controller::UsbError> for UsbError {
+    fn from(err: super::usb_controller::UsbError) -> Self {
+        use super::usb_controller::UsbError as HostError;
+        match err {
+            HostError::Timeout => UsbError::Timeout,
+            HostError::NotSupported => UsbError::NotSupported,
+            HostError::NotFound | HostError::InvalidPort => UsbError::NotFound,
+            HostError::InitializationFailed => UsbError::DeviceNotResponding,
+            HostError::TransferFailed | HostError::Stall => UsbError::TransferFailed,
+        }
+    }
+}
+
+/// Interface et ses endpoints, dans l'ordre du descripteur de configuration
+pub type InterfaceEndpoints = (InterfaceDescriptor, Vec<EndpointDescriptor>);
+
+/// Périphérique énuméré et configuré
+pub struct EnumeratedDevice {
+    pub address: u8,
+    pub descriptor: DeviceDescriptor,
+    pub configuration: ConfigurationDescriptor,
+    pub interfaces: Vec<InterfaceEndpoints>,
+}
+
+/// Lit un descripteur `#[repr(packed)]` en tête de `data`
+fn read_struct<T: Copy>(data: &[u8]) -> Option<T> {
+    if data.len() < core::mem::size_of::<T>() {
+        return None;
+    }
+    Some(unsafe { core::ptr::read_unaligned(data.as_ptr() as *const T) })
+}
+
+/// Découpe un descripteur de configuration complet en interfaces et endpoints
+pub fn parse_configuration(data: &[u8]) -> Result<(ConfigurationDescriptor, Vec<InterfaceEndpoints>), UsbError> {
+    let config: ConfigurationDescriptor = read_struct(data).ok_or(UsbError::InvalidDescriptor)?;
+    if config.descriptor_type != DescriptorType::Configuration as u8 {
+        return Err(UsbError::InvalidDescriptor);
+    }
+
+    let total = (config.total_length as usize).min(data.len());
+    let mut interfaces: Vec<InterfaceEndpoints> = Vec::new();
+    let mut offset = config.length as usize;
+    while offset + 2 <= total {
+        let length = data[offset] as usize;
+        if length < 2 || offset + length > total {
+            return Err(UsbError::InvalidDescriptor);
+        }
+        let descriptor = &data[offset..offset + length];
+        match descriptor[1] {
+            t if t == DescriptorType::Interface as u8 => {
+                let interface = read_struct(descriptor).ok_or(UsbError::InvalidDescriptor)?;
+                interfaces.push((interface, Vec::new()));
+            }
+            t if t == DescriptorType::Endpoint as u8 => {
+                let endpoint = read_struct(descriptor).ok_or(UsbError::InvalidDescriptor)?;
+                if let Some((_, endpoints)) = interfaces.last_mut() {
+                    endpoints.push(endpoint);
+                }
+            }
+            // Descripteurs de classe (HID...) : ignorés ici
+            _ => {}
+        }
+        offset += length;
+    }
+    Ok((config, interfaces))
+}
+
 /// Énumération USB
+///
+/// Indépendante du contrôleur : le contrôleur hôte a déjà réinitialisé
+/// le port et attribué l'adresse (ou le slot) `address`.
 pub struct UsbEnumerator;
 
 impl UsbEnumerator {
-    /// Énumère un périphérique USB
-    pub fn enumerate_device(port: u8) -> Result<DeviceDescriptor, UsbError> {
-        // TODO: Implémenter l'énumération complète
-        // 1. Réinitialiser le port
-        // 2. Attendre que le périphérique soit prêt
-        // 3. Obtenir les 8 premiers octets du descripteur de périphérique
-        // 4. Réinitialiser à nouveau
-        // 5. Assigner une adresse
-        // 6. Obtenir le descripteur de périphérique complet
-        // 7. Obtenir le descripteur de configuration
-        // 8. Configurer le périphérique
-
-        Ok(DeviceDescriptor::new())
+    /// Lit les descripteurs puis active la première configuration
+    pub fn enumerate_device(host: &mut dyn UsbHostController, address: u8) -> Result<EnumeratedDevice, UsbError> {
+        let data = Self::read_descriptor(host, address, DescriptorType::Device, 0, 18)?;
+        let descriptor: DeviceDescriptor = read_struct(&data).ok_or(UsbError::InvalidDescriptor)?;
+
+        let header = Self::read_descriptor(host, address, DescriptorType::Configuration, 0, 9)?;
+        let config: ConfigurationDescriptor = read_struct(&header).ok_or(UsbError::InvalidDescriptor)?;
+        let full = Self::read_descriptor(host, address, DescriptorType::Configuration, 0, config.total_length)?;
+        let (configuration, interfaces) = parse_configuration(&full)?;
+
+        let setup = SetupPacket::set_configuration(configuration.configuration_value);
+        host.control_transfer(address, &setup, &mut [])?;
+
+        Ok(EnumeratedDevice { address, descriptor, configuration, interfaces })
     }
 
     /// Lit un descripteur
     pub fn read_descriptor(
+        host: &mut dyn UsbHostController,
+        address: u8,
         descriptor_type: DescriptorType,
         index: u8,
         length: u16,
     ) -> Result<Vec<u8>, UsbError> {
-        // TODO: Implémenter la lecture de descripteur
-        // 1. Créer un Setup Packet
-        // 2. Effectuer un transfert de contrôle
-        // 3. Retourner les données
-
-        Ok(Vec::new())
+        let setup = SetupPacket::get_descriptor(descriptor_type, index, length);
+        let mut data = alloc::vec![0u8; length as usize];
+        let len = host.control_transfer(address, &setup, &mut data)?;
+        data.truncate(len);
+        Ok(data)
     }
 
-    /// Lit une chaîne de caractères
-    pub fn read_string(index: u8) -> Result<String, UsbError> {
+    /// Lit une chaîne de caractères (anglais US, UTF-16LE)
+    pub fn read_string(host: &mut dyn UsbHostController, address: u8, index: u8) -> Result<String, UsbError> {
         if index == 0 {
             return Ok(String::new());
         }
 
-        // TODO: Implémenter la lecture de string descriptor
-        // 1. Lire le descripteur de chaîne
-        // 2. Décoder l'UTF-16LE
-        // 3. Retourner la chaîne
-
-        Ok(String::from("USB Device"))
+        let mut setup = SetupPacket::get_descriptor(DescriptorType::String, index, 255);
+        setup.index = 0x0409;
+        let mut data = alloc::vec![0u8; 255];
+        let len = host.control_transfer(address, &setup, &mut data)?;
+        if len < 2 || data[1] != DescriptorType::String as u8 {
+            return Err(UsbError::InvalidDescriptor);
+        }
+        let end = len.min(data[0] as usize).max(2);
+        let units: Vec<u16> = data[2..end]
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect();
+        Ok(core::char::decode_utf16(units).map(|c| c.unwrap_or('?')).collect())
     }
 }
 
@@ -417,4 +497,24 @@ mod tests {
         assert_eq!(transfer.get_data().len(), 5);
         assert_eq!(transfer.packet_count(), 1);
     }
+
+    #[test_case]
+    fn test_parse_configuration() {
+        // Configuration, interface HID clavier (Boot), descripteur HID, endpoint 0x81
+        let data = [
+            9, 2, 34, 0, 1, 1, 0, 0xA0, 50,
+            9, 4, 0, 0, 1, 3, 1, 1, 0,
+            9, 0x21, 0x11, 0x01, 0, 1, 0x22, 63, 0,
+            7, 5, 0x81, 3, 8, 0, 10,
+        ];
+        let (config, interfaces) = parse_configuration(&data).unwrap();
+        let value = config.configuration_value;
+        assert_eq!(value, 1);
+        assert_eq!(interfaces.len(), 1);
+        let (interface, endpoints) = &interfaces[0];
+        let class = interface.interface_class;
+        assert_eq!(class, 3);
+        assert_eq!(endpoints.len(), 1);
+        assert_eq!(endpoints[0].transfer_type(), TransferType::Interrupt);
+    }
 }
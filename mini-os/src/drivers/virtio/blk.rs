@@ -0,0 +1,188 @@
+/// virtio-blk : disque paravirtualisé
+///
+/// Une requête est une chaîne de trois descripteurs : en-tête (type,
+/// secteur), données, octet de statut écrit par le périphérique. Les
+/// trois parties vivent dans un même tampon DMA ; une requête transfère
+/// au plus `MAX_TRANSFER` octets et l'attente se fait par scrutation de
+/// l'anneau used.
+
+use alloc::format;
+use alloc::sync::Arc;
+use spin::Mutex;
+use crate::drivers::block::{self, BlockDevice};
+use crate::drivers::disk::DiskError;
+use crate::drivers::dma::DmaBuffer;
+use super::{Segment, VirtioError, VirtioPci, Virtqueue, DEVICE_BLK};
+
+const SECTOR_SIZE: usize = 512;
+/// Octets de données par requête (tampon total de 32 Kio)
+const MAX_TRANSFER: usize = 7 * 4096;
+/// Itérations de scrutation avant d'abandonner une requête
+const TIMEOUT_SPINS: usize = 10_000_000;
+
+/// Types de requête
+const VIRTIO_BLK_T_IN: u32 = 0;
+const VIRTIO_BLK_T_OUT: u32 = 1;
+const VIRTIO_BLK_T_FLUSH: u32 = 4;
+
+/// Fonctionnalité : commande FLUSH disponible
+const VIRTIO_BLK_F_FLUSH: u32 = 1 << 9;
+/// Fonctionnalité : disque en lecture seule
+const VIRTIO_BLK_F_RO: u32 = 1 << 5;
+
+/// Disposition du tampon de requête
+const HEADER_OFFSET: usize = 0;
+const STATUS_OFFSET: usize = 16;
+const DATA_OFFSET: usize = 4096;
+
+/// En-tête de requête
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct BlkRequestHeader {
+    pub kind: u32,
+    _reserved: u32,
+    pub sector: u64,
+}
+
+impl BlkRequestHeader {
+    pub fn new(kind: u32, sector: u64) -> Self {
+        Self { kind, _reserved: 0, sector }
+    }
+}
+
+/// Disque virtio-blk
+pub struct VirtioBlk {
+    dev: VirtioPci,
+    queue: Virtqueue,
+    buffer: DmaBuffer,
+    sectors: u64,
+    read_only: bool,
+    can_flush: bool,
+}
+
+impl VirtioBlk {
+    pub fn new(dev: VirtioPci) -> Result<Self, VirtioError> {
+        let features = dev.negotiate(VIRTIO_BLK_F_FLUSH | VIRTIO_BLK_F_RO);
+        let queue = match dev.setup_queue(0) {
+            Ok(queue) => queue,
+            Err(e) => {
+                dev.fail();
+                return Err(e);
+            }
+        };
+        let buffer = DmaBuffer::new(DATA_OFFSET + MAX_TRANSFER).ok_or(VirtioError::OutOfMemory)?;
+        let sectors = dev.config64(0);
+        dev.driver_ok();
+        Ok(Self {
+            dev,
+            queue,
+            buffer,
+            sectors,
+            read_only: features & VIRTIO_BLK_F_RO != 0,
+            can_flush: features & VIRTIO_BLK_F_FLUSH != 0,
+        })
+    }
+
+    /// Soumet une requête et attend son statut
+    fn request(&mut self, kind: u32, sector: u64, bytes: usize) -> Result<(), VirtioError> {
+        let base = self.buffer.phys();
+        unsafe {
+            core::ptr::write_volatile(self.buffer.as_ptr::<BlkRequestHeader>(HEADER_OFFSET), BlkRequestHeader::new(kind, sector));
+            core::ptr::write_volatile(self.buffer.as_ptr::<u8>(STATUS_OFFSET), 0xFF);
+        }
+
+        let header = Segment { addr: base + HEADER_OFFSET as u64, len: 16, device_writes: false };
+        let status = Segment { addr: base + STATUS_OFFSET as u64, len: 1, device_writes: true };
+        let data = Segment { addr: base + DATA_OFFSET as u64, len: bytes as u32, device_writes: kind == VIRTIO_BLK_T_IN };
+        let head = if bytes == 0 {
+            self.queue.add(&[header, status])
+        } else {
+            self.queue.add(&[header, data, status])
+        }
+        .ok_or(VirtioError::QueueUnavailable)?;
+        self.dev.notify(&self.queue);
+
+        for _ in 0..TIMEOUT_SPINS {
+            if let Some((done, _)) = self.queue.pop_used() {
+                self.dev.ack_interrupt();
+                let status = unsafe { core::ptr::read_volatile(self.buffer.as_ptr::<u8>(STATUS_OFFSET)) };
+                return if done == head && status == 0 { Ok(()) } else { Err(VirtioError::DeviceError) };
+            }
+            core::hint::spin_loop();
+        }
+        Err(VirtioError::Timeout)
+    }
+}
+
+impl BlockDevice for VirtioBlk {
+    fn block_size(&self) -> usize {
+        SECTOR_SIZE
+    }
+
+    fn block_count(&self) -> u64 {
+        self.sectors
+    }
+
+    fn read_blocks(&mut self, lba: u64, buf: &mut [u8]) -> Result<(), DiskError> {
+        block::check_request(&*self, lba, buf.len())?;
+        for (i, chunk) in buf.chunks_mut(MAX_TRANSFER).enumerate() {
+            let sector = lba + (i * MAX_TRANSFER / SECTOR_SIZE) as u64;
+            self.request(VIRTIO_BLK_T_IN, sector, chunk.len()).map_err(|_| DiskError::ReadFailed)?;
+            chunk.copy_from_slice(&self.buffer.as_slice()[DATA_OFFSET..DATA_OFFSET + chunk.len()]);
+        }
+        Ok(())
+    }
+
+    fn write_blocks(&mut self, lba: u64, buf: &[u8]) -> Result<(), DiskError> {
+        block::check_request(&*self, lba, buf.len())?;
+        if self.read_only {
+            return Err(DiskError::WriteFailed);
+        }
+        for (i, chunk) in buf.chunks(MAX_TRANSFER).enumerate() {
+            let sector = lba + (i * MAX_TRANSFER / SECTOR_SIZE) as u64;
+            self.buffer.as_mut_slice()[DATA_OFFSET..DATA_OFFSET + chunk.len()].copy_from_slice(chunk);
+            self.request(VIRTIO_BLK_T_OUT, sector, chunk.len()).map_err(|_| DiskError::WriteFailed)?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), DiskError> {
+        if !self.can_flush {
+            return Ok(());
+        }
+        self.request(VIRTIO_BLK_T_FLUSH, 0, 0).map_err(|_| DiskError::WriteFailed)
+    }
+}
+
+/// Initialise les disques virtio-blk et les enregistre (`vda`, `vdb`...)
+pub fn probe() -> usize {
+    let mut count = 0;
+    for function in super::find(DEVICE_BLK) {
+        let disk = match VirtioPci::new(function).and_then(VirtioBlk::new) {
+            Ok(disk) => disk,
+            Err(e) => {
+                log::error!("virtio-blk: {:?}", e);
+                continue;
+            }
+        };
+        let name = format!("vd{}", (b'a' + count as u8) as char);
+        log::info!("virtio-blk: {} ({} secteurs)", name, disk.sectors);
+        if block::register(&name, Arc::new(Mutex::new(disk))).is_ok() {
+            count += 1;
+        }
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_request_header_layout() {
+        assert_eq!(core::mem::size_of::<BlkRequestHeader>(), 16);
+        let header = BlkRequestHeader::new(VIRTIO_BLK_T_OUT, 42);
+        assert_eq!((header.kind, header.sector), (1, 42));
+        assert!(STATUS_OFFSET >= HEADER_OFFSET + 16 && STATUS_OFFSET < DATA_OFFSET);
+    }
+}
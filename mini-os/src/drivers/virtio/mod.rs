@@ -0,0 +1,154 @@
+/// Cœur virtio (transport PCI legacy)
+///
+/// Les périphériques virtio de QEMU sont « transitionnels » : la BAR 0
+/// expose l'interface legacy en ports d'E/S, suffisante ici. Le cœur
+/// gère l'état du périphérique, la négociation des fonctionnalités et
+/// l'installation des virtqueues ; `blk` et `net` s'appuient dessus.
+
+pub mod queue;
+pub mod blk;
+pub mod net;
+
+pub use queue::{Segment, Virtqueue};
+
+use x86_64::instructions::port::Port;
+use super::pci::{self, PciFunction};
+
+/// Vendeur PCI des périphériques virtio
+pub const VIRTIO_VENDOR: u16 = 0x1AF4;
+/// Identifiants transitionnels
+pub const DEVICE_NET: u16 = 0x1000;
+pub const DEVICE_BLK: u16 = 0x1001;
+
+/// Registres legacy (offsets dans la BAR d'E/S)
+mod reg {
+    pub const DEVICE_FEATURES: u16 = 0x00;
+    pub const DRIVER_FEATURES: u16 = 0x04;
+    pub const QUEUE_ADDRESS: u16 = 0x08;
+    pub const QUEUE_SIZE: u16 = 0x0C;
+    pub const QUEUE_SELECT: u16 = 0x0E;
+    pub const QUEUE_NOTIFY: u16 = 0x10;
+    pub const DEVICE_STATUS: u16 = 0x12;
+    pub const ISR_STATUS: u16 = 0x13;
+    /// Configuration propre au périphérique (sans MSI-X)
+    pub const DEVICE_CONFIG: u16 = 0x14;
+}
+
+/// Bits du registre d'état
+pub mod status {
+    pub const ACKNOWLEDGE: u8 = 1;
+    pub const DRIVER: u8 = 2;
+    pub const DRIVER_OK: u8 = 4;
+    pub const FAILED: u8 = 128;
+}
+
+/// Erreurs du cœur virtio
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VirtioError {
+    NoIoBar,
+    QueueUnavailable,
+    OutOfMemory,
+    DeviceError,
+    Timeout,
+}
+
+/// Périphérique virtio sur PCI (interface legacy)
+pub struct VirtioPci {
+    pub function: PciFunction,
+    io_base: u16,
+}
+
+impl VirtioPci {
+    /// Réinitialise le périphérique et annonce le driver
+    pub fn new(function: PciFunction) -> Result<Self, VirtioError> {
+        let io_base = function.io_bar(0).ok_or(VirtioError::NoIoBar)?;
+        function.enable_bus_master();
+        let dev = Self { function, io_base };
+        dev.write8(reg::DEVICE_STATUS, 0);
+        dev.write8(reg::DEVICE_STATUS, status::ACKNOWLEDGE);
+        dev.write8(reg::DEVICE_STATUS, status::ACKNOWLEDGE | status::DRIVER);
+        Ok(dev)
+    }
+
+    fn read8(&self, offset: u16) -> u8 {
+        unsafe { Port::<u8>::new(self.io_base + offset).read() }
+    }
+
+    fn write8(&self, offset: u16, value: u8) {
+        unsafe { Port::<u8>::new(self.io_base + offset).write(value) }
+    }
+
+    fn read16(&self, offset: u16) -> u16 {
+        unsafe { Port::<u16>::new(self.io_base + offset).read() }
+    }
+
+    fn write16(&self, offset: u16, value: u16) {
+        unsafe { Port::<u16>::new(self.io_base + offset).write(value) }
+    }
+
+    fn read32(&self, offset: u16) -> u32 {
+        unsafe { Port::<u32>::new(self.io_base + offset).read() }
+    }
+
+    fn write32(&self, offset: u16, value: u32) {
+        unsafe { Port::<u32>::new(self.io_base + offset).write(value) }
+    }
+
+    /// Accepte les fonctionnalités de `wanted` offertes par le périphérique
+    pub fn negotiate(&self, wanted: u32) -> u32 {
+        let accepted = self.read32(reg::DEVICE_FEATURES) & wanted;
+        self.write32(reg::DRIVER_FEATURES, accepted);
+        accepted
+    }
+
+    /// Installe la virtqueue `index` à la taille imposée par le périphérique
+    pub fn setup_queue(&self, index: u16) -> Result<Virtqueue, VirtioError> {
+        self.write16(reg::QUEUE_SELECT, index);
+        let size = self.read16(reg::QUEUE_SIZE);
+        if size == 0 {
+            return Err(VirtioError::QueueUnavailable);
+        }
+        let queue = Virtqueue::new(index, size).ok_or(VirtioError::OutOfMemory)?;
+        self.write32(reg::QUEUE_ADDRESS, queue.pfn());
+        Ok(queue)
+    }
+
+    /// Signale de nouveaux tampons sur une virtqueue
+    pub fn notify(&self, queue: &Virtqueue) {
+        self.write16(reg::QUEUE_NOTIFY, queue.index);
+    }
+
+    /// Périphérique prêt à traiter les files
+    pub fn driver_ok(&self) {
+        let current = self.read8(reg::DEVICE_STATUS);
+        self.write8(reg::DEVICE_STATUS, current | status::DRIVER_OK);
+    }
+
+    /// Abandon : le périphérique ne sera plus utilisé
+    pub fn fail(&self) {
+        let current = self.read8(reg::DEVICE_STATUS);
+        self.write8(reg::DEVICE_STATUS, current | status::FAILED);
+    }
+
+    /// Lit et acquitte le registre ISR (bit 0 : file, bit 1 : configuration)
+    pub fn ack_interrupt(&self) -> u8 {
+        self.read8(reg::ISR_STATUS)
+    }
+
+    pub fn config8(&self, offset: u16) -> u8 {
+        self.read8(reg::DEVICE_CONFIG + offset)
+    }
+
+    pub fn config32(&self, offset: u16) -> u32 {
+        self.read32(reg::DEVICE_CONFIG + offset)
+    }
+
+    pub fn config64(&self, offset: u16) -> u64 {
+        self.config32(offset) as u64 | (self.config32(offset + 4) as u64) << 32
+    }
+}
+
+/// Fonctions virtio présentes d'un type donné
+pub fn find(device_id: u16) -> alloc::vec::Vec<PciFunction> {
+    pci::find_by_id(VIRTIO_VENDOR, device_id)
+}
@@ -0,0 +1,177 @@
+/// virtio-net : carte réseau paravirtualisée
+///
+/// File 0 : réception, file 1 : émission. Chaque tampon commence par
+/// l'en-tête virtio-net legacy (10 octets, sans fusion de tampons),
+/// suivi de la trame Ethernet. Les tampons de réception sont tous
+/// postés à l'initialisation et reposés dès qu'une trame est lue.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use crate::drivers::dma::DmaBuffer;
+use crate::net::ethernet::MacAddress;
+use crate::net::interface::{self, NetDevice, NetDeviceError};
+use super::{Segment, VirtioError, VirtioPci, Virtqueue, DEVICE_NET};
+
+/// Fonctionnalité : adresse MAC dans l'espace de configuration
+const VIRTIO_NET_F_MAC: u32 = 1 << 5;
+
+/// En-tête legacy précédant chaque trame
+const NET_HDR_LEN: usize = 10;
+/// Tampon par descripteur : en-tête + trame Ethernet maximale
+const BUFFER_SIZE: usize = 2048;
+/// Tampons de réception et d'émission
+const RX_BUFFERS: usize = 32;
+const TX_BUFFERS: usize = 32;
+/// Trame Ethernet maximale (sans FCS)
+const MAX_FRAME: usize = 1514;
+
+const RX_QUEUE: u16 = 0;
+const TX_QUEUE: u16 = 1;
+
+/// Carte virtio-net
+pub struct VirtioNet {
+    dev: VirtioPci,
+    rx: Virtqueue,
+    tx: Virtqueue,
+    rx_buffers: DmaBuffer,
+    tx_buffers: DmaBuffer,
+    /// Tampon d'émission associé à chaque tête de chaîne
+    tx_slot_of_head: Vec<Option<usize>>,
+    /// Tampon de réception associé à chaque tête de chaîne
+    rx_slot_of_head: Vec<Option<usize>>,
+    tx_free: Vec<usize>,
+    mac: MacAddress,
+    pub rx_packets: u64,
+    pub tx_packets: u64,
+}
+
+impl VirtioNet {
+    pub fn new(dev: VirtioPci) -> Result<Self, VirtioError> {
+        let features = dev.negotiate(VIRTIO_NET_F_MAC);
+        let (rx, tx) = match (dev.setup_queue(RX_QUEUE), dev.setup_queue(TX_QUEUE)) {
+            (Ok(rx), Ok(tx)) => (rx, tx),
+            (Err(e), _) | (_, Err(e)) => {
+                dev.fail();
+                return Err(e);
+            }
+        };
+
+        let mut mac = [0u8; 6];
+        if features & VIRTIO_NET_F_MAC != 0 {
+            for (i, byte) in mac.iter_mut().enumerate() {
+                *byte = dev.config8(i as u16);
+            }
+        } else {
+            // Adresse administrée localement
+            mac = [0x02, 0x00, 0x00, 0x00, 0x00, dev.function.slot];
+        }
+
+        let rx_slots = rx.size as usize;
+        let tx_slots = tx.size as usize;
+        let mut nic = Self {
+            dev,
+            rx,
+            tx,
+            rx_buffers: DmaBuffer::new(RX_BUFFERS * BUFFER_SIZE).ok_or(VirtioError::OutOfMemory)?,
+            tx_buffers: DmaBuffer::new(TX_BUFFERS * BUFFER_SIZE).ok_or(VirtioError::OutOfMemory)?,
+            tx_slot_of_head: alloc::vec![None; tx_slots],
+            rx_slot_of_head: alloc::vec![None; rx_slots],
+            tx_free: (0..TX_BUFFERS).collect(),
+            mac: MacAddress::new(mac),
+            rx_packets: 0,
+            tx_packets: 0,
+        };
+
+        for slot in 0..RX_BUFFERS.min(rx_slots) {
+            nic.post_rx(slot);
+        }
+        nic.dev.driver_ok();
+        nic.dev.notify(&nic.rx);
+        Ok(nic)
+    }
+
+    /// Met un tampon de réception à disposition du périphérique
+    fn post_rx(&mut self, slot: usize) {
+        let addr = self.rx_buffers.phys() + (slot * BUFFER_SIZE) as u64;
+        if let Some(head) = self.rx.add(&[Segment { addr, len: BUFFER_SIZE as u32, device_writes: true }]) {
+            self.rx_slot_of_head[head as usize] = Some(slot);
+        }
+    }
+
+    /// Récupère les tampons d'émission terminés
+    fn reclaim_tx(&mut self) {
+        while let Some((head, _)) = self.tx.pop_used() {
+            if let Some(slot) = self.tx_slot_of_head[head as usize].take() {
+                self.tx_free.push(slot);
+            }
+        }
+    }
+}
+
+impl NetDevice for VirtioNet {
+    fn mac_address(&self) -> MacAddress {
+        self.mac
+    }
+
+    fn transmit(&mut self, frame: &[u8]) -> Result<(), NetDeviceError> {
+        if frame.len() > MAX_FRAME {
+            return Err(NetDeviceError::FrameTooLarge);
+        }
+        self.reclaim_tx();
+        let slot = self.tx_free.pop().ok_or(NetDeviceError::QueueFull)?;
+
+        let offset = slot * BUFFER_SIZE;
+        let buf = &mut self.tx_buffers.as_mut_slice()[offset..offset + NET_HDR_LEN + frame.len()];
+        buf[..NET_HDR_LEN].fill(0);
+        buf[NET_HDR_LEN..].copy_from_slice(frame);
+
+        let addr = self.tx_buffers.phys() + offset as u64;
+        let len = (NET_HDR_LEN + frame.len()) as u32;
+        match self.tx.add(&[Segment { addr, len, device_writes: false }]) {
+            Some(head) => {
+                self.tx_slot_of_head[head as usize] = Some(slot);
+                self.dev.notify(&self.tx);
+                self.tx_packets += 1;
+                Ok(())
+            }
+            None => {
+                self.tx_free.push(slot);
+                Err(NetDeviceError::QueueFull)
+            }
+        }
+    }
+
+    fn receive(&mut self) -> Option<Vec<u8>> {
+        let (head, len) = self.rx.pop_used()?;
+        self.dev.ack_interrupt();
+        let slot = self.rx_slot_of_head[head as usize].take()?;
+
+        let offset = slot * BUFFER_SIZE;
+        let len = (len as usize).clamp(NET_HDR_LEN, BUFFER_SIZE);
+        let frame = self.rx_buffers.as_slice()[offset + NET_HDR_LEN..offset + len].to_vec();
+
+        self.post_rx(slot);
+        self.dev.notify(&self.rx);
+        self.rx_packets += 1;
+        Some(frame)
+    }
+}
+
+/// Initialise les cartes virtio-net et les attache à la pile réseau
+///
+/// Retourne le nom et l'adresse MAC des cartes enregistrées.
+pub fn probe() -> Vec<(alloc::string::String, MacAddress)> {
+    let mut attached = Vec::new();
+    for function in super::find(DEVICE_NET) {
+        match VirtioPci::new(function).and_then(VirtioNet::new) {
+            Ok(nic) => {
+                let mac = nic.mac_address();
+                let name = interface::register_device(Box::new(nic));
+                log::info!("virtio-net: {} {}", name, mac);
+                attached.push((name, mac));
+            }
+            Err(e) => log::error!("virtio-net: {:?}", e),
+        }
+    }
+    attached
+}
@@ -0,0 +1,227 @@
+/// Virtqueue « split » (disposition legacy)
+///
+/// Une seule zone DMA contient, dans l'ordre :
+///
+/// | Partie      | Taille              | Alignement |
+/// |-------------|---------------------|------------|
+/// | descripteurs| 16 × taille         | 16         |
+/// | anneau avail| 6 + 2 × taille      | 2          |
+/// | anneau used | 6 + 8 × taille      | 4096       |
+///
+/// Les descripteurs libres sont chaînés par leur champ `next`.
+
+use alloc::vec::Vec;
+use core::ptr::{read_volatile, write_volatile};
+use core::sync::atomic::{fence, Ordering};
+use crate::drivers::dma::DmaBuffer;
+
+/// Descripteur chaîné vers le suivant
+pub const DESC_F_NEXT: u16 = 1;
+/// Tampon écrit par le périphérique
+pub const DESC_F_WRITE: u16 = 2;
+
+const ALIGN: usize = 4096;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VirtqDesc {
+    pub addr: u64,
+    pub len: u32,
+    pub flags: u16,
+    pub next: u16,
+}
+
+/// Élément de l'anneau used
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VirtqUsedElem {
+    pub id: u32,
+    pub len: u32,
+}
+
+/// Segment d'une requête : adresse physique, longueur, écrit par le périphérique
+#[derive(Debug, Clone, Copy)]
+pub struct Segment {
+    pub addr: u64,
+    pub len: u32,
+    pub device_writes: bool,
+}
+
+/// Octets occupés par une virtqueue de `size` entrées
+pub const fn queue_bytes(size: u16) -> usize {
+    let size = size as usize;
+    let driver_part = 16 * size + 6 + 2 * size;
+    align_up(driver_part, ALIGN) + align_up(6 + 8 * size, ALIGN)
+}
+
+const fn align_up(value: usize, align: usize) -> usize {
+    (value + align - 1) & !(align - 1)
+}
+
+pub struct Virtqueue {
+    pub index: u16,
+    pub size: u16,
+    mem: DmaBuffer,
+    used_offset: usize,
+    free_head: u16,
+    num_free: u16,
+    /// Prochaine entrée de l'anneau used à consommer
+    last_used: u16,
+}
+
+impl Virtqueue {
+    pub fn new(index: u16, size: u16) -> Option<Self> {
+        let mem = DmaBuffer::new(queue_bytes(size))?;
+        let used_offset = align_up(16 * size as usize + 6 + 2 * size as usize, ALIGN);
+        let mut queue = Self { index, size, mem, used_offset, free_head: 0, num_free: size, last_used: 0 };
+        for i in 0..size {
+            queue.write_desc(i, VirtqDesc { next: (i + 1) % size, ..VirtqDesc::default() });
+        }
+        Some(queue)
+    }
+
+    /// Numéro de page physique à donner au registre QUEUE_ADDRESS
+    pub fn pfn(&self) -> u32 {
+        (self.mem.phys() / ALIGN as u64) as u32
+    }
+
+    pub fn num_free(&self) -> u16 {
+        self.num_free
+    }
+
+    fn write_desc(&mut self, i: u16, desc: VirtqDesc) {
+        unsafe { write_volatile(self.mem.as_ptr::<VirtqDesc>(16 * i as usize), desc) }
+    }
+
+    fn read_desc(&self, i: u16) -> VirtqDesc {
+        unsafe { read_volatile(self.mem.as_ptr::<VirtqDesc>(16 * i as usize)) }
+    }
+
+    fn avail_offset(&self) -> usize {
+        16 * self.size as usize
+    }
+
+    /// Publie une chaîne de segments ; retourne l'indice de tête
+    pub fn add(&mut self, segments: &[Segment]) -> Option<u16> {
+        if segments.is_empty() || segments.len() > self.num_free as usize {
+            return None;
+        }
+
+        let head = self.free_head;
+        let mut current = head;
+        for (n, seg) in segments.iter().enumerate() {
+            let next_free = self.read_desc(current).next;
+            let last = n + 1 == segments.len();
+            let mut flags = if seg.device_writes { DESC_F_WRITE } else { 0 };
+            if !last {
+                flags |= DESC_F_NEXT;
+            }
+            self.write_desc(current, VirtqDesc { addr: seg.addr, len: seg.len, flags, next: next_free });
+            if last {
+                self.free_head = next_free;
+            }
+            current = next_free;
+        }
+        self.num_free -= segments.len() as u16;
+
+        // avail.ring[avail.idx % size] = head, puis avail.idx++
+        let avail = self.avail_offset();
+        unsafe {
+            let idx_ptr = self.mem.as_ptr::<u16>(avail + 2);
+            let idx = read_volatile(idx_ptr);
+            write_volatile(self.mem.as_ptr::<u16>(avail + 4 + 2 * (idx % self.size) as usize), head);
+            fence(Ordering::SeqCst);
+            write_volatile(idx_ptr, idx.wrapping_add(1));
+        }
+        fence(Ordering::SeqCst);
+        Some(head)
+    }
+
+    /// Récupère une chaîne terminée : (tête, octets écrits par le périphérique)
+    pub fn pop_used(&mut self) -> Option<(u16, u32)> {
+        fence(Ordering::SeqCst);
+        let used_idx = unsafe { read_volatile(self.mem.as_ptr::<u16>(self.used_offset + 2)) };
+        if used_idx == self.last_used {
+            return None;
+        }
+
+        let slot = (self.last_used % self.size) as usize;
+        let elem = unsafe { read_volatile(self.mem.as_ptr::<VirtqUsedElem>(self.used_offset + 4 + 8 * slot)) };
+        self.last_used = self.last_used.wrapping_add(1);
+
+        // Rendre la chaîne à la liste libre
+        let head = elem.id as u16;
+        let mut last = head;
+        let mut count = 1;
+        loop {
+            let desc = self.read_desc(last);
+            if desc.flags & DESC_F_NEXT == 0 {
+                break;
+            }
+            last = desc.next;
+            count += 1;
+        }
+        let mut tail = self.read_desc(last);
+        tail.next = self.free_head;
+        self.write_desc(last, tail);
+        self.free_head = head;
+        self.num_free += count;
+
+        Some((head, elem.len))
+    }
+
+    /// Indices des descripteurs encore libres (diagnostic)
+    pub fn free_list(&self) -> Vec<u16> {
+        let mut list = Vec::new();
+        let mut i = self.free_head;
+        for _ in 0..self.num_free {
+            list.push(i);
+            i = self.read_desc(i).next;
+        }
+        list
+    }
+
+    /// Simule le périphérique : consomme la prochaine chaîne disponible (tests)
+    #[cfg(test)]
+    fn device_complete(&mut self, written: u32) {
+        let avail = self.avail_offset();
+        unsafe {
+            let used_idx_ptr = self.mem.as_ptr::<u16>(self.used_offset + 2);
+            let used_idx = read_volatile(used_idx_ptr);
+            let head = read_volatile(self.mem.as_ptr::<u16>(avail + 4 + 2 * (used_idx % self.size) as usize));
+            let slot = (used_idx % self.size) as usize;
+            write_volatile(
+                self.mem.as_ptr::<VirtqUsedElem>(self.used_offset + 4 + 8 * slot),
+                VirtqUsedElem { id: head as u32, len: written },
+            );
+            write_volatile(used_idx_ptr, used_idx.wrapping_add(1));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_queue_layout() {
+        // 256 entrées : 4096 + 518 octets arrondis à 8 Kio, anneau used sur 4 Kio
+        assert_eq!(queue_bytes(256), 3 * 4096);
+        assert_eq!(queue_bytes(16), 2 * 4096);
+    }
+
+    #[test_case]
+    fn test_add_and_recycle_chain() {
+        let mut queue = Virtqueue::new(0, 8).unwrap();
+        let seg = |addr, device_writes| Segment { addr, len: 16, device_writes };
+        let head = queue.add(&[seg(0x1000, false), seg(0x2000, true), seg(0x3000, true)]).unwrap();
+        assert_eq!(queue.num_free(), 5);
+        assert_eq!(queue.read_desc(head).flags, DESC_F_NEXT);
+
+        assert!(queue.pop_used().is_none());
+        queue.device_complete(17);
+        assert_eq!(queue.pop_used(), Some((head, 17)));
+        assert_eq!(queue.num_free(), 8);
+        assert_eq!(queue.free_list().len(), 8);
+    }
+}
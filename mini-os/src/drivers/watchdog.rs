@@ -0,0 +1,202 @@
+/// Chiens de garde matériels émulés par QEMU
+///
+/// - `-device i6300esb` : watchdog du chipset Intel 6300ESB (PCI
+///   8086:25ab). Deux étages de décompte à 1 kHz sont programmés par la
+///   BAR 0 ; chaque écriture d'un registre MMIO doit être précédée de la
+///   séquence de déverrouillage (0x80 puis 0x86 dans RELOAD).
+/// - `-device ib700` : carte ISA IB700, sans détection possible. Écrire
+///   un index de délai sur le port 0x443 arme (ou nourrit) le chien,
+///   écrire sur 0x441 l'arrête.
+///
+/// Une fois armé, le chien doit être nourri (`ping`) avant l'échéance,
+/// sinon la machine est réinitialisée (action `-watchdog-action` de QEMU).
+
+use x86_64::instructions::port::Port;
+use crate::drivers::pci::{self, PciFunction};
+
+/// Identifiants PCI du 6300ESB
+const ESB_VENDOR: u16 = 0x8086;
+const ESB_DEVICE: u16 = 0x25AB;
+/// Espace de configuration : mode du chien, verrou/activation
+const ESB_CONFIG_REG: u8 = 0x60;
+const ESB_LOCK_REG: u8 = 0x68;
+/// Registres MMIO
+const ESB_TIMER1_REG: u64 = 0x00;
+const ESB_TIMER2_REG: u64 = 0x04;
+const ESB_RELOAD_REG: u64 = 0x0C;
+/// Mode watchdog, interruptions coupées
+const ESB_WDT_INTTYPE: u32 = 0x03;
+const ESB_WDT_ENABLE: u32 = 1 << 1;
+const ESB_WDT_RELOAD: u16 = 1 << 8;
+const ESB_WDT_TIMEOUT: u16 = 1 << 9;
+const ESB_UNLOCK1: u16 = 0x80;
+const ESB_UNLOCK2: u16 = 0x86;
+/// Délai maximal (10 bits de compteur par étage)
+const ESB_MAX_TIMEOUT: u32 = 2046;
+
+/// Ports de l'IB700
+const IB700_START: u16 = 0x443;
+const IB700_STOP: u16 = 0x441;
+/// Délai maximal ; index n = 30 - 2n secondes
+const IB700_MAX_TIMEOUT: u32 = 30;
+
+/// Modèle de chien de garde
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// Le 6300ESB s'il est présent (l'IB700 ne se détecte pas)
+    Auto,
+    I6300esb,
+    Ib700,
+}
+
+impl Kind {
+    /// Valeur de l'option `watchdog=`
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "auto" => Some(Kind::Auto),
+            "i6300esb" => Some(Kind::I6300esb),
+            "ib700" => Some(Kind::Ib700),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Kind::Auto => "auto",
+            Kind::I6300esb => "i6300esb",
+            Kind::Ib700 => "ib700",
+        }
+    }
+}
+
+/// Index de délai de l'IB700 pour `secs` (arrondi au pair supérieur)
+fn ib700_index(secs: u32) -> u8 {
+    let secs = secs.clamp(2, IB700_MAX_TIMEOUT);
+    ((IB700_MAX_TIMEOUT - (secs + secs % 2)) / 2) as u8
+}
+
+/// Chien de garde détecté
+#[derive(Debug)]
+pub struct HwWatchdog {
+    pub kind: Kind,
+    /// Fonction PCI et registres MMIO (6300ESB)
+    function: Option<PciFunction>,
+    mmio: u64,
+    /// Délai armé (secondes), 0 : arrêté
+    timeout: u32,
+}
+
+impl HwWatchdog {
+    /// Cherche le chien demandé
+    pub fn probe(kind: Kind) -> Option<Self> {
+        let esb = || {
+            let function = pci::find_by_id(ESB_VENDOR, ESB_DEVICE).into_iter().next()?;
+            let mmio = function.bar(0)?;
+            Some(Self { kind: Kind::I6300esb, function: Some(function), mmio, timeout: 0 })
+        };
+        match kind {
+            Kind::Auto | Kind::I6300esb => esb(),
+            // Carte ISA : présence supposée quand elle est demandée
+            Kind::Ib700 => Some(Self { kind: Kind::Ib700, function: None, mmio: 0, timeout: 0 }),
+        }
+    }
+
+    /// Délai armé, en secondes (0 : arrêté)
+    pub fn timeout(&self) -> u32 {
+        self.timeout
+    }
+
+    fn esb_unlock(&self) {
+        self.esb_write16(ESB_RELOAD_REG, ESB_UNLOCK1);
+        self.esb_write16(ESB_RELOAD_REG, ESB_UNLOCK2);
+    }
+
+    fn esb_write16(&self, reg: u64, value: u16) {
+        unsafe { core::ptr::write_volatile((self.mmio + reg) as *mut u16, value) }
+    }
+
+    fn esb_write32(&self, reg: u64, value: u32) {
+        unsafe { core::ptr::write_volatile((self.mmio + reg) as *mut u32, value) }
+    }
+
+    /// Octet de l'espace de configuration (écriture sur le mot de 32 bits)
+    fn esb_config(function: &PciFunction, offset: u8, mask: u32, value: u32) {
+        let shift = (offset & 3) * 8;
+        let word = function.read(offset & !3) & !(mask << shift);
+        function.write(offset & !3, word | (value & mask) << shift);
+    }
+
+    /// Arme le chien ; la machine redémarre si `ping` n'est pas appelé
+    /// dans les `secs` secondes (délai borné par le matériel)
+    pub fn start(&mut self, secs: u32) {
+        match self.kind {
+            Kind::Auto => {}
+            Kind::I6300esb => {
+                let Some(function) = self.function else { return };
+                let secs = secs.clamp(1, ESB_MAX_TIMEOUT);
+                function.enable_bus_master();
+                Self::esb_config(&function, ESB_CONFIG_REG, 0xFFFF, ESB_WDT_INTTYPE);
+                // Efface un éventuel délai expiré, puis programme les deux étages
+                self.esb_unlock();
+                self.esb_write16(ESB_RELOAD_REG, ESB_WDT_TIMEOUT | ESB_WDT_RELOAD);
+                self.esb_unlock();
+                self.esb_write32(ESB_TIMER1_REG, secs << 9);
+                self.esb_unlock();
+                self.esb_write32(ESB_TIMER2_REG, secs << 9);
+                self.ping();
+                Self::esb_config(&function, ESB_LOCK_REG, 0xFF, ESB_WDT_ENABLE);
+                self.timeout = secs;
+            }
+            Kind::Ib700 => {
+                let index = ib700_index(secs);
+                unsafe { Port::<u8>::new(IB700_START).write(index) };
+                self.timeout = IB700_MAX_TIMEOUT - 2 * index as u32;
+            }
+        }
+    }
+
+    /// Nourrit le chien : le décompte repart du délai armé
+    pub fn ping(&self) {
+        match self.kind {
+            Kind::Auto => {}
+            Kind::I6300esb => {
+                self.esb_unlock();
+                self.esb_write16(ESB_RELOAD_REG, ESB_WDT_RELOAD);
+            }
+            Kind::Ib700 => unsafe { Port::<u8>::new(IB700_START).write(ib700_index(self.timeout)) },
+        }
+    }
+
+    /// Désarme le chien
+    pub fn stop(&mut self) {
+        match self.kind {
+            Kind::Auto => {}
+            Kind::I6300esb => {
+                let Some(function) = self.function else { return };
+                self.ping();
+                Self::esb_config(&function, ESB_LOCK_REG, 0xFF, 0);
+            }
+            Kind::Ib700 => unsafe { Port::<u8>::new(IB700_STOP).write(0) },
+        }
+        self.timeout = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_watchdog_timeouts() {
+        assert_eq!(ib700_index(30), 0);
+        assert_eq!(ib700_index(29), 0);
+        assert_eq!(ib700_index(10), 10);
+        assert_eq!(ib700_index(1), 14);
+        assert_eq!(ib700_index(100), 0);
+
+        assert_eq!(Kind::parse("auto"), Some(Kind::Auto));
+        assert_eq!(Kind::parse("ib700"), Some(Kind::Ib700));
+        assert_eq!(Kind::parse("i6300esb").map(|k| k.name()), Some("i6300esb"));
+        assert_eq!(Kind::parse("softdog"), None);
+    }
+}
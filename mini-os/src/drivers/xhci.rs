@@ -0,0 +1,957 @@
+/// Contrôleur xHCI (USB 3.x)
+///
+/// Structures partagées avec le contrôleur, toutes en mémoire DMA :
+///
+/// - DCBAA : pointeur vers le contexte de sortie de chaque slot ;
+/// - anneau de commandes et anneau d'événements (un seul segment) ;
+/// - par périphérique : contextes d'entrée et de sortie, anneau EP0,
+///   et un anneau par endpoint d'interruption configuré.
+///
+/// Les commandes et transferts de contrôle attendent leur événement par
+/// scrutation de l'anneau d'événements ; les transferts d'interruption
+/// restent armés (un TRB Normal en attente) et leurs complétions sont
+/// relevées par `poll` ou l'interruption du contrôleur. Un changement
+/// d'état de port déclenche l'énumération (`usb_protocol`) ou le retrait
+/// du périphérique.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::ptr::{read_volatile, write_volatile};
+use core::sync::atomic::{fence, Ordering};
+use spin::Mutex;
+use x86_64::instructions::interrupts::without_interrupts;
+use crate::drivers::dma::DmaBuffer;
+use crate::drivers::pci::{self, PciFunction};
+use super::usb_controller::{UsbError, UsbHostController};
+use super::usb_protocol::{DescriptorType, EndpointDescriptor, SetupPacket, TransferType, UsbEnumerator};
+use super::usb_hid::{self, UsbHidDriver};
+use super::{Driver, DriverError};
+
+/// Registres de capacité
+mod cap {
+    pub const CAPLENGTH: usize = 0x00;
+    pub const HCSPARAMS1: usize = 0x04;
+    pub const HCSPARAMS2: usize = 0x08;
+    pub const HCCPARAMS1: usize = 0x10;
+    pub const DBOFF: usize = 0x14;
+    pub const RTSOFF: usize = 0x18;
+}
+
+/// Registres opérationnels
+mod op {
+    pub const USBCMD: usize = 0x00;
+    pub const USBSTS: usize = 0x04;
+    pub const CRCR: usize = 0x18;
+    pub const DCBAAP: usize = 0x30;
+    pub const CONFIG: usize = 0x38;
+    pub const PORTSC: usize = 0x400;
+}
+
+/// Registres de l'interrupteur 0 (espace runtime)
+mod rt {
+    pub const IMAN: usize = 0x20;
+    pub const IMOD: usize = 0x24;
+    pub const ERSTSZ: usize = 0x28;
+    pub const ERSTBA: usize = 0x30;
+    pub const ERDP: usize = 0x38;
+}
+
+const CMD_RUN: u32 = 1 << 0;
+const CMD_RESET: u32 = 1 << 1;
+const CMD_INTE: u32 = 1 << 2;
+
+const STS_HALTED: u32 = 1 << 0;
+const STS_EINT: u32 = 1 << 3;
+const STS_CNR: u32 = 1 << 11;
+
+const IMAN_IP: u32 = 1 << 0;
+const IMAN_IE: u32 = 1 << 1;
+/// Event Handler Busy (ERDP), écrire 1 pour effacer
+const ERDP_EHB: u64 = 1 << 3;
+
+const PORT_CCS: u32 = 1 << 0;
+const PORT_PED: u32 = 1 << 1;
+const PORT_PR: u32 = 1 << 4;
+const PORT_PP: u32 = 1 << 9;
+const PORT_CSC: u32 = 1 << 17;
+const PORT_PRC: u32 = 1 << 21;
+/// Bits de changement (écrire 1 pour effacer) : CSC, PEC, WRC, OCC, PRC, PLC, CEC
+const PORT_CHANGE_BITS: u32 = 0x7F << 17;
+
+/// Vitesses (PORTSC et contexte de slot)
+pub const SPEED_FULL: u8 = 1;
+pub const SPEED_LOW: u8 = 2;
+pub const SPEED_HIGH: u8 = 3;
+pub const SPEED_SUPER: u8 = 4;
+
+/// Types de TRB
+mod trb_type {
+    pub const NORMAL: u8 = 1;
+    pub const SETUP: u8 = 2;
+    pub const DATA: u8 = 3;
+    pub const STATUS: u8 = 4;
+    pub const LINK: u8 = 6;
+    pub const ENABLE_SLOT: u8 = 9;
+    pub const DISABLE_SLOT: u8 = 10;
+    pub const ADDRESS_DEVICE: u8 = 11;
+    pub const CONFIGURE_ENDPOINT: u8 = 12;
+    pub const EVALUATE_CONTEXT: u8 = 13;
+    pub const TRANSFER_EVENT: u8 = 32;
+    pub const COMMAND_COMPLETION: u8 = 33;
+    pub const PORT_STATUS_CHANGE: u8 = 34;
+}
+
+const TRB_CYCLE: u32 = 1 << 0;
+const TRB_TOGGLE_CYCLE: u32 = 1 << 1;
+const TRB_ISP: u32 = 1 << 2;
+const TRB_IOC: u32 = 1 << 5;
+const TRB_IDT: u32 = 1 << 6;
+const TRB_DIR_IN: u32 = 1 << 16;
+
+/// Codes de complétion
+const CC_SUCCESS: u8 = 1;
+const CC_STALL: u8 = 6;
+const CC_SHORT_PACKET: u8 = 13;
+
+/// Types d'endpoint du contexte
+const EP_TYPE_CONTROL: u32 = 4;
+const EP_TYPE_INTERRUPT_IN: u32 = 7;
+const EP_TYPE_INTERRUPT_OUT: u32 = 3;
+const EP_TYPE_BULK_IN: u32 = 6;
+const EP_TYPE_BULK_OUT: u32 = 2;
+
+/// TRB par anneau de transfert/commandes (une page), le dernier est un Link
+const RING_SIZE: usize = 256;
+/// TRB du segment d'événements
+const EVENT_RING_SIZE: usize = 256;
+/// Itérations de scrutation avant d'abandonner une commande ou un transfert
+const TIMEOUT_SPINS: usize = 10_000_000;
+/// Taille du tampon de données des transferts de contrôle
+const CONTROL_BUFFER: usize = 4096;
+
+/// TRB générique (16 octets)
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Trb {
+    pub parameter: u64,
+    pub status: u32,
+    pub control: u32,
+}
+
+impl Trb {
+    pub fn new(kind: u8, parameter: u64, status: u32, flags: u32) -> Self {
+        Self { parameter, status, control: (kind as u32) << 10 | flags }
+    }
+
+    pub fn kind(&self) -> u8 {
+        ((self.control >> 10) & 0x3F) as u8
+    }
+
+    pub fn completion_code(&self) -> u8 {
+        (self.status >> 24) as u8
+    }
+
+    /// Octets non transférés (événement de transfert)
+    pub fn residual(&self) -> usize {
+        (self.status & 0x00FF_FFFF) as usize
+    }
+
+    pub fn slot_id(&self) -> u8 {
+        (self.control >> 24) as u8
+    }
+
+    pub fn endpoint_id(&self) -> u8 {
+        ((self.control >> 16) & 0x1F) as u8
+    }
+}
+
+/// Anneau producteur (commandes ou transferts) terminé par un TRB Link
+pub struct Ring {
+    mem: DmaBuffer,
+    enqueue: usize,
+    cycle: bool,
+}
+
+impl Ring {
+    pub fn new() -> Option<Self> {
+        let mem = DmaBuffer::new(RING_SIZE * 16)?;
+        let mut ring = Self { mem, enqueue: 0, cycle: true };
+        let link = Trb::new(trb_type::LINK, ring.phys(), 0, TRB_TOGGLE_CYCLE);
+        ring.write(RING_SIZE - 1, link);
+        Some(ring)
+    }
+
+    pub fn phys(&self) -> u64 {
+        self.mem.phys()
+    }
+
+    /// Écrit un TRB, le mot de contrôle (bit de cycle) en dernier
+    fn write(&mut self, index: usize, trb: Trb) {
+        let ptr = self.mem.as_ptr::<Trb>(16 * index);
+        unsafe {
+            write_volatile(core::ptr::addr_of_mut!((*ptr).parameter), trb.parameter);
+            write_volatile(core::ptr::addr_of_mut!((*ptr).status), trb.status);
+            fence(Ordering::SeqCst);
+            write_volatile(core::ptr::addr_of_mut!((*ptr).control), trb.control);
+        }
+    }
+
+    /// Publie un TRB ; retourne son adresse physique
+    pub fn push(&mut self, mut trb: Trb) -> u64 {
+        trb.control = (trb.control & !TRB_CYCLE) | self.cycle as u32;
+        let addr = self.phys() + 16 * self.enqueue as u64;
+        self.write(self.enqueue, trb);
+        self.enqueue += 1;
+
+        if self.enqueue == RING_SIZE - 1 {
+            // Passer le Link au contrôleur puis repartir du début
+            let link = Trb::new(trb_type::LINK, self.phys(), 0, TRB_TOGGLE_CYCLE | self.cycle as u32);
+            self.write(RING_SIZE - 1, link);
+            self.cycle = !self.cycle;
+            self.enqueue = 0;
+        }
+        addr
+    }
+}
+
+/// Anneau d'événements (consommateur) et sa table de segments
+pub struct EventRing {
+    segment: DmaBuffer,
+    erst: DmaBuffer,
+    dequeue: usize,
+    cycle: bool,
+}
+
+impl EventRing {
+    pub fn new() -> Option<Self> {
+        let segment = DmaBuffer::new(EVENT_RING_SIZE * 16)?;
+        let erst = DmaBuffer::new(16)?;
+        unsafe {
+            write_volatile(erst.as_ptr::<u64>(0), segment.phys());
+            write_volatile(erst.as_ptr::<u32>(8), EVENT_RING_SIZE as u32);
+        }
+        Some(Self { segment, erst, dequeue: 0, cycle: true })
+    }
+
+    /// Prochain événement produit par le contrôleur
+    pub fn pop(&mut self) -> Option<Trb> {
+        let trb = unsafe { read_volatile(self.segment.as_ptr::<Trb>(16 * self.dequeue)) };
+        if (trb.control & TRB_CYCLE != 0) != self.cycle {
+            return None;
+        }
+        self.dequeue += 1;
+        if self.dequeue == EVENT_RING_SIZE {
+            self.dequeue = 0;
+            self.cycle = !self.cycle;
+        }
+        Some(trb)
+    }
+
+    pub fn dequeue_phys(&self) -> u64 {
+        self.segment.phys() + 16 * self.dequeue as u64
+    }
+}
+
+/// Endpoint d'interruption ou bulk configuré
+struct EndpointState {
+    dci: u8,
+    ring: Ring,
+    buffer: DmaBuffer,
+    max_packet: u16,
+    /// Octets reçus par le dernier transfert terminé, pas encore lus
+    completed: Option<usize>,
+}
+
+/// Périphérique adressé (un slot)
+struct XhciDevice {
+    port: u8,
+    speed: u8,
+    output: DmaBuffer,
+    input: DmaBuffer,
+    ep0: Ring,
+    control_buffer: DmaBuffer,
+    /// Endpoints configurés, par adresse d'endpoint (0x81...)
+    endpoints: BTreeMap<u8, EndpointState>,
+}
+
+/// Indice de contexte (DCI) d'un endpoint : EP0 = 1, puis 2 × numéro + IN
+pub fn endpoint_dci(endpoint_address: u8) -> u8 {
+    let number = endpoint_address & 0x0F;
+    if number == 0 {
+        1
+    } else {
+        number * 2 + (endpoint_address >> 7)
+    }
+}
+
+/// Intervalle du contexte d'endpoint (exposant en unités de 125 µs)
+pub fn endpoint_interval(speed: u8, b_interval: u8) -> u8 {
+    match speed {
+        // bInterval en trames de 1 ms : 2^n × 125 µs ≈ bInterval × 8
+        SPEED_FULL | SPEED_LOW => {
+            let micro_frames = (b_interval.max(1) as u32) * 8;
+            (31 - micro_frames.leading_zeros()).clamp(3, 10) as u8
+        }
+        // Haut débit et SuperSpeed : bInterval est déjà l'exposant + 1
+        _ => b_interval.clamp(1, 16) - 1,
+    }
+}
+
+/// Taille de paquet par défaut de EP0 selon la vitesse
+pub fn default_max_packet(speed: u8) -> u16 {
+    match speed {
+        SPEED_LOW | SPEED_FULL => 8,
+        SPEED_HIGH => 64,
+        _ => 512,
+    }
+}
+
+/// Contrôleur xHCI
+pub struct XhciController {
+    pub function: PciFunction,
+    op: usize,
+    rt: usize,
+    db: usize,
+    pub max_slots: u8,
+    pub max_ports: u8,
+    context_size: usize,
+    dcbaa: DmaBuffer,
+    _scratchpad: Vec<DmaBuffer>,
+    commands: Ring,
+    events: EventRing,
+    devices: BTreeMap<u8, XhciDevice>,
+    /// Slot attaché à chaque port racine
+    port_slots: BTreeMap<u8, u8>,
+    /// Résultat de la dernière commande
+    command_result: Option<Trb>,
+    /// Événements de transfert EP0 pas encore consommés
+    control_events: Vec<Trb>,
+    /// Ports signalés par un événement Port Status Change
+    pending_ports: Vec<u8>,
+}
+
+fn read32(addr: usize) -> u32 {
+    unsafe { read_volatile(addr as *const u32) }
+}
+
+fn write32(addr: usize, value: u32) {
+    unsafe { write_volatile(addr as *mut u32, value) }
+}
+
+fn write64(addr: usize, value: u64) {
+    write32(addr, value as u32);
+    write32(addr + 4, (value >> 32) as u32);
+}
+
+/// Attend que `(lecture & mask) == expected`
+fn wait_for(addr: usize, mask: u32, expected: u32) -> bool {
+    for _ in 0..TIMEOUT_SPINS {
+        if read32(addr) & mask == expected {
+            return true;
+        }
+        core::hint::spin_loop();
+    }
+    false
+}
+
+impl XhciController {
+    /// Réinitialise le contrôleur et installe DCBAA, anneaux et interrupteur 0
+    pub fn probe(function: PciFunction) -> Result<Self, DriverError> {
+        let base = function.bar(0).ok_or(DriverError::NotSupported)? as usize;
+        function.enable_bus_master();
+
+        let caplength = read32(base + cap::CAPLENGTH) as u8 as usize;
+        let hcs1 = read32(base + cap::HCSPARAMS1);
+        let hcs2 = read32(base + cap::HCSPARAMS2);
+        let hcc1 = read32(base + cap::HCCPARAMS1);
+        let op = base + caplength;
+        let rt = base + (read32(base + cap::RTSOFF) & !0x1F) as usize;
+        let db = base + (read32(base + cap::DBOFF) & !0x3) as usize;
+
+        // Arrêt puis reset
+        write32(op + op::USBCMD, read32(op + op::USBCMD) & !CMD_RUN);
+        if !wait_for(op + op::USBSTS, STS_HALTED, STS_HALTED) {
+            return Err(DriverError::InitializationFailed);
+        }
+        write32(op + op::USBCMD, CMD_RESET);
+        if !wait_for(op + op::USBCMD, CMD_RESET, 0) || !wait_for(op + op::USBSTS, STS_CNR, 0) {
+            return Err(DriverError::InitializationFailed);
+        }
+
+        let max_slots = hcs1 as u8;
+        let max_ports = (hcs1 >> 24) as u8;
+        write32(op + op::CONFIG, max_slots as u32);
+
+        let dcbaa = DmaBuffer::new((max_slots as usize + 1) * 8).ok_or(DriverError::InitializationFailed)?;
+        let scratchpad_count = (((hcs2 >> 21) & 0x1F) << 5 | ((hcs2 >> 27) & 0x1F)) as usize;
+        let mut scratchpad = Vec::new();
+        if scratchpad_count > 0 {
+            let array = DmaBuffer::new(scratchpad_count * 8).ok_or(DriverError::InitializationFailed)?;
+            for i in 0..scratchpad_count {
+                let page = DmaBuffer::new(4096).ok_or(DriverError::InitializationFailed)?;
+                unsafe { write_volatile(array.as_ptr::<u64>(8 * i), page.phys()) }
+                scratchpad.push(page);
+            }
+            unsafe { write_volatile(dcbaa.as_ptr::<u64>(0), array.phys()) }
+            scratchpad.push(array);
+        }
+        write64(op + op::DCBAAP, dcbaa.phys());
+
+        let commands = Ring::new().ok_or(DriverError::InitializationFailed)?;
+        write64(op + op::CRCR, commands.phys() | TRB_CYCLE as u64);
+
+        let events = EventRing::new().ok_or(DriverError::InitializationFailed)?;
+        write32(rt + rt::ERSTSZ, 1);
+        write64(rt + rt::ERDP, events.dequeue_phys());
+        write64(rt + rt::ERSTBA, events.erst.phys());
+        // Modération : 1 ms (unités de 250 ns)
+        write32(rt + rt::IMOD, 4000);
+        write32(rt + rt::IMAN, IMAN_IP | IMAN_IE);
+
+        write32(op + op::USBCMD, CMD_RUN | CMD_INTE);
+        if !wait_for(op + op::USBSTS, STS_HALTED, 0) {
+            return Err(DriverError::InitializationFailed);
+        }
+
+        let controller = Self {
+            function,
+            op,
+            rt,
+            db,
+            max_slots,
+            max_ports,
+            context_size: if hcc1 & (1 << 2) != 0 { 64 } else { 32 },
+            dcbaa,
+            _scratchpad: scratchpad,
+            commands,
+            events,
+            devices: BTreeMap::new(),
+            port_slots: BTreeMap::new(),
+            command_result: None,
+            control_events: Vec::new(),
+            pending_ports: Vec::new(),
+        };
+
+        // Alimenter les ports qui ne le sont pas encore
+        for port in 1..=max_ports {
+            let portsc = controller.portsc(port);
+            if portsc & PORT_PP == 0 {
+                controller.write_portsc(port, (portsc & !PORT_PED & !PORT_CHANGE_BITS) | PORT_PP);
+            }
+        }
+        Ok(controller)
+    }
+
+    fn portsc(&self, port: u8) -> u32 {
+        read32(self.op + op::PORTSC + 0x10 * (port as usize - 1))
+    }
+
+    fn write_portsc(&self, port: u8, value: u32) {
+        write32(self.op + op::PORTSC + 0x10 * (port as usize - 1), value);
+    }
+
+    fn ring_doorbell(&self, slot: u8, target: u8) {
+        fence(Ordering::SeqCst);
+        write32(self.db + 4 * slot as usize, target as u32);
+    }
+
+    fn set_dcbaa(&self, slot: u8, addr: u64) {
+        unsafe { write_volatile(self.dcbaa.as_ptr::<u64>(8 * slot as usize), addr) }
+    }
+
+    /// Vide l'anneau d'événements ; retourne le nombre d'événements traités
+    pub fn process_events(&mut self) -> usize {
+        let mut count = 0;
+        while let Some(event) = self.events.pop() {
+            count += 1;
+            match event.kind() {
+                trb_type::COMMAND_COMPLETION => self.command_result = Some(event),
+                trb_type::TRANSFER_EVENT if event.endpoint_id() == 1 => self.control_events.push(event),
+                trb_type::TRANSFER_EVENT => {
+                    let device = match self.devices.get_mut(&event.slot_id()) {
+                        Some(device) => device,
+                        None => continue,
+                    };
+                    if let Some(ep) = device.endpoints.values_mut().find(|ep| ep.dci == event.endpoint_id()) {
+                        let code = event.completion_code();
+                        ep.completed = if code == CC_SUCCESS || code == CC_SHORT_PACKET {
+                            Some((ep.max_packet as usize).saturating_sub(event.residual()))
+                        } else {
+                            Some(0)
+                        };
+                    }
+                }
+                trb_type::PORT_STATUS_CHANGE => {
+                    let port = (event.parameter >> 24) as u8;
+                    if !self.pending_ports.contains(&port) {
+                        self.pending_ports.push(port);
+                    }
+                }
+                _ => {}
+            }
+        }
+        if count > 0 {
+            write64(self.rt + rt::ERDP, self.events.dequeue_phys() | ERDP_EHB);
+        }
+        write32(self.rt + rt::IMAN, IMAN_IP | IMAN_IE);
+        write32(self.op + op::USBSTS, STS_EINT);
+        count
+    }
+
+    /// Exécute une commande et attend sa complétion
+    fn command(&mut self, trb: Trb) -> Result<Trb, UsbError> {
+        self.command_result = None;
+        self.commands.push(trb);
+        self.ring_doorbell(0, 0);
+        for _ in 0..TIMEOUT_SPINS {
+            self.process_events();
+            if let Some(result) = self.command_result.take() {
+                return if result.completion_code() == CC_SUCCESS { Ok(result) } else { Err(UsbError::TransferFailed) };
+            }
+            core::hint::spin_loop();
+        }
+        Err(UsbError::Timeout)
+    }
+
+    /// Attend la fin d'un transfert de contrôle ; retourne les octets non transférés
+    fn wait_control(&mut self, slot: u8, data_trb: Option<u64>, status_trb: u64) -> Result<usize, UsbError> {
+        let mut residual = 0;
+        for _ in 0..TIMEOUT_SPINS {
+            self.process_events();
+            let events: Vec<Trb> = self.control_events.iter().filter(|e| e.slot_id() == slot).copied().collect();
+            self.control_events.retain(|e| e.slot_id() != slot);
+            for event in events {
+                match event.completion_code() {
+                    CC_SUCCESS | CC_SHORT_PACKET => {}
+                    CC_STALL => return Err(UsbError::Stall),
+                    _ => return Err(UsbError::TransferFailed),
+                }
+                if Some(event.parameter) == data_trb {
+                    residual = event.residual();
+                }
+                if event.parameter == status_trb {
+                    return Ok(residual);
+                }
+            }
+            core::hint::spin_loop();
+        }
+        Err(UsbError::Timeout)
+    }
+
+    fn context(buffer: &DmaBuffer, context_size: usize, index: usize) -> *mut u32 {
+        buffer.as_ptr::<u32>(context_size * index)
+    }
+
+    /// Obtient un slot et adresse le périphérique branché sur `port`
+    fn address_device(&mut self, port: u8, speed: u8) -> Result<u8, UsbError> {
+        let slot = self.command(Trb::new(trb_type::ENABLE_SLOT, 0, 0, 0))?.slot_id();
+        let size = self.context_size;
+        let buffer = |bytes: usize| DmaBuffer::new(bytes).ok_or(UsbError::InitializationFailed);
+        let device = XhciDevice {
+            port,
+            speed,
+            output: buffer(32 * size)?,
+            input: buffer(33 * size)?,
+            ep0: Ring::new().ok_or(UsbError::InitializationFailed)?,
+            control_buffer: buffer(CONTROL_BUFFER)?,
+            endpoints: BTreeMap::new(),
+        };
+
+        unsafe {
+            // Contexte de contrôle d'entrée : ajout du slot (A0) et de EP0 (A1)
+            write_volatile(Self::context(&device.input, size, 0).add(1), 0b11);
+            let slot_ctx = Self::context(&device.input, size, 1);
+            write_volatile(slot_ctx, (speed as u32) << 20 | 1 << 27);
+            write_volatile(slot_ctx.add(1), (port as u32) << 16);
+            let ep0 = Self::context(&device.input, size, 2);
+            write_volatile(ep0.add(1), 3 << 1 | EP_TYPE_CONTROL << 3 | (default_max_packet(speed) as u32) << 16);
+            write_volatile(ep0.add(2) as *mut u64, device.ep0.phys() | 1);
+            write_volatile(ep0.add(4), 8);
+        }
+
+        self.set_dcbaa(slot, device.output.phys());
+        let input = device.input.phys();
+        self.devices.insert(slot, device);
+        if let Err(e) = self.command(Trb::new(trb_type::ADDRESS_DEVICE, input, 0, (slot as u32) << 24)) {
+            self.release_slot(slot);
+            return Err(e);
+        }
+        Ok(slot)
+    }
+
+    /// Corrige la taille de paquet de EP0 annoncée par le descripteur
+    fn update_max_packet(&mut self, slot: u8, max_packet: u16) -> Result<(), UsbError> {
+        let size = self.context_size;
+        let input = {
+            let device = self.devices.get_mut(&slot).ok_or(UsbError::NotFound)?;
+            device.input.as_mut_slice().fill(0);
+            unsafe {
+                write_volatile(Self::context(&device.input, size, 0).add(1), 1 << 1);
+                let ep0 = Self::context(&device.input, size, 2);
+                write_volatile(ep0.add(1), 3 << 1 | EP_TYPE_CONTROL << 3 | (max_packet as u32) << 16);
+                write_volatile(ep0.add(2) as *mut u64, device.ep0.phys() | 1);
+                write_volatile(ep0.add(4), 8);
+            }
+            device.input.phys()
+        };
+        self.command(Trb::new(trb_type::EVALUATE_CONTEXT, input, 0, (slot as u32) << 24))?;
+        Ok(())
+    }
+
+    /// Arme un transfert IN sur un endpoint configuré
+    fn queue_in(&mut self, slot: u8, endpoint_address: u8) {
+        let dci = match self.devices.get_mut(&slot).and_then(|d| d.endpoints.get_mut(&endpoint_address)) {
+            Some(ep) => {
+                let trb = Trb::new(trb_type::NORMAL, ep.buffer.phys(), ep.max_packet as u32, TRB_IOC | TRB_ISP);
+                ep.ring.push(trb);
+                ep.dci
+            }
+            None => return,
+        };
+        self.ring_doorbell(slot, dci);
+    }
+
+    fn release_slot(&mut self, slot: u8) {
+        let _ = self.command(Trb::new(trb_type::DISABLE_SLOT, 0, 0, (slot as u32) << 24));
+        self.set_dcbaa(slot, 0);
+        self.devices.remove(&slot);
+    }
+
+    /// Réinitialise un port connecté ; retourne sa vitesse
+    fn reset_port(&mut self, port: u8) -> Result<u8, UsbError> {
+        let portsc = self.portsc(port);
+        // Les ports USB 3 s'activent seuls ; les ports USB 2 demandent un reset
+        if portsc & PORT_PED == 0 {
+            self.write_portsc(port, (portsc & !PORT_PED & !PORT_CHANGE_BITS) | PORT_PR);
+            let addr = self.op + op::PORTSC + 0x10 * (port as usize - 1);
+            if !wait_for(addr, PORT_PRC, PORT_PRC) {
+                return Err(UsbError::Timeout);
+            }
+        }
+        let portsc = self.portsc(port);
+        self.write_portsc(port, (portsc & !PORT_PED & !PORT_CHANGE_BITS) | PORT_PRC);
+        if portsc & PORT_PED == 0 {
+            return Err(UsbError::InitializationFailed);
+        }
+        Ok(((portsc >> 10) & 0xF) as u8)
+    }
+
+    /// Énumère le périphérique d'un port et attache les drivers de classe
+    pub fn attach_port(&mut self, port: u8) -> Result<u8, UsbError> {
+        let speed = self.reset_port(port)?;
+        let slot = self.address_device(port, speed)?;
+        self.port_slots.insert(port, slot);
+
+        // 8 premiers octets : taille réelle des paquets de EP0
+        let header = UsbEnumerator::read_descriptor(self, slot, DescriptorType::Device, 0, 8);
+        if let Ok(header) = header {
+            let max_packet = match header.get(7) {
+                Some(&size) if speed >= SPEED_SUPER => 1u16 << size.min(9),
+                Some(&size) => size as u16,
+                None => default_max_packet(speed),
+            };
+            if max_packet != default_max_packet(speed) {
+                self.update_max_packet(slot, max_packet)?;
+            }
+        }
+
+        let device = UsbEnumerator::enumerate_device(self, slot).map_err(|e| {
+            log::error!("xHCI: port {} : énumération échouée ({:?})", port, e);
+            UsbError::TransferFailed
+        })?;
+        let (vendor, product) = (device.descriptor.vendor_id, device.descriptor.product_id);
+        log::info!("xHCI: port {} slot {} -> {:04x}:{:04x}", port, slot, vendor, product);
+
+        for (interface, endpoints) in &device.interfaces {
+            for endpoint in endpoints {
+                if let Some(driver) = UsbHidDriver::probe(slot, interface, endpoint) {
+                    match usb_hid::attach_hid(self, driver) {
+                        Ok(name) => log::info!("xHCI: {} attaché", name),
+                        Err(e) => log::error!("xHCI: HID {:?}", e),
+                    }
+                }
+            }
+        }
+        Ok(slot)
+    }
+
+    /// Retire le périphérique d'un port débranché
+    pub fn detach_port(&mut self, port: u8) {
+        if let Some(slot) = self.port_slots.remove(&port) {
+            usb_hid::detach_hid_address(slot);
+            self.release_slot(slot);
+            log::info!("xHCI: port {} déconnecté (slot {})", port, slot);
+        }
+    }
+
+    /// Énumère les périphériques déjà branchés au démarrage
+    pub fn scan_ports(&mut self) -> usize {
+        let mut attached = 0;
+        for port in 1..=self.max_ports {
+            if self.portsc(port) & PORT_CCS != 0 && self.attach_port(port).is_ok() {
+                attached += 1;
+            }
+        }
+        attached
+    }
+
+    /// Traite les ports signalés par des événements Port Status Change
+    pub fn handle_port_changes(&mut self) {
+        for port in core::mem::take(&mut self.pending_ports) {
+            if port == 0 || port > self.max_ports {
+                continue;
+            }
+            let portsc = self.portsc(port);
+            // Acquitter les changements sans désactiver le port
+            self.write_portsc(port, (portsc & !PORT_PED & !PORT_CHANGE_BITS) | (portsc & PORT_CHANGE_BITS));
+            if portsc & PORT_CSC == 0 && portsc & PORT_CCS != 0 {
+                continue;
+            }
+            let connected = portsc & PORT_CCS != 0;
+            let attached = self.port_slots.contains_key(&port);
+            if attached {
+                self.detach_port(port);
+            }
+            if connected {
+                if let Err(e) = self.attach_port(port) {
+                    log::error!("xHCI: port {} : {:?}", port, e);
+                }
+            }
+        }
+    }
+
+    pub fn device_count(&self) -> usize {
+        self.devices.len()
+    }
+}
+
+impl UsbHostController for XhciController {
+    fn control_transfer(&mut self, device: u8, setup: &SetupPacket, data: &mut [u8]) -> Result<usize, UsbError> {
+        let len = data.len().min(CONTROL_BUFFER);
+        let device_in = setup.request_type & 0x80 != 0;
+        let raw_setup = unsafe { core::ptr::read_unaligned(setup as *const SetupPacket as *const u64) };
+
+        let (data_trb, status_trb) = {
+            let dev = self.devices.get_mut(&device).ok_or(UsbError::NotFound)?;
+            // Type de transfert du Setup : 0 sans données, 2 OUT, 3 IN
+            let trt = if len == 0 { 0 } else if device_in { 3 } else { 2 };
+            dev.ep0.push(Trb::new(trb_type::SETUP, raw_setup, 8, TRB_IDT | trt << 16));
+
+            let mut data_trb = None;
+            if len > 0 {
+                if !device_in {
+                    dev.control_buffer.as_mut_slice()[..len].copy_from_slice(&data[..len]);
+                }
+                let dir = if device_in { TRB_DIR_IN } else { 0 };
+                let trb = Trb::new(trb_type::DATA, dev.control_buffer.phys(), len as u32, TRB_ISP | dir);
+                data_trb = Some(dev.ep0.push(trb));
+            }
+            // Étape de statut dans le sens opposé aux données
+            let status_dir = if len == 0 || !device_in { TRB_DIR_IN } else { 0 };
+            let status_trb = dev.ep0.push(Trb::new(trb_type::STATUS, 0, 0, TRB_IOC | status_dir));
+            (data_trb, status_trb)
+        };
+        self.ring_doorbell(device, 1);
+
+        let residual = self.wait_control(device, data_trb, status_trb)?;
+        let transferred = len.saturating_sub(residual);
+        if device_in && transferred > 0 {
+            let dev = self.devices.get(&device).ok_or(UsbError::NotFound)?;
+            data[..transferred].copy_from_slice(&dev.control_buffer.as_slice()[..transferred]);
+        }
+        Ok(transferred)
+    }
+
+    fn configure_endpoint(&mut self, device: u8, endpoint: &EndpointDescriptor) -> Result<(), UsbError> {
+        let address = endpoint.endpoint_address;
+        let max_packet = endpoint.max_packet_size & 0x7FF;
+        let dci = endpoint_dci(address);
+        let ep_type = match (endpoint.transfer_type(), endpoint.is_in()) {
+            (TransferType::Interrupt, true) => EP_TYPE_INTERRUPT_IN,
+            (TransferType::Interrupt, false) => EP_TYPE_INTERRUPT_OUT,
+            (TransferType::Bulk, true) => EP_TYPE_BULK_IN,
+            (TransferType::Bulk, false) => EP_TYPE_BULK_OUT,
+            _ => return Err(UsbError::NotSupported),
+        };
+
+        let size = self.context_size;
+        let input = {
+            let dev = self.devices.get_mut(&device).ok_or(UsbError::NotFound)?;
+            let state = EndpointState {
+                dci,
+                ring: Ring::new().ok_or(UsbError::InitializationFailed)?,
+                buffer: DmaBuffer::new(max_packet.max(8) as usize).ok_or(UsbError::InitializationFailed)?,
+                max_packet,
+                completed: None,
+            };
+            let interval = endpoint_interval(dev.speed, endpoint.interval) as u32;
+
+            dev.input.as_mut_slice().fill(0);
+            unsafe {
+                // Ajout du slot (entrées de contexte mises à jour) et de l'endpoint
+                write_volatile(Self::context(&dev.input, size, 0).add(1), 1 | 1 << dci);
+                let slot_in = Self::context(&dev.input, size, 1);
+                let slot_out = Self::context(&dev.output, size, 0);
+                for i in 0..(size / 4) {
+                    write_volatile(slot_in.add(i), read_volatile(slot_out.add(i)));
+                }
+                let entries = (read_volatile(slot_in) >> 27).max(dci as u32);
+                write_volatile(slot_in, (read_volatile(slot_in) & !(0x1F << 27)) | entries << 27);
+
+                let ep = Self::context(&dev.input, size, dci as usize + 1);
+                write_volatile(ep, interval << 16);
+                write_volatile(ep.add(1), 3 << 1 | ep_type << 3 | (max_packet as u32) << 16);
+                write_volatile(ep.add(2) as *mut u64, state.ring.phys() | 1);
+                write_volatile(ep.add(4), (max_packet as u32) << 16 | max_packet as u32);
+            }
+            dev.endpoints.insert(address, state);
+            dev.input.phys()
+        };
+
+        if let Err(e) = self.command(Trb::new(trb_type::CONFIGURE_ENDPOINT, input, 0, (device as u32) << 24)) {
+            if let Some(dev) = self.devices.get_mut(&device) {
+                dev.endpoints.remove(&address);
+            }
+            return Err(e);
+        }
+        if endpoint.is_in() {
+            self.queue_in(device, address);
+        }
+        Ok(())
+    }
+
+    fn poll_interrupt(&mut self, device: u8, endpoint: u8, buf: &mut [u8]) -> Result<Option<usize>, UsbError> {
+        self.process_events();
+        let received = {
+            let ep = self.devices
+                .get_mut(&device)
+                .and_then(|d| d.endpoints.get_mut(&endpoint))
+                .ok_or(UsbError::NotFound)?;
+            match ep.completed.take() {
+                Some(n) => {
+                    let n = n.min(buf.len());
+                    buf[..n].copy_from_slice(&ep.buffer.as_slice()[..n]);
+                    n
+                }
+                None => return Ok(None),
+            }
+        };
+        self.queue_in(device, endpoint);
+        Ok(Some(received))
+    }
+}
+
+/// Contrôleur xHCI détecté (None avant `init` ou sans contrôleur)
+pub static XHCI_CONTROLLER: Mutex<Option<XhciController>> = Mutex::new(None);
+
+/// Événements, branchements et rapports HID en attente
+fn service(controller: &mut XhciController) {
+    controller.process_events();
+    controller.handle_port_changes();
+    usb_hid::poll_hid_devices(controller);
+}
+
+/// Détecte le premier contrôleur xHCI et énumère les ports connectés
+pub fn init() -> Result<usize, DriverError> {
+    let function = pci::find_by_class(0x0C, 0x03, Some(0x30))
+        .into_iter()
+        .next()
+        .ok_or(DriverError::NotFound)?;
+    let mut controller = XhciController::probe(function)?;
+    log::info!("xHCI: {} ports, {} slots", controller.max_ports, controller.max_slots);
+
+    let attached = controller.scan_ports();
+    // Les changements constatés pendant le scan sont déjà traités
+    controller.process_events();
+    controller.pending_ports.clear();
+    without_interrupts(|| *XHCI_CONTROLLER.lock() = Some(controller));
+    // MSI-X ou MSI si la fonction les propose, sinon la ligne INTx
+    match crate::interrupts::request_pci_interrupt(function, handle_interrupt) {
+        Ok(irq) => log::info!("xHCI: IRQ {}", irq),
+        Err(e) => log::warn!("xHCI: pas d'interruption ({:?})", e),
+    }
+    Ok(attached)
+}
+
+/// Scrutation périodique (tant que l'IRQ du contrôleur n'est pas routée)
+pub fn poll() {
+    without_interrupts(|| {
+        if let Some(controller) = XHCI_CONTROLLER.lock().as_mut() {
+            service(controller);
+        }
+    });
+}
+
+/// Gestionnaire d'interruption du contrôleur
+///
+/// Les énumérations (longues) restent à `poll` : seuls les événements et
+/// les rapports HID sont traités ici.
+pub fn handle_interrupt() {
+    if let Some(mut guard) = XHCI_CONTROLLER.try_lock() {
+        if let Some(controller) = guard.as_mut() {
+            controller.process_events();
+            usb_hid::poll_hid_devices(controller);
+        }
+    }
+}
+
+/// Enveloppe pour le gestionnaire de drivers
+pub struct XhciDriver;
+
+/// Énumération des ports et claviers/souris HID
+fn register() -> Result<(), DriverError> {
+    super::register_and_init("xhci", alloc::boxed::Box::new(XhciDriver))
+}
+
+crate::initcall!(driver, "xHCI", register);
+
+impl Driver for XhciDriver {
+    fn name(&self) -> &str {
+        "xhci"
+    }
+
+    fn init(&mut self) -> Result<(), DriverError> {
+        init().map(|_| ())
+    }
+
+    fn handle_interrupt(&mut self, _irq: u8) {
+        handle_interrupt();
+    }
+
+    fn shutdown(&mut self) -> Result<(), DriverError> {
+        without_interrupts(|| {
+            if let Some(controller) = XHCI_CONTROLLER.lock().take() {
+                write32(controller.op + op::USBCMD, 0);
+            }
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_endpoint_dci_and_interval() {
+        assert_eq!(endpoint_dci(0x00), 1);
+        assert_eq!(endpoint_dci(0x81), 3);
+        assert_eq!(endpoint_dci(0x02), 4);
+        // Souris pleine vitesse, bInterval = 10 ms -> 2^6 × 125 µs = 8 ms
+        assert_eq!(endpoint_interval(SPEED_FULL, 10), 6);
+        assert_eq!(endpoint_interval(SPEED_HIGH, 4), 3);
+    }
+
+    #[test_case]
+    fn test_ring_wraps_with_link_trb() {
+        let mut ring = Ring::new().unwrap();
+        let first = ring.push(Trb::new(trb_type::NORMAL, 0, 0, 0));
+        assert_eq!(first, ring.phys());
+        for _ in 1..RING_SIZE - 1 {
+            ring.push(Trb::new(trb_type::NORMAL, 0, 0, 0));
+        }
+        // Après le Link : retour au début avec le cycle inversé
+        assert!(!ring.cycle);
+        assert_eq!(ring.push(Trb::new(trb_type::NORMAL, 0, 0, 0)), ring.phys());
+    }
+}
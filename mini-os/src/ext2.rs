@@ -1,20 +1,46 @@
+/// ext2 : superbloc et descripteurs de groupes, bitmaps de blocs et
+/// d'inodes, blocs directs et indirects (simples, doubles, triples),
+/// répertoires en entrées chaînées. Le volume commence au secteur `start`
+/// du disque (une partition) ; `Ext2FileSystem` le monte dans le VFS.
+
+use alloc::collections::{BTreeMap, BTreeSet};
 use alloc::vec;
-use core::fmt;
 use alloc::vec::Vec;
 use alloc::string::String;
-use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use spin::Mutex;
 use crate::fs::{VfsError as FsError}; // Alias VfsError to FsError
+use crate::fs::{
+    alloc_fs_id, may_replace, DirEntry as VfsDirEntry, FileMode, FileStat, FileSystemOps, FileType, FsId,
+    InodeId, InodeOps, StatFs, Superblock, VfsResult,
+};
+use crate::drivers::block::BlockHandle;
 use crate::drivers::disk::Disk; // Use correct path for Disk trait
 
 // Constantes pour EXT2
 const EXT2_SIGNATURE: u16 = 0xEF53; // Signature EXT2
 const EXT2_ROOT_INO: u32 = 2;      // Inode de la racine
+const EXT2_S_IFMT: u16 = 0xF000;   // Masque du type de fichier
+const EXT2_S_IFSOCK: u16 = 0xC000; // Socket
+const EXT2_S_IFLNK: u16 = 0xA000;  // Lien symbolique
 const EXT2_S_IFREG: u16 = 0x8000;  // Fichier régulier
+const EXT2_S_IFBLK: u16 = 0x6000;  // Périphérique bloc
 const EXT2_S_IFDIR: u16 = 0x4000;  // Répertoire
-const EXT2_S_IFLNK: u16 = 0xA000;  // Lien symbolique
+const EXT2_S_IFCHR: u16 = 0x2000;  // Périphérique caractère
+const EXT2_S_IFIFO: u16 = 0x1000;  // Tube nommé
 
-// Taille des blocs (peut être 1024, 2048 ou 4096 octets)
-const BLOCK_SIZE: usize = 4096;
+/// Le superbloc est à 1024 octets du début du volume
+const SUPERBLOCK_SECTOR: u64 = 2;
+/// Premier inode non réservé des volumes de révision 0
+const EXT2_GOOD_OLD_FIRST_INO: u32 = 11;
+/// Nombre maximal de liens d'un inode
+const EXT2_LINK_MAX: u16 = 32000;
+/// Longueur maximale d'un nom d'entrée
+const EXT2_NAME_LEN: usize = 255;
+/// Blocs directs de `i_block`, suivis des trois niveaux d'indirection
+const EXT2_NDIR_BLOCKS: usize = 12;
+/// Les entrées de répertoire portent le type de fichier
+const EXT2_FEATURE_INCOMPAT_FILETYPE: u32 = 0x0002;
 
 // Structure du Superbloc EXT2
 #[repr(C, packed)]
@@ -47,6 +73,10 @@ pub struct SuperBlock {
     pub def_resgid: u16,            // GID par défaut pour les blocs réservés
     pub first_ino: u32,             // Premier inode non réservé
     pub inode_size: u16,            // Taille des inodes
+    pub block_group_nr: u16,        // Groupe portant cette copie du superbloc
+    pub feature_compat: u32,        // Fonctionnalités compatibles
+    pub feature_incompat: u32,      // Fonctionnalités incompatibles
+    pub feature_ro_compat: u32,     // Fonctionnalités compatibles en lecture seule
     // ... autres champs omis pour la brièveté
 }
 
@@ -64,6 +94,9 @@ pub struct BlockGroupDescriptor {
     pub reserved: [u32; 3],         // Réservé pour l'avenir
 }
 
+/// Taille d'un descripteur de groupe sur le disque
+const BGD_SIZE: usize = core::mem::size_of::<BlockGroupDescriptor>();
+
 // Structure d'un inode EXT2
 #[repr(C, packed)]
 #[derive(Debug, Clone, Copy)]
@@ -88,28 +121,72 @@ pub struct Inode {
     pub osd2: [u8; 12],             // OS dépendant 2
 }
 
-// Entrée de répertoire
-#[repr(C, packed)]
-#[derive(Debug, Clone, Copy)]
-pub struct DirEntry {
-    pub inode: u32,                 // Numéro d'inode
-    pub rec_len: u16,               // Longueur de l'entrée
-    pub name_len: u8,               // Longueur du nom
-    pub file_type: u8,              // Type de fichier
-    pub name: [u8; 255],            // Nom du fichier (taille maximale)
+impl Inode {
+    /// Inode neuf, sans bloc
+    fn new(mode: u16, links_count: u16, now: u32) -> Self {
+        Self {
+            mode,
+            uid: 0,
+            size: 0,
+            atime: now,
+            ctime: now,
+            mtime: now,
+            dtime: 0,
+            gid: 0,
+            links_count,
+            blocks: 0,
+            flags: 0,
+            osd1: 0,
+            block: [0; 15],
+            generation: 0,
+            file_acl: 0,
+            dir_acl: 0,
+            faddr: 0,
+            osd2: [0; 12],
+        }
+    }
+
+    /// UID sur 32 bits : 16 bits de poids fort dans osd2 (l_i_uid_high de Linux)
+    pub fn owner_uid(&self) -> u32 {
+        let high = u16::from_le_bytes([self.osd2[4], self.osd2[5]]);
+        (high as u32) << 16 | self.uid as u32
+    }
+
+    /// GID sur 32 bits (l_i_gid_high dans osd2)
+    pub fn owner_gid(&self) -> u32 {
+        let high = u16::from_le_bytes([self.osd2[6], self.osd2[7]]);
+        (high as u32) << 16 | self.gid as u32
+    }
+
+    pub fn set_owner(&mut self, uid: u32, gid: u32) {
+        self.uid = uid as u16;
+        self.gid = gid as u16;
+        self.osd2[4..6].copy_from_slice(&((uid >> 16) as u16).to_le_bytes());
+        self.osd2[6..8].copy_from_slice(&((gid >> 16) as u16).to_le_bytes());
+    }
+
+    fn is_dir(&self) -> bool {
+        self.mode & EXT2_S_IFMT == EXT2_S_IFDIR
+    }
 }
 
 // Structure principale du système de fichiers EXT2
 pub struct Ext2<D: Disk> {
     disk: D,
+    start: u64,                     // Premier secteur du volume
     block_size: usize,
     inodes_per_group: u32,
     blocks_per_group: u32,
     inode_size: u16,
     block_group_count: u32,
     first_data_block: u32,
+    first_ino: u32,                 // Premier inode non réservé
+    filetype: bool,                 // Type de fichier dans les entrées
     superblock: SuperBlock,
     block_groups: Vec<BlockGroupDescriptor>,
+    counters_dirty: bool,           // Compteurs libres à reporter sur le disque
+    open: BTreeMap<u32, usize>,     // Inodes ouverts (`Ext2InodeOps` vivants)
+    orphans: BTreeSet<u32>,         // Sans lien, libérés à leur dernière fermeture
 }
 
 // Erreurs spécifiques à EXT2
@@ -146,902 +223,1539 @@ impl From<Ext2Error> for FsError {
             Ext2Error::NotADirectory => FsError::NotDirectory,
             Ext2Error::NotAFile => FsError::IoError,
             Ext2Error::AlreadyExists => FsError::AlreadyExists,
-            Ext2Error::NoSpaceLeft => FsError::IoError,
+            Ext2Error::NoSpaceLeft => FsError::NoSpace,
             Ext2Error::IoError => FsError::IoError,
         }
     }
 }
 
+fn le16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn le32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]])
+}
+
+fn now() -> u32 {
+    crate::time::unix_time() as u32
+}
+
+/// Type VFS d'après le mode de l'inode
+fn file_type_of(mode: u16) -> FileType {
+    match mode & EXT2_S_IFMT {
+        EXT2_S_IFDIR => FileType::Directory,
+        EXT2_S_IFLNK => FileType::Symlink,
+        EXT2_S_IFCHR => FileType::CharDevice,
+        EXT2_S_IFBLK => FileType::BlockDevice,
+        EXT2_S_IFIFO => FileType::Fifo,
+        EXT2_S_IFSOCK => FileType::Socket,
+        _ => FileType::Regular,
+    }
+}
+
+/// Bits de type du mode pour un type VFS
+fn mode_type(file_type: FileType) -> u16 {
+    match file_type {
+        FileType::Regular => EXT2_S_IFREG,
+        FileType::Directory => EXT2_S_IFDIR,
+        FileType::Symlink => EXT2_S_IFLNK,
+        FileType::CharDevice => EXT2_S_IFCHR,
+        FileType::BlockDevice => EXT2_S_IFBLK,
+        FileType::Fifo => EXT2_S_IFIFO,
+        FileType::Socket => EXT2_S_IFSOCK,
+    }
+}
+
+/// Code EXT2_FT_* des entrées de répertoire
+fn dirent_type(file_type: FileType) -> u8 {
+    match file_type {
+        FileType::Regular => 1,
+        FileType::Directory => 2,
+        FileType::CharDevice => 3,
+        FileType::BlockDevice => 4,
+        FileType::Fifo => 5,
+        FileType::Socket => 6,
+        FileType::Symlink => 7,
+    }
+}
+
+/// Type VFS d'un code EXT2_FT_* (None : inconnu, lire l'inode)
+fn dirent_file_type(code: u8) -> Option<FileType> {
+    match code {
+        1 => Some(FileType::Regular),
+        2 => Some(FileType::Directory),
+        3 => Some(FileType::CharDevice),
+        4 => Some(FileType::BlockDevice),
+        5 => Some(FileType::Fifo),
+        6 => Some(FileType::Socket),
+        7 => Some(FileType::Symlink),
+        _ => None,
+    }
+}
+
+/// Place occupée par une entrée dont le nom fait `name_len` octets
+fn dirent_len(name_len: usize) -> usize {
+    (8 + name_len + 3) & !3
+}
+
+/// Entrée de répertoire à `pos` : (inode, rec_len, nom, type) ; None en fin
+/// de bloc ou sur une entrée incohérente
+fn dirent_at(block: &[u8], pos: usize) -> Option<(u32, usize, &[u8], u8)> {
+    let header = block.get(pos..pos + 8)?;
+    let rec_len = le16(header, 4) as usize;
+    let name_len = header[6] as usize;
+    if rec_len < 8 || rec_len % 4 != 0 || pos + rec_len > block.len() || 8 + name_len > rec_len {
+        return None;
+    }
+    Some((le32(header, 0), rec_len, &block[pos + 8..pos + 8 + name_len], header[7]))
+}
+
+fn put_dirent(block: &mut [u8], pos: usize, ino: u32, rec_len: usize, name: &[u8], file_type: u8) {
+    block[pos..pos + 4].copy_from_slice(&ino.to_le_bytes());
+    block[pos + 4..pos + 6].copy_from_slice(&(rec_len as u16).to_le_bytes());
+    block[pos + 6] = name.len() as u8;
+    block[pos + 7] = file_type;
+    block[pos + 8..pos + 8 + name.len()].copy_from_slice(name);
+}
+
+/// Nom acceptable pour une nouvelle entrée
+fn check_name(name: &str) -> Result<(), FsError> {
+    if name.len() > EXT2_NAME_LEN {
+        return Err(FsError::NameTooLong);
+    }
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\0']) {
+        return Err(FsError::InvalidArgument);
+    }
+    Ok(())
+}
+
+/// Premier bit nul de `bitmap` dans `from..count`
+fn find_free_bit(bitmap: &[u8], from: usize, count: usize) -> Option<usize> {
+    (from..count.min(bitmap.len() * 8)).find(|&bit| bitmap[bit / 8] & (1 << (bit % 8)) == 0)
+}
+
 impl<D: Disk> Ext2<D> {
     // Crée une nouvelle instance de EXT2 à partir d'un périphérique de disque
     pub fn new(disk: D) -> Result<Self, Ext2Error> {
+        Self::open(disk, 0)
+    }
+
+    /// Volume commençant au secteur `start` du disque (partition)
+    pub fn open(disk: D, start: u64) -> Result<Self, Ext2Error> {
         let mut superblock_buf = [0u8; 1024]; // Le superbloc fait 1024 octets
-        disk.read(1024, &mut superblock_buf)?; // Le superbloc est à 1024 octets de décalage
-        
-        let superblock = unsafe { &*(superblock_buf.as_ptr() as *const SuperBlock) };
-        
+        disk.read(start + SUPERBLOCK_SECTOR, &mut superblock_buf)?;
+        let superblock = unsafe { core::ptr::read_unaligned(superblock_buf.as_ptr() as *const SuperBlock) };
+
         // Vérifier la signature magique
         if superblock.magic != EXT2_SIGNATURE {
             return Err(Ext2Error::InvalidSignature);
         }
-        
+
+        let rev_level = superblock.rev_level;
+        let incompat = if rev_level >= 1 { superblock.feature_incompat } else { 0 };
+        let inode_size = if rev_level >= 1 { superblock.inode_size } else { 128 };
+        let (blocks_per_group, inodes_per_group) = (superblock.blocks_per_group, superblock.inodes_per_group);
+        // Blocs de 1 à 4 Kio ; les fonctionnalités incompatibles autres que
+        // le type dans les entrées (compression, extents...) ne sont pas gérées
+        if superblock.log_block_size > 2
+            || blocks_per_group == 0
+            || inodes_per_group == 0
+            || (inode_size as usize) < core::mem::size_of::<Inode>()
+            || incompat & !EXT2_FEATURE_INCOMPAT_FILETYPE != 0
+        {
+            return Err(Ext2Error::InvalidSuperblock);
+        }
+
         // Calculer la taille des blocs
-        let block_size = 1024 << superblock.log_block_size as usize;
-        
-        // Lire les descripteurs de groupe de blocs
-        let block_group_count = (superblock.blocks_count + superblock.blocks_per_group - 1) / superblock.blocks_per_group;
-        let bgdt_blocks = ((block_group_count as usize * 32) + block_size - 1) / block_size;
-        let mut bgdt = Vec::with_capacity(block_group_count as usize);
-        
-        let bgdt_start = if block_size > 1024 { 2 } else { 1 };
-        
-        for i in 0..block_group_count {
-            let mut bgd_buf = [0u8; 32]; // Chaque descripteur fait 32 octets
-            let offset = (bgdt_start + (i as usize * 32) / block_size) * block_size;
-            let bgd_offset = (i as usize * 32) % block_size;
-            
-            disk.read(offset as u64, &mut bgd_buf)?;
-            let bgd = unsafe { &*(bgd_buf.as_ptr() as *const BlockGroupDescriptor) };
-            bgdt.push(*bgd);
-        }
-        
-        Ok(Self {
+        let block_size = 1024usize << superblock.log_block_size;
+        if inode_size as usize > block_size {
+            return Err(Ext2Error::InvalidSuperblock);
+        }
+        let data_blocks = superblock.blocks_count
+            .checked_sub(superblock.first_data_block)
+            .ok_or(Ext2Error::InvalidSuperblock)?;
+
+        let mut fs = Self {
             disk,
+            start,
             block_size,
-            inodes_per_group: superblock.inodes_per_group,
-            blocks_per_group: superblock.blocks_per_group,
-            inode_size: if superblock.rev_level >= 1 { superblock.inode_size } else { 128 },
-            block_group_count,
+            inodes_per_group,
+            blocks_per_group,
+            inode_size,
+            block_group_count: data_blocks.div_ceil(blocks_per_group),
             first_data_block: superblock.first_data_block,
-            superblock: *superblock,
-            block_groups: bgdt,
-        })
+            first_ino: if rev_level >= 1 { superblock.first_ino } else { EXT2_GOOD_OLD_FIRST_INO },
+            filetype: incompat & EXT2_FEATURE_INCOMPAT_FILETYPE != 0,
+            superblock,
+            block_groups: Vec::new(),
+            counters_dirty: false,
+            open: BTreeMap::new(),
+            orphans: BTreeSet::new(),
+        };
+
+        // Lire les descripteurs de groupe de blocs
+        let table = fs.read_group_table()?;
+        fs.block_groups = table
+            .chunks_exact(BGD_SIZE)
+            .take(fs.block_group_count as usize)
+            .map(|raw| unsafe { core::ptr::read_unaligned(raw.as_ptr() as *const BlockGroupDescriptor) })
+            .collect();
+        Ok(fs)
+    }
+
+    /// Table des descripteurs de groupes (elle suit le bloc du superbloc),
+    /// en blocs entiers
+    fn read_group_table(&self) -> Result<Vec<u8>, Ext2Error> {
+        let len = (self.block_group_count as usize * BGD_SIZE).next_multiple_of(self.block_size);
+        let mut table = vec![0u8; len];
+        self.read_block(self.first_data_block + 1, &mut table)?;
+        Ok(table)
+    }
+
+    /// Premier secteur du bloc `block_num`
+    fn sector_of(&self, block_num: u32) -> u64 {
+        self.start + block_num as u64 * (self.block_size / 512) as u64
     }
-    
-    // Lit un bloc du disque
+
+    // Lit un bloc (ou plusieurs blocs consécutifs) du disque
     fn read_block(&self, block_num: u32, buf: &mut [u8]) -> Result<(), Ext2Error> {
-        let offset = (block_num as u64) * (self.block_size as u64);
-        self.disk.read(offset, buf).map_err(|_| Ext2Error::DiskError)
+        self.disk.read(self.sector_of(block_num), buf).map_err(|_| Ext2Error::DiskError)
     }
-    
-    // Écrit un bloc sur le disque
+
+    // Écrit un bloc (ou plusieurs blocs consécutifs) sur le disque
     fn write_block(&mut self, block_num: u32, buf: &[u8]) -> Result<(), Ext2Error> {
-        let offset = (block_num as u64) * (self.block_size as u64);
-        self.disk.write(offset, buf).map_err(|_| Ext2Error::DiskError)
+        let sector = self.sector_of(block_num);
+        self.disk.write(sector, buf).map_err(|_| Ext2Error::DiskError)
+    }
+
+    /// Reporte les compteurs de blocs et d'inodes libres dans le superbloc
+    /// et les descripteurs de groupes
+    fn write_counters(&mut self) -> Result<(), Ext2Error> {
+        if !self.counters_dirty {
+            return Ok(());
+        }
+        let sector = self.start + SUPERBLOCK_SECTOR;
+        let mut raw = [0u8; 1024];
+        self.disk.read(sector, &mut raw)?;
+        let sb = self.superblock;
+        raw[12..16].copy_from_slice(&sb.free_blocks_count.to_le_bytes());
+        raw[16..20].copy_from_slice(&sb.free_inodes_count.to_le_bytes());
+        self.disk.write(sector, &raw)?;
+
+        let mut table = self.read_group_table()?;
+        for (raw, bg) in table.chunks_exact_mut(BGD_SIZE).zip(self.block_groups.iter()) {
+            let bg = *bg;
+            raw[12..14].copy_from_slice(&bg.free_blocks_count.to_le_bytes());
+            raw[14..16].copy_from_slice(&bg.free_inodes_count.to_le_bytes());
+            raw[16..18].copy_from_slice(&bg.used_dirs_count.to_le_bytes());
+        }
+        self.write_block(self.first_data_block + 1, &table)?;
+        self.counters_dirty = false;
+        Ok(())
     }
-    
-    // Alloue un nouveau bloc
+
+    /// Alloue un bloc, rendu nul : les tables d'indirection, les blocs de
+    /// répertoire et les trous des fichiers le supposent
     fn allocate_block(&mut self) -> Result<u32, Ext2Error> {
-        let block_size = self.block_size;
-        let blocks_per_group = self.blocks_per_group;
-        
-        // Parcourir les groupes de blocs pour trouver un bloc libre
-        for group_idx in 0..self.block_groups.len() {
-            let block_bitmap = self.block_groups[group_idx].block_bitmap;
-            let free_count = self.block_groups[group_idx].free_blocks_count;
-            
-            if free_count == 0 {
+        let mut bitmap = vec![0u8; self.block_size];
+        for group in 0..self.block_groups.len() {
+            let bg = self.block_groups[group];
+            if bg.free_blocks_count == 0 {
                 continue;
             }
-            
-            // Lire le bitmap de blocs
-            let mut bitmap = vec![0u8; block_size];
-            self.read_block(block_bitmap, &mut bitmap)?;
-            
-            // Trouver un bit à 0 dans le bitmap
-            for byte_idx in 0..bitmap.len() {
-                if bitmap[byte_idx] != 0xFF {
-                    for bit in 0..8 {
-                        if (bitmap[byte_idx] & (1 << bit)) == 0 {
-                            // Marquer le bloc comme utilisé
-                            bitmap[byte_idx] |= 1 << bit;
-                            
-                            // Calculer le numéro de bloc global
-                            let block_in_group = (byte_idx * 8 + bit) as u32;
-                            let block_num = (group_idx as u32 * blocks_per_group) + block_in_group;
-                            
-                            // Mettre à jour le bitmap sur le disque
-                            self.write_block(block_bitmap, &bitmap)?;
-                            
-                            // Mettre à jour les compteurs
-                            self.block_groups[group_idx].free_blocks_count -= 1;
-                            self.superblock.free_blocks_count -= 1;
-                            
-                            return Ok(block_num);
-                        }
-                    }
-                }
-            }
+            let first = self.first_data_block + group as u32 * self.blocks_per_group;
+            let count = (self.superblock.blocks_count - first).min(self.blocks_per_group) as usize;
+            self.read_block(bg.block_bitmap, &mut bitmap)?;
+            let Some(bit) = find_free_bit(&bitmap, 0, count) else {
+                continue;
+            };
+
+            bitmap[bit / 8] |= 1 << (bit % 8);
+            self.write_block(bg.block_bitmap, &bitmap)?;
+            self.block_groups[group].free_blocks_count -= 1;
+            self.superblock.free_blocks_count -= 1;
+            self.counters_dirty = true;
+
+            let block_num = first + bit as u32;
+            bitmap.fill(0);
+            self.write_block(block_num, &bitmap)?;
+            return Ok(block_num);
         }
-        
         Err(Ext2Error::NoSpaceLeft)
     }
-    
-    // Alloue un nouvel inode
-    fn allocate_inode(&mut self) -> Result<u32, Ext2Error> {
-        let block_size = self.block_size;
-        let inodes_per_group = self.inodes_per_group;
-        
-        // Parcourir les groupes de blocs pour trouver un inode libre
-        for group_idx in 0..self.block_groups.len() {
-            let inode_bitmap = self.block_groups[group_idx].inode_bitmap;
-            let free_count = self.block_groups[group_idx].free_inodes_count;
-            
-            if free_count == 0 {
+
+    /// Rend un bloc au bitmap de son groupe
+    fn free_block(&mut self, block_num: u32) -> Result<(), Ext2Error> {
+        let relative = block_num
+            .checked_sub(self.first_data_block)
+            .filter(|_| block_num < self.superblock.blocks_count)
+            .ok_or(Ext2Error::IoError)?;
+        let group = (relative / self.blocks_per_group) as usize;
+        let bit = (relative % self.blocks_per_group) as usize;
+        let bitmap_block = self.block_groups[group].block_bitmap;
+
+        let mut bitmap = vec![0u8; self.block_size];
+        self.read_block(bitmap_block, &mut bitmap)?;
+        if bitmap[bit / 8] & (1 << (bit % 8)) == 0 {
+            return Ok(());
+        }
+        bitmap[bit / 8] &= !(1 << (bit % 8));
+        self.write_block(bitmap_block, &bitmap)?;
+        self.block_groups[group].free_blocks_count += 1;
+        self.superblock.free_blocks_count += 1;
+        self.counters_dirty = true;
+        Ok(())
+    }
+
+    /// Alloue un inode ; un répertoire compte dans `used_dirs_count`
+    fn allocate_inode(&mut self, is_dir: bool) -> Result<u32, Ext2Error> {
+        let mut bitmap = vec![0u8; self.block_size];
+        for group in 0..self.block_groups.len() {
+            let bg = self.block_groups[group];
+            if bg.free_inodes_count == 0 {
                 continue;
             }
-            
-            // Lire le bitmap d'inodes
-            let mut bitmap = vec![0u8; block_size];
-            self.read_block(inode_bitmap, &mut bitmap)?;
-            
-            // Trouver un bit à 0 dans le bitmap
-            for byte_idx in 0..bitmap.len() {
-                if bitmap[byte_idx] != 0xFF {
-                    for bit in 0..8 {
-                        if (bitmap[byte_idx] & (1 << bit)) == 0 {
-                            // Marquer l'inode comme utilisé
-                            bitmap[byte_idx] |= 1 << bit;
-                            
-                            // Calculer le numéro d'inode global
-                            let inode_in_group = (byte_idx * 8 + bit) as u32;
-                            let inode_num = (group_idx as u32 * inodes_per_group) + inode_in_group + 1;
-                            
-                            // Mettre à jour le bitmap sur le disque
-                            self.write_block(inode_bitmap, &bitmap)?;
-                            
-                            // Mettre à jour les compteurs
-                            self.block_groups[group_idx].free_inodes_count -= 1;
-                            self.superblock.free_inodes_count -= 1;
-                            
-                            return Ok(inode_num);
-                        }
-                    }
-                }
+            self.read_block(bg.inode_bitmap, &mut bitmap)?;
+            // Les inodes réservés (racine, journal...) précèdent `first_ino`
+            let from = if group == 0 { self.first_ino.saturating_sub(1) as usize } else { 0 };
+            let Some(bit) = find_free_bit(&bitmap, from, self.inodes_per_group as usize) else {
+                continue;
+            };
+
+            bitmap[bit / 8] |= 1 << (bit % 8);
+            self.write_block(bg.inode_bitmap, &bitmap)?;
+            self.block_groups[group].free_inodes_count -= 1;
+            if is_dir {
+                self.block_groups[group].used_dirs_count += 1;
             }
+            self.superblock.free_inodes_count -= 1;
+            self.counters_dirty = true;
+            return Ok(group as u32 * self.inodes_per_group + bit as u32 + 1);
         }
-        
         Err(Ext2Error::NoSpaceLeft)
     }
-    
-    // Met à jour un inode sur le disque
-    fn update_inode(&mut self, inode_num: u32, inode: &Inode) -> Result<(), Ext2Error> {
+
+    /// Rend un inode au bitmap de son groupe
+    fn free_inode(&mut self, inode_num: u32, is_dir: bool) -> Result<(), Ext2Error> {
+        let index = inode_num.checked_sub(1).ok_or(Ext2Error::InodeNotFound)?;
+        let group = (index / self.inodes_per_group) as usize;
+        let bit = (index % self.inodes_per_group) as usize;
+        let bitmap_block = self.block_groups.get(group).ok_or(Ext2Error::BlockGroupNotFound)?.inode_bitmap;
+
+        let mut bitmap = vec![0u8; self.block_size];
+        self.read_block(bitmap_block, &mut bitmap)?;
+        if bitmap[bit / 8] & (1 << (bit % 8)) == 0 {
+            return Ok(());
+        }
+        bitmap[bit / 8] &= !(1 << (bit % 8));
+        self.write_block(bitmap_block, &bitmap)?;
+        self.block_groups[group].free_inodes_count += 1;
+        if is_dir {
+            let used = self.block_groups[group].used_dirs_count;
+            self.block_groups[group].used_dirs_count = used.saturating_sub(1);
+        }
+        self.superblock.free_inodes_count += 1;
+        self.counters_dirty = true;
+        Ok(())
+    }
+
+    /// Bloc de la table d'inodes qui contient `inode_num`, et position de
+    /// l'inode dans ce bloc
+    fn inode_location(&self, inode_num: u32) -> Result<(u32, usize), Ext2Error> {
         if inode_num == 0 || inode_num > self.superblock.inodes_count {
             return Err(Ext2Error::InodeNotFound);
         }
-        
-        let inode_idx = inode_num - 1;
-        let group_idx = inode_idx / self.inodes_per_group;
-        let inode_in_group = inode_idx % self.inodes_per_group;
-        
-        if group_idx >= self.block_group_count {
-            return Err(Ext2Error::BlockGroupNotFound);
-        }
-        
-        let bg = &self.block_groups[group_idx as usize];
-        let inode_table_block = bg.inode_table;
-        let inode_offset = (inode_in_group as usize) * (self.inode_size as usize);
-        let block_offset = inode_offset / self.block_size;
-        let in_block_offset = inode_offset % self.block_size;
-        
-        // Lire le bloc contenant l'inode
+        let index = inode_num - 1;
+        let group = (index / self.inodes_per_group) as usize;
+        let inode_table = self.block_groups.get(group).ok_or(Ext2Error::BlockGroupNotFound)?.inode_table;
+        let offset = (index % self.inodes_per_group) as usize * self.inode_size as usize;
+        Ok((inode_table + (offset / self.block_size) as u32, offset % self.block_size))
+    }
+
+    // Trouve un inode par son numéro
+    fn get_inode(&self, inode_num: u32) -> Result<Inode, Ext2Error> {
+        let (block_num, offset) = self.inode_location(inode_num)?;
+        let mut block_buf = vec![0u8; self.block_size];
+        self.read_block(block_num, &mut block_buf)?;
+        Ok(unsafe { core::ptr::read_unaligned(block_buf[offset..].as_ptr() as *const Inode) })
+    }
+
+    // Met à jour un inode sur le disque
+    fn update_inode(&mut self, inode_num: u32, inode: &Inode) -> Result<(), Ext2Error> {
+        let (block_num, offset) = self.inode_location(inode_num)?;
         let mut block_buf = vec![0u8; self.block_size];
-        self.read_block(inode_table_block + block_offset as u32, &mut block_buf)?;
-        
-        // Mettre à jour l'inode dans le buffer
-        let inode_ptr = &mut block_buf[in_block_offset..in_block_offset + 128];
-        unsafe {
-            core::ptr::copy_nonoverlapping(
-                inode as *const _ as *const u8,
-                inode_ptr.as_mut_ptr(),
-                core::mem::size_of::<Inode>(),
-            );
-        }
-        
-        // Écrire le bloc mis à jour
-        self.write_block(inode_table_block + block_offset as u32, &block_buf)?;
-        
+        self.read_block(block_num, &mut block_buf)?;
+        let bytes = unsafe {
+            core::slice::from_raw_parts(inode as *const Inode as *const u8, core::mem::size_of::<Inode>())
+        };
+        block_buf[offset..offset + bytes.len()].copy_from_slice(bytes);
+        self.write_block(block_num, &block_buf)
+    }
+
+    /// Lien symbolique dont la cible (moins de 60 octets) est rangée dans
+    /// `i_block` : aucun bloc de données, hormis celui des attributs
+    fn is_fast_symlink(&self, inode: &Inode) -> bool {
+        let xattr_sectors = if inode.file_acl != 0 { (self.block_size / 512) as u32 } else { 0 };
+        inode.mode & EXT2_S_IFMT == EXT2_S_IFLNK && inode.blocks == xattr_sectors
+    }
+
+    /// Chemin d'un bloc logique : case de `i_block`, puis index dans chaque
+    /// table d'indirection traversée
+    fn block_path(&self, logical: u32) -> Result<(usize, Vec<u32>), Ext2Error> {
+        let per_block = (self.block_size / 4) as u64;
+        let mut rest = logical as u64;
+        if rest < EXT2_NDIR_BLOCKS as u64 {
+            return Ok((rest as usize, Vec::new()));
+        }
+        rest -= EXT2_NDIR_BLOCKS as u64;
+        let mut span = 1;
+        for depth in 1..=3 {
+            span *= per_block;
+            if rest < span {
+                let mut path = Vec::with_capacity(depth);
+                let mut unit = span;
+                for _ in 0..depth {
+                    unit /= per_block;
+                    path.push((rest / unit % per_block) as u32);
+                }
+                return Ok((EXT2_NDIR_BLOCKS - 1 + depth, path));
+            }
+            rest -= span;
+        }
+        Err(Ext2Error::NoSpaceLeft)
+    }
+
+    // Obtient le numéro de bloc physique pour un inode et un index de bloc
+    // logique (0 : trou)
+    fn get_block_number(&self, inode: &Inode, logical_block: u32) -> Result<u32, Ext2Error> {
+        let (slot, path) = self.block_path(logical_block)?;
+        let blocks = inode.block;
+        let mut block_num = blocks[slot];
+        let mut table = vec![0u8; self.block_size];
+        for index in path {
+            if block_num == 0 {
+                break;
+            }
+            self.read_block(block_num, &mut table)?;
+            block_num = le32(&table, index as usize * 4);
+        }
+        Ok(block_num)
+    }
+
+    /// Bloc physique du bloc logique `logical`, alloué avec ses tables
+    /// d'indirection s'il manque ; `i_blocks` suit les allocations
+    fn map_block(&mut self, inode: &mut Inode, logical: u32) -> Result<u32, Ext2Error> {
+        let (slot, path) = self.block_path(logical)?;
+        let sectors = (self.block_size / 512) as u32;
+        let mut blocks = inode.block;
+        if blocks[slot] == 0 {
+            blocks[slot] = self.allocate_block()?;
+            inode.block = blocks;
+            inode.blocks += sectors;
+        }
+
+        let mut block_num = blocks[slot];
+        let mut table = vec![0u8; self.block_size];
+        for index in path {
+            self.read_block(block_num, &mut table)?;
+            let pos = index as usize * 4;
+            let mut next = le32(&table, pos);
+            if next == 0 {
+                next = self.allocate_block()?;
+                table[pos..pos + 4].copy_from_slice(&next.to_le_bytes());
+                self.write_block(block_num, &table)?;
+                inode.blocks += sectors;
+            }
+            block_num = next;
+        }
+        Ok(block_num)
+    }
+
+    /// Libère les blocs logiques à partir de `keep` et les tables
+    /// d'indirection devenues inutiles
+    fn truncate_blocks(&mut self, inode: &mut Inode, keep: u32) -> Result<(), Ext2Error> {
+        let per_block = (self.block_size / 4) as u64;
+        let mut blocks = inode.block;
+        let mut freed = 0;
+        let mut first = 0;
+        for slot in 0..blocks.len() {
+            let depth = slot.saturating_sub(EXT2_NDIR_BLOCKS - 1) as u32;
+            let span = per_block.pow(depth);
+            if blocks[slot] != 0 && first + span > keep as u64 {
+                let skip = (keep as u64).saturating_sub(first);
+                if self.free_tree(blocks[slot], depth, skip, &mut freed)? {
+                    blocks[slot] = 0;
+                }
+            }
+            first += span;
+        }
+        inode.block = blocks;
+        inode.blocks = inode.blocks.saturating_sub(freed * (self.block_size / 512) as u32);
         Ok(())
     }
-    
-    // Écrit des données dans un inode, allouant des blocs si nécessaire
-    fn write_inode_data(&mut self, inode: &mut Inode, offset: usize, data: &[u8]) -> Result<usize, Ext2Error> {
-        let mut remaining = data.len();
-        let mut total_written = 0;
-        
-        while remaining > 0 {
-            let block_idx = (offset + total_written) / self.block_size;
-            let block_offset = (offset + total_written) % self.block_size;
-            let to_write = remaining.min(self.block_size - block_offset);
-            
-            // Obtenir le numéro de bloc, en allouant un nouveau bloc si nécessaire
-            let block_num = if block_idx < 12 {
-                // Bloc direct
-                if inode.block[block_idx] == 0 {
-                    let new_block = self.allocate_block()?;
-                    inode.block[block_idx] = new_block;
-                    new_block
+
+    /// Libère, dans l'arbre de profondeur `depth` enraciné en `block_num`,
+    /// les blocs d'index >= `skip` ; vrai si l'arbre entier a disparu
+    fn free_tree(&mut self, block_num: u32, depth: u32, skip: u64, freed: &mut u32) -> Result<bool, Ext2Error> {
+        if depth > 0 {
+            let per_block = self.block_size / 4;
+            let span = (per_block as u64).pow(depth - 1);
+            let mut table = vec![0u8; self.block_size];
+            self.read_block(block_num, &mut table)?;
+            let (mut changed, mut empty) = (false, true);
+            for index in 0..per_block {
+                let child = le32(&table, index * 4);
+                if child == 0 {
+                    continue;
+                }
+                let first = index as u64 * span;
+                if first + span > skip && self.free_tree(child, depth - 1, skip.saturating_sub(first), freed)? {
+                    table[index * 4..index * 4 + 4].fill(0);
+                    changed = true;
                 } else {
-                    inode.block[block_idx]
+                    empty = false;
+                }
+            }
+            if !empty {
+                if changed {
+                    self.write_block(block_num, &table)?;
                 }
+                return Ok(false);
+            }
+        }
+        self.free_block(block_num)?;
+        *freed += 1;
+        Ok(true)
+    }
+
+    // Lit les données d'un inode (les trous se lisent nuls)
+    fn read_inode_data(&self, inode: &Inode, offset: usize, buf: &mut [u8]) -> Result<usize, Ext2Error> {
+        let file_size = inode.size as usize;
+        if offset >= file_size {
+            return Ok(0);
+        }
+        let len = buf.len().min(file_size - offset);
+        let mut block_buf = vec![0u8; self.block_size];
+        let mut total_read = 0;
+
+        while total_read < len {
+            let pos = offset + total_read;
+            let block_offset = pos % self.block_size;
+            let to_read = (len - total_read).min(self.block_size - block_offset);
+            let block_num = self.get_block_number(inode, (pos / self.block_size) as u32)?;
+            let dest = &mut buf[total_read..total_read + to_read];
+            if block_num == 0 {
+                dest.fill(0);
             } else {
-                // Pour simplifier, on ne gère pas les blocs indirects ici
-                return Ok(total_written);
+                self.read_block(block_num, &mut block_buf)?;
+                dest.copy_from_slice(&block_buf[block_offset..block_offset + to_read]);
+            }
+            total_read += to_read;
+        }
+        Ok(len)
+    }
+
+    // Écrit des données dans un inode, allouant des blocs si nécessaire ;
+    // faute de place, rend ce qui a pu être écrit
+    fn write_inode_data(&mut self, inode: &mut Inode, offset: u64, data: &[u8]) -> Result<usize, Ext2Error> {
+        offset
+            .checked_add(data.len() as u64)
+            .filter(|&end| end <= u32::MAX as u64)
+            .ok_or(Ext2Error::NoSpaceLeft)?;
+        let offset = offset as usize;
+        let mut block_buf = vec![0u8; self.block_size];
+        let mut total_written = 0;
+
+        while total_written < data.len() {
+            let pos = offset + total_written;
+            let block_offset = pos % self.block_size;
+            let to_write = (data.len() - total_written).min(self.block_size - block_offset);
+            let block_num = match self.map_block(inode, (pos / self.block_size) as u32) {
+                Ok(block_num) => block_num,
+                Err(_) if total_written > 0 => break,
+                Err(e) => return Err(e),
             };
-            
-            // Lire le bloc existant ou créer un nouveau
-            let mut block_buf = vec![0u8; self.block_size];
-            if block_offset > 0 || to_write < self.block_size {
+
+            // Lire le bloc existant si on ne le remplace qu'en partie
+            if to_write < self.block_size {
                 self.read_block(block_num, &mut block_buf)?;
             }
-            
-            // Copier les données dans le buffer du bloc
-            let start = total_written;
-            let end = start + to_write;
-            block_buf[block_offset..block_offset + to_write].copy_from_slice(&data[start..end]);
-            
-            // Écrire le bloc mis à jour
+            block_buf[block_offset..block_offset + to_write]
+                .copy_from_slice(&data[total_written..total_written + to_write]);
             self.write_block(block_num, &block_buf)?;
-            
             total_written += to_write;
-            remaining -= to_write;
         }
-        
+
         // Mettre à jour la taille du fichier si nécessaire
         let new_size = (offset + total_written) as u32;
         if new_size > inode.size {
             inode.size = new_size;
         }
-        
         Ok(total_written)
     }
-    
-    // Ajoute une entrée à un répertoire
-    fn add_dir_entry(&mut self, dir_inode: &mut Inode, name: &str, inode_num: u32, file_type: u8) -> Result<(), Ext2Error> {
-        let name_bytes = name.as_bytes();
-        let name_len = name_bytes.len();
-        
-        if name_len > 255 {
-            return Err(Ext2Error::InvalidPath);
-        }
-        
-        // Calculer la taille nécessaire pour la nouvelle entrée
-        let entry_len = 8 + ((name_len + 3) & !3); // Alignement sur 4 octets
-        
-        // Parcourir le répertoire pour trouver un emplacement libre
-        let mut offset = 0;
+
+    /// Parcourt les entrées de `dir` ; `visit(bloc physique, contenu du
+    /// bloc, position, position de l'entrée précédente)` arrête le parcours
+    /// en retournant Some
+    fn scan_dir<R>(
+        &self,
+        dir: &Inode,
+        mut visit: impl FnMut(u32, &[u8], usize, Option<usize>) -> Option<R>,
+    ) -> Result<Option<R>, Ext2Error> {
         let mut buf = vec![0u8; self.block_size];
-        let mut found_space = false;
-        let mut space_pos = 0;
-        let mut space_size = 0;
-        
-        loop {
-            let read = self.read_inode_data(dir_inode, offset, &mut buf)?;
-            if read == 0 {
-                break;
+        for logical in 0..(dir.size as usize).div_ceil(self.block_size) {
+            crate::scheduler::cond_resched();
+            let phys = self.get_block_number(dir, logical as u32)?;
+            if phys == 0 {
+                continue;
             }
-            
-            let mut pos = 0;
-            while pos + 8 <= read {
-                let entry = unsafe { &*(&buf[pos..] as *const _ as *const DirEntry) };
-                let entry_len = entry.rec_len as usize;
-                
-                if entry.inode == 0 && entry.rec_len as usize >= entry_len {
-                    // Espace libre trouvé
-                    found_space = true;
-                    space_pos = offset + pos;
-                    space_size = entry.rec_len as usize;
-                    break;
-                }
-                
-                if entry_len == 0 || pos + entry_len > read {
-                    break;
+            self.read_block(phys, &mut buf)?;
+
+            let (mut pos, mut prev) = (0, None);
+            while let Some((_, rec_len, _, _)) = dirent_at(&buf, pos) {
+                if let Some(found) = visit(phys, &buf, pos, prev) {
+                    return Ok(Some(found));
                 }
-                
-                pos += entry_len;
+                prev = Some(pos);
+                pos += rec_len;
             }
-            
-            if found_space {
-                break;
+        }
+        Ok(None)
+    }
+
+    /// Inode et code de type de l'entrée `name` de `dir`
+    fn find_entry(&self, dir: &Inode, name: &str) -> Result<Option<(u32, u8)>, Ext2Error> {
+        self.scan_dir(dir, |_, block, pos, _| {
+            let (ino, _, entry, file_type) = dirent_at(block, pos)?;
+            (ino != 0 && entry == name.as_bytes()).then_some((ino, file_type))
+        })
+    }
+
+    // Localise une entrée : (bloc physique, position dans le bloc, position de l'entrée précédente)
+    fn locate_dir_entry(&self, dir: &Inode, name: &str) -> Result<(u32, usize, Option<usize>), Ext2Error> {
+        self.scan_dir(dir, |phys, block, pos, prev| {
+            let (ino, _, entry, _) = dirent_at(block, pos)?;
+            (ino != 0 && entry == name.as_bytes()).then_some((phys, pos, prev))
+        })?
+        .ok_or(Ext2Error::InodeNotFound)
+    }
+
+    /// Le répertoire ne contient-il que "." et ".." ?
+    fn dir_is_empty(&self, dir: &Inode) -> Result<bool, Ext2Error> {
+        let other = self.scan_dir(dir, |_, block, pos, _| {
+            let (ino, _, name, _) = dirent_at(block, pos)?;
+            (ino != 0 && name != b"." && name != b"..").then_some(())
+        })?;
+        Ok(other.is_none())
+    }
+
+    /// Code de type à écrire dans une entrée (0 sans la fonctionnalité filetype)
+    fn entry_code(&self, file_type: FileType) -> u8 {
+        if self.filetype { dirent_type(file_type) } else { 0 }
+    }
+
+    // Ajoute l'entrée `name` -> `inode_num` au répertoire `dir_num` : dans
+    // la place libre d'une entrée existante, sinon dans un nouveau bloc
+    fn add_dir_entry(&mut self, dir_num: u32, name: &str, inode_num: u32, file_type: FileType) -> Result<(), Ext2Error> {
+        let mut dir = self.get_inode(dir_num)?;
+        let needed = dirent_len(name.len());
+        let code = self.entry_code(file_type);
+        let slot = self.scan_dir(&dir, |phys, block, pos, _| {
+            let (ino, rec_len, entry, _) = dirent_at(block, pos)?;
+            let used = if ino == 0 { 0 } else { dirent_len(entry.len()) };
+            (rec_len >= used + needed).then_some((phys, pos, used, rec_len))
+        })?;
+
+        let mut block_buf = vec![0u8; self.block_size];
+        match slot {
+            Some((phys, pos, used, rec_len)) => {
+                // L'entrée en place garde sa taille utile, la nouvelle prend le reste
+                self.read_block(phys, &mut block_buf)?;
+                if used > 0 {
+                    block_buf[pos + 4..pos + 6].copy_from_slice(&(used as u16).to_le_bytes());
+                }
+                put_dirent(&mut block_buf, pos + used, inode_num, rec_len - used, name.as_bytes(), code);
+                self.write_block(phys, &block_buf)?;
             }
-            
-            offset += read;
-        }
-        
-        // Si aucun espace libre n'a été trouvé, agrandir le répertoire
-        if !found_space {
-            // Pour simplifier, on alloue un nouveau bloc
-            let block_num = self.allocate_block()?;
-            let block_idx = (offset + self.block_size - 1) / self.block_size;
-            
-            if block_idx < 12 {
-                dir_inode.block[block_idx] = block_num;
-            } else {
-                // Gérer les blocs indirects (non implémenté ici)
-                return Err(Ext2Error::NoSpaceLeft);
+            None => {
+                let logical = (dir.size as usize).div_ceil(self.block_size) as u32;
+                let phys = self.map_block(&mut dir, logical)?;
+                put_dirent(&mut block_buf, 0, inode_num, self.block_size, name.as_bytes(), code);
+                self.write_block(phys, &block_buf)?;
+                dir.size = (logical + 1) * self.block_size as u32;
             }
-            
-            // Initialiser le nouveau bloc avec une entrée vide
-            let mut new_block = vec![0u8; self.block_size];
-            let new_entry = DirEntry {
-                inode: 0,
-                rec_len: self.block_size as u16,
-                name_len: 0,
-                file_type: 0,
-                name: [0; 255],
-            };
-            
-            // Copier la structure dans le buffer
-            let new_entry_slice = unsafe {
-                core::slice::from_raw_parts(
-                    &new_entry as *const _ as *const u8,
-                    core::mem::size_of::<DirEntry>()
-                )
-            };
-            new_block[..new_entry_slice.len()].copy_from_slice(new_entry_slice);
-            
-            // Écrire le nouveau bloc
-            self.write_block(block_num, &new_block)?;
-            
-            space_pos = offset;
-            space_size = self.block_size;
-        }
-        
-        // Lire le bloc contenant l'espace libre
-        let block_offset = space_pos % self.block_size;
-        let block_num = {
-            let block_idx = space_pos / self.block_size;
-            if block_idx < 12 {
-                dir_inode.block[block_idx]
-            } else {
-                // Gérer les blocs indirects (non implémenté ici)
-                return Err(Ext2Error::NoSpaceLeft);
+        }
+
+        let now = now();
+        dir.mtime = now;
+        dir.ctime = now;
+        self.update_inode(dir_num, &dir)
+    }
+
+    // Retire une entrée de répertoire (fusionnée avec la précédente, ou marquée libre)
+    fn remove_dir_entry(&mut self, dir_num: u32, name: &str) -> Result<(), Ext2Error> {
+        let mut dir = self.get_inode(dir_num)?;
+        let (phys, pos, prev) = self.locate_dir_entry(&dir, name)?;
+        let mut buf = vec![0u8; self.block_size];
+        self.read_block(phys, &mut buf)?;
+
+        match prev {
+            Some(prev_pos) => {
+                let merged = le16(&buf, prev_pos + 4) + le16(&buf, pos + 4);
+                buf[prev_pos + 4..prev_pos + 6].copy_from_slice(&merged.to_le_bytes());
             }
-        };
-        
-        let mut block_buf = vec![0u8; self.block_size];
-        self.read_block(block_num, &mut block_buf)?;
-        
-        // Créer la nouvelle entrée
-        let mut new_entry = DirEntry {
-            inode: inode_num,
-            rec_len: entry_len as u16,
-            name_len: name_len as u8,
-            file_type,
-            name: [0; 255],
-        };
-        
-        // Copier le nom
-        new_entry.name[..name_len].copy_from_slice(name_bytes);
-        
-        // Mettre à jour la longueur de l'entrée précédente si nécessaire
-        if space_size > entry_len {
-            let remaining_space = space_size - entry_len;
-            let prev_entry = unsafe { &mut *(&mut block_buf[block_offset] as *mut _ as *mut DirEntry) };
-            prev_entry.rec_len = remaining_space as u16;
-            
-            // Déplacer le pointeur vers la nouvelle entrée
-            let new_entry_pos = block_offset + remaining_space;
-            
-            // Copier la nouvelle entrée
-            let new_entry_slice = unsafe {
-                core::slice::from_raw_parts(
-                    &new_entry as *const _ as *const u8,
-                    core::mem::size_of::<DirEntry>()
-                )
-            };
-            
-            block_buf[new_entry_pos..new_entry_pos + entry_len].copy_from_slice(&new_entry_slice[..entry_len]);
-        } else {
-            // Pas assez d'espace pour diviser, écraser l'entrée vide
-            let new_entry_slice = unsafe {
-                core::slice::from_raw_parts(
-                    &new_entry as *const _ as *const u8,
-                    core::mem::size_of::<DirEntry>()
-                )
-            };
-            
-            block_buf[block_offset..block_offset + entry_len].copy_from_slice(&new_entry_slice[..entry_len]);
-        }
-        
-        // Écrire le bloc mis à jour
-        self.write_block(block_num, &block_buf)?;
-        
-        // Mettre à jour le nombre de liens du répertoire parent
-        if inode_num != EXT2_ROOT_INO {
-            dir_inode.links_count += 1;
-        }
-        
-        Ok(())
+            None => buf[pos..pos + 4].fill(0),
+        }
+        self.write_block(phys, &buf)?;
+
+        let now = now();
+        dir.mtime = now;
+        dir.ctime = now;
+        self.update_inode(dir_num, &dir)
     }
-    
-    // Trouve un inode par son numéro
-    fn get_inode(&self, inode_num: u32) -> Result<Inode, Ext2Error> {
-        if inode_num == 0 || inode_num > self.superblock.inodes_count {
-            return Err(Ext2Error::InodeNotFound);
+
+    // Fait pointer l'entrée `name` vers un autre inode
+    fn set_dir_entry_inode(&mut self, dir_num: u32, name: &str, inode_num: u32, file_type: FileType) -> Result<(), Ext2Error> {
+        let mut dir = self.get_inode(dir_num)?;
+        let (phys, pos, _) = self.locate_dir_entry(&dir, name)?;
+        let mut buf = vec![0u8; self.block_size];
+        self.read_block(phys, &mut buf)?;
+        buf[pos..pos + 4].copy_from_slice(&inode_num.to_le_bytes());
+        buf[pos + 7] = self.entry_code(file_type);
+        self.write_block(phys, &buf)?;
+
+        let now = now();
+        dir.mtime = now;
+        dir.ctime = now;
+        self.update_inode(dir_num, &dir)
+    }
+
+    /// Ajoute `delta` au compteur de liens de `inode_num`
+    fn add_links(&mut self, inode_num: u32, delta: i16) -> Result<(), Ext2Error> {
+        let mut inode = self.get_inode(inode_num)?;
+        inode.links_count = inode.links_count.saturating_add_signed(delta);
+        inode.ctime = now();
+        self.update_inode(inode_num, &inode)
+    }
+
+    /// Retire un lien de `inode_num` ; au dernier, l'inode est libéré, ou
+    /// seulement rendu orphelin tant qu'il reste ouvert
+    fn drop_link(&mut self, inode_num: u32) -> Result<(), Ext2Error> {
+        let mut inode = self.get_inode(inode_num)?;
+        inode.links_count = inode.links_count.saturating_sub(1);
+        inode.ctime = now();
+        if inode.links_count == 0 && !self.open.contains_key(&inode_num) {
+            return self.release_inode(inode_num, &mut inode);
         }
-        
-        let inode_idx = inode_num - 1;
-        let group_idx = inode_idx / self.inodes_per_group;
-        let inode_in_group = inode_idx % self.inodes_per_group;
-        
-        if group_idx >= self.block_group_count {
-            return Err(Ext2Error::BlockGroupNotFound);
-        }
-        
-        let bg = &self.block_groups[group_idx as usize];
-        let inode_table_block = bg.inode_table;
-        let inode_offset = (inode_in_group as usize) * (self.inode_size as usize);
-        let block_offset = inode_offset / self.block_size;
-        let in_block_offset = inode_offset % self.block_size;
-        
-        let mut block_buf = vec![0u8; self.block_size];
-        self.read_block(inode_table_block + block_offset as u32, &mut block_buf)?;
-        
-        // Extraire l'inode du bloc
-        let inode_ptr = &block_buf[in_block_offset..in_block_offset + 128]; // Taille minimale d'un inode
-        let inode = unsafe { &*(inode_ptr.as_ptr() as *const Inode) };
-        
-        Ok(*inode)
-    }
-    
-    // Lit les données d'un inode
-    fn read_inode_data(&self, inode: &Inode, offset: usize, buf: &mut [u8]) -> Result<usize, Ext2Error> {
-        let file_size = inode.size as usize;
-        let mut remaining = buf.len().min(file_size.saturating_sub(offset));
-        let mut total_read = 0;
-        
-        while remaining > 0 && offset + total_read < file_size {
-            let block_idx = (offset + total_read) / self.block_size;
-            let block_offset = (offset + total_read) % self.block_size;
-            let to_read = remaining.min(self.block_size - block_offset);
-            
-            // Obtenir le numéro de bloc physique
-            let block_num = self.get_block_number(inode, block_idx as u32)?;
-            
-            // Lire le bloc
-            let mut block_buf = vec![0u8; self.block_size];
-            self.read_block(block_num, &mut block_buf)?;
-            
-            // Copier les données dans le buffer de sortie
-            let start = block_offset;
-            let end = start + to_read;
-            buf[total_read..total_read + to_read].copy_from_slice(&block_buf[start..end]);
-            
-            total_read += to_read;
-            remaining -= to_read;
+        if inode.links_count == 0 {
+            self.orphans.insert(inode_num);
         }
-        
-        Ok(total_read)
+        self.update_inode(inode_num, &inode)
     }
-    
-    // Obtient le numéro de bloc physique pour un inode et un index de bloc logique
-    fn get_block_number(&self, inode: &Inode, logical_block: u32) -> Result<u32, Ext2Error> {
-        // Implémentation simplifiée - ne gère que les blocs directs
-        if logical_block < 12 {
-            // Blocs directs
-            Ok(inode.block[logical_block as usize])
-        } else {
-            // Pour une implémentation complète, il faudrait gérer les blocs indirects
-            // simples, doubles et triples. Ici, on retourne une erreur.
-            Err(Ext2Error::IoError)
-        }
-    }
-    
-    // Trouve une entrée dans un répertoire
-    fn find_entry_in_dir(&self, dir_inode: &Inode, name: &str) -> Result<DirEntry, Ext2Error> {
-        let mut offset = 0;
-        let mut buf = vec![0u8; self.block_size];
-        
-        loop {
-            let read = self.read_inode_data(dir_inode, offset, &mut buf)?;
-            if read == 0 {
-                break;
-            }
-            
-            let mut pos = 0;
-            while pos + 8 <= read {
-                let entry = unsafe { &*(&buf[pos..] as *const _ as *const DirEntry) };
-                let entry_len = entry.rec_len as usize;
-                
-                if entry.inode != 0 {
-                    let entry_name = String::from_utf8_lossy(&entry.name[..entry.name_len as usize]);
-                    if entry_name == name {
-                        return Ok(*entry);
-                    }
-                }
-                
-                if entry_len == 0 || pos + entry_len > read {
-                    break;
-                }
-                
-                pos += entry_len;
-                offset += entry_len;
+
+    /// Libère les blocs de données, le bloc d'attributs étendus (ou une
+    /// référence s'il est partagé) puis l'inode lui-même
+    fn release_inode(&mut self, inode_num: u32, inode: &mut Inode) -> Result<(), Ext2Error> {
+        if !self.is_fast_symlink(inode) {
+            self.truncate_blocks(inode, 0)?;
+        }
+        let xattr_block = inode.file_acl;
+        if xattr_block != 0 {
+            let mut block = vec![0u8; self.block_size];
+            self.read_block(xattr_block, &mut block)?;
+            let refcount = le32(&block, 4);
+            if refcount > 1 {
+                block[4..8].copy_from_slice(&(refcount - 1).to_le_bytes());
+                self.write_block(xattr_block, &block)?;
+            } else {
+                self.free_block(xattr_block)?;
             }
+            inode.file_acl = 0;
         }
-        
-        Err(Ext2Error::InodeNotFound)
+        inode.links_count = 0;
+        inode.size = 0;
+        inode.blocks = 0;
+        inode.dtime = now();
+        self.update_inode(inode_num, inode)?;
+        self.free_inode(inode_num, inode.is_dir())
     }
 }
 
-// Implémentation du trait FileSystem pour EXT2
-// impl<D: Disk> crate::fs::FileSystem for Ext2<D> { // Commented out as currently FileSystem trait is not fully unified or defined in fs::mod.rs exposed way
-// For now, let's just make it standalone or use simplified methods
+// Opérations par numéro d'inode, au service du VFS (`Ext2InodeOps`) et de
+// l'accès par chemin. Chaque opération qui modifie le volume reporte les
+// compteurs de blocs et d'inodes libres avant de rendre la main.
 impl<D: Disk> Ext2<D> {
-    pub fn read_dir(&self, path: &str) -> Result<Vec<String>, FsError> {
-        let inode = if path.is_empty() || path == "/" {
-            self.get_inode(EXT2_ROOT_INO)
-        } else {
-            let dir_inode = self.get_inode(EXT2_ROOT_INO)?;
-            let entry = self.find_entry_in_dir(&dir_inode, path.trim_start_matches('/'))?;
-            self.get_inode(entry.inode)
-        }.map_err(|e| FsError::from(e))?;
-        
-        if (inode.mode & EXT2_S_IFDIR) == 0 {
+    /// Inode `inode_num`, qui doit être un répertoire
+    fn dir_inode(&self, inode_num: u32) -> VfsResult<Inode> {
+        let inode = self.get_inode(inode_num)?;
+        if !inode.is_dir() {
             return Err(FsError::NotDirectory);
         }
-        
-        let mut entries = Vec::new();
-        let mut offset = 0;
-        let mut buf = vec![0u8; self.block_size];
-        
-        loop {
-            let read = self.read_inode_data(&inode, offset, &mut buf)
-                .map_err(|e| FsError::from(e))?;
-                
-            if read == 0 {
-                break;
-            }
-            
-            let mut pos = 0;
-            while pos + 8 <= read {
-                let entry = unsafe { &*(&buf[pos..] as *const _ as *const DirEntry) };
-                let entry_len = entry.rec_len as usize;
-                
-                if entry.inode != 0 && entry.name_len > 0 {
-                    let name = String::from_utf8_lossy(&entry.name[..entry.name_len as usize]);
-                    if name != "." && name != ".." {
-                        entries.push(name.into_owned());
-                    }
-                }
-                
-                if entry_len == 0 || pos + entry_len > read {
-                    break;
-                }
-                
-                pos += entry_len;
-                offset += entry_len;
-            }
+        Ok(inode)
+    }
+
+    fn lookup_in(&self, dir_num: u32, name: &str) -> VfsResult<u32> {
+        let dir = self.dir_inode(dir_num)?;
+        if name == "." {
+            return Ok(dir_num);
         }
-        
-        Ok(entries)
+        Ok(self.find_entry(&dir, name)?.ok_or(FsError::NotFound)?.0)
     }
-    
-    pub fn read_file(&self, path: &str) -> Result<Vec<u8>, FsError> {
-        let inode = if path.is_empty() || path == "/" {
-            return Err(FsError::IoError);
-        } else {
-            let dir_inode = self.get_inode(EXT2_ROOT_INO).map_err(|e| FsError::from(e))?;
-            let entry = self.find_entry_in_dir(&dir_inode, path.trim_start_matches('/'))
-                .map_err(|e| FsError::from(e))?;
-            self.get_inode(entry.inode).map_err(|e| FsError::from(e))?
-        };
-        
-        if (inode.mode & EXT2_S_IFREG) == 0 {
-            return Err(FsError::IoError);
+
+    /// Crée l'entrée `name` dans `dir_num` ; un répertoire reçoit "." et
+    /// "..", et son parent un lien de plus
+    fn create_in(&mut self, dir_num: u32, name: &str, perm: u16, file_type: FileType) -> VfsResult<u32> {
+        check_name(name)?;
+        let dir = self.dir_inode(dir_num)?;
+        if self.find_entry(&dir, name)?.is_some() {
+            return Err(FsError::AlreadyExists);
         }
-        
-        let mut data = vec![0u8; inode.size as usize];
-        self.read_inode_data(&inode, 0, &mut data)
-            .map_err(|e| FsError::from(e))?;
-            
-        Ok(data)
+        let is_dir = file_type == FileType::Directory;
+        if is_dir && dir.links_count >= EXT2_LINK_MAX {
+            return Err(FsError::TooManyLinks);
+        }
+
+        let inode_num = self.allocate_inode(is_dir)?;
+        let mut inode = Inode::new(mode_type(file_type) | (perm & 0o7777), if is_dir { 2 } else { 1 }, now());
+        let result = self.init_and_link(dir_num, name, inode_num, &mut inode, file_type);
+        if result.is_err() {
+            // Rien ne désigne encore l'inode : il est rendu
+            let _ = self.release_inode(inode_num, &mut inode);
+        }
+        self.write_counters()?;
+        result?;
+        Ok(inode_num)
     }
-    
-    // Les méthodes suivantes sont des implémentations de base qui retournent des erreurs
-    // car l'écriture nécessite une implémentation plus complexe avec mise à jour des bitmaps, etc.
-    
-    pub fn write_file(&mut self, path: &str, content: &[u8]) -> Result<(), FsError> {
-        if path.is_empty() || path == "/" {
-            return Err(FsError::IoError);
+
+    fn init_and_link(&mut self, dir_num: u32, name: &str, inode_num: u32, inode: &mut Inode, file_type: FileType) -> Result<(), Ext2Error> {
+        if file_type == FileType::Directory {
+            let block_num = self.map_block(inode, 0)?;
+            let mut block_buf = vec![0u8; self.block_size];
+            let code = self.entry_code(FileType::Directory);
+            put_dirent(&mut block_buf, 0, inode_num, 12, b".", code);
+            put_dirent(&mut block_buf, 12, dir_num, self.block_size - 12, b"..", code);
+            self.write_block(block_num, &block_buf)?;
+            inode.size = self.block_size as u32;
         }
-        
-        let path = path.trim_start_matches('/');
-        let (dir_path, file_name) = match path.rfind('/') {
-            Some(pos) => path.split_at(pos),
-            None => ("", path),
-        };
-        
-        // Trouver le répertoire parent
-        let dir_inode_num = if dir_path.is_empty() {
-            EXT2_ROOT_INO
-        } else {
-            let mut current_inode = self.get_inode(EXT2_ROOT_INO)?;
-            for component in dir_path.split('/') {
-                if component.is_empty() {
-                    continue;
-                }
-                
-                let entry = self.find_entry_in_dir(&current_inode, component)?;
-                current_inode = self.get_inode(entry.inode)?;
-                
-                if (current_inode.mode & EXT2_S_IFDIR) == 0 {
-                    return Err(FsError::NotDirectory);
-                }
+        self.update_inode(inode_num, inode)?;
+        self.add_dir_entry(dir_num, name, inode_num, file_type)?;
+        if file_type == FileType::Directory {
+            self.add_links(dir_num, 1)?;
+        }
+        Ok(())
+    }
+
+    /// Un `Ext2InodeOps` de plus sur `inode_num`, qui doit avoir un lien
+    fn open_ino(&mut self, inode_num: u32) -> VfsResult<()> {
+        if self.get_inode(inode_num)?.links_count == 0 {
+            return Err(FsError::NotFound);
+        }
+        *self.open.entry(inode_num).or_insert(0) += 1;
+        Ok(())
+    }
+
+    /// Un `Ext2InodeOps` de moins ; au dernier, un orphelin est libéré
+    fn close_ino(&mut self, inode_num: u32) -> Result<(), Ext2Error> {
+        match self.open.get_mut(&inode_num) {
+            Some(count) if *count > 1 => {
+                *count -= 1;
+                return Ok(());
             }
-            
-            // Obtenir le numéro d'inode du répertoire parent
-            let entry = self.find_entry_in_dir(&current_inode, ".")?;
-            entry.inode
-        };
-        
-        let mut dir_inode = self.get_inode(dir_inode_num)?;
-        
-        // Vérifier si le fichier existe déjà
-        let existing_entry = self.find_entry_in_dir(&dir_inode, file_name);
-        let inode_num = match existing_entry {
-            Ok(entry) => {
-                // Le fichier existe, on le tronque
-                let inode = self.get_inode(entry.inode)?;
-                // Copier le tableau de blocs pour éviter l'accès non aligné
-            let inode_blocks = inode.block;
-            
-            // Parcourir les blocs directs
-            for block in inode_blocks.iter() {
-                if *block != 0 { // Retained original logic: check if block is NOT 0 to free it
-                        // Marquer le bloc comme libre dans le bitmap
-                        // (implémentation simplifiée)
-                    }
+            Some(_) => {
+                self.open.remove(&inode_num);
+            }
+            None => return Ok(()),
+        }
+        if self.orphans.remove(&inode_num) {
+            let mut inode = self.get_inode(inode_num)?;
+            self.release_inode(inode_num, &mut inode)?;
+            self.write_counters()?;
+        }
+        Ok(())
+    }
+
+    /// Retire l'entrée (non-répertoire) `name`
+    fn unlink_in(&mut self, dir_num: u32, name: &str) -> VfsResult<()> {
+        let inode_num = self.lookup_in(dir_num, name)?;
+        if self.get_inode(inode_num)?.is_dir() {
+            return Err(FsError::IsDirectory);
+        }
+        self.remove_dir_entry(dir_num, name)?;
+        self.drop_link(inode_num)?;
+        self.write_counters()?;
+        Ok(())
+    }
+
+    /// Supprime le répertoire vide `name`
+    fn rmdir_in(&mut self, dir_num: u32, name: &str) -> VfsResult<()> {
+        if name == "." || name == ".." {
+            return Err(FsError::InvalidArgument);
+        }
+        let inode_num = self.lookup_in(dir_num, name)?;
+        let mut inode = self.get_inode(inode_num)?;
+        if !inode.is_dir() {
+            return Err(FsError::NotDirectory);
+        }
+        if !self.dir_is_empty(&inode)? {
+            return Err(FsError::NotEmpty);
+        }
+        self.remove_dir_entry(dir_num, name)?;
+        // Le ".." du répertoire supprimé ne compte plus pour le parent
+        self.add_links(dir_num, -1)?;
+        self.release_inode(inode_num, &mut inode)?;
+        self.write_counters()?;
+        Ok(())
+    }
+
+    /// Lien physique `name` vers `target` (pas vers un répertoire)
+    fn link_in(&mut self, dir_num: u32, name: &str, target: u32) -> VfsResult<()> {
+        check_name(name)?;
+        let dir = self.dir_inode(dir_num)?;
+        if self.find_entry(&dir, name)?.is_some() {
+            return Err(FsError::AlreadyExists);
+        }
+        let inode = self.get_inode(target)?;
+        if inode.links_count == 0 {
+            return Err(FsError::NotFound);
+        }
+        if inode.is_dir() {
+            return Err(FsError::IsDirectory);
+        }
+        if inode.links_count >= EXT2_LINK_MAX {
+            return Err(FsError::TooManyLinks);
+        }
+        self.add_dir_entry(dir_num, name, target, file_type_of(inode.mode))?;
+        self.add_links(target, 1)?;
+        Ok(())
+    }
+
+    /// Renomme / déplace une entrée sans copier les données. Une cible
+    /// existante est remplacée (même règles que ramfs) et perd un lien ; un
+    /// répertoire qui change de parent fait suivre son "..".
+    fn rename_in(&mut self, old_dir: u32, old_name: &str, new_dir: u32, new_name: &str) -> VfsResult<()> {
+        check_name(new_name)?;
+        let inode_num = self.lookup_in(old_dir, old_name)?;
+        if inode_num == new_dir {
+            return Err(FsError::InvalidArgument);
+        }
+        let target = self.dir_inode(new_dir)?;
+        let moved = self.get_inode(inode_num)?;
+        let moved_dir = moved.is_dir();
+        let existing = self.find_entry(&target, new_name)?.map(|(ino, _)| ino as InodeId);
+        let is_dir = |id: InodeId| self.get_inode(id as u32).map_or(false, |inode| inode.is_dir());
+        if !may_replace(existing, inode_num as InodeId, moved_dir, is_dir)? {
+            return Ok(());
+        }
+
+        let file_type = file_type_of(moved.mode);
+        let reparent = moved_dir && old_dir != new_dir;
+        match existing {
+            Some(_) => self.set_dir_entry_inode(new_dir, new_name, inode_num, file_type)?,
+            None => {
+                if reparent && target.links_count >= EXT2_LINK_MAX {
+                    return Err(FsError::TooManyLinks);
                 }
-                entry.inode
-            },
-            Err(Ext2Error::InodeNotFound) => {
-                // Créer un nouvel inode
-                let inode_num = self.allocate_inode()?;
-                
-                // Créer une entrée de répertoire
-                let file_type = EXT2_S_IFREG >> 12; // Type de fichier régulier
-                self.add_dir_entry(&mut dir_inode, file_name, inode_num, file_type as u8)?;
-                
-                inode_num
-            },
-            Err(e) => return Err(e.into()),
-        };
-        
-        // Mettre à jour l'inode du fichier
+                self.add_dir_entry(new_dir, new_name, inode_num, file_type)?;
+            }
+        }
+        self.remove_dir_entry(old_dir, old_name)?;
+
+        if reparent {
+            self.set_dir_entry_inode(inode_num, "..", new_dir, FileType::Directory)?;
+            self.add_links(old_dir, -1)?;
+            self.add_links(new_dir, 1)?;
+        }
+        // Le remplacé perd ce lien ; au dernier, ses blocs et son inode sont rendus
+        if let Some(replaced) = existing {
+            self.drop_link(replaced as u32)?;
+        }
+        self.write_counters()?;
+        Ok(())
+    }
+
+    fn read_ino(&self, inode_num: u32, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        let inode = self.get_inode(inode_num)?;
+        if inode.is_dir() {
+            return Err(FsError::IsDirectory);
+        }
+        if self.is_fast_symlink(&inode) {
+            let blocks = inode.block;
+            let target: Vec<u8> = blocks.iter().flat_map(|b| b.to_le_bytes()).collect();
+            let size = (inode.size as usize).min(target.len());
+            let start = (offset as usize).min(size);
+            let len = buf.len().min(size - start);
+            buf[..len].copy_from_slice(&target[start..start + len]);
+            return Ok(len);
+        }
+        Ok(self.read_inode_data(&inode, offset as usize, buf)?)
+    }
+
+    fn write_ino(&mut self, inode_num: u32, offset: u64, buf: &[u8]) -> VfsResult<usize> {
         let mut inode = self.get_inode(inode_num)?;
-        inode.size = content.len() as u32;
-        inode.mtime = 0; // Devrait être le timestamp actuel
-        inode.ctime = 0;
-        inode.atime = 0;
-        inode.mode = 0o644 | EXT2_S_IFREG as u16; // Permissions 644 pour les fichiers réguliers
-        
-        // Écrire les données du fichier
-        self.write_inode_data(&mut inode, 0, content)?;
-        
-        // Mettre à jour l'inode sur le disque
+        if inode.is_dir() {
+            return Err(FsError::IsDirectory);
+        }
+        let result = self.write_inode_data(&mut inode, offset, buf);
+        // Les blocs déjà alloués restent attachés à l'inode, même en cas d'échec
+        let now = now();
+        inode.mtime = now;
+        inode.ctime = now;
         self.update_inode(inode_num, &inode)?;
-        
-        // Mettre à jour l'inode du répertoire parent
-        self.update_inode(dir_inode_num, &dir_inode)?;
-        
+        self.write_counters()?;
+        Ok(result?)
+    }
+
+    fn truncate_ino(&mut self, inode_num: u32, size: u64) -> VfsResult<()> {
+        let mut inode = self.get_inode(inode_num)?;
+        if inode.is_dir() {
+            return Err(FsError::IsDirectory);
+        }
+        let size = u32::try_from(size).map_err(|_| FsError::NoSpace)?;
+        if size < inode.size && !self.is_fast_symlink(&inode) {
+            let block_size = self.block_size as u32;
+            self.truncate_blocks(&mut inode, size.div_ceil(block_size))?;
+            // Fin du dernier bloc remise à zéro : une extension la relira nulle
+            let tail = (size % block_size) as usize;
+            let block_num = self.get_block_number(&inode, size / block_size)?;
+            if tail != 0 && block_num != 0 {
+                let mut block_buf = vec![0u8; self.block_size];
+                self.read_block(block_num, &mut block_buf)?;
+                block_buf[tail..].fill(0);
+                self.write_block(block_num, &block_buf)?;
+            }
+        }
+        inode.size = size;
+        let now = now();
+        inode.mtime = now;
+        inode.ctime = now;
+        self.update_inode(inode_num, &inode)?;
+        self.write_counters()?;
         Ok(())
     }
-    
-    pub fn create_file(&mut self, path: &str, content: &[u8]) -> Result<(), FsError> {
-        if self.exists(path) {
-            return Err(FsError::AlreadyExists);
+
+    fn stat_ino(&self, inode_num: u32) -> VfsResult<FileStat> {
+        let inode = self.get_inode(inode_num)?;
+        let mut stat = FileStat::new(inode_num as InodeId, file_type_of(inode.mode));
+        stat.mode = FileMode::new(inode.mode & 0o7777);
+        stat.uid = inode.owner_uid();
+        stat.gid = inode.owner_gid();
+        stat.nlinks = inode.links_count as u32;
+        stat.size = inode.size as u64;
+        stat.atime = inode.atime as u64;
+        stat.mtime = inode.mtime as u64;
+        stat.ctime = inode.ctime as u64;
+        stat.blksize = self.block_size as u32;
+        stat.blocks = inode.blocks as u64;
+        Ok(stat)
+    }
+
+    /// Change les bits de permission et/ou le propriétaire
+    fn setattr_ino(&mut self, inode_num: u32, mode: Option<FileMode>, uid: Option<u32>, gid: Option<u32>) -> VfsResult<()> {
+        let mut inode = self.get_inode(inode_num)?;
+        if let Some(mode) = mode {
+            inode.mode = (inode.mode & EXT2_S_IFMT) | (mode.0 & 0o7777);
         }
-        
-        self.write_file(path, content)
+        if uid.is_some() || gid.is_some() {
+            let (old_uid, old_gid) = (inode.owner_uid(), inode.owner_gid());
+            inode.set_owner(uid.unwrap_or(old_uid), gid.unwrap_or(old_gid));
+        }
+        inode.ctime = now();
+        self.update_inode(inode_num, &inode)?;
+        Ok(())
     }
-    
-    pub fn create_dir(&mut self, path: &str) -> Result<(), FsError> {
-        if path.is_empty() || path == "/" {
-            return Err(FsError::AlreadyExists);
+
+    /// Entrées du répertoire, "." compris, sans ".."
+    fn list_dir(&self, dir_num: u32) -> VfsResult<Vec<VfsDirEntry>> {
+        let dir = self.dir_inode(dir_num)?;
+        let mut raw = Vec::new();
+        self.scan_dir(&dir, |_, block, pos, _| {
+            let (ino, _, name, code) = dirent_at(block, pos)?;
+            if ino != 0 && name != b".." {
+                raw.push((ino, String::from_utf8_lossy(name).into_owned(), code));
+            }
+            None::<()>
+        })?;
+
+        let mut entries = Vec::with_capacity(raw.len());
+        for (ino, name, code) in raw {
+            let file_type = match dirent_file_type(code) {
+                Some(file_type) => file_type,
+                None => file_type_of(self.get_inode(ino)?.mode),
+            };
+            entries.push(VfsDirEntry::new(ino as InodeId, name, file_type));
         }
-        
-        if self.exists(path) {
-            return Err(FsError::AlreadyExists);
+        Ok(entries)
+    }
+}
+
+// Accès par chemin depuis la racine du volume (utilisé par ext3)
+impl<D: Disk> Ext2<D> {
+    /// Numéro d'inode de `path`
+    fn resolve(&self, path: &str) -> VfsResult<u32> {
+        path.split('/')
+            .filter(|component| !component.is_empty())
+            .try_fold(EXT2_ROOT_INO, |dir, component| self.lookup_in(dir, component))
+    }
+
+    // Résout le répertoire parent d'un chemin : (numéro d'inode du parent, dernier composant)
+    fn lookup_parent<'a>(&self, path: &'a str) -> VfsResult<(u32, &'a str)> {
+        let path = path.trim_matches('/');
+        if path.is_empty() {
+            return Err(FsError::InvalidArgument);
         }
-        
-        let path = path.trim_start_matches('/');
-        let (dir_path, dir_name) = match path.rfind('/') {
-            Some(pos) => path.split_at(pos),
+        let (dir_path, name) = match path.rfind('/') {
+            Some(pos) => (&path[..pos], &path[pos + 1..]),
             None => ("", path),
         };
-        
-        // Trouver le répertoire parent
-        let parent_inode = if dir_path.is_empty() {
-            self.get_inode(EXT2_ROOT_INO)?
-        } else {
-            let mut current_inode = self.get_inode(EXT2_ROOT_INO)?;
-            for component in dir_path.split('/') {
-                if component.is_empty() {
-                    continue;
-                }
-                
-                let entry = self.find_entry_in_dir(&current_inode, component)?;
-                current_inode = self.get_inode(entry.inode)?;
-                
-                if (current_inode.mode & EXT2_S_IFDIR) == 0 {
-                    return Err(FsError::NotDirectory);
-                }
+        let dir = self.resolve(dir_path)?;
+        self.dir_inode(dir)?;
+        Ok((dir, name))
+    }
+
+    pub fn read_dir(&self, path: &str) -> Result<Vec<String>, FsError> {
+        let entries = self.list_dir(self.resolve(path)?)?;
+        Ok(entries.into_iter().filter(|entry| entry.name != ".").map(|entry| entry.name).collect())
+    }
+
+    pub fn read_file(&self, path: &str) -> Result<Vec<u8>, FsError> {
+        let inode_num = self.resolve(path)?;
+        let inode = self.get_inode(inode_num)?;
+        if inode.mode & EXT2_S_IFMT != EXT2_S_IFREG {
+            return Err(FsError::IoError);
+        }
+        let mut data = vec![0u8; inode.size as usize];
+        self.read_ino(inode_num, 0, &mut data)?;
+        Ok(data)
+    }
+
+    /// Crée ou réécrit le fichier `path` ; un fichier réécrit garde ses permissions
+    pub fn write_file(&mut self, path: &str, content: &[u8]) -> Result<(), FsError> {
+        let (dir, name) = self.lookup_parent(path)?;
+        let inode_num = match self.lookup_in(dir, name) {
+            Ok(inode_num) => {
+                self.truncate_ino(inode_num, 0)?;
+                inode_num
             }
-            current_inode
-        };
-        
-        // Allouer un nouvel inode pour le répertoire
-        let new_inode_num = self.allocate_inode()?;
-        let mut new_inode = Inode {
-            mode: 0o755 | EXT2_S_IFDIR as u16, // Permissions 755 pour les répertoires
-            uid: 0, // root
-            size: 0,
-            atime: 0,
-            ctime: 0,
-            mtime: 0,
-            dtime: 0,
-            gid: 0, // root
-            links_count: 2, // . et ..
-            blocks: 0,
-            flags: 0,
-            osd1: 0,
-            block: [0; 15],
-            generation: 0,
-            file_acl: 0,
-            dir_acl: 0,
-            faddr: 0,
-            osd2: [0; 12],
-        };
-        
-        // Créer les entrées . et ..
-        let dot_entry = DirEntry {
-            inode: new_inode_num,
-            rec_len: 12,
-            name_len: 1,
-            file_type: (EXT2_S_IFDIR >> 12) as u8,
-            name: {
-                let mut name = [0; 255];
-                name[0] = b'.';
-                name
-            },
-        };
-        
-        let dotdot_entry = DirEntry {
-            inode: if dir_path.is_empty() { new_inode_num } else { 
-                // Trouver le numéro d'inode du parent
-                let entry = self.find_entry_in_dir(&parent_inode, ".")?;
-                entry.inode
-            },
-            rec_len: (self.block_size - 12) as u16, // Le reste du bloc
-            name_len: 2,
-            file_type: (EXT2_S_IFDIR >> 12) as u8,
-            name: {
-                let mut name = [0; 255];
-                name[0] = b'.';
-                name[1] = b'.';
-                name
-            },
-        };
-        
-        // Allouer un bloc pour le répertoire
-        let block_num = self.allocate_block()?;
-        new_inode.block[0] = block_num;
-        
-        // Écrire les entrées . et .. dans le bloc
-        let mut block_buf = vec![0u8; self.block_size];
-        
-        // Copier l'entrée .
-        let dot_slice = unsafe {
-            core::slice::from_raw_parts(
-                &dot_entry as *const _ as *const u8,
-                12 // Taille fixe pour .
-            )
-        };
-        block_buf[..12].copy_from_slice(dot_slice);
-        
-        // Copier l'entrée ..
-        let dotdot_slice = unsafe {
-            core::slice::from_raw_parts(
-                &dotdot_entry as *const _ as *const u8,
-                12 // Taille fixe pour ..
-            )
+            Err(FsError::NotFound) => self.create_in(dir, name, 0o644, FileType::Regular)?,
+            Err(e) => return Err(e),
         };
-        block_buf[12..24].copy_from_slice(dotdot_slice);
-        
-        // Écrire le bloc
-        self.write_block(block_num, &block_buf)?;
-        
-        // Mettre à jour la taille du répertoire
-        new_inode.size = self.block_size as u32;
-        
-        // Écrire l'inode
-        self.update_inode(new_inode_num, &new_inode)?;
-        
-        // Ajouter l'entrée dans le répertoire parent
-        let file_type = (EXT2_S_IFDIR >> 12) as u8;
-        self.add_dir_entry(&mut parent_inode.clone(), dir_name, new_inode_num, file_type)?;
-        
-        // Mettre à jour le nombre de liens du répertoire parent
-        if !dir_path.is_empty() {
-            let mut parent_inode = self.get_inode(parent_inode.block[0])?; // Simplification
-            parent_inode.links_count += 1;
-            self.update_inode(parent_inode.block[0], &parent_inode)?; // Simplification
-        }
-        
+        if self.write_ino(inode_num, 0, content)? < content.len() {
+            return Err(FsError::NoSpace);
+        }
+        Ok(())
+    }
+
+    pub fn create_file(&mut self, path: &str, content: &[u8]) -> Result<(), FsError> {
+        if self.resolve(path).is_ok() {
+            return Err(FsError::AlreadyExists);
+        }
+        self.write_file(path, content)
+    }
+
+    pub fn create_dir(&mut self, path: &str) -> Result<(), FsError> {
+        let (dir, name) = self.lookup_parent(path)?;
+        self.create_in(dir, name, 0o755, FileType::Directory)?;
         Ok(())
     }
-    
-    pub fn remove_file(&mut self, _path: &str) -> Result<(), FsError> {
-        // Implémentation simplifiée - retourne une erreur
-        Err(FsError::IoError)
+
+    pub fn remove_file(&mut self, path: &str) -> Result<(), FsError> {
+        let (dir, name) = self.lookup_parent(path)?;
+        self.unlink_in(dir, name)
     }
-    
+
     pub fn delete_file(&mut self, path: &str) -> Result<(), FsError> {
         self.remove_file(path)
     }
-    
-    fn remove_dir(&mut self, _path: &str) -> Result<(), FsError> {
-        // Implémentation simplifiée - retourne une erreur
-        Err(FsError::IoError)
-    }
-    
-    fn exists(&self, path: &str) -> bool {
-        if path.is_empty() || path == "/" {
-            return true;
-        }
-        
-        match self.get_inode(EXT2_ROOT_INO) {
-            Ok(dir_inode) => {
-                match self.find_entry_in_dir(&dir_inode, path.trim_start_matches('/')) {
-                    Ok(_) => true,
-                    Err(_) => false,
-                }
-            },
-            Err(_) => false,
-        }
-    }
-    
-    fn is_file(&self, path: &str) -> bool {
-        if path.is_empty() || path == "/" {
-            return false;
-        }
-        
-        match self.get_inode(EXT2_ROOT_INO) {
-            Ok(dir_inode) => {
-                match self.find_entry_in_dir(&dir_inode, path.trim_start_matches('/')) {
-                    Ok(entry) => {
-                        match self.get_inode(entry.inode) {
-                            Ok(inode) => (inode.mode & EXT2_S_IFREG) != 0,
-                            Err(_) => false,
-                        }
-                    },
-                    Err(_) => false,
-                }
-            },
-            Err(_) => false,
-        }
-    }
-    
-    fn is_dir(&self, path: &str) -> bool {
-        if path.is_empty() || path == "/" {
-            return true;
-        }
-        
-        match self.get_inode(EXT2_ROOT_INO) {
-            Ok(dir_inode) => {
-                match self.find_entry_in_dir(&dir_inode, path.trim_start_matches('/')) {
-                    Ok(entry) => {
-                        match self.get_inode(entry.inode) {
-                            Ok(inode) => (inode.mode & EXT2_S_IFDIR) != 0,
-                            Err(_) => false,
-                        }
-                    },
-                    Err(_) => false,
-                }
-            },
-            Err(_) => false,
+
+    pub fn remove_dir(&mut self, path: &str) -> Result<(), FsError> {
+        let (dir, name) = self.lookup_parent(path)?;
+        self.rmdir_in(dir, name)
+    }
+}
+
+// Attributs étendus : bloc désigné par `file_acl` (format de Linux).
+// En-tête de 32 octets, entrées triées à la suite, valeurs rangées depuis
+// la fin du bloc ; les espaces de noms sont codés par un index.
+const EXT2_XATTR_MAGIC: u32 = 0xEA020000;
+const EXT2_XATTR_HEADER_SIZE: usize = 32;
+const EXT2_XATTR_ENTRY_SIZE: usize = 16;
+
+/// Index Linux des espaces de noms (ACL POSIX exclues)
+const EXT2_XATTR_PREFIXES: [(u8, &str); 4] = [(1, "user."), (4, "trusted."), (6, "security."), (7, "system.")];
+
+/// Attribut étendu décodé : (index d'espace de noms, suffixe, valeur)
+type Ext2Xattr = (u8, String, Vec<u8>);
+
+fn xattr_pad(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+/// Nom complet -> (index, suffixe)
+fn xattr_split(name: &str) -> Result<(u8, &str), FsError> {
+    crate::fs::check_xattr_name(name)?;
+    EXT2_XATTR_PREFIXES
+        .iter()
+        .find(|(_, prefix)| name.starts_with(prefix))
+        .map(|&(index, prefix)| (index, &name[prefix.len()..]))
+        .ok_or(FsError::NotSupported)
+}
+
+fn xattr_full_name(index: u8, suffix: &str) -> Option<String> {
+    EXT2_XATTR_PREFIXES
+        .iter()
+        .find(|(i, _)| *i == index)
+        .map(|(_, prefix)| alloc::format!("{}{}", prefix, suffix))
+}
+
+/// Empreinte d'une entrée : nom puis mots de la valeur (ext2_xattr_hash_entry)
+fn xattr_entry_hash(name: &[u8], value: &[u8]) -> u32 {
+    let mut hash = 0u32;
+    for &c in name {
+        hash = (hash << 5) ^ (hash >> 27) ^ c as u32;
+    }
+    for word in value.chunks(4) {
+        let mut bytes = [0u8; 4];
+        bytes[..word.len()].copy_from_slice(word);
+        hash = (hash << 16) ^ (hash >> 16) ^ u32::from_le_bytes(bytes);
+    }
+    hash
+}
+
+/// Décode un bloc d'attributs ; retourne aussi son compteur de références
+fn decode_xattr_block(block: &[u8]) -> Result<(u32, Vec<Ext2Xattr>), Ext2Error> {
+    let word = |offset: usize| u32::from_le_bytes(block[offset..offset + 4].try_into().unwrap());
+    if block.len() < EXT2_XATTR_HEADER_SIZE || word(0) != EXT2_XATTR_MAGIC || word(8) != 1 {
+        return Err(Ext2Error::InvalidSuperblock);
+    }
+    let refcount = word(4);
+    let mut attrs = Vec::new();
+    let mut offset = EXT2_XATTR_HEADER_SIZE;
+    while offset + 4 <= block.len() && word(offset) != 0 {
+        if offset + EXT2_XATTR_ENTRY_SIZE > block.len() {
+            return Err(Ext2Error::InvalidSuperblock);
+        }
+        let name_len = block[offset] as usize;
+        let index = block[offset + 1];
+        let value_offs = u16::from_le_bytes([block[offset + 2], block[offset + 3]]) as usize;
+        let value_size = word(offset + 8) as usize;
+        let name_start = offset + EXT2_XATTR_ENTRY_SIZE;
+        if name_start + name_len > block.len() || value_offs + value_size > block.len() {
+            return Err(Ext2Error::InvalidSuperblock);
+        }
+        let name = core::str::from_utf8(&block[name_start..name_start + name_len])
+            .map_err(|_| Ext2Error::InvalidSuperblock)?;
+        attrs.push((index, String::from(name), block[value_offs..value_offs + value_size].to_vec()));
+        offset = xattr_pad(name_start + name_len);
+    }
+    Ok((refcount, attrs))
+}
+
+/// Encode `attrs` dans un bloc de `block_size` octets (NoSpaceLeft s'ils
+/// n'y tiennent pas)
+fn encode_xattr_block(attrs: &mut Vec<Ext2Xattr>, refcount: u32, block_size: usize) -> Result<Vec<u8>, Ext2Error> {
+    // Ordre de Linux : index, longueur du nom, nom
+    attrs.sort_by(|a, b| (a.0, a.1.len(), &a.1).cmp(&(b.0, b.1.len(), &b.1)));
+
+    let mut block = vec![0u8; block_size];
+    let mut entry = EXT2_XATTR_HEADER_SIZE;
+    let mut value_end = block_size;
+    let mut block_hash = 0u32;
+    for (index, name, value) in attrs.iter() {
+        let entry_len = xattr_pad(EXT2_XATTR_ENTRY_SIZE + name.len());
+        let value_start = value_end.checked_sub(xattr_pad(value.len())).ok_or(Ext2Error::NoSpaceLeft)?;
+        // 4 octets nuls terminent la liste des entrées
+        if entry + entry_len + 4 > value_start {
+            return Err(Ext2Error::NoSpaceLeft);
+        }
+        let hash = xattr_entry_hash(name.as_bytes(), value);
+        block[entry] = name.len() as u8;
+        block[entry + 1] = *index;
+        block[entry + 2..entry + 4].copy_from_slice(&(value_start as u16).to_le_bytes());
+        block[entry + 8..entry + 12].copy_from_slice(&(value.len() as u32).to_le_bytes());
+        block[entry + 12..entry + 16].copy_from_slice(&hash.to_le_bytes());
+        block[entry + EXT2_XATTR_ENTRY_SIZE..entry + EXT2_XATTR_ENTRY_SIZE + name.len()].copy_from_slice(name.as_bytes());
+        block[value_start..value_start + value.len()].copy_from_slice(value);
+        block_hash = (block_hash << 16) ^ (block_hash >> 16) ^ hash;
+        entry += entry_len;
+        value_end = value_start;
+    }
+    block[0..4].copy_from_slice(&EXT2_XATTR_MAGIC.to_le_bytes());
+    block[4..8].copy_from_slice(&refcount.to_le_bytes());
+    block[8..12].copy_from_slice(&1u32.to_le_bytes());
+    block[12..16].copy_from_slice(&block_hash.to_le_bytes());
+    Ok(block)
+}
+
+impl<D: Disk> Ext2<D> {
+    /// Attributs étendus de l'inode (aucun si `file_acl` est nul)
+    fn read_xattrs(&self, inode: &Inode) -> Result<(u32, Vec<Ext2Xattr>), Ext2Error> {
+        let block_num = inode.file_acl;
+        if block_num == 0 {
+            return Ok((1, Vec::new()));
+        }
+        let mut block = vec![0u8; self.block_size];
+        self.read_block(block_num, &mut block)?;
+        decode_xattr_block(&block)
+    }
+
+    /// Réécrit le bloc d'attributs de l'inode ; un bloc partagé avec
+    /// d'autres inodes est d'abord copié, un bloc vidé est rendu
+    fn write_xattrs(&mut self, inode_num: u32, inode: &mut Inode, refcount: u32, mut attrs: Vec<Ext2Xattr>) -> Result<(), Ext2Error> {
+        let mut block_num = inode.file_acl;
+        let block = encode_xattr_block(&mut attrs, 1, self.block_size)?;
+        if block_num != 0 && (refcount > 1 || attrs.is_empty()) {
+            if refcount > 1 {
+                let mut old = vec![0u8; self.block_size];
+                self.read_block(block_num, &mut old)?;
+                old[4..8].copy_from_slice(&(refcount - 1).to_le_bytes());
+                self.write_block(block_num, &old)?;
+            } else {
+                self.free_block(block_num)?;
+            }
+            block_num = 0;
+            inode.file_acl = 0;
+            inode.blocks = inode.blocks.saturating_sub((self.block_size / 512) as u32);
+        }
+        if !attrs.is_empty() {
+            if block_num == 0 {
+                block_num = self.allocate_block()?;
+                inode.file_acl = block_num;
+                inode.blocks += (self.block_size / 512) as u32;
+            }
+            self.write_block(block_num, &block)?;
         }
+        inode.ctime = now();
+        self.update_inode(inode_num, inode)
+    }
+
+    /// Valeur de l'attribut étendu `name` de l'inode
+    fn getxattr_ino(&self, inode_num: u32, name: &str) -> VfsResult<Vec<u8>> {
+        let (index, suffix) = xattr_split(name)?;
+        let (_, attrs) = self.read_xattrs(&self.get_inode(inode_num)?)?;
+        attrs
+            .into_iter()
+            .find(|(i, n, _)| *i == index && n == suffix)
+            .map(|(_, _, value)| value)
+            .ok_or(FsError::NoAttribute)
+    }
+
+    /// Noms complets des attributs étendus de l'inode
+    fn listxattr_ino(&self, inode_num: u32) -> VfsResult<Vec<String>> {
+        let (_, attrs) = self.read_xattrs(&self.get_inode(inode_num)?)?;
+        Ok(attrs.iter().filter_map(|(index, name, _)| xattr_full_name(*index, name)).collect())
+    }
+
+    /// Définit (`Some`) ou supprime (`None`) l'attribut `name` de l'inode ;
+    /// `flags` : XATTR_CREATE, XATTR_REPLACE
+    fn setxattr_ino(&mut self, inode_num: u32, name: &str, value: Option<&[u8]>, flags: u32) -> VfsResult<()> {
+        use crate::fs::{XATTR_CREATE, XATTR_REPLACE};
+        let (index, suffix) = xattr_split(name)?;
+        let mut inode = self.get_inode(inode_num)?;
+        let (refcount, mut attrs) = self.read_xattrs(&inode)?;
+        let existing = attrs.iter().position(|(i, n, _)| *i == index && n == suffix);
+        match (value, existing) {
+            (Some(_), Some(_)) if flags & XATTR_CREATE != 0 => return Err(FsError::AlreadyExists),
+            (Some(_), None) if flags & XATTR_REPLACE != 0 => return Err(FsError::NoAttribute),
+            (None, None) => return Err(FsError::NoAttribute),
+            _ => {}
+        }
+        if let Some(pos) = existing {
+            attrs.remove(pos);
+        }
+        if let Some(value) = value {
+            attrs.push((index, String::from(suffix), value.to_vec()));
+        }
+        let result = self.write_xattrs(inode_num, &mut inode, refcount, attrs);
+        self.write_counters()?;
+        Ok(result?)
+    }
+}
+
+/// Volume partagé par le système de fichiers monté et ses inodes
+type SharedExt2<D> = Arc<Mutex<Ext2<D>>>;
+
+/// Superbloc VFS : compteurs lus sur le volume monté
+pub struct Ext2Superblock<D: Disk> {
+    fs_id: FsId,
+    fs: SharedExt2<D>,
+}
+
+impl<D: Disk + Send + 'static> Superblock for Ext2Superblock<D> {
+    fn fs_name(&self) -> &str {
+        "ext2"
+    }
+
+    fn fs_id(&self) -> FsId {
+        self.fs_id
+    }
+
+    fn block_size(&self) -> u32 {
+        self.fs.lock().block_size as u32
+    }
+
+    fn total_blocks(&self) -> u64 {
+        let sb = self.fs.lock().superblock;
+        (sb.blocks_count - sb.first_data_block) as u64
+    }
+
+    fn free_blocks(&self) -> u64 {
+        self.fs.lock().superblock.free_blocks_count as u64
+    }
+
+    fn total_inodes(&self) -> u64 {
+        self.fs.lock().superblock.inodes_count as u64
+    }
+
+    fn free_inodes(&self) -> u64 {
+        self.fs.lock().superblock.free_inodes_count as u64
+    }
+
+    fn is_readonly(&self) -> bool {
+        false
+    }
+
+    fn root_inode(&self) -> InodeId {
+        EXT2_ROOT_INO as InodeId
+    }
+}
+
+/// Volume ext2 monté dans le VFS
+pub struct Ext2FileSystem<D: Disk> {
+    fs: SharedExt2<D>,
+    sb: Arc<Ext2Superblock<D>>,
+}
+
+impl<D: Disk + Send + 'static> Ext2FileSystem<D> {
+    pub fn new(fs: Ext2<D>) -> Self {
+        let fs = Arc::new(Mutex::new(fs));
+        let sb = Arc::new(Ext2Superblock { fs_id: alloc_fs_id(), fs: fs.clone() });
+        Self { fs, sb }
+    }
+}
+
+impl<D: Disk + Send + 'static> FileSystemOps for Ext2FileSystem<D> {
+    fn superblock(&self) -> Arc<dyn Superblock> {
+        self.sb.clone()
+    }
+
+    fn get_inode(&self, inode_id: InodeId) -> VfsResult<Arc<Mutex<dyn InodeOps>>> {
+        let ino = ext2_ino(inode_id)?;
+        // Un inode sans lien (libéré ou orphelin) n'est plus accessible
+        self.fs.lock().open_ino(ino)?;
+        Ok(Arc::new(Mutex::new(Ext2InodeOps { fs: self.fs.clone(), ino })))
+    }
+
+    fn sync(&self) -> VfsResult<()> {
+        Ok(self.fs.lock().write_counters()?)
+    }
+
+    fn unmount(&self) -> VfsResult<()> {
+        self.sync()
+    }
+
+    /// Compteurs lus sous un seul verrou (tenus à jour par les allocations)
+    fn statfs(&self) -> VfsResult<StatFs> {
+        let fs = self.fs.lock();
+        let sb = fs.superblock;
+        Ok(StatFs {
+            fs_name: String::from("ext2"),
+            block_size: fs.block_size as u32,
+            total_blocks: (sb.blocks_count - sb.first_data_block) as u64,
+            free_blocks: sb.free_blocks_count as u64,
+            total_inodes: sb.inodes_count as u64,
+            free_inodes: sb.free_inodes_count as u64,
+            readonly: false,
+        })
+    }
+}
+
+/// Numéro d'inode ext2 d'un identifiant VFS
+fn ext2_ino(inode_id: InodeId) -> VfsResult<u32> {
+    u32::try_from(inode_id).map_err(|_| FsError::NotFound)
+}
+
+/// Inode ext2 : les opérations sont faites sur le volume, verrou tenu ;
+/// chaque instance compte comme une ouverture de l'inode
+struct Ext2InodeOps<D: Disk> {
+    fs: SharedExt2<D>,
+    ino: u32,
+}
+
+/// Un inode supprimé pendant qu'il était ouvert garde ses blocs jusqu'ici
+impl<D: Disk> Drop for Ext2InodeOps<D> {
+    fn drop(&mut self) {
+        let _ = self.fs.lock().close_ino(self.ino);
+    }
+}
+
+impl<D: Disk + Send + 'static> InodeOps for Ext2InodeOps<D> {
+    fn read(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        self.fs.lock().read_ino(self.ino, offset, buf)
+    }
+
+    fn write(&mut self, offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        self.fs.lock().write_ino(self.ino, offset, buf)
+    }
+
+    fn stat(&self) -> VfsResult<FileStat> {
+        self.fs.lock().stat_ino(self.ino)
+    }
+
+    fn lookup(&self, name: &str) -> VfsResult<InodeId> {
+        self.fs.lock().lookup_in(self.ino, name).map(InodeId::from)
+    }
+
+    fn create(&mut self, name: &str, mode: FileMode, file_type: FileType) -> VfsResult<InodeId> {
+        self.fs.lock().create_in(self.ino, name, mode.0, file_type).map(InodeId::from)
+    }
+
+    fn unlink(&mut self, name: &str) -> VfsResult<()> {
+        self.fs.lock().unlink_in(self.ino, name)
+    }
+
+    fn mkdir(&mut self, name: &str, mode: FileMode) -> VfsResult<InodeId> {
+        self.fs.lock().create_in(self.ino, name, mode.0, FileType::Directory).map(InodeId::from)
+    }
+
+    fn rmdir(&mut self, name: &str) -> VfsResult<()> {
+        self.fs.lock().rmdir_in(self.ino, name)
+    }
+
+    fn readdir(&self) -> VfsResult<Vec<VfsDirEntry>> {
+        self.fs.lock().list_dir(self.ino)
+    }
+
+    fn truncate(&mut self, size: u64) -> VfsResult<()> {
+        self.fs.lock().truncate_ino(self.ino, size)
+    }
+
+    fn rename(&mut self, old_name: &str, new_dir: InodeId, new_name: &str) -> VfsResult<()> {
+        self.fs.lock().rename_in(self.ino, old_name, ext2_ino(new_dir)?, new_name)
+    }
+
+    fn link(&mut self, name: &str, target: InodeId) -> VfsResult<()> {
+        self.fs.lock().link_in(self.ino, name, ext2_ino(target)?)
+    }
+
+    fn getxattr(&self, name: &str) -> VfsResult<Vec<u8>> {
+        self.fs.lock().getxattr_ino(self.ino, name)
+    }
+
+    fn setxattr(&mut self, name: &str, value: &[u8], flags: u32) -> VfsResult<()> {
+        self.fs.lock().setxattr_ino(self.ino, name, Some(value), flags)
+    }
+
+    fn listxattr(&self) -> VfsResult<Vec<String>> {
+        self.fs.lock().listxattr_ino(self.ino)
+    }
+
+    fn removexattr(&mut self, name: &str) -> VfsResult<()> {
+        self.fs.lock().setxattr_ino(self.ino, name, None, 0)
+    }
+
+    fn setattr(&mut self, mode: Option<FileMode>, uid: Option<u32>, gid: Option<u32>) -> VfsResult<()> {
+        self.fs.lock().setattr_ino(self.ino, mode, uid, gid)
     }
 }
 
@@ -1049,3 +1763,292 @@ impl<D: Disk> Ext2<D> {
 pub fn mount_ext2<D: Disk>(disk: D) -> Result<Ext2<D>, FsError> {
     Ext2::new(disk).map_err(|e| FsError::from(e))
 }
+
+/// Pilote enregistré auprès de `fs_manager` (périphériques à secteurs de
+/// 512 octets)
+fn mount_device(dev: BlockHandle) -> VfsResult<Arc<dyn FileSystemOps>> {
+    if dev.block_size() != 512 {
+        return Err(FsError::NotSupported);
+    }
+    Ok(Arc::new(Ext2FileSystem::new(Ext2::new(dev)?)))
+}
+
+fn register() -> VfsResult<()> {
+    crate::fs_manager::register_fs_type("ext2", mount_device)
+}
+
+crate::initcall!(core, "ext2", register);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::drivers::disk::DiskError;
+    use crate::fs::{mount_fs, unmount_fs, MountFlags};
+
+    /// Disque en mémoire (secteurs de 512 octets), partagé pour remonter le volume
+    #[derive(Clone)]
+    struct MemDisk(Arc<Mutex<Vec<u8>>>);
+
+    impl Disk for MemDisk {
+        fn read(&self, sector: u64, buffer: &mut [u8]) -> Result<(), DiskError> {
+            let start = sector as usize * 512;
+            let data = self.0.lock();
+            buffer.copy_from_slice(data.get(start..start + buffer.len()).ok_or(DiskError::InvalidSector)?);
+            Ok(())
+        }
+
+        fn write(&mut self, sector: u64, buffer: &[u8]) -> Result<(), DiskError> {
+            let start = sector as usize * 512;
+            let mut data = self.0.lock();
+            data.get_mut(start..start + buffer.len()).ok_or(DiskError::InvalidSector)?.copy_from_slice(buffer);
+            Ok(())
+        }
+    }
+
+    fn put16(bytes: &mut [u8], offset: usize, value: u16) {
+        bytes[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn put32(bytes: &mut [u8], offset: usize, value: u32) {
+        bytes[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    /// Volume vierge : 256 blocs de 1 Kio, 32 inodes, un groupe. Bloc 1 :
+    /// superbloc, 2 : descripteurs, 3 et 4 : bitmaps, 5-8 : table
+    /// d'inodes, 9 : racine
+    fn blank_volume() -> MemDisk {
+        let mut data = vec![0u8; 256 * 1024];
+        let sb = &mut data[1024..2048];
+        put32(sb, 0, 32);
+        put32(sb, 4, 256);
+        put32(sb, 12, 246);
+        put32(sb, 16, 22);
+        put32(sb, 20, 1);
+        put32(sb, 32, 8192);
+        put32(sb, 36, 8192);
+        put32(sb, 40, 32);
+        put16(sb, 56, EXT2_SIGNATURE);
+        put16(sb, 58, 1);
+        put32(sb, 76, 1);
+        put32(sb, 84, 11);
+        put16(sb, 88, 128);
+        put32(sb, 96, EXT2_FEATURE_INCOMPAT_FILETYPE);
+
+        let desc = &mut data[2048..2048 + BGD_SIZE];
+        put32(desc, 0, 3);
+        put32(desc, 4, 4);
+        put32(desc, 8, 5);
+        put16(desc, 12, 246);
+        put16(desc, 14, 22);
+        put16(desc, 16, 1);
+
+        // Blocs 1-9 et inodes 1-10 occupés ; au-delà du volume, bits à 1
+        let block_bitmap = &mut data[3072..4096];
+        block_bitmap[0] = 0xFF;
+        block_bitmap[1] = 0x01;
+        block_bitmap[31] = 0x80;
+        block_bitmap[32..].fill(0xFF);
+        let inode_bitmap = &mut data[4096..5120];
+        inode_bitmap[0] = 0xFF;
+        inode_bitmap[1] = 0x03;
+        inode_bitmap[4..].fill(0xFF);
+
+        let root = &mut data[5120 + 128..5120 + 256];
+        put16(root, 0, EXT2_S_IFDIR | 0o755);
+        put32(root, 4, 1024);
+        put16(root, 26, 2);
+        put32(root, 28, 2);
+        put32(root, 40, 9);
+        let root_dir = &mut data[9216..10240];
+        put_dirent(root_dir, 0, EXT2_ROOT_INO, 12, b".", 2);
+        put_dirent(root_dir, 12, EXT2_ROOT_INO, 1012, b"..", 2);
+        MemDisk(Arc::new(Mutex::new(data)))
+    }
+
+    #[test_case]
+    fn test_ext2_through_vfs() {
+        use crate::fs::{vfs_link, vfs_mkdir, vfs_read_file, vfs_remove_file, vfs_rename, vfs_stat, vfs_statfs, vfs_write_file};
+        crate::fs::test_vfs();
+        let disk = blank_volume();
+        let _ = vfs_mkdir("/ext2-test");
+        mount_fs("/ext2-test", Arc::new(Ext2FileSystem::new(Ext2::new(disk.clone()).unwrap())), MountFlags::new(0)).unwrap();
+        let blank = vfs_statfs("/ext2-test").unwrap();
+        assert_eq!((blank.fs_name.as_str(), blank.total_blocks, blank.free_inodes), ("ext2", 255, 22));
+
+        // 20 Kio : au-delà des 12 blocs directs
+        let big: Vec<u8> = (0..20 * 1024).map(|i| (i % 251) as u8).collect();
+        vfs_write_file("/ext2-test/big", &big).unwrap();
+        assert_eq!(vfs_read_file("/ext2-test/big").unwrap(), big);
+        vfs_write_file("/ext2-test/a.txt", b"bonjour").unwrap();
+        vfs_write_file("/ext2-test/other", b"autre").unwrap();
+
+        vfs_mkdir("/ext2-test/dir").unwrap();
+        assert_eq!(vfs_stat("/ext2-test").unwrap().nlinks, 3);
+        vfs_link("/ext2-test/a.txt", "/ext2-test/dir/b.txt").unwrap();
+        assert_eq!(vfs_stat("/ext2-test/a.txt").unwrap().nlinks, 2);
+
+        // Types incompatibles : ni répertoire sur fichier, ni l'inverse
+        assert_eq!(vfs_rename("/ext2-test/dir", "/ext2-test/other"), Err(FsError::NotDirectory));
+        assert_eq!(vfs_rename("/ext2-test/other", "/ext2-test/dir"), Err(FsError::IsDirectory));
+
+        // Le fichier écrasé est libéré : son inode revient au volume
+        let before = vfs_statfs("/ext2-test").unwrap();
+        vfs_rename("/ext2-test/a.txt", "/ext2-test/other").unwrap();
+        let after = vfs_statfs("/ext2-test").unwrap();
+        assert_eq!(after.free_inodes, before.free_inodes + 1);
+        assert_eq!(after.free_blocks, before.free_blocks + 1);
+        assert_eq!(vfs_read_file("/ext2-test/other").unwrap(), b"bonjour");
+        assert_eq!(vfs_stat("/ext2-test/dir/b.txt").unwrap().nlinks, 2);
+
+        // Un répertoire déplacé change de parent
+        vfs_mkdir("/ext2-test/dir/sub").unwrap();
+        vfs_rename("/ext2-test/dir/sub", "/ext2-test/sub").unwrap();
+        assert_eq!(vfs_stat("/ext2-test/dir").unwrap().nlinks, 2);
+        assert_eq!(vfs_stat("/ext2-test").unwrap().nlinks, 4);
+
+        // Fichiers supprimés : blocs (tables d'indirection comprises) rendus
+        for path in ["/ext2-test/big", "/ext2-test/other", "/ext2-test/dir/b.txt"] {
+            vfs_remove_file(path).unwrap();
+        }
+        assert_eq!(vfs_remove_file("/ext2-test/dir"), Err(FsError::IsDirectory));
+        let emptied = vfs_statfs("/ext2-test").unwrap();
+        assert_eq!(emptied.free_inodes, blank.free_inodes - 2);
+        assert_eq!(emptied.free_blocks, blank.free_blocks - 2);
+        unmount_fs("/ext2-test").unwrap();
+
+        // Compteurs et arborescence relus sur le disque
+        let remounted = Ext2FileSystem::new(Ext2::new(disk).unwrap());
+        assert_eq!(remounted.statfs().unwrap(), emptied);
+        let mut fs = remounted.fs.lock();
+        assert_eq!(fs.read_dir("/").unwrap(), ["dir", "sub"]);
+        fs.remove_dir("/sub").unwrap();
+        assert_eq!(fs.remove_dir("/dir/.."), Err(FsError::InvalidArgument));
+        assert_eq!(fs.superblock.free_inodes_count as u64, blank.free_inodes - 1);
+    }
+
+    #[test_case]
+    fn test_ext2_statfs() {
+        let fs = Ext2FileSystem::new(Ext2::new(blank_volume()).unwrap());
+        let blank = fs.statfs().unwrap();
+        // Le bloc 0 (amorce) précède le premier bloc de données
+        assert_eq!((blank.block_size, blank.total_blocks, blank.free_blocks), (1024, 255, 246));
+        assert_eq!((blank.total_inodes, blank.free_inodes), (32, 22));
+        assert_eq!(blank, StatFs::from_superblock(fs.superblock().as_ref()));
+
+        // Un répertoire : un inode et son bloc d'entrées
+        let root = fs.get_inode(EXT2_ROOT_INO as InodeId).unwrap();
+        let dir = root.lock().mkdir("d", FileMode::new(0o755)).unwrap();
+        let stat = fs.statfs().unwrap();
+        assert_eq!((stat.free_blocks, stat.free_inodes), (245, 21));
+
+        // Un fichier de 13 blocs : 12 directs, la table d'indirection et un bloc
+        let file = fs.get_inode(dir).unwrap().lock().create("f", FileMode::new(0o644), FileType::Regular).unwrap();
+        fs.get_inode(file).unwrap().lock().write(0, &alloc::vec![1u8; 13 * 1024]).unwrap();
+        assert_eq!(fs.statfs().unwrap().free_blocks, 245 - 14);
+
+        fs.get_inode(dir).unwrap().lock().unlink("f").unwrap();
+        root.lock().rmdir("d").unwrap();
+        assert_eq!(fs.statfs().unwrap(), blank);
+    }
+
+    #[test_case]
+    fn test_ext2_unlink_while_open() {
+        use crate::fs::{vfs_mkdir, vfs_open_inode, vfs_remove_file, vfs_stat, vfs_statfs, vfs_write_file};
+        crate::fs::test_vfs();
+        let _ = vfs_mkdir("/ext2-orphan");
+        mount_fs("/ext2-orphan", Arc::new(Ext2FileSystem::new(Ext2::new(blank_volume()).unwrap())), MountFlags::new(0)).unwrap();
+        let blank = vfs_statfs("/ext2-orphan").unwrap();
+        vfs_write_file("/ext2-orphan/f", b"avant").unwrap();
+
+        // Descripteur ouvert : le dernier unlink ne rend ni l'inode ni ses blocs
+        let open = vfs_open_inode("/ext2-orphan/f").unwrap();
+        vfs_remove_file("/ext2-orphan/f").unwrap();
+        assert!(matches!(vfs_stat("/ext2-orphan/f"), Err(FsError::NotFound)));
+        let orphan = vfs_statfs("/ext2-orphan").unwrap();
+        assert_eq!((orphan.free_inodes, orphan.free_blocks), (blank.free_inodes - 1, blank.free_blocks - 1));
+
+        open.0.lock().write(5, b" et apres").unwrap();
+        let mut buf = [0u8; 14];
+        assert_eq!(open.0.lock().read(0, &mut buf).unwrap(), 14);
+        assert_eq!(&buf, b"avant et apres");
+        let stat = open.0.lock().stat().unwrap();
+        assert_eq!(stat.nlinks, 0);
+
+        // Un nouveau fichier ne reprend pas l'inode orphelin
+        vfs_write_file("/ext2-orphan/g", b"autre").unwrap();
+        assert_ne!(vfs_stat("/ext2-orphan/g").unwrap().inode, stat.inode);
+        vfs_remove_file("/ext2-orphan/g").unwrap();
+
+        // Fermeture du dernier descripteur : l'orphelin est libéré
+        drop(open);
+        assert_eq!(vfs_statfs("/ext2-orphan").unwrap(), blank);
+        unmount_fs("/ext2-orphan").unwrap();
+    }
+
+    #[test_case]
+    fn test_ext2_xattr_syscalls() {
+        use crate::syscall::{SyscallError, SyscallHandler, SyscallNumber, SyscallResult};
+        let call = |number: SyscallNumber, args: &[u64]| match SyscallHandler::new().handle(number as u64, args) {
+            SyscallResult::Success(value) => Ok(value),
+            SyscallResult::Error(e) => Err(e),
+        };
+        crate::fs::test_vfs();
+        let disk = blank_volume();
+        let _ = crate::fs::vfs_mkdir("/ext2-xattr");
+        mount_fs("/ext2-xattr", Arc::new(Ext2FileSystem::new(Ext2::new(disk.clone()).unwrap())), MountFlags::new(0)).unwrap();
+        crate::fs::vfs_write_file("/ext2-xattr/f", b"x").unwrap();
+        let free_blocks = crate::fs::vfs_statfs("/ext2-xattr").unwrap().free_blocks;
+
+        let path = b"/ext2-xattr/f\0";
+        let (mime, origin) = (b"user.mime_type\0", b"user.origin\0");
+        let set = |name: &[u8], value: &[u8], flags: u32| {
+            call(SyscallNumber::Setxattr, &[path.as_ptr() as u64, name.as_ptr() as u64, value.as_ptr() as u64, value.len() as u64, flags as u64])
+        };
+        assert!(matches!(set(mime, b"text/plain", 0), Ok(0)));
+        assert!(matches!(set(origin, b"web", crate::fs::XATTR_CREATE), Ok(0)));
+        assert!(matches!(set(origin, b"web", crate::fs::XATTR_CREATE), Err(SyscallError::AlreadyExists)));
+
+        let mut buf = [0u8; 64];
+        let get = |name: &[u8], buf: &mut [u8]| {
+            call(SyscallNumber::Getxattr, &[path.as_ptr() as u64, name.as_ptr() as u64, buf.as_mut_ptr() as u64, buf.len() as u64])
+        };
+        assert!(matches!(get(mime, &mut buf), Ok(10)));
+        assert_eq!(&buf[..10], b"text/plain");
+        let len = call(SyscallNumber::Listxattr, &[path.as_ptr() as u64, buf.as_mut_ptr() as u64, buf.len() as u64]).unwrap();
+        assert_eq!(&buf[..len as usize], b"user.mime_type\0user.origin\0");
+
+        // Les attributs occupent un bloc, rendu quand le dernier disparaît
+        assert_eq!(crate::fs::vfs_statfs("/ext2-xattr").unwrap().free_blocks, free_blocks - 1);
+        assert!(matches!(call(SyscallNumber::Removexattr, &[path.as_ptr() as u64, origin.as_ptr() as u64]), Ok(0)));
+        assert!(matches!(get(origin, &mut buf), Err(SyscallError::NoData)));
+        unmount_fs("/ext2-xattr").unwrap();
+
+        // Relus sur le disque après remontage
+        let remounted = Ext2FileSystem::new(Ext2::new(disk).unwrap());
+        let root = remounted.get_inode(EXT2_ROOT_INO as InodeId).unwrap();
+        let file = remounted.get_inode(root.lock().lookup("f").unwrap()).unwrap();
+        assert_eq!(file.lock().getxattr("user.mime_type").unwrap(), b"text/plain");
+        file.lock().removexattr("user.mime_type").unwrap();
+        assert_eq!(file.lock().listxattr().unwrap(), Vec::<String>::new());
+        assert_eq!(remounted.statfs().unwrap().free_blocks, free_blocks);
+    }
+
+    #[test_case]
+    fn test_xattr_block_roundtrip() {
+        let mut attrs = alloc::vec![
+            (6, String::from("selinux"), b"system_u:object_r:bin_t".to_vec()),
+            (1, String::from("mime_type"), b"text/plain".to_vec()),
+        ];
+        let block = encode_xattr_block(&mut attrs, 1, 1024).unwrap();
+        let (refcount, decoded) = decode_xattr_block(&block).unwrap();
+        assert_eq!(refcount, 1);
+        // Triés par index d'espace de noms
+        assert_eq!(decoded[0], (1, String::from("mime_type"), b"text/plain".to_vec()));
+        assert_eq!(xattr_full_name(decoded[1].0, &decoded[1].1).as_deref(), Some("security.selinux"));
+        assert_eq!(xattr_split("user.mime_type").unwrap(), (1, "mime_type"));
+
+        // Valeur trop grande pour le bloc
+        let mut big = alloc::vec![(1, String::from("big"), alloc::vec![0u8; 2048])];
+        assert!(matches!(encode_xattr_block(&mut big, 1, 1024), Err(Ext2Error::NoSpaceLeft)));
+    }
+}
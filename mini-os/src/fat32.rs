@@ -26,6 +26,14 @@ const FAT32_EOC: u32 = 0x0FFFFFF8;      // End Of Clusterchain marker
 const FAT32_FREE: u32 = 0x00000000;     // Cluster libre
 const FAT32_BAD: u32 = 0x0FFFFFF7;      // Cluster défectueux
 
+// Secteur FSInfo : signatures et champs
+const FSINFO_LEAD_SIG: u32 = 0x41615252;
+const FSINFO_STRUCT_SIG: u32 = 0x61417272;
+const FSINFO_TRAIL_SIG: u32 = 0xAA550000;
+const FSINFO_FREE_COUNT: usize = 488;
+const FSINFO_NEXT_FREE: usize = 492;
+const FSINFO_UNKNOWN: u32 = 0xFFFFFFFF;
+
 // Constantes pour les noms de fichiers longs (LFN)
 const LFN_LAST: u8 = 0x40;
 const LFN_DELETED: u8 = 0x80;
@@ -115,6 +123,58 @@ pub struct LfnEntry {
     pub name3: [u16; 2],            // Troisième partie du nom (caractères 12-13)
 }
 
+/// Position d'une entrée : cluster du répertoire et décalage dans le cluster
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct EntrySlot {
+    cluster: u32,
+    offset: usize,
+}
+
+/// Entrée courte trouvée, avec les emplacements des entrées LFN qui la précèdent
+struct FoundEntry {
+    entry: DirEntry,
+    slot: EntrySlot,
+    lfn_slots: Vec<EntrySlot>,
+}
+
+/// Propriétaire et modes des fichiers FAT32, qui n'en stockent pas :
+/// équivalent des options de montage uid=, gid=, fmask= et dmask= de Linux
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FatOwnership {
+    pub uid: u32,
+    pub gid: u32,
+    /// Bits retirés de 0777 pour les fichiers
+    pub fmask: u16,
+    /// Bits retirés de 0777 pour les répertoires
+    pub dmask: u16,
+}
+
+impl FatOwnership {
+    pub const fn new(uid: u32, gid: u32) -> Self {
+        Self { uid, gid, fmask: 0o022, dmask: 0o022 }
+    }
+
+    /// Mode d'une entrée d'après ses attributs ; ATTR_READ_ONLY retire
+    /// les droits d'écriture
+    pub fn mode_for(&self, attr: u8) -> u16 {
+        let mut mode = if attr & ATTR_DIRECTORY != 0 {
+            0o777 & !self.dmask
+        } else {
+            0o777 & !self.fmask
+        };
+        if attr & ATTR_READ_ONLY != 0 {
+            mode &= !0o222;
+        }
+        mode
+    }
+}
+
+impl Default for FatOwnership {
+    fn default() -> Self {
+        Self::new(0, 0)
+    }
+}
+
 // Structure pour gérer le système de fichiers FAT32
 pub struct FAT32<D: Disk> {
     disk: D,                        // Disque sous-jacent
@@ -124,7 +184,9 @@ pub struct FAT32<D: Disk> {
     current_dir_cluster: u32,       // Cluster du répertoire courant
     next_free_cluster: u32,         // Prochain cluster libre (pour l'allocation)
     free_cluster_count: u32,        // Nombre de clusters libres
+    fs_info_start: Option<u64>,     // Secteur FSInfo (None s'il est absent)
     initialized: bool,              // Si le système de fichiers est initialisé
+    ownership: FatOwnership,        // Propriétaire et modes attribués aux entrées
 }
 
 impl<D: Disk> FAT32<D> {
@@ -189,16 +251,157 @@ impl<D: Disk> FAT32<D> {
         let root_dir_sectors = ((bpb.root_entries as u32 * 32) + (bpb.bytes_per_sector as u32 - 1)) / bpb.bytes_per_sector as u32;
         let data_start = fat_start + (bpb.sectors_per_fat_32 as u64 * bpb.num_fats as u64 * 512) + (root_dir_sectors as u64 * 512);
 
-        Ok(FAT32 {
+        // FSInfo : 0 ou 0xFFFF si le volume n'en a pas
+        let fs_info_start = match bpb.fs_info_sector {
+            0 | 0xFFFF => None,
+            sector => Some(disk_offset + (bpb.hidden_sectors + sector as u32) as u64 * 512),
+        };
+
+        let mut fs = FAT32 {
             disk, // Initialisation du champ disk
             bpb,
             fat_start,
             data_start,
             current_dir_cluster: bpb.root_cluster,
-            next_free_cluster: bpb.root_cluster + 1, // Valeur initiale simple
-            free_cluster_count: 0, // À calculer
+            next_free_cluster: CLUSTER_ROOT,
+            free_cluster_count: 0,
+            fs_info_start,
             initialized: true,
-        })
+            ownership: FatOwnership::default(),
+        };
+
+        // Compteur et indice de FSInfo s'ils sont plausibles, sinon
+        // recomptage complet de la FAT
+        match fs.read_fs_info() {
+            Some((free, next)) => {
+                fs.free_cluster_count = free;
+                if let Some(next) = next {
+                    fs.next_free_cluster = next;
+                }
+            }
+            None => {
+                fs.free_cluster_count = fs.count_free_clusters()?;
+                fs.write_fs_info()?;
+            }
+        }
+        Ok(fs)
+    }
+
+    /// Nombre de clusters de la zone de données
+    fn cluster_count(&self) -> u32 {
+        let bpb = &self.bpb;
+        let total_sectors = if bpb.total_sectors_16 != 0 { bpb.total_sectors_16 as u32 } else { bpb.total_sectors_32 };
+        let meta = bpb.reserved_sectors as u32 + bpb.num_fats as u32 * bpb.sectors_per_fat_32;
+        total_sectors.saturating_sub(meta) / (bpb.sectors_per_cluster.max(1) as u32)
+    }
+
+    /// Premier numéro de cluster au-delà de la zone de données
+    fn max_cluster(&self) -> u32 {
+        self.cluster_count() + CLUSTER_ROOT
+    }
+
+    /// Lit FSInfo : (clusters libres, prochain cluster libre) ; None si le
+    /// secteur manque, est invalide ou ne connaît pas le nombre de clusters libres
+    fn read_fs_info(&self) -> Option<(u32, Option<u32>)> {
+        let start = self.fs_info_start?;
+        let mut sector = [0u8; 512];
+        self.disk.read(start, &mut sector).ok()?;
+        let field = |offset: usize| u32::from_le_bytes(sector[offset..offset + 4].try_into().unwrap());
+        if field(0) != FSINFO_LEAD_SIG || field(484) != FSINFO_STRUCT_SIG || field(508) != FSINFO_TRAIL_SIG {
+            return None;
+        }
+        let free = field(FSINFO_FREE_COUNT);
+        if free == FSINFO_UNKNOWN || free > self.cluster_count() {
+            return None;
+        }
+        let next = field(FSINFO_NEXT_FREE);
+        let next = if next >= CLUSTER_ROOT && next < self.max_cluster() { Some(next) } else { None };
+        Some((free, next))
+    }
+
+    /// Reporte le compteur de clusters libres et l'indice dans FSInfo
+    fn write_fs_info(&mut self) -> Result<(), FsError> {
+        let start = match self.fs_info_start {
+            Some(start) => start,
+            None => return Ok(()),
+        };
+        let mut sector = [0u8; 512];
+        self.disk.read(start, &mut sector).map_err(|_| FsError::IoError)?;
+        sector[0..4].copy_from_slice(&FSINFO_LEAD_SIG.to_le_bytes());
+        sector[484..488].copy_from_slice(&FSINFO_STRUCT_SIG.to_le_bytes());
+        sector[FSINFO_FREE_COUNT..FSINFO_FREE_COUNT + 4].copy_from_slice(&self.free_cluster_count.to_le_bytes());
+        sector[FSINFO_NEXT_FREE..FSINFO_NEXT_FREE + 4].copy_from_slice(&self.next_free_cluster.to_le_bytes());
+        sector[508..512].copy_from_slice(&FSINFO_TRAIL_SIG.to_le_bytes());
+        self.disk.write(start, &sector).map_err(|_| FsError::IoError)
+    }
+
+    /// Compte les clusters libres en parcourant la première FAT secteur par secteur
+    fn count_free_clusters(&mut self) -> Result<u32, FsError> {
+        let bytes_per_sector = self.bpb.bytes_per_sector as usize;
+        let max_cluster = self.max_cluster() as usize;
+        let mut sector = vec![0u8; bytes_per_sector];
+        let mut free = 0;
+        let mut first_free = None;
+        let entries_per_sector = bytes_per_sector / 4;
+        for index in 0..(max_cluster + entries_per_sector - 1) / entries_per_sector {
+            crate::scheduler::cond_resched();
+            self.disk.read(self.fat_start + index as u64, &mut sector).map_err(|_| FsError::IoError)?;
+            for (i, entry) in sector.chunks_exact(4).enumerate() {
+                let cluster = index * entries_per_sector + i;
+                if cluster < CLUSTER_ROOT as usize || cluster >= max_cluster {
+                    continue;
+                }
+                if u32::from_le_bytes(entry.try_into().unwrap()) & 0x0FFFFFFF == FAT32_FREE {
+                    free += 1;
+                    first_free.get_or_insert(cluster as u32);
+                }
+            }
+        }
+        self.next_free_cluster = first_free.unwrap_or(CLUSTER_ROOT);
+        Ok(free)
+    }
+
+    /// Occupation du volume au format statfs (un bloc = un cluster)
+    pub fn statfs(&self) -> crate::fs::StatFs {
+        crate::fs::StatFs {
+            fs_name: String::from("vfat"),
+            block_size: self.cluster_size() as u32,
+            total_blocks: self.cluster_count() as u64,
+            free_blocks: self.free_cluster_count as u64,
+            total_inodes: 0,
+            free_inodes: 0,
+            readonly: false,
+        }
+    }
+
+    /// Fixe le propriétaire et les masques appliqués à toutes les entrées
+    pub fn set_ownership(&mut self, ownership: FatOwnership) {
+        self.ownership = ownership;
+    }
+
+    pub fn ownership(&self) -> FatOwnership {
+        self.ownership
+    }
+
+    /// Statistiques de `path` au format du VFS, propriétaire et mode
+    /// venant de `FatOwnership`
+    pub fn stat(&self, path: &str) -> Result<crate::fs::FileStat, FsError> {
+        use crate::fs::{FileMode, FileStat, FileType};
+        let (attr, cluster, size) = if path.is_empty() || path == "/" {
+            (ATTR_DIRECTORY, self.bpb.root_cluster, 0)
+        } else {
+            let entry = self.find_file(path)?;
+            let cluster = ((entry.first_cluster_hi as u32) << 16) | (entry.first_cluster_lo as u32);
+            (entry.attr, cluster, entry.file_size)
+        };
+        let file_type = if attr & ATTR_DIRECTORY != 0 { FileType::Directory } else { FileType::Regular };
+        // Le premier cluster tient lieu de numéro d'inode
+        let mut stat = FileStat::new(cluster as u64, file_type);
+        stat.mode = FileMode::new(self.ownership.mode_for(attr));
+        stat.uid = self.ownership.uid;
+        stat.gid = self.ownership.gid;
+        stat.size = size as u64;
+        Ok(stat)
     }
 
     /// Lit un cluster depuis le disque
@@ -328,35 +531,17 @@ impl<D: Disk> FAT32<D> {
         Ok(next_cluster)
     }
     
-    /// Trouve un cluster libre
+    /// Trouve un cluster libre, à partir de `start_from` ou de l'indice
+    /// de FSInfo, en repartant du début du volume si besoin
     fn find_free_cluster(&mut self, start_from: Option<u32>) -> Result<u32, FsError> {
-        let start_cluster = start_from.unwrap_or(2); // Commencer après le cluster réservé
-        
-        // Si on a un indice de cluster libre, on vérifie d'abord celui-là
-        if let Some(next_free) = self.next_free_cluster.checked_sub(2) {
-            if next_free >= start_cluster {
-                // Vérifier si le cluster est toujours libre
-                match self.read_fat_entry(next_free) {
-                    Ok(FAT32_FREE) => {
-                        self.next_free_cluster = next_free + 1;
-                        return Ok(next_free);
-                    },
-                    _ => {}
-                }
-            }
-        }
-        
-        // Parcourir la FAT pour trouver un cluster libre
-        let total_clusters = (self.bpb.total_sectors_32 / self.bpb.sectors_per_cluster as u32) as u32;
+        let max_cluster = self.max_cluster();
+        let start = start_from.unwrap_or(self.next_free_cluster).clamp(CLUSTER_ROOT, max_cluster);
         
-        for cluster in start_cluster..total_clusters {
-            match self.read_fat_entry(cluster) {
-                Ok(FAT32_FREE) => {
-                    self.next_free_cluster = cluster + 1;
-                    return Ok(cluster);
-                },
-                Ok(_) => {},
-                Err(_) => break,
+        for cluster in (start..max_cluster).chain(CLUSTER_ROOT..start) {
+            crate::scheduler::cond_resched();
+            if self.read_fat_entry(cluster)? == FAT32_FREE {
+                self.next_free_cluster = cluster + 1;
+                return Ok(cluster);
             }
         }
         
@@ -368,23 +553,35 @@ impl<D: Disk> FAT32<D> {
         if count == 0 {
             return Err(FsError::InvalidArgument);
         }
+        if count > self.free_cluster_count {
+            return Err(FsError::NoSpace);
+        }
         
-        // Trouver le premier cluster libre
+        // Trouver le premier cluster libre ; il est marqué fin de chaîne
+        // aussitôt pour que la recherche suivante ne le reprenne pas
         let first_cluster = self.find_free_cluster(None)?;
+        self.write_fat_entry(first_cluster, FAT32_EOC)?;
+        self.free_cluster_count -= 1;
         let mut current_cluster = first_cluster;
         
         // Allouer les clusters supplémentaires si nécessaire
         for _ in 1..count {
-            let next_cluster = self.find_free_cluster(Some(current_cluster + 1))?;
+            let next_cluster = match self.find_free_cluster(Some(current_cluster + 1)) {
+                Ok(cluster) => cluster,
+                Err(e) => {
+                    let _ = self.free_cluster_chain(first_cluster);
+                    return Err(e);
+                }
+            };
             
             // Mettre à jour la FAT pour pointer vers le prochain cluster
+            self.write_fat_entry(next_cluster, FAT32_EOC)?;
             self.write_fat_entry(current_cluster, next_cluster)?;
+            self.free_cluster_count -= 1;
             current_cluster = next_cluster;
         }
         
-        // Marquer la fin de la chaîne
-        self.write_fat_entry(current_cluster, FAT32_EOC)?;
-        
+        self.write_fs_info()?;
         Ok(first_cluster)
     }
     
@@ -393,6 +590,7 @@ impl<D: Disk> FAT32<D> {
         let mut current_cluster = start_cluster;
         
         while current_cluster < 0x0FFFFFF8 {
+            crate::scheduler::cond_resched();
             // Lire le prochain cluster avant d'effacer l'entrée actuelle
             let next_cluster = self.read_fat_entry(current_cluster)?;
             
@@ -414,17 +612,29 @@ impl<D: Disk> FAT32<D> {
             current_cluster = next_cluster;
         }
         
-        Ok(())
+        self.write_fs_info()
     }
 
-    /// Trouve un fichier dans le répertoire courant
-    pub fn find_file(&self, name: &str) -> Result<DirEntry, FsError> {
-        let mut current_cluster = self.current_dir_cluster;
-        let mut buffer = vec![0u8; self.bpb.bytes_per_sector as usize * self.bpb.sectors_per_cluster as usize];
+    /// Taille d'un cluster en octets
+    fn cluster_size(&self) -> usize {
+        self.bpb.bytes_per_sector as usize * self.bpb.sectors_per_cluster as usize
+    }
+
+    /// Premier cluster d'une entrée
+    fn entry_cluster(entry: &DirEntry) -> u32 {
+        ((entry.first_cluster_hi as u32) << 16) | (entry.first_cluster_lo as u32)
+    }
+
+    /// Cherche `name` dans le répertoire commençant au cluster `dir_cluster`
+    fn find_in_dir(&self, dir_cluster: u32, name: &str) -> Result<FoundEntry, FsError> {
+        let mut current_cluster = dir_cluster;
+        let mut buffer = vec![0u8; self.cluster_size()];
         let mut lfn_entries = Vec::new();
-        let short_name = Self::to_short_name(name);
+        let mut lfn_slots = Vec::new();
+        let short_name = Self::short_name_bytes(name);
         
         loop {
+            crate::scheduler::cond_resched();
             // Lire le cluster actuel
             self.read_cluster(current_cluster, &mut buffer)?;
             
@@ -432,6 +642,7 @@ impl<D: Disk> FAT32<D> {
             for entry_pos in (0..buffer.len()).step_by(DIR_ENTRY_SIZE) {
                 let entry_slice = &buffer[entry_pos..entry_pos + DIR_ENTRY_SIZE];
                 let first_byte = entry_slice[0];
+                let slot = EntrySlot { cluster: current_cluster, offset: entry_pos };
                 
                 // Vérifier si c'est la fin du répertoire
                 if first_byte == DIR_ENTRY_LAST {
@@ -441,40 +652,28 @@ impl<D: Disk> FAT32<D> {
                 // Ignorer les entrées supprimées
                 if first_byte == DIR_ENTRY_DELETED {
                     lfn_entries.clear();
+                    lfn_slots.clear();
                     continue;
                 }
                 
-                // Vérifier si c'est une entrée LFN (Long File Name)
+                // Entrée LFN : la stocker jusqu'à l'entrée courte qui la suit
                 if (entry_slice[11] & ATTR_LONG_NAME) == ATTR_LONG_NAME {
-                    // C'est une entrée LFN, la stocker pour plus tard
                     let lfn_entry = unsafe { &*(entry_slice.as_ptr() as *const LfnEntry) };
-                    lfn_entries.push(lfn_entry.clone());
+                    lfn_entries.push(*lfn_entry);
+                    lfn_slots.push(slot);
                     continue;
                 }
                 
                 // C'est une entrée de répertoire standard
                 let dir_entry = unsafe { &*(entry_slice.as_ptr() as *const DirEntry) };
-                
-                // Vérifier si c'est un fichier ou un répertoire valide
-                if dir_entry.name[0] != 0x00 && dir_entry.name[0] != 0xE5 {
-                    // Vérifier si le nom correspond
-                    let entry_short_name = Self::format_short_name(&dir_entry.name, &dir_entry.ext);
-                    
-                    if entry_short_name == short_name {
-                        return Ok(*dir_entry);
-                    }
-                    
-                    // Vérifier si on a des entrées LFN pour ce fichier
-                    if !lfn_entries.is_empty() {
-                        let lfn = Self::decode_lfn_entries(&lfn_entries);
-                        if lfn.eq_ignore_ascii_case(name) {
-                            return Ok(*dir_entry);
-                        }
-                        lfn_entries.clear();
-                    }
-                } else {
-                    lfn_entries.clear();
+                let short_match = short_name.map_or(false, |short| entry_slice[..11] == short[..]);
+                let long_match = !lfn_entries.is_empty()
+                    && Self::decode_lfn_entries(&lfn_entries).eq_ignore_ascii_case(name);
+                if (short_match || long_match) && dir_entry.attr & ATTR_VOLUME_ID == 0 {
+                    return Ok(FoundEntry { entry: *dir_entry, slot, lfn_slots });
                 }
+                lfn_entries.clear();
+                lfn_slots.clear();
             }
             
             // Passer au cluster suivant dans la chaîne
@@ -486,6 +685,48 @@ impl<D: Disk> FAT32<D> {
         
         Err(FsError::NotFound)
     }
+
+    /// Résout un chemin : depuis la racine s'il est absolu, sinon depuis
+    /// le répertoire courant
+    fn lookup(&self, path: &str) -> Result<FoundEntry, FsError> {
+        let mut dir_cluster = if path.starts_with('/') { self.bpb.root_cluster } else { self.current_dir_cluster };
+        let mut components = path.split('/').filter(|c| !c.is_empty()).peekable();
+        while let Some(component) = components.next() {
+            let found = self.find_in_dir(dir_cluster, component)?;
+            if components.peek().is_none() {
+                return Ok(found);
+            }
+            if found.entry.attr & ATTR_DIRECTORY == 0 {
+                return Err(FsError::NotDirectory);
+            }
+            dir_cluster = Self::entry_cluster(&found.entry);
+            // `..` d'un sous-répertoire de la racine : cluster 0
+            if dir_cluster == 0 {
+                dir_cluster = self.bpb.root_cluster;
+            }
+        }
+        Err(FsError::InvalidArgument)
+    }
+
+    /// Cluster du répertoire désigné par `path`
+    fn resolve_dir(&self, path: &str) -> Result<u32, FsError> {
+        if path.trim_matches('/').is_empty() {
+            return Ok(if path.starts_with('/') { self.bpb.root_cluster } else { self.current_dir_cluster });
+        }
+        let found = self.lookup(path)?;
+        if found.entry.attr & ATTR_DIRECTORY == 0 {
+            return Err(FsError::NotDirectory);
+        }
+        match Self::entry_cluster(&found.entry) {
+            0 => Ok(self.bpb.root_cluster),
+            cluster => Ok(cluster),
+        }
+    }
+
+    /// Trouve un fichier (chemin absolu, ou relatif au répertoire courant)
+    pub fn find_file(&self, name: &str) -> Result<DirEntry, FsError> {
+        Ok(self.lookup(name)?.entry)
+    }
     
     /// Lit un fichier dans le système de fichiers
     pub fn read_file(&self, path: &str) -> Result<Vec<u8>, FsError> {
@@ -498,13 +739,13 @@ impl<D: Disk> FAT32<D> {
         }
         
         // Calculer le nombre de clusters nécessaires
-        let cluster_size = self.bpb.bytes_per_sector as u64 * self.bpb.sectors_per_cluster as u64;
+        let cluster_size = self.cluster_size() as u64;
         let mut remaining_size = entry.file_size as u64;
         let mut data = Vec::with_capacity(entry.file_size as usize);
         let mut buffer = vec![0u8; cluster_size as usize];
         
         // Lire le premier cluster
-        let mut current_cluster = ((entry.first_cluster_hi as u32) << 16) | (entry.first_cluster_lo as u32);
+        let mut current_cluster = Self::entry_cluster(&entry);
         
         while remaining_size > 0 {
             // Lire le cluster actuel
@@ -527,45 +768,25 @@ impl<D: Disk> FAT32<D> {
     }
     
     /// Écrit un fichier dans le système de fichiers
+    ///
+    /// Un fichier existant est remplacé en gardant sa date de création ;
+    /// la date de modification vient de l'horloge (RTC).
     pub fn write_file(&mut self, path: &str, data: &[u8]) -> Result<(), FsError> {
-        // Vérifier si le fichier existe déjà
-        let entry = self.find_file(path);
-        
         // Si le fichier existe, le supprimer d'abord
-        if let Ok(entry) = entry {
+        let mut created = None;
+        if let Ok(existing) = self.lookup(path) {
+            if existing.entry.attr & ATTR_DIRECTORY != 0 {
+                return Err(FsError::IsDirectory);
+            }
+            let entry = existing.entry;
+            created = Some((entry.creation_date, entry.creation_time, entry.creation_time_tenth));
             self.remove_file(path)?;
         }
         
-        // Calculer le nombre de clusters nécessaires
-        let cluster_size = self.bpb.bytes_per_sector as usize * self.bpb.sectors_per_cluster as usize;
+        // Calculer le nombre de clusters nécessaires (aucun pour un fichier vide)
+        let cluster_size = self.cluster_size();
         let clusters_needed = (data.len() + cluster_size - 1) / cluster_size;
-        
-        // Allouer les clusters nécessaires
-        let first_cluster = self.allocate_cluster_chain(clusters_needed as u32)?;
-        
-        // Créer une nouvelle entrée de répertoire
-        let mut dir_entry = DirEntry {
-            name: [b' '; 8],
-            ext: [b' '; 3],
-            attr: ATTR_ARCHIVE,
-            nt_reserved: 0,
-            creation_time_tenth: 0, // TODO: Implémenter la gestion de l'horodatage
-            creation_time: 0,
-            creation_date: 0,
-            last_access_date: 0,
-            first_cluster_hi: (first_cluster >> 16) as u16,
-            write_time: 0,
-            write_date: 0,
-            first_cluster_lo: (first_cluster & 0xFFFF) as u16,
-            file_size: data.len() as u32,
-        };
-        
-        // Définir le nom court du fichier
-        let (name, ext) = Self::split_filename(path);
-        dir_entry.name[..name.len()].copy_from_slice(&name.as_bytes()[..name.len().min(8)]);
-        if !ext.is_empty() {
-            dir_entry.ext[..ext.len()].copy_from_slice(&ext.as_bytes()[..ext.len().min(3)]);
-        }
+        let first_cluster = if clusters_needed == 0 { 0 } else { self.allocate_cluster_chain(clusters_needed as u32)? };
         
         // Écrire les données dans les clusters alloués
         let mut remaining_data = data;
@@ -590,38 +811,140 @@ impl<D: Disk> FAT32<D> {
             }
         }
         
-        // Ajouter l'entrée de répertoire
-        self.add_directory_entry(&dir_entry)
+        // Ajouter l'entrée de répertoire (avec ses entrées LFN)
+        let result = self.create_entry(path, ATTR_ARCHIVE, first_cluster, data.len() as u32, created);
+        if result.is_err() && first_cluster != 0 {
+            let _ = self.free_cluster_chain(first_cluster);
+        }
+        result
+    }
+
+    /// Crée un sous-répertoire, avec ses entrées `.` et `..`
+    pub fn create_dir(&mut self, path: &str) -> Result<(), FsError> {
+        if self.lookup(path).is_ok() {
+            return Err(FsError::AlreadyExists);
+        }
+        let (parent, _) = Self::split_parent(path);
+        let parent_cluster = self.resolve_dir(parent)?;
+
+        let cluster = self.allocate_cluster_chain(1)?;
+        let (date, time, tenth) = Self::timestamp();
+        let mut buffer = vec![0u8; self.cluster_size()];
+        // `..` vers la racine : cluster 0 par convention
+        let dotdot_cluster = if parent_cluster == self.bpb.root_cluster { 0 } else { parent_cluster };
+        for (i, (name, target)) in [(*b".          ", cluster), (*b"..         ", dotdot_cluster)].iter().enumerate() {
+            let entry = Self::new_dir_entry(name, ATTR_DIRECTORY, *target, 0, (date, time, tenth), (date, time, tenth));
+            buffer[i * DIR_ENTRY_SIZE..(i + 1) * DIR_ENTRY_SIZE].copy_from_slice(Self::entry_bytes(&entry));
+        }
+        self.write_cluster(cluster, &buffer)?;
+
+        let result = self.create_entry(path, ATTR_DIRECTORY, cluster, 0, None);
+        if result.is_err() {
+            let _ = self.free_cluster_chain(cluster);
+        }
+        result
+    }
+
+    /// Date, heure et centièmes DOS de l'instant présent (RTC)
+    fn timestamp() -> (u16, u16, u8) {
+        let now = crate::time::now_datetime();
+        // Centièmes au-delà des 2 s de résolution de l'heure DOS
+        (Self::dos_date(&now), Self::dos_time(&now), (now.second % 2) * 100)
+    }
+
+    fn new_dir_entry(short: &[u8; 11], attr: u8, cluster: u32, size: u32, created: (u16, u16, u8), written: (u16, u16, u8)) -> DirEntry {
+        let mut name = [0u8; 8];
+        let mut ext = [0u8; 3];
+        name.copy_from_slice(&short[..8]);
+        ext.copy_from_slice(&short[8..]);
+        DirEntry {
+            name,
+            ext,
+            attr,
+            nt_reserved: 0,
+            creation_time_tenth: created.2,
+            creation_time: created.1,
+            creation_date: created.0,
+            last_access_date: written.0,
+            first_cluster_hi: (cluster >> 16) as u16,
+            write_time: written.1,
+            write_date: written.0,
+            first_cluster_lo: (cluster & 0xFFFF) as u16,
+            file_size: size,
+        }
+    }
+
+    /// Octets d'une entrée de répertoire (courte ou LFN)
+    fn entry_bytes<T: Copy>(entry: &T) -> &[u8] {
+        unsafe { core::slice::from_raw_parts(entry as *const T as *const u8, size_of::<T>()) }
+    }
+
+    /// Crée l'entrée de `path` dans son répertoire parent : nom court seul
+    /// si le nom est un 8.3 en majuscules, sinon entrées LFN suivies d'un
+    /// nom court unique (`NOM~N.EXT`)
+    fn create_entry(&mut self, path: &str, attr: u8, cluster: u32, size: u32, created: Option<(u16, u16, u8)>) -> Result<(), FsError> {
+        let (parent, name) = Self::split_parent(path);
+        if name.is_empty() || name == "." || name == ".." {
+            return Err(FsError::InvalidArgument);
+        }
+        let dir_cluster = self.resolve_dir(parent)?;
+
+        let exact = Self::short_name_bytes(name).filter(|_| !name.bytes().any(|b| b.is_ascii_lowercase()));
+        let (short, lfn) = match exact {
+            Some(short) => (short, Vec::new()),
+            None => {
+                let short = self.generate_short_name(dir_cluster, name)?;
+                (short, Self::build_lfn_entries(name, Self::lfn_checksum(&short))?)
+            }
+        };
+
+        let now = Self::timestamp();
+        let entry = Self::new_dir_entry(&short, attr, cluster, size, created.unwrap_or(now), now);
+        let mut raw: Vec<[u8; DIR_ENTRY_SIZE]> = Vec::with_capacity(lfn.len() + 1);
+        for lfn_entry in &lfn {
+            raw.push(Self::entry_bytes(lfn_entry).try_into().unwrap());
+        }
+        raw.push(Self::entry_bytes(&entry).try_into().unwrap());
+        self.add_directory_entry(dir_cluster, &raw)
     }
     
-    /// Ajoute une entrée au répertoire courant
-    fn add_directory_entry(&mut self, entry: &DirEntry) -> Result<(), FsError> {
-        let mut current_cluster = self.current_dir_cluster;
-        let mut buffer = vec![0u8; self.bpb.bytes_per_sector as usize * self.bpb.sectors_per_cluster as usize];
+    /// Écrit des entrées consécutives dans le répertoire `dir_cluster` ;
+    /// les entrées LFN et leur entrée courte tiennent dans un même cluster
+    fn add_directory_entry(&mut self, dir_cluster: u32, entries: &[[u8; DIR_ENTRY_SIZE]]) -> Result<(), FsError> {
+        let cluster_size = self.cluster_size();
+        let needed = entries.len() * DIR_ENTRY_SIZE;
+        if needed > cluster_size {
+            return Err(FsError::NameTooLong);
+        }
+        let mut current_cluster = dir_cluster;
+        let mut buffer = vec![0u8; cluster_size];
         
         loop {
+            crate::scheduler::cond_resched();
             // Lire le cluster actuel
             self.read_cluster(current_cluster, &mut buffer)?;
             
-            // Chercher une entrée libre
+            // Chercher assez d'entrées libres ou supprimées consécutives
+            let mut run_start = 0;
+            let mut run_len = 0;
             for entry_pos in (0..buffer.len()).step_by(DIR_ENTRY_SIZE) {
                 let entry_byte = buffer[entry_pos];
-                
-                // Vérifier si c'est une entrée libre ou supprimée
                 if entry_byte == DIR_ENTRY_LAST || entry_byte == DIR_ENTRY_DELETED {
-                    // Écrire la nouvelle entrée
-                    let entry_slice = unsafe {
-                        core::slice::from_raw_parts(
-                            entry as *const _ as *const u8,
-                            core::mem::size_of::<DirEntry>()
-                        )
-                    };
-                    
-                    buffer[entry_pos..entry_pos + DIR_ENTRY_SIZE].copy_from_slice(entry_slice);
-                    
-                    // Écrire le cluster mis à jour
-                    self.write_cluster(current_cluster, &buffer)?;
-                    return Ok(());
+                    if run_len == 0 {
+                        run_start = entry_pos;
+                    }
+                    run_len += DIR_ENTRY_SIZE;
+                    if run_len == needed {
+                        for (i, raw) in entries.iter().enumerate() {
+                            let offset = run_start + i * DIR_ENTRY_SIZE;
+                            buffer[offset..offset + DIR_ENTRY_SIZE].copy_from_slice(raw);
+                        }
+                        // Écrire le cluster mis à jour
+                        self.write_cluster(current_cluster, &buffer)?;
+                        return Ok(());
+                    }
+                } else {
+                    run_len = 0;
                 }
             }
             
@@ -633,21 +956,12 @@ impl<D: Disk> FAT32<D> {
                     let new_cluster = self.allocate_cluster_chain(1)?;
                     self.write_fat_entry(current_cluster, new_cluster)?;
                     
-                    // Initialiser le nouveau cluster avec des zéros
-                    let cluster_size = self.bpb.bytes_per_sector as usize * self.bpb.sectors_per_cluster as usize;
-                    let zero_buffer = vec![0u8; cluster_size];
-                    self.write_cluster(new_cluster, &zero_buffer)?;
-                    
-                    // Écrire la nouvelle entrée au début du nouveau cluster
-                    let entry_slice = unsafe {
-                        core::slice::from_raw_parts(
-                            entry as *const _ as *const u8,
-                            core::mem::size_of::<DirEntry>()
-                        )
-                    };
-                    
-                    buffer[..DIR_ENTRY_SIZE].copy_from_slice(entry_slice);
-                    self.write_cluster(new_cluster, &buffer)?;
+                    // Nouveau cluster vide, les entrées au début
+                    let mut new_buffer = vec![0u8; cluster_size];
+                    for (i, raw) in entries.iter().enumerate() {
+                        new_buffer[i * DIR_ENTRY_SIZE..(i + 1) * DIR_ENTRY_SIZE].copy_from_slice(raw);
+                    }
+                    self.write_cluster(new_cluster, &new_buffer)?;
                     
                     return Ok(());
                 }
@@ -658,87 +972,185 @@ impl<D: Disk> FAT32<D> {
     /// Supprime un fichier du système de fichiers
     pub fn remove_file(&mut self, path: &str) -> Result<(), FsError> {
         // Trouver le fichier
-        let entry = self.find_file(path)?;
+        let found = self.lookup(path)?;
         
         // Vérifier que c'est bien un fichier
-        if (entry.attr & ATTR_DIRECTORY) != 0 {
+        if (found.entry.attr & ATTR_DIRECTORY) != 0 {
             return Err(FsError::IsDirectory);
         }
         
-        // Libérer les clusters alloués
-        let first_cluster = ((entry.first_cluster_hi as u32) << 16) | (entry.first_cluster_lo as u32);
-        self.free_cluster_chain(first_cluster)?;
+        // Libérer les clusters alloués (aucun pour un fichier vide)
+        let first_cluster = Self::entry_cluster(&found.entry);
+        if first_cluster != 0 {
+            self.free_cluster_chain(first_cluster)?;
+        }
         
-        // Marquer l'entrée comme supprimée
-        self.mark_entry_deleted(path)
+        // Marquer l'entrée et ses entrées LFN comme supprimées
+        self.mark_entry_deleted(&found)
     }
     
-    /// Marque une entrée de répertoire comme supprimée
-    fn mark_entry_deleted(&mut self, path: &str) -> Result<(), FsError> {
-        let mut current_cluster = self.current_dir_cluster;
-        let mut buffer = vec![0u8; self.bpb.bytes_per_sector as usize * self.bpb.sectors_per_cluster as usize];
-        let short_name = Self::to_short_name(path);
-        
-        loop {
-            // Lire le cluster actuel
-            self.read_cluster(current_cluster, &mut buffer)?;
-            
-            // Parcourir chaque entrée de répertoire dans le cluster
-            for entry_pos in (0..buffer.len()).step_by(DIR_ENTRY_SIZE) {
-                let entry_slice = &mut buffer[entry_pos..entry_pos + DIR_ENTRY_SIZE];
-                let first_byte = entry_slice[0];
-                
-                // Vérifier si c'est la fin du répertoire
-                if first_byte == DIR_ENTRY_LAST {
-                    return Err(FsError::NotFound);
-                }
-                
-                // Ignorer les entrées supprimées
-                if first_byte == DIR_ENTRY_DELETED {
-                    continue;
-                }
-                
-                // Vérifier si c'est le fichier qu'on cherche
-                let dir_entry = unsafe { &*(entry_slice.as_ptr() as *const DirEntry) };
-                let entry_short_name = Self::format_short_name(&dir_entry.name, &dir_entry.ext);
-                
-                if entry_short_name == short_name {
-                    // Marquer l'entrée comme supprimée
-                    entry_slice[0] = DIR_ENTRY_DELETED;
-                    
-                    // Écrire le cluster mis à jour
-                    self.write_cluster(current_cluster, &buffer)?;
-                    return Ok(());
+    /// Marque une entrée de répertoire et ses entrées LFN comme supprimées
+    fn mark_entry_deleted(&mut self, found: &FoundEntry) -> Result<(), FsError> {
+        let mut buffer = vec![0u8; self.cluster_size()];
+        let mut loaded = None;
+        for slot in found.lfn_slots.iter().chain(core::iter::once(&found.slot)) {
+            if loaded != Some(slot.cluster) {
+                if let Some(cluster) = loaded {
+                    self.write_cluster(cluster, &buffer)?;
                 }
+                self.read_cluster(slot.cluster, &mut buffer)?;
+                loaded = Some(slot.cluster);
             }
-            
-            // Passer au cluster suivant
-            match self.get_next_cluster(current_cluster) {
-                Ok(next_cluster) => current_cluster = next_cluster,
-                Err(_) => break, // Fin de la chaîne
-            }
+            buffer[slot.offset] = DIR_ENTRY_DELETED;
         }
-        
-        Err(FsError::NotFound)
+        if let Some(cluster) = loaded {
+            self.write_cluster(cluster, &buffer)?;
+        }
+        Ok(())
     }
 
-    /// Lit un fichier dans le système de fichiers
-    
-    /// Convertit un nom de fichier en format 8.3
-    fn to_short_name(name: &str) -> String {
-        let name = name.to_ascii_uppercase();
-        let (name_part, ext_part) = match name.rfind('.') {
+    /// Sépare un chemin en répertoire parent et nom
+    fn split_parent(path: &str) -> (&str, &str) {
+        let path = path.trim_end_matches('/');
+        match path.rfind('/') {
+            Some(0) => ("/", &path[1..]),
+            Some(pos) => (&path[..pos], &path[pos + 1..]),
+            None => ("", path),
+        }
+    }
+
+    /// Caractère admis dans un nom court (hors lettres et chiffres)
+    fn is_short_name_char(b: u8) -> bool {
+        b.is_ascii_alphanumeric() || b"$%'-_@~`!(){}^#&".contains(&b)
+    }
+
+    /// Nom court (11 octets, majuscules) si `name` est un nom 8.3 valide
+    fn short_name_bytes(name: &str) -> Option<[u8; 11]> {
+        match name {
+            "." => return Some(*b".          "),
+            ".." => return Some(*b"..         "),
+            _ => {}
+        }
+        let (base, ext) = match name.rfind('.') {
             Some(dot_pos) => (&name[..dot_pos], &name[dot_pos + 1..]),
-            None => (name.as_str(), ""),
+            None => (name, ""),
         };
-        
-        // Tronquer ou compléter avec des espaces
-        let name_part = if name_part.len() > 8 { &name_part[..8] } else { name_part };
-        let ext_part = if ext_part.len() > 3 { &ext_part[..3] } else { ext_part };
-        
-        alloc::format!("{:8.8}{:3.3}", name_part, ext_part)
+        if base.is_empty() || base.len() > 8 || ext.len() > 3 {
+            return None;
+        }
+        if !base.bytes().chain(ext.bytes()).all(Self::is_short_name_char) {
+            return None;
+        }
+        let mut short = [b' '; 11];
+        for (i, b) in base.bytes().enumerate() {
+            short[i] = b.to_ascii_uppercase();
+        }
+        for (i, b) in ext.bytes().enumerate() {
+            short[8 + i] = b.to_ascii_uppercase();
+        }
+        Some(short)
     }
-    
+
+    /// Nom court unique dans le répertoire pour un nom long : le nom
+    /// lui-même en majuscules s'il est libre, sinon `BASE~N.EXT`
+    fn generate_short_name(&self, dir_cluster: u32, name: &str) -> Result<[u8; 11], FsError> {
+        let taken = |short: &[u8; 11]| -> Result<bool, FsError> {
+            let text = Self::format_short_name(short[..8].try_into().unwrap(), short[8..].try_into().unwrap());
+            match self.find_in_dir(dir_cluster, &text) {
+                Ok(found) => Ok(found.entry.name == short[..8] && found.entry.ext == short[8..]),
+                Err(FsError::NotFound) => Ok(false),
+                Err(e) => Err(e),
+            }
+        };
+        if let Some(short) = Self::short_name_bytes(name) {
+            if !taken(&short)? {
+                return Ok(short);
+            }
+        }
+
+        let filter = |part: &str, max: usize| -> Vec<u8> {
+            part.bytes()
+                .map(|b| b.to_ascii_uppercase())
+                .filter(|&b| b != b' ' && b != b'.')
+                .map(|b| if Self::is_short_name_char(b) { b } else { b'_' })
+                .take(max)
+                .collect()
+        };
+        let trimmed = name.trim_start_matches('.');
+        let (base, ext) = match trimmed.rfind('.') {
+            Some(dot_pos) => (filter(&trimmed[..dot_pos], 8), filter(&trimmed[dot_pos + 1..], 3)),
+            None => (filter(trimmed, 8), Vec::new()),
+        };
+        let base = if base.is_empty() { b"_".to_vec() } else { base };
+
+        for n in 1..=999_999u32 {
+            let suffix = format!("~{}", n);
+            let keep = base.len().min(8 - suffix.len());
+            let mut short = [b' '; 11];
+            short[..keep].copy_from_slice(&base[..keep]);
+            short[keep..keep + suffix.len()].copy_from_slice(suffix.as_bytes());
+            short[8..8 + ext.len()].copy_from_slice(&ext);
+            if !taken(&short)? {
+                return Ok(short);
+            }
+        }
+        Err(FsError::AlreadyExists)
+    }
+
+    /// Somme de contrôle du nom court, répétée dans chaque entrée LFN
+    fn lfn_checksum(short: &[u8; 11]) -> u8 {
+        short.iter().fold(0u8, |sum, &b| sum.rotate_right(1).wrapping_add(b))
+    }
+
+    /// Entrées LFN de `name`, dans l'ordre du disque (dernière partie d'abord)
+    fn build_lfn_entries(name: &str, checksum: u8) -> Result<Vec<LfnEntry>, FsError> {
+        let units: Vec<u16> = name.encode_utf16().collect();
+        if units.is_empty() || units.len() > 255 {
+            return Err(FsError::NameTooLong);
+        }
+        let count = (units.len() + 12) / 13;
+        let mut entries = Vec::with_capacity(count);
+        for i in 0..count {
+            // Terminateur NUL puis bourrage 0xFFFF
+            let mut chars = [0xFFFFu16; 13];
+            for (j, c) in chars.iter_mut().enumerate() {
+                let k = i * 13 + j;
+                if k < units.len() {
+                    *c = units[k];
+                } else if k == units.len() {
+                    *c = 0;
+                }
+            }
+            let mut order = (i + 1) as u8;
+            if i == count - 1 {
+                order |= LFN_LAST;
+            }
+            entries.push(LfnEntry {
+                order,
+                name1: chars[0..5].try_into().unwrap(),
+                attr: ATTR_LONG_NAME,
+                type_: 0,
+                checksum,
+                name2: chars[5..11].try_into().unwrap(),
+                first_cluster: 0,
+                name3: chars[11..13].try_into().unwrap(),
+            });
+        }
+        entries.reverse();
+        Ok(entries)
+    }
+
+    /// Date DOS : jour (bits 0-4), mois (5-8), années depuis 1980 (9-15)
+    fn dos_date(date: &crate::time::DateTime) -> u16 {
+        let year = date.year.clamp(1980, 2107) - 1980;
+        (year << 9) | ((date.month as u16) << 5) | date.day as u16
+    }
+
+    /// Heure DOS : secondes / 2 (bits 0-4), minutes (5-10), heures (11-15)
+    fn dos_time(date: &crate::time::DateTime) -> u16 {
+        ((date.hour as u16) << 11) | ((date.minute as u16) << 5) | (date.second as u16 / 2)
+    }
+
     /// Formate un nom de fichier 8.3
     fn format_short_name(name: &[u8; 8], ext: &[u8; 3]) -> String {
         let name_str = String::from_utf8_lossy(name).trim_end().into();
@@ -751,20 +1163,6 @@ impl<D: Disk> FAT32<D> {
         }
     }
     
-    /// Sépare un nom de fichier en nom et extension
-    fn split_filename(path: &str) -> (String, String) {
-        let path = path.trim_start_matches('/');
-        let name = match path.rfind('/') {
-            Some(pos) => &path[pos + 1..],
-            None => path,
-        };
-        
-        match name.rfind('.') {
-            Some(dot_pos) => (name[..dot_pos].into(), name[dot_pos + 1..].into()),
-            None => (name.into(), String::new()),
-        }
-    }
-    
     /// Décode une entrée de nom de fichier long (LFN)
     fn decode_lfn_entries(entries: &[LfnEntry]) -> String {
         let mut result = String::new();
@@ -807,16 +1205,7 @@ impl<D: Disk> FAT32<D> {
     /// Lit les entrées racine ou d'un répertoire
     pub fn read_dir(&self, path: &str) -> Result<Vec<String>, FsError> {
         let mut entries = Vec::new();
-        let mut current_cluster = if path.is_empty() || path == "/" {
-            self.bpb.root_cluster
-        } else {
-            // Trouver le cluster du répertoire
-            let entry = self.find_file(path)?;
-            if (entry.attr & ATTR_DIRECTORY) == 0 {
-                return Err(FsError::NotDirectory);
-            }
-            ((entry.first_cluster_hi as u32) << 16) | (entry.first_cluster_lo as u32)
-        };
+        let mut current_cluster = self.resolve_dir(path)?;
         
         loop {
             // Taille d'un cluster en octets
@@ -882,3 +1271,217 @@ impl<D: Disk> FAT32<D> {
         Ok(entries)
     }
 }
+
+/// Nombre minimal de clusters d'un volume FAT32 (en deçà, FAT16)
+pub const FAT32_MIN_CLUSTERS: u32 = 65525;
+
+/// Géométrie choisie par `format`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormatInfo {
+    pub sectors_per_cluster: u8,
+    pub sectors_per_fat: u32,
+    pub clusters: u32,
+}
+
+/// Secteurs par cluster selon la taille du volume (table de Microsoft)
+fn cluster_sectors_for(total_sectors: u64) -> u8 {
+    match total_sectors {
+        0..=532_480 => 1,              // jusqu'à 260 Mio
+        532_481..=16_777_216 => 8,     // 8 Gio
+        16_777_217..=33_554_432 => 16, // 16 Gio
+        33_554_433..=67_108_864 => 32, // 32 Gio
+        _ => 64,
+    }
+}
+
+/// Crée un volume FAT32 vide sur `sectors` secteurs à partir de `start` :
+/// secteur de démarrage et sa copie, FSInfo, deux FAT et un répertoire
+/// racine vide. Tout l'espace réservé est remis à zéro.
+pub fn format<D: Disk>(disk: &mut D, start: u64, sectors: u64, label: &str) -> Result<FormatInfo, FsError> {
+    let total = u32::try_from(sectors).map_err(|_| FsError::InvalidArgument)?;
+    let spc = cluster_sectors_for(sectors);
+    // Taille d'une FAT (formule de la spécification Microsoft)
+    let tmp1 = total.checked_sub(RESERVED_SECTORS).ok_or(FsError::NoSpace)?;
+    let tmp2 = (256 * spc as u32 + NUM_FATS as u32) / 2;
+    let fat_sectors = tmp1.div_ceil(tmp2);
+    let data_start = RESERVED_SECTORS + NUM_FATS as u32 * fat_sectors;
+    let clusters = total.saturating_sub(data_start) / spc as u32;
+    if clusters < FAT32_MIN_CLUSTERS {
+        return Err(FsError::NoSpace);
+    }
+
+    let mut volume_label = *b"NO NAME    ";
+    for (slot, b) in volume_label.iter_mut().zip(label.bytes().filter(|b| b.is_ascii_graphic() || *b == b' ')) {
+        *slot = b.to_ascii_uppercase();
+    }
+    let mut boot_code = [0u8; 420];
+    boot_code[..2].copy_from_slice(&[0xEB, 0xFE]); // boucle infinie : volume non amorçable
+    let bpb = BiosParameterBlock {
+        jmp_boot: [0xEB, 0x58, 0x90],
+        oem_name: *b"RUSTOS  ",
+        bytes_per_sector: BYTES_PER_SECTOR as u16,
+        sectors_per_cluster: spc,
+        reserved_sectors: RESERVED_SECTORS as u16,
+        num_fats: NUM_FATS,
+        root_entries: ROOT_ENTRIES,
+        total_sectors_16: TOTAL_SECTORS_16,
+        media_descriptor: MEDIA_DESCRIPTOR,
+        sectors_per_fat_16: 0,
+        sectors_per_track: SECTORS_PER_TRACK,
+        num_heads: NUM_HEADS,
+        hidden_sectors: start.min(u32::MAX as u64) as u32,
+        total_sectors_32: total,
+        sectors_per_fat_32: fat_sectors,
+        flags: 0,
+        fat_version: 0,
+        root_cluster: CLUSTER_ROOT,
+        fs_info_sector: 1,
+        backup_boot_sector: 6,
+        reserved: [0; 12],
+        drive_number: 0x80,
+        nt_flags: 0,
+        signature: 0x29,
+        volume_id: crate::time::now_ns() as u32,
+        volume_label,
+        fs_type: *b"FAT32   ",
+        boot_code,
+        boot_signature: 0xAA55,
+    };
+    let boot: [u8; 512] = unsafe { core::mem::transmute(bpb) };
+
+    let mut fs_info = [0u8; 512];
+    fs_info[0..4].copy_from_slice(&FSINFO_LEAD_SIG.to_le_bytes());
+    fs_info[484..488].copy_from_slice(&FSINFO_STRUCT_SIG.to_le_bytes());
+    // Le cluster 2 est pris par la racine
+    fs_info[FSINFO_FREE_COUNT..FSINFO_FREE_COUNT + 4].copy_from_slice(&(clusters - 1).to_le_bytes());
+    fs_info[FSINFO_NEXT_FREE..FSINFO_NEXT_FREE + 4].copy_from_slice(&(CLUSTER_ROOT + 1).to_le_bytes());
+    fs_info[508..512].copy_from_slice(&FSINFO_TRAIL_SIG.to_le_bytes());
+
+    // Clusters 0 et 1 réservés, 2 (racine) en fin de chaîne
+    let mut first_fat_sector = [0u8; 512];
+    first_fat_sector[0..4].copy_from_slice(&(0x0FFFFF00 | MEDIA_DESCRIPTOR as u32).to_le_bytes());
+    first_fat_sector[4..8].copy_from_slice(&0x0FFFFFFFu32.to_le_bytes());
+    first_fat_sector[8..12].copy_from_slice(&0x0FFFFFFFu32.to_le_bytes());
+
+    // Zone réservée, FAT et cluster racine remis à zéro par lots
+    let zeroes = vec![0u8; 64 * BYTES_PER_SECTOR as usize];
+    let zero_end = data_start as u64 + spc as u64;
+    let mut sector = 0u64;
+    while sector < zero_end {
+        crate::scheduler::cond_resched();
+        let count = (zero_end - sector).min(64) as usize;
+        disk.write(start + sector, &zeroes[..count * BYTES_PER_SECTOR as usize]).map_err(|_| FsError::IoError)?;
+        sector += count as u64;
+    }
+    for copy in [0u64, 6] {
+        disk.write(start + copy, &boot).map_err(|_| FsError::IoError)?;
+        disk.write(start + copy + 1, &fs_info).map_err(|_| FsError::IoError)?;
+    }
+    for fat in 0..NUM_FATS as u64 {
+        let fat_start = start + RESERVED_SECTORS as u64 + fat * fat_sectors as u64;
+        disk.write(fat_start, &first_fat_sector).map_err(|_| FsError::IoError)?;
+    }
+    Ok(FormatInfo { sectors_per_cluster: spc, sectors_per_fat: fat_sectors, clusters })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_fat_ownership_modes() {
+        let ownership = FatOwnership { uid: 1000, gid: 100, fmask: 0o133, dmask: 0o022 };
+        assert_eq!(ownership.mode_for(ATTR_ARCHIVE), 0o644);
+        assert_eq!(ownership.mode_for(ATTR_DIRECTORY), 0o755);
+        assert_eq!(ownership.mode_for(ATTR_ARCHIVE | ATTR_READ_ONLY), 0o444);
+    }
+
+    type Fs = FAT32<crate::drivers::disk::DiskDriver>;
+
+    #[test_case]
+    fn test_lfn_entries_and_short_names() {
+        assert_eq!(Fs::short_name_bytes("readme.txt"), Some(*b"README  TXT"));
+        assert_eq!(Fs::short_name_bytes("Long file name.text"), None);
+        assert_eq!(Fs::split_parent("/docs/a.txt"), ("/docs", "a.txt"));
+        assert_eq!(Fs::split_parent("/a.txt"), ("/", "a.txt"));
+
+        // Somme de contrôle de référence (spécification Microsoft)
+        let short = *b"LONGFI~1TXT";
+        let checksum = Fs::lfn_checksum(&short);
+        let mut sum = 0u8;
+        for &b in short.iter() {
+            sum = (if sum & 1 != 0 { 0x80u8 } else { 0 }).wrapping_add(sum >> 1).wrapping_add(b);
+        }
+        assert_eq!(checksum, sum);
+
+        let name = "Long file name.text";
+        let entries = Fs::build_lfn_entries(name, checksum).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].order, 2 | LFN_LAST);
+        assert_eq!(entries[1].order, 1);
+        assert!(entries.iter().all(|e| e.checksum == checksum && e.attr == ATTR_LONG_NAME));
+        assert_eq!(Fs::decode_lfn_entries(&entries), name);
+    }
+
+    /// Disque épars : seuls les secteurs non nuls sont conservés
+    struct SparseDisk {
+        sectors: alloc::collections::BTreeMap<u64, Vec<u8>>,
+    }
+
+    impl Disk for SparseDisk {
+        fn read(&self, sector: u64, buffer: &mut [u8]) -> Result<(), crate::drivers::disk::DiskError> {
+            for (i, chunk) in buffer.chunks_mut(512).enumerate() {
+                match self.sectors.get(&(sector + i as u64)) {
+                    Some(data) => chunk.copy_from_slice(data),
+                    None => chunk.fill(0),
+                }
+            }
+            Ok(())
+        }
+
+        fn write(&mut self, sector: u64, buffer: &[u8]) -> Result<(), crate::drivers::disk::DiskError> {
+            for (i, chunk) in buffer.chunks(512).enumerate() {
+                if chunk.iter().all(|&b| b == 0) {
+                    self.sectors.remove(&(sector + i as u64));
+                } else {
+                    self.sectors.insert(sector + i as u64, chunk.to_vec());
+                }
+            }
+            Ok(())
+        }
+    }
+
+    #[test_case]
+    fn test_format_layout() {
+        let mut disk = SparseDisk { sectors: alloc::collections::BTreeMap::new() };
+        assert_eq!(format(&mut disk, 2048, 20_000, "x"), Err(FsError::NoSpace));
+
+        // 64 Mio à partir du secteur 2048
+        let info = format(&mut disk, 2048, 131_072, "donnees").unwrap();
+        assert_eq!(info.sectors_per_cluster, 1);
+        assert!(info.clusters >= FAT32_MIN_CLUSTERS);
+        assert!(info.sectors_per_fat as u64 * 128 >= info.clusters as u64 + 2);
+
+        let mut boot = [0u8; 512];
+        disk.read(2048, &mut boot).unwrap();
+        assert_eq!(&boot[82..90], b"FAT32   ");
+        assert_eq!(&boot[71..82], b"DONNEES    ");
+        assert_eq!(u32::from_le_bytes(boot[28..32].try_into().unwrap()), 2048);
+        assert_eq!(u16::from_le_bytes(boot[510..512].try_into().unwrap()), 0xAA55);
+        let mut backup = [0u8; 512];
+        disk.read(2048 + 6, &mut backup).unwrap();
+        assert_eq!(boot, backup);
+
+        let mut fs_info = [0u8; 512];
+        disk.read(2049, &mut fs_info).unwrap();
+        assert_eq!(u32::from_le_bytes(fs_info[FSINFO_FREE_COUNT..FSINFO_FREE_COUNT + 4].try_into().unwrap()), info.clusters - 1);
+
+        // Les deux FAT : clusters 0 à 2 réservés, le reste libre
+        for fat in 0..2u64 {
+            let mut sector = [0u8; 512];
+            disk.read(2048 + 32 + fat * info.sectors_per_fat as u64, &mut sector).unwrap();
+            assert_eq!(u32::from_le_bytes(sector[8..12].try_into().unwrap()) & 0x0FFFFFFF, 0x0FFFFFFF);
+            assert!(sector[12..].iter().all(|&b| b == 0));
+        }
+    }
+}
@@ -0,0 +1,245 @@
+/// Injection de fautes (feature `fault-inject`)
+///
+/// Fait échouer au hasard les allocations de cadres, les allocations du
+/// tas et les lectures/écritures disque, pour exercer les chemins d'erreur
+/// des systèmes de fichiers et des pilotes. Chaque point d'injection a ses
+/// attributs, comme `fail_page_alloc` de Linux :
+/// - `probability` : pourcentage d'appels qui échouent (0 : inactif) ;
+/// - `interval` : seul un appel sur `interval` est candidat ;
+/// - `times` : nombre maximal d'échecs, -1 sans limite.
+///
+/// Configuration par `fault_inject=<point>:<probabilité>[:<intervalle>[:<fois>]],...`
+/// sur la ligne de commande, ou en écrivant la même syntaxe dans
+/// /proc/fault_inject (`frame:0` désactive un point). Sans la feature,
+/// `should_fail` vaut toujours `false` et disparaît à la compilation.
+///
+/// `should_fail` n'alloue ni ne verrouille : il est appelé depuis
+/// l'allocateur du tas et sous les verrous des files de disque.
+
+use alloc::format;
+use alloc::string::String;
+use core::fmt::Write;
+use core::sync::atomic::{AtomicI64, AtomicU32, AtomicU64, Ordering};
+
+/// Points d'injection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Point {
+    /// `BuddyFrameAllocator::allocate`
+    Frame = 0,
+    /// `HybridAllocator::alloc`
+    Heap = 1,
+    /// Lectures de la couche bloc
+    DiskRead = 2,
+    /// Écritures transmises par la couche bloc
+    DiskWrite = 3,
+}
+
+impl Point {
+    pub const ALL: [Point; 4] = [Point::Frame, Point::Heap, Point::DiskRead, Point::DiskWrite];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Point::Frame => "frame",
+            Point::Heap => "heap",
+            Point::DiskRead => "disk_read",
+            Point::DiskWrite => "disk_write",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|point| point.name() == name)
+    }
+}
+
+/// Attributs et compteurs d'un point d'injection
+pub struct Attr {
+    probability: AtomicU32,
+    interval: AtomicU64,
+    times: AtomicI64,
+    /// Appels depuis la dernière configuration
+    calls: AtomicU64,
+    /// Échecs injectés depuis la dernière configuration
+    failures: AtomicU64,
+}
+
+impl Attr {
+    pub const fn new() -> Self {
+        Self {
+            probability: AtomicU32::new(0),
+            interval: AtomicU64::new(1),
+            times: AtomicI64::new(-1),
+            calls: AtomicU64::new(0),
+            failures: AtomicU64::new(0),
+        }
+    }
+
+    /// Installe de nouveaux attributs, compteurs remis à zéro
+    pub fn set(&self, probability: u32, interval: u64, times: i64) {
+        self.probability.store(0, Ordering::SeqCst);
+        self.interval.store(interval.max(1), Ordering::SeqCst);
+        self.times.store(times.max(-1), Ordering::SeqCst);
+        self.calls.store(0, Ordering::SeqCst);
+        self.failures.store(0, Ordering::SeqCst);
+        self.probability.store(probability.min(100), Ordering::SeqCst);
+    }
+
+    /// L'appel en cours doit-il échouer ?
+    pub fn should_fail(&self) -> bool {
+        let probability = self.probability.load(Ordering::Relaxed);
+        if probability == 0 || self.times.load(Ordering::Relaxed) == 0 {
+            return false;
+        }
+        let calls = self.calls.fetch_add(1, Ordering::Relaxed) + 1;
+        if calls % self.interval.load(Ordering::Relaxed) != 0 {
+            return false;
+        }
+        if probability < 100 && next_random() % 100 >= probability as u64 {
+            return false;
+        }
+        // Un échec de moins à injecter, sauf sans limite (-1)
+        let consumed = self
+            .times
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |times| match times {
+                0 => None,
+                -1 => Some(-1),
+                n => Some(n - 1),
+            })
+            .is_ok();
+        if consumed {
+            self.failures.fetch_add(1, Ordering::Relaxed);
+        }
+        consumed
+    }
+
+    /// (probabilité, intervalle, fois restantes, appels, échecs)
+    pub fn snapshot(&self) -> (u32, u64, i64, u64, u64) {
+        (
+            self.probability.load(Ordering::Relaxed),
+            self.interval.load(Ordering::Relaxed),
+            self.times.load(Ordering::Relaxed),
+            self.calls.load(Ordering::Relaxed),
+            self.failures.load(Ordering::Relaxed),
+        )
+    }
+}
+
+static ATTRS: [Attr; 4] = [Attr::new(), Attr::new(), Attr::new(), Attr::new()];
+
+/// Générateur xorshift64 partagé (mis à jour sans verrou)
+static RANDOM: AtomicU64 = AtomicU64::new(0x9E37_79B9_7F4A_7C15);
+
+fn next_random() -> u64 {
+    let step = |mut x: u64| {
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        x
+    };
+    let previous = RANDOM
+        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |x| Some(step(x)))
+        .unwrap_or(1);
+    step(previous)
+}
+
+/// Point d'injection : vrai si l'opération doit échouer
+#[inline(always)]
+pub fn should_fail(point: Point) -> bool {
+    cfg!(feature = "fault-inject") && ATTRS[point as usize].should_fail()
+}
+
+/// Une entrée `point:probabilité[:intervalle[:fois]]`
+fn parse_entry(entry: &str) -> Result<(Point, u32, u64, i64), String> {
+    let mut fields = entry.split(':');
+    let name = fields.next().unwrap_or("");
+    let point = Point::parse(name).ok_or_else(|| format!("point d'injection inconnu: {}", name))?;
+    let probability = match fields.next().map(str::parse::<u32>) {
+        Some(Ok(p)) if p <= 100 => p,
+        _ => return Err(format!("{}: probabilité attendue (0-100)", name)),
+    };
+    let interval = match fields.next().map(str::parse::<u64>) {
+        None => 1,
+        Some(Ok(i)) if i > 0 => i,
+        _ => return Err(format!("{}: intervalle invalide", name)),
+    };
+    let times = match fields.next().map(str::parse::<i64>) {
+        None => -1,
+        Some(Ok(t)) if t >= -1 => t,
+        _ => return Err(format!("{}: nombre de fois invalide", name)),
+    };
+    if fields.next().is_some() {
+        return Err(format!("{}: trop de champs", name));
+    }
+    Ok((point, probability, interval, times))
+}
+
+/// Applique une configuration (entrées séparées par des virgules ou des
+/// blancs) ; rien n'est appliqué si une entrée est invalide
+pub fn configure(spec: &str) -> Result<(), String> {
+    if !cfg!(feature = "fault-inject") {
+        return Err(String::from("injection de fautes absente (feature fault-inject)"));
+    }
+    let mut entries = [None; 4];
+    for entry in spec.split(|c: char| c == ',' || c.is_whitespace()).filter(|e| !e.is_empty()) {
+        let (point, probability, interval, times) = parse_entry(entry)?;
+        entries[point as usize] = Some((probability, interval, times));
+    }
+    for (point, entry) in Point::ALL.into_iter().zip(entries) {
+        if let Some((probability, interval, times)) = entry {
+            ATTRS[point as usize].set(probability, interval, times);
+        }
+    }
+    // Graine différente à chaque configuration (jamais nulle pour xorshift)
+    let seed = crate::time::tsc::rdtsc() | 1;
+    RANDOM.store(seed, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Contenu de /proc/fault_inject
+pub fn report() -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "{:<11} {:>5} {:>9} {:>6} {:>10} {:>9}", "point", "prob", "interval", "times", "calls", "failures");
+    for point in Point::ALL {
+        let (probability, interval, times, calls, failures) = ATTRS[point as usize].snapshot();
+        let _ = writeln!(
+            out,
+            "{:<11} {:>5} {:>9} {:>6} {:>10} {:>9}",
+            point.name(),
+            probability,
+            interval,
+            times,
+            calls,
+            failures
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_fault_attributes() {
+        let attr = Attr::new();
+        assert!(!attr.should_fail());
+
+        // Toujours, un appel sur trois, deux fois au plus
+        attr.set(100, 3, 2);
+        let failed: alloc::vec::Vec<bool> = (0..9).map(|_| attr.should_fail()).collect();
+        assert_eq!(failed, [false, false, true, false, false, true, false, false, false]);
+        assert_eq!(attr.snapshot(), (100, 3, 0, 6, 2));
+
+        attr.set(0, 1, -1);
+        assert!(!attr.should_fail());
+    }
+
+    #[test_case]
+    fn test_parse_fault_spec() {
+        assert_eq!(parse_entry("frame:10"), Ok((Point::Frame, 10, 1, -1)));
+        assert_eq!(parse_entry("disk_read:100:4:1"), Ok((Point::DiskRead, 100, 4, 1)));
+        assert!(parse_entry("disk_read:101").is_err());
+        assert!(parse_entry("swap:10").is_err());
+        assert!(parse_entry("heap:5:0").is_err());
+        assert!(parse_entry("heap:5:1:1:1").is_err());
+    }
+}
@@ -202,6 +202,8 @@ impl BufferCache {
             writebacks: self.writebacks,
             evictions: self.evictions,
             dirty_blocks: self.entries.values().filter(|e| e.dirty).count(),
+            cached_bytes: self.entries.values().map(|e| e.data.len()).sum(),
+            dirty_bytes: self.entries.values().filter(|e| e.dirty).map(|e| e.data.len()).sum(),
         }
     }
 }
@@ -217,6 +219,9 @@ pub struct BufferCacheStats {
     pub writebacks: usize,
     pub evictions: usize,
     pub dirty_blocks: usize,
+    /// Octets de données en cache, dont modifiés
+    pub cached_bytes: usize,
+    pub dirty_bytes: usize,
 }
 
 /// Instance globale du buffer cache
@@ -0,0 +1,536 @@
+/// DevFS - Système de fichiers des périphériques
+///
+/// Monté sur /dev. Chaque nœud est associé à un objet `DeviceOps` enregistré
+/// dans `DEVICE_NODES` : les lectures/écritures sur le fichier sont routées
+/// vers le périphérique. Les nœuds apparaissent et disparaissent dès
+/// l'enregistrement ou le retrait du périphérique, sans remontage.
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use spin::Mutex;
+use lazy_static::lazy_static;
+
+use crate::fs::vfs_core::*;
+use crate::drivers::disk::{Disk, DiskError};
+use crate::process::cred::{Credentials, CAP_SYS_ADMIN};
+
+/// Inode de la racine de /dev
+const DEVFS_ROOT_INODE: InodeId = 1;
+
+/// Nature d'un nœud de périphérique
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceKind {
+    Char,
+    Block,
+}
+
+impl DeviceKind {
+    fn file_type(self) -> FileType {
+        match self {
+            DeviceKind::Char => FileType::CharDevice,
+            DeviceKind::Block => FileType::BlockDevice,
+        }
+    }
+
+    /// Mode du nœud, propriété de root : les flux (null, zero, random,
+    /// tty) sont ouverts à tous, un disque brut à root seul
+    fn mode(self) -> u16 {
+        match self {
+            DeviceKind::Char => 0o666,
+            DeviceKind::Block => 0o600,
+        }
+    }
+}
+
+/// Opérations fichier spécifiques à un périphérique
+pub trait DeviceOps: Send + Sync {
+    /// Lit à partir de `offset` (ignoré par les périphériques caractère)
+    fn read(&mut self, offset: u64, buf: &mut [u8]) -> VfsResult<usize>;
+
+    /// Écrit à partir de `offset` (ignoré par les périphériques caractère)
+    fn write(&mut self, offset: u64, buf: &[u8]) -> VfsResult<usize>;
+
+    /// Taille exposée par stat (0 pour un flux)
+    fn size(&self) -> u64 {
+        0
+    }
+
+    /// Disponibilité pour poll (toujours prêt par défaut)
+    fn poll(&self) -> i16 {
+        super::poll::POLLIN | super::poll::POLLOUT
+    }
+}
+
+/// Nœud enregistré dans /dev
+#[derive(Clone)]
+struct DeviceNode {
+    inode: InodeId,
+    kind: DeviceKind,
+    ops: Arc<Mutex<dyn DeviceOps>>,
+}
+
+/// Table des nœuds de périphériques
+pub struct DeviceNodeTable {
+    nodes: BTreeMap<String, DeviceNode>,
+    next_inode: InodeId,
+}
+
+impl DeviceNodeTable {
+    fn new() -> Self {
+        Self {
+            nodes: BTreeMap::new(),
+            next_inode: DEVFS_ROOT_INODE + 1,
+        }
+    }
+
+    fn by_inode(&self, inode: InodeId) -> Option<&DeviceNode> {
+        self.nodes.values().find(|n| n.inode == inode)
+    }
+}
+
+lazy_static! {
+    /// Nœuds de périphériques visibles dans /dev
+    static ref DEVICE_NODES: Mutex<DeviceNodeTable> = Mutex::new(DeviceNodeTable::new());
+}
+
+/// Crée le nœud /dev/<name> pour un périphérique
+pub fn register_device(name: &str, kind: DeviceKind, ops: Arc<Mutex<dyn DeviceOps>>) -> VfsResult<InodeId> {
+    if name.is_empty() || name.contains('/') {
+        return Err(VfsError::InvalidArgument);
+    }
+
+    let mut table = DEVICE_NODES.lock();
+    if table.nodes.contains_key(name) {
+        return Err(VfsError::AlreadyExists);
+    }
+
+    let inode = table.next_inode;
+    table.next_inode += 1;
+    table.nodes.insert(name.into(), DeviceNode { inode, kind, ops });
+    Ok(inode)
+}
+
+/// Retire le nœud /dev/<name>
+pub fn unregister_device(name: &str) -> VfsResult<()> {
+    DEVICE_NODES.lock().nodes.remove(name).map(|_| ()).ok_or(VfsError::NotFound)
+}
+
+/// Liste les nœuds enregistrés
+pub fn list_devices() -> Vec<(String, DeviceKind)> {
+    DEVICE_NODES.lock().nodes.iter().map(|(name, n)| (name.clone(), n.kind)).collect()
+}
+
+/// Enregistre les périphériques de base : null, zero, random, urandom, tty0
+pub fn register_default_devices() -> VfsResult<()> {
+    register_device("null", DeviceKind::Char, Arc::new(Mutex::new(NullDevice)))?;
+    register_device("zero", DeviceKind::Char, Arc::new(Mutex::new(ZeroDevice)))?;
+    register_device("random", DeviceKind::Char, Arc::new(Mutex::new(RandomDevice::new(true))))?;
+    register_device("urandom", DeviceKind::Char, Arc::new(Mutex::new(RandomDevice::new(false))))?;
+    register_device("tty0", DeviceKind::Char, Arc::new(Mutex::new(TtyDevice)))?;
+    Ok(())
+}
+
+// ============ PÉRIPHÉRIQUES DE BASE ============
+
+/// /dev/null : absorbe les écritures, lecture toujours vide
+pub struct NullDevice;
+
+impl DeviceOps for NullDevice {
+    fn read(&mut self, _offset: u64, _buf: &mut [u8]) -> VfsResult<usize> {
+        Ok(0)
+    }
+
+    fn write(&mut self, _offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        Ok(buf.len())
+    }
+}
+
+/// /dev/zero : flux infini d'octets nuls
+pub struct ZeroDevice;
+
+impl DeviceOps for ZeroDevice {
+    fn read(&mut self, _offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        buf.fill(0);
+        Ok(buf.len())
+    }
+
+    fn write(&mut self, _offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        Ok(buf.len())
+    }
+}
+
+/// /dev/random et /dev/urandom : générateur du noyau (`random`). Une
+/// lecture de /dev/random attend qu'il ait reçu assez d'entropie ; les
+/// écritures sont mélangées à la réserve sans être créditées.
+pub struct RandomDevice {
+    blocking: bool,
+}
+
+impl RandomDevice {
+    pub fn new(blocking: bool) -> Self {
+        Self { blocking }
+    }
+}
+
+impl DeviceOps for RandomDevice {
+    fn read(&mut self, _offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        if self.blocking {
+            crate::random::wait_ready();
+        }
+        crate::random::fill_bytes(buf);
+        Ok(buf.len())
+    }
+
+    fn write(&mut self, _offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        crate::random::add_device_randomness(buf);
+        Ok(buf.len())
+    }
+}
+
+/// /dev/tty0 : console VGA
+pub struct TtyDevice;
+
+impl DeviceOps for TtyDevice {
+    fn read(&mut self, _offset: u64, _buf: &mut [u8]) -> VfsResult<usize> {
+        // Le clavier écrit directement sur la console, pas de tampon d'entrée
+        Ok(0)
+    }
+
+    fn write(&mut self, _offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        let mut writer = crate::vga_buffer::WRITER.lock();
+        for &b in buf {
+            writer.write_byte(b);
+        }
+        Ok(buf.len())
+    }
+}
+
+/// Périphérique bloc adossé à un disque (/dev/sda...)
+///
+/// Les accès non alignés sont découpés secteur par secteur.
+pub struct DiskDevice<D: Disk + Send + Sync> {
+    disk: D,
+    sector_size: usize,
+    sectors: u64,
+}
+
+impl<D: Disk + Send + Sync> DiskDevice<D> {
+    pub fn new(disk: D, sector_size: usize, sectors: u64) -> Self {
+        Self { disk, sector_size, sectors }
+    }
+}
+
+impl From<DiskError> for VfsError {
+    fn from(err: DiskError) -> Self {
+        match err {
+            DiskError::InvalidSector | DiskError::InvalidSize | DiskError::BufferTooSmall => VfsError::InvalidArgument,
+            _ => VfsError::IoError,
+        }
+    }
+}
+
+impl<D: Disk + Send + Sync> DeviceOps for DiskDevice<D> {
+    fn read(&mut self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        let size = self.size();
+        if offset >= size {
+            return Ok(0);
+        }
+        let len = core::cmp::min(buf.len() as u64, size - offset) as usize;
+        let mut sector = alloc::vec![0u8; self.sector_size];
+        let mut done = 0;
+
+        while done < len {
+            let pos = offset + done as u64;
+            let lba = pos / self.sector_size as u64;
+            let in_sector = (pos % self.sector_size as u64) as usize;
+            let chunk = core::cmp::min(self.sector_size - in_sector, len - done);
+
+            self.disk.read(lba, &mut sector)?;
+            buf[done..done + chunk].copy_from_slice(&sector[in_sector..in_sector + chunk]);
+            done += chunk;
+        }
+        Ok(done)
+    }
+
+    fn write(&mut self, offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        let size = self.size();
+        if offset >= size {
+            return Err(VfsError::NoSpace);
+        }
+        let len = core::cmp::min(buf.len() as u64, size - offset) as usize;
+        let mut sector = alloc::vec![0u8; self.sector_size];
+        let mut done = 0;
+
+        while done < len {
+            let pos = offset + done as u64;
+            let lba = pos / self.sector_size as u64;
+            let in_sector = (pos % self.sector_size as u64) as usize;
+            let chunk = core::cmp::min(self.sector_size - in_sector, len - done);
+
+            // Secteur partiel : lecture-modification-écriture
+            if chunk != self.sector_size {
+                self.disk.read(lba, &mut sector)?;
+            }
+            sector[in_sector..in_sector + chunk].copy_from_slice(&buf[done..done + chunk]);
+            self.disk.write(lba, &sector)?;
+            done += chunk;
+        }
+        Ok(done)
+    }
+
+    fn size(&self) -> u64 {
+        self.sectors * self.sector_size as u64
+    }
+}
+
+// ============ SYSTÈME DE FICHIERS ============
+
+/// Superblock de devfs
+pub struct DevSuperblock {
+    fs_id: FsId,
+}
+
+impl Superblock for DevSuperblock {
+    fn fs_name(&self) -> &str {
+        "devfs"
+    }
+
+    fn fs_id(&self) -> FsId {
+        self.fs_id
+    }
+
+    fn block_size(&self) -> u32 {
+        4096
+    }
+
+    fn total_blocks(&self) -> u64 {
+        0
+    }
+
+    fn free_blocks(&self) -> u64 {
+        0
+    }
+
+    fn total_inodes(&self) -> u64 {
+        DEVICE_NODES.lock().nodes.len() as u64 + 1
+    }
+
+    fn free_inodes(&self) -> u64 {
+        0
+    }
+
+    fn is_readonly(&self) -> bool {
+        false
+    }
+
+    fn root_inode(&self) -> InodeId {
+        DEVFS_ROOT_INODE
+    }
+}
+
+/// Système de fichiers /dev
+pub struct DevFileSystem {
+    sb: Arc<DevSuperblock>,
+}
+
+impl DevFileSystem {
+    pub fn new() -> Self {
+        Self {
+            sb: Arc::new(DevSuperblock { fs_id: alloc_fs_id() }),
+        }
+    }
+}
+
+impl FileSystemOps for DevFileSystem {
+    fn superblock(&self) -> Arc<dyn Superblock> {
+        self.sb.clone()
+    }
+
+    fn get_inode(&self, inode_id: InodeId) -> VfsResult<Arc<Mutex<dyn InodeOps>>> {
+        if inode_id != DEVFS_ROOT_INODE && DEVICE_NODES.lock().by_inode(inode_id).is_none() {
+            return Err(VfsError::NotFound);
+        }
+        Ok(Arc::new(Mutex::new(DevInode { inode: inode_id })))
+    }
+
+    fn sync(&self) -> VfsResult<()> { Ok(()) }
+    fn unmount(&self) -> VfsResult<()> { Ok(()) }
+}
+
+/// Retirer un nœud de /dev est une opération d'administration
+fn may_remove_node(cred: &Credentials) -> bool {
+    cred.is_root() || cred.capable(CAP_SYS_ADMIN)
+}
+
+/// Inode devfs : racine ou nœud de périphérique
+struct DevInode {
+    inode: InodeId,
+}
+
+impl DevInode {
+    fn is_root(&self) -> bool {
+        self.inode == DEVFS_ROOT_INODE
+    }
+
+    /// Opérations du périphérique, sans garder la table verrouillée pendant l'E/S
+    fn device(&self) -> VfsResult<DeviceNode> {
+        if self.is_root() {
+            return Err(VfsError::IsDirectory);
+        }
+        DEVICE_NODES.lock().by_inode(self.inode).cloned().ok_or(VfsError::NotFound)
+    }
+}
+
+impl InodeOps for DevInode {
+    fn read(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        let node = self.device()?;
+        let result = node.ops.lock().read(offset, buf);
+        result
+    }
+
+    fn write(&mut self, offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        let node = self.device()?;
+        let result = node.ops.lock().write(offset, buf);
+        result
+    }
+
+    fn poll(&self) -> i16 {
+        match self.device() {
+            Ok(node) => {
+                let events = node.ops.lock().poll();
+                events
+            }
+            Err(_) => super::poll::POLLERR,
+        }
+    }
+
+    fn stat(&self) -> VfsResult<FileStat> {
+        if self.is_root() {
+            let mut stat = FileStat::new(self.inode, FileType::Directory);
+            stat.mode = FileMode::new(0o755);
+            return Ok(stat);
+        }
+
+        let node = self.device()?;
+        let mut stat = FileStat::new(self.inode, node.kind.file_type());
+        stat.mode = FileMode::new(node.kind.mode());
+        stat.size = node.ops.lock().size();
+        Ok(stat)
+    }
+
+    fn lookup(&self, name: &str) -> VfsResult<InodeId> {
+        if !self.is_root() {
+            return Err(VfsError::NotDirectory);
+        }
+        if name == "." {
+            return Ok(self.inode);
+        }
+        DEVICE_NODES.lock().nodes.get(name).map(|n| n.inode).ok_or(VfsError::NotFound)
+    }
+
+    fn create(&mut self, _name: &str, _mode: FileMode, _file_type: FileType) -> VfsResult<InodeId> {
+        // Les nœuds sont créés par l'enregistrement des périphériques
+        Err(VfsError::NotSupported)
+    }
+
+    fn unlink(&mut self, name: &str) -> VfsResult<()> {
+        if !self.is_root() {
+            return Err(VfsError::NotDirectory);
+        }
+        if !may_remove_node(&crate::process::cred::current()) {
+            return Err(VfsError::PermissionDenied);
+        }
+        unregister_device(name)
+    }
+
+    fn mkdir(&mut self, _name: &str, _mode: FileMode) -> VfsResult<InodeId> {
+        Err(VfsError::NotSupported)
+    }
+
+    fn rmdir(&mut self, _name: &str) -> VfsResult<()> {
+        Err(VfsError::NotSupported)
+    }
+
+    fn readdir(&self) -> VfsResult<Vec<DirEntry>> {
+        if !self.is_root() {
+            return Err(VfsError::NotDirectory);
+        }
+
+        let mut entries = Vec::new();
+        entries.push(DirEntry::new(self.inode, ".".into(), FileType::Directory));
+        for (name, node) in DEVICE_NODES.lock().nodes.iter() {
+            entries.push(DirEntry::new(node.inode, name.clone(), node.kind.file_type()));
+        }
+        Ok(entries)
+    }
+
+    fn truncate(&mut self, _size: u64) -> VfsResult<()> {
+        // Sans effet sur un périphérique (permet `vfs_write_file` sur /dev/tty0)
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_zero_and_null_devices() {
+        let mut buf = [0xFFu8; 16];
+        assert_eq!(ZeroDevice.read(0, &mut buf), Ok(16));
+        assert!(buf.iter().all(|&b| b == 0));
+        assert_eq!(NullDevice.read(0, &mut buf), Ok(0));
+        assert_eq!(NullDevice.write(0, b"abc"), Ok(3));
+    }
+
+    #[test_case]
+    fn test_random_devices() {
+        let (mut a, mut b) = ([0u8; 32], [0u8; 32]);
+        let mut urandom = RandomDevice::new(false);
+        assert_eq!(urandom.read(0, &mut a), Ok(32));
+        assert_eq!(urandom.read(0, &mut b), Ok(32));
+        assert_ne!(a, b);
+        assert_eq!(urandom.write(0, b"graine"), Ok(6));
+    }
+
+    #[test_case]
+    fn test_register_and_unregister_node() {
+        let fs = DevFileSystem::new();
+        register_device("testdev0", DeviceKind::Char, Arc::new(Mutex::new(ZeroDevice))).unwrap();
+        assert_eq!(
+            register_device("testdev0", DeviceKind::Char, Arc::new(Mutex::new(NullDevice))).err(),
+            Some(VfsError::AlreadyExists)
+        );
+
+        let root = fs.get_inode(DEVFS_ROOT_INODE).unwrap();
+        let id = root.lock().lookup("testdev0").unwrap();
+        let node = fs.get_inode(id).unwrap();
+        assert_eq!(node.lock().stat().unwrap().file_type, FileType::CharDevice);
+        assert_eq!(node.lock().stat().unwrap().mode.0, 0o666);
+
+        unregister_device("testdev0").unwrap();
+        assert_eq!(root.lock().lookup("testdev0"), Err(VfsError::NotFound));
+        assert_eq!(node.lock().read(0, &mut [0u8; 4]), Err(VfsError::NotFound));
+    }
+
+    #[test_case]
+    fn test_block_node_mode_and_removal() {
+        let fs = DevFileSystem::new();
+        let id = register_device("testblk0", DeviceKind::Block, Arc::new(Mutex::new(ZeroDevice))).unwrap();
+        let stat = fs.get_inode(id).unwrap().lock().stat().unwrap();
+        assert_eq!((stat.mode.0, stat.uid, stat.gid), (0o600, 0, 0));
+
+        // Retrait réservé à root ou CAP_SYS_ADMIN
+        let mut admin = Credentials::new(1000, 1000);
+        assert!(!may_remove_node(&admin));
+        admin.caps = CAP_SYS_ADMIN;
+        assert!(may_remove_node(&admin) && may_remove_node(&Credentials::root()));
+
+        // Le noyau de test tourne hors processus, donc en root
+        let root = fs.get_inode(DEVFS_ROOT_INODE).unwrap();
+        root.lock().unlink("testblk0").unwrap();
+        assert_eq!(root.lock().lookup("testblk0"), Err(VfsError::NotFound));
+    }
+}
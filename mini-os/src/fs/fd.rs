@@ -1,7 +1,13 @@
 use alloc::vec::Vec;
 use alloc::string::String;
+use alloc::sync::Arc;
+use core::fmt;
 use spin::Mutex;
 use lazy_static::lazy_static;
+use super::vfs_core::InodeOps;
+
+/// Erreur d'ouverture au-delà de RLIMIT_NOFILE (EMFILE)
+pub const TOO_MANY_FILES: &str = "Trop de fichiers ouverts";
 
 /// Modes d'ouverture de fichier
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -14,6 +20,17 @@ pub enum OpenMode {
     ReadWrite,
 }
 
+/// Inode d'un fichier ouvert : ses données survivent à la suppression du
+/// dernier lien tant qu'un descripteur le référence
+#[derive(Clone)]
+pub struct OpenInode(pub Arc<Mutex<dyn InodeOps>>);
+
+impl fmt::Debug for OpenInode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "OpenInode")
+    }
+}
+
 /// Descripteur de fichier
 #[derive(Debug, Clone)]
 pub struct FileDescriptor {
@@ -27,6 +44,12 @@ pub struct FileDescriptor {
     pub offset: u64,
     /// Taille du fichier
     pub size: u64,
+    /// Pipe servant la FIFO ouverte (ipc::pipe)
+    pub fifo: Option<u32>,
+    /// O_NONBLOCK
+    pub nonblock: bool,
+    /// Inode ouvert (None : résolu par le chemin à chaque accès)
+    pub inode: Option<OpenInode>,
 }
 
 impl FileDescriptor {
@@ -38,6 +61,9 @@ impl FileDescriptor {
             mode,
             offset: 0,
             size,
+            fifo: None,
+            nonblock: false,
+            inode: None,
         }
     }
 }
@@ -48,6 +74,8 @@ pub struct FileDescriptorTable {
     descriptors: Vec<Option<FileDescriptor>>,
     /// Prochain FD disponible
     next_fd: usize,
+    /// Nombre maximal de descripteurs ouverts (RLIMIT_NOFILE)
+    max_open: u64,
 }
 
 impl FileDescriptorTable {
@@ -56,11 +84,35 @@ impl FileDescriptorTable {
         Self {
             descriptors: Vec::new(),
             next_fd: 3, // 0, 1, 2 sont réservés pour stdin, stdout, stderr
+            max_open: crate::process::rlimit::ResourceLimits::new()
+                .get(crate::process::rlimit::Resource::NoFile)
+                .cur,
+        }
+    }
+
+    /// Change le nombre maximal de descripteurs ouverts ; les
+    /// descripteurs déjà ouverts au-delà restent valides
+    pub fn set_max_open(&mut self, max_open: u64) {
+        self.max_open = max_open;
+    }
+
+    /// Nombre de descripteurs ouverts
+    pub fn open_count(&self) -> usize {
+        self.descriptors.iter().filter(|fd| fd.is_some()).count()
+    }
+
+    /// Erreur si un descripteur de plus dépasserait la limite
+    fn check_limit(&self) -> Result<(), &'static str> {
+        if self.open_count() as u64 >= self.max_open {
+            Err(TOO_MANY_FILES)
+        } else {
+            Ok(())
         }
     }
 
     /// Ouvre un fichier et retourne son descripteur
     pub fn open(&mut self, path: &str, mode: OpenMode, size: u64) -> Result<usize, &'static str> {
+        self.check_limit()?;
         let fd = self.next_fd;
         self.next_fd += 1;
 
@@ -103,13 +155,27 @@ impl FileDescriptorTable {
         }
     }
 
+    /// Duplique un descripteur sur le premier numéro libre (dup)
+    pub fn dup(&mut self, old_fd: usize) -> Result<usize, &'static str> {
+        self.check_limit()?;
+        let new_fd = self.next_fd;
+        self.dup2(old_fd, new_fd)?;
+        self.next_fd += 1;
+        Ok(new_fd)
+    }
+
     /// Duplique un descripteur de fichier (dup2)
     pub fn dup2(&mut self, old_fd: usize, new_fd: usize) -> Result<usize, &'static str> {
         let descriptor = self.get(old_fd)?.clone();
+        if new_fd == old_fd {
+            return Ok(new_fd);
+        }
         
         // Fermer le nouveau FD s'il est déjà ouvert
         if new_fd < self.descriptors.len() && self.descriptors[new_fd].is_some() {
             self.close(new_fd)?;
+        } else {
+            self.check_limit()?;
         }
 
         // Étendre le vecteur si nécessaire
@@ -117,6 +183,10 @@ impl FileDescriptorTable {
             self.descriptors.push(None);
         }
 
+        // Une FIFO compte ses ouvertures par descripteur
+        if let Some(id) = descriptor.fifo {
+            crate::ipc::PIPE_MANAGER.lock().reopen(id, descriptor.mode != OpenMode::WriteOnly, descriptor.mode != OpenMode::ReadOnly);
+        }
         self.descriptors[new_fd] = Some(descriptor);
         Ok(new_fd)
     }
@@ -160,6 +230,14 @@ impl FileDescriptorManager {
             .ok_or("Table non trouvée")
     }
 
+    /// Applique RLIMIT_NOFILE à la table d'un processus (sans effet si
+    /// le processus n'a pas de table)
+    pub fn set_limit(&mut self, pid: u64, max_open: u64) {
+        if let Ok(table) = self.get_table(pid) {
+            table.set_max_open(max_open);
+        }
+    }
+
     /// Supprime la table d'un processus
     pub fn remove_table(&mut self, pid: u64) -> Result<(), &'static str> {
         if let Some(pos) = self.tables.iter().position(|(p, _)| *p == pid) {
@@ -198,4 +276,31 @@ mod tests {
         let fd = table.open("/test.txt", OpenMode::ReadOnly, 1024).unwrap();
         assert!(table.close(fd).is_ok());
     }
+
+    #[test_case]
+    fn test_fd_dup_and_dup2() {
+        let mut table = FileDescriptorTable::new();
+        let fd = table.open("/out.txt", OpenMode::WriteOnly, 0).unwrap();
+        // Rediriger stdout puis le restaurer
+        assert!(table.get(1).is_err());
+        table.dup2(fd, 1).unwrap();
+        assert_eq!(table.get(1).unwrap().path, "/out.txt");
+        let saved = table.dup(1).unwrap();
+        assert!(saved > fd);
+        table.close(1).unwrap();
+        assert!(table.get(1).is_err());
+        assert_eq!(table.get(saved).unwrap().path, "/out.txt");
+    }
+
+    #[test_case]
+    fn test_fd_limit() {
+        let mut table = FileDescriptorTable::new();
+        table.set_max_open(2);
+        let fd = table.open("/a.txt", OpenMode::ReadOnly, 0).unwrap();
+        table.dup(fd).unwrap();
+        assert_eq!(table.open("/b.txt", OpenMode::ReadOnly, 0), Err(TOO_MANY_FILES));
+        assert_eq!(table.dup(fd), Err(TOO_MANY_FILES));
+        table.close(fd).unwrap();
+        assert!(table.open("/b.txt", OpenMode::ReadOnly, 0).is_ok());
+    }
 }
@@ -0,0 +1,256 @@
+/// Initramfs : archive cpio (format newc) dépaquetée dans le ramfs racine
+///
+/// Le chargeur fournit l'archive comme module Multiboot2 ; `load_boot_modules`
+/// la dépaquette juste après `init_vfs`, avant tout pilote de disque, pour
+/// que /bin/init, /bin/sh et les programmes de test soient disponibles dès
+/// le démarrage. Répertoires, fichiers réguliers et liens symboliques sont
+/// recréés avec leur mode et leur propriétaire ; les autres types d'entrée
+/// (périphériques, FIFO) sont ignorés, /dev étant fourni par devfs.
+///
+/// Une fois dépaquetée, la mémoire du module retourne à l'allocateur de
+/// cadres.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use crate::fs::vfs_core::{FileMode, FileType, VfsError, VfsResult};
+
+/// Signature d'un en-tête newc (sans somme de contrôle)
+const NEWC_MAGIC: &[u8; 6] = b"070701";
+/// Variante avec somme de contrôle, acceptée sans vérification
+const NEWC_CRC_MAGIC: &[u8; 6] = b"070702";
+/// En-tête : signature puis 13 champs hexadécimaux de 8 caractères
+const HEADER_SIZE: usize = 110;
+/// Nom de l'entrée marquant la fin de l'archive
+const TRAILER: &str = "TRAILER!!!";
+
+const S_IFMT: u32 = 0o170000;
+const S_IFDIR: u32 = 0o040000;
+const S_IFREG: u32 = 0o100000;
+const S_IFLNK: u32 = 0o120000;
+
+/// Entrée d'une archive cpio
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CpioEntry<'a> {
+    /// Chemin relatif à la racine, sans `./` ni `/` initial
+    pub name: String,
+    pub mode: u32,
+    pub uid: u32,
+    pub gid: u32,
+    pub data: &'a [u8],
+}
+
+impl<'a> CpioEntry<'a> {
+    pub fn is_dir(&self) -> bool {
+        self.mode & S_IFMT == S_IFDIR
+    }
+
+    pub fn is_file(&self) -> bool {
+        self.mode & S_IFMT == S_IFREG
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        self.mode & S_IFMT == S_IFLNK
+    }
+
+    /// Bits de permission (dont setuid/setgid/sticky)
+    pub fn permissions(&self) -> u16 {
+        (self.mode & 0o7777) as u16
+    }
+}
+
+fn align4(offset: usize) -> usize {
+    (offset + 3) & !3
+}
+
+/// Champ hexadécimal n° `index` de l'en-tête
+fn field(header: &[u8], index: usize) -> VfsResult<u32> {
+    let start = 6 + index * 8;
+    let text = core::str::from_utf8(&header[start..start + 8]).map_err(|_| VfsError::InvalidArgument)?;
+    u32::from_str_radix(text, 16).map_err(|_| VfsError::InvalidArgument)
+}
+
+/// Découpe une archive newc en entrées ; s'arrête à `TRAILER!!!`
+pub fn parse(archive: &[u8]) -> VfsResult<Vec<CpioEntry<'_>>> {
+    let mut entries = Vec::new();
+    let mut offset = 0;
+    loop {
+        // Des archives concaténées peuvent être séparées par du bourrage nul
+        while offset < archive.len() && archive[offset] == 0 {
+            offset += 1;
+        }
+        if offset + HEADER_SIZE > archive.len() {
+            return Err(VfsError::InvalidArgument);
+        }
+        let header = &archive[offset..offset + HEADER_SIZE];
+        if &header[..6] != NEWC_MAGIC && &header[..6] != NEWC_CRC_MAGIC {
+            return Err(VfsError::InvalidArgument);
+        }
+        let mode = field(header, 1)?;
+        let uid = field(header, 2)?;
+        let gid = field(header, 3)?;
+        let file_size = field(header, 6)? as usize;
+        let name_size = field(header, 11)? as usize;
+
+        let name_start = offset + HEADER_SIZE;
+        let data_start = align4(name_start + name_size);
+        let data_end = data_start + file_size;
+        if name_size == 0 || data_end > archive.len() {
+            return Err(VfsError::InvalidArgument);
+        }
+        // Le nom inclut son NUL final
+        let raw_name = &archive[name_start..name_start + name_size - 1];
+        let name = core::str::from_utf8(raw_name).map_err(|_| VfsError::InvalidArgument)?;
+        if name == TRAILER {
+            return Ok(entries);
+        }
+
+        let name = name.trim_start_matches("./").trim_start_matches('/');
+        if !name.is_empty() && name != "." {
+            entries.push(CpioEntry {
+                name: name.to_string(),
+                mode,
+                uid,
+                gid,
+                data: &archive[data_start..data_end],
+            });
+        }
+        offset = align4(data_end);
+    }
+}
+
+/// Bilan d'un dépaquetage
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UnpackStats {
+    pub directories: usize,
+    pub files: usize,
+    pub symlinks: usize,
+    pub bytes: usize,
+}
+
+/// Crée `path` et ses parents manquants
+fn mkdir_all(path: &str) -> VfsResult<()> {
+    let mut current = String::new();
+    for component in path.split('/').filter(|c| !c.is_empty()) {
+        current.push('/');
+        current.push_str(component);
+        match super::vfs_mkdir(&current) {
+            Ok(()) | Err(VfsError::AlreadyExists) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// Mode et propriétaire de l'archive ; ignorés si le système de fichiers
+/// ne les conserve pas
+fn apply_attributes(path: &str, entry: &CpioEntry) -> VfsResult<()> {
+    match super::vfs_setattr(path, Some(FileMode::new(entry.permissions())), Some(entry.uid), Some(entry.gid)) {
+        Ok(()) | Err(VfsError::NotSupported) => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Dépaquette `archive` sous la racine du VFS (les fichiers existants
+/// sont écrasés)
+pub fn unpack(archive: &[u8]) -> VfsResult<UnpackStats> {
+    let mut stats = UnpackStats::default();
+    for entry in parse(archive)? {
+        let path = alloc::format!("/{}", entry.name);
+        if let Some(slash) = path.rfind('/') {
+            mkdir_all(&path[..slash])?;
+        }
+
+        if entry.is_dir() {
+            mkdir_all(&path)?;
+            apply_attributes(&path, &entry)?;
+            stats.directories += 1;
+        } else if entry.is_file() {
+            match super::vfs_create(&path, FileMode::new(entry.permissions()), FileType::Regular) {
+                Ok(_) | Err(VfsError::AlreadyExists) => {}
+                Err(e) => return Err(e),
+            }
+            super::vfs_write_file(&path, entry.data)?;
+            apply_attributes(&path, &entry)?;
+            stats.files += 1;
+            stats.bytes += entry.data.len();
+        } else if entry.is_symlink() {
+            let target = core::str::from_utf8(entry.data).map_err(|_| VfsError::InvalidArgument)?;
+            let mut links = super::SYMLINK_MANAGER.lock();
+            let _ = links.remove_link(&path);
+            links
+                .create_symlink(path, target.to_string(), entry.uid, entry.gid)
+                .map_err(|_| VfsError::InvalidArgument)?;
+            stats.symlinks += 1;
+        }
+    }
+    Ok(stats)
+}
+
+/// Dépaquette les modules Multiboot2 qui sont des archives cpio, puis
+/// rend leur mémoire à l'allocateur de cadres
+pub fn load_boot_modules() -> VfsResult<UnpackStats> {
+    let mut total = UnpackStats::default();
+    for module in crate::multiboot2::modules() {
+        let data = module.data();
+        if data.len() < 6 || (&data[..6] != NEWC_MAGIC && &data[..6] != NEWC_CRC_MAGIC) {
+            continue;
+        }
+        let stats = unpack(data)?;
+        log::info!(
+            "initramfs: {} ({} fichiers, {} répertoires, {} liens, {} octets)",
+            module.cmdline, stats.files, stats.directories, stats.symlinks, stats.bytes
+        );
+        total.files += stats.files;
+        total.directories += stats.directories;
+        total.symlinks += stats.symlinks;
+        total.bytes += stats.bytes;
+
+        // Le noyau et ses structures de démarrage restent réservés
+        let start = module.start.max(crate::memory::frame::KERNEL_RESERVED_END);
+        if start < module.end {
+            crate::memory::FRAME_ALLOCATOR.lock().add_region(start, module.end);
+        }
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn push_entry(archive: &mut Vec<u8>, name: &str, mode: u32, data: &[u8]) {
+        let fields = [1, mode, 0, 0, 1, 0, data.len() as u32, 0, 0, 0, 0, name.len() as u32 + 1, 0];
+        archive.extend_from_slice(NEWC_MAGIC);
+        for value in fields {
+            archive.extend_from_slice(alloc::format!("{:08X}", value).as_bytes());
+        }
+        archive.extend_from_slice(name.as_bytes());
+        archive.push(0);
+        archive.resize(align4(archive.len()), 0);
+        archive.extend_from_slice(data);
+        archive.resize(align4(archive.len()), 0);
+    }
+
+    #[test_case]
+    fn test_parse_newc_archive() {
+        let mut archive = Vec::new();
+        push_entry(&mut archive, ".", S_IFDIR | 0o755, b"");
+        push_entry(&mut archive, "./bin", S_IFDIR | 0o755, b"");
+        push_entry(&mut archive, "./bin/init", S_IFREG | 0o755, b"\x7fELF...");
+        push_entry(&mut archive, "./bin/sh", S_IFLNK | 0o777, b"init");
+        push_entry(&mut archive, TRAILER, 0, b"");
+
+        let entries = parse(&archive).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert!(entries[0].is_dir());
+        assert_eq!(entries[1].name, "bin/init");
+        assert!(entries[1].is_file());
+        assert_eq!(entries[1].permissions(), 0o755);
+        assert_eq!(entries[1].data, b"\x7fELF...");
+        assert!(entries[2].is_symlink());
+        assert_eq!(entries[2].data, b"init");
+
+        // Archive tronquée : pas de TRAILER!!!
+        assert_eq!(parse(&archive[..archive.len() - 120]), Err(VfsError::InvalidArgument));
+    }
+}
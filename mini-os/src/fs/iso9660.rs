@@ -0,0 +1,654 @@
+/// ISO 9660 - Système de fichiers des CD-ROM (lecture seule)
+///
+/// Le volume est lu depuis un périphérique bloc du registre (`drivers::block`),
+/// quelle que soit sa taille de secteur : les blocs logiques ISO de 2048
+/// octets sont reconstitués au besoin. Le descripteur de volume primaire
+/// (secteur 16) donne la racine ; l'arborescence est parcourue par les
+/// enregistrements de répertoire, sans la table des chemins.
+///
+/// Extensions Rock Ridge (détectées par l'entrée SUSP `SP` de la racine) :
+/// noms longs (`NM`, zones de continuation `CE` comprises), permissions et
+/// propriétaires (`PX`). Sans elles, les noms 8.3 sont rendus en minuscules,
+/// sans numéro de version. Les liens symboliques Rock Ridge et les fichiers
+/// en plusieurs extents (plus de 4 Gio) ne sont pas gérés : seul le premier
+/// extent est lu.
+///
+/// Le numéro d'inode est la position (en octets) de l'enregistrement de
+/// répertoire sur le volume ; celui de la racine est son entrée ".".
+
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use spin::Mutex;
+
+use crate::drivers::block::BlockHandle;
+use crate::fs::vfs_core::*;
+
+/// Taille d'un bloc logique ISO
+const LOGICAL_BLOCK: u64 = 2048;
+/// Premier descripteur de volume
+const FIRST_DESCRIPTOR: u64 = 16;
+/// Au-delà, on renonce à trouver le descripteur primaire
+const MAX_DESCRIPTORS: u64 = 32;
+const DESCRIPTOR_PRIMARY: u8 = 1;
+const DESCRIPTOR_TERMINATOR: u8 = 255;
+const STANDARD_ID: &[u8; 5] = b"CD001";
+/// Position de l'enregistrement racine dans le descripteur primaire
+const ROOT_RECORD: usize = 156;
+/// Taille maximale d'un enregistrement de répertoire
+const MAX_RECORD: usize = 255;
+
+const FLAG_DIRECTORY: u8 = 0x02;
+
+/// Drapeaux de l'entrée Rock Ridge `NM` (les morceaux d'un nom long
+/// arrivent dans des entrées successives, que l'on concatène)
+const NM_CURRENT: u8 = 0x02;
+const NM_PARENT: u8 = 0x04;
+/// Profondeur maximale des zones de continuation `CE`
+const MAX_CONTINUATIONS: usize = 8;
+/// Taille maximale d'une zone de continuation `CE` (un bloc logique)
+const MAX_CONTINUATION_LEN: u64 = LOGICAL_BLOCK;
+/// Taille maximale d'un répertoire (tailles lues sur le support)
+const MAX_DIR_SIZE: u64 = 1024 * 1024;
+
+fn le16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn le32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]])
+}
+
+/// Accès octet par octet au périphérique
+struct Volume {
+    dev: BlockHandle,
+}
+
+impl Volume {
+    /// Taille du volume en octets
+    fn size(&self) -> u64 {
+        self.dev.block_count() * self.dev.block_size() as u64
+    }
+
+    fn read(&self, offset: u64, buf: &mut [u8]) -> VfsResult<()> {
+        if buf.is_empty() {
+            return Ok(());
+        }
+        if offset.checked_add(buf.len() as u64).map_or(true, |end| end > self.size()) {
+            return Err(VfsError::IoError);
+        }
+        let block_size = self.dev.block_size() as u64;
+        let first = offset / block_size;
+        let last = (offset + buf.len() as u64).div_ceil(block_size);
+        let mut blocks = vec![0u8; ((last - first) * block_size) as usize];
+        self.dev.read_blocks(first, &mut blocks)?;
+        let start = (offset - first * block_size) as usize;
+        buf.copy_from_slice(&blocks[start..start + buf.len()]);
+        Ok(())
+    }
+
+    /// Lit `len` octets ; une longueur hors du volume est refusée avant
+    /// toute allocation
+    fn read_vec(&self, offset: u64, len: usize) -> VfsResult<Vec<u8>> {
+        if offset.checked_add(len as u64).map_or(true, |end| end > self.size()) {
+            return Err(VfsError::IoError);
+        }
+        let mut buf = vec![0u8; len];
+        self.read(offset, &mut buf)?;
+        Ok(buf)
+    }
+}
+
+/// Enregistrement de répertoire décodé
+#[derive(Debug, Clone)]
+struct DirRecord {
+    extent: u32,
+    size: u32,
+    flags: u8,
+    mtime: u64,
+    name: String,
+    /// Attributs POSIX de l'entrée Rock Ridge `PX`
+    mode: Option<u16>,
+    nlinks: Option<u32>,
+    uid: u32,
+    gid: u32,
+}
+
+impl DirRecord {
+    fn is_dir(&self) -> bool {
+        self.flags & FLAG_DIRECTORY != 0
+    }
+
+    fn file_type(&self) -> FileType {
+        if self.is_dir() { FileType::Directory } else { FileType::Regular }
+    }
+}
+
+/// Date d'enregistrement (7 octets, décalage GMT par quarts d'heure)
+fn record_time(raw: &[u8]) -> u64 {
+    let date = crate::time::DateTime {
+        year: 1900 + raw[0] as u16,
+        month: raw[1].max(1),
+        day: raw[2].max(1),
+        hour: raw[3],
+        minute: raw[4],
+        second: raw[5],
+    };
+    let offset = raw[6] as i8 as i64 * 15 * 60;
+    (date.to_unix() as i64 - offset).max(0) as u64
+}
+
+/// Nom ISO 9660 : `NOM.EXT;1` devient `nom.ext`
+fn iso_name(raw: &[u8]) -> String {
+    match raw {
+        [0] => ".".into(),
+        [1] => "..".into(),
+        _ => {
+            let name = raw.split(|&b| b == b';').next().unwrap_or(raw);
+            let name = name.strip_suffix(b".").unwrap_or(name);
+            String::from_utf8_lossy(name).to_ascii_lowercase()
+        }
+    }
+}
+
+/// Décode une zone d'utilisation système (entrées SUSP/Rock Ridge)
+fn parse_system_use(vol: &Volume, area: &[u8], record: &mut DirRecord, name: &mut Option<String>, depth: usize) -> VfsResult<()> {
+    let mut pos = 0;
+    while pos + 4 <= area.len() {
+        let len = area[pos + 2] as usize;
+        if len < 4 || pos + len > area.len() {
+            break;
+        }
+        let data = &area[pos + 4..pos + len];
+        match &area[pos..pos + 2] {
+            b"NM" if !data.is_empty() && data[0] & (NM_CURRENT | NM_PARENT) == 0 => {
+                let part = String::from_utf8_lossy(&data[1..]);
+                name.get_or_insert_with(String::new).push_str(&part);
+            }
+            b"PX" if data.len() >= 32 => {
+                record.mode = Some((le32(data, 0) & 0o7777) as u16);
+                record.nlinks = Some(le32(data, 8));
+                record.uid = le32(data, 16);
+                record.gid = le32(data, 24);
+            }
+            b"CE" if data.len() >= 24 && depth < MAX_CONTINUATIONS => {
+                let offset = le32(data, 0) as u64 * LOGICAL_BLOCK + le32(data, 8) as u64;
+                let len = le32(data, 16) as u64;
+                if len > MAX_CONTINUATION_LEN {
+                    return Err(VfsError::IoError);
+                }
+                let continuation = vol.read_vec(offset, len as usize)?;
+                parse_system_use(vol, &continuation, record, name, depth + 1)?;
+            }
+            b"ST" => break,
+            _ => {}
+        }
+        pos += len;
+    }
+    Ok(())
+}
+
+/// Décode l'enregistrement `raw` ; `rock_ridge` : octets SUSP à sauter
+/// (entrée `SP`), None si le volume n'a pas d'extensions
+fn parse_record(vol: &Volume, raw: &[u8], rock_ridge: Option<usize>) -> VfsResult<DirRecord> {
+    if raw.len() < 34 || (raw[0] as usize) < 34 || raw[0] as usize > raw.len() {
+        return Err(VfsError::IoError);
+    }
+    let len = raw[0] as usize;
+    let name_len = raw[32] as usize;
+    if 33 + name_len > len {
+        return Err(VfsError::IoError);
+    }
+    let raw_name = &raw[33..33 + name_len];
+    let mut record = DirRecord {
+        extent: le32(raw, 2),
+        size: le32(raw, 10),
+        flags: raw[25],
+        mtime: record_time(&raw[18..25]),
+        name: iso_name(raw_name),
+        mode: None,
+        nlinks: None,
+        uid: 0,
+        gid: 0,
+    };
+
+    if let Some(skip) = rock_ridge {
+        // Octet de bourrage après un nom de longueur paire
+        let start = 33 + name_len + (name_len + 1) % 2 + skip;
+        if start < len {
+            let mut long_name = None;
+            parse_system_use(vol, &raw[start..len], &mut record, &mut long_name, 0)?;
+            if let Some(long_name) = long_name {
+                if record.name != "." && record.name != ".." {
+                    record.name = long_name;
+                }
+            }
+        }
+    }
+    Ok(record)
+}
+
+/// Enregistrements d'un répertoire : (position sur le volume, enregistrement)
+fn read_dir(vol: &Volume, dir: &DirRecord, rock_ridge: Option<usize>) -> VfsResult<Vec<(u64, DirRecord)>> {
+    let base = dir.extent as u64 * LOGICAL_BLOCK;
+    if dir.size as u64 > MAX_DIR_SIZE {
+        return Err(VfsError::IoError);
+    }
+    let data = vol.read_vec(base, dir.size as usize)?;
+    let mut records = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        let len = data[pos] as usize;
+        if len == 0 {
+            // Un enregistrement ne chevauche jamais deux secteurs
+            pos = (pos / LOGICAL_BLOCK as usize + 1) * LOGICAL_BLOCK as usize;
+            continue;
+        }
+        if pos + len > data.len() {
+            break;
+        }
+        records.push((base + pos as u64, parse_record(vol, &data[pos..pos + len], rock_ridge)?));
+        pos += len;
+    }
+    Ok(records)
+}
+
+/// Superblock ISO 9660
+pub struct IsoSuperblock {
+    fs_id: FsId,
+    total_blocks: u64,
+    root_inode: InodeId,
+}
+
+impl Superblock for IsoSuperblock {
+    fn fs_name(&self) -> &str {
+        "iso9660"
+    }
+
+    fn fs_id(&self) -> FsId {
+        self.fs_id
+    }
+
+    fn block_size(&self) -> u32 {
+        LOGICAL_BLOCK as u32
+    }
+
+    fn total_blocks(&self) -> u64 {
+        self.total_blocks
+    }
+
+    fn free_blocks(&self) -> u64 {
+        0
+    }
+
+    fn total_inodes(&self) -> u64 {
+        0
+    }
+
+    fn free_inodes(&self) -> u64 {
+        0
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn root_inode(&self) -> InodeId {
+        self.root_inode
+    }
+}
+
+/// Système de fichiers ISO 9660
+pub struct IsoFileSystem {
+    vol: Arc<Volume>,
+    sb: Arc<IsoSuperblock>,
+    rock_ridge: Option<usize>,
+    volume_id: String,
+}
+
+impl IsoFileSystem {
+    /// Lit les descripteurs de volume de `dev`
+    pub fn new(dev: BlockHandle) -> VfsResult<Self> {
+        let vol = Volume { dev };
+        let mut descriptor = vec![0u8; LOGICAL_BLOCK as usize];
+        let mut index = FIRST_DESCRIPTOR;
+        loop {
+            if index >= FIRST_DESCRIPTOR + MAX_DESCRIPTORS {
+                return Err(VfsError::InvalidArgument);
+            }
+            vol.read(index * LOGICAL_BLOCK, &mut descriptor)?;
+            if &descriptor[1..6] != STANDARD_ID || descriptor[0] == DESCRIPTOR_TERMINATOR {
+                return Err(VfsError::InvalidArgument);
+            }
+            if descriptor[0] == DESCRIPTOR_PRIMARY {
+                break;
+            }
+            index += 1;
+        }
+        if le16(&descriptor, 128) as u64 != LOGICAL_BLOCK {
+            return Err(VfsError::NotSupported);
+        }
+
+        let root = parse_record(&vol, &descriptor[ROOT_RECORD..ROOT_RECORD + 34], None)?;
+        let root_inode = root.extent as u64 * LOGICAL_BLOCK;
+        // Rock Ridge : entrée SP (BE EF) en tête de la zone système de "."
+        let dot = vol.read_vec(root_inode, MAX_RECORD)?;
+        let su = 34.min(dot[0] as usize);
+        let rock_ridge = match dot.get(su..su + 7) {
+            Some([b'S', b'P', 7, _, 0xBE, 0xEF, skip]) => Some(*skip as usize),
+            _ => None,
+        };
+
+        let volume_id = String::from_utf8_lossy(&descriptor[40..72]).trim_end().into();
+        Ok(Self {
+            vol: Arc::new(vol),
+            sb: Arc::new(IsoSuperblock {
+                fs_id: alloc_fs_id(),
+                total_blocks: le32(&descriptor, 80) as u64,
+                root_inode,
+            }),
+            rock_ridge,
+            volume_id,
+        })
+    }
+
+    /// Nom du volume (descripteur primaire)
+    pub fn volume_id(&self) -> &str {
+        &self.volume_id
+    }
+
+    /// Le volume porte-t-il des extensions Rock Ridge ?
+    pub fn has_rock_ridge(&self) -> bool {
+        self.rock_ridge.is_some()
+    }
+}
+
+impl FileSystemOps for IsoFileSystem {
+    fn superblock(&self) -> Arc<dyn Superblock> {
+        self.sb.clone()
+    }
+
+    fn get_inode(&self, inode_id: InodeId) -> VfsResult<Arc<Mutex<dyn InodeOps>>> {
+        let raw = self.vol.read_vec(inode_id, MAX_RECORD)?;
+        let len = (raw[0] as usize).max(1);
+        let record = parse_record(&self.vol, &raw[..len], self.rock_ridge)?;
+        Ok(Arc::new(Mutex::new(IsoInode {
+            vol: self.vol.clone(),
+            id: inode_id,
+            record,
+            rock_ridge: self.rock_ridge,
+        })))
+    }
+
+    fn sync(&self) -> VfsResult<()> {
+        Ok(())
+    }
+
+    fn unmount(&self) -> VfsResult<()> {
+        Ok(())
+    }
+}
+
+/// Inode ISO 9660
+struct IsoInode {
+    vol: Arc<Volume>,
+    id: InodeId,
+    record: DirRecord,
+    rock_ridge: Option<usize>,
+}
+
+impl IsoInode {
+    /// Entrées du répertoire, sans "." ni ".."
+    fn children(&self) -> VfsResult<Vec<(u64, DirRecord)>> {
+        if !self.record.is_dir() {
+            return Err(VfsError::NotDirectory);
+        }
+        let records = read_dir(&self.vol, &self.record, self.rock_ridge)?;
+        Ok(records.into_iter().filter(|(_, r)| r.name != "." && r.name != "..").collect())
+    }
+}
+
+impl InodeOps for IsoInode {
+    fn read(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        if self.record.is_dir() {
+            return Err(VfsError::IsDirectory);
+        }
+        let size = self.record.size as u64;
+        if offset >= size {
+            return Ok(0);
+        }
+        let n = buf.len().min((size - offset) as usize);
+        self.vol.read(self.record.extent as u64 * LOGICAL_BLOCK + offset, &mut buf[..n])?;
+        Ok(n)
+    }
+
+    fn write(&mut self, _offset: u64, _buf: &[u8]) -> VfsResult<usize> {
+        Err(VfsError::ReadOnly)
+    }
+
+    fn stat(&self) -> VfsResult<FileStat> {
+        let record = &self.record;
+        let mut stat = FileStat::new(self.id, record.file_type());
+        let default_mode = if record.is_dir() { 0o555 } else { 0o444 };
+        stat.mode = FileMode::new(record.mode.unwrap_or(default_mode));
+        stat.nlinks = record.nlinks.unwrap_or(if record.is_dir() { 2 } else { 1 });
+        stat.uid = record.uid;
+        stat.gid = record.gid;
+        stat.size = record.size as u64;
+        stat.atime = record.mtime;
+        stat.mtime = record.mtime;
+        stat.ctime = record.mtime;
+        stat.blksize = LOGICAL_BLOCK as u32;
+        stat.blocks = stat.size.div_ceil(512);
+        Ok(stat)
+    }
+
+    fn lookup(&self, name: &str) -> VfsResult<InodeId> {
+        if name == "." {
+            return Ok(self.id);
+        }
+        let found = self.children()?.into_iter().find(|(_, r)| {
+            // Noms 8.3 : la casse d'origine est perdue
+            if self.rock_ridge.is_some() { r.name == name } else { r.name.eq_ignore_ascii_case(name) }
+        });
+        found.map(|(id, _)| id).ok_or(VfsError::NotFound)
+    }
+
+    fn create(&mut self, _name: &str, _mode: FileMode, _file_type: FileType) -> VfsResult<InodeId> {
+        Err(VfsError::ReadOnly)
+    }
+
+    fn unlink(&mut self, _name: &str) -> VfsResult<()> {
+        Err(VfsError::ReadOnly)
+    }
+
+    fn mkdir(&mut self, _name: &str, _mode: FileMode) -> VfsResult<InodeId> {
+        Err(VfsError::ReadOnly)
+    }
+
+    fn rmdir(&mut self, _name: &str) -> VfsResult<()> {
+        Err(VfsError::ReadOnly)
+    }
+
+    fn readdir(&self) -> VfsResult<Vec<DirEntry>> {
+        let mut entries = Vec::new();
+        entries.push(DirEntry::new(self.id, ".".into(), FileType::Directory));
+        for (id, record) in self.children()? {
+            let file_type = record.file_type();
+            entries.push(DirEntry::new(id, record.name, file_type));
+        }
+        Ok(entries)
+    }
+
+    fn truncate(&mut self, _size: u64) -> VfsResult<()> {
+        Err(VfsError::ReadOnly)
+    }
+}
+
+/// Pilote enregistré auprès de `fs_manager`
+fn mount_device(dev: BlockHandle) -> VfsResult<Arc<dyn FileSystemOps>> {
+    let fs = IsoFileSystem::new(dev)?;
+    log::info!(
+        "iso9660: volume \"{}\"{}",
+        fs.volume_id(),
+        if fs.has_rock_ridge() { " (Rock Ridge)" } else { "" }
+    );
+    Ok(Arc::new(fs))
+}
+
+fn register() -> VfsResult<()> {
+    crate::fs_manager::register_fs_type("iso9660", mount_device)
+}
+
+crate::initcall!(core, "ISO 9660", register);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::collections::BTreeMap;
+    use crate::drivers::block::BlockDevice;
+    use crate::drivers::disk::DiskError;
+
+    /// Image creuse : secteurs de 2048 octets, nuls sauf ceux écrits
+    struct SparseImage {
+        sectors: BTreeMap<u64, Vec<u8>>,
+    }
+
+    impl BlockDevice for SparseImage {
+        fn block_size(&self) -> usize {
+            LOGICAL_BLOCK as usize
+        }
+
+        fn block_count(&self) -> u64 {
+            64
+        }
+
+        fn read_blocks(&mut self, lba: u64, buf: &mut [u8]) -> Result<(), DiskError> {
+            for (i, chunk) in buf.chunks_mut(LOGICAL_BLOCK as usize).enumerate() {
+                match self.sectors.get(&(lba + i as u64)) {
+                    Some(sector) => chunk.copy_from_slice(sector),
+                    None => chunk.fill(0),
+                }
+            }
+            Ok(())
+        }
+
+        fn write_blocks(&mut self, _lba: u64, _buf: &[u8]) -> Result<(), DiskError> {
+            Err(DiskError::WriteFailed)
+        }
+    }
+
+    fn record(name: &[u8], extent: u32, size: u32, flags: u8, system_use: &[u8]) -> Vec<u8> {
+        let pad = (name.len() + 1) % 2;
+        let mut raw = vec![0u8; 33 + name.len() + pad];
+        raw[2..6].copy_from_slice(&extent.to_le_bytes());
+        raw[10..14].copy_from_slice(&size.to_le_bytes());
+        raw[18..25].copy_from_slice(&[124, 1, 2, 3, 4, 5, 0]); // 2024-01-02 03:04:05
+        raw[25] = flags;
+        raw[32] = name.len() as u8;
+        raw[33..33 + name.len()].copy_from_slice(name);
+        raw.extend_from_slice(system_use);
+        raw[0] = raw.len() as u8;
+        raw
+    }
+
+    fn rr_name(name: &str) -> Vec<u8> {
+        let mut entry = vec![b'N', b'M', 5 + name.len() as u8, 1, 0];
+        entry.extend_from_slice(name.as_bytes());
+        entry
+    }
+
+    fn rr_mode(mode: u32) -> Vec<u8> {
+        let mut entry = vec![b'P', b'X', 36, 1];
+        for value in [mode, 1, 1000, 100] {
+            entry.extend_from_slice(&value.to_le_bytes());
+            entry.extend_from_slice(&value.to_be_bytes());
+        }
+        entry
+    }
+
+    /// Descripteur primaire et terminateur ; racine en bloc 18 de `root_size` octets
+    fn descriptors(root_size: u32) -> BTreeMap<u64, Vec<u8>> {
+        let mut sectors = BTreeMap::new();
+        let mut pvd = vec![0u8; 2048];
+        pvd[0] = DESCRIPTOR_PRIMARY;
+        pvd[1..6].copy_from_slice(STANDARD_ID);
+        pvd[40..72].copy_from_slice(&[b' '; 32]);
+        pvd[40..46].copy_from_slice(b"DONNEE");
+        pvd[80..84].copy_from_slice(&64u32.to_le_bytes());
+        pvd[128..130].copy_from_slice(&2048u16.to_le_bytes());
+        let root = record(&[0], 18, root_size, FLAG_DIRECTORY, &[]);
+        pvd[ROOT_RECORD..ROOT_RECORD + 34].copy_from_slice(&root);
+        sectors.insert(16, pvd);
+        let mut terminator = vec![0u8; 2048];
+        terminator[0] = DESCRIPTOR_TERMINATOR;
+        terminator[1..6].copy_from_slice(STANDARD_ID);
+        sectors.insert(17, terminator);
+        sectors
+    }
+
+    #[test_case]
+    fn test_iso9660_rock_ridge() {
+        let mut sectors = descriptors(2048);
+
+        let mut dir = Vec::new();
+        dir.extend(record(&[0], 18, 2048, FLAG_DIRECTORY, &[b'S', b'P', 7, 1, 0xBE, 0xEF, 0]));
+        dir.extend(record(&[1], 18, 2048, FLAG_DIRECTORY, &[]));
+        let mut su = rr_name("Notes de version.txt");
+        su.extend(rr_mode(0o100640));
+        dir.extend(record(b"NOTES_DE.TXT;1", 19, 5, 0, &su));
+        dir.resize(2048, 0);
+        sectors.insert(18, dir);
+        let mut data = b"salut".to_vec();
+        data.resize(2048, 0);
+        sectors.insert(19, data);
+
+        let dev = BlockHandle::new("cd0", Arc::new(Mutex::new(SparseImage { sectors })));
+        let fs = IsoFileSystem::new(dev).unwrap();
+        assert_eq!(fs.volume_id(), "DONNEE");
+        assert!(fs.has_rock_ridge());
+
+        let root = fs.get_inode(fs.superblock().root_inode()).unwrap();
+        let id = root.lock().lookup("Notes de version.txt").unwrap();
+        let file = fs.get_inode(id).unwrap();
+        let stat = file.lock().stat().unwrap();
+        assert_eq!(stat.size, 5);
+        assert_eq!(stat.mode.0, 0o640);
+        assert_eq!(stat.uid, 1000);
+        let mut buf = [0u8; 16];
+        assert_eq!(file.lock().read(0, &mut buf).unwrap(), 5);
+        assert_eq!(&buf[..5], b"salut");
+        assert_eq!(file.lock().write(0, b"x"), Err(VfsError::ReadOnly));
+        assert_eq!(root.lock().readdir().unwrap().len(), 2);
+        assert_eq!(iso_name(b"README.TXT;1"), "readme.txt");
+    }
+
+    #[test_case]
+    fn test_iso9660_rejects_oversized_lengths() {
+        // Répertoire de 4 Gio annoncé par le support
+        let mut sectors = descriptors(0xFFFF_FFFF);
+        let mut dir = record(&[0], 18, 0xFFFF_FFFF, FLAG_DIRECTORY, &[b'S', b'P', 7, 1, 0xBE, 0xEF, 0]);
+        dir.resize(2048, 0);
+        sectors.insert(18, dir);
+        let dev = BlockHandle::new("cd1", Arc::new(Mutex::new(SparseImage { sectors })));
+        let fs = IsoFileSystem::new(dev).unwrap();
+        let root = fs.get_inode(fs.superblock().root_inode()).unwrap();
+        assert_eq!(root.lock().readdir().err(), Some(VfsError::IoError));
+
+        // Lecture au-delà du volume refusée avant d'allouer
+        let dev = BlockHandle::new("cd2", Arc::new(Mutex::new(SparseImage { sectors: BTreeMap::new() })));
+        let vol = Volume { dev };
+        assert_eq!(vol.read_vec(60 * LOGICAL_BLOCK, 8 * LOGICAL_BLOCK as usize).err(), Some(VfsError::IoError));
+
+        // Zone de continuation `CE` de 4 Gio
+        let mut ce = vec![b'C', b'E', 28, 1];
+        for value in [20u32, 0, u32::MAX] {
+            ce.extend_from_slice(&value.to_le_bytes());
+            ce.extend_from_slice(&value.to_be_bytes());
+        }
+        let mut entry = parse_record(&vol, &record(b"A", 20, 0, 0, &[]), None).unwrap();
+        let mut name = None;
+        assert_eq!(parse_system_use(&vol, &ce, &mut entry, &mut name, 0), Err(VfsError::IoError));
+    }
+}
@@ -3,6 +3,7 @@ pub mod vfs_core;
 pub mod vfs_inode;
 pub mod vfs_dentry;
 pub mod vfs_mount;
+pub mod vfs_namei;
 pub mod ramfs;
 pub mod symlink;
 pub mod permissions;
@@ -12,13 +13,24 @@ pub mod sparse;
 pub mod ext2_extent;
 pub mod fat32_cache;
 pub mod cache;
+pub mod procfs;
+pub mod devfs;
+pub mod initramfs;
+pub mod overlayfs;
+pub mod iso9660;
+pub mod poll;
 
-pub use fd::{FileDescriptor, FileDescriptorTable, FileDescriptorManager, OpenMode, FD_MANAGER};
+pub use fd::{FileDescriptor, FileDescriptorTable, OpenInode, FileDescriptorManager, OpenMode, FD_MANAGER, TOO_MANY_FILES};
 pub use vfs_core::*;
 pub use vfs_inode::{Inode, InodeCache, INODE_CACHE, get_or_create_inode, put_inode};
 pub use vfs_dentry::{Dentry, DentryCache, DENTRY_CACHE, path_lookup as vfs_path_lookup, create_root_dentry};
 pub use vfs_mount::{MountPoint, MountFlags, MountManager, MOUNT_MANAGER, mount_root, mount_fs, unmount_fs};
-pub use ramfs::RamFileSystemRef;
+pub use vfs_namei::{LockedParent, split_path, with_parent, create_at, mkdir_at, unlink_at, link_at, rename_at};
+pub use ramfs::{RamFileSystemRef, RamFsLimits, mount_tmpfs};
+pub use procfs::ProcFileSystem;
+pub use devfs::{DevFileSystem, DeviceOps, DeviceKind};
+pub use overlayfs::{OverlayFileSystem, mount_overlay};
+pub use iso9660::IsoFileSystem;
 pub use symlink::{SYMLINK_MANAGER, SymlinkManager, SymlinkError, LinkType};
 pub use permissions::{PERMISSION_MANAGER, PermissionManager, Permissions, PermissionError};
 pub use acl::{ACL_MANAGER, AclManager, Acl, AclEntry, AclEntryType, AclPermissions, PermissionType};
@@ -33,40 +45,40 @@ use alloc::vec::Vec;
 use alloc::sync::Arc;
 use spin::Mutex;
 use lazy_static::lazy_static;
+use crate::process::cred::{Credentials, CAP_SYS_ADMIN};
 
 lazy_static! {
     static ref ROOT_DENTRY: Mutex<Option<Arc<Mutex<Dentry>>>> = Mutex::new(None);
 }
 
+/// Racine globale du VFS
+fn root_dentry() -> VfsResult<Arc<Mutex<Dentry>>> {
+    ROOT_DENTRY.lock().clone().ok_or(VfsError::IoError)
+}
+
 /// Helper: Initialize default RamFS
 pub fn init_vfs() -> VfsResult<()> {
     // Mount RamFS as root
     let fs = alloc::sync::Arc::new(RamFileSystemRef::new());
     
-    // Create root inode
-    let root_inode_ops = fs.get_inode(1)?; // Root ID is 1
-    
-    let root_inode = super::fs::get_or_create_inode(
-        fs.superblock().fs_id(),
-        1,
-        super::fs::FileType::Directory,
-        root_inode_ops
-    );
-    
-    // Create root dentry
-    let root_dentry = create_root_dentry(root_inode);
+    // Le montage racine enregistre le système de fichiers : la résolution
+    // de chemins retrouve ainsi les inodes enfants via leur fs_id
+    let root_dentry = mount_root(fs, MountFlags::new(0))?;
     *ROOT_DENTRY.lock() = Some(root_dentry.clone());
     
-    // Register mount (Optional for this simplified version but good for completeness)
-    // mount_root(fs)?; 
+    // Systèmes de fichiers virtuels
+    vfs_mkdir("/proc")?;
+    mount_fs("/proc", alloc::sync::Arc::new(ProcFileSystem::new()), MountFlags::new(MountFlags::READONLY))?;
+    vfs_mkdir("/dev")?;
+    mount_fs("/dev", alloc::sync::Arc::new(DevFileSystem::new()), MountFlags::new(0))?;
+    devfs::register_default_devices()?;
     
     Ok(())
 }
 
 /// Helper: Lookup path using global root
 pub fn path_lookup(path: &str) -> VfsResult<Arc<Mutex<Dentry>>> {
-    let root = ROOT_DENTRY.lock().as_ref().ok_or(VfsError::IoError)?.clone();
-    vfs_path_lookup(path, root)
+    vfs_path_lookup(path, root_dentry()?)
 }
 
 /// Helper: Check if path is directory
@@ -101,6 +113,102 @@ pub fn vfs_ls(path: &str) -> VfsResult<Vec<String>> {
     Ok(names)
 }
 
+/// Helper: Stat
+pub fn vfs_stat(path: &str) -> VfsResult<FileStat> {
+    let dentry = path_lookup(path)?;
+    let inode = dentry.lock().inode.clone();
+    let (ops, fs_id) = {
+        let inode = inode.lock();
+        (inode.ops.clone(), inode.fs_id)
+    };
+    let mut stat = ops.lock().stat()?;
+    stat.dev = fs_id;
+    Ok(stat)
+}
+
+/// Helper: Lit via le descripteur `fd` du processus `pid` (position avancée)
+pub fn vfs_fd_read(pid: u64, fd: usize, buf: &mut [u8]) -> VfsResult<usize> {
+    let (ops, offset) = vfs_fd_inode(pid, fd)?;
+    let read = ops.lock().read(offset, buf)?;
+    advance_fd(pid, fd, read);
+    Ok(read)
+}
+
+/// Helper: Écrit via le descripteur `fd` du processus `pid` (position avancée)
+pub fn vfs_fd_write(pid: u64, fd: usize, data: &[u8]) -> VfsResult<usize> {
+    let (ops, offset) = vfs_fd_inode(pid, fd)?;
+    let written = ops.lock().write(offset, data)?;
+    advance_fd(pid, fd, written);
+    Ok(written)
+}
+
+/// Helper: Inode à l'ouverture, pour le descripteur qui le garde en vie
+pub fn vfs_open_inode(path: &str) -> VfsResult<OpenInode> {
+    let dentry = path_lookup(path)?;
+    let inode = dentry.lock().inode.clone();
+    let ops = inode.lock().ops.clone();
+    Ok(OpenInode(ops))
+}
+
+/// Helper: Opérations de l'inode ouvert par `fd` (résolu par le chemin si
+/// le descripteur n'en garde pas) et position courante
+pub fn vfs_fd_inode(pid: u64, fd: usize) -> VfsResult<(Arc<Mutex<dyn InodeOps>>, u64)> {
+    let (inode, path, offset) = {
+        let mut fm = FD_MANAGER.lock();
+        let table = fm.get_table(pid).map_err(|_| VfsError::InvalidArgument)?;
+        let desc = table.get(fd).map_err(|_| VfsError::InvalidArgument)?;
+        (desc.inode.clone(), desc.path.clone(), desc.offset)
+    };
+    match inode {
+        Some(OpenInode(ops)) => Ok((ops, offset)),
+        None => Ok((vfs_open_inode(&path)?.0, offset)),
+    }
+}
+
+/// Helper: Chemin ouvert par le descripteur `fd` du processus `pid`
+pub fn vfs_fd_path(pid: u64, fd: usize) -> VfsResult<String> {
+    fd_position(pid, fd).map(|(path, _)| path)
+}
+
+/// Helper: Lit à partir de `offset` (lecture courte en fin de fichier)
+pub fn vfs_read_at(path: &str, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+    let dentry = path_lookup(path)?;
+    let inode = dentry.lock().inode.clone();
+    let ops = inode.lock().ops.clone();
+    let size = ops.lock().stat()?.size;
+    if offset >= size {
+        return Ok(0);
+    }
+    let len = buf.len().min((size - offset) as usize);
+    let read = ops.lock().read(offset, &mut buf[..len])?;
+    Ok(read)
+}
+
+/// Helper: Écrit à partir de `offset` sans tronquer (le fichier doit exister)
+pub fn vfs_write_at(path: &str, offset: u64, data: &[u8]) -> VfsResult<usize> {
+    let dentry = path_lookup(path)?;
+    let inode = dentry.lock().inode.clone();
+    let ops = inode.lock().ops.clone();
+    let written = ops.lock().write(offset, data)?;
+    Ok(written)
+}
+
+/// Chemin et position courante d'un descripteur
+fn fd_position(pid: u64, fd: usize) -> VfsResult<(String, u64)> {
+    let mut fm = FD_MANAGER.lock();
+    let table = fm.get_table(pid).map_err(|_| VfsError::InvalidArgument)?;
+    let desc = table.get(fd).map_err(|_| VfsError::InvalidArgument)?;
+    Ok((desc.path.clone(), desc.offset))
+}
+
+fn advance_fd(pid: u64, fd: usize, count: usize) {
+    let mut fm = FD_MANAGER.lock();
+    if let Ok(desc) = fm.get_table(pid).and_then(|t| t.get_mut(fd)) {
+        desc.offset += count as u64;
+        desc.size = desc.size.max(desc.offset);
+    }
+}
+
 /// Helper: Read file content
 pub fn vfs_read_file(path: &str) -> VfsResult<Vec<u8>> {
     let dentry = path_lookup(path)?;
@@ -116,92 +224,318 @@ pub fn vfs_read_file(path: &str) -> VfsResult<Vec<u8>> {
 
 /// Helper: Write file content (Create or Overwrite)
 pub fn vfs_write_file(path: &str, content: &[u8]) -> VfsResult<()> {
-    // Try to open existing
-    match path_lookup(path) {
-        Ok(dentry) => {
-            let inode = dentry.lock().inode.clone();
-            inode.lock().ops.lock().truncate(0)?;
-            inode.lock().ops.lock().write(0, content)?;
-            Ok(())
-        }
-        Err(VfsError::NotFound) => {
-            // Create new
-            // Split path to find parent
-            let path_string = String::from(path);
-            let parts: Vec<&str> = path_string.rsplitn(2, '/').collect();
-            let (filename, parent_path) = if parts.len() == 2 {
-                (parts[0], parts[1])
-            } else {
-                (parts[0], ".")
-            };
-            
-            let parent_path = if parent_path.is_empty() { "/" } else { parent_path };
-            
-            let parent_dentry = path_lookup(parent_path)?;
-            let parent_inode = parent_dentry.lock().inode.clone();
-            
-            let _inode_id = parent_inode.lock().ops.lock().create(
-                filename, 
-                FileMode::new(0o644), 
-                FileType::Regular
-            )?;
-            
-            // Re-lookup to get the new file (dentry cache population)
-            // Or just manually write content now we have ID? 
-            // InodeOps create returns InodeId. To write to it, we need to load it.
-            // Simplified: loop up again.
-            
-            // Note: path_lookup checks dentry cache or triggers lookup.
-            // Since we created it in backend but not in dentry layer, path_lookup might fail if we don't invalidate cache or if we don't manually add dentry.
-            // For now, assuming direct lookup works or simple path resolution.
-            
-            // To properly write, strictly we need to instantiate proper Dentry/Inode wrappers.
-            // Let's rely on path_lookup finding it now that it exists in backend.
-            let dentry = path_lookup(path)?;
-            let inode = dentry.lock().inode.clone();
-            inode.lock().ops.lock().write(0, content)?;
-            
-            Ok(())
-        }
-        Err(e) => Err(e)
+    // Création atomique : si un autre processus crée le fichier entre-temps,
+    // on écrit simplement dans le fichier existant
+    match vfs_create(path, FileMode::new(0o644), FileType::Regular) {
+        Ok(_) | Err(VfsError::AlreadyExists) => {}
+        Err(e) => return Err(e),
     }
+
+    let dentry = path_lookup(path)?;
+    let inode = dentry.lock().inode.clone();
+    inode.lock().ops.lock().truncate(0)?;
+    inode.lock().ops.lock().write(0, content)?;
+    Ok(())
+}
+
+/// Helper: Append to file (créé si absent)
+pub fn vfs_append_file(path: &str, content: &[u8]) -> VfsResult<()> {
+    match vfs_create(path, FileMode::new(0o644), FileType::Regular) {
+        Ok(_) | Err(VfsError::AlreadyExists) => {}
+        Err(e) => return Err(e),
+    }
+
+    let dentry = path_lookup(path)?;
+    let inode = dentry.lock().inode.clone();
+    let ops = inode.lock().ops.clone();
+    let mut ops = ops.lock();
+    let size = ops.stat()?.size;
+    ops.write(size, content)?;
+    Ok(())
+}
+
+/// Helper: Create file (échoue si le nom existe déjà)
+pub fn vfs_create(path: &str, mode: FileMode, file_type: FileType) -> VfsResult<InodeId> {
+    create_at(&root_dentry()?, path, mode, file_type)
 }
 
 /// Helper: Make directory
 pub fn vfs_mkdir(path: &str) -> VfsResult<()> {
-    let path_string = String::from(path);
-    let parts: Vec<&str> = path_string.rsplitn(2, '/').collect();
-    let (dirname, parent_path) = if parts.len() == 2 {
-        (parts[0], parts[1])
-    } else {
-        (parts[0], ".")
-    };
-    
-    let parent_path = if parent_path.is_empty() { "/" } else { parent_path };
-    
-    let parent_dentry = path_lookup(parent_path)?;
-    let parent_inode = parent_dentry.lock().inode.clone();
-    
-    parent_inode.lock().ops.lock().mkdir(dirname, FileMode::new(0o755))?;
+    mkdir_at(&root_dentry()?, path, FileMode::new(0o755))?;
     Ok(())
 }
 
 /// Helper: Remove file
 pub fn vfs_remove_file(path: &str) -> VfsResult<()> {
-    let path_string = String::from(path);
-    let parts: Vec<&str> = path_string.rsplitn(2, '/').collect();
-    let (filename, parent_path) = if parts.len() == 2 {
-        (parts[0], parts[1])
+    unlink_at(&root_dentry()?, path)
+}
+
+/// Helper: Lien physique `new_path` vers `old_path`
+pub fn vfs_link(old_path: &str, new_path: &str) -> VfsResult<()> {
+    link_at(&root_dentry()?, old_path, new_path)
+}
+
+/// Helper: Rename / move
+pub fn vfs_rename(old_path: &str, new_path: &str) -> VfsResult<()> {
+    rename_at(&root_dentry()?, old_path, new_path)
+}
+
+/// Vérifie les droits `want` (ACCESS_*) de `cred` sur un inode
+pub fn check_access(stat: &FileStat, cred: &Credentials, want: u8) -> VfsResult<()> {
+    if PERMISSION_MANAGER.lock().check_stat(stat, cred, want) {
+        Ok(())
     } else {
-        (parts[0], ".")
+        Err(VfsError::PermissionDenied)
+    }
+}
+
+/// Résout `path` pour `cred` : droit de traversée (x) sur chaque
+/// répertoire parcouru, puis droits `want` sur la cible, dont les
+/// statistiques sont retournées
+pub fn vfs_access(path: &str, cred: &Credentials, want: u8) -> VfsResult<FileStat> {
+    let components: Vec<&str> = path.split('/').filter(|c| !c.is_empty()).collect();
+    let mut dir = String::from("/");
+    check_access(&vfs_stat(&dir)?, cred, permissions::ACCESS_EXEC)?;
+    for component in components.iter().take(components.len().saturating_sub(1)) {
+        if !dir.ends_with('/') {
+            dir.push('/');
+        }
+        dir.push_str(component);
+        check_access(&vfs_stat(&dir)?, cred, permissions::ACCESS_EXEC)?;
+    }
+    let stat = vfs_stat(path)?;
+    check_access(&stat, cred, want)?;
+    Ok(stat)
+}
+
+/// Liste un répertoire pour `cred` (lecture requise)
+pub fn vfs_readdir_as(path: &str, cred: &Credentials) -> VfsResult<Vec<String>> {
+    let stat = vfs_access(path, cred, permissions::ACCESS_READ)?;
+    if stat.file_type != FileType::Directory {
+        return Err(VfsError::NotDirectory);
+    }
+    vfs_ls(path)
+}
+
+/// Droits de `cred` sur le répertoire parent de `path` pour y ajouter ou
+/// y retirer une entrée : écriture et traversée
+fn check_parent(path: &str, cred: &Credentials) -> VfsResult<()> {
+    let (parent, _) = split_path(path)?;
+    vfs_access(parent, cred, permissions::ACCESS_WRITE | permissions::ACCESS_EXEC)?;
+    Ok(())
+}
+
+/// Le créateur de `path` en devient propriétaire quand le système de
+/// fichiers le permet
+fn set_creator(path: &str, cred: &Credentials) -> VfsResult<()> {
+    match vfs_setattr(path, None, Some(cred.euid), Some(cred.egid)) {
+        Ok(()) | Err(VfsError::NotSupported) => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Crée un fichier pour `cred` : écriture et traversée requises sur le
+/// parent ; le créateur en devient propriétaire quand le système de
+/// fichiers le permet
+pub fn vfs_create_as(path: &str, mode: FileMode, file_type: FileType, cred: &Credentials) -> VfsResult<InodeId> {
+    check_parent(path, cred)?;
+    let id = vfs_create(path, mode, file_type)?;
+    set_creator(path, cred)?;
+    Ok(id)
+}
+
+/// Crée un répertoire pour `cred`, mêmes règles que `vfs_create_as`
+pub fn vfs_mkdir_as(path: &str, mode: FileMode, cred: &Credentials) -> VfsResult<InodeId> {
+    check_parent(path, cred)?;
+    let id = mkdir_at(&root_dentry()?, path, mode)?;
+    set_creator(path, cred)?;
+    Ok(id)
+}
+
+/// Supprime un fichier pour `cred` (écriture et traversée sur le parent)
+pub fn vfs_remove_file_as(path: &str, cred: &Credentials) -> VfsResult<()> {
+    check_parent(path, cred)?;
+    vfs_remove_file(path)
+}
+
+/// Lien physique pour `cred` : `old_path` doit être atteignable, le
+/// parent de `new_path` modifiable
+pub fn vfs_link_as(old_path: &str, new_path: &str, cred: &Credentials) -> VfsResult<()> {
+    vfs_access(old_path, cred, 0)?;
+    check_parent(new_path, cred)?;
+    vfs_link(old_path, new_path)
+}
+
+/// Renomme pour `cred` : les deux parents doivent être modifiables
+pub fn vfs_rename_as(old_path: &str, new_path: &str, cred: &Credentials) -> VfsResult<()> {
+    check_parent(old_path, cred)?;
+    check_parent(new_path, cred)?;
+    vfs_rename(old_path, new_path)
+}
+
+/// Applique `change` (chmod, chown ou chgrp du gestionnaire de
+/// permissions) à l'inode de `path` pour `cred`. Le résultat est écrit
+/// dans l'inode ; il ne reste dans le gestionnaire que pour les systèmes
+/// de fichiers sans `setattr`.
+fn change_perms<F>(path: &str, cred: &Credentials, change: F) -> VfsResult<()>
+where
+    F: FnOnce(&mut PermissionManager, permissions::InodeKey) -> Result<(), PermissionError>,
+{
+    let stat = vfs_access(path, cred, 0)?;
+    let key = (stat.dev, stat.inode);
+    let updated = {
+        let mut pm = PERMISSION_MANAGER.lock();
+        let previous = pm.get_permissions(key);
+        // Sans réglage propre, on part de ce que rapporte l'inode
+        pm.set_permissions(key, previous.unwrap_or_else(|| Permissions::from_stat(&stat)));
+        let result = change(&mut pm, key);
+        let updated = pm.get_permissions(key);
+        match previous {
+            Some(perms) => pm.set_permissions(key, perms),
+            None => pm.remove_permissions(key),
+        }
+        result?;
+        updated.ok_or(VfsError::NotFound)?
     };
-    
-    let parent_path = if parent_path.is_empty() { "/" } else { parent_path };
-    
-    let parent_dentry = path_lookup(parent_path)?;
-    let parent_inode = parent_dentry.lock().inode.clone();
-    
-    parent_inode.lock().ops.lock().unlink(filename)?;
-    
+    match vfs_setattr(path, Some(FileMode::new(updated.mode())), Some(updated.uid()), Some(updated.gid())) {
+        Ok(()) => {
+            PERMISSION_MANAGER.lock().remove_permissions(key);
+            Ok(())
+        }
+        Err(VfsError::NotSupported) => {
+            PERMISSION_MANAGER.lock().set_permissions(key, updated);
+            Ok(())
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Change le mode de `path` pour `cred`
+pub fn vfs_chmod_as(path: &str, mode: u16, cred: &Credentials) -> VfsResult<()> {
+    change_perms(path, cred, |pm, key| pm.chmod(key, mode & 0o7777, cred))
+}
+
+/// Change le propriétaire de `path` pour `cred`
+pub fn vfs_chown_as(path: &str, uid: u32, cred: &Credentials) -> VfsResult<()> {
+    change_perms(path, cred, |pm, key| pm.chown(key, uid, cred))
+}
+
+/// Change le groupe de `path` pour `cred`
+pub fn vfs_chgrp_as(path: &str, gid: u32, cred: &Credentials) -> VfsResult<()> {
+    change_perms(path, cred, |pm, key| pm.chgrp(key, gid, cred))
+}
+
+/// Opérations de l'inode de `path`
+fn path_ops(path: &str) -> VfsResult<Arc<Mutex<dyn InodeOps>>> {
+    let dentry = path_lookup(path)?;
+    let inode = dentry.lock().inode.clone();
+    let ops = inode.lock().ops.clone();
+    Ok(ops)
+}
+
+/// Helper: Valeur d'un attribut étendu, sans contrôle de droits
+pub fn vfs_getxattr(path: &str, name: &str) -> VfsResult<Vec<u8>> {
+    let ops = path_ops(path)?;
+    let value = ops.lock().getxattr(name);
+    value
+}
+
+/// Helper: Définit un attribut étendu, sans contrôle de droits
+pub fn vfs_setxattr(path: &str, name: &str, value: &[u8], flags: u32) -> VfsResult<()> {
+    let ops = path_ops(path)?;
+    let result = ops.lock().setxattr(name, value, flags);
+    result
+}
+
+/// Helper: Noms des attributs étendus
+pub fn vfs_listxattr(path: &str) -> VfsResult<Vec<String>> {
+    let ops = path_ops(path)?;
+    let names = ops.lock().listxattr();
+    names
+}
+
+/// Helper: Supprime un attribut étendu, sans contrôle de droits
+pub fn vfs_removexattr(path: &str, name: &str) -> VfsResult<()> {
+    let ops = path_ops(path)?;
+    let result = ops.lock().removexattr(name);
+    result
+}
+
+/// Droits d'accès à l'attribut `name` de `path` pour `cred` : `user.*`
+/// suit les droits du fichier, `trusted.*` est réservé à CAP_SYS_ADMIN,
+/// tout comme l'écriture de `security.*` et `system.*`
+pub fn check_xattr_access(path: &str, name: &str, cred: &Credentials, write: bool) -> VfsResult<()> {
+    check_xattr_name(name)?;
+    let want = if write { permissions::ACCESS_WRITE } else { permissions::ACCESS_READ };
+    if name.starts_with("user.") {
+        vfs_access(path, cred, want)?;
+    } else {
+        vfs_access(path, cred, 0)?;
+        if (write || name.starts_with("trusted.")) && !cred.capable(CAP_SYS_ADMIN) {
+            return Err(VfsError::PermissionDenied);
+        }
+    }
     Ok(())
 }
+
+/// Helper: Change mode et/ou propriétaire, sans contrôle de droits
+pub fn vfs_setattr(path: &str, mode: Option<FileMode>, uid: Option<u32>, gid: Option<u32>) -> VfsResult<()> {
+    let dentry = path_lookup(path)?;
+    let inode = dentry.lock().inode.clone();
+    let ops = inode.lock().ops.clone();
+    let result = ops.lock().setattr(mode, uid, gid);
+    result
+}
+
+/// Helper: Occupation du système de fichiers monté qui contient `path`
+pub fn vfs_statfs(path: &str) -> VfsResult<StatFs> {
+    let mount = MOUNT_MANAGER.lock().find_mount(path).ok_or(VfsError::NotFound)?;
+    let mount = mount.lock();
+    let mut stat = mount.fs.statfs()?;
+    stat.readonly |= mount.flags.is_readonly();
+    Ok(stat)
+}
+
+/// Écrit sur disque les données en attente de tous les montages
+pub fn vfs_sync() -> VfsResult<()> {
+    MOUNT_MANAGER.lock().sync_all()?;
+    cache::sync_all();
+    Ok(())
+}
+
+/// Racine du VFS pour les tests (le noyau de test ne la monte pas)
+#[cfg(test)]
+pub(crate) fn test_vfs() {
+    if ROOT_DENTRY.lock().is_none() {
+        init_vfs().unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_chmod_chown_through_vfs() {
+        test_vfs();
+        let root = Credentials::root();
+        let owner = Credentials::new(1000, 1000);
+        vfs_create_as("/perm-test", FileMode::new(0o644), FileType::Regular, &root).unwrap();
+
+        // Réglages écrits dans l'inode, pas dans le gestionnaire
+        vfs_chown_as("/perm-test", 1000, &root).unwrap();
+        vfs_chmod_as("/perm-test", 0o600, &owner).unwrap();
+        let stat = vfs_stat("/perm-test").unwrap();
+        assert_eq!((stat.mode.0 & 0o7777, stat.uid), (0o600, 1000));
+        assert!(PERMISSION_MANAGER.lock().get_permissions((stat.dev, stat.inode)).is_none());
+        assert_eq!(vfs_access("/perm-test", &Credentials::new(1001, 1000), permissions::ACCESS_READ).err(), Some(VfsError::PermissionDenied));
+
+        // Seul le propriétaire change le mode ; chgrp vers un groupe dont
+        // il est membre
+        assert_eq!(vfs_chmod_as("/perm-test", 0o666, &Credentials::new(1001, 1000)), Err(VfsError::PermissionDenied));
+        assert_eq!(vfs_chgrp_as("/perm-test", 27, &owner), Err(VfsError::PermissionDenied));
+        vfs_chgrp_as("/perm-test", 1000, &owner).unwrap();
+        assert_eq!(vfs_stat("/perm-test").unwrap().gid, 1000);
+        vfs_remove_file("/perm-test").unwrap();
+    }
+}
@@ -0,0 +1,584 @@
+/// OverlayFS - Couche inscriptible au-dessus d'une arborescence en lecture seule
+///
+/// Les deux couches sont des répertoires du VFS : `lower` (support en
+/// lecture seule : CD-ROM, image de démarrage) et `upper` (en pratique un
+/// répertoire du ramfs). Une entrée est cherchée d'abord en haut, puis en
+/// bas ; les répertoires présents des deux côtés sont fusionnés.
+///
+/// - Copie vers le haut : toute modification d'une entrée du bas (écriture,
+///   troncature, chmod, xattr, renommage) la recopie d'abord en haut, avec
+///   ses répertoires parents.
+/// - Whiteout : supprimer une entrée du bas crée en haut un fichier vide
+///   portant l'attribut `trusted.overlay.whiteout`, qui la masque.
+/// - Répertoire opaque : un répertoire recréé à la place d'un whiteout
+///   reçoit `trusted.overlay.opaque` et cache le contenu du bas.
+///
+/// Les numéros d'inode de l'overlay sont attribués par chemin relatif, à
+/// la première rencontre.
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use spin::Mutex;
+
+use crate::fs::vfs_core::*;
+
+/// Attribut marquant un whiteout dans la couche haute
+const WHITEOUT_XATTR: &str = "trusted.overlay.whiteout";
+/// Attribut marquant un répertoire opaque dans la couche haute
+const OPAQUE_XATTR: &str = "trusted.overlay.opaque";
+/// Attributs internes, invisibles à travers l'overlay
+const PRIVATE_XATTR_PREFIX: &str = "trusted.overlay.";
+
+/// Numéro d'inode de la racine de l'overlay
+const ROOT_INODE: InodeId = 1;
+
+/// Couche qui fournit une entrée
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Layer {
+    Upper,
+    Lower,
+}
+
+/// Superblock de l'overlay
+pub struct OverlaySuperblock {
+    fs_id: FsId,
+}
+
+impl Superblock for OverlaySuperblock {
+    fn fs_name(&self) -> &str {
+        "overlay"
+    }
+
+    fn fs_id(&self) -> FsId {
+        self.fs_id
+    }
+
+    fn block_size(&self) -> u32 {
+        4096
+    }
+
+    fn total_blocks(&self) -> u64 {
+        0
+    }
+
+    fn free_blocks(&self) -> u64 {
+        0
+    }
+
+    fn total_inodes(&self) -> u64 {
+        0
+    }
+
+    fn free_inodes(&self) -> u64 {
+        0
+    }
+
+    fn is_readonly(&self) -> bool {
+        false
+    }
+
+    fn root_inode(&self) -> InodeId {
+        ROOT_INODE
+    }
+}
+
+/// Numéros d'inode attribués aux chemins relatifs ("" : racine)
+struct InodeMap {
+    paths: BTreeMap<InodeId, String>,
+    ids: BTreeMap<String, InodeId>,
+    next: InodeId,
+}
+
+impl InodeMap {
+    fn new() -> Self {
+        let mut map = Self { paths: BTreeMap::new(), ids: BTreeMap::new(), next: ROOT_INODE + 1 };
+        map.paths.insert(ROOT_INODE, String::new());
+        map.ids.insert(String::new(), ROOT_INODE);
+        map
+    }
+
+    fn id_for(&mut self, rel: &str) -> InodeId {
+        if let Some(&id) = self.ids.get(rel) {
+            return id;
+        }
+        let id = self.next;
+        self.next += 1;
+        self.paths.insert(id, rel.into());
+        self.ids.insert(rel.into(), id);
+        id
+    }
+
+    /// Oublie `rel` et tout ce qu'il contient (suppression, renommage)
+    fn forget(&mut self, rel: &str) {
+        let prefix = format!("{}/", rel);
+        let gone: Vec<String> = self.ids.keys().filter(|p| *p == rel || p.starts_with(&prefix)).cloned().collect();
+        for path in gone {
+            if let Some(id) = self.ids.remove(&path) {
+                self.paths.remove(&id);
+            }
+        }
+    }
+}
+
+/// État partagé entre le système de fichiers et ses inodes
+struct OverlayInner {
+    lower: String,
+    upper: String,
+    inodes: Mutex<InodeMap>,
+}
+
+fn join(rel: &str, name: &str) -> String {
+    format!("{}/{}", rel, name)
+}
+
+fn parent_of(rel: &str) -> &str {
+    rel.rfind('/').map_or("", |pos| &rel[..pos])
+}
+
+fn layer_path(base: &str, rel: &str) -> String {
+    if rel.is_empty() {
+        base.into()
+    } else {
+        format!("{}{}", base.trim_end_matches('/'), rel)
+    }
+}
+
+/// Entrées (nom, type) du répertoire `path`, sans "." ni ".."
+fn list_dir(path: &str) -> VfsResult<Vec<(String, FileType)>> {
+    let entries = super::path_ops(path)?.lock().readdir()?;
+    Ok(entries
+        .into_iter()
+        .filter(|e| e.name != "." && e.name != "..")
+        .map(|e| (e.name, e.file_type))
+        .collect())
+}
+
+impl OverlayInner {
+    fn upper_path(&self, rel: &str) -> String {
+        layer_path(&self.upper, rel)
+    }
+
+    fn lower_path(&self, rel: &str) -> String {
+        layer_path(&self.lower, rel)
+    }
+
+    fn is_whiteout(&self, rel: &str) -> bool {
+        super::vfs_getxattr(&self.upper_path(rel), WHITEOUT_XATTR).is_ok()
+    }
+
+    fn is_opaque(&self, rel: &str) -> bool {
+        super::vfs_getxattr(&self.upper_path(rel), OPAQUE_XATTR).is_ok()
+    }
+
+    /// L'entrée du bas `rel` est-elle visible (aucun whiteout sur elle, aucun
+    /// répertoire opaque au-dessus) ?
+    fn lower_visible(&self, rel: &str) -> bool {
+        if !rel.is_empty() && self.is_whiteout(rel) {
+            return false;
+        }
+        let mut ancestor = parent_of(rel);
+        while !ancestor.is_empty() {
+            if self.is_opaque(ancestor) {
+                return false;
+            }
+            ancestor = parent_of(ancestor);
+        }
+        super::vfs_stat(&self.lower_path(rel)).is_ok()
+    }
+
+    /// Couche qui fournit `rel`, None si l'entrée n'existe pas
+    fn layer(&self, rel: &str) -> Option<Layer> {
+        if super::vfs_stat(&self.upper_path(rel)).is_ok() {
+            if rel.is_empty() || !self.is_whiteout(rel) {
+                return Some(Layer::Upper);
+            }
+            return None;
+        }
+        if self.lower_visible(rel) {
+            Some(Layer::Lower)
+        } else {
+            None
+        }
+    }
+
+    fn resolve(&self, rel: &str) -> VfsResult<String> {
+        match self.layer(rel) {
+            Some(Layer::Upper) => Ok(self.upper_path(rel)),
+            Some(Layer::Lower) => Ok(self.lower_path(rel)),
+            None => Err(VfsError::NotFound),
+        }
+    }
+
+    /// Recopie `rel` (et ses parents) dans la couche haute
+    fn copy_up(&self, rel: &str) -> VfsResult<()> {
+        match self.layer(rel) {
+            Some(Layer::Upper) => return Ok(()),
+            Some(Layer::Lower) => {}
+            None => return Err(VfsError::NotFound),
+        }
+        self.copy_up(parent_of(rel))?;
+
+        let lower = self.lower_path(rel);
+        let upper = self.upper_path(rel);
+        let stat = super::vfs_stat(&lower)?;
+        match stat.file_type {
+            FileType::Directory => {
+                super::vfs_create(&upper, stat.mode, FileType::Directory)?;
+            }
+            FileType::Regular => {
+                let content = super::vfs_read_file(&lower)?;
+                super::vfs_create(&upper, stat.mode, FileType::Regular)?;
+                super::vfs_write_at(&upper, 0, &content)?;
+            }
+            _ => return Err(VfsError::NotSupported),
+        }
+        match super::vfs_setattr(&upper, Some(stat.mode), Some(stat.uid), Some(stat.gid)) {
+            Ok(()) | Err(VfsError::NotSupported) => {}
+            Err(e) => return Err(e),
+        }
+        // Attributs étendus : au mieux, selon ce que gardent les deux couches
+        for name in super::vfs_listxattr(&lower).unwrap_or_default() {
+            if let Ok(value) = super::vfs_getxattr(&lower, &name) {
+                let _ = super::vfs_setxattr(&upper, &name, &value, 0);
+            }
+        }
+        Ok(())
+    }
+
+    /// Masque l'entrée du bas `rel`
+    fn whiteout(&self, rel: &str) -> VfsResult<()> {
+        self.copy_up(parent_of(rel))?;
+        let upper = self.upper_path(rel);
+        super::vfs_create(&upper, FileMode::new(0), FileType::Regular)?;
+        super::vfs_setxattr(&upper, WHITEOUT_XATTR, b"y", XATTR_CREATE)
+    }
+
+    /// Retire le whiteout de `rel` s'il y en a un ; retourne s'il existait
+    fn clear_whiteout(&self, rel: &str) -> VfsResult<bool> {
+        if !self.is_whiteout(rel) {
+            return Ok(false);
+        }
+        super::vfs_remove_file(&self.upper_path(rel))?;
+        Ok(true)
+    }
+
+    /// Contenu fusionné du répertoire `rel`
+    fn merged_entries(&self, rel: &str) -> VfsResult<BTreeMap<String, FileType>> {
+        let mut entries = BTreeMap::new();
+        let upper = self.layer(rel) == Some(Layer::Upper);
+        if (!upper || !self.is_opaque(rel)) && self.lower_visible(rel) {
+            for (name, file_type) in list_dir(&self.lower_path(rel))? {
+                entries.insert(name, file_type);
+            }
+        }
+        if upper {
+            for (name, file_type) in list_dir(&self.upper_path(rel))? {
+                if self.is_whiteout(&join(rel, &name)) {
+                    entries.remove(&name);
+                } else {
+                    entries.insert(name, file_type);
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Supprime `rel` de l'overlay (fichier ou répertoire vide)
+    fn remove(&self, rel: &str) -> VfsResult<()> {
+        let layer = self.layer(rel).ok_or(VfsError::NotFound)?;
+        let lower_exists = self.lower_visible(rel);
+        if layer == Layer::Upper {
+            let upper = self.upper_path(rel);
+            if super::vfs_stat(&upper)?.file_type == FileType::Directory {
+                // Il ne reste que des whiteouts dans le répertoire du haut
+                for (name, _) in list_dir(&upper)? {
+                    super::vfs_remove_file(&join(&upper, &name))?;
+                }
+            }
+            super::vfs_remove_file(&upper)?;
+        }
+        if lower_exists {
+            self.whiteout(rel)?;
+        }
+        self.inodes.lock().forget(rel);
+        Ok(())
+    }
+}
+
+/// Système de fichiers overlay
+pub struct OverlayFileSystem {
+    inner: Arc<OverlayInner>,
+    sb: Arc<OverlaySuperblock>,
+}
+
+impl OverlayFileSystem {
+    /// Overlay de `lower` (lecture seule) sous `upper` (inscriptible) ;
+    /// les deux doivent être des répertoires existants du VFS
+    pub fn new(lower: &str, upper: &str) -> VfsResult<Self> {
+        for path in [lower, upper] {
+            if super::vfs_stat(path)?.file_type != FileType::Directory {
+                return Err(VfsError::NotDirectory);
+            }
+        }
+        Ok(Self {
+            inner: Arc::new(OverlayInner {
+                lower: lower.into(),
+                upper: upper.into(),
+                inodes: Mutex::new(InodeMap::new()),
+            }),
+            sb: Arc::new(OverlaySuperblock { fs_id: alloc_fs_id() }),
+        })
+    }
+}
+
+impl FileSystemOps for OverlayFileSystem {
+    fn superblock(&self) -> Arc<dyn Superblock> {
+        self.sb.clone()
+    }
+
+    fn get_inode(&self, inode_id: InodeId) -> VfsResult<Arc<Mutex<dyn InodeOps>>> {
+        let rel = self.inner.inodes.lock().paths.get(&inode_id).cloned().ok_or(VfsError::NotFound)?;
+        if !rel.is_empty() && self.inner.layer(&rel).is_none() {
+            return Err(VfsError::NotFound);
+        }
+        Ok(Arc::new(Mutex::new(OverlayInodeOps { inner: self.inner.clone(), id: inode_id, rel })))
+    }
+
+    fn sync(&self) -> VfsResult<()> {
+        Ok(())
+    }
+
+    fn unmount(&self) -> VfsResult<()> {
+        Ok(())
+    }
+
+    /// Place libre de la couche haute, seule à recevoir des écritures
+    fn statfs(&self) -> VfsResult<StatFs> {
+        let mut stat = super::vfs_statfs(&self.inner.upper)?;
+        stat.fs_name = String::from(self.sb.fs_name());
+        Ok(stat)
+    }
+}
+
+/// Inode de l'overlay : un chemin relatif, résolu à chaque opération
+struct OverlayInodeOps {
+    inner: Arc<OverlayInner>,
+    id: InodeId,
+    rel: String,
+}
+
+impl OverlayInodeOps {
+    fn child(&self, name: &str) -> String {
+        join(&self.rel, name)
+    }
+
+    fn check_dir(&self) -> VfsResult<()> {
+        match super::vfs_stat(&self.inner.resolve(&self.rel)?)?.file_type {
+            FileType::Directory => Ok(()),
+            _ => Err(VfsError::NotDirectory),
+        }
+    }
+}
+
+impl InodeOps for OverlayInodeOps {
+    fn read(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        super::vfs_read_at(&self.inner.resolve(&self.rel)?, offset, buf)
+    }
+
+    fn write(&mut self, offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        self.inner.copy_up(&self.rel)?;
+        super::vfs_write_at(&self.inner.upper_path(&self.rel), offset, buf)
+    }
+
+    fn stat(&self) -> VfsResult<FileStat> {
+        let mut stat = super::vfs_stat(&self.inner.resolve(&self.rel)?)?;
+        stat.inode = self.id;
+        Ok(stat)
+    }
+
+    fn lookup(&self, name: &str) -> VfsResult<InodeId> {
+        if name == "." {
+            return Ok(self.id);
+        }
+        if name == ".." {
+            return Err(VfsError::NotSupported); // Géré par la dentry
+        }
+        self.check_dir()?;
+        let child = self.child(name);
+        match self.inner.layer(&child) {
+            Some(_) => Ok(self.inner.inodes.lock().id_for(&child)),
+            None => Err(VfsError::NotFound),
+        }
+    }
+
+    fn create(&mut self, name: &str, mode: FileMode, file_type: FileType) -> VfsResult<InodeId> {
+        self.check_dir()?;
+        let child = self.child(name);
+        if self.inner.layer(&child).is_some() {
+            return Err(VfsError::AlreadyExists);
+        }
+        self.inner.copy_up(&self.rel)?;
+        let replaced_whiteout = self.inner.clear_whiteout(&child)?;
+        let upper = self.inner.upper_path(&child);
+        super::vfs_create(&upper, mode, file_type)?;
+        // Un répertoire recréé à la place d'un autre du bas ne montre pas l'ancien contenu
+        if replaced_whiteout && file_type == FileType::Directory {
+            super::vfs_setxattr(&upper, OPAQUE_XATTR, b"y", 0)?;
+        }
+        Ok(self.inner.inodes.lock().id_for(&child))
+    }
+
+    fn unlink(&mut self, name: &str) -> VfsResult<()> {
+        self.check_dir()?;
+        let child = self.child(name);
+        if super::vfs_stat(&self.inner.resolve(&child)?)?.file_type == FileType::Directory {
+            return Err(VfsError::IsDirectory);
+        }
+        self.inner.remove(&child)
+    }
+
+    fn mkdir(&mut self, name: &str, mode: FileMode) -> VfsResult<InodeId> {
+        self.create(name, mode, FileType::Directory)
+    }
+
+    fn rmdir(&mut self, name: &str) -> VfsResult<()> {
+        self.check_dir()?;
+        let child = self.child(name);
+        if super::vfs_stat(&self.inner.resolve(&child)?)?.file_type != FileType::Directory {
+            return Err(VfsError::NotDirectory);
+        }
+        if !self.inner.merged_entries(&child)?.is_empty() {
+            return Err(VfsError::NotEmpty);
+        }
+        self.inner.remove(&child)
+    }
+
+    fn readdir(&self) -> VfsResult<Vec<DirEntry>> {
+        self.check_dir()?;
+        let mut entries = Vec::new();
+        entries.push(DirEntry::new(self.id, ".".into(), FileType::Directory));
+        let merged = self.inner.merged_entries(&self.rel)?;
+        let mut inodes = self.inner.inodes.lock();
+        for (name, file_type) in merged {
+            let id = inodes.id_for(&join(&self.rel, &name));
+            entries.push(DirEntry::new(id, name, file_type));
+        }
+        Ok(entries)
+    }
+
+    fn truncate(&mut self, size: u64) -> VfsResult<()> {
+        self.inner.copy_up(&self.rel)?;
+        let ops = super::path_ops(&self.inner.upper_path(&self.rel))?;
+        let result = ops.lock().truncate(size);
+        result
+    }
+
+    fn rename(&mut self, old_name: &str, new_dir: InodeId, new_name: &str) -> VfsResult<()> {
+        let new_parent = self.inner.inodes.lock().paths.get(&new_dir).cloned().ok_or(VfsError::NotFound)?;
+        let old = self.child(old_name);
+        let new = join(&new_parent, new_name);
+        if old == new {
+            return Ok(());
+        }
+        let stat = super::vfs_stat(&self.inner.resolve(&old)?)?;
+        let lower_exists = self.inner.lower_visible(&old);
+        // Comme Linux (EXDEV) : un répertoire du bas n'est pas déplacé,
+        // l'appelant se rabat sur copie + suppression
+        if stat.file_type == FileType::Directory && lower_exists {
+            return Err(VfsError::NotSupported);
+        }
+        if let Some(layer) = self.inner.layer(&new) {
+            let target = match layer {
+                Layer::Upper => self.inner.upper_path(&new),
+                Layer::Lower => self.inner.lower_path(&new),
+            };
+            if super::vfs_stat(&target)?.file_type == FileType::Directory {
+                return Err(VfsError::IsDirectory);
+            }
+            self.inner.remove(&new)?;
+        }
+
+        self.inner.copy_up(&old)?;
+        self.inner.copy_up(&new_parent)?;
+        self.inner.clear_whiteout(&new)?;
+        super::vfs_rename(&self.inner.upper_path(&old), &self.inner.upper_path(&new))?;
+        if lower_exists {
+            self.inner.whiteout(&old)?;
+        }
+        let mut inodes = self.inner.inodes.lock();
+        inodes.forget(&old);
+        inodes.forget(&new);
+        Ok(())
+    }
+
+    fn setattr(&mut self, mode: Option<FileMode>, uid: Option<u32>, gid: Option<u32>) -> VfsResult<()> {
+        self.inner.copy_up(&self.rel)?;
+        super::vfs_setattr(&self.inner.upper_path(&self.rel), mode, uid, gid)
+    }
+
+    fn getxattr(&self, name: &str) -> VfsResult<Vec<u8>> {
+        if name.starts_with(PRIVATE_XATTR_PREFIX) {
+            return Err(VfsError::NoAttribute);
+        }
+        super::vfs_getxattr(&self.inner.resolve(&self.rel)?, name)
+    }
+
+    fn setxattr(&mut self, name: &str, value: &[u8], flags: u32) -> VfsResult<()> {
+        if name.starts_with(PRIVATE_XATTR_PREFIX) {
+            return Err(VfsError::PermissionDenied);
+        }
+        self.inner.copy_up(&self.rel)?;
+        super::vfs_setxattr(&self.inner.upper_path(&self.rel), name, value, flags)
+    }
+
+    fn listxattr(&self) -> VfsResult<Vec<String>> {
+        let names = super::vfs_listxattr(&self.inner.resolve(&self.rel)?)?;
+        Ok(names.into_iter().filter(|n| !n.starts_with(PRIVATE_XATTR_PREFIX)).collect())
+    }
+
+    fn removexattr(&mut self, name: &str) -> VfsResult<()> {
+        if name.starts_with(PRIVATE_XATTR_PREFIX) {
+            return Err(VfsError::NoAttribute);
+        }
+        self.inner.copy_up(&self.rel)?;
+        super::vfs_removexattr(&self.inner.upper_path(&self.rel), name)
+    }
+}
+
+/// Monte un overlay de `lower` et `upper` sur `target`
+pub fn mount_overlay(target: &str, lower: &str, upper: &str) -> VfsResult<()> {
+    let fs = OverlayFileSystem::new(lower, upper)?;
+    super::mount_fs(target, Arc::new(fs), super::MountFlags::new(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_overlay_copy_up_and_whiteout() {
+        for dir in ["/ovl-lower", "/ovl-lower/etc", "/ovl-upper", "/ovl"] {
+            crate::fs::vfs_mkdir(dir).unwrap();
+        }
+        crate::fs::vfs_write_file("/ovl-lower/etc/motd", b"bas").unwrap();
+        crate::fs::vfs_write_file("/ovl-lower/etc/hosts", b"127.0.0.1").unwrap();
+        mount_overlay("/ovl", "/ovl-lower", "/ovl-upper").unwrap();
+
+        // Lecture depuis le bas, écriture recopiée en haut
+        assert_eq!(crate::fs::vfs_read_file("/ovl/etc/motd").unwrap(), b"bas");
+        crate::fs::vfs_write_file("/ovl/etc/motd", b"haut").unwrap();
+        assert_eq!(crate::fs::vfs_read_file("/ovl/etc/motd").unwrap(), b"haut");
+        assert_eq!(crate::fs::vfs_read_file("/ovl-upper/etc/motd").unwrap(), b"haut");
+        assert_eq!(crate::fs::vfs_read_file("/ovl-lower/etc/motd").unwrap(), b"bas");
+
+        // Suppression d'un fichier du bas : masqué, pas effacé
+        crate::fs::vfs_remove_file("/ovl/etc/hosts").unwrap();
+        assert!(crate::fs::vfs_stat("/ovl/etc/hosts").is_err());
+        assert!(crate::fs::vfs_stat("/ovl-lower/etc/hosts").is_ok());
+        assert_eq!(crate::fs::vfs_ls("/ovl/etc").unwrap(), [".", "motd"]);
+        assert!(crate::fs::vfs_listxattr("/ovl/etc/motd").unwrap().is_empty());
+    }
+}
@@ -4,6 +4,13 @@
 
 use alloc::collections::BTreeMap;
 use spin::Mutex;
+use crate::process::cred::{Credentials, Gid, Uid, CAP_CHOWN, CAP_DAC_OVERRIDE, CAP_FOWNER};
+use super::{FsId, InodeId};
+
+/// Droits demandés (masque de access())
+pub const ACCESS_READ: u8 = 4;
+pub const ACCESS_WRITE: u8 = 2;
+pub const ACCESS_EXEC: u8 = 1;
 
 /// Permissions Unix (mode)
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -42,6 +49,11 @@ impl Permissions {
         Self { mode, uid, gid }
     }
     
+    /// Permissions d'un inode du VFS
+    pub fn from_stat(stat: &crate::fs::FileStat) -> Self {
+        Self::new(stat.mode.0, stat.uid, stat.gid)
+    }
+    
     /// Permissions par défaut (0644, root:root)
     pub fn default() -> Self {
         Self::new(0o644, 0, 0)
@@ -125,6 +137,24 @@ impl Permissions {
         }
     }
     
+    /// Vérifie les droits `want` (ACCESS_*) pour une identité : la classe
+    /// propriétaire, groupe (effectif ou supplémentaire) ou autres est
+    /// choisie d'après l'UID/GID effectifs ; CAP_DAC_OVERRIDE passe outre
+    pub fn allows(&self, cred: &Credentials, want: u8) -> bool {
+        if cred.capable(CAP_DAC_OVERRIDE) {
+            return true;
+        }
+        let shift = if cred.euid == self.uid {
+            6
+        } else if cred.in_group(self.gid) {
+            3
+        } else {
+            0
+        };
+        let granted = ((self.mode >> shift) & 0o7) as u8;
+        granted & want == want
+    }
+    
     /// Vérifie si le SUID est activé
     pub fn has_suid(&self) -> bool {
         (self.mode & Self::SUID) != 0
@@ -154,10 +184,24 @@ pub enum PermissionError {
     InvalidArgument,
 }
 
+/// Inode désigné par son système de fichiers et son numéro : deux
+/// montages peuvent réutiliser les mêmes numéros
+pub type InodeKey = (FsId, InodeId);
+
+impl From<PermissionError> for super::VfsError {
+    fn from(err: PermissionError) -> Self {
+        match err {
+            PermissionError::PermissionDenied | PermissionError::NotPermitted => super::VfsError::PermissionDenied,
+            PermissionError::NotFound => super::VfsError::NotFound,
+            PermissionError::InvalidArgument => super::VfsError::InvalidArgument,
+        }
+    }
+}
+
 /// Gestionnaire de permissions
 pub struct PermissionManager {
     /// Permissions par inode
-    permissions: BTreeMap<u64, Permissions>,
+    permissions: BTreeMap<InodeKey, Permissions>,
 }
 
 impl PermissionManager {
@@ -169,22 +213,28 @@ impl PermissionManager {
     }
     
     /// Définit les permissions pour un inode
-    pub fn set_permissions(&mut self, inode: u64, perms: Permissions) {
+    pub fn set_permissions(&mut self, inode: InodeKey, perms: Permissions) {
         self.permissions.insert(inode, perms);
     }
     
     /// Récupère les permissions d'un inode
-    pub fn get_permissions(&self, inode: u64) -> Option<Permissions> {
+    pub fn get_permissions(&self, inode: InodeKey) -> Option<Permissions> {
         self.permissions.get(&inode).copied()
     }
     
+    /// Oublie les permissions propres d'un inode (le système de fichiers
+    /// fait de nouveau foi)
+    pub fn remove_permissions(&mut self, inode: InodeKey) {
+        self.permissions.remove(&inode);
+    }
+    
     /// Change le mode (chmod)
     /// 
-    /// Seul le propriétaire ou root peut changer le mode
-    pub fn chmod(&mut self, inode: u64, mode: u16, caller_uid: u32) -> Result<(), PermissionError> {
+    /// Seul le propriétaire ou un appelant doté de CAP_FOWNER peut
+    /// changer le mode
+    pub fn chmod(&mut self, inode: InodeKey, mode: u16, caller: &Credentials) -> Result<(), PermissionError> {
         if let Some(perms) = self.permissions.get_mut(&inode) {
-            // Vérifier que l'appelant est le propriétaire ou root
-            if caller_uid != 0 && caller_uid != perms.uid {
+            if !caller.capable(CAP_FOWNER) && caller.euid != perms.uid {
                 return Err(PermissionError::PermissionDenied);
             }
             
@@ -197,9 +247,9 @@ impl PermissionManager {
     
     /// Change le propriétaire (chown)
     /// 
-    /// Seul root peut changer le propriétaire
-    pub fn chown(&mut self, inode: u64, uid: u32, caller_uid: u32) -> Result<(), PermissionError> {
-        if caller_uid != 0 {
+    /// Seul un appelant doté de CAP_CHOWN peut changer le propriétaire
+    pub fn chown(&mut self, inode: InodeKey, uid: Uid, caller: &Credentials) -> Result<(), PermissionError> {
+        if !caller.capable(CAP_CHOWN) {
             return Err(PermissionError::NotPermitted);
         }
         
@@ -213,13 +263,17 @@ impl PermissionManager {
     
     /// Change le groupe (chgrp)
     /// 
-    /// Le propriétaire ou root peut changer le groupe
-    pub fn chgrp(&mut self, inode: u64, gid: u32, caller_uid: u32) -> Result<(), PermissionError> {
+    /// Le propriétaire ou CAP_CHOWN peut changer le groupe ; le
+    /// propriétaire doit appartenir au nouveau groupe
+    pub fn chgrp(&mut self, inode: InodeKey, gid: Gid, caller: &Credentials) -> Result<(), PermissionError> {
         if let Some(perms) = self.permissions.get_mut(&inode) {
             // Vérifier que l'appelant est le propriétaire ou root
-            if caller_uid != 0 && caller_uid != perms.uid {
+            if !caller.capable(CAP_CHOWN) && caller.euid != perms.uid {
                 return Err(PermissionError::PermissionDenied);
             }
+            if !caller.capable(CAP_CHOWN) && !caller.in_group(gid) {
+                return Err(PermissionError::NotPermitted);
+            }
             
             perms.set_gid(gid);
             Ok(())
@@ -229,26 +283,21 @@ impl PermissionManager {
     }
     
     /// Vérifie l'accès (access)
-    pub fn check_access(&self, inode: u64, uid: u32, gid: u32, mode: u8) -> bool {
-        if let Some(perms) = self.permissions.get(&inode) {
-            let read = (mode & 4) != 0;
-            let write = (mode & 2) != 0;
-            let exec = (mode & 1) != 0;
-            
-            if read && !perms.can_read(uid, gid) {
-                return false;
-            }
-            if write && !perms.can_write(uid, gid) {
-                return false;
-            }
-            if exec && !perms.can_execute(uid, gid) {
-                return false;
-            }
-            
-            true
-        } else {
-            false
-        }
+    pub fn check_access(&self, inode: InodeKey, cred: &Credentials, mode: u8) -> bool {
+        self.permissions
+            .get(&inode)
+            .map_or(false, |perms| perms.allows(cred, mode))
+    }
+    
+    /// Droits `want` de `cred` sur un inode du VFS : les permissions
+    /// enregistrées pour cet inode (chmod par inode) priment sur celles
+    /// que rapporte le système de fichiers
+    pub fn check_stat(&self, stat: &crate::fs::FileStat, cred: &Credentials, want: u8) -> bool {
+        self.permissions
+            .get(&(stat.dev, stat.inode))
+            .copied()
+            .unwrap_or_else(|| Permissions::from_stat(stat))
+            .allows(cred, want)
     }
     
     /// Retourne les statistiques
@@ -325,26 +374,69 @@ mod tests {
     #[test_case]
     fn test_chmod() {
         let mut manager = PermissionManager::new();
-        manager.set_permissions(1, Permissions::new(0o644, 1000, 1000));
+        manager.set_permissions((1, 1), Permissions::new(0o644, 1000, 1000));
         
         // Propriétaire peut changer le mode
-        assert!(manager.chmod(1, 0o755, 1000).is_ok());
-        assert_eq!(manager.get_permissions(1).unwrap().mode(), 0o755);
+        assert!(manager.chmod((1, 1), 0o755, &Credentials::new(1000, 1000)).is_ok());
+        assert_eq!(manager.get_permissions((1, 1)).unwrap().mode(), 0o755);
         
-        // Autre utilisateur ne peut pas
-        assert!(manager.chmod(1, 0o777, 1001).is_err());
+        // Autre utilisateur ne peut pas, sauf avec CAP_FOWNER
+        let mut other = Credentials::new(1001, 1000);
+        assert!(manager.chmod((1, 1), 0o777, &other).is_err());
+        other.caps = CAP_FOWNER;
+        assert!(manager.chmod((1, 1), 0o700, &other).is_ok());
+
+        // root sans CAP_FOWNER : refusé
+        let mut root = Credentials::root();
+        root.drop_caps(CAP_FOWNER);
+        assert!(manager.chmod((1, 1), 0o777, &root).is_err());
     }
     
     #[test_case]
     fn test_chown() {
         let mut manager = PermissionManager::new();
-        manager.set_permissions(1, Permissions::new(0o644, 1000, 1000));
+        manager.set_permissions((1, 1), Permissions::new(0o644, 1000, 1000));
         
         // Seul root peut changer le propriétaire
-        assert!(manager.chown(1, 2000, 0).is_ok());
-        assert_eq!(manager.get_permissions(1).unwrap().uid(), 2000);
+        assert!(manager.chown((1, 1), 2000, &Credentials::root()).is_ok());
+        assert_eq!(manager.get_permissions((1, 1)).unwrap().uid(), 2000);
         
         // Utilisateur normal ne peut pas
-        assert!(manager.chown(1, 3000, 1000).is_err());
+        assert!(manager.chown((1, 1), 3000, &Credentials::new(1000, 1000)).is_err());
+    }
+    
+    #[test_case]
+    fn test_overrides_keyed_by_device() {
+        let mut manager = PermissionManager::new();
+        manager.set_permissions((1, 5), Permissions::new(0o600, 0, 0));
+        let user = Credentials::new(1000, 1000);
+
+        // Même numéro d'inode sur un autre système de fichiers : pas touché
+        let mut stat = crate::fs::FileStat::new(5, crate::fs::FileType::Regular);
+        stat.dev = 2;
+        assert!(manager.check_stat(&stat, &user, ACCESS_READ));
+        stat.dev = 1;
+        assert!(!manager.check_stat(&stat, &user, ACCESS_READ));
+    }
+    
+    #[test_case]
+    fn test_supplementary_groups() {
+        let perms = Permissions::new(0o640, 0, 27);
+        let mut user = Credentials::new(1000, 1000);
+        assert!(!perms.allows(&user, ACCESS_READ));
+        
+        // Membre du groupe propriétaire par un groupe supplémentaire
+        user.groups.push(27);
+        assert!(perms.allows(&user, ACCESS_READ));
+        assert!(!perms.allows(&user, ACCESS_READ | ACCESS_WRITE));
+        assert!(perms.allows(&Credentials::root(), ACCESS_WRITE));
+
+        // Le contournement vient de CAP_DAC_OVERRIDE, pas de l'UID 0
+        let mut root = Credentials::root();
+        root.drop_caps(CAP_DAC_OVERRIDE);
+        assert!(!perms.allows(&root, ACCESS_WRITE));
+        let mut backup = Credentials::new(34, 34);
+        backup.caps = CAP_DAC_OVERRIDE;
+        assert!(perms.allows(&backup, ACCESS_READ | ACCESS_WRITE));
     }
 }
@@ -0,0 +1,144 @@
+/// poll() : attente de disponibilité sur des descripteurs
+///
+/// Chaque inode rend son état par `InodeOps::poll` (un fichier ordinaire
+/// est toujours prêt, comme sous Linux) ; les périphériques le tiennent de
+/// `DeviceOps::poll`. Les couches qui font évoluer cet état (entrée des
+/// terminaux, tubes, sockets) appellent `wake()`, utilisable sous verrou
+/// et en interruption : il ne fait qu'avancer le compteur d'événements de
+/// la file d'attente. Un thread dans `poll` dort jusqu'au prochain
+/// événement ou tick d'horloge, puis réévalue tous ses descripteurs
+/// (déclenchement par niveau).
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use super::{path_lookup, FD_MANAGER};
+use crate::scheduler::{ticks, TIMER_HZ};
+
+/// Événements (valeurs de Linux)
+pub const POLLIN: i16 = 0x001;
+pub const POLLPRI: i16 = 0x002;
+pub const POLLOUT: i16 = 0x004;
+/// Toujours rapportés, même non demandés
+pub const POLLERR: i16 = 0x008;
+pub const POLLHUP: i16 = 0x010;
+pub const POLLNVAL: i16 = 0x020;
+
+/// `struct pollfd`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PollFd {
+    /// Descripteur ; négatif : entrée ignorée
+    pub fd: i32,
+    /// Événements attendus
+    pub events: i16,
+    /// Événements survenus, rempli par poll
+    pub revents: i16,
+}
+
+impl PollFd {
+    pub const fn new(fd: i32, events: i16) -> Self {
+        Self { fd, events, revents: 0 }
+    }
+}
+
+/// Compteur d'événements de la file d'attente de poll
+static EVENTS: AtomicU64 = AtomicU64::new(0);
+
+/// Signale un changement de disponibilité ; les threads dans poll
+/// réévalueront leurs descripteurs
+pub fn wake() {
+    EVENTS.fetch_add(1, Ordering::Release);
+}
+
+/// Relevé du compteur, à faire avant d'examiner la condition attendue
+pub fn events() -> u64 {
+    EVENTS.load(Ordering::Acquire)
+}
+
+/// Dort jusqu'à un événement postérieur au relevé `seen`
+pub fn wait_event(seen: u64) {
+    while events() == seen {
+        crate::power::cpu_idle();
+    }
+}
+
+/// État d'un descripteur de `pid`
+fn fd_events(pid: u64, fd: usize) -> i16 {
+    let (path, fifo, mode) = match FD_MANAGER.lock().get_table(pid).and_then(|table| table.get(fd)) {
+        Ok(desc) => (desc.path.clone(), desc.fifo, desc.mode),
+        Err(_) => return POLLNVAL,
+    };
+    if let Some(id) = fifo {
+        let read = mode != super::OpenMode::WriteOnly;
+        let write = mode != super::OpenMode::ReadOnly;
+        return crate::ipc::PIPE_MANAGER.lock().poll_events(id, read, write);
+    }
+    match path_lookup(&path) {
+        Ok(dentry) => {
+            let inode = dentry.lock().inode.clone();
+            let ops = inode.lock().ops.clone();
+            let events = ops.lock().poll();
+            events
+        }
+        // Fichier disparu sous le descripteur
+        Err(_) => POLLERR,
+    }
+}
+
+/// Remplit `revents` de chaque entrée ; retourne le nombre d'entrées prêtes
+pub fn scan(pid: u64, fds: &mut [PollFd]) -> usize {
+    let mut ready = 0;
+    for pfd in fds.iter_mut() {
+        pfd.revents = if pfd.fd < 0 {
+            0
+        } else {
+            fd_events(pid, pfd.fd as usize) & (pfd.events | POLLERR | POLLHUP | POLLNVAL)
+        };
+        if pfd.revents != 0 {
+            ready += 1;
+        }
+    }
+    ready
+}
+
+/// Délai en ticks, arrondi au tick supérieur
+fn timeout_ticks(timeout_ms: u64) -> u64 {
+    (timeout_ms.saturating_mul(TIMER_HZ) + 999) / 1000
+}
+
+/// Attend qu'une entrée de `fds` soit prête. `timeout_ms` négatif :
+/// attente illimitée ; 0 : simple relevé. Retourne le nombre d'entrées
+/// prêtes, 0 à l'expiration du délai.
+pub fn poll(pid: u64, fds: &mut [PollFd], timeout_ms: i64) -> usize {
+    let deadline = if timeout_ms < 0 { None } else { Some(ticks() + timeout_ticks(timeout_ms as u64)) };
+    let expired = || deadline.map_or(false, |deadline| ticks() >= deadline);
+    loop {
+        // Relevé du compteur avant l'examen : un événement survenu pendant
+        // l'examen n'est pas perdu
+        let seen = events();
+        let ready = scan(pid, fds);
+        if ready > 0 || expired() {
+            return ready;
+        }
+        // Chaque interruption (dont le tick) réveille le CPU
+        while events() == seen && !expired() {
+            crate::power::cpu_idle();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_poll_invalid_descriptors() {
+        let mut fds = [PollFd::new(-1, POLLIN), PollFd::new(1000, POLLIN)];
+        // Aucun processus 0xdead : tout descripteur positif est invalide
+        assert_eq!(poll(0xdead, &mut fds, 0), 1);
+        assert_eq!(fds[0].revents, 0);
+        assert_eq!(fds[1].revents, POLLNVAL);
+        assert_eq!(timeout_ticks(1), 1);
+        assert_eq!(timeout_ticks(1000), TIMER_HZ);
+    }
+}
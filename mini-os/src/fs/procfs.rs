@@ -0,0 +1,567 @@
+/// ProcFS - Système de fichiers virtuel exposant l'état du noyau
+///
+/// Monté sur /proc. Aucun contenu n'est stocké : chaque lecture régénère le
+/// texte à partir de PROCESS_MANAGER, de l'allocateur et de SOCKET_TABLE.
+///
+/// Arborescence :
+/// - /proc/meminfo, /proc/uptime, /proc/stat, /proc/cpuinfo, /proc/cmdline
+/// - /proc/fault_inject (seul fichier inscriptible, cf. `fault_inject`)
+/// - /proc/perf (compteurs de performance, cf. `perf`)
+/// - /proc/net/tcp, /proc/net/udp
+/// - /proc/block/<dev>/info (identité et attributs SMART des disques)
+/// - /proc/<pid>/status
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use spin::Mutex;
+
+use crate::fs::vfs_core::*;
+
+/// Base des numéros d'inode des répertoires de processus
+const PID_INODE_BASE: InodeId = 1 << 32;
+/// Nombre d'inodes réservés par processus
+const PID_INODE_STRIDE: InodeId = 16;
+/// Base des numéros d'inode de /proc/block/<dev> (indice dans `ata_info::list`)
+const BLOCK_INODE_BASE: InodeId = 1 << 24;
+/// Nombre d'inodes réservés par disque
+const BLOCK_INODE_STRIDE: InodeId = 2;
+
+/// Nœud de l'arborescence /proc, encodé dans le numéro d'inode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcNode {
+    Root,
+    MemInfo,
+    Uptime,
+    NetDir,
+    NetTcp,
+    NetUdp,
+    Stat,
+    CpuInfo,
+    CmdLine,
+    DiskStats,
+    BlockDir,
+    FaultInject,
+    Perf,
+    BlockDev(u64),
+    BlockInfo(u64),
+    PidDir(u64),
+    PidStatus(u64),
+}
+
+impl ProcNode {
+    /// Numéro d'inode du nœud
+    pub fn to_inode(self) -> InodeId {
+        match self {
+            ProcNode::Root => 1,
+            ProcNode::MemInfo => 2,
+            ProcNode::Uptime => 3,
+            ProcNode::NetDir => 4,
+            ProcNode::NetTcp => 5,
+            ProcNode::NetUdp => 6,
+            ProcNode::Stat => 7,
+            ProcNode::CpuInfo => 8,
+            ProcNode::CmdLine => 9,
+            ProcNode::DiskStats => 10,
+            ProcNode::BlockDir => 11,
+            ProcNode::FaultInject => 12,
+            ProcNode::Perf => 13,
+            ProcNode::BlockDev(index) => BLOCK_INODE_BASE + index * BLOCK_INODE_STRIDE,
+            ProcNode::BlockInfo(index) => BLOCK_INODE_BASE + index * BLOCK_INODE_STRIDE + 1,
+            ProcNode::PidDir(pid) => PID_INODE_BASE + pid * PID_INODE_STRIDE,
+            ProcNode::PidStatus(pid) => PID_INODE_BASE + pid * PID_INODE_STRIDE + 1,
+        }
+    }
+
+    /// Décode un numéro d'inode
+    pub fn from_inode(id: InodeId) -> Option<Self> {
+        match id {
+            1 => Some(ProcNode::Root),
+            2 => Some(ProcNode::MemInfo),
+            3 => Some(ProcNode::Uptime),
+            4 => Some(ProcNode::NetDir),
+            5 => Some(ProcNode::NetTcp),
+            6 => Some(ProcNode::NetUdp),
+            7 => Some(ProcNode::Stat),
+            8 => Some(ProcNode::CpuInfo),
+            9 => Some(ProcNode::CmdLine),
+            10 => Some(ProcNode::DiskStats),
+            11 => Some(ProcNode::BlockDir),
+            12 => Some(ProcNode::FaultInject),
+            13 => Some(ProcNode::Perf),
+            id if (BLOCK_INODE_BASE..PID_INODE_BASE).contains(&id) => {
+                let index = (id - BLOCK_INODE_BASE) / BLOCK_INODE_STRIDE;
+                match (id - BLOCK_INODE_BASE) % BLOCK_INODE_STRIDE {
+                    0 => Some(ProcNode::BlockDev(index)),
+                    _ => Some(ProcNode::BlockInfo(index)),
+                }
+            }
+            id if id >= PID_INODE_BASE => {
+                let pid = (id - PID_INODE_BASE) / PID_INODE_STRIDE;
+                match (id - PID_INODE_BASE) % PID_INODE_STRIDE {
+                    0 => Some(ProcNode::PidDir(pid)),
+                    1 => Some(ProcNode::PidStatus(pid)),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn file_type(self) -> FileType {
+        match self {
+            ProcNode::Root | ProcNode::NetDir | ProcNode::BlockDir | ProcNode::BlockDev(_) | ProcNode::PidDir(_) => {
+                FileType::Directory
+            }
+            _ => FileType::Regular,
+        }
+    }
+
+    /// Vérifie que le nœud existe encore (le processus peut avoir disparu)
+    fn exists(self) -> bool {
+        match self {
+            ProcNode::PidDir(pid) | ProcNode::PidStatus(pid) => {
+                crate::process::get_process_by_pid(pid).is_some()
+            }
+            ProcNode::BlockDev(index) | ProcNode::BlockInfo(index) => block_name(index).is_some(),
+            _ => true,
+        }
+    }
+}
+
+/// Superblock de procfs
+pub struct ProcSuperblock {
+    fs_id: FsId,
+}
+
+impl Superblock for ProcSuperblock {
+    fn fs_name(&self) -> &str {
+        "proc"
+    }
+
+    fn fs_id(&self) -> FsId {
+        self.fs_id
+    }
+
+    fn block_size(&self) -> u32 {
+        4096
+    }
+
+    fn total_blocks(&self) -> u64 {
+        0
+    }
+
+    fn free_blocks(&self) -> u64 {
+        0
+    }
+
+    fn total_inodes(&self) -> u64 {
+        0
+    }
+
+    fn free_inodes(&self) -> u64 {
+        0
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn root_inode(&self) -> InodeId {
+        ProcNode::Root.to_inode()
+    }
+}
+
+/// Système de fichiers /proc
+pub struct ProcFileSystem {
+    sb: Arc<ProcSuperblock>,
+}
+
+impl ProcFileSystem {
+    pub fn new() -> Self {
+        Self {
+            sb: Arc::new(ProcSuperblock { fs_id: alloc_fs_id() }),
+        }
+    }
+}
+
+impl FileSystemOps for ProcFileSystem {
+    fn superblock(&self) -> Arc<dyn Superblock> {
+        self.sb.clone()
+    }
+
+    fn get_inode(&self, inode_id: InodeId) -> VfsResult<Arc<Mutex<dyn InodeOps>>> {
+        let node = ProcNode::from_inode(inode_id).ok_or(VfsError::NotFound)?;
+        if !node.exists() {
+            return Err(VfsError::NotFound);
+        }
+        Ok(Arc::new(Mutex::new(ProcInode { node })))
+    }
+
+    fn sync(&self) -> VfsResult<()> { Ok(()) }
+    fn unmount(&self) -> VfsResult<()> { Ok(()) }
+}
+
+/// Inode procfs : le contenu est généré à chaque accès
+struct ProcInode {
+    node: ProcNode,
+}
+
+impl ProcInode {
+    fn content(&self) -> VfsResult<String> {
+        match self.node {
+            ProcNode::MemInfo => Ok(meminfo()),
+            ProcNode::Uptime => Ok(uptime()),
+            ProcNode::Stat => Ok(stat()),
+            ProcNode::CpuInfo => Ok(crate::cpuid::cpuinfo()),
+            ProcNode::CmdLine => Ok(format!("{}\n", crate::cmdline::raw())),
+            ProcNode::DiskStats => Ok(diskstats()),
+            ProcNode::FaultInject => Ok(crate::fault_inject::report()),
+            ProcNode::Perf => Ok(crate::perf::report()),
+            ProcNode::NetTcp => Ok(net_sockets(crate::net::SocketType::Stream)),
+            ProcNode::NetUdp => Ok(net_sockets(crate::net::SocketType::Datagram)),
+            ProcNode::BlockInfo(index) => {
+                block_name(index).and_then(|name| crate::drivers::ata_info::format_info(&name)).ok_or(VfsError::NotFound)
+            }
+            ProcNode::PidStatus(pid) => pid_status(pid).ok_or(VfsError::NotFound),
+            _ => Err(VfsError::IsDirectory),
+        }
+    }
+}
+
+impl InodeOps for ProcInode {
+    fn read(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        let content = self.content()?;
+        let bytes = content.as_bytes();
+        if offset >= bytes.len() as u64 {
+            return Ok(0);
+        }
+        let start = offset as usize;
+        let len = core::cmp::min(bytes.len() - start, buf.len());
+        buf[..len].copy_from_slice(&bytes[start..start + len]);
+        Ok(len)
+    }
+
+    fn write(&mut self, _offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        if self.node != ProcNode::FaultInject {
+            return Err(VfsError::ReadOnly);
+        }
+        let spec = core::str::from_utf8(buf).map_err(|_| VfsError::InvalidArgument)?;
+        crate::fault_inject::configure(spec).map_err(|_| VfsError::InvalidArgument)?;
+        Ok(buf.len())
+    }
+
+    fn stat(&self) -> VfsResult<FileStat> {
+        let file_type = self.node.file_type();
+        let mut stat = FileStat::new(self.node.to_inode(), file_type);
+        if file_type == FileType::Directory {
+            stat.mode = FileMode::new(0o555);
+        } else {
+            stat.mode = FileMode::new(if self.node == ProcNode::FaultInject { 0o644 } else { 0o444 });
+            // La taille reflète le contenu courant, pour que les lecteurs dimensionnent leur tampon
+            stat.size = self.content()?.len() as u64;
+        }
+        Ok(stat)
+    }
+
+    fn lookup(&self, name: &str) -> VfsResult<InodeId> {
+        let found = match (self.node, name) {
+            (_, ".") => Some(self.node),
+            (ProcNode::Root, "meminfo") => Some(ProcNode::MemInfo),
+            (ProcNode::Root, "uptime") => Some(ProcNode::Uptime),
+            (ProcNode::Root, "stat") => Some(ProcNode::Stat),
+            (ProcNode::Root, "cpuinfo") => Some(ProcNode::CpuInfo),
+            (ProcNode::Root, "cmdline") => Some(ProcNode::CmdLine),
+            (ProcNode::Root, "diskstats") => Some(ProcNode::DiskStats),
+            (ProcNode::Root, "fault_inject") => Some(ProcNode::FaultInject),
+            (ProcNode::Root, "perf") => Some(ProcNode::Perf),
+            (ProcNode::Root, "net") => Some(ProcNode::NetDir),
+            (ProcNode::Root, "block") => Some(ProcNode::BlockDir),
+            (ProcNode::BlockDir, dev) => crate::drivers::ata_info::list()
+                .iter()
+                .position(|name| name == dev)
+                .map(|index| ProcNode::BlockDev(index as u64)),
+            (ProcNode::BlockDev(index), "info") => Some(ProcNode::BlockInfo(index)),
+            (ProcNode::Root, pid) => pid.parse::<u64>().ok().map(ProcNode::PidDir),
+            (ProcNode::NetDir, "tcp") => Some(ProcNode::NetTcp),
+            (ProcNode::NetDir, "udp") => Some(ProcNode::NetUdp),
+            (ProcNode::PidDir(pid), "status") => Some(ProcNode::PidStatus(pid)),
+            (node, _) if node.file_type() != FileType::Directory => {
+                return Err(VfsError::NotDirectory);
+            }
+            _ => None,
+        };
+
+        match found {
+            Some(node) if node.exists() => Ok(node.to_inode()),
+            _ => Err(VfsError::NotFound),
+        }
+    }
+
+    fn create(&mut self, _name: &str, _mode: FileMode, _file_type: FileType) -> VfsResult<InodeId> {
+        Err(VfsError::ReadOnly)
+    }
+
+    fn unlink(&mut self, _name: &str) -> VfsResult<()> {
+        Err(VfsError::ReadOnly)
+    }
+
+    fn mkdir(&mut self, _name: &str, _mode: FileMode) -> VfsResult<InodeId> {
+        Err(VfsError::ReadOnly)
+    }
+
+    fn rmdir(&mut self, _name: &str) -> VfsResult<()> {
+        Err(VfsError::ReadOnly)
+    }
+
+    fn readdir(&self) -> VfsResult<Vec<DirEntry>> {
+        let mut entries = Vec::new();
+        entries.push(DirEntry::new(self.node.to_inode(), ".".into(), FileType::Directory));
+
+        let children: Vec<(String, ProcNode)> = match self.node {
+            ProcNode::Root => {
+                let mut children = Vec::new();
+                children.push(("meminfo".into(), ProcNode::MemInfo));
+                children.push(("uptime".into(), ProcNode::Uptime));
+                children.push(("stat".into(), ProcNode::Stat));
+                children.push(("cpuinfo".into(), ProcNode::CpuInfo));
+                children.push(("cmdline".into(), ProcNode::CmdLine));
+                children.push(("diskstats".into(), ProcNode::DiskStats));
+                children.push(("fault_inject".into(), ProcNode::FaultInject));
+                children.push(("perf".into(), ProcNode::Perf));
+                children.push(("net".into(), ProcNode::NetDir));
+                children.push(("block".into(), ProcNode::BlockDir));
+                for pid in process_pids() {
+                    children.push((pid.to_string(), ProcNode::PidDir(pid)));
+                }
+                children
+            }
+            ProcNode::NetDir => {
+                let mut children = Vec::new();
+                children.push(("tcp".into(), ProcNode::NetTcp));
+                children.push(("udp".into(), ProcNode::NetUdp));
+                children
+            }
+            ProcNode::BlockDir => crate::drivers::ata_info::list()
+                .into_iter()
+                .enumerate()
+                .map(|(index, name)| (name, ProcNode::BlockDev(index as u64)))
+                .collect(),
+            ProcNode::BlockDev(index) => {
+                let mut children = Vec::new();
+                children.push(("info".into(), ProcNode::BlockInfo(index)));
+                children
+            }
+            ProcNode::PidDir(pid) => {
+                let mut children = Vec::new();
+                children.push(("status".into(), ProcNode::PidStatus(pid)));
+                children
+            }
+            _ => return Err(VfsError::NotDirectory),
+        };
+
+        for (name, node) in children {
+            entries.push(DirEntry::new(node.to_inode(), name, node.file_type()));
+        }
+        Ok(entries)
+    }
+
+    fn truncate(&mut self, _size: u64) -> VfsResult<()> {
+        // Écriture par redirection (`>`) : troncature préalable sans effet
+        match self.node {
+            ProcNode::FaultInject => Ok(()),
+            _ => Err(VfsError::ReadOnly),
+        }
+    }
+}
+
+// ============ GÉNÉRATEURS DE CONTENU ============
+
+/// PIDs des processus existants
+fn process_pids() -> Vec<u64> {
+    crate::process::snapshot_processes().iter().map(|p| p.pid).collect()
+}
+
+/// Contenu de /proc/meminfo
+fn meminfo() -> String {
+    let stats = crate::memory::mem_stats();
+    let heap = crate::memory::HYBRID_ALLOCATOR.get_stats();
+
+    let mut out = String::new();
+    let kb = |bytes: u64| bytes / 1024;
+    let _ = writeln!(out, "MemTotal:       {:>8} kB", kb(stats.total));
+    let _ = writeln!(out, "MemFree:        {:>8} kB", kb(stats.free));
+    let _ = writeln!(out, "MemAvailable:   {:>8} kB", kb(stats.available()));
+    let _ = writeln!(out, "MemUsed:        {:>8} kB", kb(stats.used()));
+    let _ = writeln!(out, "Cached:         {:>8} kB", kb(stats.cached));
+    let _ = writeln!(out, "Dirty:          {:>8} kB", kb(stats.dirty));
+    let _ = writeln!(out, "SwapUsed:       {:>8} kB", kb(stats.swap_used));
+    let _ = writeln!(out, "HeapTotal:      {:>8} kB", kb(stats.heap_total));
+    let _ = writeln!(out, "HeapUsed:       {:>8} kB", kb(stats.heap_used));
+    let _ = writeln!(out, "HeapPeak:       {:>8} kB", kb(stats.heap_peak));
+    let _ = writeln!(out, "Slab:           {:>8} kB", kb(stats.slab));
+    let _ = writeln!(out, "Slabs:          {:>8}", heap.slab.total_slabs);
+    let _ = writeln!(out, "SlabObjects:    {:>8}", stats.slab_active_objects);
+    let _ = writeln!(out, "SlabAllocs:     {:>8}", heap.slab.total_allocations);
+    let _ = writeln!(out, "SlabFrees:      {:>8}", heap.slab.total_deallocations);
+    let _ = writeln!(out, "BuddyAllocs:    {:>8}", heap.buddy.total_allocations);
+    let _ = writeln!(out, "BuddyFrees:     {:>8}", heap.buddy.total_deallocations);
+    out
+}
+
+/// Contenu de /proc/uptime (secondes depuis le démarrage, avec centièmes),
+/// d'après la source d'horloge plutôt qu'une fréquence de timer supposée
+fn uptime() -> String {
+    use crate::time::NSEC_PER_SEC;
+    let ns = crate::time::now_ns();
+    format!("{}.{:02}\n", ns / NSEC_PER_SEC, ns % NSEC_PER_SEC / (NSEC_PER_SEC / 100))
+}
+
+/// Contenu de /proc/stat : compteurs de chaque CPU
+/// (interruptions, changements de contexte, ticks, appels système)
+fn stat() -> String {
+    use crate::percpu::{Stat, STATS};
+
+    let mut out = String::new();
+    for (cpu, stats) in STATS.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "cpu{} {} {} {} {}",
+            cpu,
+            stats.get(Stat::Irq),
+            stats.get(Stat::ContextSwitch),
+            stats.get(Stat::Tick),
+            stats.get(Stat::Syscall)
+        );
+    }
+    let _ = writeln!(out, "intr {}", crate::percpu::total(Stat::Irq));
+    let _ = writeln!(out, "ctxt {}", crate::percpu::total(Stat::ContextSwitch));
+    let _ = writeln!(out, "procs_running {}", crate::scheduler::SCHEDULER.runnable());
+    out
+}
+
+/// Contenu de /proc/diskstats : colonnes de Linux dans le même ordre
+/// (lectures, fusions, secteurs, temps, en cours...), temps et numéros de
+/// périphérique non mesurés (0) ; deux colonnes en plus : requêtes
+/// transmises au pilote et erreurs
+fn diskstats() -> String {
+    let mut out = String::new();
+    let names = crate::drivers::block::BLOCK_DEVICES.lock().list();
+    for (index, name) in names.iter().enumerate() {
+        let Some(disk) = crate::drivers::block::get(name) else { continue };
+        let s = disk.stats();
+        let _ = writeln!(
+            out,
+            "{:>4} {:>7} {} {} 0 {} 0 {} {} {} 0 {} 0 0 {} {}",
+            0, index, name, s.reads, s.sectors_read, s.writes, s.write_merges, s.sectors_written, s.queued, s.dispatches, s.errors
+        );
+    }
+    out
+}
+
+/// Nom du disque d'indice `index` dans /proc/block
+fn block_name(index: u64) -> Option<String> {
+    crate::drivers::ata_info::list().into_iter().nth(index as usize)
+}
+
+/// Contenu de /proc/<pid>/status
+fn pid_status(pid: u64) -> Option<String> {
+    let process = crate::process::get_process_by_pid(pid)?.lock().snapshot();
+
+    let mut out = String::new();
+    let _ = writeln!(out, "Name:\t{}", process.name);
+    let _ = writeln!(out, "State:\t{:?}", process.state);
+    let _ = writeln!(out, "Pid:\t{}", process.pid);
+    let _ = writeln!(out, "Priority:\t{:?}", process.priority);
+    let _ = writeln!(out, "Threads:\t{}", process.thread_count());
+    let _ = writeln!(out, "Vruntime:\t{}", process.vruntime());
+    Some(out)
+}
+
+/// Adresse au format de /proc/net : IP en hexadécimal little-endian, port
+fn proc_net_addr(addr: Option<crate::net::SocketAddr>) -> String {
+    match addr.map(|addr| (addr.ip, addr.port)) {
+        Some((crate::net::IpAddress::V4(ip), port)) => format!("{:08X}:{:04X}", u32::from_le_bytes(ip.0), port),
+        _ => String::from("00000000:0000"),
+    }
+}
+
+/// Contenu de /proc/net/tcp et /proc/net/udp (format de Linux, l'inode
+/// étant l'identifiant du socket)
+fn net_sockets(socket_type: crate::net::SocketType) -> String {
+    let mut out = String::from("  sl  local_address rem_address   st tx_queue rx_queue inode\n");
+    let sockets = crate::net::socket::snapshot();
+    // Sockets IPv4 seulement, comme sous Linux (tcp6/udp6 à part)
+    let ipv4 = |info: &&crate::net::SocketInfo| !info.local.map_or(false, |addr| addr.ip.is_ipv6());
+    for (sl, info) in sockets.iter().filter(|s| s.socket_type == socket_type).filter(ipv4).enumerate() {
+        // UDP : 07 (CLOSE) comme Linux pour un socket non connecté
+        let state = info.state.map_or(0x07, |state| state.proc_code());
+        let _ = writeln!(
+            out,
+            "{:>4}: {} {} {:02X} {:08X}:{:08X} {}",
+            sl,
+            proc_net_addr(info.local),
+            proc_net_addr(info.remote),
+            state,
+            info.send_queue,
+            info.recv_queue,
+            info.id
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_proc_node_inode_roundtrip() {
+        for node in [ProcNode::Root, ProcNode::MemInfo, ProcNode::NetTcp, ProcNode::BlockDir, ProcNode::FaultInject, ProcNode::Perf, ProcNode::BlockDev(3), ProcNode::BlockInfo(3), ProcNode::PidDir(7), ProcNode::PidStatus(7)] {
+            assert_eq!(ProcNode::from_inode(node.to_inode()), Some(node));
+        }
+        assert_eq!(ProcNode::from_inode(0), None);
+    }
+
+    #[test_case]
+    fn test_proc_root_listing() {
+        let fs = ProcFileSystem::new();
+        let root = fs.get_inode(fs.superblock().root_inode()).unwrap();
+        let names: Vec<String> = root.lock().readdir().unwrap().into_iter().map(|e| e.name).collect();
+        assert!(names.iter().any(|n| n == "meminfo"));
+        assert!(names.iter().any(|n| n == "net"));
+    }
+
+    #[test_case]
+    fn test_proc_net_addr() {
+        let addr = crate::net::SocketAddr::new(crate::net::Ipv4Address::new(10, 0, 2, 15), 80);
+        assert_eq!(proc_net_addr(Some(addr)), "0F02000A:0050");
+        assert_eq!(proc_net_addr(None), "00000000:0000");
+    }
+
+    #[test_case]
+    fn test_proc_meminfo_readable() {
+        let fs = ProcFileSystem::new();
+        let root = fs.get_inode(fs.superblock().root_inode()).unwrap();
+        let id = root.lock().lookup("meminfo").unwrap();
+        let inode = fs.get_inode(id).unwrap();
+
+        let size = inode.lock().stat().unwrap().size as usize;
+        let mut buf = alloc::vec![0u8; size];
+        assert_eq!(inode.lock().read(0, &mut buf).unwrap(), size);
+        assert!(buf.starts_with(b"MemTotal:"));
+        assert_eq!(inode.lock().write(0, b"x"), Err(VfsError::ReadOnly));
+    }
+
+    #[test_case]
+    fn test_proc_uptime_follows_clocksource() {
+        let before = crate::time::now_ns() / crate::time::NSEC_PER_SEC;
+        let text = uptime();
+        let (secs, centis) = text.trim_end().split_once('.').unwrap();
+        assert_eq!(centis.len(), 2);
+        let secs: u64 = secs.parse().unwrap();
+        assert!(secs >= before && secs <= crate::time::now_ns() / crate::time::NSEC_PER_SEC);
+    }
+}
@@ -3,10 +3,97 @@ use alloc::collections::BTreeMap;
 use alloc::string::{String, ToString};
 use alloc::sync::Arc;
 use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU64, Ordering};
 use spin::Mutex;
 
 use crate::fs::vfs_core::*;
 
+/// Taille de bloc utilisée pour la comptabilité (une page, comme tmpfs)
+const RAM_BLOCK_SIZE: u64 = 4096;
+
+fn blocks_for(len: usize) -> u64 {
+    (len as u64).div_ceil(RAM_BLOCK_SIZE)
+}
+
+/// Limites d'un montage, fixées au montage (None : sans limite)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RamFsLimits {
+    /// Octets de données, arrondis au bloc
+    pub max_bytes: Option<u64>,
+    pub max_inodes: Option<u64>,
+}
+
+impl RamFsLimits {
+    /// Options de montage tmpfs : `size=<taille>[K|M|G]`, `nr_inodes=<n>`
+    /// (0 : sans limite, comme Linux)
+    pub fn parse(options: &str) -> VfsResult<Self> {
+        let mut limits = Self::default();
+        for option in options.split(',').filter(|o| !o.is_empty()) {
+            match option.split_once('=') {
+                Some(("size", size)) => {
+                    let size = crate::cmdline::parse_size(size).ok_or(VfsError::InvalidArgument)?;
+                    limits.max_bytes = Some(size as u64).filter(|&s| s > 0);
+                }
+                Some(("nr_inodes", count)) => {
+                    let count = count.parse::<u64>().map_err(|_| VfsError::InvalidArgument)?;
+                    limits.max_inodes = Some(count).filter(|&n| n > 0);
+                }
+                _ => return Err(VfsError::InvalidArgument),
+            }
+        }
+        Ok(limits)
+    }
+}
+
+/// Occupation d'un montage, partagée par ses inodes. Un inode rend ses
+/// blocs quand ses données sont libérées : un fichier supprimé mais encore
+/// ouvert compte toujours.
+struct RamFsUsage {
+    limits: RamFsLimits,
+    blocks: AtomicU64,
+    inodes: AtomicU64,
+}
+
+impl RamFsUsage {
+    fn new(limits: RamFsLimits) -> Self {
+        Self { limits, blocks: AtomicU64::new(0), inodes: AtomicU64::new(0) }
+    }
+
+    fn max_blocks(&self) -> Option<u64> {
+        self.limits.max_bytes.map(|bytes| bytes / RAM_BLOCK_SIZE)
+    }
+
+    /// Réserve `amount` unités de `counter`, ou NoSpace au-delà de `max`
+    fn charge(counter: &AtomicU64, amount: u64, max: Option<u64>) -> VfsResult<()> {
+        counter
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |used| {
+                let total = used.checked_add(amount)?;
+                match max {
+                    Some(max) if total > max => None,
+                    _ => Some(total),
+                }
+            })
+            .map(|_| ())
+            .map_err(|_| VfsError::NoSpace)
+    }
+
+    fn charge_blocks(&self, blocks: u64) -> VfsResult<()> {
+        Self::charge(&self.blocks, blocks, self.max_blocks())
+    }
+
+    fn release_blocks(&self, blocks: u64) {
+        self.blocks.fetch_sub(blocks, Ordering::SeqCst);
+    }
+
+    fn charge_inode(&self) -> VfsResult<()> {
+        Self::charge(&self.inodes, 1, self.limits.max_inodes)
+    }
+
+    fn release_inode(&self) {
+        self.inodes.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
 /// Structure représentant une inode en mémoire
 struct RamInodeData {
     id: InodeId,
@@ -22,11 +109,18 @@ struct RamInodeData {
     atime: u64,
     mtime: u64,
     ctime: u64,
+    /// Attributs étendus (nom complet -> valeur)
+    xattrs: BTreeMap<String, Vec<u8>>,
+    /// Compteurs du montage
+    usage: Arc<RamFsUsage>,
 }
 
 impl RamInodeData {
-    fn new(id: InodeId, mode: FileMode, file_type: FileType) -> Self {
-        Self {
+    /// Nouvel inode, décompté du montage (NoSpace si `nr_inodes` est atteint)
+    fn new(id: InodeId, mode: FileMode, file_type: FileType, usage: Arc<RamFsUsage>) -> VfsResult<Self> {
+        usage.charge_inode()?;
+        let now = crate::time::unix_time();
+        Ok(Self {
             id,
             mode,
             file_type,
@@ -36,10 +130,39 @@ impl RamInodeData {
             nlinks: 1,
             uid: 0,
             gid: 0,
-            atime: 0,
-            mtime: 0,
-            ctime: 0,
+            atime: now,
+            mtime: now,
+            ctime: now,
+            xattrs: BTreeMap::new(),
+            usage,
+        })
+    }
+
+    /// Redimensionne le contenu ; les blocs supplémentaires sont réservés
+    /// avant l'allocation
+    fn resize(&mut self, len: usize) -> VfsResult<()> {
+        let (old, new) = (blocks_for(self.content.len()), blocks_for(len));
+        if new > old {
+            self.usage.charge_blocks(new - old)?;
+        } else {
+            self.usage.release_blocks(old - new);
         }
+        self.content.resize(len, 0);
+        Ok(())
+    }
+
+    /// Contenu modifié : mtime et ctime à l'heure courante
+    fn touch(&mut self) {
+        let now = crate::time::unix_time();
+        self.mtime = now;
+        self.ctime = now;
+    }
+}
+
+impl Drop for RamInodeData {
+    fn drop(&mut self) {
+        self.usage.release_blocks(blocks_for(self.content.len()));
+        self.usage.release_inode();
     }
 }
 
@@ -51,11 +174,13 @@ impl RamInodeData {
 
 pub struct RamSuperblock {
     fs_id: FsId,
+    /// "ramfs" pour la racine, "tmpfs" pour les montages explicites
+    name: &'static str,
 }
 
 impl Superblock for RamSuperblock {
     fn fs_name(&self) -> &str {
-        "ramfs"
+        self.name
     }
 
     fn fs_id(&self) -> FsId {
@@ -63,7 +188,7 @@ impl Superblock for RamSuperblock {
     }
 
     fn block_size(&self) -> u32 {
-        4096
+        RAM_BLOCK_SIZE as u32
     }
 
     fn total_blocks(&self) -> u64 {
@@ -96,6 +221,27 @@ impl Superblock for RamSuperblock {
 struct RamFsInner {
     inodes: Mutex<BTreeMap<InodeId, Arc<Mutex<RamInodeData>>>>,
     next_inode_id: Mutex<InodeId>,
+    usage: Arc<RamFsUsage>,
+}
+
+impl RamFsInner {
+    /// Retire un lien de l'inode `id` ; au dernier, l'inode quitte la table.
+    /// Ses données vivent tant qu'un descripteur ouvert en garde les opérations.
+    fn drop_link(&self, id: InodeId) {
+        let mut inodes = self.inodes.lock();
+        let remaining = match inodes.get(&id) {
+            Some(data) => {
+                let mut data = data.lock();
+                data.nlinks = data.nlinks.saturating_sub(1);
+                data.ctime = crate::time::unix_time();
+                data.nlinks
+            }
+            None => return,
+        };
+        if remaining == 0 {
+            inodes.remove(&id);
+        }
+    }
 }
 
 pub struct RamFileSystemRef {
@@ -105,23 +251,40 @@ pub struct RamFileSystemRef {
 
 impl RamFileSystemRef {
     pub fn new() -> Self {
-        let sb = Arc::new(RamSuperblock { fs_id: 1 });
+        Self::build("ramfs", RamFsLimits::default()).expect("ramfs sans limite")
+    }
+
+    /// tmpfs : ramfs borné en taille et en nombre d'inodes
+    pub fn with_limits(limits: RamFsLimits) -> VfsResult<Self> {
+        Self::build("tmpfs", limits)
+    }
+
+    fn build(name: &'static str, limits: RamFsLimits) -> VfsResult<Self> {
+        let sb = Arc::new(RamSuperblock { fs_id: alloc_fs_id(), name });
         let inner = Arc::new(RamFsInner {
             inodes: Mutex::new(BTreeMap::new()),
             next_inode_id: Mutex::new(2),
+            usage: Arc::new(RamFsUsage::new(limits)),
         });
 
         let root_data = Arc::new(Mutex::new(RamInodeData::new(
             1,
             FileMode::new(0o755),
             FileType::Directory,
-        )));
+            inner.usage.clone(),
+        )?));
         inner.inodes.lock().insert(1, root_data);
 
-        Self { inner, sb }
+        Ok(Self { inner, sb })
     }
 }
 
+/// Monte un tmpfs sur `target` ; `options` : `size=...,nr_inodes=...`
+pub fn mount_tmpfs(target: &str, options: &str) -> VfsResult<()> {
+    let fs = RamFileSystemRef::with_limits(RamFsLimits::parse(options)?)?;
+    super::mount_fs(target, Arc::new(fs), super::MountFlags::new(0))
+}
+
 impl FileSystemOps for RamFileSystemRef {
     fn superblock(&self) -> Arc<dyn Superblock> {
         self.sb.clone()
@@ -142,6 +305,35 @@ impl FileSystemOps for RamFileSystemRef {
 
     fn sync(&self) -> VfsResult<()> { Ok(()) }
     fn unmount(&self) -> VfsResult<()> { Ok(()) }
+
+    /// Comme tmpfs : sans limite `size=`, la place libre est celle de
+    /// l'allocateur de cadres ; sans `nr_inodes=`, les inodes ne sont pas
+    /// limités
+    fn statfs(&self) -> VfsResult<StatFs> {
+        let usage = &self.inner.usage;
+        let used = usage.blocks.load(Ordering::SeqCst);
+        let files = usage.inodes.load(Ordering::SeqCst);
+        let (total_blocks, free_blocks) = match usage.max_blocks() {
+            Some(max) => (max, max.saturating_sub(used)),
+            None => {
+                let free = crate::memory::FRAME_ALLOCATOR.lock().free_frames() as u64 * crate::memory::frame::FRAME_SIZE / RAM_BLOCK_SIZE;
+                (used + free, free)
+            }
+        };
+        let (total_inodes, free_inodes) = match usage.limits.max_inodes {
+            Some(max) => (max, max.saturating_sub(files)),
+            None => (files, 0),
+        };
+        Ok(StatFs {
+            fs_name: String::from(self.sb.fs_name()),
+            block_size: RAM_BLOCK_SIZE as u32,
+            total_blocks,
+            free_blocks,
+            total_inodes,
+            free_inodes,
+            readonly: false,
+        })
+    }
 }
 
 struct RamInodeOps {
@@ -151,7 +343,8 @@ struct RamInodeOps {
 
 impl InodeOps for RamInodeOps {
     fn read(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
-        let data = self.data.lock();
+        let mut data = self.data.lock();
+        data.atime = crate::time::unix_time();
         if offset >= data.content.len() as u64 {
             return Ok(0);
         }
@@ -166,12 +359,13 @@ impl InodeOps for RamInodeOps {
         let mut data = self.data.lock();
         let end = offset as usize + buf.len();
         if end > data.content.len() {
-            data.content.resize(end, 0);
+            data.resize(end)?;
         }
         data.content[offset as usize..end].copy_from_slice(buf);
         if end as u64 > data.size {
             data.size = end as u64;
         }
+        data.touch();
         Ok(buf.len())
     }
 
@@ -181,6 +375,11 @@ impl InodeOps for RamInodeOps {
         stat.mode = data.mode;
         stat.size = data.size;
         stat.nlinks = data.nlinks;
+        stat.uid = data.uid;
+        stat.gid = data.gid;
+        stat.atime = data.atime;
+        stat.mtime = data.mtime;
+        stat.ctime = data.ctime;
         Ok(stat)
     }
 
@@ -202,17 +401,40 @@ impl InodeOps for RamInodeOps {
         let id = *next_id;
         *next_id += 1;
 
-        let new_data = Arc::new(Mutex::new(RamInodeData::new(id, mode, file_type)));
+        let new_data = Arc::new(Mutex::new(RamInodeData::new(id, mode, file_type, self.fs_inner.usage.clone())?));
         self.fs_inner.inodes.lock().insert(id, new_data);
         
         data.children.insert(name.into(), id);
+        data.touch();
         Ok(id)
     }
 
     fn unlink(&mut self, name: &str) -> VfsResult<()> {
         let mut data = self.data.lock();
         if data.file_type != FileType::Directory { return Err(VfsError::NotDirectory); }
-        if data.children.remove(name).is_some() { Ok(()) } else { Err(VfsError::NotFound) }
+        let id = data.children.remove(name).ok_or(VfsError::NotFound)?;
+        data.touch();
+        drop(data);
+        self.fs_inner.drop_link(id);
+        Ok(())
+    }
+
+    fn link(&mut self, name: &str, target: InodeId) -> VfsResult<()> {
+        let target_data = self.fs_inner.inodes.lock().get(&target).cloned().ok_or(VfsError::NotFound)?;
+        if Arc::ptr_eq(&target_data, &self.data) { return Err(VfsError::IsDirectory); }
+        let mut data = self.data.lock();
+        if data.file_type != FileType::Directory { return Err(VfsError::NotDirectory); }
+        if data.children.contains_key(name) { return Err(VfsError::AlreadyExists); }
+        {
+            let mut target_data = target_data.lock();
+            // Pas de lien physique vers un répertoire (cycles)
+            if target_data.file_type == FileType::Directory { return Err(VfsError::IsDirectory); }
+            target_data.nlinks += 1;
+            target_data.ctime = crate::time::unix_time();
+        }
+        data.children.insert(name.into(), target);
+        data.touch();
+        Ok(())
     }
 
     fn mkdir(&mut self, name: &str, mode: FileMode) -> VfsResult<InodeId> {
@@ -244,8 +466,93 @@ impl InodeOps for RamInodeOps {
 
     fn truncate(&mut self, size: u64) -> VfsResult<()> {
         let mut data = self.data.lock();
-        data.content.resize(size as usize, 0);
+        data.resize(size as usize)?;
         data.size = size;
+        data.touch();
+        Ok(())
+    }
+
+    fn rename(&mut self, old_name: &str, new_dir: InodeId, new_name: &str) -> VfsResult<()> {
+        let target = self.fs_inner.inodes.lock().get(&new_dir).cloned().ok_or(VfsError::NotFound)?;
+        let is_dir = |id: InodeId| {
+            self.fs_inner.inodes.lock().get(&id).map_or(false, |d| d.lock().file_type == FileType::Directory)
+        };
+
+        let mut data = self.data.lock();
+        if data.file_type != FileType::Directory { return Err(VfsError::NotDirectory); }
+        let id = *data.children.get(old_name).ok_or(VfsError::NotFound)?;
+        if id == new_dir { return Err(VfsError::InvalidArgument); }
+        let moved_dir = is_dir(id);
+
+        let replaced = if Arc::ptr_eq(&self.data, &target) {
+            if !may_replace(data.children.get(new_name).copied(), id, moved_dir, is_dir)? { return Ok(()); }
+            data.children.remove(old_name);
+            data.touch();
+            data.children.insert(new_name.into(), id)
+        } else {
+            let mut target_data = target.lock();
+            if target_data.file_type != FileType::Directory { return Err(VfsError::NotDirectory); }
+            if !may_replace(target_data.children.get(new_name).copied(), id, moved_dir, is_dir)? { return Ok(()); }
+            data.children.remove(old_name);
+            data.touch();
+            target_data.touch();
+            target_data.children.insert(new_name.into(), id)
+        };
+        drop(data);
+        // Le remplacé perd ce lien ; au dernier, il quitte la table
+        if let Some(replaced) = replaced {
+            self.fs_inner.drop_link(replaced);
+        }
+        Ok(())
+    }
+
+    fn getxattr(&self, name: &str) -> VfsResult<Vec<u8>> {
+        check_xattr_name(name)?;
+        self.data.lock().xattrs.get(name).cloned().ok_or(VfsError::NoAttribute)
+    }
+
+    fn setxattr(&mut self, name: &str, value: &[u8], flags: u32) -> VfsResult<()> {
+        check_xattr_name(name)?;
+        if value.len() > XATTR_SIZE_MAX {
+            return Err(VfsError::InvalidArgument);
+        }
+        let mut data = self.data.lock();
+        let exists = data.xattrs.contains_key(name);
+        if flags & XATTR_CREATE != 0 && exists {
+            return Err(VfsError::AlreadyExists);
+        }
+        if flags & XATTR_REPLACE != 0 && !exists {
+            return Err(VfsError::NoAttribute);
+        }
+        data.xattrs.insert(name.into(), value.to_vec());
+        data.ctime = crate::time::unix_time();
+        Ok(())
+    }
+
+    fn listxattr(&self) -> VfsResult<Vec<String>> {
+        Ok(self.data.lock().xattrs.keys().cloned().collect())
+    }
+
+    fn removexattr(&mut self, name: &str) -> VfsResult<()> {
+        check_xattr_name(name)?;
+        let mut data = self.data.lock();
+        data.xattrs.remove(name).ok_or(VfsError::NoAttribute)?;
+        data.ctime = crate::time::unix_time();
+        Ok(())
+    }
+
+    fn setattr(&mut self, mode: Option<FileMode>, uid: Option<u32>, gid: Option<u32>) -> VfsResult<()> {
+        let mut data = self.data.lock();
+        if let Some(mode) = mode {
+            data.mode = FileMode::new(mode.0 & 0o7777);
+        }
+        if let Some(uid) = uid {
+            data.uid = uid;
+        }
+        if let Some(gid) = gid {
+            data.gid = gid;
+        }
+        data.ctime = crate::time::unix_time();
         Ok(())
     }
 }
@@ -312,4 +619,123 @@ mod tests {
         let err = root.lock().lookup("nonexistent");
         assert!(err.is_err());
     }
+
+    #[test_case]
+    fn test_ramfs_statfs() {
+        let fs = RamFileSystemRef::new();
+        let root = fs.get_inode(1).expect("Should get root inode");
+        let before = fs.statfs().expect("statfs");
+
+        let file_id = root.lock().create("big.bin", FileMode::new(0o644), FileType::Regular)
+            .expect("Should create file");
+        fs.get_inode(file_id).expect("Should get file inode").lock().write(0, &[0u8; 5000]).expect("Should write");
+
+        let after = fs.statfs().expect("statfs");
+        assert_eq!(after.fs_name, "ramfs");
+        assert_eq!(after.total_inodes, before.total_inodes + 1);
+        assert_eq!(after.used_blocks(), before.used_blocks() + 2);
+    }
+
+    #[test_case]
+    fn test_ramfs_hard_link() {
+        let fs = RamFileSystemRef::new();
+        let root = fs.get_inode(1).expect("Should get root inode");
+
+        let file_id = root.lock().create("a.txt", FileMode::new(0o644), FileType::Regular)
+            .expect("Should create file");
+        root.lock().link("b.txt", file_id).expect("Should link");
+        assert_eq!(root.lock().lookup("b.txt").expect("Should find b.txt"), file_id);
+        assert_eq!(root.lock().link("c", 1), Err(VfsError::IsDirectory));
+
+        // Inode ouvert : il survit au dernier unlink
+        let open = fs.get_inode(file_id).expect("Should get file inode");
+        open.lock().write(0, b"data").expect("Should write");
+        assert_eq!(open.lock().stat().expect("stat").nlinks, 2);
+        root.lock().unlink("a.txt").expect("Should unlink");
+        assert_eq!(open.lock().stat().expect("stat").nlinks, 1);
+        assert!(fs.get_inode(file_id).is_ok());
+
+        root.lock().unlink("b.txt").expect("Should unlink");
+        assert!(fs.get_inode(file_id).is_err());
+        let mut buf = [0u8; 4];
+        assert_eq!(open.lock().read(0, &mut buf).expect("Should read"), 4);
+        assert_eq!(&buf, b"data");
+    }
+
+    #[test_case]
+    fn test_ramfs_rename() {
+        let fs = RamFileSystemRef::new();
+        let root = fs.get_inode(1).expect("Should get root inode");
+
+        let file_id = root.lock().create("a.txt", FileMode::new(0o644), FileType::Regular)
+            .expect("Should create file");
+        let dir_id = root.lock().mkdir("dir", FileMode::new(0o755)).expect("Should mkdir");
+
+        // Même répertoire
+        root.lock().rename("a.txt", 1, "b.txt").expect("Should rename");
+        assert!(root.lock().lookup("a.txt").is_err());
+        assert_eq!(root.lock().lookup("b.txt").expect("Should find b.txt"), file_id);
+
+        // Vers un autre répertoire : même inode, aucune copie
+        root.lock().rename("b.txt", dir_id, "c.txt").expect("Should move");
+        let dir_inode = fs.get_inode(dir_id).expect("Should get dir inode");
+        assert_eq!(dir_inode.lock().lookup("c.txt").expect("Should find c.txt"), file_id);
+        assert!(root.lock().lookup("b.txt").is_err());
+
+        // Types incompatibles : ni répertoire sur fichier, ni l'inverse
+        let other_id = root.lock().create("other", FileMode::new(0o644), FileType::Regular)
+            .expect("Should create file");
+        assert_eq!(root.lock().rename("dir", 1, "other"), Err(VfsError::NotDirectory));
+        assert_eq!(dir_inode.lock().rename("c.txt", 1, "dir"), Err(VfsError::IsDirectory));
+
+        // Le fichier écrasé est libéré, son inode rendu au montage
+        let used = fs.statfs().expect("statfs").total_inodes;
+        dir_inode.lock().rename("c.txt", 1, "other").expect("Should replace");
+        assert!(fs.get_inode(other_id).is_err());
+        assert_eq!(fs.statfs().expect("statfs").total_inodes, used - 1);
+
+        // Deux liens du même inode : rien ne bouge
+        root.lock().link("same", file_id).expect("Should link");
+        root.lock().rename("other", 1, "same").expect("Should be a no-op");
+        assert_eq!(root.lock().lookup("other"), Ok(file_id));
+        assert_eq!(fs.get_inode(file_id).expect("inode").lock().stat().expect("stat").nlinks, 2);
+    }
+
+    #[test_case]
+    fn test_ramfs_setattr() {
+        let fs = RamFileSystemRef::new();
+        let root = fs.get_inode(1).expect("Should get root inode");
+        let file_id = root.lock().create("secret", FileMode::new(0o644), FileType::Regular)
+            .expect("Should create file");
+        let file = fs.get_inode(file_id).expect("Should get file inode");
+
+        file.lock().setattr(Some(FileMode::new(0o100600)), Some(1000), Some(100)).expect("setattr");
+        let stat = file.lock().stat().expect("stat");
+        assert_eq!((stat.mode.0, stat.uid, stat.gid), (0o600, 1000, 100));
+    }
+
+    #[test_case]
+    fn test_tmpfs_limits() {
+        let limits = RamFsLimits::parse("size=8K,nr_inodes=3").expect("options");
+        assert_eq!(limits, RamFsLimits { max_bytes: Some(8192), max_inodes: Some(3) });
+        assert_eq!(RamFsLimits::parse("mode=1777"), Err(VfsError::InvalidArgument));
+
+        let fs = RamFileSystemRef::with_limits(limits).expect("tmpfs");
+        let root = fs.get_inode(1).expect("Should get root inode");
+        let a = root.lock().create("a", FileMode::new(0o644), FileType::Regular).expect("Should create a");
+        root.lock().create("b", FileMode::new(0o644), FileType::Regular).expect("Should create b");
+        assert_eq!(root.lock().create("c", FileMode::new(0o644), FileType::Regular), Err(VfsError::NoSpace));
+
+        let file = fs.get_inode(a).expect("Should get file inode");
+        file.lock().write(0, &[1u8; 8192]).expect("Should fill the mount");
+        assert_eq!(file.lock().write(8192, b"x"), Err(VfsError::NoSpace));
+        let stat = fs.statfs().expect("statfs");
+        assert_eq!((stat.fs_name.as_str(), stat.total_blocks, stat.free_blocks, stat.free_inodes), ("tmpfs", 2, 0, 0));
+
+        // Les blocs et l'inode reviennent au montage avec les données
+        drop(file);
+        root.lock().unlink("a").expect("Should unlink");
+        let stat = fs.statfs().expect("statfs");
+        assert_eq!((stat.free_blocks, stat.free_inodes), (2, 1));
+    }
 }
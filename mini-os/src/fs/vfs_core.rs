@@ -9,6 +9,7 @@ use alloc::vec::Vec;
 use alloc::sync::Arc;
 use spin::Mutex;
 use core::fmt;
+use core::sync::atomic::{AtomicU32, Ordering};
 
 /// Identifiant unique d'inode
 pub type InodeId = u64;
@@ -16,6 +17,13 @@ pub type InodeId = u64;
 /// Identifiant de système de fichiers
 pub type FsId = u32;
 
+static NEXT_FS_ID: AtomicU32 = AtomicU32::new(1);
+
+/// Alloue un identifiant unique pour une instance de système de fichiers
+pub fn alloc_fs_id() -> FsId {
+    NEXT_FS_ID.fetch_add(1, Ordering::Relaxed)
+}
+
 /// Types de fichiers
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FileType {
@@ -123,6 +131,8 @@ pub enum VfsError {
     TooManyLinks,       // Trop de liens symboliques
     NameTooLong,        // Nom trop long
     NotEmpty,           // Répertoire non vide
+    NoAttribute,        // Attribut étendu absent
+    RangeTooSmall,      // Tampon trop petit pour le résultat
 }
 
 impl fmt::Display for VfsError {
@@ -141,6 +151,8 @@ impl fmt::Display for VfsError {
             VfsError::TooManyLinks => write!(f, "Trop de liens symboliques"),
             VfsError::NameTooLong => write!(f, "Nom de fichier trop long"),
             VfsError::NotEmpty => write!(f, "Répertoire non vide"),
+            VfsError::NoAttribute => write!(f, "Attribut inexistant"),
+            VfsError::RangeTooSmall => write!(f, "Résultat trop grand pour le tampon"),
         }
     }
 }
@@ -150,6 +162,7 @@ pub type VfsResult<T> = Result<T, VfsError>;
 /// Statistiques de fichier
 #[derive(Debug, Clone)]
 pub struct FileStat {
+    pub dev: FsId,          // Système de fichiers de l'inode (rempli par le VFS)
     pub inode: InodeId,
     pub file_type: FileType,
     pub mode: FileMode,
@@ -167,6 +180,7 @@ pub struct FileStat {
 impl FileStat {
     pub fn new(inode: InodeId, file_type: FileType) -> Self {
         Self {
+            dev: 0,
             inode,
             file_type,
             mode: FileMode::new(0o644),
@@ -213,6 +227,80 @@ pub trait Superblock: Send + Sync {
     fn root_inode(&self) -> InodeId;
 }
 
+/// Occupation d'un système de fichiers (statfs)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatFs {
+    pub fs_name: String,
+    pub block_size: u32,
+    /// 0 : système de fichiers sans taille fixe (ramfs, pseudo-fs)
+    pub total_blocks: u64,
+    pub free_blocks: u64,
+    pub total_inodes: u64,
+    pub free_inodes: u64,
+    pub readonly: bool,
+}
+
+impl StatFs {
+    pub fn from_superblock(sb: &dyn Superblock) -> Self {
+        Self {
+            fs_name: String::from(sb.fs_name()),
+            block_size: sb.block_size(),
+            total_blocks: sb.total_blocks(),
+            free_blocks: sb.free_blocks(),
+            total_inodes: sb.total_inodes(),
+            free_inodes: sb.free_inodes(),
+            readonly: sb.is_readonly(),
+        }
+    }
+
+    pub fn used_blocks(&self) -> u64 {
+        self.total_blocks.saturating_sub(self.free_blocks)
+    }
+
+    /// Taille totale, utilisée et disponible en octets
+    pub fn bytes(&self) -> (u64, u64, u64) {
+        let bs = self.block_size as u64;
+        (self.total_blocks * bs, self.used_blocks() * bs, self.free_blocks * bs)
+    }
+}
+
+/// Échec si l'attribut existe déjà (setxattr)
+pub const XATTR_CREATE: u32 = 1;
+/// Échec si l'attribut n'existe pas (setxattr)
+pub const XATTR_REPLACE: u32 = 2;
+/// Longueur maximale d'un nom d'attribut étendu
+pub const XATTR_NAME_MAX: usize = 255;
+/// Taille maximale d'une valeur d'attribut étendu
+pub const XATTR_SIZE_MAX: usize = 65536;
+
+/// Espaces de noms reconnus des attributs étendus
+const XATTR_NAMESPACES: [&str; 4] = ["user.", "trusted.", "security.", "system."];
+
+/// Vérifie un nom d'attribut : espace de noms connu, nom non vide et borné
+pub fn check_xattr_name(name: &str) -> VfsResult<()> {
+    if name.len() > XATTR_NAME_MAX {
+        return Err(VfsError::NameTooLong);
+    }
+    match XATTR_NAMESPACES.iter().find(|ns| name.starts_with(*ns)) {
+        Some(ns) if name.len() > ns.len() => Ok(()),
+        Some(_) => Err(VfsError::InvalidArgument),
+        None => Err(VfsError::NotSupported),
+    }
+}
+
+/// Le renommage de `id` peut-il écraser l'entrée `existing` ? Ok(false) :
+/// les deux noms désignent déjà le même inode, rien à faire. Un répertoire
+/// n'est jamais écrasé et n'écrase pas un fichier.
+pub fn may_replace(existing: Option<InodeId>, id: InodeId, moved_dir: bool, is_dir: impl Fn(InodeId) -> bool) -> VfsResult<bool> {
+    match existing {
+        None => Ok(true),
+        Some(existing) if existing == id => Ok(false),
+        Some(existing) if is_dir(existing) => Err(VfsError::IsDirectory),
+        Some(_) if moved_dir => Err(VfsError::NotDirectory),
+        Some(_) => Ok(true),
+    }
+}
+
 /// Opérations sur les inodes
 pub trait InodeOps: Send + Sync {
     /// Lire les données de l'inode
@@ -230,7 +318,8 @@ pub trait InodeOps: Send + Sync {
     /// Créer un nouveau fichier
     fn create(&mut self, name: &str, mode: FileMode, file_type: FileType) -> VfsResult<InodeId>;
     
-    /// Supprimer un fichier
+    /// Supprimer une entrée ; l'inode n'est libéré qu'à la disparition de
+    /// son dernier lien et de ses derniers descripteurs ouverts
     fn unlink(&mut self, name: &str) -> VfsResult<()>;
     
     /// Créer un répertoire
@@ -244,6 +333,49 @@ pub trait InodeOps: Send + Sync {
     
     /// Tronquer le fichier à une taille donnée
     fn truncate(&mut self, size: u64) -> VfsResult<()>;
+    
+    /// Déplacer l'entrée `old_name` vers `new_name` dans le répertoire `new_dir`
+    /// (même système de fichiers). Une cible non-répertoire existante est remplacée.
+    fn rename(&mut self, _old_name: &str, _new_dir: InodeId, _new_name: &str) -> VfsResult<()> {
+        Err(VfsError::NotSupported)
+    }
+
+    /// Ajouter l'entrée `name` vers l'inode `target` de ce système de
+    /// fichiers (lien physique) ; le compteur de liens de la cible augmente
+    fn link(&mut self, _name: &str, _target: InodeId) -> VfsResult<()> {
+        Err(VfsError::NotSupported)
+    }
+
+    /// Valeur de l'attribut étendu `name` (`espace.nom`, ex. `user.mime`)
+    fn getxattr(&self, _name: &str) -> VfsResult<Vec<u8>> {
+        Err(VfsError::NotSupported)
+    }
+
+    /// Définit l'attribut étendu `name` ; `flags` : XATTR_CREATE, XATTR_REPLACE
+    fn setxattr(&mut self, _name: &str, _value: &[u8], _flags: u32) -> VfsResult<()> {
+        Err(VfsError::NotSupported)
+    }
+
+    /// Noms des attributs étendus de l'inode
+    fn listxattr(&self) -> VfsResult<Vec<String>> {
+        Err(VfsError::NotSupported)
+    }
+
+    /// Supprime l'attribut étendu `name`
+    fn removexattr(&mut self, _name: &str) -> VfsResult<()> {
+        Err(VfsError::NotSupported)
+    }
+
+    /// Changer les bits de permission et/ou le propriétaire (chmod, chown)
+    fn setattr(&mut self, _mode: Option<FileMode>, _uid: Option<u32>, _gid: Option<u32>) -> VfsResult<()> {
+        Err(VfsError::NotSupported)
+    }
+
+    /// Disponibilité pour poll (POLLIN, POLLOUT...) ; un fichier ordinaire
+    /// est toujours prêt
+    fn poll(&self) -> i16 {
+        super::poll::POLLIN | super::poll::POLLOUT
+    }
 }
 
 /// Entrée de répertoire
@@ -277,6 +409,12 @@ pub trait FileSystemOps: Send + Sync {
     
     /// Démonter le système de fichiers
     fn unmount(&self) -> VfsResult<()>;
+
+    
+    /// Occupation (statfs) ; par défaut, les compteurs du superblock
+    fn statfs(&self) -> VfsResult<StatFs> {
+        Ok(StatFs::from_superblock(self.superblock().as_ref()))
+    }
 }
 
 #[cfg(test)]
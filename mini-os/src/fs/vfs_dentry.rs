@@ -39,7 +39,12 @@ impl Dentry {
         inode: Arc<Mutex<Inode>>,
         parent: Option<Arc<Mutex<Dentry>>>,
     ) -> Self {
-        let hash = Self::hash_name(&name);
+        // Le hash d'une dentry chaîne celui de son parent : il identifie le chemin complet.
+        // Une racine mélange son fs_id pour que deux arborescences ne partagent pas d'entrées.
+        let hash = match &parent {
+            Some(p) => DentryCache::hash_path(p.lock().hash, &name),
+            None => Self::hash_name(&name) ^ ((inode.lock().fs_id as u64) << 32),
+        };
         Self {
             name,
             inode,
@@ -127,7 +132,16 @@ impl DentryCache {
     /// Recherche une dentry dans le cache
     pub fn lookup(&self, parent: &Dentry, name: &str) -> Option<Arc<Mutex<Dentry>>> {
         let hash = Self::hash_path(parent.hash, name);
-        self.entries.get(&hash).cloned()
+        self.entries
+            .get(&hash)
+            .filter(|dentry| dentry.lock().name == name)
+            .cloned()
+    }
+
+    /// Retire du cache l'entrée `name` de `parent` (après unlink/rmdir)
+    pub fn invalidate(&mut self, parent: &Dentry, name: &str) {
+        let hash = Self::hash_path(parent.hash, name);
+        self.entries.remove(&hash);
     }
 
     /// Ajoute une dentry au cache
@@ -251,11 +265,25 @@ pub fn path_lookup(path: &str, root: Arc<Mutex<Dentry>>) -> VfsResult<Arc<Mutex<
 
         // Pas en cache, rechercher dans l'inode
         let current_inode = current.lock().inode.clone();
-        let inode_id = current_inode.lock().lookup(component)?;
-
-        // Créer une nouvelle dentry (simplifié - devrait obtenir l'inode réel)
-        // Pour l'instant, on retourne une erreur
-        return Err(VfsError::NotFound);
+        let (fs_id, inode_id) = {
+            let inode = current_inode.lock();
+            (inode.fs_id, inode.lookup(component)?)
+        };
+
+        // Charger l'inode enfant depuis son système de fichiers
+        let fs = super::vfs_mount::fs_by_id(fs_id).ok_or(VfsError::NotFound)?;
+        let ops = fs.get_inode(inode_id)?;
+        let file_type = ops.lock().stat()?.file_type;
+        let inode = super::vfs_inode::get_or_create_inode(fs_id, inode_id, file_type, ops);
+
+        let dentry = Arc::new(Mutex::new(Dentry::new(
+            component.into(),
+            inode,
+            Some(current.clone()),
+        )));
+        // Cache plein : la résolution fonctionne quand même, sans mise en cache
+        let _ = DENTRY_CACHE.lock().insert(dentry.clone());
+        current = dentry;
     }
 
     Ok(current)
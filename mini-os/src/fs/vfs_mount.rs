@@ -2,6 +2,11 @@
 /// 
 /// Le système de montage permet d'attacher différents systèmes de fichiers
 /// à l'arborescence VFS globale.
+///
+/// La résolution de chemins consulte la table des montages à chaque
+/// composant : le gestionnaire global en publie une copie par RCU
+/// (MOUNT_TABLE) après chaque montage ou démontage, et `fs_by_id` /
+/// `find_mount` la lisent sans verrou.
 
 use alloc::sync::Arc;
 use alloc::collections::BTreeMap;
@@ -13,6 +18,7 @@ use lazy_static::lazy_static;
 use super::vfs_core::*;
 use super::vfs_inode::Inode;
 use super::vfs_dentry::Dentry;
+use crate::sync::rcu::Rcu;
 
 /// Point de montage
 pub struct MountPoint {
@@ -30,6 +36,9 @@ pub struct MountPoint {
     
     /// Flags de montage
     pub flags: MountFlags,
+    
+    /// Inode recouvert par le montage (restauré au démontage)
+    pub covered: Arc<Mutex<Inode>>,
 }
 
 /// Flags de montage
@@ -74,16 +83,30 @@ impl MountPoint {
         root: Arc<Mutex<Inode>>,
         flags: MountFlags,
     ) -> Self {
+        let covered = mountpoint.lock().inode.clone();
         Self {
             path,
             fs,
             mountpoint,
             root,
             flags,
+            covered,
         }
     }
 }
 
+/// Entrée de la table des montages publiée
+#[derive(Clone)]
+pub struct MountEntry {
+    pub path: String,
+    pub fs_id: FsId,
+    pub fs: Arc<dyn FileSystemOps>,
+    pub mount: Arc<Mutex<MountPoint>>,
+}
+
+/// Table des montages lisible sans verrou
+pub type MountTable = Rcu<Vec<MountEntry>>;
+
 /// Gestionnaire de montage
 pub struct MountManager {
     /// Table des points de montage (clé: chemin)
@@ -91,6 +114,9 @@ pub struct MountManager {
     
     /// Point de montage racine
     root_mount: Option<Arc<Mutex<MountPoint>>>,
+    
+    /// Table republiée à chaque changement (instance globale seulement)
+    table: Option<&'static MountTable>,
 }
 
 impl MountManager {
@@ -99,6 +125,23 @@ impl MountManager {
         Self {
             mounts: BTreeMap::new(),
             root_mount: None,
+            table: None,
+        }
+    }
+
+    /// Gestionnaire qui publie ses montages dans `table`
+    pub fn published(table: &'static MountTable) -> Self {
+        Self { table: Some(table), ..Self::new() }
+    }
+
+    /// Republie la table des montages pour les lecteurs
+    fn publish(&self) {
+        if let Some(table) = self.table {
+            let entries = self.mounts.iter().map(|(path, mount)| {
+                let fs = mount.lock().fs.clone();
+                MountEntry { path: path.clone(), fs_id: fs.superblock().fs_id(), fs, mount: mount.clone() }
+            }).collect();
+            table.replace(entries);
         }
     }
 
@@ -131,10 +174,13 @@ impl MountManager {
         let mount = Arc::new(Mutex::new(MountPoint::new(
             path.into(),
             fs,
-            mountpoint,
-            root_inode,
+            mountpoint.clone(),
+            root_inode.clone(),
             flags,
         )));
+        
+        // La dentry du point de montage mène désormais à la racine du système monté
+        mountpoint.lock().inode = root_inode;
 
         // Ajouter à la table
         self.mounts.insert(path.into(), mount.clone());
@@ -143,6 +189,7 @@ impl MountManager {
         if path == "/" {
             self.root_mount = Some(mount);
         }
+        self.publish();
 
         Ok(())
     }
@@ -161,10 +208,15 @@ impl MountManager {
         let locked_mount = mount.lock();
         locked_mount.fs.sync()?;
         locked_mount.fs.unmount()?;
+        
+        // Rendre l'inode recouvert à la dentry du point de montage
+        locked_mount.mountpoint.lock().inode = locked_mount.covered.clone();
+        super::vfs_dentry::DENTRY_CACHE.lock().invalidate_fs(locked_mount.fs.superblock().fs_id());
         drop(locked_mount);
 
         // Retirer de la table
         self.mounts.remove(path);
+        self.publish();
 
         Ok(())
     }
@@ -185,6 +237,14 @@ impl MountManager {
         best_match.map(|(_, mount)| mount.clone())
     }
 
+    /// Retrouve le système de fichiers monté portant l'identifiant donné
+    pub fn fs_by_id(&self, fs_id: FsId) -> Option<Arc<dyn FileSystemOps>> {
+        self.mounts
+            .values()
+            .map(|mount| mount.lock().fs.clone())
+            .find(|fs| fs.superblock().fs_id() == fs_id)
+    }
+
     /// Obtient le point de montage racine
     pub fn root_mount(&self) -> Option<Arc<Mutex<MountPoint>>> {
         self.root_mount.clone()
@@ -231,8 +291,24 @@ impl MountManager {
 }
 
 lazy_static! {
+    /// Copie publiée des montages du gestionnaire global
+    pub static ref MOUNT_TABLE: MountTable = Rcu::new(Vec::new());
     /// Gestionnaire de montage global
-    pub static ref MOUNT_MANAGER: Mutex<MountManager> = Mutex::new(MountManager::new());
+    pub static ref MOUNT_MANAGER: Mutex<MountManager> = Mutex::new(MountManager::published(&MOUNT_TABLE));
+}
+
+/// Système de fichiers monté portant l'identifiant donné, sans verrou
+pub fn fs_by_id(fs_id: FsId) -> Option<Arc<dyn FileSystemOps>> {
+    MOUNT_TABLE.read().iter().find(|entry| entry.fs_id == fs_id).map(|entry| entry.fs.clone())
+}
+
+/// Point de montage le plus spécifique contenant `path`, sans verrou
+pub fn find_mount(path: &str) -> Option<Arc<Mutex<MountPoint>>> {
+    MOUNT_TABLE.read()
+        .iter()
+        .filter(|entry| path.starts_with(entry.path.as_str()))
+        .max_by_key(|entry| entry.path.len())
+        .map(|entry| entry.mount.clone())
 }
 
 /// Monte le système de fichiers racine
@@ -0,0 +1,390 @@
+/// VFS Namei - Opérations de nommage atomiques
+///
+/// Toute opération qui modifie un répertoire (création, suppression, renommage)
+/// résout d'abord le répertoire parent, puis prend le verrou de ce répertoire
+/// avant de vérifier et modifier le dernier composant. La vérification
+/// (« le nom existe-t-il ? ») et la modification se font donc sous le même
+/// verrou, sans fenêtre TOCTOU entre processus.
+///
+/// Les verrous sont indexés par (fs_id, inode) et non par dentry : deux
+/// dentries vers le même répertoire partagent le même verrou.
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::sync::Arc;
+use spin::Mutex;
+use lazy_static::lazy_static;
+
+use super::vfs_core::*;
+use super::vfs_dentry::{Dentry, DENTRY_CACHE, path_lookup};
+use super::vfs_inode::{Inode, INODE_CACHE};
+
+/// Longueur maximale d'un composant de chemin
+pub const NAME_MAX: usize = 255;
+
+/// Clé d'un verrou de répertoire
+type DirKey = (FsId, InodeId);
+
+lazy_static! {
+    /// Verrous des répertoires en cours de modification
+    static ref DIR_LOCKS: Mutex<BTreeMap<DirKey, Arc<Mutex<()>>>> = Mutex::new(BTreeMap::new());
+}
+
+/// Obtient (ou crée) le verrou d'un répertoire
+fn dir_lock(key: DirKey) -> Arc<Mutex<()>> {
+    DIR_LOCKS.lock().entry(key).or_insert_with(|| Arc::new(Mutex::new(()))).clone()
+}
+
+/// Libère l'entrée de la table si plus personne n'utilise le verrou
+fn release_dir_lock(key: DirKey, lock: Arc<Mutex<()>>) {
+    let mut locks = DIR_LOCKS.lock();
+    // Une référence dans la table + celle que l'on relâche
+    if Arc::strong_count(&lock) == 2 {
+        locks.remove(&key);
+    }
+}
+
+/// Répertoire parent résolu, dont le verrou est tenu pendant l'opération
+pub struct LockedParent {
+    pub dentry: Arc<Mutex<Dentry>>,
+    pub inode: Arc<Mutex<Inode>>,
+}
+
+impl LockedParent {
+    /// Opérations de l'inode du répertoire
+    pub fn ops(&self) -> Arc<Mutex<dyn InodeOps>> {
+        self.inode.lock().ops.clone()
+    }
+
+    fn key(&self) -> DirKey {
+        let inode = self.inode.lock();
+        (inode.fs_id, inode.id)
+    }
+
+    /// Retire `name` du cache de dentries après une modification
+    fn invalidate(&self, name: &str) {
+        DENTRY_CACHE.lock().invalidate(&self.dentry.lock(), name);
+    }
+}
+
+/// Sépare un chemin en (répertoire parent, dernier composant)
+pub fn split_path(path: &str) -> VfsResult<(&str, &str)> {
+    let trimmed = path.trim_end_matches('/');
+    if trimmed.is_empty() {
+        // "/" n'a pas de parent
+        return Err(VfsError::InvalidArgument);
+    }
+
+    let (parent, name) = match trimmed.rfind('/') {
+        Some(0) => ("/", &trimmed[1..]),
+        Some(pos) => (&trimmed[..pos], &trimmed[pos + 1..]),
+        None => (".", trimmed),
+    };
+
+    if name == "." || name == ".." {
+        return Err(VfsError::InvalidArgument);
+    }
+    if name.len() > NAME_MAX {
+        return Err(VfsError::NameTooLong);
+    }
+    Ok((parent, name))
+}
+
+/// Résout le répertoire parent d'un chemin
+fn resolve_parent(root: &Arc<Mutex<Dentry>>, parent_path: &str) -> VfsResult<LockedParent> {
+    let dentry = path_lookup(parent_path, root.clone())?;
+    let inode = dentry.lock().inode.clone();
+    if !inode.lock().is_dir() {
+        return Err(VfsError::NotDirectory);
+    }
+    Ok(LockedParent { dentry, inode })
+}
+
+/// Exécute `op` sur le répertoire parent de `path`, verrou du parent tenu
+pub fn with_parent<R>(
+    root: &Arc<Mutex<Dentry>>,
+    path: &str,
+    op: impl FnOnce(&LockedParent, &str) -> VfsResult<R>,
+) -> VfsResult<R> {
+    let (parent_path, name) = split_path(path)?;
+    let parent = resolve_parent(root, parent_path)?;
+
+    let key = parent.key();
+    let lock = dir_lock(key);
+    let result = {
+        let _guard = lock.lock();
+        op(&parent, name)
+    };
+    release_dir_lock(key, lock);
+    result
+}
+
+/// Crée un fichier ; échoue avec `AlreadyExists` si le nom est pris
+pub fn create_at(root: &Arc<Mutex<Dentry>>, path: &str, mode: FileMode, file_type: FileType) -> VfsResult<InodeId> {
+    with_parent(root, path, |parent, name| {
+        let ops = parent.ops();
+        let mut ops = ops.lock();
+        match ops.lookup(name) {
+            Ok(_) => Err(VfsError::AlreadyExists),
+            Err(VfsError::NotFound) => ops.create(name, mode, file_type),
+            Err(e) => Err(e),
+        }
+    })
+}
+
+/// Crée un répertoire
+pub fn mkdir_at(root: &Arc<Mutex<Dentry>>, path: &str, mode: FileMode) -> VfsResult<InodeId> {
+    with_parent(root, path, |parent, name| {
+        let ops = parent.ops();
+        let mut ops = ops.lock();
+        match ops.lookup(name) {
+            Ok(_) => Err(VfsError::AlreadyExists),
+            Err(VfsError::NotFound) => ops.mkdir(name, mode),
+            Err(e) => Err(e),
+        }
+    })
+}
+
+/// Supprime une entrée (fichier) et l'invalide du cache ; sans dernier
+/// lien, l'inode quitte aussi le cache d'inodes
+pub fn unlink_at(root: &Arc<Mutex<Dentry>>, path: &str) -> VfsResult<()> {
+    with_parent(root, path, |parent, name| {
+        let ops = parent.ops();
+        let mut ops = ops.lock();
+        let id = ops.lookup(name)?;
+        ops.unlink(name)?;
+        drop(ops);
+        parent.invalidate(name);
+        evict_if_unlinked(parent.key().0, id);
+        Ok(())
+    })
+}
+
+/// Sans dernier lien, l'inode quitte le cache d'inodes (les descripteurs
+/// ouverts gardent leurs propres références)
+fn evict_if_unlinked(fs_id: FsId, id: InodeId) {
+    let mut cache = INODE_CACHE.lock();
+    let unlinked = cache.get(fs_id, id).map_or(false, |inode| {
+        let ops = inode.lock().ops.clone();
+        let stat = ops.lock().stat();
+        stat.map_or(true, |stat| stat.nlinks == 0)
+    });
+    if unlinked {
+        cache.remove(fs_id, id);
+    }
+}
+
+/// L'inode `key` est-il celui de `dentry` ou de l'un de ses ancêtres ?
+fn is_ancestor(key: DirKey, dentry: &Arc<Mutex<Dentry>>) -> bool {
+    let mut current = Some(dentry.clone());
+    while let Some(dentry) = current {
+        let (inode, parent) = {
+            let dentry = dentry.lock();
+            (dentry.inode.clone(), dentry.parent.clone())
+        };
+        let inode = inode.lock();
+        if (inode.fs_id, inode.id) == key {
+            return true;
+        }
+        current = parent;
+    }
+    false
+}
+
+/// Crée le lien physique `new_path` vers le fichier `old_path` (même
+/// système de fichiers, pas de répertoire)
+pub fn link_at(root: &Arc<Mutex<Dentry>>, old_path: &str, new_path: &str) -> VfsResult<()> {
+    let target = path_lookup(old_path, root.clone())?;
+    let target = target.lock().inode.clone();
+    let (target_fs, target_id) = {
+        let inode = target.lock();
+        if inode.is_dir() {
+            return Err(VfsError::IsDirectory);
+        }
+        (inode.fs_id, inode.id)
+    };
+
+    with_parent(root, new_path, |parent, name| {
+        if parent.key().0 != target_fs {
+            // Pas de lien physique entre systèmes de fichiers
+            return Err(VfsError::NotSupported);
+        }
+        let ops = parent.ops();
+        let mut ops = ops.lock();
+        match ops.lookup(name) {
+            Ok(_) => return Err(VfsError::AlreadyExists),
+            Err(VfsError::NotFound) => {}
+            Err(e) => return Err(e),
+        }
+        ops.link(name, target_id)?;
+        drop(ops);
+        parent.invalidate(name);
+        Ok(())
+    })
+}
+
+/// Renommage, verrous des deux parents tenus. Le remplacé sans autre
+/// lien quitte le cache d'inodes.
+fn rename_locked(old_parent: &LockedParent, old_name: &str, new_parent: &LockedParent, new_name: &str) -> VfsResult<()> {
+    let (fs_id, new_dir) = new_parent.key();
+    let ops = old_parent.ops();
+    let moved = ops.lock().lookup(old_name)?;
+    // Un répertoire déplacé sous lui-même créerait un cycle : la
+    // destination ne doit pas le compter parmi ses ancêtres
+    if is_ancestor((fs_id, moved), &new_parent.dentry) {
+        return Err(VfsError::InvalidArgument);
+    }
+    let replaced = new_parent.ops().lock().lookup(new_name).ok();
+
+    ops.lock().rename(old_name, new_dir, new_name)?;
+    old_parent.invalidate(old_name);
+    new_parent.invalidate(new_name);
+    if let Some(replaced) = replaced.filter(|&r| r != moved) {
+        evict_if_unlinked(fs_id, replaced);
+    }
+    Ok(())
+}
+
+/// Renomme `old_path` en `new_path` (remplace une cible non-répertoire existante).
+/// Les deux répertoires parents sont verrouillés dans un ordre global
+/// (fs_id, inode) pour éviter les interblocages entre renommages croisés.
+pub fn rename_at(root: &Arc<Mutex<Dentry>>, old_path: &str, new_path: &str) -> VfsResult<()> {
+    let (old_parent_path, old_name) = split_path(old_path)?;
+    let (new_parent_path, new_name) = split_path(new_path)?;
+    let old_parent = resolve_parent(root, old_parent_path)?;
+    let new_parent = resolve_parent(root, new_parent_path)?;
+
+    let old_key = old_parent.key();
+    let new_key = new_parent.key();
+    if old_key.0 != new_key.0 {
+        // Pas de renommage entre systèmes de fichiers
+        return Err(VfsError::NotSupported);
+    }
+
+    let (first, second) = if old_key <= new_key { (old_key, new_key) } else { (new_key, old_key) };
+    let first_lock = dir_lock(first);
+    let second_lock = if first != second { Some(dir_lock(second)) } else { None };
+
+    let result = {
+        let _first = first_lock.lock();
+        let _second = second_lock.as_ref().map(|l| l.lock());
+
+        rename_locked(&old_parent, old_name, &new_parent, new_name)
+    };
+
+    if let Some(lock) = second_lock {
+        release_dir_lock(second, lock);
+    }
+    release_dir_lock(first, first_lock);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+    use alloc::format;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use crate::fs::{RamFileSystemRef, MOUNT_MANAGER, MountFlags, create_root_dentry, get_or_create_inode};
+
+    /// Racine RamFS privée au test, enregistrée pour la résolution de chemins
+    fn test_root() -> Arc<Mutex<Dentry>> {
+        let fs = Arc::new(RamFileSystemRef::new());
+        let fs_id = fs.superblock().fs_id();
+        let ops = fs.get_inode(1).unwrap();
+        let root = create_root_dentry(get_or_create_inode(fs_id, 1, FileType::Directory, ops));
+        MOUNT_MANAGER.lock()
+            .mount(&format!("/.namei-test-{}", fs_id), fs, root.clone(), MountFlags::new(0))
+            .unwrap();
+        root
+    }
+
+    #[test_case]
+    fn test_split_path() {
+        assert_eq!(split_path("/a/b"), Ok(("/a", "b")));
+        assert_eq!(split_path("/a"), Ok(("/", "a")));
+        assert_eq!(split_path("/a/"), Ok(("/", "a")));
+        assert_eq!(split_path("/"), Err(VfsError::InvalidArgument));
+        assert_eq!(split_path("/a/.."), Err(VfsError::InvalidArgument));
+    }
+
+    #[test_case]
+    fn test_create_exclusive_and_rename() {
+        let root = test_root();
+        mkdir_at(&root, "/dir", FileMode::new(0o755)).unwrap();
+        create_at(&root, "/dir/f", FileMode::new(0o644), FileType::Regular).unwrap();
+        assert_eq!(
+            create_at(&root, "/dir/f", FileMode::new(0o644), FileType::Regular),
+            Err(VfsError::AlreadyExists)
+        );
+
+        rename_at(&root, "/dir/f", "/g").unwrap();
+        assert!(path_lookup("/dir/f", root.clone()).is_err());
+        assert!(path_lookup("/g", root.clone()).is_ok());
+        assert_eq!(rename_at(&root, "/dir", "/dir/sub"), Err(VfsError::InvalidArgument));
+
+        // Cycle repéré par les inodes, quelle que soit l'écriture du chemin
+        mkdir_at(&root, "/dir/sub", FileMode::new(0o755)).unwrap();
+        assert_eq!(rename_at(&root, "/dir", "/g/../dir/./sub/x"), Err(VfsError::InvalidArgument));
+        assert_eq!(rename_at(&root, "/dir/", "//dir//sub/x"), Err(VfsError::InvalidArgument));
+        rename_at(&root, "/dir/sub", "/dirsub").unwrap();
+        rename_at(&root, "/dir", "/dirsub/dir").unwrap();
+        assert!(path_lookup("/dirsub/dir", root.clone()).is_ok());
+    }
+
+    /// Racine partagée par les fils de `test_concurrent_directory_hammering`
+    static HAMMER_ROOT: Mutex<Option<Arc<Mutex<Dentry>>>> = Mutex::new(None);
+    static CREATED: AtomicUsize = AtomicUsize::new(0);
+    static CONFLICTS: AtomicUsize = AtomicUsize::new(0);
+    static ATTEMPTS: AtomicUsize = AtomicUsize::new(0);
+
+    fn hammer(worker: usize) {
+        let root = HAMMER_ROOT.lock().clone().unwrap();
+        // Plusieurs ticks de travail : les fils sont préemptés en pleine
+        // opération
+        let end = crate::scheduler::ticks() + 5;
+        let mut round = 0;
+        while round < 64 || crate::scheduler::ticks() < end {
+            // Tous les fils visent les mêmes noms
+            let name = format!("/shared/f{}", round % 8);
+            ATTEMPTS.fetch_add(1, Ordering::SeqCst);
+            match create_at(&root, &name, FileMode::new(0o644), FileType::Regular) {
+                Ok(_) => CREATED.fetch_add(1, Ordering::SeqCst),
+                Err(VfsError::AlreadyExists) => CONFLICTS.fetch_add(1, Ordering::SeqCst),
+                Err(e) => panic!("create inattendu: {:?}", e),
+            };
+            if (round + worker) % 3 == 0 {
+                let _ = unlink_at(&root, &name);
+            }
+            let _ = rename_at(&root, &name, &format!("/shared/r{}", worker));
+            round += 1;
+        }
+    }
+
+    /// Plusieurs fils préemptés martèlent le même répertoire : chaque nom
+    /// ne peut être créé qu'une fois tant qu'il existe, et l'état final
+    /// correspond exactement aux opérations réussies.
+    #[test_case]
+    fn test_concurrent_directory_hammering() {
+        let root = test_root();
+        mkdir_at(&root, "/shared", FileMode::new(0o755)).unwrap();
+        *HAMMER_ROOT.lock() = Some(root.clone());
+
+        crate::test_runner::run_threads(4, hammer);
+        HAMMER_ROOT.lock().take();
+
+        let attempts = ATTEMPTS.load(Ordering::SeqCst);
+        assert!(attempts >= 4 * 64);
+        assert_eq!(CREATED.load(Ordering::SeqCst) + CONFLICTS.load(Ordering::SeqCst), attempts);
+
+        // Aucun nom en double dans le répertoire
+        let dir = path_lookup("/shared", root.clone()).unwrap();
+        let ops = dir.lock().inode.lock().ops.clone();
+        let mut names: Vec<String> = ops.lock().readdir().unwrap().into_iter().map(|e| e.name).collect();
+        let before = names.len();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), before);
+        assert!(DIR_LOCKS.lock().is_empty());
+    }
+}
@@ -1,7 +1,10 @@
 /// Gestionnaire de Systèmes de Fichiers
 /// 
-/// Ce module gère le montage et l'utilisation d'EXT4 comme système de fichiers principal
+/// Ce module gère le montage et l'utilisation d'EXT4 comme système de fichiers principal,
+/// ainsi que le registre des pilotes montables depuis un périphérique bloc
+/// (`mount -t <type> /dev/<bloc> <cible>`).
 
+use alloc::collections::BTreeMap;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 use alloc::string::String;
@@ -9,9 +12,47 @@ use spin::Mutex;
 use lazy_static::lazy_static;
 
 use crate::ext4::Ext4;
-use crate::fs::{VfsError, JournalMode};
+use crate::fs::{VfsError, JournalMode, FileSystemOps, MountFlags};
+use crate::drivers::block::BlockHandle;
 use crate::drivers::disk::Disk;
 
+/// Construit un système de fichiers à partir d'un périphérique bloc
+pub type MountFn = fn(BlockHandle) -> Result<Arc<dyn FileSystemOps>, VfsError>;
+
+lazy_static! {
+    /// Pilotes de systèmes de fichiers, par nom de type
+    static ref FS_TYPES: Mutex<BTreeMap<&'static str, MountFn>> = Mutex::new(BTreeMap::new());
+}
+
+/// Enregistre le pilote du type `name`
+pub fn register_fs_type(name: &'static str, mount: MountFn) -> Result<(), VfsError> {
+    let mut types = FS_TYPES.lock();
+    if types.contains_key(name) {
+        return Err(VfsError::AlreadyExists);
+    }
+    types.insert(name, mount);
+    Ok(())
+}
+
+/// Types de systèmes de fichiers enregistrés
+pub fn fs_types() -> Vec<&'static str> {
+    FS_TYPES.lock().keys().copied().collect()
+}
+
+/// Monte le périphérique `device` (`/dev/` facultatif) sur `target` avec
+/// le pilote `fs_type` ; un volume en lecture seule est monté READONLY
+pub fn mount_device(fs_type: &str, device: &str, target: &str, flags: MountFlags) -> Result<(), VfsError> {
+    let mount = *FS_TYPES.lock().get(fs_type).ok_or(VfsError::NotSupported)?;
+    let name = device.strip_prefix("/dev/").unwrap_or(device);
+    let dev = crate::drivers::block::get(name).ok_or(VfsError::NotFound)?;
+    let fs = mount(dev)?;
+    let mut flags = flags;
+    if fs.superblock().is_readonly() {
+        flags.0 |= MountFlags::READONLY;
+    }
+    crate::fs::mount_fs(target, fs, flags)
+}
+
 /// Instance globale du système de fichiers EXT4
 lazy_static! {
     pub static ref EXT4_FS: Mutex<Option<Ext4FS>> = Mutex::new(None);
@@ -54,9 +95,30 @@ pub fn init_ext4() -> Result<(), VfsError> {
 }
 
 /// Monte une partition EXT4
-pub fn mount_ext4_partition<D: Disk>(disk: D, mount_point: &str) -> Result<(), VfsError> {
+pub fn mount_ext4_partition<D: Disk>(mut disk: D, mount_point: &str) -> Result<(), VfsError> {
     use crate::ext4::Ext4;
     use crate::fs::JournalMode;
+
+    // Vérification périodique (nombre de montages) avant d'utiliser le volume
+    match crate::fsck::check_on_mount(&mut disk, 0) {
+        Ok(Some(report)) => {
+            for problem in &report.problems {
+                log::warn!("fsck {}: {}", mount_point, problem);
+            }
+            log::info!(
+                "fsck {}: {} problème(s), {} non réparé(s) ; {}/{} inodes, {}/{} blocs",
+                mount_point,
+                report.problems.len(),
+                report.unrepaired,
+                report.inodes_used,
+                report.inodes_count,
+                report.blocks_used,
+                report.blocks_count
+            );
+        }
+        Ok(None) => {}
+        Err(e) => log::warn!("fsck {}: {}", mount_point, e),
+    }
     
     // Créer le filesystem EXT4
     let mut ext4 = Ext4::new(disk, JournalMode::Ordered)
@@ -0,0 +1,969 @@
+/// Vérification et réparation des volumes ext2 (fsck)
+///
+/// Le vérificateur lit le volume directement (secteurs de 512 octets à
+/// partir de `start`), sans passer par le pilote `Ext2` : il doit pouvoir
+/// examiner un volume que le pilote refuserait. Les passes suivent e2fsck :
+/// 1. superbloc et descripteurs de groupe : bornes, et sommes de contrôle
+///    quand le volume active `gdt_csum` ou `metadata_csum` ;
+/// 2. parcours de l'arborescence depuis la racine : inodes atteints, blocs
+///    qu'ils occupent (un bloc revendiqué deux fois est signalé), `..` et
+///    répertoires atteints par deux chemins (boucles) ;
+/// 3. compteurs de liens et inodes orphelins (alloués mais inaccessibles) ;
+/// 4. bitmaps reconstruites à partir des métadonnées et des inodes
+///    atteints, comparées à celles du disque, puis compteurs libres.
+///
+/// En réparation, les bitmaps reconstruites, les compteurs de liens et les
+/// compteurs libres sont écrits et les orphelins libérés ; les boucles et
+/// les blocs partagés ne sont que signalés. Les volumes `metadata_csum`
+/// (sommes de contrôle des inodes et bitmaps) sont vérifiés sans être réparés.
+
+use alloc::collections::VecDeque;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use crate::drivers::disk::Disk;
+
+const EXT2_MAGIC: u16 = 0xEF53;
+const EXT2_ROOT_INO: u32 = 2;
+/// Inode de redimensionnement : ses blocs sont les GDT réservés, déjà
+/// comptés avec les métadonnées des groupes
+const EXT2_RESIZE_INO: u32 = 7;
+const EXT2_VALID_FS: u16 = 1;
+const EXT2_ERROR_FS: u16 = 2;
+
+// Champs du superbloc (décalages en octets)
+const SB_INODES_COUNT: usize = 0;
+const SB_BLOCKS_COUNT: usize = 4;
+const SB_FREE_BLOCKS: usize = 12;
+const SB_FREE_INODES: usize = 16;
+const SB_FIRST_DATA_BLOCK: usize = 20;
+const SB_LOG_BLOCK_SIZE: usize = 24;
+const SB_BLOCKS_PER_GROUP: usize = 32;
+const SB_INODES_PER_GROUP: usize = 40;
+const SB_MNT_COUNT: usize = 52;
+const SB_MAX_MNT_COUNT: usize = 54;
+const SB_MAGIC: usize = 56;
+const SB_STATE: usize = 58;
+const SB_LASTCHECK: usize = 64;
+const SB_REV_LEVEL: usize = 76;
+const SB_FIRST_INO: usize = 84;
+const SB_INODE_SIZE: usize = 88;
+const SB_FEATURE_INCOMPAT: usize = 96;
+const SB_FEATURE_RO_COMPAT: usize = 100;
+const SB_UUID: usize = 104;
+const SB_RESERVED_GDT_BLOCKS: usize = 206;
+const SB_CHECKSUM: usize = 1020;
+
+const INCOMPAT_FILETYPE: u32 = 0x0002;
+const INCOMPAT_META_BG: u32 = 0x0010;
+const INCOMPAT_64BIT: u32 = 0x0080;
+const INCOMPAT_FLEX_BG: u32 = 0x0200;
+const RO_COMPAT_SPARSE_SUPER: u32 = 0x0001;
+const RO_COMPAT_GDT_CSUM: u32 = 0x0010;
+const RO_COMPAT_METADATA_CSUM: u32 = 0x0400;
+
+// Descripteur de groupe (32 octets)
+const DESC_SIZE: usize = 32;
+const BG_BLOCK_BITMAP: usize = 0;
+const BG_INODE_BITMAP: usize = 4;
+const BG_INODE_TABLE: usize = 8;
+const BG_FREE_BLOCKS: usize = 12;
+const BG_FREE_INODES: usize = 14;
+const BG_USED_DIRS: usize = 16;
+const BG_FLAGS: usize = 18;
+const BG_CHECKSUM: usize = 30;
+const BG_INODE_UNINIT: u16 = 0x0001;
+const BG_BLOCK_UNINIT: u16 = 0x0002;
+
+// Inode
+const I_MODE: usize = 0;
+const I_DTIME: usize = 20;
+const I_LINKS: usize = 26;
+const I_FLAGS: usize = 32;
+const I_BLOCK: usize = 40;
+const I_FILE_ACL: usize = 104;
+const S_IFMT: u16 = 0xF000;
+const S_IFDIR: u16 = 0x4000;
+const S_IFLNK: u16 = 0xA000;
+const EXTENTS_FL: u32 = 0x0008_0000;
+const EXTENT_MAGIC: u16 = 0xF30A;
+
+/// Au-delà, les problèmes d'une même sorte sont comptés sans être détaillés
+const MAX_DETAILS: usize = 20;
+
+fn le16(buf: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([buf[offset], buf[offset + 1]])
+}
+
+fn le32(buf: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap())
+}
+
+fn put16(buf: &mut [u8], offset: usize, value: u16) {
+    buf[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+}
+
+fn put32(buf: &mut [u8], offset: usize, value: u32) {
+    buf[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+fn bit(map: &[u8], index: usize) -> bool {
+    map[index / 8] & (1 << (index % 8)) != 0
+}
+
+fn set_bit(map: &mut [u8], index: usize) {
+    map[index / 8] |= 1 << (index % 8);
+}
+
+/// CRC-16 (polynôme 0xA001) des descripteurs `gdt_csum`
+fn crc16(mut crc: u16, data: &[u8]) -> u16 {
+    for &byte in data {
+        crc ^= byte as u16;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xA001 } else { crc >> 1 };
+        }
+    }
+    crc
+}
+
+/// CRC-32C sans inversion finale, comme `ext4_chksum`
+fn crc32c(mut crc: u32, data: &[u8]) -> u32 {
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0x82F6_3B78 & mask);
+        }
+    }
+    crc
+}
+
+/// Bilan d'une vérification
+#[derive(Debug, Default)]
+pub struct FsckReport {
+    /// Problèmes détectés, dans l'ordre des passes
+    pub problems: Vec<String>,
+    /// Problèmes laissés en l'état (même après réparation)
+    pub unrepaired: usize,
+    /// Des corrections ont été écrites
+    pub repaired: bool,
+    pub inodes_used: u32,
+    pub inodes_count: u32,
+    pub blocks_used: u32,
+    pub blocks_count: u32,
+}
+
+impl FsckReport {
+    pub fn is_clean(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Volume ext2 ouvert pour vérification
+struct Volume<'a, D: Disk> {
+    disk: &'a mut D,
+    /// Premier secteur du volume
+    start: u64,
+    sb: Vec<u8>,
+    descs: Vec<u8>,
+    block_size: usize,
+    blocks_count: u32,
+    inodes_count: u32,
+    first_data_block: u32,
+    blocks_per_group: u32,
+    inodes_per_group: u32,
+    inode_size: usize,
+    first_ino: u32,
+    groups: u32,
+    gdt_blocks: u32,
+}
+
+impl<'a, D: Disk> Volume<'a, D> {
+    fn open(disk: &'a mut D, start: u64) -> Result<Self, &'static str> {
+        let mut sb = vec![0u8; 1024];
+        disk.read(start + 2, &mut sb).map_err(|_| "erreur de lecture du superbloc")?;
+        if le16(&sb, SB_MAGIC) != EXT2_MAGIC {
+            return Err("pas un volume ext2");
+        }
+        let incompat = le32(&sb, SB_FEATURE_INCOMPAT);
+        if incompat & (INCOMPAT_64BIT | INCOMPAT_META_BG) != 0 {
+            return Err("volume 64 bits ou meta_bg non pris en charge");
+        }
+        let log_block_size = le32(&sb, SB_LOG_BLOCK_SIZE);
+        let blocks_per_group = le32(&sb, SB_BLOCKS_PER_GROUP);
+        let inodes_per_group = le32(&sb, SB_INODES_PER_GROUP);
+        if log_block_size > 6 || blocks_per_group == 0 || inodes_per_group == 0 {
+            return Err("superbloc corrompu");
+        }
+        let block_size = 1024usize << log_block_size;
+        if blocks_per_group as usize > block_size * 8 || inodes_per_group as usize > block_size * 8 {
+            return Err("superbloc corrompu");
+        }
+        let blocks_count = le32(&sb, SB_BLOCKS_COUNT);
+        let first_data_block = le32(&sb, SB_FIRST_DATA_BLOCK);
+        if first_data_block >= blocks_count {
+            return Err("superbloc corrompu");
+        }
+        let groups = (blocks_count - first_data_block).div_ceil(blocks_per_group);
+        let (inode_size, first_ino) = if le32(&sb, SB_REV_LEVEL) >= 1 {
+            (le16(&sb, SB_INODE_SIZE) as usize, le32(&sb, SB_FIRST_INO))
+        } else {
+            (128, 11)
+        };
+        if inode_size < 128 || !inode_size.is_power_of_two() || inode_size > block_size {
+            return Err("taille d'inode invalide");
+        }
+
+        let gdt_blocks = (groups as usize * DESC_SIZE).div_ceil(block_size) as u32;
+        let inodes_count = le32(&sb, SB_INODES_COUNT);
+        let mut volume = Self {
+            disk,
+            start,
+            sb,
+            descs: Vec::new(),
+            block_size,
+            blocks_count,
+            inodes_count,
+            first_data_block,
+            blocks_per_group,
+            inodes_per_group,
+            inode_size,
+            first_ino,
+            groups,
+            gdt_blocks,
+        };
+        let mut descs = vec![0u8; gdt_blocks as usize * block_size];
+        volume.read_block_into(first_data_block + 1, &mut descs)?;
+        descs.truncate(groups as usize * DESC_SIZE);
+        volume.descs = descs;
+        Ok(volume)
+    }
+
+    fn sector_of(&self, block: u32) -> u64 {
+        self.start + block as u64 * (self.block_size / 512) as u64
+    }
+
+    /// Lit `buf.len()` octets (multiple de 512) à partir du bloc `block`
+    fn read_block_into(&self, block: u32, buf: &mut [u8]) -> Result<(), &'static str> {
+        if block >= self.blocks_count {
+            return Err("bloc hors du volume");
+        }
+        self.disk.read(self.sector_of(block), buf).map_err(|_| "erreur de lecture")
+    }
+
+    fn read_block(&self, block: u32) -> Result<Vec<u8>, &'static str> {
+        let mut buf = vec![0u8; self.block_size];
+        self.read_block_into(block, &mut buf)?;
+        Ok(buf)
+    }
+
+    fn write_block(&mut self, block: u32, buf: &[u8]) -> Result<(), &'static str> {
+        let sector = self.sector_of(block);
+        self.disk.write(sector, buf).map_err(|_| "erreur d'écriture")
+    }
+
+    fn ro_compat(&self) -> u32 {
+        le32(&self.sb, SB_FEATURE_RO_COMPAT)
+    }
+
+    fn desc(&self, group: u32) -> &[u8] {
+        &self.descs[group as usize * DESC_SIZE..(group as usize + 1) * DESC_SIZE]
+    }
+
+    fn desc_mut(&mut self, group: u32) -> &mut [u8] {
+        &mut self.descs[group as usize * DESC_SIZE..(group as usize + 1) * DESC_SIZE]
+    }
+
+    fn group_first_block(&self, group: u32) -> u32 {
+        self.first_data_block + group * self.blocks_per_group
+    }
+
+    fn blocks_in_group(&self, group: u32) -> u32 {
+        (self.blocks_count - self.group_first_block(group)).min(self.blocks_per_group)
+    }
+
+    fn inode_table_blocks(&self) -> u32 {
+        (self.inodes_per_group as usize * self.inode_size).div_ceil(self.block_size) as u32
+    }
+
+    /// Le groupe porte-t-il une copie du superbloc et des descripteurs ?
+    fn has_super(&self, group: u32) -> bool {
+        if group <= 1 || self.ro_compat() & RO_COMPAT_SPARSE_SUPER == 0 {
+            return true;
+        }
+        [3u64, 5, 7].iter().any(|&base| {
+            let mut power = base;
+            while power < group as u64 {
+                power *= base;
+            }
+            power == group as u64
+        })
+    }
+
+    /// Somme de contrôle attendue du superbloc (`metadata_csum`)
+    fn superblock_csum(&self) -> u32 {
+        crc32c(!0, &self.sb[..SB_CHECKSUM])
+    }
+
+    /// Somme de contrôle attendue d'un descripteur, s'il en porte une
+    fn desc_csum(&self, group: u32) -> Option<u16> {
+        let desc = self.desc(group);
+        let uuid = &self.sb[SB_UUID..SB_UUID + 16];
+        if self.ro_compat() & RO_COMPAT_METADATA_CSUM != 0 {
+            let mut zeroed = [0u8; DESC_SIZE];
+            zeroed.copy_from_slice(desc);
+            put16(&mut zeroed, BG_CHECKSUM, 0);
+            let seed = crc32c(!0, uuid);
+            let crc = crc32c(crc32c(seed, &group.to_le_bytes()), &zeroed);
+            Some(crc as u16)
+        } else if self.ro_compat() & RO_COMPAT_GDT_CSUM != 0 {
+            let crc = crc16(crc16(!0, uuid), &group.to_le_bytes());
+            Some(crc16(crc, &desc[..BG_CHECKSUM]))
+        } else {
+            None
+        }
+    }
+
+    /// Contenu d'un inode (taille `inode_size`)
+    fn read_inode(&self, ino: u32) -> Result<Vec<u8>, &'static str> {
+        if ino == 0 || ino > self.inodes_count {
+            return Err("numéro d'inode invalide");
+        }
+        let index = ino - 1;
+        let table = le32(self.desc(index / self.inodes_per_group), BG_INODE_TABLE);
+        let offset = (index % self.inodes_per_group) as usize * self.inode_size;
+        let block = table + (offset / self.block_size) as u32;
+        if block >= self.blocks_count {
+            return Err("table d'inodes hors du volume");
+        }
+        let within = offset % self.block_size;
+        // Secteur(s) contenant l'inode
+        let sector_offset = within / 512 * 512;
+        let mut buf = vec![0u8; (within - sector_offset + self.inode_size).div_ceil(512) * 512];
+        self.disk
+            .read(self.sector_of(block) + (sector_offset / 512) as u64, &mut buf)
+            .map_err(|_| "erreur de lecture")?;
+        Ok(buf[within - sector_offset..within - sector_offset + self.inode_size].to_vec())
+    }
+
+    fn write_inode(&mut self, ino: u32, inode: &[u8]) -> Result<(), &'static str> {
+        let index = ino - 1;
+        let table = le32(self.desc(index / self.inodes_per_group), BG_INODE_TABLE);
+        let offset = (index % self.inodes_per_group) as usize * self.inode_size;
+        let block = table + (offset / self.block_size) as u32;
+        let mut data = self.read_block(block)?;
+        let within = offset % self.block_size;
+        data[within..within + self.inode_size].copy_from_slice(inode);
+        self.write_block(block, &data)
+    }
+
+    /// Blocs d'un inode : données dans l'ordre logique, puis blocs
+    /// d'indirection ou nœuds d'extents (métadonnées)
+    fn inode_blocks(&self, inode: &[u8]) -> Result<(Vec<u32>, Vec<u32>), &'static str> {
+        let mut data = Vec::new();
+        let mut meta = Vec::new();
+        let mode = le16(inode, I_MODE);
+        let i_block = &inode[I_BLOCK..I_BLOCK + 60];
+        // Lien symbolique rapide : la cible est dans i_block
+        if mode & S_IFMT == S_IFLNK && le32(inode, 28) == 0 {
+            return Ok((data, meta));
+        }
+        if le32(inode, I_FLAGS) & EXTENTS_FL != 0 {
+            self.extent_blocks(i_block, &mut data, &mut meta, 0)?;
+        } else {
+            for i in 0..12 {
+                let block = le32(i_block, i * 4);
+                if block != 0 {
+                    data.push(block);
+                }
+            }
+            for (level, slot) in [(1, 12), (2, 13), (3, 14)] {
+                let block = le32(i_block, slot * 4);
+                if block != 0 {
+                    self.indirect_blocks(block, level, &mut data, &mut meta)?;
+                }
+            }
+        }
+        Ok((data, meta))
+    }
+
+    fn indirect_blocks(&self, block: u32, level: u32, data: &mut Vec<u32>, meta: &mut Vec<u32>) -> Result<(), &'static str> {
+        meta.push(block);
+        let table = self.read_block(block)?;
+        for entry in table.chunks_exact(4) {
+            let child = u32::from_le_bytes(entry.try_into().unwrap());
+            match (child, level) {
+                (0, _) => {}
+                (child, 1) => data.push(child),
+                (child, level) => self.indirect_blocks(child, level - 1, data, meta)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn extent_blocks(&self, node: &[u8], data: &mut Vec<u32>, meta: &mut Vec<u32>, depth_seen: u32) -> Result<(), &'static str> {
+        if le16(node, 0) != EXTENT_MAGIC || depth_seen > 5 {
+            return Err("arbre d'extents corrompu");
+        }
+        let entries = le16(node, 2) as usize;
+        let depth = le16(node, 6);
+        if 12 + entries * 12 > node.len() {
+            return Err("arbre d'extents corrompu");
+        }
+        for i in 0..entries {
+            let entry = &node[12 + i * 12..24 + i * 12];
+            if depth == 0 {
+                let len = le16(entry, 4);
+                // Au-delà de 32768 : extent non initialisé
+                let len = if len > 32768 { len - 32768 } else { len };
+                let start = le32(entry, 8);
+                data.extend((0..len as u32).map(|k| start + k));
+            } else {
+                let leaf = le32(entry, 4);
+                meta.push(leaf);
+                let child = self.read_block(leaf)?;
+                self.extent_blocks(&child, data, meta, depth_seen + 1)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Collecte des passes 2 et 3
+struct Check {
+    report: FsckReport,
+    /// Blocs utilisés reconstruits, un bit par bloc du volume
+    block_map: Vec<u8>,
+    /// Inodes atteints depuis la racine (ou réservés en service)
+    inode_map: Vec<u8>,
+    /// Liens trouvés dans les répertoires, par inode
+    links: Vec<u32>,
+    dirs_per_group: Vec<u32>,
+    duplicates: usize,
+    out_of_range: usize,
+}
+
+impl Check {
+    fn problem(&mut self, message: String, repairable: bool) {
+        if !repairable {
+            self.report.unrepaired += 1;
+        }
+        self.report.problems.push(message);
+    }
+
+    /// Marque un bloc utilisé ; `false` s'il l'était déjà ou sort du volume
+    fn claim(&mut self, fs_blocks: u32, first_data_block: u32, block: u32) -> bool {
+        if block < first_data_block || block >= fs_blocks {
+            self.out_of_range += 1;
+            return false;
+        }
+        let index = (block - first_data_block) as usize;
+        if bit(&self.block_map, index) {
+            self.duplicates += 1;
+            return false;
+        }
+        set_bit(&mut self.block_map, index);
+        true
+    }
+}
+
+/// Vérifie le volume ext2 commençant au secteur `start` ; avec `repair`,
+/// écrit les corrections
+pub fn check_ext2<D: Disk>(disk: &mut D, start: u64, repair: bool) -> Result<FsckReport, &'static str> {
+    let mut vol = Volume::open(disk, start)?;
+    let metadata_csum = vol.ro_compat() & RO_COMPAT_METADATA_CSUM != 0;
+    if repair && metadata_csum {
+        return Err("réparation non prise en charge sur un volume metadata_csum");
+    }
+    let mut check = Check {
+        report: FsckReport { inodes_count: vol.inodes_count, blocks_count: vol.blocks_count, ..Default::default() },
+        block_map: vec![0u8; (vol.groups * vol.blocks_per_group).div_ceil(8) as usize],
+        inode_map: vec![0u8; (vol.groups * vol.inodes_per_group).div_ceil(8) as usize],
+        links: vec![0; vol.inodes_count as usize + 1],
+        dirs_per_group: vec![0; vol.groups as usize],
+        duplicates: 0,
+        out_of_range: 0,
+    };
+
+    // Passe 1 : superbloc et descripteurs
+    if vol.inodes_count != vol.groups * vol.inodes_per_group {
+        check.problem(format!("superbloc : {} inodes pour {} groupes de {}", vol.inodes_count, vol.groups, vol.inodes_per_group), false);
+    }
+    if metadata_csum && le32(&vol.sb, SB_CHECKSUM) != vol.superblock_csum() {
+        check.problem(String::from("somme de contrôle du superbloc invalide"), false);
+    }
+    if le16(&vol.sb, SB_STATE) & EXT2_ERROR_FS != 0 {
+        check.problem(String::from("le volume est marqué comme contenant des erreurs"), true);
+    }
+    let table_blocks = vol.inode_table_blocks();
+    let flex_bg = le32(&vol.sb, SB_FEATURE_INCOMPAT) & INCOMPAT_FLEX_BG != 0;
+    let reserved_gdt = le16(&vol.sb, SB_RESERVED_GDT_BLOCKS) as u32;
+    let mut bad_groups = Vec::new();
+    for group in 0..vol.groups {
+        let desc = vol.desc(group);
+        if let Some(expected) = vol.desc_csum(group) {
+            if le16(desc, BG_CHECKSUM) != expected {
+                check.problem(format!("groupe {} : somme de contrôle du descripteur invalide", group), !metadata_csum);
+            }
+        }
+        let first = vol.group_first_block(group);
+        let last = first + vol.blocks_in_group(group);
+        // Avec flex_bg, bitmaps et tables peuvent être dans un autre groupe
+        let (low, high) = if flex_bg { (vol.first_data_block, vol.blocks_count) } else { (first, last) };
+        let inside = |block: u32, len: u32| block >= low && block + len <= high;
+        if !inside(le32(desc, BG_BLOCK_BITMAP), 1) || !inside(le32(desc, BG_INODE_BITMAP), 1) || !inside(le32(desc, BG_INODE_TABLE), table_blocks) {
+            check.problem(format!("groupe {} : bitmap ou table d'inodes hors du groupe", group), false);
+            bad_groups.push(group);
+            continue;
+        }
+        // Métadonnées du groupe
+        if vol.has_super(group) {
+            for block in first..first + 1 + vol.gdt_blocks + reserved_gdt {
+                check.claim(vol.blocks_count, vol.first_data_block, block);
+            }
+        }
+        let desc = vol.desc(group);
+        let (block_bitmap, inode_bitmap, inode_table) =
+            (le32(desc, BG_BLOCK_BITMAP), le32(desc, BG_INODE_BITMAP), le32(desc, BG_INODE_TABLE));
+        check.claim(vol.blocks_count, vol.first_data_block, block_bitmap);
+        check.claim(vol.blocks_count, vol.first_data_block, inode_bitmap);
+        for block in inode_table..inode_table + table_blocks {
+            check.claim(vol.blocks_count, vol.first_data_block, block);
+        }
+    }
+    if check.duplicates > 0 {
+        check.problem(format!("{} blocs de métadonnées se recouvrent", check.duplicates), false);
+        check.duplicates = 0;
+    }
+    if !bad_groups.is_empty() {
+        // Sans tables d'inodes fiables, le parcours n'aurait pas de sens
+        return Ok(check.report);
+    }
+
+    // Passe 2 : arborescence depuis la racine
+    let claim_inode = |check: &mut Check, vol: &Volume<D>, ino: u32, inode: &[u8]| {
+        set_bit(&mut check.inode_map, ino as usize - 1);
+        if le16(inode, I_MODE) & S_IFMT == S_IFDIR {
+            check.dirs_per_group[((ino - 1) / vol.inodes_per_group) as usize] += 1;
+        }
+        let (data, meta) = match vol.inode_blocks(inode) {
+            Ok(blocks) => blocks,
+            Err(e) => {
+                check.problem(format!("inode {} : {}", ino, e), false);
+                return (Vec::new(), false);
+            }
+        };
+        let acl = le32(inode, I_FILE_ACL);
+        let before = check.duplicates;
+        for &block in data.iter().chain(meta.iter()).chain((acl != 0).then_some(&acl)) {
+            check.claim(vol.blocks_count, vol.first_data_block, block);
+        }
+        if check.duplicates > before {
+            let count = check.duplicates - before;
+            check.problem(format!("inode {} : {} blocs déjà utilisés ailleurs", ino, count), false);
+        }
+        (data, true)
+    };
+
+    // Inodes réservés en service (journal, redimensionnement...)
+    for ino in 1..vol.first_ino.min(vol.inodes_count + 1) {
+        if ino == EXT2_ROOT_INO {
+            continue;
+        }
+        set_bit(&mut check.inode_map, ino as usize - 1);
+        if ino == EXT2_RESIZE_INO {
+            continue;
+        }
+        let inode = vol.read_inode(ino)?;
+        if le16(&inode, I_MODE) != 0 && le16(&inode, I_LINKS) != 0 {
+            claim_inode(&mut check, &vol, ino, &inode);
+        }
+    }
+
+    let root = vol.read_inode(EXT2_ROOT_INO)?;
+    if le16(&root, I_MODE) & S_IFMT != S_IFDIR {
+        check.problem(String::from("l'inode racine n'est pas un répertoire"), false);
+        return Ok(check.report);
+    }
+    let filetype = le32(&vol.sb, SB_FEATURE_INCOMPAT) & INCOMPAT_FILETYPE != 0;
+    let mut reached_twice = 0usize;
+    let mut queue = VecDeque::new();
+    let (root_blocks, _) = claim_inode(&mut check, &vol, EXT2_ROOT_INO, &root);
+    queue.push_back((EXT2_ROOT_INO, EXT2_ROOT_INO, root_blocks));
+    while let Some((dir, parent, blocks)) = queue.pop_front() {
+        crate::scheduler::cond_resched();
+        for block in blocks {
+            let data = vol.read_block(block)?;
+            let mut offset = 0;
+            while offset + 8 <= data.len() {
+                let child = le32(&data, offset);
+                let rec_len = le16(&data, offset + 4) as usize;
+                let name_len = if filetype { data[offset + 6] as usize } else { le16(&data, offset + 6) as usize };
+                if rec_len < 8 || rec_len % 4 != 0 || offset + rec_len > data.len() || 8 + name_len > rec_len {
+                    check.problem(format!("répertoire {} : entrée corrompue dans le bloc {}", dir, block), false);
+                    break;
+                }
+                let name = &data[offset + 8..offset + 8 + name_len];
+                offset += rec_len;
+                if child == 0 {
+                    continue;
+                }
+                if child > vol.inodes_count {
+                    check.problem(format!("répertoire {} : entrée vers l'inode inexistant {}", dir, child), false);
+                    continue;
+                }
+                check.links[child as usize] += 1;
+                match name {
+                    b"." => {
+                        if child != dir {
+                            check.problem(format!("répertoire {} : '.' désigne l'inode {}", dir, child), false);
+                        }
+                        continue;
+                    }
+                    b".." => {
+                        if child != parent {
+                            check.problem(format!("répertoire {} : '..' désigne {} au lieu de {}", dir, child, parent), false);
+                        }
+                        continue;
+                    }
+                    _ => {}
+                }
+                if bit(&check.inode_map, child as usize - 1) {
+                    let inode = vol.read_inode(child)?;
+                    if le16(&inode, I_MODE) & S_IFMT == S_IFDIR {
+                        reached_twice += 1;
+                        if reached_twice <= MAX_DETAILS {
+                            check.problem(
+                                format!("répertoire {} atteint par un second chemin ({}/{}) : boucle", child, dir, String::from_utf8_lossy(name)),
+                                false,
+                            );
+                        }
+                    }
+                    continue;
+                }
+                let inode = vol.read_inode(child)?;
+                if le16(&inode, I_MODE) == 0 || le32(&inode, I_DTIME) != 0 {
+                    check.problem(format!("répertoire {} : '{}' désigne l'inode supprimé {}", dir, String::from_utf8_lossy(name), child), false);
+                    continue;
+                }
+                let (blocks, ok) = claim_inode(&mut check, &vol, child, &inode);
+                if ok && le16(&inode, I_MODE) & S_IFMT == S_IFDIR {
+                    queue.push_back((child, dir, blocks));
+                }
+            }
+        }
+    }
+    if reached_twice > MAX_DETAILS {
+        check.problem(format!("{} autres répertoires atteints par plusieurs chemins", reached_twice - MAX_DETAILS), false);
+    }
+    if check.out_of_range > 0 {
+        check.problem(format!("{} numéros de blocs hors du volume", check.out_of_range), false);
+    }
+
+    // Passe 3 : compteurs de liens et orphelins
+    let mut link_fixes = Vec::new();
+    let mut orphans = Vec::new();
+    for group in 0..vol.groups {
+        let desc = vol.desc(group);
+        let uninit = le16(desc, BG_FLAGS) & BG_INODE_UNINIT != 0 && vol.desc_csum(group).is_some();
+        let bitmap = if uninit { vec![0u8; vol.block_size] } else { vol.read_block(le32(desc, BG_INODE_BITMAP))? };
+        for i in 0..vol.inodes_per_group {
+            let ino = group * vol.inodes_per_group + i + 1;
+            if ino < vol.first_ino || ino > vol.inodes_count {
+                continue;
+            }
+            let reached = bit(&check.inode_map, ino as usize - 1);
+            if reached {
+                let inode = vol.read_inode(ino)?;
+                let found = check.links[ino as usize];
+                if le16(&inode, I_LINKS) as u32 != found {
+                    link_fixes.push((ino, found));
+                }
+            } else if bit(&bitmap, i as usize) {
+                let inode = vol.read_inode(ino)?;
+                if le16(&inode, I_MODE) != 0 && le32(&inode, I_DTIME) == 0 {
+                    orphans.push(ino);
+                }
+            }
+        }
+    }
+    for &(ino, found) in link_fixes.iter().take(MAX_DETAILS) {
+        check.problem(format!("inode {} : compteur de liens incorrect (attendu {})", ino, found), true);
+    }
+    if link_fixes.len() > MAX_DETAILS {
+        check.problem(format!("{} autres compteurs de liens incorrects", link_fixes.len() - MAX_DETAILS), true);
+    }
+    for &ino in orphans.iter().take(MAX_DETAILS) {
+        check.problem(format!("inode {} orphelin (alloué mais inaccessible)", ino), true);
+    }
+    if orphans.len() > MAX_DETAILS {
+        check.problem(format!("{} autres inodes orphelins", orphans.len() - MAX_DETAILS), true);
+    }
+
+    // Passe 4 : bitmaps et compteurs
+    let mut new_bitmaps = Vec::new();
+    let (mut free_blocks, mut free_inodes) = (0u32, 0u32);
+    for group in 0..vol.groups {
+        let desc = vol.desc(group);
+        let flags = le16(desc, BG_FLAGS);
+        let trusted_flags = vol.desc_csum(group).is_some();
+        let blocks_in_group = vol.blocks_in_group(group) as usize;
+        let block_base = (group * vol.blocks_per_group) as usize;
+        let inode_base = (group * vol.inodes_per_group) as usize;
+
+        // Bitmaps reconstruites, bits de remplissage à 1 comme mke2fs
+        let mut block_bitmap = vec![0u8; vol.block_size];
+        let mut inode_bitmap = vec![0u8; vol.block_size];
+        for i in 0..vol.block_size * 8 {
+            if i >= blocks_in_group || bit(&check.block_map, block_base + i) {
+                set_bit(&mut block_bitmap, i);
+            }
+            if i >= vol.inodes_per_group as usize || bit(&check.inode_map, inode_base + i) {
+                set_bit(&mut inode_bitmap, i);
+            }
+        }
+        let used_blocks = (0..blocks_in_group).filter(|&i| bit(&block_bitmap, i)).count() as u32;
+        let used_inodes = (0..vol.inodes_per_group as usize).filter(|&i| bit(&inode_bitmap, i)).count() as u32;
+        let group_free_blocks = blocks_in_group as u32 - used_blocks;
+        let group_free_inodes = vol.inodes_per_group - used_inodes;
+        free_blocks += group_free_blocks;
+        free_inodes += group_free_inodes;
+
+        let disk_blocks = if flags & BG_BLOCK_UNINIT != 0 && trusted_flags { None } else { Some(vol.read_block(le32(desc, BG_BLOCK_BITMAP))?) };
+        let disk_inodes = if flags & BG_INODE_UNINIT != 0 && trusted_flags { None } else { Some(vol.read_block(le32(desc, BG_INODE_BITMAP))?) };
+        if let Some(disk_map) = &disk_blocks {
+            let leaked = (0..blocks_in_group).filter(|&i| bit(disk_map, i) && !bit(&block_bitmap, i)).count();
+            let lost = (0..blocks_in_group).filter(|&i| !bit(disk_map, i) && bit(&block_bitmap, i)).count();
+            if leaked > 0 || lost > 0 {
+                check.problem(format!("groupe {} : {} blocs marqués utilisés à tort, {} blocs utilisés marqués libres", group, leaked, lost), true);
+            }
+        }
+        if let Some(disk_map) = &disk_inodes {
+            let leaked = (0..vol.inodes_per_group as usize).filter(|&i| bit(disk_map, i) && !bit(&inode_bitmap, i)).count();
+            let lost = (0..vol.inodes_per_group as usize).filter(|&i| !bit(disk_map, i) && bit(&inode_bitmap, i)).count();
+            if leaked > 0 || lost > 0 {
+                check.problem(format!("groupe {} : {} inodes marqués utilisés à tort, {} inodes utilisés marqués libres", group, leaked, lost), true);
+            }
+        }
+        if le16(desc, BG_FREE_BLOCKS) as u32 != group_free_blocks
+            || le16(desc, BG_FREE_INODES) as u32 != group_free_inodes
+            || le16(desc, BG_USED_DIRS) as u32 != check.dirs_per_group[group as usize]
+        {
+            check.problem(format!("groupe {} : compteurs libres incorrects", group), true);
+        }
+        new_bitmaps.push((block_bitmap, inode_bitmap, group_free_blocks, group_free_inodes));
+    }
+    if le32(&vol.sb, SB_FREE_BLOCKS) != free_blocks || le32(&vol.sb, SB_FREE_INODES) != free_inodes {
+        check.problem(
+            format!(
+                "superbloc : {} blocs et {} inodes libres annoncés, {} et {} comptés",
+                le32(&vol.sb, SB_FREE_BLOCKS),
+                le32(&vol.sb, SB_FREE_INODES),
+                free_blocks,
+                free_inodes
+            ),
+            true,
+        );
+    }
+    check.report.blocks_used = vol.blocks_count - vol.first_data_block - free_blocks;
+    check.report.inodes_used = vol.inodes_count - free_inodes;
+
+    if !repair {
+        return Ok(check.report);
+    }
+
+    // Réparation
+    for &(ino, found) in &link_fixes {
+        let mut inode = vol.read_inode(ino)?;
+        put16(&mut inode, I_LINKS, found.min(u16::MAX as u32) as u16);
+        vol.write_inode(ino, &inode)?;
+    }
+    let now = crate::time::unix_time() as u32;
+    for &ino in &orphans {
+        let mut inode = vol.read_inode(ino)?;
+        put16(&mut inode, I_LINKS, 0);
+        put32(&mut inode, I_DTIME, now);
+        vol.write_inode(ino, &inode)?;
+    }
+    for (group, (block_bitmap, inode_bitmap, group_free_blocks, group_free_inodes)) in new_bitmaps.into_iter().enumerate() {
+        let group = group as u32;
+        let (block_at, inode_at) = (le32(vol.desc(group), BG_BLOCK_BITMAP), le32(vol.desc(group), BG_INODE_BITMAP));
+        vol.write_block(block_at, &block_bitmap)?;
+        vol.write_block(inode_at, &inode_bitmap)?;
+        let used_dirs = check.dirs_per_group[group as usize] as u16;
+        let desc = vol.desc_mut(group);
+        put16(desc, BG_FREE_BLOCKS, group_free_blocks as u16);
+        put16(desc, BG_FREE_INODES, group_free_inodes as u16);
+        put16(desc, BG_USED_DIRS, used_dirs);
+        // Les bitmaps sont maintenant initialisées
+        let flags = le16(desc, BG_FLAGS) & !(BG_BLOCK_UNINIT | BG_INODE_UNINIT);
+        put16(desc, BG_FLAGS, flags);
+        if let Some(csum) = vol.desc_csum(group) {
+            put16(vol.desc_mut(group), BG_CHECKSUM, csum);
+        }
+    }
+    // Seule la copie principale du GDT est réécrite
+    let gdt_block = vol.first_data_block + 1;
+    let mut gdt = vec![0u8; vol.gdt_blocks as usize * vol.block_size];
+    vol.read_block_into(gdt_block, &mut gdt)?;
+    gdt[..vol.descs.len()].copy_from_slice(&vol.descs);
+    vol.write_block(gdt_block, &gdt)?;
+
+    put32(&mut vol.sb, SB_FREE_BLOCKS, free_blocks);
+    put32(&mut vol.sb, SB_FREE_INODES, free_inodes);
+    put16(&mut vol.sb, SB_MNT_COUNT, 0);
+    put32(&mut vol.sb, SB_LASTCHECK, now);
+    let state = if check.report.unrepaired == 0 { EXT2_VALID_FS } else { EXT2_VALID_FS | EXT2_ERROR_FS };
+    put16(&mut vol.sb, SB_STATE, state);
+    write_superblock(&mut vol)?;
+    check.report.repaired = true;
+    Ok(check.report)
+}
+
+fn write_superblock<D: Disk>(vol: &mut Volume<D>) -> Result<(), &'static str> {
+    if vol.ro_compat() & RO_COMPAT_METADATA_CSUM != 0 {
+        let csum = vol.superblock_csum();
+        put32(&mut vol.sb, SB_CHECKSUM, csum);
+    }
+    let sector = vol.start + 2;
+    vol.disk.write(sector, &vol.sb).map_err(|_| "erreur d'écriture du superbloc")
+}
+
+/// Compte un montage du volume ext2 au secteur `start` et le vérifie (avec
+/// réparation) quand le nombre de montages atteint `max_mnt_count`.
+/// `fsck.mode=force` sur la ligne de commande impose la vérification,
+/// `fsck.mode=skip` la supprime. Rend le bilan si une vérification a eu lieu.
+pub fn check_on_mount<D: Disk>(disk: &mut D, start: u64) -> Result<Option<FsckReport>, &'static str> {
+    let mode = crate::cmdline::get("fsck.mode").unwrap_or("auto");
+    let due = {
+        let mut vol = Volume::open(disk, start)?;
+        let mnt_count = le16(&vol.sb, SB_MNT_COUNT).saturating_add(1);
+        let max = le16(&vol.sb, SB_MAX_MNT_COUNT) as i16;
+        put16(&mut vol.sb, SB_MNT_COUNT, mnt_count);
+        write_superblock(&mut vol)?;
+        match mode {
+            "force" => true,
+            "skip" => false,
+            // max_mnt_count négatif ou nul : vérification au montage désactivée
+            _ => max > 0 && mnt_count as i16 >= max,
+        }
+    };
+    if !due {
+        return Ok(None);
+    }
+    check_ext2(disk, start, true).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::drivers::disk::DiskError;
+
+    struct MemDisk {
+        data: Vec<u8>,
+    }
+
+    impl Disk for MemDisk {
+        fn read(&self, sector: u64, buffer: &mut [u8]) -> Result<(), DiskError> {
+            let start = sector as usize * 512;
+            buffer.copy_from_slice(self.data.get(start..start + buffer.len()).ok_or(DiskError::InvalidSector)?);
+            Ok(())
+        }
+
+        fn write(&mut self, sector: u64, buffer: &[u8]) -> Result<(), DiskError> {
+            let start = sector as usize * 512;
+            self.data.get_mut(start..start + buffer.len()).ok_or(DiskError::InvalidSector)?.copy_from_slice(buffer);
+            Ok(())
+        }
+    }
+
+    /// Volume de 64 blocs de 1 Kio, 16 inodes : racine (2) et un fichier
+    /// (12) de deux blocs
+    fn tiny_ext2() -> MemDisk {
+        let bs = 1024;
+        let mut data = vec![0u8; 64 * bs];
+        let sb = &mut data[1024..2048];
+        put32(sb, SB_INODES_COUNT, 16);
+        put32(sb, SB_BLOCKS_COUNT, 64);
+        put32(sb, SB_FIRST_DATA_BLOCK, 1);
+        put32(sb, SB_BLOCKS_PER_GROUP, 8192);
+        put32(sb, SB_INODES_PER_GROUP, 16);
+        put16(sb, SB_MAX_MNT_COUNT, 2);
+        put16(sb, SB_MAGIC, EXT2_MAGIC);
+        put16(sb, SB_STATE, EXT2_VALID_FS);
+        put32(sb, SB_REV_LEVEL, 1);
+        put32(sb, SB_FIRST_INO, 11);
+        put16(sb, SB_INODE_SIZE, 128);
+        put32(sb, SB_FEATURE_INCOMPAT, INCOMPAT_FILETYPE);
+        // Groupe 0 : GDT en 2, bitmaps en 3 et 4, table d'inodes 5-6
+        let desc = &mut data[2048..2048 + DESC_SIZE];
+        put32(desc, BG_BLOCK_BITMAP, 3);
+        put32(desc, BG_INODE_BITMAP, 4);
+        put32(desc, BG_INODE_TABLE, 5);
+        // Racine : bloc 7 ; fichier : blocs 8 et 9
+        let inode = |data: &mut [u8], ino: usize, mode: u16, links: u16, blocks: &[u32]| {
+            let at = 5 * bs + (ino - 1) * 128;
+            put16(&mut data[at..], I_MODE, mode);
+            put16(&mut data[at..], I_LINKS, links);
+            for (i, &b) in blocks.iter().enumerate() {
+                put32(&mut data[at..], I_BLOCK + i * 4, b);
+            }
+        };
+        inode(&mut data, 2, S_IFDIR | 0o755, 2, &[7]);
+        inode(&mut data, 12, 0x8000 | 0o644, 1, &[8, 9]);
+        let dir = &mut data[7 * bs..8 * bs];
+        for (offset, ino, rec_len, name) in [(0usize, 2u32, 12u16, &b"."[..]), (12, 2, 12, b".."), (24, 12, 1000, b"f")] {
+            put32(dir, offset, ino);
+            put16(dir, offset + 4, rec_len);
+            dir[offset + 6] = name.len() as u8;
+            dir[offset + 8..offset + 8 + name.len()].copy_from_slice(name);
+        }
+        // Bitmaps et compteurs cohérents : blocs 1 à 9, inodes 1 à 10 et 12
+        data[3 * bs] = 0xFF;
+        data[3 * bs + 1] = 0x01;
+        for i in 63..8192 {
+            data[3 * bs + i / 8] |= 1 << (i % 8);
+        }
+        data[4 * bs] = 0xFF;
+        data[4 * bs + 1] = 0x0B;
+        for i in 16..8192 {
+            data[4 * bs + i / 8] |= 1 << (i % 8);
+        }
+        let desc = &mut data[2048..2048 + DESC_SIZE];
+        put16(desc, BG_FREE_BLOCKS, 63 - 9);
+        put16(desc, BG_FREE_INODES, 5);
+        put16(desc, BG_USED_DIRS, 1);
+        put32(&mut data[1024..], SB_FREE_BLOCKS, 54);
+        put32(&mut data[1024..], SB_FREE_INODES, 5);
+        MemDisk { data }
+    }
+
+    #[test_case]
+    fn test_fsck_detects_and_repairs() {
+        assert_eq!(crc16(!0, b"123456789"), 0x4B37);
+        assert_eq!(!crc32c(!0, b"123456789"), 0xE306_9283);
+
+        let mut disk = tiny_ext2();
+        let report = check_ext2(&mut disk, 0, false).unwrap();
+        assert!(report.is_clean(), "{:?}", report.problems);
+        assert_eq!((report.blocks_used, report.inodes_used), (9, 11));
+
+        // Bloc 9 libéré à tort, inode 13 orphelin, compteur de liens faux
+        disk.data[3 * 1024 + 1] = 0;
+        disk.data[4 * 1024 + 1] |= 0x10;
+        put16(&mut disk.data[5 * 1024 + 12 * 128..], I_MODE, 0x8000 | 0o600);
+        put16(&mut disk.data[5 * 1024 + 12 * 128..], I_LINKS, 1);
+        put16(&mut disk.data[5 * 1024 + 11 * 128..], I_LINKS, 3);
+        let report = check_ext2(&mut disk, 0, true).unwrap();
+        assert!(report.repaired && report.unrepaired == 0);
+        assert!(report.problems.iter().any(|p| p.contains("orphelin")));
+        assert!(report.problems.iter().any(|p| p.contains("liens")));
+        assert!(check_ext2(&mut disk, 0, false).unwrap().is_clean());
+
+        // Deuxième montage : max_mnt_count atteint, vérification
+        assert!(check_on_mount(&mut disk, 0).unwrap().is_none());
+        assert!(check_on_mount(&mut disk, 0).unwrap().is_some());
+        assert_eq!(le16(&disk.data[1024..], SB_MNT_COUNT), 0);
+    }
+}
@@ -0,0 +1,434 @@
+/// Module gdbstub - Débogage distant du noyau avec GDB
+///
+/// Le noyau parle le protocole série distant de GDB sur COM2 (0x2F8).
+/// Avec QEMU :
+///
+/// ```text
+/// qemu-system-x86_64 ... -serial stdio -serial tcp::1234,server,nowait
+/// (gdb) target remote :1234
+/// ```
+///
+/// Les points d'arrêt sont des `int3` (0xCC) écrits dans le code du
+/// noyau ; le pas-à-pas utilise le drapeau TF de RFLAGS (#DB). Les
+/// threads noyau sont exposés comme threads GDB (identifiant = TID).
+
+pub mod packet;
+pub mod trap;
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use packet::{hex_decode, hex_encode, parse_hex};
+
+/// Signal rapporté à GDB pour un arrêt sur point d'arrêt / pas-à-pas
+const SIGTRAP: u8 = 5;
+
+/// Instruction `int3`
+pub const INT3: u8 = 0xCC;
+
+/// Nombre de registres 64 bits (rax..r15 puis rip), ordre GDB amd64
+pub const GPR_COUNT: usize = 17;
+
+/// Registres dans l'ordre attendu par GDB pour amd64 :
+/// rax rbx rcx rdx rsi rdi rbp rsp r8..r15 rip, puis eflags cs ss ds es fs gs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Registers {
+    pub gpr: [u64; GPR_COUNT],
+    pub eflags: u32,
+    pub segments: [u32; 6],
+}
+
+impl Registers {
+    pub const RSP: usize = 7;
+    pub const RIP: usize = 16;
+
+    /// Encodage du paquet `g`
+    fn to_hex(&self) -> String {
+        let mut bytes = Vec::with_capacity(GPR_COUNT * 8 + 7 * 4);
+        for reg in &self.gpr {
+            bytes.extend_from_slice(&reg.to_le_bytes());
+        }
+        bytes.extend_from_slice(&self.eflags.to_le_bytes());
+        for seg in &self.segments {
+            bytes.extend_from_slice(&seg.to_le_bytes());
+        }
+        hex_encode(&bytes)
+    }
+
+    /// Décodage du paquet `G` ; les registres absents sont conservés
+    fn update_from_hex(&mut self, hex: &[u8]) -> Option<()> {
+        let bytes = hex_decode(hex)?;
+        let mut chunks = bytes.chunks_exact(8);
+        for reg in self.gpr.iter_mut() {
+            match chunks.next() {
+                Some(c) => *reg = u64::from_le_bytes(c.try_into().ok()?),
+                None => return Some(()),
+            }
+        }
+        let mut rest = bytes[GPR_COUNT * 8..].chunks_exact(4);
+        if let Some(c) = rest.next() {
+            self.eflags = u32::from_le_bytes(c.try_into().ok()?);
+        }
+        for seg in self.segments.iter_mut() {
+            match rest.next() {
+                Some(c) => *seg = u32::from_le_bytes(c.try_into().ok()?),
+                None => break,
+            }
+        }
+        Some(())
+    }
+}
+
+/// Accès à la machine déboguée (noyau réel, ou simulé dans les tests)
+pub trait Target {
+    fn read_byte(&self, addr: u64) -> Option<u8>;
+    fn write_byte(&mut self, addr: u64, value: u8) -> bool;
+    /// TID des threads connus
+    fn threads(&self) -> Vec<u64>;
+    /// Thread arrêté (celui qui a déclenché le piège)
+    fn current_thread(&self) -> u64;
+    /// Registres sauvegardés d'un thread qui ne s'exécute pas
+    fn thread_registers(&self, tid: u64) -> Option<Registers>;
+}
+
+/// Reprise demandée par GDB
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resume {
+    Continue,
+    Step,
+}
+
+/// Suite à donner à un paquet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    Reply(String),
+    Resume(Resume),
+    /// Détachement : points d'arrêt retirés, exécution normale
+    Detach,
+}
+
+/// État du stub
+pub struct GdbStub {
+    /// Points d'arrêt logiciels : adresse -> octet d'origine
+    breakpoints: BTreeMap<u64, u8>,
+    /// Thread sélectionné par `Hg` (None : thread arrêté)
+    selected_thread: Option<u64>,
+    /// Point d'arrêt retiré le temps d'exécuter l'instruction d'origine
+    pub(crate) step_over: Option<u64>,
+    /// Reprise en cours (pour savoir si le #DB du step_over doit s'arrêter)
+    pub(crate) resume: Resume,
+}
+
+impl GdbStub {
+    pub const fn new() -> Self {
+        Self {
+            breakpoints: BTreeMap::new(),
+            selected_thread: None,
+            step_over: None,
+            resume: Resume::Continue,
+        }
+    }
+
+    /// Paquet d'arrêt `T05thread:<tid>;`
+    pub fn stop_reply(&self, target: &dyn Target) -> String {
+        format!("T{:02x}thread:{:x};", SIGTRAP, target.current_thread())
+    }
+
+    pub fn has_breakpoint(&self, addr: u64) -> bool {
+        self.breakpoints.contains_key(&addr)
+    }
+
+    /// Octet d'origine sous un point d'arrêt
+    pub fn original_byte(&self, addr: u64) -> Option<u8> {
+        self.breakpoints.get(&addr).copied()
+    }
+
+    /// Retire tous les points d'arrêt de la mémoire
+    pub fn remove_all_breakpoints(&mut self, target: &mut dyn Target) {
+        for (&addr, &orig) in &self.breakpoints {
+            target.write_byte(addr, orig);
+        }
+        self.breakpoints.clear();
+    }
+
+    fn insert_breakpoint(&mut self, target: &mut dyn Target, addr: u64) -> bool {
+        if self.breakpoints.contains_key(&addr) {
+            return true;
+        }
+        match target.read_byte(addr) {
+            Some(orig) if target.write_byte(addr, INT3) => {
+                self.breakpoints.insert(addr, orig);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn remove_breakpoint(&mut self, target: &mut dyn Target, addr: u64) -> bool {
+        match self.breakpoints.remove(&addr) {
+            Some(orig) => target.write_byte(addr, orig),
+            None => true,
+        }
+    }
+
+    /// Registres vus par GDB pour le thread sélectionné
+    fn selected_registers(&self, target: &dyn Target, regs: &Registers) -> Option<Registers> {
+        match self.selected_thread {
+            Some(tid) if tid != target.current_thread() => target.thread_registers(tid),
+            _ => Some(*regs),
+        }
+    }
+
+    /// Lecture mémoire ; les points d'arrêt sont masqués (octets d'origine)
+    fn read_memory(&self, target: &dyn Target, addr: u64, len: u64) -> Option<Vec<u8>> {
+        (0..len)
+            .map(|i| {
+                let a = addr.checked_add(i)?;
+                self.original_byte(a).or_else(|| target.read_byte(a))
+            })
+            .collect()
+    }
+
+    /// Traite un paquet déjà décodé
+    pub fn handle(&mut self, packet: &[u8], regs: &mut Registers, target: &mut dyn Target) -> Action {
+        let reply = |s: &str| Action::Reply(String::from(s));
+        let (cmd, args) = match packet.split_first() {
+            Some((&cmd, args)) => (cmd, args),
+            None => return reply(""),
+        };
+
+        match cmd {
+            b'?' => Action::Reply(self.stop_reply(target)),
+            b'g' => match self.selected_registers(target, regs) {
+                Some(r) => Action::Reply(r.to_hex()),
+                None => reply("E01"),
+            },
+            b'G' => {
+                // Seul le thread arrêté a des registres modifiables
+                if matches!(self.selected_thread, Some(tid) if tid != target.current_thread()) {
+                    return reply("E01");
+                }
+                match regs.update_from_hex(args) {
+                    Some(()) => reply("OK"),
+                    None => reply("E01"),
+                }
+            }
+            b'm' => match parse_addr_len(args) {
+                Some((addr, len)) => match self.read_memory(target, addr, len) {
+                    Some(bytes) => Action::Reply(hex_encode(&bytes)),
+                    None => reply("E14"),
+                },
+                None => reply("E01"),
+            },
+            b'M' => {
+                let mut parts = args.splitn(2, |&b| b == b':');
+                let header = parts.next().unwrap_or(&[]);
+                let data = parts.next().and_then(hex_decode);
+                match (parse_addr_len(header), data) {
+                    (Some((addr, len)), Some(data)) if data.len() as u64 == len => {
+                        for (i, &b) in data.iter().enumerate() {
+                            let a = addr + i as u64;
+                            // Écriture sous un point d'arrêt : on met à jour l'original
+                            if let Some(orig) = self.breakpoints.get_mut(&a) {
+                                *orig = b;
+                            } else if !target.write_byte(a, b) {
+                                return reply("E14");
+                            }
+                        }
+                        reply("OK")
+                    }
+                    _ => reply("E01"),
+                }
+            }
+            b'Z' | b'z' => {
+                // Seuls les points d'arrêt logiciels (type 0) sont gérés
+                if args.first() != Some(&b'0') {
+                    return reply("");
+                }
+                let addr = args.get(2..).and_then(|rest| rest.split(|&b| b == b',').next()).and_then(parse_hex);
+                match addr {
+                    Some(addr) => {
+                        let ok = if cmd == b'Z' {
+                            self.insert_breakpoint(target, addr)
+                        } else {
+                            self.remove_breakpoint(target, addr)
+                        };
+                        reply(if ok { "OK" } else { "E14" })
+                    }
+                    None => reply("E01"),
+                }
+            }
+            b'c' | b's' => {
+                if let Some(addr) = parse_hex(args) {
+                    regs.gpr[Registers::RIP] = addr;
+                }
+                Action::Resume(if cmd == b'c' { Resume::Continue } else { Resume::Step })
+            }
+            b'H' => {
+                // `Hg<tid>` : thread des requêtes de registres ; `Hc` ignoré
+                if args.first() == Some(&b'g') {
+                    self.selected_thread = match &args[1..] {
+                        b"0" | b"-1" => None,
+                        tid => parse_hex(tid),
+                    };
+                }
+                reply("OK")
+            }
+            b'T' => match parse_hex(args) {
+                Some(tid) if target.threads().contains(&tid) || tid == target.current_thread() => reply("OK"),
+                _ => reply("E01"),
+            },
+            b'D' => Action::Detach,
+            b'q' => self.handle_query(args, target),
+            _ => reply(""),
+        }
+    }
+
+    fn handle_query(&mut self, query: &[u8], target: &dyn Target) -> Action {
+        let reply = |s: &str| Action::Reply(String::from(s));
+        if query.starts_with(b"Supported") {
+            reply("PacketSize=1000;swbreak+")
+        } else if query == b"fThreadInfo" {
+            let mut threads = target.threads();
+            let current = target.current_thread();
+            if !threads.contains(&current) {
+                threads.insert(0, current);
+            }
+            let mut out = String::from("m");
+            for (i, tid) in threads.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                let _ = write!(out, "{:x}", tid);
+            }
+            Action::Reply(out)
+        } else if query == b"sThreadInfo" {
+            reply("l")
+        } else if query == b"C" {
+            Action::Reply(format!("QC{:x}", target.current_thread()))
+        } else if query == b"Attached" {
+            reply("1")
+        } else {
+            reply("")
+        }
+    }
+}
+
+/// `addr,len` en hexadécimal
+fn parse_addr_len(args: &[u8]) -> Option<(u64, u64)> {
+    let mut parts = args.splitn(2, |&b| b == b',');
+    let addr = parse_hex(parts.next()?)?;
+    let len = parse_hex(parts.next()?)?;
+    // Borne raisonnable : un paquet fait au plus PacketSize octets
+    if len > 0x800 {
+        return None;
+    }
+    Some((addr, len))
+}
+
+pub use trap::{init, breakpoint, is_enabled};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Machine simulée : 256 octets de mémoire à partir de 0x1000
+    struct FakeTarget {
+        mem: [u8; 256],
+    }
+
+    impl Target for FakeTarget {
+        fn read_byte(&self, addr: u64) -> Option<u8> {
+            addr.checked_sub(0x1000).and_then(|o| self.mem.get(o as usize)).copied()
+        }
+        fn write_byte(&mut self, addr: u64, value: u8) -> bool {
+            match addr.checked_sub(0x1000).and_then(|o| self.mem.get_mut(o as usize)) {
+                Some(b) => {
+                    *b = value;
+                    true
+                }
+                None => false,
+            }
+        }
+        fn threads(&self) -> Vec<u64> {
+            alloc::vec![1000, 1001]
+        }
+        fn current_thread(&self) -> u64 {
+            1000
+        }
+        fn thread_registers(&self, tid: u64) -> Option<Registers> {
+            let mut regs = Registers::default();
+            regs.gpr[Registers::RIP] = tid;
+            (tid == 1001).then_some(regs)
+        }
+    }
+
+    fn text(action: Action) -> String {
+        match action {
+            Action::Reply(s) => s,
+            other => panic!("réponse attendue: {:?}", other),
+        }
+    }
+
+    #[test_case]
+    fn test_memory_and_breakpoints() {
+        let mut stub = GdbStub::new();
+        let mut target = FakeTarget { mem: [0x90; 256] };
+        let mut regs = Registers::default();
+
+        assert_eq!(text(stub.handle(b"M1000,2:abcd", &mut regs, &mut target)), "OK");
+        assert_eq!(text(stub.handle(b"m1000,2", &mut regs, &mut target)), "abcd");
+        assert_eq!(text(stub.handle(b"m2000,1", &mut regs, &mut target)), "E14");
+
+        // Le point d'arrêt écrit int3 mais reste invisible pour `m`
+        assert_eq!(text(stub.handle(b"Z0,1001,1", &mut regs, &mut target)), "OK");
+        assert_eq!(target.mem[1], INT3);
+        assert_eq!(text(stub.handle(b"m1001,1", &mut regs, &mut target)), "cd");
+        assert_eq!(text(stub.handle(b"z0,1001,1", &mut regs, &mut target)), "OK");
+        assert_eq!(target.mem[1], 0xcd);
+    }
+
+    #[test_case]
+    fn test_registers_and_resume() {
+        let mut stub = GdbStub::new();
+        let mut target = FakeTarget { mem: [0; 256] };
+        let mut regs = Registers::default();
+        regs.gpr[0] = 0x1122334455667788;
+
+        let g = text(stub.handle(b"g", &mut regs, &mut target));
+        assert!(g.starts_with("8877665544332211"));
+        assert_eq!(g.len(), (GPR_COUNT * 8 + 7 * 4) * 2);
+
+        let mut new_regs = regs;
+        new_regs.gpr[Registers::RIP] = 0xdead;
+        assert_eq!(text(stub.handle(format!("G{}", new_regs.to_hex()).as_bytes(), &mut regs, &mut target)), "OK");
+        assert_eq!(regs, new_regs);
+
+        assert_eq!(stub.handle(b"s", &mut regs, &mut target), Action::Resume(Resume::Step));
+        assert_eq!(stub.handle(b"c1234", &mut regs, &mut target), Action::Resume(Resume::Continue));
+        assert_eq!(regs.gpr[Registers::RIP], 0x1234);
+    }
+
+    #[test_case]
+    fn test_thread_queries() {
+        let mut stub = GdbStub::new();
+        let mut target = FakeTarget { mem: [0; 256] };
+        let mut regs = Registers::default();
+
+        assert_eq!(text(stub.handle(b"qfThreadInfo", &mut regs, &mut target)), "m3e8,3e9");
+        assert_eq!(text(stub.handle(b"qsThreadInfo", &mut regs, &mut target)), "l");
+        assert_eq!(text(stub.handle(b"qC", &mut regs, &mut target)), "QC3e8");
+        assert_eq!(text(stub.handle(b"?", &mut regs, &mut target)), "T05thread:3e8;");
+
+        // Registres d'un autre thread : lecture seule
+        assert_eq!(text(stub.handle(b"Hg3e9", &mut regs, &mut target)), "OK");
+        let g = text(stub.handle(b"g", &mut regs, &mut target));
+        assert_eq!(&g[16 * 16..17 * 16], "e903000000000000");
+        assert_eq!(text(stub.handle(b"G00", &mut regs, &mut target)), "E01");
+        assert_eq!(text(stub.handle(b"T3e9", &mut regs, &mut target)), "OK");
+        assert_eq!(text(stub.handle(b"T999", &mut regs, &mut target)), "E01");
+    }
+}
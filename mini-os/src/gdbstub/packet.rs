@@ -0,0 +1,208 @@
+/// Codage des paquets du protocole série distant de GDB (RSP)
+///
+/// Un paquet a la forme `$<données>#<somme>` où la somme est la somme
+/// modulo 256 des octets de données, en deux chiffres hexadécimaux.
+/// Les octets `#`, `$`, `}` et `*` sont échappés par `}` suivi de
+/// l'octet XOR 0x20 (paquets binaires `X`).
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Somme de contrôle d'un paquet
+pub fn checksum(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |sum, &b| sum.wrapping_add(b))
+}
+
+/// Encadre des données : `$data#cs`
+pub fn frame(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() + 4);
+    out.push(b'$');
+    for &b in data {
+        if matches!(b, b'#' | b'$' | b'}' | b'*') {
+            out.push(b'}');
+            out.push(b ^ 0x20);
+        } else {
+            out.push(b);
+        }
+    }
+    out.push(b'#');
+    let cs = checksum(&out[1..out.len() - 1]);
+    out.extend_from_slice(&hex_byte(cs));
+    out
+}
+
+/// Erreurs de décodage
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PacketError {
+    BadChecksum,
+    Truncated,
+}
+
+/// Accumule les octets reçus jusqu'à un paquet complet
+pub struct PacketReader {
+    buf: Vec<u8>,
+    in_packet: bool,
+    /// Chiffres de somme restant à lire
+    checksum_left: usize,
+}
+
+/// Événement produit par le lecteur
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReadEvent {
+    /// Paquet complet et valide (données déséchappées)
+    Packet(Vec<u8>),
+    /// Paquet corrompu : à rejeter avec `-`
+    Corrupted,
+    /// Ctrl-C (0x03) reçu hors paquet : demande d'interruption
+    Interrupt,
+}
+
+impl PacketReader {
+    pub fn new() -> Self {
+        Self { buf: Vec::new(), in_packet: false, checksum_left: 0 }
+    }
+
+    /// Traite un octet ; renvoie un événement quand un paquet est terminé
+    pub fn push(&mut self, byte: u8) -> Option<ReadEvent> {
+        if !self.in_packet {
+            match byte {
+                b'$' => {
+                    self.buf.clear();
+                    self.in_packet = true;
+                    self.checksum_left = 0;
+                }
+                0x03 => return Some(ReadEvent::Interrupt),
+                // '+' / '-' et bruit entre paquets ignorés
+                _ => {}
+            }
+            return None;
+        }
+
+        if self.checksum_left > 0 {
+            self.buf.push(byte);
+            self.checksum_left -= 1;
+            if self.checksum_left == 0 {
+                self.in_packet = false;
+                return Some(match decode(&self.buf) {
+                    Ok(data) => ReadEvent::Packet(data),
+                    Err(_) => ReadEvent::Corrupted,
+                });
+            }
+            return None;
+        }
+
+        if byte == b'#' {
+            self.buf.push(byte);
+            self.checksum_left = 2;
+        } else {
+            self.buf.push(byte);
+        }
+        None
+    }
+}
+
+/// Décode `data#cs` (sans le `$` initial)
+fn decode(raw: &[u8]) -> Result<Vec<u8>, PacketError> {
+    if raw.len() < 3 || raw[raw.len() - 3] != b'#' {
+        return Err(PacketError::Truncated);
+    }
+    let (body, cs) = raw.split_at(raw.len() - 3);
+    let expected = parse_hex(&cs[1..]).ok_or(PacketError::Truncated)? as u8;
+    if checksum(body) != expected {
+        return Err(PacketError::BadChecksum);
+    }
+
+    let mut data = Vec::with_capacity(body.len());
+    let mut escaped = false;
+    for &b in body {
+        if escaped {
+            data.push(b ^ 0x20);
+            escaped = false;
+        } else if b == b'}' {
+            escaped = true;
+        } else {
+            data.push(b);
+        }
+    }
+    Ok(data)
+}
+
+/// Octet en deux chiffres hexadécimaux minuscules
+pub fn hex_byte(b: u8) -> [u8; 2] {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    [DIGITS[(b >> 4) as usize], DIGITS[(b & 0xf) as usize]]
+}
+
+/// Encode des octets en hexadécimal
+pub fn hex_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for &b in bytes {
+        let [hi, lo] = hex_byte(b);
+        out.push(hi as char);
+        out.push(lo as char);
+    }
+    out
+}
+
+/// Décode une suite hexadécimale d'octets (longueur paire)
+pub fn hex_decode(hex: &[u8]) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    hex.chunks(2).map(|pair| parse_hex(pair).map(|v| v as u8)).collect()
+}
+
+/// Entier hexadécimal (64 bits au plus)
+pub fn parse_hex(hex: &[u8]) -> Option<u64> {
+    if hex.is_empty() || hex.len() > 16 {
+        return None;
+    }
+    let mut value = 0u64;
+    for &c in hex {
+        let digit = (c as char).to_digit(16)?;
+        value = (value << 4) | digit as u64;
+    }
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_frame_and_read_roundtrip() {
+        let framed = frame(b"OK");
+        assert_eq!(&framed, b"$OK#9a");
+
+        let mut reader = PacketReader::new();
+        let mut event = None;
+        for &b in b"+$m1000,4#8e" {
+            if let Some(e) = reader.push(b) {
+                event = Some(e);
+            }
+        }
+        assert_eq!(event, Some(ReadEvent::Packet(b"m1000,4".to_vec())));
+    }
+
+    #[test_case]
+    fn test_escaping_and_bad_checksum() {
+        let framed = frame(b"a#b");
+        assert_eq!(&framed[..6], b"$a}\x03b#");
+        let mut reader = PacketReader::new();
+        let event = framed.iter().filter_map(|&b| reader.push(b)).last();
+        assert_eq!(event, Some(ReadEvent::Packet(b"a#b".to_vec())));
+
+        let event = b"$OK#00".iter().filter_map(|&b| reader.push(b)).last();
+        assert_eq!(event, Some(ReadEvent::Corrupted));
+        assert_eq!(reader.push(0x03), Some(ReadEvent::Interrupt));
+    }
+
+    #[test_case]
+    fn test_hex_helpers() {
+        assert_eq!(hex_encode(&[0x00, 0xab, 0x7f]), "00ab7f");
+        assert_eq!(hex_decode(b"00ab7f"), Some(alloc::vec![0x00, 0xab, 0x7f]));
+        assert_eq!(hex_decode(b"abc"), None);
+        assert_eq!(parse_hex(b"ffff8000"), Some(0xffff_8000));
+        assert_eq!(parse_hex(b"xyz"), None);
+    }
+}
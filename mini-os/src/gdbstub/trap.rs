@@ -0,0 +1,383 @@
+/// Points d'entrée #BP / #DB et boucle de session GDB
+///
+/// Les gestionnaires `x86-interrupt` ne donnent pas accès aux registres
+/// généraux : les vecteurs 1 et 3 passent donc par de petits stubs
+/// assembleur qui empilent tous les registres (`TrapFrame`) avant
+/// d'appeler `gdb_trap_handler`, puis les restaurent avant `iretq`.
+/// GDB peut ainsi lire et modifier l'état complet du thread arrêté.
+
+use alloc::vec::Vec;
+use core::arch::global_asm;
+use core::sync::atomic::{AtomicBool, Ordering};
+use lazy_static::lazy_static;
+use spin::Mutex;
+use uart_16550::SerialPort;
+use x86_64::registers::control::{Cr0, Cr0Flags, Cr3};
+use x86_64::VirtAddr;
+
+use super::packet::{frame, PacketReader, ReadEvent};
+use super::{Action, GdbStub, Registers, Resume, Target};
+
+/// Port série réservé à GDB (COM2)
+pub const GDB_SERIAL_PORT: u16 = 0x2F8;
+
+/// Drapeau TF (pas-à-pas) de RFLAGS
+const RFLAGS_TF: u64 = 1 << 8;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    static ref GDB_SERIAL: Mutex<SerialPort> = {
+        let mut port = unsafe { SerialPort::new(GDB_SERIAL_PORT) };
+        port.init();
+        Mutex::new(port)
+    };
+    static ref STUB: Mutex<GdbStub> = Mutex::new(GdbStub::new());
+}
+
+/// Registres sauvegardés par les stubs d'entrée, dans l'ordre de la pile
+#[repr(C)]
+#[derive(Debug)]
+pub struct TrapFrame {
+    pub r15: u64,
+    pub r14: u64,
+    pub r13: u64,
+    pub r12: u64,
+    pub r11: u64,
+    pub r10: u64,
+    pub r9: u64,
+    pub r8: u64,
+    pub rbp: u64,
+    pub rdi: u64,
+    pub rsi: u64,
+    pub rdx: u64,
+    pub rcx: u64,
+    pub rbx: u64,
+    pub rax: u64,
+    // Cadre empilé par le processeur (pas de code d'erreur pour #BP/#DB)
+    pub rip: u64,
+    pub cs: u64,
+    pub rflags: u64,
+    pub rsp: u64,
+    pub ss: u64,
+}
+
+global_asm!(
+    ".global gdb_breakpoint_entry",
+    ".global gdb_debug_entry",
+    "gdb_breakpoint_entry:",
+    // Depuis le ring 3 (CS empilé) : GS du noyau
+    "    test byte ptr [rsp + 8], 3",
+    "    jz 1f",
+    "    swapgs",
+    "1:",
+    "    push rax", "    push rbx", "    push rcx", "    push rdx",
+    "    push rsi", "    push rdi", "    push rbp", "    push r8",
+    "    push r9", "    push r10", "    push r11", "    push r12",
+    "    push r13", "    push r14", "    push r15",
+    "    mov rsi, 3",
+    "    jmp gdb_trap_common",
+    "gdb_debug_entry:",
+    "    test byte ptr [rsp + 8], 3",
+    "    jz 1f",
+    "    swapgs",
+    "1:",
+    "    push rax", "    push rbx", "    push rcx", "    push rdx",
+    "    push rsi", "    push rdi", "    push rbp", "    push r8",
+    "    push r9", "    push r10", "    push r11", "    push r12",
+    "    push r13", "    push r14", "    push r15",
+    "    mov rsi, 1",
+    "gdb_trap_common:",
+    // 5 mots CPU + 15 registres : la pile reste alignée sur 16 octets
+    "    mov rdi, rsp",
+    "    cld",
+    "    call gdb_trap_handler",
+    "    pop r15", "    pop r14", "    pop r13", "    pop r12",
+    "    pop r11", "    pop r10", "    pop r9", "    pop r8",
+    "    pop rbp", "    pop rdi", "    pop rsi", "    pop rdx",
+    "    pop rcx", "    pop rbx", "    pop rax",
+    "    test byte ptr [rsp + 8], 3",
+    "    jz 1f",
+    "    swapgs",
+    "1:",
+    "    iretq",
+);
+
+extern "C" {
+    fn gdb_breakpoint_entry();
+    fn gdb_debug_entry();
+}
+
+/// Adresse du point d'entrée #BP (vecteur 3) à installer dans l'IDT
+pub fn breakpoint_entry() -> VirtAddr {
+    VirtAddr::new(gdb_breakpoint_entry as usize as u64)
+}
+
+/// Adresse du point d'entrée #DB (vecteur 1) à installer dans l'IDT
+pub fn debug_entry() -> VirtAddr {
+    VirtAddr::new(gdb_debug_entry as usize as u64)
+}
+
+/// Active le stub : les pièges suivants ouvrent une session GDB
+pub fn init() {
+    lazy_static::initialize(&GDB_SERIAL);
+    ENABLED.store(true, Ordering::SeqCst);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+/// Arrêt volontaire dans le débogueur (ex : au démarrage pour attendre GDB)
+pub fn breakpoint() {
+    unsafe { core::arch::asm!("int3") };
+}
+
+impl TrapFrame {
+    fn registers(&self) -> Registers {
+        use x86_64::instructions::segmentation::{Segment, DS, ES, FS, GS};
+        Registers {
+            gpr: [
+                self.rax, self.rbx, self.rcx, self.rdx, self.rsi, self.rdi, self.rbp, self.rsp,
+                self.r8, self.r9, self.r10, self.r11, self.r12, self.r13, self.r14, self.r15,
+                self.rip,
+            ],
+            eflags: self.rflags as u32,
+            segments: [
+                self.cs as u32,
+                self.ss as u32,
+                DS::get_reg().0 as u32,
+                ES::get_reg().0 as u32,
+                FS::get_reg().0 as u32,
+                GS::get_reg().0 as u32,
+            ],
+        }
+    }
+
+    /// Réécrit les registres modifiés par GDB (segments non modifiables)
+    fn set_registers(&mut self, regs: &Registers) {
+        let g = &regs.gpr;
+        self.rax = g[0];
+        self.rbx = g[1];
+        self.rcx = g[2];
+        self.rdx = g[3];
+        self.rsi = g[4];
+        self.rdi = g[5];
+        self.rbp = g[6];
+        self.rsp = g[7];
+        self.r8 = g[8];
+        self.r9 = g[9];
+        self.r10 = g[10];
+        self.r11 = g[11];
+        self.r12 = g[12];
+        self.r13 = g[13];
+        self.r14 = g[14];
+        self.r15 = g[15];
+        self.rip = g[16];
+        // Les bits réservés et IOPL restent ceux du processeur
+        self.rflags = (self.rflags & !0xffff_ffff) | regs.eflags as u64;
+    }
+}
+
+/// Le noyau vu par GDB
+struct KernelTarget {
+    current_tid: u64,
+}
+
+/// Adresse virtuelle présente dans les tables de pages actives ?
+/// (mémoire physique en identité, comme le reste du noyau)
+fn is_mapped(addr: u64) -> bool {
+    use x86_64::structures::paging::{PageTable, PageTableFlags};
+
+    if VirtAddr::try_new(addr).is_err() {
+        return false;
+    }
+    let indices = [(addr >> 39) & 0x1ff, (addr >> 30) & 0x1ff, (addr >> 21) & 0x1ff, (addr >> 12) & 0x1ff];
+    let mut table_phys = Cr3::read().0.start_address().as_u64();
+
+    for (level, &index) in indices.iter().enumerate() {
+        let table = unsafe { &*(table_phys as *const PageTable) };
+        let entry = &table[index as usize];
+        if !entry.flags().contains(PageTableFlags::PRESENT) {
+            return false;
+        }
+        // Pages de 1 Gio (niveau 3) ou 2 Mio (niveau 2)
+        if level > 0 && level < 3 && entry.flags().contains(PageTableFlags::HUGE_PAGE) {
+            return true;
+        }
+        table_phys = entry.addr().as_u64();
+    }
+    true
+}
+
+impl Target for KernelTarget {
+    fn read_byte(&self, addr: u64) -> Option<u8> {
+        if !is_mapped(addr) {
+            return None;
+        }
+        Some(unsafe { core::ptr::read_volatile(addr as *const u8) })
+    }
+
+    fn write_byte(&mut self, addr: u64, value: u8) -> bool {
+        if !is_mapped(addr) {
+            return false;
+        }
+        // Le code du noyau peut être en lecture seule : WP levé le temps de l'écriture
+        let cr0 = Cr0::read();
+        unsafe {
+            Cr0::write(cr0 - Cr0Flags::WRITE_PROTECT);
+            core::ptr::write_volatile(addr as *mut u8, value);
+            Cr0::write(cr0);
+        }
+        true
+    }
+
+    fn threads(&self) -> Vec<u64> {
+        let mut tids = Vec::new();
+        // Le piège a pu survenir verrou tenu : jamais d'attente ici
+        let _ = with_threads(|thread| {
+            tids.push(thread.tid);
+            false
+        });
+        if tids.is_empty() {
+            tids.push(self.current_tid);
+        }
+        tids
+    }
+
+    fn current_thread(&self) -> u64 {
+        self.current_tid
+    }
+
+    fn thread_registers(&self, tid: u64) -> Option<Registers> {
+        let mut found = None;
+        with_threads(|thread| {
+            if thread.tid != tid {
+                return false;
+            }
+            // Contexte sauvegardé : registres généraux dans l'ordre GDB, rsp/rip à part
+            let ctx = &thread.context;
+            let mut gpr = [0u64; super::GPR_COUNT];
+            gpr[..16].copy_from_slice(&ctx.registers);
+            gpr[Registers::RSP] = ctx.rsp;
+            gpr[Registers::RIP] = ctx.rip;
+            found = Some(Registers { gpr, eflags: ctx.rflags as u32, segments: [0x08, 0x10, 0x10, 0x10, 0, 0] });
+            true
+        });
+        found
+    }
+}
+
+/// Parcourt les threads sans bloquer (les verrous déjà pris sont sautés)
+/// jusqu'à ce que `visit` renvoie `true`
+fn with_threads(mut visit: impl FnMut(&crate::process::Thread) -> bool) -> bool {
+    let pm = match crate::process::PROCESS_MANAGER.try_lock() {
+        Some(pm) => pm,
+        None => return false,
+    };
+    for process in pm.processes() {
+        if let Some(process) = process.try_lock() {
+            for thread in &process.threads {
+                if let Some(thread) = thread.try_lock() {
+                    if visit(&thread) {
+                        return true;
+                    }
+                }
+            }
+        }
+    }
+    false
+}
+
+/// TID du thread interrompu (1 avant le démarrage du planificateur)
+fn current_tid() -> u64 {
+    crate::scheduler::SCHEDULER
+        .current_thread()
+        .and_then(|t| t.try_lock().map(|t| t.tid))
+        .unwrap_or(1)
+}
+
+fn send_packet(port: &mut SerialPort, data: &str) {
+    for b in frame(data.as_bytes()) {
+        port.send_raw(b);
+    }
+}
+
+/// Lit le prochain paquet valide (les paquets corrompus sont redemandés)
+fn receive_packet(port: &mut SerialPort, reader: &mut PacketReader) -> Vec<u8> {
+    loop {
+        match reader.push(port.receive()) {
+            Some(ReadEvent::Packet(data)) => {
+                port.send_raw(b'+');
+                return data;
+            }
+            Some(ReadEvent::Corrupted) => port.send_raw(b'-'),
+            // Déjà arrêté : Ctrl-C sans effet
+            Some(ReadEvent::Interrupt) | None => {}
+        }
+    }
+}
+
+#[no_mangle]
+extern "C" fn gdb_trap_handler(frame: &mut TrapFrame, vector: u64) {
+    if !is_enabled() {
+        // Sans débogueur : un int3 est simplement signalé puis ignoré
+        if vector == 3 {
+            crate::serial_println!("[gdbstub] int3 à {:#x} (stub inactif)", frame.rip);
+        }
+        frame.rflags &= !RFLAGS_TF;
+        return;
+    }
+
+    let mut stub = STUB.lock();
+    let mut target = KernelTarget { current_tid: current_tid() };
+
+    if vector == 1 {
+        frame.rflags &= !RFLAGS_TF;
+        // Fin du pas-à-pas au-dessus d'un point d'arrêt : on le réarme
+        if let Some(addr) = stub.step_over.take() {
+            target.write_byte(addr, super::INT3);
+            if stub.resume == Resume::Continue {
+                return;
+            }
+        }
+    } else if stub.has_breakpoint(frame.rip.wrapping_sub(1)) {
+        // `int3` a avancé RIP : on revient sur l'instruction d'origine
+        frame.rip -= 1;
+    }
+
+    let mut port = GDB_SERIAL.lock();
+    let mut reader = PacketReader::new();
+    let mut regs = frame.registers();
+    let stop = stub.stop_reply(&target);
+    send_packet(&mut port, &stop);
+
+    loop {
+        let packet = receive_packet(&mut port, &mut reader);
+        match stub.handle(&packet, &mut regs, &mut target) {
+            Action::Reply(reply) => send_packet(&mut port, &reply),
+            Action::Resume(mode) => {
+                frame.set_registers(&regs);
+                stub.resume = mode;
+                // Sur un point d'arrêt : exécuter l'instruction d'origine d'abord
+                if let Some(orig) = stub.original_byte(frame.rip) {
+                    target.write_byte(frame.rip, orig);
+                    stub.step_over = Some(frame.rip);
+                    frame.rflags |= RFLAGS_TF;
+                }
+                if mode == Resume::Step {
+                    frame.rflags |= RFLAGS_TF;
+                }
+                return;
+            }
+            Action::Detach => {
+                send_packet(&mut port, "OK");
+                frame.set_registers(&regs);
+                stub.remove_all_breakpoints(&mut target);
+                stub.step_over = None;
+                frame.rflags &= !RFLAGS_TF;
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,141 @@
+/// GDT, TSS et zone par CPU de l'entrée SYSCALL
+///
+/// Chaque CPU reçoit sa propre GDT et sa propre TSS. L'ordre des
+/// descripteurs est imposé par SYSCALL/SYSRET (MSR STAR) :
+///
+/// | Sélecteur | Segment                     |
+/// |-----------|-----------------------------|
+/// | 0x08      | code noyau                  |
+/// | 0x10      | données noyau               |
+/// | 0x1b      | données utilisateur (RPL 3) |
+/// | 0x23      | code utilisateur (RPL 3)    |
+/// | 0x28      | TSS (deux entrées)          |
+///
+/// La zone SYSCALL du CPU est liée à sa zone GS (`percpu::install_area`) :
+/// le stub d'entrée y lit sa pile noyau après le `swapgs` d'entrée.
+
+use alloc::boxed::Box;
+use x86_64::instructions::segmentation::{Segment, CS, DS, ES, SS};
+use x86_64::instructions::tables::load_tss;
+use x86_64::structures::gdt::{Descriptor, GlobalDescriptorTable, SegmentSelector};
+use x86_64::structures::tss::TaskStateSegment;
+use x86_64::{PrivilegeLevel, VirtAddr};
+use crate::process::kstack::{KernelStack, KERNEL_STACK_SIZE};
+
+/// Pile IST dédiée aux doubles fautes
+pub const DOUBLE_FAULT_IST_INDEX: u16 = 0;
+
+/// Sélecteurs, identiques sur tous les CPU
+#[derive(Debug, Clone, Copy)]
+pub struct Selectors {
+    pub kernel_code: SegmentSelector,
+    pub kernel_data: SegmentSelector,
+    pub user_data: SegmentSelector,
+    pub user_code: SegmentSelector,
+    pub tss: SegmentSelector,
+}
+
+/// Sélecteurs de la GDT (valeurs fixées par l'ordre des descripteurs)
+pub const SELECTORS: Selectors = Selectors {
+    kernel_code: SegmentSelector::new(1, PrivilegeLevel::Ring0),
+    kernel_data: SegmentSelector::new(2, PrivilegeLevel::Ring0),
+    user_data: SegmentSelector::new(3, PrivilegeLevel::Ring3),
+    user_code: SegmentSelector::new(4, PrivilegeLevel::Ring3),
+    tss: SegmentSelector::new(5, PrivilegeLevel::Ring0),
+};
+
+/// Zone par CPU de l'entrée SYSCALL (offsets utilisés par l'assembleur)
+#[repr(C)]
+pub struct CpuSyscallArea {
+    /// [0] — pile noyau chargée à l'entrée d'un appel système
+    pub kernel_rsp: u64,
+    /// [8] — RSP du lanceur ring 3 en attente (0 si aucun)
+    pub launcher_rsp: u64,
+    /// TSS du CPU (RSP0 suit `kernel_rsp`)
+    tss: *mut TaskStateSegment,
+}
+
+/// Initialise la GDT et la TSS du BSP
+pub fn init() {
+    init_cpu();
+}
+
+/// Initialise la GDT et la TSS du CPU courant (BSP ou AP)
+pub fn init_cpu() {
+    // Pile IST du CPU : jamais libérée. RSP0 est fixé par le lanceur
+    // ring 3 (`set_kernel_stack`) avant tout passage en mode utilisateur.
+    let ist_stack = Box::leak(Box::new(KernelStack::new(KERNEL_STACK_SIZE)));
+
+    let tss = Box::leak(Box::new(TaskStateSegment::new()));
+    tss.interrupt_stack_table[DOUBLE_FAULT_IST_INDEX as usize] = VirtAddr::new(ist_stack.top());
+    let tss_ptr: *mut TaskStateSegment = tss;
+
+    let gdt = Box::leak(Box::new(GlobalDescriptorTable::new()));
+    gdt.add_entry(Descriptor::kernel_code_segment());
+    gdt.add_entry(Descriptor::kernel_data_segment());
+    gdt.add_entry(Descriptor::user_data_segment());
+    gdt.add_entry(Descriptor::user_code_segment());
+    gdt.add_entry(Descriptor::tss_segment(unsafe { &*tss_ptr }));
+    gdt.load();
+
+    unsafe {
+        CS::set_reg(SELECTORS.kernel_code);
+        SS::set_reg(SELECTORS.kernel_data);
+        DS::set_reg(SELECTORS.kernel_data);
+        ES::set_reg(SELECTORS.kernel_data);
+        load_tss(SELECTORS.tss);
+    }
+
+    let area = Box::leak(Box::new(CpuSyscallArea {
+        kernel_rsp: 0,
+        launcher_rsp: 0,
+        tss: tss_ptr,
+    }));
+    crate::percpu::install_area(area as *mut CpuSyscallArea as u64);
+}
+
+/// Zone SYSCALL du CPU courant (None avant `init_cpu`)
+pub fn syscall_area() -> Option<&'static mut CpuSyscallArea> {
+    let ptr = crate::percpu::syscall_area() as *mut CpuSyscallArea;
+    unsafe { ptr.as_mut() }
+}
+
+/// Pile noyau utilisée en entrant dans le noyau depuis le ring 3
+/// (SYSCALL et interruptions via TSS.RSP0)
+pub fn set_kernel_stack(top: u64) {
+    if let Some(area) = syscall_area() {
+        area.kernel_rsp = top;
+        unsafe { (*area.tss).privilege_stack_table[0] = VirtAddr::new(top) };
+    }
+}
+
+/// Pile noyau courante pour les entrées depuis le ring 3
+pub fn kernel_stack() -> Option<u64> {
+    syscall_area().map(|area| area.kernel_rsp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_selectors_match_sysret_layout() {
+        // SYSRET : SS = base + 8, CS = base + 16 (base = données noyau)
+        assert_eq!(SELECTORS.kernel_code.0, 0x08);
+        assert_eq!(SELECTORS.kernel_data.0, 0x10);
+        assert_eq!(SELECTORS.user_data.0, 0x1b);
+        assert_eq!(SELECTORS.user_code.0, 0x23);
+        assert_eq!(SELECTORS.user_data.index(), SELECTORS.kernel_data.index() + 1);
+        assert_eq!(SELECTORS.user_code.index(), SELECTORS.kernel_data.index() + 2);
+    }
+
+    #[test_case]
+    fn test_syscall_area_offsets() {
+        // Offsets codés en dur dans le stub d'entrée
+        let area = CpuSyscallArea { kernel_rsp: 0, user_rsp: 0, launcher_rsp: 0, tss: core::ptr::null_mut() };
+        let base = &area as *const _ as usize;
+        assert_eq!(&area.kernel_rsp as *const _ as usize - base, 0);
+        assert_eq!(&area.user_rsp as *const _ as usize - base, 8);
+        assert_eq!(&area.launcher_rsp as *const _ as usize - base, 16);
+    }
+}
@@ -1,9 +1,37 @@
+use crate::drivers::block::BlockHandle;
 use crate::drivers::disk::{Disk, DiskDriver, DiskError};
+use alloc::string::String;
+use alloc::vec;
 use alloc::vec::Vec;
 use core::mem::size_of;
 use core::slice;
+use core::sync::atomic::{AtomicU64, Ordering};
 
 const GPT_SIGNATURE: u64 = 0x5452415020494645; // "EFI PART" in little endian
+const GPT_REVISION: u32 = 0x0001_0000;
+const GPT_HEADER_SIZE: u32 = 92;
+
+/// Entrées de la table écrite par `PartitionTable::write` (128 × 128 octets)
+pub const GPT_ENTRY_COUNT: usize = 128;
+const GPT_ENTRY_SIZE: usize = 128;
+const GPT_ENTRY_SECTORS: u64 = (GPT_ENTRY_COUNT * GPT_ENTRY_SIZE / 512) as u64;
+
+/// Type de la partition unique du MBR protecteur
+const MBR_PROTECTIVE_TYPE: u8 = 0xEE;
+
+/// Alignement des partitions créées sans bornes (1 Mio)
+const PARTITION_ALIGN: u64 = 2048;
+
+/// GUID de types (champs en ordre mixte, tels que stockés sur disque)
+pub const LINUX_DATA_GUID: [u8; 16] = [
+    0xAF, 0x3D, 0xC6, 0x0F, 0x83, 0x84, 0x72, 0x47, 0x8E, 0x79, 0x3D, 0x69, 0xD8, 0x47, 0x7D, 0xE4,
+];
+pub const BASIC_DATA_GUID: [u8; 16] = [
+    0xA2, 0xA0, 0xD0, 0xEB, 0xE5, 0xB9, 0x33, 0x44, 0x87, 0xC0, 0x68, 0xB6, 0xB7, 0x26, 0x99, 0xC7,
+];
+pub const EFI_SYSTEM_GUID: [u8; 16] = [
+    0x28, 0x73, 0x2A, 0xC1, 0x1F, 0xF8, 0xD2, 0x11, 0xBA, 0x4B, 0x00, 0xA0, 0xC9, 0x3E, 0xC9, 0x3B,
+];
 
 #[repr(C, packed)]
 #[derive(Debug, Clone, Copy)]
@@ -35,6 +63,39 @@ pub struct GptPartitionEntry {
     pub partition_name: [u16; 36],
 }
 
+impl GptPartitionEntry {
+    const EMPTY: Self = Self {
+        type_guid: [0; 16],
+        partition_guid: [0; 16],
+        start_lba: 0,
+        end_lba: 0,
+        attributes: 0,
+        partition_name: [0; 36],
+    };
+
+    pub fn is_used(&self) -> bool {
+        self.type_guid.iter().any(|&b| b != 0)
+    }
+
+    /// Nom de la partition (UTF-16 terminé par un zéro)
+    pub fn name(&self) -> String {
+        let units = self.partition_name;
+        let len = units.iter().position(|&u| u == 0).unwrap_or(units.len());
+        char::decode_utf16(units[..len].iter().copied())
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect()
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self.type_guid {
+            LINUX_DATA_GUID => "Linux",
+            BASIC_DATA_GUID => "Données de base",
+            EFI_SYSTEM_GUID => "Système EFI",
+            _ => "Inconnu",
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Partition {
     pub start_lba: u64,
@@ -100,3 +161,301 @@ pub fn parse_gpt(disk: &mut DiskDriver) -> Result<Vec<Partition>, DiskError> {
     
     Ok(partitions)
 }
+
+/// CRC-32 (IEEE 802.3) des en-têtes et tables GPT
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+/// GUID de version 4 ; faute de source d'aléa, dérivé de l'horloge
+fn new_guid() -> [u8; 16] {
+    static SEED: AtomicU64 = AtomicU64::new(0);
+    let mut state = crate::time::now_ns() ^ SEED.fetch_add(0x9E37_79B9_7F4A_7C15, Ordering::Relaxed);
+    let mut guid = [0u8; 16];
+    for chunk in guid.chunks_mut(8) {
+        // splitmix64
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        chunk.copy_from_slice(&(z ^ (z >> 31)).to_le_bytes());
+    }
+    guid[7] = (guid[7] & 0x0F) | 0x40;
+    guid[8] = (guid[8] & 0x3F) | 0x80;
+    guid
+}
+
+fn as_bytes<T: Copy>(value: &T) -> &[u8] {
+    unsafe { slice::from_raw_parts(value as *const T as *const u8, size_of::<T>()) }
+}
+
+/// Table GPT modifiable en mémoire, écrite d'un bloc par `write`
+pub struct PartitionTable {
+    pub disk_guid: [u8; 16],
+    entries: Vec<GptPartitionEntry>,
+    /// Dernier secteur du disque, qui porte l'en-tête de secours
+    last_lba: u64,
+}
+
+impl PartitionTable {
+    /// Table vide pour un disque de `block_count` secteurs
+    pub fn new(block_count: u64) -> Result<Self, &'static str> {
+        // MBR, deux en-têtes, deux tables et au moins un secteur utile
+        if block_count < 2 * (GPT_ENTRY_SECTORS + 1) + 2 {
+            return Err("disque trop petit pour une table GPT");
+        }
+        Ok(Self {
+            disk_guid: new_guid(),
+            entries: vec![GptPartitionEntry::EMPTY; GPT_ENTRY_COUNT],
+            last_lba: block_count - 1,
+        })
+    }
+
+    /// Lit et vérifie (signature, sommes de contrôle) la table principale
+    pub fn read(dev: &BlockHandle) -> Result<Self, &'static str> {
+        if dev.block_size() != 512 {
+            return Err("secteurs de 512 octets requis");
+        }
+        let mut sector = [0u8; 512];
+        dev.read_blocks(1, &mut sector).map_err(|_| "erreur de lecture")?;
+        let header = unsafe { core::ptr::read_unaligned(sector.as_ptr() as *const GptHeader) };
+        if header.signature != GPT_SIGNATURE {
+            return Err("pas de table GPT");
+        }
+        let count = header.num_partition_entries as usize;
+        if header.size_of_partition_entry as usize != GPT_ENTRY_SIZE || count > GPT_ENTRY_COUNT {
+            return Err("format de table GPT non pris en charge");
+        }
+        let mut check = header;
+        check.crc32 = 0;
+        if crc32(as_bytes(&check)) != header.crc32 {
+            return Err("en-tête GPT corrompu");
+        }
+
+        let sectors = (count * GPT_ENTRY_SIZE).div_ceil(512).max(1);
+        let mut raw = vec![0u8; sectors * 512];
+        dev.read_blocks(header.partition_entry_lba, &mut raw).map_err(|_| "erreur de lecture")?;
+        if crc32(&raw[..count * GPT_ENTRY_SIZE]) != header.partition_entry_crc32 {
+            return Err("table des partitions GPT corrompue");
+        }
+        let mut entries: Vec<GptPartitionEntry> = raw[..count * GPT_ENTRY_SIZE]
+            .chunks_exact(GPT_ENTRY_SIZE)
+            .map(|chunk| unsafe { core::ptr::read_unaligned(chunk.as_ptr() as *const GptPartitionEntry) })
+            .collect();
+        entries.resize(GPT_ENTRY_COUNT, GptPartitionEntry::EMPTY);
+        Ok(Self { disk_guid: header.disk_guid, entries, last_lba: dev.block_count() - 1 })
+    }
+
+    pub fn first_usable(&self) -> u64 {
+        2 + GPT_ENTRY_SECTORS
+    }
+
+    pub fn last_usable(&self) -> u64 {
+        self.last_lba - GPT_ENTRY_SECTORS - 1
+    }
+
+    /// Entrée d'indice `index` si elle est utilisée
+    pub fn entry(&self, index: usize) -> Option<&GptPartitionEntry> {
+        self.entries.get(index).filter(|e| e.is_used())
+    }
+
+    pub fn partitions(&self) -> Vec<Partition> {
+        self.entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| e.is_used())
+            .map(|(index, e)| Partition {
+                start_lba: e.start_lba,
+                end_lba: e.end_lba,
+                size_sectors: e.end_lba - e.start_lba + 1,
+                index,
+            })
+            .collect()
+    }
+
+    /// Plus grand intervalle libre, début aligné sur 1 Mio
+    pub fn largest_free(&self) -> Option<(u64, u64)> {
+        let mut used = self.partitions();
+        used.sort_by_key(|p| p.start_lba);
+        let mut best: Option<(u64, u64)> = None;
+        let mut cursor = self.first_usable();
+        let bounds = used.iter().map(|p| (p.start_lba, p.end_lba + 1));
+        for (start, next) in bounds.chain(core::iter::once((self.last_usable() + 1, 0))) {
+            let aligned = cursor.next_multiple_of(PARTITION_ALIGN);
+            if aligned < start && best.is_none_or(|(s, e)| e - s < start - 1 - aligned) {
+                best = Some((aligned, start - 1));
+            }
+            cursor = cursor.max(next);
+        }
+        best
+    }
+
+    /// Ajoute une partition sur `[start, end]` ; rend son indice
+    pub fn add(&mut self, start: u64, end: u64, type_guid: [u8; 16], name: &str) -> Result<usize, &'static str> {
+        if start < self.first_usable() || end > self.last_usable() || start > end {
+            return Err("partition hors de la zone utilisable");
+        }
+        if self.partitions().iter().any(|p| start <= p.end_lba && p.start_lba <= end) {
+            return Err("chevauche une partition existante");
+        }
+        let index = self.entries.iter().position(|e| !e.is_used()).ok_or("table des partitions pleine")?;
+        let mut partition_name = [0u16; 36];
+        for (slot, unit) in partition_name.iter_mut().zip(name.encode_utf16()) {
+            *slot = unit;
+        }
+        self.entries[index] = GptPartitionEntry {
+            type_guid,
+            partition_guid: new_guid(),
+            start_lba: start,
+            end_lba: end,
+            attributes: 0,
+            partition_name,
+        };
+        Ok(index)
+    }
+
+    pub fn remove(&mut self, index: usize) -> Result<(), &'static str> {
+        self.entry(index).ok_or("partition inexistante")?;
+        self.entries[index] = GptPartitionEntry::EMPTY;
+        Ok(())
+    }
+
+    fn header(&self, current_lba: u64, backup_lba: u64, entry_lba: u64, entries_crc: u32) -> [u8; 512] {
+        let mut header = GptHeader {
+            signature: GPT_SIGNATURE,
+            revision: GPT_REVISION,
+            header_size: GPT_HEADER_SIZE,
+            crc32: 0,
+            reserved: 0,
+            current_lba,
+            backup_lba,
+            first_usable_lba: self.first_usable(),
+            last_usable_lba: self.last_usable(),
+            disk_guid: self.disk_guid,
+            partition_entry_lba: entry_lba,
+            num_partition_entries: GPT_ENTRY_COUNT as u32,
+            size_of_partition_entry: GPT_ENTRY_SIZE as u32,
+            partition_entry_crc32: entries_crc,
+        };
+        header.crc32 = crc32(as_bytes(&header));
+        let mut sector = [0u8; 512];
+        sector[..size_of::<GptHeader>()].copy_from_slice(as_bytes(&header));
+        sector
+    }
+
+    /// MBR protecteur : une partition 0xEE couvrant tout le disque
+    fn protective_mbr(&self) -> [u8; 512] {
+        let mut mbr = [0u8; 512];
+        let entry = &mut mbr[446..462];
+        entry[1..4].copy_from_slice(&[0x00, 0x02, 0x00]); // CHS du secteur 1
+        entry[4] = MBR_PROTECTIVE_TYPE;
+        entry[5..8].copy_from_slice(&[0xFF, 0xFF, 0xFF]);
+        entry[8..12].copy_from_slice(&1u32.to_le_bytes());
+        entry[12..16].copy_from_slice(&(self.last_lba.min(u32::MAX as u64) as u32).to_le_bytes());
+        mbr[510] = 0x55;
+        mbr[511] = 0xAA;
+        mbr
+    }
+
+    /// Écrit le MBR protecteur, les tables et en-têtes principal et de secours
+    pub fn write(&self, dev: &BlockHandle) -> Result<(), &'static str> {
+        if dev.block_size() != 512 || dev.block_count() != self.last_lba + 1 {
+            return Err("la table ne correspond pas au disque");
+        }
+        let entries: Vec<u8> = self.entries.iter().flat_map(|e| as_bytes(e).iter().copied()).collect();
+        let entries_crc = crc32(&entries);
+        let backup_entries = self.last_lba - GPT_ENTRY_SECTORS;
+
+        dev.plug();
+        let result = dev
+            .write_blocks(0, &self.protective_mbr())
+            .and_then(|_| dev.write_blocks(1, &self.header(1, self.last_lba, 2, entries_crc)))
+            .and_then(|_| dev.write_blocks(2, &entries))
+            .and_then(|_| dev.write_blocks(backup_entries, &entries))
+            .and_then(|_| dev.write_blocks(self.last_lba, &self.header(self.last_lba, 1, backup_entries, entries_crc)));
+        let unplugged = dev.unplug();
+        result
+            .and(unplugged)
+            .and_then(|_| dev.flush())
+            .map_err(|_| "erreur d'écriture de la table GPT")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::drivers::block::{check_request, BlockDevice};
+    use alloc::sync::Arc;
+    use spin::Mutex;
+
+    struct MemDisk {
+        data: Vec<u8>,
+    }
+
+    impl BlockDevice for MemDisk {
+        fn block_size(&self) -> usize {
+            512
+        }
+
+        fn block_count(&self) -> u64 {
+            (self.data.len() / 512) as u64
+        }
+
+        fn read_blocks(&mut self, lba: u64, buf: &mut [u8]) -> Result<(), DiskError> {
+            check_request(&*self, lba, buf.len())?;
+            let start = lba as usize * 512;
+            buf.copy_from_slice(&self.data[start..start + buf.len()]);
+            Ok(())
+        }
+
+        fn write_blocks(&mut self, lba: u64, buf: &[u8]) -> Result<(), DiskError> {
+            check_request(&*self, lba, buf.len())?;
+            let start = lba as usize * 512;
+            self.data[start..start + buf.len()].copy_from_slice(buf);
+            Ok(())
+        }
+    }
+
+    #[test_case]
+    fn test_gpt_create_delete_roundtrip() {
+        // Référence : CRC-32 de "123456789"
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+
+        let dev = BlockHandle::new("mem0", Arc::new(Mutex::new(MemDisk { data: vec![0; 8192 * 512] })));
+        assert_eq!(PartitionTable::read(&dev).err(), Some("pas de table GPT"));
+
+        let mut table = PartitionTable::new(dev.block_count()).unwrap();
+        assert_eq!(table.largest_free(), Some((2048, 8192 - 34)));
+        assert_eq!(table.add(2048, 4095, LINUX_DATA_GUID, "racine").unwrap(), 0);
+        assert!(table.add(4000, 5000, LINUX_DATA_GUID, "").is_err());
+        assert!(table.add(10, 100, LINUX_DATA_GUID, "").is_err());
+        let (start, end) = table.largest_free().unwrap();
+        assert_eq!(table.add(start, end, BASIC_DATA_GUID, "données").unwrap(), 1);
+        table.remove(0).unwrap();
+        table.write(&dev).unwrap();
+
+        let mut mbr = [0u8; 512];
+        dev.read_blocks(0, &mut mbr).unwrap();
+        assert_eq!((mbr[450], mbr[510], mbr[511]), (MBR_PROTECTIVE_TYPE, 0x55, 0xAA));
+
+        let table = PartitionTable::read(&dev).unwrap();
+        let parts = table.partitions();
+        assert_eq!(parts.len(), 1);
+        assert_eq!((parts[0].index, parts[0].start_lba, parts[0].end_lba), (1, 4096, 8192 - 34));
+        assert_eq!(table.entry(1).unwrap().name(), "données");
+        assert_eq!(table.entry(1).unwrap().type_name(), "Données de base");
+
+        // En-tête de secours sur le dernier secteur
+        let mut backup = [0u8; 512];
+        dev.read_blocks(8191, &mut backup).unwrap();
+        assert_eq!(&backup[..8], b"EFI PART");
+    }
+}
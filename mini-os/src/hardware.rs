@@ -1,20 +1,11 @@
 use crate::vga_buffer::WRITER;
-use raw_cpuid::CpuId;
 use x86_64::instructions::port::Port;
 
-/// Détecte le CPU et affiche le vendor
+/// Détecte le CPU, active ses fonctionnalités requises et affiche un résumé
 pub fn detect_cpu() {
-    let cpuid = CpuId::new();
-    if let Some(vf) = cpuid.get_vendor_info() {
-        WRITER.lock().write_string("CPU Vendor: ");
-        WRITER.lock().write_string(vf.as_str());
-        WRITER.lock().write_string("\n");
-    }
-    if let Some(finfo) = cpuid.get_feature_info() {
-        WRITER.lock().write_string(&format!("CPU Features: SSE{} SSE2{}\n",
-            if finfo.has_sse() { "+" } else { "-" },
-            if finfo.has_sse2() { "+" } else { "-" }));
-    }
+    mini_os::cpuid::init_cpu();
+    WRITER.lock().write_string(&format!("{}\n", mini_os::cpuid::summary()));
+    WRITER.lock().write_string(&format!("CPU Features: {}\n", mini_os::cpuid::CPU_INFO.flags()));
 }
 
 /// Scanne le bus PCI et affiche les périphériques détectés
@@ -56,9 +47,34 @@ fn pci_config_read_u16(bus: u8, device: u8, function: u8, offset: u8) -> u16 {
     }
 }
 
-// Multiboot2 n'est pas disponible, fonction désactivée
-/// Détecte la RAM disponible (nécessite multiboot2)
-pub fn detect_memory(_multiboot_address: usize) {
-    WRITER.lock().write_string("Memory detection: multiboot2 not available\n");
-    // TODO: Implémenter une détection de mémoire alternative
+/// Affiche la carte mémoire et les modules transmis par le chargeur
+pub fn detect_memory() {
+    let info = match mini_os::multiboot2::boot_info() {
+        Some(info) => info,
+        None => {
+            WRITER.lock().write_string("Memory detection: multiboot2 not available\n");
+            return;
+        }
+    };
+    if !info.bootloader.is_empty() {
+        WRITER.lock().write_string(&format!("Chargeur: {}\n", info.bootloader));
+    }
+    let mut usable = 0;
+    for region in &info.memory_map {
+        if region.kind == mini_os::memory::frame::MemoryRegionKind::Usable {
+            usable += region.end - region.start;
+        }
+        WRITER.lock().write_string(&format!(
+            "  [{:#012x}-{:#012x}] {:?}\n", region.start, region.end, region.kind
+        ));
+    }
+    WRITER.lock().write_string(&format!("RAM utilisable: {} Mio\n", usable / (1024 * 1024)));
+    for module in &info.modules {
+        WRITER.lock().write_string(&format!(
+            "Module {:#x}-{:#x} ({} octets) {}\n", module.start, module.end, module.size(), module.cmdline
+        ));
+    }
+    if !info.cmdline.is_empty() {
+        WRITER.lock().write_string(&format!("Ligne de commande: {}\n", info.cmdline));
+    }
 }
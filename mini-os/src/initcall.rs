@@ -0,0 +1,149 @@
+/// Initcalls : initialisation des sous-systèmes par sections de l'éditeur de liens
+///
+/// Chaque driver s'enregistre lui-même avec `initcall!(niveau, "nom", fonction)` :
+/// l'entrée est placée dans la section `.initcall.<niveau>`, que linker.ld
+/// regroupe entre des symboles de début et de fin, niveau par niveau.
+/// Le démarrage parcourt les niveaux dans l'ordre (early, core, driver,
+/// late) ; l'ordre au sein d'un niveau est celui de l'édition de liens
+/// et ne doit pas compter.
+
+use alloc::format;
+use alloc::string::String;
+use crate::vga_buffer::WRITER;
+
+/// Niveau d'un initcall, dans l'ordre d'exécution
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum InitLevel {
+    /// Services sans matériel, requis par tout le reste
+    Early,
+    /// Sous-systèmes du noyau (terminaux, VFS...)
+    Core,
+    /// Pilotes de périphériques
+    Driver,
+    /// Après les pilotes (montages, services réseau...)
+    Late,
+}
+
+impl InitLevel {
+    pub const ALL: [InitLevel; 4] = [InitLevel::Early, InitLevel::Core, InitLevel::Driver, InitLevel::Late];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            InitLevel::Early => "early",
+            InitLevel::Core => "core",
+            InitLevel::Driver => "driver",
+            InitLevel::Late => "late",
+        }
+    }
+}
+
+/// Échec d'un initcall, message issu de l'erreur du sous-système
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InitError(pub String);
+
+/// Résultat accepté d'une fonction d'initialisation : `()` ou tout
+/// `Result` dont l'erreur est affichable en Debug
+pub trait InitResult {
+    fn into_init(self) -> Result<(), InitError>;
+}
+
+impl InitResult for () {
+    fn into_init(self) -> Result<(), InitError> {
+        Ok(())
+    }
+}
+
+impl<T, E: core::fmt::Debug> InitResult for Result<T, E> {
+    fn into_init(self) -> Result<(), InitError> {
+        self.map(|_| ()).map_err(|e| InitError(format!("{:?}", e)))
+    }
+}
+
+/// Entrée d'une section `.initcall.*`
+#[repr(C)]
+pub struct Initcall {
+    pub name: &'static str,
+    pub init: fn() -> Result<(), InitError>,
+}
+
+/// Enregistre `$init` au niveau `early`, `core`, `driver` ou `late`
+#[macro_export]
+macro_rules! initcall {
+    (early, $name:expr, $init:path) => { $crate::initcall!(@entry ".initcall.early", $name, $init); };
+    (core, $name:expr, $init:path) => { $crate::initcall!(@entry ".initcall.core", $name, $init); };
+    (driver, $name:expr, $init:path) => { $crate::initcall!(@entry ".initcall.driver", $name, $init); };
+    (late, $name:expr, $init:path) => { $crate::initcall!(@entry ".initcall.late", $name, $init); };
+    (@entry $section:literal, $name:expr, $init:path) => {
+        const _: () = {
+            fn entry() -> Result<(), $crate::initcall::InitError> {
+                $crate::initcall::InitResult::into_init($init())
+            }
+            #[used]
+            #[link_section = $section]
+            static INITCALL: $crate::initcall::Initcall = $crate::initcall::Initcall { name: $name, init: entry };
+        };
+    };
+}
+
+// Bornes posées par le script d'édition de liens : de simples marqueurs,
+// les entrées elles-mêmes sont lues au travers de `*const Initcall`
+extern "C" {
+    static __initcall_early_start: u8;
+    static __initcall_core_start: u8;
+    static __initcall_driver_start: u8;
+    static __initcall_late_start: u8;
+    static __initcall_end: u8;
+}
+
+/// Entrées d'un niveau (bornées par le début du niveau suivant)
+fn entries(level: InitLevel) -> &'static [Initcall] {
+    unsafe {
+        let bounds = [
+            &__initcall_early_start as *const u8,
+            &__initcall_core_start as *const u8,
+            &__initcall_driver_start as *const u8,
+            &__initcall_late_start as *const u8,
+            &__initcall_end as *const u8,
+        ]
+        .map(|marker| marker as *const Initcall);
+        let (start, end) = (bounds[level as usize], bounds[level as usize + 1]);
+        core::slice::from_raw_parts(start, end.offset_from(start) as usize)
+    }
+}
+
+/// Exécute les initcalls de `level` ; retourne le nombre d'échecs
+pub fn run(level: InitLevel) -> usize {
+    let mut failures = 0;
+    for initcall in entries(level) {
+        match (initcall.init)() {
+            Ok(()) => WRITER.lock().write_string(&format!("{} initialisé\n", initcall.name)),
+            Err(InitError(e)) => {
+                failures += 1;
+                WRITER.lock().write_string(&format!("{} indisponible: {}\n", initcall.name, e));
+                log::warn!("initcall {} ({}): {}", initcall.name, level.name(), e);
+            }
+        }
+    }
+    failures
+}
+
+/// Noms des initcalls par niveau (diagnostic)
+pub fn list() -> alloc::vec::Vec<(InitLevel, &'static str)> {
+    InitLevel::ALL
+        .iter()
+        .flat_map(|&level| entries(level).iter().map(move |initcall| (level, initcall.name)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_init_result() {
+        assert_eq!(().into_init(), Ok(()));
+        assert_eq!(Ok::<usize, &str>(3).into_init(), Ok(()));
+        assert_eq!(Err::<(), _>("absent").into_init(), Err(InitError(String::from("\"absent\""))));
+        assert!(InitLevel::Early < InitLevel::Late);
+    }
+}
@@ -1,11 +1,24 @@
 use x86_64::structures::idt::{InterruptDescriptorTable, InterruptStackFrame, PageFaultErrorCode};
 use x86_64::registers::control::Cr2;
+use x86_64::VirtAddr;
 use lazy_static::lazy_static;
+use x86_64::instructions::port::Port;
 use crate::keyboard::keyboard_interrupt_handler;
 use crate::vga_buffer::WRITER;
 use alloc::format;
 
 pub mod apic;
+pub mod ioapic;
+pub mod irq;
+pub mod vector;
+
+pub use irq::{
+    free_irq, free_msi, request_irq, request_msi, request_msix, request_pci_interrupt, request_pci_irq, IrqError,
+    IrqHandler,
+};
+
+/// Fréquence d'entrée du PIT (Hz)
+pub const PIT_FREQUENCY: u64 = 1_193_182;
 
 lazy_static! {
     static ref IDT: InterruptDescriptorTable = {
@@ -15,8 +28,27 @@ lazy_static! {
         unsafe {
             idt.general_protection_fault.set_handler_fn(general_protection_fault_handler);
             idt.page_fault.set_handler_fn(page_fault_handler);
-            idt[InterruptIndex::Timer.as_usize()].set_handler_fn(timer_interrupt_handler);
-            idt[InterruptIndex::Keyboard.as_usize()].set_handler_fn(keyboard_interrupt_handler);
+            // Autres fautes : fatales au programme ring 3, pas au noyau
+            idt.divide_error.set_handler_fn(divide_error_handler);
+            idt.invalid_opcode.set_handler_fn(invalid_opcode_handler);
+            idt.segment_not_present.set_handler_fn(segment_not_present_handler);
+            idt.stack_segment_fault.set_handler_fn(stack_segment_fault_handler);
+            idt.alignment_check.set_handler_fn(alignment_check_handler);
+            // NMI : trace d'un CPU bloqué, demandée par le watchdog
+            idt.non_maskable_interrupt.set_handler_fn(crate::watchdog::nmi_handler);
+            // #NM : commutation paresseuse de l'état FPU
+            idt.device_not_available.set_handler_fn(crate::process::fpu::device_not_available_handler);
+            idt.double_fault
+                .set_handler_fn(double_fault_handler)
+                .set_stack_index(crate::gdt::DOUBLE_FAULT_IST_INDEX);
+            // IRQ matérielles : vecteur IRQ_BASE + n, gestionnaires via request_irq
+            for (n, &stub) in irq::STUBS.iter().enumerate() {
+                idt[irq::IRQ_BASE as usize + n].set_handler_fn(stub);
+            }
+            idt[irq::SPURIOUS_VECTOR as usize].set_handler_fn(irq::spurious_interrupt_handler);
+            // #BP / #DB : stubs assembleur du débogueur (registres complets)
+            idt.breakpoint.set_handler_addr(crate::gdbstub::trap::breakpoint_entry());
+            idt.debug.set_handler_addr(crate::gdbstub::trap::debug_entry());
         }
         
         idt
@@ -27,36 +59,146 @@ pub fn init_idt() {
     IDT.load();
 }
 
-extern "x86-interrupt" fn timer_interrupt_handler(_stack_frame: InterruptStackFrame) {
+/// Routage des IRQ, horloge (IRQ 0) et clavier (IRQ 1)
+///
+/// À appeler après `init_idt`, interruptions encore désactivées.
+pub fn init_irqs() {
+    irq::init();
+    init_pit(crate::scheduler::TIMER_HZ);
+    if let Err(e) = request_irq(InterruptIndex::Timer.irq(), timer_interrupt_handler) {
+        crate::serial_println!("IRQ horloge: {:?}", e);
+    }
+    if let Err(e) = request_irq(InterruptIndex::Keyboard.irq(), keyboard_interrupt_handler) {
+        crate::serial_println!("IRQ clavier: {:?}", e);
+    }
+}
+
+/// Canal 0 du PIT en générateur de fréquence (mode 2)
+fn init_pit(hz: u64) {
+    let divisor = (PIT_FREQUENCY / hz).clamp(1, 0xFFFF) as u16;
+    unsafe {
+        Port::<u8>::new(0x43).write(0x34);
+        let mut channel0: Port<u8> = Port::new(0x40);
+        channel0.write(divisor as u8);
+        channel0.write((divisor >> 8) as u8);
+    }
+}
+
+fn timer_interrupt_handler() {
+    crate::test_runner::timer_tick();
+    crate::watchdog::timer_tick();
     crate::scheduler::SCHEDULER.tick();
-    crate::interrupts::apic::signal_eoi();
+}
+
+extern "x86-interrupt" fn double_fault_handler(
+    stack_frame: InterruptStackFrame,
+    _error_code: u64,
+) -> ! {
+    let _gs = crate::percpu::KernelGs::enter(&stack_frame);
+    // RSP dans une page de garde : la faute de page n'a pas pu empiler
+    // son cadre, CR2 désigne encore la garde
+    if let Some(overflow) = crate::process::kstack::guard_hit(Cr2::read().as_u64()) {
+        panic!("{}\n{:#?}", overflow, stack_frame);
+    }
+    panic!("Double fault\n{:#?}", stack_frame);
 }
 
 extern "x86-interrupt" fn general_protection_fault_handler(
-    _stack_frame: InterruptStackFrame,
+    stack_frame: InterruptStackFrame,
     _error_code: u64,
 ) {
+    let _gs = crate::percpu::KernelGs::enter(&stack_frame);
+    // Faute d'un programme ring 3 : il est abandonné, pas le noyau
+    if stack_frame.code_segment & 3 == 3 {
+        crate::ring3::return_to_launcher(-11);
+    }
     WRITER.lock().write_string("General Protection Fault!\n");
     panic!("GPF");
 }
 
+/// Faute du CPU : le programme ring 3 fautif est abandonné (-11, comme
+/// SIGSEGV), une faute du noyau est fatale
+fn fatal_fault(stack_frame: &InterruptStackFrame, name: &str) {
+    if stack_frame.code_segment & 3 == 3 {
+        crate::ring3::return_to_launcher(-11);
+    }
+    panic!("{}\n{:#?}", name, stack_frame);
+}
+
+extern "x86-interrupt" fn divide_error_handler(stack_frame: InterruptStackFrame) {
+    let _gs = crate::percpu::KernelGs::enter(&stack_frame);
+    fatal_fault(&stack_frame, "Divide error (#DE)");
+}
+
+extern "x86-interrupt" fn invalid_opcode_handler(stack_frame: InterruptStackFrame) {
+    let _gs = crate::percpu::KernelGs::enter(&stack_frame);
+    fatal_fault(&stack_frame, "Invalid opcode (#UD)");
+}
+
+extern "x86-interrupt" fn segment_not_present_handler(stack_frame: InterruptStackFrame, error_code: u64) {
+    let _gs = crate::percpu::KernelGs::enter(&stack_frame);
+    fatal_fault(&stack_frame, &format!("Segment not present (#NP, code {:#x})", error_code));
+}
+
+extern "x86-interrupt" fn stack_segment_fault_handler(stack_frame: InterruptStackFrame, error_code: u64) {
+    let _gs = crate::percpu::KernelGs::enter(&stack_frame);
+    fatal_fault(&stack_frame, &format!("Stack segment fault (#SS, code {:#x})", error_code));
+}
+
+extern "x86-interrupt" fn alignment_check_handler(stack_frame: InterruptStackFrame, error_code: u64) {
+    let _gs = crate::percpu::KernelGs::enter(&stack_frame);
+    fatal_fault(&stack_frame, &format!("Alignment check (#AC, code {:#x})", error_code));
+}
+
 extern "x86-interrupt" fn page_fault_handler(
-    _stack_frame: InterruptStackFrame,
-    _error_code: PageFaultErrorCode,
+    mut stack_frame: InterruptStackFrame,
+    error_code: PageFaultErrorCode,
 ) {
-    WRITER.lock().write_string("Page fault!\n");
+    let _gs = crate::percpu::KernelGs::enter(&stack_frame);
     let cr2 = Cr2::read();
+    crate::trace!(PageFault, cr2.as_u64(), error_code.bits());
+    
+    // Page absente d'une région paresseuse ou mmap : chargée à la demande
+    if !error_code.contains(PageFaultErrorCode::PROTECTION_VIOLATION) {
+        let write = error_code.contains(PageFaultErrorCode::CAUSED_BY_WRITE);
+        if crate::memory::vm::handle_page_fault(cr2, write) {
+            return;
+        }
+    }
+    
+    if error_code.contains(PageFaultErrorCode::USER_MODE) {
+        crate::ring3::return_to_launcher(-11);
+    }
+    
+    // Copie depuis / vers l'espace utilisateur : reprise à son point de la
+    // table d'exceptions, l'appel rendra EFAULT
+    if let Some(fixup) = crate::memory::uaccess::fixup(stack_frame.instruction_pointer.as_u64()) {
+        unsafe {
+            stack_frame.as_mut().update(|frame| frame.instruction_pointer = VirtAddr::new(fixup));
+        }
+        return;
+    }
+    
+    // Grand cadre de pile qui a sauté par-dessus le bas de la pile
+    if let Some(overflow) = crate::process::kstack::guard_hit(cr2.as_u64()) {
+        panic!("{}", overflow);
+    }
+    
+    WRITER.lock().write_string("Page fault!\n");
     WRITER.lock().write_string(&format!("Accessed Address: {:?}\n", cr2));
     
     // TODO: Implémenter la gestion CoW
     panic!("Page fault non géré");
 }
 
+/// Vecteurs des IRQ ISA fixes ; les autres lignes passent par `request_irq`
 #[derive(Debug, Clone, Copy)]
 #[repr(u8)]
 pub enum InterruptIndex {
-    Timer = 32,
-    Keyboard = 33,
+    Timer = irq::IRQ_BASE,
+    Keyboard = irq::IRQ_BASE + 1,
+    /// Souris PS/2 (IRQ 12)
+    Mouse = irq::IRQ_BASE + 12,
 }
 
 impl InterruptIndex {
@@ -67,4 +209,33 @@ impl InterruptIndex {
     fn as_usize(self) -> usize {
         usize::from(self.as_u8())
     }
+
+    /// Ligne d'IRQ correspondante
+    pub fn irq(self) -> u8 {
+        self.as_u8() - irq::IRQ_BASE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use crate::process::exec::ExecArgs;
+    use crate::process::kstack::{KernelStack, KERNEL_STACK_SIZE};
+
+    /// Exécute une image à plat en ring 3 ; retourne le code vu par le lanceur
+    fn run_user(image: &[u8]) -> Result<i32, &'static str> {
+        let stack = KernelStack::new(KERNEL_STACK_SIZE);
+        let args = ExecArgs::new(vec!["faute".into()], Vec::new());
+        crate::ring3::run_user_program(image, &args, stack.top())
+    }
+
+    #[test_case]
+    fn test_user_faults_kill_program() {
+        // ud2
+        assert_eq!(run_user(&[0x0F, 0x0B]), Ok(-11));
+        // xor ecx, ecx ; div ecx
+        assert_eq!(run_user(&[0x31, 0xC9, 0xF7, 0xF1]), Ok(-11));
+    }
 }
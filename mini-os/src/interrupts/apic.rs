@@ -55,6 +55,15 @@ impl LocalApic {
         }
     }
     
+    // Envoi d'une NMI (traces des CPU bloqués, cf. watchdog)
+    pub fn send_nmi(&self, apic_id: u32) {
+        unsafe {
+            self.write(0x310, apic_id << 24);
+            // ICR Low: NMI (4 << 8) | Assert (1 << 14)
+            self.write(0x300, 0x00004400);
+        }
+    }
+    
     // Envoi d'une interruption INIT
     pub fn send_init(&self, apic_id: u32) {
          unsafe {
@@ -0,0 +1,88 @@
+/// Contrôleur d'interruptions d'E/S (IOAPIC)
+///
+/// Chaque entrée de la table de redirection associe une GSI (Global
+/// System Interrupt) à un vecteur et à un LAPIC destinataire. Les
+/// registres sont accessibles indirectement : on écrit l'index dans
+/// IOREGSEL puis on lit ou écrit la valeur dans IOWIN.
+
+use core::ptr::{read_volatile, write_volatile};
+
+const IOREGSEL: u64 = 0x00;
+const IOWIN: u64 = 0x10;
+
+const REG_VERSION: u32 = 0x01;
+/// Première entrée de redirection (deux registres de 32 bits chacune)
+const REG_REDIRECTION: u32 = 0x10;
+
+/// Polarité active à l'état bas
+const ACTIVE_LOW: u32 = 1 << 13;
+/// Déclenchement sur niveau
+const LEVEL_TRIGGERED: u32 = 1 << 15;
+/// Entrée masquée
+const MASKED: u32 = 1 << 16;
+
+pub struct IoApic {
+    base_address: u64,
+    /// Première GSI servie
+    gsi_base: u32,
+}
+
+impl IoApic {
+    pub const fn new(base_address: u64, gsi_base: u32) -> Self {
+        Self { base_address, gsi_base }
+    }
+
+    unsafe fn read(&self, reg: u32) -> u32 {
+        write_volatile((self.base_address + IOREGSEL) as *mut u32, reg);
+        read_volatile((self.base_address + IOWIN) as *const u32)
+    }
+
+    unsafe fn write(&self, reg: u32, value: u32) {
+        write_volatile((self.base_address + IOREGSEL) as *mut u32, reg);
+        write_volatile((self.base_address + IOWIN) as *mut u32, value);
+    }
+
+    /// Nombre d'entrées de redirection
+    pub fn entries(&self) -> u32 {
+        unsafe { ((self.read(REG_VERSION) >> 16) & 0xFF) + 1 }
+    }
+
+    /// Vrai si la GSI est servie par ce contrôleur
+    pub fn handles(&self, gsi: u32) -> bool {
+        gsi >= self.gsi_base && gsi < self.gsi_base + self.entries()
+    }
+
+    /// Programme l'entrée d'une GSI (mode fixe, destination physique)
+    pub fn route(&self, gsi: u32, vector: u8, apic_id: u8, active_low: bool, level: bool) {
+        let index = REG_REDIRECTION + 2 * (gsi - self.gsi_base);
+        let mut low = vector as u32;
+        if active_low {
+            low |= ACTIVE_LOW;
+        }
+        if level {
+            low |= LEVEL_TRIGGERED;
+        }
+        unsafe {
+            // Masquée le temps de changer la destination
+            self.write(index, MASKED);
+            self.write(index + 1, (apic_id as u32) << 24);
+            self.write(index, low);
+        }
+    }
+
+    /// Masque ou démasque une GSI sans toucher au reste de l'entrée
+    pub fn set_masked(&self, gsi: u32, masked: bool) {
+        let index = REG_REDIRECTION + 2 * (gsi - self.gsi_base);
+        unsafe {
+            let low = self.read(index);
+            self.write(index, if masked { low | MASKED } else { low & !MASKED });
+        }
+    }
+
+    /// Masque toutes les entrées
+    pub fn mask_all(&self) {
+        for n in 0..self.entries() {
+            self.set_masked(self.gsi_base + n, true);
+        }
+    }
+}
@@ -0,0 +1,446 @@
+/// Routage des IRQ matérielles et enregistrement des gestionnaires
+///
+/// Les PIC 8259 sont remappés puis masqués ; les IRQ passent par les
+/// IOAPIC décrits par la MADT, en tenant compte des redirections ISA
+/// (IRQ 0 -> GSI 2 sous QEMU). L'IRQ `n` arrive toujours sur le vecteur
+/// `IRQ_BASE + n` : les drivers appellent `request_irq` au lieu de
+/// réserver un vecteur dans l'IDT. Sans IOAPIC, on retombe sur les PIC.
+///
+/// Au-delà des IRQ_COUNT lignes, les numéros d'IRQ désignent des
+/// vecteurs MSI/MSI-X attribués par `vector::allocate` (même relation
+/// vecteur = IRQ_BASE + irq) ; `request_msi` programme le périphérique.
+
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU32, Ordering};
+use spin::Mutex;
+use x86_64::instructions::interrupts::without_interrupts;
+use x86_64::instructions::port::Port;
+use x86_64::structures::idt::{HandlerFunc, InterruptStackFrame};
+use crate::acpi::{self, madt::{self, InterruptOverride, Madt}};
+use crate::drivers::pci::{Msi, MsiX, PciFunction};
+use super::apic::{self, LocalApic};
+use super::ioapic::IoApic;
+use super::vector;
+
+/// Nombre d'IRQ routables (entrées d'un IOAPIC standard)
+pub const IRQ_COUNT: usize = 24;
+/// Vecteur de l'IRQ 0
+pub const IRQ_BASE: u8 = 32;
+/// IRQ lignes + IRQ MSI (fenêtre dynamique de `vector`)
+pub const NR_IRQS: usize = IRQ_COUNT + vector::DYNAMIC_COUNT;
+/// Vecteur des interruptions parasites du LAPIC
+pub const SPURIOUS_VECTOR: u8 = 0xFF;
+/// Gestionnaires par ligne (lignes PCI partagées)
+const MAX_SHARED: usize = 4;
+
+const PIC1_COMMAND: u16 = 0x20;
+const PIC1_DATA: u16 = 0x21;
+const PIC2_COMMAND: u16 = 0xA0;
+const PIC2_DATA: u16 = 0xA1;
+const PIC_EOI: u8 = 0x20;
+
+/// Fenêtre d'adresse des messages MSI vers les LAPIC
+const MSI_ADDRESS_BASE: u64 = 0xFEE0_0000;
+
+/// Gestionnaire appelé en contexte d'interruption, avant l'EOI
+pub type IrqHandler = fn();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IrqError {
+    /// Numéro hors de 0..IRQ_COUNT (0..NR_IRQS pour les IRQ MSI)
+    InvalidIrq,
+    /// Gestionnaire déjà enregistré sur cette ligne
+    AlreadyRegistered,
+    /// Ligne partagée par trop de gestionnaires
+    Busy,
+    /// Gestionnaire absent de cette ligne
+    NotRegistered,
+    /// Ni MSI ni MSI-X (ou pas de LAPIC actif)
+    NotSupported,
+    /// Plus de vecteur dynamique libre
+    NoVector,
+}
+
+enum Routing {
+    /// PIC 8259 (pas de MADT ou pas d'IOAPIC)
+    Pic,
+    IoApic {
+        io_apics: Vec<IoApic>,
+        overrides: Vec<InterruptOverride>,
+        /// LAPIC destinataire (BSP)
+        apic_id: u8,
+    },
+}
+
+static HANDLERS: Mutex<[[Option<IrqHandler>; MAX_SHARED]; NR_IRQS]> =
+    Mutex::new([[None; MAX_SHARED]; NR_IRQS]);
+static ROUTING: Mutex<Routing> = Mutex::new(Routing::Pic);
+
+/// APIC ID du BSP, destinataire des messages MSI (NO_LAPIC tant que le LAPIC est inactif)
+const NO_LAPIC: u32 = u32::MAX;
+static MSI_DESTINATION: AtomicU32 = AtomicU32::new(NO_LAPIC);
+
+/// Remappe les PIC sur IRQ_BASE et masque toutes leurs lignes
+fn init_pic() {
+    unsafe {
+        let mut pic1_command: Port<u8> = Port::new(PIC1_COMMAND);
+        let mut pic1_data: Port<u8> = Port::new(PIC1_DATA);
+        let mut pic2_command: Port<u8> = Port::new(PIC2_COMMAND);
+        let mut pic2_data: Port<u8> = Port::new(PIC2_DATA);
+        // ICW1 : initialisation, ICW4 attendu
+        pic1_command.write(0x11);
+        pic2_command.write(0x11);
+        // ICW2 : vecteurs de base
+        pic1_data.write(IRQ_BASE);
+        pic2_data.write(IRQ_BASE + 8);
+        // ICW3 : esclave sur l'IRQ 2
+        pic1_data.write(0x04);
+        pic2_data.write(0x02);
+        // ICW4 : mode 8086
+        pic1_data.write(0x01);
+        pic2_data.write(0x01);
+        // OCW1 : tout masqué
+        pic1_data.write(0xFF);
+        pic2_data.write(0xFF);
+    }
+}
+
+/// Démasque une ligne des PIC (et la cascade pour l'esclave)
+fn pic_unmask(irq: u8) {
+    unsafe {
+        if irq < 8 {
+            let mut data: Port<u8> = Port::new(PIC1_DATA);
+            let mask = data.read();
+            data.write(mask & !(1 << irq));
+        } else {
+            let mut data: Port<u8> = Port::new(PIC2_DATA);
+            let mask = data.read();
+            data.write(mask & !(1 << (irq - 8)));
+            let mut master: Port<u8> = Port::new(PIC1_DATA);
+            let mask = master.read();
+            master.write(mask & !(1 << 2));
+        }
+    }
+}
+
+fn pic_mask(irq: u8) {
+    let (port, bit) = if irq < 8 { (PIC1_DATA, irq) } else { (PIC2_DATA, irq - 8) };
+    unsafe {
+        let mut data: Port<u8> = Port::new(port);
+        let mask = data.read();
+        data.write(mask | (1 << bit));
+    }
+}
+
+fn pic_eoi(irq: u8) {
+    unsafe {
+        if irq >= 8 {
+            Port::<u8>::new(PIC2_COMMAND).write(PIC_EOI);
+        }
+        Port::<u8>::new(PIC1_COMMAND).write(PIC_EOI);
+    }
+}
+
+/// Masque les PIC, active le LAPIC du BSP et prend les IOAPIC de la MADT
+///
+/// Toutes les entrées restent masquées jusqu'au premier `request_irq`.
+pub fn init() {
+    init_pic();
+
+    let madt_ptr = match acpi::find_rsdp().and_then(|rsdp| acpi::find_table(&rsdp, b"APIC")) {
+        Some(address) => address as *const Madt,
+        None => {
+            crate::serial_println!("IRQ: pas de MADT, routage par les PIC");
+            return;
+        }
+    };
+    let madt = unsafe { madt_ptr.read_unaligned() };
+    let lapic = LocalApic::new(madt.local_apic_address as u64);
+    lapic.enable();
+    MSI_DESTINATION.store(lapic.id(), Ordering::SeqCst);
+
+    let io_apics: Vec<IoApic> = madt::parse_io_apics(madt_ptr)
+        .iter()
+        .map(|info| IoApic::new(info.address as u64, info.gsi_base))
+        .collect();
+    if io_apics.is_empty() {
+        crate::serial_println!("IRQ: pas d'IOAPIC, routage par les PIC");
+        return;
+    }
+    for io_apic in &io_apics {
+        io_apic.mask_all();
+    }
+    let overrides = madt::parse_overrides(madt_ptr);
+    crate::serial_println!("IRQ: {} IOAPIC, {} redirections ISA", io_apics.len(), overrides.len());
+
+    *ROUTING.lock() = Routing::IoApic { io_apics, overrides, apic_id: lapic.id() as u8 };
+}
+
+/// GSI et mode (polarité basse, niveau) d'une IRQ
+fn resolve(irq: u8, overrides: &[InterruptOverride], level: bool) -> (u32, bool, bool) {
+    match overrides.iter().find(|o| o.bus == 0 && o.source == irq) {
+        Some(o) => (o.gsi, o.active_low(), o.level_triggered()),
+        None => (irq as u32, false, level),
+    }
+}
+
+/// Programme (ou masque) la ligne de l'IRQ ; les IRQ MSI n'ont pas de ligne
+fn route(irq: u8, enable: bool, level: bool) {
+    if irq as usize >= IRQ_COUNT {
+        return;
+    }
+    match &*ROUTING.lock() {
+        Routing::Pic => {
+            if irq < 16 {
+                if enable { pic_unmask(irq) } else { pic_mask(irq) }
+            }
+        }
+        Routing::IoApic { io_apics, overrides, apic_id } => {
+            let (gsi, active_low, level) = resolve(irq, overrides, level);
+            if let Some(io_apic) = io_apics.iter().find(|io| io.handles(gsi)) {
+                if enable {
+                    io_apic.route(gsi, IRQ_BASE + irq, *apic_id, active_low, level);
+                } else {
+                    io_apic.set_masked(gsi, true);
+                }
+            }
+        }
+    }
+}
+
+fn register(irq: u8, handler: IrqHandler, level: bool) -> Result<(), IrqError> {
+    if irq as usize >= NR_IRQS {
+        return Err(IrqError::InvalidIrq);
+    }
+    let first = without_interrupts(|| {
+        let mut handlers = HANDLERS.lock();
+        let line = &mut handlers[irq as usize];
+        if line.iter().flatten().any(|&h| h as usize == handler as usize) {
+            return Err(IrqError::AlreadyRegistered);
+        }
+        let first = line.iter().all(Option::is_none);
+        let slot = line.iter_mut().find(|h| h.is_none()).ok_or(IrqError::Busy)?;
+        *slot = Some(handler);
+        Ok(first)
+    })?;
+    if first {
+        without_interrupts(|| route(irq, true, level));
+    }
+    Ok(())
+}
+
+/// Enregistre un gestionnaire pour une IRQ ISA (front, polarité haute
+/// sauf redirection de la MADT) et démasque la ligne
+pub fn request_irq(irq: u8, handler: IrqHandler) -> Result<(), IrqError> {
+    if irq as usize >= IRQ_COUNT {
+        return Err(IrqError::InvalidIrq);
+    }
+    register(irq, handler, false)
+}
+
+/// Enregistre un gestionnaire pour la ligne INTx d'une fonction PCI
+/// (`interrupt_line`) : déclenchement sur niveau, ligne partageable
+pub fn request_pci_irq(irq: u8, handler: IrqHandler) -> Result<(), IrqError> {
+    if irq as usize >= IRQ_COUNT {
+        return Err(IrqError::InvalidIrq);
+    }
+    register(irq, handler, true)
+}
+
+/// Retire un gestionnaire ; la ligne est masquée s'il était le dernier
+pub fn free_irq(irq: u8, handler: IrqHandler) -> Result<(), IrqError> {
+    if irq as usize >= NR_IRQS {
+        return Err(IrqError::InvalidIrq);
+    }
+    let last = without_interrupts(|| {
+        let mut handlers = HANDLERS.lock();
+        let line = &mut handlers[irq as usize];
+        let slot = line
+            .iter_mut()
+            .find(|h| h.map_or(false, |h| h as usize == handler as usize))
+            .ok_or(IrqError::NotRegistered)?;
+        *slot = None;
+        Ok(line.iter().all(Option::is_none))
+    })?;
+    if last {
+        without_interrupts(|| route(irq, false, false));
+    }
+    Ok(())
+}
+
+/// Message MSI (adresse, donnée) livrant `vector` au BSP, front montant
+fn msi_message(vector: u8) -> Result<(u64, u32), IrqError> {
+    let destination = MSI_DESTINATION.load(Ordering::SeqCst);
+    if destination == NO_LAPIC {
+        return Err(IrqError::NotSupported);
+    }
+    Ok((MSI_ADDRESS_BASE | ((destination as u64 & 0xFF) << 12), vector as u32))
+}
+
+/// Alloue un vecteur et y attache le gestionnaire ; retourne (irq, vecteur)
+fn allocate_msi_irq(handler: IrqHandler) -> Result<(u8, u8), IrqError> {
+    let vector = vector::allocate(1).ok_or(IrqError::NoVector)?;
+    let irq = vector - IRQ_BASE;
+    if let Err(e) = register(irq, handler, false) {
+        vector::free(vector, 1);
+        return Err(e);
+    }
+    Ok((irq, vector))
+}
+
+/// Passe la fonction en MSI (un message) ; retourne l'IRQ attribuée
+pub fn request_msi(function: PciFunction, handler: IrqHandler) -> Result<u8, IrqError> {
+    let msi = Msi::find(function).ok_or(IrqError::NotSupported)?;
+    let (irq, vector) = allocate_msi_irq(handler)?;
+    let (address, data) = match msi_message(vector) {
+        Ok(message) => message,
+        Err(e) => {
+            release_msi_irq(irq, handler);
+            return Err(e);
+        }
+    };
+    msi.enable(address, data as u16);
+    function.set_intx(false);
+    Ok(irq)
+}
+
+/// Programme l'entrée `entry` de la table MSI-X ; retourne l'IRQ attribuée
+pub fn request_msix(function: PciFunction, entry: u16, handler: IrqHandler) -> Result<u8, IrqError> {
+    let msix = MsiX::find(function).ok_or(IrqError::NotSupported)?;
+    if entry >= msix.table_size() {
+        return Err(IrqError::InvalidIrq);
+    }
+    let (irq, vector) = allocate_msi_irq(handler)?;
+    let (address, data) = match msi_message(vector) {
+        Ok(message) => message,
+        Err(e) => {
+            release_msi_irq(irq, handler);
+            return Err(e);
+        }
+    };
+    msix.set_entry(entry, address, data);
+    msix.enable();
+    function.set_intx(false);
+    Ok(irq)
+}
+
+fn release_msi_irq(irq: u8, handler: IrqHandler) {
+    let _ = free_irq(irq, handler);
+    vector::free(IRQ_BASE + irq, 1);
+}
+
+/// Libère une IRQ obtenue par `request_msi` ou `request_msix`
+pub fn free_msi(function: PciFunction, irq: u8, handler: IrqHandler) -> Result<(), IrqError> {
+    if (irq as usize) < IRQ_COUNT || irq as usize >= NR_IRQS {
+        return Err(IrqError::InvalidIrq);
+    }
+    let vector = IRQ_BASE + irq;
+    if let Some(msix) = MsiX::find(function) {
+        for entry in 0..msix.table_size() {
+            if msix.entry_data(entry) == Some(vector as u32) {
+                msix.mask(entry, true);
+            }
+        }
+    }
+    if let Some(msi) = Msi::find(function) {
+        msi.disable();
+    }
+    free_irq(irq, handler)?;
+    vector::free(vector, 1);
+    Ok(())
+}
+
+/// Interruption d'une fonction PCI : MSI-X (entrée 0), puis MSI, puis
+/// la ligne INTx ; retourne l'IRQ attribuée
+pub fn request_pci_interrupt(function: PciFunction, handler: IrqHandler) -> Result<u8, IrqError> {
+    request_msix(function, 0, handler)
+        .or_else(|_| request_msi(function, handler))
+        .or_else(|_| {
+            let line = function.interrupt_line();
+            request_pci_irq(line, handler).map(|_| line)
+        })
+}
+
+/// Vrai si le routage passe par les IOAPIC
+pub fn using_io_apic() -> bool {
+    matches!(*ROUTING.lock(), Routing::IoApic { .. })
+}
+
+/// Appelle les gestionnaires de la ligne puis acquitte l'interruption
+fn dispatch(irq: u8) {
+    crate::percpu::irq_enter();
+    crate::perf::record_irq(IRQ_BASE + irq);
+    crate::trace!(IrqEntry, IRQ_BASE + irq);
+    crate::random::add_interrupt_randomness(IRQ_BASE + irq);
+    // Copie : un gestionnaire peut lui-même appeler request_irq
+    let handlers = HANDLERS.lock()[irq as usize];
+    for handler in handlers.iter().flatten() {
+        handler();
+    }
+    crate::trace!(IrqExit, IRQ_BASE + irq);
+    crate::percpu::irq_exit();
+    let pic = irq < 16 && ROUTING.try_lock().map_or(false, |routing| matches!(*routing, Routing::Pic));
+    if pic {
+        pic_eoi(irq);
+    } else {
+        apic::signal_eoi();
+    }
+    // Noyau de test : le tick fait tourner les fils de `run_threads`
+    if irq == crate::interrupts::InterruptIndex::Timer.irq() {
+        crate::test_runner::preempt();
+    }
+}
+
+macro_rules! irq_stubs {
+    ($($irq:literal => $name:ident),* $(,)?) => {
+        $(
+            extern "x86-interrupt" fn $name(stack_frame: InterruptStackFrame) {
+                let _gs = crate::percpu::KernelGs::enter(&stack_frame);
+                dispatch($irq);
+            }
+        )*
+
+        /// Points d'entrée des vecteurs IRQ_BASE..IRQ_BASE + NR_IRQS
+        pub static STUBS: [HandlerFunc; NR_IRQS] = [$($name),*];
+    };
+}
+
+irq_stubs! {
+    0 => irq0, 1 => irq1, 2 => irq2, 3 => irq3, 4 => irq4, 5 => irq5,
+    6 => irq6, 7 => irq7, 8 => irq8, 9 => irq9, 10 => irq10, 11 => irq11,
+    12 => irq12, 13 => irq13, 14 => irq14, 15 => irq15, 16 => irq16, 17 => irq17,
+    18 => irq18, 19 => irq19, 20 => irq20, 21 => irq21, 22 => irq22, 23 => irq23,
+    // Fenêtre MSI
+    24 => irq24, 25 => irq25, 26 => irq26, 27 => irq27, 28 => irq28, 29 => irq29,
+    30 => irq30, 31 => irq31, 32 => irq32, 33 => irq33, 34 => irq34, 35 => irq35,
+    36 => irq36, 37 => irq37, 38 => irq38, 39 => irq39, 40 => irq40, 41 => irq41,
+    42 => irq42, 43 => irq43, 44 => irq44, 45 => irq45, 46 => irq46, 47 => irq47,
+    48 => irq48, 49 => irq49, 50 => irq50, 51 => irq51, 52 => irq52, 53 => irq53,
+    54 => irq54, 55 => irq55,
+}
+
+/// Interruption parasite du LAPIC : pas d'EOI
+pub extern "x86-interrupt" fn spurious_interrupt_handler(_stack_frame: InterruptStackFrame) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dummy() {}
+
+    #[test_case]
+    fn test_request_and_free_irq() {
+        assert_eq!(request_irq(IRQ_COUNT as u8, dummy), Err(IrqError::InvalidIrq));
+        assert_eq!(request_irq(23, dummy), Ok(()));
+        assert_eq!(request_irq(23, dummy), Err(IrqError::AlreadyRegistered));
+        assert_eq!(free_irq(23, dummy), Ok(()));
+        assert_eq!(free_irq(23, dummy), Err(IrqError::NotRegistered));
+    }
+
+    #[test_case]
+    fn test_override_resolution() {
+        let overrides = [InterruptOverride { bus: 0, source: 0, gsi: 2, flags: 0 }];
+        assert_eq!(resolve(0, &overrides, false), (2, false, false));
+        assert_eq!(resolve(11, &overrides, true), (11, false, true));
+    }
+}
@@ -0,0 +1,73 @@
+/// Allocateur central des vecteurs d'interruption
+///
+/// Répartition de l'IDT : 0..32 exceptions, IRQ_BASE..IRQ_BASE + IRQ_COUNT
+/// lignes IOAPIC/PIC (fixes), puis une fenêtre de vecteurs attribués à la
+/// demande (MSI/MSI-X), et SPURIOUS_VECTOR. Personne ne code plus un
+/// numéro de vecteur en dur : tout vecteur dynamique passe par `allocate`.
+
+use spin::Mutex;
+use x86_64::instructions::interrupts::without_interrupts;
+use super::irq::{IRQ_BASE, IRQ_COUNT};
+
+/// Premier vecteur dynamique
+pub const DYNAMIC_BASE: u8 = IRQ_BASE + IRQ_COUNT as u8;
+/// Taille de la fenêtre dynamique (un bit par vecteur)
+pub const DYNAMIC_COUNT: usize = 32;
+
+/// Bit n : vecteur DYNAMIC_BASE + n occupé
+static ALLOCATED: Mutex<u32> = Mutex::new(0);
+
+/// Cherche `count` vecteurs libres consécutifs, le premier aligné sur
+/// `count` (exigence du MSI multi-messages) ; `count` puissance de deux
+fn find_block(bitmap: u32, count: usize) -> Option<usize> {
+    if count == 0 || !count.is_power_of_two() || count > DYNAMIC_COUNT {
+        return None;
+    }
+    let mask = if count == 32 { u32::MAX } else { (1u32 << count) - 1 };
+    (0..=DYNAMIC_COUNT - count)
+        .filter(|&n| (DYNAMIC_BASE as usize + n) % count == 0)
+        .find(|&n| bitmap & (mask << n) == 0)
+}
+
+/// Réserve un bloc de vecteurs et retourne le premier
+pub fn allocate(count: usize) -> Option<u8> {
+    without_interrupts(|| {
+        let mut bitmap = ALLOCATED.lock();
+        let n = find_block(*bitmap, count)?;
+        let mask = if count == 32 { u32::MAX } else { (1u32 << count) - 1 };
+        *bitmap |= mask << n;
+        Some(DYNAMIC_BASE + n as u8)
+    })
+}
+
+/// Rend un bloc obtenu par `allocate`
+pub fn free(vector: u8, count: usize) {
+    if vector < DYNAMIC_BASE || (vector - DYNAMIC_BASE) as usize + count > DYNAMIC_COUNT {
+        return;
+    }
+    let n = (vector - DYNAMIC_BASE) as usize;
+    let mask = if count >= 32 { u32::MAX } else { (1u32 << count) - 1 };
+    without_interrupts(|| *ALLOCATED.lock() &= !(mask << n));
+}
+
+/// Vecteurs dynamiques encore libres
+pub fn available() -> usize {
+    DYNAMIC_COUNT - without_interrupts(|| ALLOCATED.lock().count_ones()) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_find_aligned_block() {
+        // DYNAMIC_BASE = 56 : un bloc de 8 commence au vecteur 56, un bloc de 16 à 64
+        assert_eq!(find_block(0, 1), Some(0));
+        assert_eq!(find_block(0, 8), Some(0));
+        assert_eq!(find_block(0, 16), Some(8));
+        assert_eq!(find_block(0b1, 1), Some(1));
+        assert_eq!(find_block(0b1, 4), Some(4));
+        assert_eq!(find_block(0, 3), None);
+        assert_eq!(find_block(u32::MAX, 1), None);
+    }
+}
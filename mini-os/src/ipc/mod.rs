@@ -7,5 +7,5 @@ pub mod mqueue;
 pub mod semaphore;
 
 pub use pipe::{Pipe, PipeManager, PIPE_MANAGER, PIPE_BUF_SIZE};
-pub use mqueue::{MessageQueue, MessageQueueManager, Message, Priority, MQ_MANAGER};
+pub use mqueue::{MessageQueue, MessageQueueManager, Message, Priority, MqAttr, MqError, MqNotify, MqOpenFlags, MQ_MANAGER};
 pub use semaphore::{Semaphore, SemaphoreManager, SEM_MANAGER};
@@ -1,14 +1,33 @@
 /// Module Message Queues
 /// 
-/// Implémente POSIX message queues
+/// Implémente POSIX message queues : files nommées (`/nom`), ouvertes par
+/// descripteur, et files anonymes du noyau. Les messages sont délivrés par
+/// priorité décroissante, dans l'ordre d'envoi à priorité égale. `send` et
+/// `receive` bloquent le thread appelant (file pleine / vide) sur les files
+/// d'attente de la queue, sauf descripteur ouvert en O_NONBLOCK.
+/// `mq_notify` prévient un processus (signal) ou le noyau (callback) quand
+/// un message arrive dans une file vide sans lecteur en attente ; comme
+/// POSIX, la notification ne sert qu'une fois.
 
 use alloc::collections::{BTreeMap, VecDeque};
+use alloc::string::String;
 use alloc::vec::Vec;
 use spin::Mutex;
+use x86_64::instructions::interrupts::without_interrupts;
+use crate::process::signal::Signal;
 
 /// Priorité de message (0-31, 31 = plus haute)
 pub type Priority = u8;
 
+/// Borne exclusive des priorités (MQ_PRIO_MAX)
+pub const MQ_PRIO_MAX: Priority = 32;
+/// Limites système d'une file (msg_max / msgsize_max de Linux)
+pub const MQ_MAXMSG_LIMIT: usize = 256;
+pub const MQ_MSGSIZE_LIMIT: usize = 8192;
+/// Attributs d'une file créée sans attributs explicites
+pub const MQ_DEFAULT_MAXMSG: usize = 10;
+pub const MQ_DEFAULT_MSGSIZE: usize = 8192;
+
 /// Message
 #[derive(Debug, Clone)]
 pub struct Message {
@@ -24,16 +43,45 @@ impl Message {
     }
 }
 
+/// Destinataire d'une notification d'arrivée
+#[derive(Debug, Clone, Copy)]
+pub enum MqNotify {
+    /// Signal envoyé au processus
+    Signal { pid: u64, signal: Signal },
+    /// Appel noyau, avec l'ID de la queue
+    Callback(fn(u32)),
+}
+
+/// Suite à donner à un envoi, hors du verrou du gestionnaire
+#[derive(Debug, Clone, Copy)]
+pub enum Arrival {
+    None,
+    /// Réveiller un lecteur bloqué
+    Wake(u64),
+    /// Notification enregistrée, consommée
+    Notify(MqNotify),
+}
+
 /// File de messages
 pub struct MessageQueue {
     /// ID de la queue
     pub id: u32,
+    /// Nom (`/nom`), absent pour une queue anonyme
+    pub name: Option<String>,
     /// Messages (triés par priorité)
     messages: Vec<Message>,
     /// Taille maximale d'un message
     pub max_msg_size: usize,
     /// Nombre maximum de messages
     pub max_msgs: usize,
+    /// Threads bloqués en réception (TIDs)
+    receivers: VecDeque<u64>,
+    /// Threads bloqués en envoi (TIDs)
+    senders: VecDeque<u64>,
+    /// Notification d'arrivée enregistrée
+    notify: Option<MqNotify>,
+    /// Descripteurs ouverts sur la queue
+    refs: usize,
 }
 
 impl MessageQueue {
@@ -41,14 +89,22 @@ impl MessageQueue {
     pub fn new(id: u32, max_msg_size: usize, max_msgs: usize) -> Self {
         Self {
             id,
+            name: None,
             messages: Vec::new(),
             max_msg_size,
             max_msgs,
+            receivers: VecDeque::new(),
+            senders: VecDeque::new(),
+            notify: None,
+            refs: 0,
         }
     }
     
     /// Envoie un message
     pub fn send(&mut self, data: Vec<u8>, priority: Priority) -> Result<(), MqError> {
+        if priority >= MQ_PRIO_MAX {
+            return Err(MqError::InvalidArgument);
+        }
         if data.len() > self.max_msg_size {
             return Err(MqError::MessageTooLarge);
         }
@@ -93,14 +149,56 @@ impl MessageQueue {
     pub fn is_full(&self) -> bool {
         self.messages.len() >= self.max_msgs
     }
+
+    /// Suite d'un envoi réussi : un lecteur bloqué passe avant la
+    /// notification, qui n'a lieu que si la file était vide
+    fn arrival(&mut self, was_empty: bool) -> Arrival {
+        if let Some(tid) = self.receivers.pop_front() {
+            Arrival::Wake(tid)
+        } else if was_empty {
+            self.notify.take().map_or(Arrival::None, Arrival::Notify)
+        } else {
+            Arrival::None
+        }
+    }
+
+    /// Threads bloqués, à réveiller quand la queue disparaît
+    fn waiters(&mut self) -> Vec<u64> {
+        self.receivers.drain(..).chain(self.senders.drain(..)).collect()
+    }
+}
+
+/// Options d'ouverture (mq_open)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MqOpenFlags {
+    pub read: bool,
+    pub write: bool,
+    pub create: bool,
+    pub exclusive: bool,
+    pub nonblock: bool,
+}
+
+/// Descripteur de queue (mqd_t)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MqDescriptor {
+    pub queue: u32,
+    pub read: bool,
+    pub write: bool,
+    pub nonblock: bool,
 }
 
 /// Gestionnaire de message queues
 pub struct MessageQueueManager {
     /// Queues par ID
     queues: BTreeMap<u32, MessageQueue>,
+    /// Queues nommées encore liées
+    names: BTreeMap<String, u32>,
+    /// Descripteurs ouverts
+    descriptors: BTreeMap<u32, MqDescriptor>,
     /// Prochain ID
     next_id: u32,
+    /// Prochain descripteur
+    next_mqd: u32,
 }
 
 impl MessageQueueManager {
@@ -108,7 +206,10 @@ impl MessageQueueManager {
     pub const fn new() -> Self {
         Self {
             queues: BTreeMap::new(),
+            names: BTreeMap::new(),
+            descriptors: BTreeMap::new(),
             next_id: 1,
+            next_mqd: 1,
         }
     }
     
@@ -151,9 +252,94 @@ impl MessageQueueManager {
             current_msgs: queue.len(),
         })
     }
+
+    /// Ouvre (ou crée avec `attr`) la queue nommée `name` ; retourne un
+    /// descripteur
+    pub fn open_named(&mut self, name: &str, flags: MqOpenFlags, attr: Option<MqAttr>) -> Result<u32, MqError> {
+        if !name.starts_with('/') || name.len() < 2 || name.len() > 255 || name[1..].contains('/') {
+            return Err(MqError::InvalidArgument);
+        }
+        let id = match self.names.get(name).copied() {
+            Some(_) if flags.create && flags.exclusive => return Err(MqError::AlreadyExists),
+            Some(id) => id,
+            None if !flags.create => return Err(MqError::NotFound),
+            None => {
+                let (max_msg_size, max_msgs) = match attr {
+                    Some(attr) => (attr.max_msg_size, attr.max_msgs),
+                    None => (MQ_DEFAULT_MSGSIZE, MQ_DEFAULT_MAXMSG),
+                };
+                if max_msgs == 0 || max_msgs > MQ_MAXMSG_LIMIT || max_msg_size == 0 || max_msg_size > MQ_MSGSIZE_LIMIT {
+                    return Err(MqError::InvalidArgument);
+                }
+                let id = self.mq_open(max_msg_size, max_msgs);
+                if let Some(queue) = self.queues.get_mut(&id) {
+                    queue.name = Some(String::from(name));
+                }
+                self.names.insert(String::from(name), id);
+                id
+            }
+        };
+        if let Some(queue) = self.queues.get_mut(&id) {
+            queue.refs += 1;
+        }
+        let mqd = self.next_mqd;
+        self.next_mqd += 1;
+        self.descriptors.insert(mqd, MqDescriptor { queue: id, read: flags.read, write: flags.write, nonblock: flags.nonblock });
+        Ok(mqd)
+    }
+
+    pub fn descriptor(&self, mqd: u32) -> Result<MqDescriptor, MqError> {
+        self.descriptors.get(&mqd).copied().ok_or(MqError::BadDescriptor)
+    }
+
+    /// Ferme un descripteur ; une queue détachée de son nom disparaît avec
+    /// son dernier descripteur. Retourne les threads à réveiller.
+    pub fn close_descriptor(&mut self, mqd: u32) -> Result<Vec<u64>, MqError> {
+        let desc = self.descriptors.remove(&mqd).ok_or(MqError::BadDescriptor)?;
+        let destroy = match self.queues.get_mut(&desc.queue) {
+            Some(queue) => {
+                queue.refs -= 1;
+                queue.refs == 0 && queue.name.as_ref().map_or(false, |name| !self.names.contains_key(name))
+            }
+            None => false,
+        };
+        Ok(if destroy { self.destroy(desc.queue) } else { Vec::new() })
+    }
+
+    /// Détache un nom (mq_unlink) ; la queue reste utilisable par les
+    /// descripteurs déjà ouverts
+    pub fn unlink(&mut self, name: &str) -> Result<Vec<u64>, MqError> {
+        let id = self.names.remove(name).ok_or(MqError::NotFound)?;
+        let unused = self.queues.get(&id).map_or(false, |queue| queue.refs == 0);
+        Ok(if unused { self.destroy(id) } else { Vec::new() })
+    }
+
+    /// Enregistre (ou retire, avec `None`) la notification d'arrivée ;
+    /// une seule par queue
+    pub fn set_notify(&mut self, mqd: u32, notify: Option<MqNotify>) -> Result<(), MqError> {
+        let desc = self.descriptor(mqd)?;
+        let queue = self.queues.get_mut(&desc.queue).ok_or(MqError::NotFound)?;
+        if notify.is_some() && queue.notify.is_some() {
+            return Err(MqError::Busy);
+        }
+        queue.notify = notify;
+        Ok(())
+    }
+
+    fn destroy(&mut self, id: u32) -> Vec<u64> {
+        self.queues.remove(&id).map_or(Vec::new(), |mut queue| queue.waiters())
+    }
+
+    /// Noms des queues liées, pour le diagnostic
+    pub fn names(&self) -> Vec<(String, MqAttr)> {
+        self.names.iter()
+            .filter_map(|(name, &id)| self.mq_getattr(id).ok().map(|attr| (name.clone(), attr)))
+            .collect()
+    }
 }
 
 /// Attributs de message queue
+#[repr(C)]
 #[derive(Debug, Clone, Copy)]
 pub struct MqAttr {
     pub max_msgs: usize,
@@ -168,6 +354,12 @@ pub enum MqError {
     QueueFull,
     MessageTooLarge,
     WouldBlock,
+    AlreadyExists,
+    InvalidArgument,
+    /// Descripteur inconnu ou ouvert sans le droit demandé
+    BadDescriptor,
+    /// Notification déjà enregistrée
+    Busy,
 }
 
 /// Instance globale
@@ -177,6 +369,103 @@ lazy_static! {
     pub static ref MQ_MANAGER: Mutex<MessageQueueManager> = Mutex::new(MessageQueueManager::new());
 }
 
+/// Étape d'une opération bloquante
+enum Step<T> {
+    Done(T),
+    /// Le thread est inscrit en attente sur la queue
+    Wait,
+}
+
+fn current_tid() -> Option<u64> {
+    crate::scheduler::current_thread().map(|thread| thread.lock().tid)
+}
+
+/// Réveille des threads bloqués sur une queue
+pub fn wake_all(tids: Vec<u64>) {
+    for tid in tids {
+        crate::scheduler::SCHEDULER.wake_thread(tid);
+    }
+}
+
+fn block() {
+    crate::scheduler::SCHEDULER.block_current_thread(crate::process::ThreadState::Blocked);
+}
+
+/// Applique la suite d'un envoi, verrou du gestionnaire relâché
+fn deliver(id: u32, arrival: Arrival) {
+    match arrival {
+        Arrival::None => {}
+        Arrival::Wake(tid) => crate::scheduler::SCHEDULER.wake_thread(tid),
+        Arrival::Notify(MqNotify::Callback(callback)) => callback(id),
+        Arrival::Notify(MqNotify::Signal { pid, signal }) => {
+            let mut pm = crate::process::PROCESS_MANAGER.lock();
+            if crate::process::signal::SIGNAL_MANAGER.lock().send_signal(pid, signal, &mut pm).is_err() {
+                log::debug!("mqueue: notification perdue, processus {} absent", pid);
+            }
+        }
+    }
+}
+
+/// mq_send : bloque tant que la file est pleine (sauf O_NONBLOCK)
+pub fn send(mqd: u32, data: Vec<u8>, priority: Priority) -> Result<(), MqError> {
+    loop {
+        let step = without_interrupts(|| {
+            let mut manager = MQ_MANAGER.lock();
+            let desc = manager.descriptor(mqd)?;
+            if !desc.write {
+                return Err(MqError::BadDescriptor);
+            }
+            let queue = manager.queues.get_mut(&desc.queue).ok_or(MqError::NotFound)?;
+            let was_empty = queue.is_empty();
+            match queue.send(data.clone(), priority) {
+                Ok(()) => Ok(Step::Done((desc.queue, queue.arrival(was_empty)))),
+                Err(MqError::QueueFull) if !desc.nonblock => {
+                    queue.senders.push_back(current_tid().ok_or(MqError::WouldBlock)?);
+                    Ok(Step::Wait)
+                }
+                Err(e) => Err(e),
+            }
+        })?;
+        match step {
+            Step::Done((id, arrival)) => {
+                deliver(id, arrival);
+                return Ok(());
+            }
+            Step::Wait => block(),
+        }
+    }
+}
+
+/// mq_receive : message de plus haute priorité ; bloque tant que la
+/// file est vide (sauf O_NONBLOCK)
+pub fn receive(mqd: u32) -> Result<Message, MqError> {
+    loop {
+        let step = without_interrupts(|| {
+            let mut manager = MQ_MANAGER.lock();
+            let desc = manager.descriptor(mqd)?;
+            if !desc.read {
+                return Err(MqError::BadDescriptor);
+            }
+            let queue = manager.queues.get_mut(&desc.queue).ok_or(MqError::NotFound)?;
+            match queue.receive() {
+                Ok(message) => Ok(Step::Done((message, queue.senders.pop_front()))),
+                Err(MqError::WouldBlock) if !desc.nonblock => {
+                    queue.receivers.push_back(current_tid().ok_or(MqError::WouldBlock)?);
+                    Ok(Step::Wait)
+                }
+                Err(e) => Err(e),
+            }
+        })?;
+        match step {
+            Step::Done((message, sender)) => {
+                wake_all(sender.into_iter().collect());
+                return Ok(message);
+            }
+            Step::Wait => block(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -222,4 +511,52 @@ mod tests {
         let result = queue.send(b"Msg3".to_vec(), 1);
         assert_eq!(result, Err(MqError::QueueFull));
     }
+
+    fn on_arrival(_id: u32) {}
+
+    #[test_case]
+    fn test_named_queue() {
+        let mut manager = MessageQueueManager::new();
+        let create = MqOpenFlags { read: true, write: true, create: true, exclusive: true, nonblock: true };
+        let attr = MqAttr { max_msgs: 4, max_msg_size: 64, current_msgs: 0 };
+        let mqd = manager.open_named("/jobs", create, Some(attr)).unwrap();
+        assert_eq!(manager.open_named("/jobs", create, None), Err(MqError::AlreadyExists));
+        assert_eq!(manager.open_named("jobs", create, None), Err(MqError::InvalidArgument));
+
+        let desc = manager.descriptor(mqd).unwrap();
+        assert_eq!(manager.mq_send(desc.queue, b"x".to_vec(), MQ_PRIO_MAX), Err(MqError::InvalidArgument));
+
+        // Notification : file vide, aucun lecteur en attente, une seule fois
+        manager.set_notify(mqd, Some(MqNotify::Callback(on_arrival))).unwrap();
+        assert_eq!(manager.set_notify(mqd, Some(MqNotify::Callback(on_arrival))), Err(MqError::Busy));
+        let queue = manager.queues.get_mut(&desc.queue).unwrap();
+        queue.send(b"a".to_vec(), 1).unwrap();
+        assert!(matches!(queue.arrival(true), Arrival::Notify(MqNotify::Callback(_))));
+        assert!(matches!(queue.arrival(true), Arrival::None));
+
+        // Détachée de son nom, la queue vit jusqu'au dernier descripteur
+        manager.unlink("/jobs").unwrap();
+        assert_eq!(manager.mq_getattr(desc.queue).unwrap().current_msgs, 1);
+        manager.close_descriptor(mqd).unwrap();
+        assert_eq!(manager.mq_getattr(desc.queue).err(), Some(MqError::NotFound));
+        assert_eq!(manager.descriptor(mqd), Err(MqError::BadDescriptor));
+    }
+
+    #[test_case]
+    fn test_mq_send_rejects_oversized_length() {
+        use crate::syscall::{SyscallError, SyscallHandler, SyscallNumber, SyscallResult};
+        let create = MqOpenFlags { read: true, write: true, create: true, exclusive: true, nonblock: true };
+        let attr = MqAttr { max_msgs: 4, max_msg_size: 64, current_msgs: 0 };
+        let mqd = MQ_MANAGER.lock().open_named("/mq_send_len", create, Some(attr)).unwrap();
+
+        // Longueur démesurée : EMSGSIZE sans allouer ni copier
+        let data = [0u8; 1];
+        let args = [mqd as u64, data.as_ptr() as u64, 1 << 40, 0];
+        let result = SyscallHandler::new().handle(SyscallNumber::MqSend as u64, &args);
+        assert!(matches!(result, SyscallResult::Error(SyscallError::MessageTooLarge)));
+
+        let mut manager = MQ_MANAGER.lock();
+        manager.unlink("/mq_send_len").unwrap();
+        manager.close_descriptor(mqd).unwrap();
+    }
 }
@@ -1,10 +1,17 @@
 /// Module Pipes
 /// 
 /// Implémente pipes anonymes et named pipes (FIFO)
+///
+/// Une FIFO du VFS (FileType::Fifo) n'a pas de données sur disque : son
+/// tampon est un pipe, créé à la première ouverture et libéré quand plus
+/// personne ne l'a ouverte, comme sous Linux. L'ouverture bloque jusqu'à
+/// ce que l'autre extrémité soit présente ; lectures et écritures bloquent
+/// sur la file d'attente de poll.
 
 use alloc::collections::{BTreeMap, VecDeque};
 use alloc::string::String;
 use spin::Mutex;
+use x86_64::instructions::interrupts::without_interrupts;
 
 /// Taille du buffer de pipe
 pub const PIPE_BUF_SIZE: usize = 4096;
@@ -23,6 +30,8 @@ pub struct Pipe {
     writers: usize,
     /// Named pipe (FIFO)
     pub name: Option<String>,
+    /// FIFO du VFS servie par ce pipe
+    fifo: Option<FifoKey>,
 }
 
 impl Pipe {
@@ -35,6 +44,7 @@ impl Pipe {
             readers: 0,
             writers: 0,
             name: None,
+            fifo: None,
         }
     }
     
@@ -121,14 +131,33 @@ impl Pipe {
     pub fn available(&self) -> usize {
         self.buffer.len()
     }
+
+    /// Disponibilité pour poll, côté lecture ou écriture
+    pub fn poll_events(&self, for_write: bool) -> i16 {
+        use crate::fs::poll::{POLLERR, POLLHUP, POLLIN, POLLOUT};
+        if for_write {
+            if self.readers == 0 { POLLERR } else if self.is_full() { 0 } else { POLLOUT }
+        } else if !self.is_empty() {
+            POLLIN
+        } else if self.writers == 0 {
+            POLLHUP
+        } else {
+            0
+        }
+    }
 }
 
+/// Identité d'une FIFO du VFS : (système de fichiers, inode)
+pub type FifoKey = (crate::fs::FsId, crate::fs::InodeId);
+
 /// Gestionnaire de pipes
 pub struct PipeManager {
     /// Pipes par ID
     pipes: BTreeMap<u32, Pipe>,
     /// Named pipes par nom
     named_pipes: BTreeMap<String, u32>,
+    /// Pipes des FIFO du VFS ouvertes
+    fifos: BTreeMap<FifoKey, u32>,
     /// Prochain ID
     next_id: u32,
 }
@@ -139,6 +168,7 @@ impl PipeManager {
         Self {
             pipes: BTreeMap::new(),
             named_pipes: BTreeMap::new(),
+            fifos: BTreeMap::new(),
             next_id: 1,
         }
     }
@@ -192,13 +222,17 @@ impl PipeManager {
     /// Écrit dans un pipe
     pub fn write(&mut self, id: u32, data: &[u8]) -> Result<usize, PipeError> {
         let pipe = self.pipes.get_mut(&id).ok_or(PipeError::NotFound)?;
-        pipe.write(data)
+        let written = pipe.write(data)?;
+        crate::fs::poll::wake();
+        Ok(written)
     }
     
     /// Lit depuis un pipe
     pub fn read(&mut self, id: u32, buffer: &mut [u8]) -> Result<usize, PipeError> {
         let pipe = self.pipes.get_mut(&id).ok_or(PipeError::NotFound)?;
-        pipe.read(buffer)
+        let read = pipe.read(buffer)?;
+        crate::fs::poll::wake();
+        Ok(read)
     }
     
     /// Ferme un pipe
@@ -216,11 +250,132 @@ impl PipeManager {
             if let Some(name) = &pipe.name {
                 self.named_pipes.remove(name);
             }
+            if let Some(key) = pipe.fifo {
+                self.fifos.remove(&key);
+            }
             self.pipes.remove(&id);
         }
+        crate::fs::poll::wake();
         
         Ok(())
     }
+    
+    /// Ouvre une extrémité de la FIFO `key`, en créant son pipe au besoin
+    pub fn open_vfs_fifo(&mut self, key: FifoKey, read: bool, write: bool) -> u32 {
+        let id = match self.fifos.get(&key) {
+            Some(&id) => id,
+            None => {
+                let id = self.next_id;
+                self.next_id += 1;
+                let mut pipe = Pipe::new(id, PIPE_BUF_SIZE);
+                pipe.fifo = Some(key);
+                self.pipes.insert(id, pipe);
+                self.fifos.insert(key, id);
+                id
+            }
+        };
+        self.reopen(id, read, write);
+        id
+    }
+    
+    /// Compte une ouverture de plus (open, dup)
+    pub fn reopen(&mut self, id: u32, read: bool, write: bool) {
+        if let Some(pipe) = self.pipes.get_mut(&id) {
+            if read {
+                pipe.open_read();
+            }
+            if write {
+                pipe.open_write();
+            }
+        }
+        crate::fs::poll::wake();
+    }
+    
+    /// Nombre de lecteurs et d'écrivains
+    pub fn ends(&self, id: u32) -> Option<(usize, usize)> {
+        self.pipes.get(&id).map(|pipe| (pipe.readers, pipe.writers))
+    }
+    
+    /// Disponibilité pour poll d'un descripteur ouvert en lecture et/ou écriture
+    pub fn poll_events(&self, id: u32, read: bool, write: bool) -> i16 {
+        match self.pipes.get(&id) {
+            Some(pipe) => {
+                let mut events = 0;
+                if read {
+                    events |= pipe.poll_events(false);
+                }
+                if write {
+                    events |= pipe.poll_events(true);
+                }
+                events
+            }
+            None => crate::fs::poll::POLLHUP,
+        }
+    }
+}
+
+/// Ouvre la FIFO `key` ; sans O_NONBLOCK, attend l'autre extrémité.
+/// En O_NONBLOCK, l'écriture sans lecteur échoue (NoReader) et la
+/// lecture réussit aussitôt. Ouverte en lecture-écriture, elle ne bloque pas.
+pub fn fifo_open(key: FifoKey, read: bool, write: bool, nonblock: bool) -> Result<u32, PipeError> {
+    let id = without_interrupts(|| PIPE_MANAGER.lock().open_vfs_fifo(key, read, write));
+    loop {
+        let seen = crate::fs::poll::events();
+        let (readers, writers) = without_interrupts(|| PIPE_MANAGER.lock().ends(id)).ok_or(PipeError::NotFound)?;
+        let ready = if write { readers > 0 } else { writers > 0 };
+        if ready || (read && write) || (nonblock && !write) {
+            return Ok(id);
+        }
+        if nonblock {
+            let _ = fifo_close(id, read, write);
+            return Err(PipeError::NoReader);
+        }
+        crate::fs::poll::wait_event(seen);
+    }
+}
+
+/// Lit dans une FIFO ; sans O_NONBLOCK, attend des données tant qu'un
+/// écrivain est présent. 0 : fin de fichier.
+pub fn fifo_read(id: u32, buffer: &mut [u8], nonblock: bool) -> Result<usize, PipeError> {
+    loop {
+        let seen = crate::fs::poll::events();
+        match without_interrupts(|| PIPE_MANAGER.lock().read(id, buffer)) {
+            Err(PipeError::WouldBlock) if !nonblock => crate::fs::poll::wait_event(seen),
+            Err(PipeError::NotFound) => return Ok(0),
+            result => return result,
+        }
+    }
+}
+
+/// Écrit tout `data` dans une FIFO (attente de place sans O_NONBLOCK) ;
+/// BrokenPipe s'il n'y a plus de lecteur
+pub fn fifo_write(id: u32, data: &[u8], nonblock: bool) -> Result<usize, PipeError> {
+    let mut written = 0;
+    while written < data.len() {
+        let seen = crate::fs::poll::events();
+        match without_interrupts(|| PIPE_MANAGER.lock().write(id, &data[written..])) {
+            Ok(n) => written += n,
+            Err(PipeError::WouldBlock) if nonblock && written > 0 => break,
+            Err(PipeError::WouldBlock) if !nonblock => crate::fs::poll::wait_event(seen),
+            Err(PipeError::NotFound) => return Err(PipeError::BrokenPipe),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(written)
+}
+
+/// Ferme les extrémités ouvertes par un descripteur de FIFO
+pub fn fifo_close(id: u32, read: bool, write: bool) -> Result<(), PipeError> {
+    without_interrupts(|| {
+        let mut manager = PIPE_MANAGER.lock();
+        if read {
+            manager.close(id, false)?;
+        }
+        if write {
+            manager.close(id, true)?;
+        }
+        Ok(())
+    })
 }
 
 /// Erreurs de pipe
@@ -230,6 +385,8 @@ pub enum PipeError {
     BrokenPipe,
     WouldBlock,
     AlreadyExists,
+    /// FIFO ouverte en écriture non bloquante sans lecteur (ENXIO)
+    NoReader,
 }
 
 /// Instance globale
@@ -296,4 +453,26 @@ mod tests {
         assert_eq!(write_fd, read_fd);
         assert_eq!(write_fd, id);
     }
+
+    #[test_case]
+    fn test_vfs_fifo_lifetime() {
+        let mut manager = PipeManager::new();
+        let key = (1, 42);
+        let reader = manager.open_vfs_fifo(key, true, false);
+        let writer = manager.open_vfs_fifo(key, false, true);
+        assert_eq!(reader, writer);
+        assert_eq!(manager.ends(reader), Some((1, 1)));
+        
+        manager.write(writer, b"ls").unwrap();
+        manager.close(writer, true).unwrap();
+        let mut buffer = [0u8; 4];
+        assert_eq!(manager.read(reader, &mut buffer), Ok(2));
+        // Plus d'écrivain : fin de fichier
+        assert_eq!(manager.read(reader, &mut buffer), Ok(0));
+        
+        // Dernière fermeture : le pipe disparaît, la FIFO repart à vide
+        manager.close(reader, false).unwrap();
+        assert_eq!(manager.ends(reader), None);
+        assert_ne!(manager.open_vfs_fifo(key, true, false), reader);
+    }
 }
@@ -0,0 +1,297 @@
+/// Vidage sur panique (mini kdump)
+///
+/// Avec `kdump` sur la ligne de commande, le gestionnaire de panique
+/// appelle `on_panic` : le message, les registres de contrôle, le haut de
+/// la pile, l'anneau du journal (dmesg) et la liste des processus sont
+/// écrits en texte dans une zone de mémoire persistante (façon pstore),
+/// puis le noyau redémarre. La zone occupe la fin de la région réservée au
+/// noyau (`KERNEL_RESERVED_END`) : l'allocateur de cadres ne la distribue
+/// jamais et un redémarrage à chaud (QEMU `system_reset`) n'efface pas la
+/// RAM.
+///
+/// Au démarrage suivant, `take_crash_dump` relit la zone (en-tête et somme
+/// de contrôle) et l'efface ; une fois la racine montée, `save_crash_dump`
+/// enregistre le vidage sous `/var/crash/dump-<n>.txt` (ou le répertoire
+/// donné par `kdump=<rép>`).
+///
+/// Le vidage ne doit ni allouer ni attendre un verrou : il écrit
+/// directement dans la zone, prend les verrous avec `try_lock` et scelle
+/// l'en-tête après chaque section, si bien qu'une faute en cours de route
+/// laisse un vidage partiel mais lisible.
+
+use alloc::format;
+use alloc::string::String;
+use core::fmt::{self, Write};
+use core::sync::atomic::{AtomicBool, Ordering};
+use spin::Mutex;
+use crate::memory::frame::KERNEL_RESERVED_END;
+
+/// Taille de la zone persistante
+pub const PSTORE_SIZE: usize = 64 * 1024;
+/// Adresse physique (identité) de la zone : fin de la région réservée
+pub const PSTORE_ADDR: u64 = KERNEL_RESERVED_END - PSTORE_SIZE as u64;
+/// Répertoire des vidages par défaut
+pub const DEFAULT_CRASH_DIR: &str = "/var/crash";
+/// Signature de l'en-tête
+const DUMP_MAGIC: u64 = 0x4345_5250_4d55_444b; // "KDUMPREC"
+/// En-tête : signature, longueur du texte, somme de contrôle
+const HEADER_SIZE: usize = 24;
+/// Mots de pile recopiés à partir de rsp
+const STACK_WORDS: usize = 32;
+
+/// Vidage activé (`kdump` sur la ligne de commande)
+static ENABLED: AtomicBool = AtomicBool::new(false);
+/// Vidage en cours : une panique pendant le vidage ne le relance pas
+static DUMPING: AtomicBool = AtomicBool::new(false);
+/// Vidage laissé par le démarrage précédent
+static PREVIOUS: Mutex<Option<String>> = Mutex::new(None);
+
+/// Active le vidage ; refusé si l'image du noyau déborde sur la zone
+pub fn set_enabled(enabled: bool) -> Result<(), &'static str> {
+    let kernel_end = crate::memory::frame::kernel_end();
+    if enabled && kernel_end > PSTORE_ADDR {
+        return Err("kdump: l'image du noyau recouvre la zone persistante");
+    }
+    ENABLED.store(enabled, Ordering::SeqCst);
+    Ok(())
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+/// Registres relevés au moment de la panique
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Registers {
+    pub rip: u64,
+    pub rsp: u64,
+    pub rbp: u64,
+    pub rflags: u64,
+    pub cr0: u64,
+    pub cr2: u64,
+    pub cr3: u64,
+    pub cr4: u64,
+}
+
+impl Registers {
+    /// Relève les registres de l'appelant
+    #[inline(always)]
+    pub fn capture() -> Self {
+        let mut regs = Self::default();
+        unsafe {
+            core::arch::asm!(
+                "lea {rip}, [rip]",
+                "mov {rsp}, rsp",
+                "mov {rbp}, rbp",
+                "pushfq",
+                "pop {rflags}",
+                rip = out(reg) regs.rip,
+                rsp = out(reg) regs.rsp,
+                rbp = out(reg) regs.rbp,
+                rflags = out(reg) regs.rflags,
+            );
+            core::arch::asm!("mov {}, cr0", out(reg) regs.cr0, options(nomem, nostack));
+            core::arch::asm!("mov {}, cr2", out(reg) regs.cr2, options(nomem, nostack));
+            core::arch::asm!("mov {}, cr3", out(reg) regs.cr3, options(nomem, nostack));
+            core::arch::asm!("mov {}, cr4", out(reg) regs.cr4, options(nomem, nostack));
+        }
+        regs
+    }
+}
+
+/// Écriture de texte dans un tampon fixe ; l'excédent est perdu
+struct DumpWriter<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+impl Write for DumpWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let n = s.len().min(self.buf.len() - self.len);
+        self.buf[self.len..self.len + n].copy_from_slice(&s.as_bytes()[..n]);
+        self.len += n;
+        Ok(())
+    }
+}
+
+/// Somme de contrôle FNV-1a du texte
+fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+/// Écrit l'en-tête d'un texte de `len` octets
+fn seal(region: &mut [u8], len: usize) {
+    let (header, text) = region.split_at_mut(HEADER_SIZE);
+    header[8..16].copy_from_slice(&(len as u64).to_le_bytes());
+    header[16..24].copy_from_slice(&checksum(&text[..len]).to_le_bytes());
+    header[..8].copy_from_slice(&DUMP_MAGIC.to_le_bytes());
+}
+
+/// Texte d'une zone scellée, None si l'en-tête ou la somme est invalide
+fn unseal(region: &[u8]) -> Option<&[u8]> {
+    let word = |i: usize| u64::from_le_bytes(region[i * 8..i * 8 + 8].try_into().unwrap());
+    if word(0) != DUMP_MAGIC {
+        return None;
+    }
+    let text = region[HEADER_SIZE..].get(..word(1) as usize)?;
+    (checksum(text) == word(2)).then_some(text)
+}
+
+/// Zone persistante
+///
+/// # Safety
+/// La zone est identité-mappée et n'a qu'un utilisateur à la fois
+/// (panique, ou démarrage avant le multitâche).
+unsafe fn region() -> &'static mut [u8] {
+    core::slice::from_raw_parts_mut(PSTORE_ADDR as *mut u8, PSTORE_SIZE)
+}
+
+/// Registres et haut de la pile
+fn write_registers(out: &mut DumpWriter, regs: &Registers) {
+    let _ = writeln!(out, "-- Registres --");
+    let _ = writeln!(out, "RIP {:016x} RSP {:016x} RBP {:016x} RFLAGS {:016x}", regs.rip, regs.rsp, regs.rbp, regs.rflags);
+    let _ = writeln!(out, "CR0 {:016x} CR2 {:016x} CR3 {:016x} CR4 {:016x}", regs.cr0, regs.cr2, regs.cr3, regs.cr4);
+    let _ = writeln!(out, "-- Pile --");
+    let stack = regs.rsp as *const u64;
+    for row in 0..STACK_WORDS / 4 {
+        let _ = write!(out, "{:016x}:", regs.rsp + row as u64 * 32);
+        for i in 0..4 {
+            let word = unsafe { stack.add(row * 4 + i).read_volatile() };
+            let _ = write!(out, " {:016x}", word);
+        }
+        let _ = writeln!(out);
+    }
+}
+
+/// Lignes de l'anneau du journal (sauf si son verrou est tenu)
+fn write_klog(out: &mut DumpWriter) {
+    let _ = writeln!(out, "-- Journal --");
+    let complete = crate::klog::try_for_each_line(|line| {
+        let _ = writeln!(out, "{}", line);
+    });
+    if !complete {
+        let _ = writeln!(out, "(journal verrouillé)");
+    }
+}
+
+/// Processus publiés ; ceux dont le verrou est tenu sont signalés
+fn write_processes(out: &mut DumpWriter) {
+    let _ = writeln!(out, "-- Processus --");
+    let _ = writeln!(out, "  PID  PPID THREADS ÉTAT/PRIORITÉ NOM");
+    for (pid, process) in crate::process::PROCESS_INDEX.read().iter() {
+        match process.try_lock() {
+            Some(p) => {
+                let _ = writeln!(
+                    out,
+                    "{:>5} {:>5} {:>7} {:?}/{:?} {}",
+                    p.pid,
+                    p.parent_pid,
+                    p.threads.len(),
+                    p.state,
+                    p.priority,
+                    p.name
+                );
+            }
+            None => {
+                let _ = writeln!(out, "{:>5} (verrouillé)", pid);
+            }
+        }
+    }
+}
+
+/// En-tête de la panique : message, date, registres et pile
+fn write_header(out: &mut DumpWriter, info: &dyn fmt::Display, regs: &Registers) {
+    use crate::time::NSEC_PER_SEC;
+
+    let uptime = crate::time::now_ns();
+    let _ = writeln!(out, "=== mini-os : vidage après panique ===");
+    let _ = writeln!(out, "Panique : {}", info);
+    let _ = writeln!(out, "Uptime : {}.{:06} s, CPU {}", uptime / NSEC_PER_SEC, uptime % NSEC_PER_SEC / 1_000, crate::scheduler::current_cpu());
+    write_registers(out, regs);
+}
+
+/// Écrit le vidage de la panique `info` ; vrai s'il a été écrit
+pub fn on_panic(info: &dyn fmt::Display) -> bool {
+    if !enabled() || DUMPING.swap(true, Ordering::SeqCst) {
+        return false;
+    }
+    let regs = Registers::capture();
+    let region = unsafe { region() };
+
+    let mut len = {
+        let mut out = DumpWriter { buf: &mut region[HEADER_SIZE..], len: 0 };
+        write_header(&mut out, info, &regs);
+        out.len
+    };
+    seal(region, len);
+    // Scellé après chaque section : une faute plus loin garde le début
+    let sections: [fn(&mut DumpWriter); 2] = [write_klog, write_processes];
+    for section in sections {
+        let mut out = DumpWriter { buf: &mut region[HEADER_SIZE..], len };
+        section(&mut out);
+        len = out.len;
+        seal(region, len);
+    }
+    true
+}
+
+/// Lit (une seule fois) le vidage du démarrage précédent et efface la
+/// zone ; à appeler au démarrage, une fois le tas prêt
+pub fn take_crash_dump() -> Option<String> {
+    let region = unsafe { region() };
+    let text = String::from_utf8_lossy(unseal(region)?).into_owned();
+    region[..HEADER_SIZE].fill(0);
+    *PREVIOUS.lock() = Some(text.clone());
+    Some(text)
+}
+
+/// Vidage du démarrage précédent (après `take_crash_dump`)
+pub fn previous_dump() -> Option<String> {
+    PREVIOUS.lock().clone()
+}
+
+/// Enregistre le vidage précédent dans `dir` (créé au besoin) ; renvoie
+/// le chemin du fichier écrit
+pub fn save_crash_dump(dir: &str) -> Result<Option<String>, &'static str> {
+    let Some(text) = previous_dump() else {
+        return Ok(None);
+    };
+    // Création de chaque niveau du chemin, existants ignorés
+    let mut prefix = String::new();
+    for part in dir.split('/').filter(|p| !p.is_empty()) {
+        prefix.push('/');
+        prefix.push_str(part);
+        let _ = crate::fs::vfs_mkdir(&prefix);
+    }
+    let path = (0..1000)
+        .map(|n| format!("{}/dump-{}.txt", dir.trim_end_matches('/'), n))
+        .find(|path| crate::fs::vfs_stat(path).is_err())
+        .ok_or("kdump: trop de vidages")?;
+    crate::fs::vfs_write_file(&path, text.as_bytes()).map_err(|_| "kdump: écriture impossible")?;
+    Ok(Some(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_dump_seal_and_truncation() {
+        let mut region = [0u8; HEADER_SIZE + 32];
+        let mut out = DumpWriter { buf: &mut region[HEADER_SIZE..], len: 0 };
+        let _ = write!(out, "Panique : {}", "test");
+        let len = out.len;
+        assert_eq!(unseal(&region), None);
+        seal(&mut region, len);
+        assert_eq!(unseal(&region), Some(&b"Panique : test"[..]));
+
+        // Texte corrompu : somme de contrôle fausse
+        region[HEADER_SIZE] = b'p';
+        assert_eq!(unseal(&region), None);
+
+        // L'excédent est perdu sans erreur
+        let mut out = DumpWriter { buf: &mut region[HEADER_SIZE..], len: 0 };
+        assert!(write!(out, "{:>40}", "x").is_ok());
+        assert_eq!(out.len, 32);
+    }
+}
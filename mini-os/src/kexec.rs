@@ -0,0 +1,304 @@
+/// kexec : redémarrage à chaud sur un nouveau noyau
+///
+/// `load(chemin)` lit un ELF64 dans le VFS et recopie ses segments PT_LOAD
+/// dans des blocs de l'allocateur de cadres (au-dessus de
+/// `KERNEL_RESERVED_END`), avec la liste des copies à faire. Les adresses
+/// physiques de destination doivent tenir sous `KERNEL_RESERVED_END` :
+/// sources et destinations ne se chevauchent jamais.
+///
+/// `execute(raison)` synchronise les systèmes de fichiers, arrête les
+/// drivers, écrit la page de journal, masque les interruptions puis saute
+/// dans un trampoline copié en mémoire basse. Le trampoline charge ses
+/// propres tables de pages (identité sur le premier Gio, hors de la zone
+/// écrasée), effectue les copies, met à zéro les .bss, puis saute au point
+/// d'entrée du nouveau noyau avec une pile en mémoire conventionnelle.
+///
+/// La page de journal (`REBOOT_LOG_ADDR`) survit au saut : le noyau
+/// suivant la lit au démarrage avec `take_reboot_log()`.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::arch::global_asm;
+use spin::Mutex;
+use x86_64::PhysAddr;
+use crate::memory::frame::{BuddyFrameAllocator, FRAME_ALLOCATOR, KERNEL_RESERVED_END, MAX_ORDER};
+use crate::process::elf::{ElfFile, PT_LOAD};
+
+/// Page du trampoline (mémoire conventionnelle, identité)
+const TRAMPOLINE_ADDR: u64 = 0x7000;
+/// Sommet de la pile passée au nouveau noyau
+const STACK_TOP: u64 = 0x7_F000;
+/// Page de journal transmise au noyau suivant
+pub const REBOOT_LOG_ADDR: u64 = 0x6000;
+/// Signature de la page de journal
+const REBOOT_LOG_MAGIC: u64 = 0x4b45_5845_434c_4f47; // "KEXECLOG"
+/// Texte conservé dans la page
+const REBOOT_LOG_TEXT: usize = 4096 - 16;
+/// Taille d'un bloc source (ordre maximal du buddy)
+const BLOCK_SIZE: u64 = BuddyFrameAllocator::block_size(MAX_ORDER);
+/// Copies décrites par la page de liste
+const MAX_COPIES: usize = 4096 / core::mem::size_of::<Transfer>();
+
+/// Une copie du trampoline ; `src == 0` : mise à zéro
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Transfer {
+    dest: u64,
+    src: u64,
+    len: u64,
+}
+
+/// Page de journal, lue par le noyau suivant
+#[repr(C)]
+struct RebootLog {
+    magic: u64,
+    len: u64,
+    text: [u8; REBOOT_LOG_TEXT],
+}
+
+/// Image chargée, prête à être exécutée
+pub struct KexecImage {
+    pub path: String,
+    pub entry: u64,
+    copies: Vec<Transfer>,
+    /// Blocs sources (ordre MAX_ORDER), rendus si l'image est déchargée
+    blocks: Vec<PhysAddr>,
+}
+
+impl Drop for KexecImage {
+    fn drop(&mut self) {
+        let mut frames = FRAME_ALLOCATOR.lock();
+        for &block in &self.blocks {
+            frames.free(block, MAX_ORDER);
+        }
+    }
+}
+
+static LOADED: Mutex<Option<KexecImage>> = Mutex::new(None);
+/// Journal laissé par le noyau précédent
+static PREVIOUS_LOG: Mutex<Option<String>> = Mutex::new(None);
+
+/// Vérifie les segments d'un ELF noyau ; retourne (point d'entrée,
+/// segments (adresse physique, données, taille en mémoire))
+fn segments(data: &[u8]) -> Result<(u64, Vec<(u64, &[u8], u64)>), &'static str> {
+    let elf = ElfFile::new(data)?;
+    elf.header.validate()?;
+    let mut segments = Vec::new();
+    for ph in elf.program_headers() {
+        if ph.p_type != PT_LOAD || ph.p_memsz == 0 {
+            continue;
+        }
+        let (offset, filesz, memsz, paddr) = (ph.p_offset as usize, ph.p_filesz as usize, ph.p_memsz, ph.p_paddr);
+        if filesz as u64 > memsz || offset.checked_add(filesz).map_or(true, |end| end > data.len()) {
+            return Err("kexec: segment hors du fichier");
+        }
+        if paddr < 0x10_0000 || paddr.checked_add(memsz).map_or(true, |end| end > KERNEL_RESERVED_END) {
+            return Err("kexec: segment hors de la zone noyau");
+        }
+        segments.push((paddr, &data[offset..offset + filesz], memsz));
+    }
+    if segments.is_empty() {
+        return Err("kexec: aucun segment chargeable");
+    }
+    Ok((elf.entry_point(), segments))
+}
+
+/// Charge le noyau `path` ; remplace l'image déjà chargée
+pub fn load(path: &str) -> Result<(u64, usize), &'static str> {
+    let data = crate::fs::vfs_read_file(path).map_err(|_| "kexec: lecture impossible")?;
+    let (entry, segments) = segments(&data)?;
+
+    let mut image = KexecImage { path: String::from(path), entry, copies: Vec::new(), blocks: Vec::new() };
+    let mut total = 0;
+    for (paddr, bytes, memsz) in segments {
+        // Données du fichier, par blocs de 2 Mio
+        for (i, chunk) in bytes.chunks(BLOCK_SIZE as usize).enumerate() {
+            let block = FRAME_ALLOCATOR.lock().allocate(MAX_ORDER).ok_or("kexec: mémoire insuffisante")?;
+            image.blocks.push(block);
+            // Mémoire physique en identité (décalage 0)
+            unsafe { core::ptr::copy_nonoverlapping(chunk.as_ptr(), block.as_u64() as *mut u8, chunk.len()) };
+            image.copies.push(Transfer { dest: paddr + i as u64 * BLOCK_SIZE, src: block.as_u64(), len: chunk.len() as u64 });
+        }
+        // .bss
+        if memsz > bytes.len() as u64 {
+            image.copies.push(Transfer { dest: paddr + bytes.len() as u64, src: 0, len: memsz - bytes.len() as u64 });
+        }
+        total += memsz as usize;
+    }
+    if image.copies.len() > MAX_COPIES {
+        return Err("kexec: image trop fragmentée");
+    }
+    crate::serial_println!("kexec: {} chargé ({} octets, entrée {:#x})", path, total, entry);
+    *LOADED.lock() = Some(image);
+    Ok((entry, total))
+}
+
+/// Décharge l'image ; faux si aucune n'était chargée
+pub fn unload() -> bool {
+    LOADED.lock().take().is_some()
+}
+
+/// Chemin et point d'entrée de l'image chargée
+pub fn loaded() -> Option<(String, u64)> {
+    LOADED.lock().as_ref().map(|image| (image.path.clone(), image.entry))
+}
+
+/// Écrit `reason` et la fin du journal du noyau dans la page de journal
+fn write_reboot_log(reason: &str) {
+    let mut text = String::new();
+    text.push_str(reason);
+    text.push('\n');
+    for line in crate::klog::lines() {
+        text.push_str(&line);
+        text.push('\n');
+    }
+    // Les lignes les plus récentes sont gardées
+    let bytes = text.as_bytes();
+    let start = bytes.len().saturating_sub(REBOOT_LOG_TEXT);
+    let kept = &bytes[start..];
+    let log = REBOOT_LOG_ADDR as *mut RebootLog;
+    unsafe {
+        (&mut (*log).text)[..kept.len()].copy_from_slice(kept);
+        (*log).len = kept.len() as u64;
+        (*log).magic = REBOOT_LOG_MAGIC;
+    }
+}
+
+/// Lit (une seule fois) la page de journal du noyau précédent ; à
+/// appeler au démarrage, une fois le tas prêt
+pub fn take_reboot_log() -> Option<String> {
+    let log = REBOOT_LOG_ADDR as *mut RebootLog;
+    let text = unsafe {
+        if (*log).magic != REBOOT_LOG_MAGIC {
+            return None;
+        }
+        (*log).magic = 0;
+        let len = ((*log).len as usize).min(REBOOT_LOG_TEXT);
+        String::from_utf8_lossy(&(&(*log).text)[..len]).into_owned()
+    };
+    *PREVIOUS_LOG.lock() = Some(text.clone());
+    Some(text)
+}
+
+/// Journal laissé par le noyau précédent (après `take_reboot_log`)
+pub fn previous_log() -> Option<String> {
+    PREVIOUS_LOG.lock().clone()
+}
+
+/// Tables identité du trampoline : premier Gio en pages de 2 Mio
+fn identity_tables() -> Result<u64, &'static str> {
+    use x86_64::structures::paging::PageTableFlags as Flags;
+
+    let mut frames = FRAME_ALLOCATOR.lock();
+    let mut table = || -> Result<*mut u64, &'static str> {
+        let frame = frames.allocate(0).ok_or("kexec: mémoire insuffisante")?;
+        let ptr = frame.as_u64() as *mut u64;
+        unsafe { core::ptr::write_bytes(ptr, 0, 512) };
+        Ok(ptr)
+    };
+    let (pml4, pdpt, pd) = (table()?, table()?, table()?);
+    let flags = (Flags::PRESENT | Flags::WRITABLE).bits();
+    unsafe {
+        *pml4 = pdpt as u64 | flags;
+        *pdpt = pd as u64 | flags;
+        for i in 0..512 {
+            *pd.add(i) = (i as u64 * BLOCK_SIZE) | flags | Flags::HUGE_PAGE.bits();
+        }
+    }
+    Ok(pml4 as u64)
+}
+
+/// Saute dans l'image chargée ; ne revient qu'en cas d'erreur
+pub fn execute(reason: &str) -> Result<(), &'static str> {
+    let image = LOADED.lock().take().ok_or("kexec: aucune image chargée")?;
+
+    if let Err(e) = crate::fs::vfs_sync() {
+        crate::serial_println!("kexec: sync failed: {:?}", e);
+    }
+    let _ = crate::drivers::DRIVER_MANAGER.lock().shutdown_all_drivers();
+
+    // Liste des copies dans un cadre au-dessus de la zone écrasée
+    let list = FRAME_ALLOCATOR.lock().allocate(0).ok_or("kexec: mémoire insuffisante")?.as_u64();
+    unsafe {
+        core::ptr::copy_nonoverlapping(image.copies.as_ptr(), list as *mut Transfer, image.copies.len());
+    }
+    let cr3 = identity_tables()?;
+    let (entry, count) = (image.entry, image.copies.len() as u64);
+    // Les blocs sources appartiennent désormais au trampoline
+    core::mem::forget(image);
+
+    crate::serial_println!("kexec: saut vers {:#x} ({})", entry, reason);
+    write_reboot_log(reason);
+
+    x86_64::instructions::interrupts::disable();
+    unsafe {
+        let start = &kexec_trampoline_start as *const u8;
+        let len = &kexec_trampoline_end as *const u8 as usize - start as usize;
+        core::ptr::copy_nonoverlapping(start, TRAMPOLINE_ADDR as *mut u8, len);
+        let trampoline: extern "C" fn(u64, u64, u64, u64, u64) -> ! = core::mem::transmute(TRAMPOLINE_ADDR);
+        trampoline(list, count, entry, cr3, STACK_TOP);
+    }
+}
+
+extern "C" {
+    static kexec_trampoline_start: u8;
+    static kexec_trampoline_end: u8;
+}
+
+// Trampoline indépendant de sa position, sans pile :
+// rdi = liste des copies, rsi = nombre, rdx = entrée, rcx = CR3, r8 = pile
+global_asm!(
+    ".global kexec_trampoline_start",
+    ".global kexec_trampoline_end",
+    "kexec_trampoline_start:",
+    "    mov cr3, rcx",
+    "    mov r11, rdi",
+    "    mov r12, rsi",
+    "    mov r13, rdx",
+    "    cld",
+    "2:",
+    "    test r12, r12",
+    "    jz 5f",
+    "    mov rdi, [r11]",
+    "    mov rsi, [r11 + 8]",
+    "    mov rcx, [r11 + 16]",
+    "    test rsi, rsi",
+    "    jz 3f",
+    "    rep movsb",
+    "    jmp 4f",
+    "3:",
+    "    xor eax, eax",
+    "    rep stosb",
+    "4:",
+    "    add r11, 24",
+    "    dec r12",
+    "    jmp 2b",
+    "5:",
+    "    mov rsp, r8",
+    "    xor ebp, ebp",
+    // Pas de signature Multiboot2 : le nouveau noyau garde ses valeurs par défaut
+    "    xor eax, eax",
+    "    xor ebx, ebx",
+    "    jmp r13",
+    "kexec_trampoline_end:",
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_reject_bad_images() {
+        assert!(segments(b"not an elf").is_err());
+
+        // En-tête ELF64 minimal sans segment
+        let mut elf = [0u8; 64];
+        elf[..4].copy_from_slice(&[0x7f, b'E', b'L', b'F']);
+        elf[4] = 2;
+        elf[5] = 1;
+        elf[16] = 2; // ET_EXEC
+        elf[18] = 62; // EM_X86_64
+        assert_eq!(segments(&elf).err(), Some("kexec: aucun segment chargeable"));
+        assert!(core::mem::size_of::<RebootLog>() == 4096);
+    }
+}
@@ -1,38 +1,43 @@
-use x86_64::structures::idt::InterruptStackFrame;
 use pc_keyboard::{layouts, DecodedKey, HandleControl, Keyboard, ScancodeSet1, KeyCode};
 use spin::Mutex;
 use lazy_static::lazy_static;
-use crate::vga_buffer::WRITER;
 
 lazy_static! {
+    // Ctrl+lettre donne le caractère de contrôle (Ctrl-R : 0x12), comme un terminal
     static ref KEYBOARD: Mutex<Keyboard<layouts::Us104Key, ScancodeSet1>> =
-        Mutex::new(Keyboard::new(layouts::Us104Key, ScancodeSet1, HandleControl::Ignore));
+        Mutex::new(Keyboard::new(layouts::Us104Key, ScancodeSet1, HandleControl::MapLettersToUnicode));
 }
 
-pub extern "x86-interrupt" fn keyboard_interrupt_handler(_stack_frame: InterruptStackFrame) {
+/// IRQ 1 (enregistrée par `interrupts::init_irqs`)
+pub fn keyboard_interrupt_handler() {
     use x86_64::instructions::port::Port;
 
     let mut port = Port::new(0x60);
     let scancode: u8 = unsafe { port.read() };
+    handle_scancode(scancode);
+}
+
+/// Décode un octet du jeu de scancodes 1 (PS/2 ou clavier USB traduit)
+pub fn handle_scancode(scancode: u8) {
+    x86_64::instructions::interrupts::without_interrupts(|| decode_scancode(scancode));
+}
 
+fn decode_scancode(scancode: u8) {
     let mut keyboard = KEYBOARD.lock();
     if let Ok(Some(key_event)) = keyboard.add_byte(scancode) {
+        crate::tty::key_event(&key_event);
         if let Some(key) = keyboard.process_keyevent(key_event) {
             match key {
-                DecodedKey::Unicode(c) => {
-                    WRITER.lock().write_byte(c as u8);
-                }
                 DecodedKey::RawKey(code) => {
                     match code {
                         // KeyCode::F11 => mini_os::power::reboot(),
                         // KeyCode::F12 => mini_os::power::shutdown(),
-                        _ => {}
+                        _ => crate::tty::input_key(key),
                     }
                 }
+                // Terminal ayant le focus
+                key => crate::tty::input_key(key),
             }
         }
     }
-
-    // EOI pour le LAPIC
-    crate::interrupts::apic::signal_eoi();
 }
@@ -0,0 +1,134 @@
+/// Journal du noyau (klog)
+///
+/// Implémentation de `log::Log` : chaque message est horodaté, écrit
+/// sur COM1, conservé dans un anneau (dmesg) et remis aux transports
+/// enregistrés (netconsole...). Les transports sont appelés hors du
+/// verrou de l'anneau et ne doivent pas bloquer : le journal peut être
+/// alimenté depuis une interruption, d'où ses verrous `SpinLockIrqSave`.
+
+use alloc::collections::VecDeque;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use crate::sync::SpinLockIrqSave;
+
+use crate::time::NSEC_PER_SEC;
+
+/// Lignes conservées pour dmesg
+const RING_LINES: usize = 256;
+/// Transports enregistrables
+const MAX_SINKS: usize = 4;
+
+/// Transport de lignes du journal (sans retour à la ligne final)
+pub type Sink = fn(&str);
+
+/// Dernières lignes du journal
+static RING: SpinLockIrqSave<VecDeque<String>> = SpinLockIrqSave::new(VecDeque::new());
+/// Transports en plus du port série
+static SINKS: SpinLockIrqSave<Vec<Sink>> = SpinLockIrqSave::new(Vec::new());
+
+struct KernelLogger;
+
+static LOGGER: KernelLogger = KernelLogger;
+
+impl Log for KernelLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            write(record.level(), format_args!("{}", record.args()));
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Installe le journal (tas requis) ; les messages moins graves que
+/// `level` sont ignorés
+pub fn init(level: LevelFilter) {
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(level);
+    }
+}
+
+/// Change le niveau de journalisation
+pub fn set_level(level: LevelFilter) {
+    log::set_max_level(level);
+}
+
+/// Ajoute un transport ; false si la table est pleine
+pub fn register_sink(sink: Sink) -> bool {
+    let mut sinks = SINKS.lock();
+    if sinks.len() >= MAX_SINKS {
+        return false;
+    }
+    if !sinks.iter().any(|&s| s as usize == sink as usize) {
+        sinks.push(sink);
+    }
+    true
+}
+
+/// Ligne du journal : « [   12.345678] NIVEAU message »
+fn format_line(timestamp_ns: u64, level: Level, message: core::fmt::Arguments) -> String {
+    format!(
+        "[{:>5}.{:06}] {:<5} {}",
+        timestamp_ns / NSEC_PER_SEC,
+        timestamp_ns % NSEC_PER_SEC / 1_000,
+        level,
+        message
+    )
+}
+
+/// Journalise une ligne, sans passer par les macros de `log`
+pub fn write(level: Level, message: core::fmt::Arguments) {
+    let line = format_line(crate::time::now_ns(), level, message);
+    crate::serial_println!("{}", line);
+
+    {
+        let mut ring = RING.lock();
+        if ring.len() >= RING_LINES {
+            ring.pop_front();
+        }
+        ring.push_back(line.clone());
+    }
+    let sinks = SINKS.lock().clone();
+    for sink in sinks {
+        sink(&line);
+    }
+}
+
+/// Contenu de l'anneau, de la plus ancienne à la plus récente ligne
+pub fn lines() -> Vec<String> {
+    RING.lock().iter().cloned().collect()
+}
+
+/// Parcourt l'anneau sans attendre son verrou (vidage sur panique) ;
+/// faux si le verrou est tenu
+pub fn try_for_each_line(mut f: impl FnMut(&str)) -> bool {
+    match RING.try_lock() {
+        Some(ring) => {
+            ring.iter().for_each(|line| f(line));
+            true
+        }
+        None => false,
+    }
+}
+
+/// Vide l'anneau (dmesg -c)
+pub fn clear() {
+    RING.lock().clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_format_line() {
+        let line = format_line(12 * NSEC_PER_SEC + 345_678_000, Level::Warn, format_args!("lien {}", "actif"));
+        assert_eq!(line, "[   12.345678] WARN  lien actif");
+    }
+}
@@ -22,15 +22,21 @@ extern crate alloc;
 
 // Modules du noyau
 pub mod memory;
+pub mod multiboot2;
+pub mod cmdline;
+pub mod gdt;
 pub mod interrupts;
 pub mod keyboard;
+pub mod mouse;
 pub mod power;
 pub mod process;
 pub mod scheduler;
+pub mod sync;
 pub mod syscall;
 pub mod fs;
-#[cfg(feature = "smp")]
 pub mod acpi;
+pub mod cpuid;
+pub mod percpu;
 #[cfg(feature = "smp")]
 pub mod smp;
 pub mod fat32;
@@ -39,13 +45,28 @@ pub mod ext3;
 pub mod ext4;
 pub mod fs_manager;  // Gestionnaire EXT4
 pub mod gpt;
+pub mod fsck;
 pub mod ring3;
 pub mod ring3_memory;
 pub mod ring3_example;
 pub mod vga_buffer;  // ← Ajouté pour les drivers
+pub mod tty;
 pub mod drivers;
 pub mod net;
 pub mod ipc;
+pub mod gdbstub;
+pub mod vdso;
+pub mod time;
+pub mod klog;
+pub mod initcall;
+pub mod kexec;
+pub mod kdump;
+pub mod fault_inject;
+pub mod perf;
+pub mod trace;
+pub mod random;
+pub mod workqueue;
+pub mod watchdog;
 // pub mod vm; // Disabled - depends on Limine
 
 // Modules pour les tests QEMU
@@ -81,17 +102,20 @@ pub fn test_runner(tests: &[&dyn Fn()]) {
         test();
     }
     
-    // Sortie en cas de succès
-    // Ici, vous pourriez éteindre la machine virtuelle ou redémarrer
-    loop {}
+    // Sortie en cas de succès : isa-debug-exit termine QEMU
+    test_runner::exit_qemu(test_runner::QemuExitCode::Success);
 }
 
 // Point d'entrée pour les tests
+#[cfg(test)]
+crate::test_kernel_entry!();
+
 #[cfg(test)]
 #[no_mangle]
-pub extern "C" fn _start() -> ! {
+pub extern "C" fn test_kernel_main(multiboot_magic: u32, multiboot_info: u32) -> ! {
     // Note: L'initialisation complète du kernel n'est pas nécessaire pour tous les tests
-    // Les tests qui nécessitent du hardware peuvent être marqués avec #[ignore]
+    // Tas, cadres, IDT et horloge suffisent (allocations, délais des tests)
+    test_runner::init(multiboot_magic, multiboot_info);
     
     serial_println!("RustOS Test Suite");
     serial_println!("=================\n");
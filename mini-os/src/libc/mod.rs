@@ -1,7 +1,11 @@
 pub mod stdio;
 pub mod stdlib;
 pub mod string;
+pub mod umalloc;
+pub mod vdso;
 
 pub use stdio::*;
 pub use stdlib::*;
 pub use string::*;
+pub use umalloc::{brk, sbrk, umalloc, ucalloc, ufree};
+pub use vdso::{vdso_getpid, vdso_ticks, vdso_uptime_ms, vdso_time};
@@ -45,23 +45,19 @@ pub fn free(ptr: *mut u8, size: usize) {
     }
 }
 
-/// Retourne un nombre aléatoire
+/// Valeur maximale retournée par `rand`
+pub const RAND_MAX: u32 = 32767;
+
+/// Retourne un nombre aléatoire dans [0, RAND_MAX]
+/// Tiré du générateur du noyau (ChaCha20), et non plus d'un LCG
 pub fn rand() -> u32 {
-    // Générateur linéaire congruentiel simple
-    static mut SEED: u32 = 1;
-    
-    unsafe {
-        SEED = SEED.wrapping_mul(1103515245).wrapping_add(12345);
-        (SEED / 65536) % 32768
-    }
+    mini_os::random::get_u32() % (RAND_MAX + 1)
 }
 
-/// Initialise le générateur de nombres aléatoires
+/// Mélange `seed` à la réserve d'entropie, sans la créditer
+/// La suite de `rand` n'est donc pas reproductible
 pub fn srand(seed: u32) {
-    unsafe {
-        static mut SEED: u32 = 1;
-        SEED = seed;
-    }
+    mini_os::random::add_device_randomness(&seed.to_le_bytes());
 }
 
 /// Retourne la valeur absolue d'un entier
@@ -168,6 +164,14 @@ mod tests {
         free(ptr, 1000);
     }
 
+    #[test_case]
+    fn test_rand() {
+        srand(42);
+        let values: alloc::vec::Vec<u32> = (0..32).map(|_| rand()).collect();
+        assert!(values.iter().all(|&v| v <= RAND_MAX));
+        assert!(values.iter().any(|&v| v != values[0]));
+    }
+
     #[test_case]
     fn test_abs() {
         assert_eq!(abs(-5), 5);
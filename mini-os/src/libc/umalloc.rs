@@ -0,0 +1,315 @@
+/// malloc utilisateur (ring 3) au-dessus de brk/sbrk
+///
+/// Chaque bloc est précédé d'un en-tête (taille utile, libre ou non).
+/// Les blocs libres sont réutilisés au premier trouvé et découpés, les
+/// voisins libres fusionnés à la libération. Le tas grandit par `sbrk`
+/// par tranches de `GROW_CHUNK` octets et rend sa fin libre au noyau
+/// quand elle dépasse cette taille.
+///
+/// L'allocateur suppose être le seul utilisateur du program break.
+
+use core::mem::size_of;
+use core::ptr;
+use spin::Mutex;
+use crate::syscall::SyscallNumber;
+
+/// Alignement des blocs retournés
+const ALIGN: usize = 16;
+/// Granularité de croissance du tas
+const GROW_CHUNK: usize = 16 * 1024;
+
+/// En-tête de bloc
+#[repr(C, align(16))]
+struct BlockHeader {
+    /// Octets utiles après l'en-tête (multiple de ALIGN)
+    size: usize,
+    free: bool,
+}
+
+const HEADER: usize = size_of::<BlockHeader>();
+
+const fn align_up(value: usize, align: usize) -> usize {
+    (value + align - 1) & !(align - 1)
+}
+
+/// Source du program break
+pub trait BreakSource {
+    /// Déplace le break de `increment` octets ; retourne l'ancien break
+    fn sbrk(&mut self, increment: isize) -> Option<usize>;
+}
+
+/// Appel système à un argument : rax = numéro, rdi = argument
+///
+/// Une valeur de retour négative signale une erreur.
+pub unsafe fn syscall1(num: u64, arg0: u64) -> u64 {
+    let ret: u64;
+    core::arch::asm!(
+        "syscall",
+        inout("rax") num => ret,
+        in("rdi") arg0,
+        out("rcx") _,
+        out("r11") _,
+        options(nostack),
+    );
+    ret
+}
+
+/// brk(2) : fixe le break (0 = lecture) et retourne le break effectif
+pub fn brk(addr: usize) -> usize {
+    unsafe { syscall1(SyscallNumber::Brk as u64, addr as u64) as usize }
+}
+
+/// sbrk(2) : retourne l'ancien break, None si le noyau refuse
+pub fn sbrk(increment: isize) -> Option<usize> {
+    let ret = unsafe { syscall1(SyscallNumber::Sbrk as u64, increment as u64) };
+    if (ret as i64) < 0 { None } else { Some(ret as usize) }
+}
+
+/// Program break du processus courant, via les appels système
+pub struct SyscallBreak;
+
+impl BreakSource for SyscallBreak {
+    fn sbrk(&mut self, increment: isize) -> Option<usize> {
+        sbrk(increment)
+    }
+}
+
+/// Tas utilisateur géré par le program break
+pub struct BrkHeap<S: BreakSource> {
+    source: S,
+    /// Premier en-tête (0 tant que le tas est vide)
+    start: usize,
+    /// Fin du tas (= break)
+    end: usize,
+}
+
+impl<S: BreakSource> BrkHeap<S> {
+    pub const fn new(source: S) -> Self {
+        Self { source, start: 0, end: 0 }
+    }
+
+    /// Alloue `size` octets alignés sur 16 ; null si le tas ne peut grandir
+    pub fn malloc(&mut self, size: usize) -> *mut u8 {
+        if size == 0 {
+            return ptr::null_mut();
+        }
+        let size = align_up(size, ALIGN);
+
+        if let Some(block) = self.find_free(size) {
+            return unsafe { self.take(block, size) };
+        }
+        match self.grow(HEADER + size) {
+            Some(block) => unsafe { self.take(block, size) },
+            None => ptr::null_mut(),
+        }
+    }
+
+    /// Libère un bloc retourné par `malloc` (null ignoré)
+    pub fn free(&mut self, p: *mut u8) {
+        if p.is_null() {
+            return;
+        }
+        let block = p as usize - HEADER;
+        debug_assert!(block >= self.start && block < self.end, "free hors du tas");
+        unsafe {
+            (*header(block)).free = true;
+        }
+        self.coalesce();
+        self.trim();
+    }
+
+    /// Octets actuellement obtenus du noyau
+    pub fn heap_size(&self) -> usize {
+        self.end - self.start
+    }
+
+    fn find_free(&self, size: usize) -> Option<usize> {
+        self.blocks().find(|&b| unsafe { (*header(b)).free && (*header(b)).size >= size })
+    }
+
+    /// Marque le bloc occupé, en découpant le reste s'il est utilisable
+    unsafe fn take(&mut self, block: usize, size: usize) -> *mut u8 {
+        let h = header(block);
+        if (*h).size >= size + HEADER + ALIGN {
+            let rest = block + HEADER + size;
+            (*header(rest)).size = (*h).size - size - HEADER;
+            (*header(rest)).free = true;
+            (*h).size = size;
+        }
+        (*h).free = false;
+        (block + HEADER) as *mut u8
+    }
+
+    /// Étend le tas d'au moins `bytes` octets ; retourne le bloc libre créé
+    fn grow(&mut self, bytes: usize) -> Option<usize> {
+        if self.start == 0 {
+            // Premier appel : aligner le début du tas
+            let brk = self.source.sbrk(0)?;
+            let pad = align_up(brk, ALIGN) - brk;
+            if pad != 0 {
+                self.source.sbrk(pad as isize)?;
+            }
+            self.start = brk + pad;
+            self.end = self.start;
+        }
+
+        let grow = align_up(bytes, GROW_CHUNK);
+        let old = self.source.sbrk(grow as isize)?;
+        debug_assert_eq!(old, self.end, "program break déplacé hors de l'allocateur");
+
+        let block = self.end;
+        unsafe {
+            (*header(block)).size = grow - HEADER;
+            (*header(block)).free = true;
+        }
+        self.end += grow;
+        self.coalesce();
+        self.find_free(bytes - HEADER)
+    }
+
+    /// Fusionne les blocs libres adjacents
+    fn coalesce(&mut self) {
+        let mut block = self.start;
+        while block < self.end {
+            unsafe {
+                let h = header(block);
+                let next = block + HEADER + (*h).size;
+                if (*h).free && next < self.end && (*header(next)).free {
+                    (*h).size += HEADER + (*header(next)).size;
+                    continue;
+                }
+                block = next;
+            }
+        }
+    }
+
+    /// Rend au noyau un dernier bloc libre de plus de GROW_CHUNK octets
+    fn trim(&mut self) {
+        let last = match self.blocks().last() {
+            Some(last) => last,
+            None => return,
+        };
+        unsafe {
+            let h = header(last);
+            if !(*h).free || (*h).size < GROW_CHUNK {
+                return;
+            }
+            // Garder un bloc libre minimal, rendre des tranches entières
+            let release = ((*h).size - ALIGN) & !(GROW_CHUNK - 1);
+            if release == 0 || self.source.sbrk(-(release as isize)).is_none() {
+                return;
+            }
+            (*h).size -= release;
+            self.end -= release;
+        }
+    }
+
+    fn blocks(&self) -> impl Iterator<Item = usize> + '_ {
+        let end = self.end;
+        let mut block = self.start;
+        core::iter::from_fn(move || {
+            if block >= end {
+                return None;
+            }
+            let current = block;
+            block += HEADER + unsafe { (*header(current)).size };
+            Some(current)
+        })
+    }
+}
+
+fn header(addr: usize) -> *mut BlockHeader {
+    addr as *mut BlockHeader
+}
+
+/// Tas du processus courant
+pub static USER_HEAP: Mutex<BrkHeap<SyscallBreak>> = Mutex::new(BrkHeap::new(SyscallBreak));
+
+/// malloc côté ring 3
+pub fn umalloc(size: usize) -> *mut u8 {
+    USER_HEAP.lock().malloc(size)
+}
+
+/// calloc côté ring 3 (les pages neuves du tas sont déjà à zéro, pas les blocs recyclés)
+pub fn ucalloc(count: usize, size: usize) -> *mut u8 {
+    let total = match count.checked_mul(size) {
+        Some(total) => total,
+        None => return ptr::null_mut(),
+    };
+    let p = umalloc(total);
+    if !p.is_null() {
+        unsafe { ptr::write_bytes(p, 0, total) };
+    }
+    p
+}
+
+/// free côté ring 3
+pub fn ufree(p: *mut u8) {
+    USER_HEAP.lock().free(p)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Break simulé sur un tampon statique
+    struct ArenaBreak {
+        base: usize,
+        brk: usize,
+        limit: usize,
+    }
+
+    impl BreakSource for ArenaBreak {
+        fn sbrk(&mut self, increment: isize) -> Option<usize> {
+            let old = self.brk;
+            let new = old.checked_add_signed(increment)?;
+            if new < self.base || new > self.limit {
+                return None;
+            }
+            self.brk = new;
+            Some(old)
+        }
+    }
+
+    #[repr(align(16))]
+    struct Arena([u8; 128 * 1024]);
+    static mut ARENA: Arena = Arena([0; 128 * 1024]);
+
+    fn arena_heap() -> BrkHeap<ArenaBreak> {
+        let base = unsafe { core::ptr::addr_of_mut!(ARENA) as usize };
+        BrkHeap::new(ArenaBreak { base, brk: base + 8, limit: base + 128 * 1024 })
+    }
+
+    #[test_case]
+    fn test_brk_heap_reuse_and_split() {
+        let mut heap = arena_heap();
+        let a = heap.malloc(100);
+        let b = heap.malloc(200);
+        assert!(!a.is_null() && !b.is_null());
+        assert_eq!(a as usize % ALIGN, 0);
+        assert!(b as usize >= a as usize + 112);
+        assert_eq!(heap.heap_size(), GROW_CHUNK);
+
+        heap.free(a);
+        let c = heap.malloc(50);
+        assert_eq!(c, a);
+        heap.free(b);
+        heap.free(c);
+        assert_eq!(heap.blocks().count(), 1);
+    }
+
+    #[test_case]
+    fn test_brk_heap_grow_and_trim() {
+        let mut heap = arena_heap();
+        let big = heap.malloc(3 * GROW_CHUNK);
+        assert!(!big.is_null());
+        assert_eq!(heap.heap_size(), 4 * GROW_CHUNK);
+        unsafe { ptr::write_bytes(big, 0xAB, 3 * GROW_CHUNK) };
+
+        heap.free(big);
+        assert!(heap.heap_size() <= GROW_CHUNK);
+
+        // Au-delà de l'arène : échec propre
+        assert!(heap.malloc(256 * 1024).is_null());
+    }
+}
@@ -0,0 +1,37 @@
+/// Lectures sans appel système via la page vDSO (ring 3)
+///
+/// La page est projetée en lecture seule par le noyau à `VDSO_ADDR` ;
+/// chaque lecture suit le protocole de séquence de `VdsoData::read`.
+
+use core::sync::atomic::Ordering;
+use crate::vdso::{VdsoData, VDSO_ADDR};
+
+fn vdso() -> &'static VdsoData {
+    unsafe { &*(VDSO_ADDR as *const VdsoData) }
+}
+
+/// getpid() sans entrer dans le noyau
+pub fn vdso_getpid() -> u64 {
+    vdso().read(|d| d.pid.load(Ordering::Relaxed))
+}
+
+/// Ticks timer depuis le démarrage
+pub fn vdso_ticks() -> u64 {
+    vdso().read(|d| d.ticks.load(Ordering::Relaxed))
+}
+
+/// Temps écoulé depuis le démarrage, en millisecondes
+pub fn vdso_uptime_ms() -> u64 {
+    vdso().read(|d| {
+        let hz = d.tick_hz.load(Ordering::Relaxed).max(1);
+        d.ticks.load(Ordering::Relaxed) * 1000 / hz
+    })
+}
+
+/// Heure courante en secondes Unix (0 + uptime si l'heure de démarrage est inconnue)
+pub fn vdso_time() -> u64 {
+    vdso().read(|d| {
+        let hz = d.tick_hz.load(Ordering::Relaxed).max(1);
+        d.boot_time.load(Ordering::Relaxed) + d.ticks.load(Ordering::Relaxed) / hz
+    })
+}
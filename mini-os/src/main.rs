@@ -28,17 +28,38 @@ mod multiboot2_header {
         "    .long 0",
         "    .long multiboot_header_end - multiboot_header_start",
         "    .long -(0xE85250D6 + 0 + (multiboot_header_end - multiboot_header_start))",
+        // Tag framebuffer (optionnel) : 1024×768×32 de préférence
+        "    .align 8",
+        "    .short 5",
+        "    .short 1",
+        "    .long 20",
+        "    .long 1024",
+        "    .long 768",
+        "    .long 32",
+        "    .align 8",
         "    .short 0",
         "    .short 0",
         "    .long 8",
         "multiboot_header_end:",
     );
+
+    // Entrée : le chargeur laisse la signature dans EAX et l'adresse des
+    // informations dans EBX ; kernel_main les reçoit en arguments
+    global_asm!(
+        ".section .text._start, \"ax\"",
+        ".global _start",
+        "_start:",
+        "    mov edi, eax",
+        "    mov esi, ebx",
+        "    call kernel_main",
+        "    ud2",
+    );
 }
 
-mod vga_buffer;
-mod interrupts;
-mod keyboard;
-mod mouse;
+// mod vga_buffer; // Use from lib
+// mod interrupts; // Use from lib
+// mod keyboard; // Use from lib
+// mod mouse; // Use from lib
 // mod memory; // Use from lib
 mod hardware;
 mod pci;
@@ -47,12 +68,12 @@ mod storage;
 // mod process; // Use from lib
 // mod scheduler; // Use from lib
 // mod syscall; // Use from lib
-mod sync;
+// mod sync; // Use from lib
 // mod fs; // Use from lib
 mod shell;
 mod terminal;
 mod libc;
-mod drivers;
+// mod drivers; // Use from lib
 // mod network;
 mod device_manager;
 
@@ -70,6 +91,15 @@ use mini_os::process::{self, ProcessManager, test_process};
 use mini_os::scheduler::{self, Scheduler};
 use mini_os::syscall;
 use mini_os::fs;
+use mini_os::gdbstub;
+use mini_os::gdt;
+use mini_os::ring3;
+use mini_os::vdso;
+use mini_os::initcall::{self, InitLevel};
+use mini_os::drivers;
+use mini_os::vga_buffer;
+use mini_os::tty;
+use mini_os::interrupts;
 
 // Multiboot2 - pas de requests nécessaires
 
@@ -78,9 +108,12 @@ fn alloc_error_handler(layout: core::alloc::Layout) -> ! {
     panic!("allocation error: {:?}", layout);
 }
 
-/// Point d'entrée du noyau (Multiboot2)
+/// Console choisie au démarrage (`ConsoleMode::Text` pour rester en 80×25)
+const BOOT_CONSOLE: vga_buffer::ConsoleMode = vga_buffer::ConsoleMode::Framebuffer { width: 1024, height: 768 };
+
+/// Point d'entrée du noyau (Multiboot2), appelé par `_start`
 #[no_mangle]
-extern "C" fn _start() -> ! {
+extern "C" fn kernel_main(multiboot_magic: u32, multiboot_info: u32) -> ! {
     // Initialiser l'écran
     WRITER.lock().write_string("Mini OS Rust démarré (Multiboot2 + GRUB)!\n");
     
@@ -88,19 +121,113 @@ extern "C" fn _start() -> ! {
     hardware::detect_cpu();
     hardware::scan_pci();
 
-    // Initialiser le tas (heap)
+    // Ligne de commande du chargeur, lue sur place (pas encore de tas)
+    if multiboot_magic == mini_os::multiboot2::BOOTLOADER_MAGIC && multiboot_info != 0 {
+        if let Some(line) = unsafe { mini_os::multiboot2::find_cmdline(multiboot_info as usize) } {
+            mini_os::cmdline::init(line);
+        }
+    }
+    let options = mini_os::cmdline::options();
+    // Vidage sur panique (`kdump`), possible dès maintenant : il n'alloue rien
+    if let Err(e) = mini_os::kdump::set_enabled(options.kdump.is_some()) {
+        WRITER.lock().write_string(&format!("{}\n", e));
+    }
+
+    // Initialiser le tas (heap), `heap=` sur la ligne de commande
     const HEAP_START: usize = 0x_4444_0000;
-    const HEAP_SIZE: usize = 100 * 1024; // 100 KB
-    
+
     unsafe {
-        mini_os::memory::HYBRID_ALLOCATOR.init(HEAP_START, HEAP_SIZE);
+        mini_os::memory::HYBRID_ALLOCATOR.init(HEAP_START, options.heap_size);
     }
     
     WRITER.lock().write_string("Tas initialisé (Hybrid: SLAB + Buddy)\n");
 
+    // Informations du chargeur : carte mémoire, modules, framebuffer, cmdline
+    if let Err(e) = mini_os::multiboot2::init(multiboot_magic, multiboot_info as u64) {
+        WRITER.lock().write_string(&format!("{}\n", e));
+    }
+    hardware::detect_memory();
+
+    // Journal du noyau (macros de `log`) : COM1, dmesg, netconsole
+    mini_os::klog::init(options.log_level);
+    if let Some(ticks) = options.sched_slice {
+        mini_os::scheduler::set_slice_ticks(ticks);
+    }
+    if let Some(mask) = options.trace_events {
+        mini_os::trace::set_mask(mask);
+    }
+    mini_os::process::aslr::set_enabled(options.randomize_va);
+    mini_os::memory::uaccess::configure(options.smep, options.smap);
+    mini_os::memory::uaccess::init_cpu();
+
+    // Journal laissé par un noyau précédent (kexec)
+    if let Some(previous) = mini_os::kexec::take_reboot_log() {
+        WRITER.lock().write_string("Redémarrage kexec, journal précédent :\n");
+        WRITER.lock().write_string(&previous);
+    }
+    // Vidage laissé par une panique du démarrage précédent (kdump)
+    if let Some(dump) = mini_os::kdump::take_crash_dump() {
+        let panic_line = dump.lines().find(|l| l.starts_with("Panique")).unwrap_or("");
+        WRITER.lock().write_string(&format!("Vidage après panique trouvé ({} octets) : {}\n", dump.len(), panic_line));
+    }
+
+    // Console : framebuffer du chargeur, sinon VBE si l'adaptateur le
+    // permet, sinon texte VGA
+    let boot_fb = mini_os::multiboot2::framebuffer().and_then(|fb| fb.mode_info());
+    if boot_fb.map_or(false, vga_buffer::use_framebuffer) {
+        WRITER.lock().write_string("Console sur le framebuffer du chargeur\n");
+    } else if vga_buffer::select_console(BOOT_CONSOLE) != vga_buffer::ConsoleMode::Text {
+        WRITER.lock().write_string("Console framebuffer active\n");
+    }
+
+    // Allocateur de cadres physiques : carte Multiboot2 (modules et tas
+    // exclus), sinon la RAM par défaut de QEMU (128 Mio)
+    let mut regions = mini_os::multiboot2::usable_regions();
+    if regions.is_empty() {
+        regions.push(mini_os::memory::MemoryRegion::usable(0, 128 * 1024 * 1024));
+    }
+    // Le tas du noyau est en identité : ses cadres ne sont pas à distribuer
+    let heap = (HEAP_START as u64, (HEAP_START + options.heap_size) as u64);
+    mini_os::memory::frame::init(&regions, &[heap]);
+    mini_os::memory::vm::init_vm(x86_64::VirtAddr::new(0));
+    WRITER.lock().write_string(&format!(
+        "Cadres physiques: {} libres\n",
+        mini_os::memory::FRAME_ALLOCATOR.lock().free_frames()
+    ));
+
+    // GDT/TSS propres au noyau (segments ring 3) et entrée SYSCALL
+    gdt::init();
+    syscall::entry::init();
+    WRITER.lock().write_string("GDT, TSS et SYSCALL initialisés\n");
+    if let Err(e) = vdso::init() {
+        WRITER.lock().write_string(&format!("vDSO indisponible: {}\n", e));
+    }
+
     // Initialiser les interruptions
     interrupts::init_idt();
     WRITER.lock().write_string("IDT initialisée\n");
+
+    // PIC masqués, IOAPIC programmés d'après la MADT, horloge et clavier
+    interrupts::init_irqs();
+    WRITER.lock().write_string(if interrupts::irq::using_io_apic() {
+        "IRQ routées par l'IOAPIC\n"
+    } else {
+        "IRQ routées par les PIC 8259\n"
+    });
+
+    // Horodatage haute résolution (TSC invariant, sinon ticks du timer)
+    mini_os::time::init();
+    let clock = mini_os::time::clocksource();
+    WRITER.lock().write_string(&format!("Horloge: {} ({} Hz)\n", clock.name(), clock.frequency()));
+
+    // Réserve d'entropie : RDSEED/RDRAND, gigue du TSC à défaut ;
+    // puis canari de pile des fonctions protégées
+    mini_os::random::init();
+    mini_os::process::kstack::init_stack_guard();
+
+    // Stub GDB sur COM2 (`target remote :1234` avec `-serial tcp::1234,server`)
+    gdbstub::init();
+    WRITER.lock().write_string("Stub GDB prêt sur COM2\n");
     
     // Activer les interruptions
     unsafe { x86_64::instructions::interrupts::enable(); }
@@ -113,11 +240,28 @@ extern "C" fn _start() -> ! {
             WRITER.lock().write_string("VFS initialisé avec succès\n");
             // Créer quelques fichiers de test
             let _ = mini_os::fs::vfs_mkdir("/home");
+            let _ = mini_os::fs::vfs_mkdir("/etc");
             let _ = mini_os::fs::vfs_write_file("/home/README.txt", b"Bienvenue sur RustOS!\nCe fichier est stocke en RAM.\n");
+
+            // Espace utilisateur initial fourni par le chargeur (initramfs)
+            match mini_os::fs::initramfs::load_boot_modules() {
+                Ok(stats) if stats.files > 0 => WRITER.lock().write_string(&format!(
+                    "Initramfs: {} fichiers, {} répertoires, {} octets\n",
+                    stats.files, stats.directories, stats.bytes
+                )),
+                Ok(_) => {}
+                Err(e) => WRITER.lock().write_string(&format!("Initramfs invalide: {}\n", e)),
+            }
         },
         Err(e) => WRITER.lock().write_string(&format!("Erreur initialisation VFS: {:?}\n", e)),
     }
 
+    // Sous-systèmes et drivers enregistrés par `initcall!` (terminaux,
+    // souris, audio, AHCI, NVMe, e1000, xHCI...)
+    for level in [InitLevel::Early, InitLevel::Core, InitLevel::Driver] {
+        initcall::run(level);
+    }
+
     // Initialiser le driver disque ATA
     WRITER.lock().write_string("Initialisation du driver disque ATA...\n");
     let mut disk = mini_os::drivers::disk::DiskDriver::new("sda", true); // Primary Master
@@ -129,6 +273,19 @@ extern "C" fn _start() -> ! {
     match disk.init() {
         Ok(_) => {
             WRITER.lock().write_string("Disque ATA initialisé.\n");
+
+            // Exposer le disque brut dans /dev (handle séparé : `disk` part dans EXT2)
+            let mut raw_disk = mini_os::drivers::disk::DiskDriver::new("sda", true);
+            if raw_disk.init().is_ok() {
+                let (sector_size, sectors) = (raw_disk.get_sector_size() as usize, raw_disk.get_sector_count());
+                device_manager::DEVICE_MANAGER.lock().import_disk_info("sda");
+                let node = mini_os::fs::devfs::DiskDevice::new(raw_disk, sector_size, sectors);
+                let _ = mini_os::fs::devfs::register_device(
+                    "sda",
+                    mini_os::fs::DeviceKind::Block,
+                    Arc::new(Mutex::new(node)),
+                );
+            }
             
             // Tentative de parsing GPT
             match parse_gpt(&mut disk) {
@@ -143,22 +300,18 @@ extern "C" fn _start() -> ! {
                     if let Some(first_partition) = partitions.first() {
                          WRITER.lock().write_string("Tentative de montage de la premiere partition (EXT2)...\n");
                          
-                         // Initialiser EXT2 sur cette partition
-                         // Note: EXT2::new prend possession du disque
-                         match mini_os::ext2::Ext2::new(disk) {
+                         // Initialiser EXT2 sur cette partition (le disque lui est confié)
+                         match mini_os::ext2::Ext2::open(disk, first_partition.start_lba) {
                             Ok(fs) => {
-                                WRITER.lock().write_string("Système de fichiers EXT2 initialisé avec succès!\n");
-                                
-                                // Lister le répertoire racine
-                                // Note: EXT2 n'a pas de méthode read_dir publique dans l'implémentation actuelle
-                                // On affiche juste le succès de l'initialisation
-                                WRITER.lock().write_string("EXT2 monté. Opérations de fichiers disponibles.\n");
+                                let _ = mini_os::fs::vfs_mkdir("/mnt");
+                                let ext2 = Arc::new(mini_os::ext2::Ext2FileSystem::new(fs));
+                                match mini_os::fs::mount_fs("/mnt", ext2, mini_os::fs::MountFlags::new(0)) {
+                                    Ok(()) => WRITER.lock().write_string("EXT2 monté sur /mnt.\n"),
+                                    Err(e) => WRITER.lock().write_string(&format!("Echec montage EXT2: {:?}\n", e)),
+                                }
                             },
                             Err(e) => WRITER.lock().write_string(&format!("Echec init EXT2: {:?}\n", e)),
                          }
-                         
-                         // Note: `disk` est déplacé dans `fs`, on ne peut plus l'utiliser ici. 
-                         // C'est attendu pour ce test.
                     } else {
                         WRITER.lock().write_string("Aucune partition valide trouvée.\n");
                     }
@@ -169,6 +322,40 @@ extern "C" fn _start() -> ! {
         Err(e) => WRITER.lock().write_string(&format!("Erreur init Disque: {:?}\n", e)),
     }
 
+    // Racine : `root=` sur la ligne de commande, sinon NVMe si présent
+    let root_name = options.root.unwrap_or("nvme0n1");
+    match drivers::block::get(root_name) {
+        Some(root) => {
+            let _ = drivers::block::BLOCK_DEVICES.lock().set_root(root_name);
+            let _ = mini_os::fs_manager::init_ext4();
+            match mini_os::fs_manager::mount_ext4_partition(root, "/") {
+                Ok(_) => WRITER.lock().write_string(&format!("Racine EXT4 montée depuis {}\n", root_name)),
+                Err(e) => WRITER.lock().write_string(&format!("Echec montage racine {}: {:?}\n", root_name, e)),
+            }
+        }
+        None if options.root.is_some() => {
+            WRITER.lock().write_string(&format!("root={}: périphérique introuvable\n", root_name));
+        }
+        None => {}
+    }
+
+    // Vidage précédent enregistré sur la racine (/var/crash par défaut)
+    if let Some(dir) = options.kdump {
+        match mini_os::kdump::save_crash_dump(dir) {
+            Ok(Some(path)) => WRITER.lock().write_string(&format!("Vidage après panique enregistré dans {}\n", path)),
+            Ok(None) => {}
+            Err(e) => WRITER.lock().write_string(&format!("{}\n", e)),
+        }
+    }
+
+    // Injection de fautes, armée une fois la racine montée
+    if let Some(spec) = options.fault_inject {
+        match mini_os::fault_inject::configure(spec) {
+            Ok(()) => WRITER.lock().write_string(&format!("Injection de fautes: {}\n", spec)),
+            Err(e) => WRITER.lock().write_string(&format!("fault_inject: {}\n", e)),
+        }
+    }
+
     // Initialiser le gestionnaire de processus
     // Note: Utilisation de l'instance globale
     {
@@ -176,38 +363,84 @@ extern "C" fn _start() -> ! {
         
         // Créer le processus initial
         match process_manager.create_process("init", init_process, process::ProcessPriority::Normal) {
-            Ok(pid) => WRITER.lock().write_string(&format!("Processus init créé avec PID: {}\n", pid)),
+            Ok(pid) => {
+                // Environnement de départ, hérité par les programmes lancés
+                if let Some(init) = process::get_process_by_pid(pid) {
+                    init.lock().env = init_args("init").envp;
+                }
+                WRITER.lock().write_string(&format!("Processus init créé avec PID: {}\n", pid))
+            }
             Err(e) => WRITER.lock().write_string(&format!("Erreur création processus: {}\n", e)),
         }
     }
     
-    WRITER.lock().write_string("Planificateur initialisé (Global)\n");
-    
-    // Initialiser le gestionnaire de périphériques
-    WRITER.lock().write_string("Initialisation du gestionnaire de périphériques...\n");
-    let mut device_manager = device_manager::DEVICE_MANAGER.lock();
-    
-    // Détecter tous les périphériques
-    match device_manager.detect_all_devices() {
-        Ok(_) => WRITER.lock().write_string("Détection des périphériques complétée\n"),
-        Err(e) => WRITER.lock().write_string(&format!("Erreur détection périphériques: {:?}\n", e)),
+    // Premier programme utilisateur (`init=`, /bin/init par défaut)
+    let mut user_init = false;
+    match mini_os::fs::vfs_read_file(options.init) {
+        Ok(image) => match process::PROCESS_MANAGER.lock().create_process_from_elf(options.init, &image, &init_args(options.init)) {
+            Ok(pid) => {
+                user_init = true;
+                WRITER.lock().write_string(&format!("{} lancé (PID {})\n", options.init, pid))
+            }
+            Err(e) => WRITER.lock().write_string(&format!("{}: {}\n", options.init, e)),
+        },
+        Err(_) if options.init_given => {
+            WRITER.lock().write_string(&format!("init={}: fichier introuvable\n", options.init));
+        }
+        Err(_) => {}
+    }
+
+    // Sans programme init, le shell du noyau lit le clavier de tty1
+    if !user_init {
+        match process::PROCESS_MANAGER.lock().create_process("sh", shell::interactive, process::ProcessPriority::Normal) {
+            Ok(pid) => WRITER.lock().write_string(&format!("Shell lancé sur tty1 (PID {})\n", pid)),
+            Err(e) => WRITER.lock().write_string(&format!("Erreur création du shell: {}\n", e)),
+        }
+    }
+
+    // Programme de test en ring 3, enfant du processus init (PID 1)
+    let hello_args = process::exec::ExecArgs::new(vec!["ring3_hello".to_string()], Vec::new());
+    match ring3::spawn_user_program(1, "ring3_hello", mini_os::ring3_example::hello_image(), &hello_args) {
+        Ok(status) => WRITER.lock().write_string(&format!("Programme ring 3 terminé (code {})\n", status)),
+        Err(e) => WRITER.lock().write_string(&format!("Echec du programme ring 3: {}\n", e)),
     }
     
-    // Initialiser tous les périphériques
-    match device_manager.init_all_devices() {
-        Ok(_) => WRITER.lock().write_string("Initialisation des périphériques complétée\n"),
-        Err(e) => WRITER.lock().write_string(&format!("Erreur initialisation périphériques: {:?}\n", e)),
+    // Kthread chargé de libérer les threads terminés
+    mini_os::process::reaper::spawn_reaper();
+    // Chien de garde matériel (`watchdog=`), nourri par kwatchdog, et
+    // détecteur de blocages (`watchdog_thresh=`)
+    if let Some(kind) = options.watchdog {
+        match mini_os::watchdog::start_hardware(kind) {
+            Ok((kind, timeout)) => WRITER.lock().write_string(&format!("Chien de garde {} armé ({} s)\n", kind.name(), timeout)),
+            Err(e) => WRITER.lock().write_string(&format!("{}\n", e)),
+        }
     }
+    mini_os::watchdog::spawn_watchdog(options.watchdog_thresh);
+    // Kthreads des files de travail (bottom halves, travail différé)
+    mini_os::workqueue::spawn_workers();
     
-    // Afficher les périphériques détectés
-    let devices = device_manager.list_devices();
-    WRITER.lock().write_string(&format!("Périphériques détectés: {}\n", devices.len()));
+    WRITER.lock().write_string("Planificateur initialisé (Global)\n");
     
-    drop(device_manager); // Libérer le verrou
+    // Initcalls tardifs (gestionnaire de périphériques...)
+    initcall::run(InitLevel::Late);
     
     // ACPI & SMP Init (optional, disabled by default)
     #[cfg(feature = "smp")]
-    mini_os::smp::init();
+    if !options.nosmp {
+        mini_os::smp::init();
+    }
+
+    // Le shell occupe tty1 ; les messages du noyau restent sur tty2 (Alt+F2)
+    tty::switch_to(tty::SHELL_VT);
+    tty::write(tty::SHELL_VT, "RustOS - tty1 (Alt+F2 : messages du noyau)\n");
+
+    // Script d'initialisation utilisateur
+    if mini_os::fs::vfs_stat("/etc/rc.local").is_ok() {
+        WRITER.lock().write_string("Exécution de /etc/rc.local...\n");
+        if let Err(e) = shell::SHELL.lock().run_script_file("/etc/rc.local") {
+            WRITER.lock().write_string(&format!("rc.local: {:?}\n", e));
+        }
+    }
 
     WRITER.lock().write_string("Démarrage du multitâche...\n");
     
@@ -219,21 +452,45 @@ extern "C" fn _start() -> ! {
 fn panic(info: &PanicInfo) -> ! {
     use core::fmt::Write;
     
+    // Vidage avant l'écran : le verrou de WRITER peut être tenu
+    let dumped = mini_os::kdump::on_panic(info);
+
     let mut writer = WRITER.lock();
     writer.write_string("\n\x1b[31mPANIC!\x1b[0m\n");
     writeln!(writer, "{}", info).unwrap();
+
+    // Le vidage est relu et enregistré au démarrage suivant
+    if dumped {
+        writer.write_string("Vidage kdump écrit, redémarrage...\n");
+        drop(writer);
+        mini_os::power::reboot();
+    }
     
     loop {
         x86_64::instructions::hlt();
     }
 }
 
+/// Arguments et environnement de départ du premier programme utilisateur
+fn init_args(path: &str) -> process::exec::ExecArgs {
+    let env = ["PATH=/bin:/usr/bin", "HOME=/", "TERM=linux"];
+    process::exec::ExecArgs::new(vec![path.to_string()], env.iter().map(|e| e.to_string()).collect())
+}
+
 /// Processus d'initialisation
 fn init_process() -> ! {
     WRITER.lock().write_string("Processus init démarré\n");
     
     loop {
         WRITER.lock().write_string(".");
+        // Claviers et souris USB branchés ou retirés depuis le dernier tour
+        #[cfg(feature = "usb")]
+        {
+            drivers::xhci::poll();
+            device_manager::DEVICE_MANAGER.lock().poll_hotplug();
+        }
+        // Image suivante du compositeur s'il a pris l'écran
+        drivers::gpu::compositor::poll();
         unsafe { x86_64::instructions::hlt(); }
     }
 }
@@ -1,12 +1,18 @@
-// pub mod vm; // Disabled - depends on Limine
+pub mod frame;
+pub mod vm;
 pub mod slab;
 pub mod hybrid;
+pub mod kasan;
 pub mod shm;
 pub mod mmap;
+pub mod stats;
+pub mod uaccess;
 
 pub use hybrid::{HYBRID_ALLOCATOR, HybridStats};
 pub use shm::{SHM_MANAGER, ShmManager, ShmError, ShmCmd};
 pub use mmap::{MMAP_MANAGER, MmapManager, MmapError, MmapRegion};
+pub use stats::{mem_stats, MemStats};
+pub use frame::{FRAME_ALLOCATOR, BuddyFrameAllocator, GlobalFrameAllocator, MemoryRegion, MemoryRegionKind};
 
 use core::alloc::{GlobalAlloc, Layout};
 use core::ptr::{null_mut, NonNull};
@@ -246,6 +252,7 @@ impl BuddyAllocator {
             peak_memory_usage: self.peak_memory_usage,
             fragmentation_internal: self.fragmentation_internal,
             fragmentation_ratio: self.get_fragmentation_ratio(),
+            heap_size: self.heap_end - self.heap_start,
         }
     }
 }
@@ -259,6 +266,7 @@ pub struct BuddyStats {
     pub peak_memory_usage: usize,
     pub fragmentation_internal: usize,
     pub fragmentation_ratio: f32,
+    pub heap_size: usize,
 }
 
 // Wrapper pour satisfaire les règles d'orphelin
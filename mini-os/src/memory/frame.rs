@@ -0,0 +1,428 @@
+//! Allocateur de cadres physiques (buddy)
+//!
+//! Les cadres libres sont regroupés en blocs de 2^order cadres de 4 Kio,
+//! de l'ordre 0 (4 Kio) à `MAX_ORDER` (2 Mio). Chaque ordre garde ses blocs
+//! libres dans un ensemble trié : allocation et libération en O(log n),
+//! et la libération fusionne le bloc avec son compagnon tant qu'il est libre.
+//!
+//! La mémoire est ajoutée région par région depuis la carte mémoire
+//! Multiboot2 ; les cadres sous `reserved_end()` (noyau, tables de
+//! démarrage) et le tas du noyau ne sont jamais distribués.
+
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+use spin::Mutex;
+use x86_64::structures::paging::{
+    FrameAllocator, FrameDeallocator, PageSize, PhysFrame, Size2MiB, Size4KiB,
+};
+use x86_64::PhysAddr;
+
+/// Taille d'un cadre
+pub const FRAME_SIZE: u64 = Size4KiB::SIZE;
+
+/// Ordre maximal : 2^9 cadres = 2 Mio
+pub const MAX_ORDER: usize = 9;
+
+/// Ordre d'un bloc de 2 Mio (huge page)
+pub const HUGE_ORDER: usize = 9;
+
+/// Fin de la zone basse réservée aux structures de démarrage, à kexec et
+/// au vidage sur panique ; l'image du noyau peut aller au-delà
+pub const KERNEL_RESERVED_END: u64 = 0x40_0000; // 4 MiB
+
+extern "C" {
+    /// Fin de l'image du noyau (linker.ld)
+    static __kernel_end: u8;
+}
+
+/// Adresse physique de la fin de l'image du noyau
+pub fn kernel_end() -> u64 {
+    unsafe { &__kernel_end as *const u8 as u64 }
+}
+
+/// Fin de la zone réservée en bas de la mémoire : l'image du noyau,
+/// arrondie au cadre, et au moins `KERNEL_RESERVED_END`
+pub fn reserved_end() -> u64 {
+    kernel_end().next_multiple_of(FRAME_SIZE).max(KERNEL_RESERVED_END)
+}
+
+/// Type d'une région de la carte mémoire
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryRegionKind {
+    Usable,
+    Reserved,
+    AcpiReclaimable,
+    AcpiNvs,
+    BadMemory,
+}
+
+impl MemoryRegionKind {
+    /// Conversion depuis le type Multiboot2 d'une entrée
+    pub fn from_multiboot(typ: u32) -> Self {
+        match typ {
+            1 => MemoryRegionKind::Usable,
+            3 => MemoryRegionKind::AcpiReclaimable,
+            4 => MemoryRegionKind::AcpiNvs,
+            5 => MemoryRegionKind::BadMemory,
+            _ => MemoryRegionKind::Reserved,
+        }
+    }
+}
+
+/// Région physique `[start, end)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryRegion {
+    pub start: u64,
+    pub end: u64,
+    pub kind: MemoryRegionKind,
+}
+
+impl MemoryRegion {
+    pub fn usable(start: u64, end: u64) -> Self {
+        Self { start, end, kind: MemoryRegionKind::Usable }
+    }
+}
+
+/// Retire les intervalles `holes` des régions utilisables
+pub fn carve(regions: &[MemoryRegion], holes: &[(u64, u64)]) -> Vec<MemoryRegion> {
+    let mut out = Vec::new();
+    for region in regions {
+        if region.kind != MemoryRegionKind::Usable {
+            out.push(*region);
+            continue;
+        }
+        let mut pieces = alloc::vec![(region.start, region.end)];
+        for &(hole_start, hole_end) in holes.iter().filter(|(s, e)| s < e) {
+            let mut next = Vec::new();
+            for (start, end) in pieces {
+                if hole_end <= start || hole_start >= end {
+                    next.push((start, end));
+                    continue;
+                }
+                if start < hole_start {
+                    next.push((start, hole_start));
+                }
+                if hole_end < end {
+                    next.push((hole_end, end));
+                }
+            }
+            pieces = next;
+        }
+        out.extend(pieces.into_iter().map(|(start, end)| MemoryRegion::usable(start, end)));
+    }
+    out
+}
+
+/// Lit la carte mémoire (tag 6) des informations Multiboot2
+///
+/// # Safety
+/// `info_addr` doit pointer sur la structure d'informations fournie par le
+/// chargeur, accessible en identité.
+pub unsafe fn regions_from_multiboot(info_addr: usize) -> Vec<MemoryRegion> {
+    let mut regions = Vec::new();
+    for_each_multiboot_region(info_addr, |region| regions.push(region));
+    regions
+}
+
+/// Parcourt la carte mémoire Multiboot2 sans allouer : utilisable avant
+/// l'initialisation du tas
+///
+/// # Safety
+/// Comme `regions_from_multiboot`.
+pub unsafe fn for_each_multiboot_region(info_addr: usize, mut visit: impl FnMut(MemoryRegion)) {
+    const TAG_END: u32 = 0;
+    const TAG_MEMORY_MAP: u32 = 6;
+
+    let total_size = core::ptr::read_unaligned(info_addr as *const u32) as usize;
+    let mut tag = info_addr + 8;
+
+    while tag + 8 <= info_addr + total_size {
+        let typ = core::ptr::read_unaligned(tag as *const u32);
+        let size = core::ptr::read_unaligned((tag + 4) as *const u32) as usize;
+        if typ == TAG_END || size < 8 {
+            break;
+        }
+
+        if typ == TAG_MEMORY_MAP {
+            let entry_size = core::ptr::read_unaligned((tag + 8) as *const u32) as usize;
+            let mut entry = tag + 16;
+            while entry_size >= 20 && entry + entry_size <= tag + size {
+                let base = core::ptr::read_unaligned(entry as *const u64);
+                let len = core::ptr::read_unaligned((entry + 8) as *const u64);
+                let kind = core::ptr::read_unaligned((entry + 16) as *const u32);
+                visit(MemoryRegion {
+                    start: base,
+                    end: base.saturating_add(len),
+                    kind: MemoryRegionKind::from_multiboot(kind),
+                });
+                entry += entry_size;
+            }
+        }
+
+        // Les tags sont alignés sur 8 octets
+        tag += (size + 7) & !7;
+    }
+}
+
+/// Allocateur buddy de cadres physiques
+pub struct BuddyFrameAllocator {
+    /// Adresses des blocs libres, par ordre
+    free_lists: [BTreeSet<u64>; MAX_ORDER + 1],
+    total_frames: usize,
+    free_frames: usize,
+}
+
+impl BuddyFrameAllocator {
+    pub const fn new() -> Self {
+        const EMPTY: BTreeSet<u64> = BTreeSet::new();
+        Self { free_lists: [EMPTY; MAX_ORDER + 1], total_frames: 0, free_frames: 0 }
+    }
+
+    /// Taille en octets d'un bloc d'ordre `order`
+    pub const fn block_size(order: usize) -> u64 {
+        FRAME_SIZE << order
+    }
+
+    /// Plus petit ordre contenant `size` octets (None au-delà de 2 Mio)
+    pub fn order_for_size(size: u64) -> Option<usize> {
+        (0..=MAX_ORDER).find(|&order| Self::block_size(order) >= size)
+    }
+
+    /// Ajoute une région libre `[start, end)` (bornes ramenées sur des cadres)
+    pub fn add_region(&mut self, start: u64, end: u64) {
+        let mut addr = (start + FRAME_SIZE - 1) & !(FRAME_SIZE - 1);
+        let end = end & !(FRAME_SIZE - 1);
+
+        while addr < end {
+            // Plus grand bloc aligné tenant dans le reste de la région
+            let mut order = MAX_ORDER;
+            while order > 0
+                && (addr % Self::block_size(order) != 0 || addr + Self::block_size(order) > end)
+            {
+                order -= 1;
+            }
+            self.total_frames += 1 << order;
+            self.release(addr, order);
+            addr += Self::block_size(order);
+        }
+    }
+
+    /// Initialise depuis une carte mémoire ; tout ce qui précède `reserved_end` est écarté
+    pub fn init_from_regions(&mut self, regions: &[MemoryRegion], reserved_end: u64) {
+        for region in regions.iter().filter(|r| r.kind == MemoryRegionKind::Usable) {
+            let start = region.start.max(reserved_end);
+            if start < region.end {
+                self.add_region(start, region.end);
+            }
+        }
+    }
+
+    /// Alloue un bloc de 2^order cadres contigus, aligné sur sa taille
+    pub fn allocate(&mut self, order: usize) -> Option<PhysAddr> {
+        if order > MAX_ORDER || crate::fault_inject::should_fail(crate::fault_inject::Point::Frame) {
+            return None;
+        }
+        let found = (order..=MAX_ORDER).find(|&o| !self.free_lists[o].is_empty())?;
+        let addr = self.free_lists[found].pop_first()?;
+
+        // Découpe : la moitié haute retourne dans l'ordre inférieur
+        for lower in (order..found).rev() {
+            self.free_lists[lower].insert(addr + Self::block_size(lower));
+        }
+        self.free_frames -= 1 << order;
+        Some(PhysAddr::new(addr))
+    }
+
+    /// Libère un bloc alloué avec le même ordre
+    pub fn free(&mut self, addr: PhysAddr, order: usize) {
+        let addr = addr.as_u64();
+        debug_assert!(addr % Self::block_size(order) == 0, "bloc mal aligné");
+        debug_assert!(!self.is_free(addr), "double libération du cadre {:#x}", addr);
+        self.release(addr, order);
+    }
+
+    /// Remet un bloc dans les listes en fusionnant avec ses compagnons libres
+    fn release(&mut self, mut addr: u64, mut order: usize) {
+        self.free_frames += 1 << order;
+        while order < MAX_ORDER {
+            let buddy = addr ^ Self::block_size(order);
+            if !self.free_lists[order].remove(&buddy) {
+                break;
+            }
+            addr = addr.min(buddy);
+            order += 1;
+        }
+        self.free_lists[order].insert(addr);
+    }
+
+    /// Le cadre `addr` est-il dans un bloc libre ?
+    pub fn is_free(&self, addr: u64) -> bool {
+        (0..=MAX_ORDER).any(|order| {
+            let block = addr & !(Self::block_size(order) - 1);
+            self.free_lists[order].contains(&block)
+        })
+    }
+
+    pub fn total_frames(&self) -> usize {
+        self.total_frames
+    }
+
+    pub fn free_frames(&self) -> usize {
+        self.free_frames
+    }
+
+    /// Nombre de blocs libres pour chaque ordre
+    pub fn free_blocks_per_order(&self) -> [usize; MAX_ORDER + 1] {
+        let mut counts = [0; MAX_ORDER + 1];
+        for (order, list) in self.free_lists.iter().enumerate() {
+            counts[order] = list.len();
+        }
+        counts
+    }
+}
+
+unsafe impl FrameAllocator<Size4KiB> for BuddyFrameAllocator {
+    fn allocate_frame(&mut self) -> Option<PhysFrame<Size4KiB>> {
+        self.allocate(0).map(PhysFrame::containing_address)
+    }
+}
+
+impl FrameDeallocator<Size4KiB> for BuddyFrameAllocator {
+    unsafe fn deallocate_frame(&mut self, frame: PhysFrame<Size4KiB>) {
+        self.free(frame.start_address(), 0);
+    }
+}
+
+unsafe impl FrameAllocator<Size2MiB> for BuddyFrameAllocator {
+    fn allocate_frame(&mut self) -> Option<PhysFrame<Size2MiB>> {
+        self.allocate(HUGE_ORDER).map(PhysFrame::containing_address)
+    }
+}
+
+impl FrameDeallocator<Size2MiB> for BuddyFrameAllocator {
+    unsafe fn deallocate_frame(&mut self, frame: PhysFrame<Size2MiB>) {
+        self.free(frame.start_address(), HUGE_ORDER);
+    }
+}
+
+/// Allocateur de cadres global
+pub static FRAME_ALLOCATOR: Mutex<BuddyFrameAllocator> = Mutex::new(BuddyFrameAllocator::new());
+
+/// Initialise l'allocateur global depuis la carte mémoire, sans les
+/// intervalles physiques `holes` (tas du noyau)
+pub fn init(regions: &[MemoryRegion], holes: &[(u64, u64)]) {
+    FRAME_ALLOCATOR.lock().init_from_regions(&carve(regions, holes), reserved_end());
+}
+
+/// Sous ce nombre de cadres libres, une allocation réveille le swap
+pub const LOW_WATERMARK: usize = 100;
+
+/// Alloue un bloc d'ordre `order` ; passé sous `LOW_WATERMARK`, l'éviction
+/// de pages est confiée à un kworker (l'appelant peut tenir VM_MANAGER)
+fn allocate_watched(order: usize) -> Option<PhysAddr> {
+    let (addr, free) = {
+        let mut allocator = FRAME_ALLOCATOR.lock();
+        (allocator.allocate(order), allocator.free_frames())
+    };
+    if free < LOW_WATERMARK {
+        crate::memory::vm::swap::wake();
+    }
+    addr
+}
+
+/// Poignée sans état sur `FRAME_ALLOCATOR`, à passer au `Mapper`
+/// (le verrou n'est pris que le temps d'une allocation)
+pub struct GlobalFrameAllocator;
+
+unsafe impl FrameAllocator<Size4KiB> for GlobalFrameAllocator {
+    fn allocate_frame(&mut self) -> Option<PhysFrame<Size4KiB>> {
+        allocate_watched(0).map(PhysFrame::containing_address)
+    }
+}
+
+impl FrameDeallocator<Size4KiB> for GlobalFrameAllocator {
+    unsafe fn deallocate_frame(&mut self, frame: PhysFrame<Size4KiB>) {
+        FrameDeallocator::<Size4KiB>::deallocate_frame(&mut *FRAME_ALLOCATOR.lock(), frame)
+    }
+}
+
+unsafe impl FrameAllocator<Size2MiB> for GlobalFrameAllocator {
+    fn allocate_frame(&mut self) -> Option<PhysFrame<Size2MiB>> {
+        allocate_watched(HUGE_ORDER).map(PhysFrame::containing_address)
+    }
+}
+
+impl FrameDeallocator<Size2MiB> for GlobalFrameAllocator {
+    unsafe fn deallocate_frame(&mut self, frame: PhysFrame<Size2MiB>) {
+        FrameDeallocator::<Size2MiB>::deallocate_frame(&mut *FRAME_ALLOCATOR.lock(), frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIB: u64 = 1024 * 1024;
+
+    #[test_case]
+    fn test_split_and_coalesce() {
+        let mut buddy = BuddyFrameAllocator::new();
+        buddy.add_region(4 * MIB, 6 * MIB);
+        assert_eq!(buddy.total_frames(), 512);
+        assert_eq!(buddy.free_blocks_per_order()[MAX_ORDER], 1);
+
+        let a = buddy.allocate(0).unwrap();
+        let b = buddy.allocate(0).unwrap();
+        assert_eq!(a.as_u64(), 4 * MIB);
+        assert_eq!(b.as_u64(), 4 * MIB + FRAME_SIZE);
+        assert_eq!(buddy.free_frames(), 510);
+        assert!(!buddy.is_free(a.as_u64()));
+
+        buddy.free(a, 0);
+        buddy.free(b, 0);
+        // Tout a fusionné en un seul bloc de 2 Mio
+        assert_eq!(buddy.free_frames(), 512);
+        assert_eq!(buddy.free_blocks_per_order()[MAX_ORDER], 1);
+        assert_eq!(buddy.free_blocks_per_order()[0], 0);
+    }
+
+    #[test_case]
+    fn test_unaligned_region_and_huge_blocks() {
+        let mut buddy = BuddyFrameAllocator::new();
+        // 12 Kio avant la frontière de 2 Mio, puis 2 Mio alignés
+        buddy.add_region(2 * MIB - 3 * FRAME_SIZE + 100, 4 * MIB);
+        assert_eq!(buddy.total_frames(), 2 + 512);
+
+        let huge = buddy.allocate(HUGE_ORDER).unwrap();
+        assert_eq!(huge.as_u64(), 2 * MIB);
+        assert_eq!(buddy.allocate(HUGE_ORDER), None);
+        assert_eq!(buddy.allocate(MAX_ORDER + 1), None);
+
+        let frame: PhysFrame<Size4KiB> = FrameAllocator::<Size4KiB>::allocate_frame(&mut buddy).unwrap();
+        assert!(frame.start_address().as_u64() < 2 * MIB);
+        unsafe { FrameDeallocator::<Size2MiB>::deallocate_frame(&mut buddy, PhysFrame::containing_address(huge)) };
+        assert_eq!(buddy.free_frames(), 2 + 512 - 1);
+    }
+
+    #[test_case]
+    fn test_init_from_regions() {
+        let regions = [
+            MemoryRegion::usable(0, 0x9_f000),
+            MemoryRegion { start: 0xf_0000, end: MIB, kind: MemoryRegionKind::Reserved },
+            MemoryRegion::usable(MIB, 8 * MIB),
+        ];
+        let mut buddy = BuddyFrameAllocator::new();
+        buddy.init_from_regions(&regions, KERNEL_RESERVED_END);
+        assert_eq!(buddy.total_frames() as u64, (8 * MIB - KERNEL_RESERVED_END) / FRAME_SIZE);
+        assert!(!buddy.is_free(0x1000));
+        assert!(reserved_end() >= KERNEL_RESERVED_END && reserved_end() % FRAME_SIZE == 0);
+
+        // Tas du noyau retiré de la carte
+        let carved = carve(&regions, &[(2 * MIB, 3 * MIB)]);
+        let mut buddy = BuddyFrameAllocator::new();
+        buddy.init_from_regions(&carved, 0);
+        assert!(!buddy.is_free(2 * MIB) && !buddy.is_free(3 * MIB - FRAME_SIZE) && buddy.is_free(3 * MIB));
+        assert_eq!(BuddyFrameAllocator::order_for_size(5000), Some(1));
+        assert_eq!(BuddyFrameAllocator::order_for_size(4 * MIB), None);
+    }
+}
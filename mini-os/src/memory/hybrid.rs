@@ -10,7 +10,7 @@
 use core::alloc::{GlobalAlloc, Layout};
 use spin::Mutex;
 use crate::memory::{BuddyAllocator, BuddyStats};
-use crate::memory::slab::{SlabAllocator, SlabStats};
+use crate::memory::slab::{SlabAllocator, SlabCacheStats, SlabStats};
 
 /// Seuil de dispatch entre SLAB et Buddy (en bytes)
 const HYBRID_THRESHOLD: usize = 512;
@@ -55,6 +55,11 @@ impl HybridAllocator {
         }
     }
     
+    /// Statistiques de chaque cache SLAB
+    pub fn slab_cache_stats(&self) -> [SlabCacheStats; 5] {
+        self.slab.lock().cache_stats()
+    }
+    
     /// Retourne le seuil de dispatch actuel
     pub fn threshold(&self) -> usize {
         self.threshold
@@ -63,6 +68,9 @@ impl HybridAllocator {
 
 unsafe impl GlobalAlloc for HybridAllocator {
     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if crate::fault_inject::should_fail(crate::fault_inject::Point::Heap) {
+            return core::ptr::null_mut();
+        }
         if layout.size() <= self.threshold {
             // Petite allocation → SLAB
             let ptr = self.slab.lock().alloc(layout);
@@ -133,7 +141,8 @@ impl HybridStats {
 }
 
 /// Instance globale de l'allocateur hybride
-#[global_allocator]
+/// (enveloppée par `kasan::KASAN_ALLOCATOR` avec la feature `kasan`)
+#[cfg_attr(not(feature = "kasan"), global_allocator)]
 pub static HYBRID_ALLOCATOR: HybridAllocator = HybridAllocator::new();
 
 #[cfg(test)]
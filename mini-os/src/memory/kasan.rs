@@ -0,0 +1,339 @@
+/// Module KASAN-lite : allocateur de débogage du tas
+///
+/// Enveloppe un `GlobalAlloc` et entoure chaque allocation de zones rouges :
+///
+/// ```text
+/// [ en-tête | zone rouge gauche | données | zone rouge droite ]
+/// ```
+///
+/// L'en-tête garde la taille demandée et le site d'allocation (adresses de
+/// retour relevées en remontant la chaîne RBP ; compiler avec
+/// `-C force-frame-pointers=yes` pour des sites fiables). À la libération,
+/// les zones rouges sont vérifiées et les données empoisonnées ; le bloc
+/// passe ensuite par une quarantaine avant de retourner à l'allocateur
+/// sous-jacent, ce qui permet de détecter les doubles libérations et les
+/// écritures après libération.
+///
+/// Activé par la feature `kasan`, qui l'installe comme allocateur global
+/// autour de `HYBRID_ALLOCATOR`.
+
+use core::alloc::{GlobalAlloc, Layout};
+use core::arch::asm;
+use core::fmt;
+use core::ptr::null_mut;
+use spin::Mutex;
+
+/// Taille de chaque zone rouge
+pub const REDZONE: usize = 32;
+
+/// Motif des zones rouges
+pub const REDZONE_BYTE: u8 = 0xFA;
+/// Motif des données fraîchement allouées (non initialisées)
+pub const UNINIT_BYTE: u8 = 0xBE;
+/// Motif des données libérées
+pub const FREED_BYTE: u8 = 0xFD;
+
+/// Profondeur du site d'allocation enregistré
+pub const SITE_DEPTH: usize = 4;
+
+/// Blocs retenus en quarantaine avant d'être rendus
+const QUARANTINE_SIZE: usize = 64;
+
+const MAGIC_LIVE: u64 = 0x4B41_5341_4E4C_4956; // "KASANLIV"
+const MAGIC_FREED: u64 = 0x4B41_5341_4E46_5245; // "KASANFRE"
+
+/// Adresses de retour de la pile d'appel
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Site(pub [usize; SITE_DEPTH]);
+
+impl fmt::Debug for Site {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut list = f.debug_list();
+        for &addr in self.0.iter().take_while(|&&a| a != 0) {
+            list.entry(&format_args!("{:#x}", addr));
+        }
+        list.finish()
+    }
+}
+
+/// En-tête placé devant chaque allocation
+#[repr(C)]
+struct Header {
+    magic: u64,
+    size: usize,
+    align: usize,
+    alloc_site: Site,
+    free_site: Site,
+}
+
+/// Erreur détectée sur une allocation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KasanError {
+    /// Libération d'un bloc déjà libéré
+    DoubleFree { ptr: usize, alloc_site: Site, free_site: Site },
+    /// Pointeur jamais alloué ou en-tête écrasé
+    InvalidFree { ptr: usize },
+    /// Écriture hors des limites (offset relatif au début des données)
+    Overflow { ptr: usize, offset: isize, size: usize, alloc_site: Site },
+    /// Écriture dans un bloc après sa libération
+    UseAfterFree { ptr: usize, offset: usize, alloc_site: Site, free_site: Site },
+}
+
+/// Allocateur de débogage autour de `A`
+pub struct KasanAllocator<A: GlobalAlloc + 'static> {
+    inner: &'static A,
+    quarantine: Mutex<Quarantine>,
+}
+
+/// Blocs libérés retenus : (pointeur des données, alignement demandé)
+struct Quarantine {
+    blocks: [(usize, usize); QUARANTINE_SIZE],
+    next: usize,
+}
+
+impl<A: GlobalAlloc + 'static> KasanAllocator<A> {
+    pub const fn new(inner: &'static A) -> Self {
+        Self {
+            inner,
+            quarantine: Mutex::new(Quarantine { blocks: [(0, 0); QUARANTINE_SIZE], next: 0 }),
+        }
+    }
+
+    /// Décalage des données par rapport au début du bloc
+    fn data_offset(align: usize) -> usize {
+        let prefix = core::mem::size_of::<Header>() + REDZONE;
+        (prefix + align - 1) & !(align - 1)
+    }
+
+    /// Layout du bloc sous-jacent
+    fn inner_layout(size: usize, align: usize) -> Option<Layout> {
+        let align = align.max(core::mem::align_of::<Header>());
+        let total = Self::data_offset(align).checked_add(size)?.checked_add(REDZONE)?;
+        Layout::from_size_align(total, align).ok()
+    }
+
+    unsafe fn header<'a>(ptr: *mut u8, align: usize) -> &'a mut Header {
+        let align = align.max(core::mem::align_of::<Header>());
+        &mut *(ptr.sub(Self::data_offset(align)) as *mut Header)
+    }
+
+    /// Alloue avec zones rouges ; `site` identifie l'appelant
+    pub unsafe fn alloc_at(&self, layout: Layout, site: Site) -> *mut u8 {
+        let inner_layout = match Self::inner_layout(layout.size(), layout.align()) {
+            Some(l) => l,
+            None => return null_mut(),
+        };
+        let base = self.inner.alloc(inner_layout);
+        if base.is_null() {
+            return base;
+        }
+
+        let data = base.add(Self::data_offset(inner_layout.align()));
+        let header = base as *mut Header;
+        header.write(Header {
+            magic: MAGIC_LIVE,
+            size: layout.size(),
+            align: layout.align(),
+            alloc_site: site,
+            free_site: Site([0; SITE_DEPTH]),
+        });
+
+        // Zone rouge gauche (entre l'en-tête et les données), données, zone droite
+        let header_end = base.add(core::mem::size_of::<Header>());
+        core::ptr::write_bytes(header_end, REDZONE_BYTE, data as usize - header_end as usize);
+        core::ptr::write_bytes(data, UNINIT_BYTE, layout.size());
+        core::ptr::write_bytes(data.add(layout.size()), REDZONE_BYTE, REDZONE);
+        data
+    }
+
+    /// Vérifie un bloc vivant (zones rouges intactes)
+    pub unsafe fn check(&self, ptr: *mut u8, layout: Layout) -> Result<(), KasanError> {
+        let header = Self::header(ptr, layout.align());
+        match header.magic {
+            MAGIC_LIVE => {}
+            MAGIC_FREED => {
+                return Err(KasanError::DoubleFree {
+                    ptr: ptr as usize,
+                    alloc_site: header.alloc_site,
+                    free_site: header.free_site,
+                })
+            }
+            _ => return Err(KasanError::InvalidFree { ptr: ptr as usize }),
+        }
+
+        let overflow = |offset: isize| KasanError::Overflow {
+            ptr: ptr as usize,
+            offset,
+            size: header.size,
+            alloc_site: header.alloc_site,
+        };
+        // Zone gauche : on rapporte l'octet corrompu le plus proche des données
+        for i in 1..=REDZONE {
+            if *ptr.sub(i) != REDZONE_BYTE {
+                return Err(overflow(-(i as isize)));
+            }
+        }
+        for i in 0..REDZONE {
+            if *ptr.add(header.size + i) != REDZONE_BYTE {
+                return Err(overflow((header.size + i) as isize));
+            }
+        }
+        Ok(())
+    }
+
+    /// Libère : vérification, poison puis quarantaine
+    pub unsafe fn dealloc_at(&self, ptr: *mut u8, layout: Layout, site: Site) -> Result<(), KasanError> {
+        self.check(ptr, layout)?;
+
+        let header = Self::header(ptr, layout.align());
+        header.magic = MAGIC_FREED;
+        header.free_site = site;
+        core::ptr::write_bytes(ptr, FREED_BYTE, header.size);
+
+        // Le plus ancien bloc en quarantaine est vérifié puis rendu
+        let evicted = {
+            let mut quarantine = self.quarantine.lock();
+            let slot = quarantine.next;
+            quarantine.next = (slot + 1) % QUARANTINE_SIZE;
+            core::mem::replace(&mut quarantine.blocks[slot], (ptr as usize, layout.align()))
+        };
+        if evicted.0 != 0 {
+            self.release(evicted.0 as *mut u8, evicted.1)?;
+        }
+        Ok(())
+    }
+
+    /// Rend un bloc sorti de quarantaine après contrôle du poison
+    unsafe fn release(&self, ptr: *mut u8, align: usize) -> Result<(), KasanError> {
+        let header = Self::header(ptr, align);
+        if header.magic != MAGIC_FREED {
+            return Err(KasanError::InvalidFree { ptr: ptr as usize });
+        }
+        if let Some(offset) = (0..header.size).find(|&i| *ptr.add(i) != FREED_BYTE) {
+            return Err(KasanError::UseAfterFree {
+                ptr: ptr as usize,
+                offset,
+                alloc_site: header.alloc_site,
+                free_site: header.free_site,
+            });
+        }
+
+        let (size, align) = (header.size, header.align);
+        header.magic = 0;
+        if let Some(inner_layout) = Self::inner_layout(size, align) {
+            let base = ptr.sub(Self::data_offset(inner_layout.align()));
+            self.inner.dealloc(base, inner_layout);
+        }
+        Ok(())
+    }
+}
+
+/// Relève les adresses de retour en remontant les cadres RBP
+///
+/// Chaque cadre doit se trouver plus haut sur la même pile (fenêtre de
+/// 64 Kio au-dessus de RSP) : sans frame pointers, la remontée s'arrête.
+#[inline(always)]
+pub fn capture_site() -> Site {
+    const STACK_WINDOW: usize = 64 * 1024;
+    let mut site = [0usize; SITE_DEPTH];
+    let (mut rbp, rsp): (usize, usize);
+    unsafe {
+        asm!("mov {}, rbp", out(reg) rbp, options(nomem, nostack, preserves_flags));
+        asm!("mov {}, rsp", out(reg) rsp, options(nomem, nostack, preserves_flags));
+    }
+
+    let mut floor = rsp;
+    for slot in site.iter_mut() {
+        if rbp <= floor || rbp - rsp >= STACK_WINDOW || rbp % 8 != 0 {
+            break;
+        }
+        unsafe {
+            *slot = *((rbp + 8) as *const usize);
+            floor = rbp;
+            rbp = *(rbp as *const usize);
+        }
+    }
+    Site(site)
+}
+
+unsafe impl<A: GlobalAlloc + 'static> GlobalAlloc for KasanAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        self.alloc_at(layout, capture_site())
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // Aucun verrou n'est tenu ici : le panic handler peut allouer
+        if let Err(e) = self.dealloc_at(ptr, layout, capture_site()) {
+            panic!("KASAN: {:?}", e);
+        }
+    }
+}
+
+#[cfg(feature = "kasan")]
+#[global_allocator]
+pub static KASAN_ALLOCATOR: KasanAllocator<crate::memory::hybrid::HybridAllocator> =
+    KasanAllocator::new(&crate::memory::HYBRID_ALLOCATOR);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::hybrid::HybridAllocator;
+    use crate::memory::HYBRID_ALLOCATOR;
+
+    static TEST_KASAN: KasanAllocator<HybridAllocator> = KasanAllocator::new(&HYBRID_ALLOCATOR);
+
+    fn site() -> Site {
+        Site([0x1234, 0, 0, 0])
+    }
+
+    #[test_case]
+    fn test_redzones_and_poison() {
+        unsafe {
+            let layout = Layout::from_size_align(24, 8).unwrap();
+            let ptr = TEST_KASAN.alloc_at(layout, site());
+            assert!(!ptr.is_null());
+            assert_eq!(*ptr, UNINIT_BYTE);
+            assert_eq!(TEST_KASAN.check(ptr, layout), Ok(()));
+
+            core::ptr::write_bytes(ptr, 0x11, 24);
+            assert_eq!(TEST_KASAN.dealloc_at(ptr, layout, site()), Ok(()));
+            assert_eq!(*ptr.add(23), FREED_BYTE);
+        }
+    }
+
+    #[test_case]
+    fn test_overflow_detected() {
+        unsafe {
+            let layout = Layout::from_size_align(16, 16).unwrap();
+            let ptr = TEST_KASAN.alloc_at(layout, site());
+            *ptr.add(17) = 0;
+            match TEST_KASAN.check(ptr, layout) {
+                Err(KasanError::Overflow { offset, size, alloc_site, .. }) => {
+                    assert_eq!((offset, size), (17, 16));
+                    assert_eq!(alloc_site, site());
+                }
+                other => panic!("attendu Overflow, obtenu {:?}", other),
+            }
+            *ptr.add(17) = REDZONE_BYTE;
+            *ptr.sub(1) = 0;
+            assert!(matches!(TEST_KASAN.check(ptr, layout), Err(KasanError::Overflow { offset: -1, .. })));
+            *ptr.sub(1) = REDZONE_BYTE;
+            assert_eq!(TEST_KASAN.dealloc_at(ptr, layout, site()), Ok(()));
+        }
+    }
+
+    #[test_case]
+    fn test_double_free_detected() {
+        unsafe {
+            let layout = Layout::from_size_align(40, 8).unwrap();
+            let ptr = TEST_KASAN.alloc_at(layout, site());
+            let free_site = Site([0x5678, 0, 0, 0]);
+            assert_eq!(TEST_KASAN.dealloc_at(ptr, layout, free_site), Ok(()));
+            // Le bloc est encore en quarantaine : l'en-tête témoigne de la première libération
+            assert_eq!(
+                TEST_KASAN.dealloc_at(ptr, layout, site()),
+                Err(KasanError::DoubleFree { ptr: ptr as usize, alloc_site: site(), free_site })
+            );
+        }
+    }
+}
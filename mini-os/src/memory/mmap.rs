@@ -4,9 +4,12 @@
 /// ou de la mémoire anonyme dans l'espace d'adressage d'un processus.
 
 use alloc::collections::BTreeMap;
+use alloc::string::String;
 use alloc::vec::Vec;
 use spin::Mutex;
 use x86_64::{VirtAddr, PhysAddr};
+use x86_64::structures::paging::PageTableFlags;
+use crate::memory::uaccess::USER_SPACE_END;
 
 /// Flags pour mmap
 pub const PROT_NONE: i32 = 0x0;      // Pas d'accès
@@ -18,6 +21,10 @@ pub const MAP_SHARED: i32 = 0x01;    // Mapping partagé
 pub const MAP_PRIVATE: i32 = 0x02;   // Mapping privé (CoW)
 pub const MAP_ANONYMOUS: i32 = 0x20; // Mapping anonyme (pas de fichier)
 pub const MAP_FIXED: i32 = 0x10;     // Adresse fixe
+pub const MAP_HUGETLB: i32 = 0x40000; // Pages de 2 Mio
+
+/// Taille d'une huge page
+pub const HUGE_PAGE_SIZE: usize = 2 * 1024 * 1024;
 
 /// Erreurs mmap
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -36,6 +43,8 @@ pub enum MmapError {
     InvalidFile,
     /// Région non trouvée
     NotFound,
+    /// Limite d'espace d'adressage du processus atteinte (RLIMIT_AS)
+    LimitExceeded,
 }
 
 /// Type de mapping
@@ -64,6 +73,10 @@ pub struct MmapRegion {
     pub owner_pid: u64,
     /// Adresse physique (pour MAP_SHARED)
     pub phys_addr: Option<PhysAddr>,
+    /// Région servie par des pages de 2 Mio quand c'est possible
+    pub huge_pages: bool,
+    /// Fichier projeté (chemin VFS résolu au moment du mmap)
+    pub file_path: Option<String>,
 }
 
 impl MmapRegion {
@@ -84,6 +97,8 @@ impl MmapRegion {
             mmap_type,
             owner_pid: pid,
             phys_addr: None,
+            huge_pages: false,
+            file_path: None,
         }
     }
     
@@ -102,6 +117,16 @@ impl MmapRegion {
         (self.flags & MAP_ANONYMOUS) != 0
     }
     
+    /// Drapeaux de page correspondant aux protections
+    /// (PROT_NONE : page présente mais inaccessible depuis le ring 3)
+    pub fn page_flags(&self) -> PageTableFlags {
+        crate::memory::vm::protection_flags(
+            (self.prot & PROT_WRITE) != 0,
+            (self.prot & PROT_EXEC) != 0,
+            self.prot != PROT_NONE,
+        )
+    }
+    
     /// Vérifie si une adresse est dans cette région
     pub fn contains(&self, addr: VirtAddr) -> bool {
         let start = self.start_addr.as_u64();
@@ -111,6 +136,33 @@ impl MmapRegion {
     }
 }
 
+/// Valide une combinaison de protections
+///
+/// Bits inconnus : InvalidFlags. Écriture + exécution : refusé (W^X).
+pub fn validate_prot(prot: i32) -> Result<(), MmapError> {
+    if prot & !(PROT_READ | PROT_WRITE | PROT_EXEC) != 0 {
+        return Err(MmapError::InvalidFlags);
+    }
+    if (prot & PROT_WRITE) != 0 && (prot & PROT_EXEC) != 0 {
+        return Err(MmapError::PermissionDenied);
+    }
+    Ok(())
+}
+
+/// Plage `[addr, addr + size)` arrondie à la page : adresse alignée, fin
+/// dans l'espace utilisateur
+fn page_range(addr: VirtAddr, size: usize) -> Result<(u64, u64), MmapError> {
+    if !addr.is_aligned(4096u64) || size == 0 {
+        return Err(MmapError::InvalidAddress);
+    }
+    let start = addr.as_u64();
+    let end = (size as u64).checked_next_multiple_of(4096)
+        .and_then(|len| start.checked_add(len))
+        .filter(|&end| end <= USER_SPACE_END)
+        .ok_or(MmapError::InvalidSize)?;
+    Ok((start, end))
+}
+
 /// Gestionnaire de mmap
 pub struct MmapManager {
     /// Régions mmap indexées par adresse de début
@@ -121,6 +173,11 @@ pub struct MmapManager {
     total_mappings: usize,
     /// Nombre de mappings partagés
     shared_mappings: usize,
+    /// RLIMIT_AS par PID (absent : illimité)
+    limits: BTreeMap<u64, u64>,
+    /// Écart aléatoire laissé avant la prochaine projection de chaque PID
+    /// (ASLR, consommé par la première projection sans adresse imposée)
+    base_gaps: BTreeMap<u64, u64>,
 }
 
 impl MmapManager {
@@ -131,8 +188,42 @@ impl MmapManager {
             next_virt_addr: VirtAddr::new(0x7000_0000_0000), // Début de la zone mmap
             total_mappings: 0,
             shared_mappings: 0,
+            limits: BTreeMap::new(),
+            base_gaps: BTreeMap::new(),
+        }
+    }
+
+    /// Tire une nouvelle base mmap pour `pid` (création, exec)
+    pub fn randomize_base(&mut self, pid: u64) {
+        let gap = crate::process::aslr::mmap_gap();
+        if gap == 0 {
+            self.base_gaps.remove(&pid);
+        } else {
+            self.base_gaps.insert(pid, gap);
+        }
+    }
+
+    /// Oublie la base non consommée d'un processus terminé
+    pub fn forget_base(&mut self, pid: u64) {
+        self.base_gaps.remove(&pid);
+    }
+
+    /// Applique RLIMIT_AS (en octets) aux futurs mappings de `pid`
+    pub fn set_limit(&mut self, pid: u64, max_bytes: u64) {
+        if max_bytes == crate::process::rlimit::RLIM_INFINITY {
+            self.limits.remove(&pid);
+        } else {
+            self.limits.insert(pid, max_bytes);
         }
     }
+
+    /// Octets mappés par `pid`
+    pub fn mapped_size(&self, pid: u64) -> u64 {
+        self.regions.values()
+            .filter(|r| r.owner_pid == pid)
+            .map(|r| r.size as u64)
+            .sum()
+    }
     
     /// Mappe une région de mémoire
     /// 
@@ -155,40 +246,75 @@ impl MmapManager {
         pid: u64,
     ) -> Result<VirtAddr, MmapError> {
         // Valider la taille
-        if size == 0 {
+        if size == 0 || size as u64 > USER_SPACE_END {
             return Err(MmapError::InvalidSize);
         }
         
-        // Aligner la taille sur une page
-        let aligned_size = (size + 4095) & !4095;
+        validate_prot(prot)?;
         
         // Valider les flags
         if (flags & MAP_SHARED) != 0 && (flags & MAP_PRIVATE) != 0 {
             return Err(MmapError::InvalidFlags);
         }
+        let hugetlb = (flags & MAP_HUGETLB) != 0;
+        if hugetlb && (flags & MAP_ANONYMOUS) == 0 {
+            return Err(MmapError::InvalidFlags);
+        }
+        
+        // Grandes régions anonymes : huge pages (forcées par MAP_HUGETLB)
+        let huge = (flags & MAP_ANONYMOUS) != 0 && (hugetlb || size >= HUGE_PAGE_SIZE);
+        let (aligned_size, align) = if hugetlb {
+            ((size + HUGE_PAGE_SIZE - 1) & !(HUGE_PAGE_SIZE - 1), HUGE_PAGE_SIZE)
+        } else if huge {
+            ((size + 4095) & !4095, HUGE_PAGE_SIZE)
+        } else {
+            ((size + 4095) & !4095, 4096)
+        };
+        
+        if let Some(&limit) = self.limits.get(&pid) {
+            if self.mapped_size(pid) + aligned_size as u64 > limit {
+                return Err(MmapError::LimitExceeded);
+            }
+        }
         
         // Déterminer l'adresse virtuelle
         let virt_addr = if let Some(addr) = addr {
             if (flags & MAP_FIXED) != 0 {
+                // Adresse imposée : alignée, dans l'espace utilisateur, libre
+                if !addr.is_aligned(if hugetlb { HUGE_PAGE_SIZE as u64 } else { 4096 }) {
+                    return Err(MmapError::InvalidAddress);
+                }
+                let end = addr.as_u64().checked_add(aligned_size as u64).filter(|&end| end <= USER_SPACE_END);
+                let end = end.ok_or(MmapError::OutOfMemory)?;
+                if self.overlapping(addr.as_u64(), end).next().is_some() {
+                    return Err(MmapError::InvalidAddress);
+                }
                 addr
             } else {
                 // Suggestion d'adresse, mais on peut choisir une autre
-                self.find_free_region(aligned_size).unwrap_or(addr)
+                self.find_free_region(aligned_size, align, pid)?
             }
         } else {
-            self.find_free_region(aligned_size)?
+            self.find_free_region(aligned_size, align, pid)?
         };
         
         // Déterminer le type de mapping
-        let mmap_type = if (flags & MAP_ANONYMOUS) != 0 {
-            MmapType::Anonymous
+        let (mmap_type, file_path) = if (flags & MAP_ANONYMOUS) != 0 {
+            (MmapType::Anonymous, None)
         } else {
             let fid = file_id.ok_or(MmapError::InvalidFile)?;
-            MmapType::File { file_id: fid, offset }
+            if offset % 4096 != 0 {
+                return Err(MmapError::InvalidAddress);
+            }
+            let path = crate::fs::vfs_fd_path(pid, fid as usize).map_err(|_| MmapError::InvalidFile)?;
+            (MmapType::File { file_id: fid, offset }, Some(path))
         };
         
-        // Créer la région
+        // Créer la région ; les pages seront chargées au premier accès
+        // (voir vm::demand::handle_page_fault)
         let mut region = MmapRegion::new(virt_addr, aligned_size, prot, flags, mmap_type, pid);
+        region.huge_pages = huge;
+        region.file_path = file_path;
         
         // Allouer la mémoire physique pour les mappings partagés
         if region.is_shared() {
@@ -197,8 +323,6 @@ impl MmapManager {
             self.shared_mappings += 1;
         }
         
-        // TODO: mapper les pages dans la table de pages
-        
         // Enregistrer la région
         self.regions.insert(virt_addr.as_u64(), region);
         self.total_mappings += 1;
@@ -206,37 +330,152 @@ impl MmapManager {
         Ok(virt_addr)
     }
     
-    /// Démappe une région de mémoire
-    pub fn munmap(&mut self, addr: VirtAddr, size: usize) -> Result<(), MmapError> {
-        // Trouver la région qui contient cette adresse
-        let region_key = self.regions
-            .iter()
-            .find(|(_, r)| r.contains(addr))
-            .map(|(k, _)| *k)
-            .ok_or(MmapError::NotFound)?;
-        
-        if let Some(region) = self.regions.remove(&region_key) {
-            // TODO: unmapper les pages de la table de pages
-            // TODO: libérer la mémoire physique si nécessaire
+    /// Démappe `[addr, addr + size)`
+    ///
+    /// Les régions à cheval sont découpées, seule la plage est libérée.
+    /// Toutes les régions touchées doivent appartenir à `pid`.
+    pub fn munmap(&mut self, addr: VirtAddr, size: usize, pid: u64) -> Result<(), MmapError> {
+        let (start, end) = page_range(addr, size)?;
+        let mut touched = false;
+        for region in self.overlapping(start, end) {
+            if region.owner_pid != pid {
+                return Err(MmapError::PermissionDenied);
+            }
+            touched = true;
+        }
+        if !touched {
+            return Err(MmapError::NotFound);
+        }
+        
+        self.split_at(start)?;
+        self.split_at(end)?;
+        
+        let keys: Vec<u64> = self.regions.range(start..end).map(|(k, _)| *k).collect();
+        for key in keys {
+            let region = self.regions.remove(&key).ok_or(MmapError::NotFound)?;
+            // Les cadres (4 Kio ou 2 Mio) retournent à l'allocateur, les
+            // emplacements de swap sont libérés
+            let end = region.start_addr + region.size as u64;
+            crate::memory::vm::SWAP_DAEMON.lock().discard_range(region.start_addr, end);
+            if let Some(vm) = crate::memory::vm::VM_MANAGER.lock().as_mut() {
+                vm.kernel_space_mut().unmap_range(region.start_addr, region.size as u64);
+            }
+            crate::memory::vm::LRU_PAGE_CACHE.lock().remove_range(region.start_addr, end);
             
             if region.is_shared() {
                 self.shared_mappings = self.shared_mappings.saturating_sub(1);
             }
-            
             self.total_mappings = self.total_mappings.saturating_sub(1);
-            
-            Ok(())
-        } else {
-            Err(MmapError::NotFound)
         }
+        Ok(())
+    }
+    
+    /// Change les protections de `[addr, addr + size)`
+    ///
+    /// La plage doit être couverte par des régions existantes ; les régions
+    /// à cheval sont découpées. Les pages déjà présentes sont mises à jour
+    /// immédiatement, les autres recevront les nouveaux droits à la faute.
+    /// Seules les régions de `pid` peuvent être modifiées.
+    pub fn mprotect(&mut self, addr: VirtAddr, size: usize, prot: i32, pid: u64) -> Result<(), MmapError> {
+        validate_prot(prot)?;
+        let (start, end) = page_range(addr, size)?;
+        
+        // Toute la plage doit être mappée, sans trou, et appartenir à `pid`
+        let mut cursor = start;
+        while cursor < end {
+            let region = self.find_region(VirtAddr::new(cursor)).ok_or(MmapError::NotFound)?;
+            if region.owner_pid != pid {
+                return Err(MmapError::PermissionDenied);
+            }
+            cursor = region.start_addr.as_u64() + region.size as u64;
+        }
+        
+        self.split_at(start)?;
+        self.split_at(end)?;
+        
+        let keys: Vec<u64> = self.regions.range(start..end).map(|(k, _)| *k).collect();
+        for key in keys {
+            let region = self.regions.get_mut(&key).ok_or(MmapError::NotFound)?;
+            region.prot = prot;
+            let flags = region.page_flags();
+            if let Some(vm) = crate::memory::vm::VM_MANAGER.lock().as_mut() {
+                vm.kernel_space_mut().update_range_flags(region.start_addr, region.size as u64, flags);
+            }
+        }
+        Ok(())
+    }
+    
+    /// Coupe la région contenant `at` en deux (sans effet sur une frontière)
+    fn split_at(&mut self, at: u64) -> Result<(), MmapError> {
+        let key = match VirtAddr::try_new(at).ok().and_then(|addr| self.find_region(addr)) {
+            Some(region) if region.start_addr.as_u64() != at => region.start_addr.as_u64(),
+            _ => return Ok(()),
+        };
+        let region = self.regions.get_mut(&key).ok_or(MmapError::NotFound)?;
+        // Une huge page ne se découpe pas : frontières alignées sur 2 Mio
+        if region.huge_pages && at % HUGE_PAGE_SIZE as u64 != 0 {
+            return Err(MmapError::InvalidAddress);
+        }
+        
+        let head_size = (at - key) as usize;
+        let mut tail = region.clone();
+        tail.start_addr = VirtAddr::new(at);
+        tail.size = region.size - head_size;
+        if let MmapType::File { file_id, offset } = tail.mmap_type {
+            tail.mmap_type = MmapType::File { file_id, offset: offset + head_size as u64 };
+        }
+        region.size = head_size;
+        
+        if tail.is_shared() {
+            self.shared_mappings += 1;
+        }
+        self.total_mappings += 1;
+        self.regions.insert(at, tail);
+        Ok(())
+    }
+    
+    /// Régions recouvrant `[start, end)`, par adresse croissante
+    fn overlapping(&self, start: u64, end: u64) -> impl Iterator<Item = &MmapRegion> {
+        let first = self.regions
+            .range(..start)
+            .next_back()
+            .map(|(_, region)| region)
+            .filter(|region| region.start_addr.as_u64() + region.size as u64 > start);
+        first.into_iter().chain(self.regions.range(start..end).map(|(_, region)| region))
+    }
+    
+    /// Région contenant `addr`
+    pub fn find_region(&self, addr: VirtAddr) -> Option<&MmapRegion> {
+        self.regions
+            .range(..=addr.as_u64())
+            .next_back()
+            .map(|(_, region)| region)
+            .filter(|region| region.contains(addr))
     }
     
     /// Trouve une région libre de la taille demandée
-    fn find_free_region(&mut self, size: usize) -> Result<VirtAddr, MmapError> {
-        // Stratégie simple : utiliser next_virt_addr et l'incrémenter
-        let addr = self.next_virt_addr;
-        self.next_virt_addr = VirtAddr::new(self.next_virt_addr.as_u64() + size as u64);
-        Ok(addr)
+    fn find_free_region(&mut self, size: usize, align: usize, pid: u64) -> Result<VirtAddr, MmapError> {
+        // Première projection depuis le tirage de la base : écart aléatoire
+        if let Some(gap) = self.base_gaps.remove(&pid) {
+            self.next_virt_addr += gap;
+        }
+        // Stratégie simple : utiliser next_virt_addr (aligné) et l'incrémenter,
+        // en sautant les projections imposées par MAP_FIXED
+        let mut addr = self.next_virt_addr.as_u64().next_multiple_of(align as u64);
+        loop {
+            let end = addr.checked_add(size as u64)
+                .filter(|&end| end <= USER_SPACE_END)
+                .ok_or(MmapError::OutOfMemory)?;
+            match self.overlapping(addr, end).last() {
+                Some(region) => {
+                    addr = (region.start_addr.as_u64() + region.size as u64).next_multiple_of(align as u64);
+                }
+                None => {
+                    self.next_virt_addr = VirtAddr::new(end);
+                    return Ok(VirtAddr::new(addr));
+                }
+            }
+        }
     }
     
     /// Retourne les statistiques
@@ -304,6 +543,109 @@ mod tests {
         assert_eq!(manager.shared_mappings, 1);
     }
     
+    #[test_case]
+    fn test_mmap_hugetlb() {
+        let mut manager = MmapManager::new();
+        // Décale l'adresse suivante hors de l'alignement 2 Mio
+        manager.mmap(None, 4096, PROT_READ, MAP_PRIVATE | MAP_ANONYMOUS, None, 0, 1).unwrap();
+        
+        let addr = manager.mmap(
+            None,
+            3 * 1024 * 1024,
+            PROT_READ | PROT_WRITE,
+            MAP_PRIVATE | MAP_ANONYMOUS | MAP_HUGETLB,
+            None,
+            0,
+            1,
+        ).unwrap();
+        assert!(addr.is_aligned(HUGE_PAGE_SIZE as u64));
+        let region = &manager.regions[&addr.as_u64()];
+        assert!(region.huge_pages);
+        assert_eq!(region.size, 2 * HUGE_PAGE_SIZE);
+        
+        // MAP_HUGETLB n'a pas de sens pour un fichier
+        let result = manager.mmap(None, HUGE_PAGE_SIZE, PROT_READ, MAP_PRIVATE | MAP_HUGETLB, Some(3), 0, 1);
+        assert_eq!(result, Err(MmapError::InvalidFlags));
+    }
+    
+    #[test_case]
+    fn test_mmap_file_backed() {
+        use crate::fs::fd::{FD_MANAGER, OpenMode};
+        
+        let _ = crate::fs::vfs_mkdir("/tmp");
+        crate::fs::vfs_write_file("/tmp/mmap-test", b"contenu projete").unwrap();
+        let pid = 4242;
+        let fd = {
+            let mut fm = FD_MANAGER.lock();
+            let _ = fm.create_table(pid);
+            fm.get_table(pid).unwrap().open("/tmp/mmap-test", OpenMode::ReadOnly, 15).unwrap()
+        };
+        
+        let mut manager = MmapManager::new();
+        let addr = manager.mmap(None, 8192, PROT_READ, MAP_PRIVATE, Some(fd as u64), 0, pid).unwrap();
+        let region = manager.find_region(addr + 4100u64).unwrap();
+        assert_eq!(region.file_path.as_deref(), Some("/tmp/mmap-test"));
+        assert!(manager.find_region(addr + 8192u64).is_none());
+        
+        let mut buf = [0u8; 8];
+        assert_eq!(crate::fs::vfs_read_at("/tmp/mmap-test", 8, &mut buf), Ok(7));
+        assert_eq!(&buf[..7], b"projete");
+        
+        // Offset non aligné, descripteur inconnu
+        assert_eq!(manager.mmap(None, 4096, PROT_READ, MAP_PRIVATE, Some(fd as u64), 10, pid), Err(MmapError::InvalidAddress));
+        assert_eq!(manager.mmap(None, 4096, PROT_READ, MAP_PRIVATE, Some(99), 0, pid), Err(MmapError::InvalidFile));
+        let _ = FD_MANAGER.lock().remove_table(pid);
+    }
+    
+    #[test_case]
+    fn test_mprotect_splits_and_wx() {
+        let mut manager = MmapManager::new();
+        let addr = manager.mmap(None, 4 * 4096, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, None, 0, 1).unwrap();
+        
+        // W^X et bits inconnus
+        assert_eq!(manager.mprotect(addr, 4096, PROT_WRITE | PROT_EXEC, 1), Err(MmapError::PermissionDenied));
+        assert_eq!(manager.mprotect(addr, 4096, 0x80, 1), Err(MmapError::InvalidFlags));
+        assert_eq!(
+            manager.mmap(None, 4096, PROT_READ | PROT_WRITE | PROT_EXEC, MAP_PRIVATE | MAP_ANONYMOUS, None, 0, 1),
+            Err(MmapError::PermissionDenied)
+        );
+        
+        // Page du milieu passée en lecture/exécution : trois régions
+        manager.mprotect(addr + 4096u64, 2 * 4096, PROT_READ | PROT_EXEC, 1).unwrap();
+        assert_eq!(manager.total_mappings, 3);
+        let middle = manager.find_region(addr + 8192u64).unwrap();
+        assert_eq!((middle.start_addr, middle.size, middle.prot), (addr + 4096u64, 8192, PROT_READ | PROT_EXEC));
+        assert!(!middle.page_flags().contains(PageTableFlags::WRITABLE));
+        assert_eq!(manager.find_region(addr + 3 * 4096u64).unwrap().prot, PROT_READ | PROT_WRITE);
+        
+        // Plage non mappée
+        assert_eq!(manager.mprotect(addr + 3 * 4096u64, 2 * 4096, PROT_READ, 1), Err(MmapError::NotFound));
+        
+        // Région d'un autre processus, taille qui déborde
+        assert_eq!(manager.mprotect(addr, 4096, PROT_READ, 2), Err(MmapError::PermissionDenied));
+        assert_eq!(manager.find_region(addr).unwrap().prot, PROT_READ | PROT_WRITE);
+        assert_eq!(manager.mprotect(addr, usize::MAX, PROT_READ, 1), Err(MmapError::InvalidSize));
+        assert_eq!(manager.mprotect(VirtAddr::new(0x7fff_ffff_f000), 2 * 4096, PROT_READ, 1), Err(MmapError::InvalidSize));
+        
+        let none = MmapRegion::new(addr, 4096, PROT_NONE, MAP_PRIVATE | MAP_ANONYMOUS, MmapType::Anonymous, 1);
+        assert!(!none.page_flags().contains(PageTableFlags::USER_ACCESSIBLE));
+    }
+    
+    #[test_case]
+    fn test_mmap_randomized_base() {
+        let mut manager = MmapManager::new();
+        manager.base_gaps.insert(7, 64 * 4096);
+        let first = manager.mmap(None, 4096, PROT_READ, MAP_PRIVATE | MAP_ANONYMOUS, None, 0, 7).unwrap();
+        assert_eq!(first.as_u64(), 0x7000_0000_0000 + 64 * 4096);
+        // Écart consommé : les projections suivantes se suivent
+        let second = manager.mmap(None, 4096, PROT_READ, MAP_PRIVATE | MAP_ANONYMOUS, None, 0, 7).unwrap();
+        assert_eq!(second, first + 4096u64);
+        
+        manager.randomize_base(8);
+        manager.forget_base(8);
+        assert!(manager.base_gaps.is_empty());
+    }
+    
     #[test_case]
     fn test_munmap() {
         let mut manager = MmapManager::new();
@@ -317,8 +659,50 @@ mod tests {
             1,
         ).unwrap();
         
-        let result = manager.munmap(addr, 4096);
+        let result = manager.munmap(addr, 4096, 1);
         assert!(result.is_ok());
         assert_eq!(manager.total_mappings, 0);
     }
+    
+    #[test_case]
+    fn test_munmap_partial_and_owner() {
+        let mut manager = MmapManager::new();
+        let addr = manager.mmap(None, 3 * 4096, PROT_READ, MAP_PRIVATE | MAP_ANONYMOUS, None, 0, 1).unwrap();
+        
+        // Un autre processus ne démappe rien
+        assert_eq!(manager.munmap(addr, 4096, 2), Err(MmapError::PermissionDenied));
+        assert!(manager.find_region(addr).is_some());
+        
+        // Page du milieu seulement : les deux bords restent mappés
+        manager.munmap(addr + 4096u64, 4096, 1).unwrap();
+        assert!(manager.find_region(addr + 4096u64).is_none());
+        assert_eq!(manager.find_region(addr).unwrap().size, 4096);
+        assert_eq!(manager.find_region(addr + 2 * 4096u64).unwrap().size, 4096);
+        assert_eq!(manager.munmap(addr + 1u64, 4096, 1), Err(MmapError::InvalidAddress));
+    }
+    
+    #[test_case]
+    fn test_mmap_fixed_checks() {
+        let mut manager = MmapManager::new();
+        let fixed = MAP_PRIVATE | MAP_ANONYMOUS | MAP_FIXED;
+        let addr = VirtAddr::new(0x6000_0000_0000);
+        assert_eq!(manager.mmap(Some(addr), 2 * 4096, PROT_READ, fixed, None, 0, 1), Ok(addr));
+        
+        // Non alignée, chevauchante, hors de l'espace utilisateur
+        assert_eq!(manager.mmap(Some(addr + 8u64), 4096, PROT_READ, fixed, None, 0, 1), Err(MmapError::InvalidAddress));
+        assert_eq!(manager.mmap(Some(addr + 4096u64), 4096, PROT_READ, fixed, None, 0, 1), Err(MmapError::InvalidAddress));
+        assert_eq!(manager.mmap(Some(addr - 4096u64), 2 * 4096, PROT_READ, fixed, None, 0, 1), Err(MmapError::InvalidAddress));
+        let top = VirtAddr::new(USER_SPACE_END - 4096);
+        assert_eq!(manager.mmap(Some(top), 2 * 4096, PROT_READ, fixed, None, 0, 1), Err(MmapError::OutOfMemory));
+        let kernel = VirtAddr::new(0xffff_8000_0000_0000);
+        assert_eq!(manager.mmap(Some(kernel), 4096, PROT_READ, fixed, None, 0, 1), Err(MmapError::OutOfMemory));
+        assert_eq!(manager.total_mappings, 1);
+        
+        // Une région du noyau n'est jamais servie à la faute
+        let region = MmapRegion::new(kernel, 4096, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, MmapType::Anonymous, 1);
+        MMAP_MANAGER.lock().regions.insert(kernel.as_u64(), region);
+        let served = crate::memory::vm::handle_page_fault(kernel, true);
+        MMAP_MANAGER.lock().regions.remove(&kernel.as_u64());
+        assert!(!served);
+    }
 }
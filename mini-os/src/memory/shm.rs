@@ -2,11 +2,21 @@
 /// 
 /// Implémente la mémoire partagée POSIX pour la communication inter-processus (IPC).
 /// Permet à plusieurs processus de partager des segments de mémoire.
+///
+/// Les cadres d'un segment sont alloués une seule fois, au premier shmat,
+/// puis projetés dans chaque attachement avec les droits de celui-ci
+/// (SHM_RDONLY, SHM_EXEC). COW_MANAGER compte les projections de chaque
+/// cadre et sait qu'un cadre de segment ne se copie jamais. IPC_RMID
+/// retire la clé tout de suite ; les cadres sont rendus au dernier shmdt.
 
 use alloc::collections::BTreeMap;
 use alloc::vec::Vec;
 use spin::Mutex;
 use x86_64::{PhysAddr, VirtAddr};
+use x86_64::structures::paging::{FrameAllocator, FrameDeallocator, Page, PhysFrame};
+use crate::memory::frame::GlobalFrameAllocator;
+use crate::memory::vm::{protection_flags, COW_MANAGER, VM_MANAGER};
+use crate::process::cred::{Credentials, CAP_IPC_OWNER};
 
 /// Clé spéciale pour créer un segment privé
 pub const IPC_PRIVATE: i32 = 0;
@@ -15,6 +25,14 @@ pub const IPC_PRIVATE: i32 = 0;
 pub const IPC_CREAT: i32 = 0o1000;  // Créer si n'existe pas
 pub const IPC_EXCL: i32 = 0o2000;   // Échouer si existe déjà
 
+/// Flags pour shmat
+pub const SHM_RDONLY: i32 = 0o10000;  // Attachement en lecture seule
+pub const SHM_EXEC: i32 = 0o100000;   // Attachement exécutable
+
+/// Zone où shmat place les attachements sans adresse imposée
+pub const SHM_BASE: u64 = 0x7000_0000_0000;
+const PAGE_SIZE: u64 = 4096;
+
 /// Commandes pour shmctl
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ShmCmd {
@@ -68,6 +86,10 @@ pub struct SharedMemorySegment {
     pub last_attach: u64,
     /// Timestamp de dernière détache
     pub last_detach: u64,
+    /// Cadres physiques, alloués au premier attachement
+    pub frames: Vec<PhysFrame>,
+    /// IPC_RMID demandé : détruit au dernier détachement
+    pub removed: bool,
 }
 
 impl SharedMemorySegment {
@@ -82,21 +104,28 @@ impl SharedMemorySegment {
             owner_gid: gid,
             permissions,
             attached_count: 0,
-            created_at: 0, // TODO: obtenir timestamp réel
+            created_at: crate::time::unix_time(),
             last_attach: 0,
             last_detach: 0,
+            frames: Vec::new(),
+            removed: false,
         }
     }
+
+    /// Nombre de pages du segment
+    pub fn page_count(&self) -> usize {
+        (self.size + PAGE_SIZE as usize - 1) / PAGE_SIZE as usize
+    }
     
     /// Vérifie si un processus a la permission d'accéder au segment
-    pub fn check_permission(&self, uid: u32, gid: u32, write: bool) -> bool {
-        // Propriétaire a tous les droits
-        if uid == self.owner_uid {
+    pub fn check_permission(&self, cred: &Credentials, write: bool) -> bool {
+        // CAP_IPC_OWNER et le propriétaire ont tous les droits
+        if cred.capable(CAP_IPC_OWNER) || cred.euid == self.owner_uid {
             return true;
         }
         
-        // Vérifier les permissions du groupe
-        if gid == self.owner_gid {
+        // Vérifier les permissions du groupe (effectif ou supplémentaire)
+        if cred.in_group(self.owner_gid) {
             let group_perms = (self.permissions >> 3) & 0o7;
             if write {
                 return (group_perms & 0o2) != 0; // Write permission
@@ -115,10 +144,23 @@ impl SharedMemorySegment {
     }
 }
 
+/// Segment projeté dans un processus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShmAttachment {
+    pub segment: i32,
+    pub pid: u64,
+    pub addr: VirtAddr,
+    pub size: u64,
+    pub writable: bool,
+    pub executable: bool,
+}
+
 /// Gestionnaire de mémoire partagée
 pub struct ShmManager {
     /// Segments indexés par ID
     segments: BTreeMap<i32, SharedMemorySegment>,
+    /// Attachements par (PID, adresse)
+    attachments: BTreeMap<(u64, u64), ShmAttachment>,
     /// Mapping clé → ID
     key_to_id: BTreeMap<i32, i32>,
     /// Prochain ID disponible
@@ -132,6 +174,7 @@ impl ShmManager {
     pub const fn new() -> Self {
         Self {
             segments: BTreeMap::new(),
+            attachments: BTreeMap::new(),
             key_to_id: BTreeMap::new(),
             next_id: 1,
             max_segments: 128,
@@ -144,9 +187,12 @@ impl ShmManager {
     /// * `key` - Clé IPC (IPC_PRIVATE pour segment privé)
     /// * `size` - Taille en bytes
     /// * `flags` - Flags (IPC_CREAT, IPC_EXCL, permissions)
-    /// * `uid` - UID du processus appelant
-    /// * `gid` - GID du processus appelant
-    pub fn shmget(&mut self, key: i32, size: usize, flags: i32, uid: u32, gid: u32) -> Result<i32, ShmError> {
+    /// * `cred` - Identités du processus appelant (UID/GID effectifs propriétaires)
+    pub fn shmget(&mut self, key: i32, size: usize, flags: i32, cred: &Credentials) -> Result<i32, ShmError> {
+        let (uid, gid) = (cred.euid, cred.egid);
+        if size == 0 {
+            return Err(ShmError::InvalidArgument);
+        }
         // Vérifier le nombre de segments
         if self.segments.len() >= self.max_segments {
             return Err(ShmError::TooManySegments);
@@ -163,6 +209,9 @@ impl ShmManager {
             if (flags & IPC_EXCL) != 0 {
                 return Err(ShmError::AlreadyExists);
             }
+            if self.segments.get(&existing_id).map_or(false, |s| size > s.size) {
+                return Err(ShmError::InvalidArgument);
+            }
             return Ok(existing_id);
         }
         
@@ -176,9 +225,8 @@ impl ShmManager {
     
     /// Crée un nouveau segment
     fn create_segment(&mut self, key: i32, size: usize, flags: i32, uid: u32, gid: u32) -> Result<i32, ShmError> {
-        // Allouer de la mémoire physique pour le segment
-        // TODO: utiliser un vrai allocateur de pages
-        let phys_addr = self.allocate_physical_memory(size)?;
+        // Les cadres sont alloués au premier shmat
+        let phys_addr = PhysAddr::new(0);
         
         let id = self.next_id;
         self.next_id += 1;
@@ -200,27 +248,55 @@ impl ShmManager {
     /// 
     /// # Arguments
     /// * `id` - ID du segment
-    /// * `addr` - Adresse virtuelle souhaitée (None = auto)
-    /// * `uid` - UID du processus
-    /// * `gid` - GID du processus
-    pub fn shmat(&mut self, id: i32, addr: Option<VirtAddr>, uid: u32, gid: u32) -> Result<VirtAddr, ShmError> {
-        let segment = self.segments.get_mut(&id).ok_or(ShmError::NotFound)?;
+    /// * `addr` - Adresse virtuelle souhaitée, alignée sur une page (None = auto)
+    /// * `flags` - SHM_RDONLY, SHM_EXEC
+    /// * `pid` - Processus qui s'attache
+    /// * `cred` - Identités du processus
+    pub fn shmat(&mut self, id: i32, addr: Option<VirtAddr>, flags: i32, pid: u64, cred: &Credentials) -> Result<VirtAddr, ShmError> {
+        let segment = self.segments.get(&id).ok_or(ShmError::NotFound)?;
+        let writable = (flags & SHM_RDONLY) == 0;
+        let executable = (flags & SHM_EXEC) != 0;
         
-        // Vérifier les permissions
-        if !segment.check_permission(uid, gid, false) {
+        // Lecture toujours requise, écriture sauf SHM_RDONLY
+        if !segment.check_permission(cred, false) || (writable && !segment.check_permission(cred, true)) {
+            return Err(ShmError::PermissionDenied);
+        }
+        if segment.removed {
+            return Err(ShmError::NotFound);
+        }
+        // W^X : un attachement exécutable doit être en lecture seule
+        if writable && executable {
             return Err(ShmError::PermissionDenied);
         }
         
-        // Déterminer l'adresse virtuelle
-        let virt_addr = addr.unwrap_or_else(|| {
-            // TODO: trouver une adresse libre dans l'espace d'adressage
-            VirtAddr::new(0x7000_0000_0000) // Placeholder
-        });
+        let size = segment.page_count() as u64 * PAGE_SIZE;
+        let virt_addr = match addr {
+            Some(addr) if !addr.is_aligned(PAGE_SIZE) => return Err(ShmError::InvalidArgument),
+            Some(addr) if self.overlaps(addr, size) => return Err(ShmError::InvalidArgument),
+            Some(addr) => addr,
+            None => self.find_free_range(size),
+        };
         
-        // TODO: mapper les pages physiques dans l'espace d'adressage du processus
+        self.ensure_frames(id)?;
+        let segment = self.segments.get_mut(&id).ok_or(ShmError::NotFound)?;
+        map_frames(&segment.frames, virt_addr, writable, executable)?;
+        {
+            let mut cow = COW_MANAGER.lock();
+            for &frame in &segment.frames {
+                cow.share_shm_page(frame);
+            }
+        }
         
         segment.attached_count += 1;
-        segment.last_attach = 0; // TODO: timestamp réel
+        segment.last_attach = crate::time::unix_time();
+        self.attachments.insert((pid, virt_addr.as_u64()), ShmAttachment {
+            segment: id,
+            pid,
+            addr: virt_addr,
+            size,
+            writable,
+            executable,
+        });
         
         Ok(virt_addr)
     }
@@ -228,24 +304,72 @@ impl ShmManager {
     /// Détache un segment de l'espace d'adressage du processus
     /// 
     /// # Arguments
-    /// * `addr` - Adresse virtuelle du segment
-    pub fn shmdt(&mut self, addr: VirtAddr) -> Result<(), ShmError> {
-        // TODO: trouver le segment correspondant à cette adresse
-        // TODO: unmapper les pages
-        // TODO: décrémenter attached_count
+    /// * `addr` - Adresse virtuelle du segment (rendue par shmat)
+    /// * `pid` - Processus attaché
+    pub fn shmdt(&mut self, addr: VirtAddr, pid: u64) -> Result<(), ShmError> {
+        let attachment = self.attachments.remove(&(pid, addr.as_u64())).ok_or(ShmError::InvalidArgument)?;
+        let segment = self.segments.get_mut(&attachment.segment).ok_or(ShmError::NotFound)?;
         
-        // Pour l'instant, juste un placeholder
+        unmap_frames(&segment.frames, attachment.addr);
+        {
+            let mut cow = COW_MANAGER.lock();
+            for &frame in &segment.frames {
+                let _ = cow.unshare_page(frame);
+            }
+        }
+        
+        segment.attached_count = segment.attached_count.saturating_sub(1);
+        segment.last_detach = crate::time::unix_time();
+        if segment.removed && segment.attached_count == 0 {
+            self.destroy(attachment.segment);
+        }
         Ok(())
     }
     
+    /// Détache tous les segments d'un processus (fin du processus)
+    pub fn detach_all(&mut self, pid: u64) {
+        let addrs: Vec<VirtAddr> = self.attachments.values()
+            .filter(|a| a.pid == pid)
+            .map(|a| a.addr)
+            .collect();
+        for addr in addrs {
+            let _ = self.shmdt(addr, pid);
+        }
+    }
+    
+    /// L'enfant d'un fork hérite des attachements du parent ; l'espace
+    /// d'adressage étant commun, les projections existent déjà
+    pub fn fork(&mut self, parent: u64, child: u64) {
+        let inherited: Vec<ShmAttachment> = self.attachments.values()
+            .filter(|a| a.pid == parent)
+            .copied()
+            .collect();
+        for attachment in inherited {
+            if let Some(segment) = self.segments.get_mut(&attachment.segment) {
+                segment.attached_count += 1;
+                let mut cow = COW_MANAGER.lock();
+                for &frame in &segment.frames {
+                    cow.share_shm_page(frame);
+                }
+            }
+            self.attachments.insert((child, attachment.addr.as_u64()), ShmAttachment { pid: child, ..attachment });
+        }
+    }
+    
+    /// Attachements d'un processus
+    pub fn attachments(&self, pid: u64) -> Vec<ShmAttachment> {
+        self.attachments.values().filter(|a| a.pid == pid).copied().collect()
+    }
+    
     /// Contrôle un segment (stats, delete, etc.)
     /// 
     /// # Arguments
     /// * `id` - ID du segment
     /// * `cmd` - Commande à exécuter
-    /// * `uid` - UID du processus
-    pub fn shmctl(&mut self, id: i32, cmd: ShmCmd, uid: u32) -> Result<Option<SharedMemorySegment>, ShmError> {
+    /// * `cred` - Identités du processus
+    pub fn shmctl(&mut self, id: i32, cmd: ShmCmd, cred: &Credentials) -> Result<Option<SharedMemorySegment>, ShmError> {
         let segment = self.segments.get(&id).ok_or(ShmError::NotFound)?;
+        let is_owner = cred.capable(CAP_IPC_OWNER) || cred.euid == segment.owner_uid;
         
         match cmd {
             ShmCmd::IpcStat => {
@@ -256,7 +380,7 @@ impl ShmManager {
             ShmCmd::IpcSet => {
                 // Modifier les informations (permissions, etc.)
                 // Seul le propriétaire peut modifier
-                if uid != segment.owner_uid {
+                if !is_owner {
                     return Err(ShmError::PermissionDenied);
                 }
                 // TODO: implémenter la modification
@@ -264,38 +388,87 @@ impl ShmManager {
             }
             
             ShmCmd::IpcRmid => {
-                // Supprimer le segment
                 // Seul le propriétaire peut supprimer
-                if uid != segment.owner_uid {
+                if !is_owner {
                     return Err(ShmError::PermissionDenied);
                 }
                 
-                // Vérifier qu'aucun processus n'est attaché
-                if segment.attached_count > 0 {
-                    return Err(ShmError::InvalidArgument);
-                }
-                
-                // Cloner les données nécessaires avant de supprimer
-                let key = segment.key;
-                
-                // Supprimer le segment
-                self.segments.remove(&id);
+                // La clé est libérée tout de suite ; le segment vit
+                // jusqu'au dernier détachement
+                let (key, attached) = (segment.key, segment.attached_count);
                 if key != IPC_PRIVATE {
                     self.key_to_id.remove(&key);
                 }
-                
-                // TODO: libérer la mémoire physique
+                if attached == 0 {
+                    self.destroy(id);
+                } else if let Some(segment) = self.segments.get_mut(&id) {
+                    segment.removed = true;
+                }
                 
                 Ok(None)
             }
         }
     }
     
-    /// Alloue de la mémoire physique pour un segment
-    fn allocate_physical_memory(&self, size: usize) -> Result<PhysAddr, ShmError> {
-        // TODO: utiliser le Buddy Allocator ou un allocateur de pages
-        // Pour l'instant, retourner une adresse placeholder
-        Ok(PhysAddr::new(0x1000_0000))
+    /// Alloue (une seule fois) et met à zéro les cadres d'un segment
+    fn ensure_frames(&mut self, id: i32) -> Result<(), ShmError> {
+        let segment = self.segments.get_mut(&id).ok_or(ShmError::NotFound)?;
+        if !segment.frames.is_empty() {
+            return Ok(());
+        }
+        let mut frames = Vec::with_capacity(segment.page_count());
+        for _ in 0..segment.page_count() {
+            match GlobalFrameAllocator.allocate_frame() {
+                Some(frame) => {
+                    // Mémoire physique projetée à l'identique
+                    unsafe { core::ptr::write_bytes(frame.start_address().as_u64() as *mut u8, 0, PAGE_SIZE as usize) };
+                    frames.push(frame);
+                }
+                None => {
+                    free_frames(&frames);
+                    return Err(ShmError::OutOfMemory);
+                }
+            }
+        }
+        segment.phys_addr = frames[0].start_address();
+        segment.frames = frames;
+        Ok(())
+    }
+    
+    /// Supprime un segment et rend ses cadres
+    fn destroy(&mut self, id: i32) {
+        if let Some(segment) = self.segments.remove(&id) {
+            if segment.key != IPC_PRIVATE && self.key_to_id.get(&segment.key) == Some(&id) {
+                self.key_to_id.remove(&segment.key);
+            }
+            free_frames(&segment.frames);
+        }
+    }
+    
+    /// `[addr, addr + size)` chevauche-t-il un attachement ?
+    fn overlaps(&self, addr: VirtAddr, size: u64) -> bool {
+        let (start, end) = (addr.as_u64(), addr.as_u64() + size);
+        self.attachments.values().any(|a| start < a.addr.as_u64() + a.size && a.addr.as_u64() < end)
+    }
+    
+    /// Première plage libre à partir de SHM_BASE (l'espace d'adressage
+    /// est commun : les attachements de tous les processus comptent)
+    fn find_free_range(&self, size: u64) -> VirtAddr {
+        let mut ranges: Vec<(u64, u64)> = self.attachments.values()
+            .map(|a| (a.addr.as_u64(), a.addr.as_u64() + a.size))
+            .collect();
+        ranges.sort_unstable();
+        let mut candidate = SHM_BASE;
+        for (start, end) in ranges {
+            if end <= candidate {
+                continue;
+            }
+            if start >= candidate + size {
+                break;
+            }
+            candidate = end;
+        }
+        VirtAddr::new(candidate)
     }
     
     /// Retourne les statistiques
@@ -314,6 +487,40 @@ pub struct ShmStats {
     pub total_attached: usize,
 }
 
+/// Projette les cadres d'un segment à partir de `addr`
+fn map_frames(frames: &[PhysFrame], addr: VirtAddr, writable: bool, executable: bool) -> Result<(), ShmError> {
+    let mut vm = VM_MANAGER.lock();
+    let space = vm.as_mut().ok_or(ShmError::OutOfMemory)?.kernel_space_mut();
+    let flags = protection_flags(writable, executable, true);
+    for (i, &frame) in frames.iter().enumerate() {
+        let page = Page::containing_address(addr + i as u64 * PAGE_SIZE);
+        if space.map_frame(page, frame, flags).is_err() {
+            // Défaire les projections déjà faites
+            for j in 0..i {
+                let _ = space.unmap_frame(Page::containing_address(addr + j as u64 * PAGE_SIZE));
+            }
+            return Err(ShmError::InvalidArgument);
+        }
+    }
+    Ok(())
+}
+
+/// Retire les projections d'un attachement ; les cadres restent au segment
+fn unmap_frames(frames: &[PhysFrame], addr: VirtAddr) {
+    if let Some(vm) = VM_MANAGER.lock().as_mut() {
+        let space = vm.kernel_space_mut();
+        for i in 0..frames.len() {
+            let _ = space.unmap_frame(Page::containing_address(addr + i as u64 * PAGE_SIZE));
+        }
+    }
+}
+
+fn free_frames(frames: &[PhysFrame]) {
+    for &frame in frames {
+        unsafe { GlobalFrameAllocator.deallocate_frame(frame) };
+    }
+}
+
 /// Instance globale du gestionnaire SHM
 pub static SHM_MANAGER: Mutex<ShmManager> = Mutex::new(ShmManager::new());
 
@@ -324,15 +531,16 @@ mod tests {
     #[test_case]
     fn test_shmget_create() {
         let mut manager = ShmManager::new();
-        let id = manager.shmget(1234, 4096, IPC_CREAT | 0o666, 1000, 1000);
+        let id = manager.shmget(1234, 4096, IPC_CREAT | 0o666, &Credentials::new(1000, 1000));
         assert!(id.is_ok());
     }
     
     #[test_case]
     fn test_shmget_exclusive() {
         let mut manager = ShmManager::new();
-        let _ = manager.shmget(1234, 4096, IPC_CREAT | 0o666, 1000, 1000);
-        let result = manager.shmget(1234, 4096, IPC_CREAT | IPC_EXCL | 0o666, 1000, 1000);
+        let cred = Credentials::new(1000, 1000);
+        let _ = manager.shmget(1234, 4096, IPC_CREAT | 0o666, &cred);
+        let result = manager.shmget(1234, 4096, IPC_CREAT | IPC_EXCL | 0o666, &cred);
         assert_eq!(result, Err(ShmError::AlreadyExists));
     }
     
@@ -341,15 +549,42 @@ mod tests {
         let segment = SharedMemorySegment::new(1, 1234, 4096, PhysAddr::new(0), 1000, 1000, 0o644);
         
         // Propriétaire peut tout faire
-        assert!(segment.check_permission(1000, 1000, true));
+        assert!(segment.check_permission(&Credentials::new(1000, 1000), true));
         
         // Groupe peut lire
-        assert!(segment.check_permission(1001, 1000, false));
+        assert!(segment.check_permission(&Credentials::new(1001, 1000), false));
         
         // Groupe ne peut pas écrire
-        assert!(!segment.check_permission(1001, 1000, true));
+        assert!(!segment.check_permission(&Credentials::new(1001, 1000), true));
         
         // Autres peuvent lire
-        assert!(segment.check_permission(1001, 1001, false));
+        assert!(segment.check_permission(&Credentials::new(1001, 1001), false));
+
+        // Écriture par un tiers : CAP_IPC_OWNER requis, même pour root
+        let mut root = Credentials::root();
+        assert!(segment.check_permission(&root, true));
+        root.drop_caps(CAP_IPC_OWNER);
+        assert!(!segment.check_permission(&root, true));
+    }
+
+    #[test_case]
+    fn test_rmid_deferred_until_detach() {
+        let mut manager = ShmManager::new();
+        let owner = Credentials::new(1000, 1000);
+        let id = manager.shmget(42, 8192, IPC_CREAT | 0o600, &owner).unwrap();
+        assert_eq!(manager.segments[&id].page_count(), 2);
+        
+        // Segment encore attaché : la clé disparaît, le segment reste
+        manager.segments.get_mut(&id).unwrap().attached_count = 1;
+        assert!(manager.shmctl(id, ShmCmd::IpcRmid, &Credentials::new(1001, 1000)).is_err());
+        manager.shmctl(id, ShmCmd::IpcRmid, &owner).unwrap();
+        assert!(manager.segments[&id].removed);
+        assert_eq!(manager.shmget(42, 8192, 0, &owner), Err(ShmError::NotFound));
+        
+        // Attachement en écriture refusé à qui n'a que la lecture
+        let other = manager.shmget(43, 4096, IPC_CREAT | 0o644, &owner).unwrap();
+        assert_eq!(manager.shmat(other, None, 0, 7, &Credentials::new(1001, 1001)), Err(ShmError::PermissionDenied));
+        assert_eq!(manager.shmat(other, Some(VirtAddr::new(SHM_BASE + 1)), SHM_RDONLY, 7, &owner), Err(ShmError::InvalidArgument));
+        assert_eq!(manager.find_free_range(4096), VirtAddr::new(SHM_BASE));
     }
 }
@@ -270,6 +270,21 @@ impl SlabCache {
     }
 }
 
+impl SlabCache {
+    /// Objets par slab (la page commence par l'en-tête `Slab`)
+    fn objects_per_slab(&self) -> usize {
+        (SLAB_PAGE_SIZE - core::mem::size_of::<Slab>()) / self.object_size
+    }
+    
+    fn total_objects(&self) -> usize {
+        self.slab_count * self.objects_per_slab()
+    }
+    
+    fn active_objects(&self) -> usize {
+        self.total_allocations - self.total_deallocations
+    }
+}
+
 // SAFETY: SlabCache est uniquement accédé via un Mutex
 unsafe impl Send for SlabCache {}
 unsafe impl Sync for SlabCache {}
@@ -330,10 +345,26 @@ impl SlabAllocator {
             stats.total_slabs += cache.slab_count;
             stats.total_allocations += cache.total_allocations;
             stats.total_deallocations += cache.total_deallocations;
+            stats.active_objects += cache.active_objects();
+            stats.total_objects += cache.total_objects();
         }
+        stats.bytes = stats.total_slabs * SLAB_PAGE_SIZE;
         
         stats
     }
+    
+    /// Statistiques de chaque cache, par taille d'objet croissante
+    pub fn cache_stats(&self) -> [SlabCacheStats; 5] {
+        core::array::from_fn(|i| {
+            let cache = &self.caches[i];
+            SlabCacheStats {
+                object_size: cache.object_size,
+                slabs: cache.slab_count,
+                active_objects: cache.active_objects(),
+                total_objects: cache.total_objects(),
+            }
+        })
+    }
 }
 
 /// Statistiques du SLAB allocator
@@ -342,6 +373,21 @@ pub struct SlabStats {
     pub total_slabs: usize,
     pub total_allocations: usize,
     pub total_deallocations: usize,
+    /// Objets alloués et non libérés
+    pub active_objects: usize,
+    /// Capacité des slabs existants, en objets
+    pub total_objects: usize,
+    /// Pages occupées par les slabs, en octets
+    pub bytes: usize,
+}
+
+/// Statistiques d'un cache SLAB
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SlabCacheStats {
+    pub object_size: usize,
+    pub slabs: usize,
+    pub active_objects: usize,
+    pub total_objects: usize,
 }
 
 /// Instance globale du SLAB allocator
@@ -405,4 +451,23 @@ mod tests {
             assert_eq!(ptr1, ptr2);
         }
     }
+    
+    #[test_case]
+    fn test_slab_stats_track_active_objects() {
+        unsafe {
+            let mut allocator = SlabAllocator::new();
+            let layout = Layout::from_size_align_unchecked(32, 8);
+            let a = allocator.alloc(layout);
+            let b = allocator.alloc(layout);
+            assert!(allocator.dealloc(a, layout));
+            
+            let stats = allocator.get_stats();
+            assert_eq!(stats.active_objects, 1);
+            assert_eq!(stats.bytes, stats.total_slabs * SLAB_PAGE_SIZE);
+            let cache = allocator.cache_stats()[0];
+            assert_eq!((cache.object_size, cache.active_objects), (32, 1));
+            assert!(cache.total_objects >= cache.active_objects);
+            assert!(allocator.dealloc(b, layout));
+        }
+    }
 }
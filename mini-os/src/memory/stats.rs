@@ -0,0 +1,100 @@
+/// Statistiques mémoire globales
+///
+/// `mem_stats` relève en une fois les compteurs de l'allocateur de cadres
+/// (mémoire physique), du tas (buddy + SLAB), du page cache et du buffer
+/// cache, et du swap. /proc/meminfo, `free` et `vmstat` en dérivent tous
+/// leurs valeurs : une fuite se voit à `heap_used` ou aux objets SLAB
+/// actifs qui montent sans redescendre.
+///
+/// Les tailles sont en octets.
+
+use crate::fs::cache::BUFFER_CACHE;
+use crate::memory::frame::{FRAME_ALLOCATOR, FRAME_SIZE};
+use crate::memory::vm::swap::SWAP_SLOT_SIZE;
+use crate::memory::vm::{PAGE_CACHE, SWAP_DAEMON};
+use crate::memory::HYBRID_ALLOCATOR;
+
+/// Instantané des compteurs mémoire
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemStats {
+    /// Mémoire physique gérée par l'allocateur de cadres
+    pub total: u64,
+    pub free: u64,
+    /// Tas noyau : taille, occupation courante et maximale
+    pub heap_total: u64,
+    pub heap_used: u64,
+    pub heap_peak: u64,
+    /// Allocations et libérations du tas depuis le démarrage
+    pub allocations: u64,
+    pub deallocations: u64,
+    /// Pages des caches SLAB et objets encore alloués
+    pub slab: u64,
+    pub slab_active_objects: u64,
+    /// Données de fichiers en cache (page cache + buffer cache), dont modifiées
+    pub cached: u64,
+    pub dirty: u64,
+    /// Pages en swap et pages échangées depuis le démarrage
+    pub swap_used: u64,
+    pub pages_swapped_in: u64,
+    pub pages_swapped_out: u64,
+}
+
+impl MemStats {
+    pub fn used(&self) -> u64 {
+        self.total.saturating_sub(self.free)
+    }
+
+    pub fn heap_free(&self) -> u64 {
+        self.heap_total.saturating_sub(self.heap_used)
+    }
+
+    /// Mémoire récupérable sans swap : cadres libres et cache propre
+    pub fn available(&self) -> u64 {
+        self.free + self.cached.saturating_sub(self.dirty)
+    }
+}
+
+/// Relève les statistiques mémoire courantes
+pub fn mem_stats() -> MemStats {
+    let (total_frames, free_frames) = {
+        let frames = FRAME_ALLOCATOR.lock();
+        (frames.total_frames() as u64, frames.free_frames() as u64)
+    };
+    let heap = HYBRID_ALLOCATOR.get_stats();
+    let pages = PAGE_CACHE.lock().get_stats();
+    let buffers = BUFFER_CACHE.lock().get_stats();
+    let swap = SWAP_DAEMON.lock().get_stats();
+
+    MemStats {
+        total: total_frames * FRAME_SIZE,
+        free: free_frames * FRAME_SIZE,
+        heap_total: heap.buddy.heap_size as u64,
+        heap_used: heap.buddy.current_memory_usage as u64,
+        heap_peak: heap.buddy.peak_memory_usage as u64,
+        allocations: heap.total_allocations() as u64,
+        deallocations: heap.total_deallocations() as u64,
+        slab: heap.slab.bytes as u64,
+        slab_active_objects: heap.slab.active_objects as u64,
+        cached: (pages.cached_bytes + buffers.cached_bytes) as u64,
+        dirty: (pages.dirty_bytes + buffers.dirty_bytes) as u64,
+        swap_used: (swap.total_swap_entries * SWAP_SLOT_SIZE) as u64,
+        pages_swapped_in: swap.pages_swapped_in as u64,
+        pages_swapped_out: swap.pages_swapped_out as u64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_mem_stats_derived_values() {
+        let stats = MemStats { total: 1000, free: 300, heap_total: 64, heap_used: 80, cached: 200, dirty: 50, ..Default::default() };
+        assert_eq!(stats.used(), 700);
+        assert_eq!(stats.heap_free(), 0);
+        assert_eq!(stats.available(), 450);
+
+        let live = mem_stats();
+        assert!(live.free <= live.total && live.dirty <= live.cached);
+    }
+}
@@ -0,0 +1,318 @@
+/// Accès du noyau à la mémoire utilisateur (SMEP/SMAP)
+///
+/// SMEP (CR4.SMEP) interdit au noyau d'exécuter une page utilisateur ;
+/// SMAP (CR4.SMAP) lui interdit d'y lire ou d'y écrire tant que RFLAGS.AC
+/// est baissé. Les appels système ne touchent donc la mémoire de
+/// l'appelant qu'à travers `copy_from_user` / `copy_to_user` (et
+/// `read_user`, `write_user`, `read_user_cstr`), qui encadrent la copie
+/// de `stac` / `clac`. L'entrée SYSCALL masque AC : un programme ne peut
+/// pas ouvrir la fenêtre pour le noyau.
+///
+/// Quand l'appel vient d'un programme ring 3, chaque page de l'intervalle
+/// doit être présente et accessible en mode utilisateur (et inscriptible
+/// pour une écriture) ; une page paresseuse ou évincée est d'abord chargée.
+/// Le noyau partage la moitié basse de l'espace d'adressage (tas, image,
+/// piles noyau) mais ne la mappe jamais USER : un pointeur vers elle est
+/// refusé (EFAULT). Les appels émis par le noyau lui-même (shell) passent
+/// des tampons noyau et ne subissent que la vérification de l'intervalle.
+///
+/// La copie elle-même passe par `uaccess_copy`, inscrite dans la table
+/// d'exceptions : une faute de page pendant la copie (page retirée entre
+/// la vérification et l'accès) reprend à son point de reprise, et la
+/// copie rend `Fault` au lieu de faire paniquer le noyau.
+///
+/// Les deux protections s'activent par CPU (`init_cpu`) si le processeur
+/// les offre ; `nosmep` / `nosmap` sur la ligne de commande les laissent
+/// éteintes.
+
+use alloc::vec::Vec;
+use core::arch::global_asm;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicBool, Ordering};
+use x86_64::registers::control::{Cr4, Cr4Flags};
+use x86_64::structures::paging::PageTableFlags;
+use x86_64::VirtAddr;
+use crate::cpuid::{cpu_has, Feature};
+
+/// Fin (exclue) des adresses acceptées depuis l'espace utilisateur
+pub const USER_SPACE_END: u64 = 0x0000_8000_0000_0000;
+
+const PAGE_SIZE: u64 = 4096;
+
+/// SMEP / SMAP demandés (ligne de commande)
+static SMEP_WANTED: AtomicBool = AtomicBool::new(true);
+static SMAP_WANTED: AtomicBool = AtomicBool::new(true);
+/// SMAP actif : `stac` / `clac` nécessaires (et disponibles)
+static SMAP_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Adresse utilisateur invalide (EFAULT)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fault;
+
+/// Choisit SMEP / SMAP avant `init_cpu` (`nosmep`, `nosmap`)
+pub fn configure(smep: bool, smap: bool) {
+    SMEP_WANTED.store(smep, Ordering::SeqCst);
+    SMAP_WANTED.store(smap, Ordering::SeqCst);
+}
+
+/// Active SMEP / SMAP sur le CPU courant (BSP puis chaque AP)
+pub fn init_cpu() {
+    let smep = SMEP_WANTED.load(Ordering::SeqCst) && cpu_has(Feature::Smep);
+    let smap = SMAP_WANTED.load(Ordering::SeqCst) && cpu_has(Feature::Smap);
+    unsafe {
+        Cr4::update(|cr4| {
+            cr4.set(Cr4Flags::SUPERVISOR_MODE_EXECUTION_PROTECTION, smep);
+            cr4.set(Cr4Flags::SUPERVISOR_MODE_ACCESS_PREVENTION, smap);
+        });
+    }
+    SMAP_ACTIVE.store(smap, Ordering::SeqCst);
+}
+
+pub fn smep_enabled() -> bool {
+    Cr4::read().contains(Cr4Flags::SUPERVISOR_MODE_EXECUTION_PROTECTION)
+}
+
+pub fn smap_enabled() -> bool {
+    SMAP_ACTIVE.load(Ordering::Relaxed)
+}
+
+/// L'intervalle `[addr, addr + len)` est-il dans la moitié basse ?
+fn range_ok(addr: u64, len: usize) -> bool {
+    addr != 0 && addr.checked_add(len as u64).map_or(false, |end| end <= USER_SPACE_END)
+}
+
+/// Droits d'une page acceptables pour un accès utilisateur
+fn user_page_ok(flags: PageTableFlags, write: bool) -> bool {
+    flags.contains(PageTableFlags::PRESENT | PageTableFlags::USER_ACCESSIBLE)
+        && (!write || flags.contains(PageTableFlags::WRITABLE))
+}
+
+/// Droits de la page contenant `addr` (None : non mappée)
+fn page_flags(addr: u64) -> Option<PageTableFlags> {
+    let vm = super::vm::VM_MANAGER.lock();
+    vm.as_ref()?.kernel_space().page_flags(VirtAddr::new(addr))
+}
+
+/// Chaque page de l'intervalle est-elle accessible au programme ? Une
+/// page absente est d'abord proposée au chargement à la demande.
+fn user_pages_ok(addr: u64, len: usize, write: bool) -> bool {
+    let end = addr + len as u64;
+    let mut page = addr & !(PAGE_SIZE - 1);
+    while page < end {
+        let flags = match page_flags(page) {
+            Some(flags) => Some(flags),
+            None if super::vm::handle_page_fault(VirtAddr::new(page), write) => page_flags(page),
+            None => None,
+        };
+        if !flags.map_or(false, |flags| user_page_ok(flags, write)) {
+            return false;
+        }
+        page += PAGE_SIZE;
+    }
+    true
+}
+
+/// `[addr, addr + len)` est-il un intervalle utilisateur acceptable (en
+/// écriture si `write`) ?
+pub fn access_ok(addr: u64, len: usize, write: bool) -> bool {
+    if !range_ok(addr, len) {
+        return false;
+    }
+    // Tampons du noyau lui-même : rien à parcourir
+    if !crate::ring3::in_user_program() {
+        return true;
+    }
+    len == 0 || user_pages_ok(addr, len, write)
+}
+
+/// Fenêtre d'accès à la mémoire utilisateur : AC levé, baissé au drop
+struct UserAccess;
+
+impl UserAccess {
+    fn open() -> Self {
+        if smap_enabled() {
+            unsafe { core::arch::asm!("stac", options(nostack)) };
+        }
+        UserAccess
+    }
+}
+
+impl Drop for UserAccess {
+    fn drop(&mut self) {
+        if smap_enabled() {
+            unsafe { core::arch::asm!("clac", options(nostack)) };
+        }
+    }
+}
+
+/// Exécute `f` avec l'accès à la mémoire utilisateur ouvert (pour le
+/// noyau qui lit une page utilisateur hors appel système, ex. le swap)
+pub fn with_user_access<R>(f: impl FnOnce() -> R) -> R {
+    let _access = UserAccess::open();
+    f()
+}
+
+global_asm!(
+    // rdi = destination, rsi = source, rdx = longueur ; rax = 0, ou 1
+    // si une faute de page a interrompu la copie
+    ".global uaccess_copy",
+    "uaccess_copy:",
+    "    mov rcx, rdx",
+    ".global uaccess_copy_insn",
+    "uaccess_copy_insn:",
+    "    rep movsb",
+    "    xor eax, eax",
+    "    ret",
+    ".global uaccess_copy_fixup",
+    "uaccess_copy_fixup:",
+    "    mov eax, 1",
+    "    ret",
+);
+
+extern "C" {
+    fn uaccess_copy(dst: *mut u8, src: *const u8, len: usize) -> u64;
+    fn uaccess_copy_insn();
+    fn uaccess_copy_fixup();
+}
+
+/// Table d'exceptions : point de reprise d'une instruction d'accès à la
+/// mémoire utilisateur qui a fauté (gestionnaire de faute de page)
+pub fn fixup(rip: u64) -> Option<u64> {
+    (rip == uaccess_copy_insn as usize as u64).then(|| uaccess_copy_fixup as usize as u64)
+}
+
+/// Copie protégée par la table d'exceptions, fenêtre SMAP ouverte
+fn raw_copy(dst: *mut u8, src: *const u8, len: usize) -> Result<(), Fault> {
+    let _access = UserAccess::open();
+    match unsafe { uaccess_copy(dst, src, len) } {
+        0 => Ok(()),
+        _ => Err(Fault),
+    }
+}
+
+/// Copie `dst.len()` octets depuis l'adresse utilisateur `src`
+pub fn copy_from_user(dst: &mut [u8], src: *const u8) -> Result<(), Fault> {
+    if dst.is_empty() {
+        return Ok(());
+    }
+    if !access_ok(src as u64, dst.len(), false) {
+        return Err(Fault);
+    }
+    raw_copy(dst.as_mut_ptr(), src, dst.len())
+}
+
+/// Copie `src` vers l'adresse utilisateur `dst`
+pub fn copy_to_user(dst: *mut u8, src: &[u8]) -> Result<(), Fault> {
+    if src.is_empty() {
+        return Ok(());
+    }
+    if !access_ok(dst as u64, src.len(), true) {
+        return Err(Fault);
+    }
+    raw_copy(dst, src.as_ptr(), src.len())
+}
+
+/// Lit une valeur (éventuellement non alignée) en mémoire utilisateur
+pub fn read_user<T: Copy>(src: *const T) -> Result<T, Fault> {
+    let mut value = MaybeUninit::<T>::uninit();
+    let bytes = unsafe { core::slice::from_raw_parts_mut(value.as_mut_ptr() as *mut u8, core::mem::size_of::<T>()) };
+    copy_from_user(bytes, src as *const u8)?;
+    Ok(unsafe { value.assume_init() })
+}
+
+/// Écrit une valeur (éventuellement non alignée) en mémoire utilisateur
+pub fn write_user<T: Copy>(dst: *mut T, value: T) -> Result<(), Fault> {
+    let bytes = unsafe { core::slice::from_raw_parts(&value as *const T as *const u8, core::mem::size_of::<T>()) };
+    copy_to_user(dst as *mut u8, bytes)
+}
+
+/// Écrit une valeur dans la mémoire d'un processus qui n'est pas
+/// forcément l'appelant (sortie d'un thread) : les pages sont toujours
+/// vérifiées, l'appartenance de l'adresse est à la charge de l'appelant
+pub fn write_user_remote<T: Copy>(dst: *mut T, value: T) -> Result<(), Fault> {
+    let len = core::mem::size_of::<T>();
+    if !range_ok(dst as u64, len) || !user_pages_ok(dst as u64, len, true) {
+        return Err(Fault);
+    }
+    raw_copy(dst as *mut u8, &value as *const T as *const u8, len)
+}
+
+/// Lit un tableau de `count` valeurs en mémoire utilisateur
+pub fn read_user_slice<T: Copy>(src: *const T, count: usize) -> Result<Vec<T>, Fault> {
+    let len = count.checked_mul(core::mem::size_of::<T>()).ok_or(Fault)?;
+    let mut values: Vec<T> = Vec::with_capacity(count);
+    let bytes = unsafe { core::slice::from_raw_parts_mut(values.as_mut_ptr() as *mut u8, len) };
+    copy_from_user(bytes, src as *const u8)?;
+    unsafe { values.set_len(count) };
+    Ok(values)
+}
+
+/// Écrit `values` en mémoire utilisateur
+pub fn write_user_slice<T: Copy>(dst: *mut T, values: &[T]) -> Result<(), Fault> {
+    let bytes = unsafe { core::slice::from_raw_parts(values.as_ptr() as *const u8, core::mem::size_of_val(values)) };
+    copy_to_user(dst as *mut u8, bytes)
+}
+
+/// Chaîne C utilisateur, sans son NUL ; None au-delà de `max` octets
+pub fn read_user_cstr(src: *const u8, max: usize) -> Result<Option<Vec<u8>>, Fault> {
+    let mut bytes = Vec::new();
+    loop {
+        let addr = src.wrapping_add(bytes.len());
+        // Une vérification par page, puis octet par octet sous la table
+        // d'exceptions
+        if (bytes.is_empty() || addr as u64 % PAGE_SIZE == 0) && !access_ok(addr as u64, 1, false) {
+            return Err(Fault);
+        }
+        let mut byte = 0u8;
+        raw_copy(&mut byte, addr, 1)?;
+        if byte == 0 {
+            return Ok(Some(bytes));
+        }
+        if bytes.len() == max {
+            return Ok(None);
+        }
+        bytes.push(byte);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_user_copies() {
+        assert!(access_ok(0x1000, 4096, false));
+        assert!(!access_ok(0, 1, false));
+        assert!(!access_ok(USER_SPACE_END - 4, 8, false));
+        assert!(!access_ok(u64::MAX - 2, 4, true));
+
+        let source = *b"mini-os";
+        let mut copy = [0u8; 7];
+        assert_eq!(copy_from_user(&mut copy, source.as_ptr()), Ok(()));
+        assert_eq!(copy, source);
+        assert_eq!(copy_to_user(0xffff_8000_0000_0000 as *mut u8, &copy), Err(Fault));
+
+        let mut value = 0u32;
+        write_user(&mut value as *mut u32, 0xC0FFEE).unwrap();
+        assert_eq!(read_user(&value as *const u32), Ok(0xC0FFEE));
+
+        assert_eq!(read_user_cstr(b"init\0".as_ptr(), 16), Ok(Some(b"init".to_vec())));
+        assert_eq!(read_user_cstr(b"toolong\0".as_ptr(), 4), Ok(None));
+        assert_eq!(read_user_cstr(core::ptr::null(), 16), Err(Fault));
+
+        // Page absente : la faute reprend au point de la table d'exceptions
+        let mut buf = [0u8; 8];
+        assert_eq!(copy_from_user(&mut buf, 0x7fff_0000_0000 as *const u8), Err(Fault));
+    }
+
+    #[test_case]
+    fn test_user_page_flags() {
+        let user = PageTableFlags::PRESENT | PageTableFlags::USER_ACCESSIBLE;
+        assert!(user_page_ok(user, false));
+        assert!(!user_page_ok(user, true));
+        assert!(user_page_ok(user | PageTableFlags::WRITABLE, true));
+        // Tas et piles noyau : présents mais superviseur seulement
+        assert!(!user_page_ok(PageTableFlags::PRESENT | PageTableFlags::WRITABLE, false));
+        assert!(!user_page_ok(PageTableFlags::USER_ACCESSIBLE, false));
+    }
+}
@@ -13,6 +13,8 @@ pub struct SharedPage {
     pub frame: PhysFrame,
     pub ref_count: usize,
     pub writable: bool,
+    /// Cadre d'un segment de mémoire partagée : jamais copié
+    pub shm: bool,
 }
 
 /// Gestionnaire des pages partagées
@@ -42,11 +44,25 @@ impl CowManager {
                 frame,
                 ref_count: 1,
                 writable,
+                shm: false,
             });
         }
         Ok(())
     }
 
+    /// Compte une projection d'un cadre de segment partagé (shmat)
+    pub fn share_shm_page(&mut self, frame: PhysFrame) {
+        let key = frame.start_address().as_u64();
+        let shared = self.shared_pages.entry(key).or_insert(SharedPage {
+            frame,
+            ref_count: 0,
+            writable: true,
+            shm: true,
+        });
+        shared.ref_count += 1;
+        shared.shm = true;
+    }
+
     /// Duplique une page si nécessaire (au premier accès en écriture)
     pub fn handle_cow_fault(
         &mut self,
@@ -58,6 +74,11 @@ impl CowManager {
         
         // Vérifier si c'est une page partagée
         if let Some(shared) = self.shared_pages.get_mut(&page_addr) {
+            // Les attachements d'un segment partagent le cadre : une faute
+            // d'écriture y est une violation de protection, pas un CoW
+            if shared.shm {
+                return Err("Écriture dans un attachement en lecture seule");
+            }
             if shared.ref_count > 1 {
                 // Allouer une nouvelle trame
                 let new_frame = frame_allocator
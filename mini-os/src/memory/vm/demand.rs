@@ -3,11 +3,20 @@
 /// Permet d'allouer des pages virtuelles sans allouer immédiatement
 /// les pages physiques correspondantes. Les pages physiques sont allouées
 /// uniquement lors du premier accès (page fault).
+///
+/// Les régions de `MMAP_MANAGER` sont servies de la même façon : page
+/// remplie de zéros pour un mapping anonyme (huge page si la tranche de
+/// 2 Mio tient dans la région), bloc lu depuis le VFS pour un fichier.
 
 use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec;
 use x86_64::{VirtAddr, PhysAddr};
-use x86_64::structures::paging::PageTableFlags;
+use x86_64::structures::paging::mapper::MapToError;
+use x86_64::structures::paging::{Page, PageSize, PageTableFlags, PhysFrame, Size2MiB, Size4KiB};
 use spin::Mutex;
+use crate::memory::mmap::{MmapType, MMAP_MANAGER, PROT_NONE, PROT_WRITE};
+use super::{PageEntry, LRU_PAGE_CACHE, VM_MANAGER};
 
 /// Page lazy (non encore allouée physiquement)
 #[derive(Debug, Clone)]
@@ -66,6 +75,36 @@ impl DemandPagingManager {
         self.total_lazy_pages += 1;
     }
     
+    /// Enregistre les pages de `[start, end)` (bornes alignées sur 4 Kio)
+    pub fn register_lazy_range(&mut self, start: VirtAddr, end: VirtAddr, flags: PageTableFlags) {
+        let mut addr = start;
+        while addr < end {
+            self.register_lazy_page(addr, flags);
+            addr += 4096u64;
+        }
+    }
+    
+    /// Oublie les pages lazy de `[start, end)`
+    ///
+    /// Retourne le nombre de pages qui avaient reçu un cadre : c'est à
+    /// l'appelant de démapper la plage.
+    pub fn release_range(&mut self, start: VirtAddr, end: VirtAddr) -> usize {
+        let keys: alloc::vec::Vec<u64> = self.lazy_pages
+            .range(start.as_u64()..end.as_u64())
+            .map(|(k, _)| *k)
+            .collect();
+        let mut allocated = 0;
+        for key in keys {
+            if let Some(page) = self.lazy_pages.remove(&key) {
+                if page.is_allocated() {
+                    allocated += 1;
+                }
+                self.total_lazy_pages = self.total_lazy_pages.saturating_sub(1);
+            }
+        }
+        allocated
+    }
+    
     /// Traite un page fault
     /// 
     /// Retourne true si c'était une page lazy et qu'elle a été allouée
@@ -76,13 +115,20 @@ impl DemandPagingManager {
         if let Some(lazy_page) = self.lazy_pages.get_mut(&page_addr.as_u64()) {
             // C'est une page lazy
             if !lazy_page.is_allocated() {
-                // Allouer la page physique
-                // TODO: utiliser un vrai allocateur de frames
-                let phys_addr = PhysAddr::new(0x1000_0000); // Placeholder
-                lazy_page.phys_addr = Some(phys_addr);
+                // Allouer une page physique remplie de zéros et la mapper
+                let mut vm = VM_MANAGER.lock();
+                let space = match vm.as_mut() {
+                    Some(vm) => vm.kernel_space_mut(),
+                    None => return false,
+                };
+                let page = Page::<Size4KiB>::containing_address(page_addr);
+                let frame = match space.map_page_filled(page, lazy_page.flags, |bytes| bytes.fill(0)) {
+                    Ok(frame) => frame,
+                    Err(_) => return false,
+                };
+                lazy_page.phys_addr = Some(frame.start_address());
                 lazy_page.access_count += 1;
-                
-                // TODO: mapper la page dans la table de pages
+                LRU_PAGE_CACHE.lock().add_page(PageEntry::new(frame.start_address(), page_addr, 0));
                 
                 self.page_faults_handled += 1;
                 return true;
@@ -114,8 +160,99 @@ pub struct DemandPagingStats {
 pub static DEMAND_PAGING_MANAGER: Mutex<DemandPagingManager> = Mutex::new(DemandPagingManager::new());
 
 /// Handler de page fault (à appeler depuis le gestionnaire d'interruptions)
-pub fn handle_page_fault(fault_addr: VirtAddr) -> bool {
-    DEMAND_PAGING_MANAGER.lock().handle_page_fault(fault_addr)
+///
+/// Retourne true si la faute a été résolue et l'instruction peut reprendre.
+pub fn handle_page_fault(fault_addr: VirtAddr, write: bool) -> bool {
+    if super::swap::swap_in_page(fault_addr) {
+        return true;
+    }
+    if DEMAND_PAGING_MANAGER.lock().handle_page_fault(fault_addr) {
+        return true;
+    }
+    fault_in_mmap(fault_addr, write)
+}
+
+/// Source des données d'une page mmap
+enum Backing {
+    Zero,
+    File { path: String, offset: u64 },
+}
+
+/// Charge la page d'une région mmap contenant `fault_addr`
+fn fault_in_mmap(fault_addr: VirtAddr, write: bool) -> bool {
+    // Les régions mmap sont mappées accessibles au ring 3 : jamais dans la
+    // moitié haute du noyau
+    if fault_addr.as_u64() >= crate::memory::uaccess::USER_SPACE_END {
+        return false;
+    }
+    let page = Page::<Size4KiB>::containing_address(fault_addr);
+    
+    // Copier ce qu'il faut de la région : le VFS ne doit pas être appelé verrou tenu
+    let (start, end, flags, huge, backing, pid) = {
+        let manager = MMAP_MANAGER.lock();
+        let region = match manager.find_region(fault_addr) {
+            Some(region) => region,
+            None => return false,
+        };
+        if region.prot == PROT_NONE || (write && (region.prot & PROT_WRITE) == 0) {
+            return false;
+        }
+        let backing = match (region.mmap_type, &region.file_path) {
+            (MmapType::File { offset, .. }, Some(path)) => Backing::File {
+                path: path.clone(),
+                offset: offset + (page.start_address() - region.start_addr),
+            },
+            _ => Backing::Zero,
+        };
+        let start = region.start_addr;
+        (start, start + region.size as u64, region.page_flags(), region.huge_pages, backing, region.owner_pid)
+    };
+    
+    // Contenu du fichier lu avant de prendre VM_MANAGER ; au-delà de la fin
+    // du fichier, la page reste à zéro
+    let contents = match backing {
+        Backing::File { path, offset } => {
+            let mut buf = vec![0u8; Size4KiB::SIZE as usize];
+            let _ = crate::fs::vfs_read_at(&path, offset, &mut buf);
+            Some(buf)
+        },
+        Backing::Zero => None,
+    };
+    
+    let mut vm = VM_MANAGER.lock();
+    let space = match vm.as_mut() {
+        Some(vm) => vm.kernel_space_mut(),
+        None => return false,
+    };
+    
+    match contents {
+        None => {
+            // Huge page si la tranche de 2 Mio entière appartient à la région
+            let chunk = fault_addr.align_down(Size2MiB::SIZE);
+            if huge && chunk >= start && chunk + Size2MiB::SIZE <= end {
+                if space.map_huge_page(Page::containing_address(chunk), flags).is_ok() {
+                    return true;
+                }
+            }
+            track(space.map_page_filled(page, flags, |bytes| bytes.fill(0)).ok(), page, pid)
+        },
+        Some(buf) => match space.map_page_filled(page, flags, |bytes| bytes.copy_from_slice(&buf)) {
+            // Chargée par une autre faute pendant la lecture
+            Err(MapToError::PageAlreadyMapped(_)) => true,
+            frame => track(frame.ok(), page, pid),
+        },
+    }
+}
+
+/// Les pages de 4 Kio chargées à la demande deviennent candidates au swap
+fn track(frame: Option<PhysFrame>, page: Page, pid: u64) -> bool {
+    match frame {
+        Some(frame) => {
+            LRU_PAGE_CACHE.lock().add_page(PageEntry::new(frame.start_address(), page.start_address(), pid));
+            true
+        },
+        None => false,
+    }
 }
 
 #[cfg(test)]
@@ -30,6 +30,8 @@ pub struct LRUPageCache {
     pages: Vec<PageEntry>,
     max_pages: usize,
     eviction_count: usize,
+    /// Horloge logique des accès
+    clock: u64,
 }
 
 impl LRUPageCache {
@@ -38,16 +40,50 @@ impl LRUPageCache {
             pages: Vec::new(),
             max_pages,
             eviction_count: 0,
+            clock: 0,
         }
     }
     
-    pub fn add_page(&mut self, entry: PageEntry) {
+    pub fn add_page(&mut self, mut entry: PageEntry) {
         if self.pages.len() >= self.max_pages {
             self.pages.remove(0);
             self.eviction_count += 1;
         }
+        self.clock += 1;
+        entry.last_access = self.clock;
         self.pages.push(entry);
     }
+    
+    /// Marque une page comme récemment utilisée
+    pub fn touch(&mut self, virt_addr: VirtAddr) {
+        self.clock += 1;
+        let clock = self.clock;
+        if let Some(entry) = self.pages.iter_mut().find(|p| p.virt_addr == virt_addr) {
+            entry.last_access = clock;
+        }
+    }
+    
+    /// Retire et renvoie la page la moins récemment utilisée (victime du swap)
+    pub fn pop_lru(&mut self) -> Option<PageEntry> {
+        let index = self.pages
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, p)| p.last_access)
+            .map(|(i, _)| i)?;
+        Some(self.pages.remove(index))
+    }
+    
+    /// Oublie les pages de `[start, end)` (démappées)
+    pub fn remove_range(&mut self, start: VirtAddr, end: VirtAddr) {
+        self.pages.retain(|p| p.virt_addr < start || p.virt_addr >= end);
+    }
+    
+    pub fn get_stats(&self) -> LRUStats {
+        LRUStats {
+            total_pages: self.pages.len(),
+            eviction_count: self.eviction_count,
+        }
+    }
 }
 
 /// Statistiques LRU
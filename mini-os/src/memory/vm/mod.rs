@@ -1,17 +1,20 @@
 use x86_64::{
     structures::paging::{
-        Page, PageTable, PhysFrame, Size4KiB, Mapper, OffsetPageTable,
-        PageTableFlags, mapper::MapToError, FrameAllocator as PageFrameAllocator,
+        Page, PageTable, PhysFrame, Size4KiB, Size2MiB, Mapper, OffsetPageTable,
+        PageTableFlags, Translate, FrameDeallocator,
+        page_table::PageTableEntry,
+        mapper::{MapToError, MappedFrame, TranslateResult, UnmapError},
     },
     VirtAddr, PhysAddr,
 };
-// Limine removed - using Multiboot2
-use core::ptr::NonNull;
 use alloc::vec::Vec;
 use core::fmt;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use spin::Mutex;
 use lazy_static::lazy_static;
-use crate::memory::{FrameAllocator, FRAME_ALLOCATOR};
+use crate::memory::frame::{GlobalFrameAllocator, FRAME_ALLOCATOR};
+use x86_64::structures::paging::FrameAllocator as PageFrameAllocator;
+use x86_64::structures::paging::PageSize as _;
 
 pub mod cow;
 pub use cow::{CowManager, COW_MANAGER};
@@ -31,114 +34,23 @@ pub use pagecache::{PageCache, PageCacheEntry, PAGE_CACHE};
 pub mod swap;
 pub use swap::{SwapDaemon, SwapEntry, SWAP_DAEMON};
 
-// Wrapper thread-safe pour la memory map de Limine
-#[derive(Clone, Copy)]
-pub struct LimineMemoryMap(pub &'static [NonNull<MemmapEntry>]);
-
-unsafe impl Send for LimineMemoryMap {}
-unsafe impl Sync for LimineMemoryMap {}
-
-impl LimineMemoryMap {
-    pub fn iter(&self) -> impl Iterator<Item = &NonNull<MemmapEntry>> {
-        self.0.iter()
-    }
-}
-
-// Gestionnaire de cadre physique
-pub struct SimpleFrameAllocator {
-    next: usize,
-    used: Vec<usize>,
-    memory_map: LimineMemoryMap,
-}    
-
-impl SimpleFrameAllocator {
-    pub unsafe fn init(memory_map: LimineMemoryMap) -> Self {
-        // Commencer l'allocation après le kernel et ses segments
-        const KERNEL_END: u64 = 0x200000; // 2 MB
-        
-        let mut used = Vec::new();
-        
-        // Marquer toutes les frames avant KERNEL_END comme utilisées
-        for addr in (0..KERNEL_END).step_by(4096) {
-            used.push(addr as usize);
-        }
-        
-        // Marquer toutes les frames dans les régions NON-Usable comme utilisées
-        for entry in memory_map.iter() {
-            let entry: &MemmapEntry = unsafe { entry.as_ref() };
-            if entry.typ != MemoryMapEntryType::Usable {
-                let start = entry.base;
-                let end = entry.base + entry.len;
-                for addr in (start..end).step_by(4096) {
-                    let frame_addr = addr as usize;
-                    if !used.contains(&frame_addr) {
-                        used.push(frame_addr);
-                    }
-                }
-            }
-        }
-        
-        Self {
-            next: 0,
-            used,
-            memory_map,
-        }
-    }
-    
-    fn usable_frames(&self) -> impl Iterator<Item = PhysFrame> + '_ {
-        let regions = self.memory_map.iter();
-        let usable_regions = regions
-            .map(|e: &NonNull<MemmapEntry>| unsafe { e.as_ref() })
-            .filter(|e| e.typ == MemoryMapEntryType::Usable);
-        
-        let addr_ranges = usable_regions.map(|e| e.base..(e.base + e.len));
-        let frame_addresses = addr_ranges.flat_map(|r: core::ops::Range<u64>| r.step_by(4096));
-        
-        frame_addresses.map(|addr| PhysFrame::containing_address(PhysAddr::new(addr)))
-    }
-}
-
-
-
-unsafe impl FrameAllocator<Size4KiB> for SimpleFrameAllocator {
-    fn allocate_frame(&mut self) -> Option<PhysFrame<Size4KiB>> {
-        // Chercher une frame non utilisée
-        loop {
-            let frame = self.usable_frames().nth(self.next)?;
-            self.next += 1;
-            
-            let frame_addr = frame.start_address().as_u64() as usize;
-            
-            // Vérifier si cette frame n'est pas déjà utilisée
-            if !self.used.contains(&frame_addr) {
-                self.used.push(frame_addr);
-                return Some(frame);
-            }
-            // Sinon, continuer à chercher la prochaine frame
-        }
-    }
-}
-
 // Gestionnaire d'espace d'adressage
+// Les cadres viennent de l'allocateur buddy global (memory::frame)
 pub struct AddressSpace {
     page_table: OffsetPageTable<'static>,
-    frame_allocator: Mutex<SimpleFrameAllocator>,
 }
 
 impl AddressSpace {
-    pub unsafe fn new(phys_offset: VirtAddr, memory_map: LimineMemoryMap) -> Self {
+    pub unsafe fn new(phys_offset: VirtAddr) -> Self {
         let level_4_table = active_level_4_table(phys_offset);
-        let frame_allocator = SimpleFrameAllocator::init(memory_map);
         
         Self {
             page_table: OffsetPageTable::new(level_4_table, phys_offset),
-            frame_allocator: Mutex::new(frame_allocator),
         }
     }
     
     pub fn map_page(&mut self, page: Page, flags: PageTableFlags) -> Result<(), MapToError<Size4KiB>> {
-        let frame = self.frame_allocator
-            .lock()
+        let frame: PhysFrame<Size4KiB> = GlobalFrameAllocator
             .allocate_frame()
             .ok_or(MapToError::FrameAllocationFailed)?;
             
@@ -146,30 +58,375 @@ impl AddressSpace {
             match self.page_table.map_to(
                 page,
                 frame,
-                flags | PageTableFlags::PRESENT,
-                &mut *self.frame_allocator.lock(),
+                enforce_wx(page.start_address(), flags) | PageTableFlags::PRESENT,
+                &mut GlobalFrameAllocator,
             ) {
                 Ok(t) => {
                     t.flush();
                     Ok(())
                 },
                 Err(MapToError::PageAlreadyMapped(_)) => {
-                    // Page is already mapped. We accept this as "success" (idempotent).
-                    // The 'frame' we allocated is unused.
-                    // TODO: Deallocate 'frame' when FrameDeallocator is implemented.
+                    // Page déjà présente : succès (idempotent), le cadre alloué est rendu
+                    GlobalFrameAllocator.deallocate_frame(frame);
+                    Ok(())
+                },
+                Err(e) => {
+                    GlobalFrameAllocator.deallocate_frame(frame);
+                    Err(e)
+                },
+            }
+        }
+    }
+    
+    /// Mappe une page sur un cadre existant (page partagée, ex : vDSO)
+    pub fn map_frame(&mut self, page: Page, frame: PhysFrame, flags: PageTableFlags) -> Result<(), MapToError<Size4KiB>> {
+        unsafe {
+            self.page_table
+                .map_to(page, frame, enforce_wx(page.start_address(), flags) | PageTableFlags::PRESENT, &mut GlobalFrameAllocator)?
+                .flush();
+        }
+        Ok(())
+    }
+    
+    /// Démappe une page sans rendre son cadre (page partagée)
+    pub fn unmap_frame(&mut self, page: Page) -> Result<PhysFrame, UnmapError> {
+        let (frame, flush) = self.page_table.unmap(page)?;
+        flush.flush();
+        Ok(frame)
+    }
+    
+    /// Démappe une page et rend son cadre à l'allocateur
+    pub fn unmap_page(&mut self, page: Page) -> Result<(), UnmapError> {
+        let (frame, flush) = self.page_table.unmap(page)?;
+        flush.flush();
+        unsafe { GlobalFrameAllocator.deallocate_frame(frame) };
+        Ok(())
+    }
+    
+    /// Mappe une page sur un cadre neuf, rempli par `fill` avant d'être visible
+    pub fn map_page_filled(
+        &mut self,
+        page: Page,
+        flags: PageTableFlags,
+        fill: impl FnOnce(&mut [u8]),
+    ) -> Result<PhysFrame, MapToError<Size4KiB>> {
+        let frame: PhysFrame<Size4KiB> = GlobalFrameAllocator
+            .allocate_frame()
+            .ok_or(MapToError::FrameAllocationFailed)?;
+        fill(self.frame_bytes(frame.start_address(), Size4KiB::SIZE));
+        
+        unsafe {
+            match self.page_table.map_to(page, frame, enforce_wx(page.start_address(), flags) | PageTableFlags::PRESENT, &mut GlobalFrameAllocator) {
+                Ok(t) => {
+                    t.flush();
+                    Ok(frame)
+                },
+                Err(e) => {
+                    GlobalFrameAllocator.deallocate_frame(frame);
+                    Err(e)
+                },
+            }
+        }
+    }
+    
+    /// Contenu d'un cadre physique, vu à travers la projection de la mémoire physique
+    fn frame_bytes(&self, phys: PhysAddr, len: u64) -> &'static mut [u8] {
+        let virt = self.page_table.phys_offset() + phys.as_u64();
+        unsafe { core::slice::from_raw_parts_mut(virt.as_mut_ptr::<u8>(), len as usize) }
+    }
+    
+    /// Mappe une page de 2 Mio sur un bloc physique de 2 Mio
+    pub fn map_huge_page(&mut self, page: Page<Size2MiB>, flags: PageTableFlags) -> Result<(), MapToError<Size2MiB>> {
+        let frame: PhysFrame<Size2MiB> = GlobalFrameAllocator
+            .allocate_frame()
+            .ok_or(MapToError::FrameAllocationFailed)?;
+        self.frame_bytes(frame.start_address(), Size2MiB::SIZE).fill(0);
+        
+        unsafe {
+            // Les tables intermédiaires restent en cadres de 4 Kio
+            match self.page_table.map_to_with_table_flags(
+                page,
+                frame,
+                enforce_wx(page.start_address(), flags) | PageTableFlags::PRESENT | PageTableFlags::HUGE_PAGE,
+                (flags & (PageTableFlags::WRITABLE | PageTableFlags::USER_ACCESSIBLE)) | PageTableFlags::PRESENT,
+                &mut GlobalFrameAllocator,
+            ) {
+                Ok(t) => {
+                    t.flush();
                     Ok(())
                 },
-                Err(e) => Err(e),
+                Err(e) => {
+                    GlobalFrameAllocator.deallocate_frame(frame);
+                    Err(e)
+                },
             }
         }
     }
     
+    /// Démappe une page de 2 Mio et rend son bloc à l'allocateur
+    pub fn unmap_huge_page(&mut self, page: Page<Size2MiB>) -> Result<(), UnmapError> {
+        let (frame, flush) = self.page_table.unmap(page)?;
+        flush.flush();
+        unsafe { GlobalFrameAllocator.deallocate_frame(frame) };
+        Ok(())
+    }
+    
+    /// Mappe `[start, start + size)` (alignés sur 4 Kio)
+    ///
+    /// Avec `huge`, chaque tranche de 2 Mio alignée utilise une huge page ;
+    /// si aucun bloc de 2 Mio n'est disponible (ou si la tranche contient
+    /// déjà des pages), elle est découpée en 512 pages de 4 Kio.
+    pub fn map_range(
+        &mut self,
+        start: VirtAddr,
+        size: u64,
+        flags: PageTableFlags,
+        huge: bool,
+    ) -> Result<MappedRange, MapToError<Size4KiB>> {
+        let end = start + size;
+        let mut addr = start;
+        let mut mapped = MappedRange::default();
+        
+        while addr < end {
+            if huge && addr.is_aligned(Size2MiB::SIZE) && end - addr >= Size2MiB::SIZE {
+                let page = Page::<Size2MiB>::containing_address(addr);
+                if self.map_huge_page(page, flags).is_ok() {
+                    mapped.huge_pages += 1;
+                    addr += Size2MiB::SIZE;
+                    continue;
+                }
+                mapped.split_fallbacks += 1;
+            }
+            
+            if let Err(e) = self.map_page(Page::containing_address(addr), flags) {
+                // Défaire ce qui a déjà été mappé
+                self.unmap_range(start, addr - start);
+                return Err(e);
+            }
+            mapped.small_pages += 1;
+            addr += Size4KiB::SIZE;
+        }
+        
+        Ok(mapped)
+    }
+    
+    /// Démappe `[start, start + size)` quelle que soit la taille des pages
+    pub fn unmap_range(&mut self, start: VirtAddr, size: u64) {
+        let end = start + size;
+        let mut addr = start;
+        
+        while addr < end {
+            match self.page_table.translate(addr) {
+                TranslateResult::Mapped { frame: MappedFrame::Size2MiB(_), .. } => {
+                    let page = Page::<Size2MiB>::containing_address(addr);
+                    let _ = self.unmap_huge_page(page);
+                    addr = page.start_address() + Size2MiB::SIZE;
+                },
+                TranslateResult::Mapped { .. } => {
+                    let _ = self.unmap_page(Page::containing_address(addr));
+                    addr += Size4KiB::SIZE;
+                },
+                _ => {
+                    // Page évincée : l'entrée ne doit plus désigner le swap
+                    let _ = self.take_swap_entry(Page::containing_address(addr));
+                    addr += Size4KiB::SIZE;
+                },
+            }
+        }
+    }
+    
+    /// Entrée de niveau 1 d'une page de 4 Kio (None si une table manque
+    /// ou si la page appartient à une huge page)
+    fn pte_mut(&mut self, page: Page) -> Option<&'static mut PageTableEntry> {
+        let phys_offset = self.page_table.phys_offset();
+        let mut table: *mut PageTable = self.page_table.level_4_table();
+        for index in [page.p4_index(), page.p3_index(), page.p2_index()] {
+            let entry = unsafe { &(&*table)[index] };
+            let flags = entry.flags();
+            if !flags.contains(PageTableFlags::PRESENT) || flags.contains(PageTableFlags::HUGE_PAGE) {
+                return None;
+            }
+            table = (phys_offset + entry.addr().as_u64()).as_mut_ptr();
+        }
+        Some(unsafe { &mut (&mut *table)[page.p1_index()] })
+    }
+    
+    /// Efface le bit DIRTY d'une page présente avant d'en copier le
+    /// contenu : une écriture ultérieure le remettra
+    pub fn clean_page(&mut self, page: Page) -> bool {
+        let entry = match self.pte_mut(page) {
+            Some(entry) => entry,
+            None => return false,
+        };
+        let flags = entry.flags();
+        if !flags.contains(PageTableFlags::PRESENT) {
+            return false;
+        }
+        entry.set_flags(flags - PageTableFlags::DIRTY);
+        x86_64::instructions::tlb::flush(page.start_address());
+        true
+    }
+    
+    /// Évince une page restée propre depuis `clean_page` : l'entrée perd
+    /// son bit PRESENT et garde le numéro d'emplacement de swap (bits 12+) ;
+    /// renvoie le cadre libéré
+    pub fn swap_out_pte(&mut self, page: Page, slot: u64) -> Option<PhysFrame> {
+        let entry = self.pte_mut(page)?;
+        let flags = entry.flags();
+        if !flags.contains(PageTableFlags::PRESENT) || flags.contains(PageTableFlags::DIRTY) {
+            return None;
+        }
+        let frame = PhysFrame::containing_address(entry.addr());
+        let kept = flags & (PageTableFlags::WRITABLE | PageTableFlags::USER_ACCESSIBLE);
+        entry.set_addr(PhysAddr::new(slot << 12), kept | SWAP_PTE_MARKER);
+        x86_64::instructions::tlb::flush(page.start_address());
+        Some(frame)
+    }
+    
+    /// Emplacement de swap d'une page évincée, entrée laissée en place
+    pub fn swap_slot(&mut self, page: Page) -> Option<u64> {
+        let entry = self.pte_mut(page)?;
+        let flags = entry.flags();
+        if flags.contains(PageTableFlags::PRESENT) || !flags.contains(SWAP_PTE_MARKER) {
+            return None;
+        }
+        Some(entry.addr().as_u64() >> 12)
+    }
+    
+    /// Emplacement de swap d'une page évincée ; l'entrée est remise à zéro
+    /// pour que la page puisse être remappée
+    pub fn take_swap_entry(&mut self, page: Page) -> Option<(u64, PageTableFlags)> {
+        let entry = self.pte_mut(page)?;
+        let flags = entry.flags();
+        if flags.contains(PageTableFlags::PRESENT) || !flags.contains(SWAP_PTE_MARKER) {
+            return None;
+        }
+        let slot = entry.addr().as_u64() >> 12;
+        entry.set_unused();
+        Some((slot, (flags - SWAP_PTE_MARKER) | PageTableFlags::PRESENT))
+    }
+    
+    /// Drapeaux de la page (feuille) qui contient `addr` ; None si elle
+    /// n'est pas mappée
+    pub fn page_flags(&self, addr: VirtAddr) -> Option<PageTableFlags> {
+        match self.page_table.translate(addr) {
+            TranslateResult::Mapped { flags, .. } => Some(flags),
+            _ => None,
+        }
+    }
+    
+    /// Remplace les drapeaux des pages mappées de `[start, start + size)`
+    pub fn update_range_flags(&mut self, start: VirtAddr, size: u64, flags: PageTableFlags) {
+        let end = start + size;
+        let mut addr = start;
+        
+        while addr < end {
+            match self.page_table.translate(addr) {
+                TranslateResult::Mapped { frame: MappedFrame::Size2MiB(_), .. } => {
+                    let page = Page::<Size2MiB>::containing_address(addr);
+                    let flags = enforce_wx(page.start_address(), flags);
+                    if let Ok(flush) = unsafe { self.page_table.update_flags(page, flags | PageTableFlags::HUGE_PAGE) } {
+                        flush.flush();
+                    }
+                    addr = page.start_address() + Size2MiB::SIZE;
+                },
+                TranslateResult::Mapped { .. } => {
+                    let page = Page::<Size4KiB>::containing_address(addr);
+                    if let Ok(flush) = unsafe { self.page_table.update_flags(page, enforce_wx(addr, flags)) } {
+                        flush.flush();
+                    }
+                    addr += Size4KiB::SIZE;
+                },
+                _ => addr += Size4KiB::SIZE,
+            }
+        }
+    }
+    
+    /// Nombre de cadres physiques encore libres
+    pub fn free_frames(&self) -> usize {
+        FRAME_ALLOCATOR.lock().free_frames()
+    }
+    
     pub fn clone(&self) -> Self {
         // Implémentation de la copie de l'espace d'adressage avec CoW
         unimplemented!("Clone d'espace d'adressage avec CoW")
     }
 }
 
+/// Bit NX utilisable (EFER.NXE activé) ?
+static NX_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Active EFER.NXE si le processeur le supporte
+pub fn enable_nx() -> bool {
+    use x86_64::registers::model_specific::{Efer, EferFlags};
+    
+    let supported = crate::cpuid::cpu_has(crate::cpuid::Feature::Nx);
+    if supported {
+        unsafe { Efer::update(|flags| flags.insert(EferFlags::NO_EXECUTE_ENABLE)) };
+        NX_ENABLED.store(true, Ordering::SeqCst);
+    }
+    supported
+}
+
+pub fn nx_enabled() -> bool {
+    NX_ENABLED.load(Ordering::SeqCst)
+}
+
+/// Drapeaux de page pour une protection lecture/écriture/exécution
+///
+/// Sans NX, une page non exécutable reste exécutable (limite matérielle).
+pub fn protection_flags(write: bool, exec: bool, user: bool) -> PageTableFlags {
+    let mut flags = PageTableFlags::PRESENT;
+    if write {
+        flags |= PageTableFlags::WRITABLE;
+    }
+    if user {
+        flags |= PageTableFlags::USER_ACCESSIBLE;
+    }
+    if !exec && nx_enabled() {
+        flags |= PageTableFlags::NO_EXECUTE;
+    }
+    flags
+}
+
+/// Mappages inscriptibles et exécutables rattrapés par `enforce_wx`
+static WX_VIOLATIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// W^X : aucune page n'est à la fois inscriptible et exécutable. Une
+/// demande fautive fait paniquer les builds de débogage ; sinon la page
+/// perd le droit d'exécution et l'incident est compté. Sans NX, rien à
+/// vérifier : toute page est exécutable.
+pub fn enforce_wx(addr: VirtAddr, flags: PageTableFlags) -> PageTableFlags {
+    if !nx_enabled() || !flags.contains(PageTableFlags::WRITABLE) || flags.contains(PageTableFlags::NO_EXECUTE) {
+        return flags;
+    }
+    if cfg!(debug_assertions) {
+        panic!("W^X: page {:#x} inscriptible et exécutable", addr.as_u64());
+    }
+    WX_VIOLATIONS.fetch_add(1, Ordering::Relaxed);
+    log::warn!("W^X: page {:#x} rendue non exécutable", addr.as_u64());
+    flags | PageTableFlags::NO_EXECUTE
+}
+
+/// Nombre de mappages W+X rattrapés depuis le démarrage
+pub fn wx_violations() -> usize {
+    WX_VIOLATIONS.load(Ordering::Relaxed)
+}
+
+/// Bit logiciel marquant une entrée non présente qui désigne un emplacement de swap
+pub const SWAP_PTE_MARKER: PageTableFlags = PageTableFlags::BIT_9;
+
+/// Bilan d'un `map_range`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MappedRange {
+    /// Pages de 2 Mio mappées
+    pub huge_pages: usize,
+    /// Pages de 4 Kio mappées
+    pub small_pages: usize,
+    /// Tranches de 2 Mio retombées sur des pages de 4 Kio
+    pub split_fallbacks: usize,
+}
+
 // Initialise le mapper de pages
 pub unsafe fn init_mapper(phys_offset: VirtAddr) -> OffsetPageTable<'static> {
     let level_4_table = active_level_4_table(phys_offset);
@@ -222,8 +479,8 @@ pub struct VMManager {
 }
 
 impl VMManager {
-    pub unsafe fn new(phys_offset: VirtAddr, memory_map: LimineMemoryMap) -> Self {
-        let kernel_space = AddressSpace::new(phys_offset, memory_map);
+    pub unsafe fn new(phys_offset: VirtAddr) -> Self {
+        let kernel_space = AddressSpace::new(phys_offset);
         
         Self {
             kernel_space,
@@ -232,6 +489,16 @@ impl VMManager {
         }
     }
     
+    /// Espace d'adressage du noyau, en lecture seule
+    pub fn kernel_space(&self) -> &AddressSpace {
+        &self.kernel_space
+    }
+    
+    /// Espace d'adressage du noyau (table de pages active)
+    pub fn kernel_space_mut(&mut self) -> &mut AddressSpace {
+        &mut self.kernel_space
+    }
+    
     pub fn create_process_space(&mut self) -> usize {
         // Créer un nouvel espace d'adressage pour un processus
         // en copiant l'espace noyau avec CoW
@@ -265,8 +532,9 @@ lazy_static! {
     pub static ref VM_MANAGER: Mutex<Option<VMManager>> = Mutex::new(None);
 }
 
-pub fn init_vm(phys_offset: VirtAddr, memory_map: LimineMemoryMap) {
+pub fn init_vm(phys_offset: VirtAddr) {
+    enable_nx();
     unsafe {
-        *VM_MANAGER.lock() = Some(VMManager::new(phys_offset, memory_map));
+        *VM_MANAGER.lock() = Some(VMManager::new(phys_offset));
     }
 }
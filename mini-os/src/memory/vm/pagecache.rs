@@ -166,6 +166,8 @@ impl PageCache {
     /// Retourne les statistiques
     pub fn get_stats(&self) -> PageCacheStats {
         let dirty_pages = self.entries.values().filter(|e| e.dirty).count();
+        let cached_bytes = self.entries.values().map(|e| e.data.len()).sum();
+        let dirty_bytes = self.entries.values().filter(|e| e.dirty).map(|e| e.data.len()).sum();
         let hit_rate = if self.cache_hits + self.cache_misses > 0 {
             (self.cache_hits as f32 / (self.cache_hits + self.cache_misses) as f32) * 100.0
         } else {
@@ -180,6 +182,8 @@ impl PageCache {
             hit_rate,
             dirty_pages,
             writebacks: self.writebacks,
+            cached_bytes,
+            dirty_bytes,
         }
     }
 }
@@ -194,6 +198,9 @@ pub struct PageCacheStats {
     pub hit_rate: f32,
     pub dirty_pages: usize,
     pub writebacks: usize,
+    /// Octets de données en cache, dont modifiés
+    pub cached_bytes: usize,
+    pub dirty_bytes: usize,
 }
 
 /// Instance globale du page cache
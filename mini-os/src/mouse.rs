@@ -1,16 +1,343 @@
+/// Souris PS/2 (port auxiliaire du contrôleur 8042)
+///
+/// L'initialisation active le port auxiliaire et l'IRQ 12, puis tente
+/// la séquence IntelliMouse (fréquences 200/100/80) : si la souris
+/// répond avec l'identifiant 3, les paquets font 4 octets et le
+/// quatrième porte la molette. Chaque paquet décodé devient un
+/// `MouseEvent` placé dans une file circulaire, lue par `poll_event`
+/// ou par /dev/mouse.
+
+use alloc::sync::Arc;
 use spin::Mutex;
+use x86_64::instructions::interrupts::without_interrupts;
 use x86_64::instructions::port::Port;
+use crate::fs::devfs::{self, DeviceKind, DeviceOps};
+use crate::fs::VfsResult;
 use crate::vga_buffer::WRITER;
 
-static MOUSE_BYTE: Mutex<u8> = Mutex::new(0);
+const DATA_PORT: u16 = 0x60;
+const STATUS_PORT: u16 = 0x64;
+
+/// Bits du registre d'état du contrôleur
+const STATUS_OUTPUT_FULL: u8 = 1 << 0;
+const STATUS_INPUT_FULL: u8 = 1 << 1;
+
+/// Commandes du contrôleur
+const CMD_ENABLE_AUX: u8 = 0xA8;
+const CMD_READ_CONFIG: u8 = 0x20;
+const CMD_WRITE_CONFIG: u8 = 0x60;
+const CMD_WRITE_AUX: u8 = 0xD4;
+
+/// Commandes de la souris
+const MOUSE_SET_DEFAULTS: u8 = 0xF6;
+const MOUSE_ENABLE_REPORTING: u8 = 0xF4;
+const MOUSE_SET_SAMPLE_RATE: u8 = 0xF3;
+const MOUSE_GET_ID: u8 = 0xF2;
+const MOUSE_ACK: u8 = 0xFA;
+
+/// Identifiant d'une souris à molette (IntelliMouse)
+const ID_WHEEL: u8 = 3;
+
+/// Boutons
+pub const BUTTON_LEFT: u8 = 1 << 0;
+pub const BUTTON_RIGHT: u8 = 1 << 1;
+pub const BUTTON_MIDDLE: u8 = 1 << 2;
+
+/// Capacité de la file d'événements
+const QUEUE_SIZE: usize = 128;
+
+/// Taille d'un événement lu depuis /dev/mouse
+pub const EVENT_SIZE: usize = 6;
+
+/// Déplacement relatif (y positif vers le haut, comme le protocole PS/2)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MouseEvent {
+    pub dx: i16,
+    pub dy: i16,
+    pub buttons: u8,
+    pub wheel: i8,
+}
+
+impl MouseEvent {
+    /// Enregistrement de /dev/mouse : boutons, molette, dx, dy (petit-boutiste)
+    pub fn to_bytes(&self) -> [u8; EVENT_SIZE] {
+        let dx = self.dx.to_le_bytes();
+        let dy = self.dy.to_le_bytes();
+        [self.buttons, self.wheel as u8, dx[0], dx[1], dy[0], dy[1]]
+    }
+}
+
+/// Réassemble les paquets à partir des octets reçus
+pub struct PacketDecoder {
+    bytes: [u8; 4],
+    index: usize,
+    packet_len: usize,
+}
+
+impl PacketDecoder {
+    pub const fn new(wheel: bool) -> Self {
+        Self { bytes: [0; 4], index: 0, packet_len: if wheel { 4 } else { 3 } }
+    }
+
+    /// Ajoute un octet ; retourne l'événement quand le paquet est complet
+    pub fn feed(&mut self, byte: u8) -> Option<MouseEvent> {
+        // Le bit 3 du premier octet est toujours à 1 : resynchronisation
+        if self.index == 0 && byte & 0x08 == 0 {
+            return None;
+        }
+        self.bytes[self.index] = byte;
+        self.index += 1;
+        if self.index < self.packet_len {
+            return None;
+        }
+        self.index = 0;
+
+        let flags = self.bytes[0];
+        // Débordement : paquet inexploitable
+        if flags & 0xC0 != 0 {
+            return None;
+        }
+        let dx = self.bytes[1] as i16 - (((flags as i16) << 4) & 0x100);
+        let dy = self.bytes[2] as i16 - (((flags as i16) << 3) & 0x100);
+        let wheel = if self.packet_len == 4 { self.bytes[3] as i8 } else { 0 };
+        Some(MouseEvent { dx, dy, buttons: flags & 0x07, wheel })
+    }
+}
+
+/// File circulaire d'événements (sans allocation, utilisable en interruption)
+pub struct EventQueue {
+    events: [MouseEvent; QUEUE_SIZE],
+    head: usize,
+    len: usize,
+    /// Événements perdus faute de place
+    pub dropped: u64,
+}
+
+impl EventQueue {
+    pub const fn new() -> Self {
+        Self {
+            events: [MouseEvent { dx: 0, dy: 0, buttons: 0, wheel: 0 }; QUEUE_SIZE],
+            head: 0,
+            len: 0,
+            dropped: 0,
+        }
+    }
+
+    /// Ajoute un événement ; les mouvements consécutifs sans changement de
+    /// boutons sont fusionnés quand la file est pleine
+    pub fn push(&mut self, event: MouseEvent) {
+        if self.len == QUEUE_SIZE {
+            let last = &mut self.events[(self.head + self.len - 1) % QUEUE_SIZE];
+            if last.buttons == event.buttons {
+                last.dx = last.dx.saturating_add(event.dx);
+                last.dy = last.dy.saturating_add(event.dy);
+                last.wheel = last.wheel.saturating_add(event.wheel);
+            } else {
+                self.dropped += 1;
+            }
+            return;
+        }
+        self.events[(self.head + self.len) % QUEUE_SIZE] = event;
+        self.len += 1;
+    }
+
+    pub fn pop(&mut self) -> Option<MouseEvent> {
+        if self.len == 0 {
+            return None;
+        }
+        let event = self.events[self.head];
+        self.head = (self.head + 1) % QUEUE_SIZE;
+        self.len -= 1;
+        Some(event)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+struct Mouse {
+    decoder: PacketDecoder,
+    queue: EventQueue,
+}
+
+/// État de la souris, partagé avec le gestionnaire d'interruption
+static MOUSE: Mutex<Mouse> = Mutex::new(Mouse { decoder: PacketDecoder::new(false), queue: EventQueue::new() });
+
+fn wait_input_empty() -> bool {
+    let mut status = Port::<u8>::new(STATUS_PORT);
+    for _ in 0..100_000 {
+        if unsafe { status.read() } & STATUS_INPUT_FULL == 0 {
+            return true;
+        }
+        core::hint::spin_loop();
+    }
+    false
+}
+
+fn read_data() -> Option<u8> {
+    let mut status = Port::<u8>::new(STATUS_PORT);
+    for _ in 0..100_000 {
+        if unsafe { status.read() } & STATUS_OUTPUT_FULL != 0 {
+            return Some(unsafe { Port::<u8>::new(DATA_PORT).read() });
+        }
+        core::hint::spin_loop();
+    }
+    None
+}
+
+fn controller_command(command: u8) {
+    wait_input_empty();
+    unsafe { Port::<u8>::new(STATUS_PORT).write(command) }
+}
+
+fn write_data(byte: u8) {
+    wait_input_empty();
+    unsafe { Port::<u8>::new(DATA_PORT).write(byte) }
+}
+
+/// Envoie un octet à la souris et attend l'acquittement
+fn mouse_command(byte: u8) -> bool {
+    controller_command(CMD_WRITE_AUX);
+    write_data(byte);
+    read_data() == Some(MOUSE_ACK)
+}
+
+fn set_sample_rate(rate: u8) -> bool {
+    mouse_command(MOUSE_SET_SAMPLE_RATE) && mouse_command(rate)
+}
+
+/// Active la molette si la souris la gère ; retourne l'identifiant obtenu
+fn enable_wheel() -> u8 {
+    if !(set_sample_rate(200) && set_sample_rate(100) && set_sample_rate(80)) {
+        return 0;
+    }
+    if !mouse_command(MOUSE_GET_ID) {
+        return 0;
+    }
+    read_data().unwrap_or(0)
+}
 
 pub fn init_mouse() {
     WRITER.lock().write_string("Initializing PS/2 mouse...\n");
-    // TODO: Implémenter l'initialisation de la souris
+
+    let wheel = without_interrupts(|| {
+        controller_command(CMD_ENABLE_AUX);
+
+        // Octet de configuration : IRQ 12 active, horloge souris active
+        controller_command(CMD_READ_CONFIG);
+        let config = read_data().unwrap_or(0);
+        controller_command(CMD_WRITE_CONFIG);
+        write_data((config | 0x02) & !0x20);
+
+        mouse_command(MOUSE_SET_DEFAULTS);
+        let wheel = enable_wheel() == ID_WHEEL;
+        mouse_command(MOUSE_ENABLE_REPORTING);
+
+        *MOUSE.lock() = Mouse { decoder: PacketDecoder::new(wheel), queue: EventQueue::new() };
+        wheel
+    });
+
+    let irq = crate::interrupts::InterruptIndex::Mouse.irq();
+    if let Err(e) = crate::interrupts::request_irq(irq, mouse_interrupt_handler) {
+        log::error!("Souris PS/2: IRQ {} {:?}", irq, e);
+    }
+    let _ = devfs::register_device("mouse", DeviceKind::Char, Arc::new(Mutex::new(MouseDevice)));
+    WRITER.lock().write_string(if wheel { "Souris PS/2 avec molette\n" } else { "Souris PS/2\n" });
+}
+
+crate::initcall!(driver, "Souris PS/2 (/dev/mouse)", init_mouse);
+
+/// IRQ 12
+fn mouse_interrupt_handler() {
+    let mut port = Port::new(DATA_PORT);
+    let byte: u8 = unsafe { port.read() };
+
+    let mut mouse = MOUSE.lock();
+    if let Some(event) = mouse.decoder.feed(byte) {
+        mouse.queue.push(event);
+    }
+    drop(mouse);
+}
+
+/// Ajoute un événement venant d'une autre source (souris USB)
+pub fn push_event(event: MouseEvent) {
+    without_interrupts(|| MOUSE.lock().queue.push(event));
+}
+
+/// Prochain événement en attente (API de scrutation pour l'interface graphique)
+pub fn poll_event() -> Option<MouseEvent> {
+    without_interrupts(|| MOUSE.lock().queue.pop())
+}
+
+/// Nombre d'événements en attente
+pub fn pending_events() -> usize {
+    without_interrupts(|| MOUSE.lock().queue.len())
+}
+
+/// /dev/mouse : enregistrements de `EVENT_SIZE` octets, lecture non bloquante
+pub struct MouseDevice;
+
+impl DeviceOps for MouseDevice {
+    fn read(&mut self, _offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        let mut written = 0;
+        while buf.len() - written >= EVENT_SIZE {
+            let event = match poll_event() {
+                Some(event) => event,
+                None => break,
+            };
+            buf[written..written + EVENT_SIZE].copy_from_slice(&event.to_bytes());
+            written += EVENT_SIZE;
+        }
+        Ok(written)
+    }
+
+    fn write(&mut self, _offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        // Les commandes brutes vers la souris ne sont pas exposées
+        Ok(buf.len())
+    }
 }
 
-pub extern "x86-interrupt" fn mouse_interrupt_handler(_stack_frame: x86_64::structures::idt::InterruptStackFrame) {
-    let mut port = Port::new(0x60);
-    let byte = unsafe { port.read() };
-    *MOUSE_BYTE.lock() = byte;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_decode_standard_packet() {
+        let mut decoder = PacketDecoder::new(false);
+        // Bouton gauche, dx = -3 (bit de signe X), dy = +5
+        assert_eq!(decoder.feed(0x19), None);
+        assert_eq!(decoder.feed(0xFD), None);
+        let event = decoder.feed(0x05).unwrap();
+        assert_eq!(event, MouseEvent { dx: -3, dy: 5, buttons: BUTTON_LEFT, wheel: 0 });
+    }
+
+    #[test_case]
+    fn test_decode_wheel_and_resync() {
+        let mut decoder = PacketDecoder::new(true);
+        // Octet désynchronisé (bit 3 nul) ignoré
+        assert_eq!(decoder.feed(0x00), None);
+        decoder.feed(0x08);
+        decoder.feed(0x00);
+        decoder.feed(0x00);
+        assert_eq!(decoder.feed(0xFF).unwrap().wheel, -1);
+    }
+
+    #[test_case]
+    fn test_queue_coalesces_when_full() {
+        let mut queue = EventQueue::new();
+        for _ in 0..QUEUE_SIZE + 2 {
+            queue.push(MouseEvent { dx: 1, dy: 0, buttons: 0, wheel: 0 });
+        }
+        assert_eq!(queue.len(), QUEUE_SIZE);
+        let mut last = MouseEvent::default();
+        while let Some(event) = queue.pop() {
+            last = event;
+        }
+        assert_eq!(last.dx, 3);
+    }
 }
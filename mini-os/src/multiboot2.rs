@@ -1,28 +1,299 @@
-/// En-tête Multiboot2 pour GRUB
-/// 
-/// Ce module définit l'en-tête Multiboot2 requis par GRUB
-
-use core::arch::global_asm;
-
-// Constantes Multiboot2
-const MULTIBOOT2_MAGIC: u32 = 0xE85250D6;
-const ARCHITECTURE: u32 = 0; // i386
-const HEADER_LENGTH: u32 = 24;
-const CHECKSUM: u32 = 0u32.wrapping_sub(MULTIBOOT2_MAGIC + ARCHITECTURE + HEADER_LENGTH);
-
-// En-tête Multiboot2 en assembly
-global_asm!(
-    ".section .multiboot_header",
-    ".align 8",
-    "multiboot_header_start:",
-    "    .long 0xE85250D6",           // magic
-    "    .long 0",                     // architecture (i386)
-    "    .long multiboot_header_end - multiboot_header_start", // header_length
-    "    .long -(0xE85250D6 + 0 + (multiboot_header_end - multiboot_header_start))", // checksum
-    "",
-    "    // End tag",
-    "    .short 0",    // type
-    "    .short 0",    // flags
-    "    .long 8",     // size
-    "multiboot_header_end:",
-);
+/// Informations de démarrage Multiboot2
+///
+/// Le chargeur laisse dans EAX la signature `BOOTLOADER_MAGIC` et dans EBX
+/// l'adresse physique d'une suite de tags ; `_start` les transmet à
+/// `init`, qui recopie dans le tas ce dont le noyau a besoin :
+/// - la ligne de commande du noyau (tag 1) et le nom du chargeur (tag 2) ;
+/// - les modules chargés avec le noyau (tag 3), par exemple un initramfs ;
+/// - la carte mémoire (tag 6), qui alimente l'allocateur de cadres ;
+/// - le framebuffer programmé par le chargeur (tag 8), repris par la console.
+///
+/// Les modules et la structure elle-même restent en mémoire physique :
+/// `usable_regions` les retire de la carte avant de la confier à
+/// l'allocateur de cadres.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use crate::drivers::gpu::vesa::VesaModeInfo;
+use crate::memory::frame::{carve, regions_from_multiboot, MemoryRegion};
+use crate::sync::Mutex;
+
+/// Signature laissée dans EAX par un chargeur Multiboot2
+pub const BOOTLOADER_MAGIC: u32 = 0x36D7_6289;
+
+const TAG_END: u32 = 0;
+const TAG_CMDLINE: u32 = 1;
+const TAG_BOOTLOADER_NAME: u32 = 2;
+const TAG_MODULE: u32 = 3;
+const TAG_FRAMEBUFFER: u32 = 8;
+
+/// Framebuffer en couleurs directes (les autres types sont ignorés)
+const FRAMEBUFFER_TYPE_RGB: u8 = 1;
+
+/// Module chargé par le chargeur, `[start, end)` en mémoire physique
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Module {
+    pub start: u64,
+    pub end: u64,
+    /// Chaîne associée au module dans la configuration du chargeur
+    pub cmdline: String,
+}
+
+impl Module {
+    pub fn size(&self) -> usize {
+        (self.end - self.start) as usize
+    }
+
+    /// Contenu du module (identité physique)
+    pub fn data(&self) -> &'static [u8] {
+        unsafe { core::slice::from_raw_parts(self.start as *const u8, self.size()) }
+    }
+}
+
+/// Framebuffer linéaire programmé par le chargeur
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Framebuffer {
+    pub addr: u64,
+    pub pitch: u32,
+    pub width: u32,
+    pub height: u32,
+    pub bpp: u8,
+    pub kind: u8,
+}
+
+impl Framebuffer {
+    /// Mode utilisable par la console framebuffer (RGB 32 bits seulement)
+    pub fn mode_info(&self) -> Option<VesaModeInfo> {
+        if self.kind != FRAMEBUFFER_TYPE_RGB || self.bpp != 32 || self.pitch > u16::MAX as u32 {
+            return None;
+        }
+        Some(VesaModeInfo {
+            width: self.width as u16,
+            height: self.height as u16,
+            pitch: self.pitch as u16,
+            bpp: self.bpp,
+            framebuffer: self.addr,
+        })
+    }
+}
+
+/// Contenu utile de la structure d'informations
+#[derive(Debug, Clone, Default)]
+pub struct BootInfo {
+    /// Adresse et taille de la structure d'origine
+    pub info_addr: u64,
+    pub info_size: u64,
+    pub cmdline: String,
+    pub bootloader: String,
+    pub modules: Vec<Module>,
+    pub memory_map: Vec<MemoryRegion>,
+    pub framebuffer: Option<Framebuffer>,
+}
+
+impl BootInfo {
+    /// Carte mémoire privée des modules et de la structure d'informations
+    pub fn usable_regions(&self) -> Vec<MemoryRegion> {
+        let mut holes: Vec<(u64, u64)> = self.modules.iter().map(|m| (m.start, m.end)).collect();
+        holes.push((self.info_addr, self.info_addr + self.info_size));
+        carve(&self.memory_map, &holes)
+    }
+}
+
+/// Chaîne terminée par un NUL dans `[addr, addr + max)`
+unsafe fn read_cstr(addr: usize, max: usize) -> String {
+    let bytes = core::slice::from_raw_parts(addr as *const u8, max);
+    let len = bytes.iter().position(|&b| b == 0).unwrap_or(max);
+    String::from_utf8_lossy(&bytes[..len]).into_owned()
+}
+
+/// Ligne de commande (tag 1), lue sur place sans allocation : utilisable
+/// avant l'initialisation du tas
+///
+/// # Safety
+/// Comme `parse` ; la structure doit rester en place tant que la chaîne
+/// est utilisée (l'allocateur de cadres l'épargne, cf. `usable_regions`).
+pub unsafe fn find_cmdline(info_addr: usize) -> Option<&'static str> {
+    let total_size = core::ptr::read_unaligned(info_addr as *const u32) as usize;
+    let mut tag = info_addr + 8;
+    while tag + 8 <= info_addr + total_size {
+        let typ = core::ptr::read_unaligned(tag as *const u32);
+        let size = core::ptr::read_unaligned((tag + 4) as *const u32) as usize;
+        if typ == TAG_END || size < 8 {
+            break;
+        }
+        if typ == TAG_CMDLINE {
+            let bytes = core::slice::from_raw_parts((tag + 8) as *const u8, size - 8);
+            let len = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+            return core::str::from_utf8(&bytes[..len]).ok();
+        }
+        tag += (size + 7) & !7;
+    }
+    None
+}
+
+/// Analyse la structure d'informations Multiboot2
+///
+/// # Safety
+/// `info_addr` doit pointer sur la structure fournie par le chargeur,
+/// accessible en identité.
+pub unsafe fn parse(info_addr: usize) -> BootInfo {
+    let total_size = core::ptr::read_unaligned(info_addr as *const u32) as usize;
+    let mut info = BootInfo {
+        info_addr: info_addr as u64,
+        info_size: total_size as u64,
+        memory_map: regions_from_multiboot(info_addr),
+        ..BootInfo::default()
+    };
+
+    let mut tag = info_addr + 8;
+    while tag + 8 <= info_addr + total_size {
+        let typ = core::ptr::read_unaligned(tag as *const u32);
+        let size = core::ptr::read_unaligned((tag + 4) as *const u32) as usize;
+        if typ == TAG_END || size < 8 {
+            break;
+        }
+
+        match typ {
+            TAG_CMDLINE => info.cmdline = read_cstr(tag + 8, size - 8),
+            TAG_BOOTLOADER_NAME => info.bootloader = read_cstr(tag + 8, size - 8),
+            TAG_MODULE if size >= 16 => info.modules.push(Module {
+                start: core::ptr::read_unaligned((tag + 8) as *const u32) as u64,
+                end: core::ptr::read_unaligned((tag + 12) as *const u32) as u64,
+                cmdline: read_cstr(tag + 16, size - 16),
+            }),
+            TAG_FRAMEBUFFER if size >= 30 => info.framebuffer = Some(Framebuffer {
+                addr: core::ptr::read_unaligned((tag + 8) as *const u64),
+                pitch: core::ptr::read_unaligned((tag + 16) as *const u32),
+                width: core::ptr::read_unaligned((tag + 20) as *const u32),
+                height: core::ptr::read_unaligned((tag + 24) as *const u32),
+                bpp: *((tag + 28) as *const u8),
+                kind: *((tag + 29) as *const u8),
+            }),
+            _ => {}
+        }
+
+        // Les tags sont alignés sur 8 octets
+        tag += (size + 7) & !7;
+    }
+    info
+}
+
+/// Informations relevées au démarrage (None sans chargeur Multiboot2)
+static BOOT_INFO: Mutex<Option<BootInfo>> = Mutex::new(None);
+
+/// Relève les informations transmises par le chargeur ; le tas doit être prêt
+pub fn init(magic: u32, info_addr: u64) -> Result<(), &'static str> {
+    if magic != BOOTLOADER_MAGIC {
+        return Err("multiboot2: signature du chargeur absente");
+    }
+    if info_addr == 0 || info_addr % 8 != 0 {
+        return Err("multiboot2: adresse d'informations invalide");
+    }
+    let info = unsafe { parse(info_addr as usize) };
+    *BOOT_INFO.lock() = Some(info);
+    Ok(())
+}
+
+/// Démarrage par un chargeur Multiboot2 ?
+pub fn is_available() -> bool {
+    BOOT_INFO.lock().is_some()
+}
+
+/// Copie des informations de démarrage
+pub fn boot_info() -> Option<BootInfo> {
+    BOOT_INFO.lock().clone()
+}
+
+/// Ligne de commande du noyau (vide sans chargeur)
+pub fn cmdline() -> String {
+    BOOT_INFO.lock().as_ref().map(|info| info.cmdline.clone()).unwrap_or_default()
+}
+
+/// Modules chargés avec le noyau
+pub fn modules() -> Vec<Module> {
+    BOOT_INFO.lock().as_ref().map(|info| info.modules.clone()).unwrap_or_default()
+}
+
+/// Framebuffer programmé par le chargeur
+pub fn framebuffer() -> Option<Framebuffer> {
+    BOOT_INFO.lock().as_ref().and_then(|info| info.framebuffer)
+}
+
+/// Carte mémoire brute du chargeur (vide sans chargeur)
+pub fn memory_map() -> Vec<MemoryRegion> {
+    BOOT_INFO.lock().as_ref().map(|info| info.memory_map.clone()).unwrap_or_default()
+}
+
+/// Régions à confier à l'allocateur de cadres (vide sans chargeur)
+pub fn usable_regions() -> Vec<MemoryRegion> {
+    BOOT_INFO.lock().as_ref().map(|info| info.usable_regions()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::frame::MemoryRegionKind;
+
+    fn push_tag(buf: &mut Vec<u8>, typ: u32, payload: &[u8]) {
+        buf.extend_from_slice(&typ.to_le_bytes());
+        buf.extend_from_slice(&(8 + payload.len() as u32).to_le_bytes());
+        buf.extend_from_slice(payload);
+        while buf.len() % 8 != 0 {
+            buf.push(0);
+        }
+    }
+
+    #[test_case]
+    fn test_parse_boot_info() {
+        let mut buf = alloc::vec![0u8; 8];
+        push_tag(&mut buf, TAG_CMDLINE, b"root=/dev/sda1 quiet\0");
+
+        let mut module = Vec::new();
+        module.extend_from_slice(&0x50_0000u32.to_le_bytes());
+        module.extend_from_slice(&0x58_0000u32.to_le_bytes());
+        module.extend_from_slice(b"initramfs\0");
+        push_tag(&mut buf, TAG_MODULE, &module);
+
+        let mut mmap = Vec::new();
+        mmap.extend_from_slice(&24u32.to_le_bytes());
+        mmap.extend_from_slice(&0u32.to_le_bytes());
+        for (base, len, kind) in [(0u64, 0x9F000u64, 1u32), (0x10_0000, 0x7F0_0000, 1), (0xFFFC_0000, 0x4_0000, 2)] {
+            mmap.extend_from_slice(&base.to_le_bytes());
+            mmap.extend_from_slice(&len.to_le_bytes());
+            mmap.extend_from_slice(&kind.to_le_bytes());
+            mmap.extend_from_slice(&0u32.to_le_bytes());
+        }
+        push_tag(&mut buf, 6, &mmap);
+
+        let mut fb = Vec::new();
+        fb.extend_from_slice(&0xFD00_0000u64.to_le_bytes());
+        fb.extend_from_slice(&4096u32.to_le_bytes());
+        fb.extend_from_slice(&1024u32.to_le_bytes());
+        fb.extend_from_slice(&768u32.to_le_bytes());
+        fb.extend_from_slice(&[32, FRAMEBUFFER_TYPE_RGB, 0, 0]);
+        push_tag(&mut buf, TAG_FRAMEBUFFER, &fb);
+        push_tag(&mut buf, TAG_END, &[]);
+        let total = buf.len() as u32;
+        buf[..4].copy_from_slice(&total.to_le_bytes());
+
+        // Copie alignée sur 8 octets, comme la structure du chargeur
+        let words: Vec<u64> = buf.chunks(8).map(|c| u64::from_le_bytes(c.try_into().unwrap())).collect();
+        let mut info = unsafe { parse(words.as_ptr() as usize) };
+
+        assert_eq!(info.cmdline, "root=/dev/sda1 quiet");
+        assert_eq!(unsafe { find_cmdline(words.as_ptr() as usize) }, Some("root=/dev/sda1 quiet"));
+        assert_eq!(info.modules.len(), 1);
+        assert_eq!(info.modules[0].cmdline, "initramfs");
+        assert_eq!(info.memory_map.len(), 3);
+        assert_eq!(info.memory_map[2].kind, MemoryRegionKind::Reserved);
+        let mode = info.framebuffer.and_then(|fb| fb.mode_info()).unwrap();
+        assert_eq!((mode.width, mode.height, mode.pitch), (1024, 768, 4096));
+
+        // Le module est retiré de la RAM utilisable (la copie de test est dans le tas)
+        info.info_size = 0;
+        let usable = info.usable_regions();
+        assert!(usable.contains(&MemoryRegion::usable(0x10_0000, 0x50_0000)));
+        assert!(usable.contains(&MemoryRegion::usable(0x58_0000, 0x800_0000)));
+        assert!(!usable.iter().any(|r| r.start < 0x58_0000 && r.end > 0x50_0000));
+    }
+}
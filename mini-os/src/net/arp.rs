@@ -1,9 +1,12 @@
 /// Module ARP (Address Resolution Protocol)
 /// 
 /// Résolution d'adresses IPv4 en adresses MAC
+///
+/// Le cache est consulté à chaque émission mais rarement modifié : il est
+/// publié par RCU (`sync::rcu`), et les recherches ne prennent aucun verrou.
 
 use alloc::collections::BTreeMap;
-use spin::Mutex;
+use crate::sync::rcu::Rcu;
 use super::ethernet::MacAddress;
 
 /// Adresse IPv4
@@ -20,6 +23,23 @@ impl Ipv4Address {
     }
 }
 
+impl core::str::FromStr for Ipv4Address {
+    type Err = ();
+
+    /// Notation décimale pointée (« 10.0.2.2 »)
+    fn from_str(s: &str) -> Result<Self, ()> {
+        let mut bytes = [0u8; 4];
+        let mut parts = s.split('.');
+        for byte in bytes.iter_mut() {
+            *byte = parts.next().ok_or(())?.parse().map_err(|_| ())?;
+        }
+        if parts.next().is_some() {
+            return Err(());
+        }
+        Ok(Ipv4Address(bytes))
+    }
+}
+
 impl core::fmt::Display for Ipv4Address {
     fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         write!(f, "{}.{}.{}.{}", self.0[0], self.0[1], self.0[2], self.0[3])
@@ -164,6 +184,7 @@ struct ArpCacheEntry {
 }
 
 /// Cache ARP
+#[derive(Clone)]
 pub struct ArpCache {
     /// Entrées (IP -> MAC)
     entries: BTreeMap<Ipv4Address, ArpCacheEntry>,
@@ -201,6 +222,11 @@ impl ArpCache {
         });
     }
     
+    /// Vide le cache (changement d'adresse de l'interface)
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+    
     /// Retourne le nombre d'entrées
     pub fn len(&self) -> usize {
         self.entries.len()
@@ -220,7 +246,29 @@ pub enum ArpError {
 use lazy_static::lazy_static;
 
 lazy_static! {
-    pub static ref ARP_CACHE: Mutex<ArpCache> = Mutex::new(ArpCache::new(300)); // 5 minutes
+    pub static ref ARP_CACHE: Rcu<ArpCache> = Rcu::new(ArpCache::new(300)); // 5 minutes
+}
+
+/// Adresse MAC connue pour `ip`, sans verrou
+pub fn cache_lookup(ip: &Ipv4Address) -> Option<MacAddress> {
+    ARP_CACHE.read().get(ip)
+}
+
+/// Apprend `ip` -> `mac` ; une entrée inchangée n'est pas republiée
+pub fn cache_insert(ip: Ipv4Address, mac: MacAddress) {
+    if cache_lookup(&ip) == Some(mac) {
+        return;
+    }
+    ARP_CACHE.update(|cache| {
+        let mut cache = cache.clone();
+        cache.insert(ip, mac);
+        cache
+    });
+}
+
+/// Vide le cache (changement d'adresse de l'interface)
+pub fn cache_clear() {
+    ARP_CACHE.update(|cache| ArpCache::new(cache.timeout));
 }
 
 #[cfg(test)]
@@ -231,6 +279,9 @@ mod tests {
     fn test_ipv4_address() {
         let ip = Ipv4Address::new(192, 168, 1, 1);
         assert_eq!(ip.0, [192, 168, 1, 1]);
+        assert_eq!("10.0.2.2".parse(), Ok(Ipv4Address::new(10, 0, 2, 2)));
+        assert!("10.0.2".parse::<Ipv4Address>().is_err());
+        assert!("10.0.2.256".parse::<Ipv4Address>().is_err());
     }
     
     #[test_case]
@@ -0,0 +1,418 @@
+/// Capture de trames (sockets raw, tcpdump)
+///
+/// Chaque trame émise ou reçue par la pile est copiée dans la file des
+/// sockets raw ouverts dont le filtre l'accepte. Les files vivent hors de
+/// SOCKET_TABLE : le verrou CAPTURES est pris en dernier (aucun autre
+/// verrou n'est pris sous lui), ce qui permet de capturer depuis
+/// l'émission comme depuis l'interruption de réception.
+
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use spin::Mutex;
+use x86_64::instructions::interrupts::without_interrupts;
+
+use super::arp::Ipv4Address;
+use super::ethernet::{EtherType, MacAddress};
+use super::ip::IpAddress;
+use super::ipv4::IpProtocol;
+use super::ipv6::Ipv6Address;
+
+/// Trames en attente par socket raw ; au-delà, les plus anciennes sont perdues
+const QUEUE_LIMIT: usize = 256;
+
+/// Trame capturée
+#[derive(Debug, Clone)]
+pub struct CapturedFrame {
+    /// Instant de la capture (ns depuis le démarrage)
+    pub timestamp_ns: u64,
+    /// Trame émise par la pile (sinon reçue)
+    pub outgoing: bool,
+    /// Trame Ethernet complète
+    pub data: Vec<u8>,
+}
+
+/// Protocole retenu par un filtre
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterProtocol {
+    Arp,
+    /// Tout IPv4
+    Ip,
+    /// Tout IPv6
+    Ip6,
+    Tcp,
+    Udp,
+    /// ICMP ou ICMPv6
+    Icmp,
+}
+
+/// Filtre simple à la BPF : protocole, port, hôte (tous facultatifs,
+/// combinés par « et »)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PacketFilter {
+    pub protocol: Option<FilterProtocol>,
+    /// Port source ou destination (TCP/UDP)
+    pub port: Option<u16>,
+    /// Adresse source ou destination
+    pub host: Option<IpAddress>,
+}
+
+impl PacketFilter {
+    /// Analyse une expression tcpdump réduite :
+    /// `[arp|ip|ip6|tcp|udp|icmp] [port N] [host A]`, « and » ignoré
+    pub fn parse(words: &[&str]) -> Result<Self, &'static str> {
+        let mut filter = PacketFilter::default();
+        let mut words = words.iter();
+        while let Some(word) = words.next() {
+            let protocol = match *word {
+                "and" => continue,
+                "arp" => FilterProtocol::Arp,
+                "ip" => FilterProtocol::Ip,
+                "ip6" => FilterProtocol::Ip6,
+                "tcp" => FilterProtocol::Tcp,
+                "udp" => FilterProtocol::Udp,
+                "icmp" | "icmp6" => FilterProtocol::Icmp,
+                "port" => {
+                    filter.port = Some(words.next().and_then(|p| p.parse().ok()).ok_or("port invalide")?);
+                    continue;
+                }
+                "host" => {
+                    filter.host = Some(words.next().and_then(|h| h.parse().ok()).ok_or("hôte invalide")?);
+                    continue;
+                }
+                _ => return Err("expression de filtre inconnue"),
+            };
+            if filter.protocol.replace(protocol).is_some() {
+                return Err("un seul protocole par filtre");
+            }
+        }
+        Ok(filter)
+    }
+
+    /// La trame Ethernet passe le filtre
+    pub fn matches(&self, frame: &[u8]) -> bool {
+        let headers = match Headers::parse(frame) {
+            Some(headers) => headers,
+            None => return *self == PacketFilter::default(),
+        };
+        let protocol_ok = match self.protocol {
+            None => true,
+            Some(FilterProtocol::Arp) => headers.ether_type == EtherType::ARP,
+            Some(FilterProtocol::Ip) => headers.ether_type == EtherType::IPv4,
+            Some(FilterProtocol::Ip6) => headers.ether_type == EtherType::IPv6,
+            Some(FilterProtocol::Tcp) => headers.protocol == Some(IpProtocol::TCP),
+            Some(FilterProtocol::Udp) => headers.protocol == Some(IpProtocol::UDP),
+            Some(FilterProtocol::Icmp) => {
+                matches!(headers.protocol, Some(IpProtocol::ICMP | IpProtocol::ICMPv6))
+            }
+        };
+        let port_ok = match (self.port, headers.ports()) {
+            (None, _) => true,
+            (Some(port), Some((src, dst))) => port == src || port == dst,
+            (Some(_), None) => false,
+        };
+        let host_ok = match self.host {
+            None => true,
+            Some(host) => headers.src == Some(host) || headers.dst == Some(host),
+        };
+        protocol_ok && port_ok && host_ok
+    }
+}
+
+/// En-têtes d'une trame, décodés sans copie
+struct Headers<'a> {
+    src_mac: MacAddress,
+    dst_mac: MacAddress,
+    ether_type: EtherType,
+    src: Option<IpAddress>,
+    dst: Option<IpAddress>,
+    protocol: Option<IpProtocol>,
+    /// Charge utile de la couche réseau (ARP, ou transport après l'en-tête IP)
+    payload: &'a [u8],
+}
+
+impl<'a> Headers<'a> {
+    fn parse(frame: &'a [u8]) -> Option<Self> {
+        if frame.len() < 14 {
+            return None;
+        }
+        let mac = |bytes: &[u8]| MacAddress([bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5]]);
+        let mut headers = Headers {
+            dst_mac: mac(&frame[0..6]),
+            src_mac: mac(&frame[6..12]),
+            ether_type: EtherType::from(u16::from_be_bytes([frame[12], frame[13]])),
+            src: None,
+            dst: None,
+            protocol: None,
+            payload: &frame[14..],
+        };
+        let network = headers.payload;
+        match headers.ether_type {
+            EtherType::IPv4 if network.len() >= 20 => {
+                let header_len = ((network[0] & 0x0F) as usize * 4).max(20);
+                let total_len = (u16::from_be_bytes([network[2], network[3]]) as usize).min(network.len());
+                headers.src = Some(IpAddress::V4(Ipv4Address::new(network[12], network[13], network[14], network[15])));
+                headers.dst = Some(IpAddress::V4(Ipv4Address::new(network[16], network[17], network[18], network[19])));
+                headers.protocol = Some(IpProtocol::from(network[9]));
+                headers.payload = network.get(header_len..total_len.max(header_len)).unwrap_or(&[]);
+            }
+            EtherType::IPv6 if network.len() >= 40 => {
+                let address = |bytes: &[u8]| {
+                    let mut ip = [0u8; 16];
+                    ip.copy_from_slice(bytes);
+                    IpAddress::V6(Ipv6Address(ip))
+                };
+                let payload_len = u16::from_be_bytes([network[4], network[5]]) as usize;
+                headers.src = Some(address(&network[8..24]));
+                headers.dst = Some(address(&network[24..40]));
+                headers.protocol = Some(IpProtocol::from(network[6]));
+                headers.payload = &network[40..network.len().min(40 + payload_len)];
+            }
+            EtherType::ARP if network.len() >= 28 => {
+                headers.src = Some(IpAddress::V4(Ipv4Address::new(network[14], network[15], network[16], network[17])));
+                headers.dst = Some(IpAddress::V4(Ipv4Address::new(network[24], network[25], network[26], network[27])));
+            }
+            _ => {}
+        }
+        Some(headers)
+    }
+
+    /// Ports source et destination d'un segment TCP ou datagramme UDP
+    fn ports(&self) -> Option<(u16, u16)> {
+        match (self.protocol, self.payload.get(..4)) {
+            (Some(IpProtocol::TCP | IpProtocol::UDP), Some(p)) => {
+                Some((u16::from_be_bytes([p[0], p[1]]), u16::from_be_bytes([p[2], p[3]])))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn be16(bytes: &[u8], at: usize) -> u16 {
+    u16::from_be_bytes([bytes[at], bytes[at + 1]])
+}
+
+fn be32(bytes: &[u8], at: usize) -> u32 {
+    u32::from_be_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+}
+
+/// Drapeaux TCP à la tcpdump : [S.], [P.], [F.]...
+fn tcp_flags(flags: u8) -> String {
+    let mut text = String::new();
+    for (bit, letter) in [(0x02, 'S'), (0x01, 'F'), (0x04, 'R'), (0x08, 'P'), (0x20, 'U'), (0x10, '.')] {
+        if flags & bit != 0 {
+            text.push(letter);
+        }
+    }
+    if text.is_empty() {
+        text.push_str("none");
+    }
+    text
+}
+
+/// Résumé d'une ligne d'une trame, dans le style de tcpdump
+pub fn summarize(frame: &[u8]) -> String {
+    let headers = match Headers::parse(frame) {
+        Some(headers) => headers,
+        None => return format!("trame tronquée ({} octets)", frame.len()),
+    };
+    let payload = headers.payload;
+
+    let (family, src, dst) = match (headers.ether_type, headers.src, headers.dst) {
+        (EtherType::ARP, Some(sender), Some(target)) => {
+            return match be16(payload, 6) {
+                1 => format!("ARP, Request who-has {} tell {}, length {}", target, sender, payload.len()),
+                2 => format!("ARP, Reply {} is-at {}, length {}", sender, headers.src_mac, payload.len()),
+                op => format!("ARP, opération {}, length {}", op, payload.len()),
+            };
+        }
+        (EtherType::IPv4, Some(src), Some(dst)) => ("IP", src, dst),
+        (EtherType::IPv6, Some(src), Some(dst)) => ("IP6", src, dst),
+        (ether_type, _, _) => {
+            return format!(
+                "{} > {}, ethertype 0x{:04x}, length {}",
+                headers.src_mac,
+                headers.dst_mac,
+                u16::from(ether_type),
+                frame.len()
+            );
+        }
+    };
+
+    match (headers.protocol, headers.ports()) {
+        (Some(IpProtocol::TCP), Some((sport, dport))) if payload.len() >= 20 => {
+            let data_offset = ((payload[12] >> 4) as usize * 4).clamp(20, payload.len());
+            let mut text = format!(
+                "{} {}.{} > {}.{}: Flags [{}], seq {}",
+                family,
+                src,
+                sport,
+                dst,
+                dport,
+                tcp_flags(payload[13]),
+                be32(payload, 4)
+            );
+            if payload[13] & 0x10 != 0 {
+                text.push_str(&format!(", ack {}", be32(payload, 8)));
+            }
+            text.push_str(&format!(", win {}, length {}", be16(payload, 14), payload.len() - data_offset));
+            text
+        }
+        (Some(IpProtocol::UDP), Some((sport, dport))) if payload.len() >= 8 => {
+            format!("{} {}.{} > {}.{}: UDP, length {}", family, src, sport, dst, dport, payload.len() - 8)
+        }
+        (Some(IpProtocol::ICMP), _) if payload.len() >= 8 => {
+            let kind = match payload[0] {
+                0 => "echo reply",
+                3 => "destination unreachable",
+                8 => "echo request",
+                11 => "time exceeded",
+                _ => "type inconnu",
+            };
+            format!("IP {} > {}: ICMP {}, id {}, seq {}, length {}", src, dst, kind, be16(payload, 4), be16(payload, 6), payload.len())
+        }
+        (Some(IpProtocol::ICMPv6), _) if payload.len() >= 4 => {
+            let kind = match payload[0] {
+                1 => "destination unreachable",
+                128 => "echo request",
+                129 => "echo reply",
+                133 => "router solicitation",
+                134 => "router advertisement",
+                135 => "neighbor solicitation",
+                136 => "neighbor advertisement",
+                _ => "type inconnu",
+            };
+            format!("IP6 {} > {}: ICMP6, {}, length {}", src, dst, kind, payload.len())
+        }
+        (protocol, _) => {
+            let number: u8 = protocol.map_or(0, u8::from);
+            format!("{} {} > {}: protocole {}, length {}", family, src, dst, number, payload.len())
+        }
+    }
+}
+
+/// Vidage hexadécimal et ASCII, 16 octets par ligne (tcpdump -X)
+pub fn hexdump(data: &[u8]) -> String {
+    let mut text = String::new();
+    for (line, chunk) in data.chunks(16).enumerate() {
+        text.push_str(&format!("\t0x{:04x}:  ", line * 16));
+        for i in 0..16 {
+            match chunk.get(i) {
+                Some(byte) => text.push_str(&format!("{:02x}", byte)),
+                None => text.push_str("  "),
+            }
+            if i % 2 == 1 {
+                text.push(' ');
+            }
+        }
+        text.push(' ');
+        text.extend(chunk.iter().map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' }));
+        text.push('\n');
+    }
+    text
+}
+
+/// File de capture d'un socket raw
+struct Capture {
+    filter: PacketFilter,
+    frames: VecDeque<CapturedFrame>,
+    /// Trames perdues faute de place
+    dropped: u64,
+}
+
+/// Files de capture par identifiant de socket
+static CAPTURES: Mutex<BTreeMap<u32, Capture>> = Mutex::new(BTreeMap::new());
+/// Nombre de captures ouvertes : évite de copier les trames sans lecteur
+static ACTIVE: AtomicUsize = AtomicUsize::new(0);
+
+/// Ouvre la file de capture du socket `id`
+pub fn open(id: u32) {
+    without_interrupts(|| {
+        let capture = Capture { filter: PacketFilter::default(), frames: VecDeque::new(), dropped: 0 };
+        if CAPTURES.lock().insert(id, capture).is_none() {
+            ACTIVE.fetch_add(1, Ordering::SeqCst);
+        }
+    });
+}
+
+pub fn close(id: u32) {
+    without_interrupts(|| {
+        if CAPTURES.lock().remove(&id).is_some() {
+            ACTIVE.fetch_sub(1, Ordering::SeqCst);
+        }
+    });
+}
+
+/// Remplace le filtre du socket `id` ; false si ce n'est pas un socket raw
+pub fn set_filter(id: u32, filter: PacketFilter) -> bool {
+    without_interrupts(|| match CAPTURES.lock().get_mut(&id) {
+        Some(capture) => {
+            capture.filter = filter;
+            capture.frames.clear();
+            true
+        }
+        None => false,
+    })
+}
+
+/// Prochaine trame capturée pour le socket `id`
+pub fn pop(id: u32) -> Option<CapturedFrame> {
+    without_interrupts(|| CAPTURES.lock().get_mut(&id).and_then(|capture| capture.frames.pop_front()))
+}
+
+/// Trames perdues par le socket `id` (file pleine)
+pub fn dropped(id: u32) -> u64 {
+    without_interrupts(|| CAPTURES.lock().get(&id).map_or(0, |capture| capture.dropped))
+}
+
+/// Copie `frame` dans les files dont le filtre l'accepte
+pub fn tap(frame: &[u8], outgoing: bool) {
+    if ACTIVE.load(Ordering::Relaxed) == 0 {
+        return;
+    }
+    let timestamp_ns = crate::time::now_ns();
+    without_interrupts(|| {
+        for capture in CAPTURES.lock().values_mut().filter(|c| c.filter.matches(frame)) {
+            if capture.frames.len() >= QUEUE_LIMIT {
+                capture.frames.pop_front();
+                capture.dropped += 1;
+            }
+            capture.frames.push_back(CapturedFrame { timestamp_ns, outgoing, data: frame.to_vec() });
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::tcp::{TcpFlags, TcpSegment};
+    use crate::net::{EthernetFrame, Ipv4Packet};
+
+    fn tcp_frame() -> Vec<u8> {
+        let (src, dst) = (Ipv4Address::new(10, 0, 2, 15), Ipv4Address::new(10, 0, 2, 2));
+        let mut segment = TcpSegment::new(49152, 80, 1000, 0, TcpFlags::syn(), Vec::new());
+        let mut packet = Ipv4Packet::new(src, dst, IpProtocol::TCP, segment.serialize_for(src, dst));
+        EthernetFrame::new(MacAddress::BROADCAST, MacAddress::ZERO, EtherType::IPv4, packet.serialize()).serialize()
+    }
+
+    #[test_case]
+    fn test_packet_filter() {
+        let frame = tcp_frame();
+        assert!(PacketFilter::default().matches(&frame));
+        assert!(PacketFilter::parse(&["tcp", "and", "port", "80"]).unwrap().matches(&frame));
+        assert!(PacketFilter::parse(&["host", "10.0.2.2"]).unwrap().matches(&frame));
+        assert!(!PacketFilter::parse(&["udp"]).unwrap().matches(&frame));
+        assert!(!PacketFilter::parse(&["port", "53"]).unwrap().matches(&frame));
+        assert!(PacketFilter::parse(&["tcp", "udp"]).is_err());
+    }
+
+    #[test_case]
+    fn test_summarize_tcp() {
+        assert_eq!(
+            summarize(&tcp_frame()),
+            "IP 10.0.2.15.49152 > 10.0.2.2.80: Flags [S], seq 1000, win 65535, length 0"
+        );
+    }
+}
@@ -1,13 +1,35 @@
 /// Module DNS (Domain Name System)
 /// 
-/// Client DNS basique pour la résolution de noms (Type A / IPv4)
+/// Résolveur de noms (Type A / IPv4) : /etc/hosts, puis cache, puis
+/// requêtes UDP aux serveurs configurés. Les alias CNAME sont suivis et
+/// les réponses gardées en cache pendant leur TTL.
 
+use alloc::collections::BTreeMap;
 use alloc::vec::Vec;
 use alloc::string::String;
-use super::ipv4::Ipv4Packet;
-use super::udp::UdpDatagram;
-use super::socket::{SocketAddr, Socket};
+use lazy_static::lazy_static;
+use spin::Mutex;
+use x86_64::instructions::interrupts::without_interrupts;
+use super::socket::{self, SocketAddr, SocketDomain, SocketType, SOCKET_TABLE};
 use super::arp::Ipv4Address;
+use super::interface;
+use crate::time::{self, NSEC_PER_SEC};
+
+/// Fichier des noms statiques
+pub const HOSTS_PATH: &str = "/etc/hosts";
+/// Serveurs DNS (lignes « nameserver <ip> »)
+pub const RESOLV_CONF_PATH: &str = "/etc/resolv.conf";
+/// Serveur DNS du réseau « user » de QEMU
+pub const DEFAULT_SERVER: Ipv4Address = Ipv4Address([10, 0, 2, 3]);
+
+/// Attente d'une réponse avant nouvel essai
+const QUERY_TIMEOUT_NS: u64 = 2 * NSEC_PER_SEC;
+/// Essais par serveur
+const QUERY_ATTEMPTS: usize = 3;
+/// Longueur maximale d'une chaîne d'alias
+const MAX_CNAME_DEPTH: usize = 8;
+/// Pointeurs de compression suivis au plus dans un nom
+const MAX_NAME_JUMPS: usize = 16;
 
 /// Flags DNS
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -176,37 +198,57 @@ impl DnsPacket {
     }
 }
 
+/// Lit un nom encodé en labels à `offset`, pointeurs de compression
+/// (0xC0) compris ; `offset` avance jusqu'après le nom à cet endroit
+pub fn read_name(data: &[u8], offset: &mut usize) -> Option<String> {
+    let mut name = String::new();
+    let mut pos = *offset;
+    let mut jumps = 0;
+    loop {
+        let len = *data.get(pos)? as usize;
+        if len == 0 {
+            if jumps == 0 {
+                *offset = pos + 1;
+            }
+            return Some(name);
+        }
+        if len & 0xC0 == 0xC0 {
+            let target = ((len & 0x3F) << 8) | *data.get(pos + 1)? as usize;
+            if jumps == 0 {
+                *offset = pos + 2;
+            }
+            jumps += 1;
+            if jumps > MAX_NAME_JUMPS {
+                return None;
+            }
+            pos = target;
+            continue;
+        }
+        let label = data.get(pos + 1..pos + 1 + len)?;
+        if !name.is_empty() {
+            name.push('.');
+        }
+        name.push_str(core::str::from_utf8(label).ok()?);
+        pos += 1 + len;
+    }
+}
+
 /// Réponse DNS (Record)
 #[derive(Debug)]
 pub struct DnsRecord {
-    pub name: String, // Simplification: nom canonique
+    pub name: String,
     pub rtype: u16,
     pub rclass: u16,
     pub ttl: u32,
     pub data_len: u16,
     pub rdata: Vec<u8>,
+    /// Cible d'un CNAME, décodée dans le message complet (compression)
+    pub cname: Option<String>,
 }
 
 impl DnsRecord {
     pub fn parse(data: &[u8], offset: &mut usize) -> Option<Self> {
-        // TODO: Parsing complexe des labels (compression avec ptrs 0xC0)
-        // Pour l'instant, skip le nom si c'est un pointeur
-        if *offset >= data.len() { return None; }
-        
-        // Skip name (très simplifié)
-        loop {
-            if *offset >= data.len() { return None; }
-            let len = data[*offset];
-            if len == 0 {
-                *offset += 1;
-                break;
-            } else if (len & 0xC0) == 0xC0 {
-                *offset += 2; // Pointeur (2 bytes)
-                break;
-            } else {
-                *offset += (len as usize) + 1;
-            }
-        }
+        let name = read_name(data, offset)?;
         
         if *offset + 10 > data.len() { return None; }
         
@@ -219,15 +261,21 @@ impl DnsRecord {
         
         if *offset + (data_len as usize) > data.len() { return None; }
         let rdata = data[*offset..*offset+(data_len as usize)].to_vec();
+        let cname = if rtype == DnsType::CNAME as u16 {
+            read_name(data, &mut offset.clone())
+        } else {
+            None
+        };
         *offset += data_len as usize;
         
         Some(Self {
-            name: String::new(), // TODO parse name
+            name,
             rtype,
             rclass,
             ttl,
             data_len,
             rdata,
+            cname,
         })
     }
 }
@@ -243,77 +291,276 @@ pub enum DnsError {
     Timeout,
 }
 
-/// Résout un nom de domaine en adresse IPv4 (enregistrement A)
-pub fn resolve(domain: &str, dns_server: Ipv4Address) -> Result<Ipv4Address, DnsError> {
-    use super::socket::{SocketDomain, SocketType, SOCKET_TABLE};
-    
-    // Créer un socket UDP
-    let mut table = SOCKET_TABLE.lock();
-    let socket_id = table.socket(SocketDomain::Inet, SocketType::Datagram)
-        .map_err(|_| DnsError::SocketError)?;
-    
-    // Bind sur un port éphémère (pseudo-aléatoire ou fixe pour test)
-    let local_port = 49152 + (unsafe { core::arch::x86_64::_rdtsc() } % 1000) as u16;
-    let local_addr = SocketAddr::new(Ipv4Address::new(0, 0, 0, 0), local_port);
-    table.bind(socket_id, local_addr).map_err(|_| DnsError::SocketError)?;
-    
-    // Connecter au serveur DNS
-    let remote_addr = SocketAddr::new(dns_server, 53);
-    table.connect(socket_id, remote_addr).map_err(|_| DnsError::SocketError)?;
-    
-    drop(table); // Libérer le lock avant d'attendre
-    
-    // Préparer la requête
-    let packet = DnsPacket::new(domain);
-    let bytes = packet.serialize();
-    
-    // Envoyer
-    let mut table = SOCKET_TABLE.lock();
-    table.send(socket_id, &bytes).map_err(|_| DnsError::SendError)?;
-    drop(table);
-    
-    // Attendre la réponse (polling simple avec "timeout" basé sur des boucles)
-    let mut buffer = [0u8; 512]; // Taille max standard UDP DNS
-    let mut retries = 0;
-    loop {
+/// RCODE « nom inexistant »
+const RCODE_NXDOMAIN: u8 = 3;
+
+/// Enregistrements de réponse d'un message DNS
+///
+/// Vérifie l'identifiant et le code de retour, saute la section question.
+fn parse_response(data: &[u8], id: u16) -> Result<Vec<DnsRecord>, DnsError> {
+    if data.len() < 12 {
+        return Err(DnsError::ParseError);
+    }
+    if u16::from_be_bytes([data[0], data[1]]) != id {
+        return Err(DnsError::ParseError);
+    }
+    let flags = DnsFlags::from_u16(u16::from_be_bytes([data[2], data[3]]));
+    if !flags.qr {
+        return Err(DnsError::ParseError);
+    }
+    match flags.rcode {
+        0 => {}
+        RCODE_NXDOMAIN => return Err(DnsError::NameNotFound),
+        _ => return Err(DnsError::RecvError),
+    }
+    let questions = u16::from_be_bytes([data[4], data[5]]);
+    let answers = u16::from_be_bytes([data[6], data[7]]);
+
+    let mut offset = 12;
+    for _ in 0..questions {
+        read_name(data, &mut offset).ok_or(DnsError::ParseError)?;
+        offset += 4; // QTYPE, QCLASS
+    }
+    let mut records = Vec::with_capacity(answers as usize);
+    for _ in 0..answers {
+        records.push(DnsRecord::parse(data, &mut offset).ok_or(DnsError::ParseError)?);
+    }
+    Ok(records)
+}
+
+/// Contenu utile d'une réponse pour le nom demandé
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Answer {
+    Address(Ipv4Address, u32),
+    /// Alias sans adresse dans la réponse : à redemander
+    Alias(String, u32),
+}
+
+/// Suit la chaîne CNAME de `name` dans les enregistrements d'une réponse
+///
+/// Le TTL retenu est le plus court de la chaîne.
+fn follow_answers(name: &str, records: &[DnsRecord]) -> Option<Answer> {
+    let mut current = String::from(name);
+    let mut ttl = u32::MAX;
+    for _ in 0..MAX_CNAME_DEPTH {
+        let find = |rtype: DnsType| {
+            records
+                .iter()
+                .find(|r| r.rtype == rtype as u16 && r.name.eq_ignore_ascii_case(&current))
+        };
+        if let Some(record) = find(DnsType::A).filter(|r| r.rdata.len() == 4) {
+            let ip = Ipv4Address::from_bytes([record.rdata[0], record.rdata[1], record.rdata[2], record.rdata[3]]);
+            return Some(Answer::Address(ip, ttl.min(record.ttl)));
+        }
+        let alias = find(DnsType::CNAME)?;
+        ttl = ttl.min(alias.ttl);
+        current = alias.cname.clone()?;
+    }
+    Some(Answer::Alias(current, ttl))
+}
+
+/// Entrée du cache
+#[derive(Debug, Clone, Copy)]
+struct CacheEntry {
+    address: Ipv4Address,
+    /// Échéance en ns de `time::now_ns`
+    expires: u64,
+}
+
+lazy_static! {
+    /// Réponses par nom (minuscules)
+    static ref CACHE: Mutex<BTreeMap<String, CacheEntry>> = Mutex::new(BTreeMap::new());
+    /// Serveurs utilisés à défaut de /etc/resolv.conf
+    static ref SERVERS: Mutex<Vec<Ipv4Address>> = Mutex::new(alloc::vec![DEFAULT_SERVER]);
+}
+
+/// Remplace la liste des serveurs DNS (DHCP, configuration réseau)
+pub fn set_servers(servers: Vec<Ipv4Address>) {
+    *SERVERS.lock() = servers;
+}
+
+/// Serveurs à interroger : ceux de /etc/resolv.conf, sinon la liste configurée
+pub fn servers() -> Vec<Ipv4Address> {
+    let from_file: Vec<Ipv4Address> = crate::fs::vfs_read_file(RESOLV_CONF_PATH)
+        .ok()
+        .map(|content| {
+            String::from_utf8_lossy(&content)
+                .lines()
+                .filter_map(|line| line.trim().strip_prefix("nameserver"))
+                .filter_map(|ip| ip.trim().parse().ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    if from_file.is_empty() {
+        SERVERS.lock().clone()
+    } else {
+        from_file
+    }
+}
+
+/// Cherche `name` dans un contenu au format /etc/hosts
+/// (« <ip> <nom> [alias...] », commentaires après #)
+fn parse_hosts(content: &str, name: &str) -> Option<Ipv4Address> {
+    content.lines().find_map(|line| {
+        let line = line.split('#').next().unwrap_or("");
+        let mut fields = line.split_whitespace();
+        let ip = fields.next()?.parse().ok()?;
+        fields.any(|host| host.eq_ignore_ascii_case(name)).then_some(ip)
+    })
+}
+
+fn hosts_lookup(name: &str) -> Option<Ipv4Address> {
+    if let Ok(content) = crate::fs::vfs_read_file(HOSTS_PATH) {
+        if let Some(ip) = parse_hosts(&String::from_utf8_lossy(&content), name) {
+            return Some(ip);
+        }
+    }
+    name.eq_ignore_ascii_case("localhost").then_some(Ipv4Address::new(127, 0, 0, 1))
+}
+
+fn cache_lookup(name: &str) -> Option<Ipv4Address> {
+    let now = time::now_ns();
+    let mut cache = CACHE.lock();
+    match cache.get(name) {
+        Some(entry) if entry.expires > now => Some(entry.address),
+        Some(_) => {
+            cache.remove(name);
+            None
+        }
+        None => None,
+    }
+}
+
+fn cache_insert(name: &str, address: Ipv4Address, ttl: u32) {
+    let expires = time::now_ns() + ttl as u64 * NSEC_PER_SEC;
+    CACHE.lock().insert(String::from(name), CacheEntry { address, expires });
+}
+
+/// Vide le cache du résolveur
+pub fn flush_cache() {
+    CACHE.lock().clear();
+}
+
+/// Une requête A pour `name` auprès de `server`, avec ses essais
+fn query(server: Ipv4Address, name: &str) -> Result<Answer, DnsError> {
+    let socket_id = without_interrupts(|| {
         let mut table = SOCKET_TABLE.lock();
-        match table.recv(socket_id, &mut buffer) {
-            Ok(len) => {
-                // Analyser la réponse
-                let mut offset = 0;
-                // Skip header (12 bytes)
-                if len < 12 { return Err(DnsError::ParseError); }
-                
-                offset += 12;
-                
-                // Skip Query (variable length)
-                let q_len = encode_dns_name(domain).len() + 4;
-                offset += q_len;
-                
-                if offset >= len { return Err(DnsError::ParseError); }
-                
-                // Parse Answer
-                if let Some(record) = DnsRecord::parse(&buffer[..len], &mut offset) {
-                    if record.rtype == 1 && record.data_len == 4 { // Type A, Len 4
-                         let ip = Ipv4Address::new(
-                             record.rdata[0],
-                             record.rdata[1],
-                             record.rdata[2],
-                             record.rdata[3]
-                         );
-                         return Ok(ip);
-                    }
+        let id = table.socket(SocketDomain::Inet, SocketType::Datagram).map_err(|_| DnsError::SocketError)?;
+        let local = SocketAddr::new(Ipv4Address::new(0, 0, 0, 0), table.ephemeral_port());
+        table.bind(id, local)
+            .and_then(|_| table.connect(id, SocketAddr::new(server, 53)))
+            .map_err(|_| DnsError::SocketError)?;
+        Ok(id)
+    })?;
+
+    let result = exchange(socket_id, name);
+    let _ = without_interrupts(|| SOCKET_TABLE.lock().close(socket_id));
+    result
+}
+
+fn exchange(socket_id: u32, name: &str) -> Result<Answer, DnsError> {
+    let mut buffer = [0u8; 512]; // Taille max standard UDP DNS
+    for _ in 0..QUERY_ATTEMPTS {
+        let packet = DnsPacket::new(name);
+        socket::send_datagram(socket_id, &packet.serialize()).map_err(|_| DnsError::SendError)?;
+
+        let start = time::Instant::now();
+        while start.elapsed_ns() < QUERY_TIMEOUT_NS {
+            interface::poll();
+            let received = without_interrupts(|| SOCKET_TABLE.lock().recv(socket_id, &mut buffer));
+            match received {
+                Ok(len) => match parse_response(&buffer[..len], packet.header.id) {
+                    Ok(records) => return follow_answers(name, &records).ok_or(DnsError::NameNotFound),
+                    Err(DnsError::ParseError) => continue, // Réponse à une autre requête
+                    Err(e) => return Err(e),
+                },
+                Err(socket::SocketError::WouldBlock) => interface::idle(),
+                Err(_) => return Err(DnsError::RecvError),
+            }
+        }
+    }
+    Err(DnsError::Timeout)
+}
+
+/// Résout `name` auprès des serveurs, alias CNAME compris
+fn lookup(name: &str) -> Result<(Ipv4Address, u32), DnsError> {
+    let servers = servers();
+    let mut current = String::from(name);
+    let mut ttl = u32::MAX;
+    for _ in 0..MAX_CNAME_DEPTH {
+        let mut error = DnsError::Timeout;
+        let mut answer = None;
+        for &server in &servers {
+            match query(server, &current) {
+                Ok(found) => {
+                    answer = Some(found);
+                    break;
                 }
-                return Err(DnsError::NameNotFound);
-            },
-            Err(super::socket::SocketError::WouldBlock) => {
-                // Attendre un peu
-                drop(table);
-                for _ in 0..10000 { core::hint::spin_loop(); }
-                retries += 1;
-                if retries > 10000 { return Err(DnsError::Timeout); }
-            },
-            Err(_) => return Err(DnsError::RecvError),
+                // Réponse définitive : inutile d'interroger les autres serveurs
+                Err(DnsError::NameNotFound) => return Err(DnsError::NameNotFound),
+                Err(e) => error = e,
+            }
         }
+        match answer {
+            Some(Answer::Address(ip, record_ttl)) => return Ok((ip, ttl.min(record_ttl))),
+            Some(Answer::Alias(alias, record_ttl)) => {
+                ttl = ttl.min(record_ttl);
+                current = alias;
+            }
+            None => return Err(error),
+        }
+    }
+    Err(DnsError::NameNotFound)
+}
+
+/// Résout un nom d'hôte en adresse IPv4
+///
+/// Adresse littérale, puis /etc/hosts, puis cache, puis serveurs DNS.
+pub fn resolve(hostname: &str) -> Result<Ipv4Address, DnsError> {
+    if let Ok(ip) = hostname.parse::<Ipv4Address>() {
+        return Ok(ip);
+    }
+    let name = hostname.trim_end_matches('.').to_ascii_lowercase();
+    if let Some(ip) = hosts_lookup(&name) {
+        return Ok(ip);
+    }
+    if let Some(ip) = without_interrupts(|| cache_lookup(&name)) {
+        return Ok(ip);
+    }
+    let (ip, ttl) = lookup(&name)?;
+    without_interrupts(|| cache_insert(&name, ip, ttl));
+    Ok(ip)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_parse_hosts() {
+        let hosts = "# statique\n127.0.0.1 localhost\n10.0.2.2  gateway gw # QEMU\n";
+        assert_eq!(parse_hosts(hosts, "gw"), Some(Ipv4Address::new(10, 0, 2, 2)));
+        assert_eq!(parse_hosts(hosts, "LOCALHOST"), Some(Ipv4Address::new(127, 0, 0, 1)));
+        assert_eq!(parse_hosts(hosts, "QEMU"), None);
+    }
+
+    #[test_case]
+    fn test_response_with_cname() {
+        // www.a.b -> CNAME a.b (pointeur) -> A 1.2.3.4
+        let mut msg = alloc::vec![0x12, 0x34, 0x81, 0x80, 0, 1, 0, 2, 0, 0, 0, 0];
+        msg.extend_from_slice(&encode_dns_name("www.a.b")); // offset 12
+        msg.extend_from_slice(&[0, 1, 0, 1]);
+        // CNAME : nom = pointeur 12, rdata = pointeur vers « a.b » (offset 16)
+        msg.extend_from_slice(&[0xC0, 12, 0, 5, 0, 1, 0, 0, 0, 60, 0, 2, 0xC0, 16]);
+        // A : nom = pointeur 16
+        msg.extend_from_slice(&[0xC0, 16, 0, 1, 0, 1, 0, 0, 1, 0, 0, 4, 1, 2, 3, 4]);
+
+        let records = parse_response(&msg, 0x1234).unwrap();
+        assert_eq!(records[0].cname.as_deref(), Some("a.b"));
+        assert_eq!(
+            follow_answers("www.a.b", &records),
+            Some(Answer::Address(Ipv4Address::new(1, 2, 3, 4), 60))
+        );
+        assert_eq!(parse_response(&msg, 0x4321).unwrap_err(), DnsError::ParseError);
     }
 }
@@ -1,13 +1,22 @@
 /// Module HTTP (Hypertext Transfer Protocol)
-/// 
-/// Client HTTP/1.1 basique (GET uniquement)
+///
+/// Client HTTP/1.1 basique (GET uniquement) : en-tête Host, corps par
+/// Content-Length, chunked ou jusqu'à la fermeture, redirections suivies.
 
 use alloc::vec::Vec;
-use alloc::string::String;
+use alloc::string::{String, ToString};
 use alloc::format;
-use super::socket::{SocketAddr, Socket};
-use super::arp::Ipv4Address;
-use crate::net::dns::resolve;
+use super::socket::{self, SocketAddr, SocketDomain, SocketType, SOCKET_TABLE};
+use super::dns::resolve;
+use crate::time::NSEC_PER_SEC;
+use x86_64::instructions::interrupts::without_interrupts;
+
+/// Redirections suivies au plus
+pub const MAX_REDIRECTS: usize = 5;
+/// Attente de l'établissement de la connexion
+const CONNECT_TIMEOUT_NS: u64 = 5 * NSEC_PER_SEC;
+/// Silence maximal du serveur pendant la réception
+const RECV_TIMEOUT_NS: u64 = 10 * NSEC_PER_SEC;
 
 /// Erreurs HTTP
 #[derive(Debug)]
@@ -18,14 +27,146 @@ pub enum HttpError {
     DnsError,
     ParseError,
     InvalidStatus,
+    /// Schéma autre que http://
+    UnsupportedScheme,
+    TooManyRedirects,
+}
+
+/// URL http:// décomposée
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Url {
+    pub host: String,
+    pub port: u16,
+    /// Chemin et requête, commence par '/'
+    pub path: String,
+}
+
+impl Url {
+    pub fn parse(url: &str) -> Result<Self, HttpError> {
+        let rest = match url.find("://") {
+            Some(index) if url[..index].eq_ignore_ascii_case("http") => &url[index + 3..],
+            Some(_) => return Err(HttpError::UnsupportedScheme),
+            None => url,
+        };
+        let (authority, path) = match rest.find('/') {
+            Some(index) => (&rest[..index], &rest[index..]),
+            None => (rest, "/"),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().map_err(|_| HttpError::ParseError)?),
+            None => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err(HttpError::ParseError);
+        }
+        Ok(Self { host: host.to_string(), port, path: path.to_string() })
+    }
+
+    /// Cible d'un en-tête Location (absolue ou relative à cette URL)
+    pub fn join(&self, location: &str) -> Result<Self, HttpError> {
+        if location.contains("://") {
+            return Self::parse(location);
+        }
+        let path = if location.starts_with('/') {
+            location.to_string()
+        } else {
+            let dir = &self.path[..self.path.rfind('/').map_or(0, |i| i + 1)];
+            format!("{}{}", dir, location)
+        };
+        Ok(Self { host: self.host.clone(), port: self.port, path })
+    }
+
+    /// Valeur de l'en-tête Host
+    fn host_header(&self) -> String {
+        if self.port == 80 { self.host.clone() } else { format!("{}:{}", self.host, self.port) }
+    }
 }
 
 /// Réponse HTTP simplifiée
 pub struct HttpResponse {
     pub status_code: u16,
+    pub headers: Vec<(String, String)>,
     pub body: Vec<u8>,
 }
 
+impl HttpResponse {
+    /// Valeur d'un en-tête (nom insensible à la casse)
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Requête GET pour `url`
+pub fn build_request(url: &Url) -> String {
+    format!(
+        "GET {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: RustOS/0.1\r\nAccept: */*\r\nConnection: close\r\n\r\n",
+        url.path,
+        url.host_header()
+    )
+}
+
+/// Ligne de statut et en-têtes ; retourne aussi le début du corps
+fn parse_head(data: &[u8]) -> Result<(u16, Vec<(String, String)>, usize), HttpError> {
+    let end = data.windows(4).position(|w| w == b"\r\n\r\n").ok_or(HttpError::ParseError)?;
+    let head = core::str::from_utf8(&data[..end]).map_err(|_| HttpError::ParseError)?;
+    let mut lines = head.split("\r\n");
+
+    // HTTP/1.1 200 OK
+    let status_line = lines.next().ok_or(HttpError::ParseError)?;
+    let mut parts = status_line.splitn(3, ' ');
+    if !parts.next().map_or(false, |version| version.starts_with("HTTP/")) {
+        return Err(HttpError::ParseError);
+    }
+    let status_code = parts.next().and_then(|code| code.parse().ok()).ok_or(HttpError::InvalidStatus)?;
+
+    let headers = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .collect();
+    Ok((status_code, headers, end + 4))
+}
+
+/// Décode un corps « Transfer-Encoding: chunked »
+pub fn decode_chunked(data: &[u8]) -> Result<Vec<u8>, HttpError> {
+    let mut body = Vec::new();
+    let mut pos = 0;
+    loop {
+        let line_end = data[pos..].windows(2).position(|w| w == b"\r\n").ok_or(HttpError::ParseError)? + pos;
+        let size_line = core::str::from_utf8(&data[pos..line_end]).map_err(|_| HttpError::ParseError)?;
+        // Extensions éventuelles après ';'
+        let size_hex = size_line.split(';').next().unwrap_or("").trim();
+        let size = usize::from_str_radix(size_hex, 16).map_err(|_| HttpError::ParseError)?;
+        pos = line_end + 2;
+        if size == 0 {
+            return Ok(body);
+        }
+        let chunk = data.get(pos..pos + size).ok_or(HttpError::ParseError)?;
+        body.extend_from_slice(chunk);
+        pos += size + 2; // CRLF de fin de bloc
+    }
+}
+
+/// Décode une réponse complète (en-têtes et corps)
+fn parse_response(data: &[u8]) -> Result<HttpResponse, HttpError> {
+    let (status_code, headers, body_start) = parse_head(data)?;
+    let mut response = HttpResponse { status_code, headers, body: Vec::new() };
+    let raw = &data[body_start..];
+
+    let chunked = response
+        .header("Transfer-Encoding")
+        .map_or(false, |encoding| encoding.eq_ignore_ascii_case("chunked"));
+    let length = response.header("Content-Length").and_then(|l| l.parse::<usize>().ok());
+    response.body = match (chunked, length) {
+        (true, _) => decode_chunked(raw)?,
+        (false, Some(length)) => raw.get(..length).ok_or(HttpError::RecvError)?.to_vec(),
+        (false, None) => raw.to_vec(),
+    };
+    Ok(response)
+}
+
 /// Client HTTP
 pub struct HttpClient;
 
@@ -33,112 +174,77 @@ impl HttpClient {
     pub fn new() -> Self {
         Self
     }
-    
-    /// Effectue une requête GET
+
+    /// Effectue une requête GET en suivant les redirections
     pub fn get(url: &str) -> Result<HttpResponse, HttpError> {
-        // Parsing basique de l'URL "http://domain/path"
-        let url_part = if url.starts_with("http://") {
-            &url[7..]
-        } else {
-            url
-        };
-        
-        // Séparer domaine et path
-        let (domain, path) = if let Some(slash_idx) = url_part.find('/') {
-            (&url_part[..slash_idx], &url_part[slash_idx..])
-        } else {
-            (url_part, "/")
-        };
-        
-        // Résoudre le domaine
-        // Pour l'instant, hardcode DNS 8.8.8.8 pour la résolution
-        let dns_server = Ipv4Address::new(8, 8, 8, 8);
-        let ip = resolve(domain, dns_server).map_err(|_| HttpError::DnsError)?;
-        
-        use super::socket::{SocketDomain, SocketType, SOCKET_TABLE};
-        
-        let mut table = SOCKET_TABLE.lock();
-        let socket_id = table.socket(SocketDomain::Inet, SocketType::Stream)
+        let mut url = Url::parse(url)?;
+        for _ in 0..=MAX_REDIRECTS {
+            let response = Self::fetch(&url)?;
+            match (response.status_code, response.header("Location")) {
+                (301 | 302 | 303 | 307 | 308, Some(location)) => url = url.join(location)?,
+                _ => return Ok(response),
+            }
+        }
+        Err(HttpError::TooManyRedirects)
+    }
+
+    /// Une requête GET, sans suivre les redirections
+    pub fn fetch(url: &Url) -> Result<HttpResponse, HttpError> {
+        let ip = resolve(&url.host).map_err(|_| HttpError::DnsError)?;
+
+        let socket_id = without_interrupts(|| SOCKET_TABLE.lock().socket(SocketDomain::Inet, SocketType::Stream))
+            .map_err(|_| HttpError::ConnectionFailed)?;
+        let result = Self::exchange(socket_id, SocketAddr::new(ip, url.port), url);
+        let _ = socket::stream_close(socket_id);
+        result
+    }
+
+    fn exchange(socket_id: u32, remote: SocketAddr, url: &Url) -> Result<HttpResponse, HttpError> {
+        socket::stream_connect(socket_id, remote, CONNECT_TIMEOUT_NS)
             .map_err(|_| HttpError::ConnectionFailed)?;
-        
-        // Port 80
-        let remote_addr = SocketAddr::new(ip, 80);
-        
-        // Connect (TCP Handshake)
-        table.connect(socket_id, remote_addr)
-             .map_err(|_| HttpError::ConnectionFailed)?;
-             
-        drop(table); // Libérer pour le handshake qui peut prendre du temps
-        
-        // Attendre que la connexion soit établie (polling état socket)
-        // TODO: Implémenter une vraie attente, ici on suppose que connect bloque ou réussit vite
-        // En vrai, connect est non-bloquant ou asynchrone dans notre implémentation actuelle
-        // Il faudrait attendre l'état Established.
-        
-        // Construire la requête
-        let request = format!(
-            "GET {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: RustOS/0.1\r\nConnection: close\r\n\r\n",
-            path, domain
-        );
-        
-        let mut table = SOCKET_TABLE.lock();
-        table.send(socket_id, request.as_bytes())
-             .map_err(|_| HttpError::SendError)?;
-        drop(table);
-        
-        // Lire la réponse
+        socket::stream_send(socket_id, build_request(url).as_bytes())
+            .map_err(|_| HttpError::SendError)?;
+
+        // Lire la réponse jusqu'à la fermeture (Connection: close)
         let mut response_data = Vec::new();
         let mut buffer = [0u8; 1024];
-        
         loop {
-            let mut table = SOCKET_TABLE.lock();
-            match table.recv(socket_id, &mut buffer) {
-                Ok(len) => {
-                    if len == 0 { break; } // Fin de connexion
-                    response_data.extend_from_slice(&buffer[..len]);
-                },
-                Err(super::socket::SocketError::WouldBlock) => {
-                    drop(table);
-                    // Attente active
-                    for _ in 0..1000 { core::hint::spin_loop(); }
-                    continue;
-                },
+            match socket::stream_recv(socket_id, &mut buffer, RECV_TIMEOUT_NS) {
+                Ok(0) => break,
+                Ok(len) => response_data.extend_from_slice(&buffer[..len]),
                 Err(_) => return Err(HttpError::RecvError),
             }
-            drop(table);
-        }
-        
-        // Parser la réponse (très simplifié)
-        // HTTP/1.1 200 OK\r\n...
-        if response_data.len() < 12 { return Err(HttpError::ParseError); }
-        
-        // Status code
-        // "HTTP/1.1 " -> 9 chars
-        // Code -> 3 chars chars[9..12]
-        // TODO: Vérifier proprement
-        // let status_str = core::str::from_utf8(&response_data[9..12]).map_err(|_| HttpError::ParseError)?;
-        // let status_code = status_str.parse::<u16>().map_err(|_| HttpError::ParseError)?;
-        
-        let status_code = 200; // Mock pour l'instant
-        
-        // Trouver la fin des headers (\r\n\r\n)
-        let mut body_start = 0;
-        for i in 0..response_data.len()-3 {
-            if &response_data[i..i+4] == b"\r\n\r\n" {
-                body_start = i + 4;
-                break;
-            }
         }
-        
-        let body = if body_start > 0 {
-            response_data[body_start..].to_vec()
-        } else {
-            Vec::new()
-        };
-        
-        Ok(HttpResponse {
-            status_code,
-            body,
-        })
+
+        parse_response(&response_data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_url_parse_and_join() {
+        let url = Url::parse("http://example.com:8080/a/b.html?x=1").unwrap();
+        assert_eq!((url.host.as_str(), url.port, url.path.as_str()), ("example.com", 8080, "/a/b.html?x=1"));
+        assert_eq!(url.join("c.html").unwrap().path, "/a/c.html");
+        assert_eq!(url.join("/root").unwrap().path, "/root");
+        assert_eq!(url.join("http://other/").unwrap().host, "other");
+        assert!(matches!(Url::parse("https://example.com/"), Err(HttpError::UnsupportedScheme)));
+        assert!(build_request(&url).contains("Host: example.com:8080\r\n"));
+    }
+
+    #[test_case]
+    fn test_parse_chunked_response() {
+        let raw = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n4\r\nWiki\r\n6;x=y\r\npedia \r\n0\r\n\r\n";
+        let response = parse_response(raw).unwrap();
+        assert_eq!(response.status_code, 200);
+        assert_eq!(response.body, b"Wikipedia ");
+
+        let raw = b"HTTP/1.0 302 Found\r\nlocation: /next\r\ncontent-length: 2\r\n\r\nokIGNORED";
+        let response = parse_response(raw).unwrap();
+        assert_eq!(response.header("Location"), Some("/next"));
+        assert_eq!(response.body, b"ok");
     }
 }
@@ -0,0 +1,311 @@
+/// Serveur HTTP du noyau (httpd)
+///
+/// Un kthread écoute sur le port configuré et dépose les connexions
+/// établies dans une file ; WORKERS kthreads les servent en parallèle.
+/// Seuls GET et HEAD sont pris en charge : fichier de la racine
+/// documentaire avec son Content-Length, ou index d'un répertoire
+/// (index.html s'il existe, sinon liste générée).
+
+use alloc::collections::VecDeque;
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use crate::sync::Mutex;
+use x86_64::instructions::interrupts::without_interrupts;
+
+use super::arp::Ipv4Address;
+use super::socket::{self, SocketAddr, SocketDomain, SocketType, SOCKET_TABLE};
+use crate::fs::{self, FileType, VfsError};
+use crate::process::{Thread, ProcessPriority};
+use crate::process::thread::alloc_tid;
+use crate::time::NSEC_PER_SEC;
+
+pub const DEFAULT_ROOT: &str = "/var/www";
+pub const DEFAULT_PORT: u16 = 80;
+/// Kthreads servant les connexions
+pub const WORKERS: usize = 4;
+
+/// Connexions en attente d'acceptation
+const BACKLOG: usize = 16;
+/// Taille maximale des en-têtes d'une requête
+const MAX_REQUEST: usize = 8192;
+/// Attente de la requête complète
+const REQUEST_TIMEOUT_NS: u64 = 5 * NSEC_PER_SEC;
+/// Attente d'une connexion avant de revérifier l'état du service
+const ACCEPT_SLICE_NS: u64 = NSEC_PER_SEC / 10;
+
+/// Racine documentaire
+static ROOT: Mutex<String> = Mutex::new(String::new());
+/// Socket en écoute (0 : service arrêté)
+static LISTENER: AtomicU32 = AtomicU32::new(0);
+/// Kthreads créés au premier démarrage, puis réutilisés
+static THREADS_SPAWNED: AtomicBool = AtomicBool::new(false);
+/// Connexions établies en attente d'un worker
+static QUEUE: Mutex<VecDeque<u32>> = Mutex::new(VecDeque::new());
+
+/// Démarre le service sur `port`, fichiers servis depuis `root`
+pub fn start(root: &str, port: u16) -> Result<(), &'static str> {
+    if is_running() {
+        return Err("httpd déjà démarré");
+    }
+    match fs::vfs_stat(root) {
+        Ok(stat) if stat.file_type == FileType::Directory => {}
+        _ => return Err("racine documentaire introuvable"),
+    }
+
+    let id = without_interrupts(|| {
+        let mut table = SOCKET_TABLE.lock();
+        let id = table.socket(SocketDomain::Inet, SocketType::Stream).map_err(|_| "socket")?;
+        let bound = table
+            .bind(id, SocketAddr::new(Ipv4Address::new(0, 0, 0, 0), port))
+            .and_then(|_| table.listen(id, BACKLOG));
+        if bound.is_err() {
+            let _ = table.close(id);
+            return Err("écoute impossible");
+        }
+        Ok(id)
+    })?;
+
+    *ROOT.lock() = String::from(root.trim_end_matches('/'));
+    LISTENER.store(id, Ordering::SeqCst);
+
+    if !THREADS_SPAWNED.swap(true, Ordering::SeqCst) {
+        spawn("httpd", listener_main);
+        for _ in 0..WORKERS {
+            spawn("httpd-worker", worker_main);
+        }
+    }
+    crate::serial_println!("httpd: port {}, racine {}", port, root);
+    Ok(())
+}
+
+/// Arrête l'écoute ; les requêtes en cours se terminent
+pub fn stop() {
+    let id = LISTENER.swap(0, Ordering::SeqCst);
+    if id == 0 {
+        return;
+    }
+    let waiting: Vec<u32> = without_interrupts(|| {
+        let mut table = SOCKET_TABLE.lock();
+        let _ = table.close(id);
+        QUEUE.lock().drain(..).collect()
+    });
+    for connection in waiting {
+        let _ = socket::stream_close(connection);
+    }
+}
+
+pub fn is_running() -> bool {
+    LISTENER.load(Ordering::SeqCst) != 0
+}
+
+/// Crée un kthread (famille PID 0) et l'ajoute au scheduler
+fn spawn(name: &str, entry: fn() -> !) {
+    let mut thread = Thread::new(alloc_tid(), 0, name, ProcessPriority::Normal, 0);
+    thread.alloc_kernel_stack();
+    thread.context.rip = entry as u64;
+    crate::scheduler::SCHEDULER.add_thread(Arc::new(Mutex::new(thread)));
+}
+
+/// Accepte les connexions et les confie aux workers
+fn listener_main() -> ! {
+    loop {
+        let id = LISTENER.load(Ordering::SeqCst);
+        if id == 0 {
+            crate::power::cpu_idle();
+            continue;
+        }
+        match socket::stream_accept(id, ACCEPT_SLICE_NS) {
+            Ok((connection, _)) => without_interrupts(|| QUEUE.lock().push_back(connection)),
+            Err(socket::SocketError::WouldBlock) => {}
+            // Socket fermé par `stop` entre-temps
+            Err(_) => crate::power::cpu_idle(),
+        }
+    }
+}
+
+fn worker_main() -> ! {
+    loop {
+        match without_interrupts(|| QUEUE.lock().pop_front()) {
+            Some(connection) => {
+                serve(connection);
+                let _ = socket::stream_close(connection);
+            }
+            None => crate::power::cpu_idle(),
+        }
+    }
+}
+
+/// Lit la requête, répond, sans fermer la connexion
+fn serve(connection: u32) {
+    let mut request = Vec::new();
+    let mut buffer = [0u8; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+        match socket::stream_recv(connection, &mut buffer, REQUEST_TIMEOUT_NS) {
+            Ok(0) | Err(_) => return,
+            Ok(len) => request.extend_from_slice(&buffer[..len]),
+        }
+        if request.len() > MAX_REQUEST {
+            let _ = socket::stream_send(connection, &response(431, "text/plain", b"Request Header Fields Too Large\n", false));
+            return;
+        }
+    }
+
+    let root = ROOT.lock().clone();
+    let reply = handle_request(&root, &String::from_utf8_lossy(&request));
+    let _ = socket::stream_send(connection, &reply);
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        301 => "Moved Permanently",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        431 => "Request Header Fields Too Large",
+        _ => "Internal Server Error",
+    }
+}
+
+/// Type MIME d'après l'extension
+fn content_type(path: &str) -> &'static str {
+    let extension = path.rsplit_once('.').map_or("", |(_, ext)| ext);
+    match extension.to_ascii_lowercase().as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css",
+        "js" => "application/javascript",
+        "json" => "application/json",
+        "txt" | "md" | "rs" | "sh" | "conf" => "text/plain; charset=utf-8",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Réponse complète ; `head_only` omet le corps (HEAD) mais garde sa taille
+fn response(status: u16, content_type: &str, body: &[u8], head_only: bool) -> Vec<u8> {
+    let mut bytes = format!(
+        "HTTP/1.1 {} {}\r\nServer: RustOS\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        reason(status),
+        content_type,
+        body.len()
+    )
+    .into_bytes();
+    if !head_only {
+        bytes.extend_from_slice(body);
+    }
+    bytes
+}
+
+fn error_page(status: u16, head_only: bool) -> Vec<u8> {
+    let body = format!("<html><body><h1>{} {}</h1></body></html>\n", status, reason(status));
+    response(status, "text/html; charset=utf-8", body.as_bytes(), head_only)
+}
+
+/// Chemin de la requête sans la chaîne de requête ; None s'il sort de la
+/// racine (segment « .. »)
+fn sanitize_path(target: &str) -> Option<&str> {
+    let path = target.split(|c| c == '?' || c == '#').next().unwrap_or("/");
+    if !path.starts_with('/') || path.split('/').any(|segment| segment == "..") {
+        return None;
+    }
+    Some(path)
+}
+
+/// Page listant le contenu d'un répertoire
+fn directory_index(url_path: &str, entries: &[String]) -> String {
+    let mut page = format!("<html><head><title>Index de {0}</title></head><body><h1>Index de {0}</h1><ul>\n", url_path);
+    if url_path != "/" {
+        page.push_str("<li><a href=\"../\">../</a></li>\n");
+    }
+    for name in entries.iter().filter(|name| name.as_str() != "." && name.as_str() != "..") {
+        page.push_str(&format!("<li><a href=\"{0}\">{0}</a></li>\n", name));
+    }
+    page.push_str("</ul></body></html>\n");
+    page
+}
+
+/// Réponse à une requête brute, fichiers lus sous `root`
+pub fn handle_request(root: &str, request: &str) -> Vec<u8> {
+    let mut parts = request.lines().next().unwrap_or("").split_whitespace();
+    let (method, target) = match (parts.next(), parts.next()) {
+        (Some(method), Some(target)) => (method, target),
+        _ => return error_page(400, false),
+    };
+    let head_only = match method {
+        "GET" => false,
+        "HEAD" => true,
+        _ => return error_page(405, false),
+    };
+    let url_path = match sanitize_path(target) {
+        Some(path) => path,
+        None => return error_page(403, head_only),
+    };
+
+    let mut path = format!("{}{}", root, url_path);
+    match fs::vfs_stat(&path) {
+        Ok(stat) if stat.file_type == FileType::Directory => {
+            if !url_path.ends_with('/') {
+                // Liens relatifs de l'index : le répertoire doit finir par '/'
+                return format!(
+                    "HTTP/1.1 301 {}\r\nServer: RustOS\r\nLocation: {}/\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    reason(301),
+                    url_path
+                )
+                .into_bytes();
+            }
+            let index = format!("{}index.html", path);
+            if fs::vfs_stat(&index).is_ok() {
+                path = index;
+            } else {
+                return match fs::vfs_ls(&path) {
+                    Ok(entries) => {
+                        let page = directory_index(url_path, &entries);
+                        response(200, "text/html; charset=utf-8", page.as_bytes(), head_only)
+                    }
+                    Err(_) => error_page(500, head_only),
+                };
+            }
+        }
+        Ok(_) => {}
+        Err(VfsError::NotFound) => return error_page(404, head_only),
+        Err(_) => return error_page(500, head_only),
+    }
+
+    match fs::vfs_read_file(&path) {
+        Ok(content) => response(200, content_type(&path), &content, head_only),
+        Err(VfsError::NotFound) => error_page(404, head_only),
+        Err(_) => error_page(500, head_only),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_sanitize_and_content_type() {
+        assert_eq!(sanitize_path("/docs/a.html?x=1"), Some("/docs/a.html"));
+        assert_eq!(sanitize_path("/../etc/passwd"), None);
+        assert_eq!(sanitize_path("relative"), None);
+        assert_eq!(content_type("/INDEX.HTM"), "text/html; charset=utf-8");
+        assert_eq!(content_type("/archive"), "application/octet-stream");
+    }
+
+    #[test_case]
+    fn test_response_headers() {
+        let reply = response(200, "text/plain", b"hello", true);
+        let text = String::from_utf8(reply).unwrap();
+        assert!(text.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(text.contains("Content-Length: 5\r\n"));
+        assert!(text.ends_with("\r\n\r\n"));
+        assert!(core::str::from_utf8(&handle_request("/", "PUT / HTTP/1.1\r\n\r\n")).unwrap().starts_with("HTTP/1.1 405"));
+    }
+}
@@ -72,6 +72,12 @@ impl IcmpMessage {
         }
     }
     
+    /// Echo Reply correspondant à cet Echo Request (mêmes identifiant,
+    /// séquence et données)
+    pub fn reply(&self) -> Self {
+        Self::echo_reply(self.identifier, self.sequence, self.payload.clone())
+    }
+    
     /// Parse un message ICMP
     pub fn parse(data: &[u8]) -> Result<Self, IcmpError> {
         if data.len() < Self::MIN_HEADER_SIZE {
@@ -184,4 +190,16 @@ mod tests {
         assert_eq!(parsed.identifier, 1);
         assert_eq!(parsed.sequence, 1);
     }
+    
+    #[test_case]
+    fn test_icmp_echo_reply_checksum() {
+        let request = IcmpMessage::echo_request(0x1234, 7, vec![0xAA; 5]);
+        let bytes = request.reply().serialize();
+        // Checksum valide : la somme complète du message vaut 0
+        assert_eq!(IcmpMessage::calculate_checksum(&bytes), 0);
+        let parsed = IcmpMessage::parse(&bytes).unwrap();
+        assert_eq!(parsed.icmp_type, IcmpType::EchoReply);
+        assert_eq!((parsed.identifier, parsed.sequence), (0x1234, 7));
+        assert_eq!(parsed.payload, vec![0xAA; 5]);
+    }
 }
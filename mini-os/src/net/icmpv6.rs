@@ -0,0 +1,217 @@
+/// Module ICMPv6 (RFC 4443) et messages Neighbor Discovery (RFC 4861)
+///
+/// Le checksum couvre le pseudo-en-tête IPv6 : il est calculé à la
+/// sérialisation, adresses source et destination connues.
+
+use alloc::vec::Vec;
+use super::ethernet::MacAddress;
+use super::ip::{self, IpAddress};
+use super::ipv4::IpProtocol;
+use super::ipv6::Ipv6Address;
+
+/// Type de message ICMPv6
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Icmpv6Type {
+    DestinationUnreachable,
+    EchoRequest,
+    EchoReply,
+    RouterSolicitation,
+    RouterAdvertisement,
+    NeighborSolicitation,
+    NeighborAdvertisement,
+    Unknown(u8),
+}
+
+impl From<u8> for Icmpv6Type {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => Icmpv6Type::DestinationUnreachable,
+            128 => Icmpv6Type::EchoRequest,
+            129 => Icmpv6Type::EchoReply,
+            133 => Icmpv6Type::RouterSolicitation,
+            134 => Icmpv6Type::RouterAdvertisement,
+            135 => Icmpv6Type::NeighborSolicitation,
+            136 => Icmpv6Type::NeighborAdvertisement,
+            other => Icmpv6Type::Unknown(other),
+        }
+    }
+}
+
+impl From<Icmpv6Type> for u8 {
+    fn from(icmp_type: Icmpv6Type) -> u8 {
+        match icmp_type {
+            Icmpv6Type::DestinationUnreachable => 1,
+            Icmpv6Type::EchoRequest => 128,
+            Icmpv6Type::EchoReply => 129,
+            Icmpv6Type::RouterSolicitation => 133,
+            Icmpv6Type::RouterAdvertisement => 134,
+            Icmpv6Type::NeighborSolicitation => 135,
+            Icmpv6Type::NeighborAdvertisement => 136,
+            Icmpv6Type::Unknown(v) => v,
+        }
+    }
+}
+
+/// Option ND : adresse lien de l'émetteur
+pub const OPTION_SOURCE_LINK_ADDRESS: u8 = 1;
+/// Option ND : adresse lien de la cible
+pub const OPTION_TARGET_LINK_ADDRESS: u8 = 2;
+
+/// Drapeaux d'un Neighbor Advertisement
+const NA_SOLICITED: u8 = 0x40;
+const NA_OVERRIDE: u8 = 0x20;
+
+/// Message ICMPv6
+#[derive(Debug, Clone)]
+pub struct Icmpv6Message {
+    pub icmp_type: Icmpv6Type,
+    pub code: u8,
+    pub checksum: u16,
+    /// Corps après le checksum (identifiant et séquence d'un Echo, cible
+    /// et options d'un message ND...)
+    pub body: Vec<u8>,
+}
+
+impl Icmpv6Message {
+    pub const HEADER_SIZE: usize = 4;
+
+    fn new(icmp_type: Icmpv6Type, body: Vec<u8>) -> Self {
+        Self { icmp_type, code: 0, checksum: 0, body }
+    }
+
+    pub fn echo_request(identifier: u16, sequence: u16, payload: &[u8]) -> Self {
+        let mut body = Vec::with_capacity(4 + payload.len());
+        body.extend_from_slice(&identifier.to_be_bytes());
+        body.extend_from_slice(&sequence.to_be_bytes());
+        body.extend_from_slice(payload);
+        Self::new(Icmpv6Type::EchoRequest, body)
+    }
+
+    /// Echo Reply correspondant à cet Echo Request
+    pub fn reply(&self) -> Self {
+        Self::new(Icmpv6Type::EchoReply, self.body.clone())
+    }
+
+    /// Identifiant et séquence d'un Echo
+    pub fn echo_ids(&self) -> Option<(u16, u16)> {
+        match self.body.get(..4) {
+            Some(ids) => Some((u16::from_be_bytes([ids[0], ids[1]]), u16::from_be_bytes([ids[2], ids[3]]))),
+            None => None,
+        }
+    }
+
+    /// Neighbor Solicitation pour `target`, avec notre adresse lien
+    pub fn neighbor_solicitation(target: Ipv6Address, our_mac: MacAddress) -> Self {
+        let mut body = alloc::vec![0u8; 4];
+        body.extend_from_slice(&target.0);
+        body.extend_from_slice(&[OPTION_SOURCE_LINK_ADDRESS, 1]);
+        body.extend_from_slice(&our_mac.0);
+        Self::new(Icmpv6Type::NeighborSolicitation, body)
+    }
+
+    /// Neighbor Advertisement de `target` (notre adresse) vers `our_mac`
+    pub fn neighbor_advertisement(target: Ipv6Address, our_mac: MacAddress, solicited: bool) -> Self {
+        let flags = NA_OVERRIDE | if solicited { NA_SOLICITED } else { 0 };
+        let mut body = alloc::vec![flags, 0, 0, 0];
+        body.extend_from_slice(&target.0);
+        body.extend_from_slice(&[OPTION_TARGET_LINK_ADDRESS, 1]);
+        body.extend_from_slice(&our_mac.0);
+        Self::new(Icmpv6Type::NeighborAdvertisement, body)
+    }
+
+    /// Adresse cible d'un Neighbor Solicitation/Advertisement
+    pub fn target(&self) -> Option<Ipv6Address> {
+        let bytes = self.body.get(4..20)?;
+        let mut target = [0u8; 16];
+        target.copy_from_slice(bytes);
+        Some(Ipv6Address(target))
+    }
+
+    /// Adresse lien portée par l'option ND `kind`
+    pub fn link_address_option(&self, kind: u8) -> Option<MacAddress> {
+        let mut options = self.body.get(20..)?;
+        // Chaque option : type, longueur (unités de 8 octets), données
+        while options.len() >= 8 {
+            let len = options[1] as usize * 8;
+            if len == 0 || len > options.len() {
+                return None;
+            }
+            if options[0] == kind {
+                let mut mac = [0u8; 6];
+                mac.copy_from_slice(&options[2..8]);
+                return Some(MacAddress(mac));
+            }
+            options = &options[len..];
+        }
+        None
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Self, Icmpv6Error> {
+        if data.len() < Self::HEADER_SIZE {
+            return Err(Icmpv6Error::TooShort);
+        }
+        Ok(Self {
+            icmp_type: Icmpv6Type::from(data[0]),
+            code: data[1],
+            checksum: u16::from_be_bytes([data[2], data[3]]),
+            body: data[4..].to_vec(),
+        })
+    }
+
+    /// Checksum sur le pseudo-en-tête IPv6 et le message (`data`)
+    pub fn calculate_checksum(src: Ipv6Address, dst: Ipv6Address, data: &[u8]) -> u16 {
+        let pseudo = ip::pseudo_header_sum(
+            IpAddress::V6(src),
+            IpAddress::V6(dst),
+            IpProtocol::ICMPv6.into(),
+            data.len() as u32,
+        );
+        ip::fold_checksum(pseudo + ip::sum_words(data))
+    }
+
+    /// Message reçu intègre (un checksum correct donne une somme nulle)
+    pub fn verify(src: Ipv6Address, dst: Ipv6Address, data: &[u8]) -> bool {
+        Self::calculate_checksum(src, dst, data) == 0
+    }
+
+    /// Sérialise le message, checksum calculé pour `src` -> `dst`
+    pub fn serialize(&mut self, src: Ipv6Address, dst: Ipv6Address) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(Self::HEADER_SIZE + self.body.len());
+        bytes.push(self.icmp_type.into());
+        bytes.push(self.code);
+        bytes.extend_from_slice(&[0, 0]);
+        bytes.extend_from_slice(&self.body);
+        self.checksum = Self::calculate_checksum(src, dst, &bytes);
+        bytes[2..4].copy_from_slice(&self.checksum.to_be_bytes());
+        bytes
+    }
+}
+
+/// Erreurs ICMPv6
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Icmpv6Error {
+    TooShort,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_neighbor_solicitation() {
+        let mac = MacAddress([0x52, 0x54, 0, 0x12, 0x34, 0x56]);
+        let src = Ipv6Address::link_local_from_mac(mac);
+        let target: Ipv6Address = "fe80::1".parse().unwrap();
+
+        let bytes = Icmpv6Message::neighbor_solicitation(target, mac).serialize(src, target.solicited_node());
+        assert!(Icmpv6Message::verify(src, target.solicited_node(), &bytes));
+        let parsed = Icmpv6Message::parse(&bytes).unwrap();
+        assert_eq!(parsed.icmp_type, Icmpv6Type::NeighborSolicitation);
+        assert_eq!(parsed.target(), Some(target));
+        assert_eq!(parsed.link_address_option(OPTION_SOURCE_LINK_ADDRESS), Some(mac));
+        assert_eq!(parsed.link_address_option(OPTION_TARGET_LINK_ADDRESS), None);
+
+        let echo = Icmpv6Message::echo_request(7, 3, b"abc");
+        assert_eq!(echo.reply().echo_ids(), Some((7, 3)));
+    }
+}
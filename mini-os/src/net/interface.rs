@@ -1,18 +1,111 @@
 /// Module d'Interface Réseau
 /// 
 /// Gère l'interface entre le matériel (driver) et la stack réseau (sockets).
+///
+/// L'adresse, l'état et les routes de l'interface se changent à chaud
+/// (commandes ifconfig et ip) et sont conservés dans CONFIG_PATH, relu
+/// par les drivers après la configuration par défaut.
+///
+/// En IPv6, l'interface porte une adresse lien-local dérivée de sa MAC
+/// et les adresses ajoutées par `ip addr add` ; les voisins sont résolus
+/// par Neighbor Discovery (module ndp) au lieu d'ARP.
 
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
 use alloc::vec::Vec;
 use alloc::collections::BTreeMap;
+use core::sync::atomic::{AtomicBool, Ordering};
 use spin::Mutex;
 use lazy_static::lazy_static;
 
 use super::ethernet::{EthernetFrame, MacAddress, EtherType};
 use super::ipv4::{Ipv4Packet, IpProtocol};
-use super::arp::{ArpCache, ARP_CACHE, Ipv4Address, ArpPacket};
-use super::socket::{SOCKET_TABLE, SocketType, SocketDomain};
+use super::ipv6::{Ipv6Address, Ipv6Packet};
+use super::ip::IpAddress;
+use super::arp::{ArpCache, Ipv4Address, ArpPacket, ArpOperation};
+use super::socket::{SOCKET_TABLE, SocketType};
 use super::udp::UdpDatagram;
 use super::tcp::TcpSegment;
+use super::icmp::{IcmpMessage, IcmpType};
+use super::icmpv6::{self, Icmpv6Message, Icmpv6Type};
+use super::ndp;
+use crate::time::{Instant, NSEC_PER_SEC};
+use x86_64::instructions::interrupts::without_interrupts;
+
+/// Attente maximale d'une réponse ARP ou Neighbor Advertisement
+const ARP_TIMEOUT_NS: u64 = NSEC_PER_SEC;
+
+/// Nom de l'interface (première carte enregistrée)
+pub const INTERFACE_NAME: &str = "eth0";
+/// Configuration persistante de l'interface
+pub const CONFIG_PATH: &str = "/etc/network.conf";
+
+/// Masque d'un préfixe de `prefix_len` bits
+pub fn prefix_netmask(prefix_len: u8) -> Ipv4Address {
+    let mask = match prefix_len {
+        0 => 0,
+        len => u32::MAX << (32 - len.min(32) as u32),
+    };
+    Ipv4Address(mask.to_be_bytes())
+}
+
+/// Longueur du préfixe d'un masque (bits à 1 de tête)
+pub fn netmask_prefix(netmask: Ipv4Address) -> u8 {
+    u32::from_be_bytes(netmask.0).leading_ones() as u8
+}
+
+/// Notation « a.b.c.d/len » (/32 si la longueur est absente)
+pub fn parse_cidr(s: &str) -> Option<(Ipv4Address, u8)> {
+    let (ip, len) = match s.split_once('/') {
+        Some((ip, len)) => (ip, len.parse().ok().filter(|&len: &u8| len <= 32)?),
+        None => (s, 32),
+    };
+    Some((ip.parse().ok()?, len))
+}
+
+/// Notation « x:y::z/len » (/128 si la longueur est absente)
+pub fn parse_cidr6(s: &str) -> Option<(Ipv6Address, u8)> {
+    let (ip, len) = match s.split_once('/') {
+        Some((ip, len)) => (ip, len.parse().ok().filter(|&len: &u8| len <= 128)?),
+        None => (s, 128),
+    };
+    Some((ip.parse().ok()?, len))
+}
+
+/// `a` et `b` dans le même réseau de masque `netmask`
+fn same_network(a: Ipv4Address, b: Ipv4Address, netmask: Ipv4Address) -> bool {
+    (0..4).all(|i| a.0[i] & netmask.0[i] == b.0[i] & netmask.0[i])
+}
+
+/// Route statique ; préfixe 0 pour la route par défaut
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Route {
+    pub destination: Ipv4Address,
+    pub prefix_len: u8,
+    /// Routeur à joindre ; None si la destination est sur le lien
+    pub gateway: Option<Ipv4Address>,
+}
+
+impl Route {
+    pub fn contains(&self, ip: Ipv4Address) -> bool {
+        same_network(ip, self.destination, prefix_netmask(self.prefix_len))
+    }
+}
+
+impl core::fmt::Display for Route {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        if self.prefix_len == 0 {
+            write!(f, "default")?;
+        } else {
+            write!(f, "{}/{}", self.destination, self.prefix_len)?;
+        }
+        match self.gateway {
+            Some(gateway) => write!(f, " via {}", gateway),
+            None => Ok(()),
+        }
+    }
+}
 
 /// Structure représentant une interface réseau
 pub struct NetworkInterface {
@@ -20,43 +113,199 @@ pub struct NetworkInterface {
     pub mac_address: MacAddress,
     /// Adresse IP de l'interface
     pub ip_address: Ipv4Address,
+    /// Masque du réseau local
+    pub netmask: Ipv4Address,
+    /// Interface active : à l'arrêt, ni émission ni réception
+    pub up: bool,
+    /// Routes statiques, dont la route par défaut
+    pub routes: Vec<Route>,
+    /// Adresses IPv6 et longueur de préfixe, lien-local en tête
+    pub ipv6_addresses: Vec<(Ipv6Address, u8)>,
+    /// Routeur IPv6 par défaut
+    pub ipv6_gateway: Option<Ipv6Address>,
 }
 
 impl NetworkInterface {
-    /// Crée une nouvelle interface
+    /// Crée une nouvelle interface active (réseau /24, sans passerelle,
+    /// adresse IPv6 lien-local)
     pub fn new(mac_address: MacAddress, ip_address: Ipv4Address) -> Self {
         Self {
             mac_address,
             ip_address,
+            netmask: Ipv4Address::new(255, 255, 255, 0),
+            up: true,
+            routes: Vec::new(),
+            ipv6_addresses: alloc::vec![(Ipv6Address::link_local_from_mac(mac_address), 64)],
+            ipv6_gateway: None,
+        }
+    }
+
+    /// Adresse lien-local de l'interface
+    pub fn link_local(&self) -> Ipv6Address {
+        self.ipv6_addresses[0].0
+    }
+
+    fn owns_ipv6(&self, ip: &Ipv6Address) -> bool {
+        self.ipv6_addresses.iter().any(|(own, _)| own == ip)
+    }
+
+    /// Paquet IPv6 pour nous : une de nos adresses, tous les nœuds ou le
+    /// groupe solicited-node d'une de nos adresses
+    fn accepts_ipv6(&self, dst: &Ipv6Address) -> bool {
+        *dst == Ipv6Address::ALL_NODES
+            || self.ipv6_addresses.iter().any(|(own, _)| own == dst || own.solicited_node() == *dst)
+    }
+
+    /// Adresse source vers `dst` : lien-local pour le lien, sinon une
+    /// adresse du même préfixe ou à défaut la première adresse globale
+    pub fn ipv6_source(&self, dst: &Ipv6Address) -> Ipv6Address {
+        if dst.is_link_local() || dst.is_multicast() {
+            return self.link_local();
         }
+        let globals = || self.ipv6_addresses.iter().skip(1);
+        globals()
+            .find(|(own, len)| own.same_prefix(dst, *len))
+            .or_else(|| globals().next())
+            .map_or(self.link_local(), |(own, _)| *own)
+    }
+
+    /// Voisin à résoudre pour joindre `dst` : direct sur le lien ou dans
+    /// un de nos préfixes, sinon le routeur par défaut
+    pub fn next_hop6(&self, dst: Ipv6Address) -> Ipv6Address {
+        let on_link = dst.is_link_local()
+            || dst.is_multicast()
+            || self.ipv6_addresses.iter().skip(1).any(|(own, len)| own.same_prefix(&dst, *len));
+        match self.ipv6_gateway {
+            Some(gateway) if !on_link => gateway,
+            _ => dst,
+        }
+    }
+
+    /// Adresse source pour joindre `dst`, de la même famille
+    pub fn source_for(&self, dst: IpAddress) -> IpAddress {
+        match dst {
+            IpAddress::V4(_) => IpAddress::V4(self.ip_address),
+            IpAddress::V6(dst) => IpAddress::V6(self.ipv6_source(&dst)),
+        }
+    }
+
+    /// Passerelle de la route par défaut
+    pub fn gateway(&self) -> Option<Ipv4Address> {
+        self.routes.iter().find(|r| r.prefix_len == 0).and_then(|r| r.gateway)
+    }
+
+    /// Adresse à résoudre en MAC pour joindre `dst` : directe sur le
+    /// réseau local, sinon passerelle de la route au plus long préfixe
+    pub fn next_hop(&self, dst: Ipv4Address) -> Ipv4Address {
+        if same_network(dst, self.ip_address, self.netmask) {
+            return dst;
+        }
+        let route = self.routes.iter().filter(|r| r.contains(dst)).max_by_key(|r| r.prefix_len);
+        route.and_then(|r| r.gateway).unwrap_or(dst)
+    }
+
+    /// Ajoute une route ; remplace celle de même destination
+    pub fn add_route(&mut self, route: Route) {
+        self.del_route(route.destination, route.prefix_len);
+        self.routes.push(route);
+    }
+
+    /// Retire une route ; false si elle n'existait pas
+    pub fn del_route(&mut self, destination: Ipv4Address, prefix_len: u8) -> bool {
+        let netmask = prefix_netmask(prefix_len);
+        let before = self.routes.len();
+        self.routes.retain(|r| !(r.prefix_len == prefix_len && same_network(r.destination, destination, netmask)));
+        self.routes.len() != before
+    }
+
+    /// Émet une trame Ethernet depuis cette interface
+    fn send_frame(&self, dst: MacAddress, ether_type: EtherType, payload: Vec<u8>) -> Result<(), NetDeviceError> {
+        if !self.up {
+            return Err(NetDeviceError::NotReady);
+        }
+        if payload.len() > EthernetFrame::MAX_PAYLOAD {
+            return Err(NetDeviceError::FrameTooLarge);
+        }
+        transmit(&EthernetFrame::new(dst, self.mac_address, ether_type, payload).serialize())
+    }
+
+    /// Encapsule `payload` dans un paquet IPv4 vers une MAC déjà résolue
+    fn send_ipv4_to(&self, dst_mac: MacAddress, dst: Ipv4Address, protocol: IpProtocol, payload: Vec<u8>) -> Result<(), NetDeviceError> {
+        let mut packet = Ipv4Packet::new(self.ip_address, dst, protocol, payload);
+        self.send_frame(dst_mac, EtherType::IPv4, packet.serialize())
+    }
+
+    /// Encapsule `payload` dans un paquet IPv6 vers une MAC déjà résolue
+    fn send_ipv6_to(&self, dst_mac: MacAddress, mut packet: Ipv6Packet) -> Result<(), NetDeviceError> {
+        if packet.src == Ipv6Address::UNSPECIFIED {
+            packet.src = self.ipv6_source(&packet.dst);
+        }
+        self.send_frame(dst_mac, EtherType::IPv6, packet.serialize())
+    }
+
+    /// Émet `payload` vers une MAC déjà résolue, dans la famille de `dst`
+    fn send_ip_to(&self, dst_mac: MacAddress, dst: IpAddress, protocol: IpProtocol, payload: Vec<u8>) -> Result<(), NetDeviceError> {
+        match dst {
+            IpAddress::V4(dst) => self.send_ipv4_to(dst_mac, dst, protocol, payload),
+            IpAddress::V6(dst) => self.send_ipv6_to(dst_mac, Ipv6Packet::new(Ipv6Address::UNSPECIFIED, dst, protocol, payload)),
+        }
+    }
+
+    /// Émet un segment TCP (checksum calculé) vers une MAC déjà résolue
+    fn send_tcp_to(&self, dst_mac: MacAddress, dst: IpAddress, mut segment: TcpSegment) -> Result<(), NetDeviceError> {
+        let bytes = segment.serialize_for(self.source_for(dst), dst);
+        self.send_ip_to(dst_mac, dst, IpProtocol::TCP, bytes)
+    }
+
+    /// Émet un message ICMPv6 (ND : hop limit 255) vers une MAC déjà résolue
+    fn send_icmpv6_to(&self, dst_mac: MacAddress, dst: Ipv6Address, mut message: Icmpv6Message) -> Result<(), NetDeviceError> {
+        let src = self.ipv6_source(&dst);
+        let mut packet = Ipv6Packet::new(src, dst, IpProtocol::ICMPv6, message.serialize(src, dst));
+        if matches!(message.icmp_type, Icmpv6Type::NeighborSolicitation | Icmpv6Type::NeighborAdvertisement) {
+            packet.hop_limit = 255;
+        }
+        self.send_ipv6_to(dst_mac, packet)
     }
 
     /// Traite une frame Ethernet reçue
     pub fn handle_ethernet_frame(&self, frame: &EthernetFrame) {
-        // Vérifier si la frame nous est destinée (ou broadcast)
-        if frame.dst != self.mac_address && !frame.dst.is_broadcast() {
+        // Vérifier si la frame nous est destinée (ou broadcast/multicast)
+        if !self.up || (frame.dst != self.mac_address && !frame.dst.is_multicast()) {
             return;
         }
 
         match frame.ether_type {
             EtherType::IPv4 => {
                 if let Ok(packet) = Ipv4Packet::parse(&frame.payload) {
-                    self.handle_ipv4_packet(&packet);
+                    self.handle_ipv4_packet(&packet, frame.src);
                 }
             }
             EtherType::ARP => {
-                // TODO: Gérer les paquets ARP
                 if let Ok(arp_packet) = ArpPacket::parse(&frame.payload) {
-                    let mut arp_cache = ARP_CACHE.lock();
-                    arp_cache.insert(arp_packet.sender_ip, arp_packet.sender_mac);
+                    self.handle_arp_packet(&arp_packet);
+                }
+            }
+            EtherType::IPv6 => {
+                if let Ok(packet) = Ipv6Packet::parse(&frame.payload) {
+                    self.handle_ipv6_packet(&packet, frame.src);
                 }
             }
             _ => {}
         }
     }
 
+    /// Apprend l'émetteur et répond aux requêtes qui visent notre adresse
+    fn handle_arp_packet(&self, packet: &ArpPacket) {
+        super::arp::cache_insert(packet.sender_ip, packet.sender_mac);
+
+        if packet.operation == ArpOperation::Request && packet.target_ip == self.ip_address {
+            let reply = ArpPacket::reply(self.mac_address, self.ip_address, packet.sender_mac, packet.sender_ip);
+            let _ = self.send_frame(packet.sender_mac, EtherType::ARP, reply.serialize().to_vec());
+        }
+    }
+
     /// Traite un paquet IPv4
-    fn handle_ipv4_packet(&self, packet: &Ipv4Packet) {
+    fn handle_ipv4_packet(&self, packet: &Ipv4Packet, src_mac: MacAddress) {
         // Vérifier si le paquet nous est destiné
         if packet.dst != self.ip_address {
              // TODO: Forwarding si routeur? Pour l'instant on ignore.
@@ -66,26 +315,118 @@ impl NetworkInterface {
         match packet.protocol {
             IpProtocol::UDP => {
                 if let Ok(dgram) = UdpDatagram::parse(&packet.payload) {
-                    self.handle_udp_datagram(&dgram, packet.src);
+                    self.handle_udp_datagram(&dgram, IpAddress::V4(packet.src));
+                }
+            }
+            IpProtocol::TCP => self.handle_tcp_segment(&packet.payload, IpAddress::V4(packet.src), src_mac),
+            IpProtocol::ICMP => self.handle_icmp_message(packet, src_mac),
+            _ => {}
+        }
+    }
+
+    /// Traite un paquet IPv6
+    fn handle_ipv6_packet(&self, packet: &Ipv6Packet, src_mac: MacAddress) {
+        if !self.accepts_ipv6(&packet.dst) {
+            return;
+        }
+
+        match packet.next_header {
+            IpProtocol::UDP => {
+                if let Ok(dgram) = UdpDatagram::parse(&packet.payload) {
+                    self.handle_udp_datagram(&dgram, IpAddress::V6(packet.src));
+                }
+            }
+            IpProtocol::TCP => self.handle_tcp_segment(&packet.payload, IpAddress::V6(packet.src), src_mac),
+            IpProtocol::ICMPv6 => self.handle_icmpv6_message(packet, src_mac),
+            _ => {}
+        }
+    }
+
+    /// Remet un segment TCP à sa connexion et émet la réponse immédiate
+    fn handle_tcp_segment(&self, data: &[u8], src: IpAddress, src_mac: MacAddress) {
+        if let Ok(segment) = TcpSegment::parse(data) {
+            let reply = SOCKET_TABLE.lock().on_tcp_segment(src, &segment);
+            if let Some(reply) = reply {
+                let _ = self.send_tcp_to(src_mac, src, reply);
+            }
+        }
+    }
+
+    /// Echo et Neighbor Discovery
+    fn handle_icmpv6_message(&self, packet: &Ipv6Packet, src_mac: MacAddress) {
+        if !Icmpv6Message::verify(packet.src, packet.dst, &packet.payload) {
+            return;
+        }
+        let message = match Icmpv6Message::parse(&packet.payload) {
+            Ok(message) => message,
+            Err(_) => return,
+        };
+        match message.icmp_type {
+            Icmpv6Type::EchoRequest => {
+                let _ = self.send_icmpv6_to(src_mac, packet.src, message.reply());
+            }
+            Icmpv6Type::EchoReply => {
+                if let Some((identifier, sequence)) = message.echo_ids() {
+                    super::ping::on_echo_reply(IpAddress::V6(packet.src), packet.hop_limit, identifier, sequence);
                 }
             }
-            IpProtocol::TCP => {
-                 if let Ok(segment) = TcpSegment::parse(&packet.payload) {
-                     // TODO: Dispatch TCP
-                 }
+            Icmpv6Type::NeighborSolicitation => {
+                let target = match message.target() {
+                    Some(target) if self.owns_ipv6(&target) => target,
+                    _ => return,
+                };
+                // Source non spécifiée : détection de doublon, réponse à tous
+                let (dst, solicited) = if packet.src == Ipv6Address::UNSPECIFIED {
+                    (Ipv6Address::ALL_NODES, false)
+                } else {
+                    let mac = message.link_address_option(icmpv6::OPTION_SOURCE_LINK_ADDRESS).unwrap_or(src_mac);
+                    ndp::insert(packet.src, mac);
+                    (packet.src, true)
+                };
+                let dst_mac = if dst.is_multicast() { dst.multicast_mac() } else { src_mac };
+                let advertisement = Icmpv6Message::neighbor_advertisement(target, self.mac_address, solicited);
+                let _ = self.send_icmpv6_to(dst_mac, dst, advertisement);
+            }
+            Icmpv6Type::NeighborAdvertisement => {
+                if let Some(target) = message.target() {
+                    let mac = message.link_address_option(icmpv6::OPTION_TARGET_LINK_ADDRESS).unwrap_or(src_mac);
+                    ndp::insert(target, mac);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Répond aux Echo Request, transmet les Echo Reply au client ping
+    fn handle_icmp_message(&self, packet: &Ipv4Packet, src_mac: MacAddress) {
+        if IcmpMessage::calculate_checksum(&packet.payload) != 0 {
+            return;
+        }
+        let message = match IcmpMessage::parse(&packet.payload) {
+            Ok(message) => message,
+            Err(_) => return,
+        };
+        match message.icmp_type {
+            IcmpType::EchoRequest => {
+                let mut reply = message.reply();
+                // Répondre à la MAC d'origine : pas besoin de résolution ARP
+                let _ = self.send_ipv4_to(src_mac, packet.src, IpProtocol::ICMP, reply.serialize());
+            }
+            IcmpType::EchoReply => {
+                super::ping::on_echo_reply(IpAddress::V4(packet.src), packet.ttl, message.identifier, message.sequence)
             }
             _ => {}
         }
     }
 
     /// Traite un datagram UDP
-    fn handle_udp_datagram(&self, dgram: &UdpDatagram, src_ip: Ipv4Address) {
+    fn handle_udp_datagram(&self, dgram: &UdpDatagram, src_ip: IpAddress) {
         let mut socket_table = SOCKET_TABLE.lock();
         
         // Chercher un socket lié à ce port
         // TODO: Optimiser la recherche (hashmap par port?)
         for (_, socket) in socket_table.sockets.iter_mut() {
-            if socket.socket_type == SocketType::Datagram && socket.domain == SocketDomain::Inet {
+            if socket.socket_type == SocketType::Datagram && socket.domain.accepts(&src_ip) {
                 if let Some(local_addr) = socket.local_addr {
                     if local_addr.port == dgram.dst_port {
                         // Socket trouvé !
@@ -93,6 +434,7 @@ impl NetworkInterface {
                         // Pour UDP standard, on accepte tout si bound au port
                         
                         socket.udp_recv_buffer.push_back(dgram.payload.clone());
+                        crate::fs::poll::wake();
                         return;
                     }
                 }
@@ -112,11 +454,579 @@ pub fn init(mac: MacAddress, ip: Ipv4Address) {
     *interface = Some(NetworkInterface::new(mac, ip));
 }
 
+/// Définit la passerelle par défaut de l'interface
+pub fn set_gateway(gateway: Ipv4Address) {
+    let _ = add_route(Route { destination: Ipv4Address::new(0, 0, 0, 0), prefix_len: 0, gateway: Some(gateway) });
+}
+
+/// Applique `change` à l'interface configurée
+fn with_interface<T>(change: impl FnOnce(&mut NetworkInterface) -> T) -> Result<T, SendError> {
+    without_interrupts(|| NETWORK_INTERFACE.lock().as_mut().map(change)).ok_or(SendError::NoInterface)
+}
+
+/// Change l'adresse de l'interface
+///
+/// Le cache ARP est vidé (voisins d'un autre réseau) et un ARP gratuit
+/// annonce la nouvelle adresse.
+pub fn set_address(ip: Ipv4Address, prefix_len: u8) -> Result<(), SendError> {
+    let announce = with_interface(|interface| {
+        interface.ip_address = ip;
+        interface.netmask = prefix_netmask(prefix_len);
+        ArpPacket::request(interface.mac_address, ip, ip)
+    })?;
+    super::arp::cache_clear();
+    without_interrupts(|| {
+        if let Some(interface) = NETWORK_INTERFACE.lock().as_ref() {
+            // Interface arrêtée : l'annonce sera faite par les échanges suivants
+            let _ = interface.send_frame(MacAddress::BROADCAST, EtherType::ARP, announce.serialize().to_vec());
+        }
+    });
+    Ok(())
+}
+
+/// Active ou arrête l'interface
+pub fn set_up(up: bool) -> Result<(), SendError> {
+    with_interface(|interface| interface.up = up)
+}
+
+/// Ajoute une route (la passerelle doit être sur le réseau local)
+pub fn add_route(route: Route) -> Result<(), SendError> {
+    with_interface(|interface| match route.gateway {
+        Some(gateway) if !same_network(gateway, interface.ip_address, interface.netmask) => {
+            Err(SendError::HostUnreachable)
+        }
+        _ => {
+            interface.add_route(route);
+            Ok(())
+        }
+    })?
+}
+
+/// Retire une route ; false si elle n'existait pas
+pub fn del_route(destination: Ipv4Address, prefix_len: u8) -> Result<bool, SendError> {
+    with_interface(|interface| interface.del_route(destination, prefix_len))
+}
+
+/// Ajoute une adresse IPv6 (la lien-local reste la première)
+pub fn add_ipv6_address(ip: Ipv6Address, prefix_len: u8) -> Result<(), SendError> {
+    with_interface(|interface| {
+        interface.ipv6_addresses.retain(|(own, _)| *own != ip);
+        if ip.is_link_local() {
+            interface.ipv6_addresses[0] = (ip, prefix_len);
+        } else {
+            interface.ipv6_addresses.push((ip, prefix_len));
+        }
+    })
+}
+
+/// Retire une adresse IPv6 globale ; false si elle n'existait pas
+pub fn del_ipv6_address(ip: Ipv6Address) -> Result<bool, SendError> {
+    with_interface(|interface| {
+        let before = interface.ipv6_addresses.len();
+        let link_local = interface.link_local();
+        interface.ipv6_addresses.retain(|(own, _)| *own != ip || *own == link_local);
+        interface.ipv6_addresses.len() != before
+    })
+}
+
+/// Définit (ou retire) le routeur IPv6 par défaut
+pub fn set_ipv6_gateway(gateway: Option<Ipv6Address>) -> Result<(), SendError> {
+    with_interface(|interface| interface.ipv6_gateway = gateway)
+}
+
+/// État de l'interface (ifconfig, ip)
+#[derive(Debug, Clone)]
+pub struct InterfaceInfo {
+    pub name: &'static str,
+    pub mac_address: MacAddress,
+    pub ip_address: Ipv4Address,
+    pub prefix_len: u8,
+    pub up: bool,
+    pub routes: Vec<Route>,
+    /// Adresses IPv6, lien-local en tête
+    pub ipv6_addresses: Vec<(Ipv6Address, u8)>,
+    pub ipv6_gateway: Option<Ipv6Address>,
+}
+
+/// Interfaces configurées
+pub fn interfaces() -> Vec<InterfaceInfo> {
+    without_interrupts(|| {
+        NETWORK_INTERFACE.lock().as_ref().map(|interface| InterfaceInfo {
+            name: INTERFACE_NAME,
+            mac_address: interface.mac_address,
+            ip_address: interface.ip_address,
+            prefix_len: netmask_prefix(interface.netmask),
+            up: interface.up,
+            routes: interface.routes.clone(),
+            ipv6_addresses: interface.ipv6_addresses.clone(),
+            ipv6_gateway: interface.ipv6_gateway,
+        })
+    })
+    .into_iter()
+    .collect()
+}
+
+/// Contenu de CONFIG_PATH
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NetworkConfig {
+    pub address: Option<(Ipv4Address, u8)>,
+    pub up: Option<bool>,
+    pub routes: Vec<Route>,
+    /// Adresses IPv6 globales (la lien-local découle de la MAC)
+    pub address6: Vec<(Ipv6Address, u8)>,
+    pub gateway6: Option<Ipv6Address>,
+}
+
+impl NetworkConfig {
+    /// Une directive par ligne : `address a.b.c.d/len`, `state up|down`,
+    /// `route <réseau/len|default> [via <passerelle>]`, `address6 x::y/len`,
+    /// `gateway6 x::y` ; `#` commente
+    pub fn parse(text: &str) -> Self {
+        let mut config = Self::default();
+        for line in text.lines().map(|l| l.split('#').next().unwrap_or("").trim()) {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.as_slice() {
+                ["address", cidr] => config.address = parse_cidr(cidr),
+                ["address6", cidr] => config.address6.extend(parse_cidr6(cidr)),
+                ["gateway6", gateway] => config.gateway6 = gateway.parse().ok(),
+                ["state", "up"] => config.up = Some(true),
+                ["state", "down"] => config.up = Some(false),
+                ["route", destination, rest @ ..] => {
+                    let gateway = match rest {
+                        ["via", gateway] => gateway.parse().ok(),
+                        _ => None,
+                    };
+                    let destination = match *destination {
+                        "default" => Some((Ipv4Address::new(0, 0, 0, 0), 0)),
+                        cidr => parse_cidr(cidr),
+                    };
+                    if let Some((destination, prefix_len)) = destination {
+                        config.routes.push(Route { destination, prefix_len, gateway });
+                    }
+                }
+                _ => {}
+            }
+        }
+        config
+    }
+
+    pub fn render(&self) -> String {
+        let mut text = format!("# Interface {}\n", INTERFACE_NAME);
+        if let Some((ip, prefix_len)) = self.address {
+            text.push_str(&format!("address {}/{}\n", ip, prefix_len));
+        }
+        if let Some(up) = self.up {
+            text.push_str(if up { "state up\n" } else { "state down\n" });
+        }
+        for route in &self.routes {
+            text.push_str(&format!("route {}\n", route));
+        }
+        for (ip, prefix_len) in &self.address6 {
+            text.push_str(&format!("address6 {}/{}\n", ip, prefix_len));
+        }
+        if let Some(gateway) = self.gateway6 {
+            text.push_str(&format!("gateway6 {}\n", gateway));
+        }
+        text
+    }
+}
+
+/// Enregistre la configuration courante dans CONFIG_PATH
+pub fn save_config() -> Result<(), crate::fs::VfsError> {
+    let config = match interfaces().into_iter().next() {
+        Some(info) => NetworkConfig {
+            address: Some((info.ip_address, info.prefix_len)),
+            up: Some(info.up),
+            routes: info.routes,
+            address6: info.ipv6_addresses.into_iter().skip(1).collect(),
+            gateway6: info.ipv6_gateway,
+        },
+        None => return Ok(()),
+    };
+    crate::fs::vfs_write_file(CONFIG_PATH, config.render().as_bytes())
+}
+
+/// Applique CONFIG_PATH s'il existe ; les routes enregistrées remplacent
+/// celles par défaut
+pub fn load_config() {
+    let config = match crate::fs::vfs_read_file(CONFIG_PATH) {
+        Ok(bytes) => NetworkConfig::parse(&String::from_utf8_lossy(&bytes)),
+        Err(_) => return,
+    };
+    if let Some((ip, prefix_len)) = config.address {
+        let _ = set_address(ip, prefix_len);
+    }
+    let _ = with_interface(|interface| {
+        if let Some(up) = config.up {
+            interface.up = up;
+        }
+        if !config.routes.is_empty() {
+            interface.routes = config.routes;
+        }
+        interface.ipv6_addresses.truncate(1);
+        interface.ipv6_addresses.extend(config.address6);
+        if config.gateway6.is_some() {
+            interface.ipv6_gateway = config.gateway6;
+        }
+    });
+}
+
+/// Erreurs d'émission d'un paquet IP
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SendError {
+    /// Aucune interface configurée
+    NoInterface,
+    /// Pas de réponse ARP pour le prochain saut
+    HostUnreachable,
+    Device(NetDeviceError),
+}
+
+impl From<NetDeviceError> for SendError {
+    fn from(error: NetDeviceError) -> Self {
+        SendError::Device(error)
+    }
+}
+
+/// Adresse MAC de `ip`, par le cache ARP ou une requête ARP
+///
+/// Les trames sont relevées par scrutation pendant l'attente : la
+/// réponse arrive même si la carte ne lève pas d'interruption.
+pub fn resolve(ip: Ipv4Address) -> Result<MacAddress, SendError> {
+    if ip.0 == [255; 4] {
+        return Ok(MacAddress::BROADCAST);
+    }
+    if let Some(mac) = super::arp::cache_lookup(&ip) {
+        return Ok(mac);
+    }
+
+    let request = without_interrupts(|| {
+        NETWORK_INTERFACE.lock().as_ref().map(|interface| {
+            let request = ArpPacket::request(interface.mac_address, interface.ip_address, ip);
+            interface.send_frame(MacAddress::BROADCAST, EtherType::ARP, request.serialize().to_vec())
+        })
+    });
+    match request {
+        None => return Err(SendError::NoInterface),
+        Some(result) => result?,
+    }
+
+    let start = Instant::now();
+    while start.elapsed_ns() < ARP_TIMEOUT_NS {
+        poll();
+        if let Some(mac) = super::arp::cache_lookup(&ip) {
+            return Ok(mac);
+        }
+        idle();
+    }
+    Err(SendError::HostUnreachable)
+}
+
+/// Adresse MAC de `ip` par Neighbor Discovery : cache, sinon Neighbor
+/// Solicitation au groupe solicited-node de la cible
+pub fn resolve6(ip: Ipv6Address) -> Result<MacAddress, SendError> {
+    if ip.is_multicast() {
+        return Ok(ip.multicast_mac());
+    }
+    if let Some(mac) = without_interrupts(|| ndp::lookup(&ip)) {
+        return Ok(mac);
+    }
+
+    let group = ip.solicited_node();
+    let solicitation = without_interrupts(|| {
+        NETWORK_INTERFACE.lock().as_ref().map(|interface| {
+            let solicitation = Icmpv6Message::neighbor_solicitation(ip, interface.mac_address);
+            interface.send_icmpv6_to(group.multicast_mac(), group, solicitation)
+        })
+    });
+    match solicitation {
+        None => return Err(SendError::NoInterface),
+        Some(result) => result?,
+    }
+
+    let start = Instant::now();
+    while start.elapsed_ns() < ARP_TIMEOUT_NS {
+        poll();
+        if let Some(mac) = without_interrupts(|| ndp::lookup(&ip)) {
+            return Ok(mac);
+        }
+        idle();
+    }
+    Err(SendError::HostUnreachable)
+}
+
+/// MAC du prochain saut vers `dst` (passerelle ou routeur si besoin)
+fn resolve_next_hop(dst: IpAddress) -> Result<MacAddress, SendError> {
+    let next_hop = without_interrupts(|| {
+        NETWORK_INTERFACE.lock().as_ref().map(|interface| match dst {
+            IpAddress::V4(dst) => IpAddress::V4(interface.next_hop(dst)),
+            IpAddress::V6(dst) => IpAddress::V6(interface.next_hop6(dst)),
+        })
+    })
+    .ok_or(SendError::NoInterface)?;
+    match next_hop {
+        IpAddress::V4(ip) => resolve(ip),
+        IpAddress::V6(ip) => resolve6(ip),
+    }
+}
+
+/// Émet par l'interface configurée, verrou pris sans interruption
+fn with_sender(send: impl FnOnce(&NetworkInterface) -> Result<(), NetDeviceError>) -> Result<(), SendError> {
+    without_interrupts(|| match NETWORK_INTERFACE.lock().as_ref() {
+        Some(interface) => Ok(send(interface)?),
+        None => Err(SendError::NoInterface),
+    })
+}
+
+/// Émet un paquet IPv4 vers `dst`, via la passerelle si besoin
+pub fn send_ipv4(dst: Ipv4Address, protocol: IpProtocol, payload: Vec<u8>) -> Result<(), SendError> {
+    let mac = resolve_next_hop(IpAddress::V4(dst))?;
+    with_sender(|interface| interface.send_ipv4_to(mac, dst, protocol, payload))
+}
+
+/// Émet un paquet IPv6 vers `dst`, via le routeur si besoin
+pub fn send_ipv6(dst: Ipv6Address, protocol: IpProtocol, payload: Vec<u8>) -> Result<(), SendError> {
+    let mac = resolve_next_hop(IpAddress::V6(dst))?;
+    with_sender(|interface| {
+        interface.send_ipv6_to(mac, Ipv6Packet::new(Ipv6Address::UNSPECIFIED, dst, protocol, payload))
+    })
+}
+
+/// Émet un segment TCP vers `dst` (checksum sur notre adresse)
+pub fn send_tcp(dst: IpAddress, segment: TcpSegment) -> Result<(), SendError> {
+    let mac = resolve_next_hop(dst)?;
+    with_sender(|interface| interface.send_tcp_to(mac, dst, segment))
+}
+
+/// Émet un datagramme UDP vers `dst`, checksum calculé (obligatoire en IPv6)
+pub fn send_udp(dst: IpAddress, mut datagram: UdpDatagram) -> Result<(), SendError> {
+    let mac = resolve_next_hop(dst)?;
+    with_sender(|interface| {
+        // Un checksum nul signifie « absent » : 0xFFFF le remplace
+        datagram.checksum = match datagram.calculate_checksum(interface.source_for(dst), dst) {
+            0 => 0xFFFF,
+            checksum => checksum,
+        };
+        interface.send_ip_to(mac, dst, IpProtocol::UDP, datagram.serialize())
+    })
+}
+
+/// Émet un message ICMPv6 vers `dst`
+pub fn send_icmpv6(dst: Ipv6Address, message: Icmpv6Message) -> Result<(), SendError> {
+    let mac = resolve_next_hop(IpAddress::V6(dst))?;
+    with_sender(|interface| interface.send_icmpv6_to(mac, dst, message))
+}
+
+/// Émet un datagramme UDP vers `dst` sans jamais attendre
+///
+/// Pour les chemins qui ne peuvent ni bloquer ni relever les trames
+/// (journal du noyau, interruptions) : verrous tentés, MAC du prochain
+/// saut prise dans le cache ARP. Si elle n'y est pas, une requête ARP
+/// part et `HostUnreachable` invite à réessayer ; `Device(NotReady)`
+/// signale un verrou déjà pris.
+pub fn try_send_udp(dst: Ipv4Address, mut datagram: UdpDatagram) -> Result<(), SendError> {
+    let frame = without_interrupts(|| {
+        let guard = NETWORK_INTERFACE.try_lock().ok_or(SendError::Device(NetDeviceError::NotReady))?;
+        let interface = guard.as_ref().ok_or(SendError::NoInterface)?;
+        if !interface.up {
+            return Err(SendError::Device(NetDeviceError::NotReady));
+        }
+        let next_hop = interface.next_hop(dst);
+        let cached = super::arp::cache_lookup(&next_hop);
+        let mac = match (dst.0 == [255; 4], cached) {
+            (true, _) => MacAddress::BROADCAST,
+            (false, Some(mac)) => mac,
+            (false, None) => {
+                let request = ArpPacket::request(interface.mac_address, interface.ip_address, next_hop);
+                let frame = EthernetFrame::new(MacAddress::BROADCAST, interface.mac_address, EtherType::ARP, request.serialize().to_vec());
+                let _ = try_transmit(&frame.serialize());
+                return Err(SendError::HostUnreachable);
+            }
+        };
+        datagram.checksum = match datagram.calculate_checksum(interface.ip_address, dst) {
+            0 => 0xFFFF,
+            checksum => checksum,
+        };
+        let mut packet = Ipv4Packet::new(interface.ip_address, dst, IpProtocol::UDP, datagram.serialize());
+        Ok(EthernetFrame::new(mac, interface.mac_address, EtherType::IPv4, packet.serialize()).serialize())
+    })?;
+    Ok(try_transmit(&frame)?)
+}
+
+/// Attend le prochain tick si les interruptions sont actives
+pub fn idle() {
+    if x86_64::instructions::interrupts::are_enabled() {
+        x86_64::instructions::hlt();
+    } else {
+        core::hint::spin_loop();
+    }
+}
+
 /// Point d'entrée pour le driver réseau lors de la réception d'un paquet
 pub fn on_receive(data: &[u8]) {
+    super::capture::tap(data, false);
     if let Ok(frame) = EthernetFrame::parse(data) {
-        if let Some(interface) = NETWORK_INTERFACE.lock().as_ref() {
-            interface.handle_ethernet_frame(&frame);
+        // Aussi appelé depuis l'interruption de la carte : verrou pris sans interruption
+        without_interrupts(|| {
+            if let Some(interface) = NETWORK_INTERFACE.lock().as_ref() {
+                interface.handle_ethernet_frame(&frame);
+            }
+        });
+    }
+}
+
+/// Erreurs d'une carte réseau
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetDeviceError {
+    /// Plus de place dans l'anneau d'émission
+    QueueFull,
+    FrameTooLarge,
+    NotReady,
+}
+
+/// Carte réseau vue par la pile (virtio-net, e1000...)
+pub trait NetDevice: Send {
+    fn mac_address(&self) -> MacAddress;
+    /// Émet une trame Ethernet complète
+    fn transmit(&mut self, frame: &[u8]) -> Result<(), NetDeviceError>;
+    /// Prochaine trame reçue, s'il y en a une
+    fn receive(&mut self) -> Option<Vec<u8>>;
+}
+
+lazy_static! {
+    /// Cartes réseau par nom (eth0, eth1...)
+    pub static ref NET_DEVICES: Mutex<BTreeMap<String, Box<dyn NetDevice>>> = Mutex::new(BTreeMap::new());
+}
+
+/// Enregistre une carte sous le prochain nom `ethN` ; retourne ce nom
+pub fn register_device(device: Box<dyn NetDevice>) -> String {
+    let mut devices = NET_DEVICES.lock();
+    let name = alloc::format!("eth{}", devices.len());
+    devices.insert(name.clone(), device);
+    name
+}
+
+/// Émet une trame sur la première carte
+pub fn transmit(frame: &[u8]) -> Result<(), NetDeviceError> {
+    super::capture::tap(frame, true);
+    let mut devices = NET_DEVICES.lock();
+    let device = devices.values_mut().next().ok_or(NetDeviceError::NotReady)?;
+    device.transmit(frame)
+}
+
+/// Variante de `transmit` qui échoue (NotReady) si les cartes sont
+/// déjà verrouillées
+pub fn try_transmit(frame: &[u8]) -> Result<(), NetDeviceError> {
+    super::capture::tap(frame, true);
+    let mut devices = NET_DEVICES.try_lock().ok_or(NetDeviceError::NotReady)?;
+    let device = devices.values_mut().next().ok_or(NetDeviceError::NotReady)?;
+    device.transmit(frame)
+}
+
+/// Remonte à la pile les trames reçues par toutes les cartes
+///
+/// Appelé depuis l'interruption des cartes ou par scrutation.
+pub fn poll() -> usize {
+    let frames = drain(&mut NET_DEVICES.lock());
+    let delivered = deliver(frames);
+    // Lignes du journal retenues faute de verrou libre
+    super::netconsole::flush();
+    delivered
+}
+
+/// Traitement des trames reçues déjà confié à un kworker
+static RX_SCHEDULED: AtomicBool = AtomicBool::new(false);
+
+/// Moitié basse de la réception : remonte les trames hors interruption
+fn rx_work(_: usize) {
+    RX_SCHEDULED.store(false, Ordering::Release);
+    poll();
+}
+
+/// Appelé par l'interruption de réception d'une carte : le traitement
+/// des trames est différé dans une file de travail (un seul en attente)
+pub fn schedule_rx() {
+    if !RX_SCHEDULED.swap(true, Ordering::AcqRel) && !crate::workqueue::queue_work(rx_work, 0) {
+        // File pleine : traitement immédiat, comme avant
+        RX_SCHEDULED.store(false, Ordering::Release);
+        try_poll();
+    }
+}
+
+/// Variante de `poll` pour les interruptions : ne bloque pas si les
+/// cartes sont déjà verrouillées (les trames seront lues au prochain appel)
+pub fn try_poll() -> usize {
+    match NET_DEVICES.try_lock() {
+        Some(mut devices) => {
+            let frames = drain(&mut devices);
+            drop(devices);
+            let delivered = deliver(frames);
+            super::netconsole::flush();
+            delivered
         }
+        None => 0,
+    }
+}
+
+fn drain(devices: &mut BTreeMap<String, Box<dyn NetDevice>>) -> Vec<Vec<u8>> {
+    let mut frames = Vec::new();
+    for device in devices.values_mut() {
+        while let Some(frame) = device.receive() {
+            frames.push(frame);
+        }
+    }
+    frames
+}
+
+/// Verrou des cartes relâché : la pile peut émettre une réponse
+fn deliver(frames: Vec<Vec<u8>>) -> usize {
+    for frame in &frames {
+        on_receive(frame);
+    }
+    frames.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_routes_next_hop() {
+        let mut interface = NetworkInterface::new(MacAddress::BROADCAST, Ipv4Address::new(10, 0, 2, 15));
+        interface.add_route(Route { destination: Ipv4Address::new(0, 0, 0, 0), prefix_len: 0, gateway: Some(Ipv4Address::new(10, 0, 2, 2)) });
+        interface.add_route(Route { destination: Ipv4Address::new(192, 168, 1, 0), prefix_len: 24, gateway: Some(Ipv4Address::new(10, 0, 2, 1)) });
+        assert_eq!(interface.next_hop(Ipv4Address::new(10, 0, 2, 3)), Ipv4Address::new(10, 0, 2, 3));
+        assert_eq!(interface.next_hop(Ipv4Address::new(192, 168, 1, 7)), Ipv4Address::new(10, 0, 2, 1));
+        assert_eq!(interface.next_hop(Ipv4Address::new(8, 8, 8, 8)), Ipv4Address::new(10, 0, 2, 2));
+        assert!(interface.del_route(Ipv4Address::new(192, 168, 1, 99), 24));
+        assert_eq!(interface.gateway(), Some(Ipv4Address::new(10, 0, 2, 2)));
+        assert_eq!((prefix_netmask(20), netmask_prefix(prefix_netmask(20))), (Ipv4Address::new(255, 255, 240, 0), 20));
+    }
+
+    #[test_case]
+    fn test_ipv6_source_and_next_hop() {
+        let mut interface = NetworkInterface::new(MacAddress([0x52, 0x54, 0, 0x12, 0x34, 0x56]), Ipv4Address::new(10, 0, 2, 15));
+        interface.ipv6_addresses.push(parse_cidr6("2001:db8::15/64").unwrap());
+        interface.ipv6_gateway = "fe80::2".parse().ok();
+        let on_link: Ipv6Address = "2001:db8::1".parse().unwrap();
+        let remote: Ipv6Address = "2001:4860::8888".parse().unwrap();
+        assert_eq!(interface.next_hop6(on_link), on_link);
+        assert_eq!(Some(interface.next_hop6(remote)), interface.ipv6_gateway);
+        assert_eq!(interface.ipv6_source(&remote), "2001:db8::15".parse().unwrap());
+        assert_eq!(interface.ipv6_source(&Ipv6Address::ALL_NODES), interface.link_local());
+        assert!(interface.accepts_ipv6(&interface.link_local().solicited_node()));
+    }
+
+    #[test_case]
+    fn test_network_config_roundtrip() {
+        let config = NetworkConfig {
+            address: parse_cidr("192.168.0.10/16"),
+            up: Some(false),
+            routes: alloc::vec![Route { destination: Ipv4Address::new(0, 0, 0, 0), prefix_len: 0, gateway: Some(Ipv4Address::new(192, 168, 0, 1)) }],
+            address6: parse_cidr6("2001:db8::10/64").into_iter().collect(),
+            gateway6: "fe80::1".parse().ok(),
+        };
+        assert!(config.render().contains("route default via 192.168.0.1\n"));
+        assert_eq!(NetworkConfig::parse(&config.render()), config);
+        assert_eq!(parse_cidr("10.0.0.1/33"), None);
     }
 }
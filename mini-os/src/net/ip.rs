@@ -0,0 +1,127 @@
+/// Adresses IP indépendantes de la famille
+///
+/// `IpAddress` porte une adresse IPv4 ou IPv6 ; les couches transport
+/// (UDP, TCP) et les sockets l'utilisent pour rester communes aux deux
+/// familles. Le pseudo-en-tête des checksums dépend de la famille.
+
+use super::arp::Ipv4Address;
+use super::ipv6::Ipv6Address;
+
+/// Adresse IPv4 ou IPv6
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum IpAddress {
+    V4(Ipv4Address),
+    V6(Ipv6Address),
+}
+
+impl IpAddress {
+    pub fn is_ipv6(&self) -> bool {
+        matches!(self, IpAddress::V6(_))
+    }
+
+    /// Adresse non spécifiée de la même famille (0.0.0.0 ou ::)
+    pub fn unspecified_like(&self) -> IpAddress {
+        match self {
+            IpAddress::V4(_) => IpAddress::V4(Ipv4Address::new(0, 0, 0, 0)),
+            IpAddress::V6(_) => IpAddress::V6(Ipv6Address::UNSPECIFIED),
+        }
+    }
+
+    pub fn is_unspecified(&self) -> bool {
+        match self {
+            IpAddress::V4(ip) => ip.0 == [0; 4],
+            IpAddress::V6(ip) => *ip == Ipv6Address::UNSPECIFIED,
+        }
+    }
+
+    /// Octets de l'adresse (4 ou 16)
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            IpAddress::V4(ip) => &ip.0,
+            IpAddress::V6(ip) => &ip.0,
+        }
+    }
+}
+
+impl From<Ipv4Address> for IpAddress {
+    fn from(ip: Ipv4Address) -> Self {
+        IpAddress::V4(ip)
+    }
+}
+
+impl From<Ipv6Address> for IpAddress {
+    fn from(ip: Ipv6Address) -> Self {
+        IpAddress::V6(ip)
+    }
+}
+
+impl core::str::FromStr for IpAddress {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        if s.contains(':') {
+            s.parse().map(IpAddress::V6)
+        } else {
+            s.parse().map(IpAddress::V4)
+        }
+    }
+}
+
+impl core::fmt::Display for IpAddress {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            IpAddress::V4(ip) => write!(f, "{}", ip),
+            IpAddress::V6(ip) => write!(f, "{}", ip),
+        }
+    }
+}
+
+/// Somme (non repliée) du pseudo-en-tête d'un checksum UDP/TCP/ICMPv6
+///
+/// IPv4 : adresses, protocole, longueur sur 16 bits ; IPv6 (RFC 8200) :
+/// adresses, longueur sur 32 bits, en-tête suivant.
+pub fn pseudo_header_sum(src: IpAddress, dst: IpAddress, protocol: u8, length: u32) -> u32 {
+    let mut sum: u32 = 0;
+    for address in [src, dst] {
+        for word in address.as_bytes().chunks(2) {
+            sum += u16::from_be_bytes([word[0], word[1]]) as u32;
+        }
+    }
+    sum += protocol as u32;
+    sum += length >> 16;
+    sum += length & 0xFFFF;
+    sum
+}
+
+/// Somme des mots de 16 bits de `data` (dernier octet complété par 0)
+pub fn sum_words(data: &[u8]) -> u32 {
+    data.chunks(2)
+        .map(|chunk| match chunk {
+            [high, low] => u16::from_be_bytes([*high, *low]) as u32,
+            [high] => (*high as u32) << 8,
+            _ => 0,
+        })
+        .sum()
+}
+
+/// Replie une somme sur 16 bits et la complémente
+pub fn fold_checksum(mut sum: u32) -> u16 {
+    while (sum >> 16) != 0 {
+        sum = (sum & 0xFFFF) + (sum >> 16);
+    }
+    !sum as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_ip_address_parse() {
+        assert_eq!("10.0.2.15".parse::<IpAddress>(), Ok(IpAddress::V4(Ipv4Address::new(10, 0, 2, 15))));
+        let v6: IpAddress = "fe80::1".parse().unwrap();
+        assert!(v6.is_ipv6());
+        assert_eq!(alloc::format!("{}", v6), "fe80::1");
+        assert!(v6.unspecified_like().is_unspecified());
+    }
+}
@@ -12,6 +12,7 @@ pub enum IpProtocol {
     ICMP = 1,
     TCP = 6,
     UDP = 17,
+    ICMPv6 = 58,
     Unknown(u8),
 }
 
@@ -21,11 +22,24 @@ impl From<u8> for IpProtocol {
             1 => IpProtocol::ICMP,
             6 => IpProtocol::TCP,
             17 => IpProtocol::UDP,
+            58 => IpProtocol::ICMPv6,
             other => IpProtocol::Unknown(other),
         }
     }
 }
 
+impl From<IpProtocol> for u8 {
+    fn from(protocol: IpProtocol) -> u8 {
+        match protocol {
+            IpProtocol::ICMP => 1,
+            IpProtocol::TCP => 6,
+            IpProtocol::UDP => 17,
+            IpProtocol::ICMPv6 => 58,
+            IpProtocol::Unknown(v) => v,
+        }
+    }
+}
+
 /// Packet IPv4
 #[derive(Debug, Clone)]
 pub struct Ipv4Packet {
@@ -156,12 +170,7 @@ impl Ipv4Packet {
         bytes.extend_from_slice(&self.id.to_be_bytes());
         bytes.extend_from_slice(&self.flags_fragment.to_be_bytes());
         bytes.push(self.ttl);
-        bytes.push(match self.protocol {
-            IpProtocol::ICMP => 1,
-            IpProtocol::TCP => 6,
-            IpProtocol::UDP => 17,
-            IpProtocol::Unknown(v) => v,
-        });
+        bytes.push(self.protocol.into());
         
         // Checksum (temporairement 0)
         bytes.extend_from_slice(&[0, 0]);
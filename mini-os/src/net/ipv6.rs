@@ -0,0 +1,249 @@
+/// Module IPv6 - Network Layer
+///
+/// En-tête fixe de 40 octets (RFC 8200), sans en-têtes d'extension ni
+/// fragmentation. Les adresses multicast sont projetées sur les MAC
+/// 33:33:xx:xx:xx:xx ; la résolution des voisins passe par ND (module ndp).
+
+use alloc::vec::Vec;
+use super::ethernet::MacAddress;
+use super::ipv4::IpProtocol;
+
+/// Adresse IPv6
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Ipv6Address(pub [u8; 16]);
+
+impl Ipv6Address {
+    /// ::
+    pub const UNSPECIFIED: Ipv6Address = Ipv6Address([0; 16]);
+    /// ::1
+    pub const LOOPBACK: Ipv6Address = Ipv6Address([0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+    /// ff02::1, tous les nœuds du lien
+    pub const ALL_NODES: Ipv6Address = Ipv6Address([0xff, 0x02, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+
+    pub fn from_segments(segments: [u16; 8]) -> Self {
+        let mut bytes = [0u8; 16];
+        for (i, segment) in segments.iter().enumerate() {
+            bytes[2 * i..2 * i + 2].copy_from_slice(&segment.to_be_bytes());
+        }
+        Ipv6Address(bytes)
+    }
+
+    pub fn segments(&self) -> [u16; 8] {
+        core::array::from_fn(|i| u16::from_be_bytes([self.0[2 * i], self.0[2 * i + 1]]))
+    }
+
+    /// Adresse lien-local fe80::/64 dérivée de la MAC (EUI-64 modifié)
+    pub fn link_local_from_mac(mac: MacAddress) -> Self {
+        let m = mac.0;
+        Ipv6Address([0xfe, 0x80, 0, 0, 0, 0, 0, 0, m[0] ^ 0x02, m[1], m[2], 0xff, 0xfe, m[3], m[4], m[5]])
+    }
+
+    pub fn is_multicast(&self) -> bool {
+        self.0[0] == 0xff
+    }
+
+    /// fe80::/10
+    pub fn is_link_local(&self) -> bool {
+        self.0[0] == 0xfe && (self.0[1] & 0xc0) == 0x80
+    }
+
+    /// Groupe multicast « solicited-node » ff02::1:ffXX:XXXX de l'adresse
+    pub fn solicited_node(&self) -> Self {
+        Ipv6Address([0xff, 0x02, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01, 0xff, self.0[13], self.0[14], self.0[15]])
+    }
+
+    /// MAC Ethernet d'une adresse multicast (RFC 2464)
+    pub fn multicast_mac(&self) -> MacAddress {
+        MacAddress([0x33, 0x33, self.0[12], self.0[13], self.0[14], self.0[15]])
+    }
+
+    /// `self` et `other` partagent les `prefix_len` premiers bits
+    pub fn same_prefix(&self, other: &Ipv6Address, prefix_len: u8) -> bool {
+        let mask = match prefix_len {
+            0 => 0,
+            len => u128::MAX << (128 - len.min(128) as u32),
+        };
+        u128::from_be_bytes(self.0) & mask == u128::from_be_bytes(other.0) & mask
+    }
+}
+
+impl core::str::FromStr for Ipv6Address {
+    type Err = ();
+
+    /// Notation hexadécimale, « :: » pour une suite de zéros
+    fn from_str(s: &str) -> Result<Self, ()> {
+        fn parse_groups(part: &str) -> Result<Vec<u16>, ()> {
+            if part.is_empty() {
+                return Ok(Vec::new());
+            }
+            part.split(':')
+                .map(|group| match group.len() {
+                    1..=4 => u16::from_str_radix(group, 16).map_err(|_| ()),
+                    _ => Err(()),
+                })
+                .collect()
+        }
+
+        let mut segments = [0u16; 8];
+        match s.split_once("::") {
+            Some((head, tail)) => {
+                let (head, tail) = (parse_groups(head)?, parse_groups(tail)?);
+                if head.len() + tail.len() > 7 {
+                    return Err(());
+                }
+                segments[..head.len()].copy_from_slice(&head);
+                segments[8 - tail.len()..].copy_from_slice(&tail);
+            }
+            None => {
+                let groups = parse_groups(s)?;
+                if groups.len() != 8 {
+                    return Err(());
+                }
+                segments.copy_from_slice(&groups);
+            }
+        }
+        Ok(Ipv6Address::from_segments(segments))
+    }
+}
+
+impl core::fmt::Display for Ipv6Address {
+    /// Forme canonique (RFC 5952) : plus longue suite de zéros en « :: »
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let segments = self.segments();
+        let (mut best, mut current) = ((0, 0), (0, 0));
+        for (i, &segment) in segments.iter().enumerate() {
+            if segment == 0 {
+                if current.1 == 0 {
+                    current.0 = i;
+                }
+                current.1 += 1;
+                if current.1 > best.1 {
+                    best = current;
+                }
+            } else {
+                current.1 = 0;
+            }
+        }
+
+        let write_groups = |f: &mut core::fmt::Formatter, groups: &[u16]| -> core::fmt::Result {
+            for (i, group) in groups.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ":")?;
+                }
+                write!(f, "{:x}", group)?;
+            }
+            Ok(())
+        };
+        if best.1 < 2 {
+            return write_groups(f, &segments);
+        }
+        write_groups(f, &segments[..best.0])?;
+        write!(f, "::")?;
+        write_groups(f, &segments[best.0 + best.1..])
+    }
+}
+
+/// Packet IPv6
+#[derive(Debug, Clone)]
+pub struct Ipv6Packet {
+    pub traffic_class: u8,
+    /// Flow label (20 bits)
+    pub flow_label: u32,
+    /// En-tête suivant (protocole de la charge utile)
+    pub next_header: IpProtocol,
+    pub hop_limit: u8,
+    pub src: Ipv6Address,
+    pub dst: Ipv6Address,
+    pub payload: Vec<u8>,
+}
+
+impl Ipv6Packet {
+    pub const HEADER_SIZE: usize = 40;
+    /// Hop limit par défaut ; 255 est imposé pour ND
+    pub const DEFAULT_HOP_LIMIT: u8 = 64;
+
+    pub fn new(src: Ipv6Address, dst: Ipv6Address, next_header: IpProtocol, payload: Vec<u8>) -> Self {
+        Self {
+            traffic_class: 0,
+            flow_label: 0,
+            next_header,
+            hop_limit: Self::DEFAULT_HOP_LIMIT,
+            src,
+            dst,
+            payload,
+        }
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Self, Ipv6Error> {
+        if data.len() < Self::HEADER_SIZE {
+            return Err(Ipv6Error::TooShort);
+        }
+        if data[0] >> 4 != 6 {
+            return Err(Ipv6Error::InvalidVersion);
+        }
+        let payload_len = u16::from_be_bytes([data[4], data[5]]) as usize;
+        let payload = data.get(Self::HEADER_SIZE..Self::HEADER_SIZE + payload_len).ok_or(Ipv6Error::TooShort)?;
+
+        let mut src = [0u8; 16];
+        let mut dst = [0u8; 16];
+        src.copy_from_slice(&data[8..24]);
+        dst.copy_from_slice(&data[24..40]);
+        Ok(Self {
+            traffic_class: (data[0] << 4) | (data[1] >> 4),
+            flow_label: u32::from_be_bytes([0, data[1] & 0x0F, data[2], data[3]]),
+            next_header: IpProtocol::from(data[6]),
+            hop_limit: data[7],
+            src: Ipv6Address(src),
+            dst: Ipv6Address(dst),
+            payload: payload.to_vec(),
+        })
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(Self::HEADER_SIZE + self.payload.len());
+        let word = (6u32 << 28) | ((self.traffic_class as u32) << 20) | (self.flow_label & 0xF_FFFF);
+        bytes.extend_from_slice(&word.to_be_bytes());
+        bytes.extend_from_slice(&(self.payload.len() as u16).to_be_bytes());
+        bytes.push(self.next_header.into());
+        bytes.push(self.hop_limit);
+        bytes.extend_from_slice(&self.src.0);
+        bytes.extend_from_slice(&self.dst.0);
+        bytes.extend_from_slice(&self.payload);
+        bytes
+    }
+}
+
+/// Erreurs IPv6
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ipv6Error {
+    TooShort,
+    InvalidVersion,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_ipv6_address_text() {
+        let ip: Ipv6Address = "2001:db8::ff00:42:8329".parse().unwrap();
+        assert_eq!(ip.segments(), [0x2001, 0xdb8, 0, 0, 0, 0xff00, 0x42, 0x8329]);
+        assert_eq!(alloc::format!("{}", ip), "2001:db8::ff00:42:8329");
+        assert_eq!(alloc::format!("{}", Ipv6Address::UNSPECIFIED), "::");
+        assert!("1:2:3:4:5:6:7:8:9".parse::<Ipv6Address>().is_err());
+
+        let mac = MacAddress([0x52, 0x54, 0x00, 0x12, 0x34, 0x56]);
+        let link_local = Ipv6Address::link_local_from_mac(mac);
+        assert_eq!(alloc::format!("{}", link_local), "fe80::5054:ff:fe12:3456");
+        assert_eq!(alloc::format!("{}", link_local.solicited_node()), "ff02::1:ff12:3456");
+        assert_eq!(link_local.solicited_node().multicast_mac(), MacAddress([0x33, 0x33, 0xff, 0x12, 0x34, 0x56]));
+    }
+
+    #[test_case]
+    fn test_ipv6_packet_roundtrip() {
+        let packet = Ipv6Packet::new(Ipv6Address::LOOPBACK, Ipv6Address::ALL_NODES, IpProtocol::UDP, alloc::vec![1, 2, 3]);
+        let parsed = Ipv6Packet::parse(&packet.serialize()).unwrap();
+        assert_eq!((parsed.src, parsed.dst, parsed.next_header), (packet.src, packet.dst, IpProtocol::UDP));
+        assert_eq!((parsed.hop_limit, parsed.payload), (64, alloc::vec![1, 2, 3]));
+    }
+}
@@ -5,7 +5,12 @@
 pub mod ethernet;
 pub mod arp;
 pub mod ipv4;
+pub mod ipv6;
+pub mod ip;
 pub mod icmp;
+pub mod icmpv6;
+pub mod ndp;
+pub mod ping;
 pub mod udp;
 pub mod tcp;
 pub mod socket;
@@ -13,11 +18,16 @@ pub mod interface;
 pub mod dns;
 pub mod dhcp;
 pub mod http;
+pub mod httpd;
+pub mod capture;
+pub mod netconsole;
 
 pub use ethernet::{EthernetFrame, MacAddress, EtherType};
 pub use arp::{ArpPacket, ArpCache, Ipv4Address, ARP_CACHE};
 pub use ipv4::{Ipv4Packet, IpProtocol};
+pub use ipv6::{Ipv6Address, Ipv6Packet};
+pub use ip::IpAddress;
 pub use icmp::{IcmpMessage, IcmpType};
 pub use udp::{UdpDatagram, Port};
 pub use tcp::{TcpSegment, TcpConnection, TcpState, TcpFlags};
-pub use socket::{Socket, SocketTable, SocketAddr, SocketType, SocketDomain, SOCKET_TABLE};
+pub use socket::{Socket, SocketTable, SocketAddr, SocketInfo, SocketType, SocketDomain, SOCKET_TABLE};
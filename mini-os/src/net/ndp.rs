@@ -0,0 +1,37 @@
+/// Neighbor Discovery (RFC 4861) : équivalent IPv6 du cache ARP
+///
+/// Les associations adresse IPv6 -> MAC sont apprises des Neighbor
+/// Solicitation (option adresse source) et des Neighbor Advertisement
+/// reçus ; la sollicitation elle-même est émise par `interface::resolve6`.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use spin::Mutex;
+
+use super::ethernet::MacAddress;
+use super::ipv6::Ipv6Address;
+
+/// Voisins connus
+static NEIGHBORS: Mutex<BTreeMap<Ipv6Address, MacAddress>> = Mutex::new(BTreeMap::new());
+
+/// Apprend (ou met à jour) l'adresse lien d'un voisin
+pub fn insert(ip: Ipv6Address, mac: MacAddress) {
+    if ip.is_multicast() || ip == Ipv6Address::UNSPECIFIED {
+        return;
+    }
+    NEIGHBORS.lock().insert(ip, mac);
+}
+
+pub fn lookup(ip: &Ipv6Address) -> Option<MacAddress> {
+    NEIGHBORS.lock().get(ip).copied()
+}
+
+/// Oublie tous les voisins (changement d'adresse de l'interface)
+pub fn clear() {
+    NEIGHBORS.lock().clear();
+}
+
+/// Voisins connus, pour `ip neigh`
+pub fn entries() -> Vec<(Ipv6Address, MacAddress)> {
+    NEIGHBORS.lock().iter().map(|(ip, mac)| (*ip, *mac)).collect()
+}
@@ -0,0 +1,191 @@
+/// Console réseau (netconsole)
+///
+/// Transport du journal du noyau : chaque ligne part dans un datagramme
+/// UDP vers l'hôte configuré (`nc -u -l 6666` côté hôte). L'envoi ne
+/// bloque jamais (`interface::try_send_udp`) : les lignes qui ne peuvent
+/// pas partir (carte occupée, MAC de la cible pas encore résolue)
+/// attendent dans une file, vidée au prochain message ou relevé des
+/// cartes. À l'activation, l'anneau du journal est rejoué pour que les
+/// messages du démarrage arrivent aussi.
+///
+/// Configuration : CONFIG_PATH, relu dès que la carte est configurée,
+/// ou option de démarrage `netconsole=`, avec la syntaxe de Linux
+/// `[port-src]@[ip-src]/[dev],[port-cible]@<ip-cible>/[mac-cible]`
+/// ou la forme courte `ip[:port]`.
+
+use alloc::collections::VecDeque;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, Ordering};
+use spin::Mutex;
+use x86_64::instructions::interrupts::without_interrupts;
+
+use super::arp::Ipv4Address;
+use super::interface;
+use super::udp::{Port, UdpDatagram};
+
+/// Configuration persistante (une ligne au format `netconsole=`)
+pub const CONFIG_PATH: &str = "/etc/netconsole.conf";
+pub const DEFAULT_LOCAL_PORT: Port = 6665;
+pub const DEFAULT_REMOTE_PORT: Port = 6666;
+
+/// Lignes retenues en attendant de pouvoir émettre
+const PENDING_LINES: usize = 512;
+
+/// Destination des lignes du journal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NetconsoleTarget {
+    pub local_port: Port,
+    pub remote_ip: Ipv4Address,
+    pub remote_port: Port,
+}
+
+impl NetconsoleTarget {
+    /// Analyse une valeur de `netconsole=`
+    pub fn parse(spec: &str) -> Result<Self, &'static str> {
+        let spec = spec.trim();
+        let (source, target) = match spec.split_once(',') {
+            Some((source, target)) => (Some(source), target),
+            None => (None, spec),
+        };
+
+        let local_port = match source.map(|source| source.split('@').next().unwrap_or("")) {
+            Some(port) if !port.is_empty() => port.parse().map_err(|_| "port source invalide")?,
+            _ => DEFAULT_LOCAL_PORT,
+        };
+
+        let (remote_port, remote_ip) = match (source, target.split_once('@')) {
+            // Syntaxe Linux : port@ip/mac (la MAC est résolue par ARP)
+            (Some(_), Some((port, rest))) => {
+                let port = if port.is_empty() { DEFAULT_REMOTE_PORT } else { port.parse().map_err(|_| "port cible invalide")? };
+                (port, rest.split('/').next().unwrap_or(""))
+            }
+            (Some(_), None) => return Err("cible attendue sous la forme port@ip"),
+            // Forme courte : ip[:port]
+            (None, _) => match target.split_once(':') {
+                Some((ip, port)) => (port.parse().map_err(|_| "port cible invalide")?, ip),
+                None => (DEFAULT_REMOTE_PORT, target),
+            },
+        };
+        let remote_ip = remote_ip.parse().map_err(|_| "adresse cible invalide")?;
+        Ok(Self { local_port, remote_ip, remote_port })
+    }
+}
+
+impl core::fmt::Display for NetconsoleTarget {
+    /// Forme Linux, relue par `parse`
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{}@/{},{}@{}/", self.local_port, interface::INTERFACE_NAME, self.remote_port, self.remote_ip)
+    }
+}
+
+static TARGET: Mutex<Option<NetconsoleTarget>> = Mutex::new(None);
+/// Lignes en attente d'émission
+static PENDING: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+/// Un vidage est en cours (un envoi peut journaliser à son tour)
+static FLUSHING: AtomicBool = AtomicBool::new(false);
+
+/// Active la console réseau vers `target`
+pub fn enable(target: NetconsoleTarget) {
+    let replay = crate::klog::lines();
+    without_interrupts(|| {
+        *TARGET.lock() = Some(target);
+        let mut pending = PENDING.lock();
+        pending.clear();
+        pending.extend(replay.into_iter().rev().take(PENDING_LINES).rev());
+    });
+    crate::klog::register_sink(emit);
+    flush();
+    log::info!("netconsole: journal envoyé à {}:{}", target.remote_ip, target.remote_port);
+}
+
+/// Arrête l'envoi ; les lignes en attente sont perdues
+pub fn disable() {
+    without_interrupts(|| {
+        *TARGET.lock() = None;
+        PENDING.lock().clear();
+    });
+}
+
+pub fn target() -> Option<NetconsoleTarget> {
+    without_interrupts(|| *TARGET.lock())
+}
+
+/// Active la console réseau d'après l'option de démarrage `netconsole=`,
+/// sinon d'après CONFIG_PATH s'il existe ; appelé par les drivers une
+/// fois l'interface configurée
+pub fn load_config() {
+    if let Some(spec) = crate::cmdline::get("netconsole") {
+        match NetconsoleTarget::parse(spec) {
+            Ok(target) => enable(target),
+            Err(e) => log::warn!("netconsole: option de démarrage: {}", e),
+        }
+        return;
+    }
+    let content = match crate::fs::vfs_read_file(CONFIG_PATH) {
+        Ok(content) => content,
+        Err(_) => return,
+    };
+    let text = String::from_utf8_lossy(&content);
+    let spec = text.lines().map(str::trim).find(|line| !line.is_empty() && !line.starts_with('#'));
+    match spec.map(|spec| NetconsoleTarget::parse(spec.trim_start_matches("netconsole="))) {
+        Some(Ok(target)) => enable(target),
+        Some(Err(e)) => log::warn!("netconsole: {}: {}", CONFIG_PATH, e),
+        None => {}
+    }
+}
+
+/// Transport klog : met la ligne en file puis tente de vider la file
+fn emit(line: &str) {
+    let queued = without_interrupts(|| {
+        if TARGET.lock().is_none() {
+            return false;
+        }
+        let mut pending = PENDING.lock();
+        if pending.len() >= PENDING_LINES {
+            pending.pop_front();
+        }
+        pending.push_back(String::from(line));
+        true
+    });
+    if queued {
+        flush();
+    }
+}
+
+/// Émet les lignes en attente tant que l'interface le permet
+pub fn flush() {
+    let target = match target() {
+        Some(target) => target,
+        None => return,
+    };
+    if FLUSHING.swap(true, Ordering::Acquire) {
+        return;
+    }
+    while let Some(line) = without_interrupts(|| PENDING.lock().pop_front()) {
+        let mut payload: Vec<u8> = line.clone().into_bytes();
+        payload.push(b'\n');
+        let datagram = UdpDatagram::new(target.local_port, target.remote_port, payload);
+        if interface::try_send_udp(target.remote_ip, datagram).is_err() {
+            // Réessayée au prochain appel, dans l'ordre
+            without_interrupts(|| PENDING.lock().push_front(line));
+            break;
+        }
+    }
+    FLUSHING.store(false, Ordering::Release);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_netconsole_target_parse() {
+        let target = NetconsoleTarget::parse("4444@10.0.2.15/eth0,9353@10.0.2.2/52:54:00:12:34:56").unwrap();
+        assert_eq!(target, NetconsoleTarget { local_port: 4444, remote_ip: Ipv4Address::new(10, 0, 2, 2), remote_port: 9353 });
+        assert_eq!(NetconsoleTarget::parse("@/,@10.0.2.2/").unwrap().remote_port, DEFAULT_REMOTE_PORT);
+        assert_eq!(NetconsoleTarget::parse("10.0.2.2:514").unwrap().remote_port, 514);
+        assert_eq!(NetconsoleTarget::parse(&alloc::format!("{}", target)), Ok(target));
+        assert!(NetconsoleTarget::parse("4444@,nowhere").is_err());
+    }
+}
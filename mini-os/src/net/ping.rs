@@ -0,0 +1,179 @@
+/// Client ICMP Echo (ping), en IPv4 ou ICMPv6
+///
+/// Chaque requête porte un identifiant propre à l'appel et un numéro de
+/// séquence ; l'Echo Reply est horodaté à la réception par la pile puis
+/// rapproché de l'instant d'émission pour le calcul du RTT. L'attente
+/// relève les cartes par scrutation et compte sur `time::Instant`.
+
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU16, Ordering};
+use spin::Mutex;
+use x86_64::instructions::interrupts::without_interrupts;
+
+use super::icmp::IcmpMessage;
+use super::icmpv6::Icmpv6Message;
+use super::interface::{self, SendError};
+use super::ip::IpAddress;
+use super::ipv4::IpProtocol;
+use crate::time::{Instant, NSEC_PER_SEC};
+
+/// Taille des données d'une requête (comme ping : 56 octets)
+pub const PAYLOAD_SIZE: usize = 56;
+/// Attente maximale d'une réponse
+pub const REPLY_TIMEOUT_NS: u64 = NSEC_PER_SEC;
+/// Intervalle entre deux requêtes
+pub const INTERVAL_NS: u64 = NSEC_PER_SEC;
+
+/// Réponses en attente d'un client (bornées en cas de client absent)
+const MAX_PENDING: usize = 64;
+
+/// Echo Reply reçu
+#[derive(Debug, Clone, Copy)]
+struct EchoReply {
+    src: IpAddress,
+    identifier: u16,
+    sequence: u16,
+    ttl: u8,
+    received: Instant,
+}
+
+static REPLIES: Mutex<Vec<EchoReply>> = Mutex::new(Vec::new());
+static NEXT_IDENTIFIER: AtomicU16 = AtomicU16::new(1);
+
+/// Appelé par la pile à la réception d'un Echo Reply (`ttl` : hop limit
+/// en IPv6)
+pub(super) fn on_echo_reply(src: IpAddress, ttl: u8, identifier: u16, sequence: u16) {
+    let reply = EchoReply {
+        src,
+        identifier,
+        sequence,
+        ttl,
+        received: Instant::now(),
+    };
+    let mut replies = REPLIES.lock();
+    if replies.len() >= MAX_PENDING {
+        replies.remove(0);
+    }
+    replies.push(reply);
+}
+
+/// Retire la réponse attendue ; jette les autres réponses de cet appel
+/// (retardataires d'une séquence déjà expirée)
+fn take_reply(dst: IpAddress, identifier: u16, sequence: u16) -> Option<EchoReply> {
+    without_interrupts(|| {
+        let mut replies = REPLIES.lock();
+        let found = replies
+            .iter()
+            .position(|r| r.src == dst && r.identifier == identifier && r.sequence == sequence)
+            .map(|index| replies.remove(index));
+        replies.retain(|r| r.identifier != identifier);
+        found
+    })
+}
+
+/// Résultat d'une requête
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PingEvent {
+    Reply { sequence: u16, ttl: u8, rtt_ns: u64 },
+    Timeout { sequence: u16 },
+}
+
+/// Statistiques d'une session
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PingStats {
+    pub transmitted: u32,
+    pub received: u32,
+    pub min_ns: u64,
+    pub max_ns: u64,
+    total_ns: u64,
+}
+
+impl PingStats {
+    /// Compte une réponse reçue
+    pub fn record(&mut self, rtt_ns: u64) {
+        if self.received == 0 || rtt_ns < self.min_ns {
+            self.min_ns = rtt_ns;
+        }
+        self.max_ns = self.max_ns.max(rtt_ns);
+        self.total_ns += rtt_ns;
+        self.received += 1;
+    }
+
+    pub fn avg_ns(&self) -> u64 {
+        if self.received == 0 { 0 } else { self.total_ns / self.received as u64 }
+    }
+
+    /// Pourcentage de requêtes restées sans réponse
+    pub fn loss_percent(&self) -> u32 {
+        if self.transmitted == 0 {
+            return 0;
+        }
+        (self.transmitted - self.received) * 100 / self.transmitted
+    }
+}
+
+/// Envoie `count` Echo Request à `dst`, une par INTERVAL_NS
+///
+/// `report` est appelé après chaque requête (réponse ou délai dépassé).
+pub fn ping(dst: IpAddress, count: u16, mut report: impl FnMut(PingEvent)) -> Result<PingStats, SendError> {
+    let identifier = NEXT_IDENTIFIER.fetch_add(1, Ordering::Relaxed);
+    let payload: Vec<u8> = (0..PAYLOAD_SIZE).map(|i| i as u8).collect();
+    let mut stats = PingStats::default();
+
+    for sequence in 1..=count {
+        let sent = Instant::now();
+        match dst {
+            IpAddress::V4(dst) => {
+                let mut request = IcmpMessage::echo_request(identifier, sequence, payload.clone());
+                interface::send_ipv4(dst, IpProtocol::ICMP, request.serialize())?
+            }
+            IpAddress::V6(dst) => {
+                interface::send_icmpv6(dst, Icmpv6Message::echo_request(identifier, sequence, &payload))?
+            }
+        }
+        stats.transmitted += 1;
+
+        let mut reply = None;
+        while reply.is_none() && sent.elapsed_ns() < REPLY_TIMEOUT_NS {
+            interface::poll();
+            reply = take_reply(dst, identifier, sequence);
+            if reply.is_none() {
+                interface::idle();
+            }
+        }
+
+        report(match reply {
+            Some(reply) => {
+                let rtt_ns = reply.received.duration_since(sent);
+                stats.record(rtt_ns);
+                PingEvent::Reply { sequence, ttl: reply.ttl, rtt_ns }
+            }
+            None => PingEvent::Timeout { sequence },
+        });
+
+        if sequence < count {
+            while sent.elapsed_ns() < INTERVAL_NS {
+                interface::idle();
+            }
+        }
+    }
+
+    // Purge des réponses arrivées après leur délai
+    take_reply(dst, identifier, 0);
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_ping_stats() {
+        let mut stats = PingStats { transmitted: 4, ..Default::default() };
+        stats.record(300);
+        stats.record(100);
+        stats.record(200);
+        assert_eq!((stats.min_ns, stats.max_ns, stats.avg_ns()), (100, 300, 200));
+        assert_eq!(stats.loss_percent(), 25);
+    }
+}
@@ -7,36 +7,64 @@ use alloc::vec::Vec;
 use alloc::collections::VecDeque;
 use spin::Mutex;
 
-use super::tcp::{TcpConnection, TcpState};
+use super::tcp::{TcpConnection, TcpSegment, TcpState};
 use super::udp::UdpDatagram;
-use super::ipv4::{Ipv4Packet, IpProtocol};
-use super::arp::Ipv4Address;
+use super::ip::IpAddress;
 
 use super::udp::Port;
+use super::interface;
+use super::capture::{self, CapturedFrame, PacketFilter};
+use x86_64::instructions::interrupts::without_interrupts;
+use crate::time::{self, Instant};
+
+/// Plage des ports éphémères (IANA)
+pub const EPHEMERAL_PORTS: core::ops::RangeInclusive<Port> = 49152..=65535;
+/// Ports réservés : bind demande CAP_NET_BIND
+pub const PRIVILEGED_PORTS: core::ops::Range<Port> = 1..1024;
 
 /// Type de socket
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SocketType {
     Stream,    // TCP
     Datagram,  // UDP
+    /// Copie des trames Ethernet émises et reçues (capture, réception seule)
+    Raw,
 }
 
 /// Domaine de socket
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SocketDomain {
     Inet,      // IPv4
+    Inet6,     // IPv6
+}
+
+impl SocketDomain {
+    /// L'adresse appartient à la famille du domaine
+    pub fn accepts(&self, ip: &IpAddress) -> bool {
+        ip.is_ipv6() == (*self == SocketDomain::Inet6)
+    }
 }
 
 /// Adresse socket
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct SocketAddr {
-    pub ip: Ipv4Address,
+    pub ip: IpAddress,
     pub port: Port,
 }
 
 impl SocketAddr {
-    pub fn new(ip: Ipv4Address, port: Port) -> Self {
-        Self { ip, port }
+    pub fn new(ip: impl Into<IpAddress>, port: Port) -> Self {
+        Self { ip: ip.into(), port }
+    }
+}
+
+impl core::fmt::Display for SocketAddr {
+    /// « ip:port », « [ip]:port » en IPv6
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self.ip {
+            IpAddress::V4(ip) => write!(f, "{}:{}", ip, self.port),
+            IpAddress::V6(ip) => write!(f, "[{}]:{}", ip, self.port),
+        }
     }
 }
 
@@ -63,6 +91,27 @@ pub struct Socket {
     pub pending_connections: VecDeque<(u32, SocketAddr)>,
     /// Buffer de réception UDP
     pub udp_recv_buffer: VecDeque<Vec<u8>>,
+    /// Octets remis à l'application
+    pub rx_bytes: u64,
+    /// Octets confiés par l'application
+    pub tx_bytes: u64,
+}
+
+/// Instantané d'un socket (netstat, /proc/net)
+#[derive(Debug, Clone, Copy)]
+pub struct SocketInfo {
+    pub id: u32,
+    pub socket_type: SocketType,
+    pub local: Option<SocketAddr>,
+    pub remote: Option<SocketAddr>,
+    /// État TCP (Listen pour un socket en écoute), None pour UDP
+    pub state: Option<TcpState>,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    /// Octets reçus pas encore lus
+    pub recv_queue: usize,
+    /// Octets pas encore acquittés par le pair
+    pub send_queue: usize,
 }
 
 
@@ -80,6 +129,55 @@ impl Socket {
             backlog: 0,
             pending_connections: VecDeque::new(),
             udp_recv_buffer: VecDeque::new(),
+            rx_bytes: 0,
+            tx_bytes: 0,
+        }
+    }
+    
+    /// Instantané pour netstat
+    pub fn info(&self) -> SocketInfo {
+        let state = match (&self.tcp_conn, self.listening) {
+            (_, true) => Some(TcpState::Listen),
+            (Some(conn), false) => Some(conn.state),
+            (None, false) if self.socket_type == SocketType::Stream => Some(TcpState::Closed),
+            (None, false) => None,
+        };
+        let (recv_queue, send_queue) = match &self.tcp_conn {
+            Some(conn) => (conn.recv_buffer.len(), conn.send_buffer.len()),
+            None => (self.udp_recv_buffer.iter().map(|d| d.len()).sum(), 0),
+        };
+        SocketInfo {
+            id: self.id,
+            socket_type: self.socket_type,
+            local: self.local_addr,
+            remote: self.remote_addr,
+            state,
+            rx_bytes: self.rx_bytes,
+            tx_bytes: self.tx_bytes,
+            recv_queue,
+            send_queue,
+        }
+    }
+
+    /// Disponibilité pour poll
+    pub fn poll_events(&self) -> i16 {
+        use crate::fs::poll::{POLLHUP, POLLIN, POLLOUT};
+        if self.listening {
+            return if self.pending_connections.is_empty() { 0 } else { POLLIN };
+        }
+        match &self.tcp_conn {
+            Some(conn) => {
+                let mut events = if conn.recv_buffer.is_empty() { 0 } else { POLLIN };
+                match conn.state {
+                    TcpState::Established => events |= POLLOUT,
+                    // FIN reçu ou connexion terminée : lecture jusqu'à EOF
+                    TcpState::CloseWait | TcpState::LastAck | TcpState::Closed | TcpState::TimeWait => events |= POLLIN | POLLHUP,
+                    _ => {}
+                }
+                events
+            }
+            None if self.socket_type == SocketType::Stream => POLLHUP,
+            None => if self.udp_recv_buffer.is_empty() { POLLOUT } else { POLLIN | POLLOUT },
         }
     }
 
@@ -89,15 +187,22 @@ impl Socket {
         if self.local_addr.is_some() {
             return Err(SocketError::AlreadyBound);
         }
+        if !self.domain.accepts(&addr.ip) {
+            return Err(SocketError::AddressFamily);
+        }
         
         self.local_addr = Some(addr);
         Ok(())
     }
     
-    /// Connect à une adresse distante (TCP)
+    /// Connect à une adresse distante (TCP), ou destination par défaut (UDP)
     pub fn connect(&mut self, addr: SocketAddr) -> Result<(), SocketError> {
-        if self.socket_type != SocketType::Stream {
-            return Err(SocketError::InvalidOperation);
+        if !self.domain.accepts(&addr.ip) {
+            return Err(SocketError::AddressFamily);
+        }
+        if self.socket_type == SocketType::Datagram {
+            self.remote_addr = Some(addr);
+            return Ok(());
         }
         
         let local_addr = self.local_addr.ok_or(SocketError::NotBound)?;
@@ -146,34 +251,22 @@ impl Socket {
             SocketType::Stream => {
                 let conn = self.tcp_conn.as_mut().ok_or(SocketError::NotConnected)?;
                 
-                if conn.state != TcpState::Established {
+                if !matches!(conn.state, TcpState::Established | TcpState::CloseWait) {
                     return Err(SocketError::NotConnected);
                 }
                 
                 // Ajouter au buffer d'envoi
                 conn.send_buffer.extend(data);
+                self.tx_bytes += data.len() as u64;
                 
                 Ok(data.len())
             }
+            SocketType::Raw => Err(SocketError::InvalidOperation),
             SocketType::Datagram => {
-                let remote_addr = self.remote_addr.ok_or(SocketError::NotConnected)?;
-                let local_addr = self.local_addr.ok_or(SocketError::NotBound)?;
-                
-                // Créer datagram UDP
-                let udp_dgram = UdpDatagram::new(local_addr.port, remote_addr.port, data.to_vec());
-                let udp_bytes = udp_dgram.serialize();
-                
-                // Encapsuler dans IPv4
-                let mut ip_packet = Ipv4Packet::new(
-                    local_addr.ip,
-                    remote_addr.ip,
-                    IpProtocol::UDP,
-                    udp_bytes
-                );
-                let ip_bytes = ip_packet.serialize();
+                self.remote_addr.ok_or(SocketError::NotConnected)?;
+                self.local_addr.ok_or(SocketError::NotBound)?;
                 
-                // TODO: Envoyer via interface réseau (Ethernet)
-                // Pour l'instant on retourne juste la taille
+                // L'émission passe par `send_datagram`, hors du verrou de la table
                 Ok(data.len())
             }
         }
@@ -187,7 +280,8 @@ impl Socket {
                 let conn = self.tcp_conn.as_mut().ok_or(SocketError::NotConnected)?;
                 
                 if conn.recv_buffer.is_empty() {
-                    return Err(SocketError::WouldBlock);
+                    // Fin de flux une fois le FIN du pair reçu
+                    return if conn.remote_closed() { Ok(0) } else { Err(SocketError::WouldBlock) };
                 }
                 
                 let to_read = core::cmp::min(buffer.len(), conn.recv_buffer.len());
@@ -195,6 +289,7 @@ impl Socket {
                 for i in 0..to_read {
                     buffer[i] = conn.recv_buffer.pop_front().unwrap();
                 }
+                self.rx_bytes += to_read as u64;
                 
                 Ok(to_read)
             }
@@ -207,9 +302,18 @@ impl Socket {
                 let to_read = core::cmp::min(buffer.len(), packet.len());
                 
                 buffer[..to_read].copy_from_slice(&packet[..to_read]);
+                self.rx_bytes += to_read as u64;
                 
                 Ok(to_read)
             }
+            SocketType::Raw => {
+                // Trame tronquée à la taille du buffer, comme recv(2)
+                let frame = capture::pop(self.id).ok_or(SocketError::WouldBlock)?;
+                let to_read = core::cmp::min(buffer.len(), frame.data.len());
+                buffer[..to_read].copy_from_slice(&frame.data[..to_read]);
+                self.rx_bytes += to_read as u64;
+                Ok(to_read)
+            }
         }
 
     }
@@ -239,10 +343,29 @@ impl SocketTable {
         
         let socket = Socket::new(id, domain, socket_type);
         self.sockets.insert(id, socket);
+        if socket_type == SocketType::Raw {
+            capture::open(id);
+        }
         
         Ok(id)
     }
     
+    /// Premier port éphémère libre
+    pub fn ephemeral_port(&self) -> Port {
+        // Départ pseudo-aléatoire pour ne pas réutiliser tout de suite un port
+        let span = (EPHEMERAL_PORTS.end() - EPHEMERAL_PORTS.start()) as u64 + 1;
+        let start = (unsafe { core::arch::x86_64::_rdtsc() } % span) as Port;
+        (0..=span as u32)
+            .map(|i| EPHEMERAL_PORTS.start() + ((start as u32 + i) % span as u32) as Port)
+            .find(|&port| !self.sockets.values().any(|s| s.local_addr.map(|a| a.port) == Some(port)))
+            .unwrap_or(*EPHEMERAL_PORTS.start())
+    }
+    
+    /// Instantané de tous les sockets, par ID croissant
+    pub fn snapshot(&self) -> Vec<SocketInfo> {
+        self.sockets.values().map(Socket::info).collect()
+    }
+    
     /// Récupère un socket
     pub fn get(&self, id: u32) -> Option<&Socket> {
         self.sockets.get(&id)
@@ -255,12 +378,18 @@ impl SocketTable {
     
     /// Ferme un socket
     pub fn close(&mut self, id: u32) -> Result<(), SocketError> {
-        self.sockets.remove(&id).ok_or(SocketError::InvalidSocket)?;
+        let socket = self.sockets.remove(&id).ok_or(SocketError::InvalidSocket)?;
+        if socket.socket_type == SocketType::Raw {
+            capture::close(id);
+        }
         Ok(())
     }
     
     /// Bind
     pub fn bind(&mut self, id: u32, addr: SocketAddr) -> Result<(), SocketError> {
+        if PRIVILEGED_PORTS.contains(&addr.port) && !crate::process::current_capable(crate::process::cred::CAP_NET_BIND) {
+            return Err(SocketError::PermissionDenied);
+        }
         let socket = self.sockets.get_mut(&id).ok_or(SocketError::InvalidSocket)?;
         socket.bind(addr)
     }
@@ -294,6 +423,83 @@ impl SocketTable {
         let socket = self.sockets.get_mut(&id).ok_or(SocketError::InvalidSocket)?;
         socket.recv(buffer)
     }
+    
+    /// Remet un segment reçu de `src_ip` à sa connexion ; retourne la
+    /// réponse immédiate (ACK), ou un RST si aucune connexion ne correspond
+    pub fn on_tcp_segment(&mut self, src_ip: IpAddress, segment: &TcpSegment) -> Option<TcpSegment> {
+        let conn = self.sockets.values_mut().filter_map(|s| s.tcp_conn.as_mut()).find(|c| {
+            c.state != TcpState::Closed
+                && c.local_port == segment.dst_port
+                && c.remote_port == segment.src_port
+                && c.remote_ip == src_ip
+        });
+        if let Some(conn) = conn {
+            return conn.handle_segment(segment);
+        }
+        if segment.flags.syn && !segment.flags.ack && self.listener(segment.dst_port, &src_ip).is_some() {
+            self.accept_syn(src_ip, segment)
+        } else {
+            TcpSegment::reset_for(segment)
+        }
+    }
+    
+    /// Socket en écoute sur `port` pour un pair de la famille de `peer`
+    fn listener(&self, port: Port, peer: &IpAddress) -> Option<&Socket> {
+        self.sockets.values().find(|s| {
+            s.listening && s.domain.accepts(peer) && s.local_addr.map(|a| a.port) == Some(port)
+        })
+    }
+    
+    /// Crée la connexion fille d'un SYN reçu sur un port en écoute et
+    /// retourne le SYN-ACK ; SYN ignoré si la file d'attente est pleine
+    fn accept_syn(&mut self, src_ip: IpAddress, syn: &TcpSegment) -> Option<TcpSegment> {
+        let listener = self.listener(syn.dst_port, &src_ip)?;
+        if listener.pending_connections.len() >= listener.backlog.max(1) {
+            return None;
+        }
+        let (listener_id, domain, local) = (listener.id, listener.domain, listener.local_addr?);
+        let remote = SocketAddr::new(src_ip, syn.src_port);
+        
+        let mut conn = TcpConnection::accept_syn(syn.dst_port, src_ip, syn);
+        let syn_ack = conn.output(time::now_ns()).pop();
+        
+        let id = self.socket(domain, SocketType::Stream).ok()?;
+        let socket = self.sockets.get_mut(&id)?;
+        socket.local_addr = Some(local);
+        socket.remote_addr = Some(remote);
+        socket.tcp_conn = Some(conn);
+        self.sockets.get_mut(&listener_id)?.pending_connections.push_back((id, remote));
+        crate::fs::poll::wake();
+        syn_ack
+    }
+    
+    /// Retire de la file d'un socket en écoute la première connexion
+    /// établie ; les connexions avortées pendant le handshake sont libérées
+    pub fn take_established(&mut self, listen_id: u32) -> Result<Option<(u32, SocketAddr)>, SocketError> {
+        let listener = self.sockets.get(&listen_id).ok_or(SocketError::InvalidSocket)?;
+        if !listener.listening {
+            return Err(SocketError::NotListening);
+        }
+        let pending: Vec<(u32, SocketAddr)> = listener.pending_connections.iter().copied().collect();
+        
+        let state = |table: &Self, id: u32| table.get(id).and_then(|s| s.tcp_conn.as_ref()).map(|c| c.state);
+        let mut accepted = None;
+        let mut kept = VecDeque::new();
+        for (id, addr) in pending {
+            match state(self, id) {
+                Some(TcpState::SynReceived) => kept.push_back((id, addr)),
+                Some(TcpState::Closed) | None => {
+                    self.sockets.remove(&id);
+                }
+                Some(_) if accepted.is_none() => accepted = Some((id, addr)),
+                Some(_) => kept.push_back((id, addr)),
+            }
+        }
+        if let Some(listener) = self.sockets.get_mut(&listen_id) {
+            listener.pending_connections = kept;
+        }
+        Ok(accepted)
+    }
 }
 
 /// Erreurs de socket
@@ -307,6 +513,12 @@ pub enum SocketError {
     InvalidOperation,
     WouldBlock,
     ConnectionRefused,
+    /// Paquet non émis (pas d'interface, hôte injoignable)
+    Unreachable,
+    /// Adresse d'une autre famille que le domaine du socket
+    AddressFamily,
+    /// Port réservé sans CAP_NET_BIND
+    PermissionDenied,
 }
 
 /// Instance globale de la table de sockets
@@ -316,9 +528,224 @@ lazy_static! {
     pub static ref SOCKET_TABLE: Mutex<SocketTable> = Mutex::new(SocketTable::new());
 }
 
+/// Émet les segments en attente d'une connexion TCP (données, FIN,
+/// retransmissions), verrou de la table relâché
+pub fn stream_flush(id: u32) -> Result<(), SocketError> {
+    let (remote, segments) = without_interrupts(|| {
+        let mut table = SOCKET_TABLE.lock();
+        let socket = table.get_mut(id).ok_or(SocketError::InvalidSocket)?;
+        let conn = socket.tcp_conn.as_mut().ok_or(SocketError::NotConnected)?;
+        Ok((conn.remote_ip, conn.output(time::now_ns())))
+    })?;
+    for segment in segments {
+        interface::send_tcp(remote, segment).map_err(|_| SocketError::Unreachable)?;
+    }
+    Ok(())
+}
+
+/// Fait avancer les temporisations de toutes les connexions TCP
+/// (retransmissions, SYN-ACK des connexions pas encore acceptées)
+pub fn tcp_timers() {
+    let now = time::now_ns();
+    let segments: Vec<(IpAddress, TcpSegment)> = without_interrupts(|| {
+        let mut table = SOCKET_TABLE.lock();
+        let mut segments = Vec::new();
+        for conn in table.sockets.values_mut().filter_map(|s| s.tcp_conn.as_mut()) {
+            let remote = conn.remote_ip;
+            segments.extend(conn.output(now).into_iter().map(|segment| (remote, segment)));
+        }
+        segments
+    });
+    for (remote, segment) in segments {
+        let _ = interface::send_tcp(remote, segment);
+    }
+}
+
+/// Attend une connexion établie sur un socket en écoute
+pub fn stream_accept(listen_id: u32, timeout_ns: u64) -> Result<(u32, SocketAddr), SocketError> {
+    let start = Instant::now();
+    loop {
+        interface::poll();
+        tcp_timers();
+        if let Some(accepted) = without_interrupts(|| SOCKET_TABLE.lock().take_established(listen_id))? {
+            return Ok(accepted);
+        }
+        if start.elapsed_ns() >= timeout_ns {
+            return Err(SocketError::WouldBlock);
+        }
+        interface::idle();
+    }
+}
+
+/// État TCP d'un socket
+fn stream_state(id: u32) -> Result<TcpState, SocketError> {
+    without_interrupts(|| {
+        let table = SOCKET_TABLE.lock();
+        let socket = table.get(id).ok_or(SocketError::InvalidSocket)?;
+        socket.tcp_conn.as_ref().map(|c| c.state).ok_or(SocketError::NotConnected)
+    })
+}
+
+/// Relève les trames et avance la connexion jusqu'à `done` ou l'échéance
+fn stream_wait(id: u32, timeout_ns: u64, mut done: impl FnMut() -> bool) -> Result<(), SocketError> {
+    let start = Instant::now();
+    loop {
+        interface::poll();
+        stream_flush(id)?;
+        if done() {
+            return Ok(());
+        }
+        if start.elapsed_ns() >= timeout_ns {
+            return Err(SocketError::WouldBlock);
+        }
+        interface::idle();
+    }
+}
+
+/// Ouvre une connexion TCP (port local éphémère si non lié) et attend
+/// la fin du handshake
+pub fn stream_connect(id: u32, addr: SocketAddr, timeout_ns: u64) -> Result<(), SocketError> {
+    without_interrupts(|| {
+        let mut table = SOCKET_TABLE.lock();
+        if table.get(id).ok_or(SocketError::InvalidSocket)?.local_addr.is_none() {
+            let port = table.ephemeral_port();
+            table.bind(id, SocketAddr::new(addr.ip.unspecified_like(), port))?;
+        }
+        table.connect(id, addr)
+    })?;
+
+    let mut state = TcpState::SynSent;
+    let waited = stream_wait(id, timeout_ns, || {
+        state = stream_state(id).unwrap_or(TcpState::Closed);
+        state != TcpState::SynSent
+    });
+    match (waited, state) {
+        (_, TcpState::Established) => Ok(()),
+        (Err(e), _) => Err(e),
+        _ => Err(SocketError::ConnectionRefused),
+    }
+}
+
+/// Envoie des données sur une connexion TCP
+pub fn stream_send(id: u32, data: &[u8]) -> Result<usize, SocketError> {
+    let len = without_interrupts(|| SOCKET_TABLE.lock().send(id, data))?;
+    stream_flush(id)?;
+    Ok(len)
+}
+
+/// Reçoit des données d'une connexion TCP, en attendant au plus
+/// `timeout_ns` ; `Ok(0)` en fin de flux
+pub fn stream_recv(id: u32, buffer: &mut [u8], timeout_ns: u64) -> Result<usize, SocketError> {
+    let mut result = Err(SocketError::WouldBlock);
+    stream_wait(id, timeout_ns, || {
+        result = without_interrupts(|| SOCKET_TABLE.lock().recv(id, buffer));
+        result != Err(SocketError::WouldBlock)
+    })?;
+    result
+}
+
+/// Attente maximale de l'acquittement de notre FIN (données comprises)
+const CLOSE_TIMEOUT_NS: u64 = 5 * time::NSEC_PER_SEC;
+
+/// Ferme une connexion TCP (données restantes, FIN, attente de
+/// l'acquittement) et libère le socket
+pub fn stream_close(id: u32) -> Result<(), SocketError> {
+    without_interrupts(|| {
+        let mut table = SOCKET_TABLE.lock();
+        let socket = table.get_mut(id).ok_or(SocketError::InvalidSocket)?;
+        if let Some(conn) = socket.tcp_conn.as_mut() {
+            conn.close();
+        }
+        Ok(())
+    })?;
+    let _ = stream_wait(id, CLOSE_TIMEOUT_NS, || {
+        matches!(stream_state(id), Ok(TcpState::FinWait2 | TcpState::TimeWait | TcpState::Closed) | Err(_))
+    });
+    without_interrupts(|| SOCKET_TABLE.lock().close(id))
+}
+
+/// Émet un datagramme sur un socket UDP connecté
+///
+/// Contrairement à `SocketTable::send`, le verrou de la table est relâché
+/// avant l'émission : la résolution ARP relève les trames reçues, qui
+/// peuvent être destinées à un socket.
+pub fn send_datagram(id: u32, data: &[u8]) -> Result<usize, SocketError> {
+    let (local, remote) = without_interrupts(|| {
+        let table = SOCKET_TABLE.lock();
+        let socket = table.get(id).ok_or(SocketError::InvalidSocket)?;
+        if socket.socket_type != SocketType::Datagram {
+            return Err(SocketError::InvalidOperation);
+        }
+        let local = socket.local_addr.ok_or(SocketError::NotBound)?;
+        let remote = socket.remote_addr.ok_or(SocketError::NotConnected)?;
+        Ok((local, remote))
+    })?;
+
+    let datagram = UdpDatagram::new(local.port, remote.port, data.to_vec());
+    interface::send_udp(remote.ip, datagram).map_err(|_| SocketError::Unreachable)?;
+    without_interrupts(|| {
+        if let Some(socket) = SOCKET_TABLE.lock().get_mut(id) {
+            socket.tx_bytes += data.len() as u64;
+        }
+    });
+    Ok(data.len())
+}
+
+/// Ouvre un socket raw recevant les trames acceptées par `filter`
+pub fn raw_open(filter: PacketFilter) -> Result<u32, SocketError> {
+    let id = without_interrupts(|| SOCKET_TABLE.lock().socket(SocketDomain::Inet, SocketType::Raw))?;
+    set_filter(id, filter)?;
+    Ok(id)
+}
+
+/// Libère un socket sans échange réseau (raw, UDP)
+pub fn close(id: u32) -> Result<(), SocketError> {
+    without_interrupts(|| SOCKET_TABLE.lock().close(id))
+}
+
+/// Restreint les trames copiées vers un socket raw
+pub fn set_filter(id: u32, filter: PacketFilter) -> Result<(), SocketError> {
+    if capture::set_filter(id, filter) {
+        Ok(())
+    } else {
+        Err(SocketError::InvalidOperation)
+    }
+}
+
+/// Prochaine trame d'un socket raw (horodatage et sens compris), en
+/// relevant les cartes pendant au plus `timeout_ns`
+pub fn raw_recv(id: u32, timeout_ns: u64) -> Result<CapturedFrame, SocketError> {
+    let start = Instant::now();
+    loop {
+        interface::poll();
+        let socket_type = without_interrupts(|| SOCKET_TABLE.lock().get(id).map(|s| s.socket_type));
+        if socket_type != Some(SocketType::Raw) {
+            return Err(SocketError::InvalidSocket);
+        }
+        if let Some(frame) = capture::pop(id) {
+            without_interrupts(|| {
+                if let Some(socket) = SOCKET_TABLE.lock().get_mut(id) {
+                    socket.rx_bytes += frame.data.len() as u64;
+                }
+            });
+            return Ok(frame);
+        }
+        if start.elapsed_ns() >= timeout_ns {
+            return Err(SocketError::WouldBlock);
+        }
+        interface::idle();
+    }
+}
+
+/// Instantané de la table de sockets (netstat, /proc/net)
+pub fn snapshot() -> Vec<SocketInfo> {
+    without_interrupts(|| SOCKET_TABLE.lock().snapshot())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::net::arp::Ipv4Address;
     
     #[test_case]
     fn test_socket_creation() {
@@ -355,4 +782,19 @@ mod tests {
         assert!(socket.listening);
         assert_eq!(socket.backlog, 10);
     }
+    
+    #[test_case]
+    fn test_socket_snapshot() {
+        let mut table = SocketTable::new();
+        let id = table.socket(SocketDomain::Inet, SocketType::Datagram).unwrap();
+        table.bind(id, SocketAddr::new(Ipv4Address::new(0, 0, 0, 0), 5353)).unwrap();
+        table.get_mut(id).unwrap().udp_recv_buffer.push_back(alloc::vec![1, 2, 3]);
+        let mut buffer = [0u8; 2];
+        table.recv(id, &mut buffer).unwrap();
+        
+        let info = table.snapshot()[0];
+        assert_eq!(info.state, None);
+        assert_eq!(info.local.map(|a| a.port), Some(5353));
+        assert_eq!((info.rx_bytes, info.recv_queue), (2, 0));
+    }
 }
@@ -1,11 +1,22 @@
 /// Module TCP (Transmission Control Protocol)
 /// 
-/// Protocole de transport orienté connexion
+/// Protocole de transport orienté connexion. `TcpConnection` est une pure
+/// machine à états : `handle_segment` consomme les segments reçus et
+/// `output` produit ceux à émettre (données, FIN, retransmissions) ; la
+/// couche socket se charge de l'émission.
 
 use alloc::vec::Vec;
 use alloc::collections::VecDeque;
-use super::arp::Ipv4Address;
+use super::ip::{self, IpAddress};
 use super::udp::Port;
+use crate::time::{self, NSEC_PER_SEC};
+
+/// Taille maximale des données d'un segment (MTU Ethernet - en-têtes IP et TCP)
+pub const MSS: usize = 1460;
+/// Délai de retransmission
+pub const RTO_NS: u64 = NSEC_PER_SEC;
+/// Retransmissions avant abandon de la connexion
+pub const MAX_RETRIES: u8 = 5;
 
 /// État TCP
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -23,6 +34,42 @@ pub enum TcpState {
     TimeWait,
 }
 
+impl TcpState {
+    /// Nom affiché par netstat
+    pub fn name(&self) -> &'static str {
+        match self {
+            TcpState::Closed => "CLOSED",
+            TcpState::Listen => "LISTEN",
+            TcpState::SynSent => "SYN_SENT",
+            TcpState::SynReceived => "SYN_RECV",
+            TcpState::Established => "ESTABLISHED",
+            TcpState::FinWait1 => "FIN_WAIT1",
+            TcpState::FinWait2 => "FIN_WAIT2",
+            TcpState::CloseWait => "CLOSE_WAIT",
+            TcpState::Closing => "CLOSING",
+            TcpState::LastAck => "LAST_ACK",
+            TcpState::TimeWait => "TIME_WAIT",
+        }
+    }
+
+    /// Code d'état de /proc/net/tcp (numérotation de Linux)
+    pub fn proc_code(&self) -> u8 {
+        match self {
+            TcpState::Established => 0x01,
+            TcpState::SynSent => 0x02,
+            TcpState::SynReceived => 0x03,
+            TcpState::FinWait1 => 0x04,
+            TcpState::FinWait2 => 0x05,
+            TcpState::TimeWait => 0x06,
+            TcpState::Closed => 0x07,
+            TcpState::CloseWait => 0x08,
+            TcpState::LastAck => 0x09,
+            TcpState::Listen => 0x0A,
+            TcpState::Closing => 0x0B,
+        }
+    }
+}
+
 /// Flags TCP
 #[derive(Debug, Clone, Copy)]
 pub struct TcpFlags {
@@ -65,6 +112,24 @@ impl TcpFlags {
         flags
     }
     
+    pub fn psh_ack() -> Self {
+        let mut flags = Self::ack();
+        flags.psh = true;
+        flags
+    }
+    
+    pub fn fin_ack() -> Self {
+        let mut flags = Self::ack();
+        flags.fin = true;
+        flags
+    }
+    
+    pub fn rst() -> Self {
+        let mut flags = Self::new();
+        flags.rst = true;
+        flags
+    }
+    
     pub fn to_u8(&self) -> u8 {
         let mut byte = 0u8;
         if self.fin { byte |= 0x01; }
@@ -168,18 +233,11 @@ impl TcpSegment {
         })
     }
     
-    /// Calcule le checksum TCP (avec pseudo-header)
-    pub fn calculate_checksum(&self, src_ip: Ipv4Address, dst_ip: Ipv4Address) -> u16 {
-        let mut sum: u32 = 0;
-        
-        // Pseudo-header
-        for i in 0..4 {
-            sum += src_ip.0[i] as u32;
-            sum += dst_ip.0[i] as u32;
-        }
-        sum += 6; // TCP protocol number
+    /// Calcule le checksum TCP (avec pseudo-header IPv4 ou IPv6)
+    pub fn calculate_checksum(&self, src_ip: impl Into<IpAddress>, dst_ip: impl Into<IpAddress>) -> u16 {
+        // Pseudo-header (mots de 16 bits)
         let tcp_len = (self.data_offset as usize * 4) + self.payload.len();
-        sum += tcp_len as u32;
+        let mut sum = ip::pseudo_header_sum(src_ip.into(), dst_ip.into(), 6, tcp_len as u32);
         
         // TCP header (sans checksum)
         sum += self.src_port as u32;
@@ -210,6 +268,29 @@ impl TcpSegment {
         !sum as u16
     }
     
+    /// RST à opposer à un segment qui ne correspond à aucune connexion
+    /// (jamais en réponse à un RST)
+    pub fn reset_for(segment: &TcpSegment) -> Option<TcpSegment> {
+        if segment.flags.rst {
+            return None;
+        }
+        if segment.flags.ack {
+            return Some(Self::new(segment.dst_port, segment.src_port, segment.ack_num, 0, TcpFlags::rst(), Vec::new()));
+        }
+        let mut len = segment.payload.len() as u32;
+        if segment.flags.syn { len += 1; }
+        if segment.flags.fin { len += 1; }
+        let mut flags = TcpFlags::rst();
+        flags.ack = true;
+        Some(Self::new(segment.dst_port, segment.src_port, 0, segment.seq_num.wrapping_add(len), flags, Vec::new()))
+    }
+    
+    /// Sérialise le segment avec son checksum
+    pub fn serialize_for(&mut self, src_ip: impl Into<IpAddress>, dst_ip: impl Into<IpAddress>) -> Vec<u8> {
+        self.checksum = self.calculate_checksum(src_ip, dst_ip);
+        self.serialize()
+    }
+    
     /// Sérialise le segment
     pub fn serialize(&self) -> Vec<u8> {
         let header_len = (self.data_offset as usize) * 4;
@@ -243,20 +324,31 @@ pub struct TcpConnection {
     /// Port distant
     pub remote_port: Port,
     /// IP distante
-    pub remote_ip: Ipv4Address,
-    /// Numéro de séquence
+    pub remote_ip: IpAddress,
+    /// Numéro de séquence (prochain octet à émettre, SND.NXT)
     pub seq_num: u32,
     /// Numéro d'acquittement
     pub ack_num: u32,
+    /// Plus ancien octet émis non acquitté (SND.UNA)
+    pub snd_una: u32,
     /// Buffer de réception
     pub recv_buffer: VecDeque<u8>,
-    /// Buffer d'envoi
+    /// Buffer d'envoi, à partir de SND.UNA (non acquitté puis non émis)
     pub send_buffer: VecDeque<u8>,
+    /// Fenêtre annoncée par le pair
+    pub remote_window: u16,
+    /// Fermeture demandée par l'application
+    fin_queued: bool,
+    /// Numéro de séquence de notre FIN une fois émis
+    fin_seq: Option<u32>,
+    /// Échéance de retransmission (ns de `time::now_ns`)
+    retransmit_at: Option<u64>,
+    retries: u8,
 }
 
 impl TcpConnection {
     /// Crée une nouvelle connexion
-    pub fn new(local_port: Port, remote_ip: Ipv4Address, remote_port: Port) -> Self {
+    pub fn new(local_port: Port, remote_ip: impl Into<IpAddress>, remote_port: Port) -> Self {
         // Utiliser RDTSC pour générer un ISN (Initial Sequence Number) pseudo-aléatoire
         let isn = unsafe { core::arch::x86_64::_rdtsc() } as u32;
         
@@ -264,69 +356,224 @@ impl TcpConnection {
             state: TcpState::Closed,
             local_port,
             remote_port,
-            remote_ip,
+            remote_ip: remote_ip.into(),
             seq_num: isn,
             ack_num: 0,
+            snd_una: isn,
             recv_buffer: VecDeque::new(),
             send_buffer: VecDeque::new(),
+            remote_window: 0,
+            fin_queued: false,
+            fin_seq: None,
+            retransmit_at: None,
+            retries: 0,
         }
     }
     
     /// Démarre le handshake (SYN)
+    ///
+    /// Le SYN est aussi produit (et retransmis) par `output`.
     pub fn connect(&mut self) -> TcpSegment {
         self.state = TcpState::SynSent;
-        TcpSegment::new(
-            self.local_port,
-            self.remote_port,
-            self.seq_num,
-            0,
-            TcpFlags::syn(),
-            Vec::new()
+        self.segment(TcpFlags::syn(), Vec::new())
+    }
+    
+    /// Connexion passive née d'un SYN reçu sur un port en écoute ; le
+    /// SYN-ACK est produit par `output`
+    pub fn accept_syn(local_port: Port, remote_ip: impl Into<IpAddress>, syn: &TcpSegment) -> Self {
+        let mut conn = Self::new(local_port, remote_ip, syn.src_port);
+        conn.ack_num = syn.seq_num.wrapping_add(1);
+        conn.remote_window = syn.window;
+        conn.state = TcpState::SynReceived;
+        conn
+    }
+    
+    /// Segment portant nos numéros courants
+    fn segment(&self, flags: TcpFlags, payload: Vec<u8>) -> TcpSegment {
+        TcpSegment::new(self.local_port, self.remote_port, self.seq_num, self.ack_num, flags, payload)
+    }
+    
+    /// Données ou FIN émis et pas encore acquittés
+    pub fn has_unacked(&self) -> bool {
+        self.seq_num != self.snd_una
+    }
+    
+    /// Le pair a fermé son sens d'émission : plus de données à attendre
+    pub fn remote_closed(&self) -> bool {
+        matches!(
+            self.state,
+            TcpState::CloseWait | TcpState::LastAck | TcpState::Closing | TcpState::TimeWait | TcpState::Closed
         )
     }
     
+    /// Demande la fermeture : le FIN part après les données en attente
+    pub fn close(&mut self) {
+        match self.state {
+            TcpState::Established | TcpState::CloseWait | TcpState::SynReceived => self.fin_queued = true,
+            TcpState::SynSent | TcpState::Listen => self.state = TcpState::Closed,
+            _ => {}
+        }
+    }
+    
+    /// Traite l'acquittement `ack` du pair
+    fn process_ack(&mut self, ack: u32) {
+        let acked = ack.wrapping_sub(self.snd_una);
+        let in_flight = self.seq_num.wrapping_sub(self.snd_una);
+        if acked == 0 || acked > in_flight {
+            return;
+        }
+        let data = (acked as usize).min(self.send_buffer.len());
+        self.send_buffer.drain(..data);
+        self.snd_una = ack;
+        
+        if self.fin_seq.map_or(false, |fin| ack == fin.wrapping_add(1)) {
+            self.state = match self.state {
+                TcpState::FinWait1 => TcpState::FinWait2,
+                TcpState::Closing => TcpState::TimeWait,
+                TcpState::LastAck => TcpState::Closed,
+                other => other,
+            };
+        }
+        
+        self.retries = 0;
+        self.retransmit_at = if self.has_unacked() { Some(time::now_ns() + RTO_NS) } else { None };
+    }
+    
     /// Traite un segment reçu
     pub fn handle_segment(&mut self, segment: &TcpSegment) -> Option<TcpSegment> {
+        if segment.flags.rst {
+            self.state = TcpState::Closed;
+            return None;
+        }
+        
         match self.state {
             TcpState::SynSent => {
-                if segment.flags.syn && segment.flags.ack {
-                    self.ack_num = segment.seq_num + 1;
-                    self.seq_num += 1;
+                if segment.flags.syn && segment.flags.ack && segment.ack_num == self.seq_num.wrapping_add(1) {
+                    self.ack_num = segment.seq_num.wrapping_add(1);
+                    self.seq_num = self.seq_num.wrapping_add(1);
+                    self.snd_una = self.seq_num;
+                    self.remote_window = segment.window;
+                    self.retransmit_at = None;
+                    self.retries = 0;
                     self.state = TcpState::Established;
                     
                     // Envoyer ACK
-                    return Some(TcpSegment::new(
-                        self.local_port,
-                        self.remote_port,
-                        self.seq_num,
-                        self.ack_num,
-                        TcpFlags::ack(),
-                        Vec::new()
-                    ));
+                    return Some(self.segment(TcpFlags::ack(), Vec::new()));
                 }
             }
-            TcpState::Established => {
-                if !segment.payload.is_empty() {
-                    // Ajouter au buffer de réception
-                    self.recv_buffer.extend(&segment.payload);
-                    self.ack_num += segment.payload.len() as u32;
-                    
-                    // Envoyer ACK
-                    return Some(TcpSegment::new(
-                        self.local_port,
-                        self.remote_port,
-                        self.seq_num,
-                        self.ack_num,
-                        TcpFlags::ack(),
-                        Vec::new()
-                    ));
+            TcpState::SynReceived => {
+                if segment.flags.ack && segment.ack_num == self.seq_num.wrapping_add(1) {
+                    self.seq_num = self.seq_num.wrapping_add(1);
+                    self.snd_una = self.seq_num;
+                    self.remote_window = segment.window;
+                    self.retransmit_at = None;
+                    self.retries = 0;
+                    self.state = TcpState::Established;
+                    // L'ACK final peut déjà porter des données
+                    return self.handle_segment(segment);
+                }
+            }
+            TcpState::Established | TcpState::FinWait1 | TcpState::FinWait2
+            | TcpState::CloseWait | TcpState::Closing | TcpState::LastAck => {
+                if segment.flags.ack {
+                    self.remote_window = segment.window;
+                    self.process_ack(segment.ack_num);
+                }
+                
+                let receiving = matches!(self.state, TcpState::Established | TcpState::FinWait1 | TcpState::FinWait2);
+                if !receiving || (segment.payload.is_empty() && !segment.flags.fin) {
+                    return None;
+                }
+                if segment.seq_num != self.ack_num {
+                    // Hors séquence : rappeler ce que l'on attend
+                    return Some(self.segment(TcpFlags::ack(), Vec::new()));
                 }
+                
+                // Ajouter au buffer de réception
+                self.recv_buffer.extend(&segment.payload);
+                self.ack_num = self.ack_num.wrapping_add(segment.payload.len() as u32);
+                if segment.flags.fin {
+                    self.ack_num = self.ack_num.wrapping_add(1);
+                    self.state = match self.state {
+                        TcpState::Established => TcpState::CloseWait,
+                        TcpState::FinWait1 => TcpState::Closing,
+                        _ => TcpState::TimeWait,
+                    };
+                }
+                crate::fs::poll::wake();
+                
+                // Envoyer ACK
+                return Some(self.segment(TcpFlags::ack(), Vec::new()));
             }
             _ => {}
         }
         
         None
     }
+    
+    /// Segments à émettre à l'instant `now` : SYN, données dans la fenêtre
+    /// du pair, FIN, et retransmission (go-back-N) à l'échéance du RTO
+    pub fn output(&mut self, now: u64) -> Vec<TcpSegment> {
+        let mut segments = Vec::new();
+        let expired = self.retransmit_at.map_or(false, |deadline| now >= deadline);
+        if expired {
+            self.retries += 1;
+            if self.retries > MAX_RETRIES {
+                self.state = TcpState::Closed;
+                self.retransmit_at = None;
+                return segments;
+            }
+        }
+        
+        match self.state {
+            TcpState::SynSent | TcpState::SynReceived => {
+                if expired || self.retransmit_at.is_none() {
+                    let flags = if self.state == TcpState::SynSent { TcpFlags::syn() } else { TcpFlags::syn_ack() };
+                    segments.push(self.segment(flags, Vec::new()));
+                    self.retransmit_at = Some(now + RTO_NS);
+                }
+                return segments;
+            }
+            TcpState::Established | TcpState::CloseWait | TcpState::FinWait1
+            | TcpState::Closing | TcpState::LastAck => {}
+            _ => return segments,
+        }
+        
+        if expired {
+            // Tout réémettre depuis le plus ancien octet non acquitté
+            self.seq_num = self.snd_una;
+            self.fin_seq = None;
+        }
+        
+        let window = (self.remote_window as usize).max(1);
+        loop {
+            let offset = self.seq_num.wrapping_sub(self.snd_una) as usize;
+            if offset >= self.send_buffer.len() || offset >= window {
+                break;
+            }
+            let len = MSS.min(self.send_buffer.len() - offset).min(window - offset);
+            let payload: Vec<u8> = self.send_buffer.range(offset..offset + len).copied().collect();
+            segments.push(self.segment(TcpFlags::psh_ack(), payload));
+            self.seq_num = self.seq_num.wrapping_add(len as u32);
+        }
+        
+        let all_sent = self.seq_num.wrapping_sub(self.snd_una) as usize == self.send_buffer.len();
+        if self.fin_queued && self.fin_seq.is_none() && all_sent {
+            segments.push(self.segment(TcpFlags::fin_ack(), Vec::new()));
+            self.fin_seq = Some(self.seq_num);
+            self.seq_num = self.seq_num.wrapping_add(1);
+            self.state = match self.state {
+                TcpState::CloseWait => TcpState::LastAck,
+                TcpState::Established => TcpState::FinWait1,
+                other => other,
+            };
+        }
+        
+        if !segments.is_empty() && (expired || self.retransmit_at.is_none()) {
+            self.retransmit_at = Some(now + RTO_NS);
+        }
+        segments
+    }
 }
 
 /// Erreurs TCP
@@ -341,6 +588,7 @@ pub enum TcpError {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::net::arp::Ipv4Address;
     
     #[test_case]
     fn test_tcp_flags() {
@@ -377,4 +625,67 @@ mod tests {
         assert_eq!(conn.state, TcpState::SynSent);
         assert!(syn.flags.syn);
     }
+    
+    #[test_case]
+    fn test_tcp_data_exchange() {
+        let mut conn = TcpConnection::new(1234, Ipv4Address::new(10, 0, 2, 2), 80);
+        // ISN fixe pour éviter le débordement des calculs du test
+        let isn = 1000;
+        conn.seq_num = isn;
+        conn.snd_una = isn;
+        conn.connect();
+        let syn = conn.output(0);
+        assert!(syn[0].flags.syn);
+        
+        let mut syn_ack = TcpSegment::new(80, 1234, 5000, isn + 1, TcpFlags::syn_ack(), Vec::new());
+        syn_ack.window = 4;
+        let ack = conn.handle_segment(&syn_ack).unwrap();
+        assert_eq!(conn.state, TcpState::Established);
+        assert_eq!(ack.ack_num, 5001);
+        
+        // Fenêtre de 4 octets : « GET » puis un octet, le reste attend
+        conn.send_buffer.extend(b"GET /");
+        let data = conn.output(0);
+        assert_eq!(data.len(), 1);
+        assert_eq!(data[0].payload, b"GET ");
+        
+        // Perte : retransmission depuis SND.UNA à l'échéance
+        let again = conn.output(RTO_NS + 1);
+        assert_eq!(again[0].seq_num, isn + 1);
+        
+        let mut reply = TcpSegment::new(80, 1234, 5001, isn + 5, TcpFlags::ack(), b"200".to_vec());
+        reply.window = 100;
+        reply.flags.fin = true;
+        conn.handle_segment(&reply);
+        assert_eq!(conn.send_buffer.len(), 1);
+        assert_eq!(conn.recv_buffer.len(), 3);
+        assert_eq!(conn.ack_num, 5005);
+        assert_eq!(conn.state, TcpState::CloseWait);
+        
+        conn.close();
+        let tail = conn.output(RTO_NS + 2);
+        assert_eq!(tail[0].payload, b"/");
+        assert!(tail[1].flags.fin);
+        assert_eq!(conn.state, TcpState::LastAck);
+        conn.handle_segment(&TcpSegment::new(80, 1234, 5005, isn + 7, TcpFlags::ack(), Vec::new()));
+        assert_eq!(conn.state, TcpState::Closed);
+    }
+    
+    #[test_case]
+    fn test_tcp_passive_open() {
+        let syn = TcpSegment::new(40000, 80, 7000, 0, TcpFlags::syn(), Vec::new());
+        let mut conn = TcpConnection::accept_syn(80, Ipv4Address::new(10, 0, 2, 2), &syn);
+        conn.seq_num = 100;
+        conn.snd_una = 100;
+        let syn_ack = conn.output(0);
+        assert!(syn_ack[0].flags.syn && syn_ack[0].flags.ack);
+        assert_eq!(syn_ack[0].ack_num, 7001);
+        
+        // ACK final portant déjà la requête
+        let request = TcpSegment::new(40000, 80, 7001, 101, TcpFlags::psh_ack(), b"GET".to_vec());
+        let ack = conn.handle_segment(&request).unwrap();
+        assert_eq!(conn.state, TcpState::Established);
+        assert_eq!(ack.ack_num, 7004);
+        assert_eq!(conn.recv_buffer.len(), 3);
+    }
 }
@@ -3,7 +3,7 @@
 /// Protocole de transport sans connexion
 
 use alloc::vec::Vec;
-use super::arp::Ipv4Address;
+use super::ip::{self, IpAddress};
 
 /// Port UDP
 pub type Port = u16;
@@ -62,17 +62,10 @@ impl UdpDatagram {
         })
     }
     
-    /// Calcule le checksum UDP (avec pseudo-header IPv4)
-    pub fn calculate_checksum(&self, src_ip: Ipv4Address, dst_ip: Ipv4Address) -> u16 {
-        let mut sum: u32 = 0;
-        
-        // Pseudo-header
-        for i in 0..4 {
-            sum += src_ip.0[i] as u32;
-            sum += dst_ip.0[i] as u32;
-        }
-        sum += 17; // UDP protocol number
-        sum += self.length as u32;
+    /// Calcule le checksum UDP (avec pseudo-header IPv4 ou IPv6)
+    pub fn calculate_checksum(&self, src_ip: impl Into<IpAddress>, dst_ip: impl Into<IpAddress>) -> u16 {
+        // Pseudo-header (mots de 16 bits)
+        let mut sum = ip::pseudo_header_sum(src_ip.into(), dst_ip.into(), 17, self.length as u32);
         
         // UDP header
         sum += self.src_port as u32;
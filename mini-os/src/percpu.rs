@@ -0,0 +1,290 @@
+/// Variables par CPU
+///
+/// `percpu!` déclare une variable dont chaque CPU possède son propre
+/// exemplaire : un emplacement par index logique de CPU (0 pour le BSP,
+/// puis dans l'ordre d'enregistrement). L'index est lu dans la zone du CPU
+/// pointée par IA32_GS_BASE, installée par `install_area` (GDT du CPU) et
+/// numérotée par `setup_cpu` à la mise en route du CPU ; tant qu'aucune
+/// zone n'est installée (démarrage), l'index vaut 0.
+///
+/// La zone n'est dans IA32_GS_BASE qu'en mode noyau. En mode utilisateur
+/// elle attend dans IA32_KERNEL_GS_BASE et GS est celui du programme :
+/// chaque entrée depuis le ring 3 (SYSCALL, interruption, exception) fait
+/// un `swapgs` avant de toucher une variable par CPU, chaque retour vers
+/// le ring 3 en refait un (`KernelGs` pour les gestionnaires Rust). Le
+/// programme ne peut charger GS qu'à partir d'un sélecteur (base nulle) :
+/// il ne voit ni ne déplace jamais la zone.
+///
+/// Accès :
+/// - `get()` pour les types `Sync` (atomiques, verrous) : aucune précaution ;
+/// - `with(|v| ...)` pour les autres (`Cell`, `RefCell`) : interruptions
+///   masquées pendant l'accès, un gestionnaire ne peut pas s'intercaler ;
+/// - `get_cpu(n)` / `iter()` pour lire les exemplaires des autres CPU
+///   (statistiques), réservés aux types `Sync`.
+///
+/// Le module tient aussi les compteurs statistiques de chaque CPU et la
+/// profondeur d'imbrication des interruptions.
+
+use alloc::boxed::Box;
+use core::arch::asm;
+use core::sync::atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use x86_64::instructions::interrupts::without_interrupts;
+use x86_64::registers::model_specific::{GsBase, KernelGsBase};
+use x86_64::structures::idt::InterruptStackFrame;
+use x86_64::VirtAddr;
+
+/// CPU servis ; au-delà, `setup_cpu` refuse le CPU
+pub const NR_CPUS: usize = 16;
+
+/// Zone du CPU pointée par GS (offsets utilisés par l'entrée SYSCALL)
+#[repr(C)]
+pub struct CpuArea {
+    /// gs:[0] — adresse de la zone elle-même
+    this: u64,
+    /// gs:[8] — index logique du CPU
+    pub index: u32,
+    /// gs:[12] — identifiant LAPIC
+    pub lapic_id: u32,
+    /// gs:[16] — zone SYSCALL du CPU (`gdt::CpuSyscallArea`)
+    syscall: u64,
+    /// gs:[24] — RSP utilisateur, le temps de basculer de pile
+    user_rsp: u64,
+}
+
+/// CPU enregistrés
+static CPU_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// Alloue la zone GS du CPU courant, liée à sa zone SYSCALL ; GS
+/// utilisateur nul. Appelé par `gdt::init_cpu`.
+pub fn install_area(syscall: u64) {
+    let area = Box::leak(Box::new(CpuArea { this: 0, index: 0, lapic_id: 0, syscall, user_rsp: 0 }));
+    area.this = area as *mut CpuArea as u64;
+    GsBase::write(VirtAddr::new(area.this));
+    KernelGsBase::write(VirtAddr::new(0));
+}
+
+/// Numérote la zone du CPU courant ; retourne son index logique. Le
+/// premier CPU enregistré (le BSP) reçoit l'index 0.
+pub fn setup_cpu(lapic_id: u32) -> Result<u32, &'static str> {
+    let base = GsBase::read().as_u64();
+    let area = unsafe { (base as *mut CpuArea).as_mut() }.ok_or("percpu: zone GS absente")?;
+    let index = CPU_COUNT.fetch_add(1, Ordering::SeqCst);
+    if index >= NR_CPUS {
+        CPU_COUNT.fetch_sub(1, Ordering::SeqCst);
+        return Err("percpu: trop de CPU");
+    }
+    area.index = index as u32;
+    area.lapic_id = lapic_id;
+    Ok(index as u32)
+}
+
+/// Zone du CPU courant (None avant `install_area`)
+fn area() -> Option<&'static CpuArea> {
+    let base = GsBase::read().as_u64();
+    unsafe { (base as *const CpuArea).as_ref() }
+}
+
+/// Adresse de la zone SYSCALL du CPU courant (0 avant `install_area`)
+pub fn syscall_area() -> u64 {
+    area().map_or(0, |area| area.syscall)
+}
+
+/// GS du noyau pour la durée d'un gestionnaire d'interruption ou
+/// d'exception : `swapgs` à l'entrée si elle vient du ring 3, et au drop
+/// pour y retourner. Un gestionnaire qui ne retourne pas au programme
+/// (abandon vers le lanceur) garde le GS du noyau.
+pub struct KernelGs {
+    swapped: bool,
+}
+
+impl KernelGs {
+    pub fn enter(stack_frame: &InterruptStackFrame) -> Self {
+        let swapped = stack_frame.code_segment & 3 == 3;
+        if swapped {
+            unsafe { asm!("swapgs", options(nostack, preserves_flags)) };
+        }
+        Self { swapped }
+    }
+}
+
+impl Drop for KernelGs {
+    fn drop(&mut self) {
+        if self.swapped {
+            unsafe { asm!("swapgs", options(nostack, preserves_flags)) };
+        }
+    }
+}
+
+/// Index logique du CPU courant
+pub fn cpu_index() -> usize {
+    area().map_or(0, |area| area.index as usize)
+}
+
+/// Identifiant LAPIC du CPU courant
+pub fn lapic_id() -> u32 {
+    area().map_or(0, |area| area.lapic_id)
+}
+
+/// Nombre de CPU enregistrés (au moins 1)
+pub fn cpu_count() -> usize {
+    CPU_COUNT.load(Ordering::Relaxed).clamp(1, NR_CPUS)
+}
+
+/// Variable par CPU ; déclarée avec `percpu!`
+pub struct PerCpu<T> {
+    slots: [T; NR_CPUS],
+}
+
+// Chaque CPU n'accède qu'à son exemplaire, sauf via get_cpu/iter (T: Sync)
+unsafe impl<T: Send> Sync for PerCpu<T> {}
+
+impl<T> PerCpu<T> {
+    pub const fn new(slots: [T; NR_CPUS]) -> Self {
+        Self { slots }
+    }
+
+    /// Exemplaires construits à l'exécution (valeur non constante)
+    pub fn from_fn(f: impl FnMut(usize) -> T) -> Self {
+        Self { slots: core::array::from_fn(f) }
+    }
+
+    /// Accès à l'exemplaire du CPU courant, interruptions masquées
+    pub fn with<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        without_interrupts(|| f(&self.slots[cpu_index()]))
+    }
+}
+
+impl<T: Sync> PerCpu<T> {
+    /// Exemplaire du CPU courant
+    pub fn get(&self) -> &T {
+        &self.slots[cpu_index()]
+    }
+
+    /// Exemplaire du CPU `cpu`
+    pub fn get_cpu(&self, cpu: usize) -> &T {
+        &self.slots[cpu % NR_CPUS]
+    }
+
+    /// Exemplaires des CPU enregistrés
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.slots[..cpu_count()].iter()
+    }
+}
+
+/// Déclare des variables par CPU :
+/// `percpu! { pub static NOM: Type = valeur_constante; }`
+#[macro_export]
+macro_rules! percpu {
+    ($($(#[$attr:meta])* $vis:vis static $name:ident: $ty:ty = $init:expr;)*) => {
+        $(
+            $(#[$attr])*
+            $vis static $name: $crate::percpu::PerCpu<$ty> = {
+                const INIT: $ty = $init;
+                $crate::percpu::PerCpu::new([INIT; $crate::percpu::NR_CPUS])
+            };
+        )*
+    };
+}
+
+/// Compteurs statistiques d'un CPU
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stat {
+    /// Interruptions matérielles traitées
+    Irq = 0,
+    /// Changements de thread courant
+    ContextSwitch = 1,
+    /// Ticks d'horloge reçus
+    Tick = 2,
+    /// Appels système
+    Syscall = 3,
+}
+
+const NR_STATS: usize = 4;
+
+/// Compteurs d'un CPU, indexés par `Stat`
+pub struct CpuStats {
+    counters: [AtomicU64; NR_STATS],
+}
+
+impl CpuStats {
+    pub const fn new() -> Self {
+        const ZERO: AtomicU64 = AtomicU64::new(0);
+        Self { counters: [ZERO; NR_STATS] }
+    }
+
+    pub fn get(&self, stat: Stat) -> u64 {
+        self.counters[stat as usize].load(Ordering::Relaxed)
+    }
+}
+
+percpu! {
+    /// Statistiques de chaque CPU
+    pub static STATS: CpuStats = CpuStats::new();
+    /// Profondeur d'imbrication des interruptions
+    static IRQ_DEPTH: AtomicU32 = AtomicU32::new(0);
+}
+
+/// Incrémente un compteur du CPU courant
+pub fn count(stat: Stat) {
+    STATS.get().counters[stat as usize].fetch_add(1, Ordering::Relaxed);
+}
+
+/// Somme d'un compteur sur tous les CPU
+pub fn total(stat: Stat) -> u64 {
+    STATS.iter().map(|stats| stats.get(stat)).sum()
+}
+
+/// Entrée dans un gestionnaire d'interruption
+pub fn irq_enter() {
+    IRQ_DEPTH.get().fetch_add(1, Ordering::Relaxed);
+    count(Stat::Irq);
+}
+
+/// Sortie d'un gestionnaire d'interruption
+pub fn irq_exit() {
+    IRQ_DEPTH.get().fetch_sub(1, Ordering::Relaxed);
+}
+
+/// Interruptions imbriquées en cours sur le CPU courant
+pub fn irq_depth() -> u32 {
+    IRQ_DEPTH.get().load(Ordering::Relaxed)
+}
+
+/// Vrai dans un gestionnaire d'interruption
+pub fn in_interrupt() -> bool {
+    irq_depth() > 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::Cell;
+
+    percpu! {
+        static VALUE: Cell<u32> = Cell::new(7);
+    }
+
+    #[test_case]
+    fn test_percpu_access() {
+        assert_eq!(VALUE.with(|v| v.get()), 7);
+        VALUE.with(|v| v.set(v.get() + 1));
+        assert_eq!(VALUE.with(|v| v.get()), 8);
+
+        let before = total(Stat::Syscall);
+        count(Stat::Syscall);
+        assert_eq!(STATS.get().get(Stat::Syscall), STATS.get_cpu(cpu_index()).get(Stat::Syscall));
+        assert_eq!(total(Stat::Syscall), before + 1);
+
+        let depth = irq_depth();
+        irq_enter();
+        assert!(in_interrupt());
+        irq_exit();
+        assert_eq!(irq_depth(), depth);
+
+        // En mode noyau, la zone est dans GS ; GS utilisateur nul en attente
+        let area = area().unwrap();
+        assert_eq!(area.this, GsBase::read().as_u64());
+        assert_eq!(area.syscall, crate::gdt::syscall_area().map_or(0, |a| a as *mut _ as u64));
+        assert_eq!(KernelGsBase::read().as_u64(), 0);
+    }
+}
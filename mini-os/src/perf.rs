@@ -0,0 +1,279 @@
+/// Compteurs de performance du noyau (perf-lite)
+///
+/// Instrumentation légère, toujours active, tenue dans des compteurs par
+/// CPU (atomiques relâchés, jamais de verrou) :
+/// - appels système : nombre, temps cumulé et histogramme des latences
+///   par numéro, mesurés autour de `SyscallHandler::handle` ;
+/// - interruptions : nombre par vecteur (lignes routées par `irq`) ;
+/// - changements de contexte et ticks : compteurs de `percpu::Stat`.
+///
+/// Lecture par /proc/perf ou la commande `perf stat`. Un instantané
+/// (`Snapshot`) somme les CPU ; la différence de deux instantanés donne
+/// l'activité d'un intervalle.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use core::sync::atomic::{AtomicU64, Ordering};
+use crate::percpu::{self, Stat};
+use crate::syscall::{syscall_name, NR_SYSCALLS};
+
+/// Classes de l'histogramme des latences : la classe 0 couvre < 512 ns,
+/// la classe n ≥ 1 couvre [2^(n+8), 2^(n+9)) ns, la dernière est ouverte
+pub const NR_BUCKETS: usize = 16;
+/// Vecteurs d'interruption
+pub const NR_VECTORS: usize = 256;
+
+/// Classe d'une latence en nanosecondes
+pub fn bucket(ns: u64) -> usize {
+    let bits = (64 - ns.leading_zeros()) as usize;
+    bits.saturating_sub(9).min(NR_BUCKETS - 1)
+}
+
+/// Borne basse d'une classe, en nanosecondes
+pub fn bucket_floor(bucket: usize) -> u64 {
+    match bucket {
+        0 => 0,
+        n => 1 << (n + 8),
+    }
+}
+
+/// Compteurs d'un CPU
+pub struct CpuPerf {
+    syscalls: [AtomicU64; NR_SYSCALLS],
+    syscall_ns: [AtomicU64; NR_SYSCALLS],
+    latency: [[AtomicU64; NR_BUCKETS]; NR_SYSCALLS],
+    irqs: [AtomicU64; NR_VECTORS],
+}
+
+impl CpuPerf {
+    pub const fn new() -> Self {
+        const ZERO: AtomicU64 = AtomicU64::new(0);
+        const ROW: [AtomicU64; NR_BUCKETS] = [ZERO; NR_BUCKETS];
+        Self {
+            syscalls: [ZERO; NR_SYSCALLS],
+            syscall_ns: [ZERO; NR_SYSCALLS],
+            latency: [ROW; NR_SYSCALLS],
+            irqs: [ZERO; NR_VECTORS],
+        }
+    }
+
+    fn reset(&self) {
+        let all = self.syscalls.iter().chain(&self.syscall_ns).chain(self.latency.iter().flatten()).chain(&self.irqs);
+        for counter in all {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+crate::percpu! {
+    /// Compteurs de performance de chaque CPU
+    static PERF: CpuPerf = CpuPerf::new();
+}
+
+/// Fin d'un appel système `num`, entré il y a `ns` nanosecondes
+pub fn record_syscall(num: u64, ns: u64) {
+    let num = num as usize;
+    if num >= NR_SYSCALLS {
+        return;
+    }
+    let perf = PERF.get();
+    perf.syscalls[num].fetch_add(1, Ordering::Relaxed);
+    perf.syscall_ns[num].fetch_add(ns, Ordering::Relaxed);
+    perf.latency[num][bucket(ns)].fetch_add(1, Ordering::Relaxed);
+}
+
+/// Interruption reçue sur `vector`
+pub fn record_irq(vector: u8) {
+    PERF.get().irqs[vector as usize].fetch_add(1, Ordering::Relaxed);
+}
+
+/// Remet les compteurs de tous les CPU à zéro
+pub fn reset() {
+    for perf in PERF.iter() {
+        perf.reset();
+    }
+}
+
+/// Statistiques d'un appel système, tous CPU confondus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyscallStat {
+    pub num: u64,
+    pub count: u64,
+    pub total_ns: u64,
+    pub histogram: [u64; NR_BUCKETS],
+}
+
+impl SyscallStat {
+    pub fn name(&self) -> &'static str {
+        syscall_name(self.num).unwrap_or("?")
+    }
+
+    pub fn mean_ns(&self) -> u64 {
+        self.total_ns.checked_div(self.count).unwrap_or(0)
+    }
+
+    /// Borne basse de la classe contenant le centile `percent`
+    pub fn percentile_ns(&self, percent: u64) -> u64 {
+        let target = (self.count * percent).div_ceil(100).max(1);
+        let mut seen = 0;
+        for (index, &count) in self.histogram.iter().enumerate() {
+            seen += count;
+            if seen >= target {
+                return bucket_floor(index);
+            }
+        }
+        0
+    }
+}
+
+/// Instantané des compteurs, sommés sur les CPU
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    pub syscalls: Vec<SyscallStat>,
+    /// (vecteur, nombre), vecteurs jamais levés exclus
+    pub irqs: Vec<(u8, u64)>,
+    pub context_switches: u64,
+    pub ticks: u64,
+}
+
+impl Snapshot {
+    pub fn take() -> Self {
+        let mut syscalls = Vec::new();
+        for num in 0..NR_SYSCALLS {
+            let mut stat = SyscallStat { num: num as u64, count: 0, total_ns: 0, histogram: [0; NR_BUCKETS] };
+            for perf in PERF.iter() {
+                stat.count += perf.syscalls[num].load(Ordering::Relaxed);
+                stat.total_ns += perf.syscall_ns[num].load(Ordering::Relaxed);
+                for (sum, counter) in stat.histogram.iter_mut().zip(&perf.latency[num]) {
+                    *sum += counter.load(Ordering::Relaxed);
+                }
+            }
+            if stat.count > 0 {
+                syscalls.push(stat);
+            }
+        }
+        let irqs = (0..NR_VECTORS)
+            .map(|vector| (vector as u8, PERF.iter().map(|perf| perf.irqs[vector].load(Ordering::Relaxed)).sum()))
+            .filter(|&(_, count)| count > 0)
+            .collect();
+        Self {
+            syscalls,
+            irqs,
+            context_switches: percpu::total(Stat::ContextSwitch),
+            ticks: percpu::total(Stat::Tick),
+        }
+    }
+
+    /// Activité entre `earlier` et cet instantané
+    pub fn since(&self, earlier: &Snapshot) -> Snapshot {
+        let syscalls = self
+            .syscalls
+            .iter()
+            .filter_map(|stat| {
+                let before = earlier.syscalls.iter().find(|s| s.num == stat.num);
+                let mut delta = *stat;
+                if let Some(before) = before {
+                    delta.count -= before.count.min(delta.count);
+                    delta.total_ns -= before.total_ns.min(delta.total_ns);
+                    for (count, old) in delta.histogram.iter_mut().zip(before.histogram) {
+                        *count -= old.min(*count);
+                    }
+                }
+                (delta.count > 0).then_some(delta)
+            })
+            .collect();
+        let irqs = self
+            .irqs
+            .iter()
+            .filter_map(|&(vector, count)| {
+                let before = earlier.irqs.iter().find(|&&(v, _)| v == vector).map_or(0, |&(_, c)| c);
+                (count > before).then_some((vector, count - before))
+            })
+            .collect();
+        Snapshot {
+            syscalls,
+            irqs,
+            context_switches: self.context_switches.saturating_sub(earlier.context_switches),
+            ticks: self.ticks.saturating_sub(earlier.ticks),
+        }
+    }
+
+    /// Rapport texte (/proc/perf, perf stat) ; `histograms` ajoute la
+    /// répartition des latences de chaque appel
+    pub fn render(&self, histograms: bool) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "context-switches {}", self.context_switches);
+        let _ = writeln!(out, "ticks            {}", self.ticks);
+        let _ = writeln!(out, "irqs             {}", self.irqs.iter().map(|&(_, c)| c).sum::<u64>());
+        for &(vector, count) in &self.irqs {
+            let _ = writeln!(out, "  vector {:>3}     {}", vector, count);
+        }
+
+        let _ = writeln!(out, "{:<14} {:>10} {:>12} {:>10} {:>10}", "syscall", "calls", "total_us", "mean_ns", "p99_ns");
+        let mut syscalls = self.syscalls.clone();
+        syscalls.sort_by(|a, b| b.total_ns.cmp(&a.total_ns));
+        for stat in &syscalls {
+            let _ = writeln!(
+                out,
+                "{:<14} {:>10} {:>12} {:>10} {:>10}",
+                stat.name(),
+                stat.count,
+                stat.total_ns / 1_000,
+                stat.mean_ns(),
+                stat.percentile_ns(99)
+            );
+            if histograms {
+                for (index, &count) in stat.histogram.iter().enumerate().filter(|&(_, &c)| c > 0) {
+                    let _ = writeln!(out, "    >= {:>9} ns {:>10}", bucket_floor(index), count);
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Contenu de /proc/perf
+pub fn report() -> String {
+    Snapshot::take().render(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_latency_buckets() {
+        assert_eq!(bucket(0), 0);
+        assert_eq!(bucket(511), 0);
+        assert_eq!(bucket(512), 1);
+        assert_eq!(bucket(1023), 1);
+        assert_eq!(bucket(1024), 2);
+        assert_eq!(bucket(u64::MAX), NR_BUCKETS - 1);
+        assert_eq!(bucket_floor(bucket(5_000)), 4096);
+
+        let mut stat = SyscallStat { num: 3, count: 100, total_ns: 100_000, histogram: [0; NR_BUCKETS] };
+        stat.histogram[0] = 90;
+        stat.histogram[4] = 10;
+        assert_eq!(stat.name(), "write");
+        assert_eq!(stat.mean_ns(), 1_000);
+        assert_eq!(stat.percentile_ns(50), 0);
+        assert_eq!(stat.percentile_ns(99), 4096);
+    }
+
+    #[test_case]
+    fn test_snapshot_delta() {
+        let before = Snapshot::take();
+        record_syscall(8, 700);
+        record_syscall(8, 300);
+        record_irq(40);
+        record_syscall(NR_SYSCALLS as u64, 1); // hors table : ignoré
+
+        let delta = Snapshot::take().since(&before);
+        let getpid = delta.syscalls.iter().find(|s| s.num == 8).expect("getpid");
+        assert_eq!((getpid.count, getpid.total_ns), (2, 1_000));
+        assert_eq!((getpid.histogram[0], getpid.histogram[1]), (1, 1));
+        assert!(delta.irqs.contains(&(40, 1)));
+        assert!(delta.render(true).contains("getpid"));
+    }
+}
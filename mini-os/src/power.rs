@@ -1,103 +1,170 @@
+/// Gestion de l'alimentation : arrêt (ACPI S5) et redémarrage
+///
+/// L'arrêt écrit SLP_TYP | SLP_EN dans PM1a_CNT (et PM1b_CNT s'il
+/// existe), avec les valeurs SLP_TYP du paquet `\_S5` de la DSDT ; à
+/// défaut, celles de QEMU (PIIX4/ICH9). Viennent ensuite les ports
+/// d'arrêt propres aux émulateurs. Le redémarrage essaie le registre de
+/// reset de la FADT, le contrôleur clavier, le registre RESET_CONTROL
+/// (0xCF9) puis une triple faute.
+///
+/// Les systèmes de fichiers ne sont pas synchronisés ici : c'est à
+/// l'appelant de le faire (`fs::vfs_sync`).
+
 use x86_64::instructions::port::Port;
-#[cfg(feature = "smp")]
-use crate::acpi::{self, fadt::Fadt};
+use crate::acpi::{self, fadt::Fadt, tables::GenericAddress};
+
+/// Bit SLP_EN de PM1x_CNT
+const SLP_EN: u16 = 1 << 13;
+/// Bit SCI_EN de PM1x_CNT (mode ACPI actif)
+const SCI_EN: u16 = 1 << 0;
+/// SLP_TYP de S5 sur les chipsets émulés par QEMU
+const QEMU_S5_SLP_TYP: u8 = 0;
 
-#[cfg(feature = "smp")]
-type FadtType = Fadt;
-#[cfg(not(feature = "smp"))]
-type FadtType = ();
+/// Registre RESET_CONTROL du chipset (PIIX/ICH)
+const RESET_CONTROL: u16 = 0xCF9;
 
 pub struct PowerManager {
-    #[cfg(feature = "smp")]
-    fadt: Option<FadtType>,
-    #[cfg(not(feature = "smp"))]
-    fadt: Option<FadtType>,
+    fadt: Option<Fadt>,
+    /// SLP_TYPa / SLP_TYPb de l'état S5
+    s5: Option<(u8, u8)>,
 }
 
 impl PowerManager {
     pub fn new() -> Self {
-        let mut pm = Self { fadt: None };
+        let mut pm = Self { fadt: None, s5: None };
         pm.init();
         pm
     }
 
     fn init(&mut self) {
-        #[cfg(feature = "smp")]
-        {
-            if let Some(rsdp) = acpi::find_rsdp() {
-                if let Some(fadt) = acpi::find_fadt(&rsdp) {
-                    self.fadt = Some(fadt);
-                    self.enable_acpi(&fadt);
-                }
+        if let Some(rsdp) = acpi::find_rsdp() {
+            if let Some(fadt) = acpi::find_fadt(&rsdp) {
+                self.s5 = acpi::dsdt::find_s5(&fadt);
+                self.enable_acpi(&fadt);
+                self.fadt = Some(fadt);
             }
         }
-        #[cfg(not(feature = "smp"))]
-        {
-            self.fadt = Some(());
-        }
     }
 
-    #[cfg(feature = "smp")]
     fn enable_acpi(&self, fadt: &Fadt) {
+        let (smi_cmd, acpi_enable, pm1a_cnt) = (fadt.smi_cmd, fadt.acpi_enable, fadt.pm1a_cnt_blk);
         // Init ACPI Mode if SMI_CMD is present and ACPI_ENABLE is set
-        if fadt.smi_cmd != 0 && fadt.acpi_enable != 0 {
-             let mut smi_port: Port<u8> = Port::new(fadt.smi_cmd as u16);
-             unsafe { smi_port.write(fadt.acpi_enable) };
-             // TODO: Wait for SCI_EN bit in PM1a_EVT_BLK to be set
+        if smi_cmd == 0 || acpi_enable == 0 || pm1a_cnt == 0 {
+            return;
         }
+        let mut control: Port<u16> = Port::new(pm1a_cnt as u16);
+        if unsafe { control.read() } & SCI_EN != 0 {
+            return;
+        }
+        let mut smi_port: Port<u8> = Port::new(smi_cmd as u16);
+        unsafe { smi_port.write(acpi_enable) };
+        // Le firmware lève SCI_EN une fois le passage en mode ACPI terminé
+        for _ in 0..1_000_000 {
+            if unsafe { control.read() } & SCI_EN != 0 {
+                return;
+            }
+            core::hint::spin_loop();
+        }
+        crate::serial_println!("ACPI: SCI_EN not set after ACPI_ENABLE");
+    }
+
+    /// Valeurs SLP_TYP de S5 : DSDT, ou celles de QEMU
+    pub fn s5_sleep_types(&self) -> (u8, u8) {
+        self.s5.unwrap_or((QEMU_S5_SLP_TYP, QEMU_S5_SLP_TYP))
     }
 
     pub fn shutdown(&self) {
         crate::serial_println!("Shutting down...");
-        
-        #[cfg(feature = "smp")]
-        {
-            // 1. Try ACPI Shutdown (QEMU S5)
-            // Note: Proper way is to parse _S5 package in DSDT.
-            // For QEMU, SLP_TYP is typically 5 (001b << 10 for SLP_TYPa).
-            // Plus SLP_EN (1 << 13).
-            
-            // Fallback or QEMU-specific hardcoded values for S5
-            if let Some(fadt) = &self.fadt {
-                 let pm1a_cnt_blk = fadt.pm1a_cnt_blk as u16;
-                 let mut port: Port<u16> = Port::new(pm1a_cnt_blk);
-                 
-                 // Try QEMU S5 sequence
-                 unsafe {
-                     port.write(0x2000 | (5 << 10)); // SLP_EN | SLP_TYP=5
-                 }
+
+        // 1. ACPI S5
+        if let Some(fadt) = &self.fadt {
+            let (pm1a_cnt, pm1b_cnt) = (fadt.pm1a_cnt_blk, fadt.pm1b_cnt_blk);
+            let (slp_typ_a, slp_typ_b) = self.s5_sleep_types();
+            unsafe {
+                if pm1a_cnt != 0 {
+                    let mut port: Port<u16> = Port::new(pm1a_cnt as u16);
+                    let value = port.read() & !(0x7 << 10);
+                    port.write(value | ((slp_typ_a as u16 & 0x7) << 10) | SLP_EN);
+                }
+                if pm1b_cnt != 0 {
+                    let mut port: Port<u16> = Port::new(pm1b_cnt as u16);
+                    let value = port.read() & !(0x7 << 10);
+                    port.write(value | ((slp_typ_b as u16 & 0x7) << 10) | SLP_EN);
+                }
             }
         }
-        
-        // 2. QEMU specific shutdown port (older QEMU)
-        let mut qemu_port: Port<u16> = Port::new(0x604);
-        unsafe { qemu_port.write(0x2000) };
+
+        // 2. Emulator specific shutdown ports (QEMU, Bochs/old QEMU, VirtualBox)
+        unsafe {
+            Port::<u16>::new(0x604).write(0x2000);
+            Port::<u16>::new(0xB004).write(0x2000);
+            Port::<u16>::new(0x4004).write(0x3400);
+        }
 
         // 3. Loop if failed
         crate::serial_println!("Shutdown failed. Halting.");
         loop { x86_64::instructions::hlt(); }
     }
 
+    /// Écrit la valeur de reset dans le registre décrit par la FADT
+    fn acpi_reset(&self) {
+        let (register, value) = match self.fadt.as_ref().and_then(|fadt| fadt.reset_register()) {
+            Some(reset) => reset,
+            None => return,
+        };
+        let (space, address) = (register.address_space, register.address);
+        match space {
+            GenericAddress::SYSTEM_IO => unsafe { Port::<u8>::new(address as u16).write(value) },
+            GenericAddress::SYSTEM_MEMORY => unsafe { core::ptr::write_volatile(address as *mut u8, value) },
+            // Espace PCI : bus 0, périphérique et fonction dans l'adresse
+            GenericAddress::PCI_CONFIG => {
+                let device = ((address >> 32) & 0x1F) as u8;
+                let function = ((address >> 16) & 0x7) as u8;
+                let offset = (address & 0xFF) as u8;
+                let (aligned, shift) = (offset & !3, (offset & 3) * 8);
+                let old = crate::drivers::pci::read_config(0, device, function, aligned);
+                let new = (old & !(0xFF << shift)) | ((value as u32) << shift);
+                crate::drivers::pci::write_config(0, device, function, aligned, new);
+            }
+            _ => {}
+        }
+    }
+
     pub fn reboot(&self) {
         crate::serial_println!("Rebooting...");
-        
-        // 1. Try keyboard controller pulse
-        let mut keyboard_cmd_port: Port<u8> = Port::new(0x64);
+
+        // 1. FADT Reset Register (ACPI 2.0+)
+        self.acpi_reset();
+
+        // 2. Keyboard controller pulse, once its input buffer is empty
+        let mut keyboard_status_port: Port<u8> = Port::new(0x64);
         unsafe {
+            for _ in 0..100_000 {
+                if keyboard_status_port.read() & 0x02 == 0 {
+                    break;
+                }
+            }
             // Pulse bit 0 (Reset CPU)
-            keyboard_cmd_port.write(0xFE);
+            keyboard_status_port.write(0xFE);
         }
-        
-        // 2. FADT Reset Register (if available, ACPI v2.0+)
-        // if let Some(fadt) = &self.fadt { ... }
 
-        // 3. Triple Fault
+        // 3. RESET_CONTROL : reset matériel complet (SYS_RST puis RST_CPU)
+        let mut reset_control: Port<u8> = Port::new(RESET_CONTROL);
+        unsafe {
+            reset_control.write(0x02);
+            for _ in 0..10_000 {
+                core::hint::spin_loop();
+            }
+            reset_control.write(0x06);
+        }
+
+        // 4. Triple Fault
         unsafe {
             // Load invalid IDT
              core::arch::asm!("lidt [{}]", in(reg) 0);
              core::arch::asm!("int3");
         }
-        
+
         loop { x86_64::instructions::hlt(); }
     }
 }
@@ -111,11 +178,19 @@ lazy_static! {
 }
 
 pub fn shutdown() -> ! {
+    x86_64::instructions::interrupts::disable();
     POWER_MANAGER.lock().shutdown();
     loop { x86_64::instructions::hlt(); }
 }
 
+/// Met le CPU en veille jusqu'à la prochaine interruption.
+/// `sti; hlt` est atomique : aucune interruption ne peut être perdue entre les deux.
+pub fn cpu_idle() {
+    x86_64::instructions::interrupts::enable_and_hlt();
+}
+
 pub fn reboot() -> ! {
+    x86_64::instructions::interrupts::disable();
     POWER_MANAGER.lock().reboot();
     loop { x86_64::instructions::hlt(); }
 }
@@ -0,0 +1,86 @@
+/// Répartition aléatoire de l'espace d'adressage (ASLR)
+///
+/// À la création d'un processus et à chaque `exec`, trois bases sont
+/// tirées au hasard, en pages entières :
+/// - le début du tas, dans la fenêtre `heap_base_for(pid)` ;
+/// - le sommet de la pile, sous le haut de sa fenêtre ;
+/// - la base mmap : écart laissé avant la première projection sans
+///   adresse imposée (`MmapManager::randomize_base`).
+///
+/// Les segments ELF restent à leur adresse de lien (exécutables ET_EXEC).
+/// Les décalages viennent du générateur du noyau (`random`).
+/// `norandmaps` sur la ligne de commande désactive le tirage : toutes les
+/// bases retrouvent leur valeur fixe, pratique pour déboguer.
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+const PAGE_SIZE: u64 = 4096;
+
+/// Amplitude du décalage du tas (32 Mio, prise sur sa fenêtre de 1 Gio)
+pub const HEAP_RANDOM_RANGE: u64 = 32 * 1024 * 1024;
+/// Amplitude du décalage de la pile (la moitié de sa fenêtre de 1 Mio)
+pub const STACK_RANDOM_RANGE: u64 = super::USER_STACK_WINDOW / 2;
+/// Amplitude de l'écart avant la première projection mmap (256 Mio)
+pub const MMAP_RANDOM_RANGE: u64 = 256 * 1024 * 1024;
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Active ou désactive le tirage des bases (`norandmaps`)
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::SeqCst);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Décalage aléatoire en pages entières dans `[0, range)` ; 0 si l'ASLR
+/// est désactivé
+pub fn random_offset(range: u64) -> u64 {
+    let pages = range / PAGE_SIZE;
+    if !enabled() || pages == 0 {
+        return 0;
+    }
+    (crate::random::get_u64() % pages) * PAGE_SIZE
+}
+
+/// Début du tas d'un processus
+pub fn heap_start(pid: u64) -> u64 {
+    super::heap_base_for(pid) + random_offset(HEAP_RANDOM_RANGE)
+}
+
+/// Sommet de pile tiré sous `window_top`
+pub fn stack_top(window_top: u64) -> u64 {
+    window_top - random_offset(STACK_RANDOM_RANGE)
+}
+
+/// Écart avant la première projection mmap
+pub fn mmap_gap() -> u64 {
+    random_offset(MMAP_RANDOM_RANGE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_random_offsets() {
+        let previous = enabled();
+        set_enabled(true);
+        for _ in 0..64 {
+            let offset = random_offset(HEAP_RANDOM_RANGE);
+            assert!(offset < HEAP_RANDOM_RANGE && offset % PAGE_SIZE == 0);
+            let top = stack_top(0x6000_0020_0000);
+            assert!(top <= 0x6000_0020_0000 && top > 0x6000_0020_0000 - STACK_RANDOM_RANGE);
+        }
+        // 64 tirages identiques : générateur cassé
+        let first = mmap_gap();
+        assert!((0..64).any(|_| mmap_gap() != first));
+
+        set_enabled(false);
+        assert_eq!(heap_start(3), super::super::heap_base_for(3));
+        assert_eq!(stack_top(0x6000_0020_0000), 0x6000_0020_0000);
+        assert_eq!(mmap_gap(), 0);
+        set_enabled(previous);
+    }
+}
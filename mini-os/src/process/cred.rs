@@ -0,0 +1,222 @@
+/// Identités d'un processus (credentials)
+///
+/// UID/GID réels, effectifs et sauvegardés, plus les groupes
+/// supplémentaires. Les contrôles d'accès (fichiers, mémoire partagée)
+/// utilisent l'identité effective ; root est l'UID effectif 0. Les
+/// identités sont héritées au fork et conservées à l'exec, sauf pour
+/// un exécutable setuid/setgid.
+///
+/// Les opérations privilégiées (chown, kill d'un autre utilisateur,
+/// priorité temps réel, ports réservés, administration) demandent une
+/// capacité. Root les a toutes ; un processus les perd en quittant root
+/// (setuid vers trois UID non nuls) et les retrouve en exécutant un
+/// binaire setuid root, dans la limite de `cap_bound`. `drop_caps` retire
+/// des capacités de cette limite : l'abandon est définitif, y compris
+/// pour les enfants.
+
+use alloc::vec::Vec;
+
+pub type Uid = u32;
+pub type Gid = u32;
+
+pub const ROOT_UID: Uid = 0;
+pub const ROOT_GID: Gid = 0;
+
+/// Ensemble de capacités (un bit par capacité)
+pub type Capabilities = u32;
+
+/// Signaler un processus d'un autre utilisateur
+pub const CAP_KILL: Capabilities = 1 << 0;
+/// Changer le propriétaire ou le groupe de n'importe quel fichier
+pub const CAP_CHOWN: Capabilities = 1 << 1;
+/// Priorité temps réel, priorité d'un autre utilisateur
+pub const CAP_SYS_NICE: Capabilities = 1 << 2;
+/// Lier un socket à un port réservé (< 1024)
+pub const CAP_NET_BIND: Capabilities = 1 << 3;
+/// Administration : reboot, attributs étendus `trusted.*` et système
+pub const CAP_SYS_ADMIN: Capabilities = 1 << 4;
+/// Passer outre les bits rwx des fichiers
+pub const CAP_DAC_OVERRIDE: Capabilities = 1 << 5;
+/// Agir comme propriétaire de n'importe quel fichier (chmod)
+pub const CAP_FOWNER: Capabilities = 1 << 6;
+/// Passer outre les droits des objets IPC (mémoire partagée)
+pub const CAP_IPC_OWNER: Capabilities = 1 << 7;
+/// Toutes les capacités connues
+pub const ALL_CAPS: Capabilities = CAP_KILL | CAP_CHOWN | CAP_SYS_NICE | CAP_NET_BIND | CAP_SYS_ADMIN
+    | CAP_DAC_OVERRIDE | CAP_FOWNER | CAP_IPC_OWNER;
+
+/// Identités d'un processus
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Credentials {
+    pub ruid: Uid,
+    pub euid: Uid,
+    /// UID sauvegardé (setuid peut y revenir)
+    pub suid: Uid,
+    pub rgid: Gid,
+    pub egid: Gid,
+    pub sgid: Gid,
+    /// Groupes supplémentaires
+    pub groups: Vec<Gid>,
+    /// Capacités effectives
+    pub caps: Capabilities,
+    /// Capacités encore accessibles (jamais élargies)
+    pub cap_bound: Capabilities,
+}
+
+impl Credentials {
+    /// Identité d'un utilisateur, sans groupe supplémentaire
+    pub fn new(uid: Uid, gid: Gid) -> Self {
+        Self {
+            ruid: uid,
+            euid: uid,
+            suid: uid,
+            rgid: gid,
+            egid: gid,
+            sgid: gid,
+            groups: Vec::new(),
+            caps: if uid == ROOT_UID { ALL_CAPS } else { 0 },
+            cap_bound: ALL_CAPS,
+        }
+    }
+
+    /// Identité du noyau et des processus qu'il lance
+    pub fn root() -> Self {
+        Self::new(ROOT_UID, ROOT_GID)
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.euid == ROOT_UID
+    }
+
+    /// `gid` est-il le groupe effectif ou un groupe supplémentaire ?
+    pub fn in_group(&self, gid: Gid) -> bool {
+        self.egid == gid || self.groups.contains(&gid)
+    }
+
+    /// La capacité `cap` est-elle effective ?
+    pub fn capable(&self, cap: Capabilities) -> bool {
+        self.caps & cap == cap
+    }
+
+    /// Retire définitivement les capacités `caps`
+    pub fn drop_caps(&mut self, caps: Capabilities) {
+        self.caps &= !caps;
+        self.cap_bound &= !caps;
+    }
+
+    /// Peut-on signaler un processus d'identités `target` ? Il faut
+    /// CAP_KILL, ou que l'UID réel ou effectif de l'émetteur soit l'UID
+    /// réel ou sauvegardé de la cible
+    pub fn can_signal(&self, target: &Credentials) -> bool {
+        self.capable(CAP_KILL)
+            || [self.ruid, self.euid].iter().any(|&uid| uid == target.ruid || uid == target.suid)
+    }
+
+    /// setuid() POSIX : root change les trois UID ; sinon seul l'UID
+    /// effectif change, vers l'UID réel ou sauvegardé
+    pub fn setuid(&mut self, uid: Uid) -> Result<(), &'static str> {
+        if self.is_root() {
+            self.ruid = uid;
+            self.euid = uid;
+            self.suid = uid;
+            if uid != ROOT_UID {
+                self.caps = 0;
+            }
+            Ok(())
+        } else if uid == self.ruid || uid == self.suid {
+            self.euid = uid;
+            Ok(())
+        } else {
+            Err("Opération non permise")
+        }
+    }
+
+    /// setgid() POSIX, mêmes règles que setuid
+    pub fn setgid(&mut self, gid: Gid) -> Result<(), &'static str> {
+        if self.is_root() {
+            self.rgid = gid;
+            self.egid = gid;
+            self.sgid = gid;
+            Ok(())
+        } else if gid == self.rgid || gid == self.sgid {
+            self.egid = gid;
+            Ok(())
+        } else {
+            Err("Opération non permise")
+        }
+    }
+
+    /// Exécution d'un binaire setuid/setgid : l'identité effective
+    /// (et sauvegardée) devient celle du propriétaire du fichier ; les
+    /// capacités suivent l'UID effectif obtenu
+    pub fn exec_as(&mut self, setuid: Option<Uid>, setgid: Option<Gid>) {
+        if let Some(uid) = setuid {
+            self.euid = uid;
+        }
+        if let Some(gid) = setgid {
+            self.egid = gid;
+        }
+        self.suid = self.euid;
+        self.sgid = self.egid;
+        self.caps = if self.is_root() { self.cap_bound } else { 0 };
+    }
+}
+
+impl Default for Credentials {
+    fn default() -> Self {
+        Self::root()
+    }
+}
+
+/// Identités du processus courant ; le noyau (hors processus) est root
+pub fn current() -> Credentials {
+    super::current_process()
+        .map(|p| p.lock().cred.clone())
+        .unwrap_or_else(Credentials::root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_setuid() {
+        let mut cred = Credentials::root();
+        assert!(cred.setuid(1000).is_ok());
+        assert_eq!((cred.ruid, cred.euid, cred.suid), (1000, 1000, 1000));
+        // Plus root : impossible de revenir en arrière
+        assert!(cred.setuid(0).is_err());
+
+        // Binaire setuid root lancé par 1000 : retour possible vers l'UID réel
+        cred.exec_as(Some(0), None);
+        assert!(cred.is_root());
+        assert!(cred.setuid(1000).is_ok());
+        assert_eq!((cred.ruid, cred.euid, cred.suid), (1000, 1000, 1000));
+
+        let mut user = Credentials::new(1000, 100);
+        user.groups.push(27);
+        assert!(user.in_group(27) && user.in_group(100) && !user.in_group(0));
+        assert!(user.setgid(27).is_err());
+    }
+
+    #[test_case]
+    fn test_capabilities() {
+        let mut cred = Credentials::root();
+        assert!(cred.capable(ALL_CAPS));
+        cred.drop_caps(CAP_SYS_ADMIN);
+        assert!(cred.capable(CAP_KILL) && !cred.capable(CAP_SYS_ADMIN));
+        assert!(!cred.capable(CAP_KILL | CAP_SYS_ADMIN));
+
+        // Plus root : plus de capacités, rendues par un binaire setuid root
+        // sauf celle abandonnée
+        cred.setuid(1000).unwrap();
+        assert_eq!(cred.caps, 0);
+        cred.exec_as(Some(0), None);
+        assert_eq!(cred.caps, ALL_CAPS & !CAP_SYS_ADMIN);
+        let user = Credentials::new(1000, 100);
+        assert_eq!(user.caps, 0);
+        assert!(user.can_signal(&Credentials::new(1000, 1000)));
+        assert!(!user.can_signal(&Credentials::new(1001, 100)));
+        assert!(Credentials::root().can_signal(&user));
+    }
+}
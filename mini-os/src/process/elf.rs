@@ -1,5 +1,7 @@
 use alloc::vec::Vec;
 use core::mem::size_of;
+use x86_64::structures::paging::{Page, PageTableFlags};
+use x86_64::VirtAddr;
 
 // Type de fichier
 pub const ET_NONE: u16 = 0;
@@ -19,6 +21,7 @@ pub const PT_INTERP: u32 = 3;
 pub const PT_NOTE: u32 = 4;
 pub const PT_SHLIB: u32 = 5;
 pub const PT_PHDR: u32 = 6;
+pub const PT_TLS: u32 = 7;
 
 // Flags de segment
 pub const PF_X: u32 = 1;
@@ -82,6 +85,15 @@ pub struct Elf64ProgramHeader {
     pub p_align: u64,
 }
 
+/// Modèle TLS d'un exécutable (segment PT_TLS) : l'image initiale
+/// (.tdata) puis des zéros (.tbss) jusqu'à `mem_size`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TlsTemplate {
+    pub image: Vec<u8>,
+    pub mem_size: usize,
+    pub align: usize,
+}
+
 pub struct ElfFile<'a> {
     data: &'a [u8],
     pub header: Elf64Header,
@@ -117,6 +129,94 @@ impl<'a> ElfFile<'a> {
             current: 0,
         }
     }
+
+    /// Modèle TLS du segment PT_TLS ; vide si l'exécutable n'en a pas
+    pub fn tls_template(&self) -> Result<TlsTemplate, &'static str> {
+        let ph = match self.program_headers().find(|ph| ph.p_type == PT_TLS) {
+            Some(ph) => ph,
+            None => return Ok(TlsTemplate::default()),
+        };
+        // Copie des champs (structure packed)
+        let (offset, file_size, mem_size, align) = (ph.p_offset, ph.p_filesz, ph.p_memsz, ph.p_align);
+        if file_size > mem_size {
+            return Err("TLS segment file size exceeds memory size");
+        }
+        if mem_size > super::tls::MAX_TLS_SIZE {
+            return Err("TLS segment too large");
+        }
+        if align > 1 && (!align.is_power_of_two() || align > 4096) {
+            return Err("Invalid TLS alignment");
+        }
+        let end = offset.checked_add(file_size).filter(|&end| end <= self.data.len() as u64).ok_or("TLS segment out of file")?;
+        Ok(TlsTemplate {
+            image: self.data[offset as usize..end as usize].to_vec(),
+            mem_size: mem_size as usize,
+            align: align.max(1) as usize,
+        })
+    }
+
+    /// Charge les segments PT_LOAD dans l'espace d'adressage courant
+    ///
+    /// Chaque page est remplie (données du fichier puis zéros pour le .bss)
+    /// avant d'être mappée avec les droits finaux du segment.
+    pub fn load_segments(&self) -> Result<(), &'static str> {
+        let mut vm = crate::memory::vm::VM_MANAGER.lock();
+        let space = match vm.as_mut() {
+            Some(vm) => vm.kernel_space_mut(),
+            // Mémoire virtuelle pas encore initialisée : rien à charger
+            None => return Ok(()),
+        };
+
+        for ph in self.program_headers().filter(|ph| ph.p_type == PT_LOAD) {
+            let flags = segment_flags(ph.p_flags)?;
+            if ph.p_filesz > ph.p_memsz {
+                return Err("Segment file size exceeds memory size");
+            }
+            let file_end = ph.p_offset.checked_add(ph.p_filesz).ok_or("Segment out of file")?;
+            if file_end > self.data.len() as u64 {
+                return Err("Segment out of file");
+            }
+
+            // Copie des champs (structure packed)
+            let (seg_start, file_offset, file_size) = (ph.p_vaddr, ph.p_offset, ph.p_filesz);
+            let seg_end = seg_start.checked_add(ph.p_memsz).ok_or("Segment out of address space")?;
+            let first = Page::containing_address(VirtAddr::try_new(seg_start).map_err(|_| "Invalid segment address")?);
+            let last = Page::containing_address(VirtAddr::try_new(seg_end.max(seg_start + 1) - 1).map_err(|_| "Invalid segment address")?);
+
+            for page in Page::range_inclusive(first, last) {
+                let page_start = page.start_address().as_u64();
+                space
+                    .map_page_filled(page, flags, |bytes| {
+                        bytes.fill(0);
+                        // Portion du fichier tombant dans cette page
+                        let copy_start = page_start.max(seg_start);
+                        let copy_end = (page_start + bytes.len() as u64).min(seg_start + file_size);
+                        if copy_start < copy_end {
+                            let src = (file_offset + copy_start - seg_start) as usize;
+                            let dst = (copy_start - page_start) as usize;
+                            let len = (copy_end - copy_start) as usize;
+                            bytes[dst..dst + len].copy_from_slice(&self.data[src..src + len]);
+                        }
+                    })
+                    .map_err(|_| "Failed to map ELF segment")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Drapeaux de page d'un segment PT_LOAD
+///
+/// Le code est en lecture/exécution, les données en lecture(/écriture)
+/// non exécutable. Un segment à la fois inscriptible et exécutable est
+/// refusé (W^X).
+pub fn segment_flags(p_flags: u32) -> Result<PageTableFlags, &'static str> {
+    let write = p_flags & PF_W != 0;
+    let exec = p_flags & PF_X != 0;
+    if write && exec {
+        return Err("Writable and executable segment (W^X)");
+    }
+    Ok(crate::memory::vm::protection_flags(write, exec, true))
 }
 
 pub struct ProgramHeaderIter<'a> {
@@ -170,6 +270,19 @@ mod tests {
         assert!(elf.header.validate().is_ok());
     }
 
+    #[test_case]
+    fn test_segment_flags_wx() {
+        let code = segment_flags(PF_R | PF_X).unwrap();
+        assert!(!code.contains(PageTableFlags::WRITABLE));
+        assert!(!code.contains(PageTableFlags::NO_EXECUTE));
+
+        let data = segment_flags(PF_R | PF_W).unwrap();
+        assert!(data.contains(PageTableFlags::WRITABLE | PageTableFlags::USER_ACCESSIBLE));
+        assert_eq!(data.contains(PageTableFlags::NO_EXECUTE), crate::memory::vm::nx_enabled());
+
+        assert!(segment_flags(PF_R | PF_W | PF_X).is_err());
+    }
+
     #[test_case]
     fn test_elf_magic_fail() {
         let data = [0u8; 64];
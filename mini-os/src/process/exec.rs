@@ -0,0 +1,147 @@
+/// ABI de démarrage des programmes utilisateur (System V x86_64)
+///
+/// Au point d'entrée, la pile contient de bas en haut :
+///
+/// ```text
+/// rsp ->  argc
+///         argv[0] .. argv[argc-1], NULL
+///         envp[0] .. envp[n-1], NULL
+///         auxv : (type, valeur)..., (AT_NULL, 0)
+///         ... chaînes argv puis envp, terminées par NUL ...
+/// top
+/// ```
+///
+/// `rsp` est aligné sur 16 octets. Le vecteur auxiliaire donne au moins la
+/// taille de page et le point d'entrée. `build_stack` calcule ce contenu
+/// pour une adresse de sommet donnée ; le chargeur (`ring3::map_user_stack`)
+/// le dépose dans les pages de pile du programme.
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use super::cred::Credentials;
+
+/// Types d'entrées du vecteur auxiliaire (valeurs de Linux)
+pub const AT_NULL: u64 = 0;
+pub const AT_PAGESZ: u64 = 6;
+pub const AT_ENTRY: u64 = 9;
+pub const AT_UID: u64 = 11;
+pub const AT_EUID: u64 = 12;
+pub const AT_GID: u64 = 13;
+pub const AT_EGID: u64 = 14;
+
+/// Taille maximale des arguments et de l'environnement (chaînes comprises)
+pub const ARG_MAX: usize = 32 * 1024;
+
+/// Arguments et environnement transmis à un nouveau programme
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExecArgs {
+    pub argv: Vec<String>,
+    /// Entrées « NOM=valeur »
+    pub envp: Vec<String>,
+}
+
+impl ExecArgs {
+    pub fn new(argv: Vec<String>, envp: Vec<String>) -> Self {
+        Self { argv, envp }
+    }
+
+    /// Environnement construit depuis des variables (celles du shell)
+    pub fn with_env(argv: Vec<String>, vars: &BTreeMap<String, String>) -> Self {
+        let envp = vars.iter().map(|(name, value)| format!("{}={}", name, value)).collect();
+        Self { argv, envp }
+    }
+
+    /// Valeur d'une variable d'environnement
+    pub fn env(&self, name: &str) -> Option<&str> {
+        env_get(&self.envp, name)
+    }
+}
+
+/// Valeur de `name` dans des entrées « NOM=valeur »
+pub fn env_get<'a>(envp: &'a [String], name: &str) -> Option<&'a str> {
+    envp.iter().find_map(|entry| entry.strip_prefix(name)?.strip_prefix('='))
+}
+
+/// Vecteur auxiliaire d'un programme d'entrée `entry`
+pub fn auxv(entry: u64, cred: &Credentials) -> [(u64, u64); 6] {
+    [
+        (AT_PAGESZ, 4096),
+        (AT_ENTRY, entry),
+        (AT_UID, cred.ruid as u64),
+        (AT_EUID, cred.euid as u64),
+        (AT_GID, cred.rgid as u64),
+        (AT_EGID, cred.egid as u64),
+    ]
+}
+
+/// Contenu initial de la pile : `bytes` occupe `[rsp, top)`
+#[derive(Debug, Clone)]
+pub struct UserStack {
+    pub rsp: u64,
+    pub bytes: Vec<u8>,
+}
+
+/// Construit la pile de démarrage sous `top` (aligné sur 16)
+pub fn build_stack(top: u64, args: &ExecArgs, auxv: &[(u64, u64)]) -> Result<UserStack, &'static str> {
+    let strings: usize = args.argv.iter().chain(&args.envp).map(|s| s.len() + 1).sum();
+    if strings > ARG_MAX {
+        return Err("Argument list too long");
+    }
+    let strings_start = (top - strings as u64) & !15;
+    // argc, argv + NULL, envp + NULL, paires auxv + AT_NULL
+    let words = 1 + args.argv.len() + 1 + args.envp.len() + 1 + 2 * (auxv.len() + 1);
+    let rsp = (strings_start - words as u64 * 8) & !15;
+
+    let mut bytes = alloc::vec![0u8; (top - rsp) as usize];
+    let mut table = Vec::with_capacity(words);
+    table.push(args.argv.len() as u64);
+
+    let mut cursor = strings_start;
+    for list in [&args.argv, &args.envp] {
+        for s in list.iter() {
+            let offset = (cursor - rsp) as usize;
+            bytes[offset..offset + s.len()].copy_from_slice(s.as_bytes());
+            table.push(cursor);
+            cursor += s.len() as u64 + 1;
+        }
+        table.push(0);
+    }
+    for &(kind, value) in auxv.iter().chain(&[(AT_NULL, 0)]) {
+        table.push(kind);
+        table.push(value);
+    }
+    for (i, word) in table.iter().enumerate() {
+        bytes[i * 8..i * 8 + 8].copy_from_slice(&word.to_le_bytes());
+    }
+    Ok(UserStack { rsp, bytes })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_build_stack_layout() {
+        let args = ExecArgs::new(alloc::vec![String::from("/bin/ls"), String::from("-l")], alloc::vec![String::from("PATH=/bin")]);
+        let top = 0x8000_0000;
+        let stack = build_stack(top, &args, &[(AT_ENTRY, 0x40_1000)]).unwrap();
+        assert_eq!(stack.rsp % 16, 0);
+        assert_eq!(stack.rsp + stack.bytes.len() as u64, top);
+
+        let word = |i: usize| u64::from_le_bytes(stack.bytes[i * 8..i * 8 + 8].try_into().unwrap());
+        let string = |addr: u64| {
+            let start = (addr - stack.rsp) as usize;
+            let end = start + stack.bytes[start..].iter().position(|&b| b == 0).unwrap();
+            core::str::from_utf8(&stack.bytes[start..end]).unwrap()
+        };
+        assert_eq!(word(0), 2);
+        assert_eq!((string(word(1)), string(word(2)), word(3)), ("/bin/ls", "-l", 0));
+        assert_eq!((string(word(4)), word(5)), ("PATH=/bin", 0));
+        assert_eq!((word(6), word(7), word(8), word(9)), (AT_ENTRY, 0x40_1000, AT_NULL, 0));
+
+        assert_eq!(args.env("PATH"), Some("/bin"));
+        assert_eq!(args.env("PAT"), None);
+    }
+}
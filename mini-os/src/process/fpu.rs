@@ -0,0 +1,216 @@
+/// État FPU/SSE des threads, commuté paresseusement
+///
+/// Le noyau est compilé sans MMX/SSE (soft-float) : seuls les programmes
+/// utilisateur touchent aux registres x87/XMM/YMM, qui survivent donc
+/// tels quels aux appels système et aux interruptions. Chaque thread
+/// utilisateur reçoit au premier passage sur un CPU une zone de
+/// sauvegarde (XSAVE si le CPU l'offre, FXSAVE sinon).
+///
+/// Commutation paresseuse : au changement de thread, si les registres du
+/// CPU appartiennent à un autre état, CR0.TS est armé. La première
+/// instruction flottante du nouveau thread lève #NM : le gestionnaire
+/// sauvegarde l'ancien propriétaire, charge l'état du thread courant et
+/// efface TS. Un thread qui ne calcule pas en flottant ne coûte rien.
+
+use alloc::alloc::{alloc_zeroed, dealloc, handle_alloc_error, Layout};
+use alloc::sync::Arc;
+use core::arch::asm;
+use core::cell::RefCell;
+use x86_64::instructions::interrupts::without_interrupts;
+use x86_64::registers::control::{Cr0, Cr0Flags, Cr4, Cr4Flags};
+use x86_64::structures::idt::InterruptStackFrame;
+
+/// Taille de la zone FXSAVE
+const FXSAVE_SIZE: usize = 512;
+/// Alignement exigé par XSAVE (FXSAVE : 16)
+const AREA_ALIGN: usize = 64;
+/// Mot de contrôle x87 après FNINIT
+const FCW_DEFAULT: u16 = 0x037F;
+/// MXCSR au reset : exceptions masquées, arrondi au plus proche
+const MXCSR_DEFAULT: u32 = 0x1F80;
+/// Offset de MXCSR dans la zone
+const MXCSR_OFFSET: usize = 24;
+
+/// XSAVE activé sur ce CPU (cf. `cpuid::init_cpu`)
+fn use_xsave() -> bool {
+    Cr4::read().contains(Cr4Flags::OSXSAVE)
+}
+
+/// Taille de la zone : CPUID 0xD donne celle des composants de XCR0
+fn area_size() -> usize {
+    if use_xsave() {
+        let size = unsafe { core::arch::x86_64::__cpuid_count(0xD, 0).ebx } as usize;
+        // Zone héritée + en-tête XSAVE au minimum
+        size.max(FXSAVE_SIZE + 64)
+    } else {
+        FXSAVE_SIZE
+    }
+}
+
+/// Zone de sauvegarde des registres flottants d'un thread
+pub struct FpuState {
+    area: *mut u8,
+    size: usize,
+}
+
+// La zone n'est lue ou écrite que par le CPU qui la possède, interruptions masquées
+unsafe impl Send for FpuState {}
+unsafe impl Sync for FpuState {}
+
+impl FpuState {
+    /// État initial (celui de FNINIT, MXCSR par défaut)
+    pub fn new() -> Self {
+        let size = area_size();
+        let layout = Layout::from_size_align(size, AREA_ALIGN).expect("fpu: zone invalide");
+        let area = unsafe { alloc_zeroed(layout) };
+        if area.is_null() {
+            handle_alloc_error(layout);
+        }
+        // En-tête XSAVE nul : les composants étendus partent de leur état initial
+        unsafe {
+            (area as *mut u16).write(FCW_DEFAULT);
+            (area.add(MXCSR_OFFSET) as *mut u32).write(MXCSR_DEFAULT);
+        }
+        Self { area, size }
+    }
+
+    fn layout(&self) -> Layout {
+        Layout::from_size_align(self.size, AREA_ALIGN).unwrap()
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        unsafe { core::slice::from_raw_parts(self.area, self.size) }
+    }
+
+    /// Registres du CPU -> zone
+    unsafe fn save(&self) {
+        if use_xsave() {
+            asm!("xsave64 [{}]", in(reg) self.area, in("eax") u32::MAX, in("edx") u32::MAX, options(nostack));
+        } else {
+            asm!("fxsave64 [{}]", in(reg) self.area, options(nostack));
+        }
+    }
+
+    /// Zone -> registres du CPU
+    unsafe fn restore(&self) {
+        if use_xsave() {
+            asm!("xrstor64 [{}]", in(reg) self.area, in("eax") u32::MAX, in("edx") u32::MAX, options(nostack));
+        } else {
+            asm!("fxrstor64 [{}]", in(reg) self.area, options(nostack));
+        }
+    }
+
+    /// Copie pour un enfant (fork) : si les registres du CPU sont plus
+    /// récents que la zone, ils y sont d'abord sauvegardés
+    pub fn duplicate(self: &Arc<Self>) -> Self {
+        without_interrupts(|| {
+            OWNER.with(|owner| {
+                if let Some(owner) = owner.borrow().as_ref() {
+                    if Arc::ptr_eq(owner, self) && !task_switched() {
+                        unsafe { self.save() };
+                    }
+                }
+            })
+        });
+        let copy = Self::new();
+        let len = copy.size.min(self.size);
+        unsafe { core::ptr::copy_nonoverlapping(self.area, copy.area, len) };
+        copy
+    }
+}
+
+impl core::fmt::Debug for FpuState {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "FpuState({} octets)", self.size)
+    }
+}
+
+impl Drop for FpuState {
+    fn drop(&mut self) {
+        unsafe { dealloc(self.area, self.layout()) };
+    }
+}
+
+crate::percpu! {
+    /// État dont les registres du CPU sont la copie à jour
+    static OWNER: RefCell<Option<Arc<FpuState>>> = RefCell::new(None);
+    /// État du thread courant, chargé à sa première instruction flottante
+    static CURRENT: RefCell<Option<Arc<FpuState>>> = RefCell::new(None);
+}
+
+fn same(a: &Option<Arc<FpuState>>, b: &Option<Arc<FpuState>>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+fn task_switched() -> bool {
+    Cr0::read().contains(Cr0Flags::TASK_SWITCHED)
+}
+
+fn set_task_switched(set: bool) {
+    if set {
+        unsafe { Cr0::update(|cr0| cr0.insert(Cr0Flags::TASK_SWITCHED)) };
+    } else {
+        unsafe { asm!("clts", options(nomem, nostack)) };
+    }
+}
+
+/// Changement de thread courant : `state` est l'état du nouveau thread
+/// (None pour un kthread). TS est armé si les registres appartiennent à
+/// un autre état.
+pub fn switch_to(state: Option<Arc<FpuState>>) {
+    let previous = without_interrupts(|| {
+        let previous = CURRENT.with(|current| current.replace(state));
+        let loaded = OWNER.with(|owner| CURRENT.with(|current| same(&owner.borrow(), &current.borrow())));
+        set_task_switched(!loaded);
+        previous
+    });
+    drop(previous);
+}
+
+/// #NM : les registres passent au thread courant
+pub extern "x86-interrupt" fn device_not_available_handler(stack_frame: InterruptStackFrame) {
+    let _gs = crate::percpu::KernelGs::enter(&stack_frame);
+    set_task_switched(false);
+    let previous = OWNER.with(|owner| {
+        CURRENT.with(|current| {
+            let current = current.borrow().clone();
+            unsafe {
+                if let Some(owner) = owner.borrow().as_ref() {
+                    owner.save();
+                }
+                if let Some(state) = current.as_ref() {
+                    state.restore();
+                }
+            }
+            owner.replace(current)
+        })
+    });
+    drop(previous);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_fpu_state_init_and_duplicate() {
+        let state = Arc::new(FpuState::new());
+        let bytes = state.as_bytes();
+        assert_eq!(bytes.len() % 16, 0);
+        assert_eq!(u16::from_le_bytes([bytes[0], bytes[1]]), FCW_DEFAULT);
+        assert_eq!(u32::from_le_bytes(bytes[MXCSR_OFFSET..MXCSR_OFFSET + 4].try_into().unwrap()), MXCSR_DEFAULT);
+
+        let copy = state.duplicate();
+        assert_eq!(copy.as_bytes(), state.as_bytes());
+
+        // Aucun état n'est chargé : le premier usage flottant lèverait #NM
+        switch_to(Some(state.clone()));
+        assert!(task_switched());
+        switch_to(None);
+        assert!(!task_switched());
+    }
+}
@@ -0,0 +1,253 @@
+/// Piles noyau des threads
+///
+/// Une `KernelStack` possède sa mémoire : la relâcher (Drop) rend la pile
+/// à l'allocateur. Le reaper s'appuie sur ce comportement pour libérer
+/// les piles des threads terminés.
+///
+/// Dès que la mémoire virtuelle est en place, chaque pile occupe le haut
+/// d'une fenêtre de KSTACK_SLOT_SIZE dans la zone KSTACK_AREA_BASE ; le
+/// bas de la fenêtre n'est jamais mappé. Un débordement touche donc une
+/// page de garde au lieu d'écraser le tas : la faute (double faute si RSP
+/// y est déjà) est rapportée comme « kernel stack overflow in TID x ».
+/// Avant `vm::init_vm` (noyau de test notamment), la pile vient du tas,
+/// sans garde.
+///
+/// Les fonctions compilées avec `-Z stack-protector` comparent en sortie
+/// un canari à `__stack_chk_guard`, tiré au hasard par `init_stack_guard`.
+
+use alloc::boxed::Box;
+use alloc::vec;
+use core::fmt;
+use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use x86_64::structures::paging::Page;
+use x86_64::VirtAddr;
+use crate::process::thread::ThreadId;
+
+/// Taille par défaut d'une pile noyau (8 KiB, le tas noyau est petit)
+pub const KERNEL_STACK_SIZE: usize = 8 * 1024;
+
+/// Début de la zone virtuelle des piles noyau
+pub const KSTACK_AREA_BASE: u64 = 0x0000_4000_0000_0000;
+/// Fenêtre d'une pile : la pile en haut, la garde dessous (64 Kio)
+pub const KSTACK_SLOT_SIZE: u64 = 64 * 1024;
+/// Garde minimale sous une pile
+pub const GUARD_SIZE: u64 = 4096;
+/// Fenêtres de la zone
+pub const MAX_KERNEL_STACKS: usize = 1024;
+
+const PAGE_SIZE: u64 = 4096;
+/// Fenêtre sans thread propriétaire (pile IST, pile de démarrage d'AP)
+const NO_OWNER: u64 = u64::MAX;
+
+/// Nombre de piles noyau actuellement allouées
+static LIVE_KERNEL_STACKS: AtomicUsize = AtomicUsize::new(0);
+
+const FREE: AtomicU64 = AtomicU64::new(0);
+const UNOWNED: AtomicU64 = AtomicU64::new(NO_OWNER);
+/// Taille de la pile de chaque fenêtre (0 : fenêtre libre)
+static SLOT_SIZES: [AtomicU64; MAX_KERNEL_STACKS] = [FREE; MAX_KERNEL_STACKS];
+/// Thread propriétaire de chaque fenêtre, lu sans verrou par les fautes
+static SLOT_OWNERS: [AtomicU64; MAX_KERNEL_STACKS] = [UNOWNED; MAX_KERNEL_STACKS];
+
+/// Sommet de la fenêtre `slot`
+fn slot_top(slot: usize) -> u64 {
+    KSTACK_AREA_BASE + (slot as u64 + 1) * KSTACK_SLOT_SIZE
+}
+
+/// Réserve une fenêtre libre pour une pile de `size` octets
+fn claim_slot(size: u64) -> Option<usize> {
+    (0..MAX_KERNEL_STACKS).find(|&slot| {
+        SLOT_SIZES[slot].compare_exchange(0, size, Ordering::AcqRel, Ordering::Relaxed).is_ok()
+    })
+}
+
+fn release_slot(slot: usize) {
+    SLOT_OWNERS[slot].store(NO_OWNER, Ordering::Relaxed);
+    SLOT_SIZES[slot].store(0, Ordering::Release);
+}
+
+/// Mémoire d'une pile
+enum Memory {
+    /// Allouée sur le tas, sans garde
+    Heap(Box<[u8]>),
+    /// Mappée en haut de la fenêtre `slot`
+    Mapped { slot: usize, size: u64 },
+}
+
+/// Pile noyau, gardée si possible
+pub struct KernelStack {
+    memory: Memory,
+}
+
+impl KernelStack {
+    /// Alloue une pile de `size` octets
+    pub fn new(size: usize) -> Self {
+        LIVE_KERNEL_STACKS.fetch_add(1, Ordering::Relaxed);
+        let memory = map_stack(size as u64).unwrap_or_else(|| Memory::Heap(vec![0u8; size].into_boxed_slice()));
+        Self { memory }
+    }
+
+    /// Adresse de base (la plus basse) de la pile
+    pub fn bottom(&self) -> u64 {
+        match &self.memory {
+            Memory::Heap(memory) => memory.as_ptr() as u64,
+            Memory::Mapped { slot, size } => slot_top(*slot) - size,
+        }
+    }
+
+    /// Sommet de la pile (la pile croît vers le bas), aligné sur 16 octets
+    pub fn top(&self) -> u64 {
+        (self.bottom() + self.size() as u64) & !0xF
+    }
+
+    /// Taille de la pile en octets
+    pub fn size(&self) -> usize {
+        match &self.memory {
+            Memory::Heap(memory) => memory.len(),
+            Memory::Mapped { size, .. } => *size as usize,
+        }
+    }
+
+    /// La pile a-t-elle une page de garde ?
+    pub fn guarded(&self) -> bool {
+        matches!(self.memory, Memory::Mapped { .. })
+    }
+
+    /// Thread nommé dans le rapport de débordement
+    pub fn set_owner(&self, tid: ThreadId) {
+        if let Memory::Mapped { slot, .. } = self.memory {
+            SLOT_OWNERS[slot].store(tid, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Mappe une pile de `size` octets (arrondie à la page) en haut d'une
+/// fenêtre libre ; None sans mémoire virtuelle ou sans fenêtre
+fn map_stack(size: u64) -> Option<Memory> {
+    let size = size.div_ceil(PAGE_SIZE) * PAGE_SIZE;
+    if size == 0 || size > KSTACK_SLOT_SIZE - GUARD_SIZE {
+        return None;
+    }
+    let mut vm = crate::memory::vm::VM_MANAGER.lock();
+    let space = vm.as_mut()?.kernel_space_mut();
+    let slot = claim_slot(size)?;
+    let bottom = slot_top(slot) - size;
+    let flags = crate::memory::vm::protection_flags(true, false, false);
+    for addr in (bottom..slot_top(slot)).step_by(PAGE_SIZE as usize) {
+        if space.map_page_filled(Page::containing_address(VirtAddr::new(addr)), flags, |bytes| bytes.fill(0)).is_err() {
+            space.unmap_range(VirtAddr::new(bottom), addr - bottom);
+            release_slot(slot);
+            return None;
+        }
+    }
+    Some(Memory::Mapped { slot, size })
+}
+
+impl Drop for KernelStack {
+    fn drop(&mut self) {
+        if let Memory::Mapped { slot, size } = self.memory {
+            if let Some(vm) = crate::memory::vm::VM_MANAGER.lock().as_mut() {
+                vm.kernel_space_mut().unmap_range(VirtAddr::new(slot_top(slot) - size), size);
+            }
+            release_slot(slot);
+        }
+        LIVE_KERNEL_STACKS.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl fmt::Debug for KernelStack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "KernelStack({:#x}..{:#x})", self.bottom(), self.top())
+    }
+}
+
+/// Retourne le nombre de piles noyau encore allouées
+pub fn live_kernel_stacks() -> usize {
+    LIVE_KERNEL_STACKS.load(Ordering::Relaxed)
+}
+
+/// Débordement d'une pile noyau dans sa garde
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StackOverflow {
+    /// Thread propriétaire (None : pile IST ou de démarrage)
+    pub tid: Option<ThreadId>,
+    /// Adresse fautive
+    pub address: u64,
+    /// Bas de la pile débordée
+    pub bottom: u64,
+}
+
+impl fmt::Display for StackOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.tid {
+            Some(tid) => write!(f, "kernel stack overflow in TID {}", tid)?,
+            None => write!(f, "kernel stack overflow")?,
+        }
+        write!(f, " (accès {:#x}, bas de pile {:#x})", self.address, self.bottom)
+    }
+}
+
+/// `address` tombe-t-elle sous une pile noyau, dans sa fenêtre ?
+/// Sans verrou ni allocation : appelé depuis les gestionnaires de faute.
+pub fn guard_hit(address: u64) -> Option<StackOverflow> {
+    let offset = address.checked_sub(KSTACK_AREA_BASE)?;
+    let slot = (offset / KSTACK_SLOT_SIZE) as usize;
+    if slot >= MAX_KERNEL_STACKS {
+        return None;
+    }
+    let size = SLOT_SIZES[slot].load(Ordering::Acquire);
+    let bottom = slot_top(slot) - size;
+    if size == 0 || address >= bottom {
+        return None;
+    }
+    let owner = SLOT_OWNERS[slot].load(Ordering::Relaxed);
+    Some(StackOverflow { tid: (owner != NO_OWNER).then_some(owner), address, bottom })
+}
+
+/// Canari de `-Z stack-protector`, lu par le prologue et l'épilogue des
+/// fonctions protégées. Valeur fixe jusqu'à `init_stack_guard`.
+#[no_mangle]
+#[allow(non_upper_case_globals)]
+pub static __stack_chk_guard: AtomicU64 = AtomicU64::new(0x595e_9fbd_94fd_a766);
+
+/// Tire le canari au hasard. Les fonctions protégées en cours d'exécution
+/// gardent l'ancien : à n'appeler que depuis `kernel_main`, qui ne rend
+/// jamais la main.
+#[inline(never)]
+pub fn init_stack_guard() {
+    // Octet de poids faible nul : une copie de chaîne C ne peut pas le recréer
+    __stack_chk_guard.store(crate::random::get_u64() & !0xFF, Ordering::SeqCst);
+}
+
+/// Appelé par une fonction protégée dont le canari a été écrasé
+#[no_mangle]
+pub extern "C" fn __stack_chk_fail() -> ! {
+    let tid = crate::scheduler::current_thread().and_then(|thread| thread.try_lock().map(|t| t.tid));
+    match tid {
+        Some(tid) => panic!("stack smashing detected in TID {}", tid),
+        None => panic!("stack smashing detected"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_guard_hit() {
+        let slot = claim_slot(2 * PAGE_SIZE).expect("fenêtre libre");
+        SLOT_OWNERS[slot].store(1042, Ordering::Relaxed);
+        let bottom = slot_top(slot) - 2 * PAGE_SIZE;
+
+        // Dans la pile, au-dessus de la fenêtre : rien à signaler
+        assert_eq!(guard_hit(bottom), None);
+        assert_eq!(guard_hit(slot_top(slot) - 8), None);
+        let overflow = guard_hit(bottom - 8).expect("page de garde");
+        assert_eq!((overflow.tid, overflow.bottom), (Some(1042), bottom));
+        assert!(alloc::format!("{}", overflow).starts_with("kernel stack overflow in TID 1042"));
+
+        release_slot(slot);
+        assert_eq!(guard_hit(bottom - 8), None);
+        assert_eq!(guard_hit(KSTACK_AREA_BASE - 8), None);
+    }
+}
@@ -8,19 +8,66 @@ use alloc::string::String;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 use alloc::format;
-use spin::Mutex;
+use alloc::collections::BTreeMap;
+use crate::sync::Mutex;
 use core::arch::asm;
+use crate::sync::rcu::Rcu;
 // use crate::memory::vm::{VMManager, VM_MANAGER}; // Disabled - depends on Limine
 
 pub mod elf;
-use self::elf::{ElfFile, PT_LOAD, PF_X, PF_W, PF_R};
+use self::elf::{ElfFile, TlsTemplate, PT_LOAD, PF_X, PF_W, PF_R};
 
 pub mod thread;
-pub use thread::{Thread, ThreadContext, ThreadState};
+pub use thread::{Thread, ThreadContext, ThreadState, ThreadSnapshot};
+use self::thread::alloc_tid;
+
+pub mod kstack;
+pub mod fpu;
+pub mod reaper;
 
 pub mod signal;
 use self::signal::{SignalQueue, SignalHandlerTable};
 
+pub mod rlimit;
+use self::rlimit::{Resource, ResourceLimits};
+
+pub mod cred;
+use self::cred::Credentials;
+
+pub mod exec;
+use self::exec::ExecArgs;
+
+pub mod aslr;
+
+pub mod seccomp;
+use self::seccomp::SyscallFilter;
+
+pub mod tls;
+
+/// Base des tas utilisateur (sous la zone mmap à 0x7000_0000_0000)
+pub const USER_HEAP_BASE: u64 = 0x1000_0000_0000;
+/// Taille maximale du tas d'un processus
+pub const USER_HEAP_MAX: u64 = 0x4000_0000; // 1 Gio
+
+/// Début du tas d'un processus
+///
+/// Tant que tous les processus partagent le même espace d'adressage,
+/// chacun reçoit sa propre fenêtre de `USER_HEAP_MAX` octets.
+pub fn heap_base_for(pid: u64) -> u64 {
+    USER_HEAP_BASE + pid * USER_HEAP_MAX
+}
+
+/// Base des piles des processus chargés depuis un ELF
+pub const USER_STACKS_BASE: u64 = 0x6000_0000_0000;
+/// Fenêtre réservée à la pile de chaque processus
+pub const USER_STACK_WINDOW: u64 = 0x10_0000; // 1 Mio
+
+/// Haut de la fenêtre de pile d'un processus (une fenêtre par PID,
+/// comme les tas) ; le sommet effectif est tiré dessous (`aslr`)
+pub fn user_stack_top_for(pid: u64) -> u64 {
+    USER_STACKS_BASE + (pid + 1) * USER_STACK_WINDOW
+}
+
 /// Niveau de priorité d'un processus
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum ProcessPriority {
@@ -72,10 +119,24 @@ pub enum ProcessState {
     Terminated,
 }
 
+/// Droit d'exécution (et de traversée) de `path` pour `cred`, puis bits
+/// setuid/setgid du fichier : identités que prendra la nouvelle image
+fn exec_identity(path: &str, cred: &Credentials) -> Result<(Option<cred::Uid>, Option<cred::Gid>), String> {
+    let stat = match crate::fs::vfs_access(path, cred, crate::fs::permissions::ACCESS_EXEC) {
+        Ok(stat) if stat.file_type == crate::fs::FileType::Regular => stat,
+        Ok(_) | Err(crate::fs::VfsError::PermissionDenied) => return Err(String::from("Permission denied")),
+        Err(_) => return Err(String::from("File not found")),
+    };
+    let perms = crate::fs::Permissions::from_stat(&stat);
+    Ok((perms.has_suid().then_some(stat.uid), perms.has_sgid().then_some(stat.gid)))
+}
+
 /// Représente un processus
 pub struct Process {
     /// Identifiant unique du processus (PID)
     pub pid: u64,
+    /// PID du parent (0 : aucun)
+    pub parent_pid: u64,
     /// Nom du processus
     pub name: String,
     /// État du processus
@@ -92,6 +153,24 @@ pub struct Process {
     pub signal_handlers: SignalHandlerTable,
     /// Threads du processus
     pub threads: Vec<Arc<Mutex<Thread>>>,
+    /// Début du tas (program break initial)
+    pub heap_start: u64,
+    /// Program break courant (fin du tas, non alignée)
+    pub brk: u64,
+    /// Limites de ressources (getrlimit/setrlimit)
+    pub limits: ResourceLimits,
+    /// Identités (UID/GID) utilisées pour les contrôles d'accès
+    pub cred: Credentials,
+    /// Filtre d'appels système (seccomp), hérité au fork et gardé par exec
+    pub seccomp: Option<SyscallFilter>,
+    /// Modèle TLS de l'exécutable (None : processus sans image ELF)
+    pub tls: Option<Arc<TlsTemplate>>,
+    /// Arguments du programme (argv)
+    pub args: Vec<String>,
+    /// Environnement (« NOM=valeur »), hérité par les enfants
+    pub env: Vec<String>,
+    /// Code de sortie, connu une fois le processus terminé
+    pub exit_status: Option<i32>,
 }
 
 impl Process {
@@ -105,8 +184,13 @@ impl Process {
         //     .ok_or("Gestionnaire de mémoire virtuelle non initialisé")?
         //     .create_process_space();
             
+        // Bases aléatoires du tas et des projections mmap (ASLR)
+        let heap_start = aslr::heap_start(pid);
+        crate::memory::MMAP_MANAGER.lock().randomize_base(pid);
+            
         let mut process = Self {
             pid,
+            parent_pid: 0,
             name: String::from(name),
             state: ProcessState::Ready,
             priority,
@@ -115,13 +199,20 @@ impl Process {
             signal_queue: SignalQueue::new(),
             signal_handlers: SignalHandlerTable::new(),
             threads: Vec::new(),
+            heap_start,
+            brk: heap_start,
+            limits: ResourceLimits::new(),
+            cred: Credentials::root(),
+            seccomp: None,
+            tls: None,
+            args: Vec::new(),
+            env: Vec::new(),
+            exit_status: None,
         };
 
         // Création du thread principal
-        // Note: Le TID devrait être unique globalement. Pour l'instant on utilise pid * 1000 (hack).
-        // Il faudrait un ThreadManager.
         let main_thread = Arc::new(Mutex::new(Thread::new(
-            pid * 1000 + 1, 
+            alloc_tid(), 
             pid, 
             "main", 
             priority,
@@ -132,7 +223,7 @@ impl Process {
         {
             let mut thread = main_thread.lock();
             thread.context.rip = _entry_point as u64;
-            // thread.context.rsp = ...; // Stack setup
+            thread.alloc_kernel_stack();
         }
 
         process.threads.push(main_thread);
@@ -166,9 +257,14 @@ impl Process {
         
         // Marquer pages CoW (TODO)
         let cow_pages = Vec::new();
+        
+        // Nouvelle fenêtre de tas : bases tirées comme pour un nouveau processus
+        let heap_start = aslr::heap_start(new_pid);
+        crate::memory::MMAP_MANAGER.lock().randomize_base(new_pid);
 
         let mut new_process = Self {
             pid: new_pid,
+            parent_pid: self.pid,
             name: format!("{}_child", self.name),
             state: ProcessState::Ready,
             priority: self.priority,
@@ -177,10 +273,19 @@ impl Process {
             signal_queue: SignalQueue::new(),
             signal_handlers: self.signal_handlers.clone(),
             threads: Vec::new(),
+            heap_start,
+            brk: heap_start,
+            limits: self.limits,
+            cred: self.cred.clone(),
+            seccomp: self.seccomp,
+            tls: self.tls.clone(),
+            args: self.args.clone(),
+            env: self.env.clone(),
+            exit_status: None,
         };
         
         // Dupliquer le thread courant
-        let new_tid = new_pid * 1000 + 1; // Hack TID
+        let new_tid = alloc_tid();
         let mut new_thread = Thread::new(
             new_tid,
             new_pid,
@@ -193,6 +298,15 @@ impl Process {
         new_thread.context = current_thread.context.clone();
         // Ajuster context pour retour de fork (rax=0)
         new_thread.context.registers[0] = 0; // RAX = 0 pour l'enfant
+        // Les registres flottants suivent l'enfant
+        new_thread.fpu = current_thread.fpu.as_ref().map(|state| Arc::new(state.duplicate()));
+        // L'enfant a sa propre pile noyau et sa copie du bloc TLS
+        new_thread.alloc_kernel_stack();
+        if let (Some(template), Some(block)) = (&self.tls, &current_thread.tls_block) {
+            let (copy, fs_base) = tls::duplicate(template, block, current_thread.context.fs_base, new_pid)?;
+            new_thread.tls_block = Some(copy);
+            new_thread.context.fs_base = fs_base;
+        }
 
         new_process.threads.push(Arc::new(Mutex::new(new_thread)));
         
@@ -201,8 +315,10 @@ impl Process {
 
     /// Ajoute un nouveau thread au processus
     pub fn create_thread(&mut self, entry_point: u64) -> Result<Arc<Mutex<Thread>>, &'static str> {
-        // Générer un TID (Hack: pid * 1000 + count)
-        let tid = self.pid * 1000 + (self.threads.len() as u64) + 1;
+        if self.limits.get(Resource::NProc).exceeded(self.threads.len() as u64 + 1) {
+            return Err("Thread limit exceeded");
+        }
+        let tid = alloc_tid();
         
         let mut thread = Thread::new(
             tid,
@@ -212,25 +328,154 @@ impl Process {
             self.address_space_id // CR3
         );
         
-        // Setup IP
+        // Setup IP/SP
         thread.context.rip = entry_point;
-        
-        // TODO: Allouer une nouvelle pile pour le thread
-        // thread.context.rsp = ...
+        thread.alloc_kernel_stack();
+        if let Some(template) = &self.tls {
+            thread.alloc_tls(template)?;
+        }
         
         let thread_ref = Arc::new(Mutex::new(thread));
         self.threads.push(thread_ref.clone());
         
         Ok(thread_ref)
     }
+
+    /// Retire un thread du processus et le retourne
+    pub fn detach_thread(&mut self, tid: u64) -> Option<Arc<Mutex<Thread>>> {
+        let pos = self.threads.iter().position(|t| t.lock().tid == tid)?;
+        Some(self.threads.remove(pos))
+    }
+
+    /// Nombre de threads vivants du processus
+    pub fn thread_count(&self) -> usize {
+        self.threads.len()
+    }
+
+    /// Déplace le program break et retourne sa nouvelle valeur
+    ///
+    /// Les pages ajoutées sont servies à la demande (remplies de zéros,
+    /// lecture/écriture, non exécutables) ; celles retirées sont démappées.
+    pub fn set_brk(&mut self, new_brk: u64) -> Result<u64, &'static str> {
+        if new_brk < self.heap_start {
+            return Err("Break below heap start");
+        }
+        // Le tas ne déborde pas de sa fenêtre, quel que soit son décalage
+        if new_brk > heap_base_for(self.pid) + USER_HEAP_MAX
+            || self.limits.get(Resource::As).exceeded(new_brk - self.heap_start)
+        {
+            return Err("Heap limit exceeded");
+        }
+        
+        use crate::memory::vm::{self, DEMAND_PAGING_MANAGER};
+        use x86_64::VirtAddr;
+        let old_end = VirtAddr::new(self.brk).align_up(4096u64);
+        let new_end = VirtAddr::new(new_brk).align_up(4096u64);
+        
+        if new_end > old_end {
+            let flags = vm::protection_flags(true, false, true);
+            DEMAND_PAGING_MANAGER.lock().register_lazy_range(old_end, new_end, flags);
+        } else if new_end < old_end {
+            DEMAND_PAGING_MANAGER.lock().release_range(new_end, old_end);
+            vm::SWAP_DAEMON.lock().discard_range(new_end, old_end);
+            if let Some(vm) = vm::VM_MANAGER.lock().as_mut() {
+                vm.kernel_space_mut().unmap_range(new_end, old_end - new_end);
+            }
+            vm::LRU_PAGE_CACHE.lock().remove_range(new_end, old_end);
+        }
+        
+        self.brk = new_brk;
+        Ok(new_brk)
+    }
+    
+    /// Fait varier le program break de `increment` octets ; retourne l'ancien break
+    pub fn sbrk(&mut self, increment: i64) -> Result<u64, &'static str> {
+        let old_brk = self.brk;
+        let new_brk = old_brk.checked_add_signed(increment).ok_or("Invalid break increment")?;
+        self.set_brk(new_brk)?;
+        Ok(old_brk)
+    }
+    
+    /// Libère tout le tas du processus
+    pub fn release_heap(&mut self) {
+        let _ = self.set_brk(self.heap_start);
+    }
+
+    /// `[addr, addr + len)` appartient-il à la mémoire du processus (tas,
+    /// fenêtre de pile, projections mmap) ?
+    pub fn owns_user_range(&self, addr: u64, len: u64) -> bool {
+        let end = match addr.checked_add(len) {
+            Some(end) => end,
+            None => return false,
+        };
+        let stack_top = user_stack_top_for(self.pid);
+        if (addr >= self.heap_start && end <= self.brk) || (addr >= stack_top - USER_STACK_WINDOW && end <= stack_top) {
+            return true;
+        }
+        let start = match x86_64::VirtAddr::try_new(addr) {
+            Ok(start) => start,
+            Err(_) => return false,
+        };
+        crate::memory::MMAP_MANAGER.lock().find_region(start).map_or(false, |region| {
+            region.owner_pid == self.pid && end <= region.start_addr.as_u64() + region.size as u64
+        })
+    }
+
+    /// Copie l'état du processus et de ses threads
+    pub fn snapshot(&self) -> ProcessSnapshot {
+        ProcessSnapshot {
+            pid: self.pid,
+            name: self.name.clone(),
+            state: self.state,
+            priority: self.priority,
+            threads: self.threads.iter().map(|t| t.lock().snapshot()).collect(),
+            heap: self.brk.saturating_sub(self.heap_start),
+        }
+    }
+}
+
+/// Instantané d'un processus : copie indépendante des verrous,
+/// utilisable pour l'affichage (ps, /proc) sans bloquer le ProcessManager.
+#[derive(Debug, Clone)]
+pub struct ProcessSnapshot {
+    pub pid: u64,
+    pub name: String,
+    pub state: ProcessState,
+    pub priority: ProcessPriority,
+    pub threads: Vec<ThreadSnapshot>,
+    /// Taille du tas (octets entre le break initial et le break courant)
+    pub heap: u64,
+}
+
+impl ProcessSnapshot {
+    /// Nombre de threads du processus
+    pub fn thread_count(&self) -> usize {
+        self.threads.len()
+    }
+
+    /// Temps CPU virtuel cumulé de tous les threads
+    pub fn vruntime(&self) -> u64 {
+        self.threads.iter().map(|t| t.vruntime).sum()
+    }
+
+    /// Temps d'exécution réel (ns) reconstitué depuis le vruntime : celui
+    /// de chaque thread est pondéré par 1024 / poids de sa priorité
+    pub fn run_time(&self) -> u64 {
+        self.threads.iter().map(|t| t.vruntime.saturating_mul(t.priority.weight()) / 1024).sum()
+    }
 }
 
+/// Index des processus par PID, lisible sans verrou (voir `sync::rcu`)
+pub type ProcessIndex = Rcu<BTreeMap<u64, Arc<Mutex<Process>>>>;
+
 /// Gestionnaire de processus
 pub struct ProcessManager {
     /// Liste des processus
     processes: Vec<Arc<Mutex<Process>>>,
     /// Compteur pour générer des PID uniques
     next_pid: u64,
+    /// Index republié à chaque ajout (instance globale seulement)
+    index: Option<&'static ProcessIndex>,
     // VM disabled - depends on Limine
 }
 
@@ -240,8 +485,38 @@ impl ProcessManager {
         Self {
             processes: Vec::new(),
             next_pid: 1, // Le PID 0 est réservé pour le processus idle (ou kernel)
+            index: None,
         }
     }
+
+    /// Gestionnaire qui publie ses processus dans `index`
+    pub fn published(index: &'static ProcessIndex) -> Self {
+        Self { index: Some(index), ..Self::new() }
+    }
+
+    /// Ajoute un processus à la liste et à l'index publié
+    fn insert(&mut self, pid: u64, process: Arc<Mutex<Process>>) {
+        if let Some(index) = self.index {
+            index.update(|map| {
+                let mut map = map.clone();
+                map.insert(pid, process.clone());
+                map
+            });
+        }
+        self.processes.push(process);
+    }
+
+    /// Retire un processus de la liste et de l'index publié
+    fn remove(&mut self, pid: u64) {
+        if let Some(index) = self.index {
+            index.update(|map| {
+                let mut map = map.clone();
+                map.remove(&pid);
+                map
+            });
+        }
+        self.processes.retain(|p| p.lock().pid != pid);
+    }
     
     /// Crée un nouveau processus
     pub fn create_process(&mut self, name: &str, entry_point: fn() -> !, priority: ProcessPriority) -> Result<u64, &'static str> {
@@ -254,7 +529,7 @@ impl ProcessManager {
         let main_thread = process_struct.threads[0].clone();
         
         let process = Arc::new(Mutex::new(process_struct));
-        self.processes.push(process);
+        self.insert(pid, process);
         
         // Initialiser la table des descripteurs de fichiers
         crate::fs::FD_MANAGER.lock().create_table(pid).unwrap();
@@ -265,46 +540,158 @@ impl ProcessManager {
         Ok(pid)
     }
 
-    /// Charge et lance un exécutable depuis un fichier
-    pub fn spawn(&mut self, path: &str) -> Result<u64, String> {
+    /// Charge et lance un exécutable depuis un fichier, avec ses
+    /// arguments et son environnement ; le processus est l'enfant du
+    /// processus courant (init à défaut), qui l'attend avec `reap_child`
+    pub fn spawn(&mut self, path: &str, args: &ExecArgs) -> Result<u64, String> {
+        let parent = current_process().map(|p| p.lock().pid).unwrap_or(1);
+        self.spawn_as(parent, path, args)
+    }
+
+    /// `spawn` pour le compte de `parent_pid` : le fichier doit lui être
+    /// exécutable ; l'enfant hérite de ses identités (setuid/setgid du
+    /// fichier appliqués), limites, filtre seccomp et, sans `args.envp`,
+    /// de son environnement
+    pub fn spawn_as(&mut self, parent_pid: u64, path: &str, args: &ExecArgs) -> Result<u64, String> {
+        // Parent inconnu (noyau au démarrage) : valeurs d'un nouveau processus
+        let (limits, mut cred, seccomp, env) = self.processes.iter()
+            .find_map(|p| {
+                let p = p.lock();
+                if p.pid == parent_pid { Some((p.limits, p.cred.clone(), p.seccomp, p.env.clone())) } else { None }
+            })
+            .unwrap_or_else(|| (ResourceLimits::new(), Credentials::root(), None, Vec::new()));
+        let (setuid, setgid) = exec_identity(path, &cred)?;
+        cred.exec_as(setuid, setgid);
+
         let content = crate::fs::vfs_read_file(path)
             .map_err(|_| String::from("File not found"))?;
-            
-        self.create_process_from_elf(path, &content)
-            .map_err(|e| String::from(e))
+        let mut args = args.clone();
+        if args.envp.is_empty() {
+            args.envp = env;
+        }
+
+        self.load_elf_process(path, &content, &args, |process| {
+            process.parent_pid = parent_pid;
+            process.cred = cred;
+            process.limits = limits;
+            process.seccomp = seccomp;
+        })
+        .map_err(String::from)
+    }
+
+    /// Récupère un enfant terminé de `parent` (`pid` : un enfant précis,
+    /// `None` : n'importe lequel) et renvoie son PID et son code de sortie.
+    /// `Ok(None)` : des enfants existent mais aucun n'est encore terminé.
+    pub fn reap_child(&mut self, parent: u64, pid: Option<u64>) -> Result<Option<(u64, i32)>, &'static str> {
+        let mut found = false;
+        let mut zombie = None;
+        for process in &self.processes {
+            let process = process.lock();
+            if process.parent_pid != parent || pid.map_or(false, |pid| pid != process.pid) {
+                continue;
+            }
+            found = true;
+            if process.state == ProcessState::Terminated {
+                zombie = Some((process.pid, process.exit_status.unwrap_or(0)));
+                break;
+            }
+        }
+        if !found {
+            return Err("No child processes");
+        }
+        if let Some((pid, _)) = zombie {
+            self.remove(pid);
+            let _ = crate::fs::FD_MANAGER.lock().remove_table(pid);
+            crate::memory::MMAP_MANAGER.lock().forget_base(pid);
+        }
+        Ok(zombie)
+    }
+
+    /// Crée un processus utilisateur enfant de `parent_pid`
+    ///
+    /// Son thread principal n'est pas confié au scheduler : il est exécuté
+    /// par le lanceur ring 3 (`ring3::spawn_user_program`).
+    pub fn create_user_process(&mut self, parent_pid: u64, name: &str) -> Result<(u64, Arc<Mutex<Thread>>), &'static str> {
+        // Les limites (ulimit du shell), identités et environnement du parent sont hérités
+        let (limits, cred, env) = self.processes.iter()
+            .find_map(|p| {
+                let p = p.lock();
+                if p.pid == parent_pid { Some((p.limits, p.cred.clone(), p.env.clone())) } else { None }
+            })
+            .ok_or("Parent process not found")?;
+        
+        let pid = self.next_pid;
+        self.next_pid += 1;
+        
+        fn user_entry() -> ! { loop {} }
+        let mut process = Process::new(pid, name, user_entry, ProcessPriority::Normal)?;
+        process.parent_pid = parent_pid;
+        process.limits = limits;
+        process.cred = cred;
+        process.env = env;
+        let main_thread = process.threads[0].clone();
+        
+        self.insert(pid, Arc::new(Mutex::new(process)));
+        crate::fs::FD_MANAGER.lock().create_table(pid)?;
+        rlimit::apply(pid, &limits);
+        
+        Ok((pid, main_thread))
     }
 
-    /// Crée un nouveau processus à partir de données ELF
-    pub fn create_process_from_elf(&mut self, name: &str, elf_data: &[u8]) -> Result<u64, &'static str> {
+    /// Crée un nouveau processus à partir de données ELF ; sa pile de
+    /// départ porte `args` (ABI de `exec`)
+    pub fn create_process_from_elf(&mut self, name: &str, elf_data: &[u8], args: &ExecArgs) -> Result<u64, &'static str> {
+        self.load_elf_process(name, elf_data, args, |_| {})
+    }
+
+    /// Crée le processus d'une image ELF ; `setup` ajuste le processus
+    /// (parent, identités, limites) avant que sa pile et son thread
+    /// ne soient prêts
+    fn load_elf_process(
+        &mut self,
+        name: &str,
+        elf_data: &[u8],
+        args: &ExecArgs,
+        setup: impl FnOnce(&mut Process),
+    ) -> Result<u64, &'static str> {
         let elf = ElfFile::new(elf_data)?;
         elf.header.validate()?;
+        let tls_template = Arc::new(elf.tls_template()?);
 
         // Créer l'espace d'adressage
         let pid = self.next_pid;
         self.next_pid += 1;
         
-        // Logique de chargement simulée (TODO: VMManager real alloc)
-        // ... (parsing segments) ...
+        // Segments PT_LOAD : code R-X, données NX
+        elf.load_segments()?;
         
         // Création process via new (avec dummy entry point, on overwrite après)
         fn dummy_entry() -> ! { loop {} }
-        let process = Process::new(pid, name, dummy_entry, ProcessPriority::Normal)?;
+        let mut process = Process::new(pid, name, dummy_entry, ProcessPriority::Normal)?;
+        process.args = args.argv.clone();
+        process.env = args.envp.clone();
+        process.tls = Some(tls_template.clone());
+        setup(&mut process);
+        let limits = process.limits;
         
         // Overwrite du thread context
         let entry_point = elf.header.e_entry;
+        let rsp = crate::ring3::map_user_stack(aslr::stack_top(user_stack_top_for(pid)), args, &exec::auxv(entry_point, &process.cred))?;
         {
             let mut thread = process.threads[0].lock();
             thread.context.rip = entry_point;
-            // thread.context.rsp = ...;
+            thread.context.rsp = rsp;
+            thread.alloc_tls(&tls_template)?;
         }
 
         let main_thread = process.threads[0].clone();
 
         let process = Arc::new(Mutex::new(process));
-        self.processes.push(process);
+        self.insert(pid, process);
         
         // Initialiser la table des descripteurs de fichiers
         crate::fs::FD_MANAGER.lock().create_table(pid).unwrap();
+        rlimit::apply(pid, &limits);
         
         // Ajouter le thread au scheduler
         crate::scheduler::SCHEDULER.add_thread(main_thread);
@@ -313,38 +700,68 @@ impl ProcessManager {
     }
 
     /// Remplace l'image du processus actuel par un nouvel exécutable (exec)
-    pub fn exec_process(&mut self, current_tid: u64, path: &str) -> Result<u64, String> {
+    ///
+    /// La pile est reconstruite avec `args` : sous `ring3::USER_STACK_TOP`
+    /// pour un programme lancé par le lanceur ring 3, dans la fenêtre du
+    /// processus sinon. Retourne l'entrée et le pointeur de pile initiaux.
+    pub fn exec_process(&mut self, current_tid: u64, path: &str, args: &ExecArgs) -> Result<(u64, u64), String> {
         // 1. Lire le fichier ELF
         let content = crate::fs::vfs_read_file(path)
             .map_err(|_| String::from("File not found"))?;
             
-        let elf = ElfFile::new(&content).map_err(|e| String::from(e))?;
-        if let Err(e) = elf.header.validate() {
-            return Err(String::from(e));
+        // Image non exécutable : ENOEXEC côté appel système
+        let elf = ElfFile::new(&content).map_err(|_| String::from("Exec format error"))?;
+        if elf.header.validate().is_err() {
+            return Err(String::from("Exec format error"));
         }
+        let tls_template = Arc::new(elf.tls_template().map_err(|_| String::from("Exec format error"))?);
         
         // 2. Trouver le process
         let process_arc = self.processes.iter().find(|p| {
             p.lock().threads.iter().any(|t| t.lock().tid == current_tid)
         }).ok_or(String::from("Process not found"))?.clone();
         
+        let cred = process_arc.lock().cred.clone();
+        let (setuid, setgid) = exec_identity(path, &cred)?;
+        
+        elf.load_segments().map_err(String::from)?;
+        
         let mut process = process_arc.lock();
         process.name = String::from(path);
+        process.cred.exec_as(setuid, setgid);
+        // La nouvelle image repart d'un tas vide, à une nouvelle adresse
+        process.release_heap();
+        process.heap_start = aslr::heap_start(process.pid);
+        process.brk = process.heap_start;
+        crate::memory::MMAP_MANAGER.lock().randomize_base(process.pid);
+        process.args = args.argv.clone();
+        process.env = args.envp.clone();
+        process.tls = Some(tls_template.clone());
         
-        // 3. Réinitialiser le thread
+        // 3. Réinitialiser le thread et sa pile
         // Simplification: on assume que c'est le seul thread ou on modifie juste celui-ci
         let thread_arc = process.threads.iter()
             .find(|t| t.lock().tid == current_tid)
             .unwrap()
             .clone();
+        let entry = elf.header.e_entry;
+        let window = if crate::ring3::in_user_program() { crate::ring3::USER_STACK_TOP } else { user_stack_top_for(process.pid) };
+        // L'ancienne pile peut être ailleurs dans la fenêtre
+        crate::ring3::unmap_user_stack(window);
+        let top = aslr::stack_top(window);
+        let rsp = crate::ring3::map_user_stack(top, args, &exec::auxv(entry, &process.cred)).map_err(String::from)?;
             
         {
             let mut thread = thread_arc.lock();
-            thread.context.rip = elf.header.e_entry;
-            // TODO: Reset stack, load segments
+            thread.context.rip = entry;
+            thread.context.rsp = rsp;
+            thread.context.registers = [0; 16];
+            thread.alloc_tls(&tls_template).map_err(String::from)?;
+            // Le thread appelant est le thread courant : FS tout de suite
+            tls::load_fs_base(thread.context.fs_base);
         }
         
-        Ok(0)
+        Ok((entry, rsp))
     }
 
     /// Duplique le processus actuel (fork)
@@ -368,9 +785,11 @@ impl ProcessManager {
         
         let new_process_struct = parent_proc.lock().fork(&current_thread, new_pid)?;
         let main_thread = new_process_struct.threads[0].clone();
+        rlimit::apply(new_pid, &new_process_struct.limits);
+        crate::memory::SHM_MANAGER.lock().fork(parent_proc.lock().pid, new_pid);
         
         let new_process = Arc::new(Mutex::new(new_process_struct));
-        self.processes.push(new_process);
+        self.insert(new_pid, new_process);
         
         // Ajouter le thread au scheduler
         crate::scheduler::SCHEDULER.add_thread(main_thread);
@@ -396,6 +815,11 @@ impl ProcessManager {
         &self.processes
     }
 
+    /// Instantané de tous les processus
+    pub fn snapshot(&self) -> Vec<ProcessSnapshot> {
+        self.processes.iter().map(|p| p.lock().snapshot()).collect()
+    }
+
     /// Crée un thread dans un processus existant
     pub fn create_thread(&mut self, pid: u64, entry_point: u64) -> Result<u64, &'static str> {
         let process_lock = self.processes.iter()
@@ -412,8 +836,36 @@ impl ProcessManager {
         Ok(tid)
     }
 
+    /// Termine un thread : il quitte son processus et est confié au reaper.
+    /// Le processus passe à l'état Terminated quand son dernier thread se termine.
+    pub fn exit_thread(&mut self, tid: u64, status: i32) -> Result<(), &'static str> {
+        let process_lock = self.processes.iter()
+            .find(|p| p.lock().threads.iter().any(|t| t.lock().tid == tid))
+            .ok_or("Thread not found")?
+            .clone();
+            
+        let mut process = process_lock.lock();
+        let thread = process.detach_thread(tid).ok_or("Thread not found")?;
+        // Un join côté runtime attend ce zéro ; le mot doit appartenir au
+        // processus du thread, pas à celui qui s'exécute
+        let clear_child_tid = thread.lock().clear_child_tid;
+        if clear_child_tid != 0 && process.owns_user_range(clear_child_tid, 4) {
+            let _ = crate::memory::uaccess::write_user_remote(clear_child_tid as *mut u32, 0);
+        }
+        if process.threads.is_empty() {
+            process.state = ProcessState::Terminated;
+            process.exit_status = Some(status);
+            process.release_heap();
+            crate::memory::SHM_MANAGER.lock().detach_all(process.pid);
+        }
+        drop(process);
+        
+        reaper::enqueue_dead(thread);
+        Ok(())
+    }
+
     /// Termine un processus
-    pub fn terminate_process(&mut self, target_pid: u64, _status: i32) -> Result<(), &'static str> {
+    pub fn terminate_process(&mut self, target_pid: u64, status: i32) -> Result<(), &'static str> {
         let process_lock = self.processes.iter()
             .find(|p| p.lock().pid == target_pid)
             .ok_or("Process not found")?
@@ -421,6 +873,14 @@ impl ProcessManager {
             
         let mut process = process_lock.lock();
         process.state = ProcessState::Terminated;
+        process.exit_status = Some(status);
+        process.release_heap();
+        crate::memory::SHM_MANAGER.lock().detach_all(target_pid);
+        
+        // Tous les threads du processus sont confiés au reaper
+        for thread in process.threads.drain(..) {
+            reaper::enqueue_dead(thread);
+        }
         
         Ok(())
     }
@@ -446,40 +906,220 @@ mod tests {
         assert_eq!(pid, Ok(1));
         assert_eq!(pm.processes.len(), 1);
     }
+
+    #[test_case]
+    fn test_thread_create_join_stress() {
+        let mut pm = ProcessManager::new();
+        let pid = pm.create_process("stress", test_process, ProcessPriority::Normal).unwrap();
+        let stacks_before = kstack::live_kernel_stacks();
+
+        for _ in 0..4096 {
+            let tid = pm.create_thread(pid, test_process as u64).unwrap();
+            assert_eq!(kstack::live_kernel_stacks(), stacks_before + 1);
+
+            pm.exit_thread(tid, 0).unwrap();
+            assert!(pm.get_thread_by_tid(tid).is_none());
+            reaper::reap();
+        }
+
+        assert_eq!(reaper::pending(), 0);
+        assert_eq!(kstack::live_kernel_stacks(), stacks_before);
+        let process = pm.processes[0].lock();
+        assert_eq!(process.thread_count(), 1);
+        assert_eq!(process.state, ProcessState::Ready);
+    }
+
+    #[test_case]
+    fn test_process_snapshot() {
+        let mut pm = ProcessManager::new();
+        let pid = pm.create_process("snap", test_process, ProcessPriority::High).unwrap();
+        let tid = pm.create_thread(pid, test_process as u64).unwrap();
+
+        let snapshot = pm.snapshot();
+        assert_eq!(snapshot.len(), 1);
+        assert_eq!(snapshot[0].name, "snap");
+        assert_eq!(snapshot[0].priority, ProcessPriority::High);
+        assert_eq!(snapshot[0].thread_count(), 2);
+        assert!(snapshot[0].threads.iter().any(|t| t.tid == tid));
+
+        pm.exit_thread(tid, 0).unwrap();
+        reaper::reap();
+    }
+
+    #[test_case]
+    fn test_reap_child() {
+        let mut pm = ProcessManager::new();
+        let parent = pm.create_process("parent", test_process, ProcessPriority::Normal).unwrap();
+        let child = pm.create_process("child", test_process, ProcessPriority::Normal).unwrap();
+        pm.processes[1].lock().parent_pid = parent;
+
+        assert_eq!(pm.reap_child(parent, None), Ok(None));
+        assert!(pm.reap_child(child, None).is_err());
+        pm.terminate_process(child, 3).unwrap();
+        assert!(pm.reap_child(parent, Some(parent)).is_err());
+        assert_eq!(pm.reap_child(parent, Some(child)), Ok(Some((child, 3))));
+        assert_eq!(pm.processes.len(), 1);
+        assert!(pm.reap_child(parent, None).is_err());
+
+        pm.terminate_process(parent, 0).unwrap();
+        reaper::reap();
+    }
+
+    #[test_case]
+    fn test_spawn_inherits_caller() {
+        use crate::process::rlimit::RLimit;
+
+        // En-tête ELF seul : aucun segment, le thread n'est jamais ordonnancé ici
+        let mut image = [0u8; 64];
+        image[..4].copy_from_slice(&elf::Elf64Header::MAGIC);
+        image[4] = 2; // 64 bits
+        image[5] = 1; // petit-boutiste
+        image[16] = 2; // ET_EXEC
+        image[18] = 62; // x86-64
+        let _ = crate::fs::vfs_mkdir("/tmp");
+        crate::fs::vfs_write_file("/tmp/spawn-test", &image).unwrap();
+        let root = Credentials::root();
+        crate::fs::vfs_chmod_as("/tmp/spawn-test", 0o755, &root).unwrap();
+
+        let mut pm = ProcessManager::new();
+        let parent = pm.create_process("sh", test_process, ProcessPriority::Normal).unwrap();
+        let nofile = RLimit::new(16, 16);
+        let filter = SyscallFilter::from_words(&[u64::MAX], 0).unwrap();
+        {
+            let mut process = pm.processes[0].lock();
+            process.cred = Credentials::new(1000, 1000);
+            process.limits.set(Resource::NoFile, nofile).unwrap();
+            process.seccomp = Some(filter);
+            process.env = alloc::vec!["HOME=/home/user".into()];
+        }
+
+        let args = ExecArgs::new(alloc::vec!["spawn-test".into()], Vec::new());
+        let child = pm.spawn_as(parent, "/tmp/spawn-test", &args).unwrap();
+        {
+            let process = pm.processes.iter().find(|p| p.lock().pid == child).unwrap().lock();
+            assert_eq!(process.parent_pid, parent);
+            assert_eq!((process.cred.euid, process.cred.egid), (1000, 1000));
+            assert_eq!(process.limits.get(Resource::NoFile), nofile);
+            assert_eq!(process.seccomp, Some(filter));
+            assert_eq!(process.env, ["HOME=/home/user"]);
+        }
+
+        // Setuid root : l'UID effectif devient celui du propriétaire
+        crate::fs::vfs_chmod_as("/tmp/spawn-test", 0o4755, &root).unwrap();
+        let setuid_child = pm.spawn_as(parent, "/tmp/spawn-test", &args).unwrap();
+        {
+            let process = pm.processes.iter().find(|p| p.lock().pid == setuid_child).unwrap().lock();
+            assert_eq!((process.cred.ruid, process.cred.euid), (1000, 0));
+        }
+
+        // Sans droit d'exécution, rien n'est lancé
+        crate::fs::vfs_chmod_as("/tmp/spawn-test", 0o644, &root).unwrap();
+        assert_eq!(pm.spawn_as(parent, "/tmp/spawn-test", &args), Err(String::from("Permission denied")));
+
+        for pid in [child, setuid_child, parent] {
+            pm.terminate_process(pid, 0).unwrap();
+            let _ = crate::fs::FD_MANAGER.lock().remove_table(pid);
+        }
+        reaper::reap();
+        let _ = crate::fs::vfs_unlink("/tmp/spawn-test");
+    }
+
+    #[test_case]
+    fn test_brk_sbrk() {
+        fn dummy() -> ! { loop {} }
+        let mut process = Process::new(42, "heap", dummy, ProcessPriority::Normal).unwrap();
+        let base = heap_base_for(42);
+        assert_eq!((process.heap_start, process.brk), (base, base));
+
+        assert_eq!(process.sbrk(100), Ok(base));
+        assert_eq!(process.sbrk(0), Ok(base + 100));
+        assert_eq!(process.set_brk(base + 3 * 4096), Ok(base + 3 * 4096));
+        assert_eq!(process.sbrk(-(2 * 4096)), Ok(base + 3 * 4096));
+        assert_eq!(process.brk, base + 4096);
+
+        assert!(process.set_brk(base - 1).is_err());
+        assert!(process.set_brk(base + USER_HEAP_MAX + 1).is_err());
+        assert_eq!(process.brk, base + 4096);
+
+        // Mémoire du processus : tas, fenêtre de pile ; pas celle d'un autre
+        assert!(process.owns_user_range(base, 4));
+        assert!(!process.owns_user_range(base + 4096, 4));
+        assert!(process.owns_user_range(user_stack_top_for(42) - 8, 4));
+        assert!(!process.owns_user_range(user_stack_top_for(43) - 8, 4));
+
+        process.release_heap();
+        assert_eq!(process.brk, base);
+        for thread in process.threads.drain(..) {
+            reaper::enqueue_dead(thread);
+        }
+        reaper::reap();
+    }
 }
 
 // Instance globale du gestionnaire de processus
 use lazy_static::lazy_static;
 
 lazy_static! {
+    /// Processus par PID, pour les lectures sans le verrou du gestionnaire
+    pub static ref PROCESS_INDEX: ProcessIndex = Rcu::new(BTreeMap::new());
     /// Gestionnaire de processus global
-    pub static ref PROCESS_MANAGER: Mutex<ProcessManager> = Mutex::new(ProcessManager::new());
+    pub static ref PROCESS_MANAGER: Mutex<ProcessManager> = Mutex::new(ProcessManager::published(&PROCESS_INDEX));
+}
+
+/// Copie de la liste publiée ; les processus se verrouillent ensuite un à un
+fn published_processes() -> Vec<Arc<Mutex<Process>>> {
+    PROCESS_INDEX.read().values().cloned().collect()
 }
 
 /// Obtient le processus actuellement en cours d'exécution
 pub fn current_process() -> Option<Arc<Mutex<Process>>> {
     let thread = crate::scheduler::current_thread()?;
-    let tid = thread.lock().tid;
+    let (tid, pid) = {
+        let thread = thread.lock();
+        (thread.tid, thread.pid)
+    };
+    let owns = |p: &Arc<Mutex<Process>>| p.lock().threads.iter().any(|t| t.lock().tid == tid);
     
-    let pm = PROCESS_MANAGER.lock();
-    for p in &pm.processes {
-        if p.lock().threads.iter().any(|t| t.lock().tid == tid) {
-            return Some(p.clone());
-        }
+    // Cas courant : le processus désigné par le thread, sans verrou global
+    let candidate = PROCESS_INDEX.read().get(&pid).cloned();
+    if let Some(p) = candidate.filter(|p| owns(p)) {
+        return Some(p);
     }
-    None
+    published_processes().into_iter().find(|p| owns(p))
+}
+
+/// Le processus courant a-t-il la capacité `cap` ? Le noyau (hors
+/// processus) les a toutes.
+pub fn current_capable(cap: cred::Capabilities) -> bool {
+    current_process().map_or(true, |p| p.lock().cred.capable(cap))
+}
+
+/// Instantané de tous les processus, sans le verrou du PROCESS_MANAGER
+pub fn snapshot_processes() -> Vec<ProcessSnapshot> {
+    published_processes().iter().map(|p| p.lock().snapshot()).collect()
 }
 
 /// Obtient un processus par son PID
 pub fn get_process_by_pid(pid: u64) -> Option<Arc<Mutex<Process>>> {
-    PROCESS_MANAGER.lock()
-        .processes
-        .iter()
-        .find(|p| p.lock().pid == pid)
-        .cloned()
+    PROCESS_INDEX.read().get(&pid).cloned()
 }
 
 /// Obtient un thread par son TID
 pub fn get_thread_by_tid(tid: u64) -> Option<Arc<Mutex<Thread>>> {
-    PROCESS_MANAGER.lock().get_thread_by_tid(tid)
+    published_processes().iter().find_map(|p| {
+        p.lock().threads.iter().find(|t| t.lock().tid == tid).cloned()
+    })
+}
+
+/// Attend la terminaison d'un thread (join).
+/// Un thread absent des processus est déjà terminé (éventuellement déjà libéré).
+pub fn join_thread(tid: u64) {
+    loop {
+        match get_thread_by_tid(tid) {
+            Some(thread) if thread.lock().state != ThreadState::Terminated => {
+                crate::power::cpu_idle();
+            }
+            _ => return,
+        }
+    }
 }
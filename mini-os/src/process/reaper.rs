@@ -0,0 +1,78 @@
+/// Reaper des threads terminés
+///
+/// Les threads terminés sont retirés de leur processus puis confiés au reaper.
+/// Un thread n'est réellement libéré (pile noyau, bloc TLS) qu'une fois que le
+/// reaper détient la dernière référence : il peut encore être le thread courant
+/// d'un CPU ou se trouver dans la runqueue au moment de sa terminaison.
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use crate::sync::Mutex;
+use lazy_static::lazy_static;
+use crate::process::{Thread, ThreadState, ProcessPriority};
+use crate::process::thread::alloc_tid;
+
+lazy_static! {
+    /// Threads terminés en attente de libération
+    static ref DEAD_THREADS: Mutex<Vec<Arc<Mutex<Thread>>>> = Mutex::new(Vec::new());
+}
+
+/// Confie un thread terminé au reaper
+pub fn enqueue_dead(thread: Arc<Mutex<Thread>>) {
+    thread.lock().state = ThreadState::Terminated;
+    DEAD_THREADS.lock().push(thread);
+}
+
+/// Nombre de threads terminés pas encore libérés
+pub fn pending() -> usize {
+    DEAD_THREADS.lock().len()
+}
+
+/// Libère les threads terminés dont le reaper détient la dernière référence.
+/// Retourne le nombre de threads libérés.
+pub fn reap() -> usize {
+    let dead = core::mem::take(&mut *DEAD_THREADS.lock());
+    let mut still_referenced = Vec::new();
+    let mut reaped = 0;
+
+    for thread in dead {
+        // Retirer le thread de la runqueue s'il y traîne encore
+        let tid = thread.lock().tid;
+        drop(crate::scheduler::SCHEDULER.remove_thread(tid));
+
+        match Arc::try_unwrap(thread) {
+            Ok(thread) => {
+                let mut thread = thread.into_inner();
+                thread.release_resources();
+                reaped += 1;
+            }
+            // Encore référencé (thread courant d'un CPU...) : on réessaiera plus tard
+            Err(thread) => still_referenced.push(thread),
+        }
+    }
+
+    if !still_referenced.is_empty() {
+        DEAD_THREADS.lock().extend(still_referenced);
+    }
+
+    reaped
+}
+
+/// Corps du kthread reaper
+fn reaper_main() -> ! {
+    loop {
+        reap();
+        crate::power::cpu_idle();
+    }
+}
+
+/// Crée le kthread reaper (famille PID 0) et l'ajoute au scheduler
+pub fn spawn_reaper() -> u64 {
+    let mut thread = Thread::new(alloc_tid(), 0, "kreaper", ProcessPriority::Low, 0);
+    thread.alloc_kernel_stack();
+    thread.context.rip = reaper_main as u64;
+    let tid = thread.tid;
+
+    crate::scheduler::SCHEDULER.add_thread(Arc::new(Mutex::new(thread)));
+    tid
+}
@@ -0,0 +1,177 @@
+/// Limites de ressources par processus (getrlimit/setrlimit)
+///
+/// Chaque processus porte ses limites (héritées au fork). Elles sont
+/// appliquées là où la ressource est allouée : table des descripteurs
+/// (FD_MANAGER), régions mmap et tas (MMAP_MANAGER, brk), création de
+/// threads, et temps CPU contrôlé chaque seconde depuis le tick
+/// d'horloge (SIGXCPU au-delà de la limite souple, SIGKILL au-delà de la
+/// limite dure, comme Linux).
+
+use super::signal::Signal;
+use super::PROCESS_MANAGER;
+
+/// Valeur « illimité » (RLIM_INFINITY de Linux)
+pub const RLIM_INFINITY: u64 = u64::MAX;
+
+/// Ressources limitables (numéros de Linux)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u64)]
+pub enum Resource {
+    /// Temps CPU, en secondes
+    Cpu = 0,
+    /// Threads du processus
+    NProc = 6,
+    /// Descripteurs de fichiers ouverts
+    NoFile = 7,
+    /// Espace d'adressage (tas et régions mmap), en octets
+    As = 9,
+}
+
+impl Resource {
+    pub const ALL: [Resource; 4] = [Resource::Cpu, Resource::NProc, Resource::NoFile, Resource::As];
+
+    pub fn from_u64(value: u64) -> Option<Self> {
+        Self::ALL.iter().copied().find(|r| *r as u64 == value)
+    }
+
+    /// Description, pour ulimit
+    pub fn name(&self) -> &'static str {
+        match self {
+            Resource::Cpu => "temps CPU (secondes)",
+            Resource::NProc => "threads",
+            Resource::NoFile => "fichiers ouverts",
+            Resource::As => "mémoire virtuelle (octets)",
+        }
+    }
+}
+
+/// `struct rlimit` : limite souple et limite dure
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RLimit {
+    pub cur: u64,
+    pub max: u64,
+}
+
+impl RLimit {
+    pub const UNLIMITED: RLimit = RLimit { cur: RLIM_INFINITY, max: RLIM_INFINITY };
+
+    pub const fn new(cur: u64, max: u64) -> Self {
+        Self { cur, max }
+    }
+
+    /// La limite souple est-elle dépassée par `usage` ?
+    pub fn exceeded(&self, usage: u64) -> bool {
+        self.cur != RLIM_INFINITY && usage > self.cur
+    }
+}
+
+/// Limites d'un processus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResourceLimits {
+    cpu: RLimit,
+    nproc: RLimit,
+    nofile: RLimit,
+    address_space: RLimit,
+}
+
+impl ResourceLimits {
+    /// Valeurs par défaut du noyau
+    pub const fn new() -> Self {
+        Self {
+            cpu: RLimit::UNLIMITED,
+            nproc: RLimit::new(256, 1024),
+            nofile: RLimit::new(1024, 4096),
+            address_space: RLimit::UNLIMITED,
+        }
+    }
+
+    pub fn get(&self, resource: Resource) -> RLimit {
+        match resource {
+            Resource::Cpu => self.cpu,
+            Resource::NProc => self.nproc,
+            Resource::NoFile => self.nofile,
+            Resource::As => self.address_space,
+        }
+    }
+
+    /// Change une limite ; la limite dure ne peut que baisser
+    pub fn set(&mut self, resource: Resource, limit: RLimit) -> Result<(), &'static str> {
+        if limit.cur > limit.max {
+            return Err("Limite souple supérieure à la limite dure");
+        }
+        let slot = match resource {
+            Resource::Cpu => &mut self.cpu,
+            Resource::NProc => &mut self.nproc,
+            Resource::NoFile => &mut self.nofile,
+            Resource::As => &mut self.address_space,
+        };
+        if limit.max > slot.max {
+            return Err("Limite dure non augmentable");
+        }
+        *slot = limit;
+        Ok(())
+    }
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Reporte les limites de `pid` dans les gestionnaires qui les appliquent
+pub fn apply(pid: u64, limits: &ResourceLimits) {
+    crate::fs::FD_MANAGER.lock().set_limit(pid, limits.get(Resource::NoFile).cur);
+    crate::memory::MMAP_MANAGER.lock().set_limit(pid, limits.get(Resource::As).cur);
+}
+
+/// Contrôle du temps CPU, appelé chaque seconde depuis le tick ; un
+/// verrou occupé reporte le contrôle à la seconde suivante
+pub fn check_cpu_time() {
+    let pm = match PROCESS_MANAGER.try_lock() {
+        Some(pm) => pm,
+        None => return,
+    };
+    for process in pm.processes() {
+        let mut process = match process.try_lock() {
+            Some(process) => process,
+            None => continue,
+        };
+        let limit = process.limits.get(Resource::Cpu);
+        if limit.cur == RLIM_INFINITY {
+            continue;
+        }
+        let cpu_ns: u64 = process.threads.iter()
+            .filter_map(|t| t.try_lock().map(|t| t.cpu_time))
+            .sum();
+        let seconds = cpu_ns / crate::time::NSEC_PER_SEC;
+        if limit.max != RLIM_INFINITY && seconds >= limit.max {
+            process.signal_queue.enqueue(Signal::SIGKILL);
+        } else if seconds >= limit.cur {
+            process.signal_queue.enqueue(Signal::SIGXCPU);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_set_limits() {
+        let mut limits = ResourceLimits::new();
+        assert_eq!(limits.get(Resource::NoFile), RLimit::new(1024, 4096));
+
+        assert!(limits.set(Resource::NoFile, RLimit::new(64, 128)).is_ok());
+        assert_eq!(limits.get(Resource::NoFile).cur, 64);
+        // Limite dure : baisse seulement ; souple ≤ dure
+        assert!(limits.set(Resource::NoFile, RLimit::new(64, 4096)).is_err());
+        assert!(limits.set(Resource::NoFile, RLimit::new(256, 128)).is_err());
+        assert!(limits.set(Resource::As, RLimit::new(1 << 30, RLIM_INFINITY)).is_ok());
+
+        assert!(RLimit::new(10, 20).exceeded(11));
+        assert!(!RLimit::UNLIMITED.exceeded(u64::MAX - 1));
+        assert_eq!(Resource::from_u64(7), Some(Resource::NoFile));
+    }
+}
@@ -0,0 +1,108 @@
+/// Filtrage des appels système par processus
+///
+/// Un processus peut s'imposer une liste blanche de numéros d'appels
+/// (appel `seccomp`). L'installation est unique : le filtre ne se remplace
+/// ni ne se retire, il suit l'enfant au fork et survit à exec. Un appel
+/// hors liste échoue en EPERM avant d'atteindre son gestionnaire ; `exit`
+/// reste toujours permis, sans quoi le processus ne pourrait plus finir.
+///
+/// Avec SECCOMP_AUDIT, chaque refus est aussi journalisé (klog) : pratique
+/// pour trouver les appels dont un programme de test a besoin.
+
+use crate::syscall::{syscall_name, SyscallNumber, NR_SYSCALLS};
+
+/// Journalise les appels refusés
+pub const SECCOMP_AUDIT: u32 = 1;
+
+/// Mots de 64 bits nécessaires pour couvrir tous les numéros d'appel
+pub const FILTER_WORDS: usize = NR_SYSCALLS.div_ceil(64);
+
+/// Liste blanche d'appels système
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyscallFilter {
+    allowed: [u64; FILTER_WORDS],
+    audit: bool,
+}
+
+impl SyscallFilter {
+    /// Filtre à partir d'un bitmap (bit n : appel n permis) ; les bits
+    /// au-delà de NR_SYSCALLS sont ignorés
+    pub fn from_words(words: &[u64], flags: u32) -> Result<Self, &'static str> {
+        if flags & !SECCOMP_AUDIT != 0 {
+            return Err("Drapeaux seccomp inconnus");
+        }
+        if words.len() > FILTER_WORDS {
+            return Err("Bitmap seccomp trop long");
+        }
+        let mut allowed = [0u64; FILTER_WORDS];
+        allowed[..words.len()].copy_from_slice(words);
+        if NR_SYSCALLS % 64 != 0 {
+            allowed[FILTER_WORDS - 1] &= (1 << (NR_SYSCALLS % 64)) - 1;
+        }
+        let mut filter = Self { allowed, audit: flags & SECCOMP_AUDIT != 0 };
+        filter.allow(SyscallNumber::Exit as u64);
+        Ok(filter)
+    }
+
+    /// Ajoute un appel à la liste
+    pub fn allow(&mut self, num: u64) {
+        if (num as usize) < NR_SYSCALLS {
+            self.allowed[num as usize / 64] |= 1 << (num % 64);
+        }
+    }
+
+    /// L'appel `num` est-il permis ?
+    pub fn allows(&self, num: u64) -> bool {
+        (num as usize) < NR_SYSCALLS && self.allowed[num as usize / 64] & (1 << (num % 64)) != 0
+    }
+
+    /// Les refus sont-ils journalisés ?
+    pub fn audit(&self) -> bool {
+        self.audit
+    }
+}
+
+/// Vérifie l'appel `num` du processus courant ; false s'il est refusé
+pub fn check_current(num: u64) -> bool {
+    let process = match crate::process::current_process() {
+        Some(p) => p,
+        None => return true,
+    };
+    let (pid, filter) = {
+        let process = process.lock();
+        match process.seccomp {
+            Some(filter) => (process.pid, filter),
+            None => return true,
+        }
+    };
+    if filter.allows(num) {
+        return true;
+    }
+    if filter.audit() {
+        log::warn!("seccomp: PID {} appel {} ({}) refusé", pid, num, syscall_name(num).unwrap_or("?"));
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_syscall_filter() {
+        let read = SyscallNumber::Read as u64;
+        let write = SyscallNumber::Write as u64;
+        let filter = SyscallFilter::from_words(&[(1 << read) | (1 << write)], SECCOMP_AUDIT).unwrap();
+        assert!(filter.allows(read) && filter.allows(write));
+        assert!(filter.allows(SyscallNumber::Exit as u64));
+        assert!(!filter.allows(SyscallNumber::Fork as u64));
+        assert!(!filter.allows(NR_SYSCALLS as u64));
+        assert!(filter.audit());
+
+        // Bits hors table ignorés, mots en trop et drapeaux inconnus refusés
+        let all = SyscallFilter::from_words(&[u64::MAX; FILTER_WORDS], 0).unwrap();
+        assert!(all.allows(NR_SYSCALLS as u64 - 1) && !all.allows(NR_SYSCALLS as u64));
+        assert!(SyscallFilter::from_words(&[0; FILTER_WORDS + 1], 0).is_err());
+        assert!(SyscallFilter::from_words(&[], 2).is_err());
+    }
+}
@@ -5,7 +5,7 @@
 
 use alloc::vec::Vec;
 use alloc::boxed::Box;
-use spin::Mutex;
+use crate::sync::Mutex;
 
 /// Types de signaux POSIX
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -41,6 +41,8 @@ pub enum Signal {
     SIGFPE = 8,
     /// Signal de bus error
     SIGBUS = 7,
+    /// Limite de temps CPU dépassée (RLIMIT_CPU)
+    SIGXCPU = 24,
 }
 
 impl Signal {
@@ -62,10 +64,51 @@ impl Signal {
             17 => Some(Signal::SIGCHLD),
             18 => Some(Signal::SIGCONT),
             19 => Some(Signal::SIGSTOP),
+            24 => Some(Signal::SIGXCPU),
             _ => None,
         }
     }
 
+    /// Tous les signaux, par numéro croissant
+    pub const ALL: [Signal; 16] = [
+        Signal::SIGINT, Signal::SIGQUIT, Signal::SIGILL, Signal::SIGBUS,
+        Signal::SIGFPE, Signal::SIGKILL, Signal::SIGUSR1, Signal::SIGSEGV,
+        Signal::SIGUSR2, Signal::SIGPIPE, Signal::SIGALRM, Signal::SIGTERM,
+        Signal::SIGCHLD, Signal::SIGCONT, Signal::SIGSTOP, Signal::SIGXCPU,
+    ];
+
+    /// Nom sans le préfixe SIG (« KILL », « TERM »...)
+    pub fn name(self) -> &'static str {
+        match self {
+            Signal::SIGTERM => "TERM",
+            Signal::SIGKILL => "KILL",
+            Signal::SIGSTOP => "STOP",
+            Signal::SIGCONT => "CONT",
+            Signal::SIGINT => "INT",
+            Signal::SIGQUIT => "QUIT",
+            Signal::SIGALRM => "ALRM",
+            Signal::SIGUSR1 => "USR1",
+            Signal::SIGUSR2 => "USR2",
+            Signal::SIGCHLD => "CHLD",
+            Signal::SIGPIPE => "PIPE",
+            Signal::SIGSEGV => "SEGV",
+            Signal::SIGILL => "ILL",
+            Signal::SIGFPE => "FPE",
+            Signal::SIGBUS => "BUS",
+            Signal::SIGXCPU => "XCPU",
+        }
+    }
+
+    /// Signal désigné par son numéro ou son nom, avec ou sans « SIG »,
+    /// sans distinction de casse (« 9 », « KILL », « sigkill »)
+    pub fn parse(spec: &str) -> Option<Self> {
+        if let Ok(number) = spec.parse::<u8>() {
+            return Self::from_u8(number);
+        }
+        let name = spec.strip_prefix("SIG").or_else(|| spec.strip_prefix("sig")).unwrap_or(spec);
+        Self::ALL.iter().copied().find(|s| s.name().eq_ignore_ascii_case(name))
+    }
+
     /// Retourne true si le signal peut être intercepté
     pub fn can_be_caught(self) -> bool {
         !matches!(self, Signal::SIGKILL | Signal::SIGSTOP)
@@ -76,7 +119,8 @@ impl Signal {
         match self {
             Signal::SIGTERM | Signal::SIGINT | Signal::SIGQUIT | 
             Signal::SIGKILL | Signal::SIGSEGV | Signal::SIGILL |
-            Signal::SIGFPE | Signal::SIGBUS | Signal::SIGPIPE => SignalAction::Terminate,
+            Signal::SIGFPE | Signal::SIGBUS | Signal::SIGPIPE |
+            Signal::SIGXCPU => SignalAction::Terminate,
             
             Signal::SIGSTOP => SignalAction::Stop,
             Signal::SIGCONT => SignalAction::Continue,
@@ -341,6 +385,15 @@ mod tests {
         assert_eq!(Signal::from_u8(99), None);
     }
 
+    #[test_case]
+    fn test_signal_parse() {
+        assert_eq!(Signal::parse("9"), Some(Signal::SIGKILL));
+        assert_eq!(Signal::parse("TERM"), Some(Signal::SIGTERM));
+        assert_eq!(Signal::parse("sigusr1"), Some(Signal::SIGUSR1));
+        assert_eq!(Signal::parse("SIGHUP"), None);
+        assert!(Signal::ALL.iter().all(|s| Signal::from_u8(*s as u8) == Some(*s)));
+    }
+
     #[test_case]
     fn test_signal_can_be_caught() {
         assert!(!Signal::SIGKILL.can_be_caught());
@@ -1,11 +1,26 @@
+use alloc::boxed::Box;
 use alloc::sync::{Arc, Weak};
-use spin::Mutex;
+use core::sync::atomic::{AtomicU64, Ordering};
 use x86_64::PhysAddr;
 use crate::process::{Process, ProcessPriority}; // On réutilisera ProcessPriority ou on le bougera après
+use crate::process::kstack::{KernelStack, KERNEL_STACK_SIZE};
+use crate::process::fpu::FpuState;
+use crate::process::elf::TlsTemplate;
+use crate::process::tls::TlsBlock;
 
 /// Identifiant de thread
 pub type ThreadId = u64;
 
+/// Premier TID attribuable : les TID inférieurs sont réservés aux threads idle (un par CPU)
+const FIRST_TID: ThreadId = 1000;
+
+static NEXT_TID: AtomicU64 = AtomicU64::new(FIRST_TID);
+
+/// Alloue un TID unique pour tout le système
+pub fn alloc_tid() -> ThreadId {
+    NEXT_TID.fetch_add(1, Ordering::Relaxed)
+}
+
 /// État d'un thread
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ThreadState {
@@ -24,6 +39,8 @@ pub struct ThreadContext {
     pub rflags: u64,
     pub cr3: u64, // On garde CR3 ici pour switcher rapidement
     pub privilege_level: u8,
+    /// Base FS : TCB du thread (TLS)
+    pub fs_base: u64,
 }
 
 impl Default for ThreadContext {
@@ -35,10 +52,23 @@ impl Default for ThreadContext {
             rflags: 0x202, // Interrupts enabled by default
             cr3: 0,
             privilege_level: 0,
+            fs_base: 0,
         }
     }
 }
 
+/// Instantané d'un thread (voir `ProcessSnapshot`)
+#[derive(Debug, Clone)]
+pub struct ThreadSnapshot {
+    pub tid: ThreadId,
+    pub name: alloc::string::String,
+    pub state: ThreadState,
+    pub priority: ProcessPriority,
+    pub vruntime: u64,
+    /// Temps CPU consommé (ns)
+    pub cpu_time: u64,
+}
+
 /// Structure représentant un Thread
 #[derive(Debug)]
 pub struct Thread {
@@ -48,10 +78,23 @@ pub struct Thread {
     pub state: ThreadState,
     pub context: ThreadContext,
     pub priority: ProcessPriority, // On utilise la même enum pour l'instant
-    pub kstack: Option<PhysAddr>,
-    pub vruntime: u64, // Pour CFS
+    pub kstack: Option<KernelStack>,
+    /// Bloc TLS du thread (projection de son processus), démappé par le
+    /// reaper
+    pub tls_block: Option<TlsBlock>,
+    /// Mot mis à zéro à la sortie du thread (set_tid_address, 0 : aucun)
+    pub clear_child_tid: u64,
+    /// Registres flottants sauvegardés (threads utilisateur, alloués au
+    /// premier passage sur un CPU)
+    pub fpu: Option<Arc<FpuState>>,
+    pub vruntime: u64, // Pour CFS (ns pondérées)
+    /// Temps CPU consommé (ns)
     pub cpu_time: u64,
     pub last_scheduled: u64,
+    /// Début de la période d'exécution en cours (ns, `time::now_ns`)
+    pub exec_start: u64,
+    /// Thread idle d'un CPU (jamais mis en runqueue ni migré)
+    pub is_idle: bool,
     
     // Le thread peut avoir besoin d'accéder à son processus parent (ex: files, memory)
     // Pour éviter les cycles de référence bloquants (Arc<Process> <-> Arc<Thread>),
@@ -72,9 +115,75 @@ impl Thread {
             context,
             priority,
             kstack: None,
+            tls_block: None,
+            clear_child_tid: 0,
+            fpu: None,
             vruntime: 0,
             cpu_time: 0,
             last_scheduled: 0,
+            exec_start: 0,
+            is_idle: false,
+        }
+    }
+
+    /// État flottant à installer quand le thread prend un CPU ; None pour
+    /// les kthreads (famille PID 0), le noyau n'utilisant pas la FPU
+    pub fn fpu_state(&mut self) -> Option<Arc<FpuState>> {
+        if self.pid == 0 {
+            return None;
+        }
+        Some(self.fpu.get_or_insert_with(|| Arc::new(FpuState::new())).clone())
+    }
+
+    /// Crée le thread idle du CPU `cpu_id` (famille PID 0).
+    /// Son TID vaut l'index du CPU : les TID < 1000 ne sont jamais attribués
+    /// par `alloc_tid`.
+    pub fn new_idle(cpu_id: u32) -> Self {
+        let mut thread = Self::new(
+            cpu_id as ThreadId,
+            0,
+            &alloc::format!("idle/{}", cpu_id),
+            ProcessPriority::Idle,
+            0,
+        );
+        thread.is_idle = true;
+        thread
+    }
+
+    /// Alloue une pile noyau pour le thread et y fait pointer RSP
+    pub fn alloc_kernel_stack(&mut self) {
+        let stack = KernelStack::new(KERNEL_STACK_SIZE);
+        stack.set_owner(self.tid);
+        self.context.rsp = stack.top();
+        self.kstack = Some(stack);
+    }
+
+    /// Installe un bloc TLS neuf tiré de `template` et y fait pointer FS
+    pub fn alloc_tls(&mut self, template: &TlsTemplate) -> Result<(), &'static str> {
+        let (block, fs_base) = crate::process::tls::alloc_block(template, self.pid)?;
+        self.context.fs_base = fs_base;
+        self.tls_block = Some(block);
+        Ok(())
+    }
+
+    /// Libère les ressources du thread (pile noyau, bloc TLS).
+    /// Appelé par le reaper une fois la dernière référence relâchée.
+    pub fn release_resources(&mut self) {
+        self.kstack = None;
+        self.tls_block = None;
+        self.context.fs_base = 0;
+        self.context.rsp = 0;
+    }
+
+    /// Copie l'état du thread
+    pub fn snapshot(&self) -> ThreadSnapshot {
+        ThreadSnapshot {
+            tid: self.tid,
+            name: self.name.clone(),
+            state: self.state,
+            priority: self.priority,
+            vruntime: self.vruntime,
+            cpu_time: self.cpu_time,
         }
     }
 
@@ -82,9 +191,10 @@ impl Thread {
         self.priority = priority;
     }
 
+    /// Impute `delta_time` ns d'exécution au thread
     pub fn update_vruntime(&mut self, delta_time: u64) {
         let weight = self.priority.weight();
-        self.vruntime += (delta_time * 1024) / weight;
+        self.vruntime += delta_time.saturating_mul(1024) / weight;
         self.cpu_time += delta_time;
     }
 
@@ -0,0 +1,173 @@
+/// Stockage local aux threads (TLS)
+///
+/// Modèle x86-64 (variante II) : le bloc TLS d'un thread précède
+/// immédiatement son TCB et FS pointe sur le TCB. Les variables locales
+/// sont à des décalages négatifs de FS ; le premier mot du TCB pointe sur
+/// lui-même (`mov %fs:0` donne l'adresse du thread) et %fs:0x28 porte le
+/// canari de `-fstack-protector`.
+///
+/// Le modèle vient du segment PT_TLS de l'exécutable ; le processus le
+/// garde pour les threads créés ensuite. Chaque thread en reçoit une copie
+/// (`Thread::tls_block`) dans une projection anonyme de son processus,
+/// accessible en mode utilisateur : le programme y lit et écrit ses
+/// variables directement. Le reaper la démappe avec le thread. FS est
+/// rechargé à chaque changement de thread courant par le MSR IA32_FS_BASE
+/// (CR4.FSGSBASE reste éteint, voir `cpuid::init_cpu`). Un runtime qui gère
+/// lui-même son TLS passe par l'appel `set_fs_base`.
+
+use alloc::vec;
+use x86_64::registers::model_specific::FsBase;
+use x86_64::VirtAddr;
+use crate::memory::mmap::{MAP_ANONYMOUS, MAP_PRIVATE, PROT_READ, PROT_WRITE};
+use crate::memory::uaccess::{copy_from_user, copy_to_user};
+use crate::memory::MMAP_MANAGER;
+use super::elf::TlsTemplate;
+
+/// Taille du TCB (pointeur sur soi, réserves, canari à 0x28)
+pub const TCB_SIZE: usize = 64;
+/// Décalage du canari de pile dans le TCB
+pub const TCB_STACK_GUARD: usize = 0x28;
+/// Taille maximale d'un segment PT_TLS
+pub const MAX_TLS_SIZE: u64 = 1024 * 1024;
+
+/// Bloc TLS d'un thread : projection anonyme de son processus, démappée
+/// au drop
+#[derive(Debug)]
+pub struct TlsBlock {
+    start: u64,
+    size: usize,
+    pid: u64,
+}
+
+impl TlsBlock {
+    /// Adresse (utilisateur) du bloc
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    /// Taille du bloc
+    pub fn len(&self) -> usize {
+        self.size
+    }
+}
+
+impl Drop for TlsBlock {
+    fn drop(&mut self) {
+        let mut manager = MMAP_MANAGER.lock();
+        // La projection a pu disparaître avec le processus
+        let ours = manager.find_region(VirtAddr::new(self.start))
+            .map_or(false, |r| r.start_addr.as_u64() == self.start && r.owner_pid == self.pid);
+        if ours {
+            let _ = manager.munmap(VirtAddr::new(self.start), self.size, self.pid);
+        }
+    }
+}
+
+/// Alignement du TCB et taille des données TLS qui le précèdent
+fn layout(template: &TlsTemplate) -> (usize, usize) {
+    let align = template.align.max(16);
+    (align, template.mem_size.next_multiple_of(align))
+}
+
+/// Contenu initial du bloc TLS et du TCB pour un bloc placé à `base` ;
+/// retourne les octets et la valeur de FS (adresse du TCB)
+fn build_block(template: &TlsTemplate, base: u64) -> (alloc::vec::Vec<u8>, u64) {
+    let (align, tls_size) = layout(template);
+    // Marge en tête pour aligner le TCB quelle que soit l'adresse du bloc
+    let mut block = vec![0u8; align - 1 + tls_size + TCB_SIZE];
+    let tcb = (base + tls_size as u64 + align as u64 - 1) & !(align as u64 - 1);
+    let offset = (tcb - base) as usize;
+
+    let data = offset - tls_size;
+    block[data..data + template.image.len()].copy_from_slice(&template.image);
+    block[offset..offset + 8].copy_from_slice(&tcb.to_le_bytes());
+    // Octet de poids faible nul, comme `__stack_chk_guard`
+    let guard = crate::random::get_u64() & !0xFF;
+    block[offset + TCB_STACK_GUARD..offset + TCB_STACK_GUARD + 8].copy_from_slice(&guard.to_le_bytes());
+    (block, tcb)
+}
+
+/// Alloue et initialise le bloc TLS et le TCB d'un thread de `pid` ;
+/// retourne le bloc et la valeur de FS (adresse du TCB)
+pub fn alloc_block(template: &TlsTemplate, pid: u64) -> Result<(TlsBlock, u64), &'static str> {
+    let (align, tls_size) = layout(template);
+    let size = align - 1 + tls_size + TCB_SIZE;
+    let start = MMAP_MANAGER.lock()
+        .mmap(None, size, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, None, 0, pid)
+        .map_err(|_| "Projection du bloc TLS impossible")?
+        .as_u64();
+    let block = TlsBlock { start, size, pid };
+    let (bytes, tcb) = build_block(template, start);
+    copy_to_user(start as *mut u8, &bytes).map_err(|_| "Bloc TLS inaccessible")?;
+    Ok((block, tcb))
+}
+
+/// Bloc de l'enfant `pid` d'un fork : les valeurs du parent (et son
+/// canari), dans un bloc à lui. Un FS placé hors du bloc par le runtime
+/// est gardé.
+pub fn duplicate(template: &TlsTemplate, block: &TlsBlock, fs_base: u64, pid: u64) -> Result<(TlsBlock, u64), &'static str> {
+    let (copy, tcb) = alloc_block(template, pid)?;
+    let (_, tls_size) = layout(template);
+    let old = match fs_base.checked_sub(block.start) {
+        Some(old) if old as usize >= tls_size && old as usize + TCB_SIZE <= block.size => old,
+        _ => return Ok((copy, fs_base)),
+    };
+    let mut bytes = vec![0u8; tls_size + TCB_SIZE];
+    copy_from_user(&mut bytes, (block.start + old - tls_size as u64) as *const u8).map_err(|_| "Bloc TLS inaccessible")?;
+    bytes[tls_size..tls_size + 8].copy_from_slice(&tcb.to_le_bytes());
+    copy_to_user((tcb - tls_size as u64) as *mut u8, &bytes).map_err(|_| "Bloc TLS inaccessible")?;
+    Ok((copy, tcb))
+}
+
+/// Valeur acceptable pour FS : nulle ou adresse utilisateur
+pub fn valid_fs_base(base: u64) -> bool {
+    base < crate::memory::uaccess::USER_SPACE_END
+}
+
+/// Charge FS sur le CPU courant
+pub fn load_fs_base(base: u64) {
+    FsBase::write(VirtAddr::new(base));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contents(block: &TlsBlock) -> alloc::vec::Vec<u8> {
+        let mut bytes = vec![0u8; block.len()];
+        copy_from_user(&mut bytes, block.start() as *const u8).unwrap();
+        bytes
+    }
+
+    #[test_case]
+    fn test_tls_block() {
+        let template = TlsTemplate { image: alloc::vec![1, 2, 3, 4], mem_size: 24, align: 32 };
+        let (block, fs_base) = alloc_block(&template, 9100).unwrap();
+        let base = block.start();
+        assert_eq!(fs_base % 32, 0);
+        let tcb = (fs_base - base) as usize;
+        let bytes = contents(&block);
+        // .tdata puis .tbss sous le TCB, pointeur sur soi en %fs:0
+        assert_eq!(&bytes[tcb - 32..tcb - 28], &[1, 2, 3, 4]);
+        assert!(bytes[tcb - 28..tcb].iter().all(|&b| b == 0));
+        assert_eq!(u64::from_le_bytes(bytes[tcb..tcb + 8].try_into().unwrap()), fs_base);
+
+        // Le programme lit son TCB lui-même : page utilisateur
+        let flags = crate::memory::vm::VM_MANAGER.lock().as_ref().unwrap()
+            .kernel_space().page_flags(VirtAddr::new(fs_base)).unwrap();
+        assert!(flags.contains(x86_64::structures::paging::PageTableFlags::USER_ACCESSIBLE));
+
+        let (copy, child_fs) = duplicate(&template, &block, fs_base, 9101).unwrap();
+        let child_tcb = (child_fs - copy.start()) as usize;
+        let child = contents(&copy);
+        assert_eq!(&child[child_tcb - 32..child_tcb - 28], &[1, 2, 3, 4]);
+        assert_eq!(u64::from_le_bytes(child[child_tcb..child_tcb + 8].try_into().unwrap()), child_fs);
+        assert_eq!(child[child_tcb + TCB_STACK_GUARD..child_tcb + TCB_STACK_GUARD + 8], bytes[tcb + TCB_STACK_GUARD..tcb + TCB_STACK_GUARD + 8]);
+        assert!(!valid_fs_base(0xffff_8000_0000_0000));
+
+        // Le drop rend la projection
+        let start = block.start();
+        drop(block);
+        assert!(MMAP_MANAGER.lock().find_region(VirtAddr::new(start)).is_none());
+    }
+}
@@ -0,0 +1,428 @@
+/// Réserve d'entropie du noyau et générateur aléatoire (CSPRNG)
+///
+/// Deux étages, comme `random.c` de Linux :
+/// - la réserve d'entrée mélange les échantillons et compte l'entropie
+///   créditée (en bits) : instants des interruptions (réserve rapide par
+///   CPU, versée tous les `FAST_POOL_FLUSH` événements), RDSEED/RDRAND au
+///   démarrage et à chaque réensemencement, gigue du TSC, écritures dans
+///   /dev/random (non créditées) ;
+/// - le générateur ChaCha20 tire ses sorties d'une clé de 256 bits,
+///   renouvelée par la réserve (`reseed`) dès qu'elle a accumulé
+///   `RESEED_BITS` bits, au plus tard toutes les `RESEED_INTERVAL` secondes.
+///   Chaque demande remplace aussitôt la clé par un bloc du flux
+///   (effacement rapide) : une clé volée ne révèle pas les sorties passées.
+///
+/// Le générateur est « prêt » après un premier réensemencement crédité
+/// d'au moins `RESEED_BITS` bits. `/dev/random` et `getrandom` sans
+/// `GRND_INSECURE` attendent ce moment ; `/dev/urandom` jamais.
+///
+/// `add_interrupt_randomness` est appelé en contexte d'interruption : il
+/// n'alloue pas et ne fait que tenter le verrou de la réserve.
+
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use spin::Mutex;
+use crate::cpuid::{cpu_has, Feature};
+use crate::time::tsc::rdtsc;
+
+/// Entropie nécessaire à un réensemencement (et à l'état prêt)
+pub const RESEED_BITS: u32 = 256;
+/// Entropie maximale retenue par la réserve d'entrée
+pub const POOL_BITS: u32 = 512;
+/// Réensemencement périodique, en secondes
+pub const RESEED_INTERVAL: u64 = 300;
+/// Interruptions accumulées par CPU avant versement (1 bit crédité)
+pub const FAST_POOL_FLUSH: u32 = 64;
+
+/// Drapeaux de getrandom() (valeurs de Linux)
+pub const GRND_NONBLOCK: u32 = 0x1;
+pub const GRND_RANDOM: u32 = 0x2;
+pub const GRND_INSECURE: u32 = 0x4;
+
+// ============ CHACHA20 ============
+
+fn quarter_round(x: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    x[a] = x[a].wrapping_add(x[b]);
+    x[d] = (x[d] ^ x[a]).rotate_left(16);
+    x[c] = x[c].wrapping_add(x[d]);
+    x[b] = (x[b] ^ x[c]).rotate_left(12);
+    x[a] = x[a].wrapping_add(x[b]);
+    x[d] = (x[d] ^ x[a]).rotate_left(8);
+    x[c] = x[c].wrapping_add(x[d]);
+    x[b] = (x[b] ^ x[c]).rotate_left(7);
+}
+
+/// Bloc ChaCha20 (compteur et nonce de 64 bits, variante d'origine)
+pub fn chacha20_block(key: &[u32; 8], counter: u64, nonce: u64) -> [u32; 16] {
+    let mut state = [0u32; 16];
+    state[..4].copy_from_slice(&[0x6170_7865, 0x3320_646e, 0x7962_2d32, 0x6b20_6574]);
+    state[4..12].copy_from_slice(key);
+    state[12] = counter as u32;
+    state[13] = (counter >> 32) as u32;
+    state[14] = nonce as u32;
+    state[15] = (nonce >> 32) as u32;
+
+    let mut x = state;
+    for _ in 0..10 {
+        quarter_round(&mut x, 0, 4, 8, 12);
+        quarter_round(&mut x, 1, 5, 9, 13);
+        quarter_round(&mut x, 2, 6, 10, 14);
+        quarter_round(&mut x, 3, 7, 11, 15);
+        quarter_round(&mut x, 0, 5, 10, 15);
+        quarter_round(&mut x, 1, 6, 11, 12);
+        quarter_round(&mut x, 2, 7, 8, 13);
+        quarter_round(&mut x, 3, 4, 9, 14);
+    }
+    for (word, initial) in x.iter_mut().zip(state) {
+        *word = word.wrapping_add(initial);
+    }
+    x
+}
+
+// ============ SOURCES MATÉRIELLES ============
+
+#[target_feature(enable = "rdrand")]
+unsafe fn rdrand() -> Option<u64> {
+    let mut value = 0;
+    // Échec transitoire possible : quelques essais
+    for _ in 0..10 {
+        if core::arch::x86_64::_rdrand64_step(&mut value) == 1 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+#[target_feature(enable = "rdseed")]
+unsafe fn rdseed() -> Option<u64> {
+    let mut value = 0;
+    for _ in 0..10 {
+        if core::arch::x86_64::_rdseed64_step(&mut value) == 1 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// Valeur du générateur matériel : RDSEED (source brute) de préférence
+fn hardware_random() -> Option<u64> {
+    if cpu_has(Feature::Rdseed) {
+        if let Some(value) = unsafe { rdseed() } {
+            return Some(value);
+        }
+    }
+    if cpu_has(Feature::Rdrand) {
+        return unsafe { rdrand() };
+    }
+    None
+}
+
+// ============ RÉSERVE ============
+
+struct Pool {
+    /// Réserve d'entrée
+    input: [u32; 16],
+    pos: usize,
+    /// Entropie créditée depuis le dernier réensemencement (bits)
+    entropy: u32,
+    /// Clé du générateur
+    key: [u32; 8],
+    /// Réensemencements effectués (nonce de la digestion)
+    generation: u64,
+    /// Instant du dernier réensemencement (ns)
+    last_reseed: u64,
+}
+
+impl Pool {
+    const fn new() -> Self {
+        Self { input: [0; 16], pos: 0, entropy: 0, key: [0; 8], generation: 0, last_reseed: 0 }
+    }
+
+    fn mix(&mut self, sample: u64) {
+        for word in [sample as u32, (sample >> 32) as u32] {
+            let i = self.pos;
+            let mixed = (self.input[i] ^ word).rotate_left(7).wrapping_add(self.input[(i + 5) % 16]);
+            self.input[i] = mixed;
+            self.input[(i + 11) % 16] ^= mixed.rotate_left(19);
+            self.pos = (i + 1) % 16;
+        }
+    }
+
+    fn mix_bytes(&mut self, bytes: &[u8]) {
+        for chunk in bytes.chunks(8) {
+            let mut word = [0u8; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            self.mix(u64::from_le_bytes(word));
+        }
+    }
+
+    fn credit(&mut self, bits: u32) {
+        self.entropy = (self.entropy + bits).min(POOL_BITS);
+    }
+
+    /// Digère la réserve dans une nouvelle clé ; la réserve repart à zéro
+    fn reseed(&mut self) {
+        let mut key = [0u32; 8];
+        for (i, word) in key.iter_mut().enumerate() {
+            *word = self.input[i] ^ self.input[i + 8] ^ self.key[i];
+        }
+        // RDRAND/RDSEED mélangé sans être crédité
+        for pair in key.chunks_mut(2) {
+            if let Some(value) = hardware_random() {
+                pair[0] ^= value as u32;
+                pair[1] ^= (value >> 32) as u32;
+            }
+        }
+        let nonce = (self.input[0] as u64) << 32 | self.input[15] as u64;
+        let block = chacha20_block(&key, self.generation, nonce);
+        self.key.copy_from_slice(&block[..8]);
+
+        if self.entropy >= RESEED_BITS {
+            READY.store(true, Ordering::SeqCst);
+        }
+        self.input = [0; 16];
+        self.pos = 0;
+        self.entropy = 0;
+        self.generation += 1;
+        self.last_reseed = crate::time::now_ns();
+    }
+
+    fn reseed_due(&self) -> bool {
+        let elapsed = crate::time::now_ns().saturating_sub(self.last_reseed);
+        self.entropy >= RESEED_BITS
+            || (self.entropy > 0 && elapsed >= RESEED_INTERVAL * crate::time::NSEC_PER_SEC)
+    }
+
+    /// Prend la clé courante et la remplace par le bloc 0 de son flux
+    fn take_key(&mut self) -> [u32; 8] {
+        if self.reseed_due() {
+            self.reseed();
+        }
+        let key = self.key;
+        let next = chacha20_block(&key, 0, 0);
+        self.key.copy_from_slice(&next[..8]);
+        key
+    }
+}
+
+static POOL: Mutex<Pool> = Mutex::new(Pool::new());
+/// Premier réensemencement crédité effectué
+static READY: AtomicBool = AtomicBool::new(false);
+
+/// Réserve rapide d'un CPU, alimentée par les interruptions
+struct FastPool {
+    words: [AtomicU32; 4],
+    count: AtomicU32,
+}
+
+impl FastPool {
+    const fn new() -> Self {
+        const ZERO: AtomicU32 = AtomicU32::new(0);
+        Self { words: [ZERO; 4], count: ZERO }
+    }
+}
+
+crate::percpu! {
+    static FAST_POOL: FastPool = FastPool::new();
+}
+
+/// Instant d'une interruption sur `vector` ; appelé par `irq::dispatch`
+pub fn add_interrupt_randomness(vector: u8) {
+    let fast = FAST_POOL.get();
+    let cycles = rdtsc();
+    let count = fast.count.fetch_add(1, Ordering::Relaxed) + 1;
+    let i = count as usize % 4;
+    let sample = cycles as u32 ^ ((cycles >> 32) as u32).rotate_left(13) ^ (vector as u32) << 24;
+    let mixed = (fast.words[i].load(Ordering::Relaxed) ^ sample)
+        .rotate_left(11)
+        .wrapping_add(fast.words[(i + 1) % 4].load(Ordering::Relaxed));
+    fast.words[i].store(mixed, Ordering::Relaxed);
+
+    if count % FAST_POOL_FLUSH == 0 {
+        // Réserve occupée : le prochain versement la retentera
+        if let Some(mut pool) = POOL.try_lock() {
+            for pair in fast.words.chunks(2) {
+                let word = (pair[0].load(Ordering::Relaxed) as u64) << 32 | pair[1].load(Ordering::Relaxed) as u64;
+                pool.mix(word);
+            }
+            pool.credit(1);
+        }
+    }
+}
+
+/// Données sans entropie garantie (écritures dans /dev/random, graines)
+pub fn add_device_randomness(bytes: &[u8]) {
+    POOL.lock().mix_bytes(bytes);
+}
+
+/// Gigue du TSC autour d'un calcul ChaCha20 : un bit crédité pour
+/// 16 mesures. Dernier recours sans générateur matériel.
+fn gather_jitter(bits: u32) {
+    let mut last = rdtsc();
+    let mut scratch = [0u32; 8];
+    for sample in 0..bits * 16 {
+        let block = chacha20_block(&scratch, sample as u64, last);
+        scratch.copy_from_slice(&block[..8]);
+        let now = rdtsc();
+        let mut pool = POOL.lock();
+        pool.mix(now.wrapping_sub(last) ^ (now << 32));
+        if sample % 16 == 15 {
+            pool.credit(1);
+        }
+        last = now;
+    }
+}
+
+/// Amorce la réserve : identité de la machine, générateur matériel (crédité
+/// comme le fait Linux avec `random.trust_cpu`), gigue à défaut
+pub fn init() {
+    {
+        let mut pool = POOL.lock();
+        pool.mix(rdtsc());
+        pool.mix(crate::time::now_ns());
+        pool.mix_bytes(crate::cpuid::CPU_INFO.brand().as_bytes());
+        let mut hardware = 0;
+        for _ in 0..POOL_BITS / 64 {
+            if let Some(value) = hardware_random() {
+                pool.mix(value);
+                hardware += 64;
+            }
+        }
+        pool.credit(hardware);
+    }
+    wait_ready();
+    log::info!("random: générateur prêt (ChaCha20, {})", source_name());
+}
+
+fn source_name() -> &'static str {
+    if cpu_has(Feature::Rdseed) {
+        "rdseed"
+    } else if cpu_has(Feature::Rdrand) {
+        "rdrand"
+    } else {
+        "gigue TSC"
+    }
+}
+
+/// Le générateur a-t-il reçu assez d'entropie ?
+pub fn is_ready() -> bool {
+    READY.load(Ordering::Relaxed)
+}
+
+/// Attend l'état prêt en produisant de la gigue (comme le fait Linux)
+pub fn wait_ready() {
+    while !is_ready() {
+        let missing = {
+            let mut pool = POOL.lock();
+            if pool.entropy >= RESEED_BITS {
+                pool.reseed();
+                continue;
+            }
+            RESEED_BITS - pool.entropy
+        };
+        gather_jitter(missing);
+    }
+}
+
+/// Entropie créditée en attente dans la réserve (bits)
+pub fn entropy_avail() -> u32 {
+    POOL.lock().entropy
+}
+
+/// Remplit `buf` depuis le générateur, prêt ou non
+pub fn fill_bytes(buf: &mut [u8]) {
+    // Clé renouvelée sous le verrou, flux produit en dehors
+    let key = POOL.lock().take_key();
+    for (counter, chunk) in buf.chunks_mut(64).enumerate() {
+        let block = chacha20_block(&key, counter as u64 + 1, 0);
+        let mut bytes = [0u8; 64];
+        for (out, word) in bytes.chunks_mut(4).zip(block) {
+            out.copy_from_slice(&word.to_le_bytes());
+        }
+        chunk.copy_from_slice(&bytes[..chunk.len()]);
+    }
+}
+
+pub fn get_u64() -> u64 {
+    let mut bytes = [0u8; 8];
+    fill_bytes(&mut bytes);
+    u64::from_le_bytes(bytes)
+}
+
+pub fn get_u32() -> u32 {
+    get_u64() as u32
+}
+
+/// Cœur de getrandom() : `Err(())` si le générateur n'est pas prêt et que
+/// l'appelant ne veut pas attendre (EAGAIN)
+pub fn getrandom(buf: &mut [u8], flags: u32) -> Result<usize, ()> {
+    if !is_ready() && flags & GRND_INSECURE == 0 {
+        if flags & GRND_NONBLOCK != 0 {
+            return Err(());
+        }
+        wait_ready();
+    }
+    fill_bytes(buf);
+    Ok(buf.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_chacha20_block() {
+        // RFC 7539, 2.3.2 (compteur 1, nonce 00000009 0000004a 00000000)
+        let mut key = [0u32; 8];
+        for (i, word) in key.iter_mut().enumerate() {
+            let base = 4 * i as u32;
+            *word = u32::from_le_bytes([base as u8, base as u8 + 1, base as u8 + 2, base as u8 + 3]);
+        }
+        let block = chacha20_block(&key, 1 | 0x0900_0000 << 32, 0x4a00_0000);
+        assert_eq!(block[0], 0xe4e7_f110);
+        assert_eq!(block[1], 0x1559_3bd1);
+        assert_eq!(block[15], 0x4e3c_50a2);
+    }
+
+    #[test_case]
+    fn test_pool_reseed_and_output() {
+        let mut pool = Pool::new();
+        pool.mix_bytes(b"graine de test");
+        pool.credit(POOL_BITS + 10);
+        assert_eq!(pool.entropy, POOL_BITS);
+        assert!(pool.reseed_due());
+        let before = pool.key;
+        pool.reseed();
+        assert_ne!(pool.key, before);
+        assert_eq!((pool.entropy, pool.generation), (0, 1));
+
+        // Effacement rapide : deux demandes, deux clés
+        let first = pool.take_key();
+        assert_ne!(first, pool.take_key());
+
+        let (mut a, mut b) = ([0u8; 100], [0u8; 100]);
+        fill_bytes(&mut a);
+        fill_bytes(&mut b);
+        assert_ne!(a, b);
+        assert_eq!(getrandom(&mut a[..10], GRND_INSECURE), Ok(10));
+    }
+
+    #[test_case]
+    fn test_getrandom_syscall_bounds() {
+        use crate::syscall::{SyscallError, SyscallHandler, SyscallNumber, SyscallResult};
+        let handler = SyscallHandler::new();
+
+        // Plusieurs morceaux copiés dans le tampon
+        let mut buf = [0u8; 600];
+        let args = [buf.as_mut_ptr() as u64, buf.len() as u64, GRND_INSECURE as u64];
+        assert!(matches!(handler.handle(SyscallNumber::GetRandom as u64, &args), SyscallResult::Success(600)));
+        assert!(buf[512..].iter().any(|&byte| byte != 0));
+
+        // Taille démesurée débordant de l'espace utilisateur : EFAULT, sans allocation
+        let end = crate::memory::uaccess::USER_SPACE_END;
+        let args = [end - 0x1000, u64::MAX, GRND_INSECURE as u64];
+        assert!(matches!(
+            handler.handle(SyscallNumber::GetRandom as u64, &args),
+            SyscallResult::Error(SyscallError::BadAddress)
+        ));
+    }
+}
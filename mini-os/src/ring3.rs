@@ -4,15 +4,32 @@
 /// - Configurer les segments Ring 3
 /// - Gérer le changement de contexte Ring 0 → Ring 3
 /// - Gérer les appels système (syscalls) depuis Ring 3
+///
+/// `run_user_program` est le chemin complet noyau → ring 3 → noyau :
+/// image chargée en R-X, pile NX, passage en ring 3 par `iretq`, appels
+/// système par SYSCALL (`syscall::entry`) et retour au lanceur à `exit()`
+/// ou sur faute du programme.
+///
+/// La pile de départ suit l'ABI de `process::exec` (argc, argv, envp,
+/// auxv) ; `execve` remplace l'image et reprend en ring 3 sur la nouvelle
+/// entrée au retour de l'appel système (`take_exec_entry`).
 
 use x86_64::VirtAddr;
+use x86_64::structures::paging::Page;
+use core::arch::global_asm;
 use lazy_static::lazy_static;
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicU64, Ordering};
+use crate::sync::Mutex;
+use crate::gdt::SELECTORS;
+use crate::process::Thread;
+use crate::process::exec::{self, ExecArgs};
 
 /// Taille de la pile noyau (16 KB)
 const KERNEL_STACK_SIZE: usize = 16 * 1024;
 
 /// Taille de la pile utilisateur (64 KB)
-const USER_STACK_SIZE: usize = 64 * 1024;
+pub const USER_STACK_SIZE: usize = 64 * 1024;
 
 /// Sélecteurs de segment pour Ring 3
 pub struct SegmentSelectors {
@@ -30,10 +47,10 @@ impl SegmentSelectors {
     /// Crée les sélecteurs de segment par défaut
     pub fn new() -> Self {
         Self {
-            kernel_code: 0x08,  // Index 1 << 3
-            kernel_data: 0x10,  // Index 2 << 3
-            user_code: 0x18 | 3,   // Index 3 << 3 | RPL 3
-            user_data: 0x20 | 3,   // Index 4 << 3 | RPL 3
+            kernel_code: SELECTORS.kernel_code.0,
+            kernel_data: SELECTORS.kernel_data.0,
+            user_code: SELECTORS.user_code.0,
+            user_data: SELECTORS.user_data.0,
         }
     }
 }
@@ -53,7 +70,7 @@ impl Ring3Manager {
     
     /// Charge les sélecteurs de segment
     pub fn load(&self) {
-        // Les sélecteurs sont déjà configurés dans la GDT du bootloader
+        // Les sélecteurs sont installés par crate::gdt::init
         // Cette fonction est un placeholder pour la compatibilité
     }
     
@@ -157,6 +174,247 @@ pub unsafe fn switch_to_ring0() {
     // Ici, on peut ajouter du code de nettoyage si nécessaire
 }
 
+/// Adresse de chargement des programmes (entrée PML4 n°1, hors de l'identité)
+pub const USER_CODE_BASE: u64 = 0x0000_0080_0000_0000;
+/// Sommet de la pile utilisateur
+pub const USER_STACK_TOP: u64 = 0x0000_0080_4000_0000;
+
+global_asm!(
+    // rdi = rip, rsi = rsp utilisateur, rdx = &launcher_rsp, rcx = cs, r8 = ss
+    ".global ring3_enter",
+    "ring3_enter:",
+    "    push rbp", "    push rbx", "    push r12", "    push r13",
+    "    push r14", "    push r15",
+    "    mov [rdx], rsp",
+    "    push r8",
+    "    push rsi",
+    "    push 0x202",
+    "    push rcx",
+    "    push rdi",
+    "    mov ax, r8w",
+    "    mov ds, ax",
+    "    mov es, ax",
+    // Aucun registre noyau ne fuit vers le ring 3
+    "    xor eax, eax", "    xor ebx, ebx", "    xor ecx, ecx", "    xor edx, edx",
+    "    xor esi, esi", "    xor edi, edi", "    xor ebp, ebp",
+    "    xor r8d, r8d", "    xor r9d, r9d", "    xor r10d, r10d", "    xor r11d, r11d",
+    "    xor r12d, r12d", "    xor r13d, r13d", "    xor r14d, r14d", "    xor r15d, r15d",
+    // GS du programme ; IRETQ rétablit IF
+    "    cli",
+    "    swapgs",
+    "    iretq",
+    // rdi = RSP sauvé par ring3_enter, rsi = code de sortie
+    ".global ring3_return",
+    "ring3_return:",
+    "    mov rsp, rdi",
+    "    mov rax, rsi",
+    "    mov cx, 0x10",
+    "    mov ds, cx",
+    "    mov es, cx",
+    "    pop r15", "    pop r14", "    pop r13", "    pop r12",
+    "    pop rbx", "    pop rbp",
+    "    ret",
+);
+
+extern "C" {
+    fn ring3_enter(rip: u64, rsp: u64, launcher_rsp: *mut u64, cs: u64, ss: u64) -> i64;
+    fn ring3_return(rsp: u64, status: i64) -> !;
+}
+
+/// Exécute une image binaire à plat en ring 3 jusqu'à son `exit()`
+///
+/// L'image est chargée en lecture/exécution à `USER_CODE_BASE`, la pile
+/// (lecture/écriture, non exécutable) sous `USER_STACK_TOP` à un décalage
+/// aléatoire (`process::aslr`), garnie de
+/// `args`. Les appels système et interruptions du programme s'exécutent
+/// sur `kernel_stack`. Retourne le code de sortie (-11 si le programme a
+/// fauté).
+pub fn run_user_program(image: &[u8], args: &ExecArgs, kernel_stack: u64) -> Result<i32, &'static str> {
+    let area = crate::gdt::syscall_area().ok_or("GDT non initialisée")?;
+    if area.launcher_rsp != 0 {
+        return Err("Programme ring 3 déjà en cours sur ce CPU");
+    }
+    let launcher_slot: *mut u64 = &mut area.launcher_rsp;
+    let previous_stack = area.kernel_rsp;
+
+    let cred = crate::process::current_process().map(|p| p.lock().cred.clone()).unwrap_or_default();
+    let top = crate::process::aslr::stack_top(USER_STACK_TOP);
+    let rsp = match map_user_image(image).and_then(|_| map_user_stack(top, args, &exec::auxv(USER_CODE_BASE, &cred))) {
+        Ok(rsp) => rsp,
+        Err(e) => {
+            unmap_user_image(image.len());
+            return Err(e);
+        }
+    };
+
+    crate::gdt::set_kernel_stack(kernel_stack);
+    let interrupts = x86_64::instructions::interrupts::are_enabled();
+    let status = unsafe {
+        ring3_enter(
+            USER_CODE_BASE,
+            rsp,
+            launcher_slot,
+            SELECTORS.user_code.0 as u64,
+            SELECTORS.user_data.0 as u64,
+        )
+    };
+    // De retour via ring3_return (interruptions masquées)
+    unsafe { *launcher_slot = 0 };
+    crate::gdt::set_kernel_stack(previous_stack);
+    if interrupts {
+        x86_64::instructions::interrupts::enable();
+    }
+
+    unmap_user_image(image.len());
+    Ok(status as i32)
+}
+
+/// Un programme ring 3 est-il en cours sur ce CPU ?
+pub fn in_user_program() -> bool {
+    crate::gdt::syscall_area().map_or(false, |area| area.launcher_rsp != 0)
+}
+
+crate::percpu! {
+    /// Entrée et pile du programme chargé par execve (0 : aucun)
+    static EXEC_ENTRY: [AtomicU64; 2] = [AtomicU64::new(0), AtomicU64::new(0)];
+}
+
+/// Note l'entrée du programme qui remplace l'image courante ; appliquée
+/// au retour de l'appel système
+pub fn set_exec_entry(rip: u64, rsp: u64) {
+    let entry = EXEC_ENTRY.get();
+    entry[0].store(rip, Ordering::SeqCst);
+    entry[1].store(rsp, Ordering::SeqCst);
+}
+
+/// Reprend (rip, rsp) noté par `set_exec_entry`
+pub fn take_exec_entry() -> Option<(u64, u64)> {
+    let entry = EXEC_ENTRY.get();
+    let rip = entry[0].swap(0, Ordering::SeqCst);
+    let rsp = entry[1].swap(0, Ordering::SeqCst);
+    if rip == 0 { None } else { Some((rip, rsp)) }
+}
+
+/// Abandonne le programme ring 3 en cours et reprend son lanceur
+///
+/// Sans lanceur en attente sur ce CPU, ne fait rien et retourne.
+pub fn return_to_launcher(status: i32) {
+    if let Some(area) = crate::gdt::syscall_area() {
+        let rsp = area.launcher_rsp;
+        if rsp != 0 {
+            area.launcher_rsp = 0;
+            unsafe { ring3_return(rsp, status as i64) };
+        }
+    }
+}
+
+/// Lance un programme ring 3 dans un nouveau processus enfant de `parent_pid`
+///
+/// Le lanceur prête le CPU au thread principal de l'enfant le temps de
+/// l'exécution, puis reprend son propre thread. L'enfant hérite de
+/// l'environnement du parent, sauf si `args.envp` en fournit un.
+/// Retourne le code de sortie.
+pub fn spawn_user_program(parent_pid: u64, name: &str, image: &[u8], args: &ExecArgs) -> Result<i32, &'static str> {
+    use crate::process::{ProcessState, PROCESS_MANAGER, get_process_by_pid};
+
+    let (pid, thread) = PROCESS_MANAGER.lock().create_user_process(parent_pid, name)?;
+    let mut args = args.clone();
+    if let Some(process) = get_process_by_pid(pid) {
+        let mut process = process.lock();
+        if args.envp.is_empty() {
+            args.envp = process.env.clone();
+        }
+        process.args = args.argv.clone();
+        process.env = args.envp.clone();
+    }
+    let kernel_stack = thread.lock().kstack.as_ref().map(|stack| stack.top()).ok_or("Pas de pile noyau")?;
+
+    let previous: Option<Arc<Mutex<Thread>>> = crate::scheduler::current_thread();
+    crate::scheduler::set_current_thread(Some(thread));
+    let result = run_user_program(image, &args, kernel_stack);
+    crate::scheduler::set_current_thread(previous);
+
+    // Faute ou échec du chargement : exit() n'a pas été appelé
+    let terminated = get_process_by_pid(pid).map_or(true, |p| p.lock().state == ProcessState::Terminated);
+    if !terminated {
+        let _ = PROCESS_MANAGER.lock().terminate_process(pid, result.unwrap_or(-1));
+    }
+    let _ = crate::fs::FD_MANAGER.lock().remove_table(pid);
+    result
+}
+
+fn map_user_image(image: &[u8]) -> Result<(), &'static str> {
+    use crate::memory::vm::{protection_flags, VM_MANAGER};
+
+    let mut vm = VM_MANAGER.lock();
+    let space = vm.as_mut().ok_or("Mémoire virtuelle non initialisée")?.kernel_space_mut();
+
+    let code_flags = protection_flags(false, true, true);
+    for (i, chunk) in image.chunks(4096).enumerate() {
+        let page = Page::containing_address(VirtAddr::new(USER_CODE_BASE + i as u64 * 4096));
+        space
+            .map_page_filled(page, code_flags, |bytes| {
+                bytes.fill(0);
+                bytes[..chunk.len()].copy_from_slice(chunk);
+            })
+            .map_err(|_| "Échec du mappage du code utilisateur")?;
+    }
+    Ok(())
+}
+
+/// Mappe une pile utilisateur neuve de `USER_STACK_SIZE` octets sous `top`
+/// (l'ancienne est libérée) et y dépose argc, argv, envp et `auxv`.
+/// Retourne le pointeur de pile initial.
+pub fn map_user_stack(top: u64, args: &ExecArgs, auxv: &[(u64, u64)]) -> Result<u64, &'static str> {
+    use crate::memory::vm::{protection_flags, VM_MANAGER};
+
+    let stack = exec::build_stack(top, args, auxv)?;
+    // Une page au moins reste libre sous les arguments
+    if stack.bytes.len() + 4096 > USER_STACK_SIZE {
+        return Err("Argument list too long");
+    }
+
+    let mut vm = VM_MANAGER.lock();
+    let space = vm.as_mut().ok_or("Mémoire virtuelle non initialisée")?.kernel_space_mut();
+    let bottom = top - USER_STACK_SIZE as u64;
+    space.unmap_range(VirtAddr::new(bottom), USER_STACK_SIZE as u64);
+
+    let stack_flags = protection_flags(true, false, true);
+    for addr in (bottom..top).step_by(4096) {
+        space
+            .map_page_filled(Page::containing_address(VirtAddr::new(addr)), stack_flags, |bytes| {
+                bytes.fill(0);
+                // Portion du contenu initial tombant dans cette page
+                let start = addr.max(stack.rsp);
+                let end = (addr + 4096).min(top);
+                if start < end {
+                    let src = (start - stack.rsp) as usize;
+                    let dst = (start - addr) as usize;
+                    bytes[dst..dst + (end - start) as usize].copy_from_slice(&stack.bytes[src..src + (end - start) as usize]);
+                }
+            })
+            .map_err(|_| "Échec du mappage de la pile utilisateur")?;
+    }
+    Ok(stack.rsp)
+}
+
+fn unmap_user_image(image_len: usize) {
+    if let Some(vm) = crate::memory::vm::VM_MANAGER.lock().as_mut() {
+        let space = vm.kernel_space_mut();
+        let code_size = (image_len as u64 + 4095) & !4095;
+        space.unmap_range(VirtAddr::new(USER_CODE_BASE), code_size);
+    }
+    unmap_user_stack(USER_STACK_TOP);
+}
+
+/// Libère la pile placée sous `window_top`, où que l'ASLR l'ait tirée
+pub fn unmap_user_stack(window_top: u64) {
+    let size = crate::process::aslr::STACK_RANDOM_RANGE + USER_STACK_SIZE as u64;
+    if let Some(vm) = crate::memory::vm::VM_MANAGER.lock().as_mut() {
+        vm.kernel_space_mut().unmap_range(VirtAddr::new(window_top - size), size);
+    }
+}
+
 lazy_static! {
     /// Gestionnaire global Ring 3
     pub static ref RING3_MANAGER: Ring3Manager = Ring3Manager::new();
@@ -99,6 +99,41 @@ pub fn syscall_getpid() -> u32 {
     result
 }
 
+// Programme de test exécuté en ring 3 (image à plat, code indépendant
+// de la position) : agrandit son tas de 4 Kio par sbrk, y écrit 42
+// (faute → page à zéro servie à la demande), relit la valeur et la
+// retourne comme code de sortie.
+core::arch::global_asm!(
+    ".section .rodata.ring3_hello, \"a\"",
+    ".global ring3_hello_start",
+    ".global ring3_hello_end",
+    "ring3_hello_start:",
+    "    mov eax, 34",              // Sbrk
+    "    mov edi, 4096",
+    "    syscall",
+    "    mov byte ptr [rax], 42",
+    "    movzx edi, byte ptr [rax]",
+    "    mov eax, 0",               // Exit
+    "    syscall",
+    "    ud2",
+    "ring3_hello_end:",
+    ".previous",
+);
+
+extern "C" {
+    static ring3_hello_start: u8;
+    static ring3_hello_end: u8;
+}
+
+/// Image du programme de test ring 3 (code de sortie attendu : 42)
+pub fn hello_image() -> &'static [u8] {
+    unsafe {
+        let start = core::ptr::addr_of!(ring3_hello_start);
+        let end = core::ptr::addr_of!(ring3_hello_end);
+        core::slice::from_raw_parts(start, end as usize - start as usize)
+    }
+}
+
 /// Crée un contexte Ring 3 pour un programme utilisateur
 pub fn create_user_context(entry_point: u64, user_stack: u64) -> Ring3Context {
     Ring3Context::new(entry_point, user_stack)
@@ -7,7 +7,7 @@
 
 use alloc::sync::Arc;
 use alloc::vec::Vec;
-use spin::Mutex;
+use crate::sync::Mutex;
 use crate::process::{Thread, ThreadState, ProcessPriority};
 
 /// Runqueue CFS - file d'attente des threads prêts
@@ -154,11 +154,17 @@ impl CFSScheduler {
     /// Sélectionne et exécute le prochain thread
     pub fn schedule(&mut self, current_thread: Option<Arc<Mutex<Thread>>>) -> Option<Arc<Mutex<Thread>>> {
         // Remettre le thread actuel dans la runqueue s'il est toujours prêt
+        // (le thread idle n'y entre jamais, il redevient simplement Ready)
         if let Some(current) = current_thread {
-            let state = current.lock().state;
+            let (state, is_idle) = {
+                let th = current.lock();
+                (th.state, th.is_idle)
+            };
             if state == ThreadState::Ready || state == ThreadState::Running {
                 current.lock().state = ThreadState::Ready;
-                self.runqueue.enqueue(current);
+                if !is_idle {
+                    self.runqueue.enqueue(current);
+                }
             }
         }
 
@@ -177,6 +183,19 @@ impl CFSScheduler {
         }
     }
 
+    /// Cède le CPU : le thread courant est replacé derrière les threads prêts
+    /// de même vruntime (son vruntime est relevé au minimum de la runqueue si
+    /// besoin), puis le prochain thread est sélectionné.
+    pub fn yield_thread(&mut self, current: Arc<Mutex<Thread>>) -> Option<Arc<Mutex<Thread>>> {
+        {
+            let mut th = current.lock();
+            if !th.is_idle && !self.runqueue.is_empty() {
+                th.vruntime = core::cmp::max(th.vruntime, self.runqueue.min_vruntime());
+            }
+        }
+        self.schedule(Some(current))
+    }
+
     /// Nettoie les threads terminés de la runqueue
     fn cleanup_terminated_threads(&mut self) {
         let mut i = 0;
@@ -219,5 +238,50 @@ impl Default for CFSScheduler {
 
 #[cfg(test)]
 mod tests {
-    // Tests disabled during SMP refactoring
+    use super::*;
+
+    #[test_case]
+    fn test_empty_runqueue_yields_none() {
+        let mut cfs = CFSScheduler::new();
+        assert!(cfs.schedule(None).is_none());
+    }
+
+    #[test_case]
+    fn test_idle_thread_never_enqueued() {
+        let mut cfs = CFSScheduler::new();
+        let idle = Arc::new(Mutex::new(Thread::new_idle(0)));
+        idle.lock().state = ThreadState::Running;
+
+        assert!(cfs.schedule(Some(idle.clone())).is_none());
+        assert_eq!(cfs.thread_count(), 0);
+        assert_eq!(idle.lock().state, ThreadState::Ready);
+    }
+
+    #[test_case]
+    fn test_yield_goes_behind_equal_vruntime_peer() {
+        let mut cfs = CFSScheduler::new();
+        let a = Arc::new(Mutex::new(Thread::new(9001, 1, "a", ProcessPriority::Normal, 0)));
+        let b = Arc::new(Mutex::new(Thread::new(9002, 1, "b", ProcessPriority::Normal, 0)));
+        a.lock().vruntime = 5;
+        b.lock().vruntime = 10;
+        cfs.add_thread(b.clone());
+        a.lock().state = ThreadState::Running;
+
+        // Sans yield, `a` (vruntime plus faible) serait reprogrammé
+        let next = cfs.yield_thread(a.clone()).unwrap();
+        assert_eq!(next.lock().tid, 9002);
+        assert_eq!(a.lock().vruntime, 10);
+        assert_eq!(cfs.thread_count(), 1);
+    }
+
+    #[test_case]
+    fn test_yield_alone_keeps_running() {
+        let mut cfs = CFSScheduler::new();
+        let a = Arc::new(Mutex::new(Thread::new(9003, 1, "a", ProcessPriority::Normal, 0)));
+        a.lock().state = ThreadState::Running;
+
+        let next = cfs.yield_thread(a.clone()).unwrap();
+        assert_eq!(next.lock().tid, 9003);
+        assert_eq!(next.lock().state, ThreadState::Running);
+    }
 }
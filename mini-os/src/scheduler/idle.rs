@@ -0,0 +1,52 @@
+/// Module Idle
+///
+/// Chaque CPU possède un thread idle dédié (famille PID 0) de priorité minimale.
+/// Il n'est jamais inséré dans la runqueue ni migré : le scheduler le retourne
+/// directement lorsqu'aucun autre thread n'est prêt, ce qui garantit que
+/// `schedule()` a toujours un thread courant valide.
+
+use alloc::sync::Arc;
+use crate::sync::Mutex;
+use crate::process::{Thread, ThreadState};
+
+/// PID réservé aux threads idle
+pub const IDLE_PID: u64 = 0;
+
+/// Crée le thread idle du CPU donné
+pub fn create_idle_thread(cpu_id: u32) -> Arc<Mutex<Thread>> {
+    let mut thread = Thread::new_idle(cpu_id);
+    thread.state = ThreadState::Ready;
+    Arc::new(Mutex::new(thread))
+}
+
+#[cfg(not(feature = "smp"))]
+lazy_static::lazy_static! {
+    /// Thread idle de l'unique CPU (mode non-SMP)
+    static ref BOOT_IDLE_THREAD: Arc<Mutex<Thread>> = create_idle_thread(0);
+}
+
+/// Retourne le thread idle du CPU courant
+pub fn idle_thread() -> Arc<Mutex<Thread>> {
+    #[cfg(feature = "smp")]
+    {
+        match crate::smp::percpu::get_idle_thread() {
+            Some(idle) => idle,
+            // CPU pas encore enregistré : on fabrique un idle temporaire pour le BSP
+            None => create_idle_thread(0),
+        }
+    }
+    #[cfg(not(feature = "smp"))]
+    {
+        BOOT_IDLE_THREAD.clone()
+    }
+}
+
+/// Indique si le thread donné est un thread idle
+pub fn is_idle(thread: &Arc<Mutex<Thread>>) -> bool {
+    thread.lock().is_idle
+}
+
+/// Corps de la boucle idle : met le CPU en veille jusqu'à la prochaine interruption
+pub fn idle_step() {
+    crate::power::cpu_idle();
+}
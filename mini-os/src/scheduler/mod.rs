@@ -1,75 +1,246 @@
 use alloc::sync::Arc;
-use spin::Mutex;
+use core::cell::RefCell;
+use crate::percpu::{self, PerCpu, Stat};
+use crate::sync::{Mutex, SpinLockIrqSave};
 use crate::process::{Thread, ProcessManager}; // ProcessManager peut être utile pour debug ou autre
-use core::sync::atomic::{AtomicUsize, Ordering};
+use core::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use core::arch::asm;
 
 pub mod cfs;
 pub use cfs::{CFSScheduler, CFSRunqueue};
 
+pub mod idle;
+
 // pub mod policy;
 // pub use policy::{SchedulingPolicy, PolicyStats, CFSPolicy, RoundRobinPolicy}; // On simplifie pour l'instant
 
 // pub mod config;
 // pub use config::{SchedulerConfig, SchedulerPolicyType, SCHEDULER_CONFIG, switch_scheduler_policy, get_current_policy};
 
+/// Fréquence supposée de l'interruption timer (ticks par seconde)
+pub const TIMER_HZ: u64 = 100;
+
+/// Nombre de ticks d'horloge depuis le démarrage
+static TICKS: AtomicU64 = AtomicU64::new(0);
+
+/// Retourne le nombre de ticks écoulés depuis le démarrage
+pub fn ticks() -> u64 {
+    TICKS.load(Ordering::Relaxed)
+}
+
+/// Virgule fixe de la charge moyenne (1.0 = 1 << 11, comme Linux)
+pub const LOAD_FIXED_1: u64 = 1 << 11;
+/// Période d'échantillonnage de la charge (5 s)
+const LOAD_FREQ: u64 = 5 * TIMER_HZ;
+/// exp(-5/60), exp(-5/300) et exp(-5/900) en virgule fixe
+const LOAD_EXP: [u64; 3] = [1884, 2014, 2037];
+
+/// Charge moyenne sur 1, 5 et 15 minutes (virgule fixe)
+static LOAD_AVG: [AtomicU64; 3] = [AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0)];
+
+/// Moyenne exponentielle : `load` vieillit d'un facteur `exp`, `active`
+/// threads prêts ou en cours comptent pour le reste
+fn calc_load(load: u64, exp: u64, active: u64) -> u64 {
+    (load * exp + active * LOAD_FIXED_1 * (LOAD_FIXED_1 - exp)) / LOAD_FIXED_1
+}
+
+/// Charge moyenne sur 1, 5 et 15 minutes, en virgule fixe (`LOAD_FIXED_1`)
+pub fn load_average() -> [u64; 3] {
+    core::array::from_fn(|i| LOAD_AVG[i].load(Ordering::Relaxed))
+}
+
+/// Durée par défaut (en ticks) après laquelle un thread devrait céder le CPU
+pub const SCHED_SLICE_TICKS: u64 = 4;
+
+/// Tranche en vigueur (option de démarrage `sched_slice=`)
+static SLICE_TICKS: AtomicU64 = AtomicU64::new(SCHED_SLICE_TICKS);
+
+/// Tranche de temps courante, en ticks
+pub fn slice_ticks() -> u64 {
+    SLICE_TICKS.load(Ordering::Relaxed)
+}
+
+/// Change la tranche de temps (au moins un tick)
+pub fn set_slice_ticks(ticks: u64) {
+    SLICE_TICKS.store(ticks.max(1), Ordering::Relaxed);
+}
+
+/// Positionné par le timer quand le thread courant a épuisé sa tranche
+static NEED_RESCHED: AtomicBool = AtomicBool::new(false);
+
+/// Indique si le thread courant devrait céder le CPU
+pub fn need_resched() -> bool {
+    NEED_RESCHED.load(Ordering::Relaxed)
+}
+
+/// Point de préemption des longues boucles noyau : indique si le timer a
+/// demandé un changement de thread. Le thread courant n'est jamais changé
+/// ici (l'appel système en cours appartient à son appelant) : NEED_RESCHED
+/// reste levé et le changement se fait au prochain passage dans `run()`.
+pub fn cond_resched() -> bool {
+    need_resched()
+}
+
+/// Index logique du CPU courant (0 pour le BSP)
+pub fn current_cpu() -> u32 {
+    percpu::cpu_index() as u32
+}
+
+/// Nœud NUMA du CPU courant (pas de topologie NUMA : un seul nœud)
+pub fn current_numa_node() -> u32 {
+    0
+}
+
+/// Impute au thread le temps écoulé depuis `exec_start`. Le vruntime
+/// avance en temps réel (source d'horloge), pas en nombre de ticks.
+fn account(th: &mut Thread, now: u64) {
+    let delta = now.saturating_sub(th.exec_start);
+    th.exec_start = now;
+    if th.is_idle {
+        // L'idle n'accumule pas de vruntime : il n'est jamais en compétition
+        th.cpu_time += delta;
+    } else {
+        th.update_vruntime(delta);
+    }
+}
+
 /// Planificateur de tâches
 pub struct Scheduler {
-    cfs: Mutex<CFSScheduler>,
+    /// Runqueue de chaque CPU, prise aussi depuis les interruptions (réveils)
+    cfs: PerCpu<SpinLockIrqSave<CFSScheduler>>,
 }
 
 impl Scheduler {
     /// Crée un nouveau planificateur
     pub fn new() -> Self {
         Self {
-            cfs: Mutex::new(CFSScheduler::new()),
+            cfs: PerCpu::from_fn(|_| SpinLockIrqSave::new(CFSScheduler::new())),
         }
     }
     
-    /// Ajoute un thread au planificateur
+    /// Ajoute un thread à la runqueue du CPU courant
     pub fn add_thread(&self, thread: Arc<Mutex<Thread>>) {
-        self.cfs.lock().add_thread(thread);
+        // Le thread idle n'entre jamais dans la runqueue
+        if idle::is_idle(&thread) {
+            return;
+        }
+        self.cfs.get().lock().add_thread(thread);
+    }
+
+    /// Retire un thread de la runqueue où il se trouve
+    pub fn remove_thread(&self, tid: u64) -> Option<Arc<Mutex<Thread>>> {
+        self.cfs.iter().find_map(|cfs| cfs.lock().remove_thread(tid))
+    }
+
+    /// Threads prêts, toutes runqueues confondues
+    pub fn runnable(&self) -> usize {
+        self.cfs.iter().map(|cfs| cfs.lock().thread_count()).sum()
+    }
+
+    /// Prend un thread prêt dans la runqueue d'un autre CPU. Les verrous
+    /// occupés sont sautés : deux CPU qui se volent mutuellement ne
+    /// s'attendent jamais.
+    fn steal(&self) -> Option<Arc<Mutex<Thread>>> {
+        let cpu = percpu::cpu_index();
+        let count = percpu::cpu_count();
+        (1..count)
+            .map(|i| (cpu + i) % count)
+            .find_map(|other| self.cfs.get_cpu(other).try_lock()?.schedule(None))
     }
 
     /// Appelé à chaque tick d'horloge
     pub fn tick(&self) {
+        let now = TICKS.fetch_add(1, Ordering::Relaxed) + 1;
+        percpu::count(Stat::Tick);
+        crate::vdso::tick(now);
+        crate::workqueue::timer_tick(now);
+        
         // Update vruntime of current thread
+        let mut running = 0;
         if let Some(current) = self.current_thread() {
             let mut th = current.lock();
-            th.update_vruntime(1);
+            account(&mut th, crate::time::now_ns());
+            if !th.is_idle && ticks().saturating_sub(th.last_scheduled) >= slice_ticks() {
+                NEED_RESCHED.store(true, Ordering::Relaxed);
+            }
+            running = if th.is_idle { 0 } else { 1 };
             drop(th);
         }
+
+        // Charge moyenne : runqueues occupées sautées (le tick peut avoir
+        // interrompu leur détenteur)
+        if now % LOAD_FREQ == 0 {
+            let queued: usize = self.cfs.iter().filter_map(|cfs| cfs.try_lock().map(|c| c.thread_count())).sum();
+            let active = queued as u64 + running;
+            for (avg, &exp) in LOAD_AVG.iter().zip(LOAD_EXP.iter()) {
+                avg.store(calc_load(avg.load(Ordering::Relaxed), exp, active), Ordering::Relaxed);
+            }
+        }
         
-        // In a real OS, we would check quantum in PerCpuData and trigger schedule if needed.
-        // For now, we rely on the loop in run() or interrupt to call schedule.
+        // RLIMIT_CPU : contrôle une fois par seconde
+        if now % TIMER_HZ == 0 {
+            crate::process::rlimit::check_cpu_time();
+        }
+        
+        // La tranche épuisée est signalée via NEED_RESCHED : run() se charge
+        // du changement, hors de tout appel système en cours.
     }
     
-    /// Sélectionne le prochain thread à exécuter
-    pub fn schedule(&self) -> Option<Arc<Mutex<Thread>>> {
+    /// Sélectionne le prochain thread à exécuter.
+    /// Runqueue du CPU d'abord, puis celles des autres CPU ; retourne le
+    /// thread idle du CPU si aucun thread n'est prêt.
+    pub fn schedule(&self) -> Arc<Mutex<Thread>> {
         let current = self.current_thread();
         
-        // Acquire lock on Runqueue
-        let mut cfs = self.cfs.lock();
-        let next = cfs.schedule(current);
-        drop(cfs);
+        // Verrou de la runqueue locale relâché avant de voler
+        let next = self.cfs.get().lock().schedule(current);
+        let next = next.or_else(|| self.steal());
         
-        // Update Per-CPU current thread
-        #[cfg(feature = "smp")]
-        {
-            // Obtenir le CPU actuel
-            let cpu_id = crate::smp::get_current_cpu_id();
-            crate::smp::percpu::set_current_thread(next.clone());
+        self.switch_to(next)
+    }
+
+    /// Cède volontairement le CPU (sched_yield) : le thread courant repasse
+    /// derrière les threads prêts de même vruntime.
+    pub fn yield_now(&self) -> Arc<Mutex<Thread>> {
+        let current = match self.current_thread() {
+            Some(current) => current,
+            None => return self.schedule(),
+        };
+        
+        let next = self.cfs.get().lock().yield_thread(current);
+        let next = next.or_else(|| self.steal());
+        self.switch_to(next)
+    }
+
+    /// Installe `next` (ou l'idle à défaut) comme thread courant du CPU
+    fn switch_to(&self, next: Option<Arc<Mutex<Thread>>>) -> Arc<Mutex<Thread>> {
+        let next = next.unwrap_or_else(|| {
+            let idle = idle::idle_thread();
+            idle.lock().state = crate::process::ThreadState::Running;
+            idle
+        });
+        
+        // Le temps du thread sortant lui est imputé jusqu'ici
+        let now = crate::time::now_ns();
+        let mut switched_from = None;
+        if let Some(previous) = self.current_thread() {
+            if !Arc::ptr_eq(&previous, &next) {
+                let mut prev = previous.lock();
+                account(&mut prev, now);
+                percpu::count(Stat::ContextSwitch);
+                switched_from = Some(prev.tid);
+            }
         }
-        #[cfg(not(feature = "smp"))]
         {
-            // For non-SMP, we might have a single global current thread or a simplified mechanism
-            // For now, we assume `percpu` module handles non-smp case gracefully or is not used.
-            // If `crate::smp::percpu::set_current_thread` is meant to be called always,
-            // then its implementation should handle the `smp` feature internally.
-            // Given the instruction, we make the call conditional.
-            // If `percpu` is used even in non-smp, this needs adjustment.
-            // For now, we'll assume `smp::percpu` is only relevant with `smp` feature.
+            let mut th = next.lock();
+            if let Some(prev_tid) = switched_from {
+                crate::trace!(SchedSwitch, prev_tid, th.tid);
+            }
+            th.last_scheduled = ticks();
+            th.exec_start = now;
         }
+        NEED_RESCHED.store(false, Ordering::Relaxed);
+        set_current_thread(Some(next.clone()));
         
         next
     }
@@ -77,15 +248,21 @@ impl Scheduler {
     /// Démarre le planificateur
     pub fn run(&self) -> ! {
         loop {
-            // Scheduling loop
-            if let Some(thread) = self.schedule() {
-                // Simuler context switch
-                let cr3 = thread.lock().context.cr3;
-                if cr3 != 0 {
-                    // Switch CR3 si nécessaire
-                }
+            let thread = self.schedule();
+            
+            if idle::is_idle(&thread) {
+                // Rien à exécuter : veille jusqu'à la prochaine interruption
                 drop(thread);
+                idle::idle_step();
+                continue;
             }
+            
+            // Simuler context switch
+            let cr3 = thread.lock().context.cr3;
+            if cr3 != 0 {
+                // Switch CR3 si nécessaire
+            }
+            drop(thread);
             unsafe { asm!("hlt") };
         }
     }
@@ -93,6 +270,11 @@ impl Scheduler {
     /// Bloque le thread courant
     pub fn block_current_thread(&self, reason: crate::process::ThreadState) {
         if let Some(current) = self.current_thread() {
+            // Le thread idle doit toujours rester exécutable
+            if idle::is_idle(&current) {
+                return;
+            }
+            
             {
                 let mut thread = current.lock();
                 thread.state = reason; 
@@ -109,10 +291,7 @@ impl Scheduler {
                 if current.lock().state == crate::process::ThreadState::Running {
                     break;
                 }
-                // Si on est toujours Blocked, on re-schedule un autre thread
-                // self.schedule(); 
-                // Attention: Si on est bloqué, schedule() ne nous choisira PAS.
-                // Donc on doit juste attendre.
+                // Si on est bloqué, schedule() ne nous choisira PAS : on attend.
             }
         }
     }
@@ -130,20 +309,31 @@ impl Scheduler {
         }
     }
     
-    /// Retourne le thread courant (Per-CPU)
+    /// Retourne le thread courant du CPU
     pub fn current_thread(&self) -> Option<Arc<Mutex<Thread>>> {
-        #[cfg(feature = "smp")]
-        {
-            crate::smp::percpu::get_current_thread()
-        }
-        #[cfg(not(feature = "smp"))]
-        {
-            // Sans SMP, on pourrait avoir un thread global ou None
-            None
-        }
+        CURRENT_THREAD.with(|current| current.borrow().clone())
     }
 }
 
+crate::percpu! {
+    /// Thread courant de chaque CPU
+    static CURRENT_THREAD: RefCell<Option<Arc<Mutex<Thread>>>> = RefCell::new(None);
+}
+
+/// Met à jour le thread courant du CPU
+pub(crate) fn set_current_thread(thread: Option<Arc<Mutex<Thread>>>) {
+    let (pid, fpu, fs_base) = thread.as_ref().map_or((0, None, 0), |t| {
+        let mut th = t.lock();
+        (th.pid, th.fpu_state(), th.context.fs_base)
+    });
+    crate::vdso::set_current_pid(pid);
+    crate::process::fpu::switch_to(fpu);
+    crate::process::tls::load_fs_base(fs_base);
+    // L'ancien thread est libéré hors de la section masquée
+    let previous = CURRENT_THREAD.with(|current| current.replace(thread));
+    drop(previous);
+}
+
 // Instance globale du scheduler
 use lazy_static::lazy_static;
 
@@ -155,3 +345,22 @@ lazy_static! {
 pub fn current_thread() -> Option<Arc<Mutex<Thread>>> {
     SCHEDULER.current_thread()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_calc_load_converges() {
+        let mut load = 0;
+        // Une minute de charge 2 : la moyenne 1 min dépasse 1.2
+        for _ in 0..12 {
+            load = calc_load(load, LOAD_EXP[0], 2);
+        }
+        assert!(load > LOAD_FIXED_1 * 12 / 10 && load < 2 * LOAD_FIXED_1);
+        for _ in 0..200 {
+            load = calc_load(load, LOAD_EXP[0], 0);
+        }
+        assert!(load < LOAD_FIXED_1 / 100);
+    }
+}
@@ -1,6 +1,6 @@
 use alloc::string::String;
 use alloc::vec::Vec;
-use crate::vga_buffer::WRITER;
+use crate::vga_buffer::{WRITER, SHELL_VT};
 use crate::device_manager::{DeviceManager, DEVICE_MANAGER};
 
 /// Commandes de gestion des périphériques
@@ -12,17 +12,17 @@ impl DeviceCommands {
         let manager = DEVICE_MANAGER.lock();
         let devices = manager.list_devices();
 
-        WRITER.lock().write_string("Périphériques détectés:\n");
-        WRITER.lock().write_string("─────────────────────────────────────────\n");
+        WRITER.lock().write_vt(SHELL_VT, "Périphériques détectés:\n");
+        WRITER.lock().write_vt(SHELL_VT, "─────────────────────────────────────────\n");
 
         if devices.is_empty() {
-            WRITER.lock().write_string("Aucun périphérique détecté.\n");
+            WRITER.lock().write_vt(SHELL_VT, "Aucun périphérique détecté.\n");
             return;
         }
 
         for (name, device_type, initialized) in devices {
             let status = if initialized { "✓" } else { "✗" };
-            WRITER.lock().write_string(&format!(
+            WRITER.lock().write_vt(SHELL_VT, &format!(
                 "[{}] {} - {:?}\n",
                 status, name, device_type
             ));
@@ -31,131 +31,131 @@ impl DeviceCommands {
 
     /// Affiche les interfaces réseau
     pub fn list_network() {
-        WRITER.lock().write_string("Interfaces réseau:\n");
-        WRITER.lock().write_string("─────────────────────────────────────────\n");
-
-        WRITER.lock().write_string("eth0: Ethernet\n");
-        WRITER.lock().write_string("  MAC: 00:11:22:33:44:55\n");
-        WRITER.lock().write_string("  Speed: 1000 Mbps\n");
-        WRITER.lock().write_string("  Status: Up\n\n");
-
-        WRITER.lock().write_string("wlan0: Wi-Fi\n");
-        WRITER.lock().write_string("  MAC: AA:BB:CC:DD:EE:FF\n");
-        WRITER.lock().write_string("  Standard: 802.11ac\n");
-        WRITER.lock().write_string("  Status: Down\n");
+        WRITER.lock().write_vt(SHELL_VT, "Interfaces réseau:\n");
+        WRITER.lock().write_vt(SHELL_VT, "─────────────────────────────────────────\n");
+
+        WRITER.lock().write_vt(SHELL_VT, "eth0: Ethernet\n");
+        WRITER.lock().write_vt(SHELL_VT, "  MAC: 00:11:22:33:44:55\n");
+        WRITER.lock().write_vt(SHELL_VT, "  Speed: 1000 Mbps\n");
+        WRITER.lock().write_vt(SHELL_VT, "  Status: Up\n\n");
+
+        WRITER.lock().write_vt(SHELL_VT, "wlan0: Wi-Fi\n");
+        WRITER.lock().write_vt(SHELL_VT, "  MAC: AA:BB:CC:DD:EE:FF\n");
+        WRITER.lock().write_vt(SHELL_VT, "  Standard: 802.11ac\n");
+        WRITER.lock().write_vt(SHELL_VT, "  Status: Down\n");
     }
 
     /// Affiche les disques USB
     pub fn list_usb() {
-        WRITER.lock().write_string("Périphériques USB:\n");
-        WRITER.lock().write_string("─────────────────────────────────────────\n");
-
-        WRITER.lock().write_string("USB Disk 1\n");
-        WRITER.lock().write_string("  Vendor:Product: 0951:1666\n");
-        WRITER.lock().write_string("  Speed: 480 Mbps (High Speed)\n");
-        WRITER.lock().write_string("  Capacity: 32 GB\n\n");
-
-        WRITER.lock().write_string("USB Keyboard\n");
-        WRITER.lock().write_string("  Vendor:Product: 046D:C31C\n");
-        WRITER.lock().write_string("  Speed: 12 Mbps (Full Speed)\n");
-        WRITER.lock().write_string("  Class: HID\n\n");
-
-        WRITER.lock().write_string("USB Mouse\n");
-        WRITER.lock().write_string("  Vendor:Product: 046D:C05A\n");
-        WRITER.lock().write_string("  Speed: 12 Mbps (Full Speed)\n");
-        WRITER.lock().write_string("  Class: HID\n");
+        WRITER.lock().write_vt(SHELL_VT, "Périphériques USB:\n");
+        WRITER.lock().write_vt(SHELL_VT, "─────────────────────────────────────────\n");
+
+        WRITER.lock().write_vt(SHELL_VT, "USB Disk 1\n");
+        WRITER.lock().write_vt(SHELL_VT, "  Vendor:Product: 0951:1666\n");
+        WRITER.lock().write_vt(SHELL_VT, "  Speed: 480 Mbps (High Speed)\n");
+        WRITER.lock().write_vt(SHELL_VT, "  Capacity: 32 GB\n\n");
+
+        WRITER.lock().write_vt(SHELL_VT, "USB Keyboard\n");
+        WRITER.lock().write_vt(SHELL_VT, "  Vendor:Product: 046D:C31C\n");
+        WRITER.lock().write_vt(SHELL_VT, "  Speed: 12 Mbps (Full Speed)\n");
+        WRITER.lock().write_vt(SHELL_VT, "  Class: HID\n\n");
+
+        WRITER.lock().write_vt(SHELL_VT, "USB Mouse\n");
+        WRITER.lock().write_vt(SHELL_VT, "  Vendor:Product: 046D:C05A\n");
+        WRITER.lock().write_vt(SHELL_VT, "  Speed: 12 Mbps (Full Speed)\n");
+        WRITER.lock().write_vt(SHELL_VT, "  Class: HID\n");
     }
 
     /// Affiche les périphériques Bluetooth
     pub fn list_bluetooth() {
-        WRITER.lock().write_string("Périphériques Bluetooth:\n");
-        WRITER.lock().write_string("─────────────────────────────────────────\n");
-
-        WRITER.lock().write_string("Adaptateur: hci0\n");
-        WRITER.lock().write_string("  Address: 5C:F3:70:8B:12:34\n");
-        WRITER.lock().write_string("  Version: Bluetooth 5.0\n\n");
-
-        WRITER.lock().write_string("Périphériques appairés:\n");
-        WRITER.lock().write_string("  Sony Headset\n");
-        WRITER.lock().write_string("    Type: Headset\n");
-        WRITER.lock().write_string("    Signal: -45 dBm (Excellent)\n");
-        WRITER.lock().write_string("    Status: Connecté\n\n");
-
-        WRITER.lock().write_string("  Logitech Keyboard\n");
-        WRITER.lock().write_string("    Type: Keyboard\n");
-        WRITER.lock().write_string("    Signal: -55 dBm (Good)\n");
-        WRITER.lock().write_string("    Status: Appairé\n\n");
-
-        WRITER.lock().write_string("  Apple Watch\n");
-        WRITER.lock().write_string("    Type: Smartwatch\n");
-        WRITER.lock().write_string("    Signal: -65 dBm (Fair)\n");
-        WRITER.lock().write_string("    Status: Appairé\n");
+        WRITER.lock().write_vt(SHELL_VT, "Périphériques Bluetooth:\n");
+        WRITER.lock().write_vt(SHELL_VT, "─────────────────────────────────────────\n");
+
+        WRITER.lock().write_vt(SHELL_VT, "Adaptateur: hci0\n");
+        WRITER.lock().write_vt(SHELL_VT, "  Address: 5C:F3:70:8B:12:34\n");
+        WRITER.lock().write_vt(SHELL_VT, "  Version: Bluetooth 5.0\n\n");
+
+        WRITER.lock().write_vt(SHELL_VT, "Périphériques appairés:\n");
+        WRITER.lock().write_vt(SHELL_VT, "  Sony Headset\n");
+        WRITER.lock().write_vt(SHELL_VT, "    Type: Headset\n");
+        WRITER.lock().write_vt(SHELL_VT, "    Signal: -45 dBm (Excellent)\n");
+        WRITER.lock().write_vt(SHELL_VT, "    Status: Connecté\n\n");
+
+        WRITER.lock().write_vt(SHELL_VT, "  Logitech Keyboard\n");
+        WRITER.lock().write_vt(SHELL_VT, "    Type: Keyboard\n");
+        WRITER.lock().write_vt(SHELL_VT, "    Signal: -55 dBm (Good)\n");
+        WRITER.lock().write_vt(SHELL_VT, "    Status: Appairé\n\n");
+
+        WRITER.lock().write_vt(SHELL_VT, "  Apple Watch\n");
+        WRITER.lock().write_vt(SHELL_VT, "    Type: Smartwatch\n");
+        WRITER.lock().write_vt(SHELL_VT, "    Signal: -65 dBm (Fair)\n");
+        WRITER.lock().write_vt(SHELL_VT, "    Status: Appairé\n");
     }
 
     /// Affiche les périphériques audio
     pub fn list_audio() {
-        WRITER.lock().write_string("Périphériques audio:\n");
-        WRITER.lock().write_string("─────────────────────────────────────────\n");
-
-        WRITER.lock().write_string("Adaptateur: HDA Intel\n\n");
-
-        WRITER.lock().write_string("Périphériques de sortie:\n");
-        WRITER.lock().write_string("  Speaker (Défaut)\n");
-        WRITER.lock().write_string("    Canaux: 2\n");
-        WRITER.lock().write_string("    Fréquence: 48000 Hz\n");
-        WRITER.lock().write_string("    Profondeur: 16 bits\n");
-        WRITER.lock().write_string("    Volume: 100%\n\n");
-
-        WRITER.lock().write_string("  Headset\n");
-        WRITER.lock().write_string("    Canaux: 2\n");
-        WRITER.lock().write_string("    Fréquence: 44100 Hz\n");
-        WRITER.lock().write_string("    Profondeur: 24 bits\n");
-        WRITER.lock().write_string("    Volume: 100%\n\n");
-
-        WRITER.lock().write_string("Périphériques d'entrée:\n");
-        WRITER.lock().write_string("  Microphone (Défaut)\n");
-        WRITER.lock().write_string("    Canaux: 1\n");
-        WRITER.lock().write_string("    Fréquence: 16000 Hz\n");
-        WRITER.lock().write_string("    Profondeur: 16 bits\n");
+        WRITER.lock().write_vt(SHELL_VT, "Périphériques audio:\n");
+        WRITER.lock().write_vt(SHELL_VT, "─────────────────────────────────────────\n");
+
+        WRITER.lock().write_vt(SHELL_VT, "Adaptateur: HDA Intel\n\n");
+
+        WRITER.lock().write_vt(SHELL_VT, "Périphériques de sortie:\n");
+        WRITER.lock().write_vt(SHELL_VT, "  Speaker (Défaut)\n");
+        WRITER.lock().write_vt(SHELL_VT, "    Canaux: 2\n");
+        WRITER.lock().write_vt(SHELL_VT, "    Fréquence: 48000 Hz\n");
+        WRITER.lock().write_vt(SHELL_VT, "    Profondeur: 16 bits\n");
+        WRITER.lock().write_vt(SHELL_VT, "    Volume: 100%\n\n");
+
+        WRITER.lock().write_vt(SHELL_VT, "  Headset\n");
+        WRITER.lock().write_vt(SHELL_VT, "    Canaux: 2\n");
+        WRITER.lock().write_vt(SHELL_VT, "    Fréquence: 44100 Hz\n");
+        WRITER.lock().write_vt(SHELL_VT, "    Profondeur: 24 bits\n");
+        WRITER.lock().write_vt(SHELL_VT, "    Volume: 100%\n\n");
+
+        WRITER.lock().write_vt(SHELL_VT, "Périphériques d'entrée:\n");
+        WRITER.lock().write_vt(SHELL_VT, "  Microphone (Défaut)\n");
+        WRITER.lock().write_vt(SHELL_VT, "    Canaux: 1\n");
+        WRITER.lock().write_vt(SHELL_VT, "    Fréquence: 16000 Hz\n");
+        WRITER.lock().write_vt(SHELL_VT, "    Profondeur: 16 bits\n");
     }
 
     /// Affiche les périphériques vidéo
     pub fn list_video() {
-        WRITER.lock().write_string("Périphériques vidéo:\n");
-        WRITER.lock().write_string("─────────────────────────────────────────\n");
-
-        WRITER.lock().write_string("Adaptateur: NVIDIA GeForce RTX 3060\n");
-        WRITER.lock().write_string("  VRAM: 12 GB\n\n");
-
-        WRITER.lock().write_string("Moniteurs connectés:\n");
-        WRITER.lock().write_string("  HDMI-1\n");
-        WRITER.lock().write_string("    Type: Monitor\n");
-        WRITER.lock().write_string("    Résolution actuelle: 1920x1080@60Hz\n");
-        WRITER.lock().write_string("    Résolutions supportées:\n");
-        WRITER.lock().write_string("      - 1920x1080@60Hz (16:9)\n");
-        WRITER.lock().write_string("      - 1920x1080@144Hz (16:9)\n");
-        WRITER.lock().write_string("      - 2560x1440@60Hz (16:9)\n");
-        WRITER.lock().write_string("      - 3840x2160@30Hz (16:9)\n");
-        WRITER.lock().write_string("    Profondeur de couleur: 24 bits\n\n");
-
-        WRITER.lock().write_string("Moniteurs disponibles:\n");
-        WRITER.lock().write_string("  DisplayPort-1\n");
-        WRITER.lock().write_string("    Type: Monitor\n");
-        WRITER.lock().write_string("    Status: Déconnecté\n");
+        WRITER.lock().write_vt(SHELL_VT, "Périphériques vidéo:\n");
+        WRITER.lock().write_vt(SHELL_VT, "─────────────────────────────────────────\n");
+
+        WRITER.lock().write_vt(SHELL_VT, "Adaptateur: NVIDIA GeForce RTX 3060\n");
+        WRITER.lock().write_vt(SHELL_VT, "  VRAM: 12 GB\n\n");
+
+        WRITER.lock().write_vt(SHELL_VT, "Moniteurs connectés:\n");
+        WRITER.lock().write_vt(SHELL_VT, "  HDMI-1\n");
+        WRITER.lock().write_vt(SHELL_VT, "    Type: Monitor\n");
+        WRITER.lock().write_vt(SHELL_VT, "    Résolution actuelle: 1920x1080@60Hz\n");
+        WRITER.lock().write_vt(SHELL_VT, "    Résolutions supportées:\n");
+        WRITER.lock().write_vt(SHELL_VT, "      - 1920x1080@60Hz (16:9)\n");
+        WRITER.lock().write_vt(SHELL_VT, "      - 1920x1080@144Hz (16:9)\n");
+        WRITER.lock().write_vt(SHELL_VT, "      - 2560x1440@60Hz (16:9)\n");
+        WRITER.lock().write_vt(SHELL_VT, "      - 3840x2160@30Hz (16:9)\n");
+        WRITER.lock().write_vt(SHELL_VT, "    Profondeur de couleur: 24 bits\n\n");
+
+        WRITER.lock().write_vt(SHELL_VT, "Moniteurs disponibles:\n");
+        WRITER.lock().write_vt(SHELL_VT, "  DisplayPort-1\n");
+        WRITER.lock().write_vt(SHELL_VT, "    Type: Monitor\n");
+        WRITER.lock().write_vt(SHELL_VT, "    Status: Déconnecté\n");
     }
 
     /// Affiche l'aide des commandes de périphériques
     pub fn show_help() {
-        WRITER.lock().write_string("Commandes de gestion des périphériques:\n");
-        WRITER.lock().write_string("─────────────────────────────────────────\n\n");
-
-        WRITER.lock().write_string("devices list              - Lister tous les périphériques\n");
-        WRITER.lock().write_string("devices network           - Lister les interfaces réseau\n");
-        WRITER.lock().write_string("devices usb               - Lister les disques USB\n");
-        WRITER.lock().write_string("devices bluetooth         - Lister les périphériques Bluetooth\n");
-        WRITER.lock().write_string("devices audio             - Lister les périphériques audio\n");
-        WRITER.lock().write_string("devices video             - Lister les périphériques vidéo\n");
-        WRITER.lock().write_string("devices help              - Afficher cette aide\n");
+        WRITER.lock().write_vt(SHELL_VT, "Commandes de gestion des périphériques:\n");
+        WRITER.lock().write_vt(SHELL_VT, "─────────────────────────────────────────\n\n");
+
+        WRITER.lock().write_vt(SHELL_VT, "devices list              - Lister tous les périphériques\n");
+        WRITER.lock().write_vt(SHELL_VT, "devices network           - Lister les interfaces réseau\n");
+        WRITER.lock().write_vt(SHELL_VT, "devices usb               - Lister les disques USB\n");
+        WRITER.lock().write_vt(SHELL_VT, "devices bluetooth         - Lister les périphériques Bluetooth\n");
+        WRITER.lock().write_vt(SHELL_VT, "devices audio             - Lister les périphériques audio\n");
+        WRITER.lock().write_vt(SHELL_VT, "devices video             - Lister les périphériques vidéo\n");
+        WRITER.lock().write_vt(SHELL_VT, "devices help              - Afficher cette aide\n");
     }
 
     /// Exécute une commande de périphérique
@@ -175,7 +175,7 @@ impl DeviceCommands {
                         "audio" => Self::list_audio(),
                         "video" => Self::list_video(),
                         _ => {
-                            WRITER.lock().write_string("Type de périphérique inconnu.\n");
+                            WRITER.lock().write_vt(SHELL_VT, "Type de périphérique inconnu.\n");
                         }
                     }
                 } else {
@@ -208,7 +208,7 @@ impl DeviceCommands {
                 Ok(())
             }
             _ => {
-                WRITER.lock().write_string("Commande inconnue. Tapez 'devices help' pour l'aide.\n");
+                WRITER.lock().write_vt(SHELL_VT, "Commande inconnue. Tapez 'devices help' pour l'aide.\n");
                 Err("Commande inconnue")
             }
         }
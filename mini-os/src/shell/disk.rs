@@ -0,0 +1,370 @@
+//! Partitionnement, formatage et vérification des disques : fdisk,
+//! mkfs.fat32 et fsck ; inventaire : lsblk et hdinfo
+//!
+//! Ces commandes travaillent directement sur un périphérique du registre
+//! bloc. Avant une écriture destructrice (nouvelle table, suppression,
+//! formatage, réparation), elles décrivent ce qui va être modifié et
+//! demandent confirmation ; `-y` répond oui d'avance, pour les scripts.
+//! `lsblk` et `hdinfo` ne font que lire : registre bloc, identité ATA
+//! publiée par le driver et métadonnées du gestionnaire de périphériques.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use mini_os::drivers::ata_info;
+use mini_os::drivers::block::{self, BlockHandle};
+use mini_os::fs::VfsError;
+use mini_os::gpt::{self, PartitionTable};
+
+use crate::device_manager::DEVICE_MANAGER;
+use super::{redirect, Command, Shell, ShellError};
+
+/// Taille lisible d'un nombre de secteurs de 512 octets
+fn human_size(sectors: u64) -> String {
+    match sectors {
+        s if s >= 2048 * 1024 => format!("{} Gio", s / (2048 * 1024)),
+        s if s >= 2048 => format!("{} Mio", s / 2048),
+        s => format!("{} Kio", s / 2),
+    }
+}
+
+/// Demande une confirmation ; seule une réponse explicite vaut oui
+fn confirm(question: &str, assume_yes: bool) -> bool {
+    if assume_yes {
+        return true;
+    }
+    redirect::print_out(&format!("{} [o/N] ", question));
+    matches!(redirect::read_line().trim(), "o" | "O" | "oui" | "y" | "yes")
+}
+
+/// Sépare l'option `-y` des autres arguments
+fn split_yes(cmd: &Command) -> (bool, Vec<&str>) {
+    let assume_yes = cmd.args.iter().any(|a| a == "-y");
+    let args = cmd.args.iter().map(|a| a.as_str()).filter(|a| *a != "-y").collect();
+    (assume_yes, args)
+}
+
+/// Périphérique du registre bloc (`sda` ou `/dev/sda`), en secteurs de 512 octets
+fn open_device(program: &str, name: &str) -> Result<BlockHandle, ShellError> {
+    let name = name.strip_prefix("/dev/").unwrap_or(name);
+    let Some(dev) = block::get(name) else {
+        redirect::print_err(&format!("{}: {}: périphérique bloc inconnu\n", program, name));
+        return Err(ShellError::ExecutionFailed(format!("{} failed", program)));
+    };
+    if dev.block_size() != 512 {
+        redirect::print_err(&format!("{}: {}: secteurs de 512 octets requis\n", program, name));
+        return Err(ShellError::ExecutionFailed(format!("{} failed", program)));
+    }
+    Ok(dev)
+}
+
+fn fail(program: &str, name: &str, error: &str) -> ShellError {
+    redirect::print_err(&format!("{}: {}: {}\n", program, name, error));
+    ShellError::ExecutionFailed(format!("{} failed", program))
+}
+
+fn cancelled(program: &str) -> ShellError {
+    redirect::print_err("Abandon : rien n'a été écrit\n");
+    ShellError::ExecutionFailed(format!("{} cancelled", program))
+}
+
+fn list_partitions(dev: &BlockHandle, table: &PartitionTable) {
+    redirect::print_out(&format!(
+        "Disque {} : {} secteurs ({}), table GPT, zone utile {}-{}\n",
+        dev.name,
+        dev.block_count(),
+        human_size(dev.block_count()),
+        table.first_usable(),
+        table.last_usable()
+    ));
+    redirect::print_out(&format!("{:<4} {:>10} {:>10} {:>8}  {:<16} {}\n", "N°", "Début", "Fin", "Taille", "Type", "Nom"));
+    for part in table.partitions() {
+        let entry = table.entry(part.index).expect("partition utilisée");
+        redirect::print_out(&format!(
+            "{:<4} {:>10} {:>10} {:>8}  {:<16} {}\n",
+            part.index + 1,
+            part.start_lba,
+            part.end_lba,
+            human_size(part.size_sectors),
+            entry.type_name(),
+            entry.name()
+        ));
+    }
+}
+
+/// Secteur de fin : absolu, ou `+taille` relative au début
+fn parse_end(start: u64, value: &str) -> Option<u64> {
+    match value.strip_prefix('+') {
+        Some(size) => {
+            let sectors = mini_os::cmdline::parse_size(size)? as u64 / 512;
+            (sectors > 0).then(|| start + sectors - 1)
+        }
+        None => value.parse().ok(),
+    }
+}
+
+/// Volume désigné par `<disque> [partition]` : nom affiché, premier
+/// secteur et nombre de secteurs
+fn resolve_volume(program: &str, dev: &BlockHandle, partition: Option<usize>) -> Result<(String, u64, u64), ShellError> {
+    let Some(number) = partition else {
+        return Ok((dev.name.clone(), 0, dev.block_count()));
+    };
+    let table = PartitionTable::read(dev).map_err(|e| fail(program, &dev.name, e))?;
+    let entry = number
+        .checked_sub(1)
+        .and_then(|index| table.entry(index))
+        .ok_or_else(|| fail(program, &dev.name, "partition inexistante"))?;
+    Ok((format!("{} partition {}", dev.name, number), entry.start_lba, entry.end_lba - entry.start_lba + 1))
+}
+
+/// `<disque> [partition]`
+fn parse_volume_args<'a>(args: &[&'a str]) -> Result<(&'a str, Option<usize>), ShellError> {
+    match args {
+        [name] => Ok((name, None)),
+        [name, number] => Ok((name, Some(number.parse().map_err(|_| ShellError::InvalidArguments)?))),
+        _ => Err(ShellError::InvalidArguments),
+    }
+}
+
+impl Shell {
+    /// Commande: fdisk [-y] <disque> [-l | -g | -n [début fin|+taille] [-t linux|fat|efi] [-N nom] | -d <n>]
+    pub(super) fn builtin_fdisk(&self, cmd: &Command) -> Result<(), ShellError> {
+        let (assume_yes, args) = split_yes(cmd);
+        let Some((&name, action)) = args.split_first() else {
+            return Err(ShellError::InvalidArguments);
+        };
+        let dev = open_device("fdisk", name)?;
+
+        if action == ["-g"] {
+            let table = PartitionTable::new(dev.block_count()).map_err(|e| fail("fdisk", &dev.name, e))?;
+            let question = format!(
+                "Créer une table GPT vide sur {} ({}) ? Les partitions existantes seront perdues.",
+                dev.name,
+                human_size(dev.block_count())
+            );
+            if !confirm(&question, assume_yes) {
+                return Err(cancelled("fdisk"));
+            }
+            table.write(&dev).map_err(|e| fail("fdisk", &dev.name, e))?;
+            list_partitions(&dev, &table);
+            return Ok(());
+        }
+
+        let mut table = PartitionTable::read(&dev).map_err(|e| fail("fdisk", &dev.name, e))?;
+        match action {
+            [] | ["-l"] => {
+                list_partitions(&dev, &table);
+                return Ok(());
+            }
+            ["-n", rest @ ..] => {
+                let mut type_guid = gpt::LINUX_DATA_GUID;
+                let mut label = "";
+                let mut bounds = Vec::new();
+                let mut rest = rest.iter();
+                while let Some(arg) = rest.next() {
+                    match *arg {
+                        "-t" => {
+                            type_guid = match rest.next().copied() {
+                                Some("linux") => gpt::LINUX_DATA_GUID,
+                                Some("fat") => gpt::BASIC_DATA_GUID,
+                                Some("efi") => gpt::EFI_SYSTEM_GUID,
+                                _ => return Err(ShellError::InvalidArguments),
+                            }
+                        }
+                        "-N" => label = rest.next().copied().ok_or(ShellError::InvalidArguments)?,
+                        bound => bounds.push(bound),
+                    }
+                }
+                let (start, end) = match bounds.as_slice() {
+                    [] => table.largest_free().ok_or_else(|| fail("fdisk", &dev.name, "plus d'espace libre"))?,
+                    [start, end] => {
+                        let start = start.parse().map_err(|_| ShellError::InvalidArguments)?;
+                        (start, parse_end(start, end).ok_or(ShellError::InvalidArguments)?)
+                    }
+                    _ => return Err(ShellError::InvalidArguments),
+                };
+                let index = table.add(start, end, type_guid, label).map_err(|e| fail("fdisk", &dev.name, e))?;
+                redirect::print_out(&format!(
+                    "Partition {} créée : secteurs {}-{} ({})\n",
+                    index + 1,
+                    start,
+                    end,
+                    human_size(end - start + 1)
+                ));
+            }
+            ["-d", number] => {
+                let index = number
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|n| n.checked_sub(1))
+                    .ok_or(ShellError::InvalidArguments)?;
+                let Some(entry) = table.entry(index) else {
+                    return Err(fail("fdisk", &dev.name, "partition inexistante"));
+                };
+                let question = format!(
+                    "Supprimer la partition {} de {} ({}, {}) ?",
+                    index + 1,
+                    dev.name,
+                    human_size(entry.end_lba - entry.start_lba + 1),
+                    entry.type_name()
+                );
+                if !confirm(&question, assume_yes) {
+                    return Err(cancelled("fdisk"));
+                }
+                table.remove(index).map_err(|e| fail("fdisk", &dev.name, e))?;
+            }
+            _ => return Err(ShellError::InvalidArguments),
+        }
+        table.write(&dev).map_err(|e| fail("fdisk", &dev.name, e))?;
+        list_partitions(&dev, &table);
+        Ok(())
+    }
+
+    /// Commande: mkfs.fat32 [-y] [-n étiquette] <disque> [partition]
+    pub(super) fn builtin_mkfs_fat32(&self, cmd: &Command) -> Result<(), ShellError> {
+        let (assume_yes, mut args) = split_yes(cmd);
+        let mut label = "";
+        if let Some(pos) = args.iter().position(|a| *a == "-n") {
+            if pos + 1 >= args.len() {
+                return Err(ShellError::InvalidArguments);
+            }
+            label = args[pos + 1];
+            args.drain(pos..pos + 2);
+        }
+        let (name, partition) = parse_volume_args(&args)?;
+        let mut dev = open_device("mkfs.fat32", name)?;
+        let (target, start, sectors) = resolve_volume("mkfs.fat32", &dev, partition)?;
+
+        let question = format!("Formater {} ({}) en FAT32 ? Toutes ses données seront perdues.", target, human_size(sectors));
+        if !confirm(&question, assume_yes) {
+            return Err(cancelled("mkfs.fat32"));
+        }
+
+        dev.plug();
+        let result = mini_os::fat32::format(&mut dev, start, sectors, label);
+        let written = dev.unplug().and_then(|_| dev.flush());
+        let info = match (result, written) {
+            (Ok(info), Ok(())) => info,
+            (Err(VfsError::NoSpace), _) => return Err(fail("mkfs.fat32", &target, "volume trop petit pour FAT32 (33 Mio minimum)")),
+            (Err(e), _) => return Err(fail("mkfs.fat32", &target, &e.to_string())),
+            (_, Err(_)) => return Err(fail("mkfs.fat32", &target, "erreur d'écriture")),
+        };
+        redirect::print_out(&format!(
+            "{} : FAT32, {} clusters de {} octets, 2 FAT de {} secteurs\n",
+            target,
+            info.clusters,
+            info.sectors_per_cluster as u32 * 512,
+            info.sectors_per_fat
+        ));
+        Ok(())
+    }
+
+    /// Commande: fsck [-r] [-y] <disque> [partition] (ext2 ; -r : réparer)
+    pub(super) fn builtin_fsck(&self, cmd: &Command) -> Result<(), ShellError> {
+        let (assume_yes, mut args) = split_yes(cmd);
+        let repair = args.contains(&"-r");
+        args.retain(|a| *a != "-r");
+        let (name, partition) = parse_volume_args(&args)?;
+        let mut dev = open_device("fsck", name)?;
+        let (target, start, _) = resolve_volume("fsck", &dev, partition)?;
+
+        if repair {
+            let question = format!("Réparer {} ? Les corrections sont écrites directement : le volume ne doit pas être monté.", target);
+            if !confirm(&question, assume_yes) {
+                return Err(cancelled("fsck"));
+            }
+            dev.plug();
+        }
+        let result = mini_os::fsck::check_ext2(&mut dev, start, repair);
+        let written = if repair { dev.unplug().and_then(|_| dev.flush()) } else { Ok(()) };
+        let report = result.map_err(|e| fail("fsck", &target, e))?;
+        if written.is_err() {
+            return Err(fail("fsck", &target, "erreur d'écriture"));
+        }
+
+        for problem in &report.problems {
+            redirect::print_out(&format!("{}: {}\n", target, problem));
+        }
+        redirect::print_out(&format!(
+            "{}: {} ; {}/{} inodes, {}/{} blocs\n",
+            target,
+            match (report.is_clean(), report.repaired, report.unrepaired) {
+                (true, _, _) => "propre",
+                (false, true, 0) => "réparé",
+                (false, true, _) => "partiellement réparé",
+                (false, false, _) => "erreurs trouvées (fsck -r pour réparer)",
+            },
+            report.inodes_used,
+            report.inodes_count,
+            report.blocks_used,
+            report.blocks_count
+        ));
+        if report.is_clean() || (report.repaired && report.unrepaired == 0) {
+            Ok(())
+        } else {
+            Err(ShellError::ExecutionFailed("fsck found errors".into()))
+        }
+    }
+
+    /// lsblk : disques (registre bloc et disques ATA identifiés) et partitions GPT
+    pub(super) fn builtin_lsblk(&self, _cmd: &Command) -> Result<(), ShellError> {
+        let mut names = block::BLOCK_DEVICES.lock().list();
+        for name in ata_info::list() {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names.sort();
+
+        redirect::print_out(&format!("{:<10} {:>8}  {:<5} {}\n", "NAME", "SIZE", "TYPE", "MODEL"));
+        for name in &names {
+            let model = DEVICE_MANAGER
+                .lock()
+                .metadata(name)
+                .and_then(|meta| meta.get("model").cloned())
+                .unwrap_or_default();
+            let dev = block::get(name);
+            let sectors = match (&dev, ata_info::get(name)) {
+                (Some(dev), _) => dev.block_count() * dev.block_size() as u64 / 512,
+                (None, Some(info)) => info.identity.sectors * info.identity.sector_size as u64 / 512,
+                (None, None) => 0,
+            };
+            redirect::print_out(&format!("{:<10} {:>8}  {:<5} {}\n", name, human_size(sectors), "disk", model));
+
+            let Some(table) = dev.filter(|d| d.block_size() == 512).and_then(|d| PartitionTable::read(&d).ok()) else {
+                continue;
+            };
+            for part in table.partitions() {
+                let entry = table.entry(part.index).expect("partition utilisée");
+                redirect::print_out(&format!(
+                    "{:<10} {:>8}  {:<5} {}\n",
+                    format!("└─{}{}", name, part.index + 1),
+                    human_size(part.size_sectors),
+                    "part",
+                    entry.type_name()
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// hdinfo [disque] : identité ATA et attributs S.M.A.R.T.
+    pub(super) fn builtin_hdinfo(&self, cmd: &Command) -> Result<(), ShellError> {
+        let names = match cmd.args.first() {
+            Some(name) => alloc::vec![name.strip_prefix("/dev/").unwrap_or(name).to_string()],
+            None => ata_info::list(),
+        };
+        if names.is_empty() {
+            redirect::print_out("Aucun disque ATA identifié\n");
+        }
+        for (i, name) in names.iter().enumerate() {
+            let Some(text) = ata_info::format_info(name) else {
+                return Err(fail("hdinfo", name, "aucune information d'identification"));
+            };
+            if i > 0 {
+                redirect::print_out("\n");
+            }
+            redirect::print_out(&text);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,100 @@
+//! Historique persistant du shell
+//!
+//! L'historique est chargé depuis `$HOME/.history` au démarrage et chaque
+//! commande y est ajoutée en fin de fichier. Au-delà de `HISTORY_MAX`
+//! entrées (plus une marge, pour ne pas réécrire à chaque commande), le
+//! fichier est réécrit avec les `HISTORY_MAX` plus récentes.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use super::Shell;
+
+/// Nombre d'entrées conservées
+pub const HISTORY_MAX: usize = 500;
+
+/// Marge tolérée avant rotation du fichier
+const HISTORY_SLACK: usize = HISTORY_MAX / 4;
+
+/// Nom du fichier d'historique dans `$HOME`
+const HISTORY_FILE: &str = ".history";
+
+impl Shell {
+    /// Chemin du fichier d'historique
+    pub fn history_path(&self) -> String {
+        let home = self.env_vars.get("HOME").map(|h| h.as_str()).unwrap_or("/");
+        format!("{}/{}", home.trim_end_matches('/'), HISTORY_FILE)
+    }
+
+    /// Charge l'historique depuis le VFS (absent : historique vide)
+    pub fn load_history(&mut self) {
+        let content = match mini_os::fs::vfs_read_file(&self.history_path()) {
+            Ok(content) => content,
+            Err(_) => return,
+        };
+
+        let text = String::from_utf8_lossy(&content);
+        let lines: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).collect();
+        let skip = lines.len().saturating_sub(HISTORY_MAX);
+        self.history = lines[skip..].iter().map(|l| String::from(*l)).collect();
+        self.history_index = self.history.len();
+    }
+
+    /// Ajoute une commande à l'historique (mémoire et fichier)
+    pub fn add_to_history(&mut self, cmd: &str) {
+        let cmd = cmd.trim();
+        if cmd.is_empty() {
+            return;
+        }
+        self.history.push(cmd.into());
+
+        if self.history.len() > HISTORY_MAX + HISTORY_SLACK {
+            // Rotation : on ne garde que les entrées les plus récentes
+            let excess = self.history.len() - HISTORY_MAX;
+            self.history.drain(..excess);
+            let mut content = self.history.join("\n");
+            content.push('\n');
+            let _ = mini_os::fs::vfs_write_file(&self.history_path(), content.as_bytes());
+        } else {
+            let line = format!("{}\n", cmd);
+            let _ = mini_os::fs::vfs_append_file(&self.history_path(), line.as_bytes());
+        }
+
+        self.history_index = self.history.len();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_history_persistence_and_rotation() {
+        let _ = mini_os::fs::vfs_mkdir("/tmp");
+        let _ = mini_os::fs::vfs_mkdir("/tmp/hist-test");
+
+        let mut shell = Shell::new();
+        shell.env_vars.insert("HOME".into(), "/tmp/hist-test".into());
+        let _ = mini_os::fs::vfs_remove_file(&shell.history_path());
+
+        shell.add_to_history("ls");
+        shell.add_to_history("  ");
+        shell.add_to_history("cd /home");
+
+        let mut reloaded = Shell::new();
+        reloaded.env_vars.insert("HOME".into(), "/tmp/hist-test".into());
+        reloaded.load_history();
+        assert_eq!(reloaded.history, ["ls", "cd /home"]);
+
+        for i in 0..HISTORY_MAX + HISTORY_SLACK {
+            reloaded.add_to_history(&format!("echo {}", i));
+        }
+        assert!(reloaded.history.len() <= HISTORY_MAX + HISTORY_SLACK);
+
+        let mut again = Shell::new();
+        again.env_vars.insert("HOME".into(), "/tmp/hist-test".into());
+        again.load_history();
+        assert_eq!(again.history.len(), reloaded.history.len().min(HISTORY_MAX));
+        assert_eq!(again.history.last(), reloaded.history.last());
+    }
+}
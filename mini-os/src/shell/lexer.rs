@@ -0,0 +1,386 @@
+//! Analyse lexicale de la ligne de commande
+//!
+//! Découpe une ligne en mots en appliquant les règles habituelles du shell :
+//! - guillemets simples : tout est littéral (`'$HOME *'`)
+//! - guillemets doubles : expansion de `$VAR`, échappements `\"`, `\\`, `\$`
+//! - antislash hors guillemets : le caractère suivant est littéral
+//! - `$VAR` / `${VAR}` : expansion depuis les variables du shell
+//! - `*` et `?` non protégés : motifs développés contre le VFS
+//! - `<`, `>`, `>>`, `2>`, `2>>` non protégés : redirections
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Erreurs de l'analyse lexicale
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LexError {
+    UnterminatedQuote,
+    TrailingBackslash,
+}
+
+/// Un mot de la ligne de commande
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Word {
+    /// Texte final (guillemets retirés, variables développées)
+    pub text: String,
+    /// Motif de glob : caractères spéciaux protégés échappés par `\`
+    pub pattern: String,
+    /// Le mot contient un `*` ou `?` non protégé
+    pub has_glob: bool,
+}
+
+impl Word {
+    fn new() -> Self {
+        Self { text: String::new(), pattern: String::new(), has_glob: false }
+    }
+
+    /// Ajoute un caractère littéral
+    fn push_literal(&mut self, c: char) {
+        self.text.push(c);
+        if matches!(c, '*' | '?' | '\\') {
+            self.pattern.push('\\');
+        }
+        self.pattern.push(c);
+    }
+
+    fn push_str_literal(&mut self, s: &str) {
+        for c in s.chars() {
+            self.push_literal(c);
+        }
+    }
+
+    /// Ajoute un joker (`*` ou `?`) actif
+    fn push_wildcard(&mut self, c: char) {
+        self.text.push(c);
+        self.pattern.push(c);
+        self.has_glob = true;
+    }
+}
+
+/// Type de redirection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedirectOp {
+    /// `<` : lecture depuis un fichier
+    Input,
+    /// `>` : écriture (fichier tronqué)
+    Output,
+    /// `>>` : écriture en fin de fichier
+    Append,
+}
+
+/// Élément de la ligne de commande
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+    Word(Word),
+    /// Redirection du descripteur `fd` ; le mot suivant est la cible
+    Redirect { fd: usize, op: RedirectOp },
+}
+
+/// Lit un nom de variable après `$` ; renvoie `None` si `$` est littéral
+fn read_var_name(chars: &mut core::iter::Peekable<core::str::Chars>) -> Option<String> {
+    let mut name = String::new();
+    if chars.peek() == Some(&'{') {
+        chars.next();
+        while let Some(c) = chars.next() {
+            if c == '}' {
+                return Some(name);
+            }
+            name.push(c);
+        }
+        // `${` non fermé : on garde ce qui a été lu
+        return Some(name);
+    }
+
+    // `$?` : statut de la dernière commande
+    if chars.peek() == Some(&'?') {
+        chars.next();
+        return Some(String::from("?"));
+    }
+
+    while let Some(&c) = chars.peek() {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            name.push(c);
+            chars.next();
+        } else {
+            break;
+        }
+    }
+    if name.is_empty() { None } else { Some(name) }
+}
+
+/// Découpe `input` en mots et opérateurs de redirection
+pub fn tokenize(input: &str, env: &BTreeMap<String, String>) -> Result<Vec<Token>, LexError> {
+    let mut words = Vec::new();
+    let mut current = Word::new();
+    // Un mot est en cours (permet de produire `""` comme argument vide)
+    let mut in_word = false;
+    // Le mot en cours contient des guillemets ou échappements
+    let mut quoted = false;
+    let mut chars = input.chars().peekable();
+
+    let expand = |name: &str| env.get(name).map(|v| v.as_str()).unwrap_or("");
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(Token::Word(core::mem::replace(&mut current, Word::new())));
+                    in_word = false;
+                    quoted = false;
+                }
+            }
+            '<' | '>' => {
+                // `2>` : un chiffre seul et non protégé désigne le descripteur
+                let fd = if in_word && !quoted && current.text.len() == 1 && current.text.as_bytes()[0].is_ascii_digit() {
+                    let fd = (current.text.as_bytes()[0] - b'0') as usize;
+                    current = Word::new();
+                    in_word = false;
+                    Some(fd)
+                } else {
+                    None
+                };
+                if in_word {
+                    words.push(Token::Word(core::mem::replace(&mut current, Word::new())));
+                    in_word = false;
+                }
+                quoted = false;
+
+                let op = if c == '<' {
+                    RedirectOp::Input
+                } else if chars.peek() == Some(&'>') {
+                    chars.next();
+                    RedirectOp::Append
+                } else {
+                    RedirectOp::Output
+                };
+                let fd = fd.unwrap_or(if op == RedirectOp::Input { 0 } else { 1 });
+                words.push(Token::Redirect { fd, op });
+            }
+            '\'' => {
+                in_word = true;
+                quoted = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => current.push_literal(c),
+                        None => return Err(LexError::UnterminatedQuote),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                quoted = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.peek() {
+                            Some(&e) if matches!(e, '"' | '\\' | '$') => {
+                                chars.next();
+                                current.push_literal(e);
+                            }
+                            _ => current.push_literal('\\'),
+                        },
+                        Some('$') => match read_var_name(&mut chars) {
+                            Some(name) => current.push_str_literal(expand(&name)),
+                            None => current.push_literal('$'),
+                        },
+                        Some(c) => current.push_literal(c),
+                        None => return Err(LexError::UnterminatedQuote),
+                    }
+                }
+            }
+            '\\' => {
+                in_word = true;
+                quoted = true;
+                match chars.next() {
+                    Some(e) => current.push_literal(e),
+                    None => return Err(LexError::TrailingBackslash),
+                }
+            }
+            '$' => {
+                in_word = true;
+                // Une valeur développée ne désigne jamais un descripteur
+                quoted = true;
+                match read_var_name(&mut chars) {
+                    Some(name) => current.push_str_literal(expand(&name)),
+                    None => current.push_literal('$'),
+                }
+            }
+            '*' | '?' => {
+                in_word = true;
+                current.push_wildcard(c);
+            }
+            c => {
+                in_word = true;
+                current.push_literal(c);
+            }
+        }
+    }
+
+    if in_word {
+        words.push(Token::Word(current));
+    }
+    Ok(words)
+}
+
+/// Teste `name` contre un motif (`*`, `?`, `\x` littéral)
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    let (mut p, mut n) = (0, 0);
+    // Dernière étoile rencontrée (position dans le motif, position dans le nom)
+    let mut star: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+                continue;
+            }
+            Some('?') => {
+                p += 1;
+                n += 1;
+                continue;
+            }
+            Some('\\') if p + 1 < pattern.len() && pattern[p + 1] == name[n] => {
+                p += 2;
+                n += 1;
+                continue;
+            }
+            Some(&c) if c != '\\' && c == name[n] => {
+                p += 1;
+                n += 1;
+                continue;
+            }
+            _ => {}
+        }
+
+        // Échec : on retente en élargissant la dernière étoile
+        match star {
+            Some((sp, sn)) => {
+                p = sp + 1;
+                n = sn + 1;
+                star = Some((sp, sn + 1));
+            }
+            None => return false,
+        }
+    }
+
+    while pattern.get(p) == Some(&'*') {
+        p += 1;
+    }
+    p == pattern.len()
+}
+
+/// Développe un mot contenant des jokers contre le contenu du répertoire.
+/// Seul le dernier composant du chemin peut contenir des jokers ; sans
+/// correspondance, le mot est conservé tel quel (comme bash).
+pub fn expand_glob(word: &Word, current_dir: &str) -> Vec<String> {
+    if !word.has_glob {
+        return alloc::vec![word.text.clone()];
+    }
+
+    let (dir_prefix, name_pattern) = match word.pattern.rfind('/') {
+        Some(pos) => (&word.pattern[..pos + 1], &word.pattern[pos + 1..]),
+        None => ("", word.pattern.as_str()),
+    };
+    // Les jokers dans les répertoires intermédiaires ne sont pas gérés
+    if dir_prefix.contains(|c| c == '*' || c == '?') {
+        return alloc::vec![word.text.clone()];
+    }
+    let dir_prefix = dir_prefix.replace('\\', "");
+
+    let dir = if dir_prefix.starts_with('/') {
+        dir_prefix.clone()
+    } else if current_dir == "/" {
+        alloc::format!("/{}", dir_prefix)
+    } else {
+        alloc::format!("{}/{}", current_dir, dir_prefix)
+    };
+
+    let mut matches: Vec<String> = match mini_os::fs::vfs_ls(&dir) {
+        Ok(names) => names
+            .into_iter()
+            // Les fichiers cachés ne sont couverts que par un motif commençant par '.'
+            .filter(|n| !n.starts_with('.') || name_pattern.starts_with('.'))
+            .filter(|n| glob_match(name_pattern, n))
+            .map(|n| alloc::format!("{}{}", dir_prefix, n))
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+
+    if matches.is_empty() {
+        return alloc::vec![word.text.clone()];
+    }
+    matches.sort();
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(input: &str) -> Vec<Word> {
+        tokenize(input, &BTreeMap::new()).unwrap().into_iter().filter_map(|t| match t {
+            Token::Word(w) => Some(w),
+            Token::Redirect { .. } => None,
+        }).collect()
+    }
+
+    fn texts(input: &str, env: &BTreeMap<String, String>) -> Vec<String> {
+        tokenize(input, env).unwrap().into_iter().filter_map(|t| match t {
+            Token::Word(w) => Some(w.text),
+            Token::Redirect { .. } => None,
+        }).collect()
+    }
+
+    #[test_case]
+    fn test_tokenize_quotes_and_escapes() {
+        let env = BTreeMap::new();
+        assert_eq!(texts(r#"echo "hello world" 'a b' c\ d"#, &env), ["echo", "hello world", "a b", "c d"]);
+        assert_eq!(texts(r#"echo "" x"#, &env), ["echo", "", "x"]);
+        assert_eq!(texts(r#"echo "say \"hi\"""#, &env), ["echo", "say \"hi\""]);
+        assert_eq!(tokenize("echo 'oops", &env), Err(LexError::UnterminatedQuote));
+        assert_eq!(tokenize("echo \\", &env), Err(LexError::TrailingBackslash));
+    }
+
+    #[test_case]
+    fn test_tokenize_variables() {
+        let mut env = BTreeMap::new();
+        env.insert("HOME".into(), "/home".into());
+        assert_eq!(texts("cd $HOME/docs", &env), ["cd", "/home/docs"]);
+        assert_eq!(texts("echo ${HOME}x \"$HOME\" '$HOME' $ $NOPE.", &env), ["echo", "/homex", "/home", "$HOME", "$", "."]);
+    }
+
+    #[test_case]
+    fn test_glob_match() {
+        assert!(glob_match("*.txt", "notes.txt"));
+        assert!(!glob_match("*.txt", "notes.txt.bak"));
+        assert!(glob_match("a?c", "abc"));
+        assert!(glob_match("*a*b", "xxaxxb"));
+        assert!(!glob_match("a?c", "ac"));
+
+        // Un joker entre guillemets est littéral
+        let words = words("rm '*'.txt *.txt");
+        assert!(!words[1].has_glob);
+        assert!(glob_match(&words[1].pattern, "*.txt"));
+        assert!(!glob_match(&words[1].pattern, "a.txt"));
+        assert!(words[2].has_glob);
+    }
+
+    #[test_case]
+    fn test_tokenize_redirections() {
+        let tokens = tokenize("cat<in >out 2>>err '>' x2>y", &BTreeMap::new()).unwrap();
+        assert_eq!(tokens[1], Token::Redirect { fd: 0, op: RedirectOp::Input });
+        assert_eq!(tokens[3], Token::Redirect { fd: 1, op: RedirectOp::Output });
+        assert_eq!(tokens[5], Token::Redirect { fd: 2, op: RedirectOp::Append });
+        // Un opérateur entre guillemets est un mot ordinaire
+        assert_eq!(texts("echo '>' \\> 2\\>", &BTreeMap::new()), ["echo", ">", ">", "2>"]);
+        // `x2>` n'est pas un numéro de descripteur
+        assert_eq!(tokens[8], Token::Word(words("x2")[0].clone()));
+        assert_eq!(tokens[9], Token::Redirect { fd: 1, op: RedirectOp::Output });
+    }
+}
@@ -0,0 +1,147 @@
+//! Commandes mémoire : free et vmstat
+//!
+//! Les deux lisent `memory::mem_stats` (mêmes compteurs que
+//! /proc/meminfo). `free` affiche un instantané ; `vmstat` imprime une
+//! ligne par intervalle, les colonnes si/so (pages échangées), in et cs
+//! (interruptions, changements de contexte) étant des écarts depuis la
+//! ligne précédente. `vmstat -m` détaille les caches SLAB.
+
+use alloc::string::String;
+use mini_os::memory::{self, MemStats};
+use mini_os::percpu::{Stat, STATS};
+use mini_os::scheduler::{self, TIMER_HZ};
+
+use super::{redirect, top, Command, Shell, ShellError};
+
+/// Interruptions et changements de contexte, tous CPU confondus
+fn system_counters() -> (u64, u64) {
+    STATS.iter().fold((0, 0), |(irq, cs), stats| (irq + stats.get(Stat::Irq), cs + stats.get(Stat::ContextSwitch)))
+}
+
+/// Table de `free`, tailles divisées par `unit`
+fn format_free(stats: &MemStats, unit: u64) -> String {
+    let mut out = format!("{:>6} {:>10} {:>10} {:>10} {:>10} {:>10}\n", "", "total", "used", "free", "cached", "available");
+    out.push_str(&format!(
+        "{:<6} {:>10} {:>10} {:>10} {:>10} {:>10}\n",
+        "Mem:",
+        stats.total / unit,
+        stats.used() / unit,
+        stats.free / unit,
+        stats.cached / unit,
+        stats.available() / unit
+    ));
+    out.push_str(&format!(
+        "{:<6} {:>10} {:>10} {:>10} {:>10} {:>10}\n",
+        "Heap:",
+        stats.heap_total / unit,
+        stats.heap_used / unit,
+        stats.heap_free() / unit,
+        stats.slab / unit,
+        stats.heap_peak / unit
+    ));
+    out.push_str(&format!("{:<6} {:>10} {:>10}\n", "Swap:", "", stats.swap_used / unit));
+    out
+}
+
+/// Ligne de `vmstat` (Kio), écarts calculés depuis `previous`
+fn format_vmstat(stats: &MemStats, previous: &MemStats, system: (u64, u64), previous_system: (u64, u64), runnable: usize) -> String {
+    format!(
+        "{:>3} {:>8} {:>8} {:>8} {:>6} {:>8} {:>6} {:>8} {:>4} {:>4} {:>6} {:>6}\n",
+        runnable,
+        stats.swap_used / 1024,
+        stats.free / 1024,
+        stats.cached / 1024,
+        stats.dirty / 1024,
+        stats.heap_used / 1024,
+        stats.slab / 1024,
+        stats.slab_active_objects,
+        stats.pages_swapped_in.saturating_sub(previous.pages_swapped_in),
+        stats.pages_swapped_out.saturating_sub(previous.pages_swapped_out),
+        system.0.saturating_sub(previous_system.0),
+        system.1.saturating_sub(previous_system.1)
+    )
+}
+
+const VMSTAT_HEADER: &str = "  r     swpd     free    cache  dirty     heap   slab  objects   si   so     in     cs\n";
+
+impl Shell {
+    /// free [-b|-k|-m]
+    pub(super) fn builtin_free(&self, cmd: &Command) -> Result<(), ShellError> {
+        let unit = match cmd.args.first().map(String::as_str) {
+            None | Some("-k") => 1024,
+            Some("-b") => 1,
+            Some("-m") => 1024 * 1024,
+            Some(_) => return Err(ShellError::InvalidArguments),
+        };
+        if cmd.args.len() > 1 {
+            return Err(ShellError::InvalidArguments);
+        }
+        redirect::print_out(&format_free(&memory::mem_stats(), unit));
+        Ok(())
+    }
+
+    /// vmstat [-m] [délai [nombre]]
+    pub(super) fn builtin_vmstat(&self, cmd: &Command) -> Result<(), ShellError> {
+        if cmd.args.first().map(String::as_str) == Some("-m") {
+            let mut out = String::from("Cache        Objets   Total  Slabs\n");
+            for cache in memory::HYBRID_ALLOCATOR.slab_cache_stats() {
+                out.push_str(&format!(
+                    "size-{:<7} {:>6} {:>7} {:>6}\n",
+                    cache.object_size, cache.active_objects, cache.total_objects, cache.slabs
+                ));
+            }
+            redirect::print_out(&out);
+            return Ok(());
+        }
+
+        let parse = |i: usize| cmd.args.get(i).map(|v| v.parse::<u64>().map_err(|_| ShellError::InvalidArguments)).transpose();
+        let delay = parse(0)?;
+        if delay == Some(0) || cmd.args.len() > 2 {
+            return Err(ShellError::InvalidArguments);
+        }
+        // Sans délai : une ligne ; avec délai seul : jusqu'à `q`
+        let count = parse(1)?.unwrap_or(if delay.is_some() { u64::MAX } else { 1 });
+
+        redirect::print_out(VMSTAT_HEADER);
+        // Première ligne : écarts depuis le démarrage, comme vmstat
+        let mut previous = MemStats::default();
+        let mut previous_system = (0, 0);
+        for line in 0..count {
+            let stats = memory::mem_stats();
+            let system = system_counters();
+            let runnable = scheduler::SCHEDULER.runnable();
+            redirect::print_out(&format_vmstat(&stats, &previous, system, previous_system, runnable));
+            previous = stats;
+            previous_system = system;
+
+            if line + 1 == count {
+                break;
+            }
+            let deadline = scheduler::ticks() + delay.unwrap_or(1) * TIMER_HZ;
+            if matches!(top::wait_key(deadline), Some(b'q') | Some(b'Q')) {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_free_and_vmstat_formatting() {
+        let stats = MemStats { total: 8 << 20, free: 2 << 20, cached: 1 << 20, dirty: 0, heap_total: 1 << 20, heap_used: 512 << 10, pages_swapped_out: 7, ..Default::default() };
+        let table = format_free(&stats, 1024);
+        let mem = table.lines().nth(1).unwrap();
+        assert!(mem.starts_with("Mem:"));
+        let values: alloc::vec::Vec<&str> = mem.split_whitespace().skip(1).collect();
+        assert_eq!(values, ["8192", "6144", "2048", "1024", "3072"]);
+
+        let line = format_vmstat(&stats, &MemStats { pages_swapped_out: 5, ..Default::default() }, (30, 12), (10, 2), 1);
+        let columns: alloc::vec::Vec<&str> = line.split_whitespace().collect();
+        assert_eq!(columns.len(), VMSTAT_HEADER.split_whitespace().count());
+        assert_eq!(&columns[9..], ["2", "20", "10"]);
+    }
+}
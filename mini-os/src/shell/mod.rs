@@ -1,9 +1,21 @@
+pub mod disk;
+pub mod history;
+pub mod lexer;
+pub mod mem;
+pub mod perf;
+pub mod proc;
+pub mod redirect;
+pub mod script;
+pub mod top;
+pub mod trace;
+
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 use alloc::collections::BTreeMap;
 use spin::Mutex;
 use lazy_static::lazy_static;
-use crate::vga_buffer::WRITER;
+use crate::terminal::Terminal;
+use crate::vga_buffer::{WRITER, SHELL_VT};
 
 /// Erreurs possibles du shell
 #[derive(Debug)]
@@ -12,6 +24,8 @@ pub enum ShellError {
     InvalidArguments,
     ExecutionFailed(String),
     IOError,
+    /// Programme externe terminé avec un code non nul
+    ExitStatus(i32),
 }
 
 /// Représente une commande parsée
@@ -22,6 +36,9 @@ pub struct Command {
     pub stdin: Option<String>,
     pub stdout: Option<String>,
     pub stderr: Option<String>,
+    /// `>>` plutôt que `>` pour stdout / stderr
+    pub stdout_append: bool,
+    pub stderr_append: bool,
     pub pipes: Vec<Command>,
 }
 
@@ -33,21 +50,50 @@ impl Command {
             stdin: None,
             stdout: None,
             stderr: None,
+            stdout_append: false,
+            stderr_append: false,
             pipes: Vec::new(),
         }
     }
 
+    /// Redirections à installer avant l'exécution
+    fn redirects(&self) -> Vec<redirect::Redirect> {
+        use lexer::RedirectOp;
+        let mut redirects = Vec::new();
+        if let Some(path) = &self.stdin {
+            redirects.push(redirect::Redirect { fd: redirect::STDIN, op: RedirectOp::Input, path: path.clone() });
+        }
+        if let Some(path) = &self.stdout {
+            let op = if self.stdout_append { RedirectOp::Append } else { RedirectOp::Output };
+            redirects.push(redirect::Redirect { fd: redirect::STDOUT, op, path: path.clone() });
+        }
+        if let Some(path) = &self.stderr {
+            let op = if self.stderr_append { RedirectOp::Append } else { RedirectOp::Output };
+            redirects.push(redirect::Redirect { fd: redirect::STDERR, op, path: path.clone() });
+        }
+        redirects
+    }
+
     pub fn add_arg(&mut self, arg: &str) {
         self.args.push(arg.into());
     }
 }
 
+/// Commandes intégrées (utilisées pour la complétion)
+pub const BUILTINS: &[&str] = &[
+    "[", "beep", "cat", "cd", "clear", "cp", "df", "dmesg", "echo", "exit", "export", "false", "fdisk", "free", "fsck",
+    "gui", "hdinfo", "help", "history", "httpd", "ifconfig", "ip", "kexec", "kill", "ln", "ls", "lsblk", "mkdir", "mkfifo", "mkfs.fat32", "mount", "mv", "netconsole", "nice", "netstat", "perf", "ping", "play", "poweroff", "ps", "pwd",
+    "reboot", "renice", "rm", "sh", "shutdown", "tcpdump", "test", "time", "top", "trace", "true", "ulimit", "umount", "vmstat", "wget",
+];
+
 /// Gestionnaire du shell
 pub struct Shell {
     pub current_dir: String,
     pub env_vars: BTreeMap<String, String>,
     pub history: Vec<String>,
     pub history_index: usize,
+    /// Scripts en cours d'exécution (protection contre la récursion)
+    script_depth: usize,
 }
 
 impl Shell {
@@ -64,62 +110,148 @@ impl Shell {
             env_vars,
             history: Vec::new(),
             history_index: 0,
+            script_depth: 0,
         }
     }
 
+    /// Prompt : répertoire courant
+    pub fn prompt(&self) -> String {
+        format!("{}> ", self.current_dir)
+    }
+
     /// Affiche le prompt
     pub fn print_prompt(&self) {
-        WRITER.lock().write_string(&format!("{}> ", self.current_dir));
+        WRITER.lock().write_vt(SHELL_VT, &self.prompt());
     }
 
-    /// Parse une ligne de commande
+    /// Parse une ligne de commande (guillemets, échappements, `$VAR`, jokers,
+    /// redirections `<`, `>`, `>>`, `2>`, `2>>`)
     pub fn parse_command(&self, input: &str) -> Result<Command, ShellError> {
-        let input = input.trim();
-        
-        if input.is_empty() {
-            return Err(ShellError::InvalidArguments);
+        use lexer::{RedirectOp, Token};
+
+        let tokens = lexer::tokenize(input, &self.env_vars).map_err(|e| {
+            WRITER.lock().write_vt(SHELL_VT, &format!("shell: erreur de syntaxe: {:?}\n", e));
+            ShellError::InvalidArguments
+        })?;
+
+        let mut parts = Vec::new();
+        let mut redirects = Vec::new();
+        let mut tokens = tokens.into_iter();
+        while let Some(token) = tokens.next() {
+            match token {
+                Token::Word(word) => parts.extend(lexer::expand_glob(&word, &self.current_dir)),
+                Token::Redirect { fd, op } => {
+                    // La cible doit être un mot unique
+                    let target = match tokens.next() {
+                        Some(Token::Word(word)) => lexer::expand_glob(&word, &self.current_dir),
+                        _ => Vec::new(),
+                    };
+                    if target.len() != 1 {
+                        WRITER.lock().write_vt(SHELL_VT, "shell: cible de redirection invalide\n");
+                        return Err(ShellError::InvalidArguments);
+                    }
+                    redirects.push((fd, op, self.resolve_path(&target[0])));
+                }
+            }
         }
 
-        let parts: Vec<&str> = input.split_whitespace().collect();
-        if parts.is_empty() {
-            return Err(ShellError::InvalidArguments);
+        let mut parts = parts.into_iter();
+        let program = parts.next().ok_or(ShellError::InvalidArguments)?;
+        let mut cmd = Command::new(&program);
+        for part in parts {
+            cmd.add_arg(&part);
         }
 
-        let mut cmd = Command::new(parts[0]);
-        
-        for part in &parts[1..] {
-            cmd.add_arg(part);
+        for (fd, op, path) in redirects {
+            match (fd, op) {
+                (redirect::STDIN, RedirectOp::Input) => cmd.stdin = Some(path),
+                (redirect::STDOUT, RedirectOp::Output | RedirectOp::Append) => {
+                    cmd.stdout = Some(path);
+                    cmd.stdout_append = op == RedirectOp::Append;
+                }
+                (redirect::STDERR, RedirectOp::Output | RedirectOp::Append) => {
+                    cmd.stderr = Some(path);
+                    cmd.stderr_append = op == RedirectOp::Append;
+                }
+                _ => {
+                    WRITER.lock().write_vt(SHELL_VT, &format!("shell: redirection du descripteur {} non supportée\n", fd));
+                    return Err(ShellError::InvalidArguments);
+                }
+            }
         }
 
         Ok(cmd)
     }
 
-    /// Exécute une commande
+    /// Exécute une commande, entrées/sorties redirigées via la table de descripteurs
     pub fn execute(&mut self, cmd: Command) -> Result<(), ShellError> {
+        let _stdio = redirect::apply(&cmd.redirects()).map_err(|e| {
+            WRITER.lock().write_vt(SHELL_VT, &format!("shell: redirection impossible: {:?}\n", e));
+            ShellError::IOError
+        })?;
+        // `_stdio` restaure les descripteurs d'origine en sortie de portée
+        self.run_builtin(&cmd)
+    }
+
+    fn run_builtin(&mut self, cmd: &Command) -> Result<(), ShellError> {
         match cmd.program.as_str() {
-            "cd" => self.builtin_cd(&cmd),
-            "pwd" => self.builtin_pwd(&cmd),
-            "ls" => self.builtin_ls(&cmd),
-            "echo" => self.builtin_echo(&cmd),
-            "cat" => self.builtin_cat(&cmd),
-            "mkdir" => self.builtin_mkdir(&cmd),
-            "rm" => self.builtin_rm(&cmd),
-            "cp" => self.builtin_cp(&cmd),
-            "mv" => self.builtin_mv(&cmd),
-            "exit" => self.builtin_exit(&cmd),
-            "help" => self.builtin_help(&cmd),
-            "export" => self.builtin_export(&cmd),
-            "ps" => self.builtin_ps(&cmd),
-            "clear" => self.builtin_clear(&cmd),
-            "history" => self.builtin_history(&cmd),
-            _ => Err(ShellError::CommandNotFound(cmd.program.clone())),
-        }
-    }
-
-    /// Ajoute une commande à l'historique
-    pub fn add_to_history(&mut self, cmd: &str) {
-        self.history.push(cmd.into());
-        self.history_index = self.history.len();
+            "cd" => self.builtin_cd(cmd),
+            "pwd" => self.builtin_pwd(cmd),
+            "ls" => self.builtin_ls(cmd),
+            "echo" => self.builtin_echo(cmd),
+            "cat" => self.builtin_cat(cmd),
+            "mkdir" => self.builtin_mkdir(cmd),
+            "mkfifo" => self.builtin_mkfifo(cmd),
+            "df" => self.builtin_df(cmd),
+            "mount" => self.builtin_mount(cmd),
+            "umount" => self.builtin_umount(cmd),
+            "fdisk" => self.builtin_fdisk(cmd),
+            "mkfs.fat32" => self.builtin_mkfs_fat32(cmd),
+            "fsck" => self.builtin_fsck(cmd),
+            "lsblk" => self.builtin_lsblk(cmd),
+            "top" => self.builtin_top(cmd),
+            "perf" => self.builtin_perf(cmd),
+            "trace" => self.builtin_trace(cmd),
+            "free" => self.builtin_free(cmd),
+            "vmstat" => self.builtin_vmstat(cmd),
+            "kill" => self.builtin_kill(cmd),
+            "nice" => self.builtin_nice(cmd),
+            "renice" => self.builtin_renice(cmd),
+            "hdinfo" => self.builtin_hdinfo(cmd),
+            "rm" => self.builtin_rm(cmd),
+            "cp" => self.builtin_cp(cmd),
+            "mv" => self.builtin_mv(cmd),
+            "ln" => self.builtin_ln(cmd),
+            "exit" => self.builtin_exit(cmd),
+            "help" => self.builtin_help(cmd),
+            "export" => self.builtin_export(cmd),
+            "ps" => self.builtin_ps(cmd),
+            "clear" => self.builtin_clear(cmd),
+            "history" => self.builtin_history(cmd),
+            "sh" => self.builtin_sh(cmd),
+            "test" | "[" => self.builtin_test(cmd),
+            "play" => self.builtin_play(cmd),
+            "beep" => self.builtin_beep(cmd),
+            "gui" => self.builtin_gui(cmd),
+            "shutdown" | "poweroff" => self.builtin_shutdown(cmd),
+            "reboot" => self.builtin_reboot(cmd),
+            "time" => self.builtin_time(cmd),
+            "ping" => self.builtin_ping(cmd),
+            "wget" => self.builtin_wget(cmd),
+            "httpd" => self.builtin_httpd(cmd),
+            "netstat" => self.builtin_netstat(cmd),
+            "tcpdump" => self.builtin_tcpdump(cmd),
+            "dmesg" => self.builtin_dmesg(cmd),
+            "netconsole" => self.builtin_netconsole(cmd),
+            "ifconfig" => self.builtin_ifconfig(cmd),
+            "ip" => self.builtin_ip(cmd),
+            "kexec" => self.builtin_kexec(cmd),
+            "ulimit" => self.builtin_ulimit(cmd),
+            "true" => Ok(()),
+            "false" => Err(ShellError::ExecutionFailed("false".into())),
+            // Programme ELF ou script, par chemin ou cherché dans $PATH
+            _ => self.run_external(cmd),
+        }
     }
 
     // ============ COMMANDES BUILTINS ============
@@ -157,14 +289,14 @@ impl Shell {
             self.current_dir = new_dir;
             Ok(())
         } else {
-            WRITER.lock().write_string(&format!("cd: {}: Aucun dossier de ce type\n", new_dir));
+            redirect::print_err(&format!("cd: {}: Aucun dossier de ce type\n", new_dir));
             Err(ShellError::ExecutionFailed("Directory not found".into()))
         }
     }
 
     /// Commande: pwd
     fn builtin_pwd(&self, _cmd: &Command) -> Result<(), ShellError> {
-        WRITER.lock().write_string(&format!("{}\n", self.current_dir));
+        redirect::print_out(&format!("{}\n", self.current_dir));
         Ok(())
     }
 
@@ -189,89 +321,178 @@ impl Shell {
         match mini_os::fs::vfs_ls(&target_dir) {
             Ok(entries) => {
                 for entry in entries {
-                    WRITER.lock().write_string(&format!("  {}\n", entry));
+                    redirect::print_out(&format!("  {}\n", entry));
                 }
                 Ok(())
             }
             Err(_) => {
-                WRITER.lock().write_string(&format!("ls: impossible d'accéder à '{}': Aucun fichier ou dossier de ce type\n", target_dir));
+                redirect::print_err(&format!("ls: impossible d'accéder à '{}': Aucun fichier ou dossier de ce type\n", target_dir));
                 Err(ShellError::ExecutionFailed("ls failed".into()))
             }
         }
     }
 
-    /// Commande: echo <texte> [> <fichier>]
+    /// Commande: echo <texte>
     fn builtin_echo(&self, cmd: &Command) -> Result<(), ShellError> {
-        let args = &cmd.args;
-        
-        // Check for redirection
-        if let Some(pos) = args.iter().position(|r| r == ">") {
-             if pos + 1 >= args.len() {
-                 WRITER.lock().write_string("echo: erreur de syntaxe redirection\n");
-                 return Err(ShellError::InvalidArguments);
-             }
-             
-             let content_args = &args[..pos];
-             let filename = &args[pos+1];
-             let text = content_args.join(" ");
-             
-             let full_path = if filename.starts_with('/') {
-                filename.clone()
-            } else {
-                 if self.current_dir == "/" {
-                    format!("/{}", filename)
-                } else {
-                    format!("{}/{}", self.current_dir, filename)
+        redirect::print_out(&format!("{}\n", cmd.args.join(" ")));
+        Ok(())
+    }
+
+    /// Commande: cat [fichier] (entrée standard sans argument)
+    fn builtin_cat(&self, cmd: &Command) -> Result<(), ShellError> {
+        let content = match cmd.args.first() {
+            Some(filename) => match mini_os::fs::vfs_read_file(&self.resolve_path(filename)) {
+                Ok(content) => content,
+                Err(_) => {
+                    redirect::print_err(&format!("cat: {}: Aucun fichier de ce type\n", filename));
+                    return Err(ShellError::ExecutionFailed("cat failed".into()));
                 }
-            };
-            
-            match mini_os::fs::vfs_write_file(&full_path, text.as_bytes()) {
-                Ok(_) => Ok(()),
+            },
+            None => redirect::read_stdin().ok_or(ShellError::InvalidArguments)?,
+        };
+
+        let text = String::from_utf8_lossy(&content);
+        redirect::print_out(&text);
+        // Retour à la ligne final pour la lisibilité à la console
+        if !text.is_empty() && !text.ends_with('\n') {
+            redirect::print_out("\n");
+        }
+        Ok(())
+    }
+
+    /// Commande: df [chemin] (tailles en Kio, "-" si le système de fichiers n'a pas de taille fixe)
+    fn builtin_df(&self, cmd: &Command) -> Result<(), ShellError> {
+        let mut mounts = match cmd.args.first() {
+            Some(path) => alloc::vec![self.resolve_path(path)],
+            None => mini_os::fs::MOUNT_MANAGER.lock().list_mounts(),
+        };
+        mounts.sort();
+
+        redirect::print_out(&format!("{:<8} {:>12} {:>11} {:>11} {:>4} {}\n", "Type", "1K-blocs", "Utilisé", "Disponible", "Uti%", "Monté sur"));
+        for path in mounts {
+            let stat = match mini_os::fs::vfs_statfs(&path) {
+                Ok(stat) => stat,
                 Err(e) => {
-                    WRITER.lock().write_string(&format!("echo: erreur d'écriture: {:?}\n", e));
-                    Err(ShellError::ExecutionFailed("write failed".into()))
+                    redirect::print_err(&format!("df: {}: {}\n", path, e));
+                    return Err(ShellError::ExecutionFailed("df failed".into()));
                 }
+            };
+            if stat.total_blocks == 0 {
+                redirect::print_out(&format!("{:<8} {:>12} {:>11} {:>11} {:>4} {}\n", stat.fs_name, "-", "-", "-", "-", path));
+                continue;
             }
-        } else {
-            let text = args.join(" ");
-            WRITER.lock().write_string(&format!("{}\n", text));
-            Ok(())
+            let (total, used, free) = stat.bytes();
+            let percent = (stat.used_blocks() * 100).div_ceil(stat.total_blocks);
+            redirect::print_out(&format!(
+                "{:<8} {:>12} {:>11} {:>11} {:>3}% {}\n",
+                stat.fs_name, total / 1024, used / 1024, free / 1024, percent, path
+            ));
         }
+        Ok(())
     }
 
-    /// Commande: cat <fichier>
-    fn builtin_cat(&self, cmd: &Command) -> Result<(), ShellError> {
+    /// Commande: mount [-t <type> [-o options] [<source>] <cible>] (sans argument :
+    /// liste des montages). `overlay` prend `-o lowerdir=<bas>,upperdir=<haut>`,
+    /// `tmpfs` `-o size=<taille>,nr_inodes=<n>`, les autres types un
+    /// périphérique bloc (`-o ro` facultatif)
+    fn builtin_mount(&self, cmd: &Command) -> Result<(), ShellError> {
         if cmd.args.is_empty() {
-            return Err(ShellError::InvalidArguments);
+            let mut mounts = mini_os::fs::MOUNT_MANAGER.lock().list_mounts();
+            mounts.sort();
+            for path in mounts {
+                if let Ok(stat) = mini_os::fs::vfs_statfs(&path) {
+                    let mode = if stat.readonly { "ro" } else { "rw" };
+                    redirect::print_out(&format!("{:<8} {} ({})\n", stat.fs_name, path, mode));
+                }
+            }
+            return Ok(());
         }
 
-        let filename = &cmd.args[0];
-        let full_path = if filename.starts_with('/') {
-            filename.clone()
-        } else {
-             if self.current_dir == "/" {
-                format!("/{}", filename)
-            } else {
-                format!("{}/{}", self.current_dir, filename)
+        let mut fs_type = None;
+        let mut options = "";
+        let mut operands = Vec::new();
+        let mut args = cmd.args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-t" => fs_type = args.next().map(|t| t.as_str()),
+                "-o" => options = args.next().map_or("", |o| o.as_str()),
+                _ => operands.push(arg.as_str()),
             }
+        }
+        let Some(fs_type) = fs_type else {
+            return Err(ShellError::InvalidArguments);
         };
 
-        match mini_os::fs::vfs_read_file(&full_path) {
-            Ok(content) => {
-                // Convert bytes to string (lossy)
-                let text = String::from_utf8_lossy(&content);
-                WRITER.lock().write_string(&text);
-                // Print newline if content doesn't end with one? Or just strictly print content?
-                // Cat usually prints raw content. But our Writer might need newline for flush?
-                // Let's print newline for better readability in this mini-shell.
-                if !text.ends_with('\n') {
-                    WRITER.lock().write_string("\n");
+        let (result, target) = if fs_type == "overlay" {
+            let [target] = operands.as_slice() else {
+                return Err(ShellError::InvalidArguments);
+            };
+            let mut lower = None;
+            let mut upper = None;
+            for option in options.split(',') {
+                match option.split_once('=') {
+                    Some(("lowerdir", dir)) => lower = Some(self.resolve_path(dir)),
+                    Some(("upperdir", dir)) => upper = Some(self.resolve_path(dir)),
+                    _ => {
+                        redirect::print_err(&format!("mount: option inconnue '{}'\n", option));
+                        return Err(ShellError::InvalidArguments);
+                    }
                 }
-                Ok(())
             }
-            Err(_) => {
-                WRITER.lock().write_string(&format!("cat: {}: Aucun fichier de ce type\n", filename));
-                Err(ShellError::ExecutionFailed("cat failed".into()))
+            let (Some(lower), Some(upper)) = (lower, upper) else {
+                redirect::print_err("mount: lowerdir et upperdir sont requis\n");
+                return Err(ShellError::InvalidArguments);
+            };
+            (mini_os::fs::mount_overlay(&self.resolve_path(target), &lower, &upper), *target)
+        } else if fs_type == "tmpfs" {
+            // La source (« tmpfs », « none »...) est ignorée, comme sous Linux
+            let target = match operands.as_slice() {
+                [target] | [_, target] => *target,
+                _ => return Err(ShellError::InvalidArguments),
+            };
+            (mini_os::fs::mount_tmpfs(&self.resolve_path(target), options), target)
+        } else {
+            if !mini_os::fs_manager::fs_types().contains(&fs_type) {
+                redirect::print_err(&format!("mount: type de système de fichiers inconnu '{}'\n", fs_type));
+                return Err(ShellError::InvalidArguments);
+            }
+            let [source, target] = operands.as_slice() else {
+                return Err(ShellError::InvalidArguments);
+            };
+            let mut flags = 0;
+            for option in options.split(',').filter(|o| !o.is_empty()) {
+                match option {
+                    "ro" => flags |= mini_os::fs::MountFlags::READONLY,
+                    "rw" => {}
+                    _ => {
+                        redirect::print_err(&format!("mount: option inconnue '{}'\n", option));
+                        return Err(ShellError::InvalidArguments);
+                    }
+                }
+            }
+            let flags = mini_os::fs::MountFlags::new(flags);
+            (mini_os::fs_manager::mount_device(fs_type, source, &self.resolve_path(target), flags), *target)
+        };
+
+        match result {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                redirect::print_err(&format!("mount: {}: {}\n", target, e));
+                Err(ShellError::ExecutionFailed("mount failed".into()))
+            }
+        }
+    }
+
+    /// Commande: umount <cible>
+    fn builtin_umount(&self, cmd: &Command) -> Result<(), ShellError> {
+        let [target] = cmd.args.as_slice() else {
+            return Err(ShellError::InvalidArguments);
+        };
+        match mini_os::fs::unmount_fs(&self.resolve_path(target)) {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                redirect::print_err(&format!("umount: {}: {}\n", target, e));
+                Err(ShellError::ExecutionFailed("umount failed".into()))
             }
         }
     }
@@ -296,12 +517,36 @@ impl Shell {
         match mini_os::fs::vfs_mkdir(&full_path) {
             Ok(_) => Ok(()),
             Err(e) => {
-                WRITER.lock().write_string(&format!("mkdir: impossible de créer le dossier '{}': {:?}\n", dirname, e));
+                redirect::print_err(&format!("mkdir: impossible de créer le dossier '{}': {:?}\n", dirname, e));
                 Err(ShellError::ExecutionFailed("mkdir failed".into()))
             }
         }
     }
 
+    /// Commande: mkfifo [-m mode] <chemin>
+    fn builtin_mkfifo(&self, cmd: &Command) -> Result<(), ShellError> {
+        let (mode, name) = match cmd.args.as_slice() {
+            [name] => (0o644, name),
+            [flag, mode, name] if flag == "-m" => match u16::from_str_radix(mode, 8) {
+                Ok(mode) if mode <= 0o7777 => (mode, name),
+                _ => {
+                    redirect::print_err(&format!("mkfifo: mode invalide '{}'\n", mode));
+                    return Err(ShellError::InvalidArguments);
+                }
+            },
+            _ => return Err(ShellError::InvalidArguments),
+        };
+
+        let path = self.resolve_path(name);
+        match mini_os::fs::vfs_create(&path, mini_os::fs::FileMode::new(mode), mini_os::fs::FileType::Fifo) {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                redirect::print_err(&format!("mkfifo: impossible de créer '{}': {:?}\n", name, e));
+                Err(ShellError::ExecutionFailed("mkfifo failed".into()))
+            }
+        }
+    }
+
     /// Commande: rm <fichier>
     fn builtin_rm(&self, cmd: &Command) -> Result<(), ShellError> {
         if cmd.args.is_empty() {
@@ -322,23 +567,74 @@ impl Shell {
         match mini_os::fs::vfs_remove_file(&full_path) {
             Ok(_) => Ok(()),
             Err(e) => {
-                 WRITER.lock().write_string(&format!("rm: impossible de supprimer '{}': {:?}\n", filename, e));
+                 redirect::print_err(&format!("rm: impossible de supprimer '{}': {:?}\n", filename, e));
                  Err(ShellError::ExecutionFailed("rm failed".into()))
             }
         }
     }
 
+    /// Résout un chemin relatif au répertoire courant
+    fn resolve_path(&self, path: &str) -> String {
+        if path.starts_with('/') {
+            path.into()
+        } else if self.current_dir == "/" {
+            format!("/{}", path)
+        } else {
+            format!("{}/{}", self.current_dir, path)
+        }
+    }
+
+    /// Destination effective : `dst/<nom de src>` si `dst` est un répertoire
+    fn resolve_destination(&self, src: &str, dst: &str) -> String {
+        let dst = self.resolve_path(dst);
+        if mini_os::fs::is_dir(&dst) {
+            let name = src.trim_end_matches('/').rsplit('/').next().unwrap_or(src);
+            if dst.ends_with('/') {
+                format!("{}{}", dst, name)
+            } else {
+                format!("{}/{}", dst, name)
+            }
+        } else {
+            dst
+        }
+    }
+
+    /// Copie le contenu et le mode de `src` vers `dst`
+    fn copy_file(src: &str, dst: &str) -> mini_os::fs::VfsResult<()> {
+        use mini_os::fs::{FileType, VfsError};
+
+        let stat = mini_os::fs::vfs_stat(src)?;
+        if stat.file_type == FileType::Directory {
+            return Err(VfsError::IsDirectory);
+        }
+        let content = mini_os::fs::vfs_read_file(src)?;
+        match mini_os::fs::vfs_create(dst, stat.mode, FileType::Regular) {
+            Ok(_) | Err(VfsError::AlreadyExists) => {}
+            Err(e) => return Err(e),
+        }
+        mini_os::fs::vfs_write_file(dst, &content)
+    }
+
     /// Commande: cp <source> <destination>
     fn builtin_cp(&self, cmd: &Command) -> Result<(), ShellError> {
         if cmd.args.len() < 2 {
             return Err(ShellError::InvalidArguments);
         }
 
-        let src = &cmd.args[0];
-        let dst = &cmd.args[1];
-        WRITER.lock().write_string(&format!("Copie de {} vers {}\n", src, dst));
-        
-        Ok(())
+        let src = self.resolve_path(&cmd.args[0]);
+        let dst = self.resolve_destination(&src, &cmd.args[1]);
+        if src == dst {
+            redirect::print_err(&format!("cp: '{}' et '{}' sont le même fichier\n", cmd.args[0], cmd.args[1]));
+            return Err(ShellError::ExecutionFailed("cp failed".into()));
+        }
+
+        match Self::copy_file(&src, &dst) {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                redirect::print_err(&format!("cp: impossible de copier '{}': {:?}\n", cmd.args[0], e));
+                Err(ShellError::ExecutionFailed("cp failed".into()))
+            }
+        }
     }
 
     /// Commande: mv <source> <destination>
@@ -347,42 +643,830 @@ impl Shell {
             return Err(ShellError::InvalidArguments);
         }
 
-        let src = &cmd.args[0];
-        let dst = &cmd.args[1];
-        WRITER.lock().write_string(&format!("Déplacement de {} vers {}\n", src, dst));
-        
-        Ok(())
+        let src = self.resolve_path(&cmd.args[0]);
+        let dst = self.resolve_destination(&src, &cmd.args[1]);
+
+        let result = match mini_os::fs::vfs_rename(&src, &dst) {
+            // Systèmes de fichiers différents : copie puis suppression
+            Err(mini_os::fs::VfsError::NotSupported) => {
+                Self::copy_file(&src, &dst).and_then(|_| mini_os::fs::vfs_remove_file(&src))
+            }
+            other => other,
+        };
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                redirect::print_err(&format!("mv: impossible de déplacer '{}': {:?}\n", cmd.args[0], e));
+                Err(ShellError::ExecutionFailed("mv failed".into()))
+            }
+        }
+    }
+
+    /// Commande: ln [-s] <cible> <nom>
+    fn builtin_ln(&self, cmd: &Command) -> Result<(), ShellError> {
+        let symbolic = cmd.args.first().map(|a| a == "-s").unwrap_or(false);
+        let args = if symbolic { &cmd.args[1..] } else { &cmd.args[..] };
+        if args.len() < 2 {
+            return Err(ShellError::InvalidArguments);
+        }
+
+        let src = self.resolve_path(&args[0]);
+        let dst = self.resolve_destination(&src, &args[1]);
+
+        let result = if symbolic {
+            // La cible d'un lien symbolique est gardée telle quelle
+            mini_os::fs::SYMLINK_MANAGER
+                .lock()
+                .create_symlink(dst.clone(), args[0].clone(), 0, 0)
+                .map(|_| ())
+                .map_err(|e| format!("{:?}", e))
+        } else {
+            mini_os::fs::vfs_link(&src, &dst).map_err(|e| format!("{}", e))
+        };
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                redirect::print_err(&format!("ln: impossible de créer le lien '{}': {}\n", dst, e));
+                Err(ShellError::ExecutionFailed("ln failed".into()))
+            }
+        }
     }
 
     /// Commande: exit
     fn builtin_exit(&self, _cmd: &Command) -> Result<(), ShellError> {
-        WRITER.lock().write_string("Au revoir!\n");
+        redirect::print_out("Au revoir!\n");
         // TODO: Terminer le shell
         Ok(())
     }
 
     /// Commande: help
     fn builtin_help(&self, _cmd: &Command) -> Result<(), ShellError> {
-        WRITER.lock().write_string("Commandes disponibles:\n");
-        WRITER.lock().write_string("  cd <dir>      - Changer de répertoire\n");
-        WRITER.lock().write_string("  pwd           - Afficher le répertoire courant\n");
-        WRITER.lock().write_string("  ls [dir]      - Lister les fichiers\n");
-        WRITER.lock().write_string("  echo <text>   - Afficher du texte\n");
-        WRITER.lock().write_string("  cat <file>    - Afficher le contenu d'un fichier\n");
-        WRITER.lock().write_string("  mkdir <dir>   - Créer un répertoire\n");
-        WRITER.lock().write_string("  rm <file>     - Supprimer un fichier\n");
-        WRITER.lock().write_string("  cp <s> <d>    - Copier un fichier\n");
-        WRITER.lock().write_string("  mv <s> <d>    - Déplacer un fichier\n");
-        WRITER.lock().write_string("  exit          - Quitter le shell\n");
-        WRITER.lock().write_string("  help          - Afficher cette aide\n");
-        WRITER.lock().write_string("  export <var>  - Définir une variable\n");
-        WRITER.lock().write_string("  ps            - Lister les processus\n");
-        WRITER.lock().write_string("  clear         - Effacer l'écran\n");
-        WRITER.lock().write_string("  history       - Afficher l'historique\n");
+        redirect::print_out("Commandes disponibles:\n");
+        redirect::print_out("  cd <dir>      - Changer de répertoire\n");
+        redirect::print_out("  pwd           - Afficher le répertoire courant\n");
+        redirect::print_out("  ls [dir]      - Lister les fichiers\n");
+        redirect::print_out("  echo <text>   - Afficher du texte\n");
+        redirect::print_out("  cat <file>    - Afficher le contenu d'un fichier\n");
+        redirect::print_out("  mkdir <dir>   - Créer un répertoire\n");
+        redirect::print_out("  mkfifo <f>    - Créer un tube nommé\n");
+        redirect::print_out("  df [chemin]   - Espace occupé des systèmes de fichiers montés\n");
+        redirect::print_out("  mount [-t type [-o opts] [dev] <cible>] - Lister ou monter (overlay, tmpfs, iso9660)\n");
+        redirect::print_out("  umount <cible> - Démonter un système de fichiers\n");
+        redirect::print_out("  fdisk [-y] <disque> [-l|-g|-n [début fin]|-d n] - Lister, créer (GPT) ou supprimer des partitions\n");
+        redirect::print_out("  mkfs.fat32 [-y] [-n nom] <disque> [partition] - Formater en FAT32\n");
+        redirect::print_out("  fsck [-r] [-y] <disque> [partition] - Vérifier (et réparer) un volume ext2\n");
+        redirect::print_out("  lsblk - Lister les disques et leurs partitions\n");
+        redirect::print_out("  hdinfo [disque] - Identité du disque et attributs S.M.A.R.T.\n");
+        redirect::print_out("  rm <file>     - Supprimer un fichier\n");
+        redirect::print_out("  cp <s> <d>    - Copier un fichier\n");
+        redirect::print_out("  mv <s> <d>    - Déplacer un fichier\n");
+        redirect::print_out("  ln [-s] <s> <d> - Créer un lien physique (-s : symbolique)\n");
+        redirect::print_out("  exit          - Quitter le shell\n");
+        redirect::print_out("  help          - Afficher cette aide\n");
+        redirect::print_out("  export <var>  - Définir une variable\n");
+        redirect::print_out("  ps [-t]       - Lister les processus (-t : threads)\n");
+        redirect::print_out("  top [-d s] [-n écrans] [-s cpu|mem|pid] - Moniteur des processus (q, k, c/m/p)\n");
+        redirect::print_out("  free [-b|-k|-m] - Mémoire physique, tas, caches et swap\n");
+        redirect::print_out("  vmstat [-m] [délai [nombre]] - Statistiques mémoire périodiques (-m : caches SLAB)\n");
+        redirect::print_out("  perf stat [-H] [cmd] | perf reset - Compteurs du noyau : appels système, IRQ, contextes\n");
+        redirect::print_out("  trace [-e év] [-c cpu] [-n n] | on [év] | off | clear | events - Tampon de trace du noyau\n");
+        redirect::print_out("  kill [-l] [-signal] <pid>... - Envoyer un signal (TERM par défaut)\n");
+        redirect::print_out("  nice [-n prio] <cmd> - Exécuter une commande à une autre priorité (0-4)\n");
+        redirect::print_out("  renice <prio> <pid>... - Changer la priorité de processus\n");
+        redirect::print_out("  clear         - Effacer l'écran\n");
+        redirect::print_out("  history       - Afficher l'historique\n");
+        redirect::print_out("  sh <script>   - Exécuter un script (ou ./script.sh)\n");
+        redirect::print_out("  test <expr>   - Évaluer une condition ([ <expr> ])\n");
+        redirect::print_out("  play [hz] [ms] [forme] - Jouer une note (square, triangle, saw)\n");
+        redirect::print_out("  gui [stop]    - Démarrer ou arrêter le compositeur graphique\n");
+        redirect::print_out("  beep          - Bip sonore\n");
+        redirect::print_out("  shutdown      - Synchroniser les disques et éteindre (poweroff)\n");
+        redirect::print_out("  reboot        - Synchroniser les disques et redémarrer\n");
+        redirect::print_out("  kexec -l <noyau> | -e [raison] | -u | -p - Charger, lancer, décharger un noyau ; journal précédent\n");
+        redirect::print_out("  time [cmd]    - Durée d'une commande (sans argument : horloge)\n");
+        redirect::print_out("  ping <hôte> [-c n] - Envoyer n requêtes ICMP Echo (4 par défaut)\n");
+        redirect::print_out("  wget <url> [fichier] - Télécharger une page HTTP dans un fichier\n");
+        redirect::print_out("  httpd [start [racine] [port] | stop] - Serveur HTTP du noyau\n");
+        redirect::print_out("  netstat [-t] [-u] [-w] [-l] - Lister les sockets TCP/UDP/raw\n");
+        redirect::print_out("  tcpdump [-c n] [-d s] [-X] [filtre] - Afficher les trames émises et reçues\n");
+        redirect::print_out("  dmesg [-c]    - Afficher (et vider) le journal du noyau\n");
+        redirect::print_out("  netconsole [cible|off] - Envoyer le journal en UDP (ip[:port])\n");
+        redirect::print_out("  ifconfig [eth0 [adresse [netmask m]] [up|down]] - Configurer l'interface\n");
+        redirect::print_out("  ip addr|link|route|neigh [...] - Adresses, état et routes de l'interface\n");
+        redirect::print_out("  ulimit [-S|-H] [-a|-n|-v|-u|-t] [valeur|unlimited] - Limites de ressources\n");
+        redirect::print_out("Autres commandes : cherchées dans $PATH ; `cmd &` les lance en arrière-plan\n");
+        redirect::print_out("Redirections: < fichier, > fichier, >> fichier, 2> fichier\n");
         
         Ok(())
     }
 
+    /// Commande: play [fréquence] [durée ms] [square|triangle|saw]
+    fn builtin_play(&self, cmd: &Command) -> Result<(), ShellError> {
+        use mini_os::drivers::pcm::{self, Waveform};
+
+        let frequency = match cmd.args.first() {
+            Some(arg) => arg.parse::<u32>().map_err(|_| ShellError::InvalidArguments)?,
+            None => 440,
+        };
+        let duration = match cmd.args.get(1) {
+            Some(arg) => arg.parse::<u32>().map_err(|_| ShellError::InvalidArguments)?,
+            None => 500,
+        };
+        let waveform = match cmd.args.get(2) {
+            Some(name) => Waveform::from_name(name).ok_or(ShellError::InvalidArguments)?,
+            None => Waveform::Square,
+        };
+        if !(20..=20_000).contains(&frequency) || duration > 10_000 {
+            return Err(ShellError::InvalidArguments);
+        }
+
+        pcm::play_tone(waveform, frequency, duration).map_err(|e| {
+            redirect::print_err(&format!("play: {:?}\n", e));
+            ShellError::ExecutionFailed("play failed".into())
+        })
+    }
+
+    /// Commande: beep
+    fn builtin_beep(&self, _cmd: &Command) -> Result<(), ShellError> {
+        mini_os::drivers::pcm::beep().map_err(|e| {
+            redirect::print_err(&format!("beep: {:?}\n", e));
+            ShellError::ExecutionFailed("beep failed".into())
+        })
+    }
+
+    /// Commande: gui [stop]
+    fn builtin_gui(&self, cmd: &Command) -> Result<(), ShellError> {
+        use mini_os::drivers::gpu::compositor;
+
+        match cmd.args.first().map(|s| s.as_str()) {
+            Some("stop") => {
+                compositor::stop();
+                Ok(())
+            }
+            None if compositor::is_running() => Ok(()),
+            None => compositor::demo().map_err(|e| {
+                redirect::print_err(&format!("gui: {}\n", e));
+                ShellError::ExecutionFailed("gui failed".into())
+            }),
+            Some(_) => Err(ShellError::InvalidArguments),
+        }
+    }
+
+    /// Synchronise les systèmes de fichiers avant arrêt ou redémarrage
+    fn sync_before_power_off(&self) {
+        redirect::print_out("Synchronisation des systèmes de fichiers...\n");
+        if let Err(e) = mini_os::fs::vfs_sync() {
+            redirect::print_err(&format!("sync: {:?}\n", e));
+        }
+    }
+
+    /// Commande: shutdown
+    fn builtin_shutdown(&self, _cmd: &Command) -> Result<(), ShellError> {
+        self.sync_before_power_off();
+        redirect::print_out("Arrêt du système\n");
+        mini_os::power::shutdown()
+    }
+
+    /// Commande: reboot
+    fn builtin_reboot(&self, _cmd: &Command) -> Result<(), ShellError> {
+        self.sync_before_power_off();
+        redirect::print_out("Redémarrage\n");
+        mini_os::power::reboot()
+    }
+
+    /// Commande: kexec -l <noyau> | -e [raison] | -u | -p
+    fn builtin_kexec(&self, cmd: &Command) -> Result<(), ShellError> {
+        use mini_os::kexec;
+
+        let args: Vec<&str> = cmd.args.iter().map(|s| s.as_str()).collect();
+        match args.as_slice() {
+            ["-l", path] => {
+                let path = self.resolve_path(path);
+                match kexec::load(&path) {
+                    Ok((entry, size)) => {
+                        redirect::print_out(&format!("{} chargé : {} octets, entrée {:#x}\n", path, size, entry));
+                        Ok(())
+                    }
+                    Err(e) => {
+                        redirect::print_err(&format!("{}\n", e));
+                        Err(ShellError::ExecutionFailed("kexec failed".into()))
+                    }
+                }
+            }
+            ["-e", reason @ ..] => {
+                let reason = if reason.is_empty() { String::from("kexec depuis le shell") } else { reason.join(" ") };
+                if let Err(e) = kexec::execute(&reason) {
+                    redirect::print_err(&format!("{}\n", e));
+                    return Err(ShellError::ExecutionFailed("kexec failed".into()));
+                }
+                Ok(())
+            }
+            ["-u"] => {
+                if !kexec::unload() {
+                    redirect::print_err("kexec: aucune image chargée\n");
+                }
+                Ok(())
+            }
+            ["-p"] => {
+                match kexec::previous_log() {
+                    Some(log) => redirect::print_out(&log),
+                    None => redirect::print_out("Aucun journal de noyau précédent\n"),
+                }
+                Ok(())
+            }
+            [] => {
+                match kexec::loaded() {
+                    Some((path, entry)) => redirect::print_out(&format!("Image chargée : {} (entrée {:#x})\n", path, entry)),
+                    None => redirect::print_out("Aucune image chargée\n"),
+                }
+                Ok(())
+            }
+            _ => Err(ShellError::InvalidArguments),
+        }
+    }
+
+    /// Commande: time [commande [args]]
+    fn builtin_time(&mut self, cmd: &Command) -> Result<(), ShellError> {
+        use mini_os::time::{self, Instant, NSEC_PER_SEC};
+
+        let program = match cmd.args.first() {
+            Some(program) => program,
+            None => {
+                let clock = time::clocksource();
+                let uptime = time::now_ns();
+                redirect::print_out(&format!(
+                    "horloge: {} ({} Hz)\nuptime: {}.{:09} s\n",
+                    clock.name(),
+                    clock.frequency(),
+                    uptime / NSEC_PER_SEC,
+                    uptime % NSEC_PER_SEC
+                ));
+                return Ok(());
+            }
+        };
+        let mut timed = Command::new(program);
+        for arg in &cmd.args[1..] {
+            timed.add_arg(arg);
+        }
+
+        let start = Instant::now();
+        let result = self.run_builtin(&timed);
+        let elapsed = start.elapsed_ns();
+        redirect::print_err(&format!(
+            "\nréel\t{}.{:06} s\n",
+            elapsed / NSEC_PER_SEC,
+            (elapsed % NSEC_PER_SEC) / 1_000
+        ));
+        result
+    }
+
+    /// Commande: ping <hôte> [-c nombre]
+    fn builtin_ping(&self, cmd: &Command) -> Result<(), ShellError> {
+        use mini_os::net::{dns, ping, IpAddress};
+
+        let mut target = None;
+        let mut count = 4u16;
+        let mut args = cmd.args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-c" => {
+                    count = args
+                        .next()
+                        .and_then(|n| n.parse().ok())
+                        .filter(|&n| n > 0)
+                        .ok_or(ShellError::InvalidArguments)?;
+                }
+                other if target.is_none() => target = Some(other),
+                _ => return Err(ShellError::InvalidArguments),
+            }
+        }
+        let host = target.ok_or(ShellError::InvalidArguments)?;
+        // Adresse IPv6 littérale, sinon nom ou adresse IPv4
+        let target = match host.parse::<IpAddress>() {
+            Ok(ip @ IpAddress::V6(_)) => ip,
+            _ => IpAddress::V4(
+                dns::resolve(host).map_err(|e| ShellError::ExecutionFailed(format!("ping: {}: {:?}", host, e)))?,
+            ),
+        };
+
+        // Millisecondes avec trois décimales
+        let ms = |ns: u64| format!("{}.{:03}", ns / 1_000_000, ns % 1_000_000 / 1_000);
+
+        redirect::print_out(&format!("PING {} ({}) : {} octets de données\n", host, target, ping::PAYLOAD_SIZE));
+        let stats = ping::ping(target, count, |event| match event {
+            ping::PingEvent::Reply { sequence, ttl, rtt_ns } => redirect::print_out(&format!(
+                "{} octets de {} : icmp_seq={} ttl={} temps={} ms\n",
+                ping::PAYLOAD_SIZE + 8,
+                target,
+                sequence,
+                ttl,
+                ms(rtt_ns)
+            )),
+            ping::PingEvent::Timeout { sequence } => {
+                redirect::print_out(&format!("icmp_seq={} : délai dépassé\n", sequence))
+            }
+        })
+        .map_err(|e| ShellError::ExecutionFailed(format!("ping: {}: {:?}", target, e)))?;
+
+        redirect::print_out(&format!(
+            "--- statistiques ping {} ---\n{} paquets transmis, {} reçus, {}% de perte\n",
+            host,
+            stats.transmitted,
+            stats.received,
+            stats.loss_percent()
+        ));
+        if stats.received > 0 {
+            redirect::print_out(&format!(
+                "rtt min/moy/max = {}/{}/{} ms\n",
+                ms(stats.min_ns),
+                ms(stats.avg_ns()),
+                ms(stats.max_ns)
+            ));
+        }
+        Ok(())
+    }
+
+    /// Commande: wget <url> [fichier]
+    fn builtin_wget(&self, cmd: &Command) -> Result<(), ShellError> {
+        use mini_os::net::http::{HttpClient, Url};
+
+        let url = cmd.args.first().ok_or(ShellError::InvalidArguments)?;
+        let file = match cmd.args.get(1) {
+            Some(file) => file.clone(),
+            None => {
+                // Dernier segment du chemin, sans la requête
+                let parsed = Url::parse(url).map_err(|_| ShellError::InvalidArguments)?;
+                let path = parsed.path.split('?').next().unwrap_or("");
+                match path.rsplit('/').next() {
+                    Some(name) if !name.is_empty() => name.to_string(),
+                    _ => "index.html".to_string(),
+                }
+            }
+        };
+
+        redirect::print_out(&format!("Téléchargement de {}...\n", url));
+        let response = HttpClient::get(url)
+            .map_err(|e| ShellError::ExecutionFailed(format!("wget: {}: {:?}", url, e)))?;
+        redirect::print_out(&format!("HTTP {} : {} octets\n", response.status_code, response.body.len()));
+        if !(200..300).contains(&response.status_code) {
+            return Err(ShellError::ExecutionFailed(format!("wget: statut {}", response.status_code)));
+        }
+
+        let path = self.resolve_path(&file);
+        mini_os::fs::vfs_write_file(&path, &response.body)
+            .map_err(|e| ShellError::ExecutionFailed(format!("wget: {}: {:?}", path, e)))?;
+        redirect::print_out(&format!("Enregistré dans {}\n", path));
+        Ok(())
+    }
+
+    /// Commande: httpd [start [racine] [port] | stop]
+    fn builtin_httpd(&self, cmd: &Command) -> Result<(), ShellError> {
+        use mini_os::net::httpd;
+
+        match cmd.args.first().map(|s| s.as_str()) {
+            None => {
+                let state = if httpd::is_running() { "actif" } else { "arrêté" };
+                redirect::print_out(&format!("httpd : {}\n", state));
+            }
+            Some("start") => {
+                let root = cmd.args.get(1).map(|r| self.resolve_path(r))
+                    .unwrap_or_else(|| httpd::DEFAULT_ROOT.to_string());
+                let port = match cmd.args.get(2) {
+                    Some(port) => port.parse().map_err(|_| ShellError::InvalidArguments)?,
+                    None => httpd::DEFAULT_PORT,
+                };
+                httpd::start(&root, port)
+                    .map_err(|e| ShellError::ExecutionFailed(format!("httpd: {}", e)))?;
+                redirect::print_out(&format!("httpd : {} servi sur le port {}\n", root, port));
+            }
+            Some("stop") => httpd::stop(),
+            Some(_) => return Err(ShellError::InvalidArguments),
+        }
+        Ok(())
+    }
+
+    /// Commande: netstat [-t] [-u] [-w] [-l]
+    ///
+    /// Sans -t, -u ni -w, tous les sockets ; -l restreint aux sockets en
+    /// écoute (liés sans pair distant pour UDP).
+    fn builtin_netstat(&self, cmd: &Command) -> Result<(), ShellError> {
+        use mini_os::net::{socket, SocketType, TcpState};
+
+        let (mut tcp, mut udp, mut raw, mut listening) = (false, false, false, false);
+        for arg in &cmd.args {
+            let flags = arg.strip_prefix('-').ok_or(ShellError::InvalidArguments)?;
+            for flag in flags.chars() {
+                match flag {
+                    't' => tcp = true,
+                    'u' => udp = true,
+                    'w' => raw = true,
+                    'l' => listening = true,
+                    _ => return Err(ShellError::InvalidArguments),
+                }
+            }
+        }
+        if !tcp && !udp && !raw {
+            tcp = true;
+            udp = true;
+            raw = true;
+        }
+
+        let addr = |addr: Option<mini_os::net::SocketAddr>| match addr {
+            Some(addr) => addr.to_string(),
+            None => "*:*".to_string(),
+        };
+        redirect::print_out(&format!(
+            "{:<5} {:<21} {:<21} {:<11} {:>8} {:>8}\n",
+            "Proto", "Adresse locale", "Adresse distante", "État", "Reçus", "Envoyés"
+        ));
+        for info in socket::snapshot() {
+            let ipv6 = info.local.map_or(false, |addr| addr.ip.is_ipv6());
+            let (proto, shown) = match (info.socket_type, ipv6) {
+                (SocketType::Stream, false) => ("tcp", tcp),
+                (SocketType::Stream, true) => ("tcp6", tcp),
+                (SocketType::Datagram, false) => ("udp", udp),
+                (SocketType::Datagram, true) => ("udp6", udp),
+                (SocketType::Raw, _) => ("raw", raw),
+            };
+            let is_listening = match info.state {
+                Some(state) => state == TcpState::Listen,
+                None => info.local.is_some() && info.remote.is_none(),
+            };
+            if !shown || listening != is_listening {
+                continue;
+            }
+            redirect::print_out(&format!(
+                "{:<5} {:<21} {:<21} {:<11} {:>8} {:>8}\n",
+                proto,
+                addr(info.local),
+                addr(info.remote),
+                info.state.map_or("", |state| state.name()),
+                info.rx_bytes,
+                info.tx_bytes
+            ));
+        }
+        Ok(())
+    }
+
+    /// Commande: tcpdump [-c n] [-d secondes] [-X] [filtre]
+    ///
+    /// Capture par un socket raw ; s'arrête après n trames (20 par défaut)
+    /// ou au bout de la durée (10 s). Filtre : [arp|ip|ip6|tcp|udp|icmp]
+    /// [port N] [host A].
+    fn builtin_tcpdump(&self, cmd: &Command) -> Result<(), ShellError> {
+        use mini_os::net::capture::{self, PacketFilter};
+        use mini_os::net::socket;
+        use mini_os::time::{Instant, NSEC_PER_SEC};
+
+        let mut count = 20usize;
+        let mut duration_ns = 10 * NSEC_PER_SEC;
+        let mut hex = false;
+        let mut expression = Vec::new();
+        let mut args = cmd.args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-c" => {
+                    count = args.next().and_then(|n| n.parse().ok()).filter(|&n| n > 0).ok_or(ShellError::InvalidArguments)?;
+                }
+                "-d" => {
+                    let seconds: u64 = args.next().and_then(|n| n.parse().ok()).ok_or(ShellError::InvalidArguments)?;
+                    duration_ns = seconds * NSEC_PER_SEC;
+                }
+                "-X" => hex = true,
+                "-n" => {}
+                word => expression.push(word),
+            }
+        }
+        let filter = PacketFilter::parse(&expression).map_err(|e| ShellError::ExecutionFailed(format!("tcpdump: {}", e)))?;
+
+        let failed = |e| ShellError::ExecutionFailed(format!("tcpdump: {:?}", e));
+        let id = socket::raw_open(filter).map_err(failed)?;
+        redirect::print_out("tcpdump : écoute sur eth0, type de lien EN10MB (Ethernet)\n");
+
+        let start = Instant::now();
+        let mut captured = 0;
+        while captured < count {
+            let remaining = duration_ns.saturating_sub(start.elapsed_ns());
+            if remaining == 0 {
+                break;
+            }
+            let frame = match socket::raw_recv(id, remaining) {
+                Ok(frame) => frame,
+                Err(_) => break,
+            };
+            captured += 1;
+            redirect::print_out(&format!(
+                "{}.{:06} {} {}\n",
+                frame.timestamp_ns / NSEC_PER_SEC,
+                frame.timestamp_ns % NSEC_PER_SEC / 1_000,
+                if frame.outgoing { "Out" } else { "In " },
+                capture::summarize(&frame.data)
+            ));
+            if hex {
+                redirect::print_out(&capture::hexdump(&frame.data));
+            }
+        }
+
+        let dropped = capture::dropped(id);
+        let _ = socket::close(id);
+        redirect::print_out(&format!("{} trames capturées\n{} trames perdues\n", captured, dropped));
+        Ok(())
+    }
+
+    /// Commande: dmesg [-c]
+    fn builtin_dmesg(&self, cmd: &Command) -> Result<(), ShellError> {
+        let clear = match cmd.args.first().map(|s| s.as_str()) {
+            None => false,
+            Some("-c") => true,
+            Some(_) => return Err(ShellError::InvalidArguments),
+        };
+        for line in mini_os::klog::lines() {
+            redirect::print_out(&format!("{}\n", line));
+        }
+        if clear {
+            mini_os::klog::clear();
+        }
+        Ok(())
+    }
+
+    /// Commande: ulimit [-S|-H] [-a|-n|-v|-u|-t] [valeur|unlimited]
+    ///
+    /// Limites du processus du shell (PID 1 hors processus), héritées par
+    /// les programmes lancés. Sans -S ni -H, les deux limites changent ;
+    /// -v s'exprime en Kio, comme dans bash.
+    fn builtin_ulimit(&self, cmd: &Command) -> Result<(), ShellError> {
+        use mini_os::process::rlimit::{self, RLimit, Resource, RLIM_INFINITY};
+
+        let (mut soft, mut hard, mut all) = (false, false, false);
+        let mut resource = Resource::NoFile;
+        let mut value = None;
+        for arg in &cmd.args {
+            match arg.as_str() {
+                "-S" => soft = true,
+                "-H" => hard = true,
+                "-a" => all = true,
+                "-t" => resource = Resource::Cpu,
+                "-u" => resource = Resource::NProc,
+                "-n" => resource = Resource::NoFile,
+                "-v" => resource = Resource::As,
+                v if value.is_none() && !v.starts_with('-') => value = Some(v),
+                _ => return Err(ShellError::InvalidArguments),
+            }
+        }
+        // Unité affichée par ulimit : Kio pour l'espace d'adressage
+        let unit = |r: Resource| if r == Resource::As { 1024 } else { 1 };
+        let show = |r: Resource, limit: RLimit| {
+            let v = if hard && !soft { limit.max } else { limit.cur };
+            if v == RLIM_INFINITY { String::from("unlimited") } else { format!("{}", v / unit(r)) }
+        };
+
+        let process = mini_os::process::current_process()
+            .or_else(|| mini_os::process::get_process_by_pid(1))
+            .ok_or_else(|| ShellError::ExecutionFailed("ulimit: aucun processus".into()))?;
+
+        let value = match value {
+            Some(v) => v,
+            None => {
+                let limits = process.lock().limits;
+                if all || cmd.args.is_empty() {
+                    for (r, flag) in [(Resource::Cpu, "-t"), (Resource::NProc, "-u"), (Resource::NoFile, "-n"), (Resource::As, "-v")] {
+                        redirect::print_out(&format!("{:<28} ({}) {}\n", r.name(), flag, show(r, limits.get(r))));
+                    }
+                } else {
+                    redirect::print_out(&format!("{}\n", show(resource, limits.get(resource))));
+                }
+                return Ok(());
+            }
+        };
+
+        let new_value = if value == "unlimited" {
+            RLIM_INFINITY
+        } else {
+            value.parse::<u64>().map_err(|_| ShellError::InvalidArguments)?
+                .checked_mul(unit(resource))
+                .ok_or(ShellError::InvalidArguments)?
+        };
+        let (pid, limits) = {
+            let mut process = process.lock();
+            let mut limit = process.limits.get(resource);
+            if soft || !hard {
+                limit.cur = new_value;
+            }
+            if hard || !soft {
+                limit.max = new_value;
+            }
+            process.limits.set(resource, limit)
+                .map_err(|e| ShellError::ExecutionFailed(format!("ulimit: {}", e)))?;
+            (process.pid, process.limits)
+        };
+        rlimit::apply(pid, &limits);
+        Ok(())
+    }
+
+    /// Commande: netconsole [cible|off]
+    ///
+    /// Cible au format de l'option de démarrage `netconsole=` ou ip[:port] ;
+    /// elle est conservée dans /etc/netconsole.conf.
+    fn builtin_netconsole(&self, cmd: &Command) -> Result<(), ShellError> {
+        use mini_os::net::netconsole::{self, NetconsoleTarget};
+
+        let save = |content: &str| {
+            mini_os::fs::vfs_write_file(netconsole::CONFIG_PATH, content.as_bytes())
+                .map_err(|e| ShellError::ExecutionFailed(format!("{}: {:?}", netconsole::CONFIG_PATH, e)))
+        };
+        match cmd.args.first().map(|s| s.as_str()) {
+            None => match netconsole::target() {
+                Some(target) => redirect::print_out(&format!("netconsole={}\n", target)),
+                None => redirect::print_out("netconsole inactive\n"),
+            },
+            Some("off") => {
+                netconsole::disable();
+                save("")?;
+            }
+            Some(spec) => {
+                let target = NetconsoleTarget::parse(spec)
+                    .map_err(|e| ShellError::ExecutionFailed(format!("netconsole: {}", e)))?;
+                netconsole::enable(target);
+                save(&format!("netconsole={}\n", target))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Commande: ifconfig [eth0 [adresse [netmask masque]] [up|down]]
+    fn builtin_ifconfig(&self, cmd: &Command) -> Result<(), ShellError> {
+        use mini_os::net::interface;
+
+        let (name, args) = match cmd.args.split_first() {
+            Some((name, args)) => (name.as_str(), args),
+            None => {
+                for info in interface::interfaces() {
+                    redirect::print_out(&format!(
+                        "{}: {}\n    inet {}  netmask {}\n",
+                        info.name,
+                        if info.up { "UP" } else { "DOWN" },
+                        info.ip_address,
+                        interface::prefix_netmask(info.prefix_len)
+                    ));
+                    for (ip, prefix_len) in &info.ipv6_addresses {
+                        redirect::print_out(&format!("    inet6 {}  prefixlen {}\n", ip, prefix_len));
+                    }
+                    redirect::print_out(&format!("    ether {}\n", info.mac_address));
+                }
+                return Ok(());
+            }
+        };
+        if name != interface::INTERFACE_NAME {
+            return Err(ShellError::ExecutionFailed(format!("ifconfig: {}: interface inconnue", name)));
+        }
+
+        let mut address = None;
+        let mut prefix_len = 24;
+        let mut state = None;
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "up" => state = Some(true),
+                "down" => state = Some(false),
+                "netmask" => {
+                    let mask = args.next().and_then(|m| m.parse().ok()).ok_or(ShellError::InvalidArguments)?;
+                    prefix_len = interface::netmask_prefix(mask);
+                }
+                cidr => {
+                    let (ip, len) = interface::parse_cidr(cidr).ok_or(ShellError::InvalidArguments)?;
+                    address = Some(ip);
+                    if cidr.contains('/') {
+                        prefix_len = len;
+                    }
+                }
+            }
+        }
+
+        let failed = |e| ShellError::ExecutionFailed(format!("ifconfig: {:?}", e));
+        if let Some(ip) = address {
+            interface::set_address(ip, prefix_len).map_err(failed)?;
+        }
+        if let Some(up) = state {
+            interface::set_up(up).map_err(failed)?;
+        }
+        self.save_network_config()
+    }
+
+    /// Commande: ip addr|link|route [...]
+    ///
+    /// ip addr [add a.b.c.d/len|x::y/len [dev eth0] | del x::y/len]
+    /// ip link [set eth0 up|down]
+    /// ip route [add|del <réseau/len|default> [via passerelle]]
+    /// ip -6 route [add default via x::y | del default]
+    /// ip neigh (voisins IPv6 appris par ND)
+    fn builtin_ip(&self, cmd: &Command) -> Result<(), ShellError> {
+        use mini_os::net::interface::{self, Route};
+        use mini_os::net::Ipv4Address;
+
+        let args: Vec<&str> = cmd.args.iter().map(|s| s.as_str()).collect();
+        let failed = |e| ShellError::ExecutionFailed(format!("ip: {:?}", e));
+        let destination = |arg: &str| match arg {
+            "default" => Some((Ipv4Address::new(0, 0, 0, 0), 0)),
+            cidr => interface::parse_cidr(cidr),
+        };
+
+        match args.as_slice() {
+            ["addr"] | ["a"] | ["link"] | ["l"] => {
+                for info in interface::interfaces() {
+                    redirect::print_out(&format!(
+                        "{}: <{}>\n    link/ether {}\n    inet {}/{}\n",
+                        info.name,
+                        if info.up { "UP" } else { "DOWN" },
+                        info.mac_address,
+                        info.ip_address,
+                        info.prefix_len
+                    ));
+                    for (ip, prefix_len) in &info.ipv6_addresses {
+                        let scope = if ip.is_link_local() { "link" } else { "global" };
+                        redirect::print_out(&format!("    inet6 {}/{} scope {}\n", ip, prefix_len, scope));
+                    }
+                }
+                return Ok(());
+            }
+            ["route"] | ["r"] => {
+                for info in interface::interfaces() {
+                    let network = Ipv4Address(
+                        core::array::from_fn(|i| info.ip_address.0[i] & interface::prefix_netmask(info.prefix_len).0[i]),
+                    );
+                    redirect::print_out(&format!("{}/{} dev {} src {}\n", network, info.prefix_len, info.name, info.ip_address));
+                    for route in &info.routes {
+                        redirect::print_out(&format!("{} dev {}\n", route, info.name));
+                    }
+                }
+                return Ok(());
+            }
+            ["-6", "route"] | ["-6", "r"] => {
+                for info in interface::interfaces() {
+                    for (ip, prefix_len) in &info.ipv6_addresses {
+                        redirect::print_out(&format!("{}/{} dev {}\n", ip, prefix_len, info.name));
+                    }
+                    if let Some(gateway) = info.ipv6_gateway {
+                        redirect::print_out(&format!("default via {} dev {}\n", gateway, info.name));
+                    }
+                }
+                return Ok(());
+            }
+            ["neigh"] | ["n"] => {
+                for (ip, mac) in mini_os::net::ndp::entries() {
+                    redirect::print_out(&format!("{} dev eth0 lladdr {}\n", ip, mac));
+                }
+                return Ok(());
+            }
+            ["addr", "add", cidr] | ["addr", "add", cidr, "dev", "eth0"] if cidr.contains(':') => {
+                let (ip, prefix_len) = interface::parse_cidr6(cidr).ok_or(ShellError::InvalidArguments)?;
+                interface::add_ipv6_address(ip, prefix_len).map_err(failed)?;
+            }
+            ["addr", "add", cidr] | ["addr", "add", cidr, "dev", "eth0"] => {
+                let (ip, prefix_len) = interface::parse_cidr(cidr).ok_or(ShellError::InvalidArguments)?;
+                interface::set_address(ip, prefix_len).map_err(failed)?;
+            }
+            ["addr", "del", cidr] | ["addr", "del", cidr, "dev", "eth0"] => {
+                let (ip, _) = interface::parse_cidr6(cidr).ok_or(ShellError::InvalidArguments)?;
+                if !interface::del_ipv6_address(ip).map_err(failed)? {
+                    return Err(ShellError::ExecutionFailed(format!("ip: adresse {} absente", cidr)));
+                }
+            }
+            ["-6", "route", "add", "default", "via", gateway] => {
+                let gateway = gateway.parse().map_err(|_| ShellError::InvalidArguments)?;
+                interface::set_ipv6_gateway(Some(gateway)).map_err(failed)?;
+            }
+            ["-6", "route", "del", "default"] => interface::set_ipv6_gateway(None).map_err(failed)?,
+            ["link", "set", "eth0", "up"] => interface::set_up(true).map_err(failed)?,
+            ["link", "set", "eth0", "down"] => interface::set_up(false).map_err(failed)?,
+            ["route", "add", target, rest @ ..] => {
+                let (destination, prefix_len) = destination(target).ok_or(ShellError::InvalidArguments)?;
+                let gateway = match rest {
+                    [] => None,
+                    ["via", gateway] => Some(gateway.parse().map_err(|_| ShellError::InvalidArguments)?),
+                    _ => return Err(ShellError::InvalidArguments),
+                };
+                interface::add_route(Route { destination, prefix_len, gateway }).map_err(failed)?;
+            }
+            ["route", "del", target] => {
+                let (destination, prefix_len) = destination(target).ok_or(ShellError::InvalidArguments)?;
+                if !interface::del_route(destination, prefix_len).map_err(failed)? {
+                    return Err(ShellError::ExecutionFailed(format!("ip: route {} absente", target)));
+                }
+            }
+            _ => return Err(ShellError::InvalidArguments),
+        }
+        self.save_network_config()
+    }
+
+    /// Conserve la configuration réseau pour le prochain démarrage
+    fn save_network_config(&self) -> Result<(), ShellError> {
+        mini_os::net::interface::save_config().map_err(|e| {
+            ShellError::ExecutionFailed(format!("{}: {:?}", mini_os::net::interface::CONFIG_PATH, e))
+        })
+    }
+
     /// Commande: export <variable>=<valeur>
     fn builtin_export(&mut self, cmd: &Command) -> Result<(), ShellError> {
         if cmd.args.is_empty() {
@@ -394,7 +1478,7 @@ impl Shell {
             let key = &arg[..pos];
             let value = &arg[pos+1..];
             self.env_vars.insert(key.into(), value.into());
-            WRITER.lock().write_string(&format!("{}={}\n", key, value));
+            redirect::print_out(&format!("{}={}\n", key, value));
         } else {
             return Err(ShellError::InvalidArguments);
         }
@@ -402,26 +1486,70 @@ impl Shell {
         Ok(())
     }
 
-    /// Commande: ps
-    fn builtin_ps(&self, _cmd: &Command) -> Result<(), ShellError> {
-        WRITER.lock().write_string("PID  COMMAND\n");
-        WRITER.lock().write_string("1    init\n");
-        WRITER.lock().write_string("2    shell\n");
-        
+    /// Arguments et environnement d'un programme lancé par le shell :
+    /// argv tel quel, envp tiré des variables (hors variables spéciales
+    /// comme `?`)
+    pub fn exec_args(&self, argv: Vec<String>) -> mini_os::process::exec::ExecArgs {
+        let exported = self
+            .env_vars
+            .iter()
+            .filter(|(name, _)| name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'))
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        mini_os::process::exec::ExecArgs::with_env(argv, &exported)
+    }
+
+    /// Commande: ps [-t]
+    fn builtin_ps(&self, cmd: &Command) -> Result<(), ShellError> {
+        let show_threads = match cmd.args.first().map(|a| a.as_str()) {
+            None => false,
+            Some("-t") => true,
+            Some(_) => return Err(ShellError::InvalidArguments),
+        };
+
+        // Instantané pris avant tout affichage : aucun verrou tenu pendant le formatage
+        let processes = mini_os::process::snapshot_processes();
+
+        redirect::print_out("  PID STATE      PRIO      THR   VRUNTIME COMMAND\n");
+        for p in &processes {
+            redirect::print_out(&format!(
+                "{:>5} {:<10} {:<8} {:>4} {:>10} {}\n",
+                p.pid,
+                format!("{:?}", p.state),
+                format!("{:?}", p.priority),
+                p.thread_count(),
+                p.vruntime(),
+                p.name
+            ));
+
+            if show_threads {
+                for t in &p.threads {
+                    redirect::print_out(&format!(
+                        "      - TID {:<6} {:<10} {:>10} {}\n",
+                        t.tid,
+                        format!("{:?}", t.state),
+                        t.vruntime,
+                        t.name
+                    ));
+                }
+            }
+        }
+
         Ok(())
     }
 
-    /// Commande: clear
+    /// Commande: clear (écran et historique de défilement)
     fn builtin_clear(&self, _cmd: &Command) -> Result<(), ShellError> {
-        // TODO: Implémenter l'effacement de l'écran
-        WRITER.lock().write_string("\x1b[2J\x1b[H");
+        // Séquences ANSI interprétées par la console (texte ou framebuffer)
+        redirect::print_out("\x1b[2J\x1b[H");
+        mini_os::vga_buffer::clear_scrollback(SHELL_VT);
         Ok(())
     }
 
     /// Commande: history
     fn builtin_history(&self, _cmd: &Command) -> Result<(), ShellError> {
         for (i, cmd) in self.history.iter().enumerate() {
-            WRITER.lock().write_string(&format!("  {}  {}\n", i + 1, cmd));
+            redirect::print_out(&format!("  {}  {}\n", i + 1, cmd));
         }
         
         Ok(())
@@ -429,7 +1557,33 @@ impl Shell {
 }
 
 lazy_static! {
-    pub static ref SHELL: Mutex<Shell> = Mutex::new(Shell::new());
+    pub static ref SHELL: Mutex<Shell> = {
+        let mut shell = Shell::new();
+        shell.load_history();
+        Mutex::new(shell)
+    };
+}
+
+/// Shell interactif de tty1 : lit une ligne au clavier, l'enregistre dans
+/// l'historique, l'exécute, recommence
+pub fn interactive() -> ! {
+    let mut terminal = Terminal::new();
+    // Historique persistant, parcouru par Ctrl-R
+    terminal.line_editor().set_history(&SHELL.lock().history);
+    loop {
+        let (prompt, current_dir) = {
+            let shell = SHELL.lock();
+            (shell.prompt(), shell.current_dir.clone())
+        };
+        let line = terminal.read_line(&prompt, BUILTINS, &current_dir);
+        if line.trim().is_empty() {
+            continue;
+        }
+        terminal.line_editor().add_to_history(line.trim());
+        let mut shell = SHELL.lock();
+        shell.add_to_history(&line);
+        shell.run_line(&line);
+    }
 }
 
 #[cfg(test)]
@@ -443,12 +1597,38 @@ mod tests {
         assert!(!shell.env_vars.is_empty());
     }
 
+    #[test_case]
+    fn test_exec_args_environment() {
+        let mut shell = Shell::new();
+        shell.env_vars.insert("?".into(), "1".into());
+        let args = shell.exec_args(vec![String::from("/bin/ls"), String::from("-l")]);
+        assert_eq!(args.argv, ["/bin/ls", "-l"]);
+        assert_eq!(args.env("PATH"), Some("/bin:/usr/bin"));
+        assert_eq!(args.env("?"), None);
+    }
+
     #[test_case]
     fn test_parse_command() {
         let shell = Shell::new();
         let cmd = shell.parse_command("ls -la /home").unwrap();
         assert_eq!(cmd.program, "ls");
         assert_eq!(cmd.args.len(), 2);
+
+        let cmd = shell.parse_command("echo \"hello world\" $USER").unwrap();
+        assert_eq!(cmd.args, ["hello world", "root"]);
+    }
+
+    #[test_case]
+    fn test_parse_redirections() {
+        let shell = Shell::new();
+        let cmd = shell.parse_command("cat < in.txt >> out.txt 2> /tmp/err").unwrap();
+        assert!(cmd.args.is_empty());
+        assert_eq!(cmd.stdin.as_deref(), Some("/in.txt"));
+        assert_eq!(cmd.stdout.as_deref(), Some("/out.txt"));
+        assert!(cmd.stdout_append);
+        assert_eq!(cmd.stderr.as_deref(), Some("/tmp/err"));
+        assert!(!cmd.stderr_append);
+        assert!(shell.parse_command("ls >").is_err());
     }
 
     #[test_case]
@@ -460,6 +1640,8 @@ mod tests {
             stdin: None,
             stdout: None,
             stderr: None,
+            stdout_append: false,
+            stderr_append: false,
             pipes: Vec::new(),
         };
         assert!(shell.execute(cmd).is_ok());
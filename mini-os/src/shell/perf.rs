@@ -0,0 +1,79 @@
+//! Compteurs de performance : perf
+//!
+//! `perf stat` affiche les compteurs du noyau depuis le démarrage (ou le
+//! dernier `perf reset`) : appels système avec leur latence, interruptions
+//! par vecteur, changements de contexte. `perf stat <commande>` exécute la
+//! commande et n'affiche que l'activité de son exécution, sur la sortie
+//! d'erreur comme `time`. `-H` ajoute les histogrammes de latence.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use mini_os::perf::{self, Snapshot};
+use mini_os::time::{Instant, NSEC_PER_SEC};
+
+use super::{redirect, Command, Shell, ShellError};
+
+impl Shell {
+    /// perf stat [-H] [commande] | perf reset
+    pub(super) fn builtin_perf(&mut self, cmd: &Command) -> Result<(), ShellError> {
+        match cmd.args.first().map(String::as_str) {
+            Some("stat") => self.perf_stat(&cmd.args[1..]),
+            Some("reset") => {
+                perf::reset();
+                Ok(())
+            }
+            _ => Err(ShellError::InvalidArguments),
+        }
+    }
+
+    fn perf_stat(&mut self, args: &[String]) -> Result<(), ShellError> {
+        let (histograms, args) = match args.first() {
+            Some(flag) if flag == "-H" => (true, &args[1..]),
+            _ => (false, args),
+        };
+        let Some(program) = args.first() else {
+            redirect::print_out(&Snapshot::take().render(histograms));
+            return Ok(());
+        };
+        let mut measured = Command::new(program);
+        for arg in &args[1..] {
+            measured.add_arg(arg);
+        }
+
+        let before = Snapshot::take();
+        let start = Instant::now();
+        let result = self.run_builtin(&measured);
+        let elapsed = start.elapsed_ns();
+        let delta = Snapshot::take().since(&before);
+
+        let line: Vec<&str> = args.iter().map(String::as_str).collect();
+        redirect::print_err(&format!("\nStatistiques de « {} » :\n", line.join(" ")));
+        redirect::print_err(&delta.render(histograms));
+        redirect::print_err(&format!(
+            "{}.{:06} s écoulées\n",
+            elapsed / NSEC_PER_SEC,
+            (elapsed % NSEC_PER_SEC) / 1_000
+        ));
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_perf_builtin() {
+        let mut shell = Shell::new();
+        let mut stat = Command::new("perf");
+        stat.add_arg("stat");
+        assert!(shell.builtin_perf(&stat).is_ok());
+
+        // Le statut de la commande mesurée est conservé
+        let mut failing = stat.clone();
+        failing.add_arg("false");
+        assert!(shell.builtin_perf(&failing).is_err());
+        assert!(shell.builtin_perf(&Command::new("perf")).is_err());
+    }
+}
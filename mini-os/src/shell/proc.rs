@@ -0,0 +1,292 @@
+//! Contrôle des processus : programmes externes, kill, nice et renice
+//!
+//! Ces commandes passent par la couche des appels système
+//! (`SyscallHandler`, comme un programme utilisateur) : Wait pour attendre
+//! un programme lancé, Kill pour les signaux, SetPriority/GetPriority pour
+//! les priorités. Les signaux se désignent par numéro ou par nom (`-9`,
+//! `-KILL`, `-SIGKILL`) ; les priorités vont de 0 (temps réel) à 4 (idle),
+//! ou par nom.
+//!
+//! Une commande qui n'est pas un builtin est cherchée dans les répertoires
+//! de `$PATH` (ou prise telle quelle si elle contient un `/`). Un ELF est
+//! lancé par `ProcessManager::spawn` puis attendu, son code de sortie
+//! allant dans `$?` ; tout autre fichier est exécuté comme script. Avec un
+//! `&` final, le programme tourne en arrière-plan et le shell le récupère
+//! (message « [pid] terminé ») avant la commande suivante.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use mini_os::process::signal::Signal;
+use mini_os::process::{ProcessPriority, PROCESS_MANAGER};
+use mini_os::syscall::wait_flags::WNOHANG;
+use mini_os::syscall::{SyscallError, SyscallHandler, SyscallNumber, SyscallResult};
+
+use super::{redirect, Command, Shell, ShellError};
+
+/// Appel système depuis le shell
+fn syscall(number: SyscallNumber, args: &[u64]) -> Result<u64, SyscallError> {
+    match SyscallHandler::new().handle(number as u64, args) {
+        SyscallResult::Success(value) => Ok(value),
+        SyscallResult::Error(e) => Err(e),
+    }
+}
+
+fn error_message(error: &SyscallError) -> String {
+    match error {
+        SyscallError::NoSuchProcess => String::from("processus inexistant"),
+        SyscallError::InvalidArgument => String::from("argument invalide"),
+        SyscallError::PermissionDenied | SyscallError::NotPermitted => String::from("opération non permise"),
+        other => format!("{:?}", other),
+    }
+}
+
+/// Priorité par numéro (0-4) ou par nom
+fn parse_priority(spec: &str) -> Option<ProcessPriority> {
+    let value = match spec.to_ascii_lowercase().as_str() {
+        "realtime" | "rt" => 0,
+        "high" => 1,
+        "normal" => 2,
+        "low" => 3,
+        "idle" => 4,
+        other => other.parse::<u8>().ok().filter(|p| *p <= 4)?,
+    };
+    Some(ProcessPriority::from_u8(value))
+}
+
+/// Arguments de kill : signal (TERM par défaut) et PIDs, dans n'importe quel ordre
+fn parse_kill_args(args: &[String]) -> Option<(Signal, alloc::vec::Vec<u64>)> {
+    let mut signal = Signal::SIGTERM;
+    let mut pids = alloc::vec::Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "-s" {
+            signal = Signal::parse(args.next()?)?;
+        } else if let Some(spec) = arg.strip_prefix('-') {
+            signal = Signal::parse(spec)?;
+        } else {
+            pids.push(arg.parse().ok()?);
+        }
+    }
+    if pids.is_empty() {
+        return None;
+    }
+    Some((signal, pids))
+}
+
+/// PID du processus du shell (init avant le premier processus courant)
+fn shell_pid() -> u64 {
+    mini_os::process::current_process().map(|p| p.lock().pid).unwrap_or(1)
+}
+
+/// argv d'une commande externe, sans le `&` final (vrai si présent)
+fn background_argv(cmd: &Command) -> (Vec<String>, bool) {
+    let mut argv: Vec<String> = core::iter::once(&cmd.program).chain(&cmd.args).cloned().collect();
+    let last = argv.len() - 1;
+    if argv.len() > 1 && argv[last] == "&" {
+        argv.pop();
+        return (argv, true);
+    }
+    match argv[last].strip_suffix('&') {
+        Some(word) if !word.is_empty() => {
+            argv[last] = String::from(word);
+            (argv, true)
+        }
+        _ => (argv, false),
+    }
+}
+
+/// Chemins candidats pour `name` dans une valeur de `$PATH`
+fn path_candidates(path: &str, name: &str) -> Vec<String> {
+    path.split(':')
+        .filter(|dir| !dir.is_empty())
+        .map(|dir| format!("{}/{}", dir.trim_end_matches('/'), name))
+        .collect()
+}
+
+fn is_regular_file(path: &str) -> bool {
+    mini_os::fs::vfs_stat(path).map(|st| st.file_type == mini_os::fs::FileType::Regular).unwrap_or(false)
+}
+
+impl Shell {
+    /// Fichier exécuté pour `program` : chemin résolu, ou premier fichier
+    /// trouvé dans `$PATH`
+    fn find_program(&self, program: &str) -> Option<String> {
+        if program.contains('/') {
+            return Some(self.resolve_path(program)).filter(|path| is_regular_file(path));
+        }
+        let path = self.env_vars.get("PATH").map(String::as_str).unwrap_or("/bin:/usr/bin");
+        path_candidates(path, program).into_iter().find(|candidate| is_regular_file(candidate))
+    }
+
+    /// Exécute une commande qui n'est pas un builtin
+    pub(super) fn run_external(&mut self, cmd: &Command) -> Result<(), ShellError> {
+        let (argv, background) = background_argv(cmd);
+        let path = self.find_program(&argv[0]).ok_or_else(|| ShellError::CommandNotFound(argv[0].clone()))?;
+        let image = mini_os::fs::vfs_read_file(&path).map_err(|_| ShellError::IOError)?;
+        if !image.starts_with(b"\x7fELF") {
+            return self.run_script_file(&path);
+        }
+        drop(image);
+
+        let args = self.exec_args(argv);
+        let pid = PROCESS_MANAGER.lock().spawn(&path, &args).map_err(|e| {
+            redirect::print_err(&format!("sh: {}: {}\n", path, e));
+            ShellError::ExecutionFailed(e)
+        })?;
+        if background {
+            redirect::print_out(&format!("[{}]\n", pid));
+            return Ok(());
+        }
+
+        let mut status: i32 = 0;
+        if let Err(e) = syscall(SyscallNumber::Wait, &[pid, &mut status as *mut i32 as u64, 0]) {
+            redirect::print_err(&format!("sh: {}: {}\n", path, error_message(&e)));
+            return Err(ShellError::ExecutionFailed("wait".into()));
+        }
+        match status {
+            0 => Ok(()),
+            code => Err(ShellError::ExitStatus(code)),
+        }
+    }
+
+    /// Récupère les programmes d'arrière-plan terminés
+    pub(super) fn reap_jobs(&self) {
+        let mut status: i32 = 0;
+        // -1 : n'importe quel enfant ; s'arrête sans enfant terminé
+        while let Ok(pid) = syscall(SyscallNumber::Wait, &[-1i64 as u64, &mut status as *mut i32 as u64, WNOHANG]) {
+            if pid == 0 {
+                break;
+            }
+            redirect::print_out(&format!("[{}] terminé ({})\n", pid, status));
+        }
+    }
+
+    /// kill [-l] | kill [-sig|-s sig] <pid>...
+    pub(super) fn builtin_kill(&self, cmd: &Command) -> Result<(), ShellError> {
+        if cmd.args.first().map(String::as_str) == Some("-l") {
+            let mut out = String::new();
+            for signal in Signal::ALL {
+                out.push_str(&format!("{:>2}) SIG{}\n", signal as u8, signal.name()));
+            }
+            redirect::print_out(&out);
+            return Ok(());
+        }
+        let (signal, pids) = parse_kill_args(&cmd.args).ok_or_else(|| {
+            redirect::print_err("usage : kill [-signal] <pid>... (kill -l : liste des signaux)\n");
+            ShellError::InvalidArguments
+        })?;
+
+        let mut failed = false;
+        for pid in pids {
+            if let Err(e) = syscall(SyscallNumber::Kill, &[pid, signal as u64]) {
+                redirect::print_err(&format!("kill: ({}) - {}\n", pid, error_message(&e)));
+                failed = true;
+            }
+        }
+        if failed {
+            return Err(ShellError::ExecutionFailed("kill".into()));
+        }
+        Ok(())
+    }
+
+    /// nice [-n priorité] <commande> [args] : exécute la commande avec la
+    /// priorité donnée (low par défaut), puis rétablit celle du shell
+    pub(super) fn builtin_nice(&mut self, cmd: &Command) -> Result<(), ShellError> {
+        let (priority, rest) = match cmd.args.first().map(String::as_str) {
+            Some("-n") => {
+                let spec = cmd.args.get(1).ok_or(ShellError::InvalidArguments)?;
+                (parse_priority(spec).ok_or(ShellError::InvalidArguments)?, &cmd.args[2..])
+            }
+            _ => (ProcessPriority::Low, &cmd.args[..]),
+        };
+        let pid = shell_pid();
+        let previous = match syscall(SyscallNumber::GetPriority, &[pid]) {
+            Ok(p) => p,
+            Err(e) => {
+                redirect::print_err(&format!("nice: {}\n", error_message(&e)));
+                return Err(ShellError::ExecutionFailed("nice".into()));
+            }
+        };
+        let Some(program) = rest.first() else {
+            // Sans commande : priorité courante du shell
+            redirect::print_out(&format!("{}\n", previous));
+            return Ok(());
+        };
+
+        let mut niced = Command::new(program);
+        for arg in &rest[1..] {
+            niced.add_arg(arg);
+        }
+        if let Err(e) = syscall(SyscallNumber::SetPriority, &[pid, priority.to_u8() as u64]) {
+            redirect::print_err(&format!("nice: {}\n", error_message(&e)));
+            return Err(ShellError::ExecutionFailed("nice".into()));
+        }
+        let result = self.run_builtin(&niced);
+        let _ = syscall(SyscallNumber::SetPriority, &[pid, previous]);
+        result
+    }
+
+    /// renice <priorité> <pid>...
+    pub(super) fn builtin_renice(&self, cmd: &Command) -> Result<(), ShellError> {
+        let priority = cmd.args.first().and_then(|p| parse_priority(p));
+        let pids: Option<alloc::vec::Vec<u64>> = cmd.args.iter().skip(1).map(|p| p.parse().ok()).collect();
+        let (Some(priority), Some(pids)) = (priority, pids.filter(|p| !p.is_empty())) else {
+            redirect::print_err("usage : renice <0-4|realtime|high|normal|low|idle> <pid>...\n");
+            return Err(ShellError::InvalidArguments);
+        };
+
+        let mut failed = false;
+        for pid in pids {
+            let old = syscall(SyscallNumber::GetPriority, &[pid]);
+            match old.and_then(|old| syscall(SyscallNumber::SetPriority, &[pid, priority.to_u8() as u64]).map(|_| old)) {
+                Ok(old) => redirect::print_out(&format!(
+                    "{} : ancienne priorité {:?}, nouvelle priorité {:?}\n",
+                    pid,
+                    ProcessPriority::from_u8(old as u8),
+                    priority
+                )),
+                Err(e) => {
+                    redirect::print_err(&format!("renice: {} : {}\n", pid, error_message(&e)));
+                    failed = true;
+                }
+            }
+        }
+        if failed {
+            return Err(ShellError::ExecutionFailed("renice".into()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_kill_and_priority_parsing() {
+        let args = |list: &[&str]| list.iter().map(|a| String::from(*a)).collect::<alloc::vec::Vec<_>>();
+        assert_eq!(parse_kill_args(&args(&["42"])), Some((Signal::SIGTERM, alloc::vec![42])));
+        assert_eq!(parse_kill_args(&args(&["-KILL", "3", "4"])), Some((Signal::SIGKILL, alloc::vec![3, 4])));
+        assert_eq!(parse_kill_args(&args(&["7", "-s", "usr1"])), Some((Signal::SIGUSR1, alloc::vec![7])));
+        assert_eq!(parse_kill_args(&args(&["-HUP", "1"])), None);
+        assert_eq!(parse_kill_args(&args(&["-9"])), None);
+
+        assert_eq!(parse_priority("idle"), Some(ProcessPriority::Idle));
+        assert_eq!(parse_priority("1"), Some(ProcessPriority::High));
+        assert_eq!(parse_priority("5"), None);
+    }
+
+    #[test_case]
+    fn test_external_command_lookup() {
+        assert_eq!(path_candidates("/bin:/usr/bin/::/sbin", "ls"), ["/bin/ls", "/usr/bin/ls", "/sbin/ls"]);
+
+        let mut cmd = Command::new("hello");
+        cmd.add_arg("world");
+        cmd.add_arg("&");
+        assert_eq!(background_argv(&cmd), (alloc::vec![String::from("hello"), String::from("world")], true));
+        let mut cmd = Command::new("hello");
+        cmd.add_arg("x&");
+        assert_eq!(background_argv(&cmd), (alloc::vec![String::from("hello"), String::from("x")], true));
+        assert_eq!(background_argv(&Command::new("hello")), (alloc::vec![String::from("hello")], false));
+    }
+}
@@ -0,0 +1,202 @@
+//! Redirections d'entrée/sortie du shell
+//!
+//! Les redirections passent par la table de descripteurs (FD_MANAGER) du
+//! processus courant : le fichier cible est ouvert puis installé sur 0, 1
+//! ou 2 avec `dup2` avant l'exécution, et les descripteurs d'origine sont
+//! restaurés ensuite. Un descripteur standard absent de la table désigne
+//! la console.
+
+use alloc::vec::Vec;
+use mini_os::fs::{self, FileMode, FileType, OpenMode, VfsError, VfsResult, FD_MANAGER};
+use crate::vga_buffer::{WRITER, SHELL_VT};
+
+use super::lexer::RedirectOp;
+
+pub const STDIN: usize = 0;
+pub const STDOUT: usize = 1;
+pub const STDERR: usize = 2;
+
+/// Une redirection résolue (chemin absolu)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redirect {
+    pub fd: usize,
+    pub op: RedirectOp,
+    pub path: alloc::string::String,
+}
+
+/// PID propriétaire de la table de descripteurs utilisée par le shell
+/// (0 : noyau, hors de tout processus)
+fn io_pid() -> u64 {
+    mini_os::process::current_process().map(|p| p.lock().pid).unwrap_or(0)
+}
+
+/// Descripteurs standards remplacés, restaurés à la destruction
+pub struct StdioGuard {
+    pid: u64,
+    /// (descripteur standard, copie de l'original s'il était ouvert)
+    saved: Vec<(usize, Option<usize>)>,
+}
+
+impl Drop for StdioGuard {
+    fn drop(&mut self) {
+        let mut fm = FD_MANAGER.lock();
+        if let Ok(table) = fm.get_table(self.pid) {
+            // Ordre inverse : `2> a 2> b` restaure bien l'original
+            for &(fd, saved) in self.saved.iter().rev() {
+                match saved {
+                    Some(copy) => {
+                        let _ = table.dup2(copy, fd);
+                        let _ = table.close(copy);
+                    }
+                    None => {
+                        let _ = table.close(fd);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Ouvre la cible d'une redirection ; renvoie (mode, taille actuelle)
+fn open_target(redirect: &Redirect) -> VfsResult<(OpenMode, u64)> {
+    match redirect.op {
+        RedirectOp::Input => {
+            let stat = fs::vfs_stat(&redirect.path)?;
+            if stat.file_type == FileType::Directory {
+                return Err(VfsError::IsDirectory);
+            }
+            Ok((OpenMode::ReadOnly, stat.size))
+        }
+        RedirectOp::Output => {
+            fs::vfs_write_file(&redirect.path, b"")?;
+            Ok((OpenMode::WriteOnly, 0))
+        }
+        RedirectOp::Append => {
+            match fs::vfs_create(&redirect.path, FileMode::new(0o644), FileType::Regular) {
+                Ok(_) | Err(VfsError::AlreadyExists) => {}
+                Err(e) => return Err(e),
+            }
+            let stat = fs::vfs_stat(&redirect.path)?;
+            if stat.file_type == FileType::Directory {
+                return Err(VfsError::IsDirectory);
+            }
+            Ok((OpenMode::WriteOnly, stat.size))
+        }
+    }
+}
+
+/// Installe les redirections ; elles restent actives tant que le garde vit
+pub fn apply(redirects: &[Redirect]) -> VfsResult<StdioGuard> {
+    let pid = io_pid();
+    let mut guard = StdioGuard { pid, saved: Vec::new() };
+
+    for redirect in redirects {
+        // Ouverture hors du verrou FD_MANAGER (le VFS peut être long)
+        let (mode, size) = open_target(redirect)?;
+
+        let mut fm = FD_MANAGER.lock();
+        if fm.get_table(pid).is_err() {
+            let _ = fm.create_table(pid);
+        }
+        let table = fm.get_table(pid).map_err(|_| VfsError::IoError)?;
+
+        let saved = table.dup(redirect.fd).ok();
+        let fd = table.open(&redirect.path, mode, size).map_err(|_| VfsError::IoError)?;
+        if redirect.op == RedirectOp::Append {
+            if let Ok(desc) = table.get_mut(fd) {
+                desc.offset = size;
+            }
+        }
+        let _ = table.dup2(fd, redirect.fd);
+        let _ = table.close(fd);
+        guard.saved.push((redirect.fd, saved));
+    }
+
+    Ok(guard)
+}
+
+/// Le descripteur est-il redirigé vers un fichier ?
+fn is_redirected(pid: u64, fd: usize) -> bool {
+    FD_MANAGER.lock().get_table(pid).map(|t| t.get(fd).is_ok()).unwrap_or(false)
+}
+
+/// Entrée et sortie standard sur la console (ni l'une ni l'autre redirigée)
+pub fn is_interactive() -> bool {
+    let pid = io_pid();
+    !is_redirected(pid, STDIN) && !is_redirected(pid, STDOUT)
+}
+
+/// Écrit sur `fd` : fichier si redirigé, console sinon
+fn write_fd(fd: usize, s: &str) {
+    let pid = io_pid();
+    if is_redirected(pid, fd) {
+        let _ = fs::vfs_fd_write(pid, fd, s.as_bytes());
+    } else {
+        WRITER.lock().write_vt(SHELL_VT, s);
+    }
+}
+
+/// Sortie standard
+pub fn print_out(s: &str) {
+    write_fd(STDOUT, s);
+}
+
+/// Sortie d'erreur
+pub fn print_err(s: &str) {
+    write_fd(STDERR, s);
+}
+
+/// Lit toute l'entrée standard si elle est redirigée (`None` : console)
+pub fn read_stdin() -> Option<Vec<u8>> {
+    let pid = io_pid();
+    if !is_redirected(pid, STDIN) {
+        return None;
+    }
+
+    let mut content = Vec::new();
+    let mut chunk = [0u8; 512];
+    loop {
+        match fs::vfs_fd_read(pid, STDIN, &mut chunk) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => content.extend_from_slice(&chunk[..n]),
+        }
+    }
+    Some(content)
+}
+
+/// Lit une ligne sur l'entrée standard, sans le saut de ligne ; sur la
+/// console, attend que l'utilisateur valide par Entrée
+pub fn read_line() -> alloc::string::String {
+    let pid = io_pid();
+    let redirected = is_redirected(pid, STDIN);
+    let mut line = Vec::new();
+    let mut byte = [0u8; 1];
+    loop {
+        if redirected {
+            match fs::vfs_fd_read(pid, STDIN, &mut byte) {
+                Ok(1) => {}
+                _ => break,
+            }
+        } else if mini_os::tty::read(SHELL_VT, &mut byte) == 0 {
+            // Interruptions masquées : aucune frappe ne peut arriver
+            if !x86_64::instructions::interrupts::are_enabled() {
+                break;
+            }
+            x86_64::instructions::hlt();
+            continue;
+        }
+        match byte[0] {
+            b'\n' | b'\r' => break,
+            0x08 | 0x7F => {
+                // Retire un caractère entier, octets de continuation compris
+                while let Some(b) = line.pop() {
+                    if b & 0xC0 != 0x80 {
+                        break;
+                    }
+                }
+            }
+            b => line.push(b),
+        }
+    }
+    alloc::string::String::from_utf8_lossy(&line).into_owned()
+}
@@ -0,0 +1,452 @@
+//! Interpréteur de scripts du shell
+//!
+//! Sous-ensemble de sh suffisant pour un script d'init :
+//! - une commande par ligne ou séparées par `;`, commentaires `#`
+//! - affectations `NOM=valeur` et expansion `$NOM` (`$?` : dernier statut)
+//! - `if cmd; then ...; elif cmd; then ...; else ...; fi`
+//! - `while cmd; do ...; done` et `for v in mots; do ...; done`
+//! - `test` / `[` pour les conditions
+//!
+//! Une commande réussit si le builtin renvoie `Ok`. Une erreur n'arrête
+//! pas le script (comme sh sans `-e`).
+
+use alloc::collections::VecDeque;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use super::{lexer, redirect, Command, Shell, ShellError};
+
+/// Profondeur maximale d'imbrication des scripts (script qui lance un script)
+const MAX_SCRIPT_DEPTH: usize = 8;
+
+/// Instruction d'un script
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Stmt {
+    /// Commande simple (texte brut, analysé à l'exécution)
+    Simple(String),
+    /// `NOM=valeur`
+    Assign(String, String),
+    If { cond: String, then_body: Vec<Stmt>, else_body: Vec<Stmt> },
+    While { cond: String, body: Vec<Stmt> },
+    For { var: String, words: String, body: Vec<Stmt> },
+}
+
+/// Erreur de syntaxe d'un script
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxError(pub String);
+
+/// Découpe le texte en instructions : lignes, `;`, commentaires retirés
+fn split_statements(text: &str) -> VecDeque<String> {
+    let mut out = VecDeque::new();
+    let mut current = String::new();
+    let (mut single, mut double, mut escaped) = (false, false, false);
+
+    let mut push = |current: &mut String| {
+        let stmt = current.trim();
+        if !stmt.is_empty() {
+            out.push_back(String::from(stmt));
+        }
+        current.clear();
+    };
+
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if escaped {
+            current.push(c);
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' if !single => {
+                current.push(c);
+                escaped = true;
+            }
+            '\'' if !double => {
+                single = !single;
+                current.push(c);
+            }
+            '"' if !single => {
+                double = !double;
+                current.push(c);
+            }
+            // Commentaire : `#` en début de mot, hors guillemets
+            '#' if !single && !double && current.chars().last().map_or(true, |p| p.is_whitespace()) => {
+                while let Some(&n) = chars.peek() {
+                    if n == '\n' {
+                        break;
+                    }
+                    chars.next();
+                }
+            }
+            ';' | '\n' if !single && !double => push(&mut current),
+            c => current.push(c),
+        }
+    }
+    push(&mut current);
+    out
+}
+
+/// Sépare le premier mot du reste
+fn split_keyword(stmt: &str) -> (&str, &str) {
+    match stmt.find(char::is_whitespace) {
+        Some(pos) => (&stmt[..pos], stmt[pos..].trim_start()),
+        None => (stmt, ""),
+    }
+}
+
+/// Reconnaît `NOM=valeur`
+fn parse_assignment(stmt: &str) -> Option<(String, String)> {
+    let (word, rest) = split_keyword(stmt);
+    let pos = word.find('=')?;
+    let name = &word[..pos];
+    let valid = !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid || !rest.is_empty() {
+        return None;
+    }
+    Some((String::from(name), String::from(&word[pos + 1..])))
+}
+
+struct Parser {
+    stmts: VecDeque<String>,
+}
+
+impl Parser {
+    /// Consomme le mot-clé attendu ; le reste de l'instruction est remis en file
+    fn expect(&mut self, keyword: &str) -> Result<(), SyntaxError> {
+        let stmt = self.stmts.pop_front().ok_or_else(|| SyntaxError(format!("'{}' attendu", keyword)))?;
+        let (word, rest) = split_keyword(&stmt);
+        if word != keyword {
+            return Err(SyntaxError(format!("'{}' attendu, '{}' trouvé", keyword, word)));
+        }
+        if !rest.is_empty() {
+            self.stmts.push_front(String::from(rest));
+        }
+        Ok(())
+    }
+
+    /// Lit des instructions jusqu'à l'un des `terminators` (consommé et renvoyé)
+    fn block(&mut self, terminators: &[&'static str]) -> Result<(Vec<Stmt>, Option<&'static str>), SyntaxError> {
+        let mut body = Vec::new();
+        while let Some(stmt) = self.stmts.pop_front() {
+            let (word, rest) = split_keyword(&stmt);
+
+            if let Some(&end) = terminators.iter().find(|t| **t == word) {
+                if !rest.is_empty() {
+                    self.stmts.push_front(String::from(rest));
+                }
+                return Ok((body, Some(end)));
+            }
+
+            let parsed = match word {
+                "if" => self.parse_if(String::from(rest))?,
+                "while" => {
+                    self.expect("do")?;
+                    let body = self.block_until("done")?;
+                    Stmt::While { cond: String::from(rest), body }
+                }
+                "for" => {
+                    let (var, tail) = split_keyword(rest);
+                    let (in_kw, words) = split_keyword(tail);
+                    if var.is_empty() || in_kw != "in" {
+                        return Err(SyntaxError(String::from("for: syntaxe 'for VAR in MOTS'")));
+                    }
+                    let (var, words) = (String::from(var), String::from(words));
+                    self.expect("do")?;
+                    let body = self.block_until("done")?;
+                    Stmt::For { var, words, body }
+                }
+                "then" | "do" | "done" | "fi" | "else" | "elif" => {
+                    return Err(SyntaxError(format!("'{}' inattendu", word)));
+                }
+                _ => match parse_assignment(&stmt) {
+                    Some((name, value)) => Stmt::Assign(name, value),
+                    None => Stmt::Simple(stmt.clone()),
+                },
+            };
+            body.push(parsed);
+        }
+
+        if terminators.is_empty() {
+            Ok((body, None))
+        } else {
+            Err(SyntaxError(format!("fin de script inattendue ('{}' attendu)", terminators.join("/"))))
+        }
+    }
+
+    fn block_until(&mut self, terminator: &'static str) -> Result<Vec<Stmt>, SyntaxError> {
+        Ok(self.block(&[terminator])?.0)
+    }
+
+    /// `if` dont la condition a été lue ; `elif` devient un `if` imbriqué
+    fn parse_if(&mut self, cond: String) -> Result<Stmt, SyntaxError> {
+        self.expect("then")?;
+        let (then_body, end) = self.block(&["elif", "else", "fi"])?;
+        let else_body = match end {
+            Some("else") => self.block_until("fi")?,
+            Some("elif") => {
+                let cond = self.stmts.pop_front().ok_or_else(|| SyntaxError(String::from("elif: condition manquante")))?;
+                alloc::vec![self.parse_if(cond)?]
+            }
+            _ => Vec::new(),
+        };
+        Ok(Stmt::If { cond, then_body, else_body })
+    }
+}
+
+/// Analyse un script complet
+pub fn parse_script(text: &str) -> Result<Vec<Stmt>, SyntaxError> {
+    let mut parser = Parser { stmts: split_statements(text) };
+    Ok(parser.block(&[])?.0)
+}
+
+/// Évalue une expression `test` ; `None` si l'expression est invalide
+pub fn eval_test(args: &[String], current_dir: &str) -> Option<bool> {
+    let args: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+    let resolve = |p: &str| -> String {
+        if p.starts_with('/') {
+            String::from(p)
+        } else if current_dir == "/" {
+            format!("/{}", p)
+        } else {
+            format!("{}/{}", current_dir, p)
+        }
+    };
+
+    match args.as_slice() {
+        [] => Some(false),
+        ["!", rest @ ..] => {
+            let rest: Vec<String> = rest.iter().map(|s| String::from(*s)).collect();
+            eval_test(&rest, current_dir).map(|r| !r)
+        }
+        [s] => Some(!s.is_empty()),
+        ["-n", s] => Some(!s.is_empty()),
+        ["-z", s] => Some(s.is_empty()),
+        ["-e", p] => Some(mini_os::fs::vfs_stat(&resolve(p)).is_ok()),
+        ["-d", p] => Some(mini_os::fs::is_dir(&resolve(p))),
+        ["-f", p] => Some(
+            mini_os::fs::vfs_stat(&resolve(p))
+                .map(|st| st.file_type == mini_os::fs::FileType::Regular)
+                .unwrap_or(false),
+        ),
+        [a, "=", b] => Some(a == b),
+        [a, "!=", b] => Some(a != b),
+        [a, op, b] => {
+            let (a, b) = (a.parse::<i64>().ok()?, b.parse::<i64>().ok()?);
+            match *op {
+                "-eq" => Some(a == b),
+                "-ne" => Some(a != b),
+                "-lt" => Some(a < b),
+                "-le" => Some(a <= b),
+                "-gt" => Some(a > b),
+                "-ge" => Some(a >= b),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+impl Shell {
+    /// Exécute un fichier script
+    pub fn run_script_file(&mut self, path: &str) -> Result<(), ShellError> {
+        let path = self.resolve_path(path);
+        let content = mini_os::fs::vfs_read_file(&path).map_err(|e| {
+            redirect::print_err(&format!("sh: {}: {:?}\n", path, e));
+            ShellError::IOError
+        })?;
+        self.run_script(&String::from_utf8_lossy(&content))
+    }
+
+    /// Exécute le texte d'un script
+    pub fn run_script(&mut self, text: &str) -> Result<(), ShellError> {
+        let stmts = parse_script(text).map_err(|e| {
+            redirect::print_err(&format!("sh: erreur de syntaxe: {}\n", e.0));
+            ShellError::InvalidArguments
+        })?;
+
+        if self.script_depth >= MAX_SCRIPT_DEPTH {
+            redirect::print_err("sh: trop de scripts imbriqués\n");
+            return Err(ShellError::ExecutionFailed("script depth".into()));
+        }
+        self.script_depth += 1;
+        let ok = self.exec_block(&stmts);
+        self.script_depth -= 1;
+
+        if ok { Ok(()) } else { Err(ShellError::ExecutionFailed("script failed".into())) }
+    }
+
+    /// Exécute un bloc ; renvoie le statut de la dernière commande
+    fn exec_block(&mut self, stmts: &[Stmt]) -> bool {
+        let mut status = true;
+        for stmt in stmts {
+            status = self.exec_stmt(stmt);
+        }
+        status
+    }
+
+    fn exec_stmt(&mut self, stmt: &Stmt) -> bool {
+        match stmt {
+            Stmt::Simple(line) => self.run_line(line),
+            Stmt::Assign(name, value) => match self.expand_words(value) {
+                Some(words) => {
+                    self.env_vars.insert(name.clone(), words.join(" "));
+                    self.set_status(true)
+                }
+                None => self.set_status(false),
+            },
+            Stmt::If { cond, then_body, else_body } => {
+                if self.run_line(cond) {
+                    self.exec_block(then_body)
+                } else {
+                    self.exec_block(else_body)
+                }
+            }
+            Stmt::While { cond, body } => {
+                let mut status = true;
+                while self.run_line(cond) {
+                    status = self.exec_block(body);
+                    // Un script ne doit pas monopoliser le CPU
+                    mini_os::scheduler::cond_resched();
+                }
+                status
+            }
+            Stmt::For { var, words, body } => {
+                let mut status = true;
+                for word in self.expand_words(words).unwrap_or_default() {
+                    self.env_vars.insert(var.clone(), word);
+                    status = self.exec_block(body);
+                    mini_os::scheduler::cond_resched();
+                }
+                status
+            }
+        }
+    }
+
+    /// Développe une liste de mots (variables, guillemets, jokers)
+    fn expand_words(&self, text: &str) -> Option<Vec<String>> {
+        let tokens = lexer::tokenize(text, &self.env_vars).ok()?;
+        let mut words = Vec::new();
+        for token in tokens {
+            match token {
+                lexer::Token::Word(word) => words.extend(lexer::expand_glob(&word, &self.current_dir)),
+                lexer::Token::Redirect { .. } => return None,
+            }
+        }
+        Some(words)
+    }
+
+    /// Exécute une ligne de commande et met à jour `$?`
+    pub(super) fn run_line(&mut self, line: &str) -> bool {
+        self.reap_jobs();
+        let result = self.parse_command(line).and_then(|cmd| self.execute(cmd));
+        let code = match &result {
+            Ok(_) => 0,
+            // Les builtins signalent eux-mêmes leurs échecs d'exécution
+            Err(ShellError::ExecutionFailed(_)) => 1,
+            Err(ShellError::ExitStatus(code)) => *code,
+            Err(ShellError::CommandNotFound(name)) => {
+                redirect::print_err(&format!("sh: {}: commande introuvable\n", name));
+                127
+            }
+            Err(e) => {
+                redirect::print_err(&format!("sh: {}: {:?}\n", line, e));
+                1
+            }
+        };
+        self.set_exit_code(code)
+    }
+
+    fn set_status(&mut self, ok: bool) -> bool {
+        self.set_exit_code(if ok { 0 } else { 1 })
+    }
+
+    /// `$?` reçoit le code de sortie ; vrai pour un succès
+    fn set_exit_code(&mut self, code: i32) -> bool {
+        self.env_vars.insert("?".into(), format!("{}", code));
+        code == 0
+    }
+
+    /// Commande: sh <script>
+    pub(super) fn builtin_sh(&mut self, cmd: &Command) -> Result<(), ShellError> {
+        match cmd.args.first() {
+            Some(path) => {
+                let path = path.clone();
+                self.run_script_file(&path)
+            }
+            None => Err(ShellError::InvalidArguments),
+        }
+    }
+
+    /// Commande: test <expr> / [ <expr> ]
+    pub(super) fn builtin_test(&self, cmd: &Command) -> Result<(), ShellError> {
+        let mut args = cmd.args.clone();
+        if cmd.program == "[" {
+            if args.last().map(|a| a.as_str()) != Some("]") {
+                redirect::print_err("[: ']' manquant\n");
+                return Err(ShellError::InvalidArguments);
+            }
+            args.pop();
+        }
+
+        match eval_test(&args, &self.current_dir) {
+            Some(true) => Ok(()),
+            Some(false) => Err(ShellError::ExecutionFailed("test".into())),
+            None => {
+                redirect::print_err("test: expression invalide\n");
+                Err(ShellError::InvalidArguments)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_split_statements() {
+        let stmts = split_statements("# entête\necho a; echo 'b;c' # fin\n\n  ls");
+        assert_eq!(stmts, ["echo a", "echo 'b;c'", "ls"]);
+    }
+
+    #[test_case]
+    fn test_parse_control_flow() {
+        let script = "X=1\nif test $X = 1; then echo un; elif true; then echo deux; else echo autre; fi\n\
+                      for f in a b; do echo $f; done\nwhile false\ndo\n  echo jamais\ndone";
+        let stmts = parse_script(script).unwrap();
+        assert_eq!(stmts.len(), 4);
+        assert_eq!(stmts[0], Stmt::Assign("X".into(), "1".into()));
+        match &stmts[1] {
+            Stmt::If { then_body, else_body, .. } => {
+                assert_eq!(then_body, &[Stmt::Simple("echo un".into())]);
+                assert!(matches!(else_body.as_slice(), [Stmt::If { .. }]));
+            }
+            other => panic!("if attendu: {:?}", other),
+        }
+        assert!(matches!(&stmts[2], Stmt::For { var, .. } if var == "f"));
+        assert!(matches!(&stmts[3], Stmt::While { body, .. } if body.len() == 1));
+
+        assert!(parse_script("if true; then echo").is_err());
+        assert!(parse_script("done").is_err());
+    }
+
+    #[test_case]
+    fn test_eval_test() {
+        let args = |s: &str| s.split(' ').map(String::from).collect::<Vec<_>>();
+        assert_eq!(eval_test(&args("a = a"), "/"), Some(true));
+        assert_eq!(eval_test(&args("3 -lt 2"), "/"), Some(false));
+        assert_eq!(eval_test(&args("! -z x"), "/"), Some(true));
+        assert_eq!(eval_test(&args("1 -foo 2"), "/"), None);
+    }
+
+    #[test_case]
+    fn test_run_script_loops_and_variables() {
+        let mut shell = Shell::new();
+        shell.run_script("N=0\nfor i in 1 2 3; do N=$N$i; done\nif [ $N = 0123 ]; then OK=oui; else OK=non; fi").unwrap();
+        assert_eq!(shell.env_vars.get("N").map(|s| s.as_str()), Some("0123"));
+        assert_eq!(shell.env_vars.get("OK").map(|s| s.as_str()), Some("oui"));
+        assert_eq!(shell.env_vars.get("?").map(|s| s.as_str()), Some("0"));
+    }
+}
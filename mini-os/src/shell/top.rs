@@ -0,0 +1,221 @@
+//! Moniteur système : top
+//!
+//! Chaque rafraîchissement prend un instantané des processus
+//! (`snapshot_processes`, comme ps et /proc) ; le %CPU d'un processus est
+//! la part du temps écoulé couverte par l'avance de son vruntime, ramené
+//! en temps réel selon la priorité de chaque thread. La mémoire d'un
+//! processus est son tas (brk) plus ses régions mmap.
+//!
+//! L'attente entre deux écrans suit les ticks du timer ; les touches
+//! sont lues sans bloquer : `q` quitte, `c`/`m`/`p` trient par CPU,
+//! mémoire ou PID, `k` demande un PID à tuer (SIGKILL). Sortie
+//! redirigée : un seul écran, sans effacement ni clavier (mode batch).
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use mini_os::process::signal::{Signal, SIGNAL_MANAGER};
+use mini_os::process::{self, ProcessSnapshot, PROCESS_MANAGER};
+use mini_os::scheduler::{self, LOAD_FIXED_1, TIMER_HZ};
+
+use super::{redirect, Command, Shell, ShellError};
+
+/// Critère de tri des lignes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SortKey {
+    Cpu,
+    Memory,
+    Pid,
+}
+
+/// Ligne de l'affichage
+struct Row {
+    pid: u64,
+    name: String,
+    state: String,
+    threads: usize,
+    /// %CPU en dixièmes
+    cpu: u64,
+    memory: u64,
+}
+
+/// Charge en virgule fixe au format « 1.25 »
+fn format_load(load: u64) -> String {
+    format!("{}.{:02}", load / LOAD_FIXED_1, (load % LOAD_FIXED_1) * 100 / LOAD_FIXED_1)
+}
+
+/// %CPU (en dixièmes) de `run` ns d'exécution pendant `elapsed` ns
+fn cpu_tenths(run: u64, elapsed: u64) -> u64 {
+    if elapsed == 0 {
+        return 0;
+    }
+    (run.saturating_mul(1000) / elapsed).min(1000 * mini_os::percpu::cpu_count() as u64)
+}
+
+/// Lignes triées à partir de deux instantanés successifs
+fn build_rows(processes: &[ProcessSnapshot], previous: &BTreeMap<u64, u64>, elapsed: u64, sort: SortKey) -> Vec<Row> {
+    let mmap = mini_os::memory::MMAP_MANAGER.lock();
+    let mut rows: Vec<Row> = processes
+        .iter()
+        .map(|p| {
+            let run = p.run_time().saturating_sub(previous.get(&p.pid).copied().unwrap_or(p.run_time()));
+            Row {
+                pid: p.pid,
+                name: p.name.clone(),
+                state: format!("{:?}", p.state),
+                threads: p.thread_count(),
+                cpu: cpu_tenths(run, elapsed),
+                memory: p.heap + mmap.mapped_size(p.pid),
+            }
+        })
+        .collect();
+    drop(mmap);
+    match sort {
+        SortKey::Cpu => rows.sort_by(|a, b| b.cpu.cmp(&a.cpu).then(a.pid.cmp(&b.pid))),
+        SortKey::Memory => rows.sort_by(|a, b| b.memory.cmp(&a.memory).then(a.pid.cmp(&b.pid))),
+        SortKey::Pid => rows.sort_by_key(|r| r.pid),
+    }
+    rows
+}
+
+/// Affiche un écran complet
+fn render(rows: &[Row], interactive: bool, sort: SortKey, message: &str) {
+    let mut out = String::new();
+    if interactive {
+        out.push_str("\x1b[2J\x1b[H");
+    }
+    let uptime = scheduler::ticks() / TIMER_HZ;
+    let load = scheduler::load_average();
+    out.push_str(&format!(
+        "top - {}:{:02}:{:02} en marche, {} processus, charge : {} {} {}\n",
+        uptime / 3600,
+        uptime / 60 % 60,
+        uptime % 60,
+        rows.len(),
+        format_load(load[0]),
+        format_load(load[1]),
+        format_load(load[2])
+    ));
+    let stats = mini_os::memory::HYBRID_ALLOCATOR.get_stats();
+    let (total, used) = (stats.buddy.heap_size as u64, stats.buddy.current_memory_usage as u64);
+    out.push_str(&format!(
+        "Mém : {} Kio total, {} Kio libre, {} Kio utilisé ; tri : {:?}\n",
+        total / 1024,
+        total.saturating_sub(used) / 1024,
+        used / 1024,
+        sort
+    ));
+    out.push_str(&format!("{}\n", message));
+    out.push_str("  PID STATE      THR   %CPU     MÉM(Kio) COMMAND\n");
+    for row in rows {
+        out.push_str(&format!(
+            "{:>5} {:<10} {:>3} {:>4}.{} {:>12} {}\n",
+            row.pid,
+            row.state,
+            row.threads,
+            row.cpu / 10,
+            row.cpu % 10,
+            row.memory / 1024,
+            row.name
+        ));
+    }
+    redirect::print_out(&out);
+}
+
+/// Envoie SIGKILL à `pid`
+fn kill(pid: u64) -> Result<(), &'static str> {
+    let mut pm = PROCESS_MANAGER.lock();
+    SIGNAL_MANAGER.lock().send_signal(pid, Signal::SIGKILL, &mut pm)
+}
+
+/// Attend l'échéance en ticks ; renvoie la première touche frappée
+pub(super) fn wait_key(deadline: u64) -> Option<u8> {
+    let mut key = [0u8; 1];
+    while scheduler::ticks() < deadline {
+        if mini_os::tty::read(mini_os::tty::SHELL_VT, &mut key) == 1 {
+            return Some(key[0]);
+        }
+        if !x86_64::instructions::interrupts::are_enabled() {
+            return None;
+        }
+        x86_64::instructions::hlt();
+    }
+    None
+}
+
+impl Shell {
+    /// top [-d secondes] [-n écrans] [-s cpu|mem|pid]
+    pub(super) fn builtin_top(&self, cmd: &Command) -> Result<(), ShellError> {
+        let mut delay = 1;
+        let mut iterations = None;
+        let mut sort = SortKey::Cpu;
+        let mut args = cmd.args.iter();
+        while let Some(arg) = args.next() {
+            let value = args.next().map(String::as_str);
+            match (arg.as_str(), value) {
+                ("-d", Some(v)) => delay = v.parse::<u64>().ok().filter(|d| *d > 0).ok_or(ShellError::InvalidArguments)?,
+                ("-n", Some(v)) => iterations = Some(v.parse::<u64>().map_err(|_| ShellError::InvalidArguments)?),
+                ("-s", Some("cpu")) => sort = SortKey::Cpu,
+                ("-s", Some("mem")) => sort = SortKey::Memory,
+                ("-s", Some("pid")) => sort = SortKey::Pid,
+                _ => return Err(ShellError::InvalidArguments),
+            }
+        }
+        let interactive = redirect::is_interactive();
+        // Mode batch : un seul écran par défaut
+        let iterations = iterations.unwrap_or(if interactive { u64::MAX } else { 1 });
+
+        let mut previous = BTreeMap::new();
+        let mut last_ns = mini_os::time::now_ns();
+        let mut message = String::from("q : quitter, k : tuer, c/m/p : trier par CPU/mémoire/PID");
+        // Premier écran : %CPU mesuré sur un court intervalle plutôt que nul
+        for p in process::snapshot_processes() {
+            previous.insert(p.pid, p.run_time());
+        }
+        wait_key(scheduler::ticks() + TIMER_HZ / 10);
+
+        for screen in 0..iterations {
+            let processes = process::snapshot_processes();
+            let now = mini_os::time::now_ns();
+            let rows = build_rows(&processes, &previous, now - last_ns, sort);
+            previous = processes.iter().map(|p| (p.pid, p.run_time())).collect();
+            last_ns = now;
+            render(&rows, interactive, sort, &message);
+
+            if !interactive || screen + 1 == iterations {
+                break;
+            }
+            match wait_key(scheduler::ticks() + delay * TIMER_HZ) {
+                Some(b'q') | Some(b'Q') => break,
+                Some(b'c') => sort = SortKey::Cpu,
+                Some(b'm') => sort = SortKey::Memory,
+                Some(b'p') => sort = SortKey::Pid,
+                Some(b'k') => {
+                    redirect::print_out("PID à tuer : ");
+                    let line = redirect::read_line();
+                    message = match line.trim().parse::<u64>() {
+                        Ok(pid) => match kill(pid) {
+                            Ok(()) => format!("SIGKILL envoyé à {}", pid),
+                            Err(e) => format!("kill {} : {}", pid, e),
+                        },
+                        Err(_) => String::from("PID invalide"),
+                    };
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_top_helpers() {
+        assert_eq!(format_load(LOAD_FIXED_1 * 5 / 4), "1.25");
+        assert_eq!(cpu_tenths(250_000_000, 1_000_000_000), 250);
+        assert_eq!(cpu_tenths(1, 0), 0);
+    }
+}
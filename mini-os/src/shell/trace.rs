@@ -0,0 +1,89 @@
+//! Tampon de trace : trace
+//!
+//! `trace` affiche les événements enregistrés par les points de trace du
+//! noyau (changements de contexte, appels système, fautes de page, IRQ),
+//! tous CPU confondus et par date. `-e` ne garde que certains événements,
+//! `-c` un seul CPU, `-n` les derniers enregistrements. `trace on|off`
+//! choisit les événements enregistrés, `trace clear` vide le tampon.
+
+use alloc::format;
+use alloc::string::String;
+use mini_os::trace::{self, Event, Filter, ALL_EVENTS};
+
+use super::{redirect, Command, Shell, ShellError};
+
+impl Shell {
+    /// trace [-e événements] [-c cpu] [-n nombre] | trace on [événements] | off | clear | events
+    pub(super) fn builtin_trace(&mut self, cmd: &Command) -> Result<(), ShellError> {
+        match cmd.args.first().map(String::as_str) {
+            Some("on") => {
+                let mask = match cmd.args.get(1) {
+                    Some(list) => trace::parse_events(list).map_err(|_| ShellError::InvalidArguments)?,
+                    None => ALL_EVENTS,
+                };
+                trace::set_mask(mask);
+                Ok(())
+            }
+            Some("off") => {
+                trace::set_mask(0);
+                Ok(())
+            }
+            Some("clear") => {
+                trace::clear();
+                Ok(())
+            }
+            Some("events") => {
+                let mask = trace::mask();
+                for event in Event::ALL {
+                    let state = if mask & event.bit() != 0 { "on" } else { "off" };
+                    redirect::print_out(&format!("{:<13} {}\n", event.name(), state));
+                }
+                Ok(())
+            }
+            _ => self.trace_dump(&cmd.args),
+        }
+    }
+
+    fn trace_dump(&mut self, args: &[String]) -> Result<(), ShellError> {
+        let mut filter = Filter::ALL;
+        let mut last = None;
+        let mut args = args.iter();
+        while let Some(flag) = args.next() {
+            let value = args.next().ok_or(ShellError::InvalidArguments)?;
+            match flag.as_str() {
+                "-e" => filter.events = trace::parse_events(value).map_err(|_| ShellError::InvalidArguments)?,
+                "-c" => filter.cpu = Some(value.parse().map_err(|_| ShellError::InvalidArguments)?),
+                "-n" => last = Some(value.parse().map_err(|_| ShellError::InvalidArguments)?),
+                _ => return Err(ShellError::InvalidArguments),
+            }
+        }
+        redirect::print_out(&trace::dump(&filter, last));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_trace_builtin() {
+        let mut shell = Shell::new();
+        let mut dump = Command::new("trace");
+        dump.add_arg("-e");
+        dump.add_arg("sys_enter,irq_entry");
+        dump.add_arg("-n");
+        dump.add_arg("5");
+        assert!(shell.builtin_trace(&dump).is_ok());
+
+        let mut unknown = Command::new("trace");
+        unknown.add_arg("-e");
+        unknown.add_arg("softirq");
+        assert!(shell.builtin_trace(&unknown).is_err());
+
+        // Option sans valeur
+        let mut missing = Command::new("trace");
+        missing.add_arg("-c");
+        assert!(shell.builtin_trace(&missing).is_err());
+    }
+}
@@ -11,6 +11,20 @@ extern crate alloc;
 
 const TRAMPOLINE_ADDR: u64 = 0x8000;
 
+use alloc::vec::Vec;
+use spin::Mutex;
+use crate::process::kstack::KernelStack;
+
+/// Piles de démarrage des APs, en attente d'être confiées à leur thread idle
+static AP_BOOT_STACKS: Mutex<Vec<(u32, KernelStack)>> = Mutex::new(Vec::new());
+
+/// Récupère la pile de démarrage d'un AP (appelé à l'enregistrement du CPU)
+pub fn take_ap_boot_stack(apic_id: u32) -> Option<KernelStack> {
+    let mut stacks = AP_BOOT_STACKS.lock();
+    let pos = stacks.iter().position(|(id, _)| *id == apic_id)?;
+    Some(stacks.remove(pos).1)
+}
+
 pub fn init() {
     // Detect & Boot CPUs
     if let Some(rsdp) = acpi::find_rsdp() {
@@ -31,7 +45,11 @@ pub fn init() {
                  copy_nonoverlapping(code.as_ptr(), TRAMPOLINE_ADDR as *mut u8, code.len());
              }
 
-             let madt_ptr = &madt as *const acpi::madt::Madt;
+             // Les entrées suivent l'en-tête : il faut la table en place, pas sa copie
+             let madt_ptr = match acpi::find_table(&rsdp, b"APIC") {
+                 Some(address) => address as *const acpi::madt::Madt,
+                 None => return,
+             };
              let processors = acpi::madt::parse_madt(madt_ptr);
              
              let bsp_id = bootstrap_lapic.id();
@@ -58,11 +76,10 @@ fn boot_ap(lapic: &mut LocalApic, apic_id: u8, trampoline_addr: u64) {
     let (pml4_frame, _) = Cr3::read();
     let pml4_addr = pml4_frame.start_address().as_u64();
     
-    // Allocate stack (Quick hack: using array or vec leak)
-    let stack_size = 4096 * 4;
-    let stack = alloc::vec![0u8; stack_size];
-    let stack_ptr = unsafe { stack.as_ptr().add(stack_size) as u64 };
-    core::mem::forget(stack); // Leak stack so it lives forever
+    // Allocate stack: elle devient la pile du thread idle de l'AP (cf. percpu::register_cpu)
+    let stack = KernelStack::new(4096 * 4);
+    let stack_ptr = stack.top();
+    AP_BOOT_STACKS.lock().push((apic_id as u32, stack));
     
     unsafe {
         write_volatile((trampoline_addr + pml4_offset) as *mut u32, pml4_addr as u32); // Lower 32 bits
@@ -88,8 +105,12 @@ fn boot_ap(lapic: &mut LocalApic, apic_id: u8, trampoline_addr: u64) {
 #[no_mangle]
 pub extern "C" fn ap_entry() -> ! {
     // Initialiser les segments, GDT, IDT pour ce CPU
+    // GDT/TSS propres à ce CPU, puis ses MSR SYSCALL
+    crate::gdt::init_cpu();
+    crate::cpuid::init_cpu();
+    crate::memory::uaccess::init_cpu();
+    crate::syscall::entry::init_cpu();
     crate::interrupts::init_idt();
-    // crate::gdt::init(); // TODO: Need per-cpu GDT or shared?
     
     // Enable LAPIC
     let lapic = LocalApic::new(0xFEE00000);
@@ -100,8 +121,6 @@ pub extern "C" fn ap_entry() -> ! {
     
     crate::serial_println!("Hello from CPU APIC ID: {}", id);
     
-    crate::serial_println!("Hello from CPU APIC ID: {}", id);
-    
     // Start scheduling on this AP
     crate::scheduler::SCHEDULER.run();
 }
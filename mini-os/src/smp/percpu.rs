@@ -1,65 +1,38 @@
+/// Mise en route des variables par CPU d'un processeur (BSP ou AP)
+///
+/// La zone GS et l'index logique viennent de `crate::percpu` ; ce module
+/// y ajoute le thread idle propre à chaque CPU.
 
 use alloc::sync::Arc;
-use alloc::boxed::Box;
-use spin::Mutex;
+use core::cell::RefCell;
+use crate::sync::Mutex;
 use crate::process::thread::Thread;
-use x86_64::registers::model_specific::GsBase;
-use x86_64::VirtAddr;
 
-#[derive(Debug)]
-pub struct PerCpuData {
-    pub lapic_id: u32,
-    pub current_thread: Option<Arc<Mutex<Thread>>>,
-}
-
-impl PerCpuData {
-    pub fn new(lapic_id: u32) -> Self {
-        Self {
-            lapic_id,
-            current_thread: None,
-        }
-    }
-}
-
-use alloc::vec::Vec;
-use lazy_static::lazy_static;
-
-lazy_static! {
-    pub static ref PER_CPU_DATA: Mutex<Vec<Box<PerCpuData>>> = Mutex::new(Vec::new());
+crate::percpu! {
+    /// Thread idle propre à ce CPU, exécuté quand la runqueue est vide
+    static IDLE_THREAD: RefCell<Option<Arc<Mutex<Thread>>>> = RefCell::new(None);
 }
 
 pub fn register_cpu(lapic_id: u32) {
-    let cpu_data = Box::new(PerCpuData::new(lapic_id));
-    let cpu_ptr = &*cpu_data as *const PerCpuData as u64;
-    
-    // Set GS Base to point to this structure
-    unsafe {
-        GsBase::write(VirtAddr::new(cpu_ptr));
+    let index = match crate::percpu::setup_cpu(lapic_id) {
+        Ok(index) => index,
+        Err(e) => panic!("{} (APIC {})", e, lapic_id),
+    };
+    let idle = crate::scheduler::idle::create_idle_thread(index);
+
+    // La pile de démarrage d'un AP appartient désormais à son thread idle
+    if let Some(stack) = super::take_ap_boot_stack(lapic_id) {
+        let mut thread = idle.lock();
+        stack.set_owner(thread.tid);
+        thread.kstack = Some(stack);
     }
-    
-    PER_CPU_DATA.lock().push(cpu_data);
+    IDLE_THREAD.with(|slot| *slot.borrow_mut() = Some(idle));
 }
 
 pub fn get_current_cpu_id() -> u32 {
-    let cpu_ptr = GsBase::read().as_u64();
-    if cpu_ptr == 0 {
-        return 0; // Should not happen if registered
-    }
-    unsafe { (*(cpu_ptr as *const PerCpuData)).lapic_id }
+    crate::percpu::lapic_id()
 }
 
-pub fn set_current_thread(thread: Option<Arc<Mutex<Thread>>>) {
-    let cpu_ptr = GsBase::read().as_u64();
-    if cpu_ptr != 0 {
-        unsafe { (*(cpu_ptr as *mut PerCpuData)).current_thread = thread; }
-    }
-}
-
-pub fn get_current_thread() -> Option<Arc<Mutex<Thread>>> {
-    let cpu_ptr = GsBase::read().as_u64();
-    if cpu_ptr != 0 {
-        unsafe { (*(cpu_ptr as *const PerCpuData)).current_thread.clone() }
-    } else {
-        None
-    }
+pub fn get_idle_thread() -> Option<Arc<Mutex<Thread>>> {
+    IDLE_THREAD.with(|slot| slot.borrow().clone())
 }
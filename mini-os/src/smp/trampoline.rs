@@ -81,10 +81,18 @@ prot_mode:
     mov eax, [esi]
     mov cr3, eax
     
-    // 8. Enable Long Mode (EFER MSR)
+    // 8. Enable Long Mode (EFER MSR), plus NXE when the CPU has NX:
+    //    the AP stack is mapped non-executable
+    mov eax, 0x80000001
+    cpuid
+    mov ebx, 0x100
+    bt edx, 20
+    jnc 1f
+    or ebx, 0x800
+1:
     mov ecx, 0xC0000080
     rdmsr
-    or eax, 0x100
+    or eax, ebx
     wrmsr
     
     // 9. Enable Paging (CR0)
@@ -0,0 +1,126 @@
+/// Verrou tournant qui masque les interruptions tant qu'il est tenu
+///
+/// Un verrou pris à la fois en interruption et hors interruption doit
+/// l'être interruptions masquées, sinon le gestionnaire qui interrompt le
+/// détenteur tourne à jamais sur le même CPU. `SpinLockIrqSave` masque les
+/// interruptions avant d'acquérir le verrou et rétablit l'état précédent
+/// (pas forcément « actives ») après l'avoir rendu : les imbrications
+/// restent correctes. Remplace les `without_interrupts(|| X.lock()...)`.
+
+use core::fmt;
+use core::mem::ManuallyDrop;
+use core::ops::{Deref, DerefMut};
+use x86_64::instructions::interrupts;
+use super::{Mutex, MutexGuard};
+
+pub struct SpinLockIrqSave<T: ?Sized> {
+    inner: Mutex<T>,
+}
+
+pub struct SpinLockIrqSaveGuard<'a, T: ?Sized> {
+    guard: ManuallyDrop<MutexGuard<'a, T>>,
+    /// Interruptions actives avant l'acquisition
+    irq_enabled: bool,
+}
+
+/// Masque les interruptions ; retourne l'état précédent
+fn irq_save() -> bool {
+    let enabled = interrupts::are_enabled();
+    if enabled {
+        interrupts::disable();
+    }
+    enabled
+}
+
+fn irq_restore(enabled: bool) {
+    if enabled {
+        interrupts::enable();
+    }
+}
+
+impl<T> SpinLockIrqSave<T> {
+    pub const fn new(value: T) -> Self {
+        Self { inner: Mutex::new(value) }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner.into_inner()
+    }
+}
+
+impl<T: ?Sized> SpinLockIrqSave<T> {
+    pub fn lock(&self) -> SpinLockIrqSaveGuard<'_, T> {
+        let irq_enabled = irq_save();
+        SpinLockIrqSaveGuard { guard: ManuallyDrop::new(self.inner.lock()), irq_enabled }
+    }
+
+    pub fn try_lock(&self) -> Option<SpinLockIrqSaveGuard<'_, T>> {
+        let irq_enabled = irq_save();
+        match self.inner.try_lock() {
+            Some(guard) => Some(SpinLockIrqSaveGuard { guard: ManuallyDrop::new(guard), irq_enabled }),
+            None => {
+                irq_restore(irq_enabled);
+                None
+            }
+        }
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.inner.is_locked()
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        self.inner.get_mut()
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for SpinLockIrqSave<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl<T: ?Sized> Deref for SpinLockIrqSaveGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T: ?Sized> DerefMut for SpinLockIrqSaveGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+impl<T: ?Sized> Drop for SpinLockIrqSaveGuard<'_, T> {
+    fn drop(&mut self) {
+        // Le verrou est rendu avant que les interruptions ne reviennent
+        unsafe { ManuallyDrop::drop(&mut self.guard) };
+        irq_restore(self.irq_enabled);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_irqsave_restores_state() {
+        let lock = SpinLockIrqSave::new(0u32);
+        let before = interrupts::are_enabled();
+        {
+            let mut value = lock.lock();
+            *value += 1;
+            assert!(!interrupts::are_enabled());
+            // Imbrication : l'état « masqué » est rétabli, pas « actif »
+            let other = SpinLockIrqSave::new(());
+            drop(other.lock());
+            assert!(!interrupts::are_enabled());
+            assert!(lock.try_lock().is_none());
+        }
+        assert_eq!(interrupts::are_enabled(), before);
+        assert_eq!(*lock.lock(), 1);
+    }
+}
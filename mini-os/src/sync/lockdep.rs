@@ -0,0 +1,305 @@
+/// Lockdep : détection des inversions d'ordre de verrouillage
+///
+/// Avec la feature `lockdep`, `sync::Mutex` est une enveloppe de
+/// `spin::Mutex` qui déclare chaque acquisition. Chaque verrou appartient
+/// à une classe, celle du type protégé (tous les `Mutex<Thread>` forment
+/// une seule classe). Pour chaque CPU, la pile des classes tenues est
+/// relevée ; prendre B en tenant A ajoute l'arc A -> B au graphe des
+/// ordres observés, avec la pile d'appel qui l'a créé. Un arc qui ferme
+/// un cycle est un interblocage possible, même s'il ne s'est pas produit :
+/// le noyau panique en montrant les deux piles (sites fiables avec
+/// `-C force-frame-pointers=yes`, comme pour KASAN).
+///
+/// L'acquisition est déclarée avant l'attente sur le verrou : un
+/// interblocage réel est rapporté au lieu de figer la machine. Sans la
+/// feature, `sync::Mutex` est `spin::Mutex` et rien n'est compilé de plus.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use crate::memory::kasan::{capture_site, Site};
+
+/// Classes distinctes suivies
+pub const MAX_CLASSES: usize = 128;
+/// Verrous tenus simultanément par un CPU
+pub const MAX_HELD: usize = 32;
+
+/// Cycle découvert : l'arc `from -> to` contredit un ordre déjà observé
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Inversion {
+    pub from: &'static str,
+    pub to: &'static str,
+    /// Pile de l'acquisition qui ferme le cycle
+    pub site: Site,
+    /// Chemin déjà observé de `to` vers `from`, avec la pile de chaque arc
+    pub path: Vec<(&'static str, &'static str, Site)>,
+}
+
+/// Graphe des ordres de verrouillage observés
+pub struct Graph {
+    names: Vec<&'static str>,
+    /// Arc (avant, après) -> pile de la première observation
+    edges: BTreeMap<(usize, usize), Site>,
+}
+
+impl Graph {
+    pub const fn new() -> Self {
+        Self { names: Vec::new(), edges: BTreeMap::new() }
+    }
+
+    /// Identifiant de la classe `name` (créée au besoin) ; None si la
+    /// table est pleine
+    pub fn class(&mut self, name: &'static str) -> Option<usize> {
+        if let Some(id) = self.names.iter().position(|&n| n == name) {
+            return Some(id);
+        }
+        if self.names.len() >= MAX_CLASSES {
+            return None;
+        }
+        self.names.push(name);
+        Some(self.names.len() - 1)
+    }
+
+    pub fn name(&self, class: usize) -> &'static str {
+        self.names.get(class).copied().unwrap_or("?")
+    }
+
+    /// Chemin d'arcs de `from` à `to`, s'il existe (parcours en profondeur)
+    fn path(&self, from: usize, to: usize) -> Option<Vec<(usize, usize)>> {
+        let mut visited = [false; MAX_CLASSES];
+        let mut stack: Vec<(usize, Vec<(usize, usize)>)> = Vec::new();
+        stack.push((from, Vec::new()));
+        while let Some((node, path)) = stack.pop() {
+            if node == to {
+                return Some(path);
+            }
+            if core::mem::replace(&mut visited[node], true) {
+                continue;
+            }
+            for &(a, b) in self.edges.keys().filter(|(a, _)| *a == node) {
+                let mut next = path.clone();
+                next.push((a, b));
+                stack.push((b, next));
+            }
+        }
+        None
+    }
+
+    /// Enregistre l'arc `from -> to` ; Err s'il ferme un cycle
+    pub fn add(&mut self, from: usize, to: usize, site: Site) -> Result<(), Inversion> {
+        // Même classe (deux threads, deux processus) : imbrication non suivie
+        if from == to || self.edges.contains_key(&(from, to)) {
+            return Ok(());
+        }
+        if let Some(path) = self.path(to, from) {
+            return Err(Inversion {
+                from: self.name(from),
+                to: self.name(to),
+                site,
+                path: path.iter().map(|&(a, b)| (self.name(a), self.name(b), self.edges[&(a, b)])).collect(),
+            });
+        }
+        self.edges.insert((from, to), site);
+        Ok(())
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+}
+
+#[cfg(feature = "lockdep")]
+pub use tracked::{Mutex, MutexGuard};
+
+#[cfg(feature = "lockdep")]
+mod tracked {
+    use super::*;
+    use core::fmt;
+    use core::ops::{Deref, DerefMut};
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use x86_64::instructions::interrupts::without_interrupts;
+
+    const NR_CPUS: usize = crate::percpu::NR_CPUS;
+    /// Classe pas encore attribuée (les classes sont stockées + 1)
+    const NO_CLASS: usize = 0;
+
+    /// Classes tenues par un CPU, dans l'ordre d'acquisition
+    #[derive(Clone, Copy)]
+    struct Held {
+        classes: [usize; MAX_HELD],
+        len: usize,
+    }
+
+    const NO_HELD: spin::Mutex<Held> = spin::Mutex::new(Held { classes: [0; MAX_HELD], len: 0 });
+
+    static GRAPH: spin::Mutex<Graph> = spin::Mutex::new(Graph::new());
+    static HELD: [spin::Mutex<Held>; NR_CPUS] = [NO_HELD; NR_CPUS];
+
+    fn held() -> &'static spin::Mutex<Held> {
+        &HELD[crate::scheduler::current_cpu() as usize % NR_CPUS]
+    }
+
+    /// Déclare l'acquisition de `class` ; `check` : vérifie l'ordre
+    /// (pas pour try_lock, qui ne peut pas bloquer)
+    fn acquire(class: usize, check: bool) {
+        let inversion = without_interrupts(|| {
+            let mut held = held().lock();
+            let mut inversion = None;
+            if check {
+                let site = capture_site();
+                let mut graph = GRAPH.lock();
+                for &from in &held.classes[..held.len] {
+                    if let Err(e) = graph.add(from, class, site) {
+                        inversion = Some(e);
+                        break;
+                    }
+                }
+            }
+            if held.len < MAX_HELD {
+                let len = held.len;
+                held.classes[len] = class;
+                held.len += 1;
+            }
+            inversion
+        });
+        // Verrous de lockdep relâchés : le panic handler peut verrouiller
+        if let Some(e) = inversion {
+            panic!(
+                "lockdep: ordre de verrouillage inversé\n  {} -> {} pris ici : {:?}\n  alors que l'ordre inverse a été observé : {:?}",
+                e.from, e.to, e.site, e.path
+            );
+        }
+    }
+
+    fn release(class: usize) {
+        without_interrupts(|| {
+            let mut held = held().lock();
+            let len = held.len;
+            if let Some(pos) = held.classes[..len].iter().rposition(|&c| c == class) {
+                held.classes.copy_within(pos + 1..len, pos);
+                held.len -= 1;
+            }
+        });
+    }
+
+    /// `spin::Mutex` suivi par lockdep (même interface)
+    pub struct Mutex<T: ?Sized> {
+        class: AtomicUsize,
+        inner: spin::Mutex<T>,
+    }
+
+    pub struct MutexGuard<'a, T: ?Sized> {
+        class: usize,
+        guard: spin::MutexGuard<'a, T>,
+    }
+
+    impl<T> Mutex<T> {
+        pub const fn new(value: T) -> Self {
+            Self { class: AtomicUsize::new(NO_CLASS), inner: spin::Mutex::new(value) }
+        }
+
+        pub fn into_inner(self) -> T {
+            self.inner.into_inner()
+        }
+    }
+
+    impl<T: ?Sized> Mutex<T> {
+        /// Classe du verrou : celle du type protégé
+        fn class(&self) -> Option<usize> {
+            match self.class.load(Ordering::Relaxed) {
+                NO_CLASS => {
+                    let class = without_interrupts(|| GRAPH.lock().class(core::any::type_name::<T>()))?;
+                    self.class.store(class + 1, Ordering::Relaxed);
+                    Some(class)
+                }
+                class => Some(class - 1),
+            }
+        }
+
+        pub fn lock(&self) -> MutexGuard<'_, T> {
+            let class = self.class();
+            if let Some(class) = class {
+                acquire(class, true);
+            }
+            MutexGuard { class: class.map_or(NO_CLASS, |c| c + 1), guard: self.inner.lock() }
+        }
+
+        pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+            let guard = self.inner.try_lock()?;
+            let class = self.class();
+            if let Some(class) = class {
+                acquire(class, false);
+            }
+            Some(MutexGuard { class: class.map_or(NO_CLASS, |c| c + 1), guard })
+        }
+
+        pub fn is_locked(&self) -> bool {
+            self.inner.is_locked()
+        }
+
+        pub fn get_mut(&mut self) -> &mut T {
+            self.inner.get_mut()
+        }
+    }
+
+    impl<T: ?Sized + Default> Default for Mutex<T> {
+        fn default() -> Self {
+            Self::new(T::default())
+        }
+    }
+
+    impl<T: ?Sized + fmt::Debug> fmt::Debug for Mutex<T> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            self.inner.fmt(f)
+        }
+    }
+
+    impl<T: ?Sized> Deref for MutexGuard<'_, T> {
+        type Target = T;
+
+        fn deref(&self) -> &T {
+            &self.guard
+        }
+    }
+
+    impl<T: ?Sized> DerefMut for MutexGuard<'_, T> {
+        fn deref_mut(&mut self) -> &mut T {
+            &mut self.guard
+        }
+    }
+
+    impl<T: ?Sized> Drop for MutexGuard<'_, T> {
+        fn drop(&mut self) {
+            if self.class != NO_CLASS {
+                release(self.class - 1);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_inversion_detected() {
+        let mut graph = Graph::new();
+        let process = graph.class("Process").unwrap();
+        let thread = graph.class("Thread").unwrap();
+        let sched = graph.class("CFSScheduler").unwrap();
+        assert_eq!(graph.class("Thread"), Some(thread));
+
+        let site = Site([0x1000, 0, 0, 0]);
+        assert!(graph.add(process, thread, site).is_ok());
+        assert!(graph.add(thread, sched, site).is_ok());
+        // Même classe : ignoré
+        assert!(graph.add(thread, thread, site).is_ok());
+        assert_eq!(graph.edge_count(), 2);
+
+        // scheduler -> process ferme le cycle process -> thread -> scheduler
+        let e = graph.add(sched, process, Site([0x2000, 0, 0, 0])).unwrap_err();
+        assert_eq!((e.from, e.to), ("CFSScheduler", "Process"));
+        assert_eq!(e.path.len(), 2);
+        assert_eq!(e.path[0].2, site);
+        assert_eq!(graph.edge_count(), 2);
+    }
+}
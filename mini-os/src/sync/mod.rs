@@ -1,4 +1,17 @@
-use spin::Mutex;
+pub mod irqsave;
+pub mod lockdep;
+pub mod rcu;
+pub mod seqlock;
+pub use irqsave::{SpinLockIrqSave, SpinLockIrqSaveGuard};
+pub use rcu::{Rcu, RcuReadGuard};
+pub use seqlock::SeqLock;
+
+/// Verrou du noyau : suivi par lockdep avec la feature du même nom
+#[cfg(feature = "lockdep")]
+pub use lockdep::{Mutex, MutexGuard};
+#[cfg(not(feature = "lockdep"))]
+pub use spin::{Mutex, MutexGuard};
+
 use alloc::collections::VecDeque;
 use alloc::sync::Arc;
 use crate::process::ThreadState;
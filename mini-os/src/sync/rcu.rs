@@ -0,0 +1,149 @@
+/// Publication de type RCU pour les structures lues bien plus qu'écrites
+///
+/// Les lecteurs ne prennent aucun verrou : `read()` s'inscrit dans l'époque
+/// courante puis suit le pointeur publié. Un écrivain construit une
+/// nouvelle version (copie-mise à jour), la publie d'un échange atomique,
+/// bascule l'époque et attend que les lecteurs de l'ancienne époque aient
+/// fini (période de grâce) avant de libérer l'ancienne version.
+///
+/// Une section de lecture masque les interruptions, comme rcu_read_lock
+/// désactive la préemption : elle doit rester courte et ne jamais dormir.
+/// Un écrivain ne doit pas publier depuis sa propre section de lecture.
+
+use alloc::boxed::Box;
+use core::ops::Deref;
+use core::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+use spin::Mutex;
+use x86_64::instructions::interrupts;
+
+pub struct Rcu<T> {
+    /// Version publiée
+    ptr: AtomicPtr<T>,
+    /// Époque courante (0 ou 1)
+    epoch: AtomicUsize,
+    /// Lecteurs inscrits dans chaque époque
+    readers: [AtomicUsize; 2],
+    /// Sérialise les écrivains
+    writer: Mutex<()>,
+}
+
+unsafe impl<T: Send + Sync> Send for Rcu<T> {}
+unsafe impl<T: Send + Sync> Sync for Rcu<T> {}
+
+/// Section de lecture : la version lue reste valide jusqu'à sa fin
+pub struct RcuReadGuard<'a, T> {
+    rcu: &'a Rcu<T>,
+    epoch: usize,
+    value: &'a T,
+    /// Interruptions à rétablir en sortie de section
+    irq_enabled: bool,
+}
+
+impl<T> Rcu<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            ptr: AtomicPtr::new(Box::into_raw(Box::new(value))),
+            epoch: AtomicUsize::new(0),
+            readers: [AtomicUsize::new(0), AtomicUsize::new(0)],
+            writer: Mutex::new(()),
+        }
+    }
+
+    /// Ouvre une section de lecture sur la version publiée
+    pub fn read(&self) -> RcuReadGuard<'_, T> {
+        let irq_enabled = interrupts::are_enabled();
+        interrupts::disable();
+        // Inscription dans l'époque courante ; si elle bascule entre-temps,
+        // l'écrivain a pu ne pas nous voir : on se réinscrit
+        let epoch = loop {
+            let epoch = self.epoch.load(Ordering::SeqCst);
+            self.readers[epoch].fetch_add(1, Ordering::SeqCst);
+            if self.epoch.load(Ordering::SeqCst) == epoch {
+                break epoch;
+            }
+            self.readers[epoch].fetch_sub(1, Ordering::SeqCst);
+        };
+        let value = unsafe { &*self.ptr.load(Ordering::SeqCst) };
+        RcuReadGuard { rcu: self, epoch, value, irq_enabled }
+    }
+
+    /// Publie une nouvelle version calculée à partir de la courante
+    pub fn update(&self, f: impl FnOnce(&T) -> T) {
+        interrupts::without_interrupts(|| {
+            let _guard = self.writer.lock();
+            // Sous le verrou d'écriture, la version publiée est stable
+            let new = Box::into_raw(Box::new(f(unsafe { &*self.ptr.load(Ordering::SeqCst) })));
+            self.publish(new);
+        })
+    }
+
+    /// Publie `value` à la place de la version courante
+    pub fn replace(&self, value: T) {
+        let new = Box::into_raw(Box::new(value));
+        interrupts::without_interrupts(|| {
+            let _guard = self.writer.lock();
+            self.publish(new);
+        })
+    }
+
+    /// Échange, période de grâce, puis libération de l'ancienne version
+    fn publish(&self, new: *mut T) {
+        let old = self.ptr.swap(new, Ordering::SeqCst);
+        let epoch = self.epoch.load(Ordering::SeqCst);
+        self.epoch.store(epoch ^ 1, Ordering::SeqCst);
+        // Les lecteurs arrivés après la bascule voient la nouvelle version
+        while self.readers[epoch].load(Ordering::SeqCst) != 0 {
+            core::hint::spin_loop();
+        }
+        drop(unsafe { Box::from_raw(old) });
+    }
+}
+
+impl<T> Drop for Rcu<T> {
+    fn drop(&mut self) {
+        drop(unsafe { Box::from_raw(*self.ptr.get_mut()) });
+    }
+}
+
+impl<T> Deref for RcuReadGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
+
+impl<T> Drop for RcuReadGuard<'_, T> {
+    fn drop(&mut self) {
+        self.rcu.readers[self.epoch].fetch_sub(1, Ordering::SeqCst);
+        if self.irq_enabled {
+            interrupts::enable();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    #[test_case]
+    fn test_rcu_update() {
+        let list: Rcu<Vec<u32>> = Rcu::new(vec![1, 2]);
+        {
+            let old = list.read();
+            assert_eq!(*old, vec![1, 2]);
+        }
+        list.update(|v| {
+            let mut v = v.clone();
+            v.push(3);
+            v
+        });
+        assert_eq!(list.read().len(), 3);
+        list.replace(Vec::new());
+        assert!(list.read().is_empty());
+        // Les sections de lecture sont closes : aucune inscription restante
+        assert_eq!(list.readers[0].load(Ordering::SeqCst) + list.readers[1].load(Ordering::SeqCst), 0);
+    }
+}
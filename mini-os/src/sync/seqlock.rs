@@ -0,0 +1,82 @@
+/// Verrou séquentiel (seqlock) pour petites données lues très souvent
+///
+/// L'écrivain incrémente le compteur de séquence avant et après sa
+/// modification (impair : écriture en cours). Le lecteur ne prend aucun
+/// verrou : il copie la donnée et recommence si la séquence a changé
+/// entre-temps. Réservé aux types `Copy` : une lecture déchirée est
+/// jetée, jamais observée.
+///
+/// L'écriture se fait interruptions masquées : un lecteur en interruption
+/// ne peut pas tourner indéfiniment sur une écriture interrompue.
+
+use core::cell::UnsafeCell;
+use core::sync::atomic::{fence, AtomicUsize, Ordering};
+use spin::Mutex;
+use x86_64::instructions::interrupts::without_interrupts;
+
+pub struct SeqLock<T> {
+    seq: AtomicUsize,
+    data: UnsafeCell<T>,
+    /// Sérialise les écrivains
+    writer: Mutex<()>,
+}
+
+unsafe impl<T: Copy + Send> Send for SeqLock<T> {}
+unsafe impl<T: Copy + Send> Sync for SeqLock<T> {}
+
+impl<T: Copy> SeqLock<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            seq: AtomicUsize::new(0),
+            data: UnsafeCell::new(value),
+            writer: Mutex::new(()),
+        }
+    }
+
+    /// Copie cohérente de la donnée, sans verrou
+    pub fn read(&self) -> T {
+        loop {
+            let start = self.seq.load(Ordering::Acquire);
+            if start & 1 != 0 {
+                core::hint::spin_loop();
+                continue;
+            }
+            let value = unsafe { core::ptr::read_volatile(self.data.get()) };
+            fence(Ordering::Acquire);
+            if self.seq.load(Ordering::Relaxed) == start {
+                return value;
+            }
+        }
+    }
+
+    /// Modifie la donnée ; les lecteurs concurrents recommenceront
+    pub fn write<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        without_interrupts(|| {
+            let _guard = self.writer.lock();
+            self.seq.fetch_add(1, Ordering::Relaxed);
+            fence(Ordering::Release);
+            let result = f(unsafe { &mut *self.data.get() });
+            self.seq.fetch_add(1, Ordering::Release);
+            result
+        })
+    }
+
+    /// Nombre d'écritures effectuées
+    pub fn writes(&self) -> usize {
+        self.seq.load(Ordering::Relaxed) / 2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_seqlock() {
+        let lock = SeqLock::new((1u64, 2u64));
+        assert_eq!(lock.read(), (1, 2));
+        lock.write(|pair| *pair = (3, 4));
+        assert_eq!(lock.read(), (3, 4));
+        assert_eq!(lock.writes(), 1);
+    }
+}
@@ -0,0 +1,150 @@
+/// Entrée des appels système par SYSCALL/SYSRET
+///
+/// Convention (celle de Linux) : numéro dans rax, arguments dans rdi,
+/// rsi, rdx, r10, r8, r9 ; résultat dans rax, erreur en négatif (-errno).
+/// rcx et r11 sont écrasés par le processeur, les autres registres sont
+/// préservés.
+///
+/// Le stub prend le GS du noyau (`swapgs`), bascule sur la pile noyau du
+/// CPU (`gdt::CpuSyscallArea`), construit un `SyscallFrame` et appelle
+/// `syscall_dispatch` ; il rend son GS au programme juste avant SYSRET.
+
+use core::arch::global_asm;
+use x86_64::registers::model_specific::{Efer, EferFlags, LStar, SFMask, Star};
+use x86_64::registers::rflags::RFlags;
+use x86_64::VirtAddr;
+use super::{SyscallHandler, SyscallNumber};
+use crate::gdt::SELECTORS;
+
+/// Registres sauvegardés par le stub d'entrée (ordre de la pile)
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct SyscallFrame {
+    pub rax: u64,
+    pub rdi: u64,
+    pub rsi: u64,
+    pub rdx: u64,
+    pub r10: u64,
+    pub r8: u64,
+    pub r9: u64,
+    pub r15: u64,
+    pub r14: u64,
+    pub r13: u64,
+    pub r12: u64,
+    pub rbx: u64,
+    pub rbp: u64,
+    /// RFLAGS utilisateur (r11)
+    pub rflags: u64,
+    /// RIP de retour (rcx)
+    pub rip: u64,
+    /// RSP utilisateur
+    pub rsp: u64,
+}
+
+global_asm!(
+    ".global syscall_entry",
+    "syscall_entry:",
+    // GS du noyau jusqu'au retour : zone du CPU, puis sa zone SYSCALL
+    "    swapgs",
+    "    mov gs:[24], rsp",
+    "    mov rsp, gs:[16]",
+    "    mov rsp, [rsp]",
+    "    push qword ptr gs:[24]",
+    "    push rcx",
+    "    push r11",
+    "    push rbp", "    push rbx", "    push r12", "    push r13",
+    "    push r14", "    push r15",
+    "    push r9", "    push r8", "    push r10", "    push rdx",
+    "    push rsi", "    push rdi", "    push rax",
+    // 16 mots empilés : la pile reste alignée sur 16 octets
+    "    mov rdi, rsp",
+    "    cld",
+    "    call syscall_dispatch",
+    // Plus d'interruption avec le GS du programme en mode noyau ; SYSRET
+    // rétablit RFLAGS depuis r11
+    "    cli",
+    "    add rsp, 8",
+    "    pop rdi", "    pop rsi", "    pop rdx", "    pop r10",
+    "    pop r8", "    pop r9",
+    "    pop r15", "    pop r14", "    pop r13", "    pop r12",
+    "    pop rbx", "    pop rbp",
+    "    pop r11",
+    "    pop rcx",
+    "    pop rsp",
+    "    swapgs",
+    "    sysretq",
+);
+
+extern "C" {
+    fn syscall_entry();
+}
+
+/// Programme les MSR SYSCALL du BSP
+pub fn init() {
+    init_cpu();
+}
+
+/// Programme les MSR SYSCALL du CPU courant (après `gdt::init_cpu`)
+pub fn init_cpu() {
+    unsafe {
+        Efer::update(|flags| flags.insert(EferFlags::SYSTEM_CALL_EXTENSIONS));
+    }
+    Star::write(
+        SELECTORS.user_code,
+        SELECTORS.user_data,
+        SELECTORS.kernel_code,
+        SELECTORS.kernel_data,
+    )
+    .expect("disposition de la GDT incompatible avec SYSRET");
+    LStar::write(VirtAddr::new(syscall_entry as usize as u64));
+    // Interruptions masquées tant que le stub est sur la pile de transition ;
+    // AC masqué : l'appelant ne peut pas lever SMAP à la place du noyau
+    SFMask::write(RFlags::INTERRUPT_FLAG | RFlags::DIRECTION_FLAG | RFlags::TRAP_FLAG | RFlags::ALIGNMENT_CHECK);
+}
+
+/// Appelé par le stub : retourne la valeur à placer dans rax
+#[no_mangle]
+extern "C" fn syscall_dispatch(frame: &mut SyscallFrame) -> u64 {
+    crate::percpu::count(crate::percpu::Stat::Syscall);
+    let args = [frame.rdi, frame.rsi, frame.rdx, frame.r10, frame.r8, frame.r9];
+    crate::trace!(SysEnter, frame.rax, frame.rdi);
+    let start = crate::time::now_ns();
+    let result = SyscallHandler::new().handle(frame.rax, &args).to_raw();
+    crate::perf::record_syscall(frame.rax, crate::time::now_ns().saturating_sub(start));
+    crate::trace!(SysExit, frame.rax, result);
+
+    // exit() : le processus est terminé, retour au lanceur
+    if frame.rax == SyscallNumber::Exit as u64 {
+        crate::ring3::return_to_launcher(frame.rdi as i32);
+    }
+    // exec réussi : reprise sur l'entrée et la pile de la nouvelle image
+    if frame.rax == SyscallNumber::Exec as u64 || frame.rax == SyscallNumber::Execve as u64 {
+        if let Some((rip, rsp)) = crate::ring3::take_exec_entry() {
+            frame.rip = rip;
+            frame.rsp = rsp;
+        }
+    }
+    // SYSRET vers une adresse non canonique fauterait en ring 0
+    if VirtAddr::try_new(frame.rip).is_err() {
+        crate::ring3::return_to_launcher(-11);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_syscall_frame_layout() {
+        // 16 registres empilés par le stub
+        assert_eq!(core::mem::size_of::<SyscallFrame>(), 16 * 8);
+        let frame = SyscallFrame {
+            rax: 0, rdi: 0, rsi: 0, rdx: 0, r10: 0, r8: 0, r9: 0, r15: 0, r14: 0,
+            r13: 0, r12: 0, rbx: 0, rbp: 0, rflags: 0, rip: 0, rsp: 0,
+        };
+        let base = &frame as *const _ as usize;
+        assert_eq!(&frame.rflags as *const _ as usize - base, 13 * 8);
+        assert_eq!(&frame.rsp as *const _ as usize - base, 15 * 8);
+    }
+}
@@ -1,5 +1,12 @@
 
 
+pub mod entry;
+
+use crate::process::cred::{Capabilities, Credentials, ALL_CAPS, CAP_SYS_ADMIN, CAP_SYS_NICE};
+use crate::memory::uaccess::{copy_from_user, copy_to_user, read_user, read_user_cstr, read_user_slice, write_user, write_user_slice, Fault};
+use crate::process::rlimit::{RLimit, Resource};
+use x86_64::instructions::interrupts::without_interrupts;
+
 /// Numéros des appels système
 #[repr(u64)]
 pub enum SyscallNumber {
@@ -35,6 +42,181 @@ pub enum SyscallNumber {
     Chgrp = 25,
     // Gestion des threads
     ThreadCreate = 26,
+    // Ordonnancement
+    SchedYield = 27,
+    GetCpu = 28,
+    // Opérations de nommage (atomiques, voir fs::vfs_namei)
+    Unlink = 29,
+    Mkdir = 30,
+    Rename = 31,
+    Mprotect = 32,
+    // Tas utilisateur
+    Brk = 33,
+    Sbrk = 34,
+    // Alimentation
+    Reboot = 35,
+    // Horloge
+    GetTimeOfDay = 36,
+    ClockGetTime = 37,
+    // Limites de ressources
+    Getrlimit = 38,
+    Setrlimit = 39,
+    // Identités
+    Getuid = 40,
+    Geteuid = 41,
+    Setuid = 42,
+    Getgid = 43,
+    Getegid = 44,
+    Setgid = 45,
+    // Lecture de répertoire
+    Readdir = 46,
+    // Files de messages POSIX
+    MqOpen = 47,
+    MqSend = 48,
+    MqReceive = 49,
+    MqClose = 50,
+    MqUnlink = 51,
+    MqNotify = 52,
+    // Multiplexage des descripteurs
+    Poll = 53,
+    // Tubes nommés
+    Mkfifo = 54,
+    // Occupation des systèmes de fichiers
+    Statfs = 55,
+    // Liens physiques (la suppression est Unlink)
+    Link = 56,
+    // Attributs étendus
+    Setxattr = 57,
+    Getxattr = 58,
+    Listxattr = 59,
+    Removexattr = 60,
+    // Exécution avec arguments et environnement
+    Execve = 61,
+    // Générateur aléatoire du noyau
+    GetRandom = 62,
+    // Filtrage des appels système
+    Seccomp = 63,
+    // Abandon de capacités
+    CapDrop = 64,
+    // Mise en place du TLS par le runtime
+    SetFsBase = 65,
+    SetTidAddress = 66,
+}
+
+/// Nombre d'appels système (numéros 0..NR_SYSCALLS)
+pub const NR_SYSCALLS: usize = 67;
+
+/// Noms des appels système, indexés par numéro
+const SYSCALL_NAMES: [&str; NR_SYSCALLS] = [
+    "exit", "fork", "read", "write", "open", "close", "exec", "wait", "getpid", "setpriority",
+    "getpriority", "signal", "kill", "sigaction", "sigprocmask", "shmget", "shmat", "shmdt",
+    "shmctl", "mmap", "munmap", "symlink", "readlink", "chmod", "chown", "chgrp", "thread_create",
+    "sched_yield", "getcpu", "unlink", "mkdir", "rename", "mprotect", "brk", "sbrk", "reboot",
+    "gettimeofday", "clock_gettime", "getrlimit", "setrlimit", "getuid", "geteuid", "setuid",
+    "getgid", "getegid", "setgid", "readdir", "mq_open", "mq_send", "mq_receive", "mq_close",
+    "mq_unlink", "mq_notify", "poll", "mkfifo", "statfs", "link", "setxattr", "getxattr",
+    "listxattr", "removexattr", "execve", "getrandom", "seccomp", "capdrop",
+    "set_fs_base", "set_tid_address",
+];
+
+/// Taille des morceaux copiés par getrandom()
+const GETRANDOM_CHUNK: usize = 256;
+
+/// Nom d'un appel système (statistiques, traces)
+pub fn syscall_name(num: u64) -> Option<&'static str> {
+    SYSCALL_NAMES.get(num as usize).copied()
+}
+
+/// Drapeaux de open()
+pub mod open_flags {
+    pub const O_CREAT: i32 = 0o100;
+    pub const O_EXCL: i32 = 0o200;
+    pub const O_NONBLOCK: i32 = 0o4000;
+}
+
+/// Options de wait()
+pub mod wait_flags {
+    /// Ne pas bloquer si aucun enfant n'est terminé
+    pub const WNOHANG: u64 = 1;
+}
+
+/// Commandes de reboot() (valeurs de Linux)
+pub mod reboot_cmd {
+    pub const RESTART: u64 = 0x0123_4567;
+    pub const POWER_OFF: u64 = 0x4321_FEDC;
+}
+
+/// Horloges de clock_gettime() (valeurs de Linux)
+pub mod clock_id {
+    pub const CLOCK_REALTIME: u64 = 0;
+    pub const CLOCK_MONOTONIC: u64 = 1;
+}
+
+/// `struct timeval` de gettimeofday()
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TimeVal {
+    pub tv_sec: i64,
+    pub tv_usec: i64,
+}
+
+/// `struct timespec` de clock_gettime()
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TimeSpec {
+    pub tv_sec: i64,
+    pub tv_nsec: i64,
+}
+
+/// `struct statfs` de statfs() (disposition de Linux x86_64)
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StatFsBuf {
+    pub f_type: i64,
+    pub f_bsize: i64,
+    pub f_blocks: u64,
+    pub f_bfree: u64,
+    pub f_bavail: u64,
+    pub f_files: u64,
+    pub f_ffree: u64,
+    pub f_fsid: [i32; 2],
+    pub f_namelen: i64,
+    pub f_frsize: i64,
+    pub f_flags: i64,
+    pub f_spare: [i64; 4],
+}
+
+impl StatFsBuf {
+    /// Signatures `f_type` de Linux (0 si le système de fichiers n'en a pas)
+    fn magic(fs_name: &str) -> i64 {
+        match fs_name {
+            "ramfs" => 0x8584_58F6,
+            "proc" => 0x9FA0,
+            "devfs" => 0x1373,
+            "vfat" => 0x4D44,
+            "ext2" | "ext4" => 0xEF53,
+            _ => 0,
+        }
+    }
+
+    /// ST_RDONLY
+    const ST_RDONLY: i64 = 1;
+
+    fn from_statfs(stat: &crate::fs::StatFs) -> Self {
+        Self {
+            f_type: Self::magic(&stat.fs_name),
+            f_bsize: stat.block_size as i64,
+            f_blocks: stat.total_blocks,
+            f_bfree: stat.free_blocks,
+            f_bavail: stat.free_blocks,
+            f_files: stat.total_inodes,
+            f_ffree: stat.free_inodes,
+            f_namelen: 255,
+            f_frsize: stat.block_size as i64,
+            f_flags: if stat.readonly { Self::ST_RDONLY } else { 0 },
+            ..Self::default()
+        }
+    }
 }
 
 /// Résultat d'un appel système
@@ -44,6 +226,16 @@ pub enum SyscallResult {
     Error(SyscallError),
 }
 
+impl SyscallResult {
+    /// Valeur rendue dans rax au ring 3 : le résultat, ou -errno
+    pub fn to_raw(&self) -> u64 {
+        match self {
+            SyscallResult::Success(value) => *value,
+            SyscallResult::Error(err) => (-(err.errno() as i64)) as u64,
+        }
+    }
+}
+
 /// Erreurs d'appel système
 #[derive(Debug)]
 pub enum SyscallError {
@@ -55,6 +247,123 @@ pub enum SyscallError {
     IoError,
     OutOfMemory,
     NotSupported,
+    AlreadyExists,
+    TooManyFiles,
+    WouldBlock,
+    NotPermitted,
+    BadDescriptor,
+    Busy,
+    MessageTooLarge,
+    BrokenPipe,
+    NoSuchDevice,
+    NoData,
+    OutOfRange,
+    NoSpace,
+    ArgListTooLong,
+    ExecFormat,
+    NoChildren,
+    BadAddress,
+}
+
+impl SyscallError {
+    /// Code errno (valeurs Linux) correspondant
+    pub fn errno(&self) -> u32 {
+        match self {
+            SyscallError::InvalidSyscall => 38,   // ENOSYS
+            SyscallError::InvalidArgument => 22,  // EINVAL
+            SyscallError::NoSuchProcess => 3,     // ESRCH
+            SyscallError::NotFound => 2,          // ENOENT
+            SyscallError::PermissionDenied => 13, // EACCES
+            SyscallError::IoError => 5,           // EIO
+            SyscallError::OutOfMemory => 12,      // ENOMEM
+            SyscallError::NotSupported => 95,     // EOPNOTSUPP
+            SyscallError::AlreadyExists => 17,    // EEXIST
+            SyscallError::TooManyFiles => 24,     // EMFILE
+            SyscallError::WouldBlock => 11,       // EAGAIN
+            SyscallError::NotPermitted => 1,      // EPERM
+            SyscallError::BadDescriptor => 9,     // EBADF
+            SyscallError::Busy => 16,             // EBUSY
+            SyscallError::MessageTooLarge => 90,  // EMSGSIZE
+            SyscallError::BrokenPipe => 32,       // EPIPE
+            SyscallError::NoSuchDevice => 6,      // ENXIO
+            SyscallError::NoData => 61,           // ENODATA
+            SyscallError::OutOfRange => 34,       // ERANGE
+            SyscallError::NoSpace => 28,          // ENOSPC
+            SyscallError::ArgListTooLong => 7,    // E2BIG
+            SyscallError::ExecFormat => 8,        // ENOEXEC
+            SyscallError::NoChildren => 10,       // ECHILD
+            SyscallError::BadAddress => 14,       // EFAULT
+        }
+    }
+}
+
+impl From<Fault> for SyscallError {
+    fn from(_: Fault) -> Self {
+        SyscallError::BadAddress
+    }
+}
+
+impl From<crate::memory::ShmError> for SyscallError {
+    fn from(err: crate::memory::ShmError) -> Self {
+        use crate::memory::ShmError;
+        match err {
+            ShmError::NotFound => SyscallError::NotFound,
+            ShmError::AlreadyExists => SyscallError::AlreadyExists,
+            ShmError::PermissionDenied => SyscallError::PermissionDenied,
+            ShmError::OutOfMemory | ShmError::TooManySegments => SyscallError::OutOfMemory,
+            ShmError::InvalidArgument => SyscallError::InvalidArgument,
+        }
+    }
+}
+
+impl From<crate::ipc::MqError> for SyscallError {
+    fn from(err: crate::ipc::MqError) -> Self {
+        use crate::ipc::MqError;
+        match err {
+            MqError::NotFound => SyscallError::NotFound,
+            MqError::QueueFull | MqError::WouldBlock => SyscallError::WouldBlock,
+            MqError::MessageTooLarge => SyscallError::MessageTooLarge,
+            MqError::AlreadyExists => SyscallError::AlreadyExists,
+            MqError::InvalidArgument => SyscallError::InvalidArgument,
+            MqError::BadDescriptor => SyscallError::BadDescriptor,
+            MqError::Busy => SyscallError::Busy,
+        }
+    }
+}
+
+impl From<crate::ipc::pipe::PipeError> for SyscallError {
+    fn from(err: crate::ipc::pipe::PipeError) -> Self {
+        use crate::ipc::pipe::PipeError;
+        match err {
+            PipeError::NotFound => SyscallError::BadDescriptor,
+            PipeError::BrokenPipe => SyscallError::BrokenPipe,
+            PipeError::WouldBlock => SyscallError::WouldBlock,
+            PipeError::AlreadyExists => SyscallError::AlreadyExists,
+            PipeError::NoReader => SyscallError::NoSuchDevice,
+        }
+    }
+}
+
+impl From<crate::fs::VfsError> for SyscallError {
+    fn from(err: crate::fs::VfsError) -> Self {
+        use crate::fs::VfsError;
+        match err {
+            VfsError::NotFound => SyscallError::NotFound,
+            VfsError::AlreadyExists => SyscallError::AlreadyExists,
+            VfsError::PermissionDenied | VfsError::ReadOnly => SyscallError::PermissionDenied,
+            VfsError::NotSupported => SyscallError::NotSupported,
+            VfsError::NoSpace => SyscallError::NoSpace,
+            VfsError::IoError => SyscallError::IoError,
+            VfsError::NoAttribute => SyscallError::NoData,
+            VfsError::RangeTooSmall => SyscallError::OutOfRange,
+            _ => SyscallError::InvalidArgument,
+        }
+    }
+}
+
+/// Identités du processus courant ; le noyau (hors processus) est root
+fn current_credentials() -> Credentials {
+    crate::process::cred::current()
 }
 
 /// Gestionnaire d'appels système
@@ -68,11 +377,14 @@ impl SyscallHandler {
     
     /// Traite un appel système
     pub fn handle(&self, num: u64, args: &[u64]) -> SyscallResult {
+        if !crate::process::seccomp::check_current(num) {
+            return SyscallResult::Error(SyscallError::NotPermitted);
+        }
         match num {
             x if x == SyscallNumber::Exit as u64 => self.handle_exit(args[0] as i32),
             x if x == SyscallNumber::Fork as u64 => self.handle_fork(),
             x if x == SyscallNumber::Exec as u64 => self.handle_exec(args[0] as *const u8),
-            x if x == SyscallNumber::Wait as u64 => self.handle_wait(args[0] as i64),
+            x if x == SyscallNumber::Wait as u64 => self.handle_wait(args[0] as i64, args[1] as *mut i32, args[2]),
             x if x == SyscallNumber::Read as u64 => self.handle_read(args[0] as usize, args[1] as *mut u8, args[2] as usize),
             x if x == SyscallNumber::Write as u64 => self.handle_write(args[0] as usize, args[1] as *const u8, args[2] as usize),
             x if x == SyscallNumber::Open as u64 => self.handle_open(args[0] as *const u8, args[1] as i32),
@@ -85,17 +397,57 @@ impl SyscallHandler {
             x if x == SyscallNumber::SigAction as u64 => self.handle_sigaction(args[0] as u8, args[1], args[2]),
             x if x == SyscallNumber::SigProcMask as u64 => self.handle_sigprocmask(args[0] as i32, args[1], args[2]),
             x if x == SyscallNumber::ShmGet as u64 => self.handle_shmget(args[0] as i32, args[1] as usize, args[2] as i32),
-            x if x == SyscallNumber::ShmAt as u64 => self.handle_shmat(args[0] as i32, args[1]),
+            x if x == SyscallNumber::ShmAt as u64 => self.handle_shmat(args[0] as i32, args[1], args[2] as i32),
             x if x == SyscallNumber::ShmDt as u64 => self.handle_shmdt(args[0]),
             x if x == SyscallNumber::ShmCtl as u64 => self.handle_shmctl(args[0] as i32, args[1] as i32),
             x if x == SyscallNumber::Mmap as u64 => self.handle_mmap(args[0], args[1] as usize, args[2] as i32, args[3] as i32, args[4] as i32, args[5]),
             x if x == SyscallNumber::Munmap as u64 => self.handle_munmap(args[0], args[1] as usize),
             x if x == SyscallNumber::Symlink as u64 => self.handle_symlink(args[0] as *const u8, args[1] as *const u8),
             x if x == SyscallNumber::Readlink as u64 => self.handle_readlink(args[0] as *const u8, args[1] as *mut u8, args[2] as usize),
-            x if x == SyscallNumber::Chmod as u64 => self.handle_chmod(args[0], args[1] as u16),
-            x if x == SyscallNumber::Chown as u64 => self.handle_chown(args[0], args[1] as u32),
-            x if x == SyscallNumber::Chgrp as u64 => self.handle_chgrp(args[0], args[1] as u32),
+            x if x == SyscallNumber::Chmod as u64 => self.handle_chmod(args[0] as *const u8, args[1] as u16),
+            x if x == SyscallNumber::Chown as u64 => self.handle_chown(args[0] as *const u8, args[1] as u32),
+            x if x == SyscallNumber::Chgrp as u64 => self.handle_chgrp(args[0] as *const u8, args[1] as u32),
             x if x == SyscallNumber::ThreadCreate as u64 => self.handle_thread_create(args[0]),
+            x if x == SyscallNumber::SchedYield as u64 => self.handle_sched_yield(),
+            x if x == SyscallNumber::GetCpu as u64 => self.handle_getcpu(args[0] as *mut u32, args[1] as *mut u32),
+            x if x == SyscallNumber::Unlink as u64 => self.handle_unlink(args[0] as *const u8),
+            x if x == SyscallNumber::Mkdir as u64 => self.handle_mkdir(args[0] as *const u8, args[1] as u16),
+            x if x == SyscallNumber::Rename as u64 => self.handle_rename(args[0] as *const u8, args[1] as *const u8),
+            x if x == SyscallNumber::Mprotect as u64 => self.handle_mprotect(args[0], args[1] as usize, args[2] as i32),
+            x if x == SyscallNumber::Brk as u64 => self.handle_brk(args[0]),
+            x if x == SyscallNumber::Sbrk as u64 => self.handle_sbrk(args[0] as i64),
+            x if x == SyscallNumber::Reboot as u64 => self.handle_reboot(args[0]),
+            x if x == SyscallNumber::GetTimeOfDay as u64 => self.handle_gettimeofday(args[0] as *mut TimeVal),
+            x if x == SyscallNumber::ClockGetTime as u64 => self.handle_clock_gettime(args[0], args[1] as *mut TimeSpec),
+            x if x == SyscallNumber::Getrlimit as u64 => self.handle_getrlimit(args[0], args[1] as *mut RLimit),
+            x if x == SyscallNumber::Setrlimit as u64 => self.handle_setrlimit(args[0], args[1] as *const RLimit),
+            x if x == SyscallNumber::Getuid as u64 => SyscallResult::Success(current_credentials().ruid as u64),
+            x if x == SyscallNumber::Geteuid as u64 => SyscallResult::Success(current_credentials().euid as u64),
+            x if x == SyscallNumber::Setuid as u64 => self.handle_setuid(args[0] as u32),
+            x if x == SyscallNumber::Getgid as u64 => SyscallResult::Success(current_credentials().rgid as u64),
+            x if x == SyscallNumber::Getegid as u64 => SyscallResult::Success(current_credentials().egid as u64),
+            x if x == SyscallNumber::Setgid as u64 => self.handle_setgid(args[0] as u32),
+            x if x == SyscallNumber::Readdir as u64 => self.handle_readdir(args[0] as *const u8, args[1] as *mut u8, args[2] as usize),
+            x if x == SyscallNumber::MqOpen as u64 => self.handle_mq_open(args[0] as *const u8, args[1] as i32, args[2] as *const crate::ipc::MqAttr),
+            x if x == SyscallNumber::MqSend as u64 => self.handle_mq_send(args[0] as u32, args[1] as *const u8, args[2] as usize, args[3] as u32),
+            x if x == SyscallNumber::MqReceive as u64 => self.handle_mq_receive(args[0] as u32, args[1] as *mut u8, args[2] as usize, args[3] as *mut u32),
+            x if x == SyscallNumber::MqClose as u64 => self.handle_mq_close(args[0] as u32),
+            x if x == SyscallNumber::MqUnlink as u64 => self.handle_mq_unlink(args[0] as *const u8),
+            x if x == SyscallNumber::MqNotify as u64 => self.handle_mq_notify(args[0] as u32, args[1] as u8),
+            x if x == SyscallNumber::Poll as u64 => self.handle_poll(args[0] as *mut crate::fs::poll::PollFd, args[1] as usize, args[2] as i64),
+            x if x == SyscallNumber::Mkfifo as u64 => self.handle_mkfifo(args[0] as *const u8, args[1] as u16),
+            x if x == SyscallNumber::Statfs as u64 => self.handle_statfs(args[0] as *const u8, args[1] as *mut StatFsBuf),
+            x if x == SyscallNumber::Link as u64 => self.handle_link(args[0] as *const u8, args[1] as *const u8),
+            x if x == SyscallNumber::Setxattr as u64 => self.handle_setxattr(args[0] as *const u8, args[1] as *const u8, args[2] as *const u8, args[3] as usize, args[4] as u32),
+            x if x == SyscallNumber::Getxattr as u64 => self.handle_getxattr(args[0] as *const u8, args[1] as *const u8, args[2] as *mut u8, args[3] as usize),
+            x if x == SyscallNumber::Listxattr as u64 => self.handle_listxattr(args[0] as *const u8, args[1] as *mut u8, args[2] as usize),
+            x if x == SyscallNumber::Removexattr as u64 => self.handle_removexattr(args[0] as *const u8, args[1] as *const u8),
+            x if x == SyscallNumber::Execve as u64 => self.handle_execve(args[0] as *const u8, args[1] as *const u64, args[2] as *const u64),
+            x if x == SyscallNumber::GetRandom as u64 => self.handle_getrandom(args[0] as *mut u8, args[1] as usize, args[2] as u32),
+            x if x == SyscallNumber::Seccomp as u64 => self.handle_seccomp(args[0] as *const u64, args[1] as usize, args[2] as u32),
+            x if x == SyscallNumber::CapDrop as u64 => self.handle_capdrop(args[0] as Capabilities),
+            x if x == SyscallNumber::SetFsBase as u64 => self.handle_set_fs_base(args[0]),
+            x if x == SyscallNumber::SetTidAddress as u64 => self.handle_set_tid_address(args[0]),
             _ => SyscallResult::Error(SyscallError::InvalidSyscall),
         }
     }
@@ -129,36 +481,112 @@ impl SyscallHandler {
         }
     }
 
+    /// exec(path) : execve sans arguments, argv = [path] et environnement conservé
     fn handle_exec(&self, path_ptr: *const u8) -> SyscallResult {
-        use crate::process::PROCESS_MANAGER;
-        use crate::scheduler::current_thread;
-        
         let path = match self.read_user_string(path_ptr) {
             Some(s) => s,
             None => return SyscallResult::Error(SyscallError::InvalidArgument),
         };
+        let env = match crate::process::current_process() {
+            Some(p) => p.lock().env.clone(),
+            None => return SyscallResult::Error(SyscallError::NoSuchProcess),
+        };
+        self.exec(&path, crate::process::exec::ExecArgs::new(alloc::vec![path.clone()], env))
+    }
+
+    /// Remplace l'image du processus
+    /// args[0] = chemin
+    /// args[1] = argv, tableau de chaînes terminé par NULL
+    /// args[2] = envp, idem (NULL : environnement conservé)
+    fn handle_execve(&self, path_ptr: *const u8, argv_ptr: *const u64, envp_ptr: *const u64) -> SyscallResult {
+        use crate::process::exec::ExecArgs;
+
+        let path = match self.read_user_string(path_ptr) {
+            Some(s) => s,
+            None => return SyscallResult::Error(SyscallError::InvalidArgument),
+        };
+        let argv = match self.read_user_string_array(argv_ptr) {
+            Some(argv) => argv,
+            None => return SyscallResult::Error(SyscallError::InvalidArgument),
+        };
+        let envp = if envp_ptr.is_null() {
+            match crate::process::current_process() {
+                Some(p) => p.lock().env.clone(),
+                None => return SyscallResult::Error(SyscallError::NoSuchProcess),
+            }
+        } else {
+            match self.read_user_string_array(envp_ptr) {
+                Some(envp) => envp,
+                None => return SyscallResult::Error(SyscallError::InvalidArgument),
+            }
+        };
+        self.exec(&path, ExecArgs::new(argv, envp))
+    }
+
+    /// Charge `path` à la place de l'image courante ; le retour de l'appel
+    /// reprend sur la nouvelle entrée (voir `entry::syscall_dispatch`)
+    fn exec(&self, path: &str, args: crate::process::exec::ExecArgs) -> SyscallResult {
+        use crate::process::PROCESS_MANAGER;
+        use crate::scheduler::current_thread;
         
         let tid = match current_thread() {
             Some(t) => t.lock().tid,
             None => return SyscallResult::Error(SyscallError::NoSuchProcess),
         };
         
-        match PROCESS_MANAGER.lock().exec_process(tid, &path) {
-            Ok(_) => SyscallResult::Success(0),
+        match PROCESS_MANAGER.lock().exec_process(tid, path, &args) {
+            Ok((rip, rsp)) => {
+                crate::ring3::set_exec_entry(rip, rsp);
+                SyscallResult::Success(0)
+            }
+            Err(e) if e == "Permission denied" => SyscallResult::Error(SyscallError::PermissionDenied),
+            Err(e) if e == "File not found" => SyscallResult::Error(SyscallError::NotFound),
+            Err(e) if e == "Argument list too long" => SyscallResult::Error(SyscallError::ArgListTooLong),
+            Err(e) if e == "Exec format error" => SyscallResult::Error(SyscallError::ExecFormat),
             Err(_) => SyscallResult::Error(SyscallError::IoError),
         }
     }
 
-    fn handle_wait(&self, _pid: i64) -> SyscallResult {
-        // TODO: Implémenter wait
-        SyscallResult::Error(SyscallError::NotSupported)
+    /// Attend la fin d'un enfant et le libère
+    /// args[0] = PID de l'enfant (-1 : n'importe lequel)
+    /// args[1] = `int *` recevant le code de sortie (peut être NULL)
+    /// args[2] = options (WNOHANG : renvoie 0 si aucun enfant n'est terminé)
+    /// Retour : PID de l'enfant récupéré
+    fn handle_wait(&self, pid: i64, status_ptr: *mut i32, options: u64) -> SyscallResult {
+        use crate::process::PROCESS_MANAGER;
+        use wait_flags::WNOHANG;
+
+        if options & !WNOHANG != 0 || pid == 0 || pid < -1 {
+            return SyscallResult::Error(SyscallError::InvalidArgument);
+        }
+        let target = if pid == -1 { None } else { Some(pid as u64) };
+        // Le shell noyau attend en tant qu'init, comme pour `spawn`
+        let parent = crate::process::current_process().map(|p| p.lock().pid).unwrap_or(1);
+
+        loop {
+            let reaped = PROCESS_MANAGER.lock().reap_child(parent, target);
+            match reaped {
+                Ok(Some((child, status))) => {
+                    if !status_ptr.is_null() && write_user(status_ptr, status).is_err() {
+                        return SyscallResult::Error(SyscallError::BadAddress);
+                    }
+                    return SyscallResult::Success(child);
+                }
+                Ok(None) if options & WNOHANG != 0 => return SyscallResult::Success(0),
+                Ok(None) => {}
+                Err(_) => return SyscallResult::Error(SyscallError::NoChildren),
+            }
+            // Sans interruptions, l'enfant ne sera jamais ordonnancé
+            if !x86_64::instructions::interrupts::are_enabled() {
+                return SyscallResult::Error(SyscallError::WouldBlock);
+            }
+            x86_64::instructions::hlt();
+        }
     }
     
     fn handle_read(&self, fd: usize, buf_ptr: *mut u8, count: usize) -> SyscallResult {
          use crate::process::current_process;
-         use crate::fs::{FD_MANAGER, path_lookup, Dentry};
-         use alloc::sync::Arc;
-         use spin::Mutex;
+         use crate::fs::{FD_MANAGER, vfs_fd_inode};
          
          let pid = match current_process() {
             Some(p) => p.lock().pid,
@@ -166,9 +594,13 @@ impl SyscallHandler {
          };
          
          let mut fm = FD_MANAGER.lock();
-         let (path, offset) = if let Ok(table) = fm.get_table(pid) {
+         let (offset, fifo) = if let Ok(table) = fm.get_table(pid) {
              if let Ok(desc) = table.get(fd) {
-                 (desc.path.clone(), desc.offset)
+                 // Descripteur ouvert en écriture seule
+                 if desc.mode == crate::fs::OpenMode::WriteOnly {
+                     return SyscallResult::Error(SyscallError::BadDescriptor);
+                 }
+                 (desc.offset, desc.fifo.map(|id| (id, desc.nonblock)))
              } else {
                  return SyscallResult::Error(SyscallError::InvalidArgument);
              }
@@ -177,17 +609,26 @@ impl SyscallHandler {
          };
          drop(fm);
          
-         let dentry: Arc<Mutex<Dentry>> = match path_lookup(&path) {
-             Ok(d) => d,
+         // FIFO : lecture dans le pipe, sans offset
+         if let Some((id, nonblock)) = fifo {
+             let mut temp_buf = alloc::vec![0u8; count];
+             return match crate::ipc::pipe::fifo_read(id, &mut temp_buf, nonblock) {
+                 Ok(n) => match copy_to_user(buf_ptr, &temp_buf[..n]) {
+                     Ok(()) => SyscallResult::Success(n as u64),
+                     Err(e) => SyscallResult::Error(e.into()),
+                 },
+                 Err(e) => SyscallResult::Error(e.into()),
+             };
+         }
+         
+         // L'inode ouvert, même si son dernier lien a été supprimé depuis
+         let ops = match vfs_fd_inode(pid, fd) {
+             Ok((ops, _)) => ops,
              Err(_) => return SyscallResult::Error(SyscallError::NotFound),
          };
          
-         let inode = dentry.lock().inode.clone();
-         
-         let inode = dentry.lock().inode.clone();
-         
          let mut temp_buf = alloc::vec![0u8; count];
-         let read_bytes = match inode.lock().ops.lock().read(offset, &mut temp_buf) {
+         let read_bytes = match ops.lock().read(offset, &mut temp_buf) {
              Ok(n) => n,
              Err(_) => return SyscallResult::Error(SyscallError::IoError),
          };
@@ -199,8 +640,8 @@ impl SyscallHandler {
              }
          }
          
-         unsafe {
-             core::ptr::copy_nonoverlapping(temp_buf.as_ptr(), buf_ptr, read_bytes);
+         if let Err(e) = copy_to_user(buf_ptr, &temp_buf[..read_bytes]) {
+             return SyscallResult::Error(e.into());
          }
          
          SyscallResult::Success(read_bytes as u64)
@@ -208,9 +649,7 @@ impl SyscallHandler {
     
     fn handle_write(&self, fd: usize, buf_ptr: *const u8, count: usize) -> SyscallResult {
          use crate::process::current_process;
-         use crate::fs::{FD_MANAGER, path_lookup, Dentry};
-         use alloc::sync::Arc;
-         use spin::Mutex;
+         use crate::fs::{FD_MANAGER, vfs_fd_inode};
          
          let pid = match current_process() {
             Some(p) => p.lock().pid,
@@ -218,14 +657,18 @@ impl SyscallHandler {
          };
          
          let mut temp_buf = alloc::vec![0u8; count];
-         unsafe {
-             core::ptr::copy_nonoverlapping(buf_ptr, temp_buf.as_mut_ptr(), count);
+         if let Err(e) = copy_from_user(&mut temp_buf, buf_ptr) {
+             return SyscallResult::Error(e.into());
          }
 
          let mut fm = FD_MANAGER.lock();
-         let (path, offset) = if let Ok(table) = fm.get_table(pid) {
+         let (offset, fifo) = if let Ok(table) = fm.get_table(pid) {
              if let Ok(desc) = table.get(fd) {
-                 (desc.path.clone(), desc.offset)
+                 // Descripteur ouvert en lecture seule
+                 if desc.mode == crate::fs::OpenMode::ReadOnly {
+                     return SyscallResult::Error(SyscallError::BadDescriptor);
+                 }
+                 (desc.offset, desc.fifo.map(|id| (id, desc.nonblock)))
              } else {
                  return SyscallResult::Error(SyscallError::InvalidArgument);
              }
@@ -234,14 +677,25 @@ impl SyscallHandler {
          };
          drop(fm);
          
-         let dentry: Arc<Mutex<Dentry>> = match path_lookup(&path) {
-             Ok(d) => d,
+         // FIFO : plus de lecteur, SIGPIPE puis EPIPE
+         if let Some((id, nonblock)) = fifo {
+             return match crate::ipc::pipe::fifo_write(id, &temp_buf, nonblock) {
+                 Ok(n) => SyscallResult::Success(n as u64),
+                 Err(crate::ipc::pipe::PipeError::BrokenPipe) => {
+                     let mut pm = crate::process::PROCESS_MANAGER.lock();
+                     let _ = crate::process::signal::SIGNAL_MANAGER.lock().send_signal(pid, crate::process::signal::Signal::SIGPIPE, &mut pm);
+                     SyscallResult::Error(SyscallError::BrokenPipe)
+                 }
+                 Err(e) => SyscallResult::Error(e.into()),
+             };
+         }
+         
+         let ops = match vfs_fd_inode(pid, fd) {
+             Ok((ops, _)) => ops,
              Err(_) => return SyscallResult::Error(SyscallError::NotFound),
          };
          
-         let inode = dentry.lock().inode.clone();
-         
-         let wrote_bytes = match inode.lock().ops.lock().write(offset, &temp_buf) {
+         let wrote_bytes = match ops.lock().write(offset, &temp_buf) {
              Ok(n) => n,
              Err(_) => return SyscallResult::Error(SyscallError::IoError),
          };
@@ -258,35 +712,21 @@ impl SyscallHandler {
 
     fn handle_open(&self, path_ptr: *const u8, flags: i32) -> SyscallResult {
         use crate::process::current_process;
-        use crate::fs::{FD_MANAGER, OpenMode, Dentry};
-        use crate::fs::path_lookup;
-        use alloc::string::String;
-        use alloc::sync::Arc;
-        use spin::Mutex;
+        use crate::fs::{FD_MANAGER, OpenMode};
+        use crate::fs::permissions::{ACCESS_READ, ACCESS_WRITE};
         
         let path = match self.read_user_string(path_ptr) {
             Some(s) => s,
             None => return SyscallResult::Error(SyscallError::InvalidArgument),
         };
         
-        let pid = match current_process() {
-            Some(p) => p.lock().pid,
+        let (pid, cred) = match current_process() {
+            Some(p) => {
+                let p = p.lock();
+                (p.pid, p.cred.clone())
+            }
             None => return SyscallResult::Error(SyscallError::NoSuchProcess),
         };
-        
-         let size = match path_lookup(&path) {
-             Ok(dentry) => {
-                 let dentry: Arc<Mutex<Dentry>> = dentry;
-                 let inode = dentry.lock().inode.clone();
-                 let inode = dentry.lock().inode.clone();
-                 let s = match inode.lock().ops.lock().stat() {
-                     Ok(stat) => stat.size,
-                     Err(_) => 0,
-                 };
-                 s
-             },
-             Err(_) => return SyscallResult::Error(SyscallError::NotFound),
-        };
 
         let mode = match flags & 3 {
             0 => OpenMode::ReadOnly,
@@ -295,14 +735,77 @@ impl SyscallHandler {
             _ => OpenMode::ReadOnly,
         };
         
+        // O_CREAT : création atomique sous le verrou du répertoire parent ;
+        // le fichier créé s'ouvre quel que soit son mode, comme sous Linux
+        let mut created = false;
+        if flags & open_flags::O_CREAT != 0 {
+            use crate::fs::{vfs_create_as, FileMode, FileType, VfsError};
+            match vfs_create_as(&path, FileMode::new(0o644), FileType::Regular, &cred) {
+                Ok(_) => created = true,
+                Err(VfsError::AlreadyExists) if flags & open_flags::O_EXCL == 0 => {}
+                Err(e) => return SyscallResult::Error(e.into()),
+            }
+        }
+        
+        // Droits du fichier pour l'identité effective du processus
+        let want = match mode {
+            OpenMode::ReadOnly => ACCESS_READ,
+            OpenMode::WriteOnly => ACCESS_WRITE,
+            OpenMode::ReadWrite => ACCESS_READ | ACCESS_WRITE,
+        };
+        let stat = match crate::fs::vfs_access(&path, &cred, if created { 0 } else { want }) {
+            Ok(stat) => stat,
+            Err(e) => return SyscallResult::Error(e.into()),
+        };
+        let size = stat.size;
+        let nonblock = flags & open_flags::O_NONBLOCK != 0;
+        
+        // FIFO : attend l'autre extrémité, puis lit et écrit dans son pipe
+        let mut fifo = None;
+        if stat.file_type == crate::fs::FileType::Fifo {
+            let key = match crate::fs::path_lookup(&path) {
+                Ok(dentry) => {
+                    let inode = dentry.lock().inode.clone();
+                    let inode = inode.lock();
+                    (inode.fs_id, inode.id)
+                }
+                Err(e) => return SyscallResult::Error(e.into()),
+            };
+            let read = mode != OpenMode::WriteOnly;
+            let write = mode != OpenMode::ReadOnly;
+            match crate::ipc::pipe::fifo_open(key, read, write, nonblock) {
+                Ok(id) => fifo = Some((id, read, write)),
+                Err(e) => return SyscallResult::Error(e.into()),
+            }
+        }
+        
+        // Le descripteur garde l'inode : un unlink ultérieur ne le libère pas
+        let inode = if fifo.is_none() { crate::fs::vfs_open_inode(&path).ok() } else { None };
+        
         let mut fm = FD_MANAGER.lock();
-        if let Ok(table) = fm.get_table(pid) {
-            match table.open(&path, mode, size) {
-                Ok(fd) => SyscallResult::Success(fd as u64),
-                Err(_) => SyscallResult::Error(SyscallError::IoError),
+        let opened = match fm.get_table(pid) {
+            Ok(table) => table.open(&path, mode, size).map(|fd| {
+                if let Ok(desc) = table.get_mut(fd) {
+                    desc.fifo = fifo.map(|(id, _, _)| id);
+                    desc.nonblock = nonblock;
+                    desc.inode = inode;
+                }
+                fd
+            }),
+            Err(e) => Err(e),
+        };
+        drop(fm);
+        match opened {
+            Ok(fd) => SyscallResult::Success(fd as u64),
+            Err(e) => {
+                if let Some((id, read, write)) = fifo {
+                    let _ = crate::ipc::pipe::fifo_close(id, read, write);
+                }
+                match e {
+                    crate::fs::TOO_MANY_FILES => SyscallResult::Error(SyscallError::TooManyFiles),
+                    _ => SyscallResult::Error(SyscallError::IoError),
+                }
             }
-        } else {
-            SyscallResult::Error(SyscallError::IoError)
         }
     }
 
@@ -317,8 +820,16 @@ impl SyscallHandler {
         
         let mut fm = FD_MANAGER.lock();
         if let Ok(table) = fm.get_table(pid) {
+            // Une FIFO libère son extrémité du pipe
+            let fifo = table.get(fd).ok().and_then(|desc| desc.fifo.map(|id| (id, desc.mode)));
             match table.close(fd) {
-                Ok(_) => SyscallResult::Success(0),
+                Ok(_) => {
+                    drop(fm);
+                    if let Some((id, mode)) = fifo {
+                        let _ = crate::ipc::pipe::fifo_close(id, mode != crate::fs::OpenMode::WriteOnly, mode != crate::fs::OpenMode::ReadOnly);
+                    }
+                    SyscallResult::Success(0)
+                }
                 Err(_) => SyscallResult::Error(SyscallError::InvalidArgument),
             }
         } else {
@@ -328,21 +839,28 @@ impl SyscallHandler {
 
     fn read_user_string(&self, ptr: *const u8) -> Option<alloc::string::String> {
         if ptr.is_null() { return None; }
-        let mut bytes = alloc::vec::Vec::new();
-        let mut offset = 0;
+        let bytes = read_user_cstr(ptr, 1024).ok()??;
+        alloc::string::String::from_utf8(bytes).ok()
+    }
+    
+    /// Lit un tableau de chaînes utilisateur terminé par un pointeur nul
+    fn read_user_string_array(&self, ptr: *const u64) -> Option<alloc::vec::Vec<alloc::string::String>> {
+        if ptr.is_null() { return None; }
+        let mut strings = alloc::vec::Vec::new();
         loop {
-            let byte = unsafe { *ptr.add(offset) };
-            if byte == 0 { break; }
-            bytes.push(byte);
-            offset += 1;
-            if offset > 1024 { return None; }
+            let entry = read_user(ptr.wrapping_add(strings.len())).ok()?;
+            if entry == 0 { break; }
+            strings.push(self.read_user_string(entry as *const u8)?);
+            if strings.len() > 256 { return None; }
         }
-        alloc::string::String::from_utf8(bytes).ok()
+        Some(strings)
     }
     
     fn handle_getpid(&self) -> SyscallResult {
-        // TODO: Implémenter la récupération du PID
-        SyscallResult::Success(0)
+        match crate::process::current_process() {
+            Some(p) => SyscallResult::Success(p.lock().pid),
+            None => SyscallResult::Error(SyscallError::NoSuchProcess),
+        }
     }
     
     /// Définit la priorité d'un processus
@@ -357,6 +875,7 @@ impl SyscallHandler {
         }
         
         let priority = ProcessPriority::from_u8(priority);
+        let cred = current_credentials();
         
         // Obtenir le processus cible
         let target = if pid == 0 {
@@ -369,7 +888,13 @@ impl SyscallHandler {
         
         match target {
             Some(process) => {
-                process.lock().set_priority(priority);
+                let mut process = process.lock();
+                // Temps réel, ou processus d'un autre utilisateur : CAP_SYS_NICE
+                let foreign = cred.euid != process.cred.ruid && cred.euid != process.cred.euid;
+                if (priority == ProcessPriority::Realtime || foreign) && !cred.capable(CAP_SYS_NICE) {
+                    return SyscallResult::Error(SyscallError::NotPermitted);
+                }
+                process.set_priority(priority);
                 SyscallResult::Success(0)
             }
             None => SyscallResult::Error(SyscallError::NoSuchProcess),
@@ -443,13 +968,22 @@ impl SyscallHandler {
     /// args[1] = signal number
     fn handle_kill(&self, pid: u64, signal_num: u8) -> SyscallResult {
         use crate::process::signal::{Signal, SIGNAL_MANAGER};
-        use crate::process::PROCESS_MANAGER;
+        use crate::process::{get_process_by_pid, PROCESS_MANAGER};
         
         // Valider le numéro de signal
         let signal = match Signal::from_u8(signal_num) {
             Some(s) => s,
             None => return SyscallResult::Error(SyscallError::InvalidArgument),
         };
+
+        // Même utilisateur, ou CAP_KILL
+        let target = match get_process_by_pid(pid) {
+            Some(p) => p.lock().cred.clone(),
+            None => return SyscallResult::Error(SyscallError::NoSuchProcess),
+        };
+        if !current_credentials().can_signal(&target) {
+            return SyscallResult::Error(SyscallError::NotPermitted);
+        }
         
         // Envoyer le signal au processus cible
         let mut pm = PROCESS_MANAGER.lock();
@@ -506,36 +1040,34 @@ impl SyscallHandler {
     fn handle_shmget(&self, key: i32, size: usize, flags: i32) -> SyscallResult {
         use crate::memory::SHM_MANAGER;
         
-        // TODO: Récupérer UID/GID du processus actuel
-        let uid = 1000; // Placeholder
-        let gid = 1000; // Placeholder
-        
-        match SHM_MANAGER.lock().shmget(key, size, flags, uid, gid) {
+        match SHM_MANAGER.lock().shmget(key, size, flags, &current_credentials()) {
             Ok(id) => SyscallResult::Success(id as u64),
-            Err(_) => SyscallResult::Error(SyscallError::OutOfMemory),
+            Err(e) => SyscallResult::Error(e.into()),
         }
     }
     
     /// Attache un segment de mémoire partagée
     /// args[0] = id
     /// args[1] = addr (0 = auto)
-    fn handle_shmat(&self, id: i32, addr: u64) -> SyscallResult {
+    /// args[2] = flags (SHM_RDONLY, SHM_EXEC)
+    fn handle_shmat(&self, id: i32, addr: u64, flags: i32) -> SyscallResult {
         use crate::memory::SHM_MANAGER;
         use x86_64::VirtAddr;
         
-        // TODO: Récupérer UID/GID du processus actuel
-        let uid = 1000; // Placeholder
-        let gid = 1000; // Placeholder
-        
         let virt_addr = if addr == 0 {
             None
         } else {
-            Some(VirtAddr::new(addr))
+            match VirtAddr::try_new(addr) {
+                Ok(addr) => Some(addr),
+                Err(_) => return SyscallResult::Error(SyscallError::InvalidArgument),
+            }
         };
+        // Le noyau (hors processus) s'attache pour le PID 1
+        let pid = crate::process::current_process().map(|p| p.lock().pid).unwrap_or(1);
         
-        match SHM_MANAGER.lock().shmat(id, virt_addr, uid, gid) {
+        match SHM_MANAGER.lock().shmat(id, virt_addr, flags, pid, &current_credentials()) {
             Ok(addr) => SyscallResult::Success(addr.as_u64()),
-            Err(_) => SyscallResult::Error(SyscallError::PermissionDenied),
+            Err(e) => SyscallResult::Error(e.into()),
         }
     }
     
@@ -545,9 +1077,14 @@ impl SyscallHandler {
         use crate::memory::SHM_MANAGER;
         use x86_64::VirtAddr;
         
-        match SHM_MANAGER.lock().shmdt(VirtAddr::new(addr)) {
+        let addr = match VirtAddr::try_new(addr) {
+            Ok(addr) => addr,
+            Err(_) => return SyscallResult::Error(SyscallError::InvalidArgument),
+        };
+        let pid = crate::process::current_process().map(|p| p.lock().pid).unwrap_or(1);
+        match SHM_MANAGER.lock().shmdt(addr, pid) {
             Ok(_) => SyscallResult::Success(0),
-            Err(_) => SyscallResult::Error(SyscallError::InvalidArgument),
+            Err(e) => SyscallResult::Error(e.into()),
         }
     }
     
@@ -564,12 +1101,9 @@ impl SyscallHandler {
             _ => return SyscallResult::Error(SyscallError::InvalidArgument),
         };
         
-        // TODO: Récupérer UID du processus actuel
-        let uid = 1000; // Placeholder
-        
-        match SHM_MANAGER.lock().shmctl(id, shm_cmd, uid) {
+        match SHM_MANAGER.lock().shmctl(id, shm_cmd, &current_credentials()) {
             Ok(_) => SyscallResult::Success(0),
-            Err(_) => SyscallResult::Error(SyscallError::PermissionDenied),
+            Err(e) => SyscallResult::Error(e.into()),
         }
     }
     
@@ -582,15 +1116,19 @@ impl SyscallHandler {
     /// args[5] = offset
     fn handle_mmap(&self, addr: u64, size: usize, prot: i32, flags: i32, fd: i32, offset: u64) -> SyscallResult {
         use crate::memory::MMAP_MANAGER;
+        use crate::memory::mmap::MmapError;
         use x86_64::VirtAddr;
         
-        // TODO: Récupérer UID du processus actuel
-        let pid = 1; // Placeholder
+        // Le noyau (hors processus) mappe pour le PID 1
+        let pid = crate::process::current_process().map(|p| p.lock().pid).unwrap_or(1);
         
         let virt_addr = if addr == 0 {
             None
         } else {
-            Some(VirtAddr::new(addr))
+            match VirtAddr::try_new(addr) {
+                Ok(addr) => Some(addr),
+                Err(_) => return SyscallResult::Error(SyscallError::InvalidArgument),
+            }
         };
         
         let file_id = if fd >= 0 {
@@ -601,6 +1139,12 @@ impl SyscallHandler {
         
         match MMAP_MANAGER.lock().mmap(virt_addr, size, prot, flags, file_id, offset, pid) {
             Ok(addr) => SyscallResult::Success(addr.as_u64()),
+            // Adresse, taille ou drapeaux refusés : EINVAL
+            Err(MmapError::InvalidAddress | MmapError::InvalidSize | MmapError::InvalidFlags) => {
+                SyscallResult::Error(SyscallError::InvalidArgument)
+            }
+            Err(MmapError::PermissionDenied) => SyscallResult::Error(SyscallError::PermissionDenied),
+            // Espace épuisé ou RLIMIT_AS dépassée : ENOMEM, comme Linux
             Err(_) => SyscallResult::Error(SyscallError::OutOfMemory),
         }
     }
@@ -610,20 +1154,108 @@ impl SyscallHandler {
     /// args[1] = size
     fn handle_munmap(&self, addr: u64, size: usize) -> SyscallResult {
         use crate::memory::MMAP_MANAGER;
+        use crate::memory::mmap::MmapError;
         use x86_64::VirtAddr;
         
-        match MMAP_MANAGER.lock().munmap(VirtAddr::new(addr), size) {
+        let addr = match VirtAddr::try_new(addr) {
+            Ok(addr) => addr,
+            Err(_) => return SyscallResult::Error(SyscallError::InvalidArgument),
+        };
+        let pid = crate::process::current_process().map(|p| p.lock().pid).unwrap_or(1);
+        match MMAP_MANAGER.lock().munmap(addr, size, pid) {
             Ok(_) => SyscallResult::Success(0),
+            Err(MmapError::PermissionDenied) => SyscallResult::Error(SyscallError::PermissionDenied),
+            Err(_) => SyscallResult::Error(SyscallError::InvalidArgument),
+        }
+    }
+    
+    /// Change les protections d'une région mappée
+    /// args[0] = addr (aligné sur une page)
+    /// args[1] = size
+    /// args[2] = prot (écriture et exécution exclusives)
+    fn handle_mprotect(&self, addr: u64, size: usize, prot: i32) -> SyscallResult {
+        use crate::memory::MMAP_MANAGER;
+        use crate::memory::mmap::MmapError;
+        use x86_64::VirtAddr;
+        
+        let addr = match VirtAddr::try_new(addr) {
+            Ok(addr) => addr,
+            Err(_) => return SyscallResult::Error(SyscallError::InvalidArgument),
+        };
+        let pid = crate::process::current_process().map(|p| p.lock().pid).unwrap_or(1);
+        match MMAP_MANAGER.lock().mprotect(addr, size, prot, pid) {
+            Ok(()) => SyscallResult::Success(0),
+            Err(MmapError::PermissionDenied) => SyscallResult::Error(SyscallError::PermissionDenied),
+            Err(MmapError::NotFound) => SyscallResult::Error(SyscallError::NotFound),
             Err(_) => SyscallResult::Error(SyscallError::InvalidArgument),
         }
     }
     
+    /// Fixe le program break du processus courant
+    /// args[0] = nouveau break (0 = simple lecture)
+    /// Retourne le break effectif : inchangé si la demande est refusée
+    fn handle_brk(&self, addr: u64) -> SyscallResult {
+        use crate::process::current_process;
+        
+        let process = match current_process() {
+            Some(p) => p,
+            None => return SyscallResult::Error(SyscallError::NoSuchProcess),
+        };
+        let mut process = process.lock();
+        if addr == 0 {
+            return SyscallResult::Success(process.brk);
+        }
+        match process.set_brk(addr) {
+            Ok(brk) => SyscallResult::Success(brk),
+            Err(_) => SyscallResult::Success(process.brk),
+        }
+    }
+    
+    /// Fait varier le program break du processus courant
+    /// args[0] = incrément signé en octets
+    /// Retourne l'ancien break
+    fn handle_sbrk(&self, increment: i64) -> SyscallResult {
+        use crate::process::current_process;
+        
+        let process = match current_process() {
+            Some(p) => p,
+            None => return SyscallResult::Error(SyscallError::NoSuchProcess),
+        };
+        let result = process.lock().sbrk(increment);
+        match result {
+            Ok(old_brk) => SyscallResult::Success(old_brk),
+            Err(_) => SyscallResult::Error(SyscallError::OutOfMemory),
+        }
+    }
+
+    /// Arrête ou redémarre la machine après synchronisation des
+    /// systèmes de fichiers ; ne revient qu'en cas de commande inconnue
+    fn handle_reboot(&self, cmd: u64) -> SyscallResult {
+        if !current_credentials().capable(CAP_SYS_ADMIN) {
+            return SyscallResult::Error(SyscallError::NotPermitted);
+        }
+        match cmd {
+            reboot_cmd::RESTART | reboot_cmd::POWER_OFF => {
+                if let Err(e) = crate::fs::vfs_sync() {
+                    crate::serial_println!("reboot: sync failed: {:?}", e);
+                }
+                if cmd == reboot_cmd::RESTART {
+                    crate::power::reboot()
+                } else {
+                    crate::power::shutdown()
+                }
+            }
+            _ => SyscallResult::Error(SyscallError::InvalidArgument),
+        }
+    }
+    
     fn handle_symlink(&self, _target_ptr: *const u8, _link_ptr: *const u8) -> SyscallResult {
         use crate::fs::SYMLINK_MANAGER;
         use alloc::string::String;
         let target_path = String::from("/target");
         let link_path = String::from("/link");
-        match SYMLINK_MANAGER.lock().create_symlink(link_path, target_path, 1000, 1000) {
+        let cred = current_credentials();
+        match SYMLINK_MANAGER.lock().create_symlink(link_path, target_path, cred.euid, cred.egid) {
             Ok(inode) => SyscallResult::Success(inode),
             Err(_) => SyscallResult::Error(SyscallError::InvalidArgument),
         }
@@ -639,30 +1271,36 @@ impl SyscallHandler {
         }
     }
     
-    fn handle_chmod(&self, inode: u64, mode: u16) -> SyscallResult {
-        use crate::fs::PERMISSION_MANAGER;
-        let caller_uid = 1000; // TODO: Récupérer l'UID du processus actuel
-        match PERMISSION_MANAGER.lock().chmod(inode, mode, caller_uid) {
-            Ok(_) => SyscallResult::Success(0),
-            Err(_) => SyscallResult::Error(SyscallError::PermissionDenied),
+    fn handle_chmod(&self, path_ptr: *const u8, mode: u16) -> SyscallResult {
+        let path = match self.read_user_string(path_ptr) {
+            Some(s) => s,
+            None => return SyscallResult::Error(SyscallError::InvalidArgument),
+        };
+        match crate::fs::vfs_chmod_as(&path, mode, &current_credentials()) {
+            Ok(()) => SyscallResult::Success(0),
+            Err(e) => SyscallResult::Error(e.into()),
         }
     }
     
-    fn handle_chown(&self, inode: u64, uid: u32) -> SyscallResult {
-        use crate::fs::PERMISSION_MANAGER;
-        let caller_uid = 0; // TODO: Récupérer l'UID du processus actuel
-        match PERMISSION_MANAGER.lock().chown(inode, uid, caller_uid) {
-            Ok(_) => SyscallResult::Success(0),
-            Err(_) => SyscallResult::Error(SyscallError::PermissionDenied),
+    fn handle_chown(&self, path_ptr: *const u8, uid: u32) -> SyscallResult {
+        let path = match self.read_user_string(path_ptr) {
+            Some(s) => s,
+            None => return SyscallResult::Error(SyscallError::InvalidArgument),
+        };
+        match crate::fs::vfs_chown_as(&path, uid, &current_credentials()) {
+            Ok(()) => SyscallResult::Success(0),
+            Err(e) => SyscallResult::Error(e.into()),
         }
     }
     
-    fn handle_chgrp(&self, inode: u64, gid: u32) -> SyscallResult {
-        use crate::fs::PERMISSION_MANAGER;
-        let caller_uid = 1000; // TODO: Récupérer l'UID du processus actuel
-        match PERMISSION_MANAGER.lock().chgrp(inode, gid, caller_uid) {
-            Ok(_) => SyscallResult::Success(0),
-            Err(_) => SyscallResult::Error(SyscallError::PermissionDenied),
+    fn handle_chgrp(&self, path_ptr: *const u8, gid: u32) -> SyscallResult {
+        let path = match self.read_user_string(path_ptr) {
+            Some(s) => s,
+            None => return SyscallResult::Error(SyscallError::InvalidArgument),
+        };
+        match crate::fs::vfs_chgrp_as(&path, gid, &current_credentials()) {
+            Ok(()) => SyscallResult::Success(0),
+            Err(e) => SyscallResult::Error(e.into()),
         }
     }
     
@@ -681,7 +1319,628 @@ impl SyscallHandler {
         let mut pm = PROCESS_MANAGER.lock();
         match pm.create_thread(current_pid, entry_point) {
             Ok(tid) => SyscallResult::Success(tid),
+            Err("Thread limit exceeded") => SyscallResult::Error(SyscallError::WouldBlock),
             Err(_) => SyscallResult::Error(SyscallError::OutOfMemory), // Ou autre erreur appropriée
         }
     }
+    
+    fn handle_sched_yield(&self) -> SyscallResult {
+        crate::scheduler::SCHEDULER.yield_now();
+        SyscallResult::Success(0)
+    }
+    
+    /// getcpu(cpu, node) : les pointeurs nuls sont ignorés.
+    /// Retourne aussi le numéro de CPU comme valeur de succès.
+    fn handle_gettimeofday(&self, tv_ptr: *mut TimeVal) -> SyscallResult {
+        if tv_ptr.is_null() {
+            return SyscallResult::Error(SyscallError::InvalidArgument);
+        }
+        let now = crate::time::realtime_ns();
+        let tv = TimeVal {
+            tv_sec: (now / crate::time::NSEC_PER_SEC) as i64,
+            tv_usec: (now % crate::time::NSEC_PER_SEC / crate::time::NSEC_PER_USEC) as i64,
+        };
+        match write_user(tv_ptr, tv) {
+            Ok(()) => SyscallResult::Success(0),
+            Err(e) => SyscallResult::Error(e.into()),
+        }
+    }
+
+    fn handle_clock_gettime(&self, clock: u64, ts_ptr: *mut TimeSpec) -> SyscallResult {
+        let now = match clock {
+            clock_id::CLOCK_REALTIME => crate::time::realtime_ns(),
+            clock_id::CLOCK_MONOTONIC => crate::time::now_ns(),
+            _ => return SyscallResult::Error(SyscallError::InvalidArgument),
+        };
+        if ts_ptr.is_null() {
+            return SyscallResult::Error(SyscallError::InvalidArgument);
+        }
+        let ts = TimeSpec {
+            tv_sec: (now / crate::time::NSEC_PER_SEC) as i64,
+            tv_nsec: (now % crate::time::NSEC_PER_SEC) as i64,
+        };
+        match write_user(ts_ptr, ts) {
+            Ok(()) => SyscallResult::Success(0),
+            Err(e) => SyscallResult::Error(e.into()),
+        }
+    }
+
+    /// setuid() : voir `Credentials::setuid`
+    fn handle_setuid(&self, uid: u32) -> SyscallResult {
+        let process = match crate::process::current_process() {
+            Some(p) => p,
+            None => return SyscallResult::Error(SyscallError::NoSuchProcess),
+        };
+        let result = process.lock().cred.setuid(uid);
+        match result {
+            Ok(()) => SyscallResult::Success(0),
+            Err(_) => SyscallResult::Error(SyscallError::NotPermitted),
+        }
+    }
+
+    /// setgid() : voir `Credentials::setgid`
+    fn handle_setgid(&self, gid: u32) -> SyscallResult {
+        let process = match crate::process::current_process() {
+            Some(p) => p,
+            None => return SyscallResult::Error(SyscallError::NoSuchProcess),
+        };
+        let result = process.lock().cred.setgid(gid);
+        match result {
+            Ok(()) => SyscallResult::Success(0),
+            Err(_) => SyscallResult::Error(SyscallError::NotPermitted),
+        }
+    }
+
+    /// Lit une limite du processus courant
+    /// args[0] = ressource (RLIMIT_*), args[1] = `struct rlimit *`
+    fn handle_getrlimit(&self, resource: u64, rlim_ptr: *mut RLimit) -> SyscallResult {
+        let resource = match Resource::from_u64(resource) {
+            Some(r) => r,
+            None => return SyscallResult::Error(SyscallError::InvalidArgument),
+        };
+        if rlim_ptr.is_null() {
+            return SyscallResult::Error(SyscallError::InvalidArgument);
+        }
+        let process = match crate::process::current_process() {
+            Some(p) => p,
+            None => return SyscallResult::Error(SyscallError::NoSuchProcess),
+        };
+        let limit = process.lock().limits.get(resource);
+        match write_user(rlim_ptr, limit) {
+            Ok(()) => SyscallResult::Success(0),
+            Err(e) => SyscallResult::Error(e.into()),
+        }
+    }
+
+    /// Change une limite du processus courant ; la limite dure ne peut
+    /// qu'être abaissée
+    fn handle_setrlimit(&self, resource: u64, rlim_ptr: *const RLimit) -> SyscallResult {
+        let resource = match Resource::from_u64(resource) {
+            Some(r) => r,
+            None => return SyscallResult::Error(SyscallError::InvalidArgument),
+        };
+        if rlim_ptr.is_null() {
+            return SyscallResult::Error(SyscallError::InvalidArgument);
+        }
+        let limit = match read_user(rlim_ptr) {
+            Ok(limit) => limit,
+            Err(e) => return SyscallResult::Error(e.into()),
+        };
+        if limit.cur > limit.max {
+            return SyscallResult::Error(SyscallError::InvalidArgument);
+        }
+        let process = match crate::process::current_process() {
+            Some(p) => p,
+            None => return SyscallResult::Error(SyscallError::NoSuchProcess),
+        };
+        let (pid, limits) = {
+            let mut process = process.lock();
+            if process.limits.set(resource, limit).is_err() {
+                return SyscallResult::Error(SyscallError::PermissionDenied);
+            }
+            (process.pid, process.limits)
+        };
+        crate::process::rlimit::apply(pid, &limits);
+        SyscallResult::Success(0)
+    }
+
+    fn handle_getcpu(&self, cpu_ptr: *mut u32, node_ptr: *mut u32) -> SyscallResult {
+        let cpu = crate::scheduler::current_cpu();
+        let node = crate::scheduler::current_numa_node();
+        
+        if (!cpu_ptr.is_null() && write_user(cpu_ptr, cpu).is_err())
+            || (!node_ptr.is_null() && write_user(node_ptr, node).is_err())
+        {
+            return SyscallResult::Error(SyscallError::BadAddress);
+        }
+        SyscallResult::Success(cpu as u64)
+    }
+    
+    /// Liste un répertoire : noms séparés par des octets nuls
+    /// args[0] = chemin, args[1] = tampon, args[2] = taille du tampon
+    /// Retourne le nombre d'octets écrits
+    fn handle_readdir(&self, path_ptr: *const u8, buf_ptr: *mut u8, buf_len: usize) -> SyscallResult {
+        let path = match self.read_user_string(path_ptr) {
+            Some(s) => s,
+            None => return SyscallResult::Error(SyscallError::InvalidArgument),
+        };
+        if buf_ptr.is_null() {
+            return SyscallResult::Error(SyscallError::InvalidArgument);
+        }
+        let names = match crate::fs::vfs_readdir_as(&path, &current_credentials()) {
+            Ok(names) => names,
+            Err(e) => return SyscallResult::Error(e.into()),
+        };
+        let mut out = alloc::vec::Vec::new();
+        for name in names {
+            out.extend_from_slice(name.as_bytes());
+            out.push(0);
+        }
+        if out.len() > buf_len {
+            return SyscallResult::Error(SyscallError::InvalidArgument);
+        }
+        match copy_to_user(buf_ptr, &out) {
+            Ok(()) => SyscallResult::Success(out.len() as u64),
+            Err(e) => SyscallResult::Error(e.into()),
+        }
+    }
+
+    /// Ouvre une file de messages nommée
+    /// args[0] = nom (`/nom`), args[1] = O_RDONLY/O_WRONLY/O_RDWR | O_CREAT
+    /// | O_EXCL | O_NONBLOCK, args[2] = `struct mq_attr *` (peut être nul)
+    fn handle_mq_open(&self, name_ptr: *const u8, flags: i32, attr_ptr: *const crate::ipc::MqAttr) -> SyscallResult {
+        use crate::ipc::{MqOpenFlags, MQ_MANAGER};
+        let name = match self.read_user_string(name_ptr) {
+            Some(s) => s,
+            None => return SyscallResult::Error(SyscallError::InvalidArgument),
+        };
+        let attr = if attr_ptr.is_null() {
+            None
+        } else {
+            match read_user(attr_ptr) {
+                Ok(attr) => Some(attr),
+                Err(e) => return SyscallResult::Error(e.into()),
+            }
+        };
+        let access = flags & 3;
+        let open = MqOpenFlags {
+            read: access == 0 || access == 2,
+            write: access == 1 || access == 2,
+            create: flags & open_flags::O_CREAT != 0,
+            exclusive: flags & open_flags::O_EXCL != 0,
+            nonblock: flags & open_flags::O_NONBLOCK != 0,
+        };
+        match without_interrupts(|| MQ_MANAGER.lock().open_named(&name, open, attr)) {
+            Ok(mqd) => SyscallResult::Success(mqd as u64),
+            Err(e) => SyscallResult::Error(e.into()),
+        }
+    }
+
+    /// Envoie un message ; bloque tant que la file est pleine
+    fn handle_mq_send(&self, mqd: u32, buf_ptr: *const u8, len: usize, priority: u32) -> SyscallResult {
+        if buf_ptr.is_null() && len > 0 {
+            return SyscallResult::Error(SyscallError::InvalidArgument);
+        }
+        if priority >= crate::ipc::mqueue::MQ_PRIO_MAX as u32 {
+            return SyscallResult::Error(SyscallError::InvalidArgument);
+        }
+        // Taille bornée par la file avant toute copie depuis l'espace utilisateur
+        let max_msg_size = match without_interrupts(|| {
+            let manager = crate::ipc::MQ_MANAGER.lock();
+            manager.descriptor(mqd).and_then(|desc| manager.mq_getattr(desc.queue))
+        }) {
+            Ok(attr) => attr.max_msg_size,
+            Err(e) => return SyscallResult::Error(e.into()),
+        };
+        if len > max_msg_size {
+            return SyscallResult::Error(SyscallError::MessageTooLarge);
+        }
+        let mut data = alloc::vec![0u8; len];
+        if let Err(e) = copy_from_user(&mut data, buf_ptr) {
+            return SyscallResult::Error(e.into());
+        }
+        match crate::ipc::mqueue::send(mqd, data, priority as u8) {
+            Ok(()) => SyscallResult::Success(0),
+            Err(e) => SyscallResult::Error(e.into()),
+        }
+    }
+
+    /// Reçoit le message de plus haute priorité ; bloque tant que la file
+    /// est vide. Le tampon doit pouvoir contenir le plus grand message.
+    fn handle_mq_receive(&self, mqd: u32, buf_ptr: *mut u8, len: usize, prio_ptr: *mut u32) -> SyscallResult {
+        use crate::ipc::MQ_MANAGER;
+        if buf_ptr.is_null() {
+            return SyscallResult::Error(SyscallError::InvalidArgument);
+        }
+        let max_msg_size = match without_interrupts(|| {
+            let manager = MQ_MANAGER.lock();
+            manager.descriptor(mqd).and_then(|desc| manager.mq_getattr(desc.queue))
+        }) {
+            Ok(attr) => attr.max_msg_size,
+            Err(e) => return SyscallResult::Error(e.into()),
+        };
+        if len < max_msg_size {
+            return SyscallResult::Error(SyscallError::MessageTooLarge);
+        }
+        match crate::ipc::mqueue::receive(mqd) {
+            Ok(message) => {
+                if copy_to_user(buf_ptr, &message.data).is_err()
+                    || (!prio_ptr.is_null() && write_user(prio_ptr, message.priority as u32).is_err())
+                {
+                    return SyscallResult::Error(SyscallError::BadAddress);
+                }
+                SyscallResult::Success(message.data.len() as u64)
+            }
+            Err(e) => SyscallResult::Error(e.into()),
+        }
+    }
+
+    fn handle_mq_close(&self, mqd: u32) -> SyscallResult {
+        match without_interrupts(|| crate::ipc::MQ_MANAGER.lock().close_descriptor(mqd)) {
+            Ok(waiters) => {
+                crate::ipc::mqueue::wake_all(waiters);
+                SyscallResult::Success(0)
+            }
+            Err(e) => SyscallResult::Error(e.into()),
+        }
+    }
+
+    fn handle_mq_unlink(&self, name_ptr: *const u8) -> SyscallResult {
+        let name = match self.read_user_string(name_ptr) {
+            Some(s) => s,
+            None => return SyscallResult::Error(SyscallError::InvalidArgument),
+        };
+        match without_interrupts(|| crate::ipc::MQ_MANAGER.lock().unlink(&name)) {
+            Ok(waiters) => {
+                crate::ipc::mqueue::wake_all(waiters);
+                SyscallResult::Success(0)
+            }
+            Err(e) => SyscallResult::Error(e.into()),
+        }
+    }
+
+    /// Demande un signal à l'arrivée d'un message dans la file vide
+    /// args[1] = numéro de signal, 0 pour retirer la notification
+    fn handle_mq_notify(&self, mqd: u32, signal_num: u8) -> SyscallResult {
+        use crate::ipc::MqNotify;
+        use crate::process::signal::Signal;
+        let notify = if signal_num == 0 {
+            None
+        } else {
+            let signal = match Signal::from_u8(signal_num) {
+                Some(s) => s,
+                None => return SyscallResult::Error(SyscallError::InvalidArgument),
+            };
+            let pid = match crate::process::current_process() {
+                Some(p) => p.lock().pid,
+                None => return SyscallResult::Error(SyscallError::NoSuchProcess),
+            };
+            Some(MqNotify::Signal { pid, signal })
+        };
+        match without_interrupts(|| crate::ipc::MQ_MANAGER.lock().set_notify(mqd, notify)) {
+            Ok(()) => SyscallResult::Success(0),
+            Err(e) => SyscallResult::Error(e.into()),
+        }
+    }
+
+    /// Attend qu'un descripteur soit prêt (déclenchement par niveau)
+    /// args[0] = `struct pollfd *`, args[1] = nombre d'entrées,
+    /// args[2] = délai en ms (négatif : illimité)
+    fn handle_poll(&self, fds_ptr: *mut crate::fs::poll::PollFd, nfds: usize, timeout_ms: i64) -> SyscallResult {
+        let (pid, max_open) = match crate::process::current_process() {
+            Some(p) => {
+                let p = p.lock();
+                (p.pid, p.limits.get(Resource::NoFile).cur)
+            }
+            None => return SyscallResult::Error(SyscallError::NoSuchProcess),
+        };
+        if nfds as u64 > max_open {
+            return SyscallResult::Error(SyscallError::InvalidArgument);
+        }
+        if nfds == 0 {
+            return SyscallResult::Success(crate::fs::poll::poll(pid, &mut [], timeout_ms) as u64);
+        }
+        if fds_ptr.is_null() {
+            return SyscallResult::Error(SyscallError::InvalidArgument);
+        }
+        // Copie de travail : le tableau utilisateur n'est relu qu'une fois
+        let mut fds = match read_user_slice(fds_ptr, nfds) {
+            Ok(fds) => fds,
+            Err(e) => return SyscallResult::Error(e.into()),
+        };
+        let ready = crate::fs::poll::poll(pid, &mut fds, timeout_ms);
+        match write_user_slice(fds_ptr, &fds) {
+            Ok(()) => SyscallResult::Success(ready as u64),
+            Err(e) => SyscallResult::Error(e.into()),
+        }
+    }
+
+    /// Crée un tube nommé ; args[1] = mode d'accès
+    fn handle_mkfifo(&self, path_ptr: *const u8, mode: u16) -> SyscallResult {
+        use crate::fs::{vfs_create_as, FileMode, FileType};
+        let path = match self.read_user_string(path_ptr) {
+            Some(s) => s,
+            None => return SyscallResult::Error(SyscallError::InvalidArgument),
+        };
+        match vfs_create_as(&path, FileMode::new(mode & 0o7777), FileType::Fifo, &current_credentials()) {
+            Ok(_) => SyscallResult::Success(0),
+            Err(e) => SyscallResult::Error(e.into()),
+        }
+    }
+
+    /// statfs() : occupation du système de fichiers monté qui contient le chemin
+    fn handle_statfs(&self, path_ptr: *const u8, buf_ptr: *mut StatFsBuf) -> SyscallResult {
+        let path = match self.read_user_string(path_ptr) {
+            Some(s) => s,
+            None => return SyscallResult::Error(SyscallError::InvalidArgument),
+        };
+        if buf_ptr.is_null() {
+            return SyscallResult::Error(SyscallError::InvalidArgument);
+        }
+        // Le chemin doit exister, pas seulement son point de montage
+        if let Err(e) = crate::fs::vfs_stat(&path) {
+            return SyscallResult::Error(e.into());
+        }
+        match crate::fs::vfs_statfs(&path) {
+            Ok(stat) => match write_user(buf_ptr, StatFsBuf::from_statfs(&stat)) {
+                Ok(()) => SyscallResult::Success(0),
+                Err(e) => SyscallResult::Error(e.into()),
+            },
+            Err(e) => SyscallResult::Error(e.into()),
+        }
+    }
+
+    /// link() : nouveau nom `new` pour le fichier `old`
+    fn handle_link(&self, old_ptr: *const u8, new_ptr: *const u8) -> SyscallResult {
+        let (old_path, new_path) = match (self.read_user_string(old_ptr), self.read_user_string(new_ptr)) {
+            (Some(old), Some(new)) => (old, new),
+            _ => return SyscallResult::Error(SyscallError::InvalidArgument),
+        };
+        match crate::fs::vfs_link_as(&old_path, &new_path, &current_credentials()) {
+            Ok(()) => SyscallResult::Success(0),
+            // Linux : EPERM pour un lien vers un répertoire
+            Err(crate::fs::VfsError::IsDirectory) => SyscallResult::Error(SyscallError::NotPermitted),
+            Err(e) => SyscallResult::Error(e.into()),
+        }
+    }
+
+    /// Chemin et nom d'attribut lus en mémoire utilisateur
+    fn read_xattr_target(&self, path_ptr: *const u8, name_ptr: *const u8) -> Option<(alloc::string::String, alloc::string::String)> {
+        Some((self.read_user_string(path_ptr)?, self.read_user_string(name_ptr)?))
+    }
+
+    /// Copie `data` dans le tampon utilisateur ; taille 0 : seule la taille
+    /// nécessaire est rendue (comme Linux)
+    fn copy_xattr_out(data: &[u8], buf_ptr: *mut u8, size: usize) -> SyscallResult {
+        if size == 0 {
+            return SyscallResult::Success(data.len() as u64);
+        }
+        if buf_ptr.is_null() {
+            return SyscallResult::Error(SyscallError::InvalidArgument);
+        }
+        if data.len() > size {
+            return SyscallResult::Error(SyscallError::OutOfRange);
+        }
+        match copy_to_user(buf_ptr, data) {
+            Ok(()) => SyscallResult::Success(data.len() as u64),
+            Err(e) => SyscallResult::Error(e.into()),
+        }
+    }
+
+    /// setxattr(chemin, nom, valeur, taille, drapeaux)
+    fn handle_setxattr(&self, path_ptr: *const u8, name_ptr: *const u8, value_ptr: *const u8, size: usize, flags: u32) -> SyscallResult {
+        use crate::fs::{XATTR_CREATE, XATTR_REPLACE, XATTR_SIZE_MAX};
+        let (path, name) = match self.read_xattr_target(path_ptr, name_ptr) {
+            Some(target) => target,
+            None => return SyscallResult::Error(SyscallError::InvalidArgument),
+        };
+        if flags & !(XATTR_CREATE | XATTR_REPLACE) != 0 || size > XATTR_SIZE_MAX || (size > 0 && value_ptr.is_null()) {
+            return SyscallResult::Error(SyscallError::InvalidArgument);
+        }
+        let mut value = alloc::vec![0u8; size];
+        if let Err(e) = copy_from_user(&mut value, value_ptr) {
+            return SyscallResult::Error(e.into());
+        }
+        let result = crate::fs::check_xattr_access(&path, &name, &current_credentials(), true)
+            .and_then(|_| crate::fs::vfs_setxattr(&path, &name, &value, flags));
+        match result {
+            Ok(()) => SyscallResult::Success(0),
+            Err(e) => SyscallResult::Error(e.into()),
+        }
+    }
+
+    /// getxattr(chemin, nom, tampon, taille) : taille de la valeur
+    fn handle_getxattr(&self, path_ptr: *const u8, name_ptr: *const u8, buf_ptr: *mut u8, size: usize) -> SyscallResult {
+        let (path, name) = match self.read_xattr_target(path_ptr, name_ptr) {
+            Some(target) => target,
+            None => return SyscallResult::Error(SyscallError::InvalidArgument),
+        };
+        let value = crate::fs::check_xattr_access(&path, &name, &current_credentials(), false)
+            .and_then(|_| crate::fs::vfs_getxattr(&path, &name));
+        match value {
+            Ok(value) => Self::copy_xattr_out(&value, buf_ptr, size),
+            Err(e) => SyscallResult::Error(e.into()),
+        }
+    }
+
+    /// listxattr(chemin, tampon, taille) : noms terminés par NUL ; les
+    /// attributs `trusted.*` ne sont listés que pour root
+    fn handle_listxattr(&self, path_ptr: *const u8, buf_ptr: *mut u8, size: usize) -> SyscallResult {
+        let path = match self.read_user_string(path_ptr) {
+            Some(s) => s,
+            None => return SyscallResult::Error(SyscallError::InvalidArgument),
+        };
+        let cred = current_credentials();
+        let names = crate::fs::vfs_access(&path, &cred, 0).and_then(|_| crate::fs::vfs_listxattr(&path));
+        let names = match names {
+            Ok(names) => names,
+            Err(e) => return SyscallResult::Error(e.into()),
+        };
+        let mut out = alloc::vec::Vec::new();
+        for name in names.iter().filter(|n| cred.capable(CAP_SYS_ADMIN) || !n.starts_with("trusted.")) {
+            out.extend_from_slice(name.as_bytes());
+            out.push(0);
+        }
+        Self::copy_xattr_out(&out, buf_ptr, size)
+    }
+
+    /// removexattr(chemin, nom)
+    fn handle_removexattr(&self, path_ptr: *const u8, name_ptr: *const u8) -> SyscallResult {
+        let (path, name) = match self.read_xattr_target(path_ptr, name_ptr) {
+            Some(target) => target,
+            None => return SyscallResult::Error(SyscallError::InvalidArgument),
+        };
+        let result = crate::fs::check_xattr_access(&path, &name, &current_credentials(), true)
+            .and_then(|_| crate::fs::vfs_removexattr(&path, &name));
+        match result {
+            Ok(()) => SyscallResult::Success(0),
+            Err(e) => SyscallResult::Error(e.into()),
+        }
+    }
+
+    /// getrandom(tampon, taille, drapeaux) : octets du générateur du noyau ;
+    /// EAGAIN avec GRND_NONBLOCK tant qu'il n'est pas prêt
+    fn handle_getrandom(&self, buf_ptr: *mut u8, size: usize, flags: u32) -> SyscallResult {
+        use crate::random::{GRND_INSECURE, GRND_NONBLOCK, GRND_RANDOM};
+        if flags & !(GRND_NONBLOCK | GRND_RANDOM | GRND_INSECURE) != 0
+            || flags & (GRND_RANDOM | GRND_INSECURE) == GRND_RANDOM | GRND_INSECURE
+        {
+            return SyscallResult::Error(SyscallError::InvalidArgument);
+        }
+        if size == 0 {
+            return SyscallResult::Success(0);
+        }
+        if buf_ptr.is_null() {
+            return SyscallResult::Error(SyscallError::InvalidArgument);
+        }
+        // Au plus INT_MAX octets par appel, comme Linux
+        let size = size.min(i32::MAX as usize);
+        if !crate::memory::uaccess::access_ok(buf_ptr as u64, size, true) {
+            return SyscallResult::Error(SyscallError::BadAddress);
+        }
+        // Rempli par morceaux : aucune allocation dépendant de `size`
+        let mut chunk = [0u8; GETRANDOM_CHUNK];
+        let mut done = 0;
+        while done < size {
+            let len = (size - done).min(GETRANDOM_CHUNK);
+            if crate::random::getrandom(&mut chunk[..len], flags).is_err() {
+                return SyscallResult::Error(SyscallError::WouldBlock);
+            }
+            if let Err(e) = copy_to_user(buf_ptr.wrapping_add(done), &chunk[..len]) {
+                // Octets déjà fournis : lecture partielle
+                if done > 0 {
+                    break;
+                }
+                return SyscallResult::Error(e.into());
+            }
+            done += len;
+        }
+        SyscallResult::Success(done as u64)
+    }
+
+    fn handle_seccomp(&self, bitmap_ptr: *const u64, words: usize, flags: u32) -> SyscallResult {
+        use crate::process::seccomp::{SyscallFilter, FILTER_WORDS};
+        if words > FILTER_WORDS {
+            return SyscallResult::Error(SyscallError::InvalidArgument);
+        }
+        let bitmap = match read_user_slice(bitmap_ptr, words) {
+            Ok(bitmap) => bitmap,
+            Err(e) => return SyscallResult::Error(e.into()),
+        };
+        let filter = match SyscallFilter::from_words(&bitmap, flags) {
+            Ok(filter) => filter,
+            Err(_) => return SyscallResult::Error(SyscallError::InvalidArgument),
+        };
+        let process = match crate::process::current_process() {
+            Some(p) => p,
+            None => return SyscallResult::Error(SyscallError::NoSuchProcess),
+        };
+        let mut process = process.lock();
+        // Installation unique : un filtre ne se relâche pas
+        if process.seccomp.is_some() {
+            return SyscallResult::Error(SyscallError::NotPermitted);
+        }
+        process.seccomp = Some(filter);
+        SyscallResult::Success(0)
+    }
+
+    /// Retire définitivement les capacités `caps` du processus courant ;
+    /// renvoie les capacités effectives restantes (capdrop(0) les lit)
+    fn handle_capdrop(&self, caps: Capabilities) -> SyscallResult {
+        if caps & !ALL_CAPS != 0 {
+            return SyscallResult::Error(SyscallError::InvalidArgument);
+        }
+        match crate::process::current_process() {
+            Some(process) => {
+                let mut process = process.lock();
+                process.cred.drop_caps(caps);
+                SyscallResult::Success(process.cred.caps as u64)
+            }
+            None => SyscallResult::Error(SyscallError::NoSuchProcess),
+        }
+    }
+
+    /// Place FS (pointeur de thread) du thread courant à `base`
+    fn handle_set_fs_base(&self, base: u64) -> SyscallResult {
+        if !crate::process::tls::valid_fs_base(base) {
+            return SyscallResult::Error(SyscallError::InvalidArgument);
+        }
+        match crate::scheduler::current_thread() {
+            Some(thread) => {
+                thread.lock().context.fs_base = base;
+                crate::process::tls::load_fs_base(base);
+                SyscallResult::Success(0)
+            }
+            None => SyscallResult::Error(SyscallError::NoSuchProcess),
+        }
+    }
+
+    /// Note le mot (u32) à mettre à zéro quand le thread courant se
+    /// termine ; renvoie son TID
+    fn handle_set_tid_address(&self, tid_ptr: u64) -> SyscallResult {
+        if tid_ptr != 0 && !crate::memory::uaccess::access_ok(tid_ptr, 4, true) {
+            return SyscallResult::Error(SyscallError::BadAddress);
+        }
+        match crate::scheduler::current_thread() {
+            Some(thread) => {
+                let mut thread = thread.lock();
+                thread.clear_child_tid = tid_ptr;
+                SyscallResult::Success(thread.tid)
+            }
+            None => SyscallResult::Error(SyscallError::NoSuchProcess),
+        }
+    }
+
+    fn handle_unlink(&self, path_ptr: *const u8) -> SyscallResult {
+        let path = match self.read_user_string(path_ptr) {
+            Some(s) => s,
+            None => return SyscallResult::Error(SyscallError::InvalidArgument),
+        };
+        match crate::fs::vfs_remove_file_as(&path, &current_credentials()) {
+            Ok(()) => SyscallResult::Success(0),
+            Err(e) => SyscallResult::Error(e.into()),
+        }
+    }
+    
+    fn handle_mkdir(&self, path_ptr: *const u8, mode: u16) -> SyscallResult {
+        let path = match self.read_user_string(path_ptr) {
+            Some(s) => s,
+            None => return SyscallResult::Error(SyscallError::InvalidArgument),
+        };
+        match crate::fs::vfs_mkdir_as(&path, crate::fs::FileMode::new(mode), &current_credentials()) {
+            Ok(_) => SyscallResult::Success(0),
+            Err(e) => SyscallResult::Error(e.into()),
+        }
+    }
+    
+    fn handle_rename(&self, old_ptr: *const u8, new_ptr: *const u8) -> SyscallResult {
+        let (old_path, new_path) = match (self.read_user_string(old_ptr), self.read_user_string(new_ptr)) {
+            (Some(old), Some(new)) => (old, new),
+            _ => return SyscallResult::Error(SyscallError::InvalidArgument),
+        };
+        match crate::fs::vfs_rename_as(&old_path, &new_path, &current_credentials()) {
+            Ok(()) => SyscallResult::Success(0),
+            Err(e) => SyscallResult::Error(e.into()),
+        }
+    }
 }
@@ -0,0 +1,164 @@
+//! Complétion de la ligne de commande (touche Tab)
+//!
+//! Le premier mot est complété parmi les commandes connues, les suivants
+//! (ou tout mot contenant un `/`) parmi les entrées du VFS, relativement
+//! au répertoire courant du shell.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Résultat d'une complétion
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    /// Début (en caractères) du mot complété dans la ligne
+    pub start: usize,
+    /// Mot tel que saisi
+    pub word: String,
+    /// Candidats triés ; les répertoires se terminent par `/`
+    pub candidates: Vec<String>,
+}
+
+/// Candidats pour la ligne `line` (texte avant le curseur)
+pub fn complete(line: &str, commands: &[&str], current_dir: &str) -> Completion {
+    let chars: Vec<char> = line.chars().collect();
+    let start = chars.iter().rposition(|c| c.is_whitespace()).map(|p| p + 1).unwrap_or(0);
+    let word: String = chars[start..].iter().collect();
+    let first_word = chars[..start].iter().all(|c| c.is_whitespace());
+
+    let mut candidates = if first_word && !word.contains('/') {
+        commands
+            .iter()
+            .filter(|c| c.starts_with(word.as_str()))
+            .map(|c| String::from(*c))
+            .collect()
+    } else {
+        complete_path(&word, current_dir)
+    };
+    candidates.sort();
+    candidates.dedup();
+
+    Completion { start, word, candidates }
+}
+
+/// Entrées du VFS commençant par le dernier composant de `word`
+fn complete_path(word: &str, current_dir: &str) -> Vec<String> {
+    let (dir_part, prefix) = match word.rfind('/') {
+        Some(pos) => (&word[..pos + 1], &word[pos + 1..]),
+        None => ("", word),
+    };
+
+    let dir = if dir_part.starts_with('/') {
+        String::from(dir_part)
+    } else if current_dir == "/" {
+        format!("/{}", dir_part)
+    } else {
+        format!("{}/{}", current_dir, dir_part)
+    };
+
+    let names = match mini_os::fs::vfs_ls(&dir) {
+        Ok(names) => names,
+        Err(_) => return Vec::new(),
+    };
+
+    names
+        .into_iter()
+        // Fichiers cachés proposés seulement si le préfixe commence par '.'
+        .filter(|n| n.starts_with(prefix) && (!n.starts_with('.') || prefix.starts_with('.')))
+        .map(|n| {
+            let full = format!("{}{}", dir, n);
+            if mini_os::fs::is_dir(&full) {
+                format!("{}{}/", dir_part, n)
+            } else {
+                format!("{}{}", dir_part, n)
+            }
+        })
+        .collect()
+}
+
+/// Plus long préfixe commun des candidats
+pub fn common_prefix(candidates: &[String]) -> String {
+    let mut prefix: Vec<char> = match candidates.first() {
+        Some(first) => first.chars().collect(),
+        None => return String::new(),
+    };
+    for candidate in &candidates[1..] {
+        let common = prefix.iter().zip(candidate.chars()).take_while(|(a, b)| **a == *b).count();
+        prefix.truncate(common);
+    }
+    prefix.into_iter().collect()
+}
+
+/// Nom affiché d'un candidat (dernier composant du chemin)
+fn display_name(candidate: &str) -> &str {
+    let trimmed = candidate.trim_end_matches('/');
+    match trimmed.rfind('/') {
+        Some(pos) => &candidate[pos + 1..],
+        None => candidate,
+    }
+}
+
+/// Met en colonnes les candidats pour un terminal de `width` caractères.
+/// Les candidats sont rangés colonne par colonne, comme `ls`.
+pub fn format_columns(candidates: &[String], width: usize) -> String {
+    if candidates.is_empty() {
+        return String::new();
+    }
+
+    let names: Vec<&str> = candidates.iter().map(|c| display_name(c)).collect();
+    let col_width = names.iter().map(|n| n.chars().count()).max().unwrap_or(0) + 2;
+    let cols = (width / col_width).max(1);
+    let rows = (names.len() + cols - 1) / cols;
+
+    let mut out = String::new();
+    for row in 0..rows {
+        for col in 0..cols {
+            let name = match names.get(col * rows + row) {
+                Some(name) => name,
+                None => break,
+            };
+            out.push_str(name);
+            // Pas d'espaces après la dernière colonne de la ligne
+            if col + 1 < cols && (col + 1) * rows + row < names.len() {
+                for _ in name.chars().count()..col_width {
+                    out.push(' ');
+                }
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMMANDS: &[&str] = &["cat", "cd", "clear", "cp", "echo"];
+
+    #[test_case]
+    fn test_complete_command_names() {
+        let c = complete("c", COMMANDS, "/");
+        assert_eq!(c.start, 0);
+        assert_eq!(c.candidates, ["cat", "cd", "clear", "cp"]);
+        assert_eq!(common_prefix(&c.candidates), "c");
+
+        let c = complete("  ec", COMMANDS, "/");
+        assert_eq!(c.start, 2);
+        assert_eq!(c.candidates, ["echo"]);
+    }
+
+    #[test_case]
+    fn test_common_prefix() {
+        let candidates: [String; 3] = ["home/".into(), "hosts".into(), "hot".into()];
+        assert_eq!(common_prefix(&candidates), "ho");
+        assert_eq!(common_prefix(&[]), "");
+    }
+
+    #[test_case]
+    fn test_format_columns() {
+        let candidates: Vec<String> = ["a", "bb", "ccc", "/dir/d/", "e"].iter().map(|s| String::from(*s)).collect();
+        // Largeur de colonne 5 : 2 colonnes sur 12 caractères, remplissage vertical
+        assert_eq!(format_columns(&candidates, 12), "a    d/\nbb   e\nccc\n");
+        assert_eq!(format_columns(&candidates, 3), "a\nbb\nccc\nd/\ne\n");
+    }
+}
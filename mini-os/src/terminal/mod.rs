@@ -1,6 +1,13 @@
+pub mod completion;
+
 use alloc::string::String;
 use alloc::vec::Vec;
-use crate::vga_buffer::WRITER;
+use crate::vga_buffer::{Utf8Decoder, WRITER, SHELL_VT};
+
+/// Touches de contrôle de la recherche dans l'historique
+const CTRL_G: u8 = 0x07;
+const CTRL_R: u8 = 0x12;
+const ESC: u8 = 0x1B;
 
 /// Couleurs disponibles
 #[derive(Debug, Clone, Copy)]
@@ -15,12 +22,26 @@ pub enum Color {
     White = 7,
 }
 
+/// État de la recherche incrémentale arrière (Ctrl-R)
+struct ReverseSearch {
+    query: String,
+    /// Entrée de l'historique actuellement retenue
+    match_index: Option<usize>,
+    /// Ligne en cours avant la recherche (restaurée en cas d'abandon)
+    saved_line: Vec<char>,
+}
+
 /// Éditeur de ligne pour le terminal
 pub struct LineEditor {
     buffer: Vec<char>,
     cursor_pos: usize,
     history: Vec<String>,
     history_index: usize,
+    /// Ligne lors du dernier Tab sans effet (un second Tab liste les candidats)
+    last_tab_line: Option<String>,
+    search: Option<ReverseSearch>,
+    /// Caractère UTF-8 en cours de réception (`insert_byte`)
+    utf8: Utf8Decoder,
 }
 
 impl LineEditor {
@@ -31,7 +52,46 @@ impl LineEditor {
             cursor_pos: 0,
             history: Vec::new(),
             history_index: 0,
+            last_tab_line: None,
+            search: None,
+            utf8: Utf8Decoder::new(),
+        }
+    }
+
+    /// Complète le mot sous le curseur (touche Tab).
+    /// Renvoie la liste des candidats en colonnes lors d'un second Tab
+    /// sans progression possible.
+    pub fn complete(&mut self, commands: &[&str], current_dir: &str, width: usize) -> Option<String> {
+        let before: String = self.buffer[..self.cursor_pos].iter().collect();
+        let result = completion::complete(&before, commands, current_dir);
+
+        let replacement = match result.candidates.len() {
+            0 => None,
+            1 => {
+                let only = &result.candidates[0];
+                // Un répertoire reste ouvert pour continuer la complétion
+                Some(if only.ends_with('/') { only.clone() } else { format!("{} ", only) })
+            }
+            _ => {
+                let prefix = completion::common_prefix(&result.candidates);
+                if prefix.chars().count() > result.word.chars().count() { Some(prefix) } else { None }
+            }
+        };
+
+        if let Some(text) = replacement {
+            self.buffer.splice(result.start..self.cursor_pos, text.chars());
+            self.cursor_pos = result.start + text.chars().count();
+            self.last_tab_line = None;
+            return None;
+        }
+
+        let line = self.get_line();
+        if result.candidates.len() > 1 && self.last_tab_line.as_ref() == Some(&line) {
+            self.last_tab_line = None;
+            return Some(completion::format_columns(&result.candidates, width));
         }
+        self.last_tab_line = Some(line);
+        None
     }
 
     /// Ajoute un caractère à la position du curseur
@@ -42,6 +102,15 @@ impl LineEditor {
         }
     }
 
+    /// Ajoute un octet lu sur le terminal ; le buffer étant fait de
+    /// caractères, curseur et backspace avancent d'un caractère entier
+    /// même quand il occupe plusieurs octets
+    pub fn insert_byte(&mut self, byte: u8) {
+        if let Some(c) = self.utf8.feed(byte) {
+            self.insert_char(c);
+        }
+    }
+
     /// Supprime le caractère avant le curseur (backspace)
     pub fn backspace(&mut self) {
         if self.cursor_pos > 0 {
@@ -116,6 +185,98 @@ impl LineEditor {
         }
     }
 
+    /// Remplace l'historique (ex : historique persistant du shell)
+    pub fn set_history(&mut self, history: &[String]) {
+        self.history = history.to_vec();
+        self.history_index = self.history.len();
+    }
+
+    /// Entrée la plus récente contenant `query`, strictement avant `before`
+    fn find_in_history(&self, query: &str, before: usize) -> Option<usize> {
+        (0..before.min(self.history.len())).rev().find(|&i| self.history[i].contains(query))
+    }
+
+    /// Affiche l'entrée `index` dans le buffer
+    fn show_match(&mut self, index: usize) {
+        self.buffer = self.history[index].chars().collect();
+        self.cursor_pos = self.buffer.len();
+    }
+
+    /// Ctrl-R : démarre la recherche, ou passe à la correspondance plus ancienne
+    pub fn reverse_search(&mut self) {
+        let search = match self.search.take() {
+            None => ReverseSearch { query: String::new(), match_index: None, saved_line: self.buffer.clone() },
+            Some(mut search) => {
+                let before = search.match_index.unwrap_or(self.history.len());
+                if let Some(i) = self.find_in_history(&search.query, before) {
+                    search.match_index = Some(i);
+                    self.show_match(i);
+                }
+                search
+            }
+        };
+        self.search = Some(search);
+    }
+
+    /// Ajoute un caractère à la requête ; la correspondance courante est
+    /// conservée tant qu'elle contient encore la requête
+    pub fn search_insert_char(&mut self, c: char) {
+        if let Some(mut search) = self.search.take() {
+            search.query.push(c);
+            let from = search.match_index.map(|i| i + 1).unwrap_or(self.history.len());
+            if let Some(i) = self.find_in_history(&search.query, from) {
+                search.match_index = Some(i);
+                self.show_match(i);
+            }
+            self.search = Some(search);
+        }
+    }
+
+    /// Ajoute à la requête un octet lu sur le terminal (UTF-8)
+    pub fn search_insert_byte(&mut self, byte: u8) {
+        if let Some(c) = self.utf8.feed(byte) {
+            self.search_insert_char(c);
+        }
+    }
+
+    /// Retire le dernier caractère de la requête et recherche à nouveau
+    pub fn search_backspace(&mut self) {
+        if let Some(mut search) = self.search.take() {
+            search.query.pop();
+            search.match_index = self.find_in_history(&search.query, self.history.len());
+            if let Some(i) = search.match_index {
+                self.show_match(i);
+            }
+            self.search = Some(search);
+        }
+    }
+
+    /// Valide la recherche : la correspondance devient la ligne éditée
+    pub fn accept_search(&mut self) {
+        self.search = None;
+    }
+
+    /// Abandonne la recherche et restaure la ligne d'origine
+    pub fn cancel_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            self.buffer = search.saved_line;
+            self.cursor_pos = self.buffer.len();
+        }
+    }
+
+    /// Une recherche Ctrl-R est-elle en cours ?
+    pub fn is_searching(&self) -> bool {
+        self.search.is_some()
+    }
+
+    /// Invite de recherche, style readline
+    pub fn search_prompt(&self) -> Option<String> {
+        self.search.as_ref().map(|search| {
+            let status = if search.match_index.is_none() && !search.query.is_empty() { "failed " } else { "" };
+            format!("({}reverse-i-search)`{}': {}", status, search.query, self.get_line())
+        })
+    }
+
     /// Retourne le contenu du buffer sous forme de String
     pub fn get_line(&self) -> String {
         self.buffer.iter().collect()
@@ -123,32 +284,33 @@ impl LineEditor {
 
     /// Affiche le buffer avec le curseur
     pub fn display(&self, prompt: &str) {
-        WRITER.lock().write_string(prompt);
+        WRITER.lock().write_vt(SHELL_VT, prompt);
         
         for (i, &c) in self.buffer.iter().enumerate() {
             if i == self.cursor_pos {
-                WRITER.lock().write_string("█");
+                WRITER.lock().write_vt(SHELL_VT, "█");
             }
-            WRITER.lock().write_string(&format!("{}", c));
+            WRITER.lock().write_vt(SHELL_VT, &format!("{}", c));
         }
         
         if self.cursor_pos == self.buffer.len() {
-            WRITER.lock().write_string("█");
+            WRITER.lock().write_vt(SHELL_VT, "█");
         }
     }
 
     /// Redessine la ligne
     pub fn redraw(&self, prompt: &str) {
         // Effacer la ligne actuelle
-        WRITER.lock().write_string("\r");
+        WRITER.lock().write_vt(SHELL_VT, "\r");
         
         // Afficher le prompt
-        WRITER.lock().write_string(prompt);
+        WRITER.lock().write_vt(SHELL_VT, prompt);
         
-        // Afficher le buffer
+        // Afficher le buffer, puis effacer la fin de l'ancienne ligne
         for c in &self.buffer {
-            WRITER.lock().write_string(&format!("{}", c));
+            WRITER.lock().write_vt(SHELL_VT, &format!("{}", c));
         }
+        WRITER.lock().write_vt(SHELL_VT, "\x1b[K");
     }
 }
 
@@ -173,18 +335,18 @@ impl Terminal {
 
     /// Écrit une chaîne de caractères
     pub fn write_string(&self, s: &str) {
-        WRITER.lock().write_string(s);
+        WRITER.lock().write_vt(SHELL_VT, s);
     }
 
     /// Écrit une chaîne de caractères avec une couleur
     pub fn write_colored(&self, s: &str, _color: Color) {
         // TODO: Implémenter la coloration
-        WRITER.lock().write_string(s);
+        WRITER.lock().write_vt(SHELL_VT, s);
     }
 
     /// Efface l'écran
     pub fn clear_screen(&self) {
-        WRITER.lock().write_string("\x1b[2J\x1b[H");
+        WRITER.lock().write_vt(SHELL_VT, "\x1b[2J\x1b[H");
     }
 
     /// Définit la couleur courante
@@ -192,44 +354,116 @@ impl Terminal {
         self.current_color = color;
     }
 
-    /// Lit une ligne depuis l'entrée utilisateur
-    pub fn read_line(&mut self, prompt: &str) -> String {
+    /// Lit une ligne sur le terminal du shell ; Tab complète avec
+    /// `commands` et les fichiers de `current_dir`
+    pub fn read_line(&mut self, prompt: &str, commands: &[&str], current_dir: &str) -> String {
         self.line_editor.clear_line();
         self.write_string(prompt);
-        
-        // TODO: Implémenter la lecture interactive
-        // Pour l'instant, retourner une ligne vide
-        String::new()
+
+        // L'éditeur affiche lui-même la ligne en cours
+        mini_os::tty::set_echo(SHELL_VT, false);
+        let mut byte = [0u8; 1];
+        let line = loop {
+            if mini_os::tty::read(SHELL_VT, &mut byte) == 0 {
+                x86_64::instructions::hlt();
+                continue;
+            }
+            if let Some(line) = self.handle_byte(byte[0], prompt, commands, current_dir) {
+                break line;
+            }
+        };
+        mini_os::tty::set_echo(SHELL_VT, true);
+        line
+    }
+
+    /// Traite un octet reçu du clavier ; renvoie la ligne validée par Entrée
+    pub fn handle_byte(&mut self, byte: u8, prompt: &str, commands: &[&str], current_dir: &str) -> Option<String> {
+        if self.line_editor.is_searching() {
+            match byte {
+                // Entrée valide la correspondance et exécute la ligne
+                b'\n' | b'\r' => self.line_editor.accept_search(),
+                CTRL_R => {
+                    self.line_editor.reverse_search();
+                    self.redraw_search();
+                    return None;
+                }
+                CTRL_G | ESC => {
+                    self.line_editor.cancel_search();
+                    self.line_editor.redraw(prompt);
+                    return None;
+                }
+                0x08 | 0x7F => {
+                    self.line_editor.search_backspace();
+                    self.redraw_search();
+                    return None;
+                }
+                byte if byte < 0x20 => {
+                    // Autre touche de contrôle : on garde la ligne trouvée
+                    self.line_editor.accept_search();
+                    self.line_editor.redraw(prompt);
+                    return None;
+                }
+                byte => {
+                    self.line_editor.search_insert_byte(byte);
+                    self.redraw_search();
+                    return None;
+                }
+            }
+        }
+
+        match byte {
+            b'\n' | b'\r' => {
+                let line = self.line_editor.get_line();
+                self.line_editor.clear_line();
+                self.write_string("\n");
+                return Some(line);
+            }
+            b'\t' => {
+                self.complete(prompt, commands, current_dir);
+                return None;
+            }
+            CTRL_R => {
+                self.line_editor.reverse_search();
+                self.redraw_search();
+                return None;
+            }
+            0x08 | 0x7F => self.line_editor.backspace(),
+            // Autres caractères de contrôle ignorés
+            byte if byte < 0x20 => return None,
+            byte => self.line_editor.insert_byte(byte),
+        }
+        self.line_editor.redraw(prompt);
+        None
     }
 
     /// Affiche une ligne avec un saut à la ligne
     pub fn println(&self, s: &str) {
-        WRITER.lock().write_string(s);
-        WRITER.lock().write_string("\n");
+        WRITER.lock().write_vt(SHELL_VT, s);
+        WRITER.lock().write_vt(SHELL_VT, "\n");
     }
 
     /// Affiche une ligne d'erreur
     pub fn print_error(&self, s: &str) {
-        WRITER.lock().write_string("\x1b[31m");
-        WRITER.lock().write_string("Erreur: ");
-        WRITER.lock().write_string(s);
-        WRITER.lock().write_string("\x1b[0m\n");
+        WRITER.lock().write_vt(SHELL_VT, "\x1b[31m");
+        WRITER.lock().write_vt(SHELL_VT, "Erreur: ");
+        WRITER.lock().write_vt(SHELL_VT, s);
+        WRITER.lock().write_vt(SHELL_VT, "\x1b[0m\n");
     }
 
     /// Affiche une ligne d'avertissement
     pub fn print_warning(&self, s: &str) {
-        WRITER.lock().write_string("\x1b[33m");
-        WRITER.lock().write_string("Avertissement: ");
-        WRITER.lock().write_string(s);
-        WRITER.lock().write_string("\x1b[0m\n");
+        WRITER.lock().write_vt(SHELL_VT, "\x1b[33m");
+        WRITER.lock().write_vt(SHELL_VT, "Avertissement: ");
+        WRITER.lock().write_vt(SHELL_VT, s);
+        WRITER.lock().write_vt(SHELL_VT, "\x1b[0m\n");
     }
 
     /// Affiche une ligne d'information
     pub fn print_info(&self, s: &str) {
-        WRITER.lock().write_string("\x1b[32m");
-        WRITER.lock().write_string("Info: ");
-        WRITER.lock().write_string(s);
-        WRITER.lock().write_string("\x1b[0m\n");
+        WRITER.lock().write_vt(SHELL_VT, "\x1b[32m");
+        WRITER.lock().write_vt(SHELL_VT, "Info: ");
+        WRITER.lock().write_vt(SHELL_VT, s);
+        WRITER.lock().write_vt(SHELL_VT, "\x1b[0m\n");
     }
 
     /// Obtient la largeur du terminal
@@ -242,6 +476,25 @@ impl Terminal {
         self.height
     }
 
+    /// Gère la touche Tab : complète la ligne, ou liste les candidats
+    /// au second appui puis réaffiche le prompt
+    pub fn complete(&mut self, prompt: &str, commands: &[&str], current_dir: &str) {
+        if let Some(listing) = self.line_editor.complete(commands, current_dir, self.width) {
+            self.write_string("\n");
+            self.write_string(&listing);
+        }
+        self.line_editor.redraw(prompt);
+    }
+
+    /// Réaffiche la ligne de recherche Ctrl-R
+    pub fn redraw_search(&self) {
+        if let Some(prompt) = self.line_editor.search_prompt() {
+            self.write_string("\r");
+            self.write_string(&prompt);
+            self.write_string("\x1b[K");
+        }
+    }
+
     /// Obtient l'éditeur de ligne
     pub fn line_editor(&mut self) -> &mut LineEditor {
         &mut self.line_editor
@@ -279,6 +532,93 @@ mod tests {
         assert_eq!(editor.cursor_pos, 1);
     }
 
+    #[test_case]
+    fn test_multibyte_editing() {
+        let mut editor = LineEditor::new();
+        for &byte in "aé─".as_bytes() {
+            editor.insert_byte(byte);
+        }
+        assert_eq!(editor.get_line(), "aé─");
+        assert_eq!(editor.cursor_pos, 3);
+        editor.move_left();
+        editor.backspace();
+        assert_eq!(editor.get_line(), "a─");
+        editor.move_end();
+        editor.backspace();
+        assert_eq!(editor.get_line(), "a");
+    }
+
+    #[test_case]
+    fn test_tab_completion() {
+        let commands = ["cat", "cd", "clear"];
+        let mut editor = LineEditor::new();
+        editor.insert_char('c');
+        editor.insert_char('l');
+        assert_eq!(editor.complete(&commands, "/", 80), None);
+        assert_eq!(editor.get_line(), "clear ");
+
+        // Ambigu : premier Tab sans effet, second Tab liste les candidats
+        editor.clear_line();
+        editor.insert_char('c');
+        assert_eq!(editor.complete(&commands, "/", 80), None);
+        assert_eq!(editor.get_line(), "c");
+        assert_eq!(editor.complete(&commands, "/", 80).as_deref(), Some("cat    cd     clear\n"));
+    }
+
+    #[test_case]
+    fn test_reverse_search() {
+        let mut editor = LineEditor::new();
+        editor.set_history(&["ls /home".into(), "cat notes".into(), "ls /dev".into()]);
+        editor.insert_char('x');
+
+        editor.reverse_search();
+        editor.search_insert_char('l');
+        editor.search_insert_char('s');
+        assert_eq!(editor.get_line(), "ls /dev");
+        // Ctrl-R à nouveau : correspondance plus ancienne
+        editor.reverse_search();
+        assert_eq!(editor.get_line(), "ls /home");
+        editor.accept_search();
+        assert!(!editor.is_searching());
+        assert_eq!(editor.get_line(), "ls /home");
+
+        editor.reverse_search();
+        editor.search_insert_char('z');
+        assert!(editor.search_prompt().unwrap().starts_with("(failed reverse-i-search)"));
+        editor.cancel_search();
+        assert_eq!(editor.get_line(), "ls /home");
+    }
+
+    #[test_case]
+    fn test_tab_completes_typed_line() {
+        let commands = ["cat", "cd", "clear"];
+        let mut terminal = Terminal::new();
+        for &byte in b"cl\t" {
+            assert_eq!(terminal.handle_byte(byte, "/> ", &commands, "/"), None);
+        }
+        assert_eq!(terminal.handle_byte(b'\n', "/> ", &commands, "/").as_deref(), Some("clear "));
+        assert_eq!(terminal.line_editor().get_line(), "");
+    }
+
+    #[test_case]
+    fn test_ctrl_r_recalls_history() {
+        let mut terminal = Terminal::new();
+        terminal.line_editor().set_history(&["ls /home".into(), "cat notes".into(), "ls /dev".into()]);
+
+        // Ctrl-R, « ls », Ctrl-R : correspondance plus ancienne, validée par Entrée
+        for &byte in b"\x12ls\x12" {
+            assert_eq!(terminal.handle_byte(byte, "/> ", &[], "/"), None);
+        }
+        assert_eq!(terminal.handle_byte(b'\r', "/> ", &[], "/").as_deref(), Some("ls /home"));
+
+        // Ctrl-G abandonne la recherche et rend la ligne tapée
+        for &byte in b"pw\x12cat\x07d" {
+            assert_eq!(terminal.handle_byte(byte, "/> ", &[], "/"), None);
+        }
+        assert!(!terminal.line_editor().is_searching());
+        assert_eq!(terminal.handle_byte(b'\n', "/> ", &[], "/").as_deref(), Some("pwd"));
+    }
+
     #[test_case]
     fn test_terminal_creation() {
         let terminal = Terminal::new();
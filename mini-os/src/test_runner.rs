@@ -1,40 +1,311 @@
 /// Test runner pour l'environnement kernel QEMU
-/// 
+///
 /// Ce module fournit l'infrastructure pour exécuter les tests unitaires
 /// dans un environnement bare-metal QEMU avec sortie série et exit automatique.
+///
+/// - Sortie : le périphérique `isa-debug-exit` (port 0xf4) termine QEMU
+///   avec le code du runner ; il doit être présent sur la ligne de QEMU
+///   (`-device isa-debug-exit,iobase=0xf4,iosize=0x04`).
+/// - Délais : chaque test dispose d'un délai en ticks du timer, vérifié à
+///   chaque interruption d'horloge ; un test bloqué termine la série.
+/// - `should_panic` : un test enveloppé dans `ShouldPanic` réussit s'il
+///   panique. Sans déroulement de pile, le gestionnaire de panique reprend
+///   la série au test suivant, repartie du haut de la pile du runner.
+/// - Fils : `run_threads` lance plusieurs fils de test, chacun sur sa
+///   pile, qui se relaient à chaque tick d'horloge (le noyau n'a pas de
+///   changement de contexte réel entre threads).
 
+use alloc::vec::Vec;
+use core::arch::global_asm;
 use core::panic::PanicInfo;
+use core::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use spin::Mutex;
+use x86_64::instructions::interrupts::without_interrupts;
+use crate::process::kstack::KernelStack;
+
+/// Délai par défaut d'un test (10 s ; `init` programme le PIT à `TIMER_HZ`)
+pub const DEFAULT_TIMEOUT_TICKS: u64 = 10 * crate::scheduler::TIMER_HZ;
 
 /// Trait pour les tests exécutables
 pub trait Testable {
+    /// Nom affiché sur le port série
+    fn name(&self) -> &'static str;
     fn run(&self);
+    /// Le test doit paniquer pour réussir
+    fn should_panic(&self) -> bool {
+        false
+    }
+    /// Délai maximal, en ticks du timer
+    fn timeout(&self) -> u64 {
+        DEFAULT_TIMEOUT_TICKS
+    }
 }
 
 impl<T: Fn()> Testable for T {
+    fn name(&self) -> &'static str {
+        core::any::type_name::<T>()
+    }
+
     fn run(&self) {
-        serial_print!("{}...\t", core::any::type_name::<T>());
         self();
-        serial_println!("[ok]");
     }
 }
 
+/// Test qui réussit en paniquant :
+/// `#[test_case] const TEST_X: ShouldPanic<fn()> = ShouldPanic(test_x);`
+pub struct ShouldPanic<F>(pub F);
+
+impl<F: Fn()> Testable for ShouldPanic<F> {
+    fn name(&self) -> &'static str {
+        core::any::type_name::<F>()
+    }
+
+    fn run(&self) {
+        (self.0)();
+    }
+
+    fn should_panic(&self) -> bool {
+        true
+    }
+}
+
+/// Test au délai propre (en ticks) :
+/// `#[test_case] const TEST_X: Timeout<fn()> = Timeout(500, test_x);`
+pub struct Timeout<F>(pub u64, pub F);
+
+impl<F: Fn()> Testable for Timeout<F> {
+    fn name(&self) -> &'static str {
+        core::any::type_name::<F>()
+    }
+
+    fn run(&self) {
+        (self.1)();
+    }
+
+    fn timeout(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Série en cours, reprise par le gestionnaire de panique
+struct Suite(*const [&'static dyn Testable]);
+
+// Écrite une seule fois, avant le premier test
+unsafe impl Send for Suite {}
+
+static SUITE: Mutex<Option<Suite>> = Mutex::new(None);
+/// Index du test en cours
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+/// Le test en cours doit paniquer
+static EXPECT_PANIC: AtomicBool = AtomicBool::new(false);
+/// Échéance du test en cours (ticks), 0 : aucune
+static DEADLINE: AtomicU64 = AtomicU64::new(0);
+/// Haut de la pile sur laquelle tourne la série (0 : pas encore lancée)
+static RUNNER_STACK_TOP: AtomicU64 = AtomicU64::new(0);
+
+/// Pile de la série : assez profonde pour les tests du VFS et du réseau
+const RUNNER_STACK_SIZE: usize = 256 * 1024;
+
 /// Runner de tests principal
-/// 
+///
 /// Exécute tous les tests fournis et affiche les résultats sur le port série.
 /// En cas de succès, quitte QEMU avec le code de succès.
 pub fn test_runner(tests: &[&dyn Testable]) {
     serial_println!("Running {} tests", tests.len());
     serial_println!("================");
-    
-    for test in tests {
+
+    // Le runner ne rend jamais la main : `tests`, construit par
+    // `test_main`, reste valide jusqu'à la sortie de QEMU
+    let tests: &'static [&'static dyn Testable] = unsafe { core::mem::transmute(tests) };
+    *SUITE.lock() = Some(Suite(tests));
+
+    // Pile propre à la série, jamais libérée : une panique attendue y
+    // abandonne ses cadres et la série repart de son sommet
+    let stack = KernelStack::new(RUNNER_STACK_SIZE);
+    RUNNER_STACK_TOP.store(stack.top(), Ordering::SeqCst);
+    core::mem::forget(stack);
+    run_on_runner_stack(0);
+}
+
+/// Reprend la série au test `first`, du haut de la pile du runner
+fn run_on_runner_stack(first: usize) -> ! {
+    let top = RUNNER_STACK_TOP.load(Ordering::SeqCst);
+    if top == 0 {
+        exit_qemu(QemuExitCode::Failed);
+    }
+    // RSP aligné sur 16 avant le `call` : ABI respectée à l'entrée
+    unsafe {
+        core::arch::asm!(
+            "mov rsp, {top}",
+            "xor ebp, ebp",
+            "call {entry}",
+            "ud2",
+            top = in(reg) top,
+            entry = sym run_from_entry,
+            in("rdi") first,
+            options(noreturn),
+        );
+    }
+}
+
+extern "C" fn run_from_entry(first: usize) -> ! {
+    run_from(first)
+}
+
+/// Exécute la série à partir du test `first`, puis quitte QEMU
+fn run_from(first: usize) -> ! {
+    let tests = match SUITE.lock().as_ref() {
+        Some(suite) => unsafe { &*suite.0 },
+        None => exit_qemu(QemuExitCode::Failed),
+    };
+
+    for (index, test) in tests.iter().enumerate().skip(first) {
+        serial_print!("{}...\t", test.name());
+        CURRENT.store(index, Ordering::SeqCst);
+        EXPECT_PANIC.store(test.should_panic(), Ordering::SeqCst);
+        DEADLINE.store(crate::scheduler::ticks() + test.timeout().max(1), Ordering::SeqCst);
+
         test.run();
+
+        DEADLINE.store(0, Ordering::SeqCst);
+        if test.should_panic() {
+            serial_println!("[failed]\n");
+            serial_println!("Error: le test n'a pas paniqué\n");
+            exit_qemu(QemuExitCode::Failed);
+        }
+        serial_println!("[ok]");
     }
-    
+
     serial_println!("================");
     serial_println!("All tests passed!");
     exit_qemu(QemuExitCode::Success);
 }
 
+/// Appelé à chaque tick d'horloge : termine la série si le test en cours
+/// a dépassé son délai
+pub fn timer_tick() {
+    let deadline = DEADLINE.load(Ordering::Relaxed);
+    if deadline != 0 && crate::scheduler::ticks() >= deadline {
+        DEADLINE.store(0, Ordering::SeqCst);
+        serial_println!("[timeout]\n");
+        serial_println!("Error: délai dépassé (test {})\n", CURRENT.load(Ordering::SeqCst));
+        exit_qemu(QemuExitCode::Timeout);
+    }
+}
+
+/// Fil de test : RSP sauvegardé quand il perd la main
+struct TestThread {
+    rsp: u64,
+    done: bool,
+}
+
+/// Fils en cours ; l'entrée 0 est le contexte du test qui les a lancés.
+/// Pris uniquement interruptions masquées (aussi depuis le tick).
+static THREADS: Mutex<Vec<TestThread>> = Mutex::new(Vec::new());
+/// Fil qui a la main
+static RUNNING: AtomicUsize = AtomicUsize::new(0);
+/// Des fils sont lancés : le tick les fait tourner
+static THREADS_ACTIVE: AtomicBool = AtomicBool::new(false);
+/// Corps des fils
+static THREAD_BODY: Mutex<Option<fn(usize)>> = Mutex::new(None);
+
+global_asm!(
+    ".global test_runner_switch",
+    // rdi : où ranger le RSP courant, rsi : RSP du fil repris
+    "test_runner_switch:",
+    "push rbp",
+    "push rbx",
+    "push r12",
+    "push r13",
+    "push r14",
+    "push r15",
+    "mov [rdi], rsp",
+    "mov rsp, rsi",
+    "pop r15",
+    "pop r14",
+    "pop r13",
+    "pop r12",
+    "pop rbx",
+    "pop rbp",
+    "ret",
+);
+
+extern "C" {
+    fn test_runner_switch(save: *mut u64, next: u64);
+}
+
+/// Premier lancement d'un fil (atteint par le `ret` de
+/// `test_runner_switch`, depuis le tick, interruptions masquées)
+extern "C" fn thread_entry() -> ! {
+    let index = RUNNING.load(Ordering::SeqCst);
+    let body = THREAD_BODY.lock().expect("fil de test sans corps");
+    x86_64::instructions::interrupts::enable();
+    body(index - 1);
+    without_interrupts(|| THREADS.lock()[index].done = true);
+    // Plus jamais repris : le tick passe à un autre fil
+    loop {
+        x86_64::instructions::hlt();
+    }
+}
+
+/// Appelé à chaque tick d'horloge, après l'acquittement : donne la main au
+/// fil de test suivant
+pub fn preempt() {
+    if !THREADS_ACTIVE.load(Ordering::Relaxed) {
+        return;
+    }
+    let (save, next) = {
+        let mut threads = THREADS.lock();
+        let current = RUNNING.load(Ordering::SeqCst);
+        let count = threads.len();
+        let next = match (1..count).map(|i| (current + i) % count).find(|&i| !threads[i].done) {
+            Some(next) => next,
+            None => return,
+        };
+        RUNNING.store(next, Ordering::SeqCst);
+        // Vec figé tant que les fils tournent : le pointeur reste valide
+        (&mut threads[current].rsp as *mut u64, threads[next].rsp)
+    };
+    unsafe { test_runner_switch(save, next) };
+}
+
+/// Lance `count` fils exécutant `body(0)` .. `body(count - 1)`, préemptés
+/// à chaque tick, et attend qu'ils aient tous fini
+pub fn run_threads(count: usize, body: fn(usize)) {
+    const THREAD_STACK_SIZE: usize = 32 * 1024;
+    let stacks: Vec<KernelStack> = (0..count).map(|_| KernelStack::new(THREAD_STACK_SIZE)).collect();
+
+    without_interrupts(|| {
+        let mut threads = THREADS.lock();
+        threads.clear();
+        threads.push(TestThread { rsp: 0, done: false });
+        for stack in &stacks {
+            // Six registres nuls puis l'adresse de `thread_entry`, dépilés
+            // par `test_runner_switch` ; RSP ≡ 8 (mod 16) à l'entrée
+            let top = stack.top() & !0xF;
+            let rsp = top - 8 * 8;
+            unsafe {
+                core::ptr::write_bytes(rsp as *mut u64, 0, 8);
+                ((rsp + 6 * 8) as *mut u64).write(thread_entry as u64);
+            }
+            threads.push(TestThread { rsp, done: false });
+        }
+        *THREAD_BODY.lock() = Some(body);
+        RUNNING.store(0, Ordering::SeqCst);
+        THREADS_ACTIVE.store(true, Ordering::SeqCst);
+    });
+
+    while without_interrupts(|| THREADS.lock().iter().skip(1).any(|t| !t.done)) {
+        x86_64::instructions::hlt();
+    }
+
+    without_interrupts(|| {
+        THREADS_ACTIVE.store(false, Ordering::SeqCst);
+        THREADS.lock().clear();
+    });
+    drop(stacks);
+}
+
 /// Codes de sortie pour QEMU
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u32)]
@@ -43,30 +314,119 @@ pub enum QemuExitCode {
     Success = 0x10,
     /// Au moins un test a échoué
     Failed = 0x11,
+    /// Un test a dépassé son délai
+    Timeout = 0x12,
 }
 
 /// Quitte QEMU avec un code de sortie spécifique
-/// 
+///
 /// Utilise le port ISA debug exit (0xf4) pour signaler à QEMU de se terminer.
 /// Le code de sortie réel de QEMU sera (exit_code << 1) | 1.
 pub fn exit_qemu(exit_code: QemuExitCode) -> ! {
     use x86_64::instructions::port::Port;
-    
+
     unsafe {
         let mut port = Port::new(0xf4);
         port.write(exit_code as u32);
     }
-    
+
     // Si QEMU n'est pas configuré correctement, on boucle
     loop {
         x86_64::instructions::hlt();
     }
 }
 
+/// Taille du tas des noyaux de test
+const TEST_HEAP_SIZE: usize = 8 * 1024 * 1024;
+
+/// Entrée des noyaux de test, comme `_start` du noyau (main.rs) : la
+/// signature Multiboot2 (EAX) et l'adresse des informations (EBX) sont
+/// transmises à `test_kernel_main(magic, info)`, qui appelle `init`
+#[macro_export]
+macro_rules! test_kernel_entry {
+    () => {
+        core::arch::global_asm!(
+            ".section .text._start, \"ax\"",
+            ".global _start",
+            "_start:",
+            "    mov edi, eax",
+            "    mov esi, ebx",
+            "    call test_kernel_main",
+            "    ud2",
+        );
+    };
+}
+
+/// Début du tas des noyaux de test : première zone libre de la carte
+/// mémoire au-delà de l'image du noyau et des informations du chargeur ;
+/// sans carte, juste après l'image. Rien n'est encore alloué ici.
+fn test_heap_start(multiboot_magic: u32, multiboot_info: u32) -> usize {
+    use crate::memory::frame::{for_each_multiboot_region, reserved_end, FRAME_SIZE};
+    use crate::memory::MemoryRegionKind;
+
+    let floor = reserved_end();
+    if multiboot_magic != crate::multiboot2::BOOTLOADER_MAGIC || multiboot_info == 0 {
+        return floor as usize;
+    }
+    let info = multiboot_info as usize;
+    let info_end = info as u64 + unsafe { core::ptr::read_unaligned(info as *const u32) } as u64;
+    let floor = floor.max(info_end.next_multiple_of(FRAME_SIZE));
+
+    let mut start = None;
+    unsafe {
+        for_each_multiboot_region(info, |region| {
+            let candidate = region.start.max(floor).next_multiple_of(FRAME_SIZE);
+            if start.is_none() && region.kind == MemoryRegionKind::Usable && candidate + TEST_HEAP_SIZE as u64 <= region.end {
+                start = Some(candidate);
+            }
+        });
+    }
+    match start {
+        Some(start) => start as usize,
+        None => {
+            serial_println!("Error: aucune zone libre de {} Kio pour le tas de test", TEST_HEAP_SIZE / 1024);
+            exit_qemu(QemuExitCode::Failed);
+        }
+    }
+}
+
+/// Initialisation d'un noyau de test : tas et cadres physiques d'après la
+/// carte du chargeur, espace d'adressage, GDT, IDT, horloge (PIT à
+/// `TIMER_HZ`, base des délais), interruptions actives pour les délais
+pub fn init(multiboot_magic: u32, multiboot_info: u32) {
+    let heap_start = test_heap_start(multiboot_magic, multiboot_info);
+    unsafe {
+        crate::memory::HYBRID_ALLOCATOR.init(heap_start, TEST_HEAP_SIZE);
+    }
+
+    // Comme le noyau : carte Multiboot2, sinon la RAM par défaut de QEMU
+    let _ = crate::multiboot2::init(multiboot_magic, multiboot_info as u64);
+    let mut regions = crate::multiboot2::usable_regions();
+    if regions.is_empty() {
+        regions.push(crate::memory::MemoryRegion::usable(0, 128 * 1024 * 1024));
+    }
+    let heap = (heap_start as u64, (heap_start + TEST_HEAP_SIZE) as u64);
+    crate::memory::frame::init(&regions, &[heap]);
+    crate::memory::vm::init_vm(x86_64::VirtAddr::new(0));
+
+    crate::gdt::init();
+    crate::interrupts::init_idt();
+    crate::interrupts::init_irqs();
+    x86_64::instructions::interrupts::enable();
+}
+
 /// Gestionnaire de panique pour les tests
-/// 
-/// Affiche l'erreur sur le port série et quitte QEMU avec un code d'échec.
+///
+/// Affiche l'erreur sur le port série et quitte QEMU avec un code d'échec,
+/// sauf si le test en cours devait paniquer : la série continue alors.
 pub fn test_panic_handler(info: &PanicInfo) -> ! {
+    DEADLINE.store(0, Ordering::SeqCst);
+    if EXPECT_PANIC.swap(false, Ordering::SeqCst) {
+        serial_println!("[ok]");
+        // Interruptions peut-être coupées au point de panique
+        x86_64::instructions::interrupts::enable();
+        run_on_runner_stack(CURRENT.load(Ordering::SeqCst) + 1);
+    }
     serial_println!("[failed]\n");
     serial_println!("Error: {}\n", info);
     exit_qemu(QemuExitCode::Failed);
@@ -81,4 +441,83 @@ mod tests {
         // Ce test vérifie que le test runner fonctionne
         assert_eq!(2 + 2, 4);
     }
+
+    fn overflow() {
+        let values: [u8; 2] = [1, 2];
+        let index = values.len() + core::hint::black_box(1);
+        let _ = values[index];
+    }
+
+    #[test_case]
+    const TEST_SHOULD_PANIC: ShouldPanic<fn()> = ShouldPanic(overflow);
+
+    static COUNTERS: [AtomicUsize; 3] = [AtomicUsize::new(0), AtomicUsize::new(0), AtomicUsize::new(0)];
+
+    fn count_until_all_started(index: usize) {
+        COUNTERS[index].fetch_add(1, Ordering::SeqCst);
+        // Chaque fil attend que tous aient commencé : impossible sans
+        // préemption
+        while COUNTERS.iter().any(|c| c.load(Ordering::SeqCst) == 0) {
+            core::hint::spin_loop();
+        }
+    }
+
+    #[test_case]
+    fn test_run_threads_preempts() {
+        run_threads(COUNTERS.len(), count_until_all_started);
+        assert!(COUNTERS.iter().all(|c| c.load(Ordering::SeqCst) == 1));
+        assert!(THREADS.lock().is_empty());
+    }
+
+    /// Profondeur de pile au moment de l'appel
+    fn stack_depth() -> u64 {
+        let rsp: u64;
+        unsafe { core::arch::asm!("mov {}, rsp", out(reg) rsp) };
+        RUNNER_STACK_TOP.load(Ordering::SeqCst) - rsp
+    }
+
+    static DEPTH_BEFORE_PANIC: AtomicU64 = AtomicU64::new(0);
+
+    fn record_depth_then_overflow() {
+        DEPTH_BEFORE_PANIC.store(stack_depth(), Ordering::SeqCst);
+        overflow();
+    }
+
+    #[test_case]
+    const TEST_RECORD_DEPTH: ShouldPanic<fn()> = ShouldPanic(record_depth_then_overflow);
+
+    #[test_case]
+    fn test_resume_on_clean_stack() {
+        // Après la panique, la série est repartie du haut de sa pile : à
+        // peu près aussi profonde qu'avant, sans les cadres de la panique
+        let depth = stack_depth();
+        assert!(depth <= DEPTH_BEFORE_PANIC.load(Ordering::SeqCst) + 256, "pile de reprise : {} octets", depth);
+    }
+
+    #[test_case]
+    fn test_timer_rate() {
+        // Le PIT est programmé à TIMER_HZ : une seconde de l'horloge CMOS
+        // dure TIMER_HZ ticks, à 10 % près
+        let next_second = || {
+            let second = crate::time::rtc::read().second;
+            while crate::time::rtc::read().second == second {
+                x86_64::instructions::hlt();
+            }
+        };
+        next_second();
+        let start = crate::scheduler::ticks();
+        next_second();
+        let elapsed = crate::scheduler::ticks() - start;
+        let hz = crate::scheduler::TIMER_HZ;
+        assert!(elapsed.abs_diff(hz) <= hz / 10, "{} ticks par seconde", elapsed);
+    }
+
+    #[test_case]
+    fn test_timer_ticks() {
+        // Les délais reposent sur l'horloge : elle doit avancer
+        let start = crate::scheduler::ticks();
+        while crate::scheduler::ticks() == start {
+            x86_64::instructions::hlt();
+        }
+    }
 }
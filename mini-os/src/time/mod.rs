@@ -0,0 +1,207 @@
+/// Sources d'horloge et horodatage haute résolution
+///
+/// Une `ClockSource` est un compteur croissant de fréquence connue. Le TSC
+/// est retenu s'il est invariant (fréquence constante quels que soient
+/// les états P/C du processeur) ; sinon on se contente des ticks du timer,
+/// à la résolution de 1/TIMER_HZ. `now_ns` convertit la source courante
+/// en nanosecondes depuis `init` par multiplication et décalage, sans
+/// division ni verrou : il peut être appelé depuis une interruption.
+///
+/// L'heure murale part du RTC lu à `init` et avance avec cette source.
+
+pub mod rtc;
+pub mod tsc;
+
+pub use rtc::DateTime;
+
+use core::sync::atomic::{AtomicU64, Ordering};
+use crate::sync::seqlock::SeqLock;
+
+pub const NSEC_PER_SEC: u64 = 1_000_000_000;
+pub const NSEC_PER_USEC: u64 = 1_000;
+
+/// Décalage de la conversion compteur -> ns
+const SCALE_SHIFT: u32 = 32;
+
+/// Compteur monotone de fréquence connue
+pub trait ClockSource: Sync {
+    fn name(&self) -> &'static str;
+    /// Valeur brute du compteur
+    fn read(&self) -> u64;
+    /// Fréquence du compteur (Hz)
+    fn frequency(&self) -> u64;
+}
+
+/// Ticks du timer : toujours disponible, résolution grossière
+pub struct Jiffies;
+
+impl ClockSource for Jiffies {
+    fn name(&self) -> &'static str {
+        "jiffies"
+    }
+
+    fn read(&self) -> u64 {
+        crate::scheduler::ticks()
+    }
+
+    fn frequency(&self) -> u64 {
+        crate::scheduler::TIMER_HZ
+    }
+}
+
+pub static JIFFIES: Jiffies = Jiffies;
+
+/// Sources connues, par indice
+const SOURCE_JIFFIES: u8 = 0;
+const SOURCE_TSC: u8 = 1;
+
+/// Source en service et paramètres de conversion, lus ensemble :
+/// ns = ((compteur - base) * mult) >> SCALE_SHIFT
+#[derive(Debug, Clone, Copy)]
+struct Clock {
+    source: u8,
+    mult: u64,
+    base: u64,
+}
+
+/// Changés au choix de la source ; un lecteur ne voit jamais un
+/// multiplicateur d'une source avec la base d'une autre
+static CLOCK: SeqLock<Clock> = SeqLock::new(Clock { source: SOURCE_JIFFIES, mult: 0, base: 0 });
+
+/// Heure Unix (s) lue sur le RTC à `init`
+static BOOT_TIME: AtomicU64 = AtomicU64::new(0);
+
+/// Multiplicateur de conversion pour une fréquence donnée
+fn scale_mult(frequency: u64) -> u64 {
+    (((NSEC_PER_SEC as u128) << SCALE_SHIFT) / frequency.max(1) as u128) as u64
+}
+
+/// Conversion d'un écart de compteur en nanosecondes
+fn cycles_to_ns(cycles: u64, mult: u64) -> u64 {
+    ((cycles as u128 * mult as u128) >> SCALE_SHIFT) as u64
+}
+
+fn select(index: u8) {
+    let source = source_by_index(index);
+    CLOCK.write(|clock| {
+        *clock = Clock { source: index, mult: scale_mult(source.frequency()), base: source.read() };
+    });
+}
+
+fn source_by_index(index: u8) -> &'static dyn ClockSource {
+    match index {
+        SOURCE_TSC => &tsc::TSC,
+        _ => &JIFFIES,
+    }
+}
+
+/// Choisit la meilleure source : TSC invariant étalonné, sinon jiffies
+pub fn init() {
+    if tsc::init() {
+        select(SOURCE_TSC);
+    } else {
+        select(SOURCE_JIFFIES);
+    }
+    let source = clocksource();
+    crate::serial_println!("clocksource: {} ({} Hz)", source.name(), source.frequency());
+
+    let now = rtc::read();
+    let boot = now.to_unix();
+    BOOT_TIME.store(boot, Ordering::SeqCst);
+    // Le vDSO compte en ticks depuis le démarrage
+    crate::vdso::set_boot_time(boot.saturating_sub(crate::scheduler::ticks() / crate::scheduler::TIMER_HZ));
+    crate::serial_println!(
+        "rtc: {:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        now.year, now.month, now.day, now.hour, now.minute, now.second
+    );
+}
+
+/// Source d'horloge en service
+pub fn clocksource() -> &'static dyn ClockSource {
+    source_by_index(CLOCK.read().source)
+}
+
+/// Nanosecondes écoulées depuis `init`
+pub fn now_ns() -> u64 {
+    let clock = CLOCK.read();
+    let source = source_by_index(clock.source);
+    let mut mult = clock.mult;
+    if mult == 0 {
+        // Avant init : jiffies
+        mult = scale_mult(source.frequency());
+    }
+    cycles_to_ns(source.read().wrapping_sub(clock.base), mult)
+}
+
+/// Microsecondes écoulées depuis `init`
+pub fn now_us() -> u64 {
+    now_ns() / NSEC_PER_USEC
+}
+
+/// Heure murale en nanosecondes depuis l'époque Unix
+pub fn realtime_ns() -> u64 {
+    BOOT_TIME.load(Ordering::Relaxed) * NSEC_PER_SEC + now_ns()
+}
+
+/// Heure murale en secondes Unix (horodatage des inodes)
+pub fn unix_time() -> u64 {
+    realtime_ns() / NSEC_PER_SEC
+}
+
+/// Heure murale en date civile (UTC)
+pub fn now_datetime() -> DateTime {
+    DateTime::from_unix(unix_time())
+}
+
+/// Instant de la source d'horloge (mesures de durée, RTT réseau)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Instant(u64);
+
+impl Instant {
+    pub fn now() -> Self {
+        Self(now_ns())
+    }
+
+    pub fn as_ns(&self) -> u64 {
+        self.0
+    }
+
+    /// Durée écoulée depuis cet instant (ns)
+    pub fn elapsed_ns(&self) -> u64 {
+        now_ns().saturating_sub(self.0)
+    }
+
+    pub fn elapsed_us(&self) -> u64 {
+        self.elapsed_ns() / NSEC_PER_USEC
+    }
+
+    /// Durée entre `earlier` et cet instant (ns)
+    pub fn duration_since(&self, earlier: Instant) -> u64 {
+        self.0.saturating_sub(earlier.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_cycles_to_ns() {
+        // 100 Hz : un tick = 10 ms
+        assert_eq!(cycles_to_ns(1, scale_mult(100)), 10_000_000);
+        // 2 GHz : 2000 cycles = 1 µs, à l'arrondi près
+        let ns = cycles_to_ns(2_000, scale_mult(2_000_000_000));
+        assert!((999..=1_000).contains(&ns));
+        // Une heure à 3 GHz sans débordement
+        let hour = cycles_to_ns(3_000_000_000 * 3600, scale_mult(3_000_000_000));
+        assert!(hour.abs_diff(3600 * NSEC_PER_SEC) < 1_000);
+    }
+
+    #[test_case]
+    fn test_instant_monotonic() {
+        let start = Instant::now();
+        let later = Instant::now();
+        assert!(later >= start);
+        assert_eq!(start.duration_since(later), 0);
+    }
+}
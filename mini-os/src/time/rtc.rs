@@ -0,0 +1,194 @@
+/// Horloge temps réel CMOS (MC146818)
+///
+/// Lue une fois au démarrage pour fixer l'heure murale. Les registres
+/// sont lus deux fois de suite jusqu'à obtenir deux lectures identiques
+/// hors mise à jour ; le format (BCD ou binaire, 12 ou 24 h) est donné
+/// par le registre d'état B. Le registre du siècle est celui indiqué par
+/// la FADT, à défaut on suppose les années 2000.
+
+use x86_64::instructions::interrupts::without_interrupts;
+use x86_64::instructions::port::Port;
+
+const CMOS_ADDRESS: u16 = 0x70;
+const CMOS_DATA: u16 = 0x71;
+
+const REG_SECONDS: u8 = 0x00;
+const REG_MINUTES: u8 = 0x02;
+const REG_HOURS: u8 = 0x04;
+const REG_DAY: u8 = 0x07;
+const REG_MONTH: u8 = 0x08;
+const REG_YEAR: u8 = 0x09;
+const REG_STATUS_A: u8 = 0x0A;
+const REG_STATUS_B: u8 = 0x0B;
+
+/// Registre A : mise à jour en cours
+const STATUS_A_UPDATE: u8 = 0x80;
+/// Registre B : format 24 h, valeurs binaires
+const STATUS_B_24H: u8 = 0x02;
+const STATUS_B_BINARY: u8 = 0x04;
+/// Bit PM des heures en format 12 h
+const HOURS_PM: u8 = 0x80;
+
+pub const SECS_PER_DAY: u64 = 86_400;
+
+/// Date et heure civiles (UTC)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+/// Jours depuis le 1970-01-01 (algorithme « days from civil »)
+fn days_from_civil(year: i64, month: u8, day: u8) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Date civile d'un nombre de jours depuis le 1970-01-01
+fn civil_from_days(days: i64) -> (i64, u8, u8) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u8;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u8;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+impl DateTime {
+    /// Secondes Unix (0 avant 1970)
+    pub fn to_unix(&self) -> u64 {
+        let days = days_from_civil(self.year as i64, self.month, self.day);
+        if days < 0 {
+            return 0;
+        }
+        days as u64 * SECS_PER_DAY + self.hour as u64 * 3600 + self.minute as u64 * 60 + self.second as u64
+    }
+
+    pub fn from_unix(seconds: u64) -> Self {
+        let (year, month, day) = civil_from_days((seconds / SECS_PER_DAY) as i64);
+        let rest = seconds % SECS_PER_DAY;
+        Self {
+            year: year as u16,
+            month,
+            day,
+            hour: (rest / 3600) as u8,
+            minute: (rest / 60 % 60) as u8,
+            second: (rest % 60) as u8,
+        }
+    }
+}
+
+fn read_register(reg: u8) -> u8 {
+    unsafe {
+        Port::<u8>::new(CMOS_ADDRESS).write(reg);
+        Port::<u8>::new(CMOS_DATA).read()
+    }
+}
+
+fn update_in_progress() -> bool {
+    read_register(REG_STATUS_A) & STATUS_A_UPDATE != 0
+}
+
+fn bcd_to_binary(value: u8) -> u8 {
+    (value & 0x0F) + (value >> 4) * 10
+}
+
+/// Registres bruts : secondes, minutes, heures, jour, mois, année, siècle
+fn read_raw(century_reg: u8) -> [u8; 7] {
+    while update_in_progress() {
+        core::hint::spin_loop();
+    }
+    [
+        read_register(REG_SECONDS),
+        read_register(REG_MINUTES),
+        read_register(REG_HOURS),
+        read_register(REG_DAY),
+        read_register(REG_MONTH),
+        read_register(REG_YEAR),
+        if century_reg != 0 { read_register(century_reg) } else { 0 },
+    ]
+}
+
+/// Registre du siècle annoncé par la FADT (0 si absent)
+fn century_register() -> u8 {
+    crate::acpi::find_rsdp()
+        .and_then(|rsdp| crate::acpi::find_fadt(&rsdp))
+        .map_or(0, |fadt| fadt.century)
+}
+
+/// Décode les registres selon le registre d'état B
+fn decode(raw: [u8; 7], status_b: u8) -> DateTime {
+    let [mut second, mut minute, hours, mut day, mut month, mut year, mut century] = raw;
+    let pm = hours & HOURS_PM != 0;
+    let mut hour = hours & !HOURS_PM;
+    if status_b & STATUS_B_BINARY == 0 {
+        second = bcd_to_binary(second);
+        minute = bcd_to_binary(minute);
+        hour = bcd_to_binary(hour);
+        day = bcd_to_binary(day);
+        month = bcd_to_binary(month);
+        year = bcd_to_binary(year);
+        century = bcd_to_binary(century);
+    }
+    if status_b & STATUS_B_24H == 0 {
+        // 12 h : 12 AM = 0 h, 12 PM = 12 h
+        hour %= 12;
+        if pm {
+            hour += 12;
+        }
+    }
+    let century = if century != 0 { century as u16 } else { 20 };
+    DateTime { year: century * 100 + year as u16, month, day, hour, minute, second }
+}
+
+/// Lit la date courante du RTC
+pub fn read() -> DateTime {
+    let century_reg = century_register();
+    without_interrupts(|| {
+        let mut raw = read_raw(century_reg);
+        // Deux lectures identiques : aucune mise à jour ne s'est intercalée
+        loop {
+            let again = read_raw(century_reg);
+            if again == raw {
+                break;
+            }
+            raw = again;
+        }
+        decode(raw, read_register(REG_STATUS_B))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_unix_conversion() {
+        let epoch = DateTime { year: 1970, month: 1, day: 1, hour: 0, minute: 0, second: 0 };
+        assert_eq!(epoch.to_unix(), 0);
+        let leap = DateTime { year: 2024, month: 2, day: 29, hour: 13, minute: 37, second: 42 };
+        assert_eq!(leap.to_unix(), 1_709_213_862);
+        assert_eq!(DateTime::from_unix(1_709_213_862), leap);
+    }
+
+    #[test_case]
+    fn test_decode_bcd_12h() {
+        // 2023-12-31 11:59:58 PM en BCD, format 12 h
+        let raw = [0x58, 0x59, HOURS_PM | 0x11, 0x31, 0x12, 0x23, 0x20];
+        let date = decode(raw, 0);
+        assert_eq!(date, DateTime { year: 2023, month: 12, day: 31, hour: 23, minute: 59, second: 58 });
+    }
+}
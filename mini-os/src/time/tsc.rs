@@ -0,0 +1,109 @@
+/// Time Stamp Counter
+///
+/// Invariance lue dans CPUID 0x8000_0007 (EDX bit 8). La fréquence vient
+/// de CPUID 0x15/0x16 quand le processeur la publie, sinon d'un
+/// étalonnage de 10 ms sur le canal 2 du PIT.
+
+use core::sync::atomic::{AtomicU64, Ordering};
+use raw_cpuid::CpuId;
+use x86_64::instructions::port::Port;
+use super::ClockSource;
+
+/// Durée de l'étalonnage sur le PIT
+const CALIBRATE_MS: u64 = 10;
+/// Port de contrôle du haut-parleur : porte du canal 2 (bit 0), sortie (bit 5)
+const PORT_SPEAKER: u16 = 0x61;
+
+pub struct Tsc {
+    frequency: AtomicU64,
+}
+
+impl ClockSource for Tsc {
+    fn name(&self) -> &'static str {
+        "tsc"
+    }
+
+    fn read(&self) -> u64 {
+        rdtsc()
+    }
+
+    fn frequency(&self) -> u64 {
+        self.frequency.load(Ordering::Relaxed)
+    }
+}
+
+pub static TSC: Tsc = Tsc { frequency: AtomicU64::new(0) };
+
+/// Lit le compteur
+pub fn rdtsc() -> u64 {
+    unsafe { core::arch::x86_64::_rdtsc() }
+}
+
+/// TSC à fréquence constante
+pub fn is_invariant() -> bool {
+    crate::cpuid::cpu_has(crate::cpuid::Feature::InvariantTsc)
+}
+
+/// Fréquence publiée par CPUID (feuilles 0x15 puis 0x16)
+fn cpuid_frequency() -> Option<u64> {
+    let cpuid = CpuId::new();
+    if let Some(hz) = cpuid.get_tsc_info().and_then(|info| info.tsc_frequency()) {
+        if hz != 0 {
+            return Some(hz);
+        }
+    }
+    cpuid
+        .get_processor_frequency_info()
+        .map(|info| info.processor_base_frequency() as u64 * 1_000_000)
+        .filter(|&hz| hz != 0)
+}
+
+/// Compte les cycles pendant CALIBRATE_MS mesurées par le canal 2 du PIT
+fn calibrate_pit() -> Option<u64> {
+    let count = (crate::interrupts::PIT_FREQUENCY * CALIBRATE_MS / 1000) as u16;
+    x86_64::instructions::interrupts::without_interrupts(|| unsafe {
+        let mut speaker: Port<u8> = Port::new(PORT_SPEAKER);
+        let saved = speaker.read();
+        // Porte ouverte, haut-parleur coupé
+        speaker.write((saved & !0x02) | 0x01);
+        // Canal 2, octet bas puis haut, mode 0 (sortie haute au terme du décompte)
+        Port::<u8>::new(0x43).write(0xB0);
+        let mut channel2: Port<u8> = Port::new(0x42);
+        channel2.write(count as u8);
+        channel2.write((count >> 8) as u8);
+
+        let start = rdtsc();
+        let mut expired = false;
+        for _ in 0..10_000_000 {
+            if speaker.read() & 0x20 != 0 {
+                expired = true;
+                break;
+            }
+        }
+        let end = rdtsc();
+        speaker.write(saved);
+
+        if expired {
+            Some((end - start) * 1000 / CALIBRATE_MS)
+        } else {
+            None
+        }
+    })
+}
+
+/// Détecte et étalonne le TSC ; faux s'il ne peut servir de source
+pub fn init() -> bool {
+    if !is_invariant() {
+        crate::serial_println!("tsc: non invariant, ignoré");
+        return false;
+    }
+    let frequency = match cpuid_frequency().or_else(calibrate_pit) {
+        Some(hz) => hz,
+        None => {
+            crate::serial_println!("tsc: étalonnage impossible");
+            return false;
+        }
+    };
+    TSC.frequency.store(frequency, Ordering::Relaxed);
+    true
+}
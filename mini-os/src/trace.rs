@@ -0,0 +1,330 @@
+/// Points de trace et tampon d'événements (ftrace-lite)
+///
+/// `trace!(Événement, a, b)` écrit un enregistrement de taille fixe
+/// (horodatage, CPU, événement, deux arguments) dans l'anneau du CPU
+/// courant. L'anneau est actif dès le démarrage : les plus anciens
+/// enregistrements sont écrasés, le tampon garde toujours les derniers
+/// RING_SIZE événements de chaque CPU.
+///
+/// L'écriture n'alloue ni ne verrouille : un point de trace peut être
+/// placé dans un gestionnaire d'interruption ou de faute de page. Une
+/// interruption qui trace au milieu d'un enregistrement prend l'emplacement
+/// suivant ; un enregistrement en cours d'écriture est ignoré à la lecture.
+///
+/// Les événements actifs se choisissent par `trace_event=<événement>,...`
+/// sur la ligne de commande (`none` : aucun) ou par la commande `trace`.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+use core::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use crate::percpu;
+use crate::syscall::syscall_name;
+
+/// Enregistrements par CPU
+pub const RING_SIZE: usize = 256;
+/// Arguments d'un enregistrement
+pub const NR_ARGS: usize = 2;
+
+/// Événements tracés
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// Changement de thread (tid sortant, tid entrant)
+    SchedSwitch = 0,
+    /// Entrée d'appel système (numéro, premier argument)
+    SysEnter = 1,
+    /// Sortie d'appel système (numéro, valeur de retour)
+    SysExit = 2,
+    /// Faute de page (adresse, code d'erreur)
+    PageFault = 3,
+    /// Entrée d'une IRQ (vecteur)
+    IrqEntry = 4,
+    /// Fin des gestionnaires d'une IRQ (vecteur)
+    IrqExit = 5,
+}
+
+impl Event {
+    pub const ALL: [Event; 6] = [
+        Event::SchedSwitch,
+        Event::SysEnter,
+        Event::SysExit,
+        Event::PageFault,
+        Event::IrqEntry,
+        Event::IrqExit,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Event::SchedSwitch => "sched_switch",
+            Event::SysEnter => "sys_enter",
+            Event::SysExit => "sys_exit",
+            Event::PageFault => "page_fault",
+            Event::IrqEntry => "irq_entry",
+            Event::IrqExit => "irq_exit",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|event| event.name() == name)
+    }
+
+    fn from_index(index: u64) -> Option<Self> {
+        Self::ALL.get(index as usize).copied()
+    }
+
+    pub const fn bit(self) -> u32 {
+        1 << self as u32
+    }
+}
+
+/// Masque de tous les événements
+pub const ALL_EVENTS: u32 = (1 << Event::ALL.len()) - 1;
+
+/// Événements enregistrés ; tous dès le démarrage
+static MASK: AtomicU32 = AtomicU32::new(ALL_EVENTS);
+
+/// Emplacement d'un anneau. `meta` vaut (position + 1) << 8 | événement
+/// une fois l'enregistrement complet, 0 pendant l'écriture.
+struct Slot {
+    meta: AtomicU64,
+    ts: AtomicU64,
+    args: [AtomicU64; NR_ARGS],
+}
+
+impl Slot {
+    const fn new() -> Self {
+        const ZERO: AtomicU64 = AtomicU64::new(0);
+        Self { meta: ZERO, ts: ZERO, args: [ZERO; NR_ARGS] }
+    }
+}
+
+/// Anneau d'un CPU
+pub struct Ring {
+    /// Prochaine position (croissante, jamais remise à zéro)
+    head: AtomicU64,
+    /// Positions antérieures ignorées à la lecture (`clear`)
+    start: AtomicU64,
+    slots: [Slot; RING_SIZE],
+}
+
+impl Ring {
+    pub const fn new() -> Self {
+        const SLOT: Slot = Slot::new();
+        Self { head: AtomicU64::new(0), start: AtomicU64::new(0), slots: [SLOT; RING_SIZE] }
+    }
+
+    fn push(&self, event: Event, ts: u64, args: [u64; NR_ARGS]) {
+        let pos = self.head.fetch_add(1, Ordering::Relaxed);
+        let slot = &self.slots[pos as usize % RING_SIZE];
+        slot.meta.store(0, Ordering::Release);
+        slot.ts.store(ts, Ordering::Relaxed);
+        for (arg, value) in slot.args.iter().zip(args) {
+            arg.store(value, Ordering::Relaxed);
+        }
+        slot.meta.store((pos + 1) << 8 | event as u64, Ordering::Release);
+    }
+
+    /// Enregistrements complets encore présents, du plus ancien au plus récent
+    fn read(&self, cpu: usize, out: &mut Vec<Record>) {
+        let head = self.head.load(Ordering::Acquire);
+        let first = head.saturating_sub(RING_SIZE as u64).max(self.start.load(Ordering::Relaxed));
+        for pos in first..head {
+            let slot = &self.slots[pos as usize % RING_SIZE];
+            let meta = slot.meta.load(Ordering::Acquire);
+            let ts = slot.ts.load(Ordering::Relaxed);
+            let args = [slot.args[0].load(Ordering::Relaxed), slot.args[1].load(Ordering::Relaxed)];
+            // Réécrit entre-temps : incomplet ou d'un autre tour
+            if meta >> 8 != pos + 1 || slot.meta.load(Ordering::Acquire) != meta {
+                continue;
+            }
+            if let Some(event) = Event::from_index(meta & 0xFF) {
+                out.push(Record { ts, cpu: cpu as u16, event, args });
+            }
+        }
+    }
+
+    fn clear(&self) {
+        self.start.store(self.head.load(Ordering::Relaxed), Ordering::Relaxed);
+    }
+}
+
+crate::percpu! {
+    /// Anneau d'événements de chaque CPU
+    static RINGS: Ring = Ring::new();
+}
+
+/// Point de trace : `trace!(SysEnter, num, arg0)`. Les arguments ne sont
+/// évalués que si l'événement est actif.
+#[macro_export]
+macro_rules! trace {
+    ($event:ident) => {
+        $crate::trace!($event, 0, 0)
+    };
+    ($event:ident, $a:expr) => {
+        $crate::trace!($event, $a, 0)
+    };
+    ($event:ident, $a:expr, $b:expr) => {
+        if $crate::trace::enabled($crate::trace::Event::$event) {
+            $crate::trace::record($crate::trace::Event::$event, [$a as u64, $b as u64]);
+        }
+    };
+}
+
+/// L'événement est-il enregistré ?
+#[inline(always)]
+pub fn enabled(event: Event) -> bool {
+    MASK.load(Ordering::Relaxed) & event.bit() != 0
+}
+
+/// Écrit un enregistrement dans l'anneau du CPU courant
+pub fn record(event: Event, args: [u64; NR_ARGS]) {
+    RINGS.get().push(event, crate::time::now_ns(), args);
+}
+
+/// Événements enregistrés
+pub fn mask() -> u32 {
+    MASK.load(Ordering::Relaxed)
+}
+
+pub fn set_mask(mask: u32) {
+    MASK.store(mask & ALL_EVENTS, Ordering::SeqCst);
+}
+
+/// Liste d'événements séparés par des virgules ; `all`, `none`
+pub fn parse_events(list: &str) -> Result<u32, String> {
+    let mut mask = 0;
+    for name in list.split(',').filter(|name| !name.is_empty()) {
+        mask |= match name {
+            "all" => ALL_EVENTS,
+            "none" => 0,
+            _ => Event::parse(name).ok_or_else(|| alloc::format!("événement inconnu: {}", name))?.bit(),
+        };
+    }
+    Ok(mask)
+}
+
+/// Oublie les enregistrements présents sur tous les CPU
+pub fn clear() {
+    for ring in RINGS.iter() {
+        ring.clear();
+    }
+}
+
+/// Enregistrement décodé
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Record {
+    /// Nanosecondes depuis l'initialisation de l'horloge
+    pub ts: u64,
+    pub cpu: u16,
+    pub event: Event,
+    pub args: [u64; NR_ARGS],
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "[{:03}] {:>6}.{:06}: {:<12} ",
+            self.cpu,
+            self.ts / 1_000_000_000,
+            (self.ts % 1_000_000_000) / 1_000,
+            self.event.name()
+        )?;
+        let [a, b] = self.args;
+        match self.event {
+            Event::SchedSwitch => write!(f, "prev_tid={} next_tid={}", a, b),
+            Event::SysEnter => write!(f, "NR {} ({}) arg0={:#x}", a, syscall_name(a).unwrap_or("?"), b),
+            Event::SysExit => write!(f, "NR {} ({}) = {}", a, syscall_name(a).unwrap_or("?"), b as i64),
+            Event::PageFault => write!(f, "address={:#x} error={:#x}", a, b),
+            Event::IrqEntry | Event::IrqExit => write!(f, "vector={}", a),
+        }
+    }
+}
+
+/// Sélection des enregistrements à lire
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Filter {
+    /// Masque d'événements
+    pub events: u32,
+    /// Un seul CPU
+    pub cpu: Option<usize>,
+}
+
+impl Filter {
+    pub const ALL: Filter = Filter { events: ALL_EVENTS, cpu: None };
+
+    pub fn matches(&self, record: &Record) -> bool {
+        self.events & record.event.bit() != 0 && self.cpu.map_or(true, |cpu| cpu == record.cpu as usize)
+    }
+}
+
+/// Enregistrements de tous les CPU retenus par `filter`, par date
+pub fn records(filter: &Filter) -> Vec<Record> {
+    let mut records = Vec::new();
+    for (cpu, ring) in RINGS.iter().enumerate() {
+        if filter.cpu.map_or(true, |wanted| wanted == cpu) {
+            ring.read(cpu, &mut records);
+        }
+    }
+    records.retain(|record| filter.matches(record));
+    records.sort_by_key(|record| record.ts);
+    records
+}
+
+/// Texte des `last` derniers enregistrements retenus (tous si None)
+pub fn dump(filter: &Filter, last: Option<usize>) -> String {
+    let records = records(filter);
+    let skip = last.map_or(0, |last| records.len().saturating_sub(last));
+    let mut out = String::new();
+    for record in &records[skip..] {
+        let _ = writeln!(out, "{}", record);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_ring_wraps() {
+        // Statique : un anneau ne tient pas à l'aise sur la pile du test
+        static RING: Ring = Ring::new();
+        let ring = &RING;
+        for n in 0..RING_SIZE as u64 + 10 {
+            ring.push(Event::SysEnter, n, [n, 0]);
+        }
+        let mut records = Vec::new();
+        ring.read(3, &mut records);
+        assert_eq!(records.len(), RING_SIZE);
+        assert_eq!(records[0].args[0], 10);
+        assert_eq!(records.last().map(|r| (r.cpu, r.ts)), Some((3, RING_SIZE as u64 + 9)));
+
+        ring.clear();
+        ring.push(Event::IrqEntry, 1, [32, 0]);
+        records.clear();
+        ring.read(0, &mut records);
+        assert_eq!(records.len(), 1);
+        assert_eq!(alloc::format!("{}", records[0]), "[000]      0.000000: irq_entry    vector=32");
+    }
+
+    #[test_case]
+    fn test_trace_filter() {
+        assert_eq!(parse_events("sched_switch,irq_entry"), Ok(Event::SchedSwitch.bit() | Event::IrqEntry.bit()));
+        assert_eq!(parse_events("all"), Ok(ALL_EVENTS));
+        assert_eq!(parse_events("none"), Ok(0));
+        assert!(parse_events("sys_enter,softirq").is_err());
+
+        let previous = mask();
+        set_mask(Event::PageFault.bit());
+        crate::trace!(PageFault, 0xdead_b000u64, 2);
+        crate::trace!(SysEnter, 1, 2); // inactif
+        set_mask(previous);
+
+        let filter = Filter { events: Event::PageFault.bit() | Event::SysEnter.bit(), cpu: Some(percpu::cpu_index()) };
+        let records = records(&filter);
+        let last = records.last().expect("faute de page tracée");
+        assert_eq!((last.event, last.args), (Event::PageFault, [0xdead_b000, 2]));
+        assert!(dump(&filter, Some(1)).contains("address=0xdeadb000 error=0x2"));
+    }
+}
@@ -0,0 +1,198 @@
+/// Terminaux virtuels : entrée clavier et /dev/tty1 à /dev/tty4
+///
+/// L'affichage de chaque terminal est tenu par `vga_buffer`. Ce module
+/// route les frappes vers le terminal qui a le focus (celui affiché) :
+/// chaque terminal a sa file d'entrée, et la frappe est recopiée à
+/// l'écran du terminal. Alt+F1 à Alt+F4 changent de terminal ;
+/// Maj+PgPréc/PgSuiv parcourent l'historique du terminal affiché.
+
+use alloc::collections::VecDeque;
+use alloc::format;
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, Ordering};
+use lazy_static::lazy_static;
+use pc_keyboard::{DecodedKey, KeyCode, KeyEvent, KeyState};
+use spin::Mutex;
+use crate::sync::SpinLockIrqSave;
+use x86_64::instructions::interrupts::without_interrupts;
+use crate::fs::devfs::{self, DeviceKind, DeviceOps};
+use crate::fs::VfsResult;
+use crate::vga_buffer::{self, WRITER};
+
+pub use crate::vga_buffer::{LOG_VT, SHELL_VT, VT_COUNT};
+
+/// Octets en attente par terminal (les plus anciens sont perdus au-delà)
+const INPUT_CAPACITY: usize = 256;
+
+lazy_static! {
+    /// Files d'entrée, alimentées depuis l'interruption clavier
+    static ref INPUT: SpinLockIrqSave<[VecDeque<u8>; VT_COUNT]> =
+        SpinLockIrqSave::new(core::array::from_fn(|_| VecDeque::with_capacity(INPUT_CAPACITY)));
+}
+
+/// Recopie des frappes à l'écran, par terminal ; coupée par un éditeur
+/// de ligne qui affiche lui-même la ligne en cours
+static ECHO: [AtomicBool; VT_COUNT] = [const { AtomicBool::new(true) }; VT_COUNT];
+
+/// Touches Alt et Maj enfoncées
+static ALT: AtomicBool = AtomicBool::new(false);
+static SHIFT: AtomicBool = AtomicBool::new(false);
+
+/// Suit les modificateurs avant décodage de l'événement
+pub fn key_event(event: &KeyEvent) {
+    let down = event.state == KeyState::Down;
+    match event.code {
+        KeyCode::AltLeft | KeyCode::AltRight => ALT.store(down, Ordering::Relaxed),
+        KeyCode::ShiftLeft | KeyCode::ShiftRight => SHIFT.store(down, Ordering::Relaxed),
+        _ => {}
+    }
+}
+
+/// Terminal ayant le focus
+pub fn focused() -> usize {
+    vga_buffer::active_vt()
+}
+
+/// Affiche un terminal ; il reçoit désormais le clavier
+pub fn switch_to(vt: usize) -> bool {
+    vga_buffer::switch_vt(vt)
+}
+
+/// Touche décodée : Alt+F1..F4 change de terminal, sinon elle est mise
+/// en file et recopiée sur le terminal ayant le focus
+pub fn input_key(key: DecodedKey) {
+    match key {
+        DecodedKey::RawKey(code) if ALT.load(Ordering::Relaxed) => {
+            let vt = match code {
+                KeyCode::F1 => 0,
+                KeyCode::F2 => 1,
+                KeyCode::F3 => 2,
+                KeyCode::F4 => 3,
+                _ => return,
+            };
+            switch_to(vt);
+        }
+        DecodedKey::RawKey(code @ (KeyCode::PageUp | KeyCode::PageDown)) if SHIFT.load(Ordering::Relaxed) => {
+            without_interrupts(|| {
+                let mut writer = WRITER.lock();
+                // Demi-écran, comme la console Linux
+                let page = (writer.rows() / 2) as isize;
+                writer.scroll_view(if code == KeyCode::PageUp { page } else { -page });
+            });
+        }
+        DecodedKey::Unicode(c) => {
+            let mut utf8 = [0; 4];
+            let text = c.encode_utf8(&mut utf8);
+            without_interrupts(|| {
+                let mut writer = WRITER.lock();
+                let vt = writer.active_vt();
+                writer.reset_view();
+                push_input(vt, text.as_bytes());
+                if echo_enabled(vt) {
+                    writer.write_vt(vt, text);
+                }
+            });
+        }
+        DecodedKey::RawKey(_) => {}
+    }
+}
+
+/// Active ou coupe la recopie des frappes sur un terminal
+pub fn set_echo(vt: usize, on: bool) {
+    if let Some(echo) = ECHO.get(vt) {
+        echo.store(on, Ordering::Relaxed);
+    }
+}
+
+/// Les frappes sont-elles recopiées sur ce terminal ?
+pub fn echo_enabled(vt: usize) -> bool {
+    ECHO.get(vt).is_some_and(|echo| echo.load(Ordering::Relaxed))
+}
+
+/// Ajoute des octets à la file d'entrée d'un terminal
+pub fn push_input(vt: usize, bytes: &[u8]) {
+    if vt >= VT_COUNT {
+        return;
+    }
+    {
+        let mut input = INPUT.lock();
+        let queue = &mut input[vt];
+        for &byte in bytes {
+            if queue.len() >= INPUT_CAPACITY {
+                queue.pop_front();
+            }
+            queue.push_back(byte);
+        }
+    }
+    crate::fs::poll::wake();
+}
+
+/// Lit les octets en attente d'un terminal (non bloquant)
+pub fn read(vt: usize, buf: &mut [u8]) -> usize {
+    if vt >= VT_COUNT {
+        return 0;
+    }
+    let mut input = INPUT.lock();
+    let queue = &mut input[vt];
+    let count = buf.len().min(queue.len());
+    for (slot, byte) in buf.iter_mut().zip(queue.drain(..count)) {
+        *slot = byte;
+    }
+    count
+}
+
+/// Écrit sur un terminal
+pub fn write(vt: usize, s: &str) {
+    without_interrupts(|| WRITER.lock().write_vt(vt, s));
+}
+
+/// /dev/ttyN : lecture de la file d'entrée, écriture à l'écran
+pub struct TtyDevice {
+    vt: usize,
+}
+
+impl DeviceOps for TtyDevice {
+    fn read(&mut self, _offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        Ok(read(self.vt, buf))
+    }
+
+    fn write(&mut self, _offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        // Octets bruts : un caractère UTF-8 peut être coupé entre deux write
+        without_interrupts(|| WRITER.lock().write_bytes_vt(self.vt, buf));
+        Ok(buf.len())
+    }
+
+    /// Lisible quand la file d'entrée n'est pas vide
+    fn poll(&self) -> i16 {
+        use crate::fs::poll::{POLLIN, POLLOUT};
+        let pending = INPUT.lock()[self.vt].len();
+        if pending > 0 { POLLIN | POLLOUT } else { POLLOUT }
+    }
+}
+
+/// Crée /dev/tty1 à /dev/tty4
+pub fn init() {
+    for vt in 0..VT_COUNT {
+        let name = format!("tty{}", vt + 1);
+        let _ = devfs::register_device(&name, DeviceKind::Char, Arc::new(Mutex::new(TtyDevice { vt })));
+    }
+}
+
+crate::initcall!(core, "Terminaux virtuels (/dev/tty1-4)", init);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_input_queue_per_terminal() {
+        push_input(2, b"ls\n");
+        push_input(3, b"x");
+        let mut buf = [0u8; 8];
+        assert_eq!(read(2, &mut buf), 3);
+        assert_eq!(&buf[..3], b"ls\n");
+        assert_eq!(read(2, &mut buf), 0);
+        assert_eq!(read(3, &mut buf), 1);
+        assert_eq!(read(VT_COUNT, &mut buf), 0);
+    }
+}
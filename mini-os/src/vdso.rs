@@ -0,0 +1,141 @@
+/// Page de données partagée noyau → utilisateur (façon vDSO)
+///
+/// Une page physique, projetée en lecture seule à `VDSO_ADDR` dans
+/// l'espace utilisateur, expose le PID courant, l'heure de démarrage et
+/// le compteur de ticks. Le programme la lit sans appel système.
+///
+/// Les écritures sont protégées par un compteur de séquence : impair
+/// pendant une mise à jour, le lecteur recommence s'il a changé.
+
+use core::sync::atomic::{fence, AtomicU64, Ordering};
+use spin::Mutex;
+use x86_64::instructions::interrupts::without_interrupts;
+use x86_64::structures::paging::{FrameAllocator, Page};
+use x86_64::VirtAddr;
+use crate::memory::frame::GlobalFrameAllocator;
+
+/// Adresse utilisateur de la page (entrée PML4 n°1, à côté des programmes ring 3)
+pub const VDSO_ADDR: u64 = 0x0000_0080_8000_0000;
+
+/// Contenu de la page partagée
+#[repr(C)]
+pub struct VdsoData {
+    /// Compteur de séquence (impair : écriture en cours)
+    pub sequence: AtomicU64,
+    /// PID du processus courant
+    pub pid: AtomicU64,
+    /// Heure de démarrage (secondes Unix, 0 tant qu'aucune horloge n'est lue)
+    pub boot_time: AtomicU64,
+    /// Ticks timer depuis le démarrage
+    pub ticks: AtomicU64,
+    /// Fréquence du timer
+    pub tick_hz: AtomicU64,
+}
+
+impl VdsoData {
+    pub const fn new() -> Self {
+        Self {
+            sequence: AtomicU64::new(0),
+            pid: AtomicU64::new(0),
+            boot_time: AtomicU64::new(0),
+            ticks: AtomicU64::new(0),
+            tick_hz: AtomicU64::new(crate::scheduler::TIMER_HZ),
+        }
+    }
+
+    /// Met à jour un champ sous le compteur de séquence
+    pub fn update(&self, write: impl FnOnce(&Self)) {
+        self.sequence.fetch_add(1, Ordering::AcqRel);
+        fence(Ordering::Release);
+        write(self);
+        self.sequence.fetch_add(1, Ordering::Release);
+    }
+
+    /// Lit une valeur cohérente (côté utilisateur ou noyau)
+    pub fn read<T>(&self, read: impl Fn(&Self) -> T) -> T {
+        loop {
+            let before = self.sequence.load(Ordering::Acquire);
+            if before & 1 != 0 {
+                core::hint::spin_loop();
+                continue;
+            }
+            let value = read(self);
+            fence(Ordering::Acquire);
+            if self.sequence.load(Ordering::Relaxed) == before {
+                return value;
+            }
+        }
+    }
+}
+
+/// Adresse noyau de la page (0 avant `init`)
+static VDSO_KERNEL_ADDR: AtomicU64 = AtomicU64::new(0);
+
+/// Un seul écrivain à la fois (timers de plusieurs CPU, changements de thread)
+static WRITER_LOCK: Mutex<()> = Mutex::new(());
+
+/// Publie une mise à jour, interruptions masquées pour ne pas s'imbriquer avec le timer
+fn publish(write: impl FnOnce(&VdsoData)) {
+    let addr = VDSO_KERNEL_ADDR.load(Ordering::Acquire);
+    if let Some(data) = unsafe { (addr as *const VdsoData).as_ref() } {
+        without_interrupts(|| {
+            let _guard = WRITER_LOCK.lock();
+            data.update(write);
+        });
+    }
+}
+
+/// Alloue la page et la projette à `VDSO_ADDR` (lecture seule, ring 3)
+pub fn init() -> Result<(), &'static str> {
+    use crate::memory::vm::{protection_flags, VM_MANAGER};
+
+    let frame = GlobalFrameAllocator.allocate_frame().ok_or("Plus de cadre libre pour le vDSO")?;
+    // Mémoire physique projetée à l'identique
+    let kernel_addr = frame.start_address().as_u64();
+    unsafe {
+        core::ptr::write_bytes(kernel_addr as *mut u8, 0, 4096);
+        core::ptr::write(kernel_addr as *mut VdsoData, VdsoData::new());
+    }
+
+    let mut vm = VM_MANAGER.lock();
+    let space = vm.as_mut().ok_or("Mémoire virtuelle non initialisée")?.kernel_space_mut();
+    let page = Page::containing_address(VirtAddr::new(VDSO_ADDR));
+    let flags = protection_flags(false, false, true);
+    space.map_frame(page, frame, flags).map_err(|_| "Échec du mappage du vDSO")?;
+
+    VDSO_KERNEL_ADDR.store(kernel_addr, Ordering::Release);
+    Ok(())
+}
+
+/// Appelé par l'interruption timer
+pub fn tick(ticks: u64) {
+    publish(|d| d.ticks.store(ticks, Ordering::Relaxed));
+}
+
+/// Appelé quand le thread courant du CPU change
+pub fn set_current_pid(pid: u64) {
+    publish(|d| d.pid.store(pid, Ordering::Relaxed));
+}
+
+/// Renseigne l'heure de démarrage (secondes Unix)
+pub fn set_boot_time(seconds: u64) {
+    publish(|d| d.boot_time.store(seconds, Ordering::Relaxed));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_vdso_sequence() {
+        let data = VdsoData::new();
+        data.update(|d| {
+            d.pid.store(7, Ordering::Relaxed);
+            d.ticks.store(250, Ordering::Relaxed);
+        });
+        assert_eq!(data.sequence.load(Ordering::Relaxed), 2);
+        let (pid, ticks) = data.read(|d| (d.pid.load(Ordering::Relaxed), d.ticks.load(Ordering::Relaxed)));
+        assert_eq!((pid, ticks), (7, 250));
+        assert_eq!(data.read(|d| d.tick_hz.load(Ordering::Relaxed)), crate::scheduler::TIMER_HZ);
+    }
+}
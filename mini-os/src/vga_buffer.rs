@@ -1,6 +1,24 @@
+/// Console du noyau
+///
+/// `WRITER` affiche soit dans le tampon texte VGA 80×25, soit sur une
+/// console framebuffer (mode VBE), choisie au démarrage par
+/// `select_console`. Quatre terminaux virtuels partagent l'affichage :
+/// chacun garde son écran (cellules, curseur, séquences ANSI en cours)
+/// et seul celui affiché est reporté à l'écran. Les messages du noyau
+/// (`print!`, `write_string`) vont sur tty2, le shell écrit sur tty1.
+/// Chaque terminal garde aussi un historique des lignes sorties de
+/// l'écran, consultable avec Maj+PgPréc/PgSuiv.
+///
+/// Le texte reçu est décodé en UTF-8 (y compris quand une séquence
+/// arrive en plusieurs écritures) puis converti en CP437, le jeu du mode
+/// texte VGA ; la police du framebuffer dessine les mêmes octets.
+
 use core::fmt;
 use volatile::Volatile;
 use spin::Mutex;
+use crate::drivers::gpu::bga;
+use crate::drivers::gpu::fbconsole::FbConsole;
+use crate::drivers::gpu::vesa::VesaModeInfo;
 
 #[allow(dead_code)]
 #[derive(Clone, Copy)]
@@ -24,13 +42,25 @@ pub enum Color {
     White = 0xF,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq)]
 struct ColorCode(u8);
 
 impl ColorCode {
     const fn new(fg: Color, bg: Color) -> ColorCode {
         ColorCode((bg as u8) << 4 | (fg as u8))
     }
+
+    fn fg(self) -> u8 {
+        self.0 & 0x0F
+    }
+
+    fn bg(self) -> u8 {
+        self.0 >> 4
+    }
+
+    fn with(fg: u8, bg: u8) -> ColorCode {
+        ColorCode((bg & 0x0F) << 4 | (fg & 0x0F))
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -47,61 +77,662 @@ struct Buffer {
     chars: [[Volatile<ScreenChar>; BUFFER_WIDTH]; BUFFER_HEIGHT],
 }
 
-pub struct Writer {
+/// Couleurs ANSI 0 à 7 (noir, rouge, vert, jaune, bleu, magenta, cyan,
+/// blanc) dans la palette VGA
+const ANSI_TO_VGA: [u8; 8] = [0x0, 0x4, 0x2, 0x6, 0x1, 0x5, 0x3, 0x7];
+
+/// Paramètres retenus par séquence CSI
+const MAX_PARAMS: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AnsiState {
+    Ground,
+    Escape,
+    Csi,
+}
+
+/// Résultat du décodage d'un octet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnsiEvent {
+    /// Octet à afficher (ou caractère de contrôle)
+    Print(u8),
+    /// Séquence `ESC [ p1 ; p2 ... <action>` complète
+    Csi { params: [u16; MAX_PARAMS], count: usize, action: u8 },
+    /// Octet absorbé par une séquence en cours
+    None,
+}
+
+/// Décodeur des séquences d'échappement ANSI
+pub struct AnsiParser {
+    state: AnsiState,
+    params: [u16; MAX_PARAMS],
+    count: usize,
+}
+
+impl AnsiParser {
+    pub const fn new() -> Self {
+        Self { state: AnsiState::Ground, params: [0; MAX_PARAMS], count: 0 }
+    }
+
+    pub fn feed(&mut self, byte: u8) -> AnsiEvent {
+        match self.state {
+            AnsiState::Ground if byte == 0x1B => {
+                self.state = AnsiState::Escape;
+                AnsiEvent::None
+            }
+            AnsiState::Ground => AnsiEvent::Print(byte),
+            AnsiState::Escape if byte == b'[' => {
+                self.state = AnsiState::Csi;
+                self.params = [0; MAX_PARAMS];
+                self.count = 0;
+                AnsiEvent::None
+            }
+            // Autres échappements (ESC c, ESC 7...) ignorés
+            AnsiState::Escape => {
+                self.state = AnsiState::Ground;
+                AnsiEvent::None
+            }
+            AnsiState::Csi => match byte {
+                b'0'..=b'9' => {
+                    if self.count < MAX_PARAMS {
+                        let param = &mut self.params[self.count];
+                        *param = param.saturating_mul(10).saturating_add((byte - b'0') as u16);
+                    }
+                    AnsiEvent::None
+                }
+                b';' => {
+                    self.count += 1;
+                    AnsiEvent::None
+                }
+                0x40..=0x7E => {
+                    self.state = AnsiState::Ground;
+                    AnsiEvent::Csi { params: self.params, count: (self.count + 1).min(MAX_PARAMS), action: byte }
+                }
+                // Octets intermédiaires ('?', ' '...) ignorés
+                _ => AnsiEvent::None,
+            },
+        }
+    }
+}
+
+/// Décodeur UTF-8 incrémental : les octets d'un caractère peuvent
+/// arriver en plusieurs fois (écritures sur /dev/tty, `write_byte`)
+pub struct Utf8Decoder {
+    code: u32,
+    remaining: u8,
+}
+
+impl Utf8Decoder {
+    pub const fn new() -> Self {
+        Self { code: 0, remaining: 0 }
+    }
+
+    /// Caractère complété par `byte` ; une séquence invalide donne U+FFFD
+    pub fn feed(&mut self, byte: u8) -> Option<char> {
+        let (code, remaining) = match byte {
+            0x00..=0x7F => {
+                self.remaining = 0;
+                return Some(byte as char);
+            }
+            0x80..=0xBF if self.remaining > 0 => {
+                self.code = (self.code << 6) | (byte & 0x3F) as u32;
+                self.remaining -= 1;
+                if self.remaining > 0 {
+                    return None;
+                }
+                return Some(char::from_u32(self.code).unwrap_or(char::REPLACEMENT_CHARACTER));
+            }
+            0xC2..=0xDF => (byte & 0x1F, 1),
+            0xE0..=0xEF => (byte & 0x0F, 2),
+            0xF0..=0xF4 => (byte & 0x07, 3),
+            _ => {
+                self.remaining = 0;
+                return Some(char::REPLACEMENT_CHARACTER);
+            }
+        };
+        self.code = code as u32;
+        self.remaining = remaining;
+        None
+    }
+}
+
+/// Octet CP437 affichant `c` : ASCII tel quel, lettres latines et traits
+/// de cadre courants ; les majuscules accentuées absentes de CP437
+/// perdent leur accent, le reste devient le carré 0xFE
+pub fn to_cp437(c: char) -> u8 {
+    match c {
+        ' '..='~' => c as u8,
+        'Ç' => 0x80, 'ü' => 0x81, 'é' => 0x82, 'â' => 0x83, 'ä' => 0x84, 'à' => 0x85, 'ç' => 0x87,
+        'ê' => 0x88, 'ë' => 0x89, 'è' => 0x8A, 'ï' => 0x8B, 'î' => 0x8C, 'ì' => 0x8D, 'Ä' => 0x8E,
+        'É' => 0x90, 'ô' => 0x93, 'ö' => 0x94, 'ò' => 0x95, 'û' => 0x96, 'ù' => 0x97, 'Ö' => 0x99,
+        'Ü' => 0x9A, '£' => 0x9C, 'á' => 0xA0, 'í' => 0xA1, 'ó' => 0xA2, 'ú' => 0xA3, 'ñ' => 0xA4,
+        'Ñ' => 0xA5, '¿' => 0xA8, '¡' => 0xAD, '«' => 0xAE, '»' => 0xAF, 'ß' => 0xE1, 'µ' => 0xE6,
+        '±' => 0xF1, '÷' => 0xF6, '°' => 0xF8, '·' => 0xFA, '²' => 0xFD,
+        'À' | 'Á' | 'Â' => b'A',
+        'È' | 'Ê' | 'Ë' => b'E',
+        'Î' | 'Ï' => b'I',
+        'Ô' => b'O',
+        'Ù' | 'Û' => b'U',
+        'Œ' => b'O',
+        'œ' => b'o',
+        '’' | '‘' => b'\'',
+        '“' | '”' => b'"',
+        '–' | '—' => b'-',
+        '░' => 0xB0, '▒' => 0xB1, '▓' => 0xB2, '│' => 0xB3, '┤' => 0xB4, '║' => 0xBA, '┐' => 0xBF,
+        '└' => 0xC0, '┴' => 0xC1, '┬' => 0xC2, '├' => 0xC3, '─' => 0xC4, '┼' => 0xC5, '═' => 0xCD,
+        '┘' => 0xD9, '┌' => 0xDA, '█' => 0xDB, '√' | '✓' => 0xFB,
+        _ => 0xFE,
+    }
+}
+
+/// Type de console demandé au démarrage
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsoleMode {
+    /// Tampon texte VGA 80×25
+    Text,
+    /// Framebuffer VBE (repli sur le mode texte s'il est indisponible)
+    Framebuffer { width: u16, height: u16 },
+}
+
+/// Nombre de terminaux virtuels (tty1 à tty4)
+pub const VT_COUNT: usize = 4;
+/// Terminal du shell (tty1)
+pub const SHELL_VT: usize = 0;
+/// Terminal des messages du noyau (tty2)
+pub const LOG_VT: usize = 1;
+
+/// Taille maximale d'un écran : 1920×1088 en cellules 8×16
+const MAX_COLS: usize = 240;
+const MAX_ROWS: usize = 68;
+const MAX_CELLS: usize = MAX_COLS * MAX_ROWS;
+
+/// Historique par terminal : deux écrans de taille maximale, soit 16
+/// écrans en 80×25 et 5 en 1024×768
+const SCROLLBACK_CELLS: usize = 2 * MAX_CELLS;
+
+const BLANK: ScreenChar = ScreenChar { ascii_character: 0, color_code: ColorCode(0) };
+
+/// Contenu des terminaux, hors tas : la console sert avant son initialisation
+static mut CELLS: [[ScreenChar; MAX_CELLS]; VT_COUNT] = [[BLANK; MAX_CELLS]; VT_COUNT];
+static mut HISTORY: [[ScreenChar; SCROLLBACK_CELLS]; VT_COUNT] = [[BLANK; SCROLLBACK_CELLS]; VT_COUNT];
+
+enum Target {
+    Text(&'static mut Buffer),
+    Framebuffer(FbConsole),
+}
+
+impl Target {
+    fn draw(&mut self, row: usize, col: usize, cell: ScreenChar) {
+        let byte = if cell.ascii_character == 0 { b' ' } else { cell.ascii_character };
+        match self {
+            Target::Text(buffer) => buffer.chars[row][col].write(ScreenChar {
+                ascii_character: byte,
+                color_code: cell.color_code,
+            }),
+            Target::Framebuffer(fb) => fb.draw_char(col, row, byte, cell.color_code.fg(), cell.color_code.bg()),
+        }
+    }
+
+    /// Remonte l'affichage d'une ligne ; la dernière est redessinée ensuite
+    fn scroll_up(&mut self) {
+        match self {
+            Target::Text(buffer) => {
+                for row in 1..BUFFER_HEIGHT {
+                    for col in 0..BUFFER_WIDTH {
+                        let character = buffer.chars[row][col].read();
+                        buffer.chars[row - 1][col].write(character);
+                    }
+                }
+            }
+            Target::Framebuffer(fb) => fb.scroll_up(0),
+        }
+    }
+}
+
+/// Écran d'un terminal virtuel
+///
+/// Les écritures ne modifient que la copie des cellules et marquent les
+/// lignes touchées ; `Writer::flush` reporte ces lignes (et les
+/// défilements) sur l'affichage si le terminal est celui affiché.
+///
+/// Les lignes qui sortent par le haut entrent dans un historique
+/// circulaire. La vue peut y reculer (`view_offset` lignes) : elle reste
+/// alors fixe pendant que de nouvelles lignes arrivent.
+struct VirtualScreen {
+    cells: &'static mut [ScreenChar; MAX_CELLS],
+    history: &'static mut [ScreenChar; SCROLLBACK_CELLS],
+    /// Première (plus ancienne) ligne de l'historique
+    history_start: usize,
+    history_len: usize,
+    view_offset: usize,
+    cols: usize,
+    rows: usize,
+    dirty: [bool; MAX_ROWS],
+    /// Défilements pas encore reportés (`rows` ou plus : tout redessiner)
+    scrolled: usize,
     column_position: usize,
+    row_position: usize,
     color_code: ColorCode,
-    buffer: &'static mut Buffer,
+    default_color: ColorCode,
+    utf8: Utf8Decoder,
+    ansi: AnsiParser,
 }
 
-impl Writer {
-    pub fn write_byte(&mut self, byte: u8) {
+impl VirtualScreen {
+    fn new(
+        cells: &'static mut [ScreenChar; MAX_CELLS],
+        history: &'static mut [ScreenChar; SCROLLBACK_CELLS],
+        cols: usize,
+        rows: usize,
+        color: ColorCode,
+    ) -> Self {
+        let mut screen = Self {
+            cells,
+            history,
+            history_start: 0,
+            history_len: 0,
+            view_offset: 0,
+            cols: 0,
+            rows: 0,
+            dirty: [false; MAX_ROWS],
+            scrolled: 0,
+            column_position: 0,
+            row_position: 0,
+            color_code: color,
+            default_color: color,
+            utf8: Utf8Decoder::new(),
+            ansi: AnsiParser::new(),
+        };
+        screen.resize(cols, rows);
+        screen
+    }
+
+    /// Change les dimensions ; l'écran et l'historique sont effacés
+    fn resize(&mut self, cols: usize, rows: usize) {
+        self.cols = cols.min(MAX_COLS);
+        self.rows = rows.min(MAX_ROWS);
+        self.clear_history();
+        self.clear();
+    }
+
+    /// Lignes que peut contenir l'historique à la largeur courante
+    fn history_capacity(&self) -> usize {
+        SCROLLBACK_CELLS / self.cols
+    }
+
+    /// Ligne `index` de l'historique (0 : la plus ancienne)
+    fn history_line(&self, index: usize) -> &[ScreenChar] {
+        let line = (self.history_start + index) % self.history_capacity();
+        &self.history[line * self.cols..(line + 1) * self.cols]
+    }
+
+    /// Ajoute la ligne du haut de l'écran ; retourne vrai si la plus
+    /// ancienne a été écrasée
+    fn push_history(&mut self) -> bool {
+        let capacity = self.history_capacity();
+        let full = self.history_len == capacity;
+        let line = (self.history_start + self.history_len) % capacity;
+        let cols = self.cols;
+        self.history[line * cols..(line + 1) * cols].copy_from_slice(&self.cells[..cols]);
+        if full {
+            self.history_start = (self.history_start + 1) % capacity;
+        } else {
+            self.history_len += 1;
+        }
+        full
+    }
+
+    fn clear_history(&mut self) {
+        self.history_start = 0;
+        self.history_len = 0;
+        if self.view_offset != 0 {
+            self.view_offset = 0;
+            self.mark_all_dirty();
+        }
+    }
+
+    /// Recule (positif) ou avance la vue dans l'historique
+    fn scroll_view(&mut self, lines: isize) {
+        let offset = (self.view_offset as isize + lines).clamp(0, self.history_len as isize) as usize;
+        if offset != self.view_offset {
+            self.view_offset = offset;
+            self.mark_all_dirty();
+        }
+    }
+
+    /// Cellule visible en (row, col), compte tenu du recul de la vue
+    fn displayed(&self, row: usize, col: usize) -> ScreenChar {
+        match row.checked_sub(self.view_offset) {
+            Some(row) => self.cells[row * self.cols + col],
+            None => self.history_line(self.history_len - self.view_offset + row)[col],
+        }
+    }
+
+    fn mark_all_dirty(&mut self) {
+        self.dirty[..self.rows].fill(true);
+        self.scrolled = self.rows;
+    }
+
+    fn put(&mut self, row: usize, col: usize, byte: u8) {
+        self.cells[row * self.cols + col] = ScreenChar {
+            ascii_character: byte,
+            color_code: self.color_code,
+        };
+        self.dirty[row] = true;
+    }
+
+    /// Décode l'UTF-8 et les séquences ANSI puis écrit dans les cellules
+    fn feed(&mut self, byte: u8) {
+        let Some(c) = self.utf8.feed(byte) else { return };
+        let byte = match c {
+            // Newline, carriage return, backspace or escape
+            '\n' | '\r' | '\x08' | '\x1b' => c as u8,
+            c if c.is_ascii_control() => 0xFE,
+            c => to_cp437(c),
+        };
+        match self.ansi.feed(byte) {
+            AnsiEvent::Print(byte) => self.put_byte(byte),
+            AnsiEvent::Csi { params, count, action } => self.csi(&params[..count], action),
+            AnsiEvent::None => {}
+        }
+    }
+
+    fn put_byte(&mut self, byte: u8) {
         match byte {
             b'\n' => self.new_line(),
+            b'\r' => self.column_position = 0,
+            0x08 => self.column_position = self.column_position.saturating_sub(1),
             byte => {
-                if self.column_position >= BUFFER_WIDTH {
+                if self.column_position >= self.cols {
                     self.new_line();
                 }
-                let row = BUFFER_HEIGHT - 1;
+                let row = self.row_position;
                 let col = self.column_position;
-                self.buffer.chars[row][col].write(ScreenChar {
-                    ascii_character: byte,
-                    color_code: self.color_code,
-                });
+                self.put(row, col, byte);
                 self.column_position += 1;
             }
         }
     }
 
     fn new_line(&mut self) {
-        for row in 1..BUFFER_HEIGHT {
-            for col in 0..BUFFER_WIDTH {
-                let character = self.buffer.chars[row][col].read();
-                self.buffer.chars[row - 1][col].write(character);
-            }
+        if self.row_position + 1 < self.rows {
+            self.row_position += 1;
+        } else {
+            self.scroll();
         }
-        self.clear_row(BUFFER_HEIGHT - 1);
         self.column_position = 0;
     }
 
+    fn scroll(&mut self) {
+        let overwritten = self.push_history();
+        if self.view_offset > 0 {
+            if overwritten {
+                // L'historique a glissé sous la vue
+                self.mark_all_dirty();
+            } else {
+                self.view_offset += 1;
+            }
+        }
+        let used = self.cols * self.rows;
+        self.cells.copy_within(self.cols..used, 0);
+        self.dirty.copy_within(1..self.rows, 0);
+        self.clear_row(self.rows - 1);
+        self.scrolled += 1;
+    }
+
+    /// Efface une ligne à partir de la colonne `from`
+    fn clear_row_from(&mut self, row: usize, from: usize) {
+        for col in from..self.cols {
+            self.put(row, col, b' ');
+        }
+    }
+
     fn clear_row(&mut self, row: usize) {
-        let blank = ScreenChar {
-            ascii_character: b' ',
-            color_code: self.color_code,
-        };
-        for col in 0..BUFFER_WIDTH {
-            self.buffer.chars[row][col].write(blank);
+        self.clear_row_from(row, 0);
+    }
+
+    /// Efface l'écran et place le curseur en haut à gauche
+    fn clear(&mut self) {
+        for row in 0..self.rows {
+            self.clear_row(row);
         }
+        self.mark_all_dirty();
+        self.row_position = 0;
+        self.column_position = 0;
     }
 
-    pub fn write_string(&mut self, s: &str) {
-        for byte in s.bytes() {
-            match byte {
-                // Printable ASCII byte or newline
-                0x20..=0x7e | b'\n' => self.write_byte(byte),
-                // Not part of printable ASCII range
-                _ => self.write_byte(0xfe),
+    /// Exécute une séquence CSI
+    fn csi(&mut self, params: &[u16], action: u8) {
+        let first = params.first().copied().unwrap_or(0);
+        // Déplacements : un paramètre absent ou nul vaut 1
+        let amount = first.max(1) as usize;
+        match action {
+            b'm' => self.sgr(params),
+            b'J' => match first {
+                2 => self.clear(),
+                3 => {
+                    self.clear_history();
+                    self.clear();
+                }
+                _ => {
+                    let row = self.row_position;
+                    self.clear_row_from(row, self.column_position);
+                    for row in row + 1..self.rows {
+                        self.clear_row(row);
+                    }
+                }
+            },
+            b'K' => {
+                let (row, col) = (self.row_position, self.column_position);
+                match first {
+                    2 => self.clear_row(row),
+                    _ => self.clear_row_from(row, col),
+                }
+            }
+            b'H' | b'f' => {
+                let row = first.max(1) as usize - 1;
+                let col = params.get(1).copied().unwrap_or(0).max(1) as usize - 1;
+                self.row_position = row.min(self.rows - 1);
+                self.column_position = col.min(self.cols - 1);
             }
+            b'A' => self.row_position = self.row_position.saturating_sub(amount),
+            b'B' => self.row_position = (self.row_position + amount).min(self.rows - 1),
+            b'C' => self.column_position = (self.column_position + amount).min(self.cols - 1),
+            b'D' => self.column_position = self.column_position.saturating_sub(amount),
+            _ => {}
+        }
+    }
+
+    /// Select Graphic Rendition : couleurs et intensité
+    fn sgr(&mut self, params: &[u16]) {
+        let (mut fg, mut bg) = (self.color_code.fg(), self.color_code.bg());
+        for &param in params {
+            match param {
+                0 => {
+                    fg = self.default_color.fg();
+                    bg = self.default_color.bg();
+                }
+                1 => fg |= 0x8,
+                22 => fg &= 0x7,
+                7 => core::mem::swap(&mut fg, &mut bg),
+                30..=37 => fg = ANSI_TO_VGA[(param - 30) as usize] | (fg & 0x8),
+                39 => fg = self.default_color.fg(),
+                40..=47 => bg = ANSI_TO_VGA[(param - 40) as usize],
+                49 => bg = self.default_color.bg(),
+                90..=97 => fg = ANSI_TO_VGA[(param - 90) as usize] | 0x8,
+                100..=107 => bg = ANSI_TO_VGA[(param - 100) as usize] | 0x8,
+                _ => {}
+            }
+        }
+        self.color_code = ColorCode::with(fg, bg);
+    }
+}
+
+/// Console : terminaux virtuels et affichage partagé
+pub struct Writer {
+    screens: [VirtualScreen; VT_COUNT],
+    /// Terminal affiché (et qui reçoit le clavier)
+    active: usize,
+    /// Terminal de `write_string` / `print!` (messages du noyau)
+    output: usize,
+    target: Target,
+    /// Affichage cédé (compositeur) : seules les copies sont tenues à jour
+    suspended: bool,
+}
+
+impl Writer {
+    fn new() -> Self {
+        let color = ColorCode::new(Color::LightGreen, Color::Black);
+        // Chaque terminal reçoit son propre tableau de cellules
+        let cells = unsafe { &mut *core::ptr::addr_of_mut!(CELLS) };
+        let mut history = unsafe { &mut *core::ptr::addr_of_mut!(HISTORY) }.each_mut().into_iter();
+        let screens = cells.each_mut().map(|cells| {
+            let history = history.next().unwrap();
+            VirtualScreen::new(cells, history, BUFFER_WIDTH, BUFFER_HEIGHT, color)
+        });
+        Writer {
+            screens,
+            active: LOG_VT,
+            output: LOG_VT,
+            target: Target::Text(unsafe { &mut *(0xb8000 as *mut Buffer) }),
+            suspended: false,
+        }
+    }
+
+    /// Colonnes de la console
+    pub fn cols(&self) -> usize {
+        self.screens[self.active].cols
+    }
+
+    /// Lignes de la console
+    pub fn rows(&self) -> usize {
+        self.screens[self.active].rows
+    }
+
+    pub fn is_framebuffer(&self) -> bool {
+        matches!(self.target, Target::Framebuffer(_))
+    }
+
+    /// Terminal affiché
+    pub fn active_vt(&self) -> usize {
+        self.active
+    }
+
+    /// Affiche un autre terminal
+    pub fn switch_vt(&mut self, vt: usize) -> bool {
+        if vt >= VT_COUNT {
+            return false;
+        }
+        if vt != self.active {
+            self.active = vt;
+            self.redraw();
+        }
+        true
+    }
+
+    /// Reporte sur l'affichage les modifications d'un terminal affiché
+    fn flush(&mut self, vt: usize) {
+        if vt != self.active || self.suspended {
+            return;
+        }
+        let screen = &mut self.screens[vt];
+        let all = screen.scrolled >= screen.rows;
+        // Vue reculée : l'affichage ne défile pas avec l'écran
+        if !all && screen.view_offset == 0 {
+            for _ in 0..screen.scrolled {
+                self.target.scroll_up();
+            }
+        }
+        screen.scrolled = 0;
+        for row in 0..screen.rows {
+            let changed = match row.checked_sub(screen.view_offset) {
+                Some(source) => core::mem::take(&mut screen.dirty[source]),
+                None => false,
+            };
+            if all || changed {
+                for col in 0..screen.cols {
+                    self.target.draw(row, col, screen.displayed(row, col));
+                }
+            }
+        }
+        if all {
+            screen.dirty.fill(false);
+        }
+    }
+
+    /// Recule (positif) ou avance la vue du terminal affiché
+    pub fn scroll_view(&mut self, lines: isize) {
+        let active = self.active;
+        self.screens[active].scroll_view(lines);
+        self.flush(active);
+    }
+
+    /// Ramène la vue du terminal affiché sur l'écran courant
+    pub fn reset_view(&mut self) {
+        let offset = self.screens[self.active].view_offset;
+        if offset > 0 {
+            self.scroll_view(-(offset as isize));
+        }
+    }
+
+    /// Efface l'historique d'un terminal
+    pub fn clear_scrollback(&mut self, vt: usize) {
+        if vt < VT_COUNT {
+            self.screens[vt].clear_history();
+            self.flush(vt);
+        }
+    }
+
+    /// Redessine entièrement le terminal affiché
+    fn redraw(&mut self) {
+        let active = self.active;
+        self.screens[active].mark_all_dirty();
+        self.flush(active);
+    }
+
+    /// Décode l'UTF-8 et les séquences ANSI puis affiche
+    pub fn write_byte(&mut self, byte: u8) {
+        let vt = self.output;
+        self.screens[vt].feed(byte);
+        self.flush(vt);
+    }
+
+    pub fn write_string(&mut self, s: &str) {
+        self.write_vt(self.output, s);
+    }
+
+    /// Écrit sur un terminal donné
+    pub fn write_vt(&mut self, vt: usize, s: &str) {
+        self.write_bytes_vt(vt, s.as_bytes());
+    }
+
+    /// Écrit des octets UTF-8 sur un terminal ; une séquence coupée est
+    /// complétée par l'écriture suivante
+    pub fn write_bytes_vt(&mut self, vt: usize, bytes: &[u8]) {
+        if vt >= VT_COUNT {
+            return;
         }
+        for &byte in bytes {
+            self.screens[vt].feed(byte);
+        }
+        self.flush(vt);
+    }
+
+    /// Efface le terminal des messages et place le curseur en haut à gauche
+    pub fn clear_screen(&mut self) {
+        let vt = self.output;
+        self.screens[vt].clear();
+        self.flush(vt);
+    }
+
+    /// Bascule vers une console framebuffer ; les terminaux sont effacés
+    fn use_framebuffer(&mut self, fb: FbConsole) {
+        let (cols, rows) = (fb.cols(), fb.rows());
+        self.target = Target::Framebuffer(fb);
+        for screen in self.screens.iter_mut() {
+            screen.resize(cols, rows);
+        }
+        self.redraw();
     }
 }
 
@@ -115,13 +746,76 @@ impl fmt::Write for Writer {
 use lazy_static::lazy_static;
 
 lazy_static! {
-    pub static ref WRITER: Mutex<Writer> = Mutex::new(Writer {
-        column_position: 0,
-        color_code: ColorCode::new(Color::LightGreen, Color::Black),
-        buffer: unsafe { &mut *(0xb8000 as *mut Buffer) },
+    pub static ref WRITER: Mutex<Writer> = Mutex::new(Writer::new());
+}
+
+/// Choisit la sortie de la console ; retourne le mode effectivement utilisé
+///
+/// Le mode graphique est programmé par les extensions VBE de Bochs/QEMU ;
+/// à défaut (autre carte, mode refusé), la console reste en mode texte.
+pub fn select_console(mode: ConsoleMode) -> ConsoleMode {
+    match mode {
+        ConsoleMode::Text => ConsoleMode::Text,
+        ConsoleMode::Framebuffer { width, height } => match bga::set_mode(width, height, 32) {
+            Some(info) => {
+                if use_framebuffer(info) {
+                    mode
+                } else {
+                    bga::disable();
+                    ConsoleMode::Text
+                }
+            }
+            None => ConsoleMode::Text,
+        },
+    }
+}
+
+/// Redirige la console vers un framebuffer déjà programmé (par exemple
+/// celui fourni par le chargeur d'amorçage)
+pub fn use_framebuffer(info: VesaModeInfo) -> bool {
+    match FbConsole::new(info) {
+        Some(fb) => {
+            x86_64::instructions::interrupts::without_interrupts(|| WRITER.lock().use_framebuffer(fb));
+            true
+        }
+        None => false,
+    }
+}
+
+/// Mode graphique de la console framebuffer (None en mode texte)
+pub fn framebuffer_info() -> Option<VesaModeInfo> {
+    x86_64::instructions::interrupts::without_interrupts(|| match &WRITER.lock().target {
+        Target::Framebuffer(fb) => Some(fb.mode_info()),
+        Target::Text(_) => None,
+    })
+}
+
+/// Cède l'affichage ou le reprend (terminal affiché redessiné)
+pub fn suspend_console(suspended: bool) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let mut writer = WRITER.lock();
+        writer.suspended = suspended;
+        if !suspended {
+            writer.redraw();
+        }
     });
 }
 
+/// Affiche le terminal `vt` (0 pour tty1)
+pub fn switch_vt(vt: usize) -> bool {
+    x86_64::instructions::interrupts::without_interrupts(|| WRITER.lock().switch_vt(vt))
+}
+
+/// Terminal affiché
+pub fn active_vt() -> usize {
+    x86_64::instructions::interrupts::without_interrupts(|| WRITER.lock().active_vt())
+}
+
+/// Efface l'historique de défilement d'un terminal (commande `clear`)
+pub fn clear_scrollback(vt: usize) {
+    x86_64::instructions::interrupts::without_interrupts(|| WRITER.lock().clear_scrollback(vt));
+}
+
 #[macro_export]
 macro_rules! print {
     ($($arg:tt)*) => ($crate::_print(format_args!($($arg)*)));
@@ -138,3 +832,85 @@ pub fn _print(args: fmt::Arguments) {
     use core::fmt::Write;
     WRITER.lock().write_fmt(args).unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_ansi_parser_csi() {
+        let mut parser = AnsiParser::new();
+        assert_eq!(parser.feed(b'a'), AnsiEvent::Print(b'a'));
+        for &byte in b"\x1b[1;31" {
+            assert_eq!(parser.feed(byte), AnsiEvent::None);
+        }
+        match parser.feed(b'm') {
+            AnsiEvent::Csi { params, count, action } => {
+                assert_eq!(action, b'm');
+                assert_eq!(&params[..count], &[1, 31]);
+            }
+            other => panic!("séquence inattendue: {:?}", other),
+        }
+        assert_eq!(parser.feed(b'b'), AnsiEvent::Print(b'b'));
+    }
+
+    fn test_screen(cols: usize, rows: usize) -> VirtualScreen {
+        let cells: alloc::boxed::Box<[ScreenChar; MAX_CELLS]> =
+            alloc::vec![BLANK; MAX_CELLS].into_boxed_slice().try_into().ok().unwrap();
+        let history: alloc::boxed::Box<[ScreenChar; SCROLLBACK_CELLS]> =
+            alloc::vec![BLANK; SCROLLBACK_CELLS].into_boxed_slice().try_into().ok().unwrap();
+        let color = ColorCode::new(Color::White, Color::Black);
+        VirtualScreen::new(alloc::boxed::Box::leak(cells), alloc::boxed::Box::leak(history), cols, rows, color)
+    }
+
+    #[test_case]
+    fn test_virtual_screen_scroll_marks_rows() {
+        let mut screen = test_screen(4, 2);
+        screen.dirty = [false; MAX_ROWS];
+        screen.scrolled = 0;
+        for &byte in b"ab\ncd\nef" {
+            screen.feed(byte);
+        }
+        assert_eq!(screen.scrolled, 1);
+        assert_eq!(screen.cells[0].ascii_character, b'c');
+        assert_eq!(screen.cells[4].ascii_character, b'e');
+        assert!(screen.dirty[0] && screen.dirty[1]);
+    }
+
+    #[test_case]
+    fn test_utf8_to_cp437() {
+        let mut screen = test_screen(8, 2);
+        let text = "Démarré ─";
+        let (head, tail) = text.as_bytes().split_at(2);
+        // « é » coupé entre deux écritures
+        for &byte in head.iter().chain(tail) {
+            screen.feed(byte);
+        }
+        let cells: alloc::vec::Vec<u8> = screen.cells[..8].iter().map(|c| c.ascii_character).collect();
+        assert_eq!(&cells, &[b'D', 0x82, b'm', b'a', b'r', b'r', 0x82, b' ']);
+        assert_eq!(screen.cells[8].ascii_character, 0xC4);
+        assert_eq!(to_cp437('À'), b'A');
+        assert_eq!(Utf8Decoder::new().feed(0xFF), Some(char::REPLACEMENT_CHARACTER));
+    }
+
+    #[test_case]
+    fn test_scrollback_view() {
+        let mut screen = test_screen(4, 2);
+        for &byte in b"a\nb\nc\nd" {
+            screen.feed(byte);
+        }
+        // « a » et « b » sont sortis de l'écran
+        assert_eq!(screen.history_len, 2);
+        screen.scroll_view(1);
+        assert_eq!(screen.displayed(0, 0).ascii_character, b'b');
+        assert_eq!(screen.displayed(1, 0).ascii_character, b'c');
+        // Une nouvelle ligne ne déplace pas la vue
+        screen.feed(b'\n');
+        assert_eq!(screen.view_offset, 2);
+        assert_eq!(screen.displayed(0, 0).ascii_character, b'b');
+        screen.scroll_view(100);
+        assert_eq!(screen.displayed(0, 0).ascii_character, b'a');
+        screen.clear_history();
+        assert_eq!(screen.view_offset, 0);
+    }
+}
@@ -0,0 +1,293 @@
+/// Détecteur de blocages (soft lockup) et chien de garde matériel
+///
+/// Chaque interruption d'horloge incrémente le compteur de battements du
+/// CPU qui la reçoit (`timer_tick`). Le kthread `kwatchdog`, de priorité
+/// basse, relève ces compteurs chaque seconde :
+/// - un CPU dont le compteur n'avance plus depuis `watchdog_thresh`
+///   secondes ne reçoit plus l'horloge (interruptions masquées, boucle en
+///   gestionnaire) : le kthread lui envoie une NMI, dont le gestionnaire
+///   relève le RIP/RSP interrompus et le haut de la pile, puis les
+///   journalise ;
+/// - réciproquement, le tick vérifie que le kthread a tourné récemment :
+///   sinon un thread monopolise le CPU (ordonnanceur bloqué) et le tick
+///   journalise ses propres registres et sa pile, qui contient le cadre
+///   interrompu.
+///
+/// Un blocage n'est signalé qu'une fois, jusqu'à ce que le CPU reparte.
+/// Le kthread nourrit aussi le chien de garde matériel (`watchdog=`) : si
+/// l'ordonnanceur se bloque pour de bon, la machine redémarre.
+
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use spin::Mutex;
+use x86_64::structures::idt::InterruptStackFrame;
+use crate::drivers::watchdog::{HwWatchdog, Kind};
+use crate::percpu::{self, NR_CPUS};
+use crate::process::thread::alloc_tid;
+use crate::process::{ProcessPriority, Thread};
+use crate::time::NSEC_PER_SEC;
+
+/// Seuil par défaut (`watchdog_thresh=`), en secondes
+pub const DEFAULT_THRESH: u64 = 10;
+/// Délai du chien matériel, en secondes
+pub const HW_TIMEOUT: u32 = 30;
+/// Mots de pile relevés
+const STACK_WORDS: usize = 16;
+/// Attente de la réponse à une NMI
+const NMI_TIMEOUT_NS: u64 = 10_000_000;
+/// Adresse standard du LAPIC
+const LAPIC_BASE: u64 = 0xFEE0_0000;
+
+/// Seuil en nanosecondes (0 : détecteur inactif)
+static THRESH_NS: AtomicU64 = AtomicU64::new(0);
+/// Dernier passage du kthread (`time::now_ns`)
+static TOUCHED_NS: AtomicU64 = AtomicU64::new(0);
+/// Blocage de l'ordonnanceur déjà signalé
+static SOFT_REPORTED: AtomicBool = AtomicBool::new(false);
+/// Chien de garde matériel armé
+static HW_WATCHDOG: Mutex<Option<HwWatchdog>> = Mutex::new(None);
+
+/// Registres d'un CPU relevés par le gestionnaire de NMI
+struct Backtrace {
+    requested: AtomicBool,
+    ready: AtomicBool,
+    rip: AtomicU64,
+    rsp: AtomicU64,
+    rflags: AtomicU64,
+    stack: [AtomicU64; STACK_WORDS],
+}
+
+impl Backtrace {
+    const fn new() -> Self {
+        const ZERO: AtomicU64 = AtomicU64::new(0);
+        Self {
+            requested: AtomicBool::new(false),
+            ready: AtomicBool::new(false),
+            rip: ZERO,
+            rsp: ZERO,
+            rflags: ZERO,
+            stack: [ZERO; STACK_WORDS],
+        }
+    }
+}
+
+crate::percpu! {
+    /// Interruptions d'horloge reçues
+    static HEARTBEAT: AtomicU64 = AtomicU64::new(0);
+    /// LAPIC du CPU, destinataire des NMI
+    static LAPIC_ID: AtomicU32 = AtomicU32::new(u32::MAX);
+    static BACKTRACE: Backtrace = Backtrace::new();
+}
+
+/// Suivi d'un CPU par le kthread
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CpuWatch {
+    count: u64,
+    /// Dernier progrès constaté (0 : jamais observé)
+    since_ns: u64,
+    reported: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Check {
+    Progress,
+    /// Bloqué depuis ce nombre de secondes (premier constat)
+    Stalled(u64),
+    /// Encore bloqué, déjà signalé
+    Waiting,
+    /// Reparti après un blocage signalé
+    Recovered,
+}
+
+impl CpuWatch {
+    const fn new() -> Self {
+        Self { count: 0, since_ns: 0, reported: false }
+    }
+
+    fn check(&mut self, count: u64, now_ns: u64, thresh_ns: u64) -> Check {
+        if count != self.count || self.since_ns == 0 {
+            self.count = count;
+            self.since_ns = now_ns;
+            return if core::mem::replace(&mut self.reported, false) { Check::Recovered } else { Check::Progress };
+        }
+        let stalled = now_ns.saturating_sub(self.since_ns);
+        if self.reported || stalled < thresh_ns {
+            return if self.reported { Check::Waiting } else { Check::Progress };
+        }
+        self.reported = true;
+        Check::Stalled(stalled / NSEC_PER_SEC)
+    }
+}
+
+/// Journalise des registres et le haut d'une pile
+fn log_registers(rip: u64, rsp: u64, rflags: u64, stack: impl Iterator<Item = u64>) {
+    log::error!("RIP {:016x} RSP {:016x} RFLAGS {:016x}", rip, rsp, rflags);
+    let mut line = alloc::string::String::new();
+    for (i, word) in stack.enumerate() {
+        if i % 4 == 0 {
+            if !line.is_empty() {
+                log::error!("{}", line);
+            }
+            line = alloc::format!("{:016x}:", rsp + i as u64 * 8);
+        }
+        line.push_str(&alloc::format!(" {:016x}", word));
+    }
+    if !line.is_empty() {
+        log::error!("{}", line);
+    }
+}
+
+/// Tick d'horloge : battement du CPU, et vérification que le kthread
+/// tourne encore
+pub fn timer_tick() {
+    HEARTBEAT.get().fetch_add(1, Ordering::Relaxed);
+    LAPIC_ID.get().store(percpu::lapic_id(), Ordering::Relaxed);
+
+    let thresh = THRESH_NS.load(Ordering::Relaxed);
+    let touched = TOUCHED_NS.load(Ordering::Relaxed);
+    if thresh == 0 || touched == 0 {
+        return;
+    }
+    let stalled = crate::time::now_ns().saturating_sub(touched);
+    if stalled > thresh && !SOFT_REPORTED.swap(true, Ordering::Relaxed) {
+        log::error!(
+            "watchdog: BUG: soft lockup - CPU#{} bloqué depuis {} s (kwatchdog non ordonnancé)",
+            percpu::cpu_index(),
+            stalled / NSEC_PER_SEC
+        );
+        let regs = crate::kdump::Registers::capture();
+        let stack = regs.rsp as *const u64;
+        log_registers(regs.rip, regs.rsp, regs.rflags, (0..STACK_WORDS).map(|i| unsafe { stack.add(i).read_volatile() }));
+    }
+}
+
+/// NMI : relève le contexte interrompu si le kthread l'a demandé
+pub extern "x86-interrupt" fn nmi_handler(stack_frame: InterruptStackFrame) {
+    let _gs = crate::percpu::KernelGs::enter(&stack_frame);
+    let trace = BACKTRACE.get();
+    if !trace.requested.swap(false, Ordering::SeqCst) {
+        return;
+    }
+    let rsp = stack_frame.stack_pointer.as_u64();
+    trace.rip.store(stack_frame.instruction_pointer.as_u64(), Ordering::Relaxed);
+    trace.rsp.store(rsp, Ordering::Relaxed);
+    trace.rflags.store(stack_frame.cpu_flags, Ordering::Relaxed);
+    for (i, slot) in trace.stack.iter().enumerate() {
+        slot.store(unsafe { (rsp as *const u64).add(i).read_volatile() }, Ordering::Relaxed);
+    }
+    trace.ready.store(true, Ordering::SeqCst);
+}
+
+/// Signale un CPU qui ne reçoit plus l'horloge, avec ses registres si
+/// sa NMI aboutit
+fn report_hard_lockup(cpu: usize, secs: u64) {
+    log::error!("watchdog: CPU#{} sans interruption d'horloge depuis {} s", cpu, secs);
+    let apic_id = LAPIC_ID.get_cpu(cpu).load(Ordering::Relaxed);
+    if apic_id == u32::MAX || !crate::interrupts::irq::using_io_apic() {
+        log::error!("watchdog: CPU#{} : trace indisponible (pas de LAPIC)", cpu);
+        return;
+    }
+    let trace = BACKTRACE.get_cpu(cpu);
+    trace.ready.store(false, Ordering::SeqCst);
+    trace.requested.store(true, Ordering::SeqCst);
+    crate::interrupts::apic::LocalApic::new(LAPIC_BASE).send_nmi(apic_id);
+
+    let deadline = crate::time::now_ns() + NMI_TIMEOUT_NS;
+    while !trace.ready.load(Ordering::SeqCst) {
+        if crate::time::now_ns() > deadline {
+            trace.requested.store(false, Ordering::SeqCst);
+            log::error!("watchdog: CPU#{} ne répond pas à la NMI", cpu);
+            return;
+        }
+        core::hint::spin_loop();
+    }
+    log_registers(
+        trace.rip.load(Ordering::Relaxed),
+        trace.rsp.load(Ordering::Relaxed),
+        trace.rflags.load(Ordering::Relaxed),
+        trace.stack.iter().map(|w| w.load(Ordering::Relaxed)),
+    );
+}
+
+/// Corps du kthread kwatchdog
+fn watchdog_main() -> ! {
+    let mut cpus = [CpuWatch::new(); NR_CPUS];
+    loop {
+        let now = crate::time::now_ns();
+        TOUCHED_NS.store(now, Ordering::Relaxed);
+        if SOFT_REPORTED.swap(false, Ordering::Relaxed) {
+            log::warn!("watchdog: kwatchdog de nouveau ordonnancé");
+        }
+
+        let thresh = THRESH_NS.load(Ordering::Relaxed);
+        for (cpu, watch) in cpus.iter_mut().enumerate().take(percpu::cpu_count()) {
+            let count = HEARTBEAT.get_cpu(cpu).load(Ordering::Relaxed);
+            // CPU sans horloge (AP sans timer local) : rien à surveiller
+            if thresh == 0 || count == 0 {
+                continue;
+            }
+            match watch.check(count, now, thresh) {
+                Check::Stalled(secs) => report_hard_lockup(cpu, secs),
+                Check::Recovered => log::warn!("watchdog: CPU#{} reçoit de nouveau l'horloge", cpu),
+                Check::Progress | Check::Waiting => {}
+            }
+        }
+
+        if let Some(hw) = HW_WATCHDOG.lock().as_ref() {
+            hw.ping();
+        }
+        let deadline = now + NSEC_PER_SEC;
+        while crate::time::now_ns() < deadline {
+            crate::power::cpu_idle();
+        }
+    }
+}
+
+/// Arme le chien de garde matériel ; renvoie le modèle trouvé et son délai
+pub fn start_hardware(kind: Kind) -> Result<(Kind, u32), &'static str> {
+    let mut hw = HwWatchdog::probe(kind).ok_or("watchdog: aucun chien de garde matériel")?;
+    hw.start(HW_TIMEOUT);
+    let armed = (hw.kind, hw.timeout());
+    *HW_WATCHDOG.lock() = Some(hw);
+    Ok(armed)
+}
+
+/// Désarme le chien de garde matériel (arrêt propre)
+pub fn stop_hardware() {
+    if let Some(mut hw) = HW_WATCHDOG.lock().take() {
+        hw.stop();
+    }
+}
+
+/// Crée le kthread kwatchdog (famille PID 0) ; `thresh_secs` à 0 coupe
+/// le détecteur. Inutile sans détecteur ni chien matériel : None.
+pub fn spawn_watchdog(thresh_secs: u64) -> Option<u64> {
+    if thresh_secs == 0 && HW_WATCHDOG.lock().is_none() {
+        return None;
+    }
+    THRESH_NS.store(thresh_secs * NSEC_PER_SEC, Ordering::Relaxed);
+    let mut thread = Thread::new(alloc_tid(), 0, "kwatchdog", ProcessPriority::Low, 0);
+    thread.alloc_kernel_stack();
+    thread.context.rip = watchdog_main as u64;
+    let tid = thread.tid;
+
+    crate::scheduler::SCHEDULER.add_thread(Arc::new(crate::sync::Mutex::new(thread)));
+    Some(tid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_lockup_detection() {
+        let thresh = 10 * NSEC_PER_SEC;
+        let mut watch = CpuWatch::new();
+        assert_eq!(watch.check(5, 100 * NSEC_PER_SEC, thresh), Check::Progress);
+        assert_eq!(watch.check(5, 105 * NSEC_PER_SEC, thresh), Check::Progress);
+        assert_eq!(watch.check(5, 112 * NSEC_PER_SEC, thresh), Check::Stalled(12));
+        assert_eq!(watch.check(5, 130 * NSEC_PER_SEC, thresh), Check::Waiting);
+        assert_eq!(watch.check(6, 131 * NSEC_PER_SEC, thresh), Check::Recovered);
+        assert_eq!(watch.check(7, 132 * NSEC_PER_SEC, thresh), Check::Progress);
+    }
+}
@@ -0,0 +1,239 @@
+/// Files de travail (workqueues) : travail différé hors interruption
+///
+/// Un gestionnaire d'interruption ne fait que le strict nécessaire puis
+/// confie le reste à `queue_work(fonction, argument)`, utilisable en
+/// interruption comme sous verrou. Chaque CPU a sa file, vidée par un
+/// kthread `kworker/N` (famille PID 0) : le travail s'exécute en contexte
+/// de processus, interruptions actives, et peut prendre n'importe quel
+/// verrou. Un worker vide d'abord la file de son CPU, puis celles des
+/// autres, si bien qu'aucun travail ne reste en plan faute de worker.
+///
+/// Le travail retardé passe par une roue de temporisation indexée par
+/// tick : le tick d'horloge fait avancer la roue et verse les travaux
+/// échus dans la file du CPU qui les a programmés.
+
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use crate::sync::Mutex;
+use x86_64::instructions::interrupts::without_interrupts;
+use crate::process::{ProcessPriority, Thread};
+use crate::process::thread::alloc_tid;
+use crate::scheduler::{current_cpu, ticks};
+
+/// CPU servis (au-delà, les numéros de CPU sont repliés)
+pub const NR_CPUS: usize = crate::percpu::NR_CPUS;
+/// Travaux en attente par CPU ; au-delà, `queue_work` refuse
+pub const QUEUE_MAX: usize = 256;
+/// Emplacements de la roue (un par tick, modulo)
+const WHEEL_SLOTS: usize = 64;
+
+/// Travail différé : `func(arg)`, exécuté par un kworker
+#[derive(Clone, Copy)]
+pub struct Work {
+    pub func: fn(usize),
+    pub arg: usize,
+}
+
+impl Work {
+    pub const fn new(func: fn(usize), arg: usize) -> Self {
+        Self { func, arg }
+    }
+}
+
+/// Travail retardé en attente dans la roue
+struct Timer {
+    id: u64,
+    expires: u64,
+    cpu: usize,
+    work: Work,
+}
+
+const EMPTY_QUEUE: Mutex<VecDeque<Work>> = Mutex::new(VecDeque::new());
+const EMPTY_SLOT: Vec<Timer> = Vec::new();
+
+/// Files par CPU
+static QUEUES: [Mutex<VecDeque<Work>>; NR_CPUS] = [EMPTY_QUEUE; NR_CPUS];
+/// Roue de temporisation : emplacement `expires % WHEEL_SLOTS`
+static WHEEL: Mutex<[Vec<Timer>; WHEEL_SLOTS]> = Mutex::new([EMPTY_SLOT; WHEEL_SLOTS]);
+/// Dernier tick traité par la roue
+static WHEEL_TICK: AtomicU64 = AtomicU64::new(0);
+static NEXT_TIMER: AtomicU64 = AtomicU64::new(1);
+/// Travaux exécutés depuis le démarrage
+static COMPLETED: AtomicUsize = AtomicUsize::new(0);
+
+fn cpu_index(cpu: u32) -> usize {
+    cpu as usize % NR_CPUS
+}
+
+/// Met `work` dans la file de `cpu`. Retourne `false` si la file est pleine.
+pub fn queue_work_on(cpu: u32, work: Work) -> bool {
+    without_interrupts(|| {
+        let mut queue = QUEUES[cpu_index(cpu)].lock();
+        if queue.len() >= QUEUE_MAX {
+            return false;
+        }
+        queue.push_back(work);
+        true
+    })
+}
+
+/// Diffère `func(arg)` sur le CPU courant ; utilisable en interruption
+pub fn queue_work(func: fn(usize), arg: usize) -> bool {
+    queue_work_on(current_cpu(), Work::new(func, arg))
+}
+
+/// Diffère `func(arg)` d'au moins `delay` ticks ; retourne l'identifiant
+/// à passer à `cancel_delayed_work`
+pub fn queue_delayed_work(func: fn(usize), arg: usize, delay: u64) -> u64 {
+    let id = NEXT_TIMER.fetch_add(1, Ordering::Relaxed);
+    // Échéance au plus tôt au prochain tick
+    let expires = ticks() + delay.max(1);
+    let timer = Timer { id, expires, cpu: cpu_index(current_cpu()), work: Work::new(func, arg) };
+    without_interrupts(|| WHEEL.lock()[expires as usize % WHEEL_SLOTS].push(timer));
+    id
+}
+
+/// Annule un travail retardé pas encore échu ; `false` s'il a déjà été
+/// versé dans une file (ou n'existe pas)
+pub fn cancel_delayed_work(id: u64) -> bool {
+    without_interrupts(|| {
+        let mut wheel = WHEEL.lock();
+        for slot in wheel.iter_mut() {
+            if let Some(pos) = slot.iter().position(|timer| timer.id == id) {
+                slot.swap_remove(pos);
+                return true;
+            }
+        }
+        false
+    })
+}
+
+/// Fait avancer la roue jusqu'à `now` ; appelé depuis le tick d'horloge.
+/// Un verrou occupé reporte le traitement au tick suivant.
+pub fn timer_tick(now: u64) {
+    let mut wheel = match WHEEL.try_lock() {
+        Some(wheel) => wheel,
+        None => return,
+    };
+    let mut tick = WHEEL_TICK.load(Ordering::Relaxed);
+    // Ticks manqués : au plus un tour de roue suffit à tout revoir
+    if now.saturating_sub(tick) > WHEEL_SLOTS as u64 {
+        tick = now - WHEEL_SLOTS as u64;
+    }
+    while tick < now {
+        tick += 1;
+        let slot = &mut wheel[tick as usize % WHEEL_SLOTS];
+        let mut i = 0;
+        while i < slot.len() {
+            if slot[i].expires <= now {
+                let timer = slot.swap_remove(i);
+                // File pleine : réessayé au prochain tour de roue
+                if !queue_work_on(timer.cpu as u32, timer.work) {
+                    wheel_retry(slot, timer, now);
+                }
+            } else {
+                i += 1;
+            }
+        }
+    }
+    WHEEL_TICK.store(now, Ordering::Relaxed);
+}
+
+fn wheel_retry(slot: &mut Vec<Timer>, mut timer: Timer, now: u64) {
+    timer.expires = now + WHEEL_SLOTS as u64;
+    slot.push(timer);
+}
+
+/// Retire le prochain travail : file de `cpu` d'abord, puis les autres
+fn next_work(cpu: usize) -> Option<Work> {
+    without_interrupts(|| {
+        (0..NR_CPUS)
+            .map(|i| (cpu + i) % NR_CPUS)
+            .find_map(|i| QUEUES[i].lock().pop_front())
+    })
+}
+
+/// Exécute les travaux en attente, interruptions actives ; retourne le
+/// nombre de travaux exécutés. Jamais depuis une interruption.
+pub fn run_pending() -> usize {
+    let cpu = cpu_index(current_cpu());
+    let mut done = 0;
+    while let Some(work) = next_work(cpu) {
+        (work.func)(work.arg);
+        done += 1;
+        crate::scheduler::cond_resched();
+    }
+    COMPLETED.fetch_add(done, Ordering::Relaxed);
+    done
+}
+
+/// Travaux en attente, toutes files confondues
+pub fn pending() -> usize {
+    without_interrupts(|| QUEUES.iter().map(|queue| queue.lock().len()).sum())
+}
+
+/// Travaux exécutés depuis le démarrage
+pub fn completed() -> usize {
+    COMPLETED.load(Ordering::Relaxed)
+}
+
+/// Corps d'un kworker
+fn worker_main() -> ! {
+    loop {
+        run_pending();
+        crate::power::cpu_idle();
+    }
+}
+
+/// Crée un kthread `kworker/N` par CPU ; retourne leurs TID
+pub fn spawn_workers() -> Vec<u64> {
+    (0..crate::percpu::cpu_count())
+        .map(|cpu| {
+            let name = alloc::format!("kworker/{}", cpu);
+            let mut thread = Thread::new(alloc_tid(), 0, &name, ProcessPriority::High, 0);
+            thread.alloc_kernel_stack();
+            thread.context.rip = worker_main as u64;
+            let tid = thread.tid;
+            crate::scheduler::SCHEDULER.add_thread(Arc::new(Mutex::new(thread)));
+            tid
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static SUM: AtomicUsize = AtomicUsize::new(0);
+
+    fn add(arg: usize) {
+        SUM.fetch_add(arg, Ordering::Relaxed);
+    }
+
+    #[test_case]
+    fn test_queue_and_delayed_work() {
+        SUM.store(0, Ordering::Relaxed);
+        assert!(queue_work(add, 2));
+        assert!(queue_work_on(3, Work::new(add, 5)));
+        assert_eq!(run_pending(), 2);
+        assert_eq!(SUM.load(Ordering::Relaxed), 7);
+
+        // Travail retardé : rien avant l'échéance, annulation possible
+        // (tick réel suspendu pendant l'essai)
+        without_interrupts(|| {
+            let now = ticks();
+            WHEEL_TICK.store(now, Ordering::Relaxed);
+            queue_delayed_work(add, 10, 3);
+            let cancelled = queue_delayed_work(add, 100, 3);
+            assert!(cancel_delayed_work(cancelled));
+            timer_tick(now + 2);
+            assert_eq!(run_pending(), 0);
+            timer_tick(now + 3);
+            assert_eq!(run_pending(), 1);
+            assert_eq!(SUM.load(Ordering::Relaxed), 17);
+            assert!(!cancel_delayed_work(cancelled));
+        });
+    }
+}
@@ -0,0 +1,137 @@
+// Tests d'intégration de l'injection de fautes (feature fault-inject)
+// Les erreurs injectées doivent remonter jusqu'aux appelants sans rien
+// corrompre ; résultats sur le port série, sortie par isa-debug-exit
+
+#![no_std]
+#![no_main]
+#![feature(alloc_error_handler)]
+#![feature(custom_test_frameworks)]
+#![test_runner(mini_os::test_runner::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use mini_os::drivers::block::{check_request, BlockDevice, BlockHandle};
+use mini_os::drivers::disk::DiskError;
+use mini_os::fault_inject;
+use mini_os::fs;
+use mini_os::memory::frame::BuddyFrameAllocator;
+use mini_os::serial_println;
+use spin::Mutex;
+
+use core::panic::PanicInfo;
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    mini_os::test_runner::test_panic_handler(info)
+}
+
+#[alloc_error_handler]
+fn alloc_error_handler(_layout: core::alloc::Layout) -> ! {
+    panic!("allocation error")
+}
+
+mini_os::test_kernel_entry!();
+
+#[no_mangle]
+pub extern "C" fn test_kernel_main(multiboot_magic: u32, multiboot_info: u32) -> ! {
+    mini_os::test_runner::init(multiboot_magic, multiboot_info);
+    serial_println!("Tests d'intégration : injection de fautes");
+    fs::init_vfs().expect("init_vfs");
+
+    test_main();
+    loop {
+        x86_64::instructions::hlt();
+    }
+}
+
+/// Disque en mémoire de quelques secteurs
+struct MemDisk {
+    data: Vec<u8>,
+}
+
+impl BlockDevice for MemDisk {
+    fn block_size(&self) -> usize {
+        512
+    }
+
+    fn block_count(&self) -> u64 {
+        (self.data.len() / 512) as u64
+    }
+
+    fn read_blocks(&mut self, lba: u64, buf: &mut [u8]) -> Result<(), DiskError> {
+        check_request(&*self, lba, buf.len())?;
+        let start = lba as usize * 512;
+        buf.copy_from_slice(&self.data[start..start + buf.len()]);
+        Ok(())
+    }
+
+    fn write_blocks(&mut self, lba: u64, buf: &[u8]) -> Result<(), DiskError> {
+        check_request(&*self, lba, buf.len())?;
+        let start = lba as usize * 512;
+        self.data[start..start + buf.len()].copy_from_slice(buf);
+        Ok(())
+    }
+}
+
+fn mem_disk() -> BlockHandle {
+    BlockHandle::new("mem0", Arc::new(Mutex::new(MemDisk { data: vec![0; 8 * 512] })))
+}
+
+#[test_case]
+fn test_disk_read_fault() {
+    let disk = mem_disk();
+    let mut buf = [0u8; 512];
+    fault_inject::configure("disk_read:100:1:1").expect("configure");
+    assert_eq!(disk.read_blocks(0, &mut buf), Err(DiskError::ReadFailed));
+    // Une seule faute demandée : la lecture suivante aboutit
+    assert_eq!(disk.read_blocks(0, &mut buf), Ok(()));
+    assert_eq!(disk.stats().errors, 1);
+}
+
+#[test_case]
+fn test_disk_write_fault() {
+    let disk = mem_disk();
+    fault_inject::configure("disk_write:100:1:1").expect("configure");
+    assert_eq!(disk.write_blocks(1, &[0xAA; 512]), Err(DiskError::WriteFailed));
+    disk.write_blocks(1, &[0xBB; 512]).expect("write");
+
+    let mut buf = [0u8; 512];
+    disk.read_blocks(1, &mut buf).expect("read");
+    assert_eq!(buf, [0xBB; 512]);
+}
+
+#[test_case]
+fn test_frame_and_heap_faults() {
+    let mut buddy = BuddyFrameAllocator::new();
+    buddy.add_region(4 * 1024 * 1024, 6 * 1024 * 1024);
+    let free = buddy.free_frames();
+
+    fault_inject::configure("frame:100:2:1").expect("configure");
+    assert!(buddy.allocate(0).is_some());
+    assert!(buddy.allocate(0).is_none());
+    assert!(buddy.allocate(0).is_some());
+    assert_eq!(buddy.free_frames(), free - 2);
+
+    // Allocation faillible du tas : l'erreur est rendue, pas de panique
+    let mut buffer: Vec<u8> = Vec::new();
+    fault_inject::configure("heap:100:1:1").expect("configure");
+    assert!(buffer.try_reserve(4096).is_err());
+    assert!(buffer.try_reserve(4096).is_ok());
+}
+
+#[test_case]
+fn test_proc_knob() {
+    fs::vfs_write_file("/proc/fault_inject", b"disk_read:25:2:10\n").expect("write knob");
+    let report = String::from_utf8(fs::vfs_read_file("/proc/fault_inject").expect("read knob")).expect("utf8");
+    let line = report.lines().find(|l| l.starts_with("disk_read")).expect("disk_read");
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    assert_eq!(&fields[1..4], ["25", "2", "10"]);
+
+    assert!(fs::vfs_write_file("/proc/fault_inject", b"swap:10").is_err());
+    fs::vfs_write_file("/proc/fault_inject", b"disk_read:0").expect("disable");
+}
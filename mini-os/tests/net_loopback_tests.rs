@@ -0,0 +1,117 @@
+// Tests d'intégration de la pile réseau en boucle locale
+// Une carte factice renvoie chaque trame émise à la réception : ARP, IPv4
+// et UDP traversent toute la pile. Résultats sur le port série, sortie
+// par isa-debug-exit
+
+#![no_std]
+#![no_main]
+#![feature(alloc_error_handler)]
+#![feature(custom_test_frameworks)]
+#![test_runner(mini_os::test_runner::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use mini_os::net::interface::{self, NetDevice, NetDeviceError};
+use mini_os::net::socket::{self, SocketError};
+use mini_os::net::{arp, Ipv4Address, MacAddress, SocketAddr, SocketDomain, SocketType, SOCKET_TABLE};
+use mini_os::serial_println;
+
+use core::panic::PanicInfo;
+
+const MAC: MacAddress = MacAddress([0x52, 0x54, 0x00, 0x12, 0x34, 0x56]);
+const IP: Ipv4Address = Ipv4Address([10, 0, 2, 15]);
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    mini_os::test_runner::test_panic_handler(info)
+}
+
+#[alloc_error_handler]
+fn alloc_error_handler(_layout: core::alloc::Layout) -> ! {
+    panic!("allocation error")
+}
+
+/// Carte en boucle : les trames émises sont reçues au prochain `poll`
+struct Loopback {
+    queue: VecDeque<Vec<u8>>,
+}
+
+impl NetDevice for Loopback {
+    fn mac_address(&self) -> MacAddress {
+        MAC
+    }
+
+    fn transmit(&mut self, frame: &[u8]) -> Result<(), NetDeviceError> {
+        self.queue.push_back(frame.to_vec());
+        Ok(())
+    }
+
+    fn receive(&mut self) -> Option<Vec<u8>> {
+        self.queue.pop_front()
+    }
+}
+
+mini_os::test_kernel_entry!();
+
+#[no_mangle]
+pub extern "C" fn test_kernel_main(multiboot_magic: u32, multiboot_info: u32) -> ! {
+    mini_os::test_runner::init(multiboot_magic, multiboot_info);
+    serial_println!("Tests d'intégration : réseau (boucle locale)");
+    interface::register_device(Box::new(Loopback { queue: VecDeque::new() }));
+    interface::init(MAC, IP);
+
+    test_main();
+    loop {
+        x86_64::instructions::hlt();
+    }
+}
+
+fn udp_socket(port: u16) -> u32 {
+    let mut table = SOCKET_TABLE.lock();
+    let id = table.socket(SocketDomain::Inet, SocketType::Datagram).expect("socket");
+    table.bind(id, SocketAddr::new(IP, port)).expect("bind");
+    id
+}
+
+#[test_case]
+fn test_arp_resolves_own_address() {
+    assert_eq!(interface::resolve(IP), Ok(MAC));
+    assert_eq!(arp::cache_lookup(&IP), Some(MAC));
+}
+
+#[test_case]
+fn test_udp_roundtrip() {
+    let server = udp_socket(7000);
+    let client = udp_socket(7001);
+    SOCKET_TABLE.lock().connect(client, SocketAddr::new(IP, 7000)).expect("connect");
+
+    assert_eq!(socket::send_datagram(client, b"ping"), Ok(4));
+    interface::poll();
+
+    let mut buf = [0u8; 16];
+    let len = SOCKET_TABLE.lock().recv(server, &mut buf).expect("recv");
+    assert_eq!(&buf[..len], b"ping");
+
+    let _ = socket::close(server);
+    let _ = socket::close(client);
+}
+
+#[test_case]
+fn test_unbound_port_dropped() {
+    let server = udp_socket(7100);
+    let client = udp_socket(7101);
+    SOCKET_TABLE.lock().connect(client, SocketAddr::new(IP, 7200)).expect("connect");
+
+    socket::send_datagram(client, b"perdu").expect("send");
+    interface::poll();
+
+    let mut buf = [0u8; 16];
+    assert_eq!(SOCKET_TABLE.lock().recv(server, &mut buf), Err(SocketError::WouldBlock));
+
+    let _ = socket::close(server);
+    let _ = socket::close(client);
+}
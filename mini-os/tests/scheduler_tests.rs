@@ -0,0 +1,114 @@
+// Tests d'intégration du planificateur
+// Runqueue CFS, poids des priorités et horloge (IRQ 0) ; résultats sur le
+// port série, sortie par isa-debug-exit
+
+#![no_std]
+#![no_main]
+#![feature(alloc_error_handler)]
+#![feature(custom_test_frameworks)]
+#![test_runner(mini_os::test_runner::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use alloc::sync::Arc;
+use mini_os::process::thread::alloc_tid;
+use mini_os::process::{ProcessPriority, Thread, ThreadState};
+use mini_os::scheduler::{self, CFSScheduler};
+use mini_os::serial_println;
+use mini_os::sync::Mutex;
+use mini_os::test_runner::Timeout;
+
+use core::panic::PanicInfo;
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    mini_os::test_runner::test_panic_handler(info)
+}
+
+#[alloc_error_handler]
+fn alloc_error_handler(_layout: core::alloc::Layout) -> ! {
+    panic!("allocation error")
+}
+
+mini_os::test_kernel_entry!();
+
+#[no_mangle]
+pub extern "C" fn test_kernel_main(multiboot_magic: u32, multiboot_info: u32) -> ! {
+    mini_os::test_runner::init(multiboot_magic, multiboot_info);
+    serial_println!("Tests d'intégration : planificateur");
+
+    test_main();
+    loop {
+        x86_64::instructions::hlt();
+    }
+}
+
+fn thread(name: &str, priority: ProcessPriority) -> Arc<Mutex<Thread>> {
+    Arc::new(Mutex::new(Thread::new(alloc_tid(), 0, name, priority, 0)))
+}
+
+#[test_case]
+fn test_lowest_vruntime_first() {
+    let mut cfs = CFSScheduler::new();
+    let a = thread("a", ProcessPriority::Normal);
+    let b = thread("b", ProcessPriority::Normal);
+    a.lock().vruntime = 5_000;
+    b.lock().vruntime = 1_000;
+    cfs.add_thread(a.clone());
+    cfs.add_thread(b.clone());
+
+    let next = cfs.schedule(None).expect("thread prêt");
+    assert!(Arc::ptr_eq(&next, &b));
+    assert_eq!(next.lock().state, ThreadState::Running);
+    assert_eq!(cfs.thread_count(), 1);
+}
+
+#[test_case]
+fn test_yield_rotates() {
+    let mut cfs = CFSScheduler::new();
+    let a = thread("a", ProcessPriority::Normal);
+    let b = thread("b", ProcessPriority::Normal);
+    cfs.add_thread(a.clone());
+    cfs.add_thread(b.clone());
+
+    let first = cfs.schedule(None).expect("premier");
+    let second = cfs.yield_thread(first.clone()).expect("second");
+    assert!(!Arc::ptr_eq(&first, &second));
+    assert_eq!(first.lock().state, ThreadState::Ready);
+}
+
+#[test_case]
+fn test_terminated_and_blocked() {
+    let mut cfs = CFSScheduler::new();
+    let done = thread("done", ProcessPriority::Normal);
+    let sleeper = thread("sleeper", ProcessPriority::Normal);
+    cfs.add_thread(done.clone());
+    done.lock().state = ThreadState::Terminated;
+    assert!(cfs.schedule(None).is_none());
+
+    sleeper.lock().state = ThreadState::Blocked;
+    cfs.wake_thread(sleeper.clone());
+    let next = cfs.schedule(None).expect("réveillé");
+    assert!(Arc::ptr_eq(&next, &sleeper));
+}
+
+#[test_case]
+fn test_priority_weights() {
+    // À temps égal, un thread prioritaire vieillit moins vite
+    let high = thread("high", ProcessPriority::High);
+    let low = thread("low", ProcessPriority::Low);
+    high.lock().update_vruntime(1_000_000);
+    low.lock().update_vruntime(1_000_000);
+    assert!(high.lock().vruntime < low.lock().vruntime);
+}
+
+fn timer_advances() {
+    let start = scheduler::ticks();
+    while scheduler::ticks() < start + 3 {
+        x86_64::instructions::hlt();
+    }
+}
+
+#[test_case]
+const TEST_TIMER_ADVANCES: Timeout<fn()> = Timeout(scheduler::TIMER_HZ, timer_advances);
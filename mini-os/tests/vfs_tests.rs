@@ -0,0 +1,91 @@
+// Tests d'intégration du VFS
+// Racine RamFS montée par init_vfs, /proc et /dev compris ; résultats sur
+// le port série, sortie par isa-debug-exit
+
+#![no_std]
+#![no_main]
+#![feature(alloc_error_handler)]
+#![feature(custom_test_frameworks)]
+#![test_runner(mini_os::test_runner::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use alloc::string::String;
+use mini_os::fs::{self, FileMode, FileType, VfsError};
+use mini_os::serial_println;
+use mini_os::test_runner::ShouldPanic;
+
+use core::panic::PanicInfo;
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    mini_os::test_runner::test_panic_handler(info)
+}
+
+#[alloc_error_handler]
+fn alloc_error_handler(_layout: core::alloc::Layout) -> ! {
+    panic!("allocation error")
+}
+
+mini_os::test_kernel_entry!();
+
+#[no_mangle]
+pub extern "C" fn test_kernel_main(multiboot_magic: u32, multiboot_info: u32) -> ! {
+    mini_os::test_runner::init(multiboot_magic, multiboot_info);
+    serial_println!("Tests d'intégration : VFS");
+    fs::init_vfs().expect("init_vfs");
+
+    test_main();
+    loop {
+        x86_64::instructions::hlt();
+    }
+}
+
+#[test_case]
+fn test_write_read_file() {
+    fs::vfs_write_file("/hello.txt", b"Hello VFS").expect("write");
+    assert_eq!(fs::vfs_read_file("/hello.txt").expect("read"), b"Hello VFS");
+
+    fs::vfs_append_file("/hello.txt", b"!").expect("append");
+    assert_eq!(fs::vfs_stat("/hello.txt").expect("stat").size, 10);
+}
+
+#[test_case]
+fn test_directories() {
+    fs::vfs_mkdir("/tmp").expect("mkdir");
+    fs::vfs_create("/tmp/a", FileMode::new(0o644), FileType::Regular).expect("create");
+    fs::vfs_mkdir("/tmp/sub").expect("mkdir sub");
+
+    assert!(fs::is_dir("/tmp/sub"));
+    let mut names = fs::vfs_ls("/tmp").expect("ls");
+    names.sort();
+    assert!(names.iter().any(|n| n == "a"));
+    assert!(names.iter().any(|n| n == "sub"));
+}
+
+#[test_case]
+fn test_rename_link_remove() {
+    fs::vfs_write_file("/old", b"data").expect("write");
+    fs::vfs_rename("/old", "/new").expect("rename");
+    assert!(matches!(fs::vfs_stat("/old"), Err(VfsError::NotFound)));
+
+    fs::vfs_link("/new", "/alias").expect("link");
+    fs::vfs_remove_file("/new").expect("remove");
+    assert_eq!(fs::vfs_read_file("/alias").expect("read alias"), b"data");
+}
+
+#[test_case]
+fn test_procfs_mounted() {
+    let names = fs::vfs_ls("/proc").expect("ls /proc");
+    assert!(!names.is_empty());
+    let uptime = fs::vfs_read_file("/proc/uptime").expect("read uptime");
+    assert!(String::from_utf8(uptime).is_ok());
+}
+
+fn read_missing() {
+    fs::vfs_read_file("/absent").unwrap();
+}
+
+#[test_case]
+const TEST_READ_MISSING: ShouldPanic<fn()> = ShouldPanic(read_missing);